@@ -32,9 +32,11 @@ esac
 
 readonly NATIVE_ENGINE_BINARY="native_engine.so"
 export NATIVE_CLIENT_BINARY="${REPO_ROOT}/src/python/pants/bin/native_client"
+readonly NATIVE_SANDBOXER_BINARY="${REPO_ROOT}/src/python/pants/bin/sandboxer"
 readonly NATIVE_ENGINE_RESOURCE="${REPO_ROOT}/src/python/pants/engine/internals/${NATIVE_ENGINE_BINARY}"
 readonly NATIVE_ENGINE_RESOURCE_METADATA="${NATIVE_ENGINE_RESOURCE}.metadata"
 readonly NATIVE_CLIENT_TARGET="${NATIVE_ROOT}/target/${MODE}/pants"
+readonly NATIVE_SANDBOXER_TARGET="${NATIVE_ROOT}/target/${MODE}/sandboxer"
 
 function _build_native_code() {
   banner "Building native code..."
@@ -43,7 +45,8 @@ function _build_native_code() {
     --features=extension-module \
     ${MODE_FLAG} \
     -p engine \
-    -p client || die
+    -p client \
+    -p process_execution || die
 }
 
 function bootstrap_native_code() {
@@ -56,7 +59,7 @@ function bootstrap_native_code() {
   fi
 
   if [[ -f "${NATIVE_ENGINE_RESOURCE}" && -f "${NATIVE_CLIENT_BINARY}" &&
-    "${engine_version_calculated}" == "${engine_version_in_metadata}" ]]; then
+    -f "${NATIVE_SANDBOXER_BINARY}" && "${engine_version_calculated}" == "${engine_version_in_metadata}" ]]; then
     return 0
   fi
 
@@ -72,6 +75,9 @@ function bootstrap_native_code() {
   if [[ ! -f "${NATIVE_CLIENT_TARGET}" ]]; then
     die "Failed to build native client."
   fi
+  if [[ ! -f "${NATIVE_SANDBOXER_TARGET}" ]]; then
+    die "Failed to build sandboxer."
+  fi
 
   # Pick up Cargo.lock changes if any caused by the `cargo build`.
   engine_version_calculated="$(calculate_current_hash)"
@@ -79,9 +85,10 @@ function bootstrap_native_code() {
   # Create the native engine resource.
   # NB: On Mac Silicon, for some reason, first removing the old native_engine.so is necessary to avoid the Pants
   #  process from being killed when recompiling.
-  rm -f "${NATIVE_ENGINE_RESOURCE}" "${NATIVE_CLIENT_BINARY}"
+  rm -f "${NATIVE_ENGINE_RESOURCE}" "${NATIVE_CLIENT_BINARY}" "${NATIVE_SANDBOXER_BINARY}"
   cp "${native_binary}" "${NATIVE_ENGINE_RESOURCE}"
   cp "${NATIVE_CLIENT_TARGET}" "${NATIVE_CLIENT_BINARY}"
+  cp "${NATIVE_SANDBOXER_TARGET}" "${NATIVE_SANDBOXER_BINARY}"
 
   # Create the accompanying metadata file.
   local -r metadata_file=$(mktemp -t pants.native_engine.metadata.XXXXXX)
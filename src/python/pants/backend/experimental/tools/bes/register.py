@@ -0,0 +1,10 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+from pants.backend.tools.bes import rules as bes_rules
+
+
+def rules():
+    return bes_rules.rules()
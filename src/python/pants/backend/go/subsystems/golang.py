@@ -9,6 +9,7 @@ import os
 from pants.core.util_rules.asdf import AsdfPathString
 from pants.option.option_types import BoolOption, StrListOption, StrOption
 from pants.option.subsystem import Subsystem
+from pants.util.docutil import bin_name
 from pants.util.memo import memoized_property
 from pants.util.ordered_set import OrderedSet
 from pants.util.strutil import softwrap
@@ -243,6 +244,25 @@ class GolangSubsystem(Subsystem):
         ),
     )
 
+    use_rust_parser = BoolOption(
+        default=False,
+        help=softwrap(
+            f"""
+            Use the Rust-based, in-process parser to determine the imports of first-party
+            packages for dependency inference, instead of running the `analyze_package` helper
+            binary for each package.
+
+            The Rust-based parser reads the imports, build constraints and `//go:embed` directives
+            of each source file natively, and evaluates the build constraints in Pants itself.
+
+            If you think the new behaviour is causing problems, it is recommended that you run
+            `{bin_name()} peek :: > before.json` and then
+            `{bin_name()} --golang-use-rust-parser peek :: > after.json` and compare the two
+            results.
+            """
+        ),
+    )
+
     asdf_tool_name = StrOption(
         default="go-sdk",
         help=softwrap(
@@ -14,6 +14,7 @@ from pants.backend.go.dependency_inference import (
     GoModuleImportPathsMappings,
     GoModuleImportPathsMappingsHook,
 )
+from pants.backend.go.subsystems.golang import GolangSubsystem
 from pants.backend.go.target_types import (
     GoImportPathField,
     GoModSourcesField,
@@ -26,9 +27,12 @@ from pants.backend.go.util_rules import build_opts, first_party_pkg, import_anal
 from pants.backend.go.util_rules.build_opts import GoBuildOptions, GoBuildOptionsFromTargetRequest
 from pants.backend.go.util_rules.first_party_pkg import (
     FallibleFirstPartyPkgAnalysis,
+    FirstPartyPkgAnalysis,
     FirstPartyPkgAnalysisRequest,
     FirstPartyPkgImportPath,
     FirstPartyPkgImportPathRequest,
+    FirstPartyPkgImports,
+    FirstPartyPkgImportsRequest,
 )
 from pants.backend.go.util_rules.go_mod import (
     GoModInfo,
@@ -216,7 +220,7 @@ class InferGoPackageDependenciesRequest(InferDependenciesRequest):
 
 @rule(desc="Infer dependencies for first-party Go packages", level=LogLevel.DEBUG)
 async def infer_go_dependencies(
-    request: InferGoPackageDependenciesRequest,
+    request: InferGoPackageDependenciesRequest, golang_subsystem: GolangSubsystem
 ) -> InferredDependencies:
     go_mod_addr = await Get(OwningGoMod, OwningGoModRequest(request.field_set.address))
     package_mapping, build_opts = await MultiGet(
@@ -225,32 +229,31 @@ async def infer_go_dependencies(
     )
 
     addr = request.field_set.address
-    maybe_pkg_analysis, stdlib_packages = await MultiGet(
-        Get(
+    pkg_imports: FirstPartyPkgImports | FirstPartyPkgAnalysis
+    if golang_subsystem.use_rust_parser:
+        pkg_imports = await Get(
+            FirstPartyPkgImports, FirstPartyPkgImportsRequest(addr, build_opts=build_opts)
+        )
+    else:
+        maybe_pkg_analysis = await Get(
             FallibleFirstPartyPkgAnalysis, FirstPartyPkgAnalysisRequest(addr, build_opts=build_opts)
-        ),
-        Get(
-            GoStdLibPackages,
-            GoStdLibPackagesRequest(with_race_detector=build_opts.with_race_detector),
-        ),
-    )
-
-    if maybe_pkg_analysis.analysis is None:
-        logger.error(
-            f"Failed to analyze {maybe_pkg_analysis.import_path} for dependency inference:\n"
-            f"{maybe_pkg_analysis.stderr}"
         )
-        return InferredDependencies([])
-    pkg_analysis = maybe_pkg_analysis.analysis
+        if maybe_pkg_analysis.analysis is None:
+            logger.error(
+                f"Failed to analyze {maybe_pkg_analysis.import_path} for dependency inference:\n"
+                f"{maybe_pkg_analysis.stderr}"
+            )
+            return InferredDependencies([])
+        pkg_imports = maybe_pkg_analysis.analysis
 
     inferred_dependencies: list[Address] = []
     for import_path in (
-        *pkg_analysis.imports,
-        *pkg_analysis.test_imports,
-        *pkg_analysis.xtest_imports,
+        *pkg_imports.imports,
+        *pkg_imports.test_imports,
+        *pkg_imports.xtest_imports,
     ):
         # Avoid a dependency cycle caused by external test imports of this package (i.e., "xtest").
-        if import_path == pkg_analysis.import_path:
+        if import_path == pkg_imports.import_path:
             continue
         candidate_packages = package_mapping.mapping.get(import_path)
         if candidate_packages:
@@ -161,6 +161,58 @@ def test_go_package_dependency_inference(rule_runner: RuleRunner) -> None:
     assert not get_deps(Address("foo/bad"))
 
 
+def test_go_package_dependency_inference_with_rust_parser(rule_runner: RuleRunner) -> None:
+    rule_runner.set_options(["--golang-use-rust-parser"], env_inherit={"PATH"})
+    rule_runner.write_files(
+        {
+            "foo/BUILD": "go_mod()",
+            "foo/go.mod": "module go.example.com/foo\ngo 1.17\n",
+            "foo/pkg/BUILD": "go_package()",
+            "foo/pkg/foo.go": "package pkg\n",
+            "foo/util/BUILD": "go_package()",
+            "foo/util/util.go": "package util\n",
+            "foo/testutil/BUILD": "go_package()",
+            "foo/testutil/testutil.go": "package testutil\n",
+            "foo/ignored/BUILD": "go_package()",
+            "foo/ignored/ignored.go": "package ignored\n",
+            "foo/cmd/BUILD": "go_package()",
+            "foo/cmd/main.go": dedent(
+                """\
+                package main
+                import (
+                    "fmt"
+                    "go.example.com/foo/pkg"
+                )
+                """
+            ),
+            "foo/cmd/ignored.go": dedent(
+                """\
+                //go:build ignore
+
+                package main
+                import "go.example.com/foo/ignored"
+                """
+            ),
+            "foo/cmd/main_test.go": dedent(
+                """\
+                package main
+                import "go.example.com/foo/util"
+                """
+            ),
+            "foo/cmd/main_ext_test.go": dedent(
+                """\
+                package main_test
+                import "go.example.com/foo/testutil"
+                """
+            ),
+        }
+    )
+
+    tgt = rule_runner.get_target(Address("foo/cmd"))
+    deps = rule_runner.request(Addresses, [DependenciesRequest(tgt[Dependencies])])
+    assert set(deps) == {Address("foo/pkg"), Address("foo/util"), Address("foo/testutil")}
+
+
 # -----------------------------------------------------------------------------------------------
 # `go_package` validation
 # -----------------------------------------------------------------------------------------------
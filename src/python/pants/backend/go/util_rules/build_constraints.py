@@ -0,0 +1,163 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+"""Evaluation of Go build constraints, as per https://pkg.go.dev/cmd/go#hdr-Build_constraints.
+
+This mirrors the matching done by `go/build` (and so by the `analyze_package` helper binary), for
+use with the files parsed by the Rust-based Go dependency parser.
+"""
+
+from __future__ import annotations
+
+import re
+from dataclasses import dataclass
+
+# NB: Copied from https://github.com/golang/go/blob/master/src/go/build/syslist.go.
+KNOWN_OS = frozenset(
+    "aix android darwin dragonfly freebsd hurd illumos ios js linux nacl netbsd openbsd plan9 "
+    "solaris wasip1 windows zos".split()
+)
+KNOWN_ARCH = frozenset(
+    "386 amd64 amd64p32 arm armbe arm64 arm64be loong64 mips mipsle mips64 mips64le mips64p32 "
+    "mips64p32le ppc ppc64 ppc64le riscv riscv64 s390 s390x sparc sparc64 wasm".split()
+)
+UNIX_OS = frozenset(
+    "aix android darwin dragonfly freebsd hurd illumos ios linux netbsd openbsd solaris".split()
+)
+
+
+@dataclass(frozen=True)
+class GoBuildContext:
+    goos: str
+    goarch: str
+    cgo_enabled: bool
+    build_tags: frozenset[str] = frozenset()
+    release_tags: frozenset[str] = frozenset()
+    compiler: str = "gc"
+
+    @classmethod
+    def create(
+        cls,
+        *,
+        goos: str,
+        goarch: str,
+        go_version: str,
+        cgo_enabled: bool,
+        build_tags: tuple[str, ...] = (),
+    ) -> GoBuildContext:
+        """Creates a context with the `go1.N` release tags for all releases up to `go_version`."""
+        _, minor = (int(part) for part in go_version.split(".")[:2])
+        return cls(
+            goos=goos,
+            goarch=goarch,
+            cgo_enabled=cgo_enabled,
+            build_tags=frozenset(build_tags),
+            release_tags=frozenset(f"go1.{i}" for i in range(1, minor + 1)),
+        )
+
+    def matches_tag(self, tag: str) -> bool:
+        if tag == "cgo":
+            return self.cgo_enabled
+        if tag in (self.goos, self.goarch, self.compiler):
+            return True
+        if (self.goos, tag) in (("android", "linux"), ("illumos", "solaris"), ("ios", "darwin")):
+            return True
+        if tag == "unix":
+            return self.goos in UNIX_OS
+        return tag in self.build_tags or tag in self.release_tags
+
+    def matches_file_name(self, file_name: str) -> bool:
+        """Whether the `_GOOS`, `_GOARCH` or `_GOOS_GOARCH` suffix of the file name (if any)
+        matches."""
+        name, _, _ = file_name.rpartition(".")
+        if name.endswith("_test"):
+            name = name[: -len("_test")]
+        # NB: The part of the name before the first underscore is never a constraint, so that
+        # e.g. `linux.go` applies to all platforms.
+        _, _, name = name.partition("_")
+        parts = name.split("_")
+        if len(parts) >= 2 and parts[-2] in KNOWN_OS and parts[-1] in KNOWN_ARCH:
+            return self.matches_tag(parts[-2]) and self.matches_tag(parts[-1])
+        if parts[-1] in KNOWN_OS or parts[-1] in KNOWN_ARCH:
+            return self.matches_tag(parts[-1])
+        return True
+
+    def matches_constraint(self, expression: str) -> bool:
+        """Evaluates a `//go:build` expression."""
+        return _ConstraintParser(expression).parse(self)
+
+
+class InvalidBuildConstraintError(ValueError):
+    pass
+
+
+class _ConstraintParser:
+    _TOKEN_RE = re.compile(r"\s*(\|\||&&|!|\(|\)|[\w.]+)")
+
+    def __init__(self, expression: str) -> None:
+        self._expression = expression
+        self._tokens: list[str] = []
+        pos = 0
+        while pos < len(expression):
+            match = self._TOKEN_RE.match(expression, pos)
+            if not match:
+                if expression[pos:].strip():
+                    raise InvalidBuildConstraintError(
+                        f"Invalid build constraint `{expression}` at position {pos}."
+                    )
+                break
+            self._tokens.append(match.group(1))
+            pos = match.end()
+        self._pos = 0
+
+    def parse(self, context: GoBuildContext) -> bool:
+        result = self._or(context)
+        if self._pos != len(self._tokens):
+            raise InvalidBuildConstraintError(
+                f"Unexpected `{self._tokens[self._pos]}` in build constraint `{self._expression}`."
+            )
+        return result
+
+    def _peek(self) -> str | None:
+        return self._tokens[self._pos] if self._pos < len(self._tokens) else None
+
+    def _next(self) -> str:
+        token = self._peek()
+        if token is None:
+            raise InvalidBuildConstraintError(
+                f"Unexpected end of build constraint `{self._expression}`."
+            )
+        self._pos += 1
+        return token
+
+    def _or(self, context: GoBuildContext) -> bool:
+        result = self._and(context)
+        while self._peek() == "||":
+            self._next()
+            # NB: Both sides are always parsed, so that syntax errors are not hidden.
+            result = self._and(context) or result
+        return result
+
+    def _and(self, context: GoBuildContext) -> bool:
+        result = self._not(context)
+        while self._peek() == "&&":
+            self._next()
+            result = self._not(context) and result
+        return result
+
+    def _not(self, context: GoBuildContext) -> bool:
+        token = self._next()
+        if token == "!":
+            return not self._not(context)
+        if token == "(":
+            result = self._or(context)
+            if self._next() != ")":
+                raise InvalidBuildConstraintError(
+                    f"Missing `)` in build constraint `{self._expression}`."
+                )
+            return result
+        if token in ("||", "&&", ")"):
+            raise InvalidBuildConstraintError(
+                f"Unexpected `{token}` in build constraint `{self._expression}`."
+            )
+        return context.matches_tag(token)
@@ -0,0 +1,63 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+import pytest
+
+from pants.backend.go.util_rules.build_constraints import (
+    GoBuildContext,
+    InvalidBuildConstraintError,
+)
+
+LINUX_AMD64 = GoBuildContext.create(
+    goos="linux", goarch="amd64", go_version="1.21", cgo_enabled=True, build_tags=("integration",)
+)
+
+
+@pytest.mark.parametrize(
+    "expression,expected",
+    [
+        ("linux", True),
+        ("darwin", False),
+        ("!windows", True),
+        ("linux && amd64", True),
+        ("linux && arm64", False),
+        ("darwin || amd64", True),
+        ("linux && (arm64 || amd64)", True),
+        ("!(linux || darwin)", False),
+        ("unix", True),
+        ("cgo && gc", True),
+        ("integration", True),
+        ("go1.18 && !go1.22", True),
+        ("ignore", False),
+    ],
+)
+def test_matches_constraint(expression: str, expected: bool) -> None:
+    assert LINUX_AMD64.matches_constraint(expression) is expected
+
+
+@pytest.mark.parametrize("expression", ["linux &&", "(linux", "linux darwin", "linux | darwin"])
+def test_invalid_constraint(expression: str) -> None:
+    with pytest.raises(InvalidBuildConstraintError):
+        LINUX_AMD64.matches_constraint(expression)
+
+
+@pytest.mark.parametrize(
+    "file_name,expected",
+    [
+        ("foo.go", True),
+        ("linux.go", True),
+        ("foo_linux.go", True),
+        ("foo_windows.go", False),
+        ("foo_amd64.go", True),
+        ("foo_arm64.go", False),
+        ("foo_linux_amd64.go", True),
+        ("foo_linux_arm64.go", False),
+        ("foo_darwin_test.go", False),
+        ("foo_linux_test.go", True),
+        ("foo_bar.go", True),
+    ],
+)
+def test_matches_file_name(file_name: str, expected: bool) -> None:
+    assert LINUX_AMD64.matches_file_name(file_name) is expected
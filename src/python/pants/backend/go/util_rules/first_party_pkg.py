@@ -12,6 +12,7 @@ from pants.backend.go.go_sources import load_go_binary
 from pants.backend.go.go_sources.load_go_binary import LoadedGoBinary, LoadedGoBinaryRequest
 from pants.backend.go.target_types import GoPackageSourcesField
 from pants.backend.go.util_rules import pkg_analyzer
+from pants.backend.go.util_rules.build_constraints import GoBuildContext
 from pants.backend.go.util_rules.build_opts import GoBuildOptions
 from pants.backend.go.util_rules.cgo import CGoCompilerFlags
 from pants.backend.go.util_rules.embedcfg import EmbedConfig
@@ -21,13 +22,24 @@ from pants.backend.go.util_rules.go_mod import (
     OwningGoMod,
     OwningGoModRequest,
 )
+from pants.backend.go.util_rules.goroot import GoRoot
 from pants.backend.go.util_rules.pkg_analyzer import PackageAnalyzerSetup
 from pants.build_graph.address import Address
 from pants.core.target_types import ResourceSourceField
 from pants.core.util_rules import source_files
 from pants.core.util_rules.source_files import SourceFiles, SourceFilesRequest
 from pants.engine.engine_aware import EngineAwareParameter
-from pants.engine.fs import AddPrefix, CreateDigest, Digest, FileContent, MergeDigests
+from pants.engine.fs import (
+    AddPrefix,
+    CreateDigest,
+    Digest,
+    DigestSubset,
+    FileContent,
+    MergeDigests,
+    PathGlobs,
+)
+from pants.engine.internals.native_dep_inference import NativeParsedGoDependencies
+from pants.engine.internals.native_engine import NativeDependenciesRequest
 from pants.engine.process import FallibleProcessResult, Process
 from pants.engine.rules import Get, MultiGet, collect_rules, rule
 from pants.engine.target import (
@@ -205,6 +217,29 @@ class FirstPartyPkgAnalysisRequest(EngineAwareParameter):
         return self.address.spec
 
 
+@dataclass(frozen=True)
+class FirstPartyPkgImports:
+    """The imports of a first-party Go package, as determined by the Rust-based parser.
+
+    Use `FirstPartyPkgAnalysis` instead for all the metadata of the package.
+    """
+
+    import_path: str
+    imports: tuple[str, ...]
+    test_imports: tuple[str, ...]
+    xtest_imports: tuple[str, ...]
+
+
+@dataclass(frozen=True)
+class FirstPartyPkgImportsRequest(EngineAwareParameter):
+    address: Address
+    build_opts: GoBuildOptions
+    extra_build_tags: tuple[str, ...] = ()
+
+    def debug_hint(self) -> str:
+        return self.address.spec
+
+
 @dataclass(frozen=True)
 class FirstPartyPkgDigest:
     """The source files needed to build the package."""
@@ -301,6 +336,75 @@ async def analyze_first_party_package(
     )
 
 
+@rule
+async def analyze_first_party_package_imports(
+    request: FirstPartyPkgImportsRequest, goroot: GoRoot
+) -> FirstPartyPkgImports:
+    wrapped_target, import_path_info = await MultiGet(
+        Get(
+            WrappedTarget,
+            WrappedTargetRequest(
+                request.address, description_of_origin="<first party pkg imports analysis>"
+            ),
+        ),
+        Get(FirstPartyPkgImportPath, FirstPartyPkgImportPathRequest(request.address)),
+    )
+    pkg_sources = await Get(
+        HydratedSources,
+        HydrateSourcesRequest(wrapped_target.target[GoPackageSourcesField]),
+    )
+
+    build_context = GoBuildContext.create(
+        goos=goroot.goos,
+        goarch=goroot.goarch,
+        go_version=goroot.version,
+        cgo_enabled=request.build_opts.cgo_enabled,
+        build_tags=request.extra_build_tags,
+    )
+    # NB: Like `go/build`, ignore files starting with `_` or `.`, and files excluded by their
+    # name before even parsing them.
+    go_files = tuple(
+        path
+        for path in pkg_sources.snapshot.files
+        if path.endswith(".go")
+        and not os.path.basename(path).startswith(("_", "."))
+        and build_context.matches_file_name(os.path.basename(path))
+    )
+    file_digests = await MultiGet(
+        Get(Digest, DigestSubset(pkg_sources.snapshot.digest, PathGlobs([path])))
+        for path in go_files
+    )
+    parsed_files = await MultiGet(
+        Get(NativeParsedGoDependencies, NativeDependenciesRequest(digest))
+        for digest in file_digests
+    )
+
+    imports: set[str] = set()
+    test_imports: set[str] = set()
+    xtest_imports: set[str] = set()
+    for path, parsed in zip(go_files, parsed_files):
+        if parsed.build_constraint and not build_context.matches_constraint(
+            parsed.build_constraint
+        ):
+            continue
+        if "C" in parsed.imports and not build_context.cgo_enabled:
+            continue
+        if not path.endswith("_test.go"):
+            imports.update(parsed.imports)
+        elif parsed.package.endswith("_test"):
+            xtest_imports.update(parsed.imports)
+        else:
+            test_imports.update(parsed.imports)
+
+    # NB: `C` is not a real package, but the marker for Cgo.
+    return FirstPartyPkgImports(
+        import_path=import_path_info.import_path,
+        imports=tuple(sorted(imports - {"C"})),
+        test_imports=tuple(sorted(test_imports - {"C"})),
+        xtest_imports=tuple(sorted(xtest_imports - {"C"})),
+    )
+
+
 @rule
 async def setup_first_party_pkg_digest(
     request: FirstPartyPkgDigestRequest,
@@ -10,11 +10,17 @@ from dataclasses import dataclass
 
 import pkg_resources
 
-from pants.backend.java.dependency_inference.types import JavaSourceDependencyAnalysis
+from pants.backend.java.dependency_inference.types import JavaImport, JavaSourceDependencyAnalysis
+from pants.backend.java.subsystems.java_infer import JavaInferSubsystem
 from pants.core.goals.generate_lockfiles import DEFAULT_TOOL_LOCKFILE, GenerateToolLockfileSentinel
 from pants.core.util_rules.source_files import SourceFiles
 from pants.engine.fs import AddPrefix, CreateDigest, Digest, DigestContents, Directory, FileContent
-from pants.engine.internals.native_engine import MergeDigests, RemovePrefix
+from pants.engine.internals.native_dep_inference import NativeParsedJavaDependencies
+from pants.engine.internals.native_engine import (
+    MergeDigests,
+    NativeDependenciesRequest,
+    RemovePrefix,
+)
 from pants.engine.process import FallibleProcessResult, ProcessResult, ProductDescription
 from pants.engine.rules import Get, MultiGet, collect_rules, rule
 from pants.engine.unions import UnionRule
@@ -51,8 +57,28 @@ class JavaParserCompiledClassfiles:
 
 @rule(level=LogLevel.DEBUG)
 async def resolve_fallible_result_to_analysis(
-    fallible_result: FallibleJavaSourceDependencyAnalysisResult,
+    request: JavaSourceDependencyAnalysisRequest,
+    java_infer_subsystem: JavaInferSubsystem,
 ) -> JavaSourceDependencyAnalysis:
+    if java_infer_subsystem.use_rust_parser:
+        native_result = await Get(
+            NativeParsedJavaDependencies,
+            NativeDependenciesRequest(request.source_files.snapshot.digest),
+        )
+        return JavaSourceDependencyAnalysis(
+            declared_package=native_result.declared_package,
+            imports=tuple(
+                JavaImport(name=name, is_static=is_static, is_asterisk=is_asterisk)
+                for name, is_static, is_asterisk in native_result.imports
+            ),
+            top_level_types=native_result.top_level_types,
+            consumed_types=native_result.consumed_types,
+            export_types=native_result.export_types,
+        )
+
+    fallible_result = await Get(
+        FallibleJavaSourceDependencyAnalysisResult, JavaSourceDependencyAnalysisRequest, request
+    )
     desc = ProductDescription("Java source dependency analysis failed.")
     result = await Get(
         ProcessResult,
@@ -309,3 +309,50 @@ def test_java_parser_consumed_types(rule_runner: RuleRunner) -> None:
         "String",
         "provider",  # note: false positive on a variable identifier
     ]
+
+
+def test_rust_parser_analysis(rule_runner: RuleRunner) -> None:
+    rule_runner.set_options(["--java-infer-use-rust-parser"], env_inherit=PYTHON_BOOTSTRAP_ENV)
+    rule_runner.write_files(
+        {
+            "BUILD": "java_source(name='source', source='Source.java')",
+            "Source.java": dedent(
+                """
+                package org.pantsbuild.test;
+
+                import java.util.Date;
+                import static bogus.Foo.*;
+
+                public class Source extends Base {
+                    public Result compute(Input input) {
+                        StaticClassRef.someMethod();
+                        return null;
+                    }
+                }
+                """
+            ),
+        }
+    )
+
+    target = rule_runner.get_target(address=Address(spec_path="", target_name="source"))
+
+    source_files = rule_runner.request(
+        SourceFiles,
+        [
+            SourceFilesRequest(
+                (target.get(SourcesField),),
+                for_sources_types=(JavaSourceField,),
+                enable_codegen=True,
+            )
+        ],
+    )
+
+    analysis = rule_runner.request(JavaSourceDependencyAnalysis, [source_files])
+    assert analysis.declared_package == "org.pantsbuild.test"
+    assert analysis.imports == (
+        JavaImport(name="java.util.Date"),
+        JavaImport(name="bogus.Foo", is_asterisk=True, is_static=True),
+    )
+    assert analysis.top_level_types == ("org.pantsbuild.test.Source",)
+    assert analysis.consumed_types == ("Base", "Input", "Result", "StaticClassRef")
+    assert analysis.export_types == ("Base", "Input", "Result")
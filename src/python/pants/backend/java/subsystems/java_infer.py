@@ -4,6 +4,7 @@ from typing import Any
 
 from pants.option.option_types import BoolOption, DictOption
 from pants.option.subsystem import Subsystem
+from pants.util.docutil import bin_name
 from pants.util.strutil import softwrap
 
 
@@ -19,6 +20,24 @@ class JavaInferSubsystem(Subsystem):
         default=True,
         help="Infer a target's dependencies by parsing consumed types from sources.",
     )
+    use_rust_parser = BoolOption(
+        default=False,
+        help=softwrap(
+            f"""
+            Use the Rust-based, in-process dependency parser, instead of the
+            JavaParser-based analysis.
+
+            The Rust-based parser uses tree-sitter, and runs in the same process as Pants itself,
+            instead of launching a JVM process to analyze each Java source file. This
+            significantly improves cold-cache performance.
+
+            If you think the new behaviour is causing problems, it is recommended that you run
+            `{bin_name()} peek :: > before.json` and then
+            `{bin_name()} --java-infer-use-rust-parser peek :: > after.json` and compare the two
+            results.
+            """
+        ),
+    )
     # TODO: Move to `coursier` or a generic `jvm` subsystem.
     third_party_import_mapping = DictOption[Any](
         help=softwrap(
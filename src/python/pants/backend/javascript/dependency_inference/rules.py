@@ -9,23 +9,28 @@ from typing import Iterable
 
 from pants.backend.javascript import package_json
 from pants.backend.javascript.package_json import (
+    AllPackageJson,
     FirstPartyNodePackageTargets,
     NodePackageDependenciesField,
     NodePackageNameField,
     OwningNodePackage,
     OwningNodePackageRequest,
+    PackageJson,
     PackageJsonEntryPoints,
     PackageJsonImports,
     PackageJsonSourceField,
 )
 from pants.backend.javascript.subsystems.nodejs_infer import NodeJSInfer
 from pants.backend.javascript.target_types import JSDependenciesField, JSSourceField
+from pants.backend.typescript import tsconfig
+from pants.backend.typescript.tsconfig import ParentTSConfig, ParentTSConfigRequest
 from pants.build_graph.address import Address
 from pants.engine.addresses import Addresses
+from pants.engine.fs import PathGlobs, Paths
 from pants.engine.internals.graph import Owners, OwnersRequest
 from pants.engine.internals.native_dep_inference import NativeParsedJavascriptDependencies
 from pants.engine.internals.native_engine import InferenceMetadata, NativeDependenciesRequest
-from pants.engine.internals.selectors import Get
+from pants.engine.internals.selectors import Get, MultiGet
 from pants.engine.rules import Rule, collect_rules, rule
 from pants.engine.target import (
     FieldSet,
@@ -90,31 +95,50 @@ class RequestNodePackagesCandidateMap:
 
 @rule
 async def map_candidate_node_packages(
-    req: RequestNodePackagesCandidateMap, first_party: FirstPartyNodePackageTargets
+    req: RequestNodePackagesCandidateMap,
+    first_party: FirstPartyNodePackageTargets,
+    all_pkg_jsons: AllPackageJson,
 ) -> NodePackageCandidateMap:
     owning_pkg = await Get(OwningNodePackage, OwningNodePackageRequest(req.address))
     candidate_tgts = itertools.chain(
         first_party, owning_pkg.third_party if owning_pkg != OwningNodePackage.no_owner() else ()
     )
-    return NodePackageCandidateMap(
-        (tgt[NodePackageNameField].value, tgt.address) for tgt in candidate_tgts
-    )
+    candidates = {tgt[NodePackageNameField].value: tgt.address for tgt in candidate_tgts}
+    if owning_pkg.target:
+        pkg_json = await Get(
+            PackageJson, PackageJsonSourceField, owning_pkg.target[PackageJsonSourceField]
+        )
+        # The `workspace:` protocol may alias a first party package under another name.
+        for name, workspace_name in pkg_json.workspace_dependencies(all_pkg_jsons).items():
+            if workspace_name in candidates:
+                candidates.setdefault(name, candidates[workspace_name])
+    return NodePackageCandidateMap(candidates)
 
 
-@rule
-async def prepare_inference_metadata(imports: PackageJsonImports) -> InferenceMetadata:
-    return InferenceMetadata.javascript(
-        imports.root_dir,
-        {pattern: list(replacements) for pattern, replacements in imports.imports.items()},
+async def _prepare_inference_metadata(address: Address, file_path: str) -> InferenceMetadata:
+    owning_pkg, parent_ts_config = await MultiGet(
+        Get(OwningNodePackage, OwningNodePackageRequest(address)),
+        Get(ParentTSConfig, ParentTSConfigRequest(file_path)),
     )
-
-
-async def _prepare_inference_metadata(address: Address) -> InferenceMetadata:
-    owning_pkg = await Get(OwningNodePackage, OwningNodePackageRequest(address))
     if not owning_pkg.target:
-        return InferenceMetadata.javascript(address.spec_path, {})
-    return await Get(
-        InferenceMetadata, PackageJsonSourceField, owning_pkg.target[PackageJsonSourceField]
+        package_root, import_patterns = address.spec_path, {}
+    else:
+        imports = await Get(
+            PackageJsonImports, PackageJsonSourceField, owning_pkg.target[PackageJsonSourceField]
+        )
+        package_root = imports.root_dir
+        import_patterns = {
+            pattern: list(replacements) for pattern, replacements in imports.imports.items()
+        }
+    config = parent_ts_config.config
+    if not config:
+        return InferenceMetadata.javascript(package_root, import_patterns)
+    return InferenceMetadata.javascript(
+        package_root,
+        import_patterns,
+        config_root=config.resolution_root,
+        config_paths={pattern: list(paths) for pattern, paths in config.paths.items()},
+        config_has_base_url=config.base_url is not None,
     )
 
 
@@ -130,14 +154,24 @@ async def infer_js_source_dependencies(
     sources = await Get(
         HydratedSources, HydrateSourcesRequest(source, for_sources_types=[JSSourceField])
     )
-    metadata = await _prepare_inference_metadata(request.field_set.address)
+    metadata = await _prepare_inference_metadata(request.field_set.address, source.file_path)
 
     import_strings = await Get(
         NativeParsedJavascriptDependencies,
         NativeDependenciesRequest(sources.snapshot.digest, metadata),
     )
 
-    owners = await Get(Owners, OwnersRequest(tuple(import_strings.file_imports)))
+    # NB: Imports resolved via a `tsconfig.json` or `jsconfig.json` are candidates, of which only
+    # those that exist are dependencies. Paths outside of the build root can't be owned anyway.
+    existing_file_imports = await Get(
+        Paths,
+        PathGlobs(
+            sorted(
+                path for path in import_strings.file_imports if not path.startswith(("/", "../"))
+            )
+        ),
+    )
+    owners = await Get(Owners, OwnersRequest(existing_file_imports.files))
     owning_targets = await Get(Targets, Addresses(owners))
 
     non_path_string_bases = FrozenOrderedSet(
@@ -165,6 +199,7 @@ def rules() -> Iterable[Rule | UnionRule]:
     return [
         *collect_rules(),
         *package_json.rules(),
+        *tsconfig.rules(),
         UnionRule(InferDependenciesRequest, InferNodePackageDependenciesRequest),
         UnionRule(InferDependenciesRequest, InferJSDependenciesRequest),
     ]
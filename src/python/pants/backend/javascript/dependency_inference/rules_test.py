@@ -436,3 +436,104 @@ def test_infers_first_party_package_json_field_js_source_dependency_with_starred
     ).include
 
     assert set(addresses) == {Address("src/js/b", generated_name="spam")}
+
+
+def test_infers_js_source_dependencies_from_tsconfig_paths(rule_runner: RuleRunner) -> None:
+    rule_runner.write_files(
+        {
+            "tsconfig.base.json": dedent(
+                """\
+                {
+                  // Comments and trailing commas are allowed.
+                  "compilerOptions": {
+                    "paths": {
+                      "@app/*": ["./src/js/app/*"],
+                      "@utils": ["./src/js/utils/index.mjs"],
+                    },
+                  },
+                }
+                """
+            ),
+            "src/js/jsconfig.json": json.dumps({"extends": "../../tsconfig.base.json"}),
+            "src/js/BUILD": "javascript_sources()",
+            "src/js/index.mjs": dedent(
+                """\
+                import { Button } from "@app/components/Button";
+                import { helper } from "@utils";
+                import { missing } from "@app/missing";
+                """
+            ),
+            "src/js/app/components/BUILD": "javascript_sources()",
+            "src/js/app/components/Button.mjs": "",
+            "src/js/utils/BUILD": "javascript_sources()",
+            "src/js/utils/index.mjs": "",
+        }
+    )
+
+    index_tgt = rule_runner.get_target(Address("src/js", relative_file_path="index.mjs"))
+    addresses = rule_runner.request(
+        InferredDependencies,
+        [InferJSDependenciesRequest(JSSourceInferenceFieldSet.create(index_tgt))],
+    ).include
+
+    assert set(addresses) == {
+        Address("src/js/app/components", relative_file_path="Button.mjs"),
+        Address("src/js/utils", relative_file_path="index.mjs"),
+    }
+
+
+def test_infers_js_source_dependencies_from_tsconfig_base_url(rule_runner: RuleRunner) -> None:
+    rule_runner.write_files(
+        {
+            "src/js/tsconfig.json": json.dumps({"compilerOptions": {"baseUrl": "lib"}}),
+            "src/js/BUILD": "javascript_sources()",
+            "src/js/index.mjs": 'import { api } from "services/api";',
+            "src/js/lib/services/api/BUILD": "javascript_sources()",
+            "src/js/lib/services/api/index.js": "",
+        }
+    )
+
+    index_tgt = rule_runner.get_target(Address("src/js", relative_file_path="index.mjs"))
+    addresses = rule_runner.request(
+        InferredDependencies,
+        [InferJSDependenciesRequest(JSSourceInferenceFieldSet.create(index_tgt))],
+    ).include
+
+    assert set(addresses) == {Address("src/js/lib/services/api", relative_file_path="index.js")}
+
+
+def test_infers_first_party_package_dependency_from_workspace_protocol_alias(
+    rule_runner: RuleRunner,
+) -> None:
+    rule_runner.write_files(
+        {
+            "src/js/a/BUILD": "package_json()",
+            "src/js/a/package.json": given_package(
+                "ham",
+                "0.0.1",
+                dependencies={"eggs": "workspace:spam@*", "bacon": "workspace:../c"},
+            ),
+            "src/js/a/lib/BUILD": "javascript_sources()",
+            "src/js/a/lib/index.js": dedent(
+                """\
+                import { x } from "eggs";
+                const y = await import("bacon");
+                """
+            ),
+            "src/js/b/BUILD": "package_json()",
+            "src/js/b/package.json": given_package("spam", "0.0.1"),
+            "src/js/c/BUILD": "package_json()",
+            "src/js/c/package.json": given_package("bacon-impl", "0.0.1"),
+        }
+    )
+
+    pkg_tgt = rule_runner.get_target(Address("src/js/a/lib", relative_file_path="index.js"))
+    addresses = rule_runner.request(
+        InferredDependencies,
+        [InferJSDependenciesRequest(JSSourceInferenceFieldSet.create(pkg_tgt))],
+    ).include
+
+    assert set(addresses) == {
+        Address("src/js/b", generated_name="spam"),
+        Address("src/js/c", generated_name="bacon-impl"),
+    }
@@ -612,6 +612,34 @@ class PackageJson:
     def root_dir(self) -> str:
         return os.path.dirname(self.file)
 
+    def workspace_dependencies(self, workspace: Iterable[PackageJson]) -> FrozenDict[str, str]:
+        """The dependencies that use the `workspace:` protocol of pnpm and yarn, mapped to the name
+        of the package in the `workspace` they refer to.
+
+        The protocol either refers to the package of the same name (`workspace:*` or
+        `workspace:^1.0.0`), to an aliased package (`workspace:other@*`) or, with yarn, to the
+        directory of a package (`workspace:../other`).
+        """
+        names_by_dir = {pkg.root_dir: pkg.name for pkg in workspace}
+        result = {}
+        for name, version in self.dependencies.items():
+            if not version.startswith(WORKSPACE_PROTOCOL):
+                continue
+            spec = version[len(WORKSPACE_PROTOCOL) :]
+            if spec.startswith("."):
+                path = os.path.normpath(os.path.join(self.root_dir, spec))
+                if path in names_by_dir:
+                    result[name] = names_by_dir[path]
+            elif spec and (spec[0] == "@" or spec[0].isalpha()):
+                version_sep = spec.find("@", 1)
+                result[name] = spec if version_sep == -1 else spec[:version_sep]
+            else:
+                result[name] = name
+        return FrozenDict(result)
+
+
+WORKSPACE_PROTOCOL = "workspace:"
+
 
 class FirstPartyNodePackageTargets(Targets):
     pass
@@ -865,7 +893,7 @@ async def generate_node_package_targets(
             union_membership,
         )
         for name, version in pkg_json.dependencies.items()
-        if name not in first_party_names
+        if name not in first_party_names and not version.startswith(WORKSPACE_PROTOCOL)
     ]
 
     package_target = NodePackageTarget(
@@ -124,6 +124,42 @@ def test_does_not_generate_third_party_node_package_target_for_first_party_packa
     ]
 
 
+def test_parses_workspace_protocol_dependencies(rule_runner: RuleRunner) -> None:
+    rule_runner.write_files(
+        {
+            "src/js/a/BUILD": "package_json()",
+            "src/js/a/package.json": json.dumps(
+                {
+                    "name": "ham",
+                    "version": "0.0.1",
+                    "dependencies": {
+                        "chalk": "^5.2.0",
+                        "spam": "workspace:*",
+                        "eggs": "workspace:@scope/eggs@^1.0.0",
+                        "bacon": "workspace:../b",
+                    },
+                }
+            ),
+            "src/js/b/BUILD": "package_json()",
+            "src/js/b/package.json": given_package("spam", "0.0.1"),
+        }
+    )
+    pkg_jsons = rule_runner.request(AllPackageJson, [])
+    [ham] = (pkg for pkg in pkg_jsons if pkg.name == "ham")
+    assert ham.workspace_dependencies(pkg_jsons) == FrozenDict(
+        {"spam": "spam", "eggs": "@scope/eggs", "bacon": "spam"}
+    )
+
+    addresses = sorted(str(tgt.address) for tgt in rule_runner.request(AllTargets, ()))
+    assert addresses == [
+        "src/js/a#chalk",
+        "src/js/a#ham",
+        "src/js/a#src/js/a/package.json",
+        "src/js/b#spam",
+        "src/js/b#src/js/b/package.json",
+    ]
+
+
 def test_does_not_consider_package_json_without_a_target(
     rule_runner: RuleRunner,
 ) -> None:
@@ -7,10 +7,18 @@ import os
 from dataclasses import dataclass
 from typing import Any, Iterator
 
+from pants.backend.kotlin.subsystems.kotlin_infer import KotlinInferSubsystem
 from pants.core.goals.generate_lockfiles import DEFAULT_TOOL_LOCKFILE, GenerateToolLockfileSentinel
 from pants.core.util_rules.source_files import SourceFiles
 from pants.engine.fs import CreateDigest, DigestContents, Directory, FileContent
-from pants.engine.internals.native_engine import AddPrefix, Digest, MergeDigests, RemovePrefix
+from pants.engine.internals.native_dep_inference import NativeParsedKotlinDependencies
+from pants.engine.internals.native_engine import (
+    AddPrefix,
+    Digest,
+    MergeDigests,
+    NativeDependenciesRequest,
+    RemovePrefix,
+)
 from pants.engine.internals.selectors import Get, MultiGet
 from pants.engine.process import FallibleProcessResult, ProcessResult, ProductDescription
 from pants.engine.rules import collect_rules, rule
@@ -117,6 +125,19 @@ class KotlinSourceDependencyAnalysis:
             scopes=frozenset(d["scopes"]),
         )
 
+    @classmethod
+    def from_native(cls, native: NativeParsedKotlinDependencies) -> KotlinSourceDependencyAnalysis:
+        return cls(
+            package=native.package,
+            imports=frozenset(
+                KotlinImport(name=name, alias=alias, is_wildcard=is_wildcard)
+                for name, alias, is_wildcard in native.imports
+            ),
+            named_declarations=native.named_declarations,
+            consumed_symbols_by_scope=native.consumed_symbols_by_scope,
+            scopes=native.scopes,
+        )
+
     def to_debug_json_dict(self) -> dict[str, Any]:
         return {
             "package": self.package,
@@ -209,8 +230,17 @@ async def analyze_kotlin_source_dependencies(
 
 @rule(level=LogLevel.DEBUG)
 async def resolve_fallible_result_to_analysis(
-    fallible_result: FallibleKotlinSourceDependencyAnalysisResult,
+    source_files: SourceFiles, kotlin_infer_subsystem: KotlinInferSubsystem
 ) -> KotlinSourceDependencyAnalysis:
+    if kotlin_infer_subsystem.use_rust_parser:
+        native_result = await Get(
+            NativeParsedKotlinDependencies, NativeDependenciesRequest(source_files.snapshot.digest)
+        )
+        return KotlinSourceDependencyAnalysis.from_native(native_result)
+
+    fallible_result = await Get(
+        FallibleKotlinSourceDependencyAnalysisResult, SourceFiles, source_files
+    )
     desc = ProductDescription("Kotlin source dependency analysis failed.")
     result = await Get(
         ProcessResult,
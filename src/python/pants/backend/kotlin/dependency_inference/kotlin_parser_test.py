@@ -121,3 +121,48 @@ def test_parser_simple(rule_runner: RuleRunner) -> None:
         "org.pantsbuild.backend.kotlin.Foo",
         "org.pantsbuild.backend.kotlin.Bar",
     }
+
+
+def test_rust_parser(rule_runner: RuleRunner) -> None:
+    rule_runner.set_options(["--kotlin-infer-use-rust-parser"], env_inherit=PYTHON_BOOTSTRAP_ENV)
+    analysis = _analyze(
+        rule_runner,
+        textwrap.dedent(
+            """\
+            package org.pantsbuild.backend.kotlin
+
+            import java.io.File
+            import java.util.*
+
+            open class Foo {
+              fun grok() {
+                val x = X()
+                val y = Y()
+              }
+            }
+
+            fun main(args: Array<String>) {
+            }
+            """
+        ),
+    )
+
+    assert analysis.package == "org.pantsbuild.backend.kotlin"
+    assert analysis.imports == {
+        KotlinImport(name="java.io.File", alias=None, is_wildcard=False),
+        KotlinImport(name="java.util", alias=None, is_wildcard=True),
+    }
+    assert analysis.named_declarations == {
+        "org.pantsbuild.backend.kotlin.Foo",
+        "org.pantsbuild.backend.kotlin.main",
+    }
+    assert analysis.consumed_symbols_by_scope == FrozenDict(
+        {
+            "org.pantsbuild.backend.kotlin.Foo": frozenset({"X", "Y"}),
+            "org.pantsbuild.backend.kotlin": frozenset({"Array", "String"}),
+        }
+    )
+    assert analysis.scopes == {
+        "org.pantsbuild.backend.kotlin",
+        "org.pantsbuild.backend.kotlin.Foo",
+    }
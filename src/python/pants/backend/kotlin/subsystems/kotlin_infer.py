@@ -2,6 +2,8 @@
 # Licensed under the Apache License, Version 2.0 (see LICENSE).
 from pants.option.option_types import BoolOption
 from pants.option.subsystem import Subsystem
+from pants.util.docutil import bin_name
+from pants.util.strutil import softwrap
 
 
 class KotlinInferSubsystem(Subsystem):
@@ -17,3 +19,22 @@ class KotlinInferSubsystem(Subsystem):
         default=True,
         help="Infer a target's dependencies by parsing consumed types from sources.",
     )
+
+    use_rust_parser = BoolOption(
+        default=False,
+        help=softwrap(
+            f"""
+            Use the Rust-based, in-process dependency parser, instead of the
+            Kotlin compiler-based analysis.
+
+            The Rust-based parser uses tree-sitter, and runs in the same process as Pants itself,
+            instead of launching a JVM process to analyze each Kotlin source file. This
+            significantly improves cold-cache performance.
+
+            If you think the new behaviour is causing problems, it is recommended that you run
+            `{bin_name()} peek :: > before.json` and then
+            `{bin_name()} --kotlin-infer-use-rust-parser peek :: > after.json` and compare the two
+            results.
+            """
+        ),
+    )
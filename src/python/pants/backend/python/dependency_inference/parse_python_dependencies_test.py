@@ -171,8 +171,36 @@ def test_dunder_import_call(rule_runner: RuleRunner) -> None:
         rule_runner,
         content,
         expected_imports={
-            "pkg_resources": ImpInfo(lineno=1, weak=False),
-            "not_ignored": ImpInfo(lineno=13, weak=False),
+            "pkg_resources": ImpInfo(lineno=1, weak=True),
+            "not_ignored": ImpInfo(lineno=13, weak=True),
+        },
+    )
+
+
+def test_dynamic_import_calls(rule_runner: RuleRunner) -> None:
+    content = dedent(
+        """\
+        import importlib
+        from importlib import import_module
+
+        importlib.import_module("dynamic")
+        import_module("from_import")
+        lazy_import.lazy_module("lazy")
+        importlib.import_module("ignored")  # pants: no-infer-dep
+        importlib.import_module(name)
+        importlib.import_module(".relative", package="pkg")
+        """
+    )
+    assert_deps_parsed(
+        rule_runner,
+        content,
+        string_imports=False,
+        expected_imports={
+            "importlib": ImpInfo(lineno=1, weak=False),
+            "importlib.import_module": ImpInfo(lineno=2, weak=False),
+            "dynamic": ImpInfo(lineno=4, weak=True),
+            "from_import": ImpInfo(lineno=5, weak=True),
+            "lazy": ImpInfo(lineno=6, weak=True),
         },
     )
 
@@ -472,8 +500,8 @@ def test_works_with_python2(rule_runner: RuleRunner) -> None:
         expected_imports={
             "demo": ImpInfo(lineno=4, weak=False),
             "project.demo.Demo": ImpInfo(lineno=5, weak=False),
-            "pkg_resources": ImpInfo(lineno=7, weak=False),
-            "treat.as.a.regular.import.not.a.string.import": ImpInfo(lineno=8, weak=False),
+            "pkg_resources": ImpInfo(lineno=7, weak=True),
+            "treat.as.a.regular.import.not.a.string.import": ImpInfo(lineno=8, weak=True),
             "dep.from.bytes": ImpInfo(lineno=11, weak=True),
             "dep.from.str": ImpInfo(lineno=12, weak=True),
             "dep.from.str_狗": ImpInfo(lineno=13, weak=True),
@@ -509,8 +537,8 @@ def test_works_with_python38(rule_runner: RuleRunner) -> None:
         expected_imports={
             "demo": ImpInfo(lineno=5, weak=False),
             "project.demo.Demo": ImpInfo(lineno=6, weak=False),
-            "pkg_resources": ImpInfo(lineno=8, weak=False),
-            "treat.as.a.regular.import.not.a.string.import": ImpInfo(lineno=9, weak=False),
+            "pkg_resources": ImpInfo(lineno=8, weak=True),
+            "treat.as.a.regular.import.not.a.string.import": ImpInfo(lineno=9, weak=True),
             "dep.from.str": ImpInfo(lineno=11, weak=True),
         },
     )
@@ -542,8 +570,8 @@ def test_works_with_python39(rule_runner: RuleRunner) -> None:
         expected_imports={
             "demo": ImpInfo(lineno=7, weak=False),
             "project.demo.Demo": ImpInfo(lineno=8, weak=False),
-            "pkg_resources": ImpInfo(lineno=10, weak=False),
-            "treat.as.a.regular.import.not.a.string.import": ImpInfo(lineno=11, weak=False),
+            "pkg_resources": ImpInfo(lineno=10, weak=True),
+            "treat.as.a.regular.import.not.a.string.import": ImpInfo(lineno=11, weak=True),
             "dep.from.str": ImpInfo(lineno=13, weak=True),
         },
         expected_assets=["/dev/null"],
@@ -9,6 +9,7 @@ from dataclasses import dataclass
 from typing import Any, Iterator, Mapping
 
 from pants.backend.scala.subsystems.scala import ScalaSubsystem
+from pants.backend.scala.subsystems.scala_infer import ScalaInferSubsystem
 from pants.backend.scala.subsystems.scalac import Scalac
 from pants.backend.scala.util_rules.versions import (
     ScalaArtifactsForVersionRequest,
@@ -27,6 +28,8 @@ from pants.engine.fs import (
     MergeDigests,
     RemovePrefix,
 )
+from pants.engine.internals.native_dep_inference import NativeParsedScalaDependencies
+from pants.engine.internals.native_engine import NativeDependenciesRequest
 from pants.engine.internals.selectors import Get, MultiGet
 from pants.engine.process import FallibleProcessResult, ProcessResult, ProductDescription
 from pants.engine.rules import collect_rules, rule
@@ -207,6 +210,38 @@ class ScalaSourceDependencyAnalysis:
             scopes=FrozenOrderedSet(d["scopes"]),
         )
 
+    @classmethod
+    def from_native(cls, native: NativeParsedScalaDependencies) -> ScalaSourceDependencyAnalysis:
+        return cls(
+            provided_symbols=FrozenOrderedSet(
+                ScalaProvidedSymbol(name=name, recursive=recursive)
+                for name, recursive in native.provided_symbols
+            ),
+            provided_symbols_encoded=FrozenOrderedSet(
+                ScalaProvidedSymbol(name=name, recursive=recursive)
+                for name, recursive in native.provided_symbols_encoded
+            ),
+            imports_by_scope=FrozenDict(
+                {
+                    key: tuple(
+                        ScalaImport(name=name, alias=alias, is_wildcard=is_wildcard)
+                        for name, alias, is_wildcard in values
+                    )
+                    for key, values in native.imports_by_scope.items()
+                }
+            ),
+            _consumed_symbols_by_scope=FrozenDict(
+                {
+                    key: FrozenOrderedSet(
+                        ScalaConsumedSymbol(name=name, is_absolute=is_absolute)
+                        for name, is_absolute in values
+                    )
+                    for key, values in native.consumed_symbols_by_scope.items()
+                }
+            ),
+            scopes=FrozenOrderedSet(native.scopes),
+        )
+
     def to_debug_json_dict(self) -> dict[str, Any]:
         return {
             "provided_symbols": [v.to_debug_json_dict() for v in self.provided_symbols],
@@ -334,8 +369,18 @@ async def analyze_scala_source_dependencies(
 
 @rule(level=LogLevel.DEBUG)
 async def resolve_fallible_result_to_analysis(
-    fallible_result: FallibleScalaSourceDependencyAnalysisResult,
+    request: AnalyzeScalaSourceRequest, scala_infer_subsystem: ScalaInferSubsystem
 ) -> ScalaSourceDependencyAnalysis:
+    if scala_infer_subsystem.use_rust_parser:
+        native_result = await Get(
+            NativeParsedScalaDependencies,
+            NativeDependenciesRequest(request.source_files.snapshot.digest),
+        )
+        return ScalaSourceDependencyAnalysis.from_native(native_result)
+
+    fallible_result = await Get(
+        FallibleScalaSourceDependencyAnalysisResult, AnalyzeScalaSourceRequest, request
+    )
     description = ProductDescription("Scala source dependency analysis failed.")
     result = await Get(
         ProcessResult,
@@ -778,3 +778,49 @@ def test_self_types_on_same_package(rule_runner: RuleRunner) -> None:
     assert sorted(analysis.fully_qualified_consumed_symbols()) == [
         "foo.Foo",
     ]
+
+
+def test_rust_parser(rule_runner: RuleRunner) -> None:
+    rule_runner.set_options(
+        args=["-ldebug", "--scala-infer-use-rust-parser"], env_inherit=PYTHON_BOOTSTRAP_ENV
+    )
+    analysis = _analyze(
+        rule_runner,
+        textwrap.dedent(
+            """\
+            package foo
+
+            import scala.{io => sio}
+            import a.b._
+
+            object Bar {
+                def a = ???
+            }
+
+            class Baz(param: Param) extends Base
+            """
+        ),
+    )
+
+    assert analysis.provided_symbols == FrozenOrderedSet(
+        [
+            ScalaProvidedSymbol(name="foo.Bar", recursive=False),
+            ScalaProvidedSymbol(name="foo.Bar.a", recursive=False),
+            ScalaProvidedSymbol(name="foo.Baz", recursive=False),
+        ]
+    )
+    assert analysis.imports_by_scope == FrozenDict(
+        {
+            "foo": (
+                ScalaImport(name="scala.io", alias="sio", is_wildcard=False),
+                ScalaImport(name="a.b", alias=None, is_wildcard=True),
+            ),
+        }
+    )
+    assert analysis.scopes == FrozenOrderedSet(["foo"])
+    assert analysis.consumed_symbols_by_scope == FrozenDict(
+        {
+            "foo": FrozenOrderedSet(["Base", "Param"]),
+            "foo.Bar": FrozenOrderedSet(["???"]),
+        }
+    )
@@ -3,6 +3,7 @@
 
 from pants.option.option_types import BoolOption
 from pants.option.subsystem import Subsystem
+from pants.util.docutil import bin_name
 from pants.util.strutil import softwrap
 
 
@@ -37,3 +38,21 @@ class ScalaInferSubsystem(Subsystem):
             """
         ),
     )
+    use_rust_parser = BoolOption(
+        default=False,
+        help=softwrap(
+            f"""
+            Use the Rust-based, in-process dependency parser, instead of the
+            Scalameta-based analysis.
+
+            The Rust-based parser uses tree-sitter, and runs in the same process as Pants itself,
+            instead of launching a JVM process to analyze each Scala source file. This
+            significantly improves cold-cache performance.
+
+            If you think the new behaviour is causing problems, it is recommended that you run
+            `{bin_name()} peek :: > before.json` and then
+            `{bin_name()} --scala-infer-use-rust-parser peek :: > after.json` and compare the two
+            results.
+            """
+        ),
+    )
@@ -0,0 +1,252 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+"""Extraction of the files that the commands of a Shell script refer to, beyond the `source`
+statements that Shellcheck can follow."""
+
+from __future__ import annotations
+
+import re
+from dataclasses import dataclass, field
+
+# Words which may precede the name of the command in a simple command.
+_COMMAND_PREFIXES = frozenset(
+    ("!", "{", "}", "if", "then", "elif", "else", "fi", "do", "done", "while", "until", "time")
+)
+_ASSIGNMENT = re.compile(r"^[A-Za-z_][A-Za-z0-9_]*(\[[^]]*\])?\+?=")
+_REDIRECTION = re.compile(r"^[0-9]*(<<?<?|>>?|<>|>\|)&?-?")
+_PARAMETER = re.compile(r"\$([A-Za-z_][A-Za-z0-9_]*|[0-9@*#?$!-])")
+
+
+@dataclass(frozen=True)
+class ShellCommandReferences:
+    # The programs run by `command`, `exec` or in a command substitution (`$(tool ...)`), as
+    # written.
+    tools: tuple[str, ...] = ()
+    # The paths of `source` (or `.`) statements which start with a variable or command
+    # substitution, e.g. `source "$(dirname "$0")/lib.sh"`, without that prefix (i.e. `lib.sh`).
+    weak_sources: tuple[str, ...] = ()
+
+
+@dataclass
+class _Word:
+    text: str = ""
+    # Whether the word starts with an expansion, and the index at which the literal text following
+    # its last expansion starts.
+    starts_with_expansion: bool = False
+    literal_start: int = 0
+
+    def append_expansion(self, expansion: str) -> None:
+        if not self.text:
+            self.starts_with_expansion = True
+        self.text += expansion
+        self.literal_start = len(self.text)
+
+
+@dataclass
+class _Lexer:
+    script: str
+    commands: list[tuple[list[_Word], bool]] = field(default_factory=list)
+
+    def lex(self, start: int, in_substitution: bool) -> int:
+        """Lexes the simple commands from `start`, until the end of the script or (when in a
+        command substitution) the closing `)`, and returns the index after the end."""
+        script = self.script
+        words: list[_Word] = []
+        word = _Word()
+        heredoc_delimiters: list[str] = []
+        # The depth of the subshells (and e.g. `case` patterns) opened in this command substitution.
+        depth = 0
+        i = start
+
+        def end_word() -> None:
+            nonlocal word
+            if word.text:
+                words.append(word)
+            word = _Word()
+
+        def end_command() -> None:
+            nonlocal words
+            end_word()
+            if words:
+                self.commands.append((words, in_substitution))
+                heredoc_delimiters.extend(_heredoc_delimiters(words))
+            words = []
+
+        while i < len(script):
+            c = script[i]
+            if c == "#" and not word.text:
+                newline = script.find("\n", i)
+                i = len(script) if newline == -1 else newline
+            elif c == "\\":
+                if script.startswith("\n", i + 1):
+                    i += 2
+                else:
+                    word.text += script[i + 1 : i + 2]
+                    i += 2
+            elif c in " \t":
+                end_word()
+                i += 1
+            elif c == ")" and in_substitution and depth == 0:
+                end_command()
+                return i + 1
+            elif c == "&" and word.text and word.text[-1] in "<>":
+                word.text += c
+                i += 1
+            elif c in "\n;&|()":
+                end_command()
+                depth += {"(": 1, ")": -1}.get(c, 0)
+                i += 1
+                if c == "\n" and heredoc_delimiters:
+                    i = _skip_heredocs(script, i, heredoc_delimiters)
+                    heredoc_delimiters.clear()
+            elif c == "'":
+                end = script.find("'", i + 1)
+                end = len(script) if end == -1 else end
+                word.text += script[i + 1 : end]
+                i = end + 1
+            elif c == '"':
+                i = self._lex_double_quoted(i + 1, word)
+            elif c == "`" or c == "$":
+                i = self._lex_expansion(i, word)
+            else:
+                word.text += c
+                i += 1
+        end_command()
+        return i
+
+    def _lex_double_quoted(self, start: int, word: _Word) -> int:
+        script = self.script
+        i = start
+        while i < len(script) and script[i] != '"':
+            if script[i] == "\\":
+                word.text += script[i + 1 : i + 2]
+                i += 2
+            elif script[i] in "`$":
+                i = self._lex_expansion(i, word)
+            else:
+                word.text += script[i]
+                i += 1
+        return i + 1
+
+    def _lex_expansion(self, start: int, word: _Word) -> int:
+        """Lexes the expansion (or the lone `$`) at `start`, and returns the index after it."""
+        script = self.script
+        if script[start] == "`":
+            end = start + 1
+            while end < len(script) and script[end] != "`":
+                end += 2 if script[end] == "\\" else 1
+            self._lex_nested(script[start + 1 : end])
+            word.append_expansion(script[start : end + 1])
+            return end + 1
+        if script.startswith("$((", start):
+            end = _matching_close(script, start + 1, "(", ")")
+        elif script.startswith("$(", start):
+            end = self.lex(start + 2, in_substitution=True)
+        elif script.startswith("${", start):
+            end = _matching_close(script, start + 1, "{", "}")
+        else:
+            match = _PARAMETER.match(script, start)
+            if not match:
+                word.text += "$"
+                return start + 1
+            end = match.end()
+        word.append_expansion(script[start:end])
+        return end
+
+    def _lex_nested(self, script: str) -> None:
+        nested = _Lexer(script)
+        nested.lex(0, in_substitution=False)
+        self.commands.extend((words, True) for words, _ in nested.commands)
+
+
+def _matching_close(script: str, start: int, open: str, close: str) -> int:
+    """The index after the `close` matching the `open` at `start`."""
+    depth = 0
+    for i in range(start, len(script)):
+        if script[i] == open:
+            depth += 1
+        elif script[i] == close:
+            depth -= 1
+            if depth == 0:
+                return i + 1
+    return len(script)
+
+
+def _heredoc_delimiters(words: list[_Word]) -> list[str]:
+    delimiters = []
+    for i, word in enumerate(words):
+        if not word.text.startswith("<<") or word.text.startswith("<<<"):
+            continue
+        delimiter = word.text[2:].lstrip("-")
+        if not delimiter and i + 1 < len(words):
+            delimiter = words[i + 1].text
+        if delimiter:
+            delimiters.append(delimiter)
+    return delimiters
+
+
+def _skip_heredocs(script: str, start: int, delimiters: list[str]) -> int:
+    """Skips the bodies of the here-documents starting at `start`, in order."""
+    i = start
+    for delimiter in delimiters:
+        while i < len(script):
+            newline = script.find("\n", i)
+            end = len(script) if newline == -1 else newline
+            line = script[i:end]
+            i = end + 1
+            if line.lstrip("\t") == delimiter:
+                break
+    return min(i, len(script))
+
+
+def _command_arguments(words: list[_Word]) -> list[_Word]:
+    """The words of the simple command from its name, without redirections or prefixes."""
+    result = []
+    skip_next = False
+    for word in words:
+        if skip_next:
+            skip_next = False
+            continue
+        redirection = _REDIRECTION.match(word.text)
+        if redirection and not word.starts_with_expansion:
+            # The target of the redirection is the next word if not part of this one.
+            skip_next = redirection.end() == len(word.text)
+            continue
+        if not result and (word.text in _COMMAND_PREFIXES or _ASSIGNMENT.match(word.text)):
+            continue
+        result.append(word)
+    return result
+
+
+def parse_shell_command_references(script: str) -> ShellCommandReferences:
+    lexer = _Lexer(script)
+    lexer.lex(0, in_substitution=False)
+
+    tools: dict[str, None] = {}
+    weak_sources: dict[str, None] = {}
+    for words, in_substitution in lexer.commands:
+        arguments = _command_arguments(words)
+        if not arguments:
+            continue
+        name, args = arguments[0].text, arguments[1:]
+        if name in ("source", ".") and args:
+            path = args[0]
+            literal = path.text[path.literal_start :]
+            if path.starts_with_expansion and literal.startswith("/") and "$" not in literal:
+                weak_sources[literal.lstrip("/")] = None
+            continue
+        if name == "exec":
+            # Skip the options, including the name given with `-a`.
+            while args and args[0].text.startswith("-"):
+                args = args[2:] if args[0].text == "-a" else args[1:]
+        elif name == "command":
+            while args and args[0].text.startswith("-"):
+                args = args[1:]
+        elif in_substitution:
+            args = arguments
+        else:
+            continue
+        if args and not args[0].starts_with_expansion and "$" not in args[0].text:
+            tools[args[0].text] = None
+    return ShellCommandReferences(tuple(tools), tuple(weak_sources))
@@ -0,0 +1,56 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+from textwrap import dedent
+
+from pants.backend.shell.command_references import (
+    ShellCommandReferences,
+    parse_shell_command_references,
+)
+
+
+def test_tools() -> None:
+    def tools(script: str) -> tuple[str, ...]:
+        return parse_shell_command_references(script).tools
+
+    assert not tools("")
+    assert not tools("echo hello\nbin/tool --help")
+    assert tools("command -v jq > /dev/null") == ("jq",)
+    assert tools("exec -a server bin/server --port 8080") == ("bin/server",)
+    assert tools('VERSION="$(./version.sh)"') == ("./version.sh",)
+    assert tools("echo `tools/gen.sh | sort`") == ("tools/gen.sh", "sort")
+    assert tools('ROOT="$(cd "$(dirname "$0")" && pwd)"') == ("dirname", "cd", "pwd")
+    assert tools("X=$(FOO=1 2>/dev/null build/info.sh)") == ("build/info.sh",)
+
+    # Commands which are not literal can't be inferred.
+    assert not tools('exec "$TOOL"\nX=$("${TOOLS}/tool")')
+    # Arithmetic expansions and quoted text are not commands.
+    assert not tools("echo $((1 + 2)) '$(rm -rf /)' # $(comment)")
+
+
+def test_heredocs_are_skipped() -> None:
+    script = dedent(
+        """\
+        cat <<EOF > out.txt
+        $(not/a/tool)
+        EOF
+        exec ./run.sh
+        """
+    )
+    assert parse_shell_command_references(script) == ShellCommandReferences(tools=("./run.sh",))
+
+
+def test_weak_sources() -> None:
+    def weak_sources(script: str) -> tuple[str, ...]:
+        return parse_shell_command_references(script).weak_sources
+
+    assert weak_sources('source "$(dirname "$0")/lib/common.sh"') == ("lib/common.sh",)
+    assert weak_sources('. "${BASH_SOURCE[0]%/*}/helpers.sh"') == ("helpers.sh",)
+    assert weak_sources("source $DIR/other.sh; source $DIR/other.sh") == ("other.sh",)
+
+    # Literal paths are left to Shellcheck.
+    assert not weak_sources("source lib/common.sh")
+    assert not weak_sources('source "$DIR/$NAME.sh"')
+    assert not weak_sources('source "$DIR"')
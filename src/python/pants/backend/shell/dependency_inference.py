@@ -5,18 +5,23 @@ from __future__ import annotations
 
 import json
 import logging
+import os.path
 import re
 from collections import defaultdict
 from dataclasses import dataclass
 from typing import DefaultDict
 
+from pants.backend.shell.command_references import (
+    ShellCommandReferences,
+    parse_shell_command_references,
+)
 from pants.backend.shell.lint.shellcheck.subsystem import Shellcheck
 from pants.backend.shell.subsystems.shell_setup import ShellSetup
 from pants.backend.shell.target_types import ShellDependenciesField, ShellSourceField
 from pants.core.util_rules.external_tool import DownloadedExternalTool, ExternalToolRequest
 from pants.engine.addresses import Address
 from pants.engine.collection import DeduplicatedCollection
-from pants.engine.fs import Digest, MergeDigests
+from pants.engine.fs import Digest, DigestContents, MergeDigests
 from pants.engine.platform import Platform
 from pants.engine.process import FallibleProcessResult, Process, ProcessCacheScope
 from pants.engine.rules import Get, MultiGet, collect_rules, rule
@@ -149,6 +154,15 @@ async def parse_shell_imports(
     return ParsedShellImports(paths)
 
 
+@rule
+async def find_shell_command_references(
+    request: ParseShellImportsRequest,
+) -> ShellCommandReferences:
+    digest_contents = await Get(DigestContents, Digest, request.digest)
+    [file_content] = (fc for fc in digest_contents if fc.path == request.fp)
+    return parse_shell_command_references(file_content.content.decode(errors="replace"))
+
+
 @dataclass(frozen=True)
 class ShellDependenciesInferenceFieldSet(FieldSet):
     required_fields = (ShellSourceField, ShellDependenciesField)
@@ -175,28 +189,52 @@ async def infer_shell_dependencies(
     )
     assert len(hydrated_sources.snapshot.files) == 1
 
-    detected_imports = await Get(
-        ParsedShellImports,
-        ParseShellImportsRequest(
-            hydrated_sources.snapshot.digest, hydrated_sources.snapshot.files[0]
-        ),
+    file_path = hydrated_sources.snapshot.files[0]
+    script_dir = os.path.dirname(file_path)
+    request_for_file = ParseShellImportsRequest(hydrated_sources.snapshot.digest, file_path)
+    detected_imports, command_references = await MultiGet(
+        Get(ParsedShellImports, ParseShellImportsRequest, request_for_file),
+        Get(ShellCommandReferences, ParseShellImportsRequest, request_for_file),
     )
+
+    # How each file is referenced, its candidate paths in order of preference, and whether the
+    # reference is weak (i.e. ambiguity is not warned about).
+    references: list[tuple[str, tuple[str, ...], bool]] = [
+        (f"sources `{import_path}`", (import_path,), False) for import_path in detected_imports
+    ]
+    references.extend(
+        # A tool may be run relative to the build root or to the directory of the script.
+        (f"runs `{tool}`", (tool, os.path.join(script_dir, tool)), False)
+        for tool in command_references.tools
+        if "/" in tool
+    )
+    references.extend(
+        # E.g. `source "$(dirname "$0")/lib.sh"` or `source "$REPO_ROOT/lib.sh"`.
+        (f"sources `{path}`", (os.path.join(script_dir, path), path), True)
+        for path in command_references.weak_sources
+    )
+
     result: OrderedSet[Address] = OrderedSet()
-    for import_path in detected_imports:
-        unambiguous = shell_mapping.mapping.get(import_path)
-        ambiguous = shell_mapping.ambiguous_modules.get(import_path)
-        if unambiguous:
-            result.add(unambiguous)
-        elif ambiguous:
-            explicitly_provided_deps.maybe_warn_of_ambiguous_dependency_inference(
-                ambiguous,
-                address,
-                import_reference="file",
-                context=f"The target {address} sources `{import_path}`",
-            )
-            maybe_disambiguated = explicitly_provided_deps.disambiguated(ambiguous)
-            if maybe_disambiguated:
-                result.add(maybe_disambiguated)
+    for reference, candidates, weak in references:
+        for candidate in (os.path.normpath(candidate) for candidate in candidates):
+            unambiguous = shell_mapping.mapping.get(candidate)
+            ambiguous = shell_mapping.ambiguous_modules.get(candidate)
+            if unambiguous:
+                result.add(unambiguous)
+            elif ambiguous:
+                if not weak:
+                    explicitly_provided_deps.maybe_warn_of_ambiguous_dependency_inference(
+                        ambiguous,
+                        address,
+                        import_reference="file",
+                        context=f"The target {address} {reference}",
+                    )
+                maybe_disambiguated = explicitly_provided_deps.disambiguated(ambiguous)
+                if maybe_disambiguated:
+                    result.add(maybe_disambiguated)
+            else:
+                continue
+            break
     return InferredDependencies(sorted(result))
 
 
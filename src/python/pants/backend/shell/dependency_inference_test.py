@@ -157,3 +157,57 @@ def test_dependency_inference(rule_runner: RuleRunner, caplog) -> None:
     assert "The target ambiguous/main.sh:main sources `ambiguous/dep.sh`" in caplog.text
     assert "['ambiguous/dep.sh:dep1', 'ambiguous/dep.sh:dep2']" in caplog.text
     assert "disambiguated.sh" not in caplog.text
+
+
+def test_dependency_inference_from_commands(rule_runner: RuleRunner, caplog) -> None:
+    rule_runner.write_files(
+        {
+            "scripts/main.sh": dedent(
+                """\
+                source "$(dirname "$0")/lib/common.sh"
+                source "${REPO_ROOT}/scripts/helpers.sh"
+                VERSION="$(./version.sh)"
+                command -v jq > /dev/null
+                exec bin/server --port 8080
+                """
+            ),
+            "scripts/version.sh": "",
+            "scripts/lib/common.sh": "",
+            "scripts/BUILD": "shell_sources()",
+            "bin/server": "",
+            "bin/BUILD": "shell_sources(sources=['server'])",
+            # Weak references are not warned about when ambiguous.
+            "ambiguous/helpers.sh": "",
+            "ambiguous/main.sh": 'source "$(dirname "$0")/helpers.sh"',
+            "ambiguous/BUILD": dedent(
+                """\
+                shell_sources(name='dep1', sources=['helpers.sh'])
+                shell_sources(name='dep2', sources=['helpers.sh'])
+                shell_sources(name='main', sources=['main.sh'])
+                """
+            ),
+        }
+    )
+
+    def run_dep_inference(address: Address) -> InferredDependencies:
+        tgt = rule_runner.get_target(address)
+        return rule_runner.request(
+            InferredDependencies,
+            [InferShellDependencies(ShellDependenciesInferenceFieldSet.create(tgt))],
+        )
+
+    assert run_dep_inference(
+        Address("scripts", relative_file_path="main.sh")
+    ) == InferredDependencies(
+        [
+            Address("bin", relative_file_path="server"),
+            Address("scripts", relative_file_path="lib/common.sh"),
+            Address("scripts", relative_file_path="version.sh"),
+        ]
+    )
+
+    caplog.clear()
+    assert run_dep_inference(
+        Address("ambiguous", target_name="main", relative_file_path="main.sh")
+    ) == InferredDependencies([])
+    assert not caplog.records
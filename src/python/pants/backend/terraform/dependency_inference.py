@@ -2,6 +2,7 @@
 # Licensed under the Apache License, Version 2.0 (see LICENSE).
 from __future__ import annotations
 
+import os
 from dataclasses import dataclass
 from pathlib import PurePath
 from typing import Sequence
@@ -20,10 +21,11 @@ from pants.backend.terraform.target_types import (
 from pants.base.glob_match_error_behavior import GlobMatchErrorBehavior
 from pants.base.specs import DirGlobSpec, DirLiteralSpec, RawSpecs
 from pants.engine.addresses import Addresses
-from pants.engine.fs import CreateDigest, Digest, FileContent
-from pants.engine.internals.native_engine import Address, AddressInput
+from pants.engine.fs import CreateDigest, Digest, DigestSubset, FileContent, PathGlobs
+from pants.engine.internals.native_dep_inference import NativeParsedTerraformDependencies
+from pants.engine.internals.native_engine import Address, AddressInput, NativeDependenciesRequest
 from pants.engine.internals.selectors import Get, MultiGet
-from pants.engine.process import Process, ProcessResult
+from pants.engine.process import Process
 from pants.engine.rules import collect_rules, rule
 from pants.engine.target import (
     DependenciesRequest,
@@ -105,6 +107,20 @@ async def setup_process_for_parse_terraform_module_sources(
     return process
 
 
+def local_module_path(directory: str, source: str) -> str | None:
+    """The path of the module with the given `source`, if it is a local path within the build root.
+
+    Local paths to modules must begin with "./" or "../" as per
+    https://developer.hashicorp.com/terraform/language/modules/sources#local-paths.
+    """
+    if not source.startswith(("./", "../")):
+        return None
+    path = os.path.normpath(os.path.join(directory, source))
+    if path == ".." or path.startswith("../"):
+        return None
+    return "" if path == "." else path
+
+
 @dataclass(frozen=True)
 class TerraformModuleDependenciesInferenceFieldSet(FieldSet):
     required_fields = (TerraformModuleSourcesField,)
@@ -122,17 +138,23 @@ async def infer_terraform_module_dependencies(
 ) -> InferredDependencies:
     hydrated_sources = await Get(HydratedSources, HydrateSourcesRequest(request.field_set.sources))
 
-    paths = OrderedSet(
+    paths = tuple(
         filename for filename in hydrated_sources.snapshot.files if filename.endswith(".tf")
     )
-    result = await Get(
-        ProcessResult,
-        ParseTerraformModuleSources(
-            sources_digest=hydrated_sources.snapshot.digest,
-            paths=tuple(paths),
-        ),
+    file_digests = await MultiGet(
+        Get(Digest, DigestSubset(hydrated_sources.snapshot.digest, PathGlobs([path])))
+        for path in paths
+    )
+    parsed_files = await MultiGet(
+        Get(NativeParsedTerraformDependencies, NativeDependenciesRequest(digest))
+        for digest in file_digests
+    )
+    candidate_spec_paths = OrderedSet(
+        spec_path
+        for path, parsed in zip(paths, parsed_files)
+        for source, _ in parsed.modules.values()
+        if (spec_path := local_module_path(os.path.dirname(path), source)) is not None
     )
-    candidate_spec_paths = [line for line in result.stdout.decode("utf-8").split("\n") if line]
 
     # For each path, see if there is a `terraform_module` target at the specified spec_path.
     candidate_targets = await Get(
@@ -12,6 +12,7 @@ from pants.backend.terraform.dependency_inference import (
     TerraformDeploymentDependenciesInferenceFieldSet,
     TerraformHcl2Parser,
     TerraformModuleDependenciesInferenceFieldSet,
+    local_module_path,
 )
 from pants.backend.terraform.target_types import (
     TerraformBackendTarget,
@@ -112,6 +113,59 @@ def test_dependency_inference_module(rule_runner: RuleRunner) -> None:
     )
 
 
+def test_dependency_inference_module_with_nested_expressions(rule_runner: RuleRunner) -> None:
+    rule_runner.write_files(
+        {
+            "src/tf/modules/network/BUILD": "terraform_module()\n",
+            "src/tf/modules/network/main.tf": "",
+            "src/tf/root/BUILD": "terraform_module()\n",
+            "src/tf/root/main.tf": textwrap.dedent(
+                """\
+                terraform {
+                  backend "s3" {
+                    key = "${path.module}/state"
+                  }
+                  required_providers {
+                    aws = { source = "hashicorp/aws", version = "~> 5.0" }
+                  }
+                }
+
+                module "network" {
+                  tags = merge(var.tags, {
+                    Name = "net-${var.env == "prod" ? "main" : "dev-${var.suffix}"}"
+                  })
+                  source = "../modules/network"
+                }
+                # Outside of the build root.
+                module "outside" {
+                  source = "../../../../outside"
+                }
+                """
+            ),
+        }
+    )
+
+    target = rule_runner.get_target(Address("src/tf/root"))
+    inferred_deps = rule_runner.request(
+        InferredDependencies,
+        [
+            InferTerraformModuleDependenciesRequest(
+                TerraformModuleDependenciesInferenceFieldSet.create(target)
+            )
+        ],
+    )
+    assert inferred_deps == InferredDependencies([Address("src/tf/modules/network")])
+
+
+def test_local_module_path() -> None:
+    assert local_module_path("src/tf", "./sub") == "src/tf/sub"
+    assert local_module_path("src/tf", "../modules/./foo/") == "src/modules/foo"
+    assert local_module_path("src/tf", "../..") == ""
+    assert local_module_path("src/tf", "../../..") is None
+    assert local_module_path("src/tf", "hashicorp/consul/aws") is None
+    assert local_module_path("src/tf", "git::https://example.com/vpc.git?ref=v1.2.0") is None
+
+
 def test_dependency_inference_deployment(rule_runner: RuleRunner) -> None:
     rule_runner.write_files(
         {
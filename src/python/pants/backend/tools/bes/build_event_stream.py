@@ -0,0 +1,263 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+"""Encodes the events of a run as Bazel `build_event_stream.BuildEvent` protobuf messages.
+
+See https://bazel.build/remote/bep for the protocol. Only the handful of messages and fields which
+Pants publishes are encoded here (by hand, to avoid depending on `protobuf`): consumers ignore the
+fields that are absent.
+"""
+
+from __future__ import annotations
+
+from dataclasses import dataclass
+from typing import Iterable, Sequence
+
+# Wire types: see https://protobuf.dev/programming-guides/encoding/.
+_VARINT = 0
+_LENGTH_DELIMITED = 2
+
+# The values of the `build_event_stream.TestStatus` enum.
+TEST_STATUS_PASSED = 1
+TEST_STATUS_FAILED = 4
+
+
+def _varint(value: int) -> bytes:
+    if value < 0:
+        # Negative values are encoded as their 64-bit two's complement.
+        value += 1 << 64
+    result = bytearray()
+    while True:
+        bits = value & 0x7F
+        value >>= 7
+        if not value:
+            result.append(bits)
+            return bytes(result)
+        result.append(bits | 0x80)
+
+
+def _key(number: int, wire_type: int) -> bytes:
+    return _varint(number << 3 | wire_type)
+
+
+def _int(number: int, value: int) -> bytes:
+    # NB: Like all proto3 scalars, zero values are not encoded.
+    return _key(number, _VARINT) + _varint(value) if value else b""
+
+
+def _bool(number: int, value: bool) -> bytes:
+    return _int(number, int(value))
+
+
+def _string(number: int, value: str) -> bytes:
+    data = value.encode()
+    return _key(number, _LENGTH_DELIMITED) + _varint(len(data)) + data if data else b""
+
+
+def _strings(number: int, values: Iterable[str]) -> bytes:
+    # NB: Unlike a single string, each (possibly empty) element of a repeated field is encoded.
+    encoded = (value.encode() for value in values)
+    return b"".join(_key(number, _LENGTH_DELIMITED) + _varint(len(v)) + v for v in encoded)
+
+
+def _message(number: int, *fields: bytes) -> bytes:
+    # NB: Unlike scalars, empty messages are encoded, since their presence is meaningful (e.g. to
+    # select the member of a `oneof`).
+    data = b"".join(fields)
+    return _key(number, _LENGTH_DELIMITED) + _varint(len(data)) + data
+
+
+def _timestamp(number: int, nanos: int) -> bytes:
+    seconds, nanos = divmod(nanos, 1_000_000_000)
+    return _message(number, _int(1, seconds), _int(2, nanos))
+
+
+def _duration(number: int, nanos: int) -> bytes:
+    return _timestamp(number, nanos)
+
+
+# The members of the `build_event_stream.BuildEventId.id` oneof.
+
+
+def progress_id(count: int) -> bytes:
+    return _message(2, _int(1, count))
+
+
+def started_id() -> bytes:
+    return _message(3)
+
+
+def pattern_id(patterns: Sequence[str]) -> bytes:
+    return _message(4, _strings(1, patterns))
+
+
+def target_completed_id(label: str) -> bytes:
+    return _message(5, _string(1, label))
+
+
+def test_summary_id(label: str) -> bytes:
+    return _message(7, _string(1, label))
+
+
+def test_result_id(label: str) -> bytes:
+    # Pants neither shards nor reruns tests, so every result is the first run, shard and attempt.
+    return _message(8, _string(1, label), _int(2, 1), _int(3, 1), _int(4, 1))
+
+
+def build_finished_id() -> bytes:
+    return _message(9)
+
+
+def unstructured_command_line_id() -> bytes:
+    return _message(11)
+
+
+def build_event(
+    event_id: bytes, payload: bytes, *, children: Iterable[bytes] = (), last_message: bool = False
+) -> bytes:
+    """Encodes a `build_event_stream.BuildEvent`.
+
+    :param event_id: The (encoded) member of the `BuildEventId.id` oneof which identifies the event.
+    :param payload: The (encoded) member of the `BuildEvent.payload` oneof.
+    :param children: The ids of the events which this event announces.
+    :param last_message: Whether this is the last event of the stream.
+    """
+    return b"".join(
+        (
+            _message(1, event_id),
+            *(_message(2, child) for child in children),
+            _bool(20, last_message),
+            payload,
+        )
+    )
+
+
+@dataclass(frozen=True)
+class TestOutcome:
+    """The outcome of the tests of a target."""
+
+    label: str
+    passed: bool
+    start_nanos: int
+    duration_nanos: int
+
+    # Prevent this class from being detected by pytest as a test class.
+    __test__ = False
+
+
+class BuildEventStream:
+    """Encodes the events of a run, in the order in which they should be published.
+
+    Every event but the first must have been announced as a child of an earlier event. The events
+    which are not known at the start of the run are announced by a chain of `Progress` events: each
+    one announces the next, along with the events which follow it.
+    """
+
+    def __init__(self) -> None:
+        self._progress_count = 0
+
+    def started(
+        self,
+        *,
+        invocation_id: str,
+        start_nanos: int,
+        version: str,
+        command: str,
+        args: Sequence[str],
+        patterns: Sequence[str],
+        workspace_directory: str,
+        server_pid: int,
+    ) -> list[bytes]:
+        started = _message(
+            5,
+            _string(1, invocation_id),
+            _int(2, start_nanos // 1_000_000),
+            _string(3, version),
+            _string(5, command),
+            _string(6, workspace_directory),
+            _string(7, workspace_directory),
+            _int(8, server_pid),
+            _timestamp(9, start_nanos),
+        )
+        return [
+            build_event(
+                started_id(),
+                started,
+                children=(
+                    unstructured_command_line_id(),
+                    pattern_id(patterns),
+                    progress_id(0),
+                    build_finished_id(),
+                ),
+            ),
+            build_event(unstructured_command_line_id(), _message(12, _strings(1, args))),
+            build_event(pattern_id(patterns), _message(6)),
+        ]
+
+    def _progress(self, children: Iterable[bytes], *, last: bool) -> bytes:
+        count = self._progress_count
+        self._progress_count += 1
+        next_progress = () if last else (progress_id(count + 1),)
+        return build_event(progress_id(count), _message(3), children=(*next_progress, *children))
+
+    def test_outcomes(self, outcomes: Sequence[TestOutcome]) -> list[bytes]:
+        events = []
+        for outcome in outcomes:
+            status = TEST_STATUS_PASSED if outcome.passed else TEST_STATUS_FAILED
+            end_nanos = outcome.start_nanos + outcome.duration_nanos
+            test_result = _message(
+                10,
+                _int(3, outcome.duration_nanos // 1_000_000),
+                _int(5, status),
+                _int(6, outcome.start_nanos // 1_000_000),
+                _timestamp(10, outcome.start_nanos),
+                _duration(11, outcome.duration_nanos),
+            )
+            test_summary = _message(
+                9,
+                _int(1, 1),
+                _int(5, status),
+                _int(7, outcome.start_nanos // 1_000_000),
+                _int(8, end_nanos // 1_000_000),
+                _int(9, outcome.duration_nanos // 1_000_000),
+                _int(10, 1),
+                _int(11, 1),
+                _duration(12, outcome.duration_nanos),
+                _timestamp(13, outcome.start_nanos),
+                _timestamp(14, end_nanos),
+                _int(15, 1),
+            )
+            events.extend(
+                (
+                    build_event(test_result_id(outcome.label), test_result),
+                    build_event(test_summary_id(outcome.label), test_summary),
+                    build_event(
+                        target_completed_id(outcome.label), _message(8, _bool(1, outcome.passed))
+                    ),
+                )
+            )
+        children = [
+            event_id(outcome.label)
+            for outcome in outcomes
+            for event_id in (test_result_id, test_summary_id, target_completed_id)
+        ]
+        return [self._progress(children, last=False), *events]
+
+    def finished(self, *, exit_code: int, finish_nanos: int, tests_failed: bool) -> list[bytes]:
+        if exit_code == 0:
+            exit_code_name = "SUCCESS"
+        elif tests_failed:
+            exit_code_name = "TESTS_FAILED"
+        else:
+            exit_code_name = "BUILD_FAILURE"
+        finished = _message(
+            14,
+            _bool(1, exit_code == 0),
+            _int(2, finish_nanos // 1_000_000),
+            _message(3, _string(1, exit_code_name), _int(2, exit_code)),
+            _timestamp(5, finish_nanos),
+        )
+        return [
+            self._progress((), last=True),
+            build_event(build_finished_id(), finished, last_message=True),
+        ]
@@ -0,0 +1,95 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+from pants.backend.tools.bes.build_event_stream import (
+    BuildEventStream,
+    TestOutcome,
+    _message,
+    _varint,
+    build_event,
+    progress_id,
+)
+
+
+def fields(data: bytes) -> list[tuple[int, int | bytes]]:
+    """Decodes the (varint and length-delimited) fields of an encoded message."""
+
+    def read_varint(offset: int) -> tuple[int, int]:
+        value, shift = 0, 0
+        while True:
+            byte = data[offset]
+            value |= (byte & 0x7F) << shift
+            offset += 1
+            shift += 7
+            if not byte & 0x80:
+                return value, offset
+
+    result: list[tuple[int, int | bytes]] = []
+    offset = 0
+    while offset < len(data):
+        key, offset = read_varint(offset)
+        value, offset = read_varint(offset)
+        if key & 0x7 == 2:
+            result.append((key >> 3, data[offset : offset + value]))
+            offset += value
+        else:
+            result.append((key >> 3, value))
+    return result
+
+
+def test_varint() -> None:
+    assert _varint(1) == b"\x01"
+    assert _varint(300) == b"\xac\x02"
+    assert _varint(-1) == b"\xff" * 9 + b"\x01"
+
+
+def test_build_event() -> None:
+    assert build_event(progress_id(0), _message(3), children=(progress_id(1),)) == (
+        # id { progress {} }
+        b"\x0a\x02\x12\x00"
+        # children { progress { opaque_count: 1 } }
+        + b"\x12\x04\x12\x02\x08\x01"
+        # progress {}
+        + b"\x1a\x00"
+    )
+    assert build_event(progress_id(0), _message(3), last_message=True) == (
+        b"\x0a\x02\x12\x00" + b"\xa0\x01\x01" + b"\x1a\x00"
+    )
+
+
+def test_every_event_is_announced() -> None:
+    stream = BuildEventStream()
+    events = [
+        *stream.started(
+            invocation_id="abc",
+            start_nanos=1_500_000_000,
+            version="2.23.0",
+            command="test",
+            args=["pants", "test", "::"],
+            patterns=["::"],
+            workspace_directory="/repo",
+            server_pid=1,
+        ),
+        *stream.test_outcomes(
+            [
+                TestOutcome("src:a", passed=True, start_nanos=2_000_000_000, duration_nanos=5),
+                TestOutcome("src:b", passed=False, start_nanos=2_000_000_000, duration_nanos=5),
+            ]
+        ),
+        *stream.finished(exit_code=1, finish_nanos=3_000_000_000, tests_failed=True),
+    ]
+
+    announced: set[int | bytes] = set()
+    for i, event in enumerate(events):
+        event_fields = fields(event)
+        [event_id] = [value for number, value in event_fields if number == 1]
+        assert i == 0 or event_id in announced
+        announced.discard(event_id)
+        announced.update(value for number, value in event_fields if number == 2)
+        last_message = (20, 1) in event_fields
+        assert last_message == (i == len(events) - 1)
+
+    # Every announced event was published.
+    assert not announced
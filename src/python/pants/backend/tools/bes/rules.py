@@ -0,0 +1,242 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+import logging
+import os
+import time
+import uuid
+from typing import Any, Iterable
+
+from pants.backend.tools.bes.build_event_stream import BuildEventStream, TestOutcome
+from pants.engine.internals import native_engine
+from pants.engine.internals.scheduler import Workunit
+from pants.engine.rules import collect_rules, rule
+from pants.engine.streaming_workunit_handler import (
+    StreamingWorkunitContext,
+    WorkunitsCallback,
+    WorkunitsCallbackFactory,
+    WorkunitsCallbackFactoryRequest,
+)
+from pants.engine.unions import UnionRule
+from pants.option.option_types import BoolOption, DictOption, IntOption, StrOption
+from pants.option.subsystem import Subsystem
+from pants.util.strutil import softwrap
+
+logger = logging.getLogger(__name__)
+
+
+class BuildEventServiceSubsystem(Subsystem):
+    options_scope = "bes"
+    help = softwrap(
+        """
+        Streams the events of each run to a Build Event Service (BES), using the Build Event
+        Protocol of Bazel: see https://bazel.build/remote/bep.
+
+        The start and end of the run, and the outcome of the tests of each target, are published.
+        Targets which are not tested are not reported.
+        """
+    )
+
+    enabled = BoolOption(default=False, help="Whether to stream the events of each run.")
+    address = StrOption(
+        default=None,
+        help=softwrap(
+            """
+            The address of the Build Event Service, e.g. `grpcs://bes.example.com:443`.
+
+            The address must use the `grpc://` or `grpcs://` scheme: the latter connects with TLS.
+            """
+        ),
+    )
+    headers = DictOption[str](
+        help=softwrap(
+            """
+            Headers to send with each request to the Build Event Service, e.g. to authenticate with
+            `{"x-api-key": "%(env.BES_API_KEY)s"}`.
+            """
+        )
+    )
+    results_url = StrOption(
+        default=None,
+        help=softwrap(
+            """
+            The URL at which the Build Event Service shows the results of runs, if any.
+
+            If set, the URL of the results of each run, `<results_url>/<invocation id>`, is logged
+            once its events are published.
+            """
+        ),
+    )
+    project_id = StrOption(
+        default=None,
+        help="The id of the project to publish the events under, if the service requires one.",
+    )
+    timeout = IntOption(
+        default=60,
+        help=softwrap(
+            """
+            The maximum number of seconds to wait for the service when the run starts, and to
+            publish the remaining events of the run once it ends.
+            """
+        ),
+        advanced=True,
+    )
+
+
+def _normalize_address(address: str) -> str:
+    # The gRPC stack of the engine expects `http` and `https` schemes.
+    if address.startswith("grpc://"):
+        return f"http://{address[len('grpc://'):]}"
+    if address.startswith("grpcs://"):
+        return f"https://{address[len('grpcs://'):]}"
+    raise ValueError(
+        f"The `[bes].address` must use the `grpc://` or `grpcs://` scheme, but was `{address}`."
+    )
+
+
+def _start_and_duration_nanos(workunit: Workunit) -> tuple[int, int]:
+    start = workunit["start_secs"] * 1_000_000_000 + workunit["start_nanos"]
+    duration = workunit.get("duration_secs", 0) * 1_000_000_000 + workunit.get("duration_nanos", 0)
+    return start, duration
+
+
+def outcomes_of_tests(workunits: Iterable[Workunit]) -> list[TestOutcome]:
+    """The outcomes of the tests which completed in the given workunits, per target.
+
+    The workunits of tests are those of rules which return a `TestResult`, whose metadata includes
+    the addresses which were tested and the exit code of the tests.
+    """
+    outcomes = []
+    for workunit in workunits:
+        metadata = workunit.get("metadata", {})
+        if "addresses" not in metadata or metadata.get("exit_code") is None:
+            continue
+        start, duration = _start_and_duration_nanos(workunit)
+        outcomes.extend(
+            TestOutcome(
+                label=address,
+                passed=metadata["exit_code"] == 0,
+                start_nanos=start,
+                duration_nanos=duration,
+            )
+            for address in metadata["addresses"]
+        )
+    return outcomes
+
+
+class BuildEventServiceCallback(WorkunitsCallback):
+    """Streams the events of the run to a Build Event Service as its workunits complete."""
+
+    def __init__(self, subsystem: BuildEventServiceSubsystem) -> None:
+        self.subsystem = subsystem
+        self.invocation_id = str(uuid.uuid4())
+        self._events = BuildEventStream()
+        self._stream: native_engine.PyBuildEventStream | None = None
+        self._tests_failed = False
+        # Set if publishing failed, after which no further events are published.
+        self._failed = False
+
+    @property
+    def can_finish_async(self) -> bool:
+        # We log the URL of the results in the final call.
+        return False
+
+    def _start(self, context: StreamingWorkunitContext) -> native_engine.PyBuildEventStream:
+        run_information = context.run_tracker.run_information()
+        stream = native_engine.PyBuildEventStream(
+            context._scheduler.scheduler.py_executor,
+            _normalize_address(self.subsystem.address),
+            self.subsystem.headers,
+            str(uuid.uuid4()),
+            self.invocation_id,
+            self.subsystem.project_id,
+            self.subsystem.timeout,
+        )
+        stream.start()
+        for event in self._events.started(
+            invocation_id=self.invocation_id,
+            start_nanos=int(run_information.get("timestamp", time.time()) * 1_000_000_000),
+            version=run_information.get("version", ""),
+            command=" ".join(context.run_tracker.goals),
+            args=run_information.get("cmd_line", "").split(),
+            patterns=run_information.get("specs_from_command_line", []),
+            workspace_directory=run_information.get("buildroot", ""),
+            server_pid=os.getpid(),
+        ):
+            stream.publish(event)
+        return stream
+
+    def _publish(
+        self,
+        context: StreamingWorkunitContext,
+        completed_workunits: tuple[Workunit, ...],
+        finished: bool,
+    ) -> None:
+        if self._stream is None:
+            self._stream = self._start(context)
+
+        outcomes = outcomes_of_tests(completed_workunits)
+        if outcomes:
+            self._tests_failed |= any(not outcome.passed for outcome in outcomes)
+            for event in self._events.test_outcomes(outcomes):
+                self._stream.publish(event)
+        if not finished:
+            return
+
+        outcome = context.run_tracker.run_information().get("outcome")
+        exit_code = 0 if outcome == "SUCCESS" else 1
+        for event in self._events.finished(
+            exit_code=exit_code, finish_nanos=time.time_ns(), tests_failed=self._tests_failed
+        ):
+            self._stream.publish(event)
+        self._stream.finish(exit_code)
+        if self.subsystem.results_url:
+            logger.info(
+                f"The results of this run are at {self.subsystem.results_url.rstrip('/')}/"
+                f"{self.invocation_id}"
+            )
+
+    def __call__(
+        self,
+        *,
+        completed_workunits: tuple[Workunit, ...],
+        started_workunits: tuple[Workunit, ...],
+        context: StreamingWorkunitContext,
+        finished: bool = False,
+        **kwargs: Any,
+    ) -> None:
+        if self._failed:
+            return
+        try:
+            self._publish(context, completed_workunits, finished)
+        except Exception as e:
+            self._failed = True
+            logger.warning(
+                f"Failed to publish build events to {self.subsystem.address}, so no further events "
+                f"will be published for this run: {e}"
+            )
+
+
+class BuildEventServiceCallbackFactoryRequest:
+    """A unique request type that is installed to trigger construction of our WorkunitsCallback."""
+
+
+@rule
+def construct_callback(
+    _: BuildEventServiceCallbackFactoryRequest, subsystem: BuildEventServiceSubsystem
+) -> WorkunitsCallbackFactory:
+    enabled = subsystem.enabled and subsystem.address
+    if subsystem.enabled and not subsystem.address:
+        logger.warning("`[bes].enabled` is set, but no `[bes].address` is configured.")
+    return WorkunitsCallbackFactory(
+        lambda: BuildEventServiceCallback(subsystem) if enabled else None
+    )
+
+
+def rules():
+    return [
+        UnionRule(WorkunitsCallbackFactoryRequest, BuildEventServiceCallbackFactoryRequest),
+        *collect_rules(),
+    ]
@@ -0,0 +1,146 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+from types import SimpleNamespace
+from typing import Any
+
+import pytest
+
+from pants.backend.tools.bes import rules as bes_rules
+from pants.backend.tools.bes.build_event_stream import TestOutcome
+from pants.backend.tools.bes.rules import (
+    BuildEventServiceCallback,
+    BuildEventServiceSubsystem,
+    _normalize_address,
+    outcomes_of_tests,
+)
+from pants.engine.internals.scheduler import Workunit
+from pants.testutil.option_util import create_subsystem
+from pants.testutil.workunit_util import create_workunit
+
+
+def test_normalize_address() -> None:
+    assert _normalize_address("grpc://localhost:1985") == "http://localhost:1985"
+    assert _normalize_address("grpcs://bes.example.com:443") == "https://bes.example.com:443"
+    with pytest.raises(ValueError):
+        _normalize_address("https://bes.example.com:443")
+
+
+def workunit(metadata: dict[str, Any]) -> Workunit:
+    return create_workunit("run_tests", start_secs=2.000_000_5, duration_secs=1, metadata=metadata)
+
+
+def test_outcomes_of_tests() -> None:
+    workunits = [
+        workunit({"addresses": ["src:a", "src:b"], "exit_code": 1}),
+        # Tests which were not found, and the partitions of tests, have no outcome.
+        workunit({"addresses": ["src:c"], "exit_code": None}),
+        workunit({"addresses": ["src:d"], "partition_description": None}),
+        workunit({}),
+    ]
+    assert outcomes_of_tests(workunits) == [  # type: ignore[arg-type]
+        TestOutcome("src:a", passed=False, start_nanos=2_000_000_500, duration_nanos=10**9),
+        TestOutcome("src:b", passed=False, start_nanos=2_000_000_500, duration_nanos=10**9),
+    ]
+
+
+class FakeBuildEventStream:
+    def __init__(self, *args: Any) -> None:
+        self.args = args
+        self.calls: list[str] = []
+        self.events: list[bytes] = []
+        streams.append(self)
+
+    def start(self) -> None:
+        self.calls.append("start")
+
+    def publish(self, event: bytes) -> None:
+        self.events.append(event)
+
+    def finish(self, exit_code: int) -> None:
+        self.calls.append(f"finish {exit_code}")
+
+
+streams: list[FakeBuildEventStream] = []
+
+
+def context(outcome: str | None = None) -> SimpleNamespace:
+    run_tracker = SimpleNamespace(
+        goals=["test"],
+        run_information=lambda: {
+            "timestamp": 1.5,
+            "cmd_line": "pants test ::",
+            "specs_from_command_line": ["::"],
+            "buildroot": "/repo",
+            **({"outcome": outcome} if outcome else {}),
+        },
+    )
+    scheduler = SimpleNamespace(scheduler=SimpleNamespace(py_executor="executor"))
+    return SimpleNamespace(run_tracker=run_tracker, _scheduler=scheduler)
+
+
+def test_callback(monkeypatch: pytest.MonkeyPatch) -> None:
+    monkeypatch.setattr(bes_rules.native_engine, "PyBuildEventStream", FakeBuildEventStream)
+    streams.clear()
+    subsystem = create_subsystem(
+        BuildEventServiceSubsystem,
+        enabled=True,
+        address="grpc://localhost:1985",
+        headers={"x-api-key": "secret"},
+        results_url=None,
+        project_id=None,
+        timeout=60,
+    )
+    callback = BuildEventServiceCallback(subsystem)
+    callback(completed_workunits=(), started_workunits=(), context=context())  # type: ignore
+    callback(
+        completed_workunits=(workunit({"addresses": ["src:a"], "exit_code": 0}),),  # type: ignore
+        started_workunits=(),
+        context=context(),  # type: ignore[arg-type]
+    )
+    callback(
+        completed_workunits=(),
+        started_workunits=(),
+        context=context("SUCCESS"),  # type: ignore[arg-type]
+        finished=True,
+    )
+
+    [stream] = streams
+    _, address, headers, build_id, invocation_id, *_ = stream.args
+    assert (address, headers) == ("http://localhost:1985", {"x-api-key": "secret"})
+    assert invocation_id == callback.invocation_id != build_id
+    assert stream.calls == ["start", "finish 0"]
+    # The started events, the events of the result of `src:a`, and the finished events.
+    assert len(stream.events) == 3 + 4 + 2
+
+
+def test_callback_failure(monkeypatch: pytest.MonkeyPatch, caplog) -> None:
+    class FailingBuildEventStream(FakeBuildEventStream):
+        def start(self) -> None:
+            raise Exception("Connection refused.")
+
+    monkeypatch.setattr(bes_rules.native_engine, "PyBuildEventStream", FailingBuildEventStream)
+    streams.clear()
+    subsystem = create_subsystem(
+        BuildEventServiceSubsystem,
+        enabled=True,
+        address="grpc://localhost:1985",
+        headers={},
+        results_url=None,
+        project_id=None,
+        timeout=60,
+    )
+    callback = BuildEventServiceCallback(subsystem)
+    for finished in (False, True):
+        callback(
+            completed_workunits=(),
+            started_workunits=(),
+            context=context("SUCCESS"),  # type: ignore[arg-type]
+            finished=finished,
+        )
+
+    # Publishing is attempted once, and warned about once.
+    assert len(streams) == 1
+    assert caplog.text.count("Connection refused.") == 1
@@ -0,0 +1,211 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+import logging
+import secrets
+from typing import Any
+
+import requests
+
+from pants.engine.internals.scheduler import Workunit
+from pants.engine.rules import collect_rules, rule
+from pants.engine.streaming_workunit_handler import (
+    StreamingWorkunitContext,
+    WorkunitsCallback,
+    WorkunitsCallbackFactory,
+    WorkunitsCallbackFactoryRequest,
+)
+from pants.engine.unions import UnionRule
+from pants.option.option_types import BoolOption, DictOption, IntOption, StrOption
+from pants.option.subsystem import Subsystem
+from pants.util.strutil import softwrap
+from pants.version import VERSION
+
+logger = logging.getLogger(__name__)
+
+# See https://opentelemetry.io/docs/specs/otlp/#otlphttp.
+_SPAN_KIND_INTERNAL = 1
+
+
+class OpenTelemetry(Subsystem):
+    options_scope = "opentelemetry"
+    help = softwrap(
+        """
+        Exports the workunits of each run as OpenTelemetry spans to an OTLP collector.
+
+        Each run is one trace, and each workunit is one span of it: the spans of workunits with more
+        than one parent link to the other parents. Workunit metadata is exported as span attributes,
+        and the counters of the run as attributes of its root span.
+
+        Which workunits are exported is controlled by `[GLOBAL].streaming_workunits_level`.
+        """
+    )
+
+    enabled = BoolOption(default=False, help="Whether to export workunits as spans.")
+    endpoint = StrOption(
+        default="http://localhost:4318/v1/traces",
+        help="The URL of the OTLP/HTTP traces endpoint of the collector to export spans to.",
+    )
+    headers = DictOption[str](
+        help="Headers to send with each export request, e.g. to authenticate with the collector."
+    )
+    service_name = StrOption(
+        default="pants", help="The `service.name` resource attribute of the exported spans."
+    )
+    resource_attributes = DictOption[str](
+        help="Additional resource attributes of the exported spans, e.g. `deployment.environment`."
+    )
+    batch_size = IntOption(
+        default=512,
+        help="The number of spans to buffer before exporting them in one request.",
+        advanced=True,
+    )
+    timeout = IntOption(
+        default=10,
+        help="The timeout, in seconds, of each export request.",
+        advanced=True,
+    )
+
+
+def _attribute_value(value: Any) -> dict[str, Any]:
+    """Converts a value to an OTLP `AnyValue`, falling back to its string representation."""
+    if isinstance(value, bool):
+        return {"boolValue": value}
+    if isinstance(value, int):
+        # NB: 64-bit integers are encoded as strings in the JSON encoding of OTLP.
+        return {"intValue": str(value)}
+    if isinstance(value, float):
+        return {"doubleValue": value}
+    if isinstance(value, str):
+        return {"stringValue": value}
+    if isinstance(value, (list, tuple)):
+        return {"arrayValue": {"values": [_attribute_value(v) for v in value]}}
+    return {"stringValue": str(value)}
+
+
+def _attributes(attributes: dict[str, Any]) -> list[dict[str, Any]]:
+    return [{"key": key, "value": _attribute_value(value)} for key, value in attributes.items()]
+
+
+def workunit_to_span(
+    workunit: Workunit, *, trace_id: str, counters: dict[str, int] | None = None
+) -> dict[str, Any]:
+    """Converts a completed workunit to an OTLP span, in the JSON encoding of OTLP."""
+    start_nanos = workunit["start_secs"] * 1_000_000_000 + workunit["start_nanos"]
+    duration_nanos = (
+        workunit.get("duration_secs", 0) * 1_000_000_000 + workunit.get("duration_nanos", 0)
+    )
+    attributes: dict[str, Any] = {"pants.level": workunit["level"]}
+    if workunit.get("description"):
+        attributes["pants.description"] = workunit["description"]
+    for key, value in workunit.get("metadata", {}).items():
+        attributes[f"pants.metadata.{key}"] = value
+    for name, value in (counters or {}).items():
+        attributes[f"pants.counter.{name}"] = value
+
+    span: dict[str, Any] = {
+        "traceId": trace_id,
+        "spanId": workunit["span_id"],
+        "name": workunit["name"],
+        "kind": _SPAN_KIND_INTERNAL,
+        "startTimeUnixNano": str(start_nanos),
+        "endTimeUnixNano": str(start_nanos + duration_nanos),
+        "attributes": _attributes(attributes),
+    }
+    parent_ids = workunit.get("parent_ids", ())
+    if parent_ids:
+        span["parentSpanId"] = parent_ids[0]
+    if len(parent_ids) > 1:
+        span["links"] = [{"traceId": trace_id, "spanId": parent_id} for parent_id in parent_ids[1:]]
+    return span
+
+
+class OpenTelemetryCallback(WorkunitsCallback):
+    """Exports completed workunits as spans, in batches of `[opentelemetry].batch_size`."""
+
+    def __init__(self, opentelemetry: OpenTelemetry):
+        self.opentelemetry = opentelemetry
+        self._trace_id = secrets.token_hex(16)
+        self._spans: list[dict[str, Any]] = []
+        self._export_failed = False
+
+    @property
+    def can_finish_async(self) -> bool:
+        return True
+
+    def __call__(
+        self,
+        *,
+        completed_workunits: tuple[Workunit, ...],
+        started_workunits: tuple[Workunit, ...],
+        context: StreamingWorkunitContext,
+        finished: bool = False,
+        **kwargs: Any,
+    ) -> None:
+        for workunit in completed_workunits:
+            # The root workunit of the run is the one without parents.
+            counters = None if workunit.get("parent_ids") else context.get_metrics()
+            self._spans.append(
+                workunit_to_span(workunit, trace_id=self._trace_id, counters=counters)
+            )
+
+        batch_size = max(self.opentelemetry.batch_size, 1)
+        while len(self._spans) >= batch_size or (finished and self._spans):
+            batch, self._spans = self._spans[:batch_size], self._spans[batch_size:]
+            self._export(batch, run_id=context.run_tracker.run_id)
+
+    def _export(self, spans: list[dict[str, Any]], *, run_id: str) -> None:
+        resource_attributes = {
+            "service.name": self.opentelemetry.service_name,
+            "service.version": VERSION,
+            "pants.run_id": run_id,
+            **self.opentelemetry.resource_attributes,
+        }
+        payload = {
+            "resourceSpans": [
+                {
+                    "resource": {"attributes": _attributes(resource_attributes)},
+                    "scopeSpans": [
+                        {"scope": {"name": "pants", "version": VERSION}, "spans": spans}
+                    ],
+                }
+            ]
+        }
+        try:
+            response = requests.post(
+                self.opentelemetry.endpoint,
+                json=payload,
+                headers=self.opentelemetry.headers,
+                timeout=self.opentelemetry.timeout,
+            )
+            response.raise_for_status()
+        except requests.RequestException as e:
+            # Only warn once per run, rather than once per batch.
+            if not self._export_failed:
+                logger.warning(
+                    f"Failed to export spans to {self.opentelemetry.endpoint}: {e}\n\n"
+                    "The spans of this run will be incomplete."
+                )
+            self._export_failed = True
+
+
+class OpenTelemetryCallbackFactoryRequest:
+    """A unique request type that is installed to trigger construction of our WorkunitsCallback."""
+
+
+@rule
+def construct_callback(
+    _: OpenTelemetryCallbackFactoryRequest, opentelemetry: OpenTelemetry
+) -> WorkunitsCallbackFactory:
+    return WorkunitsCallbackFactory(
+        lambda: OpenTelemetryCallback(opentelemetry) if opentelemetry.enabled else None
+    )
+
+
+def rules():
+    return [
+        UnionRule(WorkunitsCallbackFactoryRequest, OpenTelemetryCallbackFactoryRequest),
+        *collect_rules(),
+    ]
@@ -0,0 +1,135 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+import unittest.mock
+from types import SimpleNamespace
+from typing import Any
+
+import requests
+
+from pants.backend.tools.opentelemetry.rules import (
+    OpenTelemetry,
+    OpenTelemetryCallback,
+    workunit_to_span,
+)
+from pants.engine.internals.scheduler import Workunit
+from pants.testutil.option_util import create_subsystem
+from pants.testutil.workunit_util import create_workunit
+
+TRACE_ID = "0" * 32
+
+
+def workunit(span_id: str, *parent_ids: str, **metadata: Any) -> Workunit:
+    return create_workunit(
+        span_id,
+        *parent_ids,
+        name=f"workunit_{span_id}",
+        start_secs=1.000_000_5,
+        duration_secs=2.000_000_25,
+        description="A workunit.",
+        metadata=metadata,
+    )
+
+
+def attributes(span: dict[str, Any]) -> dict[str, Any]:
+    return {attribute["key"]: attribute["value"] for attribute in span["attributes"]}
+
+
+def test_workunit_to_span() -> None:
+    span = workunit_to_span(
+        workunit("c", "a", "b", exit_code=1, cached=False, argv=["ls"]), trace_id=TRACE_ID
+    )
+    assert span["traceId"] == TRACE_ID
+    assert span["spanId"] == "c"
+    assert span["name"] == "workunit_c"
+    assert span["startTimeUnixNano"] == "1000000500"
+    assert span["endTimeUnixNano"] == "3000000750"
+    assert span["parentSpanId"] == "a"
+    assert span["links"] == [{"traceId": TRACE_ID, "spanId": "b"}]
+    assert attributes(span) == {
+        "pants.level": {"stringValue": "DEBUG"},
+        "pants.description": {"stringValue": "A workunit."},
+        "pants.metadata.exit_code": {"intValue": "1"},
+        "pants.metadata.cached": {"boolValue": False},
+        "pants.metadata.argv": {"arrayValue": {"values": [{"stringValue": "ls"}]}},
+    }
+
+
+def test_root_workunit_to_span() -> None:
+    span = workunit_to_span(workunit("a"), trace_id=TRACE_ID, counters={"local_cache_requests": 3})
+    assert "parentSpanId" not in span
+    assert "links" not in span
+    assert attributes(span)["pants.counter.local_cache_requests"] == {"intValue": "3"}
+
+
+def exported_batches(
+    *calls: tuple[Workunit, ...], status_code: int = 200, **options: Any
+) -> list[list[dict[str, Any]]]:
+    opentelemetry = create_subsystem(
+        OpenTelemetry,
+        enabled=True,
+        endpoint="http://localhost:4318/v1/traces",
+        headers={"Authorization": "Bearer token"},
+        service_name="pants",
+        resource_attributes={"deployment.environment": "ci"},
+        batch_size=2,
+        timeout=10,
+        **options,
+    )
+    callback = OpenTelemetryCallback(opentelemetry)
+    context = SimpleNamespace(
+        get_metrics=lambda: {"local_cache_requests": 3},
+        run_tracker=SimpleNamespace(run_id="pants_run_1"),
+    )
+    response = requests.Response()
+    response.status_code = status_code
+    with unittest.mock.patch.object(requests, "post", return_value=response) as post:
+        for i, completed_workunits in enumerate(calls):
+            callback(
+                completed_workunits=completed_workunits,
+                started_workunits=(),
+                context=context,  # type: ignore[arg-type]
+                finished=i == len(calls) - 1,
+            )
+
+    batches = []
+    for call in post.call_args_list:
+        assert call.args == ("http://localhost:4318/v1/traces",)
+        assert call.kwargs["headers"] == {"Authorization": "Bearer token"}
+        (resource_spans,) = call.kwargs["json"]["resourceSpans"]
+        resource_attributes = {
+            attribute["key"]: attribute["value"]["stringValue"]
+            for attribute in resource_spans["resource"]["attributes"]
+        }
+        assert resource_attributes["service.name"] == "pants"
+        assert resource_attributes["pants.run_id"] == "pants_run_1"
+        assert resource_attributes["deployment.environment"] == "ci"
+        (scope_spans,) = resource_spans["scopeSpans"]
+        batches.append(scope_spans["spans"])
+    return batches
+
+
+def test_batching() -> None:
+    batches = exported_batches(
+        (workunit("c", "b"),),
+        (workunit("d", "b"), workunit("e", "b"), workunit("b", "a")),
+        (workunit("a"),),
+    )
+    assert [[span["spanId"] for span in batch] for batch in batches] == [
+        ["c", "d"],
+        ["e", "b"],
+        ["a"],
+    ]
+    # All of the spans of a run belong to the same trace.
+    assert len({span["traceId"] for batch in batches for span in batch}) == 1
+
+
+def test_export_failure(caplog) -> None:
+    batches = exported_batches(
+        (workunit("c", "b"), workunit("b", "a")), (workunit("a"),), status_code=503
+    )
+    # Later batches are still attempted, but the failure is only reported once.
+    assert len(batches) == 2
+    assert len([r for r in caplog.records if "Failed to export spans" in r.message]) == 1
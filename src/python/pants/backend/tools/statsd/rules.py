@@ -0,0 +1,199 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+import logging
+import re
+import socket
+from typing import Any, Iterable, Mapping
+
+from pants.engine.internals.scheduler import Workunit
+from pants.engine.rules import collect_rules, rule
+from pants.engine.streaming_workunit_handler import (
+    StreamingWorkunitContext,
+    WorkunitsCallback,
+    WorkunitsCallbackFactory,
+    WorkunitsCallbackFactoryRequest,
+)
+from pants.engine.unions import UnionRule
+from pants.option.option_types import BoolOption, DictOption, IntOption, StrOption
+from pants.option.subsystem import Subsystem
+from pants.util.strutil import softwrap
+
+logger = logging.getLogger(__name__)
+
+
+class StatsD(Subsystem):
+    options_scope = "statsd"
+    help = softwrap(
+        """
+        Sends the metrics of each run to a StatsD (or DogStatsD) server over UDP.
+
+        At the end of each run, the following metrics are sent, prefixed with `[statsd].prefix`:
+
+          - `run.count` and `run.duration_ms`, tagged with the `outcome` of the run and its `goal`s.
+          - `counter.<name>`, for each counter which was incremented during the run.
+          - `observation.<name>.count` and `observation.<name>.sum`, for each metric which was
+            observed during the run (e.g. `observation.local_process_time_run_ms.sum`).
+        """
+    )
+
+    enabled = BoolOption(default=False, help="Whether to send the metrics of each run.")
+    host = StrOption(default="localhost", help="The host of the StatsD server.")
+    port = IntOption(default=8125, help="The UDP port of the StatsD server.")
+    prefix = StrOption(default="pants", help="The prefix of the names of the metrics sent.")
+    tags = DictOption[str](
+        help=softwrap(
+            """
+            Tags to send with each metric, e.g. `{"repo": "monorepo", "ci_job": "%(env.CI_JOB)s"}`.
+
+            Tags are sent with the DogStatsD extension to the StatsD protocol: see
+            `[statsd].dogstatsd`.
+            """
+        )
+    )
+    dogstatsd = BoolOption(
+        default=True,
+        help=softwrap(
+            """
+            Whether to send tags with the DogStatsD extension to the StatsD protocol, which is
+            supported by Datadog, Telegraf and the StatsD exporter of Prometheus, among others.
+
+            If disabled, no tags are sent, since plain StatsD does not support them.
+            """
+        ),
+    )
+    max_packet_size = IntOption(
+        default=1432,
+        help="The maximum size of each UDP packet, in bytes.",
+        advanced=True,
+    )
+
+
+# Characters which delimit the parts of a StatsD line.
+_RESERVED_CHARACTERS = re.compile(r"[:|@#,\s]")
+
+
+def _sanitize(value: str) -> str:
+    return _RESERVED_CHARACTERS.sub("_", value)
+
+
+def format_metric(
+    name: str, value: int | float, kind: str, tags: Iterable[tuple[str, str]] = ()
+) -> str:
+    """Formats a metric as a line of the StatsD protocol, with tags in the DogStatsD format."""
+    line = f"{_sanitize(name)}:{value}|{kind}"
+    tag_strs = [f"{_sanitize(key)}:{_sanitize(value)}" for key, value in tags]
+    return f"{line}|#{','.join(tag_strs)}" if tag_strs else line
+
+
+def run_metrics(
+    *,
+    prefix: str,
+    tags: Mapping[str, str] | None,
+    outcome: str,
+    goals: Iterable[str],
+    duration_secs: float,
+    counters: Mapping[str, int],
+    observations: Mapping[str, tuple[Any, int, int]],
+) -> list[str]:
+    """The metrics of a completed run, as lines of the StatsD protocol.
+
+    If `tags` is None, the metrics are not tagged at all, for StatsD servers which do not support
+    the DogStatsD extension.
+    """
+    common_tags = sorted((tags or {}).items())
+    run_tags = [*common_tags, ("outcome", outcome.lower()), *(("goal", goal) for goal in goals)]
+    if tags is None:
+        run_tags = []
+    lines = [
+        format_metric(f"{prefix}.run.count", 1, "c", run_tags),
+        format_metric(f"{prefix}.run.duration_ms", round(duration_secs * 1000), "ms", run_tags),
+    ]
+    for name, value in sorted(counters.items()):
+        if value:
+            lines.append(format_metric(f"{prefix}.counter.{name}", value, "c", common_tags))
+    for name, (_, count, total) in sorted(observations.items()):
+        lines.append(format_metric(f"{prefix}.observation.{name}.count", count, "c", common_tags))
+        lines.append(format_metric(f"{prefix}.observation.{name}.sum", total, "c", common_tags))
+    return lines
+
+
+def packets(lines: Iterable[str], *, max_packet_size: int) -> list[bytes]:
+    """Joins lines into as few newline-delimited packets of at most `max_packet_size` as possible.
+
+    A line which is larger than `max_packet_size` on its own is sent in a packet of its own.
+    """
+    result: list[bytes] = []
+    packet = b""
+    for line in lines:
+        encoded = line.encode()
+        if packet and len(packet) + 1 + len(encoded) > max_packet_size:
+            result.append(packet)
+            packet = b""
+        packet = b"\n".join((packet, encoded)) if packet else encoded
+    if packet:
+        result.append(packet)
+    return result
+
+
+class StatsDCallback(WorkunitsCallback):
+    """Sends the metrics of the run to a StatsD server once it has finished."""
+
+    def __init__(self, statsd: StatsD):
+        self.statsd = statsd
+
+    @property
+    def can_finish_async(self) -> bool:
+        return True
+
+    def __call__(
+        self,
+        *,
+        completed_workunits: tuple[Workunit, ...],
+        started_workunits: tuple[Workunit, ...],
+        context: StreamingWorkunitContext,
+        finished: bool = False,
+        **kwargs: Any,
+    ) -> None:
+        if not finished:
+            return
+
+        run_tracker = context.run_tracker
+        lines = run_metrics(
+            prefix=self.statsd.prefix,
+            tags=self.statsd.tags if self.statsd.dogstatsd else None,
+            outcome=run_tracker.run_information().get("outcome", "unknown"),
+            goals=run_tracker.goals,
+            duration_secs=sum(t["timing"] for t in run_tracker.get_cumulative_timings()),
+            counters=context.get_metrics(),
+            observations=context.get_observation_buckets(),
+        )
+        try:
+            family, _, _, _, address = socket.getaddrinfo(
+                self.statsd.host, self.statsd.port, type=socket.SOCK_DGRAM
+            )[0]
+            with socket.socket(family, socket.SOCK_DGRAM) as sock:
+                for packet in packets(lines, max_packet_size=self.statsd.max_packet_size):
+                    sock.sendto(packet, address)
+        except OSError as e:
+            logger.warning(
+                f"Failed to send metrics to StatsD at {self.statsd.host}:{self.statsd.port}: {e}"
+            )
+
+
+class StatsDCallbackFactoryRequest:
+    """A unique request type that is installed to trigger construction of our WorkunitsCallback."""
+
+
+@rule
+def construct_callback(_: StatsDCallbackFactoryRequest, statsd: StatsD) -> WorkunitsCallbackFactory:
+    return WorkunitsCallbackFactory(lambda: StatsDCallback(statsd) if statsd.enabled else None)
+
+
+def rules():
+    return [
+        UnionRule(WorkunitsCallbackFactoryRequest, StatsDCallbackFactoryRequest),
+        *collect_rules(),
+    ]
@@ -0,0 +1,99 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+import socket
+from types import SimpleNamespace
+
+from pants.backend.tools.statsd.rules import (
+    StatsD,
+    StatsDCallback,
+    format_metric,
+    packets,
+    run_metrics,
+)
+from pants.testutil.option_util import create_subsystem
+
+
+def test_format_metric() -> None:
+    assert format_metric("pants.run.count", 1, "c") == "pants.run.count:1|c"
+    assert (
+        format_metric("pants.run.duration_ms", 1500, "ms", [("ci_job", "lint:py"), ("goal", "fmt")])
+        == "pants.run.duration_ms:1500|ms|#ci_job:lint_py,goal:fmt"
+    )
+
+
+def test_run_metrics() -> None:
+    kwargs = dict(
+        prefix="pants",
+        outcome="SUCCESS",
+        goals=["lint", "test"],
+        duration_secs=1.2345,
+        counters={"local_cache_requests": 3, "remote_cache_requests": 0},
+        observations={"local_process_time_run_ms": ([(1, 0)], 2, 40)},
+    )
+    assert run_metrics(tags={"repo": "monorepo"}, **kwargs) == [  # type: ignore[arg-type]
+        "pants.run.count:1|c|#repo:monorepo,outcome:success,goal:lint,goal:test",
+        "pants.run.duration_ms:1234|ms|#repo:monorepo,outcome:success,goal:lint,goal:test",
+        "pants.counter.local_cache_requests:3|c|#repo:monorepo",
+        "pants.observation.local_process_time_run_ms.count:2|c|#repo:monorepo",
+        "pants.observation.local_process_time_run_ms.sum:40|c|#repo:monorepo",
+    ]
+    assert run_metrics(tags=None, **kwargs) == [  # type: ignore[arg-type]
+        "pants.run.count:1|c",
+        "pants.run.duration_ms:1234|ms",
+        "pants.counter.local_cache_requests:3|c",
+        "pants.observation.local_process_time_run_ms.count:2|c",
+        "pants.observation.local_process_time_run_ms.sum:40|c",
+    ]
+
+
+def test_packets() -> None:
+    assert packets(["a" * 4, "b" * 4, "c" * 4, "d" * 12], max_packet_size=10) == [
+        b"aaaa\nbbbb",
+        b"cccc",
+        b"d" * 12,
+    ]
+    assert packets([], max_packet_size=10) == []
+
+
+def test_callback() -> None:
+    with socket.socket(socket.AF_INET, socket.SOCK_DGRAM) as server:
+        server.bind(("127.0.0.1", 0))
+        server.settimeout(10)
+        statsd = create_subsystem(
+            StatsD,
+            enabled=True,
+            host="127.0.0.1",
+            port=server.getsockname()[1],
+            prefix="pants",
+            tags={"repo": "monorepo"},
+            dogstatsd=True,
+            max_packet_size=1432,
+        )
+        run_tracker = SimpleNamespace(
+            goals=["lint"],
+            run_information=lambda: {"outcome": "FAILURE"},
+            get_cumulative_timings=lambda: [{"label": "main", "timing": 2.0}],
+        )
+        context = SimpleNamespace(
+            run_tracker=run_tracker,
+            get_metrics=lambda: {"local_cache_requests": 3},
+            get_observation_buckets=lambda: {},
+        )
+        callback = StatsDCallback(statsd)
+        for finished in (False, True):
+            callback(
+                completed_workunits=(),
+                started_workunits=(),
+                context=context,  # type: ignore[arg-type]
+                finished=finished,
+            )
+
+        # Only the final call sends metrics, all in one packet.
+        assert server.recv(4096).decode().splitlines() == [
+            "pants.run.count:1|c|#repo:monorepo,outcome:failure,goal:lint",
+            "pants.run.duration_ms:2000|ms|#repo:monorepo,outcome:failure,goal:lint",
+            "pants.counter.local_cache_requests:3|c|#repo:monorepo",
+        ]
@@ -0,0 +1,180 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+"""Loading of the module resolution options of `tsconfig.json` and `jsconfig.json` files.
+
+See https://www.typescriptlang.org/tsconfig.
+"""
+
+from __future__ import annotations
+
+import json
+import logging
+import os.path
+from dataclasses import dataclass
+from typing import Any, Iterable
+
+from pants.engine.fs import DigestContents, PathGlobs
+from pants.engine.internals.selectors import Get
+from pants.engine.rules import Rule, collect_rules, rule
+from pants.util.frozendict import FrozenDict
+
+logger = logging.getLogger(__name__)
+
+TS_CONFIG_FILE_NAMES = ("tsconfig.json", "jsconfig.json")
+
+
+@dataclass(frozen=True)
+class TSConfig:
+    """The `baseUrl` and `paths` compiler options of a config, including those it `extends`.
+
+    All paths are relative to the build root.
+    """
+
+    path: str
+    base_url: str | None = None
+    paths: FrozenDict[str, tuple[str, ...]] = FrozenDict()
+    paths_root: str | None = None
+
+    @property
+    def resolution_root(self) -> str:
+        """The directory that `paths` (and, with a `baseUrl`, non-relative imports) resolve
+        against."""
+        return self.base_url if self.base_url is not None else self.paths_root or ""
+
+
+@dataclass(frozen=True)
+class ParentTSConfigRequest:
+    """Find the nearest `tsconfig.json` or `jsconfig.json` in the ancestor directories of a file."""
+
+    file_path: str
+
+
+@dataclass(frozen=True)
+class ParentTSConfig:
+    config: TSConfig | None
+
+
+def parse_jsonc(content: str) -> Any:
+    """Parses JSON with comments and trailing commas, as allowed in `tsconfig.json` files."""
+    result = []
+    i = 0
+    while i < len(content):
+        char = content[i]
+        if char == '"':
+            end = i + 1
+            while end < len(content) and content[end] != '"':
+                end += 2 if content[end] == "\\" else 1
+            result.append(content[i : end + 1])
+            i = end + 1
+        elif content.startswith("//", i):
+            newline = content.find("\n", i)
+            i = len(content) if newline == -1 else newline
+        elif content.startswith("/*", i):
+            end = content.find("*/", i + 2)
+            i = len(content) if end == -1 else end + 2
+        elif char in "]}":
+            # Drop any trailing comma before the closing bracket.
+            while result and result[-1].isspace():
+                result.pop()
+            if result and result[-1] == ",":
+                result.pop()
+            result.append(char)
+            i += 1
+        else:
+            result.append(char)
+            i += 1
+    return json.loads("".join(result))
+
+
+def _resolve_extends(config_path: str, extends: str) -> str | None:
+    # NB: Configs extended from packages (e.g. `@tsconfig/node18`) are not supported, since they
+    # can't contain module resolution options that refer to sources in the repo anyway.
+    if not extends.startswith("."):
+        return None
+    path = os.path.normpath(os.path.join(os.path.dirname(config_path), extends))
+    return path if path.endswith(".json") else f"{path}.json"
+
+
+def _join(config_dir: str, path: str) -> str:
+    joined = os.path.normpath(os.path.join(config_dir, path))
+    return "" if joined == "." else joined
+
+
+def merge_ts_config(
+    config_path: str, compiler_options: dict[str, Any], extended: TSConfig | None
+) -> TSConfig:
+    """Creates the config for the `compilerOptions` of the config at `config_path`, where options
+    which are not set are inherited from the `extended` config, if any.
+
+    As for the compiler, the `baseUrl` and `paths` options are resolved relative to the config
+    they're set in.
+    """
+    config_dir = os.path.dirname(config_path)
+    base_url = compiler_options.get("baseUrl")
+    paths = compiler_options.get("paths")
+    if base_url is not None:
+        base_url = _join(config_dir, base_url)
+    elif extended:
+        base_url = extended.base_url
+    if paths is not None:
+        return TSConfig(
+            path=config_path,
+            base_url=base_url,
+            paths=FrozenDict({key: tuple(value) for key, value in paths.items()}),
+            paths_root=config_dir,
+        )
+    return TSConfig(
+        path=config_path,
+        base_url=base_url,
+        paths=extended.paths if extended else FrozenDict(),
+        paths_root=extended.paths_root if extended else None,
+    )
+
+
+async def _load_ts_config(config_path: str) -> TSConfig | None:
+    # Load the chain of configs that the config extends, from the config itself to the root one.
+    chain: list[tuple[str, dict[str, Any]]] = []
+    path: str | None = config_path
+    while path is not None:
+        if any(path == seen for seen, _ in chain):
+            raise ValueError(f"The `extends` of the config file `{config_path}` form a cycle.")
+        digest_contents = await Get(DigestContents, PathGlobs([path]))
+        if not digest_contents:
+            logger.warning(f"The config file `{path}` extended by `{chain[-1][0]}` does not exist.")
+            break
+        try:
+            content = parse_jsonc(digest_contents[0].content.decode())
+        except ValueError as e:
+            logger.warning(f"Failed to parse `{path}`, ignoring it: {e}")
+            break
+        chain.append((path, content))
+        extends = content.get("extends")
+        path = _resolve_extends(path, extends) if isinstance(extends, str) else None
+
+    config = None
+    for path, content in reversed(chain):
+        config = merge_ts_config(path, content.get("compilerOptions") or {}, config)
+    return config
+
+
+@rule
+async def find_parent_ts_config(request: ParentTSConfigRequest) -> ParentTSConfig:
+    directory = os.path.dirname(request.file_path)
+    candidates = []
+    while True:
+        candidates.extend(os.path.join(directory, name) for name in TS_CONFIG_FILE_NAMES)
+        if not directory:
+            break
+        directory = os.path.dirname(directory)
+
+    digest_contents = await Get(DigestContents, PathGlobs(candidates))
+    existing = {file_content.path for file_content in digest_contents}
+    nearest = next((candidate for candidate in candidates if candidate in existing), None)
+    if nearest is None:
+        return ParentTSConfig(None)
+    return ParentTSConfig(await _load_ts_config(nearest))
+
+
+def rules() -> Iterable[Rule]:
+    return collect_rules()
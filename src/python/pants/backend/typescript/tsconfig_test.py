@@ -0,0 +1,52 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+from __future__ import annotations
+
+from textwrap import dedent
+
+from pants.backend.typescript.tsconfig import TSConfig, merge_ts_config, parse_jsonc
+from pants.util.frozendict import FrozenDict
+
+
+def test_parse_jsonc() -> None:
+    content = dedent(
+        """\
+        {
+          // A line comment.
+          "compilerOptions": {
+            /* A block comment. */
+            "baseUrl": "./src", // Comments are stripped, but not within "strings".
+            "paths": {"@app/*": ["app/*",],},
+          },
+        }
+        """
+    )
+    assert parse_jsonc(content) == {
+        "compilerOptions": {"baseUrl": "./src", "paths": {"@app/*": ["app/*"]}}
+    }
+
+
+def test_merge_ts_config() -> None:
+    base = merge_ts_config(
+        "tsconfig.base.json", {"baseUrl": ".", "paths": {"@app/*": ["src/app/*"]}}, None
+    )
+    assert base == TSConfig(
+        path="tsconfig.base.json",
+        base_url="",
+        paths=FrozenDict({"@app/*": ("src/app/*",)}),
+        paths_root="",
+    )
+    assert base.resolution_root == ""
+
+    child = merge_ts_config("js/tsconfig.json", {"strict": True}, base)
+    assert child.base_url == ""
+    assert child.paths == base.paths
+
+    overridden = merge_ts_config("js/tsconfig.json", {"paths": {"~/*": ["./*"]}}, base)
+    assert overridden.paths == FrozenDict({"~/*": ("./*",)})
+    # The `baseUrl` is still inherited, and so paths resolve relative to it.
+    assert overridden.resolution_root == ""
+
+    without_base_url = merge_ts_config("js/tsconfig.json", {"paths": {"~/*": ["./*"]}}, None)
+    assert without_base_url.base_url is None
+    assert without_base_url.resolution_root == "js"
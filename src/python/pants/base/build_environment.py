@@ -37,6 +37,20 @@ def get_default_pants_config_file() -> str:
     return os.path.join(get_buildroot(), "pants.toml")
 
 
+def get_default_pants_config_files() -> list[str]:
+    """Return the default locations of the Pants config files, in ascending order of precedence.
+
+    These are those of `pants.toml` and `pants.yaml` which exist, or else `pants.toml`.
+    """
+    buildroot = get_buildroot()
+    paths = [
+        path
+        for path in (os.path.join(buildroot, name) for name in ("pants.toml", "pants.yaml"))
+        if os.path.isfile(path)
+    ]
+    return paths or [get_default_pants_config_file()]
+
+
 def is_in_container() -> bool:
     """Return true if this process is likely running inside of a container."""
     # https://stackoverflow.com/a/49944991/38265 and https://github.com/containers/podman/issues/3586
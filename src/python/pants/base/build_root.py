@@ -17,7 +17,7 @@ class BuildRoot(metaclass=SingletonMetaclass):
     this interface for re-location of the build root in tests.
     """
 
-    sentinel_files = ["pants.toml", "pants", "BUILDROOT", "BUILD_ROOT"]
+    sentinel_files = ["pants.toml", "pants.yaml", "pants", "BUILDROOT", "BUILD_ROOT"]
 
     class NotFoundError(Exception):
         """Raised when unable to find the current workspace build root."""
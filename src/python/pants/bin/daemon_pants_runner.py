@@ -9,6 +9,7 @@ from contextlib import contextmanager
 from threading import Lock
 from typing import Dict, Tuple
 
+from pants.base.build_environment import get_buildroot
 from pants.base.exiter import PANTS_FAILED_EXIT_CODE, ExitCode
 from pants.bin.local_pants_runner import LocalPantsRunner
 from pants.engine.env_vars import CompleteEnvironmentVars
@@ -111,6 +112,8 @@ class DaemonPantsRunner:
 
         try:
             logger.debug("Connected to pantsd")
+            # NB: The working directory of a remote client is relative to the build root.
+            working_dir = os.path.join(get_buildroot(), working_dir)
             logger.debug(f"work dir: {working_dir}")
 
             # Capture the client's start time, which we propagate here in order to get an accurate
@@ -129,7 +132,8 @@ class DaemonPantsRunner:
             )
 
             # Run using the pre-warmed Session.
-            complete_env = CompleteEnvironmentVars(env)
+            # NB: This includes any variables from a `.env` file (see `--dotenv`).
+            complete_env = CompleteEnvironmentVars(options_bootstrapper.env)
             scheduler, options_initializer = self._core.prepare(options_bootstrapper, complete_env)
             runner = LocalPantsRunner.create(
                 complete_env,
@@ -139,7 +143,10 @@ class DaemonPantsRunner:
                 options_initializer=options_initializer,
                 cancellation_latch=cancellation_latch,
             )
-            return runner.run(start_time)
+            try:
+                return runner.run(start_time)
+            finally:
+                self._core.run_completed(runner.graph_session.scheduler_session)
         except Exception as e:
             logger.exception(e)
             return PANTS_FAILED_EXIT_CODE
@@ -28,10 +28,10 @@ from pants.engine.unions import UnionMembership
 from pants.goal.builtin_goal import BuiltinGoal
 from pants.goal.run_tracker import RunTracker
 from pants.init.engine_initializer import EngineInitializer, GraphScheduler, GraphSession
-from pants.init.logging import stdio_destination_use_color
+from pants.init.logging import stdio_destination_use_color, use_color
 from pants.init.options_initializer import OptionsInitializer
 from pants.init.specs_calculator import calculate_specs
-from pants.option.global_options import DynamicRemoteOptions, DynamicUIRenderer, GlobalOptions
+from pants.option.global_options import DynamicRemoteOptions, GlobalOptions
 from pants.option.options import Options
 from pants.option.options_bootstrapper import OptionsBootstrapper
 from pants.util.logging import LogLevel
@@ -95,7 +95,8 @@ class LocalPantsRunner:
         options = options_initializer.options(
             options_bootstrapper, env, build_config, union_membership, raise_=True
         )
-        stdio_destination_use_color(options.for_global_scope().colors)
+        colors = use_color(options.for_global_scope(), env)
+        stdio_destination_use_color(colors)
 
         run_tracker = RunTracker(options_bootstrapper.args, options)
         native_engine.maybe_set_panic_handler()
@@ -129,9 +130,8 @@ class LocalPantsRunner:
         graph_session = scheduler.new_session(
             run_tracker.run_id,
             dynamic_ui=global_options.dynamic_ui,
-            ui_use_prodash=global_options.dynamic_ui_renderer
-            == DynamicUIRenderer.experimental_prodash,
-            use_colors=global_options.get("colors", True),
+            ui_renderer=global_options.dynamic_ui_renderer,
+            use_colors=colors,
             max_workunit_level=max(
                 global_options.streaming_workunits_level,
                 global_options.level,
@@ -148,6 +148,7 @@ class LocalPantsRunner:
                 }
             ),
             cancellation_latch=cancellation_latch,
+            niceness=global_options.niceness,
         )
 
         specs = calculate_specs(
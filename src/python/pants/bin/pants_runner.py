@@ -140,7 +140,8 @@ class PantsRunner:
                 log_location=init_workdir(global_bootstrap_options), pantsd_instance=False
             )
             runner = LocalPantsRunner.create(
-                env=CompleteEnvironmentVars(self.env),
+                # NB: This includes any variables from a `.env` file (see `--dotenv`).
+                env=CompleteEnvironmentVars(options_bootstrapper.env),
                 working_dir=os.getcwd(),
                 options_bootstrapper=options_bootstrapper,
             )
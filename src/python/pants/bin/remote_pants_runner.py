@@ -10,8 +10,11 @@ import time
 from contextlib import contextmanager
 from typing import List, Mapping
 
+import psutil
+
 from pants.base.exiter import ExitCode
 from pants.engine.internals.native_engine import (
+    PantsdClientException,
     PantsdConnectionException,
     PyExecutor,
     PyNailgunClient,
@@ -153,6 +156,7 @@ class RemotePantsRunner:
 
         retries = 3
         attempt = 1
+        rerun_after_restart = global_options.pantsd_rerun_after_restart
         while True:
             port = pantsd_handle.port
             logger.debug(f"Connecting to pantsd on port {port} attempt {attempt}/{retries}")
@@ -164,6 +168,17 @@ class RemotePantsRunner:
             with STTYSettings.preserved(), interrupts_ignored():
                 try:
                     return PyNailgunClient(port, executor).execute(command, args, modified_env)
+                # Re-run (at most once) if pantsd exited during the run.
+                except PantsdClientException:
+                    if not rerun_after_restart or psutil.pid_exists(pantsd_handle.pid):
+                        raise
+                    logger.warning(
+                        f"pantsd (pid {pantsd_handle.pid}) exited during the run: relaunching it, "
+                        "and running again. Work which completed before it exited will be "
+                        "served from the local caches."
+                    )
+                    rerun_after_restart = False
+                    pantsd_handle = self._client.maybe_launch()
                 # Retry if we failed to connect to Pantsd.
                 except PantsdConnectionException as e:
                     if attempt > retries:
@@ -36,6 +36,7 @@ from pants.engine.desktop import OpenFiles, OpenFilesRequest
 from pants.engine.engine_aware import EngineAwareReturnType
 from pants.engine.env_vars import EnvironmentVars, EnvironmentVarsRequest
 from pants.engine.fs import EMPTY_FILE_DIGEST, Digest, FileDigest, MergeDigests, Snapshot, Workspace
+from pants.engine import metrics
 from pants.engine.goal import Goal, GoalSubsystem
 from pants.engine.internals.session import RunId
 from pants.engine.process import (
@@ -270,7 +271,10 @@ class TestResult(EngineAwareReturnType):
         return f"{message}{output}"
 
     def metadata(self) -> dict[str, Any]:
-        return {"addresses": [address.spec for address in self.addresses]}
+        return {
+            "addresses": [address.spec for address in self.addresses],
+            "exit_code": self.exit_code,
+        }
 
     def cacheable(self) -> bool:
         """Is marked uncacheable to ensure that it always renders."""
@@ -869,6 +873,42 @@ async def _run_debug_tests(
     return Test(exit_code)
 
 
+@dataclass(frozen=True)
+class _TestBatchRequest:
+    batch: TestRequest.Batch
+    environment_name: EnvironmentName
+
+
+@dataclass(frozen=True)
+class _TestBatchResult:
+    result: TestResult
+
+
+def _count_test_result(result: TestResult) -> None:
+    """Counts the targets of the result as passed, failed or skipped, so that the dynamic UI can
+    display the outcomes of tests while the remaining tests run."""
+    if result.exit_code is None:
+        outcome = "skipped"
+    elif result.exit_code == 0:
+        outcome = "passed"
+    else:
+        outcome = "failed"
+    metrics.increment_counter(f"tests_{outcome}", len(result.addresses), display=True)
+
+
+@rule
+async def run_test_batch(request: _TestBatchRequest) -> _TestBatchResult:
+    result = await Get(
+        TestResult,
+        {
+            request.batch: TestRequest.Batch,
+            request.environment_name: EnvironmentName,
+        },
+    )
+    _count_test_result(result)
+    return _TestBatchResult(result)
+
+
 @goal_rule
 async def run_tests(
     console: Console,
@@ -925,16 +965,11 @@ async def run_tests(
         )
 
     to_test = list(zip(test_batches, environment_names))
-    results = await MultiGet(
-        Get(
-            TestResult,
-            {
-                batch: TestRequest.Batch,
-                environment_name: EnvironmentName,
-            },
-        )
+    batch_results = await MultiGet(
+        Get(_TestBatchResult, _TestBatchRequest(batch, environment_name))
         for batch, environment_name in to_test
     )
+    results = [batch_result.result for batch_result in batch_results]
 
     # Print summary.
     exit_code = 0
@@ -1041,6 +1076,7 @@ _SOURCE_MAP = {
     ProcessResultMetadata.Source.RAN: "ran",
     ProcessResultMetadata.Source.HIT_LOCALLY: "cached locally",
     ProcessResultMetadata.Source.HIT_REMOTELY: "cached remotely",
+    ProcessResultMetadata.Source.DRY_RUN: "dry run",
 }
 
 
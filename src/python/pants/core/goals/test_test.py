@@ -33,8 +33,11 @@ from pants.core.goals.test import (
     TestResult,
     TestSubsystem,
     TestTimeoutField,
+    _count_test_result,
     _format_test_rerun_command,
     _format_test_summary,
+    _TestBatchRequest,
+    _TestBatchResult,
     build_runtime_package_dependencies,
     run_tests,
 )
@@ -45,6 +48,7 @@ from pants.core.util_rules.environments import (
     SingleEnvironmentNameRequest,
 )
 from pants.core.util_rules.partitions import Partition, Partitions
+from pants.engine import metrics
 from pants.engine.addresses import Address
 from pants.engine.console import Console
 from pants.engine.desktop import OpenFiles, OpenFilesRequest
@@ -371,9 +375,11 @@ def run_test_rule(
                     mock=lambda _a: EnvironmentName(None),
                 ),
                 MockGet(
-                    output_type=TestResult,
-                    input_types=(TestRequest.Batch, EnvironmentName),
-                    mock=mock_test_partition,
+                    output_type=_TestBatchResult,
+                    input_types=(_TestBatchRequest,),
+                    mock=lambda request: _TestBatchResult(
+                        mock_test_partition(request.batch, request.environment_name)
+                    ),
                 ),
                 MockGet(
                     output_type=TestDebugRequest,
@@ -600,6 +606,23 @@ def test_format_rerun_command(results: list[TestResult], expected: None | str) -
     assert expected == _format_test_rerun_command(results)
 
 
+@pytest.mark.parametrize(
+    "exit_code, counter", [(0, "tests_passed"), (1, "tests_failed"), (None, "tests_skipped")]
+)
+def test_count_test_result(
+    monkeypatch: pytest.MonkeyPatch, exit_code: int | None, counter: str
+) -> None:
+    counted: list[tuple[str, int, bool]] = []
+
+    def increment_counter(name: str, change: int = 1, *, display: bool = False) -> None:
+        counted.append((name, change, display))
+
+    monkeypatch.setattr(metrics, "increment_counter", increment_counter)
+    addresses = [Address("", target_name="a"), Address("", target_name="b")]
+    _count_test_result(make_test_result(addresses, exit_code=exit_code))
+    assert counted == [(counter, 2, True)]
+
+
 def test_debug_target(rule_runner: PythonRuleRunner) -> None:
     exit_code, _ = run_test_rule(
         rule_runner,
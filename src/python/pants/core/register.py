@@ -59,7 +59,14 @@ from pants.core.util_rules.environments import (
 )
 from pants.core.util_rules.wrap_source import wrap_source_rule_and_target
 from pants.engine.internals.parametrize import Parametrize
-from pants.goal import anonymous_telemetry, stats_aggregator
+from pants.goal import (
+    anonymous_telemetry,
+    provenance,
+    run_history,
+    stats_aggregator,
+    trace_events,
+    workunit_logs,
+)
 from pants.source import source_root
 from pants.vcs import git
 from pants.version import PANTS_SEMVER
@@ -95,13 +102,17 @@ def rules():
         *environments.rules(),
         *external_tool.rules(),
         *git.rules(),
+        *provenance.rules(),
         *source_files.rules(),
+        *run_history.rules(),
         *source_root.rules(),
         *stats_aggregator.rules(),
         *stripped_source_files.rules(),
         *subprocess_environment.rules(),
         *system_binaries.rules(),
         *target_type_rules(),
+        *trace_events.rules(),
+        *workunit_logs.rules(),
         *wrap_as_resources.rules,
     ]
 
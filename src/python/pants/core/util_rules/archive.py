@@ -5,26 +5,20 @@ from __future__ import annotations
 
 import logging
 import os
-import shlex
 from dataclasses import dataclass
 from pathlib import PurePath
 
 from pants.core.util_rules import system_binaries
 from pants.core.util_rules.adhoc_binaries import GunzipBinary
 from pants.core.util_rules.system_binaries import ArchiveFormat as ArchiveFormat
-from pants.core.util_rules.system_binaries import (
-    BashBinary,
-    SystemBinariesSubsystem,
-    TarBinary,
-    UnzipBinary,
-    ZipBinary,
-)
+from pants.core.util_rules.system_binaries import SystemBinariesSubsystem, TarBinary, UnzipBinary
 from pants.engine.fs import (
     CreateDigest,
     Digest,
     Directory,
-    FileContent,
     MergeDigests,
+    NativeCreateArchive,
+    NativeExtractArchive,
     RemovePrefix,
     Snapshot,
 )
@@ -32,7 +26,6 @@ from pants.engine.process import Process, ProcessResult
 from pants.engine.rules import Get, MultiGet, collect_rules, rule
 from pants.util.frozendict import FrozenDict
 from pants.util.logging import LogLevel
-from pants.util.strutil import softwrap
 
 logger = logging.getLogger(__name__)
 
@@ -41,73 +34,32 @@ logger = logging.getLogger(__name__)
 class CreateArchive:
     """A request to create an archive.
 
-    All files in the input snapshot will be included in the resulting archive.
+    All files, symlinks and empty directories in the input snapshot will be included in the
+    resulting archive, which is created reproducibly, with sorted entries and normalized timestamps
+    and permissions.
+
+    :param compression_level: The compression level to use for compressed formats: 0-9 for `tar.gz`,
+        `tar.xz` and `zip`, 1-9 for `tar.bz2`, and 1-22 for `tar.zst`. It may not be set for `tar`.
+        If not set, the default level of the compression algorithm is used.
     """
 
     snapshot: Snapshot
     output_filename: str
     format: ArchiveFormat
+    compression_level: int | None = None
 
 
 @rule(desc="Creating an archive file", level=LogLevel.DEBUG)
-async def create_archive(
-    request: CreateArchive, system_binaries_environment: SystemBinariesSubsystem.EnvironmentAware
-) -> Digest:
-    # #16091 -- if an arg list is really long, archive utilities tend to get upset.
-    # passing a list of filenames into the utilities fixes this.
-    FILE_LIST_FILENAME = "__pants_archive_filelist__"
-    file_list_file = FileContent(
-        FILE_LIST_FILENAME, "\n".join(request.snapshot.files).encode("utf-8")
-    )
-    file_list_file_digest = await Get(Digest, CreateDigest([file_list_file]))
-    files_digests = [file_list_file_digest, request.snapshot.digest]
-    input_digests = []
-
-    if request.format == ArchiveFormat.ZIP:
-        zip_binary, bash_binary = await MultiGet(Get(ZipBinary), Get(BashBinary))
-        env = {}
-        argv: tuple[str, ...] = (
-            bash_binary.path,
-            "-c",
-            softwrap(
-                f"""
-                {zip_binary.path} --names-stdin {shlex.quote(request.output_filename)}
-                < {FILE_LIST_FILENAME}
-                """
-            ),
-        )
-    else:
-        tar_binary = await Get(TarBinary)
-        argv = tar_binary.create_archive_argv(
+async def create_archive(request: CreateArchive) -> Digest:
+    return await Get(
+        Digest,
+        NativeCreateArchive(
+            request.snapshot.digest,
             request.output_filename,
-            request.format,
-            input_file_list_filename=FILE_LIST_FILENAME,
-        )
-        # `tar` expects to find a couple binaries like `gzip` and `xz` by looking on the PATH.
-        env = {"PATH": os.pathsep.join(system_binaries_environment.system_binary_paths)}
-
-        # `tar` requires that the output filename's parent directory exists, so if the caller
-        # wants the output in a directory we explicitly create it here.
-        # We have to guard this path as the Rust code will crash if we give it empty paths.
-        output_dir = os.path.dirname(request.output_filename)
-        if output_dir != "":
-            output_dir_digest = await Get(Digest, CreateDigest([Directory(output_dir)]))
-            input_digests.append(output_dir_digest)
-
-    input_digest = await Get(Digest, MergeDigests([*files_digests, *input_digests]))
-
-    result = await Get(
-        ProcessResult,
-        Process(
-            argv=argv,
-            env=env,
-            input_digest=input_digest,
-            description=f"Create {request.output_filename}",
-            level=LogLevel.DEBUG,
-            output_files=(request.output_filename,),
+            request.format.value,
+            compression_level=request.compression_level,
         ),
     )
-    return result.output_digest
 
 
 @dataclass(frozen=True)
@@ -133,6 +85,10 @@ class ExtractedArchive:
     digest: Digest
 
 
+# The archive suffixes which are supported by `NativeExtractArchive`.
+_NATIVE_ARCHIVE_SUFFIXES = (".tar.zst", ".tzst", ".tar.xz", ".txz", ".7z", ".zst", ".xz")
+
+
 @rule
 async def convert_digest_to_MaybeExtractArchiveRequest(
     digest: Digest,
@@ -157,10 +113,15 @@ async def maybe_extract_archive(
 
     archive_path = snapshot.files[0]
     archive_suffix = request.use_suffix or "".join(PurePath(archive_path).suffixes)
+    if archive_suffix.endswith(_NATIVE_ARCHIVE_SUFFIXES):
+        # These formats are extracted by the engine, without spawning a process.
+        resulting_digest = await Get(
+            Digest, NativeExtractArchive(request.digest, use_suffix=archive_suffix)
+        )
+        return ExtractedArchive(resulting_digest)
+
     is_zip = archive_suffix.endswith(".zip")
-    is_tar = archive_suffix.endswith(
-        (".tar", ".tar.gz", ".tgz", ".tar.bz2", ".tbz2", ".tar.xz", ".txz", ".tar.lz4")
-    )
+    is_tar = archive_suffix.endswith((".tar", ".tar.gz", ".tgz", ".tar.bz2", ".tbz2", ".tar.lz4"))
     is_gz = not is_tar and archive_suffix.endswith(".gz")
     if not is_zip and not is_tar and not is_gz:
         return ExtractedArchive(request.digest)
@@ -1,8 +1,11 @@
 # Copyright 2020 Pants project contributors (see CONTRIBUTORS.md).
 # Licensed under the Apache License, Version 2.0 (see LICENSE).
 
+from __future__ import annotations
+
 import base64
 import gzip
+import lzma
 import subprocess
 import tarfile
 import zipfile
@@ -113,6 +116,44 @@ def test_extract_gz(extract_from_file_info: ExtractorFixtureT, rule_runner: Rule
     assert digest_contents == DigestContents([FileContent(name, content)])
 
 
+def test_extract_xz(extract_from_file_info: ExtractorFixtureT) -> None:
+    # NB: Like `gz` files, `xz` files represent a single compressed file.
+    content = b"Hello world!\n"
+    digest_contents = extract_from_file_info(".xz", lzma.compress(content))
+    assert digest_contents == DigestContents([FileContent("test", content)])
+
+
+def _zstd_frame(content: bytes) -> bytes:
+    """Encode the content as a zstd frame containing a single uncompressed block.
+
+    The stdlib has no zstd support, but a frame of raw blocks is trivial to construct.
+    """
+    assert len(content) < 128 * 1024
+    # The magic number, a single-segment frame header with a 4 byte content size, and then a
+    # header for the last (and only) block, which is raw.
+    frame_header = b"\x28\xb5\x2f\xfd\xa0" + len(content).to_bytes(4, "little")
+    block_header = (1 | (len(content) << 3)).to_bytes(3, "little")
+    return frame_header + block_header + content
+
+
+def test_extract_zst(extract_from_file_info: ExtractorFixtureT) -> None:
+    content = b"Hello world!\n"
+    digest_contents = extract_from_file_info(".zst", _zstd_frame(content))
+    assert digest_contents == DigestContents([FileContent("test", content)])
+
+
+@pytest.mark.parametrize("suffix", [".tar.zst", ".tzst"])
+def test_extract_tar_zst(extract_from_file_info: ExtractorFixtureT, suffix: str) -> None:
+    io = BytesIO()
+    with tarfile.open(mode="w", fileobj=io) as tf:
+        for name, content in FILES.items():
+            tarinfo = tarfile.TarInfo(name)
+            tarinfo.size = len(content)
+            tf.addfile(tarinfo, BytesIO(content))
+    digest_contents = extract_from_file_info(suffix, _zstd_frame(io.getvalue()))
+    assert digest_contents == EXPECTED_DIGEST_CONTENTS
+
+
 def test_extract_non_archive(rule_runner: RuleRunner) -> None:
     input_snapshot = rule_runner.make_snapshot({"test.sh": b"# A shell script"})
     extracted_archive = rule_runner.request(ExtractedArchive, [input_snapshot.digest])
@@ -200,3 +241,37 @@ def test_create_tar_archive_in_root_dir(rule_runner: RuleRunner, format: Archive
     extracted_archive = rule_runner.request(ExtractedArchive, [created_digest])
     digest_contents = rule_runner.request(DigestContents, [extracted_archive.digest])
     assert digest_contents == EXPECTED_DIGEST_CONTENTS
+
+
+@pytest.mark.parametrize("compression_level", [None, 1, 9])
+def test_create_archive_is_reproducible(
+    rule_runner: RuleRunner, compression_level: int | None
+) -> None:
+    input_snapshot = rule_runner.make_snapshot({"b": b"b", "a/c": b"c", "a/b": b"b"})
+    tar_digest, zip_digest = (
+        rule_runner.request(
+            Digest,
+            [
+                CreateArchive(
+                    input_snapshot,
+                    output_filename=f"a.{format.value}",
+                    format=format,
+                    compression_level=compression_level,
+                )
+            ],
+        )
+        for format in (ArchiveFormat.TGZ, ArchiveFormat.ZIP)
+    )
+
+    (tar_content,) = rule_runner.request(DigestContents, [tar_digest])
+    with tarfile.open(fileobj=BytesIO(tar_content.content), mode="r:gz") as tf:
+        members = tf.getmembers()
+    assert ["a/b", "a/c", "b"] == [member.name for member in members]
+    assert {315532800} == {member.mtime for member in members}
+    assert {0} == {member.uid for member in members}
+
+    (zip_content,) = rule_runner.request(DigestContents, [zip_digest])
+    with zipfile.ZipFile(BytesIO(zip_content.content)) as zf:
+        infos = zf.infolist()
+    assert ["a/b", "a/c", "b"] == [info.filename for info in infos]
+    assert {(1980, 1, 1, 0, 0, 0)} == {info.date_time for info in infos}
@@ -0,0 +1,91 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+"""A bridge between `@rule`s and `asyncio`, for plugins which use async client libraries.
+
+The engine drives rules itself, so a rule cannot directly await `asyncio` futures (such as those of
+`aiohttp` or `grpc.aio`). Instead, a rule may run a coroutine on a shared `asyncio` event loop with
+`run_asyncio`, which awaits its result without blocking the engine:
+
+    @rule
+    async def fetch_metadata(request: MetadataRequest) -> Metadata:
+        engine = EngineBridge()
+        return await run_asyncio(_fetch(engine, request))
+
+Within the coroutine, engine calls (downloads, process executions, store reads) may be awaited as
+`asyncio` futures via an `EngineBridge`, which must be created synchronously in the body of the
+rule, and runs the calls as if the rule had awaited them itself:
+
+    async def _fetch(engine: EngineBridge, request: MetadataRequest) -> Metadata:
+        async with aiohttp.ClientSession() as session:
+            response, digest = await asyncio.gather(
+                session.get(request.url), engine.download_file(request.download)
+            )
+            ...
+
+The event loop runs on a single background thread, which is shared by all rules: coroutines must
+not block it.
+"""
+
+from __future__ import annotations
+
+import asyncio
+import threading
+from typing import Any, Awaitable, Coroutine, TypeVar, cast
+
+from pants.engine.fs import Digest, DigestContents, NativeDownloadFile
+from pants.engine.internals import native_engine
+from pants.engine.internals.native_engine import ProcessExecutionEnvironment
+from pants.engine.process import FallibleProcessResult, Process
+
+_T = TypeVar("_T")
+
+_event_loop_lock = threading.Lock()
+_event_loop: asyncio.AbstractEventLoop | None = None
+
+
+def event_loop() -> asyncio.AbstractEventLoop:
+    """The event loop on which `run_asyncio` runs coroutines, which is started on first use."""
+    global _event_loop
+    with _event_loop_lock:
+        if _event_loop is None or _event_loop.is_closed():
+            loop = asyncio.new_event_loop()
+            threading.Thread(target=loop.run_forever, name="pants-asyncio", daemon=True).start()
+            _event_loop = loop
+        return _event_loop
+
+
+async def run_asyncio(coroutine: Coroutine[Any, Any, _T]) -> _T:
+    """Runs the coroutine on the shared event loop, and awaits its result from a `@rule`.
+
+    If the rule is cancelled (e.g. by Ctrl-C) while awaiting the result, so is the coroutine.
+    """
+    future = asyncio.run_coroutine_threadsafe(coroutine, event_loop())
+    return cast(_T, await native_engine.await_concurrent_future(future))
+
+
+class EngineBridge:
+    """Starts engine calls on behalf of a `@rule`, as `asyncio` futures.
+
+    An `EngineBridge` must be created synchronously within the body of a `@rule`, but its methods
+    may then be called from coroutines on the event loop (see `run_asyncio`). Calls run as if the
+    rule had awaited them itself: their workunits are its children.
+    """
+
+    def __init__(self) -> None:
+        self._task_context = native_engine.task_context_capture()
+
+    def run(self, call: Awaitable[_T]) -> asyncio.Future[_T]:
+        """Starts a call to a `native_engine` intrinsic, and returns a future for its result."""
+        return asyncio.wrap_future(self._task_context.spawn(call), loop=event_loop())
+
+    def download_file(self, request: NativeDownloadFile) -> asyncio.Future[Digest]:
+        return self.run(native_engine.download_file_to_digest(request))
+
+    def execute_process(
+        self, process: Process, environment: ProcessExecutionEnvironment
+    ) -> asyncio.Future[FallibleProcessResult]:
+        return self.run(native_engine.process_request_to_process_result(process, environment))
+
+    def digest_contents(self, digest: Digest) -> asyncio.Future[DigestContents]:
+        return self.run(native_engine.directory_digest_to_digest_contents(digest))
@@ -0,0 +1,93 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+import asyncio
+from dataclasses import dataclass
+
+import pytest
+
+from pants.engine.asyncio_bridge import EngineBridge, run_asyncio
+from pants.engine.fs import CreateDigest, Digest, FileContent
+from pants.engine.rules import rule
+from pants.testutil.rule_runner import QueryRule, RuleRunner, engine_error
+
+
+@dataclass(frozen=True)
+class Greet:
+    name: str
+
+
+@dataclass(frozen=True)
+class Greeting:
+    text: str
+
+
+async def _greet(name: str) -> str:
+    await asyncio.sleep(0.01)
+    if not name:
+        raise ValueError("Nobody to greet.")
+    return f"Hello, {name}!"
+
+
+@rule
+async def greet(request: Greet) -> Greeting:
+    return Greeting(await run_asyncio(_greet(request.name)))
+
+
+@dataclass(frozen=True)
+class Measure:
+    digest: Digest
+
+
+@dataclass(frozen=True)
+class Measurement:
+    total_length: int
+
+
+async def _measure(engine: EngineBridge, digest: Digest) -> int:
+    first, second = await asyncio.gather(engine.digest_contents(digest), _greet("again"))
+    return sum(len(file_content.content) for file_content in first) + len(second)
+
+
+@rule
+async def measure(request: Measure) -> Measurement:
+    engine = EngineBridge()
+    return Measurement(await run_asyncio(_measure(engine, request.digest)))
+
+
+@pytest.fixture
+def rule_runner() -> RuleRunner:
+    return RuleRunner(
+        rules=[
+            greet,
+            measure,
+            QueryRule(Digest, [CreateDigest]),
+            QueryRule(Greeting, [Greet]),
+            QueryRule(Measurement, [Measure]),
+        ]
+    )
+
+
+def test_run_asyncio(rule_runner: RuleRunner) -> None:
+    assert rule_runner.request(Greeting, [Greet("pants")]) == Greeting("Hello, pants!")
+
+
+def test_run_asyncio_error(rule_runner: RuleRunner) -> None:
+    with engine_error(ValueError, contains="Nobody to greet."):
+        rule_runner.request(Greeting, [Greet("")])
+
+
+def test_engine_bridge(rule_runner: RuleRunner) -> None:
+    digest = rule_runner.request(
+        Digest, [CreateDigest([FileContent("a.txt", b"abc"), FileContent("b.txt", b"de")])]
+    )
+    assert rule_runner.request(Measurement, [Measure(digest)]) == Measurement(
+        5 + len("Hello, again!")
+    )
+
+
+def test_engine_bridge_outside_of_rule() -> None:
+    with pytest.raises(Exception, match="only be captured within the body of the `@rule`"):
+        EngineBridge()
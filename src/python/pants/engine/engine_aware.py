@@ -10,7 +10,7 @@ from pants.engine.internals import native_engine
 from pants.util.logging import LogLevel
 
 if TYPE_CHECKING:
-    from pants.engine.fs import FileDigest, Snapshot
+    from pants.engine.fs import Digest, FileDigest, Snapshot
 
 
 class EngineAwareParameter(ABC):
@@ -71,11 +71,15 @@ class EngineAwareReturnType(ABC):
         """
         return True
 
-    def artifacts(self) -> dict[str, FileDigest | Snapshot] | None:
+    def artifacts(self) -> dict[str, FileDigest | Snapshot | Digest | bytes] | None:
         """If implemented, this sets the `artifacts` entry for the workunit of any `@rule`'s that
         return the annotated type.
 
-        `artifacts` is a mapping of arbitrary string keys to `Snapshot`s or `FileDigest`s.
+        `artifacts` is a mapping of arbitrary string keys to `Snapshot`s, `Digest`s, `FileDigest`s,
+        or small `bytes` payloads (such as test reports). Artifacts are persisted to the local store
+        and retained for `[GLOBAL].local_store_artifacts_retention_secs`, so that they may be loaded
+        by their digests after the run. In the workunit, `bytes` are replaced by the `FileDigest` of
+        the stored file, and `Digest`s by their `Snapshot`.
         """
         return None
 
@@ -5,7 +5,7 @@ from __future__ import annotations
 
 from dataclasses import dataclass
 from enum import Enum
-from typing import TYPE_CHECKING, Iterable, Mapping, Optional, Sequence, Tuple, Union
+from typing import TYPE_CHECKING, Iterable, Iterator, Mapping, Optional, Sequence, Tuple, Union
 
 # Note: several of these types are re-exported as the public API of `engine/fs.py`.
 from pants.base.glob_match_error_behavior import GlobMatchErrorBehavior as GlobMatchErrorBehavior
@@ -19,9 +19,11 @@ from pants.engine.internals.native_engine import EMPTY_SNAPSHOT as EMPTY_SNAPSHO
 from pants.engine.internals.native_engine import AddPrefix as AddPrefix
 from pants.engine.internals.native_engine import Digest as Digest
 from pants.engine.internals.native_engine import FileDigest as FileDigest
+from pants.engine.internals.native_engine import FileDigestHasher as FileDigestHasher
 from pants.engine.internals.native_engine import MergeDigests as MergeDigests
 from pants.engine.internals.native_engine import RemovePrefix as RemovePrefix
 from pants.engine.internals.native_engine import Snapshot as Snapshot
+from pants.engine.internals.native_engine import StructuredData as StructuredData
 from pants.engine.rules import QueryRule
 from pants.util.frozendict import FrozenDict
 
@@ -66,6 +68,28 @@ class FileContent:
         )
 
 
+@dataclass(frozen=True)
+class FileContentView:
+    """The content of a file, as a read-only view of memory which is owned by the engine.
+
+    Unlike `FileContent`, the content is not copied into Python `bytes`, which makes this suitable
+    for reading large files. The `memoryview` supports the buffer protocol (so it may be passed
+    directly to `hashlib`, `zipfile`, `json.loads`, etc.), and `bytes(view.content)` or
+    `view.content.tobytes()` will copy it if `bytes` are needed. You can get back a list of
+    `FileContentView` objects by using `Get(DigestContentViews, Digest)`.
+    """
+
+    path: str
+    content: memoryview
+    is_executable: bool = False
+
+    def __repr__(self) -> str:
+        return (
+            f"FileContentView(path={self.path}, content=(len:{len(self.content)}), "
+            f"is_executable={self.is_executable})"
+        )
+
+
 @dataclass(frozen=True)
 class FileEntry:
     """An indirect reference to the content of a file by digest.
@@ -120,6 +144,15 @@ class DigestContents(Collection[FileContent]):
     """
 
 
+class DigestContentViews(Collection[FileContentView]):
+    """The file contents of a Digest, as views of memory which is owned by the engine.
+
+    This is an alternative to `DigestContents` for large files, which avoids holding a copy of
+    their content in both the engine and Python. The memory is released once the last view of it
+    has been garbage collected.
+    """
+
+
 class DigestEntries(Collection[Union[FileEntry, SymlinkEntry, Directory]]):
     """The indirect file contents of a Digest.
 
@@ -240,6 +273,139 @@ class DigestSubset:
     globs: PathGlobs
 
 
+class Digests(Collection[Digest]):
+    """The results of a batched digest-manipulation request, in the order of its requests."""
+
+
+@dataclass(frozen=True)
+class AddPrefixBatch:
+    """A batch of `AddPrefix` requests, which are all applied in a single call to the engine.
+
+    Example:
+
+        digests = await Get(
+            Digests, AddPrefixBatch(AddPrefix(digest, f"prefix/{i}") for i, digest in ...)
+        )
+    """
+
+    requests: tuple[AddPrefix, ...]
+
+    def __init__(self, requests: Iterable[AddPrefix]) -> None:
+        object.__setattr__(self, "requests", tuple(requests))
+
+
+@dataclass(frozen=True)
+class RemovePrefixBatch:
+    """A batch of `RemovePrefix` requests, which are all applied in a single call to the engine."""
+
+    requests: tuple[RemovePrefix, ...]
+
+    def __init__(self, requests: Iterable[RemovePrefix]) -> None:
+        object.__setattr__(self, "requests", tuple(requests))
+
+
+@dataclass(frozen=True)
+class MergeDigestsBatch:
+    """A batch of `MergeDigests` requests, which are all applied in a single call to the engine."""
+
+    requests: tuple[MergeDigests, ...]
+
+    def __init__(self, requests: Iterable[MergeDigests]) -> None:
+        object.__setattr__(self, "requests", tuple(requests))
+
+
+class DiffMode(Enum):
+    # Diff UTF-8 content by lines, and only report that non-UTF-8 (i.e. binary) content differs.
+    LINES = "lines"
+    # Diff arbitrary content by lines, without decoding it.
+    BYTES = "bytes"
+
+
+@dataclass(frozen=True)
+class DiffDigests:
+    """A request for a unified diff between two digests, computed natively.
+
+    By default, all files and symlinks (which are diffed by their targets) which differ between the
+    two digests are diffed by path. If `before_path` and `after_path` are set, only those two files
+    are diffed.
+
+    Example:
+
+        result = await Get(DigestDiff, DiffDigests(original_digest, formatted_digest))
+    """
+
+    before: Digest
+    after: Digest
+    before_path: str | None = None
+    after_path: str | None = None
+    mode: DiffMode = DiffMode.LINES
+    context_lines: int = 3
+
+    def __post_init__(self) -> None:
+        if (self.before_path is None) != (self.after_path is None):
+            raise ValueError("Both or neither of `before_path` and `after_path` must be set.")
+
+
+@dataclass(frozen=True)
+class DigestDiff:
+    """A unified diff (with git-style `a/` and `b/` path prefixes), and the paths that differ."""
+
+    content: bytes
+    changed_paths: tuple[str, ...]
+
+    @property
+    def text(self) -> str:
+        return self.content.decode(errors="replace")
+
+
+@dataclass(frozen=True)
+class FingerprintDigest:
+    """A request for a stable fingerprint of the paths, file contents and metadata of a digest.
+
+    Unlike the fingerprint of the `Digest` itself, which depends on how its directories are
+    serialized, the fingerprint is computed over a canonical encoding of the entries of the digest,
+    and so is suitable for embedding into artifacts or into the cache keys of external systems.
+
+    If `include_permissions` is set, whether files are executable affects the fingerprint. If
+    `include_symlinks` is not set, symlinks are excluded from it.
+    """
+
+    digest: Digest
+    include_permissions: bool = True
+    include_symlinks: bool = True
+
+
+@dataclass(frozen=True)
+class DigestFingerprint:
+    # A hex-encoded SHA-256 fingerprint.
+    fingerprint: str
+
+
+class StructuredFileFormat(Enum):
+    JSON = "json"
+    TOML = "toml"
+    YAML = "yaml"
+
+
+@dataclass(frozen=True)
+class ParseStructuredFile:
+    """A request to natively parse a JSON, TOML or YAML file (such as a lockfile) in a digest.
+
+    If the `format` is not set, it is inferred from the extension of the `path`. The result is a
+    `StructuredData` handle, which is queried by JSON pointer so that only the portions of the
+    document which are actually used are converted into Python structures.
+
+    Example:
+
+        lockfile = await Get(StructuredData, ParseStructuredFile(digest, "pnpm-lock.yaml"))
+        packages = lockfile.keys("/packages")
+    """
+
+    digest: Digest
+    path: str
+    format: StructuredFileFormat | None = None
+
+
 @dataclass(frozen=True)
 class DownloadFile:
     """Retrieve the contents of a file via an HTTP GET request or directly for local file:// URLs.
@@ -264,6 +430,21 @@ class NativeDownloadFile:
     The auth_headers are part of this nodes' cache key for memoization (changing a header invalidates
     prior results) but are not part of the underlying cache key for the local/remote cache (changing
     a header won't re-download a file if the file was previously downloaded).
+
+    The `mirror_urls` are tried in order if downloading from `url` fails, and each URL is retried
+    `retry_attempts` times for retryable failures, with an exponential backoff starting at
+    `retry_delay_ms` and multiplied by `retry_backoff_factor` after each attempt.
+
+    In addition to the required `expected_digest`, the downloaded file may be verified against
+    `checksums`: a mapping from a checksum algorithm (one of `sha256`, `sha512` or `blake3`) to the
+    expected hex-encoded checksum.
+
+    If the `auth_headers` do not include an `Authorization` header, credentials may instead be
+    provided by running `auth_token_command` (whose stdout is used as a bearer token, and which
+    receives the URL being downloaded as `PANTS_DOWNLOAD_URL`), or else via the `~/.netrc` file (or
+    `$NETRC`) if `use_netrc` is set. Credentials from these sources are resolved when downloading,
+    and so are never part of any cache key. The `auth_headers` are only sent to the host of `url`,
+    and not to any mirror hosts.
     """
 
     url: str
@@ -271,13 +452,185 @@ class NativeDownloadFile:
     # NB: This mapping can be of any arbitrary headers, but should be limited to those required for
     # authorization.
     auth_headers: FrozenDict[str, str]
+    mirror_urls: tuple[str, ...]
+    checksums: FrozenDict[str, str]
+    retry_attempts: int
+    retry_delay_ms: int
+    retry_backoff_factor: int
+    auth_token_command: tuple[str, ...]
+    use_netrc: bool
 
     def __init__(
-        self, url: str, expected_digest: FileDigest, auth_headers: Mapping[str, str] | None = None
+        self,
+        url: str,
+        expected_digest: FileDigest,
+        auth_headers: Mapping[str, str] | None = None,
+        *,
+        mirror_urls: Iterable[str] = (),
+        checksums: Mapping[str, str] | None = None,
+        retry_attempts: int = 4,
+        retry_delay_ms: int = 10,
+        retry_backoff_factor: int = 10,
+        auth_token_command: Iterable[str] = (),
+        use_netrc: bool = False,
     ) -> None:
         object.__setattr__(self, "url", url)
         object.__setattr__(self, "expected_digest", expected_digest)
         object.__setattr__(self, "auth_headers", FrozenDict(auth_headers or {}))
+        object.__setattr__(self, "mirror_urls", tuple(mirror_urls))
+        object.__setattr__(self, "checksums", FrozenDict(checksums or {}))
+        object.__setattr__(self, "retry_attempts", retry_attempts)
+        object.__setattr__(self, "retry_delay_ms", retry_delay_ms)
+        object.__setattr__(self, "retry_backoff_factor", retry_backoff_factor)
+        object.__setattr__(self, "auth_token_command", tuple(auth_token_command))
+        object.__setattr__(self, "use_netrc", use_netrc)
+
+
+@dataclass(frozen=True)
+class NativeCreateArchive:
+    """Create an archive of all of the entries of a digest natively, without spawning any process.
+
+    The `format` is one of `tar`, `tar.gz`, `tar.bz2`, `tar.xz`, `tar.zst` or `zip`. Archives are
+    reproducible: entries are sorted by path, and have normalized timestamps, owners, and
+    permissions. If `compression_level` is not set, the default level of the compression algorithm
+    is used.
+
+    The result is a digest containing only the archive, at `output_filename`. Most callers should
+    use `CreateArchive`.
+    """
+
+    digest: Digest
+    output_filename: str
+    format: str
+    compression_level: int | None = None
+
+
+@dataclass(frozen=True)
+class NativeExtractArchive:
+    """Extract a single archive file natively, without spawning any process.
+
+    The `digest` must contain exactly one file, which is extracted based on its suffix (or on
+    `use_suffix` if provided). The supported formats are `.tar`, `.tar.zst` (or `.tzst`), `.tar.xz`
+    (or `.txz`) and `.7z` archives, as well as single files compressed as `.zst` or `.xz`, which
+    are extracted to a file named without the compression suffix.
+
+    Most callers should use `MaybeExtractArchiveRequest`, which also supports other formats.
+    """
+
+    digest: Digest
+    use_suffix: str | None = None
+
+
+class PathNamespace(Enum):
+    """The namespace that the paths of a `PathMetadataRequest` are resolved in."""
+
+    # Paths relative to the build root. Requests are invalidated when the paths change.
+    WORKSPACE = "workspace"
+    # Absolute paths anywhere on the local system. Requests are NOT invalidated if the paths change
+    # while `pantsd` is running.
+    SYSTEM = "system"
+    # Paths within the `digest` of the request.
+    DIGEST = "digest"
+
+
+class PathMetadataKind(Enum):
+    FILE = "file"
+    DIRECTORY = "directory"
+    SYMLINK = "symlink"
+
+
+@dataclass(frozen=True)
+class PathMetadata:
+    """The metadata of a single path, which is not followed if it is a symlink.
+
+    The `unix_mode`, `uid`, `gid`, and `mtime_ns` (nanoseconds since the epoch) are not recorded in
+    digests, and so are None for paths in the `PathNamespace.DIGEST` namespace.
+    """
+
+    path: str
+    kind: PathMetadataKind
+    length: int
+    is_executable: bool
+    unix_mode: int | None
+    uid: int | None
+    gid: int | None
+    mtime_ns: int | None
+    symlink_target: str | None
+
+    def __post_init__(self) -> None:
+        # NB: The engine constructs this type with the string value of the kind.
+        object.__setattr__(self, "kind", PathMetadataKind(self.kind))
+
+
+@dataclass(frozen=True)
+class PathMetadataRequest:
+    """Request the metadata of a batch of paths in a single call.
+
+    The result is a `PathMetadataResult` containing the metadata for each path in order, or None for
+    paths which do not exist.
+    """
+
+    paths: tuple[str, ...]
+    namespace: PathNamespace
+    digest: Digest | None
+
+    def __init__(
+        self,
+        paths: Iterable[str],
+        namespace: PathNamespace = PathNamespace.WORKSPACE,
+        *,
+        digest: Digest | None = None,
+    ) -> None:
+        if (namespace == PathNamespace.DIGEST) != (digest is not None):
+            raise ValueError(
+                "A `digest` must be provided if and only if the namespace is `PathNamespace.DIGEST`."
+            )
+        object.__setattr__(self, "paths", tuple(paths))
+        object.__setattr__(self, "namespace", namespace)
+        object.__setattr__(self, "digest", digest)
+
+
+@dataclass(frozen=True)
+class PathMetadataResult:
+    metadata: tuple[PathMetadata | None, ...]
+
+    def __iter__(self) -> Iterator[PathMetadata | None]:
+        return iter(self.metadata)
+
+
+@dataclass(frozen=True)
+class GitMetadataRequest:
+    """Request the state of the git repository containing the build root, read natively.
+
+    Unlike shelling out to `git`, the resulting `GitMetadata` is cached, and is invalidated when
+    `.git/HEAD` (or the ref that it points to) changes.
+
+    If `include_dirty_paths` is set, the result additionally depends on the index and on every
+    tracked file (and its parent directories), and is invalidated by any change to the worktree.
+    That is expensive in large repositories, so it should only be set by callers which need the
+    dirty paths.
+    """
+
+    include_dirty_paths: bool = False
+
+
+@dataclass(frozen=True)
+class GitMetadata:
+    """The state of the git repository containing the build root.
+
+    All fields are None (and `dirty_paths` is empty) if the build root is not in a git repository.
+    The `describe` output is that of `git describe --tags --always`, and `dirty_paths` (relative to
+    the build root) contains modified, deleted, and untracked files, as reported by `git status`.
+    """
+
+    commit_id: str | None
+    branch_name: str | None
+    describe: str | None
+    dirty_paths: tuple[str, ...]
+
+    @property
+    def is_dirty(self) -> bool:
+        return bool(self.dirty_paths)
 
 
 @dataclass(frozen=True)
@@ -335,11 +688,22 @@ def rules():
         QueryRule(Digest, (CreateDigest,)),
         QueryRule(Digest, (PathGlobs,)),
         QueryRule(Digest, (AddPrefix,)),
+        QueryRule(Digests, (AddPrefixBatch,)),
         QueryRule(Digest, (RemovePrefix,)),
+        QueryRule(Digests, (RemovePrefixBatch,)),
         QueryRule(Digest, (NativeDownloadFile,)),
+        QueryRule(Digest, (NativeCreateArchive,)),
+        QueryRule(Digest, (NativeExtractArchive,)),
         QueryRule(Digest, (MergeDigests,)),
+        QueryRule(Digests, (MergeDigestsBatch,)),
         QueryRule(Digest, (DigestSubset,)),
+        QueryRule(DigestDiff, (DiffDigests,)),
+        QueryRule(DigestFingerprint, (FingerprintDigest,)),
+        QueryRule(StructuredData, (ParseStructuredFile,)),
         QueryRule(DigestContents, (Digest,)),
+        QueryRule(DigestContentViews, (Digest,)),
         QueryRule(Snapshot, (Digest,)),
         QueryRule(Paths, (PathGlobs,)),
+        QueryRule(PathMetadataResult, (PathMetadataRequest,)),
+        QueryRule(GitMetadata, (GitMetadataRequest,)),
     )
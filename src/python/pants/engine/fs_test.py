@@ -9,12 +9,14 @@ import pkgutil
 import shutil
 import socket
 import ssl
+import subprocess
 import tarfile
 import time
 from dataclasses import dataclass
 from http.server import BaseHTTPRequestHandler
 from io import BytesIO
 from pathlib import Path
+from textwrap import dedent
 from typing import Callable, Dict, Iterable, Optional, Set, Union
 
 import pytest
@@ -22,25 +24,47 @@ import pytest
 from pants.engine.console import Console
 from pants.engine.fs import (
     EMPTY_DIGEST,
+    EMPTY_FILE_DIGEST,
     EMPTY_SNAPSHOT,
     AddPrefix,
+    AddPrefixBatch,
     CreateDigest,
+    DiffDigests,
+    DiffMode,
     Digest,
     DigestContents,
+    DigestContentViews,
+    DigestDiff,
     DigestEntries,
+    DigestFingerprint,
+    Digests,
     DigestSubset,
     Directory,
     DownloadFile,
     FileContent,
     FileDigest,
+    FileDigestHasher,
     FileEntry,
+    FingerprintDigest,
+    GitMetadata,
+    GitMetadataRequest,
     GlobMatchErrorBehavior,
     MergeDigests,
+    MergeDigestsBatch,
+    NativeDownloadFile,
+    ParseStructuredFile,
     PathGlobs,
     PathGlobsAndRoot,
+    PathMetadataKind,
+    PathMetadataRequest,
+    PathMetadataResult,
+    PathNamespace,
     RemovePrefix,
+    RemovePrefixBatch,
     Snapshot,
     SnapshotDiff,
+    StructuredData,
+    StructuredFileFormat,
     SymlinkEntry,
     Workspace,
 )
@@ -59,11 +83,20 @@ def rule_runner() -> RuleRunner:
         rules=[
             QueryRule(Digest, [CreateDigest]),
             QueryRule(DigestContents, [PathGlobs]),
+            QueryRule(DigestContentViews, [Digest]),
             QueryRule(DigestEntries, [Digest]),
             QueryRule(DigestEntries, [PathGlobs]),
             QueryRule(Snapshot, [CreateDigest]),
             QueryRule(Snapshot, [DigestSubset]),
             QueryRule(Snapshot, [PathGlobs]),
+            QueryRule(PathMetadataResult, [PathMetadataRequest]),
+            QueryRule(GitMetadata, [GitMetadataRequest]),
+            QueryRule(DigestDiff, [DiffDigests]),
+            QueryRule(DigestFingerprint, [FingerprintDigest]),
+            QueryRule(StructuredData, [ParseStructuredFile]),
+            QueryRule(Digests, [AddPrefixBatch]),
+            QueryRule(Digests, [RemovePrefixBatch]),
+            QueryRule(Digests, [MergeDigestsBatch]),
         ],
         isolated_local_store=True,
     )
@@ -412,6 +445,35 @@ def test_path_globs_to_digest_contents(rule_runner: RuleRunner) -> None:
     assert not get_contents(["c.ln"])
 
 
+def test_digest_to_digest_content_views(rule_runner: RuleRunner) -> None:
+    content = b"content" * 1024
+    digest = rule_runner.request(
+        Digest,
+        [
+            CreateDigest(
+                [FileContent("a.txt", content), FileContent("b.sh", b"", is_executable=True)]
+            )
+        ],
+    )
+
+    views = rule_runner.request(DigestContentViews, [digest])
+    assert [(view.path, bytes(view.content), view.is_executable) for view in views] == [
+        ("a.txt", content, False),
+        ("b.sh", b"", True),
+    ]
+
+    view = views[0].content
+    assert view.readonly
+    assert view.nbytes == len(content)
+    assert view[:7].tobytes() == b"content"
+    with pytest.raises(TypeError):
+        view[0] = 0
+
+    # The content remains valid after the collection (and the buffer which owns it) are dropped.
+    del views
+    assert bytes(view) == content
+
+
 def test_path_globs_to_digest_entries(rule_runner: RuleRunner) -> None:
     setup_fs_test_tar(rule_runner)
 
@@ -456,6 +518,21 @@ def test_digest_entries_handles_empty_directory(rule_runner: RuleRunner) -> None
     )
 
 
+def test_file_digest_hasher(rule_runner: RuleRunner) -> None:
+    hasher = FileDigestHasher()
+    assert hasher.digest() == EMPTY_FILE_DIGEST
+    hasher.update(b"fo")
+    hasher.update(b"ur\n")
+    # The digest may be computed while content is still being added.
+    assert hasher.digest() == FileDigest(hashlib.sha256(b"four\n").hexdigest(), 5)
+    hasher.update(b"five\n")
+
+    digest = rule_runner.request(Digest, [CreateDigest([FileContent("foo.txt", b"four\nfive\n")])])
+    [entry] = rule_runner.request(DigestEntries, [digest])
+    assert isinstance(entry, FileEntry)
+    assert hasher.digest() == entry.file_digest
+
+
 def test_digest_entries_handles_symlinks(rule_runner: RuleRunner) -> None:
     digest = rule_runner.request(
         Digest,
@@ -964,6 +1041,40 @@ def test_remove_prefix(rule_runner: RuleRunner) -> None:
         ) in str(exc.value)
 
 
+def test_batched_digest_manipulation(rule_runner: RuleRunner) -> None:
+    digests = [
+        rule_runner.request(Digest, [CreateDigest([FileContent(f"{i}.txt", b"")])])
+        for i in range(3)
+    ]
+
+    prefixed = rule_runner.request(
+        Digests, [AddPrefixBatch(AddPrefix(digest, f"dir{i}") for i, digest in enumerate(digests))]
+    )
+    assert list(prefixed) == [
+        rule_runner.request(Digest, [AddPrefix(digest, f"dir{i}")])
+        for i, digest in enumerate(digests)
+    ]
+
+    stripped = rule_runner.request(
+        Digests,
+        [RemovePrefixBatch(RemovePrefix(digest, f"dir{i}") for i, digest in enumerate(prefixed))],
+    )
+    assert list(stripped) == digests
+
+    merged = rule_runner.request(
+        Digests, [MergeDigestsBatch([MergeDigests(prefixed), MergeDigests(digests[:2])])]
+    )
+    assert [rule_runner.request(Snapshot, [digest]).files for digest in merged] == [
+        ("dir0/0.txt", "dir1/1.txt", "dir2/2.txt"),
+        ("0.txt", "1.txt"),
+    ]
+
+    assert rule_runner.request(Digests, [AddPrefixBatch([])]) == Digests()
+
+    with pytest.raises(Exception, match=r"The `prefix` must be relative."):
+        rule_runner.request(Digests, [AddPrefixBatch([AddPrefix(digests[0], "../something")])])
+
+
 # -----------------------------------------------------------------------------------------------
 # `DownloadFile`
 # -----------------------------------------------------------------------------------------------
@@ -1085,6 +1196,110 @@ def test_download_wrong_digest(downloads_rule_runner: RuleRunner) -> None:
     assert "wrong digest" in str(exc.value).lower()
 
 
+@pytest.fixture
+def native_downloads_rule_runner() -> RuleRunner:
+    return RuleRunner(rules=[QueryRule(Digest, [NativeDownloadFile])], isolated_local_store=True)
+
+
+def test_download_mirror_fallback(native_downloads_rule_runner: RuleRunner) -> None:
+    with http_server(StubHandler) as port:
+        digest = native_downloads_rule_runner.request(
+            Digest,
+            [
+                NativeDownloadFile(
+                    f"http://localhost:{port}/missing/file.txt",
+                    DOWNLOADS_FILE_DIGEST,
+                    mirror_urls=[f"http://localhost:{port}/file.txt"],
+                )
+            ],
+        )
+    assert digest == DOWNLOADS_EXPECTED_DIRECTORY_DIGEST
+
+
+def test_download_no_retries(native_downloads_rule_runner: RuleRunner) -> None:
+    with http_server(stub_erroring_handler(1)) as port:
+        with pytest.raises(ExecutionError):
+            native_downloads_rule_runner.request(
+                Digest,
+                [
+                    NativeDownloadFile(
+                        f"http://localhost:{port}/file.txt",
+                        DOWNLOADS_FILE_DIGEST,
+                        retry_attempts=0,
+                    )
+                ],
+            )
+
+
+def test_download_checksums(native_downloads_rule_runner: RuleRunner) -> None:
+    sha512 = hashlib.sha512(StubHandler.response_text).hexdigest()
+    with http_server(StubHandler) as port:
+        digest = native_downloads_rule_runner.request(
+            Digest,
+            [
+                NativeDownloadFile(
+                    f"http://localhost:{port}/file.txt",
+                    DOWNLOADS_FILE_DIGEST,
+                    checksums={"sha512": sha512},
+                )
+            ],
+        )
+    assert digest == DOWNLOADS_EXPECTED_DIRECTORY_DIGEST
+
+    with pytest.raises(ExecutionError) as exc:
+        with http_server(StubHandler) as port:
+            native_downloads_rule_runner.request(
+                Digest,
+                [
+                    NativeDownloadFile(
+                        f"http://localhost:{port}/file.txt",
+                        DOWNLOADS_FILE_DIGEST,
+                        checksums={"sha512": "0" * 128},
+                    )
+                ],
+            )
+    assert "wrong sha512 checksum" in str(exc.value).lower()
+
+
+class StubAuthHandler(StubHandler):
+    def send_headers(self):
+        if self.headers.get("Authorization") != "Bearer s3cr3t":
+            self.send_response(401)
+            self.end_headers()
+            return
+        super().send_headers()
+
+    def do_GET(self):
+        self.send_headers()
+        if self.headers.get("Authorization") == "Bearer s3cr3t":
+            self.wfile.write(self.response_text)
+
+
+def test_download_auth_token_command(native_downloads_rule_runner: RuleRunner) -> None:
+    with http_server(StubAuthHandler) as port:
+        digest = native_downloads_rule_runner.request(
+            Digest,
+            [
+                NativeDownloadFile(
+                    f"http://localhost:{port}/file.txt",
+                    DOWNLOADS_FILE_DIGEST,
+                    auth_token_command=["echo", "s3cr3t"],
+                )
+            ],
+        )
+    assert digest == DOWNLOADS_EXPECTED_DIRECTORY_DIGEST
+
+
+def test_download_unauthorized(native_downloads_rule_runner: RuleRunner) -> None:
+    with pytest.raises(ExecutionError) as exc:
+        with http_server(StubAuthHandler) as port:
+            native_downloads_rule_runner.request(
+                Digest,
+                [NativeDownloadFile(f"http://localhost:{port}/file.txt", DOWNLOADS_FILE_DIGEST)],
+            )
+    assert "401" in str(exc.value)
+
+
 def test_download_file(downloads_rule_runner: RuleRunner) -> None:
     with temporary_dir() as temp_dir:
         roland = Path(temp_dir, "roland")
@@ -1293,6 +1508,306 @@ def test_workspace_in_goal_rule() -> None:
     assert Path(rule_runner.build_root, "a.txt").read_text() == "hello"
 
 
+# -----------------------------------------------------------------------------------------------
+# `DiffDigests`
+# -----------------------------------------------------------------------------------------------
+
+
+def test_diff_digests(rule_runner: RuleRunner) -> None:
+    before = rule_runner.make_snapshot(
+        {"unchanged.txt": "same\n", "modified.txt": "one\ntwo\n", "removed.txt": "gone\n"}
+    ).digest
+    after = rule_runner.make_snapshot(
+        {"unchanged.txt": "same\n", "modified.txt": "one\nthree\n", "added.txt": "new\n"}
+    ).digest
+
+    result = rule_runner.request(DigestDiff, [DiffDigests(before, after)])
+    assert result.changed_paths == ("added.txt", "modified.txt", "removed.txt")
+    assert result.text == dedent(
+        """\
+        --- /dev/null
+        +++ b/added.txt
+        @@ -0,0 +1 @@
+        +new
+        --- a/modified.txt
+        +++ b/modified.txt
+        @@ -1,2 +1,2 @@
+         one
+        -two
+        +three
+        --- a/removed.txt
+        +++ /dev/null
+        @@ -1 +0,0 @@
+        -gone
+        """
+    )
+
+    assert rule_runner.request(DigestDiff, [DiffDigests(before, before)]) == DigestDiff(b"", ())
+
+
+def test_diff_digests_files(rule_runner: RuleRunner) -> None:
+    before = rule_runner.make_snapshot({"a.txt": "one\n", "b.bin": b"\xff\n"}).digest
+    after = rule_runner.make_snapshot({"c.txt": "two\n", "d.bin": b"\xfe\n"}).digest
+
+    result = rule_runner.request(
+        DigestDiff, [DiffDigests(before, after, before_path="a.txt", after_path="c.txt")]
+    )
+    assert result.changed_paths == ("c.txt",)
+    assert result.text == "--- a/a.txt\n+++ b/c.txt\n@@ -1 +1 @@\n-one\n+two\n"
+
+    result = rule_runner.request(
+        DigestDiff, [DiffDigests(before, after, before_path="b.bin", after_path="d.bin")]
+    )
+    assert result.text == "Binary files a/b.bin and b/d.bin differ\n"
+
+    result = rule_runner.request(
+        DigestDiff,
+        [
+            DiffDigests(
+                before, after, before_path="b.bin", after_path="d.bin", mode=DiffMode.BYTES
+            )
+        ],
+    )
+    assert result.content == b"--- a/b.bin\n+++ b/d.bin\n@@ -1 +1 @@\n-\xff\n+\xfe\n"
+
+    with pytest.raises(ValueError, match="Both or neither"):
+        DiffDigests(before, after, before_path="a.txt")
+
+
+# -----------------------------------------------------------------------------------------------
+# `FingerprintDigest`
+# -----------------------------------------------------------------------------------------------
+
+
+def test_fingerprint_digest(rule_runner: RuleRunner) -> None:
+    def fingerprint(*entries: FileContent | Directory | SymlinkEntry, **kwargs: bool) -> str:
+        digest = rule_runner.request(Digest, [CreateDigest(entries)])
+        return rule_runner.request(
+            DigestFingerprint, [FingerprintDigest(digest, **kwargs)]
+        ).fingerprint
+
+    base = fingerprint(FileContent("a.txt", b"a"), Directory("empty"))
+    assert len(base) == 64
+    assert base == fingerprint(Directory("empty"), FileContent("a.txt", b"a"))
+    # Contents, paths, and empty directories are all significant.
+    assert base != fingerprint(FileContent("a.txt", b"b"), Directory("empty"))
+    assert base != fingerprint(FileContent("b.txt", b"a"), Directory("empty"))
+    assert base != fingerprint(FileContent("a.txt", b"a"))
+
+    plain = (FileContent("a.txt", b"a"), Directory("empty"))
+    executable = (FileContent("a.txt", b"a", is_executable=True), Directory("empty"))
+    assert fingerprint(*plain) != fingerprint(*executable)
+    assert fingerprint(*plain, include_permissions=False) == fingerprint(
+        *executable, include_permissions=False
+    )
+
+    with_symlink = (*plain, SymlinkEntry("link", "a.txt"))
+    assert fingerprint(*plain) != fingerprint(*with_symlink)
+    assert fingerprint(*plain, include_symlinks=False) == fingerprint(
+        *with_symlink, include_symlinks=False
+    )
+
+
+# -----------------------------------------------------------------------------------------------
+# `ParseStructuredFile`
+# -----------------------------------------------------------------------------------------------
+
+
+def test_parse_structured_file(rule_runner: RuleRunner) -> None:
+    digest = rule_runner.make_snapshot(
+        {
+            "package-lock.json": '{"packages": {"node_modules/a": {"version": "1.0", "dev": true}}}',
+            "Cargo.lock": '[[package]]\nname = "a"\nversion = "1.0"\n',
+            "pnpm-lock.yaml": "packages:\n  /a@1.0:\n    resolution: {integrity: sha512-x}\n",
+        }
+    ).digest
+
+    def parse(path: str, format: StructuredFileFormat | None = None) -> StructuredData:
+        return rule_runner.request(StructuredData, [ParseStructuredFile(digest, path, format)])
+
+    json_data = parse("package-lock.json")
+    assert json_data.format == "json"
+    assert json_data.keys("/packages") == ["node_modules/a"]
+    assert json_data.get("/packages/node_modules~1a") == {"version": "1.0", "dev": True}
+    assert json_data.get("/packages/node_modules~1a/dev") is True
+    assert json_data.get("/missing", "default") == "default"
+    assert json_data.length("/packages") == 1
+    assert "/packages" in json_data
+    assert "/missing" not in json_data
+
+    toml_data = parse("Cargo.lock", StructuredFileFormat.TOML)
+    assert toml_data.get() == {"package": [{"name": "a", "version": "1.0"}]}
+    assert toml_data.get("/package/0/name") == "a"
+    assert toml_data.keys("/package") is None
+
+    yaml_data = parse("pnpm-lock.yaml")
+    assert yaml_data.get("/packages/~1a@1.0/resolution/integrity") == "sha512-x"
+
+    # Parsing the same content again is equal, and so is memoized.
+    assert parse("package-lock.json") == json_data
+    assert hash(parse("package-lock.json")) == hash(json_data)
+
+    with pytest.raises(ExecutionError, match="Could not infer the format of Cargo.lock"):
+        parse("Cargo.lock")
+    with pytest.raises(ExecutionError, match="Failed to parse pnpm-lock.yaml as json"):
+        parse("pnpm-lock.yaml", StructuredFileFormat.JSON)
+    with pytest.raises(ExecutionError, match="missing.json does not exist"):
+        parse("missing.json")
+
+
+# -----------------------------------------------------------------------------------------------
+# `PathMetadataRequest`
+# -----------------------------------------------------------------------------------------------
+
+
+def test_path_metadata_workspace(rule_runner: RuleRunner) -> None:
+    rule_runner.write_files({"tool": "#!/bin/sh", "dir/data.txt": "data"})
+    Path(rule_runner.build_root, "tool").chmod(0o755)
+    Path(rule_runner.build_root, "link").symlink_to("tool")
+
+    result = rule_runner.request(
+        PathMetadataResult, [PathMetadataRequest(["tool", "dir", "link", "missing/file"])]
+    )
+    tool, directory, link, missing = result.metadata
+
+    assert tool is not None
+    assert tool.path == "tool"
+    assert tool.kind == PathMetadataKind.FILE
+    assert tool.length == len("#!/bin/sh")
+    assert tool.is_executable
+    assert tool.unix_mode is not None and tool.unix_mode & 0o777 == 0o755
+    assert tool.uid == os.getuid()
+    assert tool.mtime_ns is not None
+
+    assert directory is not None
+    assert directory.kind == PathMetadataKind.DIRECTORY
+
+    assert link is not None
+    assert link.kind == PathMetadataKind.SYMLINK
+    assert link.symlink_target == "tool"
+
+    assert missing is None
+
+
+def test_path_metadata_system(rule_runner: RuleRunner) -> None:
+    system_path = os.path.join(rule_runner.build_root, "tool")
+    Path(system_path).write_text("#!/bin/sh")
+    result = rule_runner.request(
+        PathMetadataResult,
+        [PathMetadataRequest([system_path, "/does/not/exist"], PathNamespace.SYSTEM)],
+    )
+    metadata, missing = result.metadata
+    assert metadata is not None
+    assert metadata.path == system_path
+    assert metadata.kind == PathMetadataKind.FILE
+    assert missing is None
+
+    with pytest.raises(ExecutionError, match="System paths must be absolute"):
+        rule_runner.request(
+            PathMetadataResult, [PathMetadataRequest(["relative"], PathNamespace.SYSTEM)]
+        )
+
+
+def test_path_metadata_digest(rule_runner: RuleRunner) -> None:
+    digest = rule_runner.request(
+        Digest,
+        [
+            CreateDigest(
+                [
+                    FileContent("dir/tool", b"#!/bin/sh", is_executable=True),
+                    SymlinkEntry("link", "dir/tool"),
+                ]
+            )
+        ],
+    )
+    result = rule_runner.request(
+        PathMetadataResult,
+        [
+            PathMetadataRequest(
+                ["dir/tool", "dir", "link", "missing"], PathNamespace.DIGEST, digest=digest
+            )
+        ],
+    )
+    tool, directory, link, missing = result.metadata
+    assert tool is not None
+    assert tool.kind == PathMetadataKind.FILE
+    assert tool.length == len("#!/bin/sh")
+    assert tool.is_executable
+    assert tool.unix_mode is None and tool.mtime_ns is None
+    assert directory is not None and directory.kind == PathMetadataKind.DIRECTORY
+    assert link is not None and link.symlink_target == "dir/tool"
+    assert missing is None
+
+    with pytest.raises(ValueError, match="A `digest` must be provided"):
+        PathMetadataRequest(["dir/tool"], PathNamespace.DIGEST)
+
+
+# -----------------------------------------------------------------------------------------------
+# `GitMetadataRequest`
+# -----------------------------------------------------------------------------------------------
+
+
+def git(rule_runner: RuleRunner, *args: str) -> str:
+    return subprocess.check_output(
+        ["git", "-c", "user.email=you@example.com", "-c", "user.name=Your Name", *args],
+        cwd=rule_runner.build_root,
+        text=True,
+    ).strip()
+
+
+def test_git_metadata_outside_of_repository(rule_runner: RuleRunner) -> None:
+    assert rule_runner.request(GitMetadata, [GitMetadataRequest()]) == GitMetadata(
+        commit_id=None, branch_name=None, describe=None, dirty_paths=()
+    )
+
+
+def test_git_metadata(rule_runner: RuleRunner) -> None:
+    git(rule_runner, "init")
+    git(rule_runner, "symbolic-ref", "HEAD", "refs/heads/main")
+    rule_runner.write_files({"src/a.txt": "a", "b.txt": "b"})
+    git(rule_runner, "add", ".")
+    git(rule_runner, "commit", "-m", "Initial commit.")
+    git(rule_runner, "tag", "v1.0.0")
+
+    def git_metadata() -> GitMetadata:
+        return rule_runner.request(GitMetadata, [GitMetadataRequest(include_dirty_paths=True)])
+
+    metadata = git_metadata()
+    assert metadata.commit_id == git(rule_runner, "rev-parse", "HEAD")
+    assert metadata.branch_name == "main"
+    assert metadata.describe == "v1.0.0"
+    assert not metadata.is_dirty
+
+    # Modifying a tracked file or creating an untracked file dirties the worktree.
+    rule_runner.write_files({"src/a.txt": "modified", "src/c.txt": "c"})
+    assert try_with_backoff(lambda: git_metadata().dirty_paths == ("src/a.txt", "src/c.txt"))
+
+    # Committing moves HEAD.
+    git(rule_runner, "add", ".")
+    git(rule_runner, "commit", "-m", "Second commit.")
+    commit_id = git(rule_runner, "rev-parse", "HEAD")
+    assert try_with_backoff(lambda: git_metadata().commit_id == commit_id)
+    metadata = git_metadata()
+    assert metadata.describe == f"v1.0.0-1-g{git(rule_runner, 'rev-parse', '--short', 'HEAD')}"
+    assert not metadata.is_dirty
+
+    # As does checking out another branch.
+    git(rule_runner, "checkout", "-b", "feature")
+    assert try_with_backoff(lambda: git_metadata().branch_name == "feature")
+
+
+def test_git_metadata_without_dirty_paths(rule_runner: RuleRunner) -> None:
+    git(rule_runner, "init")
+    rule_runner.write_files({"a.txt": "a"})
+    git(rule_runner, "add", ".")
+    git(rule_runner, "commit", "-m", "Initial commit.")
+    rule_runner.write_files({"a.txt": "modified"})
+
+    metadata = rule_runner.request(GitMetadata, [GitMetadataRequest()])
+    assert metadata.commit_id == git(rule_runner, "rev-parse", "HEAD")
+    assert metadata.dirty_paths == ()
+
+
 # -----------------------------------------------------------------------------------------------
 # Invalidation of the FS
 # -----------------------------------------------------------------------------------------------
@@ -1371,6 +1886,18 @@ def test_invalidated_after_new_child(rule_runner: RuleRunner) -> None:
     assert try_with_backoff(is_changed_snapshot)
 
 
+def test_path_metadata_invalidated_after_creation(rule_runner: RuleRunner) -> None:
+    def path_exists() -> bool:
+        result = rule_runner.request(PathMetadataResult, [PathMetadataRequest(["a/b/c.txt"])])
+        return result.metadata[0] is not None
+
+    # Neither the file nor its parent directories exist yet.
+    assert not path_exists()
+
+    rule_runner.write_files({"a/b/c.txt": "c"})
+    assert try_with_backoff(path_exists)
+
+
 # -----------------------------------------------------------------------------------------------
 # Native types
 # -----------------------------------------------------------------------------------------------
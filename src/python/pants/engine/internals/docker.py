@@ -1,6 +1,8 @@
 # Copyright 2022 Pants project contributors (see CONTRIBUTORS.md).
 # Licensed under the Apache License, Version 2.0 (see LICENSE).
-from dataclasses import dataclass
+from __future__ import annotations
+
+from dataclasses import dataclass, field
 
 
 @dataclass(frozen=True)
@@ -12,3 +14,28 @@ class DockerResolveImageRequest:
 @dataclass(frozen=True)
 class DockerResolveImageResult:
     image_id: str
+
+
+@dataclass(frozen=True)
+class ResolveImageDigestRequest:
+    """Resolve an OCI image reference (`[registry/]repository[:tag]`) to its manifest digest.
+
+    The registry HTTP API is used directly (rather than a docker daemon), and the resolved digest is
+    cached locally for `cache_ttl_seconds` (or not at all if it is 0), since tags are mutable.
+
+    If no `username` and `password` are set, credentials are loaded from the `auths` of the docker
+    `config.json`, if any. Credentials are not part of the cache key.
+    """
+
+    image_ref: str
+    username: str | None = None
+    password: str | None = field(default=None, repr=False)
+    cache_ttl_seconds: int = 300
+
+
+@dataclass(frozen=True)
+class ResolveImageDigestResult:
+    # The digest of the manifest (or image index), e.g. `sha256:...`.
+    digest: str
+    # The fully qualified reference, pinned to the digest rather than a tag.
+    pinned_image_ref: str
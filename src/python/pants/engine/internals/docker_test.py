@@ -2,18 +2,30 @@
 # Licensed under the Apache License, Version 2.0 (see LICENSE).
 import json
 import subprocess
+from http.server import BaseHTTPRequestHandler
 
 import pytest
 
-from pants.engine.internals.docker import DockerResolveImageRequest, DockerResolveImageResult
+from pants.engine.internals.docker import (
+    DockerResolveImageRequest,
+    DockerResolveImageResult,
+    ResolveImageDigestRequest,
+    ResolveImageDigestResult,
+)
 from pants.engine.platform import Platform
 from pants.engine.rules import QueryRule
 from pants.testutil.rule_runner import RuleRunner
+from pants.util.contextutil import http_server
 
 
 @pytest.fixture
 def rule_runner() -> RuleRunner:
-    return RuleRunner(rules=[QueryRule(DockerResolveImageResult, (DockerResolveImageRequest,))])
+    return RuleRunner(
+        rules=[
+            QueryRule(DockerResolveImageResult, (DockerResolveImageRequest,)),
+            QueryRule(ResolveImageDigestResult, (ResolveImageDigestRequest,)),
+        ]
+    )
 
 
 def test_resolve_image_id(rule_runner: RuleRunner) -> None:
@@ -27,3 +39,70 @@ def test_resolve_image_id(rule_runner: RuleRunner) -> None:
     expected_image_id = json.loads(inspect_output)[0]["Id"]
 
     assert image_result.image_id == expected_image_id
+
+
+def stub_registry_handler() -> type[BaseHTTPRequestHandler]:
+    """Return a handler for a registry which serves a single manifest, and counts its requests."""
+
+    class StubRegistryHandler(BaseHTTPRequestHandler):
+        digest = "sha256:" + "a" * 64
+        requests = 0
+
+        def do_HEAD(self):
+            StubRegistryHandler.requests += 1
+            if self.path == "/v2/team/app/manifests/1.0":
+                self.send_response(200)
+                self.send_header("Docker-Content-Digest", self.digest)
+            else:
+                self.send_response(404)
+            self.send_header("Content-Length", "0")
+            self.end_headers()
+
+    return StubRegistryHandler
+
+
+def test_resolve_image_digest(rule_runner: RuleRunner) -> None:
+    handler = stub_registry_handler()
+    with http_server(handler) as port:
+
+        def resolve(cache_ttl_seconds: int) -> ResolveImageDigestResult:
+            rule_runner.new_session(f"resolve-{handler.requests}-{cache_ttl_seconds}")
+            return rule_runner.request(
+                ResolveImageDigestResult,
+                [
+                    ResolveImageDigestRequest(
+                        f"localhost:{port}/team/app:1.0",
+                        username="user",
+                        password="pass",
+                        cache_ttl_seconds=cache_ttl_seconds,
+                    )
+                ],
+            )
+
+        result = resolve(300)
+        assert result.digest == handler.digest
+        assert result.pinned_image_ref == f"localhost:{port}/team/app@{handler.digest}"
+        assert handler.requests == 1
+
+        # The digest is cached until the TTL expires, even though the tag has moved.
+        old_digest = handler.digest
+        handler.digest = "sha256:" + "b" * 64
+        assert resolve(300).digest == old_digest
+        assert handler.requests == 1
+
+        # But not if caching is disabled.
+        assert resolve(0).digest == handler.digest
+        assert handler.requests == 2
+
+
+def test_resolve_image_digest_missing(rule_runner: RuleRunner) -> None:
+    with http_server(stub_registry_handler()) as port:
+        with pytest.raises(Exception, match="does not exist"):
+            rule_runner.request(
+                ResolveImageDigestResult,
+                [
+                    ResolveImageDigestRequest(
+                        f"localhost:{port}/team/missing:1.0", username="user", password="pass"
+                    )
+                ],
+            )
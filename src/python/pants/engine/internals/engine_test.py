@@ -22,6 +22,7 @@ from pants.engine.fs import (
     Digest,
     DigestContents,
     FileContent,
+    FileDigest,
     MergeDigests,
     Snapshot,
 )
@@ -602,6 +603,39 @@ class TestStreamingWorkunit(SchedulerTestBase):
         artifacts = workunit["artifacts"]
         assert artifacts["some_arbitrary_key"] == EMPTY_SNAPSHOT
 
+    def test_bytes_and_digest_artifacts_on_engine_aware_type(self, tmp_path: Path) -> None:
+        @dataclass(frozen=True)
+        class Output(EngineAwareReturnType):
+            val: int
+
+            def artifacts(self):
+                return {"report": b"<testsuite/>", "digest": EMPTY_SNAPSHOT.digest}
+
+        @rule(desc="a_rule")
+        def a_rule(n: int) -> Output:
+            return Output(val=n)
+
+        scheduler, tracker, handler = self._fixture_for_rules(
+            tmp_path, [a_rule, QueryRule(Output, (int,))], max_workunit_verbosity=LogLevel.TRACE
+        )
+        with handler:
+            scheduler.product_request(Output, subjects=[0])
+
+        finished = list(itertools.chain.from_iterable(tracker.finished_workunit_chunks))
+        workunit = next(
+            item
+            for item in finished
+            if item["name"]
+            == "pants.engine.internals.engine_test.TestStreamingWorkunit.test_bytes_and_digest_artifacts_on_engine_aware_type.a_rule"
+        )
+        artifacts = workunit["artifacts"]
+        # Bytes are persisted to the store, and may then be loaded by their digest.
+        assert isinstance(artifacts["report"], FileDigest)
+        assert handler.context.single_file_digests_to_bytes([artifacts["report"]]) == [
+            b"<testsuite/>"
+        ]
+        assert artifacts["digest"] == EMPTY_SNAPSHOT
+
     def test_metadata_on_engine_aware_type(self, tmp_path: Path) -> None:
         @dataclass(frozen=True)
         class Output(EngineAwareReturnType):
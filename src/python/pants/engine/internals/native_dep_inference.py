@@ -26,3 +26,165 @@ class NativeParsedJavascriptDependencies:
     def __init__(self, file_imports: set[str], package_imports: set[str]):
         object.__setattr__(self, "file_imports", file_imports)
         object.__setattr__(self, "package_imports", package_imports)
+
+
+@dataclass(frozen=True)
+class NativeParsedJavaDependencies:
+    declared_package: str | None
+    imports: tuple[tuple[str, bool, bool], ...]
+    top_level_types: tuple[str, ...]
+    consumed_types: tuple[str, ...]
+    export_types: tuple[str, ...]
+
+    def __init__(
+        self,
+        declared_package: str | None,
+        imports: list[tuple[str, bool, bool]],
+        top_level_types: list[str],
+        consumed_types: list[str],
+        export_types: list[str],
+    ):
+        object.__setattr__(self, "declared_package", declared_package)
+        object.__setattr__(self, "imports", tuple(imports))
+        object.__setattr__(self, "top_level_types", tuple(top_level_types))
+        object.__setattr__(self, "consumed_types", tuple(consumed_types))
+        object.__setattr__(self, "export_types", tuple(export_types))
+
+
+@dataclass(frozen=True)
+class NativeParsedScalaDependencies:
+    provided_symbols: tuple[tuple[str, bool], ...]
+    provided_symbols_encoded: tuple[tuple[str, bool], ...]
+    imports_by_scope: FrozenDict[str, tuple[tuple[str, str | None, bool], ...]]
+    consumed_symbols_by_scope: FrozenDict[str, tuple[tuple[str, bool], ...]]
+    scopes: tuple[str, ...]
+
+    def __init__(
+        self,
+        provided_symbols: list[tuple[str, bool]],
+        provided_symbols_encoded: list[tuple[str, bool]],
+        imports_by_scope: dict[str, list[tuple[str, str | None, bool]]],
+        consumed_symbols_by_scope: dict[str, list[tuple[str, bool]]],
+        scopes: list[str],
+    ):
+        object.__setattr__(self, "provided_symbols", tuple(provided_symbols))
+        object.__setattr__(self, "provided_symbols_encoded", tuple(provided_symbols_encoded))
+        object.__setattr__(
+            self,
+            "imports_by_scope",
+            FrozenDict((scope, tuple(imports)) for scope, imports in imports_by_scope.items()),
+        )
+        object.__setattr__(
+            self,
+            "consumed_symbols_by_scope",
+            FrozenDict(
+                (scope, tuple(symbols)) for scope, symbols in consumed_symbols_by_scope.items()
+            ),
+        )
+        object.__setattr__(self, "scopes", tuple(scopes))
+
+
+@dataclass(frozen=True)
+class NativeParsedKotlinDependencies:
+    package: str
+    imports: tuple[tuple[str, str | None, bool], ...]
+    named_declarations: frozenset[str]
+    consumed_symbols_by_scope: FrozenDict[str, frozenset[str]]
+    scopes: frozenset[str]
+
+    def __init__(
+        self,
+        package: str,
+        imports: list[tuple[str, str | None, bool]],
+        named_declarations: list[str],
+        consumed_symbols_by_scope: dict[str, list[str]],
+        scopes: list[str],
+    ):
+        object.__setattr__(self, "package", package)
+        object.__setattr__(self, "imports", tuple(imports))
+        object.__setattr__(self, "named_declarations", frozenset(named_declarations))
+        object.__setattr__(
+            self,
+            "consumed_symbols_by_scope",
+            FrozenDict(
+                (scope, frozenset(symbols)) for scope, symbols in consumed_symbols_by_scope.items()
+            ),
+        )
+        object.__setattr__(self, "scopes", frozenset(scopes))
+
+
+@dataclass(frozen=True)
+class NativeParsedGoDependencies:
+    package: str
+    imports: tuple[str, ...]
+    build_constraint: str | None
+    embed_patterns: tuple[str, ...]
+
+    def __init__(
+        self,
+        package: str,
+        imports: list[str],
+        build_constraint: str | None,
+        embed_patterns: list[str],
+    ):
+        object.__setattr__(self, "package", package)
+        object.__setattr__(self, "imports", tuple(imports))
+        object.__setattr__(self, "build_constraint", build_constraint)
+        object.__setattr__(self, "embed_patterns", tuple(embed_patterns))
+
+
+@dataclass(frozen=True)
+class NativeParsedDotnetDependencies:
+    namespaces: tuple[str, ...]
+    usings: tuple[str, ...]
+    project_references: tuple[str, ...]
+    package_references: FrozenDict[str, str | None]
+
+    def __init__(
+        self,
+        namespaces: list[str],
+        usings: list[str],
+        project_references: list[str],
+        package_references: list[tuple[str, str | None]],
+    ):
+        object.__setattr__(self, "namespaces", tuple(namespaces))
+        object.__setattr__(self, "usings", tuple(usings))
+        object.__setattr__(self, "project_references", tuple(project_references))
+        object.__setattr__(self, "package_references", FrozenDict(package_references))
+
+
+@dataclass(frozen=True)
+class NativeParsedRustDependencies:
+    uses: tuple[str, ...]
+    # The (qualified) names of `mod` declarations, mapped to the value of their `#[path]` attribute.
+    mods: FrozenDict[str, str | None]
+    includes: tuple[str, ...]
+
+    def __init__(
+        self,
+        uses: list[str],
+        mods: list[tuple[str, str | None]],
+        includes: list[str],
+    ):
+        object.__setattr__(self, "uses", tuple(uses))
+        object.__setattr__(self, "mods", FrozenDict(mods))
+        object.__setattr__(self, "includes", tuple(includes))
+
+
+@dataclass(frozen=True)
+class NativeParsedTerraformDependencies:
+    # The names of `module` blocks, mapped to their source and version.
+    modules: FrozenDict[str, tuple[str, str | None]]
+    backend: str | None
+    # The names of the `required_providers`, mapped to their source and version constraint.
+    providers: FrozenDict[str, tuple[str | None, str | None]]
+
+    def __init__(
+        self,
+        modules: list[tuple[str, tuple[str, str | None]]],
+        backend: str | None,
+        providers: list[tuple[str, tuple[str | None, str | None]]],
+    ):
+        object.__setattr__(self, "modules", FrozenDict(modules))
+        object.__setattr__(self, "backend", backend)
+        object.__setattr__(self, "providers", FrozenDict(providers))
@@ -3,9 +3,11 @@
 
 from __future__ import annotations
 
+import concurrent.futures
 from io import RawIOBase
 from typing import (
     Any,
+    Awaitable,
     Callable,
     ClassVar,
     FrozenSet,
@@ -24,18 +26,46 @@ from typing import (
 from typing_extensions import Self
 
 from pants.engine.fs import (
+    AddPrefixBatch,
     CreateDigest,
+    DiffDigests,
     DigestContents,
+    DigestContentViews,
+    DigestDiff,
     DigestEntries,
+    DigestFingerprint,
+    Digests,
     DigestSubset,
+    FingerprintDigest,
+    GitMetadata,
+    GitMetadataRequest,
+    MergeDigestsBatch,
+    NativeCreateArchive,
     NativeDownloadFile,
+    NativeExtractArchive,
+    ParseStructuredFile,
     PathGlobs,
+    PathMetadataRequest,
+    PathMetadataResult,
     Paths,
+    RemovePrefixBatch,
+)
+from pants.engine.internals.docker import (
+    DockerResolveImageRequest,
+    DockerResolveImageResult,
+    ResolveImageDigestRequest,
+    ResolveImageDigestResult,
 )
-from pants.engine.internals.docker import DockerResolveImageRequest, DockerResolveImageResult
 from pants.engine.internals.native_dep_inference import (
+    NativeParsedDotnetDependencies,
+    NativeParsedGoDependencies,
+    NativeParsedJavaDependencies,
     NativeParsedJavascriptDependencies,
+    NativeParsedKotlinDependencies,
     NativeParsedPythonDependencies,
+    NativeParsedRustDependencies,
+    NativeParsedScalaDependencies,
+    NativeParsedTerraformDependencies,
 )
 from pants.engine.internals.scheduler import Workunit, _PathGlobsAndRootCollection
 from pants.engine.internals.session import RunId, SessionValues
@@ -268,7 +298,9 @@ class Address:
 # ------------------------------------------------------------------------------
 
 class PyExecutor:
-    def __init__(self, core_threads: int, max_threads: int) -> None: ...
+    def __init__(
+        self, core_threads: int, max_threads: int, io_threads: int | None = None
+    ) -> None: ...
     def to_borrowed(self) -> PyExecutor: ...
     def shutdown(self, duration_secs: float) -> None: ...
 
@@ -401,6 +433,51 @@ class FileDigest:
     def __hash__(self) -> int: ...
     def __repr__(self) -> str: ...
 
+class FileDigestHasher:
+    """Incrementally computes the FileDigest of content, without it needing to be in memory at once.
+
+    This computes the same FileDigest as the engine would for a file with the same content, but it
+    does not store the content: use `CreateDigest` (or `Workspace`) for that.
+    """
+
+    def __init__(self) -> None: ...
+    def update(self, data: bytes) -> None:
+        """Adds the data to the content which is being hashed."""
+    def digest(self) -> FileDigest:
+        """The FileDigest of the content which has been added so far."""
+
+class ContentBuffer:
+    """Read-only file content which is owned by the engine, and exposed via the buffer protocol.
+
+    This is not constructed directly: see `FileContentView`, which holds a `memoryview` of it.
+    """
+
+    def __len__(self) -> int: ...
+    def __bytes__(self) -> bytes: ...
+    def __repr__(self) -> str: ...
+
+class StructuredData:
+    """A parsed JSON, TOML or YAML file, which is queried by JSON pointer (RFC 6901).
+
+    The document is held natively, and only the queried portions of it are converted into Python
+    structures. TOML datetimes are represented as strings.
+    """
+
+    @property
+    def path(self) -> str: ...
+    @property
+    def format(self) -> str: ...
+    def get(self, pointer: str = "", default: Any = None) -> Any:
+        """The value at the pointer (or the entire document by default), or `default`."""
+    def keys(self, pointer: str = "") -> list[str] | None:
+        """The keys of the mapping at the pointer, or None if there is no mapping there."""
+    def length(self, pointer: str = "") -> int | None:
+        """The length of the mapping or sequence at the pointer, or None if there is neither."""
+    def __contains__(self, pointer: str) -> bool: ...
+    def __eq__(self, other: StructuredData | Any) -> bool: ...
+    def __hash__(self) -> int: ...
+    def __repr__(self) -> str: ...
+
 class Snapshot:
     """A Snapshot is a collection of sorted file paths and dir paths fingerprinted by their
     names/content.
@@ -508,34 +585,76 @@ async def path_globs_to_paths(
 async def download_file_to_digest(
     native_download_file: NativeDownloadFile,
 ) -> Digest: ...
+async def create_archive_to_digest(
+    native_create_archive: NativeCreateArchive,
+) -> Digest: ...
+async def extract_archive_to_digest(
+    native_extract_archive: NativeExtractArchive,
+) -> Digest: ...
+async def path_metadata_request(request: PathMetadataRequest) -> PathMetadataResult: ...
+async def git_metadata_request(request: GitMetadataRequest) -> GitMetadata: ...
 async def digest_to_snapshot(digest: Digest) -> Snapshot: ...
 async def directory_digest_to_digest_contents(digest: Digest) -> DigestContents: ...
+async def directory_digest_to_digest_content_views(digest: Digest) -> DigestContentViews: ...
 async def directory_digest_to_digest_entries(digest: Digest) -> DigestEntries: ...
 async def merge_digests_request_to_digest(merge_digests: MergeDigests) -> Digest: ...
+async def merge_digests_batch_to_digests(merge_digests_batch: MergeDigestsBatch) -> Digests: ...
 async def remove_prefix_request_to_digest(remove_prefix: RemovePrefix) -> Digest: ...
+async def remove_prefix_batch_to_digests(remove_prefix_batch: RemovePrefixBatch) -> Digests: ...
 async def add_prefix_request_to_digest(add_prefix: AddPrefix) -> Digest: ...
+async def add_prefix_batch_to_digests(add_prefix_batch: AddPrefixBatch) -> Digests: ...
 async def process_request_to_process_result(
     process: Process, process_execution_environment: ProcessExecutionEnvironment
 ) -> FallibleProcessResult: ...
 async def digest_subset_to_digest(digest_subset: DigestSubset) -> Digest: ...
+async def diff_digests_to_digest_diff(diff_digests: DiffDigests) -> DigestDiff: ...
+async def fingerprint_digest_to_digest_fingerprint(
+    fingerprint_digest: FingerprintDigest,
+) -> DigestFingerprint: ...
+async def parse_structured_file(parse_structured_file: ParseStructuredFile) -> StructuredData: ...
 async def session_values() -> SessionValues: ...
 async def run_id() -> RunId: ...
 async def interactive_process(
     process: InteractiveProcess, process_execution_environment: ProcessExecutionEnvironment
 ) -> InteractiveProcessResult: ...
 async def docker_resolve_image(request: DockerResolveImageRequest) -> DockerResolveImageResult: ...
+async def resolve_image_digest(request: ResolveImageDigestRequest) -> ResolveImageDigestResult: ...
 async def parse_python_deps(
     deps_request: NativeDependenciesRequest,
 ) -> NativeParsedPythonDependencies: ...
 async def parse_javascript_deps(
     deps_request: NativeDependenciesRequest,
 ) -> NativeParsedJavascriptDependencies: ...
+async def parse_java_deps(
+    deps_request: NativeDependenciesRequest,
+) -> NativeParsedJavaDependencies: ...
+async def parse_scala_deps(
+    deps_request: NativeDependenciesRequest,
+) -> NativeParsedScalaDependencies: ...
+async def parse_kotlin_deps(
+    deps_request: NativeDependenciesRequest,
+) -> NativeParsedKotlinDependencies: ...
+async def parse_go_deps(
+    deps_request: NativeDependenciesRequest,
+) -> NativeParsedGoDependencies: ...
+async def parse_dotnet_deps(
+    deps_request: NativeDependenciesRequest,
+) -> NativeParsedDotnetDependencies: ...
+async def parse_rust_deps(
+    deps_request: NativeDependenciesRequest,
+) -> NativeParsedRustDependencies: ...
+async def parse_terraform_deps(
+    deps_request: NativeDependenciesRequest,
+) -> NativeParsedTerraformDependencies: ...
 
 # ------------------------------------------------------------------------------
 # `pantsd`
 # ------------------------------------------------------------------------------
 
 def pantsd_fingerprint_compute(expected_option_names: set[str]) -> str: ...
+def pantsd_tokio_console_init() -> None: ...
+def pantsd_heap_profiling_activate() -> None: ...
+def pantsd_heap_profile_dump(path: str) -> None: ...
 
 # ------------------------------------------------------------------------------
 # Process
@@ -579,6 +698,27 @@ class ProcessExecutionEnvironment:
 # ------------------------------------------------------------------------------
 
 def all_counter_names() -> list[str]: ...
+def increment_custom_counter(name: str, change: int, display: bool) -> None: ...
+def record_custom_observation(name: str, value: int) -> None: ...
+
+# ------------------------------------------------------------------------------
+# Build Event Service
+# ------------------------------------------------------------------------------
+
+class PyBuildEventStream:
+    def __init__(
+        self,
+        executor: PyExecutor,
+        address: str,
+        headers: dict[str, str],
+        build_id: str,
+        invocation_id: str,
+        project_id: str | None,
+        timeout_secs: int,
+    ) -> None: ...
+    def start(self) -> None: ...
+    def publish(self, event: bytes) -> None: ...
+    def finish(self, exit_code: int) -> None: ...
 
 # ------------------------------------------------------------------------------
 # Nailgun
@@ -636,6 +776,33 @@ class PyOptionParser:
     ) -> OptionListValue[str]: ...
     def get_dict(self, option_id: PyOptionId, default: dict[str, Any]) -> OptionDictValue: ...
     def get_passthrough_args(self) -> Optional[list[str]]: ...
+    def with_directory_config(self, configs: Sequence[PyConfigSource]) -> PyOptionParser: ...
+
+def generate_completion_script(
+    shell: str,
+    scopes: Sequence[tuple[str, bool, str, Sequence[tuple[PyOptionId, bool, str, Sequence[str]]]]],
+) -> str: ...
+def generate_config_schema(
+    scopes: Sequence[
+        tuple[
+            str,
+            str,
+            str | None,
+            Sequence[tuple[PyOptionId, str, str | None, str, str, Sequence[str], str | None]],
+        ]
+    ],
+) -> str: ...
+def read_dotenv_file(path: str) -> dict[str, str]:
+    """Reads the variables set by the given `.env` file, or none if it does not exist."""
+def did_you_mean(name: str, candidates: Sequence[str]) -> list[str]:
+    """Returns the candidates that are likely to have been intended instead of `name`."""
+def directory_config_paths(directory: str) -> list[str]:
+    """Returns the paths of the config files which may override options in the given directory."""
+def resolve_option_secret(build_root: str, value: str, name: str) -> str:
+    """Resolves a `@cmd(...)` or `@file(...)` secret reference for the named option.
+
+    The command of a `@cmd(...)` reference is run at most once per process.
+    """
 
 # ------------------------------------------------------------------------------
 # Testutil
@@ -661,8 +828,18 @@ class PyStubCAS:
 class InferenceMetadata:
     @staticmethod
     def javascript(
-        package_root: str, import_patterns: dict[str, list[str]]
-    ) -> InferenceMetadata: ...
+        package_root: str,
+        import_patterns: dict[str, list[str]],
+        config_root: str | None = None,
+        config_paths: dict[str, list[str]] | None = None,
+        config_has_base_url: bool = False,
+    ) -> InferenceMetadata:
+        """Metadata for the inference of Javascript dependencies.
+
+        The `import_patterns` are the subpath imports of a `package.json`, and the `config_paths`
+        are the `paths` of a `tsconfig.json` or `jsconfig.json`, which are resolved relative to the
+        `config_root` (the `baseUrl`, if the config has one).
+        """
     def __eq__(self, other: InferenceMetadata | Any) -> bool: ...
     def __hash__(self) -> int: ...
     def __repr__(self) -> str: ...
@@ -685,6 +862,35 @@ class NativeDependenciesRequest:
     def __hash__(self) -> int: ...
     def __repr__(self) -> str: ...
 
+class PythonRequirement:
+    """A PEP 508 requirement, as parsed by `parse_python_requirement`."""
+
+    @property
+    def name(self) -> str: ...
+    @property
+    def extras(self) -> list[str]: ...
+    @property
+    def specifiers(self) -> list[tuple[str, str]]: ...
+    @property
+    def url(self) -> str | None: ...
+    @property
+    def marker(self) -> str | None: ...
+    def __eq__(self, other: PythonRequirement | Any) -> bool: ...
+    def __str__(self) -> str: ...
+    def __repr__(self) -> str: ...
+
+def parse_python_requirement(requirement: str) -> PythonRequirement:
+    """Parses a PEP 508 requirement string, raising a `ValueError` if it is invalid."""
+
+def parse_python_requirements_file(
+    contents: str, rel_path: str
+) -> list[tuple[int, PythonRequirement]]:
+    """Parses a requirements.txt-style file into its requirements and their line numbers.
+
+    Comments and pip options are ignored, and pip-style VCS requirements with an `#egg=` fragment
+    are converted to PEP 508 requirements. Raises a `ValueError` for invalid requirements.
+    """
+
 # ------------------------------------------------------------------------------
 # (etc.)
 # ------------------------------------------------------------------------------
@@ -729,10 +935,18 @@ def stdio_initialize(
     level: int,
     show_rust_3rdparty_logs: bool,
     show_target: bool,
+    log_format: str,
     log_levels_by_target: dict[str, int],
     literal_filters: tuple[str, ...],
     regex_filters: tuple[str, ...],
     log_file: str,
+    log_max_size_bytes: int,
+    log_max_rotated_files: int,
+    log_compress_rotated_files: bool,
+    log_max_run_logs: int,
+    log_system_sink: str | None,
+    log_dedup_window_secs: float,
+    log_dedup_excluded_targets: tuple[str, ...],
 ) -> tuple[RawIOBase, TextIO, TextIO]: ...
 def stdio_thread_get_destination() -> PyStdioDestination: ...
 def stdio_thread_set_destination(destination: PyStdioDestination) -> None: ...
@@ -742,6 +956,15 @@ def stdio_thread_console_clear() -> None: ...
 def stdio_write_stdout(msg: str) -> None: ...
 def stdio_write_stderr(msg: str) -> None: ...
 def task_side_effected() -> None: ...
+def task_context_capture() -> TaskContext: ...
+async def await_concurrent_future(future: concurrent.futures.Future[T]) -> T: ...
+
+class TaskContext:
+    """The context of a running `@rule`, which allows engine calls to be started on its behalf from
+    other threads: see `pants.engine.asyncio_bridge.EngineBridge`."""
+
+    def spawn(self, call: Awaitable[T]) -> concurrent.futures.Future[T]: ...
+
 def teardown_dynamic_ui(scheduler: PyScheduler, session: PySession) -> None: ...
 def tasks_task_begin(
     tasks: PyTasks,
@@ -773,7 +996,13 @@ def execution_add_root_select(
 ) -> None: ...
 def nailgun_server_await_shutdown(server: PyNailgunServer) -> None: ...
 def nailgun_server_create(
-    executor: PyExecutor, port: int, runner: RawFdRunner
+    executor: PyExecutor,
+    port: int,
+    runner: RawFdRunner,
+    remote_address: str | None = None,
+    remote_tls_cert_path: str | None = None,
+    remote_tls_key_path: str | None = None,
+    remote_token: str | None = None,
 ) -> PyNailgunServer: ...
 def scheduler_create(
     executor: PyExecutor,
@@ -785,10 +1014,15 @@ def scheduler_create(
     ignore_patterns: Sequence[str],
     use_gitignore: bool,
     watch_filesystem: bool,
+    file_watcher: str,
+    file_watcher_poll_interval_secs: float,
+    file_watcher_coalesce_window_secs: float,
+    file_watcher_ignore: Sequence[str],
     remoting_options: PyRemotingOptions,
     local_store_options: PyLocalStoreOptions,
     exec_strategy_opts: PyExecutionStrategyOptions,
     ca_certs_path: str | None,
+    rule_graph_cache: PyRuleGraphCache | None,
 ) -> PyScheduler: ...
 def scheduler_execute(
     scheduler: PyScheduler, session: PySession, execution_request: PyExecutionRequest
@@ -797,6 +1031,8 @@ def scheduler_metrics(scheduler: PyScheduler, session: PySession) -> dict[str, i
 def scheduler_live_items(
     scheduler: PyScheduler, session: PySession
 ) -> tuple[list[Any], dict[str, tuple[int, int]]]: ...
+def scheduler_shed_memory(scheduler: PyScheduler, max_idle_runs: int) -> dict[str, int]: ...
+def scheduler_live_sessions(scheduler: PyScheduler) -> list[str]: ...
 def scheduler_shutdown(scheduler: PyScheduler, timeout_secs: int) -> None: ...
 def session_new_run_id(session: PySession) -> None: ...
 def session_poll_workunits(
@@ -809,9 +1045,16 @@ def session_get_metrics(session: PySession) -> dict[str, int]: ...
 def session_get_observation_histograms(
     scheduler: PyScheduler, session: PySession
 ) -> dict[str, Any]: ...
+def session_get_observation_buckets(
+    session: PySession,
+) -> dict[str, tuple[list[tuple[int, int]], int, int]]: ...
+def session_get_provenance(session: PySession) -> list[str]: ...
 def session_record_test_observation(
     scheduler: PyScheduler, session: PySession, value: int
 ) -> None: ...
+def session_set_run_estimate(
+    session: PySession, remaining_secs: float, process_secs: dict[str, float]
+) -> None: ...
 def session_isolated_shallow_clone(session: PySession, build_id: str) -> PySession: ...
 def session_wait_for_tail_tasks(
     scheduler: PyScheduler, session: PySession, timeout: float
@@ -834,6 +1077,7 @@ def rule_subgraph_visualize(
     scheduler: PyScheduler, param_types: Sequence[type], product_type: type, path: str
 ) -> None: ...
 def garbage_collect_store(scheduler: PyScheduler, target_size_bytes: int) -> None: ...
+def clear_local_cache(scheduler: PyScheduler, namespaces: list[str]) -> dict[str, int]: ...
 def lease_files_in_graph(scheduler: PyScheduler, session: PySession) -> None: ...
 def strongly_connected_components(
     adjacency_lists: Sequence[Tuple[Any, Sequence[Any]]]
@@ -934,11 +1178,12 @@ class PySession:
         *,
         scheduler: PyScheduler,
         dynamic_ui: bool,
-        ui_use_prodash: bool,
+        ui_renderer: str,
         max_workunit_level: int,
         build_id: str,
         session_values: SessionValues,
         cancellation_latch: PySessionCancellationLatch,
+        niceness: int,
     ) -> None: ...
     def cancel(self) -> None: ...
     def is_cancelled(self) -> bool: ...
@@ -948,6 +1193,9 @@ class PySession:
 class PySessionCancellationLatch:
     def __init__(self) -> None: ...
 
+class PyRuleGraphCache:
+    def __init__(self) -> None: ...
+
 class PyTasks:
     def __init__(self) -> None: ...
 
@@ -4,13 +4,15 @@
 from dataclasses import dataclass
 
 from pants.build_graph.build_configuration import BuildConfiguration
+from pants.engine.fs import DigestContents, PathGlobs
+from pants.engine.internals import native_engine
 from pants.engine.internals.session import SessionValues
-from pants.engine.rules import collect_rules, rule
+from pants.engine.rules import Get, collect_rules, rule
 from pants.engine.unions import UnionMembership
 from pants.option.global_options import GlobalOptions, KeepSandboxes, NamedCachesDirOption
 from pants.option.options import Options
 from pants.option.options_bootstrapper import OptionsBootstrapper
-from pants.option.scope import Scope, ScopedOptions
+from pants.option.scope import DirectoryScopedOptionsRequest, Scope, ScopedOptions
 from pants.util.logging import LogLevel
 from pants.util.memo import memoized_property
 
@@ -54,6 +56,22 @@ def scope_options(scope: Scope, options: _Options) -> ScopedOptions:
     return ScopedOptions(scope, options.options.for_scope(scope.scope))
 
 
+@rule
+async def directory_scope_options(
+    request: DirectoryScopedOptionsRequest, options: _Options
+) -> ScopedOptions:
+    paths = native_engine.directory_config_paths(request.directory)
+    # The directory config files are read via the engine, so that the options are recomputed when
+    # they change.
+    digest_contents = await Get(DigestContents, PathGlobs(paths))
+    contents_by_path = {file_content.path: file_content for file_content in digest_contents}
+    config_sources = [contents_by_path[path] for path in paths if path in contents_by_path]
+    return ScopedOptions(
+        request.scope,
+        options.options.for_scope_in_directory(request.scope.scope, config_sources),
+    )
+
+
 @rule
 def log_level(global_options: GlobalOptions) -> LogLevel:
     return global_options.level
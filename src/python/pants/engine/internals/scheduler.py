@@ -3,13 +3,14 @@
 
 from __future__ import annotations
 
+import json
 import logging
 import os
 import time
 from dataclasses import dataclass
 from pathlib import PurePath
 from types import CoroutineType
-from typing import Any, Callable, Dict, Iterable, NoReturn, Sequence, cast
+from typing import Any, Callable, Dict, Iterable, Mapping, NoReturn, Sequence, cast
 
 from typing_extensions import TypedDict
 
@@ -19,25 +20,44 @@ from pants.engine.fs import (
     CreateDigest,
     Digest,
     DigestContents,
+    DigestContentViews,
+    DigestDiff,
     DigestEntries,
+    DigestFingerprint,
+    Digests,
     DigestSubset,
     Directory,
     FileContent,
+    FileContentView,
     FileDigest,
     FileEntry,
+    GitMetadata,
     NativeDownloadFile,
     PathGlobs,
     PathGlobsAndRoot,
+    PathMetadata,
+    PathMetadataResult,
     Paths,
     Snapshot,
     SymlinkEntry,
 )
 from pants.engine.goal import CurrentExecutingGoals, Goal
 from pants.engine.internals import native_engine
-from pants.engine.internals.docker import DockerResolveImageRequest, DockerResolveImageResult
+from pants.engine.internals.docker import (
+    DockerResolveImageRequest,
+    DockerResolveImageResult,
+    ResolveImageDigestResult,
+)
 from pants.engine.internals.native_dep_inference import (
+    NativeParsedDotnetDependencies,
+    NativeParsedGoDependencies,
+    NativeParsedJavaDependencies,
     NativeParsedJavascriptDependencies,
+    NativeParsedKotlinDependencies,
     NativeParsedPythonDependencies,
+    NativeParsedRustDependencies,
+    NativeParsedScalaDependencies,
+    NativeParsedTerraformDependencies,
 )
 from pants.engine.internals.native_engine import (
     PyExecutionRequest,
@@ -45,6 +65,7 @@ from pants.engine.internals.native_engine import (
     PyExecutor,
     PyLocalStoreOptions,
     PyRemotingOptions,
+    PyRuleGraphCache,
     PyScheduler,
     PySession,
     PySessionCancellationLatch,
@@ -66,7 +87,9 @@ from pants.engine.rules import Rule, RuleIndex, TaskRule
 from pants.engine.unions import UnionMembership, is_union, union_in_scope_types
 from pants.option.global_options import (
     LOCAL_STORE_LEASE_TIME_SECS,
+    DynamicUIRenderer,
     ExecutionOptions,
+    FileWatcher,
     LocalStoreOptions,
 )
 from pants.util.contextutil import temporary_file_path
@@ -124,6 +147,11 @@ class Scheduler:
         visualize_to_dir: str | None = None,
         validate_reachability: bool = True,
         watch_filesystem: bool = True,
+        file_watcher: FileWatcher = FileWatcher.auto,
+        file_watcher_poll_interval: float = 1.0,
+        file_watcher_coalesce_window: float = 0.1,
+        file_watcher_ignore: Sequence[str] = (),
+        rule_graph_cache: PyRuleGraphCache | None = None,
     ) -> None:
         """
         :param ignore_patterns: A list of gitignore-style file patterns for pants to ignore.
@@ -141,6 +169,15 @@ class Scheduler:
           constructed rule graph are reachable: if a graph cannot be successfully constructed, it
           is always a fatal error.
         :param watch_filesystem: False if filesystem watching should be disabled.
+        :param file_watcher: How to watch the filesystem, if it is watched.
+        :param file_watcher_poll_interval: The seconds between polls of the filesystem, if it is
+          polled.
+        :param file_watcher_coalesce_window: The seconds for which changes to the filesystem are
+          collected before they are invalidated together.
+        :param file_watcher_ignore: A list of gitignore-style patterns for paths whose changes
+          should not be invalidated.
+        :param rule_graph_cache: If set, the rule graph is solved incrementally against the one
+          most recently solved via the cache (i.e. for a previous Scheduler).
         """
         self.include_trace_on_error = include_trace_on_error
         self._visualize_to_dir = visualize_to_dir
@@ -153,15 +190,23 @@ class Scheduler:
         types = PyTypes(
             paths=Paths,
             file_content=FileContent,
+            file_content_view=FileContentView,
             file_entry=FileEntry,
             symlink_entry=SymlinkEntry,
             directory=Directory,
             digest_contents=DigestContents,
+            digest_content_views=DigestContentViews,
             digest_entries=DigestEntries,
             path_globs=PathGlobs,
             create_digest=CreateDigest,
             digest_subset=DigestSubset,
             native_download_file=NativeDownloadFile,
+            path_metadata=PathMetadata,
+            path_metadata_result=PathMetadataResult,
+            git_metadata=GitMetadata,
+            digest_diff=DigestDiff,
+            digests=Digests,
+            digest_fingerprint=DigestFingerprint,
             platform=Platform,
             process=Process,
             process_result=FallibleProcessResult,
@@ -174,8 +219,16 @@ class Scheduler:
             engine_aware_parameter=EngineAwareParameter,
             docker_resolve_image_request=DockerResolveImageRequest,
             docker_resolve_image_result=DockerResolveImageResult,
+            resolve_image_digest_result=ResolveImageDigestResult,
             parsed_python_deps_result=NativeParsedPythonDependencies,
             parsed_javascript_deps_result=NativeParsedJavascriptDependencies,
+            parsed_java_deps_result=NativeParsedJavaDependencies,
+            parsed_scala_deps_result=NativeParsedScalaDependencies,
+            parsed_kotlin_deps_result=NativeParsedKotlinDependencies,
+            parsed_go_deps_result=NativeParsedGoDependencies,
+            parsed_dotnet_deps_result=NativeParsedDotnetDependencies,
+            parsed_rust_deps_result=NativeParsedRustDependencies,
+            parsed_terraform_deps_result=NativeParsedTerraformDependencies,
         )
         remoting_options = PyRemotingOptions(
             provider=execution_options.remote_provider.value,
@@ -204,23 +257,41 @@ class Scheduler:
         )
         py_local_store_options = PyLocalStoreOptions(
             store_dir=local_store_options.store_dir,
+            shared_store_dirs=[
+                *filter(None, [local_store_options.shared_store_dir]),
+                *local_store_options.peer_store_dirs,
+            ],
             process_cache_max_size_bytes=local_store_options.processes_max_size_bytes,
+            dep_inference_cache_max_size_bytes=local_store_options.dep_inference_max_size_bytes,
             files_max_size_bytes=local_store_options.files_max_size_bytes,
             directories_max_size_bytes=local_store_options.directories_max_size_bytes,
+            max_size_growth_factor=local_store_options.max_size_growth_factor,
             lease_time_millis=LOCAL_STORE_LEASE_TIME_SECS * 1000,
+            artifacts_lease_time_millis=local_store_options.artifacts_retention_secs * 1000,
             shard_count=local_store_options.shard_count,
+            backend=local_store_options.backend.value,
+            digest_function=local_store_options.digest_function.value,
         )
         exec_strategy_opts = PyExecutionStrategyOptions(
             local_cache=execution_options.local_cache,
             remote_cache_read=execution_options.remote_cache_read,
             remote_cache_write=execution_options.remote_cache_write,
             local_keep_sandboxes=execution_options.keep_sandboxes.value,
+            local_deterministic_sandbox_paths=execution_options.deterministic_sandbox_paths,
+            local_overlay_sandboxes=execution_options.overlay_sandboxes,
             local_parallelism=execution_options.process_execution_local_parallelism,
             local_enable_nailgun=execution_options.process_execution_local_enable_nailgun,
             remote_parallelism=execution_options.process_execution_remote_parallelism,
             child_max_memory=execution_options.process_total_child_memory_usage or 0,
             child_default_memory=execution_options.process_per_child_memory_usage,
             graceful_shutdown_timeout=execution_options.process_execution_graceful_shutdown_timeout,
+            sandboxer_bin=execution_options.sandboxer_bin,
+            provenance_output_globs=list(execution_options.provenance_outputs),
+            process_audit_dir=execution_options.process_audit_log_dir,
+            process_audit_env_allowlist=list(execution_options.process_audit_env_allowlist),
+            dry_run=execution_options.process_execution_dry_run,
+            explain_cache_misses=execution_options.explain_cache_misses,
+            process_routing_rules=list(execution_options.process_routing_rules),
         )
 
         self._py_executor = executor
@@ -234,10 +305,15 @@ class Scheduler:
             ignore_patterns,
             use_gitignore,
             watch_filesystem,
+            file_watcher.value,
+            file_watcher_poll_interval,
+            file_watcher_coalesce_window,
+            file_watcher_ignore,
             remoting_options,
             py_local_store_options,
             exec_strategy_opts,
             ca_certs_path,
+            rule_graph_cache,
         )
 
         # If configured, visualize the rule graph before asserting that it is valid.
@@ -307,6 +383,10 @@ class Scheduler:
     def graph_len(self) -> int:
         return native_engine.graph_len(self.py_scheduler)
 
+    def live_sessions(self) -> list[str]:
+        """The build ids of the live Sessions of this Scheduler."""
+        return native_engine.scheduler_live_sessions(self.py_scheduler)
+
     def execution_add_root_select(
         self, execution_request: PyExecutionRequest, subject_or_params: Any | Params, product: type
     ) -> None:
@@ -322,14 +402,30 @@ class Scheduler:
     def garbage_collect_store(self, target_size_bytes: int) -> None:
         native_engine.garbage_collect_store(self.py_scheduler, target_size_bytes)
 
+    def clear_local_cache(self, namespaces: Sequence[str]) -> dict[str, int]:
+        """Removes the entries of the given namespaces from the local cache, along with any entries
+        which have expired.
+
+        Returns the number of entries which were removed, by namespace.
+        """
+        return native_engine.clear_local_cache(self.py_scheduler, list(namespaces))
+
+    def shed_memory(self, max_idle_runs: int) -> dict[str, int]:
+        """Releases the memory of values which have not been requested within `max_idle_runs` runs.
+
+        Returns the number of entries which were released, by kind.
+        """
+        return native_engine.scheduler_shed_memory(self.py_scheduler, max_idle_runs)
+
     def new_session(
         self,
         build_id: str,
         dynamic_ui: bool = False,
-        ui_use_prodash: bool = False,
+        ui_renderer: DynamicUIRenderer = DynamicUIRenderer.indicatif_spinner,
         max_workunit_level: LogLevel = LogLevel.DEBUG,
         session_values: SessionValues | None = None,
         cancellation_latch: PySessionCancellationLatch | None = None,
+        niceness: int = 0,
     ) -> SchedulerSession:
         """Creates a new SchedulerSession for this Scheduler."""
         return SchedulerSession(
@@ -337,11 +433,12 @@ class Scheduler:
             PySession(
                 scheduler=self.py_scheduler,
                 dynamic_ui=dynamic_ui,
-                ui_use_prodash=ui_use_prodash,
+                ui_renderer=ui_renderer.value,
                 max_workunit_level=max_workunit_level.level,
                 build_id=build_id,
                 session_values=session_values or SessionValues(),
                 cancellation_latch=cancellation_latch or PySessionCancellationLatch(),
+                niceness=niceness,
             ),
         )
 
@@ -637,15 +734,40 @@ class SchedulerSession:
     def garbage_collect_store(self, target_size_bytes: int) -> None:
         self._scheduler.garbage_collect_store(target_size_bytes)
 
+    def clear_local_cache(self, namespaces: Sequence[str]) -> dict[str, int]:
+        return self._scheduler.clear_local_cache(namespaces)
+
     def get_metrics(self) -> dict[str, int]:
         return native_engine.session_get_metrics(self.py_session)
 
     def get_observation_histograms(self) -> dict[str, Any]:
         return native_engine.session_get_observation_histograms(self.py_scheduler, self.py_session)
 
+    def get_observation_buckets(self) -> dict[str, tuple[list[tuple[int, int]], int, int]]:
+        """Returns the observations of this session by metric, bucketed for aggregation.
+
+        Each metric has a list of (upper bound, cumulative count) pairs, the total count, and the
+        (approximate) sum of its observations.
+        """
+        return native_engine.session_get_observation_buckets(self.py_session)
+
+    def get_provenance(self) -> list[dict[str, Any]]:
+        """Returns the provenance recorded for the outputs of the processes of this session.
+
+        See `[GLOBAL].provenance_outputs`.
+        """
+        return [
+            json.loads(record) for record in native_engine.session_get_provenance(self.py_session)
+        ]
+
     def record_test_observation(self, value: int) -> None:
         native_engine.session_record_test_observation(self.py_scheduler, self.py_session, value)
 
+    def set_run_estimate(self, remaining_secs: float, process_secs: Mapping[str, float]) -> None:
+        """Sets the estimated time remaining of the run, and the expected duration of each process
+        by description, for display by the dynamic UI."""
+        native_engine.session_set_run_estimate(self.py_session, remaining_secs, dict(process_secs))
+
     @property
     def is_cancelled(self) -> bool:
         return self.py_session.is_cancelled()
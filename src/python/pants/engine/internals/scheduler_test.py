@@ -505,6 +505,32 @@ def test_trace_includes_nested_exception_traceback() -> None:
         rule_runner.request(SomeOutput, [OuterInput("asdf")])
 
 
+# -----------------------------------------------------------------------------------------------
+# Test shedding memory
+# -----------------------------------------------------------------------------------------------
+
+
+def test_shed_memory() -> None:
+    rule_runner = RuleRunner(
+        rules=[consumes_a_and_b, QueryRule(str, [A, B])],
+        inherent_environment=None,
+    )
+    a, b = A(), B()
+    expected = consumes_a_and_b.rule.func(a, b)  # type: ignore[attr-defined]
+    assert rule_runner.request(str, [a, b]) == expected
+
+    # Values which were requested in the current run are retained.
+    scheduler = rule_runner.scheduler.scheduler
+    assert scheduler.shed_memory(max_idle_runs=0)["graph_entries"] == 0
+
+    # But once they are idle for long enough, they are dropped, and recomputed if requested again.
+    rule_runner.scheduler.new_run_id()
+    rule_runner.scheduler.new_run_id()
+    assert scheduler.shed_memory(max_idle_runs=2)["graph_entries"] == 0
+    assert scheduler.shed_memory(max_idle_runs=1)["graph_entries"] > 0
+    assert rule_runner.request(str, [a, b]) == expected
+
+
 # -----------------------------------------------------------------------------------------------
 # Test unhashable types
 # -----------------------------------------------------------------------------------------------
@@ -5,23 +5,52 @@ from __future__ import annotations
 
 from pants.engine.fs import (
     AddPrefix,
+    AddPrefixBatch,
     CreateDigest,
+    DiffDigests,
     Digest,
     DigestContents,
+    DigestContentViews,
+    DigestDiff,
     DigestEntries,
+    DigestFingerprint,
+    Digests,
     DigestSubset,
+    FingerprintDigest,
+    GitMetadata,
+    GitMetadataRequest,
     MergeDigests,
+    MergeDigestsBatch,
+    NativeCreateArchive,
     NativeDownloadFile,
+    NativeExtractArchive,
+    ParseStructuredFile,
     PathGlobs,
+    PathMetadataRequest,
+    PathMetadataResult,
     Paths,
     RemovePrefix,
+    RemovePrefixBatch,
     Snapshot,
+    StructuredData,
 )
 from pants.engine.internals import native_engine
-from pants.engine.internals.docker import DockerResolveImageRequest, DockerResolveImageResult
+from pants.engine.internals.docker import (
+    DockerResolveImageRequest,
+    DockerResolveImageResult,
+    ResolveImageDigestRequest,
+    ResolveImageDigestResult,
+)
 from pants.engine.internals.native_dep_inference import (
+    NativeParsedDotnetDependencies,
+    NativeParsedGoDependencies,
+    NativeParsedJavaDependencies,
     NativeParsedJavascriptDependencies,
+    NativeParsedKotlinDependencies,
     NativeParsedPythonDependencies,
+    NativeParsedRustDependencies,
+    NativeParsedScalaDependencies,
+    NativeParsedTerraformDependencies,
 )
 from pants.engine.internals.native_engine import NativeDependenciesRequest
 from pants.engine.internals.session import RunId, SessionValues
@@ -63,6 +92,30 @@ async def download_file_to_digest(
     return await native_engine.download_file_to_digest(native_download_file)
 
 
+@rule
+async def create_archive_to_digest(
+    native_create_archive: NativeCreateArchive,
+) -> Digest:
+    return await native_engine.create_archive_to_digest(native_create_archive)
+
+
+@rule
+async def extract_archive_to_digest(
+    native_extract_archive: NativeExtractArchive,
+) -> Digest:
+    return await native_engine.extract_archive_to_digest(native_extract_archive)
+
+
+@rule
+async def path_metadata_request(request: PathMetadataRequest) -> PathMetadataResult:
+    return await native_engine.path_metadata_request(request)
+
+
+@rule
+async def git_metadata_request(request: GitMetadataRequest) -> GitMetadata:
+    return await native_engine.git_metadata_request(request)
+
+
 @rule
 async def digest_to_snapshot(digest: Digest) -> Snapshot:
     return await native_engine.digest_to_snapshot(digest)
@@ -73,6 +126,11 @@ async def directory_digest_to_digest_contents(digest: Digest) -> DigestContents:
     return await native_engine.directory_digest_to_digest_contents(digest)
 
 
+@rule
+async def directory_digest_to_digest_content_views(digest: Digest) -> DigestContentViews:
+    return await native_engine.directory_digest_to_digest_content_views(digest)
+
+
 @rule
 async def directory_digest_to_digest_entries(digest: Digest) -> DigestEntries:
     return await native_engine.directory_digest_to_digest_entries(digest)
@@ -83,16 +141,31 @@ async def merge_digests_request_to_digest(merge_digests: MergeDigests) -> Digest
     return await native_engine.merge_digests_request_to_digest(merge_digests)
 
 
+@rule
+async def merge_digests_batch_to_digests(merge_digests_batch: MergeDigestsBatch) -> Digests:
+    return await native_engine.merge_digests_batch_to_digests(merge_digests_batch)
+
+
 @rule
 async def remove_prefix_request_to_digest(remove_prefix: RemovePrefix) -> Digest:
     return await native_engine.remove_prefix_request_to_digest(remove_prefix)
 
 
+@rule
+async def remove_prefix_batch_to_digests(remove_prefix_batch: RemovePrefixBatch) -> Digests:
+    return await native_engine.remove_prefix_batch_to_digests(remove_prefix_batch)
+
+
 @rule
 async def add_prefix_request_to_digest(add_prefix: AddPrefix) -> Digest:
     return await native_engine.add_prefix_request_to_digest(add_prefix)
 
 
+@rule
+async def add_prefix_batch_to_digests(add_prefix_batch: AddPrefixBatch) -> Digests:
+    return await native_engine.add_prefix_batch_to_digests(add_prefix_batch)
+
+
 @rule
 async def process_request_to_process_result(
     process: Process, process_execution_environment: ProcessExecutionEnvironment
@@ -107,6 +180,23 @@ async def digest_subset_to_digest(digest_subset: DigestSubset) -> Digest:
     return await native_engine.digest_subset_to_digest(digest_subset)
 
 
+@rule
+async def diff_digests_to_digest_diff(diff_digests: DiffDigests) -> DigestDiff:
+    return await native_engine.diff_digests_to_digest_diff(diff_digests)
+
+
+@rule
+async def fingerprint_digest_to_digest_fingerprint(
+    fingerprint_digest: FingerprintDigest,
+) -> DigestFingerprint:
+    return await native_engine.fingerprint_digest_to_digest_fingerprint(fingerprint_digest)
+
+
+@rule
+async def parse_structured_file(parse_structured_file: ParseStructuredFile) -> StructuredData:
+    return await native_engine.parse_structured_file(parse_structured_file)
+
+
 @rule
 async def session_values() -> SessionValues:
     return await native_engine.session_values()
@@ -129,6 +219,12 @@ async def docker_resolve_image(request: DockerResolveImageRequest) -> DockerReso
     return await native_engine.docker_resolve_image(request)
 
 
+# NB: This rule is uncacheable so that the TTL of the underlying cache is respected across sessions.
+@_uncacheable_rule
+async def resolve_image_digest(request: ResolveImageDigestRequest) -> ResolveImageDigestResult:
+    return await native_engine.resolve_image_digest(request)
+
+
 @rule
 async def parse_python_deps(
     deps_request: NativeDependenciesRequest,
@@ -143,6 +239,55 @@ async def parse_javascript_deps(
     return await native_engine.parse_javascript_deps(deps_request)
 
 
+@rule
+async def parse_java_deps(
+    deps_request: NativeDependenciesRequest,
+) -> NativeParsedJavaDependencies:
+    return await native_engine.parse_java_deps(deps_request)
+
+
+@rule
+async def parse_scala_deps(
+    deps_request: NativeDependenciesRequest,
+) -> NativeParsedScalaDependencies:
+    return await native_engine.parse_scala_deps(deps_request)
+
+
+@rule
+async def parse_kotlin_deps(
+    deps_request: NativeDependenciesRequest,
+) -> NativeParsedKotlinDependencies:
+    return await native_engine.parse_kotlin_deps(deps_request)
+
+
+@rule
+async def parse_go_deps(
+    deps_request: NativeDependenciesRequest,
+) -> NativeParsedGoDependencies:
+    return await native_engine.parse_go_deps(deps_request)
+
+
+@rule
+async def parse_dotnet_deps(
+    deps_request: NativeDependenciesRequest,
+) -> NativeParsedDotnetDependencies:
+    return await native_engine.parse_dotnet_deps(deps_request)
+
+
+@rule
+async def parse_rust_deps(
+    deps_request: NativeDependenciesRequest,
+) -> NativeParsedRustDependencies:
+    return await native_engine.parse_rust_deps(deps_request)
+
+
+@rule
+async def parse_terraform_deps(
+    deps_request: NativeDependenciesRequest,
+) -> NativeParsedTerraformDependencies:
+    return await native_engine.parse_terraform_deps(deps_request)
+
+
 def rules():
     return [
         *collect_rules(),
@@ -0,0 +1,35 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+"""Custom metrics, which plugins can record from their rules.
+
+Custom metrics are reported alongside the engine's builtin metrics: by `[stats].log`, by the
+`/metrics` endpoint of the pantsd status service, and to any `WorkunitsCallback` via
+`StreamingWorkunitContext.get_metrics()` and
+`StreamingWorkunitContext.get_observation_histograms()`.
+
+Names must be in snake_case, and must not be the name of a builtin metric: it is a good idea to
+prefix them with the name of the plugin.
+
+NB: Rules are memoized, so a metric is only recorded when the rule which records it actually runs:
+not when its result is reused from an earlier run of pantsd, or from the cache.
+"""
+
+from __future__ import annotations
+
+from pants.engine.internals import native_engine
+
+
+def increment_counter(name: str, change: int = 1, *, display: bool = False) -> None:
+    """Increments the custom counter with the given name by `change`.
+
+    If `display` is set, the counter is also displayed live by the dynamic UI while the run is in
+    progress, e.g. to show how many tests have passed so far.
+    """
+    native_engine.increment_custom_counter(name, change, display)
+
+
+def record_observation(name: str, value: int) -> None:
+    """Records an observation of the custom histogram metric with the given name, such as the
+    duration of some operation in milliseconds."""
+    native_engine.record_custom_observation(name, value)
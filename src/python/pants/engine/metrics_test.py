@@ -0,0 +1,55 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+from dataclasses import dataclass
+
+import pytest
+
+from pants.engine import metrics
+from pants.engine.rules import rule
+from pants.testutil.rule_runner import QueryRule, RuleRunner, engine_error
+
+
+@dataclass(frozen=True)
+class Lint:
+    violations: int
+    metric_name: str = "plugin_lint_violations"
+
+
+@dataclass(frozen=True)
+class LintResult:
+    violations: int
+
+
+@rule
+async def lint(request: Lint) -> LintResult:
+    metrics.increment_counter(request.metric_name, request.violations)
+    metrics.record_observation("plugin_lint_time_ms", 12)
+    return LintResult(request.violations)
+
+
+@pytest.fixture
+def rule_runner() -> RuleRunner:
+    return RuleRunner(rules=[lint, QueryRule(LintResult, [Lint])])
+
+
+def test_custom_metrics(rule_runner: RuleRunner) -> None:
+    rule_runner.request(LintResult, [Lint(2)])
+    rule_runner.request(LintResult, [Lint(3)])
+    # Memoized, so not recorded again.
+    rule_runner.request(LintResult, [Lint(3)])
+
+    assert rule_runner.scheduler.get_metrics()["plugin_lint_violations"] == 5
+    histograms = rule_runner.scheduler.get_observation_histograms()["histograms"]
+    assert "plugin_lint_time_ms" in histograms
+    _, count, _ = rule_runner.scheduler.get_observation_buckets()["plugin_lint_time_ms"]
+    assert count == 2
+
+
+@pytest.mark.parametrize("name", ["PluginLintViolations", "local_cache_requests"])
+def test_invalid_name(rule_runner: RuleRunner, name: str) -> None:
+    with engine_error(ValueError, contains=name):
+        rule_runner.request(LintResult, [Lint(1, metric_name=name)])
+
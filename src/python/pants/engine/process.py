@@ -50,12 +50,14 @@ class ProcessCacheScope(Enum):
 class Process:
     argv: tuple[str, ...]
     description: str = dataclasses.field(compare=False)
+    tags: tuple[str, ...] = dataclasses.field(compare=False)
     level: LogLevel
     input_digest: Digest
     immutable_input_digests: FrozenDict[str, Digest]
     use_nailgun: tuple[str, ...]
     working_directory: str | None
     env: FrozenDict[str, str]
+    execution_only_env_vars: tuple[str, ...]
     append_only_caches: FrozenDict[str, str]
     output_files: tuple[str, ...]
     output_directories: tuple[str, ...]
@@ -65,6 +67,10 @@ class Process:
     concurrency_available: int
     cache_scope: ProcessCacheScope
     remote_cache_speculation_delay_millis: int
+    local_cache_namespace: str | None
+    local_cache_namespace_version: int
+    local_cache_ttl_seconds: int | None
+    remote_persistent_worker: bool
     attempt: int
 
     def __init__(
@@ -72,12 +78,14 @@ class Process:
         argv: Iterable[str],
         *,
         description: str,
+        tags: Iterable[str] = (),
         level: LogLevel = LogLevel.INFO,
         input_digest: Digest = EMPTY_DIGEST,
         immutable_input_digests: Mapping[str, Digest] | None = None,
         use_nailgun: Iterable[str] = (),
         working_directory: str | None = None,
         env: Mapping[str, str] | None = None,
+        execution_only_env_vars: Iterable[str] = (),
         append_only_caches: Mapping[str, str] | None = None,
         output_files: Iterable[str] | None = None,
         output_directories: Iterable[str] | None = None,
@@ -87,6 +95,10 @@ class Process:
         concurrency_available: int = 0,
         cache_scope: ProcessCacheScope = ProcessCacheScope.SUCCESSFUL,
         remote_cache_speculation_delay_millis: int = 0,
+        local_cache_namespace: str | None = None,
+        local_cache_namespace_version: int = 0,
+        local_cache_ttl_seconds: int | None = None,
+        remote_persistent_worker: bool = False,
         attempt: int = 0,
     ) -> None:
         """Request to run a subprocess, similar to subprocess.Popen.
@@ -107,6 +119,26 @@ class Process:
         `output_digest` on the `ProcessResult`. If you want to split up this output digest into
         multiple digests, use `await Get(Digest, DigestSubset)` on the `output_digest`.
 
+        The `env` vars named in `execution_only_env_vars` are set for the process, but are excluded
+        from its cache key: e.g. credentials, whose values should neither prevent the process from
+        hitting caches, nor be stored in them. The process must not produce outputs which depend on
+        their values. Remote execution fails for processes which set them, since the remote
+        execution API would store their values in the remote CAS.
+
+        Results which are cached locally may be stored in a `local_cache_namespace`, whose entries
+        can be invalidated by bumping the `local_cache_namespace_version`, or removed with
+        the `clear-local-cache` goal. The entry expires after `local_cache_ttl_seconds`, if set.
+
+        The `tags` of a process may be matched by `[GLOBAL].process_routing_rules`, in order to
+        decide whether it is executed locally or remotely. Like its `description`, they are not part
+        of its identity or cache key.
+
+        If `remote_persistent_worker` is set, the process may be run by a persistent worker when it
+        is executed remotely (for servers which support the remote persistent workers extension of
+        the REAPI), whose tool inputs are the `immutable_input_digests` named in `use_nailgun`. The
+        process must then implement the persistent worker protocol, and pass its arguments in a
+        flagfile (`@<path>` or `--flagfile=<path>`) as its last argument.
+
         To actually run the process, use `await Get(ProcessResult, Process)` or
         `await Get(FallibleProcessResult, Process)`.
 
@@ -122,6 +154,7 @@ class Process:
 
         object.__setattr__(self, "argv", tuple(argv))
         object.__setattr__(self, "description", description)
+        object.__setattr__(self, "tags", tuple(tags))
         object.__setattr__(self, "level", level)
         object.__setattr__(self, "input_digest", input_digest)
         object.__setattr__(
@@ -130,6 +163,7 @@ class Process:
         object.__setattr__(self, "use_nailgun", tuple(use_nailgun))
         object.__setattr__(self, "working_directory", working_directory)
         object.__setattr__(self, "env", FrozenDict(env or {}))
+        object.__setattr__(self, "execution_only_env_vars", tuple(execution_only_env_vars))
         object.__setattr__(self, "append_only_caches", FrozenDict(append_only_caches or {}))
         object.__setattr__(self, "output_files", tuple(output_files or ()))
         object.__setattr__(self, "output_directories", tuple(output_directories or ()))
@@ -146,6 +180,10 @@ class Process:
         object.__setattr__(
             self, "remote_cache_speculation_delay_millis", remote_cache_speculation_delay_millis
         )
+        object.__setattr__(self, "local_cache_namespace", local_cache_namespace)
+        object.__setattr__(self, "local_cache_namespace_version", local_cache_namespace_version)
+        object.__setattr__(self, "local_cache_ttl_seconds", local_cache_ttl_seconds)
+        object.__setattr__(self, "remote_persistent_worker", remote_persistent_worker)
         object.__setattr__(self, "attempt", attempt)
 
 
@@ -212,6 +250,7 @@ class ProcessResultMetadata:
         RAN = "ran"
         HIT_LOCALLY = "hit_locally"
         HIT_REMOTELY = "hit_remotely"
+        DRY_RUN = "dry_run"
         MEMOIZED = "memoized"
 
     # The execution time of the process, in milliseconds, or None if it could not be captured
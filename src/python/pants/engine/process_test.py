@@ -3,6 +3,7 @@
 
 from __future__ import annotations
 
+import json
 import textwrap
 from pathlib import Path
 
@@ -30,6 +31,7 @@ from pants.engine.process import (
     Process,
     ProcessCacheScope,
     ProcessResult,
+    ProcessResultMetadata,
 )
 from pants.testutil.rule_runner import QueryRule, RuleRunner, mock_console
 from pants.util.contextutil import environment_as
@@ -91,6 +93,79 @@ def test_env(rule_runner: RuleRunner) -> None:
     assert b"VAR2=VAL" in result.stdout
 
 
+def test_execution_only_env_vars(rule_runner: RuleRunner) -> None:
+    def process(token: str) -> Process:
+        return Process(
+            argv=("/bin/bash", "-c", "echo $TOKEN $RANDOM"),
+            description="use a token",
+            env={"TOKEN": token},
+            execution_only_env_vars=("TOKEN",),
+        )
+
+    result_one = rule_runner.request(ProcessResult, [process("first")])
+    assert result_one.stdout.startswith(b"first ")
+
+    # The token is not part of the cache key, so a process with another token hits the cache.
+    result_two = rule_runner.request(ProcessResult, [process("second")])
+    assert result_two.stdout == result_one.stdout
+
+
+def test_routing_rules() -> None:
+    rule_runner = new_rule_runner(
+        bootstrap_args=["--process-routing-rules=['cache_only: tag=expensive']"]
+    )
+
+    def process(*tags: str) -> Process:
+        return Process(argv=("/bin/echo", *tags), description="routed", tags=tags)
+
+    assert rule_runner.request(ProcessResult, [process("cheap")]).stdout == b"cheap\n"
+    with pytest.raises(ExecutionError) as exc:
+        rule_runner.request(ProcessResult, [process("cheap", "expensive")])
+    assert "`routed` missed the caches, but the routing rule" in str(exc.value)
+
+
+def test_audit_log(tmp_path: Path) -> None:
+    rule_runner = new_rule_runner(
+        bootstrap_args=[
+            f"--process-audit-log-dir={tmp_path}",
+            "--process-audit-env-allowlist=['VISIBLE']",
+        ]
+    )
+    process = Process(
+        argv=("/bin/bash", "-c", "echo hello > out.txt; exit 3"),
+        description="audited",
+        env={"VISIBLE": "1", "SECRET": "2"},
+        output_files=("out.txt",),
+    )
+    result = rule_runner.request(FallibleProcessResult, [process])
+
+    (line,) = (tmp_path / "buildid_for_test.jsonl").read_text().splitlines()
+    entry = json.loads(line)
+    assert entry["description"] == "audited"
+    assert entry["argv"] == list(process.argv)
+    assert entry["env"] == {"VISIBLE": "1"}
+    assert entry["redacted_env"] == ["SECRET"]
+    assert entry["exit_code"] == 3
+    assert entry["output_digest"]["fingerprint"] == result.output_digest.fingerprint
+    assert entry["source"] == "Ran"
+
+
+def test_dry_run() -> None:
+    rule_runner = new_rule_runner(bootstrap_args=["--process-execution-dry-run"])
+    process = Process(
+        argv=("/bin/bash", "-c", "exit 1"),
+        description="would fail",
+        output_files=("out.txt",),
+    )
+    result = rule_runner.request(FallibleProcessResult, [process])
+
+    assert result.exit_code == 0
+    assert result.stdout == b""
+    assert result.output_digest == EMPTY_DIGEST
+    metadata = result.metadata
+    assert metadata.source(metadata.source_run_id) == ProcessResultMetadata.Source.DRY_RUN
+
+
 @pytest.mark.parametrize("working_directory", ["", "subdir"])
 def test_output_digest(rule_runner: RuleRunner, working_directory) -> None:
     # Test that the output files are relative to the working directory, both in how
@@ -0,0 +1,74 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+"""Routing the output of threads which are started by plugins to the right console.
+
+The engine routes the output of `print` and `logging` (and the workunits which are started) to the
+console and workunit of the run that the current thread is working on. Threads which are started by
+plugins (or by third-party libraries which they use) are not associated with any run, and so their
+output is lost, or is interleaved with the output of other runs of `pantsd`.
+
+A `StdioDestination` captures the association of the current thread, and applies it to another:
+
+    @rule
+    async def compile(request: CompileRequest) -> CompileResult:
+        destination = StdioDestination.for_current_thread()
+        with ThreadPoolExecutor() as executor:
+            results = list(executor.map(destination.wrap(compile_one), request.sources))
+        ...
+
+or equivalently `with destination.redirect(): ...` within the body of the thread.
+"""
+
+from __future__ import annotations
+
+import functools
+from contextlib import contextmanager
+from dataclasses import dataclass
+from typing import Callable, Iterator, TypeVar
+
+from typing_extensions import ParamSpec
+
+from pants.engine.internals.native_engine import PyThreadLocals
+
+_P = ParamSpec("_P")
+_R = TypeVar("_R")
+
+
+@dataclass(frozen=True)
+class StdioDestination:
+    """The console and workunit to which the output of a thread is routed."""
+
+    _thread_locals: PyThreadLocals
+
+    @classmethod
+    def for_current_thread(cls) -> StdioDestination:
+        """The destination of the current thread, such as the thread which is running a `@rule`."""
+        return cls(PyThreadLocals.get_for_current_thread())
+
+    @contextmanager
+    def redirect(self) -> Iterator[None]:
+        """Routes the output of the current thread to this destination until the block exits.
+
+        The previous destination of the thread is restored afterward, so blocks may be nested.
+        """
+        previous = PyThreadLocals.get_for_current_thread()
+        self._thread_locals.set_for_current_thread()
+        try:
+            yield
+        finally:
+            previous.set_for_current_thread()
+
+    def wrap(self, func: Callable[_P, _R]) -> Callable[_P, _R]:
+        """Wraps the function to route its output to this destination, on whichever thread it runs.
+
+        This is useful for the `target` of a `threading.Thread`, or a function which is submitted to
+        a `concurrent.futures.ThreadPoolExecutor`.
+        """
+
+        @functools.wraps(func)
+        def wrapper(*args: _P.args, **kwargs: _P.kwargs) -> _R:
+            with self.redirect():
+                return func(*args, **kwargs)
+
+        return wrapper
@@ -0,0 +1,55 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+from concurrent.futures import ThreadPoolExecutor
+from dataclasses import dataclass
+
+import pytest
+
+from pants.engine.rules import rule
+from pants.engine.stdio import StdioDestination
+from pants.testutil.rule_runner import QueryRule, RuleRunner
+
+
+@dataclass(frozen=True)
+class Square:
+    values: tuple[int, ...]
+
+
+@dataclass(frozen=True)
+class Squares:
+    values: tuple[int, ...]
+
+
+def _square(value: int) -> int:
+    """Squares the value."""
+    print(f"Squaring {value}.")
+    return value * value
+
+
+@rule
+async def square(request: Square) -> Squares:
+    destination = StdioDestination.for_current_thread()
+    with ThreadPoolExecutor(max_workers=2) as executor:
+        return Squares(tuple(executor.map(destination.wrap(_square), request.values)))
+
+
+def test_wrap_in_rule() -> None:
+    rule_runner = RuleRunner(rules=[square, QueryRule(Squares, [Square])])
+    assert rule_runner.request(Squares, [Square((1, 2, 3))]) == Squares((1, 4, 9))
+
+
+def test_wrap() -> None:
+    wrapped = StdioDestination.for_current_thread().wrap(_square)
+    assert wrapped.__doc__ == "Squares the value."
+    assert wrapped(3) == 9
+
+
+def test_redirect_restores_on_error() -> None:
+    destination = StdioDestination.for_current_thread()
+    with pytest.raises(ValueError):
+        with destination.redirect():
+            with destination.redirect():
+                raise ValueError()
@@ -3,11 +3,12 @@
 
 from __future__ import annotations
 
+import fnmatch
 import logging
 import threading
 from abc import ABC, abstractmethod
-from dataclasses import dataclass
-from typing import Any, Callable, Iterable, Sequence, Tuple
+from dataclasses import dataclass, field
+from typing import Any, Callable, Iterable, Mapping, Sequence, Tuple
 
 from pants.base.specs import Specs
 from pants.core.util_rules.environments import determine_bootstrap_environment
@@ -61,12 +62,56 @@ class ExpandedSpecs:
     targets: dict[str, list[TargetInfo]]
 
 
+class CompletedWorkunits:
+    """The workunits of a run which have completed so far, which can be queried."""
+
+    def __init__(self) -> None:
+        self._workunits: list[Workunit] = []
+
+    def extend(self, workunits: Iterable[Workunit]) -> None:
+        self._workunits.extend(workunits)
+
+    def query(
+        self,
+        *,
+        name: str | None = None,
+        max_level: LogLevel | None = None,
+        min_duration_secs: float | None = None,
+        metadata_keys: Iterable[str] = (),
+    ) -> tuple[Workunit, ...]:
+        """Returns the completed workunits which match all of the given filters, in the order that
+        they completed.
+
+        :param name: A glob which the name of the workunit must match, e.g. `pants.*.test`.
+        :param max_level: The most verbose level of workunit to return.
+        :param min_duration_secs: The minimum duration of workunit to return.
+        :param metadata_keys: Keys which must all be present in the metadata of the workunit.
+        """
+        metadata_keys = tuple(metadata_keys)
+
+        def matches(workunit: Workunit) -> bool:
+            if name is not None and not fnmatch.fnmatchcase(workunit["name"], name):
+                return False
+            if max_level is not None and LogLevel(workunit["level"].lower()) > max_level:
+                return False
+            if min_duration_secs is not None:
+                duration_secs = workunit.get("duration_secs", 0)
+                duration_secs += workunit.get("duration_nanos", 0) / 1_000_000_000
+                if duration_secs < min_duration_secs:
+                    return False
+            metadata = workunit.get("metadata", {})
+            return all(key in metadata for key in metadata_keys)
+
+        return tuple(workunit for workunit in self._workunits if matches(workunit))
+
+
 @dataclass(frozen=True)
 class StreamingWorkunitContext:
     _scheduler: SchedulerSession
     _run_tracker: RunTracker
     _specs: Specs
     _options_bootstrapper: OptionsBootstrapper
+    _completed_workunits: CompletedWorkunits | None = field(default=None, compare=False)
 
     @property
     def run_tracker(self) -> RunTracker:
@@ -89,10 +134,42 @@ class StreamingWorkunitContext:
         ByteStore, if it exists, has a copy of the files fingerprinted by each Digest."""
         return self._scheduler.ensure_remote_has_recursive(digests)
 
+    def completed_workunits(
+        self,
+        *,
+        name: str | None = None,
+        max_level: LogLevel | None = None,
+        min_duration_secs: float | None = None,
+        metadata_keys: Iterable[str] = (),
+    ) -> tuple[Workunit, ...]:
+        """Returns the workunits of the run which have completed so far (including those passed
+        to the current call), filtered by `CompletedWorkunits.query`.
+
+        This is only available if a `WorkunitsCallback` of the run sets
+        `retains_completed_workunits`, and only returns workunits at up to
+        `[GLOBAL].streaming_workunits_level`.
+        """
+        if self._completed_workunits is None:
+            raise ValueError(
+                "Completed workunits are only retained if a WorkunitsCallback sets "
+                "`retains_completed_workunits`."
+            )
+        return self._completed_workunits.query(
+            name=name,
+            max_level=max_level,
+            min_duration_secs=min_duration_secs,
+            metadata_keys=metadata_keys,
+        )
+
     def get_metrics(self) -> dict[str, int]:
         """Invoke the internal get_metrics function, which returns metrics for the Session."""
         return self._scheduler.get_metrics()
 
+    def set_run_estimate(self, remaining_secs: float, process_secs: Mapping[str, float]) -> None:
+        """Sets the estimated time remaining of the run, and the expected duration of each process
+        (by description) from previous runs, for display by the dynamic UI (if it is enabled)."""
+        self._scheduler.set_run_estimate(remaining_secs, process_secs)
+
     def get_observation_histograms(self) -> dict[str, Any]:
         """Invoke the internal get_observation_histograms function, which serializes histograms
         generated from Pants-internal observation metrics observed during the current run of Pants.
@@ -101,6 +178,19 @@ class StreamingWorkunitContext:
         """
         return self._scheduler.get_observation_histograms()
 
+    def get_observation_buckets(self) -> dict[str, tuple[list[tuple[int, int]], int, int]]:
+        """Returns the observations of the current run of Pants by metric, as the cumulative counts
+        of observations in each bucket, the total count and the (approximate) sum.
+
+        Unlike the histograms, these do not need to be decoded, and can be summed across runs.
+        """
+        return self._scheduler.get_observation_buckets()
+
+    def get_provenance(self) -> list[dict[str, Any]]:
+        """Returns the provenance which the engine recorded for the outputs of the processes of the
+        current run of Pants which match `[GLOBAL].provenance_outputs`."""
+        return self._scheduler.get_provenance()
+
     def get_expanded_specs(self) -> ExpandedSpecs:
         """Return a dict containing the canonicalized addresses of the specs for this run, and what
         files they expand to."""
@@ -183,6 +273,15 @@ class WorkunitsCallback(ABC):
         to avoid slowing down Pants from finishing the run.
         """
 
+    @property
+    def retains_completed_workunits(self) -> bool:
+        """Should the completed workunits of the run be retained, so that this callback can query
+        them with `StreamingWorkunitContext.completed_workunits` (generally once `finished=True`)?
+
+        Retaining workunits uses memory for the duration of the run, so this is disabled by default.
+        """
+        return False
+
 
 @dataclass(frozen=True)
 class WorkunitsCallbackFactory:
@@ -239,12 +338,17 @@ class StreamingWorkunitHandler:
         max_workunit_verbosity: LogLevel,
     ) -> None:
         scheduler = scheduler.isolated_shallow_clone("streaming_workunit_handler_session")
-        self.callbacks = callbacks
+        self.callbacks = tuple(callbacks)
         self.context = StreamingWorkunitContext(
             _scheduler=scheduler,
             _run_tracker=run_tracker,
             _specs=specs,
             _options_bootstrapper=options_bootstrapper,
+            _completed_workunits=(
+                CompletedWorkunits()
+                if any(callback.retains_completed_workunits for callback in self.callbacks)
+                else None
+            ),
         )
         self.thread_runner = (
             _InnerHandler(
@@ -257,7 +361,7 @@ class StreamingWorkunitHandler:
                 max_workunit_verbosity=max_workunit_verbosity,
                 allow_async_completion=allow_async_completion,
             )
-            if callbacks
+            if self.callbacks
             else None
         )
 
@@ -302,6 +406,8 @@ class _InnerHandler(threading.Thread):
 
     def poll_workunits(self, *, finished: bool) -> None:
         workunits = self.scheduler.poll_workunits(self.max_workunit_verbosity)
+        if self.context._completed_workunits is not None:
+            self.context._completed_workunits.extend(workunits["completed"])
         for callback in self.callbacks:
             callback(
                 started_workunits=workunits["started"],
@@ -0,0 +1,51 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+from pants.engine.streaming_workunit_handler import CompletedWorkunits
+from pants.testutil.workunit_util import create_workunit
+from pants.util.logging import LogLevel
+
+
+def test_query() -> None:
+    completed_workunits = CompletedWorkunits()
+    completed_workunits.extend(
+        [
+            create_workunit(
+                "pants.core.goals.test.run_test",
+                level="INFO",
+                duration_secs=2.5,
+                metadata={"exit_code": "0"},
+            ),
+            create_workunit("pants.core.goals.lint.lint", level="INFO", duration_secs=0.5),
+            create_workunit("process", duration_secs=1.5, metadata={"exit_code": "1"}),
+        ]
+    )
+
+    def query(**kwargs) -> list[str]:
+        return [wu["name"] for wu in completed_workunits.query(**kwargs)]
+
+    assert query() == [
+        "pants.core.goals.test.run_test",
+        "pants.core.goals.lint.lint",
+        "process",
+    ]
+    assert query(name="pants.core.goals.*") == [
+        "pants.core.goals.test.run_test",
+        "pants.core.goals.lint.lint",
+    ]
+    assert query(max_level=LogLevel.INFO) == [
+        "pants.core.goals.test.run_test",
+        "pants.core.goals.lint.lint",
+    ]
+    assert query(max_level=LogLevel.DEBUG) == [
+        "pants.core.goals.test.run_test",
+        "pants.core.goals.lint.lint",
+        "process",
+    ]
+    assert query(min_duration_secs=1.5) == ["pants.core.goals.test.run_test", "process"]
+    assert query(metadata_keys=["exit_code"]) == ["pants.core.goals.test.run_test", "process"]
+    assert query(name="pants.*", min_duration_secs=1, metadata_keys=["exit_code"]) == [
+        "pants.core.goals.test.run_test"
+    ]
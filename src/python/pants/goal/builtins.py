@@ -7,9 +7,12 @@ from pants.bsp.goal import BSPGoal
 from pants.build_graph.build_configuration import BuildConfiguration
 from pants.goal import help
 from pants.goal.builtin_goal import BuiltinGoal
+from pants.goal.clear_local_cache import ClearLocalCacheBuiltinGoal
 from pants.goal.completion import CompletionBuiltinGoal
+from pants.goal.config_schema import ConfigSchemaBuiltinGoal
 from pants.goal.explorer import ExplorerBuiltinGoal
 from pants.goal.migrate_call_by_name import MigrateCallByNameBuiltinGoal
+from pants.goal.run_history import CompareRunsBuiltinGoal
 
 
 def register_builtin_goals(build_configuration: BuildConfiguration.Builder) -> None:
@@ -19,7 +22,10 @@ def register_builtin_goals(build_configuration: BuildConfiguration.Builder) -> N
 def builtin_goals() -> tuple[type[BuiltinGoal], ...]:
     return (
         BSPGoal,
+        ClearLocalCacheBuiltinGoal,
+        CompareRunsBuiltinGoal,
         CompletionBuiltinGoal,
+        ConfigSchemaBuiltinGoal,
         ExplorerBuiltinGoal,
         MigrateCallByNameBuiltinGoal,
         help.AllHelpBuiltinGoal,
@@ -0,0 +1,57 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+import logging
+
+from pants.base.exiter import PANTS_SUCCEEDED_EXIT_CODE, ExitCode
+from pants.base.specs import Specs
+from pants.build_graph.build_configuration import BuildConfiguration
+from pants.engine.unions import UnionMembership
+from pants.goal.builtin_goal import BuiltinGoal
+from pants.init.engine_initializer import GraphSession
+from pants.option.option_types import StrListOption
+from pants.option.options import Options
+from pants.util.strutil import softwrap
+
+logger = logging.getLogger(__name__)
+
+
+class ClearLocalCacheBuiltinGoal(BuiltinGoal):
+    name = "clear-local-cache"
+    help = softwrap(
+        """
+        Removes the entries of the given namespaces from the local process cache, along with any
+        entries which have expired.
+
+        Plugins may store the results of their processes in a namespace by setting
+        `local_cache_namespace` on a `Process`, and can invalidate all of their existing entries by
+        bumping its `local_cache_namespace_version`.
+        """
+    )
+
+    namespaces = StrListOption(
+        flag_name="--namespace",
+        default=[""],
+        help=softwrap(
+            """
+            The namespaces whose entries to remove. The results of the processes of Pants itself
+            are stored in the empty namespace, which is cleared by default.
+            """
+        ),
+    )
+
+    def run(
+        self,
+        *,
+        build_config: BuildConfiguration,
+        graph_session: GraphSession,
+        options: Options,
+        specs: Specs,
+        union_membership: UnionMembership,
+    ) -> ExitCode:
+        removed = graph_session.scheduler_session.clear_local_cache(self.namespaces)
+        for namespace, count in removed.items():
+            logger.info(f"Removed {count} entries of namespace `{namespace}` from the local cache.")
+        return PANTS_SUCCEEDED_EXIT_CODE
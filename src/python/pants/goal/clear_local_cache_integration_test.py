@@ -0,0 +1,11 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from pants.testutil.pants_integration_test import run_pants
+
+
+def test_clear_local_cache() -> None:
+    result = run_pants(["clear-local-cache", "--namespace=['', 'my-plugin']"])
+    result.assert_success()
+    assert "entries of namespace `` from the local cache." in result.stderr
+    assert "entries of namespace `my-plugin` from the local cache." in result.stderr
@@ -1,34 +1,40 @@
 # Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
 # Licensed under the Apache License, Version 2.0 (see LICENSE).
 
-# TODO: This was written as a port of the original bash script, but since we have
-# more knowledge of the options and goals, we can make this more robust and accurate (after tests are written).
-
 from __future__ import annotations
 
+import json
 import logging
+import os
 from enum import Enum
 
 from pants.base.exiter import PANTS_SUCCEEDED_EXIT_CODE, ExitCode
 from pants.base.specs import Specs
 from pants.build_graph.build_configuration import BuildConfiguration
+from pants.engine.internals import native_engine
 from pants.engine.unions import UnionMembership
 from pants.goal.builtin_goal import BuiltinGoal
+from pants.help.help_info_extracter import HelpInfoExtracter
 from pants.init.engine_initializer import GraphSession
 from pants.option.option_types import EnumOption
 from pants.option.options import Options
+from pants.option.parser import Parser
 from pants.option.scope import GLOBAL_SCOPE
-from pants.util.resources import read_resource
+from pants.util.dirutil import safe_concurrent_creation
 from pants.util.strutil import softwrap
-
-_COMPLETIONS_PACKAGE = "pants.goal"
+from pants.version import VERSION
 
 logger = logging.getLogger(__name__)
 
+# The file in the `pants_workdir` which caches the registered goals and options, for the native
+# client (see `src/rust/engine/client/src/completion.rs`).
+COMPLETION_CACHE_FILE = "completion-cache.json"
+
 
 class Shell(Enum):
     BASH = "bash"
     ZSH = "zsh"
+    FISH = "fish"
 
 
 class CompletionBuiltinGoal(BuiltinGoal):
@@ -37,9 +43,14 @@ class CompletionBuiltinGoal(BuiltinGoal):
         """
         Generates a completion script for the specified shell. The script is printed to stdout.
 
-        For example, `pants complete --zsh > pants-completions.zsh` will generate a zsh
-        completion script and write it to the file `my-pants-completions.zsh`. You can then
-        source this file in your `.zshrc` file to enable completion for Pants.
+        For example, `pants complete --shell=zsh > pants-completions.zsh` will generate a zsh
+        completion script and write it to the file `pants-completions.zsh`. You can then
+        source this file in your `.zshrc` file to enable completion for Pants. Completion scripts
+        can be generated for bash, zsh and fish.
+
+        The script completes the goals, the flags of each goal and subsystem, and the values of
+        options with a fixed set of choices, as registered in this repo (including by its plugins
+        and enabled backends). Regenerate it when those change.
 
         This command is also used by the completion scripts to generate the completion options using
         passthrough options. This usage is not intended for use by end users, but could be
@@ -48,6 +59,9 @@ class CompletionBuiltinGoal(BuiltinGoal):
         An example of this usage is in the bash completion script, where we use the following command:
         `pants complete -- ${COMP_WORDS[@]}`. This will generate the completion options for the
         current args, and then pass them to the bash completion script.
+
+        This usage also caches the registered goals and options in the `pants_workdir`, so that
+        later completions can be answered by the native client without running this goal.
         """
     )
 
@@ -67,7 +81,7 @@ class CompletionBuiltinGoal(BuiltinGoal):
     ) -> ExitCode:
         """This function is called under two main circumstances.
 
-        - By a user generating a completion script for their shell (e.g. `pants complete --zsh > pants-completions.zsh`)
+        - By a user generating a completion script for their shell (e.g. `pants complete --shell=zsh > pants-completions.zsh`)
         - By the shell completions script when the user attempts a tab completion (e.g. `pants <tab>`, `pants fmt lint che<tab>`, etc...)
 
         In the first case, we should generate a completion script for the specified shell and print it to stdout.
@@ -81,25 +95,56 @@ class CompletionBuiltinGoal(BuiltinGoal):
             completion_options = self._generate_completion_options(options)
             if completion_options:
                 print("\n".join(completion_options))
+            self._write_completion_cache(options)
             return PANTS_SUCCEEDED_EXIT_CODE
 
-        script = self._generate_completion_script(self.shell)
+        script = self._generate_completion_script(self.shell, options)
         print(script)
         return PANTS_SUCCEEDED_EXIT_CODE
 
-    def _generate_completion_script(self, shell: Shell) -> str:
+    def _generate_completion_script(self, shell: Shell, options: Options) -> str:
         """Generate a completion script for the specified shell.
 
-        Implementation note: In practice, we're just going to read in
-        and return the contents of the appropriate static completion script file.
+        The script is generated by the native options parser from the metadata of the registered
+        scopes and options, skipping deprecated ones.
 
         :param shell: The shell to generate a completion script for.
+        :param options: The options object for the current Pants run.
         :return: The completion script for the specified shell.
         """
-        if shell == Shell.ZSH:
-            return read_resource(_COMPLETIONS_PACKAGE, "pants-completion.zsh").decode("utf-8")
-        else:
-            return read_resource(_COMPLETIONS_PACKAGE, "pants-completion.bash").decode("utf-8")
+        deprecated_scopes = {
+            info.deprecated_scope
+            for info in options.known_scope_to_info.values()
+            if info.deprecated_scope
+        }
+        scopes = []
+        for scope, info in sorted(options.known_scope_to_info.items()):
+            if info.removal_version or scope in deprecated_scopes:
+                continue
+            scope_options = []
+            for args, kwargs in options.get_parser(scope).option_registrations_iter():
+                if kwargs.get("removal_version"):
+                    continue
+                flags = [arg for arg in args if arg.startswith("-")]
+                if not flags:
+                    continue
+                name_parts = flags[-1][2:].replace(".", "-").split("-")
+                switch = flags[0][1:] if len(flags) > 1 else None  # '-d' -> 'd'
+                option_id = native_engine.PyOptionId(
+                    *name_parts, scope=scope or "GLOBAL", switch=switch
+                )
+                scope_options.append(
+                    (
+                        option_id,
+                        Parser.is_bool(kwargs),
+                        kwargs.get("help", ""),
+                        list(HelpInfoExtracter.compute_choices(kwargs) or ()),
+                    )
+                )
+            scopes.append(
+                (scope or "GLOBAL", info.is_goal, info.description or "", scope_options)
+            )
+        return native_engine.generate_completion_script(shell.value, scopes)
 
     def _generate_completion_options(self, options: Options) -> list[str]:
         """Generate the completion options for the specified args.
@@ -170,15 +215,49 @@ class CompletionBuiltinGoal(BuiltinGoal):
         :param goal: The goal to build options for. Defaults to "" for the global scope.
         :return: A list of options for the specified goal.
         """
+        return [f"--{o}" for o in self._option_names(options, goal)]
 
-        if goal == GLOBAL_SCOPE:
-            global_options = sorted(options.for_global_scope().as_dict().keys())
-            return [f"--{o}" for o in global_options]
+    def _option_names(self, options: Options, scope: str) -> list[str]:
+        """The sorted names of the options of the specified scope, or none if it is unknown."""
+        if scope == GLOBAL_SCOPE:
+            return sorted(options.for_global_scope().as_dict().keys())
 
         try:
-            scoped_options = sorted(options.for_scope(goal).as_dict().keys())
-            return [f"--{o}" for o in scoped_options]
+            return sorted(options.for_scope(scope).as_dict().keys())
         except Exception:
             # options.for_scope will throw if the goal is unknown, so we'll just return an empty list
             # Since this is used for user-entered tab completion, it's not a warning or error
             return []
+
+    def _write_completion_cache(self, options: Options) -> None:
+        """Cache the registered goals and options, unless they are already cached.
+
+        The native client answers `pants complete -- <words>` from this cache the same way that
+        `_generate_completion_options` does, as long as the options which determine the registered
+        goals and options (which make up the key of the cache) are unchanged.
+        """
+        global_options = options.for_global_scope()
+        key = {
+            "pants_version": VERSION,
+            "backend_packages": list(global_options.backend_packages),
+            "plugins": list(global_options.plugins),
+            "pythonpath": list(global_options.pythonpath),
+        }
+        path = os.path.join(global_options.pants_workdir, COMPLETION_CACHE_FILE)
+        try:
+            with open(path) as f:
+                if json.load(f)["key"] == key:
+                    return
+        except (OSError, ValueError, KeyError, TypeError):
+            pass
+
+        cache = {
+            "key": key,
+            "goals": sorted(k for k, v in options.known_scope_to_info.items() if v.is_goal),
+            "scopes": {
+                scope: self._option_names(options, scope) for scope in options.known_scope_to_info
+            },
+        }
+        with safe_concurrent_creation(path) as tmp_path:
+            with open(tmp_path, "w") as f:
+                json.dump(cache, f)
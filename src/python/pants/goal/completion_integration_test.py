@@ -1,9 +1,13 @@
 # Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
 # Licensed under the Apache License, Version 2.0 (see LICENSE).
 
-from pants.goal.completion import CompletionBuiltinGoal
+import json
+import os
+
+from pants.goal.completion import COMPLETION_CACHE_FILE, CompletionBuiltinGoal
 from pants.option.option_value_container import OptionValueContainer
 from pants.testutil.pants_integration_test import PantsResult, run_pants
+from pants.version import VERSION
 
 
 def test_get_previous_goal():
@@ -41,6 +45,16 @@ def test_completion_script_generation():
     zsh_result.assert_success()
     assert "compdef" in zsh_result.stdout
 
+    fish_result = run_pants(["complete", "--shell=fish"])
+    fish_result.assert_success()
+    assert "complete -c pants" in fish_result.stdout
+
+    # The scripts are generated from the registered options.
+    for result in (bash_result, zsh_result, fish_result):
+        assert "--no-colors" in result.stdout
+        assert "--keep-sandboxes" in result.stdout
+        assert "on_failure" in result.stdout
+
     other_result = run_pants(["complete", "--shell=gibberish"])
     other_result.assert_failure()
 
@@ -90,3 +104,14 @@ def test_completions_with_options_on_invalid_goal():
     result = run_pants_complete(["unknown-goal", "-"])
     result.assert_success()
     assert result.stdout == ""
+
+
+def test_completions_are_cached() -> None:
+    result = run_pants_complete([""])
+    result.assert_success()
+    with open(os.path.join(result.workdir, COMPLETION_CACHE_FILE)) as f:
+        cache = json.load(f)
+    assert cache["key"]["pants_version"] == VERSION
+    assert cache["goals"] == result.stdout.splitlines()
+    assert all(o in cache["scopes"][""] for o in ("backend_packages", "colors", "loop"))
+    assert all(o in cache["scopes"]["fmt"] for o in ("batch_size", "only"))
@@ -0,0 +1,110 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+import json
+from typing import Any
+
+from pants.base.exiter import PANTS_SUCCEEDED_EXIT_CODE, ExitCode
+from pants.base.specs import Specs
+from pants.build_graph.build_configuration import BuildConfiguration
+from pants.engine.internals import native_engine
+from pants.engine.unions import UnionMembership
+from pants.goal.builtin_goal import BuiltinGoal
+from pants.help.help_info_extracter import HelpInfoExtracter
+from pants.init.engine_initializer import GraphSession
+from pants.option.native_options import native_option_type
+from pants.option.options import Options
+from pants.option.ranked_value import RankedValue
+from pants.util.strutil import softwrap
+
+
+def _deprecation(removal_version: str | None, removal_hint: str | None) -> str | None:
+    if not removal_version:
+        return None
+    return " ".join(filter(None, [f"Will be removed in version {removal_version}.", removal_hint]))
+
+
+class ConfigSchemaBuiltinGoal(BuiltinGoal):
+    name = "config-schema"
+    help = softwrap(
+        """
+        Generates a JSON Schema for `pants.toml` config files. The schema is printed to stdout.
+
+        For example, `pants config-schema > pants.schema.json` will write the schema to the file
+        `pants.schema.json`, which editors and IDEs can then use to validate and complete the
+        config files of this repo.
+
+        The schema describes the scopes and options registered in this repo (including by its
+        plugins and enabled backends), with their types, defaults, choices and deprecations.
+        Regenerate it when those change.
+        """
+    )
+
+    def run(
+        self,
+        *,
+        build_config: BuildConfiguration,
+        graph_session: GraphSession,
+        options: Options,
+        specs: Specs,
+        union_membership: UnionMembership,
+    ) -> ExitCode:
+        print(self._generate_config_schema(options))
+        return PANTS_SUCCEEDED_EXIT_CODE
+
+    def _generate_config_schema(self, options: Options) -> str:
+        """Generate the JSON Schema of config files, from the registered scopes and options.
+
+        The schema is generated by the native options parser, which is also the one that reads
+        config files.
+        """
+        # A deprecated scope is registered under its old name as well as its new one.
+        deprecated_scopes = {
+            info.deprecated_scope: (scope, info.deprecated_scope_removal_version)
+            for scope, info in options.known_scope_to_info.items()
+            if info.deprecated_scope
+        }
+        scopes = []
+        for scope, info in sorted(options.known_scope_to_info.items()):
+            if scope in deprecated_scopes:
+                new_scope, removal_version = deprecated_scopes[scope]
+                hint = f"Use the `[{new_scope}]` section instead."
+                deprecation = _deprecation(removal_version, hint) or hint
+            else:
+                deprecation = _deprecation(info.removal_version, info.removal_hint)
+            scope_options = []
+            for args, kwargs in options.get_parser(scope).option_registrations_iter():
+                flags = [arg for arg in args if arg.startswith("-")]
+                if not flags:
+                    continue
+                name_parts = flags[-1][2:].replace(".", "-").split("-")
+                switch = flags[0][1:] if len(flags) > 1 else None  # '-d' -> 'd'
+                option_id = native_engine.PyOptionId(
+                    *name_parts, scope=scope or "GLOBAL", switch=switch
+                )
+                default = kwargs["default"]
+                option_type, member_type, default = native_option_type(
+                    kwargs.get("type", str),
+                    kwargs.get("member_type"),
+                    default.value if isinstance(default, RankedValue) else default,
+                )
+                scope_options.append(
+                    (
+                        option_id,
+                        option_type.__name__,
+                        member_type.__name__ if option_type is list and member_type else None,
+                        self._to_json(default),
+                        kwargs.get("help", ""),
+                        list(HelpInfoExtracter.compute_choices(kwargs) or ()),
+                        _deprecation(kwargs.get("removal_version"), kwargs.get("removal_hint")),
+                    )
+                )
+            scopes.append((scope or "GLOBAL", info.description or "", deprecation, scope_options))
+        return native_engine.generate_config_schema(scopes)
+
+    @staticmethod
+    def _to_json(value: Any) -> str:
+        # Defaults of specialized types (e.g. in dicts) are rendered as strings.
+        return json.dumps(value, default=str)
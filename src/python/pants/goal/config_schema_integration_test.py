@@ -0,0 +1,32 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+import json
+
+from pants.testutil.pants_integration_test import run_pants
+
+
+def test_config_schema_generation() -> None:
+    result = run_pants(["config-schema"])
+    result.assert_success()
+    schema = json.loads(result.stdout)
+    assert schema["$schema"] == "https://json-schema.org/draft/2020-12/schema"
+
+    sections = schema["properties"]
+    assert {"DEFAULT", "GLOBAL", "include"} <= sections.keys()
+
+    global_options = sections["GLOBAL"]["properties"]
+    assert global_options["colors"]["anyOf"] == [{"type": "boolean"}, {"type": "string"}]
+    level = global_options["level"]
+    assert level["default"] == "info"
+    assert {"enum": ["trace", "debug", "info", "warn", "error"]} in level["anyOf"]
+    assert global_options["pantsd_max_memory_usage"]["default"]
+
+    # An option which is registered by a backend.
+    result = run_pants(["--backend-packages=pants.backend.python", "config-schema"])
+    result.assert_success()
+    python_options = json.loads(result.stdout)["properties"]["python"]["properties"]
+    assert python_options["interpreter_constraints"]["anyOf"][0] == {
+        "type": "array",
+        "items": {"type": "string"},
+    }
@@ -57,6 +57,7 @@ class HelpBuiltinGoalBase(BuiltinGoal):
             help_request=self.create_help_request(options),
             all_help_info=all_help_info,
             color=global_options.colors,
+            cli_aliases=options.for_scope("cli").alias.keys(),
         )
         return help_printer.print_help()
 
@@ -0,0 +1,265 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+"""Writes the provenance which the engine records for the outputs of processes as attestations.
+
+Each process with outputs which match `[GLOBAL].provenance_outputs` is attested by an in-toto
+Statement with a SLSA provenance predicate, whose subjects are those outputs. Each Statement is
+wrapped in a DSSE envelope, which is signed by an external command (so that no keys are handled by
+Pants itself), and the envelopes of a run are written as an in-toto bundle (JSON Lines).
+"""
+
+from __future__ import annotations
+
+import base64
+import json
+import logging
+import os
+import subprocess
+from dataclasses import dataclass
+from typing import Any, Callable, Iterable
+
+from pants.engine.internals.scheduler import Workunit
+from pants.engine.rules import collect_rules, rule
+from pants.engine.streaming_workunit_handler import (
+    StreamingWorkunitContext,
+    WorkunitsCallback,
+    WorkunitsCallbackFactory,
+    WorkunitsCallbackFactoryRequest,
+)
+from pants.engine.unions import UnionRule
+from pants.option.global_options import GlobalOptions
+from pants.option.option_types import StrListOption, StrOption
+from pants.option.subsystem import Subsystem
+from pants.util.dirutil import safe_open
+from pants.util.strutil import softwrap
+from pants.version import VERSION
+
+logger = logging.getLogger(__name__)
+
+STATEMENT_TYPE = "https://in-toto.io/Statement/v1"
+PREDICATE_TYPE = "https://slsa.dev/provenance/v1"
+PAYLOAD_TYPE = "application/vnd.in-toto+json"
+BUILD_TYPE = "https://www.pantsbuild.org/provenance/process/v1"
+BUILDER_ID = "https://www.pantsbuild.org/provenance/builder/v1"
+
+
+class ProvenanceSubsystem(Subsystem):
+    options_scope = "provenance"
+    help = softwrap(
+        """
+        Writes signed SLSA provenance attestations for the outputs of the processes of a run
+        which match `[GLOBAL].provenance_outputs`.
+        """
+    )
+
+    output_dir = StrOption(
+        default=None,
+        metavar="<dir>",
+        help=softwrap(
+            """
+            The directory to write attestations to, as `<run id>.intoto.jsonl`: one DSSE envelope
+            per attested process, each of which wraps an in-toto Statement whose subjects are the
+            outputs of the process.
+
+            Defaults to `provenance` in `[GLOBAL].pants_distdir`.
+            """
+        ),
+    )
+    signing_command = StrListOption(
+        metavar="<argv>",
+        help=softwrap(
+            """
+            The argv of a command which signs attestations: it is run once per attestation, with
+            the DSSE pre-authentication encoding of the attestation on stdin, and must write the
+            raw signature to stdout (e.g. `["openssl", "pkeyutl", "-sign", "-inkey", "key.pem"]`).
+
+            If empty, attestations are written unsigned.
+            """
+        ),
+    )
+    key_id = StrOption(
+        default=None,
+        help="The `keyid` to record in the signatures of attestations, which identifies the key.",
+    )
+
+
+def _resource(name: str, digest: dict[str, Any], *, directory: bool = False) -> dict[str, Any]:
+    # NB: The digests of Pants are SHA-256: those of directories are of their REAPI `Directory`
+    # protos, rather than of their contents.
+    resource: dict[str, Any] = {"name": name, "digest": {"sha256": digest["fingerprint"]}}
+    if directory:
+        resource["annotations"] = {"directory": True, "size_bytes": digest["size_bytes"]}
+    return resource
+
+
+def provenance_statement(record: dict[str, Any], *, invocation_id: str) -> dict[str, Any]:
+    """Converts a provenance record of the engine into an in-toto Statement."""
+    definition = record["definition"]
+    input_digests = definition["input_digests"]
+    resolved_dependencies = [
+        _resource("inputs", input_digests["inputs"]["digest"], directory=True),
+        # Immutable inputs are generally the tools which a process runs, and identify them more
+        # precisely than a version would.
+        *(
+            _resource(path, digest["digest"], directory=True)
+            for path, digest in sorted(input_digests["immutable_inputs"].items())
+        ),
+    ]
+    return {
+        "_type": STATEMENT_TYPE,
+        "subject": [_resource(output["path"], output["digest"]) for output in record["outputs"]],
+        "predicateType": PREDICATE_TYPE,
+        "predicate": {
+            "buildDefinition": {
+                "buildType": BUILD_TYPE,
+                "externalParameters": {
+                    "description": record["description"],
+                    "argv": definition["argv"],
+                    "env": definition["env"],
+                    "working_directory": definition["working_directory"],
+                },
+                "internalParameters": {
+                    "action_digest": record["action_digest"],
+                    "environment": record["environment"],
+                    # Whether the process ran during this run, or its result was hit in a cache.
+                    "source": record["source"],
+                },
+                "resolvedDependencies": resolved_dependencies,
+            },
+            "runDetails": {
+                "builder": {"id": BUILDER_ID, "version": {"pants": VERSION}},
+                "metadata": {"invocationId": invocation_id},
+            },
+        },
+    }
+
+
+def pre_authentication_encoding(payload_type: str, payload: bytes) -> bytes:
+    """The DSSE encoding of a payload which is signed, which binds its type to it."""
+    payload_type_bytes = payload_type.encode()
+    return b"DSSEv1 %d %b %d %b" % (
+        len(payload_type_bytes),
+        payload_type_bytes,
+        len(payload),
+        payload,
+    )
+
+
+def dsse_envelope(
+    statement: dict[str, Any], *, sign: Callable[[bytes], bytes] | None, key_id: str | None
+) -> dict[str, Any]:
+    """Wraps the Statement in a DSSE envelope, which is signed by `sign` (if given)."""
+    payload = json.dumps(statement, sort_keys=True).encode()
+    signatures: list[dict[str, str]] = []
+    if sign is not None:
+        signature = sign(pre_authentication_encoding(PAYLOAD_TYPE, payload))
+        signatures.append(
+            {"keyid": key_id or "", "sig": base64.standard_b64encode(signature).decode()}
+        )
+    return {
+        "payloadType": PAYLOAD_TYPE,
+        "payload": base64.standard_b64encode(payload).decode(),
+        "signatures": signatures,
+    }
+
+
+def _command_signer(argv: tuple[str, ...]) -> Callable[[bytes], bytes]:
+    def sign(data: bytes) -> bytes:
+        return subprocess.run(argv, input=data, capture_output=True, check=True).stdout
+
+    return sign
+
+
+class ProvenanceCallback(WorkunitsCallback):
+    def __init__(
+        self, *, output_dir: str, signing_command: tuple[str, ...], key_id: str | None
+    ) -> None:
+        self.output_dir = output_dir
+        self.signing_command = signing_command
+        self.key_id = key_id
+
+    @property
+    def can_finish_async(self) -> bool:
+        # The attestations must be complete before the run exits.
+        return False
+
+    def _envelopes(self, records: Iterable[dict[str, Any]], invocation_id: str) -> list[str]:
+        sign = _command_signer(self.signing_command) if self.signing_command else None
+        return [
+            json.dumps(
+                dsse_envelope(
+                    provenance_statement(record, invocation_id=invocation_id),
+                    sign=sign,
+                    key_id=self.key_id,
+                )
+            )
+            for record in records
+        ]
+
+    def __call__(
+        self,
+        *,
+        started_workunits: tuple[Workunit, ...],
+        completed_workunits: tuple[Workunit, ...],
+        finished: bool,
+        context: StreamingWorkunitContext,
+    ) -> None:
+        if not finished:
+            return
+        records = context.get_provenance()
+        if not records:
+            return
+
+        run_id = context.run_tracker.run_id
+        try:
+            envelopes = self._envelopes(records, run_id)
+        except subprocess.CalledProcessError as e:
+            # A partially signed bundle would not be trustworthy: write none of it.
+            logger.error(
+                f"Failed to sign the provenance of this run with `{' '.join(e.cmd)}` (exit code "
+                f"{e.returncode}): {e.stderr.decode(errors='replace')}"
+            )
+            return
+        if not self.signing_command:
+            logger.warning(
+                "Writing unsigned provenance, because `[provenance].signing_command` is not set."
+            )
+
+        output_file = os.path.join(self.output_dir, f"{run_id}.intoto.jsonl")
+        with safe_open(output_file, "w") as fh:
+            fh.writelines(f"{envelope}\n" for envelope in envelopes)
+        logger.info(f"Wrote the provenance of {len(envelopes)} processes to {output_file}")
+
+
+@dataclass(frozen=True)
+class ProvenanceCallbackFactoryRequest:
+    """A unique request type that is installed to trigger construction of the WorkunitsCallback."""
+
+
+@rule
+def construct_callback(
+    _: ProvenanceCallbackFactoryRequest,
+    subsystem: ProvenanceSubsystem,
+    global_options: GlobalOptions,
+) -> WorkunitsCallbackFactory:
+    enabled = bool(global_options.provenance_outputs)
+    output_dir = subsystem.output_dir or os.path.join(global_options.pants_distdir, "provenance")
+    signing_command = tuple(subsystem.signing_command)
+    key_id = subsystem.key_id
+    return WorkunitsCallbackFactory(
+        lambda: (
+            ProvenanceCallback(
+                output_dir=output_dir, signing_command=signing_command, key_id=key_id
+            )
+            if enabled
+            else None
+        )
+    )
+
+
+def rules():
+    return [
+        UnionRule(WorkunitsCallbackFactoryRequest, ProvenanceCallbackFactoryRequest),
+        *collect_rules(),
+    ]
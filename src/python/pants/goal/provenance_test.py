@@ -0,0 +1,70 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+import base64
+import hashlib
+import json
+
+from pants.engine.process import Process, ProcessResult
+from pants.goal.provenance import (
+    PAYLOAD_TYPE,
+    PREDICATE_TYPE,
+    dsse_envelope,
+    pre_authentication_encoding,
+    provenance_statement,
+)
+from pants.testutil.rule_runner import QueryRule, RuleRunner
+
+
+def test_pre_authentication_encoding() -> None:
+    # The example from the DSSE specification.
+    assert (
+        pre_authentication_encoding("http://example.com/HelloWorld", b"hello world")
+        == b"DSSEv1 29 http://example.com/HelloWorld 11 hello world"
+    )
+
+
+def test_dsse_envelope() -> None:
+    statement = {"_type": "test"}
+    signed: list[bytes] = []
+
+    def sign(data: bytes) -> bytes:
+        signed.append(data)
+        return b"signature"
+
+    envelope = dsse_envelope(statement, sign=sign, key_id="key")
+    payload = base64.standard_b64decode(envelope["payload"])
+    assert json.loads(payload) == statement
+    assert envelope["payloadType"] == PAYLOAD_TYPE
+    assert signed == [pre_authentication_encoding(PAYLOAD_TYPE, payload)]
+    assert envelope["signatures"] == [
+        {"keyid": "key", "sig": base64.standard_b64encode(b"signature").decode()}
+    ]
+
+    assert dsse_envelope(statement, sign=None, key_id=None)["signatures"] == []
+
+
+def test_records_provenance_of_matching_outputs() -> None:
+    rule_runner = RuleRunner(
+        rules=[QueryRule(ProcessResult, [Process])],
+        bootstrap_args=["--provenance-outputs=['*.txt', '!skipped.txt']"],
+    )
+    process = Process(
+        argv=("/bin/bash", "-c", "echo hello > out.txt; touch skipped.txt out.bin"),
+        description="write some outputs",
+        output_files=("out.txt", "skipped.txt", "out.bin"),
+    )
+    rule_runner.request(ProcessResult, [process])
+
+    (record,) = rule_runner.scheduler.get_provenance()
+    statement = provenance_statement(record, invocation_id="run")
+    assert statement["predicateType"] == PREDICATE_TYPE
+    assert statement["subject"] == [
+        {"name": "out.txt", "digest": {"sha256": hashlib.sha256(b"hello\n").hexdigest()}}
+    ]
+    build_definition = statement["predicate"]["buildDefinition"]
+    assert build_definition["externalParameters"]["argv"] == list(process.argv)
+    assert build_definition["externalParameters"]["description"] == "write some outputs"
+    assert statement["predicate"]["runDetails"]["metadata"] == {"invocationId": "run"}
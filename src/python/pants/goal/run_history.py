@@ -0,0 +1,689 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+import json
+import logging
+import os
+import re
+import sqlite3
+import statistics
+import time
+from collections import defaultdict
+from dataclasses import dataclass
+from typing import Any, Iterable, Mapping, Sequence
+
+from pants.base.exiter import PANTS_FAILED_EXIT_CODE, PANTS_SUCCEEDED_EXIT_CODE, ExitCode
+from pants.base.specs import Specs
+from pants.build_graph.build_configuration import BuildConfiguration
+from pants.engine.internals.scheduler import Workunit
+from pants.engine.rules import collect_rules, rule
+from pants.engine.streaming_workunit_handler import (
+    StreamingWorkunitContext,
+    WorkunitsCallback,
+    WorkunitsCallbackFactory,
+    WorkunitsCallbackFactoryRequest,
+)
+from pants.engine.unions import UnionMembership, UnionRule
+from pants.goal.builtin_goal import BuiltinGoal
+from pants.init.engine_initializer import GraphSession
+from pants.option.global_options import GlobalOptions
+from pants.option.option_types import BoolOption, FloatOption, IntOption, StrOption
+from pants.option.option_value_container import OptionValueContainer
+from pants.option.options import Options
+from pants.option.subsystem import Subsystem
+from pants.util.dirutil import safe_mkdir_for
+from pants.util.frozendict import FrozenDict
+from pants.util.strutil import softwrap
+
+logger = logging.getLogger(__name__)
+
+
+class RunHistorySubsystem(Subsystem):
+    options_scope = "run-history"
+    help = softwrap(
+        """
+        Records a summary of the performance of each run in a local history, so that runs can be
+        compared against a baseline of previous runs with `pants compare-runs`.
+
+        The summary of a run includes its wall time, the wall time of each of its goals, the time
+        spent in each kind of workunit on its critical path, and the hit rates of the local and
+        remote caches.
+        """
+    )
+
+    record = BoolOption(
+        default=False,
+        help=softwrap(
+            """
+            Whether to record a summary of each run in the history.
+
+            Summaries are only comparable if workunits are retained at the same
+            `[GLOBAL].streaming_workunits_level`.
+            """
+        ),
+    )
+    path = StrOption(
+        default=None,
+        metavar="<path>",
+        help=softwrap(
+            """
+            The path of the SQLite database of the history. If unset, the history is stored in
+            `run_history.db` under `[GLOBAL].pants_workdir`.
+            """
+        ),
+        advanced=True,
+    )
+    max_runs = IntOption(
+        default=1000,
+        help="The number of runs to keep in the history: older runs are removed.",
+        advanced=True,
+    )
+    check = BoolOption(
+        default=False,
+        help=softwrap(
+            """
+            At the end of each recorded run, compare it against its baseline (see
+            `[run-history].baseline_runs`), and warn about any regressions.
+            """
+        ),
+    )
+    baseline_runs = IntOption(
+        default=5,
+        help=softwrap(
+            """
+            The baseline of a run is the median of each metric over this many of the most recent
+            previous successful runs of the same goals.
+            """
+        ),
+    )
+    regression_threshold = FloatOption(
+        default=0.2,
+        help=softwrap(
+            """
+            The fraction by which a time must exceed its baseline to be a regression, e.g. `0.2`
+            for 20%.
+            """
+        ),
+    )
+    min_regression_secs = FloatOption(
+        default=1.0,
+        help=softwrap(
+            """
+            The number of seconds by which a time must exceed its baseline to be a regression, so
+            that small times which vary a lot (relative to their size) are not flagged.
+            """
+        ),
+    )
+    estimate = BoolOption(
+        default=True,
+        help=softwrap(
+            """
+            Whether to display an estimate of the time remaining of each recorded run in the
+            dynamic UI.
+
+            The estimate is from the median wall time of the baseline of the run (see
+            `[run-history].baseline_runs`), and from the durations of the processes of previous
+            runs: a run is not expected to complete before the running process which is expected
+            to complete last (its critical path) does.
+            """
+        ),
+    )
+    hit_rate_regression_threshold = FloatOption(
+        default=0.1,
+        help=softwrap(
+            """
+            The amount by which a cache hit rate must fall below its baseline to be a regression,
+            e.g. `0.1` for a drop from 90% to 80%.
+            """
+        ),
+    )
+
+
+def history_path(path: str | None, pants_workdir: str) -> str:
+    return path or os.path.join(pants_workdir, "run_history.db")
+
+
+@dataclass(frozen=True)
+class RunSummary:
+    """The performance metrics of a run, by name.
+
+    The metrics are:
+      * `wall_time`: the wall time of the run.
+      * `goal:<goal>`: the wall time of each goal of the run.
+      * `critical_path:<workunit name>`: the time on the critical path of the run which was spent
+        in workunits with the name (for the names which took the most time).
+      * `cache_hit_rate:local` and `cache_hit_rate:remote`: the fraction of cache lookups which hit,
+        if there were any lookups.
+
+    Times are in seconds.
+    """
+
+    run_id: str
+    timestamp: float
+    command: str
+    goals: tuple[str, ...]
+    outcome: str
+    metrics: FrozenDict[str, float]
+
+
+def _start_and_end_nanos(workunit: Workunit) -> tuple[int, int]:
+    start = workunit["start_secs"] * 1_000_000_000 + workunit["start_nanos"]
+    duration = workunit.get("duration_secs", 0) * 1_000_000_000 + workunit.get("duration_nanos", 0)
+    return start, start + duration
+
+
+def critical_path(workunits: Iterable[Workunit]) -> dict[str, float]:
+    """The time on the critical path of the given completed workunits, by workunit name.
+
+    The critical path is found by walking backwards from the end of the workunit which ended last:
+    each workunit is on the critical path while the child which ends last before that point runs,
+    and any remaining time is attributed to the workunit itself.
+    """
+    workunits_by_id = {workunit["span_id"]: workunit for workunit in workunits}
+    children: dict[str, list[Workunit]] = defaultdict(list)
+    roots = []
+    for workunit in workunits_by_id.values():
+        parent_ids = [p for p in workunit.get("parent_ids", ()) if p in workunits_by_id]
+        if parent_ids:
+            children[parent_ids[0]].append(workunit)
+        else:
+            roots.append(workunit)
+
+    result: dict[str, float] = defaultdict(float)
+    # The workunits on the critical path which remain to be walked, with the time until which they
+    # are on it.
+    stack = []
+    if roots:
+        last_root = max(roots, key=lambda w: _start_and_end_nanos(w)[1])
+        stack.append((last_root, _start_and_end_nanos(last_root)[1]))
+    while stack:
+        workunit, end = stack.pop()
+        start = _start_and_end_nanos(workunit)[0]
+        cursor = end
+        own_nanos = 0
+        for child in sorted(
+            children[workunit["span_id"]], key=lambda w: _start_and_end_nanos(w)[1], reverse=True
+        ):
+            child_start, child_end = _start_and_end_nanos(child)
+            if child_end > cursor or child_start == child_end:
+                # The child was still running (or took no time), so it did not delay its parent.
+                continue
+            own_nanos += cursor - child_end
+            stack.append((child, child_end))
+            cursor = child_start
+        own_nanos += max(cursor - start, 0)
+        result[workunit["name"]] += own_nanos / 1_000_000_000
+    return dict(result)
+
+
+_GOAL_DESCRIPTION = re.compile(r"^`(?P<goal>[^`]+)` goal$")
+
+_HIT_RATE_PREFIX = "cache_hit_rate:"
+
+
+def process_durations(workunits: Iterable[Workunit]) -> dict[str, float]:
+    """The duration of each of the given completed processes, by description.
+
+    If processes have the same description, the longest of them is used.
+    """
+    durations: dict[str, float] = {}
+    for workunit in workunits:
+        description = workunit.get("description")
+        if workunit["name"] != "process" or not description:
+            continue
+        start, end = _start_and_end_nanos(workunit)
+        durations[description] = max(durations.get(description, 0.0), (end - start) / 1_000_000_000)
+    return durations
+
+
+def summarize(
+    *,
+    run_information: Mapping[str, Any],
+    goals: Sequence[str],
+    wall_secs: float,
+    counters: Mapping[str, int],
+    workunits: Iterable[Workunit],
+    critical_path_entries: int = 10,
+) -> RunSummary:
+    """Summarizes a run from the information of its `RunTracker`, its counters, and its completed
+    workunits."""
+    workunits = list(workunits)
+    metrics: dict[str, float] = {"wall_time": wall_secs}
+    for workunit in workunits:
+        match = _GOAL_DESCRIPTION.match(workunit.get("description") or "")
+        if match:
+            start, end = _start_and_end_nanos(workunit)
+            metrics[f"goal:{match['goal']}"] = (end - start) / 1_000_000_000
+
+    path = sorted(critical_path(workunits).items(), key=lambda item: item[1], reverse=True)
+    metrics.update((f"critical_path:{name}", secs) for name, secs in path[:critical_path_entries])
+
+    for cache in ("local", "remote"):
+        requests = counters.get(f"{cache}_cache_requests", 0)
+        if requests:
+            hits = counters.get(f"{cache}_cache_requests_cached", 0)
+            metrics[f"{_HIT_RATE_PREFIX}{cache}"] = hits / requests
+
+    return RunSummary(
+        run_id=run_information["id"],
+        timestamp=run_information["timestamp"],
+        command=run_information.get("cmd_line", ""),
+        goals=tuple(goals),
+        outcome=run_information.get("outcome", "UNKNOWN"),
+        metrics=FrozenDict(metrics),
+    )
+
+
+class RunHistory:
+    """The summaries of previous runs, stored in a SQLite database."""
+
+    def __init__(self, path: str) -> None:
+        safe_mkdir_for(path)
+        self._connection = sqlite3.connect(path)
+        with self._connection:
+            self._connection.execute(
+                """
+                CREATE TABLE IF NOT EXISTS runs (
+                    run_id TEXT PRIMARY KEY,
+                    timestamp REAL NOT NULL,
+                    command TEXT NOT NULL,
+                    goals TEXT NOT NULL,
+                    outcome TEXT NOT NULL,
+                    metrics TEXT NOT NULL
+                )
+                """
+            )
+            self._connection.execute(
+                """
+                CREATE TABLE IF NOT EXISTS process_durations (
+                    description TEXT PRIMARY KEY,
+                    duration REAL NOT NULL,
+                    timestamp REAL NOT NULL
+                )
+                """
+            )
+
+    def close(self) -> None:
+        self._connection.close()
+
+    def record(
+        self,
+        summary: RunSummary,
+        *,
+        max_runs: int,
+        process_durations: Mapping[str, float] = FrozenDict(),
+    ) -> None:
+        """Records the summary of a run and the durations of its processes, and removes the oldest
+        runs beyond `max_runs`.
+
+        The recorded duration of a process is the mean of its duration in the run and its
+        previously recorded duration, so that it follows changes without being swayed by a single
+        run. The durations of processes which have not run since the oldest kept run are removed.
+        """
+        with self._connection:
+            self._connection.execute(
+                "INSERT OR REPLACE INTO runs VALUES (?, ?, ?, ?, ?, ?)",
+                (
+                    summary.run_id,
+                    summary.timestamp,
+                    summary.command,
+                    " ".join(summary.goals),
+                    summary.outcome,
+                    json.dumps(dict(summary.metrics), sort_keys=True),
+                ),
+            )
+            self._connection.execute(
+                """
+                DELETE FROM runs WHERE run_id NOT IN (
+                    SELECT run_id FROM runs ORDER BY timestamp DESC LIMIT ?
+                )
+                """,
+                (max_runs,),
+            )
+            self._connection.executemany(
+                """
+                INSERT INTO process_durations VALUES (?, ?, ?)
+                ON CONFLICT(description) DO UPDATE SET
+                    duration = (duration + excluded.duration) / 2,
+                    timestamp = excluded.timestamp
+                """,
+                (
+                    (description, duration, summary.timestamp)
+                    for description, duration in process_durations.items()
+                ),
+            )
+            self._connection.execute(
+                "DELETE FROM process_durations WHERE timestamp < (SELECT MIN(timestamp) FROM runs)"
+            )
+
+    def process_durations(self) -> dict[str, float]:
+        """The recorded duration of each process, by description."""
+        rows = self._connection.execute("SELECT description, duration FROM process_durations")
+        return dict(rows)
+
+    def _query(self, where: str, parameters: Sequence[Any], limit: int) -> list[RunSummary]:
+        rows = self._connection.execute(
+            f"SELECT * FROM runs {where} ORDER BY timestamp DESC LIMIT ?", (*parameters, limit)
+        )
+        return [
+            RunSummary(
+                run_id=run_id,
+                timestamp=timestamp,
+                command=command,
+                goals=tuple(goals.split()),
+                outcome=outcome,
+                metrics=FrozenDict(json.loads(metrics)),
+            )
+            for run_id, timestamp, command, goals, outcome, metrics in rows
+        ]
+
+    def get(self, run_id: str) -> RunSummary | None:
+        runs = self._query("WHERE run_id = ?", (run_id,), limit=1)
+        return runs[0] if runs else None
+
+    def latest(self) -> RunSummary | None:
+        runs = self._query("", (), limit=1)
+        return runs[0] if runs else None
+
+    def baseline_runs(self, summary: RunSummary, *, count: int) -> list[RunSummary]:
+        """The most recent successful runs of the same goals before the given run."""
+        return self.runs_of_goals(
+            summary.goals, before=summary.timestamp, count=count, excluding=summary.run_id
+        )
+
+    def runs_of_goals(
+        self, goals: Sequence[str], *, before: float, count: int, excluding: str = ""
+    ) -> list[RunSummary]:
+        """The most recent successful runs of the given goals before the given timestamp."""
+        return self._query(
+            "WHERE goals = ? AND outcome = 'SUCCESS' AND timestamp < ? AND run_id != ?",
+            (" ".join(goals), before, excluding),
+            limit=count,
+        )
+
+
+def median_metrics(summaries: Iterable[RunSummary]) -> dict[str, float]:
+    """The median of each metric over the given runs (among those runs which have the metric)."""
+    values: dict[str, list[float]] = defaultdict(list)
+    for summary in summaries:
+        for name, value in summary.metrics.items():
+            values[name].append(value)
+    return {name: statistics.median(v) for name, v in values.items()}
+
+
+@dataclass(frozen=True)
+class MetricComparison:
+    name: str
+    baseline: float
+    current: float
+    regression: bool
+
+    @property
+    def is_hit_rate(self) -> bool:
+        return self.name.startswith(_HIT_RATE_PREFIX)
+
+    def format(self) -> str:
+        if self.is_hit_rate:
+            values = f"{self.baseline:.1%} -> {self.current:.1%}"
+            change = f"{(self.current - self.baseline) * 100:+.1f} points"
+        else:
+            values = f"{self.baseline:.2f}s -> {self.current:.2f}s"
+            change = f"{self.current / self.baseline - 1:+.1%}" if self.baseline else "new time"
+        flag = "  REGRESSION" if self.regression else ""
+        return f"{self.name}: {values} ({change}){flag}"
+
+
+def compare(
+    current: Mapping[str, float],
+    baseline: Mapping[str, float],
+    *,
+    regression_threshold: float,
+    min_regression_secs: float,
+    hit_rate_regression_threshold: float,
+) -> list[MetricComparison]:
+    """Compares the metrics which are in both the current run and its baseline.
+
+    A time is a regression if it exceeds its baseline by both `regression_threshold` (as a fraction
+    of the baseline) and `min_regression_secs`, and a hit rate is a regression if it falls below its
+    baseline by `hit_rate_regression_threshold`.
+    """
+    comparisons = []
+    for name in sorted(current.keys() & baseline.keys()):
+        before, after = baseline[name], current[name]
+        if name.startswith(_HIT_RATE_PREFIX):
+            regression = before - after >= hit_rate_regression_threshold
+        else:
+            increase = after - before
+            regression = (
+                increase >= min_regression_secs and increase > before * regression_threshold
+            )
+        comparisons.append(MetricComparison(name, before, after, regression))
+    return comparisons
+
+
+def _compare_with_subsystem_thresholds(
+    subsystem: RunHistorySubsystem | OptionValueContainer,
+    current: RunSummary,
+    baseline: Mapping[str, float],
+) -> list[MetricComparison]:
+    return compare(
+        current.metrics,
+        baseline,
+        regression_threshold=subsystem.regression_threshold,
+        min_regression_secs=subsystem.min_regression_secs,
+        hit_rate_regression_threshold=subsystem.hit_rate_regression_threshold,
+    )
+
+
+def estimate_remaining_secs(
+    baseline_runs: Iterable[RunSummary], *, elapsed_secs: float
+) -> float | None:
+    """The time remaining of a run which has run for `elapsed_secs`, from the median wall time of
+    its baseline runs (if there are any)."""
+    wall_time = median_metrics(baseline_runs).get("wall_time")
+    return None if wall_time is None else max(wall_time - elapsed_secs, 0.0)
+
+
+def _is_recorded(goals: Sequence[str]) -> bool:
+    return bool(goals) and CompareRunsBuiltinGoal.name not in goals
+
+
+class RunHistoryCallback(WorkunitsCallback):
+    """Records the summary of the run in the history once it finishes, and estimates the time
+    remaining of the run from the history once it starts."""
+
+    def __init__(self, subsystem: RunHistorySubsystem, path: str) -> None:
+        self.subsystem = subsystem
+        self.path = path
+        self._estimated = not subsystem.estimate
+
+    @property
+    def can_finish_async(self) -> bool:
+        # We may warn about regressions in the final call.
+        return not self.subsystem.check
+
+    @property
+    def retains_completed_workunits(self) -> bool:
+        return True
+
+    def __call__(
+        self,
+        *,
+        started_workunits: tuple[Workunit, ...],
+        completed_workunits: tuple[Workunit, ...],
+        finished: bool,
+        context: StreamingWorkunitContext,
+    ) -> None:
+        goals = context.run_tracker.goals
+        if not _is_recorded(goals):
+            return
+        if not finished:
+            if not self._estimated:
+                self._estimated = True
+                self._estimate(goals, context)
+            return
+
+        run_information = context.run_tracker.run_information()
+        summary = summarize(
+            run_information=run_information,
+            goals=goals,
+            wall_secs=time.time() - run_information["timestamp"],
+            counters=context.get_metrics(),
+            workunits=context.completed_workunits(),
+        )
+        history = RunHistory(self.path)
+        try:
+            history.record(
+                summary,
+                max_runs=self.subsystem.max_runs,
+                process_durations=process_durations(context.completed_workunits()),
+            )
+            if not self.subsystem.check:
+                return
+            baseline_runs = history.baseline_runs(summary, count=self.subsystem.baseline_runs)
+        finally:
+            history.close()
+        if not baseline_runs:
+            return
+
+        comparisons = _compare_with_subsystem_thresholds(
+            self.subsystem, summary, median_metrics(baseline_runs)
+        )
+        regressions = "\n".join(f"  {c.format()}" for c in comparisons if c.regression)
+        if regressions:
+            logger.warning(
+                f"This run regressed against the median of its {len(baseline_runs)} previous "
+                f"runs:\n{regressions}\nRun `pants compare-runs` for the full comparison."
+            )
+
+    def _estimate(self, goals: Sequence[str], context: StreamingWorkunitContext) -> None:
+        start_time = context.run_tracker.run_information().get("timestamp")
+        if start_time is None:
+            return
+        history = RunHistory(self.path)
+        try:
+            baseline_runs = history.runs_of_goals(
+                goals, before=start_time, count=self.subsystem.baseline_runs
+            )
+            remaining_secs = estimate_remaining_secs(
+                baseline_runs, elapsed_secs=time.time() - start_time
+            )
+            if remaining_secs is None:
+                return
+            durations = history.process_durations()
+        finally:
+            history.close()
+        context.set_run_estimate(remaining_secs, durations)
+
+
+@dataclass(frozen=True)
+class RunHistoryCallbackFactoryRequest:
+    """A unique request type that is installed to trigger construction of the WorkunitsCallback."""
+
+
+@rule
+def construct_callback(
+    _: RunHistoryCallbackFactoryRequest,
+    subsystem: RunHistorySubsystem,
+    global_options: GlobalOptions,
+) -> WorkunitsCallbackFactory:
+    path = history_path(subsystem.path, global_options.pants_workdir)
+    return WorkunitsCallbackFactory(
+        lambda: RunHistoryCallback(subsystem, path) if subsystem.record else None
+    )
+
+
+class CompareRunsBuiltinGoal(BuiltinGoal):
+    name = "compare-runs"
+    help = softwrap(
+        f"""
+        Compares the performance of a run recorded in the history (the latest one by default)
+        against a baseline, and fails if any of its metrics regressed.
+
+        Runs are only recorded if `[{RunHistorySubsystem.options_scope}].record` is set. The
+        thresholds for regressions are set by the `[{RunHistorySubsystem.options_scope}]` options.
+        """
+    )
+
+    run_id = StrOption(
+        flag_name="--run",
+        default=None,
+        metavar="<run id>",
+        help="The id of the run to compare. Defaults to the latest recorded run.",
+    )
+    baseline = StrOption(
+        default=None,
+        metavar="<run id>",
+        help=softwrap(
+            f"""
+            The id of the run to compare against. Defaults to the median of the
+            `[{RunHistorySubsystem.options_scope}].baseline_runs` previous successful runs of the
+            same goals.
+            """
+        ),
+    )
+
+    def run(
+        self,
+        *,
+        build_config: BuildConfiguration,
+        graph_session: GraphSession,
+        options: Options,
+        specs: Specs,
+        union_membership: UnionMembership,
+    ) -> ExitCode:
+        subsystem = options.for_scope(RunHistorySubsystem.options_scope)
+        pants_workdir = options.for_global_scope().pants_workdir
+        history = RunHistory(history_path(subsystem.path, pants_workdir))
+        try:
+            current = history.get(self.run_id) if self.run_id else history.latest()
+            if current is None:
+                logger.error(
+                    f"The run `{self.run_id}` is not in the history."
+                    if self.run_id
+                    else softwrap(
+                        f"""
+                        No runs have been recorded in the history: set
+                        `[{RunHistorySubsystem.options_scope}].record` to record them.
+                        """
+                    )
+                )
+                return PANTS_FAILED_EXIT_CODE
+
+            if self.baseline:
+                baseline_run = history.get(self.baseline)
+                if baseline_run is None:
+                    logger.error(f"The baseline run `{self.baseline}` is not in the history.")
+                    return PANTS_FAILED_EXIT_CODE
+                baseline_runs = [baseline_run]
+                description = f"run {baseline_run.run_id} (`{baseline_run.command}`)"
+            else:
+                baseline_runs = history.baseline_runs(current, count=subsystem.baseline_runs)
+                description = f"the median of {len(baseline_runs)} previous runs"
+        finally:
+            history.close()
+
+        if not baseline_runs:
+            print(f"There are no previous successful runs of `{current.command}` to compare to.")
+            return PANTS_SUCCEEDED_EXIT_CODE
+
+        comparisons = _compare_with_subsystem_thresholds(
+            subsystem, current, median_metrics(baseline_runs)
+        )
+        print(f"Run {current.run_id} (`{current.command}`) against {description}:")
+        for comparison in comparisons:
+            print(f"  {comparison.format()}")
+        regressions = sum(comparison.regression for comparison in comparisons)
+        if regressions:
+            print(f"\n{regressions} of {len(comparisons)} metrics regressed.")
+            return PANTS_FAILED_EXIT_CODE
+        return PANTS_SUCCEEDED_EXIT_CODE
+
+
+def rules():
+    return [
+        UnionRule(WorkunitsCallbackFactoryRequest, RunHistoryCallbackFactoryRequest),
+        *collect_rules(),
+    ]
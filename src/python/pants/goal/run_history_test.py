@@ -0,0 +1,179 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+from pathlib import Path
+from typing import Any
+
+import pytest
+
+from pants.engine.internals.scheduler import Workunit
+from pants.goal.run_history import (
+    MetricComparison,
+    RunHistory,
+    RunSummary,
+    compare,
+    critical_path,
+    estimate_remaining_secs,
+    median_metrics,
+    process_durations,
+    summarize,
+)
+from pants.testutil.workunit_util import create_workunit
+from pants.util.frozendict import FrozenDict
+
+
+def workunit(
+    name: str, start: float, end: float, parent: str | None = None, **kwargs: Any
+) -> Workunit:
+    parent_ids = [parent] if parent else []
+    return create_workunit(name, *parent_ids, start_secs=start, duration_secs=end - start, **kwargs)
+
+
+def test_critical_path() -> None:
+    workunits = [
+        workunit("root", 0, 10),
+        # `b` ends last, and `a` ends before `b` starts, so both are on the critical path.
+        workunit("a", 1, 3, "root"),
+        workunit("b", 4, 9, "root"),
+        # `c` was still running when `b` started, so it is not on the critical path.
+        workunit("c", 2, 8, "root"),
+        workunit("d", 2, 3, "a"),
+    ]
+    assert critical_path(workunits) == pytest.approx({"root": 3, "a": 1, "b": 5, "d": 1})
+    assert critical_path([]) == {}
+
+
+def test_summarize() -> None:
+    summary = summarize(
+        run_information={"id": "run", "timestamp": 1.5, "cmd_line": "pants test ::"},
+        goals=["test"],
+        wall_secs=12,
+        counters={
+            "local_cache_requests": 4,
+            "local_cache_requests_cached": 3,
+            "remote_cache_requests": 0,
+        },
+        workunits=[
+            workunit("test", 1, 11, description="`test` goal"),
+            workunit("run_tests", 2, 10, "test"),
+        ],
+        critical_path_entries=1,
+    )
+    assert summary.run_id == "run"
+    assert summary.goals == ("test",)
+    assert summary.outcome == "UNKNOWN"
+    assert summary.metrics == pytest.approx(
+        {
+            "wall_time": 12,
+            "goal:test": 10,
+            "critical_path:run_tests": 8,
+            "cache_hit_rate:local": 0.75,
+        }
+    )
+
+
+def test_process_durations() -> None:
+    workunits = [
+        workunit("root", 0, 10),
+        workunit("process", 1, 3, "root", description="Run pytest"),
+        workunit("process", 4, 9, "root", description="Run pytest"),
+        workunit("process", 2, 3, "root"),
+        workunit("rule", 5, 6, "root", description="Find targets"),
+    ]
+    assert process_durations(workunits) == pytest.approx({"Run pytest": 5})
+
+
+def run_summary(run_id: str, timestamp: float, outcome: str = "SUCCESS", **metrics) -> RunSummary:
+    return RunSummary(
+        run_id=run_id,
+        timestamp=timestamp,
+        command="pants test ::",
+        goals=("test",),
+        outcome=outcome,
+        metrics=FrozenDict({"wall_time": 10.0, **metrics}),
+    )
+
+
+def test_history(tmp_path: Path) -> None:
+    history = RunHistory(str(tmp_path / "history" / "runs.db"))
+    runs = [
+        run_summary("a", 1),
+        run_summary("b", 2, outcome="FAILURE"),
+        run_summary("c", 3),
+        run_summary("d", 4),
+    ]
+    for run in runs:
+        history.record(run, max_runs=3)
+    other_goals = RunSummary("e", 5, "pants lint ::", ("lint",), "SUCCESS", FrozenDict())
+    history.record(other_goals, max_runs=3)
+
+    # The oldest runs are removed.
+    assert history.get("a") is None
+    assert history.get("c") == runs[2]
+    assert history.latest() == other_goals
+    # Only the successful runs of the same goals are baselines.
+    assert history.baseline_runs(runs[3], count=5) == [runs[2]]
+    history.close()
+
+
+def test_history_process_durations(tmp_path: Path) -> None:
+    history = RunHistory(str(tmp_path / "runs.db"))
+    history.record(run_summary("a", 1), max_runs=2, process_durations={"lint": 2.0, "test": 4.0})
+    history.record(run_summary("b", 2), max_runs=2, process_durations={"test": 8.0})
+    # Durations are averaged with those of previous runs.
+    assert history.process_durations() == {"lint": 2.0, "test": 6.0}
+
+    # The durations of processes which have not run since the oldest kept run are removed.
+    history.record(run_summary("c", 3), max_runs=2)
+    assert history.process_durations() == {"test": 6.0}
+    history.close()
+
+
+def test_estimate_remaining_secs() -> None:
+    runs = [run_summary("a", 1), run_summary("b", 2, wall_time=20.0), run_summary("c", 3)]
+    assert estimate_remaining_secs(runs, elapsed_secs=4) == 6.0
+    # A run which has overrun its baseline is expected to complete imminently.
+    assert estimate_remaining_secs(runs, elapsed_secs=15) == 0.0
+    assert estimate_remaining_secs([], elapsed_secs=4) is None
+
+
+def test_median_metrics() -> None:
+    assert median_metrics(
+        [
+            run_summary("a", 1, **{"goal:test": 1.0}),
+            run_summary("b", 2, wall_time=20.0, **{"goal:test": 2.0}),
+            run_summary("c", 3, wall_time=30.0),
+        ]
+    ) == {"wall_time": 20.0, "goal:test": 1.5}
+
+
+def test_compare() -> None:
+    comparisons = compare(
+        {
+            "wall_time": 13.0,
+            "goal:test": 1.5,
+            "cache_hit_rate:local": 0.7,
+            "cache_hit_rate:remote": 0.75,
+            "critical_path:new": 5.0,
+        },
+        {
+            "wall_time": 10.0,
+            "goal:test": 1.0,
+            "cache_hit_rate:local": 0.9,
+            "cache_hit_rate:remote": 0.8,
+        },
+        regression_threshold=0.2,
+        min_regression_secs=1.0,
+        hit_rate_regression_threshold=0.1,
+    )
+    assert comparisons == [
+        MetricComparison("cache_hit_rate:local", 0.9, 0.7, regression=True),
+        MetricComparison("cache_hit_rate:remote", 0.8, 0.75, regression=False),
+        # The time of the goal increased by 50%, but by less than a second.
+        MetricComparison("goal:test", 1.0, 1.5, regression=False),
+        MetricComparison("wall_time", 10.0, 13.0, regression=True),
+    ]
+    assert comparisons[0].format().endswith("90.0% -> 70.0% (-20.0 points)  REGRESSION")
+    assert comparisons[3].format() == "wall_time: 10.00s -> 13.00s (+30.0%)  REGRESSION"
@@ -11,7 +11,7 @@ from collections import Counter
 from dataclasses import dataclass
 from enum import Enum
 from pathlib import Path
-from typing import Optional, TypedDict
+from typing import Iterable, Optional, TypedDict
 
 from pants.engine.internals.scheduler import Workunit
 from pants.engine.rules import collect_rules, rule
@@ -54,11 +54,19 @@ class ObservationHistogramObject(TypedDict):
     sum: int
 
 
+class TargetTimingObject(TypedDict):
+    address: str
+    rule_secs: float
+    process_secs: float
+    processes: int
+
+
 class StatsObject(TypedDict, total=False):
     timestamp: str
     command: str
     counters: list[CounterObject]
     memory_summary: list[MemorySummaryObject]
+    target_timings: list[TargetTimingObject]
     observation_histograms: list[ObservationHistogramObject]
 
 
@@ -103,6 +111,21 @@ class StatsAggregatorSubsystem(Subsystem):
         ),
         advanced=True,
     )
+    target_timings = BoolOption(
+        default=False,
+        help=softwrap(
+            """
+            At the end of the Pants run, report the wall time attributed to each target: the time
+            spent in the rules which operated on it, and in the processes which those rules ran.
+
+            Time is attributed to the target of the nearest enclosing workunit with an `address`
+            in its metadata, so only workunits at up to `[GLOBAL].streaming_workunits_level` are
+            counted. The targets of a run are worked on concurrently, so their times overlap, and
+            do not sum to the duration of the run.
+            """
+        ),
+        advanced=True,
+    )
     output_file = StrOption(
         default=None,
         metavar="<path>",
@@ -141,12 +164,73 @@ def _log_or_write_to_file_json(output_file: Optional[str], stats_object: StatsOb
     logger.info(f"Wrote Pants stats to {output_file}")
 
 
+def _duration_secs(workunit: Workunit) -> float:
+    return workunit.get("duration_secs", 0) + workunit.get("duration_nanos", 0) / 1_000_000_000
+
+
+def target_timings(workunits: Iterable[Workunit]) -> list[TargetTimingObject]:
+    """Attributes the wall time of the given completed workunits to the addresses of targets.
+
+    The outermost workunit with a given `address` in its metadata contributes its duration to the
+    rule time of the address, and each `process` workunit contributes its duration to the process
+    time of the address of its nearest enclosing workunit with an address.
+    """
+    workunits_by_id = {workunit["span_id"]: workunit for workunit in workunits}
+    address_by_id: dict[str, str | None] = {}
+
+    def address_of(span_id: str) -> str | None:
+        # Walk up to the nearest workunit with an address (or a known address), and then record the
+        # address for all of the workunits on the way.
+        path = []
+        address = None
+        current: str | None = span_id
+        while current is not None:
+            if current in address_by_id:
+                address = address_by_id[current]
+                break
+            path.append(current)
+            workunit = workunits_by_id.get(current)
+            if workunit is None:
+                break
+            address = workunit.get("metadata", {}).get("address")
+            if isinstance(address, str):
+                break
+            address = None
+            parent_ids = workunit.get("parent_ids", ())
+            current = parent_ids[0] if parent_ids else None
+        for visited in path:
+            address_by_id[visited] = address
+        return address
+
+    timings: dict[str, TargetTimingObject] = {}
+
+    def timing(address: str) -> TargetTimingObject:
+        return timings.setdefault(
+            address, {"address": address, "rule_secs": 0.0, "process_secs": 0.0, "processes": 0}
+        )
+
+    for span_id, workunit in workunits_by_id.items():
+        parent_ids = workunit.get("parent_ids", ())
+        parent_address = address_of(parent_ids[0]) if parent_ids else None
+        address = address_of(span_id)
+        if address is None:
+            continue
+        if address != parent_address:
+            timing(address)["rule_secs"] += _duration_secs(workunit)
+        if workunit["name"] == "process":
+            timing(address)["process_secs"] += _duration_secs(workunit)
+            timing(address)["processes"] += 1
+
+    return sorted(timings.values(), key=lambda t: (-t["rule_secs"], t["address"]))
+
+
 class StatsAggregatorCallback(WorkunitsCallback):
     def __init__(
         self,
         *,
         log: bool,
         memory: bool,
+        timings: bool,
         output_file: Optional[str],
         has_histogram_module: bool,
         format: StatsOutputFormat,
@@ -154,6 +238,7 @@ class StatsAggregatorCallback(WorkunitsCallback):
         super().__init__()
         self.log = log
         self.memory = memory
+        self.timings = timings
         self.output_file = output_file
         self.has_histogram_module = has_histogram_module
         self.format = format
@@ -163,6 +248,10 @@ class StatsAggregatorCallback(WorkunitsCallback):
         # We need to finish synchronously for access to the console.
         return False
 
+    @property
+    def retains_completed_workunits(self) -> bool:
+        return self.timings
+
     def _output_stats_in_plain_text(self, context: StreamingWorkunitContext):
         output_lines = []
         if self.output_file:
@@ -212,6 +301,17 @@ class StatsAggregatorCallback(WorkunitsCallback):
                 f"Memory summary (total size in bytes, count, name):\n{memory_lines}"
             )
 
+        if self.timings:
+            timing_lines = "\n".join(
+                f"  {t['rule_secs']:.3f}\t\t{t['process_secs']:.3f}\t\t{t['processes']}\t\t"
+                f"{t['address']}"
+                for t in target_timings(context.completed_workunits())
+            )
+            output_lines.append(
+                "Target timings (rule seconds, process seconds, process count, address):\n"
+                f"{timing_lines}"
+            )
+
         if not (self.log and self.has_histogram_module):
             _log_or_write_to_file_plain(self.output_file, output_lines)
             return
@@ -292,6 +392,9 @@ class StatsAggregatorCallback(WorkunitsCallback):
             ]
             stats_object["memory_summary"] = memory_lines
 
+        if self.timings:
+            stats_object["target_timings"] = target_timings(context.completed_workunits())
+
         if not (self.log and self.has_histogram_module):
             _log_or_write_to_file_json(self.output_file, stats_object)
             return
@@ -377,11 +480,12 @@ def construct_callback(
             StatsAggregatorCallback(
                 log=subsystem.log,
                 memory=subsystem.memory_summary,
+                timings=subsystem.target_timings,
                 output_file=subsystem.output_file,
                 has_histogram_module=has_histogram_module,
                 format=subsystem.format,
             )
-            if subsystem.log or subsystem.memory_summary
+            if subsystem.log or subsystem.memory_summary or subsystem.target_timings
             else None
         )
     )
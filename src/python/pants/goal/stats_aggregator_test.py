@@ -0,0 +1,40 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+from pants.engine.internals.scheduler import Workunit
+from pants.goal.stats_aggregator import target_timings
+from pants.testutil.workunit_util import create_workunit
+
+
+def workunit(
+    name: str, span_id: str, duration_secs: int, *parent_ids: str, address: str | None = None
+) -> Workunit:
+    return create_workunit(
+        span_id,
+        *parent_ids,
+        name=name,
+        duration_secs=duration_secs,
+        metadata={"address": address} if address else None,
+    )
+
+
+def test_target_timings() -> None:
+    workunits = [
+        workunit("pants_run", "root", 100),
+        workunit("run_tests", "test", 30, "root", address="src:tests"),
+        # Nested in a rule for the same target, and so already counted in its rule time.
+        workunit("setup_tests", "setup", 10, "test", address="src:tests"),
+        workunit("process", "p1", 5, "setup"),
+        workunit("process", "p2", 15, "test"),
+        # Nested in a rule for a different target (a dependency).
+        workunit("build_dependency", "dep", 4, "test", address="src:lib"),
+        workunit("process", "p3", 3, "dep"),
+        # Not attributable to any target.
+        workunit("process", "p4", 7, "root"),
+    ]
+    assert target_timings(workunits) == [
+        {"address": "src:tests", "rule_secs": 30.0, "process_secs": 20.0, "processes": 2},
+        {"address": "src:lib", "rule_secs": 4.0, "process_secs": 3.0, "processes": 1},
+    ]
@@ -0,0 +1,196 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+import json
+import logging
+from dataclasses import dataclass
+from typing import Any, Iterable
+
+from pants.engine.internals.scheduler import Workunit
+from pants.engine.rules import collect_rules, rule
+from pants.engine.streaming_workunit_handler import (
+    StreamingWorkunitContext,
+    WorkunitsCallback,
+    WorkunitsCallbackFactory,
+    WorkunitsCallbackFactoryRequest,
+)
+from pants.engine.unions import UnionRule
+from pants.option.option_types import StrOption
+from pants.option.subsystem import Subsystem
+from pants.util.dirutil import safe_open
+from pants.util.strutil import softwrap
+
+logger = logging.getLogger(__name__)
+
+
+class TraceEventsSubsystem(Subsystem):
+    options_scope = "trace-events"
+    help = softwrap(
+        """
+        Writes the workunits of a run as a trace, which can be loaded into
+        [Perfetto](https://ui.perfetto.dev/) or `chrome://tracing` to find the bottlenecks of the
+        run.
+
+        Which workunits are written is controlled by `[GLOBAL].streaming_workunits_level`.
+        """
+    )
+
+    output_file = StrOption(
+        default=None,
+        metavar="<path>",
+        help=softwrap(
+            """
+            At the end of the run, write its workunits to this file in the trace event JSON
+            format.
+
+            Workunits run concurrently on async tasks rather than on dedicated threads, so each
+            workunit is assigned to a track of the trace such that the workunits on a track are
+            either nested in their parent, or do not overlap.
+            """
+        ),
+    )
+
+
+@dataclass
+class _Track:
+    tid: int
+    # The span ids and end times of the workunits which are open on this track, outermost first.
+    open: list[tuple[str, int]]
+
+    def close_before(self, start: int) -> None:
+        while self.open and self.open[-1][1] <= start:
+            self.open.pop()
+
+
+def _start_and_end_nanos(workunit: Workunit) -> tuple[int, int]:
+    start = workunit["start_secs"] * 1_000_000_000 + workunit["start_nanos"]
+    duration = workunit.get("duration_secs", 0) * 1_000_000_000
+    return start, start + duration + workunit.get("duration_nanos", 0)
+
+
+def assign_tracks(workunits: Iterable[Workunit]) -> list[tuple[int, Workunit]]:
+    """Assigns each workunit to a track, in order of their start times.
+
+    A workunit is placed on the track of its (first) parent if it is the innermost workunit open on
+    that track, so that it is rendered nested in its parent. Otherwise it is placed on the first
+    track with no open workunits, so that it is not rendered as nested in an unrelated workunit.
+    """
+
+    def order(workunit: Workunit) -> tuple[int, int]:
+        # Parents start before (or with, and end after) their children.
+        start, end = _start_and_end_nanos(workunit)
+        return start, -end
+
+    tracks: list[_Track] = []
+    track_of_span: dict[str, _Track] = {}
+    result = []
+    for workunit in sorted(workunits, key=order):
+        start, end = _start_and_end_nanos(workunit)
+        parent_ids = workunit.get("parent_ids", ())
+        track = track_of_span.get(parent_ids[0]) if parent_ids else None
+        if track is not None:
+            track.close_before(start)
+            if not track.open or track.open[-1][0] != parent_ids[0] or track.open[-1][1] < end:
+                track = None
+        if track is None:
+            for candidate in tracks:
+                candidate.close_before(start)
+                if not candidate.open:
+                    track = candidate
+                    break
+            else:
+                track = _Track(tid=len(tracks) + 1, open=[])
+                tracks.append(track)
+        track.open.append((workunit["span_id"], end))
+        track_of_span[workunit["span_id"]] = track
+        result.append((track.tid, workunit))
+    return result
+
+
+def trace_events(workunits: Iterable[Workunit], *, process_name: str) -> dict[str, Any]:
+    """Converts completed workunits to the trace event JSON format."""
+    events: list[dict[str, Any]] = [
+        {"name": "process_name", "ph": "M", "pid": 1, "tid": 0, "args": {"name": process_name}}
+    ]
+    tids = set()
+    for tid, workunit in assign_tracks(workunits):
+        start, end = _start_and_end_nanos(workunit)
+        args = {
+            "span_id": workunit["span_id"],
+            "parent_ids": workunit.get("parent_ids", []),
+            "level": workunit["level"],
+            **({"description": workunit["description"]} if workunit.get("description") else {}),
+            **workunit.get("metadata", {}),
+        }
+        events.append(
+            {
+                "name": workunit["name"],
+                "cat": workunit["level"],
+                "ph": "X",
+                # NB: Timestamps and durations are in microseconds.
+                "ts": start / 1000,
+                "dur": (end - start) / 1000,
+                "pid": 1,
+                "tid": tid,
+                "args": args,
+            }
+        )
+        tids.add(tid)
+    events.extend(
+        {"name": "thread_name", "ph": "M", "pid": 1, "tid": tid, "args": {"name": f"Track {tid}"}}
+        for tid in sorted(tids)
+    )
+    return {"traceEvents": events, "displayTimeUnit": "ms"}
+
+
+class TraceEventsCallback(WorkunitsCallback):
+    def __init__(self, output_file: str) -> None:
+        self.output_file = output_file
+        self._completed_workunits: list[Workunit] = []
+
+    @property
+    def can_finish_async(self) -> bool:
+        # We log the path of the trace in the final call.
+        return False
+
+    def __call__(
+        self,
+        *,
+        started_workunits: tuple[Workunit, ...],
+        completed_workunits: tuple[Workunit, ...],
+        finished: bool,
+        context: StreamingWorkunitContext,
+    ) -> None:
+        self._completed_workunits.extend(completed_workunits)
+        if not finished:
+            return
+
+        trace = trace_events(self._completed_workunits, process_name=context.run_tracker.run_id)
+        with safe_open(self.output_file, "w") as fh:
+            # Metadata values which are not JSON serializable are written as their string form.
+            json.dump(trace, fh, default=str)
+        logger.info(f"Wrote the trace of this run to {self.output_file}")
+
+
+@dataclass(frozen=True)
+class TraceEventsCallbackFactoryRequest:
+    """A unique request type that is installed to trigger construction of the WorkunitsCallback."""
+
+
+@rule
+def construct_callback(
+    _: TraceEventsCallbackFactoryRequest, subsystem: TraceEventsSubsystem
+) -> WorkunitsCallbackFactory:
+    output_file = subsystem.output_file
+    return WorkunitsCallbackFactory(
+        lambda: TraceEventsCallback(output_file) if output_file else None
+    )
+
+
+def rules():
+    return [
+        UnionRule(WorkunitsCallbackFactoryRequest, TraceEventsCallbackFactoryRequest),
+        *collect_rules(),
+    ]
@@ -0,0 +1,72 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+from pants.engine.internals.scheduler import Workunit
+from pants.goal.trace_events import assign_tracks, trace_events
+from pants.testutil.workunit_util import create_workunit
+
+
+def workunit(span_id: str, start_secs: int, duration_secs: int, *parent_ids: str) -> Workunit:
+    return create_workunit(
+        span_id,
+        *parent_ids,
+        name=f"workunit_{span_id}",
+        level="INFO",
+        start_secs=start_secs,
+        duration_secs=duration_secs + 0.000_000_5,
+        metadata={"exit_code": 0},
+    )
+
+
+def test_assign_tracks() -> None:
+    workunits = [
+        workunit("root", 0, 10),
+        workunit("a", 1, 3, "root"),
+        # Overlaps with its sibling `a`, and so cannot be nested in `root` on its track.
+        workunit("b", 2, 5, "root"),
+        workunit("a1", 1, 1, "a"),
+        # Starts after `a` has ended, and so can be nested in `root` on its track.
+        workunit("c", 5, 2, "root"),
+        workunit("b1", 3, 1, "b"),
+        # A second root, which overlaps with everything else.
+        workunit("other", 4, 1),
+    ]
+    assert [(tid, wu["span_id"]) for tid, wu in assign_tracks(workunits)] == [
+        (1, "root"),
+        (1, "a"),
+        (1, "a1"),
+        (2, "b"),
+        (2, "b1"),
+        (3, "other"),
+        (1, "c"),
+    ]
+
+
+def test_trace_events() -> None:
+    trace = trace_events([workunit("root", 1, 2), workunit("a", 1, 1, "root")], process_name="run")
+    assert trace["traceEvents"] == [
+        {"name": "process_name", "ph": "M", "pid": 1, "tid": 0, "args": {"name": "run"}},
+        {
+            "name": "workunit_root",
+            "cat": "INFO",
+            "ph": "X",
+            "ts": 1_000_000.0,
+            "dur": 2_000_000.5,
+            "pid": 1,
+            "tid": 1,
+            "args": {"span_id": "root", "parent_ids": [], "level": "INFO", "exit_code": 0},
+        },
+        {
+            "name": "workunit_a",
+            "cat": "INFO",
+            "ph": "X",
+            "ts": 1_000_000.0,
+            "dur": 1_000_000.5,
+            "pid": 1,
+            "tid": 1,
+            "args": {"span_id": "a", "parent_ids": ["root"], "level": "INFO", "exit_code": 0},
+        },
+        {"name": "thread_name", "ph": "M", "pid": 1, "tid": 1, "args": {"name": "Track 1"}},
+    ]
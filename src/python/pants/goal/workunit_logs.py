@@ -0,0 +1,218 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+import datetime
+import logging
+import os
+import re
+from dataclasses import dataclass
+from typing import Iterable, Mapping
+
+from pants.engine.fs import FileDigest
+from pants.engine.internals.scheduler import Workunit
+from pants.engine.rules import collect_rules, rule
+from pants.engine.streaming_workunit_handler import (
+    StreamingWorkunitContext,
+    WorkunitsCallback,
+    WorkunitsCallbackFactory,
+    WorkunitsCallbackFactoryRequest,
+)
+from pants.engine.unions import UnionRule
+from pants.option.global_options import GlobalOptions
+from pants.option.option_types import BoolOption, StrOption
+from pants.option.subsystem import Subsystem
+from pants.util.dirutil import safe_mkdir
+from pants.util.strutil import softwrap
+
+logger = logging.getLogger(__name__)
+
+
+class WorkunitLogsSubsystem(Subsystem):
+    options_scope = "workunit-logs"
+    help = softwrap(
+        """
+        Writes the output of each test and process of a run to a file of its own, e.g. to publish
+        them as artifacts of CI jobs.
+
+        Each file holds the description and outcome of the workunit, and its captured stdout and
+        stderr. The output of a process which ran for a test is only written to the file of the
+        test.
+        """
+    )
+
+    enabled = BoolOption(default=False, help="Whether to write the output of workunits to files.")
+    dir = StrOption(
+        default=None,
+        metavar="<dir>",
+        help=softwrap(
+            """
+            The directory to write the files to, in a subdirectory named by the id of the run. If
+            unset, the files are written under `workunit-logs` in `[GLOBAL].pants_workdir`.
+
+            Files are named by the targets of the workunit if it has any (as tests do), and by its
+            description otherwise.
+            """
+        ),
+    )
+
+
+# The keys of the artifacts of the stdout and stderr of a workunit: those of the workunits of
+# processes, and those of `EngineAwareReturnType`s (such as `TestResult`s).
+_STDIO_ARTIFACTS = (("stdout_digest", "stderr_digest"), ("stdout", "stderr"))
+
+
+@dataclass(frozen=True)
+class CapturedWorkunit:
+    span_id: str
+    file_stem: str
+    header: tuple[str, ...]
+    stdout: FileDigest
+    stderr: FileDigest
+
+
+def _file_stem(workunit: Workunit) -> str:
+    addresses = workunit.get("metadata", {}).get("addresses")
+    name = "+".join(addresses) if addresses else workunit.get("description") or workunit["name"]
+    return re.sub(r"[^\w.-]+", "_", name).strip("_")[:100] or workunit["span_id"]
+
+
+def _header(workunit: Workunit) -> tuple[str, ...]:
+    start_secs = workunit["start_secs"] + workunit["start_nanos"] / 1_000_000_000
+    duration_secs = (
+        workunit.get("duration_secs", 0) + workunit.get("duration_nanos", 0) / 1_000_000_000
+    )
+    start = datetime.datetime.fromtimestamp(start_secs, tz=datetime.timezone.utc)
+    header = [
+        workunit.get("description") or workunit["name"],
+        f"Started at {start.isoformat(timespec='milliseconds')}, and took {duration_secs:.2f}s.",
+    ]
+    exit_code = workunit.get("metadata", {}).get("exit_code")
+    if exit_code is not None:
+        outcome = "succeeded" if exit_code == 0 else "failed"
+        header.append(f"Exit code {exit_code}: {outcome}.")
+    return tuple(header)
+
+
+def captured_workunit(workunit: Workunit) -> CapturedWorkunit | None:
+    """The captured stdio of the workunit, if any."""
+    artifacts = workunit.get("artifacts", {})
+    for stdout_key, stderr_key in _STDIO_ARTIFACTS:
+        stdout, stderr = artifacts.get(stdout_key), artifacts.get(stderr_key)
+        if isinstance(stdout, FileDigest) and isinstance(stderr, FileDigest):
+            return CapturedWorkunit(
+                span_id=workunit["span_id"],
+                file_stem=_file_stem(workunit),
+                header=_header(workunit),
+                stdout=stdout,
+                stderr=stderr,
+            )
+    return None
+
+
+def outermost(
+    captured: Iterable[CapturedWorkunit], parent_ids: Mapping[str, Iterable[str]]
+) -> list[CapturedWorkunit]:
+    """The captured workunits which do not run within another captured workunit."""
+    captured = list(captured)
+    captured_ids = {c.span_id for c in captured}
+
+    def has_captured_ancestor(span_id: str) -> bool:
+        seen = set()
+        pending = list(parent_ids.get(span_id, ()))
+        while pending:
+            parent_id = pending.pop()
+            if parent_id in captured_ids:
+                return True
+            if parent_id not in seen:
+                seen.add(parent_id)
+                pending.extend(parent_ids.get(parent_id, ()))
+        return False
+
+    return [c for c in captured if not has_captured_ancestor(c.span_id)]
+
+
+def render(captured: CapturedWorkunit, stdout: bytes, stderr: bytes) -> bytes:
+    sections = [
+        "\n".join(captured.header).encode(),
+        b"==== stdout ====\n" + stdout,
+        b"==== stderr ====\n" + stderr,
+    ]
+    return b"\n\n".join(sections)
+
+
+class WorkunitLogsCallback(WorkunitsCallback):
+    """Writes the captured stdio of the outermost workunits which have any, once the run ends.
+
+    The files are written at the end of the run because whether the output of a workunit is written
+    depends on whether a workunit which it ran within (and which completes after it) has output.
+    """
+
+    def __init__(self, output_dir: str) -> None:
+        self.output_dir = output_dir
+        self._captured: list[CapturedWorkunit] = []
+        self._parent_ids: dict[str, list[str]] = {}
+
+    @property
+    def can_finish_async(self) -> bool:
+        # We log the directory of the files in the final call.
+        return False
+
+    def __call__(
+        self,
+        *,
+        started_workunits: tuple[Workunit, ...],
+        completed_workunits: tuple[Workunit, ...],
+        finished: bool,
+        context: StreamingWorkunitContext,
+    ) -> None:
+        for workunit in completed_workunits:
+            self._parent_ids[workunit["span_id"]] = workunit.get("parent_ids", [])
+            captured = captured_workunit(workunit)
+            if captured:
+                self._captured.append(captured)
+        if not finished or not self._captured:
+            return
+
+        output_dir = os.path.join(self.output_dir, context.run_tracker.run_id)
+        safe_mkdir(output_dir)
+        captured = outermost(self._captured, self._parent_ids)
+        contents = context.single_file_digests_to_bytes(
+            [digest for c in captured for digest in (c.stdout, c.stderr)]
+        )
+        file_names: set[str] = set()
+        for i, c in enumerate(captured):
+            file_name = f"{c.file_stem}.log"
+            suffix = 1
+            while file_name in file_names:
+                suffix += 1
+                file_name = f"{c.file_stem}.{suffix}.log"
+            file_names.add(file_name)
+            with open(os.path.join(output_dir, file_name), "wb") as fh:
+                fh.write(render(c, contents[2 * i], contents[2 * i + 1]))
+        logger.info(f"Wrote the output of {len(captured)} workunits to {output_dir}")
+
+
+@dataclass(frozen=True)
+class WorkunitLogsCallbackFactoryRequest:
+    """A unique request type that is installed to trigger construction of the WorkunitsCallback."""
+
+
+@rule
+def construct_callback(
+    _: WorkunitLogsCallbackFactoryRequest,
+    subsystem: WorkunitLogsSubsystem,
+    global_options: GlobalOptions,
+) -> WorkunitsCallbackFactory:
+    output_dir = subsystem.dir or os.path.join(global_options.pants_workdir, "workunit-logs")
+    return WorkunitsCallbackFactory(
+        lambda: WorkunitLogsCallback(output_dir) if subsystem.enabled else None
+    )
+
+
+def rules():
+    return [
+        UnionRule(WorkunitsCallbackFactoryRequest, WorkunitLogsCallbackFactoryRequest),
+        *collect_rules(),
+    ]
@@ -0,0 +1,74 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+from typing import Any
+
+from pants.engine.fs import EMPTY_FILE_DIGEST, FileDigest
+from pants.engine.internals.scheduler import Workunit
+from pants.goal.workunit_logs import captured_workunit, outermost, render
+from pants.testutil.workunit_util import create_workunit
+
+STDOUT = FileDigest("a" * 64, 3)
+
+
+def workunit(span_id: str, *parent_ids: str, **kwargs: Any) -> Workunit:
+    return create_workunit(
+        span_id,
+        *parent_ids,
+        name="process",
+        description=f"Run {span_id}",
+        start_secs=1.5,
+        duration_secs=2,
+        **kwargs,
+    )
+
+
+def test_captured_workunit() -> None:
+    process = captured_workunit(
+        workunit(
+            "process",
+            metadata={"exit_code": 1},
+            artifacts={"stdout_digest": STDOUT, "stderr_digest": EMPTY_FILE_DIGEST},
+        )
+    )
+    assert process is not None
+    assert process.file_stem == "Run_process"
+    assert process.header == (
+        "Run process",
+        "Started at 1970-01-01T00:00:01.500+00:00, and took 2.00s.",
+        "Exit code 1: failed.",
+    )
+    assert render(process, b"out", b"") == (
+        b"Run process\n"
+        b"Started at 1970-01-01T00:00:01.500+00:00, and took 2.00s.\n"
+        b"Exit code 1: failed.\n\n"
+        b"==== stdout ====\nout\n\n"
+        b"==== stderr ====\n"
+    )
+
+    test = captured_workunit(
+        workunit(
+            "test",
+            metadata={"addresses": ["src/a_test.py:tests", "src/b_test.py"]},
+            artifacts={"stdout": STDOUT, "stderr": EMPTY_FILE_DIGEST},
+        )
+    )
+    assert test is not None
+    assert test.file_stem == "src_a_test.py_tests_src_b_test.py"
+
+    assert captured_workunit(workunit("rule")) is None
+
+
+def test_outermost() -> None:
+    test = workunit("test", artifacts={"stdout": STDOUT, "stderr": STDOUT})
+    # A process of the test, run by a rule of the test.
+    process = workunit(
+        "process", "rule", artifacts={"stdout_digest": STDOUT, "stderr_digest": STDOUT}
+    )
+    other_process = workunit("other", artifacts={"stdout_digest": STDOUT, "stderr_digest": STDOUT})
+    captured = [captured_workunit(w) for w in (process, test, other_process)]
+    parent_ids = {"process": ["rule"], "rule": ["test"], "test": [], "other": []}
+
+    assert outermost(captured, parent_ids) == [captured[1], captured[2]]  # type: ignore[arg-type]
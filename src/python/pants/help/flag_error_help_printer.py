@@ -1,8 +1,7 @@
 # Copyright 2020 Pants project contributors (see CONTRIBUTORS.md).
 # Licensed under the Apache License, Version 2.0 (see LICENSE).
 
-import difflib
-
+from pants.engine.internals import native_engine
 from pants.engine.internals.parser import BuildFileSymbolsInfo
 from pants.engine.target import RegisteredTargetTypes
 from pants.engine.unions import UnionMembership
@@ -28,6 +27,10 @@ class FlagErrorHelpPrinter(MaybeColor):
             RegisteredTargetTypes({}),
             BuildFileSymbolsInfo.from_info(),
         )
+        cli_alias = (
+            options.for_scope("cli").alias if "cli" in options.known_scope_to_info else {}
+        )
+        self._flag_aliases = {alias for alias in cli_alias if alias.startswith("--")}
 
     def handle_unknown_flags(self, err: UnknownFlagsError):
         global_flags = self._all_help_info.scope_to_help_info[GLOBAL_SCOPE].collect_unscoped_flags()
@@ -39,10 +42,12 @@ class FlagErrorHelpPrinter(MaybeColor):
             # the cmd line (that is, to the left of any goals).
             for oshi in self._all_help_info.scope_to_help_info.values():
                 possibilities.update(oshi.collect_scoped_flags())
+        # Flag-like aliases are expanded before flags are parsed, so may be used in any scope.
+        possibilities.update(self._flag_aliases)
 
         for flag in err.flags:
             print(f"Unknown flag {self.maybe_red(flag)} on {err.arg_scope or 'global'} scope")
-            did_you_mean = difflib.get_close_matches(flag, possibilities)
+            did_you_mean = native_engine.did_you_mean(flag, sorted(possibilities))
             if err.arg_scope != GLOBAL_SCOPE and flag in global_flags:
                 # It's a common error to use a global flag in a goal scope, so we special-case it.
                 print(
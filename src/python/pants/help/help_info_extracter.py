@@ -1011,6 +1011,8 @@ class HelpInfoExtracter:
         deprecated_options = []
         for args, kwargs in parser.option_registrations_iter():
             history = parser.history(kwargs["dest"])
+            if history and parser.is_secret(kwargs["dest"]):
+                history = history.redacted()
             ohi = self.get_option_help_info(args, kwargs)
             ohi = dataclasses.replace(ohi, value_history=history)
             if ohi.deprecation_active:
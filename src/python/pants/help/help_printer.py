@@ -9,6 +9,7 @@ from itertools import cycle
 from typing import Callable, Dict, Iterable, List, Literal, Optional, Set, Tuple, cast
 
 from pants.base.build_environment import pants_version
+from pants.engine.internals import native_engine
 from pants.help.help_formatter import HelpFormatter
 from pants.help.help_info_extracter import AllHelpInfo, HelpJSONEncoder
 from pants.help.help_tools import ToolHelpInfo
@@ -35,10 +36,13 @@ class HelpPrinter(MaybeColor):
         help_request: HelpRequest,
         all_help_info: AllHelpInfo,
         color: bool,
+        cli_aliases: Iterable[str] = (),
     ) -> None:
         super().__init__(color)
         self._help_request = help_request
         self._all_help_info = all_help_info
+        # Goal-like aliases, from `[cli].alias`, which may be mistyped as well as goals.
+        self._goal_aliases = {alias for alias in cli_aliases if not alias.startswith("-")}
         self._width = terminal_width()
         self._reserved_names = {
             "api-types",
@@ -71,7 +75,9 @@ class HelpPrinter(MaybeColor):
             # It gets confusing to try and show suggestions for multiple cases.
             unknown_goal = self._help_request.unknown_goals[0]
             print(f"Unknown goal: {self.maybe_red(unknown_goal)}")
-            self._print_alternatives(unknown_goal, self._all_help_info.name_to_goal_info.keys())
+            self._print_alternatives(
+                unknown_goal, {*self._all_help_info.name_to_goal_info.keys(), *self._goal_aliases}
+            )
             print_hint()
             return 1
         elif isinstance(self._help_request, NoGoalHelp):
@@ -83,7 +89,7 @@ class HelpPrinter(MaybeColor):
             return 1
 
     def _print_alternatives(self, match: str, all_things: Iterable[str]) -> None:
-        did_you_mean = difflib.get_close_matches(match, all_things)
+        did_you_mean = native_engine.did_you_mean(match, sorted(all_things))
 
         if did_you_mean:
             formatted_matches = self._format_did_you_mean_matches(did_you_mean)
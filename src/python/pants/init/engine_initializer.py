@@ -6,7 +6,7 @@ from __future__ import annotations
 import logging
 from dataclasses import dataclass
 from pathlib import Path
-from typing import Any, ClassVar, Iterable, Mapping, cast
+from typing import Any, ClassVar, Iterable, Mapping, Sequence, cast
 
 from pants.base.build_environment import get_buildroot
 from pants.base.build_root import BuildRoot
@@ -30,7 +30,11 @@ from pants.engine.internals import (
     specs_rules,
     synthetic_targets,
 )
-from pants.engine.internals.native_engine import PyExecutor, PySessionCancellationLatch
+from pants.engine.internals.native_engine import (
+    PyExecutor,
+    PyRuleGraphCache,
+    PySessionCancellationLatch,
+)
 from pants.engine.internals.parser import Parser
 from pants.engine.internals.scheduler import Scheduler, SchedulerSession
 from pants.engine.internals.selectors import Params
@@ -44,7 +48,9 @@ from pants.init.bootstrap_scheduler import BootstrapStatus
 from pants.option.global_options import (
     DEFAULT_EXECUTION_OPTIONS,
     DynamicRemoteOptions,
+    DynamicUIRenderer,
     ExecutionOptions,
+    FileWatcher,
     GlobalOptions,
     LocalStoreOptions,
 )
@@ -71,19 +77,21 @@ class GraphScheduler:
         self,
         build_id,
         dynamic_ui: bool = False,
-        ui_use_prodash: bool = False,
+        ui_renderer: DynamicUIRenderer = DynamicUIRenderer.indicatif_spinner,
         use_colors=True,
         max_workunit_level: LogLevel = LogLevel.DEBUG,
         session_values: SessionValues | None = None,
         cancellation_latch: PySessionCancellationLatch | None = None,
+        niceness: int = 0,
     ) -> GraphSession:
         session = self.scheduler.new_session(
             build_id,
             dynamic_ui,
-            ui_use_prodash,
+            ui_renderer,
             max_workunit_level=max_workunit_level,
             session_values=session_values,
             cancellation_latch=cancellation_latch,
+            niceness=niceness,
         )
         console = Console(use_colors=use_colors, session=session if dynamic_ui else None)
         return GraphSession(session, console, self.goal_map)
@@ -190,6 +198,7 @@ class EngineInitializer:
         dynamic_remote_options: DynamicRemoteOptions,
         executor: PyExecutor,
         is_bootstrap: bool = False,
+        rule_graph_cache: PyRuleGraphCache | None = None,
     ) -> GraphScheduler:
         build_root = get_buildroot()
         executor = executor or GlobalOptions.create_py_executor(bootstrap_options)
@@ -209,7 +218,12 @@ class EngineInitializer:
             include_trace_on_error=bootstrap_options.print_stacktrace,
             engine_visualize_to=bootstrap_options.engine_visualize_to,
             watch_filesystem=bootstrap_options.watch_filesystem,
+            file_watcher=bootstrap_options.file_watcher,
+            file_watcher_poll_interval=bootstrap_options.file_watcher_poll_interval,
+            file_watcher_coalesce_window=bootstrap_options.file_watcher_coalesce_window,
+            file_watcher_ignore=bootstrap_options.file_watcher_ignore,
             is_bootstrap=is_bootstrap,
+            rule_graph_cache=rule_graph_cache,
         )
 
     @staticmethod
@@ -228,7 +242,12 @@ class EngineInitializer:
         include_trace_on_error: bool = True,
         engine_visualize_to: str | None = None,
         watch_filesystem: bool = True,
+        file_watcher: FileWatcher = FileWatcher.auto,
+        file_watcher_poll_interval: float = 1.0,
+        file_watcher_coalesce_window: float = 0.1,
+        file_watcher_ignore: Sequence[str] = (),
         is_bootstrap: bool = False,
+        rule_graph_cache: PyRuleGraphCache | None = None,
     ) -> GraphScheduler:
         build_root_path = build_root or get_buildroot()
 
@@ -358,6 +377,11 @@ class EngineInitializer:
             include_trace_on_error=include_trace_on_error,
             visualize_to_dir=engine_visualize_to,
             watch_filesystem=watch_filesystem,
+            file_watcher=file_watcher,
+            file_watcher_poll_interval=file_watcher_poll_interval,
+            file_watcher_coalesce_window=file_watcher_coalesce_window,
+            file_watcher_ignore=file_watcher_ignore,
+            rule_graph_cache=rule_graph_cache,
         )
 
         return GraphScheduler(scheduler, goal_map)
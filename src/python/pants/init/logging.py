@@ -8,13 +8,15 @@ import locale
 import logging
 import sys
 from contextlib import contextmanager
+from dataclasses import dataclass
 from io import BufferedReader, TextIOWrapper
 from logging import Formatter, Handler, LogRecord
 from pathlib import PurePath
-from typing import Iterator
+from typing import Iterator, Mapping, cast
 
 import pants.util.logging as pants_logging
 from pants.engine.internals import native_engine
+from pants.option.global_options import LogFormat, SystemLogSink
 from pants.option.option_value_container import OptionValueContainer
 from pants.util.dirutil import safe_mkdir_for
 from pants.util.docutil import doc_url
@@ -98,6 +100,33 @@ def stdio_destination_use_color(use_color: bool) -> None:
     native_engine.stdio_thread_console_color_mode_set(use_color)
 
 
+def colors_from_env(env: Mapping[str, str]) -> bool | None:
+    """Whether the conventional environment variables of a run force color to be used or not.
+
+    A non-empty `NO_COLOR` disables color (see https://no-color.org/), and takes precedence over a
+    `FORCE_COLOR` (see https://force-color.org/), which enables color unless it is `0` or `false`.
+    """
+    if env.get("NO_COLOR"):
+        return False
+    force_color = env.get("FORCE_COLOR")
+    if force_color is not None:
+        return force_color.lower() not in ("0", "false")
+    return None
+
+
+def use_color(global_options: OptionValueContainer, env: Mapping[str, str]) -> bool:
+    """Whether the output of a run (and of the interactive processes which it runs) uses color.
+
+    An explicitly set `--[no-]colors` takes precedence over the environment variables of the run
+    (see `colors_from_env`), which take precedence over whether stdout is a terminal. This must be
+    called once the `stdio_destination` of the run is set, so that stdout is that of the client.
+    """
+    if not global_options.is_default("colors"):
+        return cast(bool, global_options.colors)
+    from_env = colors_from_env(env)
+    return sys.stdout.isatty() if from_env is None else from_env
+
+
 @contextmanager
 def _python_logging_setup(
     level: LogLevel, log_levels_by_target: dict[str, LogLevel], *, print_stacktrace: bool
@@ -147,8 +176,35 @@ def _python_logging_setup(
         set_logging_handlers(handlers)
 
 
+@dataclass(frozen=True)
+class LogRetention:
+    """How `pants.log` is rotated and the logs of runs are retained: see the `[GLOBAL].log_*`
+    options.
+
+    The default keeps every log, which is appropriate for short-lived processes such as tests.
+    """
+
+    max_size_bytes: int = 0
+    max_rotated_files: int = 0
+    compress_rotated_files: bool = False
+    max_run_logs: int = sys.maxsize
+
+
+@dataclass(frozen=True)
+class LogDedup:
+    """How repeated log records are collapsed: see the `[GLOBAL].log_dedup_*` options.
+
+    The default logs every record.
+    """
+
+    window_secs: float = 0.0
+    excluded_targets: tuple[str, ...] = ()
+
+
 @contextmanager
-def initialize_stdio(global_bootstrap_options: OptionValueContainer) -> Iterator[None]:
+def initialize_stdio(
+    global_bootstrap_options: OptionValueContainer, *, daemon: bool = False
+) -> Iterator[None]:
     """Mutates sys.std* and logging to route stdio for a Pants process to thread local destinations.
 
     In this context, `sys.std*` and logging handlers will route through Rust code that uses
@@ -161,15 +217,29 @@ def initialize_stdio(global_bootstrap_options: OptionValueContainer) -> Iterator
       immediately before setting a `stdio_destination` for the remainder of the run.
     * PantsDaemon, immediately on startup. The process will then default to sending stdio to the log
       until client connections arrive, at which point `stdio_destination` is used per-connection.
+
+    Records are only forwarded to the `[GLOBAL].log_system_sink` by the daemon.
     """
     with initialize_stdio_raw(
         global_bootstrap_options.level,
         global_bootstrap_options.log_show_rust_3rdparty,
         global_bootstrap_options.show_log_target,
+        global_bootstrap_options.log_format,
         _get_log_levels_by_target(global_bootstrap_options),
         global_bootstrap_options.print_stacktrace,
         global_bootstrap_options.ignore_warnings,
         global_bootstrap_options.pants_workdir,
+        LogRetention(
+            max_size_bytes=global_bootstrap_options.log_max_size_bytes,
+            max_rotated_files=global_bootstrap_options.log_max_rotated_files,
+            compress_rotated_files=global_bootstrap_options.log_compress_rotated_files,
+            max_run_logs=global_bootstrap_options.log_max_run_logs,
+        ),
+        global_bootstrap_options.log_system_sink if daemon else None,
+        LogDedup(
+            window_secs=global_bootstrap_options.log_dedup_window,
+            excluded_targets=tuple(global_bootstrap_options.log_dedup_excluded_targets),
+        ),
     ):
         yield
 
@@ -179,10 +249,14 @@ def initialize_stdio_raw(
     global_level: LogLevel,
     log_show_rust_3rdparty: bool,
     show_target: bool,
+    log_format: LogFormat,
     log_levels_by_target: dict[str, LogLevel],
     print_stacktrace: bool,
     ignore_warnings: list[str],
     pants_workdir: str,
+    log_retention: LogRetention = LogRetention(),
+    system_log_sink: SystemLogSink | None = None,
+    log_dedup: LogDedup = LogDedup(),
 ) -> Iterator[None]:
     literal_filters = []
     regex_filters = []
@@ -203,10 +277,18 @@ def initialize_stdio_raw(
             global_level.level,
             log_show_rust_3rdparty,
             show_target,
+            log_format.value,
             {k: v.level for k, v in log_levels_by_target.items()},
             tuple(literal_filters),
             tuple(regex_filters),
             log_path,
+            log_retention.max_size_bytes,
+            log_retention.max_rotated_files,
+            log_retention.compress_rotated_files,
+            log_retention.max_run_logs,
+            system_log_sink.value if system_log_sink else None,
+            log_dedup.window_secs,
+            log_dedup.excluded_targets,
         )
         sys.stdin = TextIOWrapper(
             BufferedReader(raw_stdin),
@@ -1,6 +1,8 @@
 # Copyright 2021 Pants project contributors (see CONTRIBUTORS.md).
 # Licensed under the Apache License, Version 2.0 (see LICENSE).
 
+import json
+
 from pants.testutil.pants_integration_test import run_pants, setup_tmpdir
 
 PLUGIN = """
@@ -83,3 +85,29 @@ def test_log_by_level() -> None:
     # properly.
     assert "[DEBUG] (workunit_store) Starting: `logger` goal" in result.stderr
     assert "[DEBUG] (workunit_store) Completed: `logger` goal" in result.stderr
+
+
+def test_json_log_format() -> None:
+    with setup_tmpdir({"plugins/logger.py": PLUGIN, "plugins/register.py": REGISTER}) as tmpdir:
+        result = run_pants(
+            [
+                f"--pythonpath={tmpdir}",
+                "--backend-packages=plugins",
+                "--no-dynamic-ui",
+                "--log-format=json",
+                "--level=info",
+                "logger",
+            ]
+        )
+    result.assert_success()
+
+    records = [json.loads(line) for line in result.stderr.splitlines() if line.startswith("{")]
+    [record] = [
+        record
+        for record in records
+        if record["target"] == "plugins.logger.globalLevel" and record["level"] == "WARN"
+    ]
+    assert record["message"] == "warn log"
+    assert record["timestamp"].endswith("Z")
+    # The record was logged by the `@goal_rule`, and so in its workunit.
+    assert isinstance(record["workunit_id"], str)
@@ -0,0 +1,43 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+import pytest
+
+from pants.init.logging import colors_from_env, use_color
+from pants.option.option_value_container import OptionValueContainerBuilder
+from pants.option.ranked_value import Rank, RankedValue
+
+
+@pytest.mark.parametrize(
+    "env, expected",
+    [
+        ({}, None),
+        ({"NO_COLOR": "1"}, False),
+        ({"NO_COLOR": ""}, None),
+        ({"FORCE_COLOR": "1"}, True),
+        ({"FORCE_COLOR": ""}, True),
+        ({"FORCE_COLOR": "0"}, False),
+        ({"FORCE_COLOR": "false"}, False),
+        ({"NO_COLOR": "1", "FORCE_COLOR": "1"}, False),
+    ],
+)
+def test_colors_from_env(env: dict[str, str], expected: bool | None) -> None:
+    assert colors_from_env(env) == expected
+
+
+@pytest.mark.parametrize(
+    "rank, colors, env, expected",
+    [
+        # An explicitly set option takes precedence over the environment.
+        (Rank.FLAG, True, {"NO_COLOR": "1"}, True),
+        (Rank.CONFIG, False, {"FORCE_COLOR": "1"}, False),
+        (Rank.HARDCODED, False, {"FORCE_COLOR": "1"}, True),
+        (Rank.HARDCODED, True, {"NO_COLOR": "1"}, False),
+    ],
+)
+def test_use_color(rank: Rank, colors: bool, env: dict[str, str], expected: bool) -> None:
+    builder = OptionValueContainerBuilder()
+    builder.colors = RankedValue(rank, colors)
+    assert use_color(builder.build(), env) == expected
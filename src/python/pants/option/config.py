@@ -11,6 +11,7 @@ from types import SimpleNamespace
 from typing import Any, Dict, Iterable, List, Mapping, Protocol, Union, cast
 
 import toml
+import yaml
 
 from pants.base.build_environment import get_buildroot
 from pants.option.errors import ConfigError, ConfigValidationError, InterpolationMissingOptionError
@@ -41,7 +42,20 @@ class ConfigSource(Protocol):
         raise NotImplementedError()
 
 
+@dataclass(frozen=True)
+class _IncludedConfigSource:
+    path: str
+    content: bytes
+
+
 DEFAULT_SECTION = "DEFAULT"
+# The top-level key of a config file which lists the config files that it includes.
+INCLUDE_KEY = "include"
+
+
+def is_yaml(path: str) -> bool:
+    """Whether the config file at the given path is in YAML format, rather than in TOML format."""
+    return path.endswith((".yaml", ".yml"))
 
 
 @dataclass(frozen=True, eq=False)
@@ -51,6 +65,15 @@ class Config:
 
     Supports variable substitution using old-style Python format strings. E.g., %(var_name)s will be
     replaced with the value of var_name.
+
+    Environment variables may also be referenced as ${env.VAR_NAME}, or as
+    ${env.VAR_NAME:-fallback} to use `fallback` if the variable is unset or empty.
+
+    A config file may include other config files, listed by a top-level `include` key, which are
+    loaded before it (so that it overrides them).
+
+    Config files are in TOML format, or in YAML format if their path ends with `.yaml` or `.yml`,
+    with the same structure: a top-level table (mapping) of sections.
     """
 
     values: tuple[_ConfigValues, ...]
@@ -72,27 +95,91 @@ class Config:
         access of the environment variable names (e.g.: `env.HOME`).
         """
         config_values = []
+        parsed_paths: set[str] = set()
         for file_content in file_contents:
             normalized_seed_values = cls._determine_seed_values(seed_values=seed_values, env=env)
+            config_values.extend(
+                cls._parse_with_includes(file_content, normalized_seed_values, parsed_paths)
+            )
+        return cls(tuple(config_values))
+
+    @classmethod
+    def _parse_with_includes(
+        cls,
+        config_source: ConfigSource,
+        normalized_seed_values: dict[str, Any],
+        parsed_paths: set[str],
+        stack: tuple[str, ...] = (),
+    ) -> list[_ConfigValues]:
+        """Parse the given config source, preceded by the config files that it transitively
+        includes, in ascending order of precedence.
+
+        This mirrors the native options parser: each included file is loaded (only the first time
+        it is included by any file) before the file including it, and a file which transitively
+        includes itself is an error.
+        """
+        path = os.path.realpath(config_source.path)
+        parsed_paths.add(path)
+        stack = (*stack, path)
+        try:
+            config_values = cls._parse(config_source, normalized_seed_values)
+        except Exception as e:
+            config_format = "YAML" if is_yaml(config_source.path) else "TOML"
+            raise ConfigError(
+                f"Config file {config_source.path} could not be parsed as {config_format}:\n  {e}"
+            )
+
+        result = []
+        for include in config_values.included_paths():
+            include_path = os.path.realpath(include)
+            if include_path in stack:
+                cycle = " -> ".join((*stack[stack.index(include_path) :], include_path))
+                raise ConfigError(f"Config file {include} includes itself, via: {cycle}")
+            if include_path in parsed_paths:
+                continue
             try:
-                config_values.append(cls._parse_toml(file_content, normalized_seed_values))
-            except Exception as e:
+                with open(include, "rb") as f:
+                    content = f.read()
+            except OSError as e:
                 raise ConfigError(
-                    f"Config file {file_content.path} could not be parsed as TOML:\n  {e}"
+                    f"Failed to read config file {include}: {e} (included by {config_source.path})"
                 )
-        return cls(tuple(config_values))
+            result.extend(
+                cls._parse_with_includes(
+                    _IncludedConfigSource(include, content),
+                    normalized_seed_values,
+                    parsed_paths,
+                    stack,
+                )
+            )
+        result.append(config_values)
+        return result
 
     @classmethod
-    def _parse_toml(
+    def _parse(
         cls, config_source: ConfigSource, normalized_seed_values: dict[str, Any]
     ) -> _ConfigValues:
-        """Attempt to parse as TOML, raising an exception on failure."""
-        toml_values = toml.loads(config_source.content.decode())
+        """Attempt to parse as TOML or YAML, raising an exception on failure."""
+        content = config_source.content.decode()
+        if is_yaml(config_source.path):
+            # An empty YAML document is an empty config.
+            values = yaml.safe_load(content)
+            if values is None:
+                values = {}
+            if not isinstance(values, dict):
+                raise ValueError(f"Expected a mapping of sections, but given {values}")
+        else:
+            values = toml.loads(content)
+        include = values.pop(INCLUDE_KEY, [])
+        if not isinstance(include, list) or not all(isinstance(p, str) for p in include):
+            raise ValueError(
+                f"Expected `{INCLUDE_KEY}` to be an array of paths, but given {include}"
+            )
         seed_values = {
             **normalized_seed_values,
-            **toml_values.get(DEFAULT_SECTION, {}),
+            **values.get(DEFAULT_SECTION, {}),
         }
-        return _ConfigValues(config_source, toml_values, seed_values)
+        return _ConfigValues(config_source, values, seed_values, tuple(include))
 
     def verify(self, section_to_valid_options: dict[str, set[str]]):
         error_log = []
@@ -174,16 +261,30 @@ _TomlValue = Union[_TomlPrimitive, List[_TomlPrimitive], Dict[str, _TomlPrimitiv
 
 @dataclass(frozen=True)
 class _ConfigValues:
-    """The parsed contents of a TOML config file."""
+    """The parsed contents of a TOML or YAML config file."""
 
     source: ConfigSource
     section_to_values: dict[str, dict[str, Any]]
     seed_values: dict[str, Any]
+    include: tuple[str, ...] = ()
 
     @property
     def path(self) -> str:
         return self.source.path
 
+    def included_paths(self) -> list[str]:
+        """The paths of the config files included by this one, which may contain placeholders, and
+        are relative to this one."""
+        return [
+            os.path.join(
+                os.path.dirname(self.path),
+                self._possibly_interpolate_value(
+                    path, option=INCLUDE_KEY, section="<top level>", section_values={}
+                ),
+            )
+            for path in self.include
+        ]
+
     def _possibly_interpolate_value(
         self,
         raw_value: str,
@@ -215,7 +316,21 @@ class _ConfigValues:
                     bad_reference,
                 )
 
+        def substitute_env_var(match: re.Match[str]) -> str:
+            env_var, fallback = match.group("env_var"), match.group("fallback")
+            env_value = getattr(self.seed_values.get("env"), env_var, None)
+            if fallback is not None and not env_value:
+                return fallback
+            if env_value is None:
+                raise InterpolationMissingOptionError(option, section, raw_value, f"env.{env_var}")
+            return str(env_value)
+
         def recursively_format_str(value: str) -> str:
+            value = re.sub(
+                pattern=r"\$\{env\.(?P<env_var>[a-zA-Z_0-9]+)(?::-(?P<fallback>[^}]*))?\}",
+                repl=substitute_env_var,
+                string=value,
+            )
             # It's possible to interpolate with a value that itself has an interpolation.
             match = re.search(r"%\(([a-zA-Z_0-9.]+)\)s", value)
             if not match:
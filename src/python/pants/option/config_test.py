@@ -3,7 +3,10 @@
 from __future__ import annotations
 
 import itertools
+import os
+import re
 from dataclasses import dataclass
+from pathlib import Path
 from textwrap import dedent
 from typing import Dict
 
@@ -11,6 +14,7 @@ import pytest
 
 from pants.engine.fs import FileContent
 from pants.option.config import Config, TomlSerializer
+from pants.option.errors import ConfigError, InterpolationMissingOptionError
 
 
 @dataclass(frozen=True)
@@ -286,6 +290,144 @@ def test_merged_config() -> None:
     _compare(config, _expected_combined_values)
 
 
+def test_env_var_references() -> None:
+    content = dedent(
+        """
+        [DEFAULT]
+        cache_root = "${env.NAME:-default}/%(answer)s"
+        answer = 42
+
+        [a]
+        set = "${env.NAME}"
+        unset = "${env.UNSET:-fallback} ${env.UNSET:-}"
+        interpolated = "${env.UNSET:-%(cache_root)s}/cache"
+        not_env = "${NAME}"
+        missing = "${env.UNSET}"
+        """
+    )
+    config = Config.load(
+        [FileContent("file.toml", content.encode())], seed_values=_seed_values, env=_env
+    )
+    assert config.get("a", "set") == ["foo"]
+    assert config.get("a", "unset") == ["fallback "]
+    assert config.get("a", "interpolated") == ["foo/42/cache"]
+    assert config.get("a", "not_env") == ["${NAME}"]
+    with pytest.raises(InterpolationMissingOptionError):
+        config.get("a", "missing")
+
+
+def test_includes(tmp_path: Path) -> None:
+    files = {
+        "pants.toml": dedent(
+            """\
+            include = ["shared/remote-cache.toml", "shared/%(buildroot)s.toml"]
+            [a]
+            foo = "pants"
+            """
+        ),
+        "shared/remote-cache.toml": dedent(
+            """\
+            include = ["base.toml"]
+            [a]
+            foo = "remote"
+            bar = "remote"
+            """
+        ),
+        "shared/fake_buildroot.toml": 'include = ["base.toml"]\n[a]\nbar = "interpolated"\n',
+        "shared/base.toml": '[a]\nbaz = "base"\n',
+    }
+    for path, content in files.items():
+        (tmp_path / path).parent.mkdir(parents=True, exist_ok=True)
+        (tmp_path / path).write_text(content)
+
+    root = tmp_path / "pants.toml"
+    config = Config.load(
+        [FileContent(str(root), root.read_bytes())], seed_values=_seed_values, env=_env
+    )
+    # The base config is only included once, by the first file which includes it.
+    assert [source.path for source in config.sources()] == [
+        str(tmp_path / "shared/base.toml"),
+        str(tmp_path / "shared/remote-cache.toml"),
+        str(tmp_path / "shared/fake_buildroot.toml"),
+        str(root),
+    ]
+    assert config.get("a", "foo") == ["remote", "pants"]
+    assert config.get("a", "bar") == ["remote", "interpolated"]
+    assert config.get("a", "baz") == ["base"]
+
+    # Reloading the sources, as options bootstrapping does, does not include files again.
+    reloaded = Config.load(config.sources(), seed_values=_seed_values, env=_env)
+    assert reloaded.sources() == config.sources()
+
+
+def test_include_errors(tmp_path: Path) -> None:
+    files = {
+        "a.toml": 'include = ["b/b.toml"]',
+        "b/b.toml": 'include = ["../a.toml"]',
+        "missing.toml": 'include = ["does-not-exist.toml"]',
+        "not_a_list.toml": 'include = "a.toml"',
+    }
+    for path, content in files.items():
+        (tmp_path / path).parent.mkdir(parents=True, exist_ok=True)
+        (tmp_path / path).write_text(content)
+
+    def load(path: str) -> Config:
+        file_path = tmp_path / path
+        return Config.load([FileContent(str(file_path), file_path.read_bytes())])
+
+    root = os.path.realpath(tmp_path)
+    cycle = f"includes itself, via: {root}/a.toml -> {root}/b/b.toml -> {root}/a.toml"
+    with pytest.raises(ConfigError, match=re.escape(cycle)):
+        load("a.toml")
+    with pytest.raises(ConfigError, match="Failed to read config file .*does-not-exist.toml"):
+        load("missing.toml")
+    with pytest.raises(ConfigError, match="Expected `include` to be an array of paths"):
+        load("not_a_list.toml")
+
+
+def test_yaml_config() -> None:
+    toml_content = dedent(
+        """\
+        [DEFAULT]
+        answer = 42
+
+        [a]
+        fast = true
+        path = "/a/b/%(answer)s"
+        list = ["x", "y"]
+        list2.add = [1, 2]
+        list2.remove = [3]
+        dict = {key = "value"}
+        """
+    )
+    yaml_content = dedent(
+        """\
+        DEFAULT:
+          answer: 42
+        a:
+          fast: true
+          path: /a/b/%(answer)s
+          list: [x, y]
+          list2:
+            add: [1, 2]
+            remove: [3]
+          dict:
+            key: value
+        """
+    )
+    toml_config = Config.load([FileContent("pants.toml", toml_content.encode())])
+    yaml_config = Config.load([FileContent("pants.yaml", yaml_content.encode())])
+    for option in ("fast", "path", "list", "list2", "dict"):
+        assert toml_config.get("a", option) == yaml_config.get("a", option)
+    assert yaml_config.get("a", "path") == ["/a/b/42"]
+    assert yaml_config.get("a", "list2") == ["+[1, 2],-[3]"]
+
+    # An empty document is an empty config.
+    assert Config.load([FileContent("pants.yml", b"")]).get("a", "fast") == []
+    with pytest.raises(ConfigError, match="could not be parsed as YAML"):
+        Config.load([FileContent("pants.yaml", b"- a\n")])
+
+
 def test_toml_serializer() -> None:
     original_values: Dict = {
         "GLOBAL": {
@@ -19,7 +19,7 @@ from typing_extensions import assert_never
 
 from pants.base.build_environment import (
     get_buildroot,
-    get_default_pants_config_file,
+    get_default_pants_config_files,
     get_pants_cachedir,
     is_in_container,
     pants_version,
@@ -63,6 +63,9 @@ logger = logging.getLogger(__name__)
 # in order to ease interaction with the StoreGCService, which needs to be aware of its value.
 LOCAL_STORE_LEASE_TIME_SECS = 2 * 60 * 60
 
+# NB: Must be kept in sync with `MAX_NICENESS` in `process_execution::bounded`.
+MAX_NICENESS = 19
+
 
 MEGABYTES = 1_000_000
 GIGABYTES = 1_000 * MEGABYTES
@@ -73,6 +76,8 @@ class DynamicUIRenderer(Enum):
 
     indicatif_spinner = "indicatif-spinner"
     experimental_prodash = "experimental-prodash"
+    compact = "compact"
+    experimental_tui = "experimental-tui"
 
 
 _G = TypeVar("_G", bound="_GlobMatchErrorBehaviorOptionBase")
@@ -222,6 +227,29 @@ class OwnersNotFoundBehavior(_GlobMatchErrorBehaviorOptionBase):
 
 
 @enum.unique
+class LogFormat(Enum):
+    """The format of log records: see the global option `log_format`."""
+
+    text = "text"
+    json = "json"
+
+
+class SystemLogSink(Enum):
+    """A system log which pantsd forwards records to: see the global option `log_system_sink`."""
+
+    syslog = "syslog"
+    journald = "journald"
+
+
+class FileWatcher(Enum):
+    """How to watch the filesystem for changes: see the global option `file_watcher`."""
+
+    native = "native"
+    polling = "polling"
+    auto = "auto"
+    watchman = "watchman"
+
+
 class RemoteCacheWarningsBehavior(Enum):
     ignore = "ignore"
     first_only = "first_only"
@@ -236,6 +264,20 @@ class CacheContentBehavior(Enum):
     defer = "defer"
 
 
+class DigestFunction(Enum):
+    """The function that the digests of files and directories are computed with."""
+
+    sha256 = "sha256"
+    blake3 = "blake3"
+
+
+class LocalStoreBackend(Enum):
+    """The database that the local store and caches are stored in."""
+
+    lmdb = "lmdb"
+    sqlite = "sqlite"
+
+
 class KeepSandboxes(Enum):
     """An enum for the global option `keep_sandboxes`.
 
@@ -599,6 +641,11 @@ class DynamicRemoteOptions:
         return re.sub(r"^grpc", "http", address) if address else None
 
 
+def _bundled_sandboxer_bin() -> str:
+    # NB: The binary is bundled alongside the native client: see `src/python/pants/bin:sandboxer`.
+    return str(Path(__file__).parent.parent / "bin" / "sandboxer")
+
+
 @dataclass(frozen=True)
 class ExecutionOptions:
     """A collection of all options related to (remote) execution of processes.
@@ -619,12 +666,21 @@ class ExecutionOptions:
     remote_client_key_path: str | None
 
     keep_sandboxes: KeepSandboxes
+    deterministic_sandbox_paths: bool
+    overlay_sandboxes: bool
     local_cache: bool
     process_execution_local_parallelism: int
     process_execution_local_enable_nailgun: bool
     process_execution_remote_parallelism: int
     process_execution_cache_namespace: str | None
     process_execution_graceful_shutdown_timeout: int
+    process_execution_dry_run: bool
+    explain_cache_misses: bool
+    sandboxer_bin: str | None
+    provenance_outputs: tuple[str, ...]
+    process_audit_log_dir: str | None
+    process_audit_env_allowlist: tuple[str, ...]
+    process_routing_rules: tuple[str, ...]
     cache_content_behavior: CacheContentBehavior
 
     process_total_child_memory_usage: int | None
@@ -668,12 +724,24 @@ class ExecutionOptions:
             remote_client_key_path=bootstrap_options.remote_client_key_path,
             # Process execution setup.
             keep_sandboxes=GlobalOptions.resolve_keep_sandboxes(bootstrap_options),
+            deterministic_sandbox_paths=bootstrap_options.deterministic_sandbox_paths,
+            overlay_sandboxes=bootstrap_options.overlay_sandboxes,
             local_cache=bootstrap_options.local_cache,
             process_execution_local_parallelism=bootstrap_options.process_execution_local_parallelism,
             process_execution_remote_parallelism=dynamic_remote_options.parallelism,
             process_execution_cache_namespace=bootstrap_options.process_execution_cache_namespace,
             process_execution_graceful_shutdown_timeout=bootstrap_options.process_execution_graceful_shutdown_timeout,
             process_execution_local_enable_nailgun=bootstrap_options.process_execution_local_enable_nailgun,
+            process_execution_dry_run=bootstrap_options.process_execution_dry_run,
+            explain_cache_misses=bootstrap_options.explain_cache_misses,
+            sandboxer_bin=(
+                bootstrap_options.sandboxer_bin
+                or (_bundled_sandboxer_bin() if bootstrap_options.sandboxer else None)
+            ),
+            provenance_outputs=tuple(bootstrap_options.provenance_outputs),
+            process_audit_log_dir=bootstrap_options.process_audit_log_dir,
+            process_audit_env_allowlist=tuple(bootstrap_options.process_audit_env_allowlist),
+            process_routing_rules=tuple(bootstrap_options.process_routing_rules),
             cache_content_behavior=bootstrap_options.cache_content_behavior,
             process_total_child_memory_usage=bootstrap_options.process_total_child_memory_usage,
             process_per_child_memory_usage=bootstrap_options.process_per_child_memory_usage,
@@ -707,10 +775,17 @@ class LocalStoreOptions:
     """
 
     store_dir: str = os.path.join(get_pants_cachedir(), "lmdb_store")
+    shared_store_dir: str | None = None
+    peer_store_dirs: tuple[str, ...] = ()
     processes_max_size_bytes: int = 16 * GIGABYTES
+    dep_inference_max_size_bytes: int = 4 * GIGABYTES
     files_max_size_bytes: int = 256 * GIGABYTES
     directories_max_size_bytes: int = 16 * GIGABYTES
+    max_size_growth_factor: int = 4
     shard_count: int = 16
+    backend: LocalStoreBackend = LocalStoreBackend.lmdb
+    artifacts_retention_secs: int = LOCAL_STORE_LEASE_TIME_SECS
+    digest_function: DigestFunction = DigestFunction.sha256
 
     def target_total_size_bytes(self) -> int:
         """Returns the target total size of all of the stores.
@@ -723,6 +798,7 @@ class LocalStoreOptions:
         """
         max_total_size_bytes = (
             self.processes_max_size_bytes
+            + self.dep_inference_max_size_bytes
             + self.files_max_size_bytes
             + self.directories_max_size_bytes
         )
@@ -732,10 +808,23 @@ class LocalStoreOptions:
     def from_options(cls, options: OptionValueContainer) -> LocalStoreOptions:
         return cls(
             store_dir=str(Path(options.local_store_dir).resolve()),
+            shared_store_dir=(
+                str(Path(options.local_store_shared_dir).resolve())
+                if options.local_store_shared_dir
+                else None
+            ),
+            peer_store_dirs=tuple(
+                str(Path(peer_dir).resolve()) for peer_dir in options.local_store_peer_dirs
+            ),
             processes_max_size_bytes=options.local_store_processes_max_size_bytes,
+            dep_inference_max_size_bytes=options.local_store_dep_inference_max_size_bytes,
             files_max_size_bytes=options.local_store_files_max_size_bytes,
             directories_max_size_bytes=options.local_store_directories_max_size_bytes,
+            max_size_growth_factor=options.local_store_max_size_growth_factor,
             shard_count=options.local_store_shard_count,
+            backend=options.local_store_backend,
+            artifacts_retention_secs=options.local_store_artifacts_retention_secs,
+            digest_function=options.digest_function,
         )
 
 
@@ -760,10 +849,19 @@ DEFAULT_EXECUTION_OPTIONS = ExecutionOptions(
     process_execution_remote_parallelism=128,
     process_execution_cache_namespace=None,
     keep_sandboxes=KeepSandboxes.never,
+    deterministic_sandbox_paths=False,
+    overlay_sandboxes=False,
     local_cache=True,
     cache_content_behavior=CacheContentBehavior.fetch,
     process_execution_local_enable_nailgun=True,
     process_execution_graceful_shutdown_timeout=3,
+    process_execution_dry_run=False,
+    explain_cache_misses=False,
+    sandboxer_bin=None,
+    provenance_outputs=(),
+    process_audit_log_dir=None,
+    process_audit_env_allowlist=(),
+    process_routing_rules=(),
     # Remote store setup.
     remote_store_address=None,
     remote_store_headers={
@@ -861,6 +959,21 @@ class BootstrapOptions:
             """
         ),
     )
+    log_format = EnumOption(
+        default=LogFormat.text,
+        daemon=True,
+        advanced=True,
+        help=softwrap(
+            """
+            The format of log records.
+
+            With `json`, each record is a JSON object on a line of its own, with the fields
+            `timestamp` (in RFC 3339 format, in UTC), `level`, `target`, `workunit_id` (the id of
+            the workunit which the record was logged in, if any) and `message`, for ingestion by
+            log aggregation tools.
+            """
+        ),
+    )
     log_levels_by_target = DictOption[str](
         # TODO: While we would like this option to be fingerprinted for the daemon, the Rust side
         # option parser does not support dict options. See #19832.
@@ -882,6 +995,87 @@ class BootstrapOptions:
         advanced=True,
         help="Whether to show/hide logging done by 3rdparty Rust crates used by the Pants engine.",
     )
+    log_max_size_bytes = IntOption(
+        default=100 * MEGABYTES,
+        daemon=True,
+        advanced=True,
+        help=softwrap(
+            """
+            The size beyond which the log of Pants (`pants.log` in `--pants-workdir`, which
+            `pantsd` logs to) is rotated: it is renamed to `pants.log.1`, after renaming any
+            previously rotated logs from `pants.log.<n>` to `pants.log.<n+1>`.
+
+            Set to `0` to never rotate the log.
+            """
+        ),
+    )
+    log_max_rotated_files = IntOption(
+        default=5,
+        daemon=True,
+        advanced=True,
+        help="The number of rotated logs (see `--log-max-size-bytes`) to keep.",
+    )
+    log_compress_rotated_files = BoolOption(
+        default=False,
+        daemon=True,
+        advanced=True,
+        help="Whether to compress rotated logs with gzip, as `pants.log.<n>.gz`.",
+    )
+    log_max_run_logs = IntOption(
+        default=100,
+        daemon=True,
+        advanced=True,
+        help=softwrap(
+            """
+            The number of the logs of individual runs (in `run-tracker` in `--pants-workdir`) to
+            keep. The logs of older runs are removed as new runs start.
+            """
+        ),
+    )
+    log_system_sink = EnumOption(
+        enum_type=SystemLogSink,
+        default=None,
+        daemon=True,
+        advanced=True,
+        help=softwrap(
+            """
+            A system log to forward the records of pantsd to, in addition to `pants.log`.
+
+            With `syslog`, records are sent to the local syslog daemon with the `daemon` facility,
+            under the identifier `pantsd`. With `journald`, records are sent to systemd-journald
+            along with the structured fields `PANTS_LOG_TARGET` and `PANTS_WORKUNIT_ID` (the
+            id of the workunit which the record was logged in, if any), and the source location of
+            the record.
+
+            In both cases, the levels of records are mapped to the equivalent syslog priorities.
+            """
+        ),
+    )
+    log_dedup_window = FloatOption(
+        default=10.0,
+        daemon=True,
+        advanced=True,
+        help=softwrap(
+            """
+            The number of seconds within which identical log records (with the same level, target
+            and message) are collapsed: the first record is logged, and any repeats of it within
+            the window are logged once the window ends, as `<message> (repeated <n> times)`.
+
+            Set to `0` to log every record.
+            """
+        ),
+    )
+    log_dedup_excluded_targets = StrListOption(
+        daemon=True,
+        advanced=True,
+        help=softwrap(
+            """
+            Logging targets whose records are never collapsed (see `--log-dedup-window`). The
+            names of logging targets are specified in log strings when the `--show-log-target`
+            option is set.
+            """
+        ),
+    )
     ignore_warnings = StrListOption(
         daemon=True,
         advanced=True,
@@ -971,7 +1165,7 @@ class BootstrapOptions:
         # NB: We don't fingerprint the list of config files, because the content of the config
         # files independently affects fingerprints.
         fingerprint=False,
-        default=lambda _: [get_default_pants_config_file()],
+        default=lambda _: get_default_pants_config_files(),
         help=softwrap(
             """
             Paths to Pants config files. This may only be set through the environment variable
@@ -1018,6 +1212,27 @@ class BootstrapOptions:
         default=["/etc/pantsrc", "~/.pants.rc", ".pants.rc"],
         help="Override config with values from these files, using syntax matching that of `--pants-config-files`.",
     )
+    dotenv = BoolOption(
+        advanced=True,
+        default=False,
+        # NB: See `--pants-config-files`.
+        fingerprint=False,
+        help=softwrap(
+            """
+            Load environment variables from the `.env` file in the build root, if it exists.
+
+            Variables set in the file have a lower precedence than those set in the actual
+            environment. They are visible both to options (via `PANTS_*` variables and
+            `${env.VAR}` references in config files) and to processes run by Pants that request
+            environment variables, e.g. via `[subprocess-environment].env_vars`. The file is
+            re-read on every run, so edits take effect without restarting `pantsd`.
+
+            Each line of the file is either blank, a `#` comment, or of the form `NAME=value`,
+            optionally prefixed by `export`. Values may be single quoted, in which case they are
+            taken literally, or double quoted, in which case backslash escapes are interpreted.
+            """
+        ),
+    )
     pythonpath = StrListOption(
         advanced=True,
         help=softwrap(
@@ -1084,6 +1299,8 @@ class BootstrapOptions:
             example, you can use `!my_pattern` in `pants_ignore` to have Pants operate on files
             that are gitignored.
 
+            When `pantsd` is used, changes to these files are applied without restarting it.
+
             Warning: this does not yet support reading nested gitignore files.
             """
         ),
@@ -1135,10 +1352,28 @@ class BootstrapOptions:
             raising a timeout exception.
             Because pantsd currently does not support parallel runs,
             any prior running Pants command must be finished for the current one to start.
+            The native client also waits for up to this long for a pantsd which is still starting up.
             To never timeout, use the value -1.
             """
         ),
     )
+    pantsd_rerun_after_restart = BoolOption(
+        advanced=True,
+        default=False,
+        help=softwrap(
+            """
+            If pantsd exits during a run (e.g. because it was killed for using too much memory),
+            relaunch it and run the same command again, once, rather than failing. When disabled,
+            a run whose pantsd exits fails, and is not run again by either client.
+
+            The whole command runs again: a run does not resume where it stopped, by skipping the
+            goals or targets which had completed. But the processes and other work which completed
+            before pantsd exited are served from the local caches. Commands with side effects (such
+            as `publish` or `run`) will have those side effects again, so this is disabled by
+            default.
+            """
+        ),
+    )
     pantsd_max_memory_usage = MemorySizeOption(
         advanced=True,
         default=memory_size("4GiB"),
@@ -1160,6 +1395,26 @@ class BootstrapOptions:
             """
         ),
     )
+    pantsd_memory_budget = MemorySizeOption(
+        advanced=True,
+        default=None,
+        help=softwrap(
+            """
+            A memory usage of the pantsd process above which it releases memory, without restarting.
+
+            When the budget is exceeded, the daemon progressively drops the in-memory values which
+            have gone unused for the most runs (which are recomputed or read from the local caches
+            if they are needed again), until it is back within the budget, and logs what it
+            released. If it is still above `--pantsd-max-memory-usage` afterward, it restarts.
+
+            The budget should be lower than `--pantsd-max-memory-usage`, and is disabled by
+            default.
+
+            You can suffix with `GiB`, `MiB`, `KiB`, or `B` to indicate the unit, e.g.
+            `2GiB` or `2.12GiB`. A bare number will be in bytes.
+            """
+        ),
+    )
 
     # These facilitate configuring the native engine.
     print_stacktrace = BoolOption(
@@ -1186,6 +1441,121 @@ class BootstrapOptions:
         daemon=True,
         help="The port to bind the Pants nailgun server to. Defaults to a random port.",
     )
+    pantsd_tokio_console = BoolOption(
+        advanced=True,
+        default=False,
+        daemon=True,
+        help=softwrap(
+            """
+            Allow [`tokio-console`](https://github.com/tokio-rs/console) to attach to pantsd, to
+            inspect the async tasks of the engine: e.g. to find tasks which are stuck, or which
+            are starved of time to run.
+
+            pantsd listens for `tokio-console` on `127.0.0.1:6669`, or on the address set by the
+            `TOKIO_CONSOLE_BIND` environment variable when pantsd starts. Tracking tasks has a
+            small overhead, so this is disabled by default.
+            """
+        ),
+    )
+    pantsd_heap_profiling = BoolOption(
+        advanced=True,
+        default=False,
+        daemon=True,
+        help=softwrap(
+            """
+            Sample the allocations of pantsd, so that profiles of its heap can be written by
+            `POST`ing to the `/heap_profile` endpoint of the status service of pantsd (see
+            `--pantsd-status-port`).
+
+            The profiles are written under the `pantsd-diagnostics` directory of the
+            `--pants-workdir`, in the format of jemalloc's `jeprof` tool, which can render them as
+            call graphs or flamegraphs. Heap profiling is only supported on Linux.
+            """
+        ),
+    )
+    pantsd_remote_listen_address = StrOption(
+        advanced=True,
+        default=None,
+        daemon=True,
+        help=softwrap(
+            """
+            An address (e.g. `0.0.0.0:7777`) for pantsd to additionally listen on for the
+            connections of native clients on other machines, which set
+            `[GLOBAL].pantsd_remote_address` to this machine's `host:port`.
+
+            Remote connections use TLS with `[GLOBAL].pantsd_remote_tls_cert_path` and
+            `[GLOBAL].pantsd_remote_tls_key_path`, and must authenticate with
+            `[GLOBAL].pantsd_remote_token`, all of which must be set too.
+
+            The commands of remote clients run in this workspace, with the environment of pantsd
+            and the `PANTS_*` variables of the client. Anyone with the token can run any command
+            as the user running pantsd.
+            """
+        ),
+    )
+    pantsd_remote_tls_cert_path = StrOption(
+        advanced=True,
+        default=None,
+        daemon=True,
+        help=softwrap(
+            """
+            Path to a PEM file containing the certificate chain that pantsd presents to remote
+            clients: see `[GLOBAL].pantsd_remote_listen_address`.
+            """
+        ),
+    )
+    pantsd_remote_tls_key_path = StrOption(
+        advanced=True,
+        default=None,
+        daemon=True,
+        help=softwrap(
+            """
+            Path to a PEM file containing the private key of
+            `[GLOBAL].pantsd_remote_tls_cert_path`.
+            """
+        ),
+    )
+    pantsd_remote_token = StrOption(
+        advanced=True,
+        default=None,
+        help=softwrap(
+            """
+            The token that remote clients authenticate with: see
+            `[GLOBAL].pantsd_remote_listen_address` and `[GLOBAL].pantsd_remote_address`.
+
+            pantsd reads the token when it starts, so it must be restarted for a change to apply.
+            Prefer setting it in the environment (`PANTS_PANTSD_REMOTE_TOKEN`) over in a config file.
+            """
+        ),
+    )
+    pantsd_remote_address = StrOption(
+        advanced=True,
+        default=None,
+        help=softwrap(
+            """
+            The `host:port` of a pantsd on another machine to run commands with, rather than with
+            a pantsd on this one: see `[GLOBAL].pantsd_remote_listen_address`.
+
+            The remote pantsd runs commands in its own checkout of this workspace, in the same
+            directory relative to the root of the workspace as the client, and with the `PANTS_*`
+            variables of the client's environment. The local checkout is not synchronized with it.
+
+            Only the native client supports remote connections.
+            """
+        ),
+    )
+    pantsd_remote_ca_certs_path = StrOption(
+        advanced=True,
+        default=None,
+        help=softwrap(
+            """
+            Path to a PEM file containing CA certificates used for verifying the certificate of
+            `[GLOBAL].pantsd_remote_address`.
+
+            If unspecified, the root CA certificates of the system are used.
+            """
+        ),
+    )
     pantsd_invalidation_globs = StrListOption(
         advanced=True,
         daemon=True,
@@ -1196,6 +1566,25 @@ class BootstrapOptions:
             """
         ),
     )
+    pantsd_status_port = IntOption(
+        advanced=True,
+        default=None,
+        help=softwrap(
+            """
+            If set, pantsd serves an HTTP status endpoint on this port of localhost (or on a
+            random port, if `0`), which is written to the `status_port` file of the pantsd
+            metadata directory under `--pants-subprocessdir`.
+
+            `GET /status` returns the uptime, live sessions, graph size, local store size and
+            memory usage of pantsd as JSON. `POST /gc` garbage collects the local store, and
+            `POST /diagnostics` writes the graph and the stacks of all threads of pantsd under
+            the `pantsd-diagnostics` directory of `--pants-workdir`.
+
+            `GET /metrics` returns the counters and observation histograms of the runs of pantsd
+            (summed since its scheduler started) in the Prometheus text format, for scraping.
+            """
+        ),
+    )
     rule_threads_core = IntOption(
         default=max(2, CPU_COUNT // 2),
         default_help_repr="max(2, #cores/2)",
@@ -1219,6 +1608,25 @@ class BootstrapOptions:
             """
             The maximum number of threads to use to execute `@rule` logic. Defaults to
             a small multiple of `--rule-threads-core`.
+
+            The threads beyond `--rule-threads-core` form the blocking pool, which is used by
+            `@rule` logic that blocks, and (unless `--io-threads-max` is set) by blocking I/O such
+            as reading and digesting files.
+            """
+        ),
+    )
+    io_threads_max = IntOption(
+        default=None,
+        advanced=True,
+        help=softwrap(
+            """
+            If set, the maximum number of threads of a dedicated pool for blocking I/O (such as
+            reading and digesting files to capture snapshots).
+
+            By default, blocking I/O shares the blocking pool of `--rule-threads-max` with `@rule`
+            logic, which a run that captures many large snapshots can starve. The
+            `executor_blocking_queue_depth` and `executor_blocking_task_delay_micros`
+            observations (see `[stats].log`) show whether blocking tasks wait for threads.
             """
         ),
     )
@@ -1243,6 +1651,43 @@ class BootstrapOptions:
         # are likely to be able to use the same storage location.
         default=DEFAULT_LOCAL_STORE_OPTIONS.store_dir,
     )
+    local_store_shared_dir = StrOption(
+        advanced=True,
+        help=softwrap(
+            """
+            The directory of an existing local store (i.e. the `--local-store-dir` of another
+            Pants installation) to attach in read-only mode. Content which is missing from the
+            local store is copied from the shared store if it is present there, before it is
+            fetched from a remote store (if any).
+
+            This allows one store which has been warmed on a machine to be shared by all of
+            the checkouts and ephemeral containers on it, without any of them writing to it.
+            If the shared store is on a read-only filesystem, it is read without locking, and
+            so it must not be written to while it is shared.
+            """
+        ),
+        default=DEFAULT_LOCAL_STORE_OPTIONS.shared_store_dir,
+    )
+    local_store_peer_dirs = StrListOption(
+        advanced=True,
+        help=softwrap(
+            """
+            The directories of the local stores of peer workspaces (e.g. the `--local-store-dir`s
+            of other checkouts of the same repository) to attach in read-only mode.
+
+            Like `--local-store-shared-dir`, content which is missing from the local store is
+            copied from the first peer store which contains it, before it is fetched from a
+            remote store (if any). Process and dependency inference cache entries which are
+            missing locally are also copied from peer stores, so that identical work (such as
+            resolving and building third-party artifacts) is not repeated per checkout.
+
+            The peer stores are consulted after the shared store, in the order in which they are
+            listed. A peer store may be on a network filesystem, but it should not be written to
+            by another process while it is attached unless its filesystem supports locking.
+            """
+        ),
+        default=list(DEFAULT_LOCAL_STORE_OPTIONS.peer_store_dirs),
+    )
     local_store_shard_count = IntOption(
         advanced=True,
         help=softwrap(
@@ -1255,12 +1700,32 @@ class BootstrapOptions:
             into multiple shards to allow for more concurrent writers. The faster your disks
             are, the fewer shards you are likely to need for performance.
 
-            NB: After changing this value, you will likely want to manually clear the
-            `--local-store-dir` directory to clear the space used by old shard layouts.
+            After this value is changed, the existing content of each store is migrated into
+            the new shards the next time that it is opened, which may take a while for large
+            stores.
             """
         ),
         default=DEFAULT_LOCAL_STORE_OPTIONS.shard_count,
     )
+    local_store_backend = EnumOption(
+        advanced=True,
+        default=DEFAULT_LOCAL_STORE_OPTIONS.backend,
+        help=softwrap(
+            """
+            The database that the shards of the local store and caches are stored in.
+
+            `sqlite` stores each shard in a SQLite database, which (unlike an LMDB map) does not
+            reserve virtual memory for the max size of the store, and which supports concurrent
+            access on network filesystems which support locking (the databases use a rollback
+            journal rather than a write-ahead log, which does not work on network filesystems).
+            Garbage collection, sharding and peer stores behave the same for both.
+
+            The content of a store is not migrated when this value is changed: the stores of
+            each backend live side by side in the `--local-store-dir`. Peer and shared stores are
+            read with whichever backend they were created with.
+            """
+        ),
+    )
     local_store_processes_max_size_bytes = IntOption(
         advanced=True,
         help=softwrap(
@@ -1271,6 +1736,17 @@ class BootstrapOptions:
         ),
         default=DEFAULT_LOCAL_STORE_OPTIONS.processes_max_size_bytes,
     )
+    local_store_dep_inference_max_size_bytes = IntOption(
+        advanced=True,
+        help=softwrap(
+            """
+            The maximum size in bytes of the local store containing the results of dependency
+            inference, which are keyed by the digest of each parsed file so that unchanged files
+            are not parsed again. Stored below `--local-store-dir`.
+            """
+        ),
+        default=DEFAULT_LOCAL_STORE_OPTIONS.dep_inference_max_size_bytes,
+    )
     local_store_files_max_size_bytes = IntOption(
         advanced=True,
         help=softwrap(
@@ -1301,6 +1777,54 @@ class BootstrapOptions:
         ),
         default=DEFAULT_LOCAL_STORE_OPTIONS.directories_max_size_bytes,
     )
+    local_store_max_size_growth_factor = IntOption(
+        advanced=True,
+        help=softwrap(
+            """
+            The factor by which each of the local stores may automatically grow beyond its max
+            size (e.g. `--local-store-files-max-size-bytes`) when it fills up, rather than failing
+            the write that filled it. A factor of 1 disables growth.
+
+            Each shard of a store grows independently, by doubling its size until the limit
+            is reached.
+            """
+        ),
+        default=DEFAULT_LOCAL_STORE_OPTIONS.max_size_growth_factor,
+    )
+    local_store_artifacts_retention_secs = IntOption(
+        advanced=True,
+        help=softwrap(
+            f"""
+            The minimum number of seconds to retain the artifacts of workunits (such as the output
+            and reports of tests) in the local store, so that they may be loaded by their digests
+            after the run which produced them.
+
+            Other content of the local store is retained for {LOCAL_STORE_LEASE_TIME_SECS} seconds
+            after it was last used, and a shorter retention than that has no effect.
+            """
+        ),
+        default=DEFAULT_LOCAL_STORE_OPTIONS.artifacts_retention_secs,
+    )
+    digest_function = EnumOption(
+        advanced=True,
+        default=DEFAULT_LOCAL_STORE_OPTIONS.digest_function,
+        daemon=True,
+        help=softwrap(
+            """
+            The function to compute the digests of files and directories with. `blake3` is
+            substantially faster than `sha256` for large files.
+
+            Each function has its own local store (in a `blake3` directory of the
+            `--local-store-dir`, for `blake3`), and a remote store or remote execution server
+            must advertise support for a function other than `sha256`. To keep the content of an
+            existing local store when switching functions, migrate it with
+            `fs_util migrate --from-digest-function=sha256`.
+
+            Digests are computed before options are parsed, so this option may only be set with
+            the `PANTS_DIGEST_FUNCTION` environment variable.
+            """
+        ),
+    )
     _named_caches_dir = StrOption(
         advanced=True,
         help=softwrap(
@@ -1360,6 +1884,49 @@ class BootstrapOptions:
             """
         ),
     )
+    deterministic_sandbox_paths = BoolOption(
+        advanced=True,
+        default=DEFAULT_EXECUTION_OPTIONS.deterministic_sandbox_paths,
+        help=softwrap(
+            """
+            If true, the local directories used as chroots for running processes are created at
+            stable paths below `--local-execution-root-dir` (`pants-sandbox-slot-<N>`, for the
+            lowest `N` which is not in use), rather than at random paths. Each slot is cleaned
+            before it is reused, and is locked while in use, so that concurrent runs of Pants
+            never share one.
+
+            Tools which embed the absolute path of their chroot into their outputs (such as debug
+            info, pytest caches or virtualenv shebangs) then produce the same outputs for the same
+            inputs, which allows those outputs to be cached and reused by other processes.
+
+            A chroot which is preserved by `--keep-sandboxes` retains its slot until Pants exits,
+            and is removed when its slot is next used.
+            """
+        ),
+    )
+    overlay_sandboxes = BoolOption(
+        advanced=True,
+        default=DEFAULT_EXECUTION_OPTIONS.overlay_sandboxes,
+        help=softwrap(
+            """
+            If true, the chroots of local processes which have immutable inputs (such as
+            toolchains and resolved tools) are overlayfs mounts: the immutable inputs are
+            materialized once, and shared between chroots as the read-only lower layers of their
+            overlays, rather than being symlinked into each chroot. The other inputs of each process
+            are materialized in the (per-execution) upper layer of its overlay, to which its outputs
+            are also written.
+
+            Tools which do not support being symlinked can thus be used as immutable inputs,
+            without being materialized into every chroot.
+
+            The overlay is mounted by the process in its own unprivileged user and mount namespace,
+            which requires Linux 5.11 or newer, with unprivileged user namespaces enabled. Outputs
+            are captured from the upper layer, so a process cannot output the unmodified contents
+            of its immutable inputs. A chroot which is preserved by `--keep-sandboxes` contains
+            only the upper layer. Cannot be used with `--sandboxer`.
+            """
+        ),
+    )
     cache_content_behavior = EnumOption(
         advanced=True,
         default=DEFAULT_EXECUTION_OPTIONS.cache_content_behavior,
@@ -1461,6 +2028,22 @@ class BootstrapOptions:
         advanced=True,
         help="Number of concurrent processes that may be executed remotely.",
     )
+    niceness = IntOption(
+        default=0,
+        advanced=True,
+        help=softwrap(
+            f"""
+            How readily this run yields process execution slots to other runs which are using the
+            same `pantsd` instance, from 0 to {MAX_NICENESS}.
+
+            When slots are contended, they are shared between concurrent runs in proportion to
+            `{MAX_NICENESS + 1} - niceness`: for example, a long-running `lint ::` with
+            `--niceness={MAX_NICENESS}` will give up most of its slots to a concurrent `test` run
+            with the default niceness. This only affects which process starts next when a slot is
+            freed: running processes are never preempted in favor of another run.
+            """
+        ),
+    )
     process_execution_cache_namespace = StrOption(
         advanced=True,
         default=cast(str, DEFAULT_EXECUTION_OPTIONS.process_execution_cache_namespace),
@@ -1487,6 +2070,151 @@ class BootstrapOptions:
         ),
         advanced=True,
     )
+    process_execution_dry_run = BoolOption(
+        default=DEFAULT_EXECUTION_OPTIONS.process_execution_dry_run,
+        help=softwrap(
+            """
+            If true, processes which miss the caches are not executed: instead, each is logged
+            along with the components of its cache key (its argv, environment, input digests,
+            platform and execution strategy), and is treated as having succeeded without any
+            outputs. No caches are written.
+
+            This is useful to understand which processes a goal will run, and why they miss the
+            cache. But because the outputs of processes which are not executed are empty, the
+            processes which would consume them may differ from those of a real run (or the goal may
+            fail). Interactive processes (such as those of `run`) are still executed.
+            """
+        ),
+        advanced=True,
+    )
+    explain_cache_misses = BoolOption(
+        default=DEFAULT_EXECUTION_OPTIONS.explain_cache_misses,
+        help=softwrap(
+            """
+            If true, the components of the cache key of each process (its argv, environment,
+            input files, platform and execution strategy) are persisted in the local cache, and
+            when a process misses the cache, the components which changed since the last process
+            with the same description and platform are logged: e.g. the paths of the input files
+            which changed, or the names of the environment variables.
+
+            This has a cost, because the input files of every process must be listed, and so is
+            intended for investigating unexpected cache misses.
+            """
+        ),
+        advanced=True,
+    )
+    sandboxer = BoolOption(
+        default=False,
+        help=softwrap(
+            """
+            Use a `sandboxer` helper process (which is bundled with Pants) to materialize
+            sandboxes and to spawn local processes.
+
+            Spawning processes directly from a large `pantsd` makes each `fork` slow and memory
+            hungry: the sandboxer is a small helper process which does so on its behalf instead.
+            """
+        ),
+        advanced=True,
+    )
+    sandboxer_bin = StrOption(
+        default=DEFAULT_EXECUTION_OPTIONS.sandboxer_bin,
+        help=softwrap(
+            """
+            The path to a `sandboxer` binary (built from the `process_execution` crate) to use
+            rather than the one bundled with Pants. Setting it implies `--sandboxer`.
+            """
+        ),
+        advanced=True,
+    )
+    provenance_outputs = StrListOption(
+        default=DEFAULT_EXECUTION_OPTIONS.provenance_outputs,
+        metavar="<glob>",
+        help=softwrap(
+            """
+            Globs (relative to the sandbox, with `!`-prefixed excludes) for the output files of
+            processes whose provenance should be attested, e.g. `["**/*.pex", "**/*.whl"]`.
+
+            For each process which produces a matching file during a run, the engine records the
+            digest of the action, the digests of its inputs, and its argv, environment and
+            execution environment. At the end of the run, those records are written as signed
+            [SLSA provenance](https://slsa.dev/spec/v1.0/provenance) attestations: see the
+            `[provenance]` scope.
+
+            Processes whose results were memoized by `pantsd` during an earlier run are not
+            attested again: their attestations were written by that run.
+            """
+        ),
+        advanced=True,
+    )
+    process_audit_log_dir = StrOption(
+        default=DEFAULT_EXECUTION_OPTIONS.process_audit_log_dir,
+        metavar="<dir>",
+        help=softwrap(
+            """
+            If set, each run appends every process which it executes (or hits in a cache) to an
+            audit log in this directory, named `<run id>.jsonl`.
+
+            Each line is a JSON object with the argv, environment, working directory and input
+            digests of the process, and the digests of its outputs, its exit code, the environment
+            it was executed in, and its `source` (i.e. whether it ran, or which cache it was hit
+            in). Only the values of `--process-audit-env-allowlist` variables are recorded: the
+            names of other variables are listed as `redacted_env`.
+
+            Processes whose results were memoized by `pantsd` during an earlier run are recorded in
+            the audit log of that run.
+            """
+        ),
+        advanced=True,
+    )
+    process_audit_env_allowlist = StrListOption(
+        default=DEFAULT_EXECUTION_OPTIONS.process_audit_env_allowlist,
+        metavar="<name>",
+        help=softwrap(
+            """
+            The names of the environment variables of processes whose values are recorded in
+            `--process-audit-log-dir`: values are redacted by default, because they may contain
+            secrets.
+            """
+        ),
+        advanced=True,
+    )
+    process_routing_rules = StrListOption(
+        default=DEFAULT_EXECUTION_OPTIONS.process_routing_rules,
+        metavar="<route>: <conditions>",
+        help=softwrap(
+            """
+            Rules which decide whether each process which misses the caches is executed `local`ly,
+            `remote`ly, or not at all (`cache_only`, in which case the process fails). The first
+            rule whose conditions all match a process applies to it, and processes which match no
+            rule are executed as their environment specifies: e.g.
+
+                process_routing_rules = [
+                    "remote: tag=heavy",
+                    "remote: min_estimated_duration=30",
+                    "local: description=^Run (Black|isort); max_input_size=1048576",
+                ]
+
+            Conditions are separated by `;`, and are any of:
+
+            * `description=<regex>`: the regex is searched for in the description of the process.
+            * `tag=<tag>`: the process declares the tag (see `Process.tags`). May be repeated.
+            * `min_input_size=<bytes>`/`max_input_size=<bytes>`: (inclusive) bounds of the total
+            size of the input files of the process.
+            * `min_estimated_duration=<secs>`/`max_estimated_duration=<secs>`: (inclusive) bounds
+            of the duration which the process took when it last ran, which is recorded in the local
+            cache. Processes which have not run before match neither.
+
+            Only processes whose environments are local or remote are routed. A process is only
+            executed locally if its platform is that of this machine, and is only executed remotely
+            if its environment is remote, since the platform properties which select a remote worker
+            for it are those of that environment: so `local` rules move processes of remote
+            environments to this machine, and `remote` rules exempt them from later `local` rules.
+            Routing a process does not change its cache key. `remote` rules require
+            `--remote-execution`.
+            """
+        ),
+        advanced=True,
+    )
     session_end_tasks_timeout = FloatOption(
         default=3.0,
         help=softwrap(
@@ -1746,6 +2474,66 @@ class BootstrapOptions:
             """
         ),
     )
+    file_watcher = EnumOption(
+        default=FileWatcher.auto,
+        advanced=True,
+        help=softwrap(
+            f"""
+            How to watch the filesystem for changes, when `watch_filesystem` is enabled.
+
+            `{FileWatcher.native.value}` uses the notifications of the operating system (inotify on
+            Linux, and FSEvents on macOS). `{FileWatcher.polling.value}` instead polls the watched
+            files and directories for changes every `file_watcher_poll_interval`, which works on
+            network filesystems (such as NFS and SMB mounts) and in containers where native
+            notifications are unreliable or unavailable. Files which are modified are re-hashed,
+            and are only considered changed if their content has changed.
+
+            `{FileWatcher.auto.value}` uses native notifications, but falls back to polling for
+            the paths which cannot be watched natively (for example, because the limit on inotify
+            watches was reached), or for all paths if native notifications are unavailable.
+
+            `{FileWatcher.watchman.value}` subscribes to the changes observed by a
+            [Watchman](https://facebook.github.io/watchman/) server (which is started if it is not
+            already running, and must be installed). Watchman watches the whole build root, so that
+            Pants does not need to watch individual paths, and its watches are shared with the other
+            tools which use it. This is useful for very large repositories, particularly those which
+            already use Watchman.
+            """
+        ),
+    )
+    file_watcher_poll_interval = FloatOption(
+        default=1.0,
+        advanced=True,
+        help="The number of seconds between polls of the filesystem, if it is polled for changes.",
+    )
+    file_watcher_coalesce_window = FloatOption(
+        default=0.1,
+        advanced=True,
+        help=softwrap(
+            """
+            The number of seconds for which changes to the filesystem are collected before they
+            are invalidated together, in a single pass over the graph.
+
+            Bulk changes (such as checking out a different branch) cause many changes in quick
+            succession, which are far cheaper to invalidate together than one at a time. Set to
+            `0` to invalidate each change as soon as it is observed.
+            """
+        ),
+    )
+    file_watcher_ignore = StrListOption(
+        advanced=True,
+        help=softwrap(
+            """
+            Patterns (using the gitignore syntax) for paths whose changes are ignored entirely by
+            the file watcher, such as `bazel-out/`, `*.swp` for editor swap files, or large vendored
+            directories which are not used by any target.
+
+            Unlike `[GLOBAL].pants_ignore`, these paths remain visible to Pants: but changes to
+            them never invalidate anything which `pantsd` has already computed, so they should only
+            match paths which no target depends on.
+            """
+        ),
+    )
 
 
 # N.B. By subclassing BootstrapOptions, we inherit all of those options and are also able to extend
@@ -1758,10 +2546,15 @@ class GlobalOptions(BootstrapOptions, Subsystem):
         default=sys.stdout.isatty(),
         help=softwrap(
             """
-            Whether Pants should use colors in output or not. This may also impact whether
-            some tools Pants runs use color.
+            Whether Pants should use colors in output or not. This also decides whether the
+            interactive processes which Pants runs (e.g. for `run` and `repl`) use color: they
+            are run with `CLICOLOR_FORCE`, `FORCE_COLOR` and `TERM` set if so, and with
+            `NO_COLOR` set otherwise, unless they set those variables themselves.
 
-            When unset, this value defaults based on whether the output destination supports color.
+            When unset, color is disabled if the `NO_COLOR` environment variable is set, and
+            enabled if the `FORCE_COLOR` environment variable is set (to anything other than `0`
+            or `false`). Otherwise, this value defaults based on whether the output destination
+            supports color.
             """
         ),
     )
@@ -1777,7 +2570,21 @@ class GlobalOptions(BootstrapOptions, Subsystem):
     )
     dynamic_ui_renderer = EnumOption(
         default=DynamicUIRenderer.indicatif_spinner,
-        help="If `--dynamic-ui` is enabled, selects the renderer.",
+        help=softwrap(
+            """
+            If `--dynamic-ui` is enabled, selects the renderer.
+
+            The `compact` renderer renders a single status line for the whole run (with the elapsed
+            time, the number of running workunits, and the number of processes and how many of them
+            were cached) rather than a line for each running workunit, for narrow terminals and for
+            CI log viewers which do not support redrawing multiple lines.
+
+            The `experimental-tui` renderer takes over the whole terminal to list all of the running
+            workunits with their elapsed times. The up and down arrow keys select a workunit, `enter`
+            displays the live output of a selected process (and `esc` returns to the list), and `c`
+            cancels the run.
+            """
+        ),
     )
 
     tag = StrListOption(
@@ -1961,6 +2768,17 @@ class GlobalOptions(BootstrapOptions, Subsystem):
                 )
             )
 
+        if not 0 <= opts.niceness <= MAX_NICENESS:
+            raise OptionsError(
+                f"--niceness must be between 0 and {MAX_NICENESS}, but it was set to "
+                f"{opts.niceness}."
+            )
+
+        if opts.io_threads_max is not None and opts.io_threads_max < 1:
+            raise OptionsError(
+                f"--io-threads-max must be at least 1, but it was set to {opts.io_threads_max}."
+            )
+
         if (
             opts.process_total_child_memory_usage is not None
             and opts.process_total_child_memory_usage < opts.process_per_child_memory_usage
@@ -1988,6 +2806,49 @@ class GlobalOptions(BootstrapOptions, Subsystem):
                 )
             )
 
+        if opts.pantsd_remote_listen_address:
+            for opt_name in (
+                "pantsd_remote_tls_cert_path",
+                "pantsd_remote_tls_key_path",
+                "pantsd_remote_token",
+            ):
+                if not getattr(opts, opt_name):
+                    raise OptionsError(
+                        f"--{opt_name.replace('_', '-')} must be set if "
+                        "--pantsd-remote-listen-address is set."
+                    )
+
+        for opt_name in ("log_max_size_bytes", "log_max_rotated_files"):
+            if getattr(opts, opt_name) < 0:
+                raise OptionsError(
+                    f"--{opt_name.replace('_', '-')} may not be negative, but it was set to "
+                    f"{getattr(opts, opt_name)}."
+                )
+        if opts.log_max_run_logs < 1:
+            raise OptionsError(
+                f"--log-max-run-logs must be at least 1, but it was set to {opts.log_max_run_logs}."
+            )
+        if opts.log_dedup_window < 0:
+            raise OptionsError(
+                "--log-dedup-window must not be negative, but it was set to "
+                f"{opts.log_dedup_window}."
+            )
+        if opts.local_store_max_size_growth_factor < 1:
+            raise OptionsError(
+                "--local-store-max-size-growth-factor must be at least 1, but it was set to "
+                f"{opts.local_store_max_size_growth_factor}."
+            )
+        if opts.file_watcher_poll_interval <= 0:
+            raise OptionsError(
+                "--file-watcher-poll-interval must be positive, but it was set to "
+                f"{opts.file_watcher_poll_interval}."
+            )
+        if opts.file_watcher_coalesce_window < 0:
+            raise OptionsError(
+                "--file-watcher-coalesce-window must not be negative, but it was set to "
+                f"{opts.file_watcher_coalesce_window}."
+            )
+
         provider_source = "the `[GLOBAL].remote_provider` option"
         if opts.remote_execution_address:
             address_source = "the `[GLOBAL].remote_execution_address` option"
@@ -2063,7 +2924,9 @@ class GlobalOptions(BootstrapOptions, Subsystem):
             else 4 * bootstrap_options.rule_threads_core
         )
         return PyExecutor(
-            core_threads=bootstrap_options.rule_threads_core, max_threads=rule_threads_max
+            core_threads=bootstrap_options.rule_threads_core,
+            max_threads=rule_threads_max,
+            io_threads=bootstrap_options.io_threads_max,
         )
 
     @staticmethod
@@ -2145,7 +3008,7 @@ class GlobalOptions(BootstrapOptions, Subsystem):
 
         # Explicitly specified globs are already relative, and are added verbatim.
         invalidation_globs.update(
-            ("!*.pyc", "!__pycache__/", ".gitignore", *bootstrap_options.pantsd_invalidation_globs)
+            ("!*.pyc", "!__pycache__/", *bootstrap_options.pantsd_invalidation_globs)
         )
         return tuple(invalidation_globs)
 
@@ -286,3 +286,22 @@ def test_remote_provider_matches_rust_enum(
         client_key_path=None,
         append_only_caches_base_path=None,
     )
+
+
+def test_create_py_executor_with_io_threads() -> None:
+    ob = create_options_bootstrapper(["--rule-threads-core=2", "--io-threads-max=2"])
+    executor = GlobalOptions.create_py_executor(ob.bootstrap_options.for_global_scope())
+    executor.shutdown(3)
+
+
+def test_invalid_io_threads() -> None:
+    ob = create_options_bootstrapper(["--io-threads-max=0"])
+    with pytest.raises(OptionsError, match="--io-threads-max must be at least 1"):
+        GlobalOptions.validate_instance(ob.bootstrap_options.for_global_scope())
+
+
+@pytest.mark.parametrize("niceness", [-1, 20])
+def test_invalid_niceness(niceness: int) -> None:
+    ob = create_options_bootstrapper([f"--niceness={niceness}"])
+    with pytest.raises(OptionsError, match="--niceness must be between 0 and 19"):
+        GlobalOptions.validate_instance(ob.bootstrap_options.for_global_scope())
@@ -20,6 +20,57 @@ from pants.util.strutil import get_strict_env
 logger = logging.getLogger()
 
 
+def is_enum(typ) -> bool:
+    # TODO: When we switch to Python 3.11, use: return isinstance(typ, EnumType)
+    return inspect.isclass(typ) and issubclass(typ, Enum)
+
+
+def native_option_type(option_type, member_type, default) -> Tuple[type, Optional[type], Any]:
+    """Returns the type (and member type, for lists) that the native parser parses an option of
+    the given registered types as, along with the given default converted to that type."""
+    rust_option_type = option_type
+    rust_member_type = member_type
+
+    if option_type is dict:
+        # The Python code allows registering default=None for dicts/lists, and forces it to
+        # an empty dict/list at registration. Since here we only have access to what the user
+        # provided, we do the same.
+        if default is None:
+            default = {}
+        elif isinstance(default, str):
+            default = eval(default)
+    elif option_type is list:
+        if default is None:
+            default = []
+        if member_type is None:
+            member_type = rust_member_type = str
+
+        if member_type == shell_str:
+            rust_member_type = str
+            if isinstance(default, str):
+                default = shlex.split(default)
+        elif is_enum(member_type):
+            rust_member_type = str
+            default = [x.value for x in default]
+        elif inspect.isfunction(rust_member_type):
+            rust_member_type = str
+        elif rust_member_type != str and isinstance(default, str):
+            default = eval(default)
+    elif is_enum(option_type):
+        if default is not None:
+            default = default.value
+            rust_option_type = type(default)
+        else:
+            rust_option_type = str
+    elif option_type not in {bool, int, float, str}:
+        # For enum and other specialized types.
+        rust_option_type = str
+        if default is not None:
+            default = str(default)
+
+    return rust_option_type, rust_member_type, default
+
+
 class NativeOptionParser:
     """A Python wrapper around the Rust options parser."""
 
@@ -44,13 +95,18 @@ class NativeOptionParser:
             if config_sources is None
             else [PyConfigSource(cs.path, cs.content) for cs in config_sources]
         )
-        self._native_parser = native_engine.PyOptionParser(
-            args,
-            dict(get_strict_env(env, logger)),
-            py_config_sources,
-            allow_pantsrc,
+        self._init(
+            native_engine.PyOptionParser(
+                args,
+                dict(get_strict_env(env, logger)),
+                py_config_sources,
+                allow_pantsrc,
+            )
         )
 
+    def _init(self, native_parser: native_engine.PyOptionParser) -> None:
+        self._native_parser = native_parser
+
         # (type, member_type) -> native get for that type.
         self._getter_by_type = {
             (bool, None): self._native_parser.get_bool,
@@ -64,57 +120,31 @@ class NativeOptionParser:
             (dict, None): self._native_parser.get_dict,
         }
 
+    def with_directory_config(self, config_sources: Sequence[ConfigSource]) -> NativeOptionParser:
+        """Returns a parser for the options of the targets in a directory, which also reads the
+        given directory config files, in ascending order of precedence.
+
+        See `native_engine.directory_config_paths` for where those files are found.
+        """
+        parser = NativeOptionParser.__new__(NativeOptionParser)
+        parser._init(
+            self._native_parser.with_directory_config(
+                [PyConfigSource(cs.path, cs.content) for cs in config_sources]
+            )
+        )
+        return parser
+
     def get(
         self, *, scope, flags, default, option_type, member_type=None, passthrough=False
     ) -> Tuple[Any, Rank]:
-        def is_enum(typ):
-            # TODO: When we switch to Python 3.11, use: return isinstance(typ, EnumType)
-            return inspect.isclass(typ) and issubclass(typ, Enum)
-
         # '--foo.bar-baz' -> ['foo', 'bar', 'baz']
         name_parts = flags[-1][2:].replace(".", "-").split("-")
         switch = flags[0][1:] if len(flags) > 1 else None  # '-d' -> 'd'
         option_id = native_engine.PyOptionId(*name_parts, scope=scope or "GLOBAL", switch=switch)
 
-        rust_option_type = option_type
-        rust_member_type = member_type
-
-        if option_type is dict:
-            # The Python code allows registering default=None for dicts/lists, and forces it to
-            # an empty dict/list at registration. Since here we only have access to what the user
-            # provided, we do the same.
-            if default is None:
-                default = {}
-            elif isinstance(default, str):
-                default = eval(default)
-        elif option_type is list:
-            if default is None:
-                default = []
-            if member_type is None:
-                member_type = rust_member_type = str
-
-            if member_type == shell_str:
-                rust_member_type = str
-                if isinstance(default, str):
-                    default = shlex.split(default)
-            elif is_enum(member_type):
-                rust_member_type = str
-                default = [x.value for x in default]
-            elif inspect.isfunction(rust_member_type):
-                rust_member_type = str
-            elif rust_member_type != str and isinstance(default, str):
-                default = eval(default)
-        elif is_enum(option_type):
-            if default is not None:
-                default = default.value
-                rust_option_type = type(default)
-            else:
-                rust_option_type = str
-        elif option_type not in {bool, int, float, str}:
-            # For enum and other specialized types.
-            rust_option_type = str
-            if default is not None:
-                default = str(default)
+        rust_option_type, rust_member_type, default = native_option_type(
+            option_type, member_type, default
+        )
 
         getter = self._getter_by_type.get((rust_option_type, rust_member_type))
         if getter is None:
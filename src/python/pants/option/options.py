@@ -11,12 +11,12 @@ from typing import Any, Iterable, Mapping, Sequence
 from pants.base.build_environment import get_buildroot
 from pants.base.deprecated import warn_or_error
 from pants.option.arg_splitter import ArgSplitter
-from pants.option.config import Config
+from pants.option.config import Config, ConfigSource
 from pants.option.errors import ConfigValidationError
 from pants.option.native_options import NativeOptionParser
 from pants.option.option_util import is_list_option
 from pants.option.option_value_container import OptionValueContainer, OptionValueContainerBuilder
-from pants.option.parser import Parser
+from pants.option.parser import REDACTED, Parser
 from pants.option.scope import GLOBAL_SCOPE, GLOBAL_SCOPE_CONFIG_SECTION, ScopeInfo
 from pants.util.memo import memoized_method
 from pants.util.ordered_set import FrozenOrderedSet, OrderedSet
@@ -418,10 +418,11 @@ class Options:
 
         if native_values:
             msgs = []
+            parser = self.get_parser(scope)
 
             def legacy_val_info(k):
                 if k in legacy_values:
-                    val = legacy_values[k]
+                    val = REDACTED if parser.is_secret(k) else legacy_values[k]
                     descr = (
                         f"{val} of type {type(val)}, from source {legacy_values.get_rank(k).name}"
                     )
@@ -431,7 +432,7 @@ class Options:
 
             def native_val_info(k):
                 if k in native_values:
-                    val = native_values[k]
+                    val = REDACTED if parser.is_secret(k) else native_values[k]
                     descr = (
                         f"{val} of type {type(val)}, from source {native_values.get_rank(k).name}"
                     )
@@ -473,6 +474,27 @@ class Options:
         # TODO: In a future release, switch to the native_values as authoritative.
         return legacy_values
 
+    def for_scope_in_directory(
+        self, scope: str, directory_config_sources: Sequence[ConfigSource]
+    ) -> OptionValueContainer:
+        """Return the option values for the given scope, for the targets in a directory.
+
+        The values are overridden by the given directory config files of the directory, in
+        ascending order of precedence. Directory config files are only supported by the native
+        options parser, so the values are computed by it alone.
+
+        :API: public
+        :param scope: The scope to get options for.
+        :param directory_config_sources: The directory config files which exist for the directory,
+          at (some of) the paths given by `native_engine.directory_config_paths`.
+        """
+        if not directory_config_sources:
+            return self.for_scope(scope)
+        native_parser = self._native_parser.with_directory_config(directory_config_sources)
+        values_builder = self.get_parser(scope).parse_args_native(native_parser).to_builder()
+        self._check_and_apply_deprecations(scope, values_builder)
+        return values_builder.build()
+
     def get_fingerprintable_for_scope(
         self,
         scope: str,
@@ -482,6 +504,7 @@ class Options:
         given scope.
 
         Options are fingerprintable by default, but may be registered with "fingerprint=False".
+        Options whose value was resolved from a secret reference are never fingerprintable.
 
         This method also searches enclosing options scopes of `bottom_scope` to determine the set of
         fingerprintable pairs.
@@ -492,6 +515,7 @@ class Options:
 
         pairs = []
         parser = self.get_parser(scope)
+        values = self.for_scope(scope)
         # Sort the arguments, so that the fingerprint is consistent.
         for _, kwargs in sorted(parser.option_registrations_iter()):
             if not kwargs.get("fingerprint", True):
@@ -499,7 +523,10 @@ class Options:
             if daemon_only and not kwargs.get("daemon", False):
                 continue
             dest = kwargs["dest"]
-            val = self.for_scope(scope)[dest]
+            if parser.is_secret(dest):
+                # Secrets must not leak into cache keys.
+                continue
+            val = values[dest]
             # If we have a list then we delegate to the fingerprinting implementation of the members.
             if is_list_option(kwargs):
                 val_type = kwargs.get("member_type", str)
@@ -10,8 +10,13 @@ from dataclasses import dataclass
 from pathlib import Path
 from typing import TYPE_CHECKING, Iterable, Mapping, Sequence
 
-from pants.base.build_environment import get_buildroot, get_default_pants_config_file, pants_version
+from pants.base.build_environment import (
+    get_buildroot,
+    get_default_pants_config_files,
+    pants_version,
+)
 from pants.base.exceptions import BuildConfigurationError
+from pants.engine.internals import native_engine
 from pants.engine.unions import UnionMembership
 from pants.option.alias import CliAlias
 from pants.option.config import Config
@@ -30,6 +35,10 @@ if TYPE_CHECKING:
     from pants.build_graph.build_configuration import BuildConfiguration
 
 
+# The name of the optional file of environment variables in the build root (see `--dotenv`).
+DOTENV_FILE = ".env"
+
+
 @dataclass(frozen=True)
 class OptionsBootstrapper:
     """Holds the result of the first stage of options parsing, and assists with parsing full
@@ -71,9 +80,9 @@ class OptionsBootstrapper:
         ]
 
         path_list_values = []
-        default = get_default_pants_config_file()
-        if Path(default).is_file():
-            path_list_values.append(ListValueComponent.create(default))
+        for default in get_default_pants_config_files():
+            if Path(default).is_file():
+                path_list_values.append(ListValueComponent.create(default))
         for var in evars:
             if var in env:
                 path_list_values.append(ListValueComponent.create(env[var]))
@@ -116,7 +125,8 @@ class OptionsBootstrapper:
     ) -> OptionsBootstrapper:
         """Parses the minimum amount of configuration necessary to create an OptionsBootstrapper.
 
-        :param env: An environment dictionary.
+        :param env: An environment dictionary. If the `dotenv` option is set, the variables from
+          the `.env` file in the buildroot are merged into it, and exposed via `env`.
         :param args: An args array.
         :param allow_pantsrc: True to allow pantsrc files to be used. Unless tests are expecting to
           consume pantsrc files, they should pass False in order to avoid reading files from
@@ -147,6 +157,20 @@ class OptionsBootstrapper:
             )
             bootstrap_option_values = initial_bootstrap_options.for_global_scope()
 
+            if bootstrap_option_values.dotenv:
+                # The `.env` file may itself set options (including those that determine which
+                # config files to read), so we bootstrap again using the merged environment, in
+                # which the actual environment takes precedence.
+                dotenv_path = os.path.join(get_buildroot(), DOTENV_FILE)
+                env = {**native_engine.read_dotenv_file(dotenv_path), **env}
+                config_file_paths = cls.get_config_file_paths(env=env, args=args)
+                config_files_products = [filecontent_for(p) for p in config_file_paths]
+                pre_bootstrap_config = Config.load(config_files_products, env=env)
+                initial_bootstrap_options = cls.parse_bootstrap_options(
+                    env, bargs, pre_bootstrap_config
+                )
+                bootstrap_option_values = initial_bootstrap_options.for_global_scope()
+
             # Now re-read the config, post-bootstrapping. Note the order: First whatever we bootstrapped
             # from (typically pants.toml), then config override, then rcfiles.
             full_config_sources = pre_bootstrap_config.sources()
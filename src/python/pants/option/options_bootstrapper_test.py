@@ -9,6 +9,7 @@ from pathlib import Path
 from textwrap import dedent
 
 from pants.base.build_environment import get_buildroot
+from pants.base.build_root import BuildRoot
 from pants.engine.unions import UnionMembership
 from pants.option.option_value_container import OptionValueContainer
 from pants.option.options_bootstrapper import OptionsBootstrapper, munge_bin_name
@@ -407,6 +408,42 @@ class TestOptionsBootstrapper:
         logdir = ob.get_bootstrap_options().for_global_scope().logdir
         assert "logdir1" == logdir
 
+    def test_dotenv(self, tmp_path: Path) -> None:
+        config = tmp_path / "pants.toml"
+        config.write_text("[GLOBAL]\nlogdir = '${env.LOGDIR_ROOT:-/tmp}/logdir'\n")
+        (tmp_path / ".env").write_text(
+            dedent(
+                """\
+                # Set by the `.env` file.
+                export PANTS_LEVEL=debug
+                LOGDIR_ROOT="/from/dotenv"
+                PANTS_PANTSD=false
+                """
+            )
+        )
+
+        def create(env: dict[str, str]) -> OptionsBootstrapper:
+            with BuildRoot().temporary(str(tmp_path)):
+                return OptionsBootstrapper.create(
+                    env=env,
+                    args=[f"--pants-config-files=['{config.as_posix()}']"],
+                    allow_pantsrc=False,
+                )
+
+        ob = create({"PANTS_PANTSD": "true"})
+        opts = ob.get_bootstrap_options().for_global_scope()
+        assert LogLevel.INFO == opts.level
+        assert "/tmp/logdir" == opts.logdir
+        assert "LOGDIR_ROOT" not in ob.env
+
+        # The actual environment takes precedence over the `.env` file.
+        ob = create({"PANTS_DOTENV": "true", "PANTS_PANTSD": "true"})
+        opts = ob.get_bootstrap_options().for_global_scope()
+        assert LogLevel.DEBUG == opts.level
+        assert "/from/dotenv/logdir" == opts.logdir
+        assert opts.pantsd is True
+        assert "/from/dotenv" == ob.env["LOGDIR_ROOT"]
+
     def test_alias(self, tmp_path: Path) -> None:
         config0 = tmp_path / "config0"
         config0.write_text(
@@ -43,7 +43,7 @@ from pants.option.option_types import StrOption
 from pants.option.options import Options
 from pants.option.options_bootstrapper import OptionsBootstrapper
 from pants.option.options_fingerprinter import OptionEncoder
-from pants.option.parser import Parser
+from pants.option.parser import REDACTED, Parser
 from pants.option.ranked_value import Rank, RankedValue
 from pants.option.scope import GLOBAL_SCOPE, ScopeInfo
 from pants.option.subsystem import Subsystem
@@ -1471,6 +1471,42 @@ def test_fromfile_error() -> None:
         options.for_scope("fromfile")
 
 
+def test_secret_from_file() -> None:
+    with temporary_file(suffix=".json", binary_mode=False) as fp:
+        fp.write("s3cr3t\n")
+        fp.close()
+        options = _parse(flags="fromfile", config={"fromfile": {"string": f"@file({fp.name})"}})
+        # Secrets are not decoded from json/yaml, whatever the extension of the file.
+        assert "s3cr3t" == options.for_scope("fromfile").string
+        parser = options.get_parser("fromfile")
+        assert parser.is_secret("string")
+        assert not parser.is_secret("intvalue")
+
+
+def test_secret_from_command() -> None:
+    options = _parse(
+        flags="fromfile --intvalue=42",
+        env={"PANTS_FROMFILE_STRING": "@cmd(printf 's3cr3t\\n')"},
+    )
+    assert "s3cr3t" == options.for_scope("fromfile").string
+
+    pairs = options.get_fingerprintable_for_scope("fromfile")
+    assert ("intvalue", int, 42) in pairs
+    assert not any(dest == "string" for dest, _, _ in pairs)
+
+    history = options.get_parser("fromfile").history("string")
+    assert history is not None
+    assert RankedValue(Rank.ENVIRONMENT, REDACTED, "from env var PANTS_FROMFILE_STRING") == (
+        history.redacted().final_value
+    )
+
+
+def test_secret_error() -> None:
+    options = _parse(flags="fromfile", config={"fromfile": {"string": "@cmd(false)"}})
+    with pytest.raises(FromfileError, match="The command failed with exit status: 1"):
+        options.for_scope("fromfile")
+
+
 def test_fromfile_escape() -> None:
     options = _parse(flags=r"fromfile --string=@@/does/not/exist")
     assert "@/does/not/exist" == options.for_scope("fromfile").string
@@ -1600,3 +1636,36 @@ def test_list_of_enum_append() -> None:
 def test_list_of_enum_remove() -> None:
     options = _parse(flags="other-enum-scope --some-list-enum-with-default=\"-['yet-another']\"")
     assert [] == options.for_scope("other-enum-scope").some_list_enum_with_default
+
+
+def test_for_scope_in_directory() -> None:
+    def register(opts: Options) -> None:
+        opts.register("scope", "--foo", default="default")
+        opts.register("scope", "--bar", default="default")
+        opts.register("scope", "--items", type=list, member_type=int, default=[1])
+
+    options = create_options(
+        ["scope"],
+        register,
+        ["scope", "--bar=from_flag"],
+        config={"scope": {"foo": "root", "items": "+[2]"}},
+    )
+    directory_config = [
+        FileContent("src/pants.dir.toml", b"[scope]\nfoo = 'src'\nitems.add = [3]\n"),
+        FileContent("src/legacy/pants.dir.toml", b"[scope]\nfoo = 'legacy'\n"),
+    ]
+    values = options.for_scope_in_directory("scope", directory_config)
+    assert "legacy" == values.foo
+    assert Rank.CONFIG == values.get_rank("foo")
+    assert "from_flag" == values.bar
+    assert [1, 2, 3] == values.items
+
+    # Without directory config files, the values are those for the whole repo.
+    values = options.for_scope_in_directory("scope", [])
+    assert "root" == values.foo
+    assert [1, 2] == values.items
+
+    with pytest.raises(ValueError, match="may not set global options"):
+        options.for_scope_in_directory(
+            "scope", [FileContent("src/pants.dir.toml", b"[GLOBAL]\nlevel = 'debug'\n")]
+        )
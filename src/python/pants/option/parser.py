@@ -19,6 +19,7 @@ import yaml
 
 from pants.base.build_environment import get_buildroot
 from pants.base.deprecated import validate_deprecation_semver, warn_or_error
+from pants.engine.internals import native_engine
 from pants.option.config import DEFAULT_SECTION, Config
 from pants.option.custom_types import (
     DictValueComponent,
@@ -58,6 +59,9 @@ from pants.util.strutil import softwrap
 
 logger = logging.getLogger(__name__)
 
+# Displayed in place of the values of options which were resolved from secret references.
+REDACTED = "<redacted>"
+
 
 @dataclass(frozen=True)
 class OptionValueHistory:
@@ -67,6 +71,15 @@ class OptionValueHistory:
     def final_value(self) -> RankedValue:
         return self.ranked_values[-1]
 
+    def redacted(self) -> OptionValueHistory:
+        """A copy of this history, with all values other than the defaults redacted."""
+        return OptionValueHistory(
+            tuple(
+                rv if rv.rank <= Rank.HARDCODED else RankedValue(rv.rank, REDACTED, rv.details)
+                for rv in self.ranked_values
+            )
+        )
+
 
 class Parser:
     """An argument parser."""
@@ -133,6 +146,9 @@ class Parser:
         # Map of dest -> history.
         self._history: dict[str, OptionValueHistory] = {}
 
+        # The dests of options whose value was resolved from a secret reference.
+        self._secret_dests: set[str] = set()
+
     @property
     def scope_info(self) -> ScopeInfo:
         return self._scope_info
@@ -149,6 +165,11 @@ class Parser:
     def history(self, dest: str) -> OptionValueHistory | None:
         return self._history.get(dest)
 
+    def is_secret(self, dest: str) -> bool:
+        """Whether the value of the option was resolved from a `@cmd(...)` or `@file(...)` secret
+        reference, in which case it must not be displayed or fingerprinted."""
+        return dest in self._secret_dests
+
     @dataclass(frozen=True)
     class ParseArgsRequest:
         flag_value_map: dict[str, list[Any]]
@@ -590,6 +611,9 @@ class Parser:
                 and isinstance(val_or_str, str)
                 and val_or_str.startswith("@")
             ):
+                if self._SECRET_REF_RE.match(val_or_str):
+                    self._secret_dests.add(dest)
+                    return self._resolve_secret(dest, val_or_str)
                 if val_or_str.startswith("@@"):  # Support a literal @ for fromfile values via @@.
                     return val_or_str[1:]
                 else:
@@ -778,6 +802,21 @@ class Parser:
 
         return value_history
 
+    _SECRET_REF_RE = re.compile(r"^@(cmd|file)\((.*)\)$", re.DOTALL)
+
+    def _resolve_secret(self, dest: str, value: str) -> str:
+        """Resolve a `@cmd(<command>)` or `@file(<path>)` secret reference.
+
+        Secrets are resolved by the native options parser, which memoizes the output of commands, so
+        that a command is not run again by this parser.
+        """
+        try:
+            return native_engine.resolve_option_secret(
+                get_buildroot(), value, f"{dest} in {self._scope_str()}"
+            )
+        except ValueError as e:
+            raise FromfileError(str(e))
+
     def _inverse_arg(self, arg: str) -> str | None:
         if not arg.startswith("--"):
             return None
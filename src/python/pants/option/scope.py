@@ -69,3 +69,12 @@ class ScopedOptions:
 
     scope: Scope
     options: OptionValueContainer
+
+
+@dataclass(frozen=True)
+class DirectoryScopedOptionsRequest:
+    """A request for the options of a Scope, as overridden for the targets in a directory by the
+    directory config files (`pants.dir.toml`) in it and its ancestors."""
+
+    scope: Scope
+    directory: str
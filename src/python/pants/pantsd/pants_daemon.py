@@ -26,8 +26,9 @@ from pants.option.options import Options
 from pants.option.options_bootstrapper import OptionsBootstrapper
 from pants.pantsd.pants_daemon_core import PantsDaemonCore
 from pants.pantsd.process_manager import PantsDaemonProcessManager
-from pants.pantsd.service.pants_service import PantsServices
+from pants.pantsd.service.pants_service import PantsService, PantsServices
 from pants.pantsd.service.scheduler_service import SchedulerService
+from pants.pantsd.service.status_service import StatusService
 from pants.pantsd.service.store_gc_service import StoreGCService
 from pants.util.contextutil import argv_as, hermetic_environment_as
 from pants.util.dirutil import safe_open
@@ -70,6 +71,12 @@ class PantsDaemon(PantsDaemonProcessManager):
             bootstrap_options = options_bootstrapper.bootstrap_options
             bootstrap_options_values = bootstrap_options.for_global_scope()
 
+        if bootstrap_options_values.pantsd_tokio_console:
+            # NB: Tasks are only tracked once this is installed, so it precedes the executor.
+            native_engine.pantsd_tokio_console_init()
+        if bootstrap_options_values.pantsd_heap_profiling:
+            native_engine.pantsd_heap_profiling_activate()
+
         # This executor is owned by the PantsDaemon, and borrowed by the Pants runs that are launched by
         # PantsDaemonCore. Individual runs will call shutdown to tear down the executor, but those calls
         # have no effect on a borrowed executor.
@@ -80,6 +87,10 @@ class PantsDaemon(PantsDaemonProcessManager):
             executor,
             bootstrap_options_values.pantsd_pailgun_port,
             DaemonPantsRunner(core),
+            remote_address=bootstrap_options_values.pantsd_remote_listen_address or None,
+            remote_tls_cert_path=bootstrap_options_values.pantsd_remote_tls_cert_path,
+            remote_tls_key_path=bootstrap_options_values.pantsd_remote_tls_key_path,
+            remote_token=bootstrap_options_values.pantsd_remote_token,
         )
 
         return PantsDaemon(
@@ -115,13 +126,31 @@ class PantsDaemon(PantsDaemonProcessManager):
             ),
             pid=os.getpid(),
             max_memory_usage_in_bytes=bootstrap_options.pantsd_max_memory_usage,
+            memory_budget_in_bytes=bootstrap_options.pantsd_memory_budget,
         )
 
+        local_store_options = LocalStoreOptions.from_options(bootstrap_options)
         store_gc_service = StoreGCService(
             graph_scheduler.scheduler,
-            local_store_options=LocalStoreOptions.from_options(bootstrap_options),
+            local_store_options=local_store_options,
         )
-        return PantsServices(services=(scheduler_service, store_gc_service))
+        services: tuple[PantsService, ...] = (scheduler_service, store_gc_service)
+
+        if bootstrap_options.pantsd_status_port is not None:
+            status_service = StatusService(
+                scheduler=graph_scheduler.scheduler,
+                port=bootstrap_options.pantsd_status_port,
+                port_file=PantsDaemon.metadata_file_path(
+                    "pantsd", "status_port", bootstrap_options.pants_subprocessdir
+                ),
+                pid=os.getpid(),
+                diagnostics_dir=os.path.join(
+                    bootstrap_options.pants_workdir, "pantsd-diagnostics"
+                ),
+                local_store_options=local_store_options,
+            )
+            services += (status_service,)
+        return PantsServices(services=services)
 
     def __init__(
         self,
@@ -195,9 +224,9 @@ class PantsDaemon(PantsDaemonProcessManager):
         # Switch log output to the daemon's log stream, and empty `env` and `argv` to encourage all
         # further usage of those variables to happen via engine APIs and options.
         self._close_stdio(pants_log_path(PurePath(global_bootstrap_options.pants_workdir)))
-        with initialize_stdio(global_bootstrap_options), argv_as(tuple()), hermetic_environment_as(
-            *_PRESERVED_ENV_VARS
-        ):
+        with initialize_stdio(global_bootstrap_options, daemon=True), argv_as(
+            tuple()
+        ), hermetic_environment_as(*_PRESERVED_ENV_VARS):
             # Install signal and panic handling.
             ExceptionSink.install(
                 log_location=init_workdir(global_bootstrap_options), pantsd_instance=True
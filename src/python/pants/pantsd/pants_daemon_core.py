@@ -10,7 +10,8 @@ from typing import Iterator, Protocol
 
 from pants.build_graph.build_configuration import BuildConfiguration
 from pants.engine.env_vars import CompleteEnvironmentVars
-from pants.engine.internals.native_engine import PyExecutor
+from pants.engine.internals.native_engine import PyExecutor, PyRuleGraphCache
+from pants.engine.internals.scheduler import SchedulerSession
 from pants.engine.unions import UnionMembership
 from pants.init.engine_initializer import EngineInitializer, GraphScheduler
 from pants.init.options_initializer import OptionsInitializer
@@ -55,6 +56,11 @@ class PantsDaemonCore:
         self._kill_switch = threading.Event()
 
         self._scheduler: GraphScheduler | None = None
+        # The most recently solved rule graph, against which the rule graph of a re-initialized
+        # Scheduler is solved incrementally (e.g. after the enabled backends have changed). Solved
+        # rule graphs are also persisted, so that a restarted pantsd (e.g. after a plugin has been
+        # edited) solves its first rule graph incrementally too.
+        self._rule_graph_cache = PyRuleGraphCache()
         self._services: PantsServices | None = None
         self._fingerprint: str | None = None
 
@@ -105,7 +111,11 @@ class PantsDaemonCore:
             if self._services:
                 self._services.shutdown()
             self._scheduler = EngineInitializer.setup_graph(
-                bootstrap_options, build_config, dynamic_remote_options, self._executor
+                bootstrap_options,
+                build_config,
+                dynamic_remote_options,
+                self._executor,
+                rule_graph_cache=self._rule_graph_cache,
             )
 
             self._services = self._services_constructor(bootstrap_options, self._scheduler)
@@ -185,6 +195,17 @@ class PantsDaemonCore:
             assert self._scheduler is not None
             return self._scheduler, self._options_initializer
 
+    def run_completed(self, scheduler_session: SchedulerSession) -> None:
+        """Notifies the current PantsServices that a run has completed.
+
+        Runs in a client context (generally in DaemonPantsRunner).
+        """
+        services = self._services
+        if services is None:
+            return
+        for service in services.services:
+            service.run_completed(scheduler_session)
+
     def shutdown(self) -> None:
         with self._lifecycle_lock:
             if self._services is not None:
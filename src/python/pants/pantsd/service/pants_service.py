@@ -6,7 +6,10 @@ import threading
 import time
 from abc import ABC, abstractmethod
 from dataclasses import dataclass
-from typing import Dict, KeysView, Tuple
+from typing import TYPE_CHECKING, Dict, KeysView, Tuple
+
+if TYPE_CHECKING:
+    from pants.engine.internals.scheduler import SchedulerSession
 
 logger = logging.getLogger(__name__)
 
@@ -42,6 +45,9 @@ class PantsService(ABC):
     def run(self):
         """The main entry-point for the service called by the service runner."""
 
+    def run_completed(self, scheduler_session: "SchedulerSession") -> None:
+        """Called on the thread of each run of pantsd once it has completed, with its Session."""
+
     def mark_pausing(self):
         """Triggers pausing of the service, without waiting for it to have paused.
 
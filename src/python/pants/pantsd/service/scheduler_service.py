@@ -27,6 +27,12 @@ class SchedulerService(PantsService):
     INVALIDATION_POLL_INTERVAL = 0.5
     # A grace period after startup that we will wait before enforcing our pid.
     PIDFILE_GRACE_PERIOD = 5
+    # When the memory budget is exceeded, the progressively lower numbers of runs for which values
+    # may go unrequested before they are dropped.
+    MEMORY_SHEDDING_IDLE_RUNS = (16, 4, 1, 0)
+    # The minimum interval between attempts to shed memory, since values which were just dropped
+    # are likely to be recomputed by the next run.
+    MEMORY_SHEDDING_INTERVAL = 60
 
     def __init__(
         self,
@@ -37,6 +43,7 @@ class SchedulerService(PantsService):
         pidfile: str,
         pid: int,
         max_memory_usage_in_bytes: int,
+        memory_budget_in_bytes: Optional[int] = None,
     ) -> None:
         """
         :param graph_scheduler: The GraphScheduler instance for graph construction.
@@ -48,6 +55,8 @@ class SchedulerService(PantsService):
         :param pid: This processes' pid.
         :param max_memory_usage_in_bytes: The maximum memory usage of the process: the service will
                                           shut down if it observes more than this amount in use.
+        :param memory_budget_in_bytes: A memory usage above which the service will release memory
+                                       (if set).
         """
         super().__init__()
         self._graph_helper = graph_scheduler
@@ -70,6 +79,8 @@ class SchedulerService(PantsService):
         self._pidfile = pidfile
         self._pid = pid
         self._max_memory_usage_in_bytes = max_memory_usage_in_bytes
+        self._memory_budget_in_bytes = memory_budget_in_bytes
+        self._last_memory_shedding = 0.0
 
     def _get_snapshot(self, globs: Tuple[str, ...], poll: bool) -> Optional[Snapshot]:
         """Returns a Snapshot of the input globs.
@@ -115,8 +126,42 @@ class SchedulerService(PantsService):
         if int(pid_from_file) != self._pid:
             raise Exception(f"Another instance of pantsd is running at {pid_from_file}")
 
+    def _memory_usage_in_bytes(self) -> int:
+        return cast(int, psutil.Process(self._pid).memory_info()[0])
+
+    def _shed_memory(self, memory_usage_in_bytes: int) -> int:
+        """Progressively releases memory until the usage is within the budget, and returns it."""
+        assert self._memory_budget_in_bytes is not None
+        self._last_memory_shedding = time.time()
+        bytes_per_mib = 1_048_576
+        for max_idle_runs in self.MEMORY_SHEDDING_IDLE_RUNS:
+            released = self._scheduler.shed_memory(max_idle_runs)
+            previous_usage_in_bytes = memory_usage_in_bytes
+            memory_usage_in_bytes = self._memory_usage_in_bytes()
+            self._logger.info(
+                softwrap(
+                    f"""
+                    pantsd was using {previous_usage_in_bytes / bytes_per_mib:.2f} MiB of memory
+                    (above the `--pantsd-memory-budget` of
+                    {self._memory_budget_in_bytes / bytes_per_mib:.2f} MiB): released the values of
+                    {released["graph_entries"]} graph nodes which were unused for more than
+                    {max_idle_runs} runs, and {released["store_entries"]} store entries, and is now
+                    using {memory_usage_in_bytes / bytes_per_mib:.2f} MiB.
+                    """
+                )
+            )
+            if memory_usage_in_bytes <= self._memory_budget_in_bytes:
+                break
+        return memory_usage_in_bytes
+
     def _check_memory_usage(self):
-        memory_usage_in_bytes = psutil.Process(self._pid).memory_info()[0]
+        memory_usage_in_bytes = self._memory_usage_in_bytes()
+        if (
+            self._memory_budget_in_bytes is not None
+            and memory_usage_in_bytes > self._memory_budget_in_bytes
+            and time.time() > self._last_memory_shedding + self.MEMORY_SHEDDING_INTERVAL
+        ):
+            memory_usage_in_bytes = self._shed_memory(memory_usage_in_bytes)
         if memory_usage_in_bytes > self._max_memory_usage_in_bytes:
             bytes_per_mib = 1_048_576
             raise Exception(
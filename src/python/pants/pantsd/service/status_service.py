@@ -0,0 +1,279 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+import json
+import logging
+import os
+import sys
+import threading
+import time
+import traceback
+from http import HTTPStatus
+from http.server import BaseHTTPRequestHandler, HTTPServer
+from collections import Counter
+from typing import Any, Callable, Dict, Union
+
+import psutil
+
+from pants.engine.internals import native_engine
+from pants.engine.internals.scheduler import Scheduler, SchedulerSession
+from pants.option.global_options import DEFAULT_LOCAL_STORE_OPTIONS, LocalStoreOptions
+from pants.pantsd.service.pants_service import PantsService
+from pants.util.dirutil import safe_file_dump, safe_mkdir
+from pants.version import VERSION
+
+logger = logging.getLogger(__name__)
+
+# An endpoint responds with either JSON, or (for a str) plain text.
+_Endpoint = Callable[[], Union[Dict[str, Any], str]]
+
+
+class StatusService(PantsService):
+    """The pantsd status service.
+
+    This service serves an HTTP endpoint on localhost, which allows operators to inspect a running
+    pantsd and to trigger some of its maintenance:
+
+      GET /status: the uptime, live sessions, graph size, local store size and memory usage.
+      GET /metrics: the counters and observations of the completed runs, in the Prometheus text
+        format.
+      POST /gc: garbage collects the local store down to its target size.
+      POST /diagnostics: writes the graph and the stacks of all threads under `diagnostics_dir`.
+      POST /heap_profile: writes a `jeprof` profile of the heap under `diagnostics_dir`, when
+        `--pantsd-heap-profiling` is enabled.
+
+    All other responses are JSON.
+    """
+
+    # The interval on which the service checks whether it should pause or terminate while it is
+    # waiting for requests.
+    POLL_INTERVAL = 0.5
+
+    def __init__(
+        self,
+        *,
+        scheduler: Scheduler,
+        port: int,
+        port_file: str,
+        pid: int,
+        diagnostics_dir: str,
+        local_store_options: LocalStoreOptions = DEFAULT_LOCAL_STORE_OPTIONS,
+    ) -> None:
+        """
+        :param scheduler: The Scheduler of this pantsd.
+        :param port: The port to serve on, or 0 for a random port.
+        :param port_file: A file to write the port that is served on to, for clients to find it.
+        :param pid: This processes' pid.
+        :param diagnostics_dir: The directory to write diagnostics under.
+        :param local_store_options: The options of the local store, to report on and collect.
+        """
+        super().__init__()
+        self._scheduler = scheduler
+        # This session is only used to collect the store and to visualize the graph.
+        self._scheduler_session = scheduler.new_session(build_id="status_service_session")
+        self._port_file = port_file
+        self._pid = pid
+        self._diagnostics_dir = diagnostics_dir
+        self._store_dir = local_store_options.store_dir
+        self._target_size_bytes = local_store_options.target_total_size_bytes()
+        self._server = _StatusServer(port, self)
+        # The metrics of the runs which have completed, summed.
+        self._metrics_lock = threading.Lock()
+        self._runs = 0
+        self._counters: Counter[str] = Counter()
+        self._observations: dict[str, _Observations] = {}
+
+    @property
+    def port(self) -> int:
+        return self._server.server_address[1]
+
+    def status(self) -> dict[str, Any]:
+        process = psutil.Process(self._pid)
+        return {
+            "pid": self._pid,
+            "version": VERSION,
+            "uptime_secs": time.time() - process.create_time(),
+            # Includes the sessions of pantsd's own services.
+            "sessions": sorted(self._scheduler.live_sessions()),
+            "graph_nodes": self._scheduler.graph_len(),
+            "local_store_size_bytes": _disk_usage(self._store_dir),
+            "local_store_target_size_bytes": self._target_size_bytes,
+            "memory_usage_bytes": process.memory_info().rss,
+        }
+
+    def run_completed(self, scheduler_session: SchedulerSession) -> None:
+        counters = scheduler_session.get_metrics()
+        observations = scheduler_session.get_observation_buckets()
+        with self._metrics_lock:
+            self._runs += 1
+            self._counters.update(counters)
+            for name, (buckets, count, total) in observations.items():
+                self._observations.setdefault(name, _Observations()).add(buckets, count, total)
+
+    def metrics(self) -> str:
+        process = psutil.Process(self._pid)
+        lines = [
+            *_metric("pants_uptime_seconds", "gauge", time.time() - process.create_time()),
+            *_metric("pants_graph_nodes", "gauge", self._scheduler.graph_len()),
+            *_metric("pants_memory_usage_bytes", "gauge", process.memory_info().rss),
+        ]
+        with self._metrics_lock:
+            lines.extend(_metric("pants_runs_total", "counter", self._runs))
+            for name, value in sorted(self._counters.items()):
+                lines.extend(_metric(f"pants_{name}_total", "counter", value))
+            for name, observations in sorted(self._observations.items()):
+                lines.extend(observations.metric(f"pants_{name}"))
+        return "".join(f"{line}\n" for line in lines)
+
+    def garbage_collect(self) -> dict[str, Any]:
+        logger.info(f"Garbage collecting store. target_size={self._target_size_bytes:,}")
+        self._scheduler_session.garbage_collect_store(self._target_size_bytes)
+        logger.info("Done garbage collecting store")
+        return {"local_store_size_bytes": _disk_usage(self._store_dir)}
+
+    def dump_diagnostics(self) -> dict[str, Any]:
+        directory = os.path.join(self._diagnostics_dir, time.strftime("%Y%m%d-%H%M%S"))
+        safe_mkdir(directory)
+        graph = os.path.join(directory, "graph.dot")
+        self._scheduler_session.visualize_graph_to_file(graph)
+        threads = os.path.join(directory, "threads.txt")
+        names = {thread.ident: thread.name for thread in threading.enumerate()}
+        safe_file_dump(
+            threads,
+            "\n".join(
+                f"Thread {names.get(ident, ident)}:\n{''.join(traceback.format_stack(frame))}"
+                for ident, frame in sys._current_frames().items()
+            ),
+        )
+        logger.info(f"Wrote pantsd diagnostics to {directory}")
+        return {"graph": graph, "threads": threads}
+
+    def dump_heap_profile(self) -> dict[str, Any]:
+        directory = os.path.join(self._diagnostics_dir, time.strftime("%Y%m%d-%H%M%S"))
+        safe_mkdir(directory)
+        heap_profile = os.path.join(directory, "heap.prof")
+        native_engine.pantsd_heap_profile_dump(heap_profile)
+        logger.info(f"Wrote a pantsd heap profile to {heap_profile}")
+        return {"heap_profile": heap_profile}
+
+    def run(self):
+        """Main service entrypoint.
+
+        Called via Thread.start() via PantsDaemon.run().
+        """
+        safe_file_dump(self._port_file, str(self.port), makedirs=True)
+        logger.info(f"Serving the pantsd status on http://127.0.0.1:{self.port}/status")
+        try:
+            while not self._state.is_terminating:
+                # Waits for at most `POLL_INTERVAL` for a request.
+                self._server.handle_request()
+                self._state.maybe_pause()
+        finally:
+            self._server.server_close()
+            self._scheduler_session.cancel()
+
+
+class _StatusServer(HTTPServer):
+    def __init__(self, port: int, service: StatusService) -> None:
+        super().__init__(("127.0.0.1", port), _StatusRequestHandler)
+        self.service = service
+        self.timeout = StatusService.POLL_INTERVAL
+
+
+class _StatusRequestHandler(BaseHTTPRequestHandler):
+    server: _StatusServer
+
+    def do_GET(self) -> None:
+        self._respond(
+            {
+                "/status": self.server.service.status,
+                "/metrics": self.server.service.metrics,
+            }
+        )
+
+    def do_POST(self) -> None:
+        if "Origin" in self.headers:
+            # Browsers send an `Origin` with cross-site requests: refuse to let web pages trigger
+            # maintenance.
+            error = "Cross-origin requests are not allowed."
+            self._send_json(HTTPStatus.FORBIDDEN, {"error": error})
+            return
+        self._respond(
+            {
+                "/gc": self.server.service.garbage_collect,
+                "/diagnostics": self.server.service.dump_diagnostics,
+                "/heap_profile": self.server.service.dump_heap_profile,
+            }
+        )
+
+    def _respond(self, endpoints: dict[str, _Endpoint]) -> None:
+        endpoint = endpoints.get(self.path)
+        if endpoint is None:
+            error = f"Unknown endpoint: {self.command} {self.path}"
+            self._send_json(HTTPStatus.NOT_FOUND, {"error": error})
+            return
+        try:
+            body = endpoint()
+        except Exception as e:
+            logger.exception(f"Failed to serve {self.command} {self.path}")
+            self._send_json(HTTPStatus.INTERNAL_SERVER_ERROR, {"error": str(e)})
+            return
+        if isinstance(body, str):
+            self._send(HTTPStatus.OK, body.encode(), "text/plain; version=0.0.4; charset=utf-8")
+        else:
+            self._send_json(HTTPStatus.OK, body)
+
+    def _send_json(self, status: HTTPStatus, body: dict[str, Any]) -> None:
+        self._send(status, json.dumps(body).encode(), "application/json")
+
+    def _send(self, status: HTTPStatus, payload: bytes, content_type: str) -> None:
+        self.send_response(status)
+        self.send_header("Content-Type", content_type)
+        self.send_header("Content-Length", str(len(payload)))
+        self.end_headers()
+        self.wfile.write(payload)
+
+    def log_message(self, format: str, *args: Any) -> None:
+        logger.debug(f"{self.address_string()} - {format % args}")
+
+
+class _Observations:
+    """The observations of a metric in the completed runs, as a Prometheus histogram."""
+
+    def __init__(self) -> None:
+        self.buckets: Counter[int] = Counter()
+        self.count = 0
+        self.total = 0
+
+    def add(self, buckets: list[tuple[int, int]], count: int, total: int) -> None:
+        self.buckets.update(dict(buckets))
+        self.count += count
+        self.total += total
+
+    def metric(self, name: str) -> list[str]:
+        return [
+            f"# TYPE {name} histogram",
+            *(f'{name}_bucket{{le="{le}"}} {n}' for le, n in sorted(self.buckets.items())),
+            f'{name}_bucket{{le="+Inf"}} {self.count}',
+            f"{name}_sum {self.total}",
+            f"{name}_count {self.count}",
+        ]
+
+
+def _metric(name: str, kind: str, value: int | float) -> list[str]:
+    return [f"# TYPE {name} {kind}", f"{name} {value}"]
+
+
+def _disk_usage(directory: str) -> int:
+    """The disk usage of the files under the given directory, in bytes."""
+    total = 0
+    for root, _, files in os.walk(directory):
+        for name in files:
+            try:
+                total += os.lstat(os.path.join(root, name)).st_blocks * 512
+            except OSError:
+                # The file was removed concurrently (e.g. by garbage collection).
+                pass
+    return total
@@ -0,0 +1,114 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+import json
+import os
+import sys
+import threading
+from pathlib import Path
+from typing import Any
+from urllib.error import HTTPError
+from urllib.request import Request, urlopen
+
+import pytest
+
+from pants.engine.internals import native_engine
+from pants.pantsd.service.status_service import StatusService
+from pants.testutil.rule_runner import RuleRunner
+from pants.version import VERSION
+
+
+def request(
+    service: StatusService, method: str, path: str, headers: dict[str, str] | None = None
+) -> tuple[int, dict[str, Any]]:
+    url = f"http://127.0.0.1:{service.port}{path}"
+    try:
+        with urlopen(Request(url, method=method, headers=headers or {}), timeout=10) as response:
+            return response.status, json.load(response)
+    except HTTPError as e:
+        return e.code, json.load(e)
+
+
+@pytest.fixture
+def service(tmp_path: Path):
+    # Start the service in another thread (`setup` is a required part of the service lifecycle, but
+    # is unused in this case.)
+    status_service = StatusService(
+        scheduler=RuleRunner().scheduler.scheduler,
+        port=0,
+        port_file=str(tmp_path / "status_port"),
+        pid=os.getpid(),
+        diagnostics_dir=str(tmp_path / "diagnostics"),
+    )
+    status_service.setup(services=None)  # type: ignore[arg-type]
+    t = threading.Thread(target=status_service.run, name="status")
+    t.daemon = True
+    t.start()
+
+    yield status_service
+
+    # Exit the thread, and then join it.
+    status_service.terminate()
+    t.join(timeout=StatusService.POLL_INTERVAL * 10)
+    assert not t.is_alive()
+
+
+def test_status(service: StatusService, tmp_path: Path) -> None:
+    status, body = request(service, "GET", "/status")
+    assert status == 200
+    assert body["pid"] == os.getpid()
+    assert body["version"] == VERSION
+    assert body["uptime_secs"] > 0
+    assert "status_service_session" in body["sessions"]
+    assert body["graph_nodes"] >= 0
+    assert body["memory_usage_bytes"] > 0
+    assert (tmp_path / "status_port").read_text() == str(service.port)
+
+
+def test_maintenance(service: StatusService) -> None:
+    status, body = request(service, "POST", "/gc")
+    assert status == 200
+    assert body["local_store_size_bytes"] >= 0
+
+    status, body = request(service, "POST", "/diagnostics")
+    assert status == 200
+    assert os.path.isfile(body["graph"])
+    assert "Thread status" in Path(body["threads"]).read_text()
+
+    status, body = request(service, "POST", "/gc", headers={"Origin": "https://example.com"})
+    assert status == 403
+
+
+@pytest.mark.skipif(sys.platform != "linux", reason="Heap profiling is only supported on Linux.")
+def test_heap_profile(service: StatusService) -> None:
+    native_engine.pantsd_heap_profiling_activate()
+    status, body = request(service, "POST", "/heap_profile")
+    assert status == 200
+    assert Path(body["heap_profile"]).read_text().startswith("heap_v2/")
+
+
+def test_metrics(service: StatusService) -> None:
+    session = service._scheduler.new_session(build_id="test_metrics_session")
+    session.record_test_observation(7)
+    service.run_completed(session)
+    service.run_completed(session)
+
+    with urlopen(f"http://127.0.0.1:{service.port}/metrics", timeout=10) as response:
+        assert response.headers["Content-Type"].startswith("text/plain")
+        lines = response.read().decode().splitlines()
+    assert "# TYPE pants_graph_nodes gauge" in lines
+    assert "pants_runs_total 2" in lines
+    assert "# TYPE pants_test_observation histogram" in lines
+    assert 'pants_test_observation_bucket{le="5"} 0' in lines
+    assert 'pants_test_observation_bucket{le="10"} 2' in lines
+    assert 'pants_test_observation_bucket{le="+Inf"} 2' in lines
+    assert "pants_test_observation_sum 14" in lines
+    assert "pants_test_observation_count 2" in lines
+
+
+def test_unknown_endpoint(service: StatusService) -> None:
+    status, body = request(service, "GET", "/gc")
+    assert status == 404
+    assert body == {"error": "Unknown endpoint: GET /gc"}
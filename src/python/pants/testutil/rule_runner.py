@@ -59,6 +59,7 @@ from pants.option.global_options import (
     ExecutionOptions,
     GlobalOptions,
     LocalStoreOptions,
+    LogFormat,
 )
 from pants.option.options_bootstrapper import OptionsBootstrapper
 from pants.source import source_root
@@ -96,7 +97,7 @@ def logging(original_function=None, *, level: LogLevel = LogLevel.INFO):
         def wrapper(*args, **kwargs):
             stdout_fileno, stderr_fileno = sys.stdout.fileno(), sys.stderr.fileno()
             with temporary_dir() as tempdir, initialize_stdio_raw(
-                level, False, False, {}, True, [], tempdir
+                level, False, False, LogFormat.text, {}, True, [], tempdir
             ), stdin_context() as stdin, stdio_destination(
                 stdin.fileno(), stdout_fileno, stderr_fileno
             ):
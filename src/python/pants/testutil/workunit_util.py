@@ -0,0 +1,43 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+from __future__ import annotations
+
+from typing import Any
+
+from pants.engine.internals.scheduler import Workunit
+
+
+def _secs_and_nanos(secs: float) -> tuple[int, int]:
+    return divmod(round(secs * 1_000_000_000), 1_000_000_000)
+
+
+def create_workunit(
+    span_id: str,
+    *parent_ids: str,
+    name: str | None = None,
+    level: str = "DEBUG",
+    start_secs: float = 0,
+    duration_secs: float = 0,
+    metadata: dict[str, Any] | None = None,
+    **fields: Any,
+) -> Workunit:
+    """Create a completed workunit, as passed to a `WorkunitsCallback`.
+
+    The `name` defaults to the `span_id`, and times are given in (fractional) seconds. Any other
+    `fields`, such as a `description` or `artifacts`, are added to the workunit as given.
+    """
+    start_secs, start_nanos = _secs_and_nanos(start_secs)
+    duration_secs, duration_nanos = _secs_and_nanos(duration_secs)
+    return {
+        "name": name or span_id,
+        "span_id": span_id,
+        "level": level,
+        "parent_ids": list(parent_ids),
+        "start_secs": start_secs,
+        "start_nanos": start_nanos,
+        "duration_secs": duration_secs,
+        "duration_nanos": duration_nanos,
+        "metadata": metadata or {},
+        **fields,
+    }
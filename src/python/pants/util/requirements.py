@@ -2,19 +2,21 @@
 # Licensed under the Apache License, Version 2.0 (see LICENSE).
 from typing import Iterator
 
+from pants.engine.internals import native_engine
 from pants.util.pip_requirement import PipRequirement
 
 
 def parse_requirements_file(content: str, *, rel_path: str) -> Iterator[PipRequirement]:
     """Parse all `PipRequirement` objects from a requirements.txt-style file.
 
-    This will safely ignore any options starting with `--` and will ignore comments. Any pip-style
-    VCS requirements will fail, with a helpful error message describing how to use PEP 440.
-    """
-    for i, line in enumerate(content.splitlines(), start=1):
-        line, _, _ = line.partition("--")
-        line = line.strip().rstrip("\\")
-        if not line or line.startswith(("#", "-")):
-            continue
+    This will safely ignore any pip options (such as `-r` or `--hash`) and comments, and will join
+    lines ending in a backslash. Pip-style VCS requirements with an `#egg=` fragment are converted
+    to PEP 508 requirements.
 
-        yield PipRequirement.parse(line, description_of_origin=f"{rel_path} at line {i}")
+    The file is parsed natively, following pip's grammar, and each resulting (canonical)
+    requirement string is then parsed into a `PipRequirement`.
+    """
+    for line_number, req in native_engine.parse_python_requirements_file(content, rel_path):
+        yield PipRequirement.parse(
+            str(req), description_of_origin=f"{rel_path} at line {line_number}"
+        )
@@ -0,0 +1,44 @@
+# Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+# Licensed under the Apache License, Version 2.0 (see LICENSE).
+from textwrap import dedent
+
+import pytest
+
+from pants.util.pip_requirement import PipRequirement
+from pants.util.requirements import parse_requirements_file
+
+
+def test_parse_requirements_file() -> None:
+    content = dedent(
+        """\
+        # Comment.
+        --find-links=https://duckduckgo.com
+        -r other-requirements.txt
+
+        ansicolors>=1.18.0  # Inline comment.
+        Django[argon2]==3.2 ; python_version>'3' and (os_name == "nt" or sys_platform == "linux")
+        requests==2.31.0 \\
+            --hash=sha256:abcd \\
+            --hash=sha256:ef01
+        git+https://github.com/django/django.git@stable/2.1.x#egg=Django
+        """
+    )
+    assert list(parse_requirements_file(content, rel_path="requirements.txt")) == [
+        PipRequirement.parse("ansicolors>=1.18.0"),
+        PipRequirement.parse(
+            "Django[argon2]==3.2; python_version > '3' and "
+            "(os_name == 'nt' or sys_platform == 'linux')"
+        ),
+        PipRequirement.parse("requests==2.31.0"),
+        PipRequirement.parse(
+            "Django@ git+https://github.com/django/django.git@stable/2.1.x#egg=Django"
+        ),
+    ]
+
+
+def test_parse_requirements_file_error() -> None:
+    with pytest.raises(ValueError) as exc_info:
+        list(parse_requirements_file("foo\n\nNot A Valid Req == 3.7\n", rel_path="reqs.txt"))
+    assert "Invalid requirement 'Not A Valid Req == 3.7' in reqs.txt at line 3:" in str(
+        exc_info.value
+    )
@@ -76,6 +76,7 @@ def hash_files(path: str) -> str:
 NATIVE_FILES_COMMON_PREFIX = "src/python/pants"
 NATIVE_FILES = [
     f"{NATIVE_FILES_COMMON_PREFIX}/bin/native_client",
+    f"{NATIVE_FILES_COMMON_PREFIX}/bin/sandboxer",
     f"{NATIVE_FILES_COMMON_PREFIX}/engine/internals/native_engine.so",
     f"{NATIVE_FILES_COMMON_PREFIX}/engine/internals/native_engine.so.metadata",
 ]
@@ -472,7 +473,7 @@ class Helper:
             },
             {
                 "name": "Make native-client runnable",
-                "run": f"chmod +x {NATIVE_FILES[0]}",
+                "run": f"chmod +x {NATIVE_FILES[0]} {NATIVE_FILES[1]}",
             },
         ]
 
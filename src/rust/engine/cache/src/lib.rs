@@ -2,15 +2,25 @@
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
 use std::path::Path;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use bytes::Bytes;
 use grpc_util::prost::MessageExt;
-use hashing::Digest;
-use protos::gen::pants::cache::CacheKey;
-use sharded_lmdb::ShardedLmdb;
+use hashing::{Digest, Fingerprint};
+use prost::Message;
+use protos::gen::pants::cache::{CacheKey, CacheValue};
+use sharded_lmdb::{ShardedStore, StoreBackend};
 use task_executor::Executor;
 
+#[cfg(test)]
+mod tests;
+
+///
+/// The version of the format of entries, which is included in the fingerprints of their keys so
+/// that entries which were stored in a previous format are never read.
+///
+const ENTRY_FORMAT_VERSION: u8 = 1;
+
 ///
 /// A persistent cache for small values and keys.
 ///
@@ -21,44 +31,209 @@ use task_executor::Executor;
 /// Because keys and values are always small, this class exposes a simpler API than our LMDB
 /// access in general.
 ///
+/// Entries may be stored with a time to live, after which they are treated as missing. Entries
+/// belong to the namespace of their key, and can be invalidated per namespace: either by bumping
+/// the `namespace_version` of the keys (which orphans the existing entries), or by `clear`ing the
+/// namespace.
+///
+/// A cache may also consult the (read-only) caches of "peer" stores on misses, e.g. those of
+/// sibling checkouts of the same repository, and copies any entries which it finds into its own
+/// store. It never writes to its peers.
+///
 #[derive(Clone)]
 pub struct PersistentCache {
-    store: ShardedLmdb,
+    name: String,
+    store: ShardedStore,
+    peers: Vec<ShardedStore>,
 }
 
 impl PersistentCache {
     pub fn new(
         store_dir: &Path,
         max_size_bytes: usize,
+        max_size_growth_factor: usize,
+        executor: Executor,
+        lease_time: Duration,
+        shard_count: u8,
+        backend: StoreBackend,
+    ) -> Result<Self, String> {
+        Self::new_named(
+            store_dir,
+            "cache",
+            max_size_bytes,
+            max_size_growth_factor,
+            executor,
+            lease_time,
+            shard_count,
+            backend,
+        )
+    }
+
+    ///
+    /// Creates a cache stored in its own table (i.e. the `name` directory below `store_dir`), so
+    /// that its entries neither compete for space with, nor are invalidated with, other caches.
+    ///
+    pub fn new_named(
+        store_dir: &Path,
+        name: &str,
+        max_size_bytes: usize,
+        max_size_growth_factor: usize,
         executor: Executor,
         lease_time: Duration,
         shard_count: u8,
+        backend: StoreBackend,
     ) -> Result<Self, String> {
-        let store = ShardedLmdb::new(
-            store_dir.join("cache"),
+        let store = ShardedStore::new(
+            backend,
+            store_dir.join(name),
             max_size_bytes,
+            max_size_growth_factor,
             executor,
             lease_time,
             shard_count,
         )
-        .map_err(|err| format!("Could not initialize store for cache: {err:?}"))?;
+        .map_err(|err| format!("Could not initialize store for {name}: {err:?}"))?;
+
+        Ok(Self {
+            name: name.to_owned(),
+            store,
+            peers: Vec::new(),
+        })
+    }
 
-        Ok(Self { store })
+    ///
+    /// Attaches the table of this cache in the given store directory as a read-only peer, which is
+    /// consulted (after any previously attached peers) when an entry is missing.
+    ///
+    /// A peer store which does not contain this table (because it has never used this cache) is
+    /// ignored.
+    ///
+    pub fn into_with_peer(
+        mut self,
+        store_dir: &Path,
+        executor: Executor,
+        lease_time: Duration,
+    ) -> Result<Self, String> {
+        let path = store_dir.join(&self.name);
+        if !path.exists() {
+            return Ok(self);
+        }
+        let peer = ShardedStore::new_read_only(path, executor, lease_time).map_err(|err| {
+            format!(
+                "Could not open the peer store for {} at {}: {err}",
+                self.name,
+                store_dir.display()
+            )
+        })?;
+        self.peers.push(peer);
+        Ok(self)
     }
 
     pub async fn store(&self, key: &CacheKey, value: Bytes) -> Result<(), String> {
-        // NB: This is an unusual usage of the ShardedLmdb interface. In order for this to be a cache,
-        // rather than storing the value under its _own_ Fingerprint, the value is stored under the
-        // Fingerprint of the CacheKey.
-        let fingerprint = Digest::of_bytes(&key.to_bytes()).hash;
-        self.store.store_bytes(fingerprint, value, false).await?;
+        self.store_with_ttl(key, value, None).await
+    }
+
+    ///
+    /// Stores the value for the key, which will be treated as missing once the given time to live
+    /// has elapsed.
+    ///
+    pub async fn store_with_ttl(
+        &self,
+        key: &CacheKey,
+        value: Bytes,
+        ttl: Option<Duration>,
+    ) -> Result<(), String> {
+        let expires_at_unix_secs = match ttl {
+            Some(ttl) => unix_secs(SystemTime::now() + ttl)?,
+            None => 0,
+        };
+        let value = CacheValue {
+            value,
+            namespace: key.namespace.clone(),
+            expires_at_unix_secs,
+        };
+        // NB: This is an unusual usage of the ShardedStore interface. In order for this to be a
+        // cache, rather than storing the value under its _own_ Fingerprint, the value is stored
+        // under the Fingerprint of the CacheKey.
+        self.store
+            .store_bytes(Self::fingerprint(key), value.to_bytes(), false)
+            .await?;
         Ok(())
     }
 
     pub async fn load(&self, key: &CacheKey) -> Result<Option<Bytes>, String> {
-        let fingerprint = Digest::of_bytes(&key.to_bytes()).hash;
-        self.store
-            .load_bytes_with(fingerprint, move |bytes| Ok(Bytes::copy_from_slice(bytes)))
-            .await
+        let fingerprint = Self::fingerprint(key);
+        let now = unix_secs(SystemTime::now())?;
+        let maybe_value = self.store.load_bytes_with(fingerprint, decode).await?;
+        match maybe_value {
+            Some(value) if is_expired(&value, now) => {
+                self.store.remove(fingerprint).await?;
+            }
+            Some(value) => return Ok(Some(value.value)),
+            None => {}
+        }
+
+        for peer in &self.peers {
+            match peer.load_bytes_with(fingerprint, decode).await? {
+                Some(value) if !is_expired(&value, now) => {
+                    // Backfill our own store, so that the peer need not be consulted again.
+                    self.store
+                        .store_bytes(fingerprint, value.to_bytes(), false)
+                        .await?;
+                    return Ok(Some(value.value));
+                }
+                _ => {}
+            }
+        }
+        Ok(None)
     }
+
+    ///
+    /// Removes all entries in the given namespace, along with any entries which have expired or
+    /// which were stored in a previous format (and so could never be read again). Returns the
+    /// number of entries which were removed.
+    ///
+    pub async fn clear(&self, namespace: &str) -> Result<usize, String> {
+        let now = unix_secs(SystemTime::now())?;
+        let mut removed = 0;
+        for aged_fingerprint in self.store.all_fingerprints().await? {
+            let fingerprint = aged_fingerprint.fingerprint;
+            let namespace = namespace.to_owned();
+            let should_remove = self
+                .store
+                .load_bytes_with(fingerprint, move |bytes| {
+                    Ok(match CacheValue::decode(bytes) {
+                        Ok(value) => value.namespace == namespace || is_expired(&value, now),
+                        Err(_) => true,
+                    })
+                })
+                .await?
+                .unwrap_or(false);
+            if should_remove && self.store.remove(fingerprint).await? {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn fingerprint(key: &CacheKey) -> Fingerprint {
+        let mut bytes = Vec::with_capacity(1 + key.encoded_len());
+        bytes.push(ENTRY_FORMAT_VERSION);
+        bytes.extend_from_slice(&key.to_bytes());
+        Digest::of_bytes(&bytes).hash
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<CacheValue, String> {
+    CacheValue::decode(bytes).map_err(|e| format!("Invalid cache entry: {e}"))
+}
+
+fn is_expired(value: &CacheValue, now_unix_secs: u64) -> bool {
+    value.expires_at_unix_secs != 0 && value.expires_at_unix_secs <= now_unix_secs
+}
+
+fn unix_secs(time: SystemTime) -> Result<u64, String> {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .map_err(|e| format!("System time is before the unix epoch: {e}"))
 }
@@ -0,0 +1,219 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use std::time::Duration;
+
+use bytes::Bytes;
+use hashing::Digest;
+use protos::gen::pants::cache::{CacheKey, CacheKeyType};
+use sharded_lmdb::StoreBackend;
+use task_executor::Executor;
+use tempfile::TempDir;
+
+use crate::PersistentCache;
+
+fn new_cache() -> (PersistentCache, TempDir) {
+    let tempdir = TempDir::new().unwrap();
+    (new_cache_in(&tempdir), tempdir)
+}
+
+fn new_cache_in(tempdir: &TempDir) -> PersistentCache {
+    PersistentCache::new(
+        tempdir.path(),
+        15_000_000,
+        1,
+        Executor::new(),
+        Duration::from_secs(3600),
+        1,
+        StoreBackend::Lmdb,
+    )
+    .unwrap()
+}
+
+fn key(content: &str, namespace: &str, namespace_version: u32) -> CacheKey {
+    CacheKey {
+        key_type: CacheKeyType::Process.into(),
+        digest: Some((&Digest::of_bytes(content.as_bytes())).into()),
+        namespace: namespace.to_owned(),
+        namespace_version,
+    }
+}
+
+#[tokio::test]
+async fn store_and_load() {
+    let (cache, _tempdir) = new_cache();
+    let key = key("roland", "", 0);
+    assert_eq!(cache.load(&key).await.unwrap(), None);
+
+    cache.store(&key, Bytes::from("deschain")).await.unwrap();
+    assert_eq!(
+        cache.load(&key).await.unwrap(),
+        Some(Bytes::from("deschain"))
+    );
+}
+
+#[tokio::test]
+async fn expired_entries_are_missing() {
+    let (cache, _tempdir) = new_cache();
+    let expired = key("expired", "", 0);
+    let live = key("live", "", 0);
+    cache
+        .store_with_ttl(&expired, Bytes::from("x"), Some(Duration::ZERO))
+        .await
+        .unwrap();
+    cache
+        .store_with_ttl(&live, Bytes::from("y"), Some(Duration::from_secs(3600)))
+        .await
+        .unwrap();
+
+    assert_eq!(cache.load(&expired).await.unwrap(), None);
+    assert_eq!(cache.load(&live).await.unwrap(), Some(Bytes::from("y")));
+    // The expired entry was removed when it was loaded.
+    assert_eq!(cache.store.all_fingerprints().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn bumping_the_namespace_version_invalidates_entries() {
+    let (cache, _tempdir) = new_cache();
+    cache
+        .store(&key("a", "plugin", 1), Bytes::from("old"))
+        .await
+        .unwrap();
+
+    assert_eq!(cache.load(&key("a", "plugin", 2)).await.unwrap(), None);
+    assert_eq!(
+        cache.load(&key("a", "plugin", 1)).await.unwrap(),
+        Some(Bytes::from("old"))
+    );
+}
+
+#[tokio::test]
+async fn clear_namespace() {
+    let (cache, _tempdir) = new_cache();
+    for content in ["a", "b"] {
+        cache
+            .store(&key(content, "plugin", 0), Bytes::from(content))
+            .await
+            .unwrap();
+        cache
+            .store(&key(content, "other", 0), Bytes::from(content))
+            .await
+            .unwrap();
+    }
+    // An entry which was stored in a previous format, without a CacheValue.
+    cache
+        .store
+        .store_bytes(
+            Digest::of_bytes(b"legacy").hash,
+            Bytes::from_static(&[0xff, 0xff]),
+            false,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(cache.clear("plugin").await.unwrap(), 3);
+    for content in ["a", "b"] {
+        assert_eq!(cache.load(&key(content, "plugin", 0)).await.unwrap(), None);
+        assert_eq!(
+            cache.load(&key(content, "other", 0)).await.unwrap(),
+            Some(Bytes::from(content))
+        );
+    }
+    assert_eq!(cache.clear("plugin").await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn load_falls_back_to_peers_and_backfills() {
+    let peer_dir = TempDir::new().unwrap();
+    {
+        let peer = new_cache_in(&peer_dir);
+        peer.store(&key("a", "", 0), Bytes::from("from peer"))
+            .await
+            .unwrap();
+        peer.store_with_ttl(&key("b", "", 0), Bytes::from("x"), Some(Duration::ZERO))
+            .await
+            .unwrap();
+    }
+    // A peer which has never used the cache is ignored.
+    let empty_peer_dir = TempDir::new().unwrap();
+
+    let (cache, _tempdir) = new_cache();
+    let cache = cache
+        .into_with_peer(
+            empty_peer_dir.path(),
+            Executor::new(),
+            Duration::from_secs(3600),
+        )
+        .unwrap()
+        .into_with_peer(peer_dir.path(), Executor::new(), Duration::from_secs(3600))
+        .unwrap();
+    assert_eq!(
+        cache.load(&key("a", "", 0)).await.unwrap(),
+        Some(Bytes::from("from peer"))
+    );
+    assert_eq!(cache.load(&key("b", "", 0)).await.unwrap(), None);
+    assert_eq!(cache.load(&key("c", "", 0)).await.unwrap(), None);
+    // Only the live entry was copied from the peer.
+    assert_eq!(cache.store.all_fingerprints().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn named_caches_are_isolated() {
+    let (cache, tempdir) = new_cache();
+    let named = PersistentCache::new_named(
+        tempdir.path(),
+        "named",
+        15_000_000,
+        1,
+        Executor::new(),
+        Duration::from_secs(3600),
+        1,
+        StoreBackend::Lmdb,
+    )
+    .unwrap();
+    assert!(tempdir.path().join("named").is_dir());
+
+    let key = key("roland", "", 0);
+    named.store(&key, Bytes::from("deschain")).await.unwrap();
+    assert_eq!(
+        named.load(&key).await.unwrap(),
+        Some(Bytes::from("deschain"))
+    );
+    assert_eq!(cache.load(&key).await.unwrap(), None);
+
+    // Clearing one cache does not affect the other.
+    cache.store(&key, Bytes::from("gunslinger")).await.unwrap();
+    assert_eq!(cache.clear("").await.unwrap(), 1);
+    assert_eq!(
+        named.load(&key).await.unwrap(),
+        Some(Bytes::from("deschain"))
+    );
+}
+
+#[tokio::test]
+async fn peers_are_opened_with_their_own_backend() {
+    let peer_dir = TempDir::new().unwrap();
+    {
+        let peer = PersistentCache::new(
+            peer_dir.path(),
+            15_000_000,
+            1,
+            Executor::new(),
+            Duration::from_secs(3600),
+            2,
+            StoreBackend::Sqlite,
+        )
+        .unwrap();
+        peer.store(&key("a", "", 0), Bytes::from("from sqlite"))
+            .await
+            .unwrap();
+    }
+
+    let (cache, _tempdir) = new_cache();
+    let cache = cache
+        .into_with_peer(peer_dir.path(), Executor::new(), Duration::from_secs(3600))
+        .unwrap();
+    assert_eq!(
+        cache.load(&key("a", "", 0)).await.unwrap(),
+        Some(Bytes::from("from sqlite"))
+    );
+}
@@ -1,20 +1,136 @@
 // Copyright 2021 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
+use std::fmt;
 use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
 use std::time::SystemTime;
 
 use log::debug;
 
-use nailgun::NailgunClientError;
+use nailgun::{NailgunClientError, RemoteClientConfig};
+use options::{option_id, BuildRoot, OptionParser};
 use pantsd::ConnectionSettings;
 
+#[derive(Debug)]
+pub enum ExecuteError {
+    /// The command could not be run via pantsd.
+    Failed(String),
+    /// The connection to pantsd was lost while the command was running: e.g., because pantsd
+    /// exited.
+    ConnectionLost(String),
+    /// The command could not be run via a remote pantsd: which the legacy client cannot fall back
+    /// to, since it only runs commands locally.
+    RemoteFailed(String),
+}
+
+impl From<String> for ExecuteError {
+    fn from(err: String) -> Self {
+        ExecuteError::Failed(err)
+    }
+}
+
+impl fmt::Display for ExecuteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExecuteError::Failed(err)
+            | ExecuteError::ConnectionLost(err)
+            | ExecuteError::RemoteFailed(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+/// The pantsd to run a command in.
+pub enum Connection {
+    /// The pantsd of this workspace, on this machine.
+    Local(ConnectionSettings),
+    /// A pantsd on another machine, which runs the command in its own checkout of this workspace.
+    Remote {
+        config: RemoteClientConfig,
+        /// The working directory of the command, relative to the root of the workspace.
+        working_dir: PathBuf,
+        timeout_limit: f64,
+    },
+}
+
+impl Connection {
+    ///
+    /// A connection to the remote pantsd at the given address, configured by the
+    /// `--pantsd-remote-*` options.
+    ///
+    pub fn remote(
+        build_root: &BuildRoot,
+        options_parser: &OptionParser,
+        address: String,
+    ) -> Result<Connection, String> {
+        let token = options_parser
+            .parse_string_optional(&option_id!("pantsd", "remote", "token"), None)?
+            .value
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| {
+                format!(
+                    "`--pantsd-remote-token` must be set to connect to the pantsd at {address}."
+                )
+            })?;
+        let root_ca_certs_pem = options_parser
+            .parse_string_optional(&option_id!("pantsd", "remote", "ca", "certs", "path"), None)?
+            .value
+            .map(|path| {
+                std::fs::read(&path)
+                    .map_err(|e| format!("Failed to read CA certs from {path}: {e}"))
+            })
+            .transpose()?;
+        let working_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to determine the working directory: {e}"))?
+            .strip_prefix(&**build_root)
+            .map_err(|_| {
+                format!(
+                    "A remote pantsd can only run commands within the workspace at {}.",
+                    build_root.display()
+                )
+            })?
+            .to_path_buf();
+        let timeout_limit = options_parser
+            .parse_float(
+                &option_id!("pantsd", "timeout", "when", "multiple", "invocations"),
+                ConnectionSettings::new(0).timeout_limit,
+            )?
+            .value;
+        Ok(Connection::Remote {
+            config: RemoteClientConfig {
+                address,
+                root_ca_certs_pem,
+                token,
+            },
+            working_dir,
+            timeout_limit,
+        })
+    }
+}
+
+// The prefixes of the environment variables which are sent to a remote pantsd, which runs commands
+// in its own environment: i.e. those of Pants options, except for the options of the connection
+// to it.
+const REMOTE_ENV_PREFIX: &str = "PANTS_";
+const REMOTE_ENV_EXCLUDED_PREFIX: &str = "PANTS_PANTSD_REMOTE_";
+
 pub async fn execute_command(
     start: SystemTime,
-    connection_settings: ConnectionSettings,
+    connection: Connection,
     mut env: Vec<(String, String)>,
     argv: Vec<String>,
-) -> Result<i32, String> {
+) -> Result<i32, ExecuteError> {
+    let (timeout_limit, dynamic_ui) = match &connection {
+        Connection::Local(settings) => (settings.timeout_limit, settings.dynamic_ui),
+        Connection::Remote { timeout_limit, .. } => {
+            env.retain(|(name, _)| {
+                name.starts_with(REMOTE_ENV_PREFIX) && !name.starts_with(REMOTE_ENV_EXCLUDED_PREFIX)
+            });
+            // NB: The TTYs of the client are not available to a remote pantsd.
+            (*timeout_limit, false)
+        }
+    };
+
     env.push((
         "PANTSD_RUNTRACKER_CLIENT_START_TIME".to_owned(),
         start
@@ -26,7 +142,7 @@ pub async fn execute_command(
 
     env.push((
         "PANTSD_REQUEST_TIMEOUT_LIMIT".to_owned(),
-        connection_settings.timeout_limit.to_string(),
+        timeout_limit.to_string(),
     ));
 
     let raw_io_fds = [
@@ -44,7 +160,7 @@ pub async fn execute_command(
                 err = err
             ),
         }
-        if connection_settings.dynamic_ui {
+        if dynamic_ui {
             if let Ok(path) = nix::unistd::ttyname(*raw_fd) {
                 env.push((
                     format!("NAILGUN_TTY_PATH_{raw_fd}"),
@@ -61,8 +177,21 @@ pub async fn execute_command(
 
     let args = argv.iter().skip(1).cloned().collect();
 
-    let nailgun_result =
-        nailgun::client_execute(connection_settings.port, command, args, env).await;
+    let remote = matches!(connection, Connection::Remote { .. });
+    let (location, nailgun_result) = match connection {
+        Connection::Local(settings) => (
+            settings.port.to_string(),
+            nailgun::client_execute(settings.port, command, args, env).await,
+        ),
+        Connection::Remote {
+            config,
+            working_dir,
+            ..
+        } => (
+            config.address.clone(),
+            nailgun::client_execute_remote(config, working_dir, command, args, env).await,
+        ),
+    };
     for (raw_fd, termios) in tty_settings {
         if let Err(err) =
             nix::sys::termios::tcsetattr(*raw_fd, nix::sys::termios::SetArg::TCSADRAIN, &termios)
@@ -75,20 +204,18 @@ pub async fn execute_command(
         }
     }
     nailgun_result.map_err(|error| match error {
-        NailgunClientError::PreConnect(err) => format!(
-            "Problem connecting to pantsd at {port}: {err}",
-            port = connection_settings.port,
-            err = err
-        ),
-        NailgunClientError::PostConnect(err) => format!(
-            "Problem communicating with pantsd at {port}: {err}",
-            port = connection_settings.port,
-            err = err
-        ),
-        NailgunClientError::BrokenPipe => format!(
-            "Broken pipe communicating with pantsd at {port}.",
-            port = connection_settings.port
-        ),
-        NailgunClientError::KeyboardInterrupt => "User interrupt.".to_owned(),
+        NailgunClientError::PreConnect(err) if remote => {
+            ExecuteError::RemoteFailed(format!("Problem connecting to pantsd at {location}: {err}"))
+        }
+        NailgunClientError::PreConnect(err) => {
+            ExecuteError::Failed(format!("Problem connecting to pantsd at {location}: {err}"))
+        }
+        NailgunClientError::PostConnect(err) => ExecuteError::ConnectionLost(format!(
+            "Problem communicating with pantsd at {location}: {err}"
+        )),
+        NailgunClientError::BrokenPipe => ExecuteError::ConnectionLost(format!(
+            "Broken pipe communicating with pantsd at {location}."
+        )),
+        NailgunClientError::KeyboardInterrupt => ExecuteError::Failed("User interrupt.".to_owned()),
     })
 }
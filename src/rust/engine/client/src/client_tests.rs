@@ -7,16 +7,18 @@ use std::time::SystemTime;
 use options::{Args, Env, OptionParser};
 use pantsd::pantsd_testing::launch_pantsd;
 
-use crate::execute_command;
+use crate::{execute_command, Connection};
 
 #[tokio::test]
 async fn test_client() {
     let (build_root, options_parser, _tmpdir) = launch_pantsd();
 
-    let connection_settings = pantsd::find_pantsd(&build_root, &options_parser).unwrap();
+    let connection_settings = pantsd::find_pantsd(&build_root, &options_parser)
+        .await
+        .unwrap();
     let exit_code = execute_command(
         SystemTime::now(),
-        connection_settings,
+        Connection::Local(connection_settings),
         std::env::vars().collect(),
         ["pants", "-V"].iter().map(ToString::to_string).collect(),
     )
@@ -45,6 +47,7 @@ async fn test_client_fingerprint_mismatch() {
     )
     .unwrap();
     let error = pantsd::find_pantsd(&build_root, &options_parser)
+        .await
         .err()
         .unwrap();
 
@@ -0,0 +1,140 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use options::{option_id, BuildRoot, OptionParser};
+
+const PANTS_VERSION: &str = include_str!("../../VERSION");
+
+/// The file in the `pants_workdir` that the `complete` goal caches the registered goals and options
+/// in (see `src/python/pants/goal/completion.py`).
+const COMPLETION_CACHE_FILE: &str = "completion-cache.json";
+
+///
+/// The options which determine which goals and options are registered: the cache is only used if
+/// they have the same values as when it was written.
+///
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+pub(crate) struct CacheKey {
+    pub(crate) pants_version: String,
+    pub(crate) backend_packages: Vec<String>,
+    pub(crate) plugins: Vec<String>,
+    pub(crate) pythonpath: Vec<String>,
+}
+
+impl CacheKey {
+    fn compute(options_parser: &OptionParser) -> Result<CacheKey, String> {
+        let string_list = |option_id| -> Result<Vec<String>, String> {
+            Ok(options_parser.parse_string_list(&option_id, vec![])?.value)
+        };
+        Ok(CacheKey {
+            pants_version: PANTS_VERSION.trim().to_string(),
+            backend_packages: string_list(option_id!("backend", "packages"))?,
+            plugins: string_list(option_id!("plugins"))?,
+            pythonpath: string_list(option_id!("pythonpath"))?,
+        })
+    }
+}
+
+///
+/// The goals and options registered in a repo, as cached by the `complete` goal, which allow the
+/// completion scripts to be answered without running the `complete` goal (and so `pantsd`).
+///
+#[derive(Debug, Deserialize)]
+pub struct CompletionCache {
+    pub(crate) key: CacheKey,
+    pub(crate) goals: Vec<String>,
+    /// The names of the options of each scope, where the global scope is the empty string.
+    pub(crate) scopes: BTreeMap<String, Vec<String>>,
+}
+
+impl CompletionCache {
+    pub(crate) fn path(
+        build_root: &BuildRoot,
+        options_parser: &OptionParser,
+    ) -> Result<PathBuf, String> {
+        let default_workdir = build_root
+            .join(".pants.d")
+            .join("workdir")
+            .into_os_string()
+            .into_string()
+            .map_err(|e| format!("Build root was not UTF8: {e:?}"))?;
+        let workdir = options_parser
+            .parse_string(&option_id!("pants", "workdir"), &default_workdir)?
+            .value;
+        Ok(Path::new(&workdir).join(COMPLETION_CACHE_FILE))
+    }
+
+    pub(crate) fn read(path: &Path) -> Result<CompletionCache, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read the completion cache {path:?}: {e}"))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse the completion cache {path:?}: {e}"))
+    }
+
+    ///
+    /// Loads the completion cache of the given build root, if it was written for the current
+    /// values of the options which determine the registered goals and options.
+    ///
+    pub fn load(
+        build_root: &BuildRoot,
+        options_parser: &OptionParser,
+    ) -> Result<CompletionCache, String> {
+        let path = Self::path(build_root, options_parser)?;
+        let cache = Self::read(&path)?;
+        let key = CacheKey::compute(options_parser)?;
+        if cache.key != key {
+            return Err(format!(
+                "The completion cache {path:?} is stale: it was written for {:?}, not {key:?}.",
+                cache.key
+            ));
+        }
+        Ok(cache)
+    }
+
+    ///
+    /// The candidate completions of the last of the given words of a command line, as computed by
+    /// the `complete` goal for its passthrough args.
+    ///
+    pub fn complete(&self, words: &[String]) -> Vec<String> {
+        let mut args = words
+            .iter()
+            .map(String::as_str)
+            .filter(|word| *word != "pants")
+            .collect::<Vec<_>>();
+        let current_word = args.pop().unwrap_or_default();
+        // A "goal" in the context of completions is any arg which starts with an alphanumeric
+        // character.
+        let previous_goal = args
+            .iter()
+            .rev()
+            .find(|arg| arg.chars().next().is_some_and(char::is_alphanumeric));
+
+        if current_word.starts_with('-') {
+            return self.options(previous_goal.copied().unwrap_or_default(), current_word);
+        }
+        self.goals
+            .iter()
+            .filter(|goal| goal.starts_with(current_word))
+            // Goals which are already on the command line are not offered again.
+            .filter(|goal| previous_goal.is_none() || !words.contains(goal))
+            .cloned()
+            .collect()
+    }
+
+    /// The flags of the options of the given scope which start with the given prefix.
+    fn options(&self, scope: &str, prefix: &str) -> Vec<String> {
+        self.scopes
+            .get(scope)
+            .into_iter()
+            .flatten()
+            .map(|name| format!("--{name}"))
+            .filter(|flag| flag.starts_with(prefix))
+            .collect()
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+use std::fs;
+
+use serde_json::json;
+use tempfile::TempDir;
+
+use options::{Args, BuildRoot, Env, OptionParser};
+
+use crate::completion::{CacheKey, CompletionCache};
+
+fn cache() -> CompletionCache {
+    let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    CompletionCache {
+        key: CacheKey {
+            pants_version: "2.23.0".to_string(),
+            backend_packages: vec![],
+            plugins: vec![],
+            pythonpath: vec![],
+        },
+        goals: names(&["check", "fmt", "lint"]),
+        scopes: [
+            ("", names(&["colors", "level", "loop"])),
+            ("fmt", names(&["batch_size", "only"])),
+        ]
+        .into_iter()
+        .map(|(scope, options)| (scope.to_string(), options))
+        .collect(),
+    }
+}
+
+fn complete(words: &[&str]) -> Vec<String> {
+    let words = words.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+    cache().complete(&words)
+}
+
+#[test]
+fn test_complete_goals() {
+    assert_eq!(vec!["check", "fmt", "lint"], complete(&["pants", ""]));
+    assert_eq!(vec!["fmt"], complete(&["pants", "f"]));
+    assert_eq!(vec!["check", "lint"], complete(&["pants", "fmt", ""]));
+    assert_eq!(vec!["check"], complete(&["pants", "fmt", "lint", ""]));
+    assert_eq!(
+        vec!["check", "fmt", "lint"],
+        complete(&["pants", "--loop", ""])
+    );
+}
+
+#[test]
+fn test_complete_options() {
+    assert_eq!(
+        vec!["--colors", "--level", "--loop"],
+        complete(&["pants", "-"])
+    );
+    assert_eq!(vec!["--level", "--loop"], complete(&["pants", "--l"]));
+    assert_eq!(
+        vec!["--batch_size", "--only"],
+        complete(&["pants", "fmt", "-"])
+    );
+    assert_eq!(vec!["--only"], complete(&["pants", "fmt", "::", "--o"]));
+    assert!(complete(&["pants", "lint", "-"]).is_empty());
+    assert!(complete(&["pants", "unknown-goal", "-"]).is_empty());
+}
+
+#[test]
+fn test_load() {
+    let buildroot = TempDir::new().unwrap();
+    let build_root = BuildRoot::for_path(buildroot.path().to_path_buf());
+    let options_parser = |args: &[&str]| {
+        OptionParser::new(
+            Args::new(args.iter().map(|a| a.to_string())),
+            Env::new(HashMap::new()),
+            Some(vec![]),
+            false,
+            false,
+            Some(build_root.clone()),
+        )
+        .unwrap()
+    };
+    let write_cache = |backend_packages: &[&str]| {
+        let path = CompletionCache::path(&build_root, &options_parser(&[])).unwrap();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let cache = json!({
+            "key": {
+                "pants_version": include_str!("../../VERSION").trim(),
+                "backend_packages": backend_packages,
+                "plugins": [],
+                "pythonpath": [],
+            },
+            "goals": ["fmt", "lint"],
+            "scopes": {"": ["level"], "fmt": ["only"]},
+        });
+        fs::write(path, cache.to_string()).unwrap();
+    };
+
+    assert!(CompletionCache::load(&build_root, &options_parser(&[]))
+        .unwrap_err()
+        .contains("Failed to read the completion cache"));
+
+    write_cache(&["pants.backend.python"]);
+    let cache = CompletionCache::load(
+        &build_root,
+        &options_parser(&["--backend-packages=['pants.backend.python']"]),
+    )
+    .unwrap();
+    assert_eq!(vec!["fmt", "lint"], cache.goals);
+    assert!(CompletionCache::load(&build_root, &options_parser(&[]))
+        .unwrap_err()
+        .contains("is stale"));
+
+    write_cache(&[]);
+    assert!(CompletionCache::load(&build_root, &options_parser(&[])).is_ok());
+
+    let path = CompletionCache::path(&build_root, &options_parser(&[])).unwrap();
+    fs::write(path, "{").unwrap();
+    assert!(CompletionCache::load(&build_root, &options_parser(&[]))
+        .unwrap_err()
+        .contains("Failed to parse the completion cache"));
+}
@@ -4,8 +4,12 @@
 mod client;
 #[cfg(test)]
 mod client_tests;
+mod completion;
+#[cfg(test)]
+mod completion_tests;
 
-pub use crate::client::execute_command;
+pub use crate::client::{execute_command, Connection, ExecuteError};
+pub use crate::completion::CompletionCache;
 
 #[cfg(test)]
 mod lib_tests;
@@ -4,6 +4,7 @@
 use std::convert::{AsRef, Infallible};
 use std::env;
 use std::ffi::{CString, OsString};
+use std::io::Write;
 use std::os::unix::ffi::OsStringExt;
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -13,6 +14,7 @@ use nix::unistd::execv;
 use strum::VariantNames;
 use strum_macros::{AsRefStr, EnumString, EnumVariantNames};
 
+use client::{CompletionCache, Connection, ExecuteError};
 use options::{option_id, render_choice, Args, BuildRoot, Env, OptionParser};
 use pantsd::find_pantsd;
 
@@ -27,24 +29,47 @@ enum PythonLogLevel {
     Error,
 }
 
-async fn execute(start: SystemTime) -> Result<i32, String> {
+// The formats of `[GLOBAL].log_format`: see `logging::LogFormat` for those of `pantsd`.
+#[derive(AsRefStr, EnumString, EnumVariantNames, PartialEq)]
+#[strum(serialize_all = "snake_case")]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+async fn execute(start: SystemTime) -> Result<i32, ExecuteError> {
     let build_root = BuildRoot::find()?;
     let (env, dropped) = Env::capture_lossy();
     let env_items = (&env).into();
     let argv = env::args().collect::<Vec<_>>();
     let options_parser = OptionParser::new(Args::argv(), env, None, true, false, None)?;
 
+    // The completion scripts run `pants complete -- <words>` for every TAB, so answer it from the
+    // goals and options cached by the last run of the `complete` goal, if any, without pantsd.
+    if argv.get(1).is_some_and(|arg| arg == "complete") {
+        if let Some(words) = options_parser.get_passthrough_args() {
+            if let Ok(cache) = CompletionCache::load(&build_root, &options_parser) {
+                for candidate in cache.complete(words) {
+                    println!("{candidate}");
+                }
+                return Ok(0);
+            }
+        }
+    }
+
     let use_pantsd = options_parser.parse_bool(&option_id!("pantsd"), true)?;
     if !use_pantsd.value {
-        return Err(format!(
+        return Err(ExecuteError::Failed(format!(
             "Pantsd has been turned off via {option_source:?}.",
             option_source = use_pantsd.source
-        ));
+        )));
     }
 
     let concurrent = options_parser.parse_bool(&option_id!("concurrent"), false)?;
     if concurrent.value {
-        return Err("Pantsd is being turned off since --concurrent is true.".to_owned());
+        return Err(ExecuteError::Failed(
+            "Pantsd is being turned off since --concurrent is true.".to_owned(),
+        ));
     }
 
     let level_option = option_id!(-'l', "level");
@@ -59,7 +84,35 @@ async fn execute(start: SystemTime) -> Result<i32, String> {
                 .expect("We know there is at least one PythonLogLevel enum variant."),
         )
     })?;
-    env_logger::init_from_env(env_logger::Env::new().filter_or("__PANTS_LEVEL__", level.as_ref()));
+    let log_format_option_value =
+        options_parser.parse_string(&option_id!("log", "format"), LogFormat::Text.as_ref())?;
+    let log_format = LogFormat::from_str(&log_format_option_value.value).map_err(|_| {
+        format!(
+            "Not a valid log format {log_format} from {option_source:?}. Should be one of \
+            {log_formats}.",
+            log_format = log_format_option_value.value,
+            option_source = log_format_option_value.source,
+            log_formats = render_choice(LogFormat::VARIANTS)
+                .expect("We know there is at least one LogFormat enum variant."),
+        )
+    })?;
+    let mut logger = env_logger::Builder::from_env(
+        env_logger::Env::new().filter_or("__PANTS_LEVEL__", level.as_ref()),
+    );
+    if log_format == LogFormat::Json {
+        // The same fields as the records of `pantsd`, although the client never runs in a workunit.
+        logger.format(|buf, record| {
+            let log_record = serde_json::json!({
+                "timestamp": buf.timestamp_millis().to_string(),
+                "level": record.level().as_str(),
+                "target": record.target(),
+                "workunit_id": null,
+                "message": record.args().to_string(),
+            });
+            writeln!(buf, "{log_record}")
+        });
+    }
+    logger.init();
 
     // Now that the logger has been set up, we can retroactively log any dropped env vars.
     let mut keys_with_non_utf8_values = dropped.keys_with_non_utf8_values;
@@ -75,8 +128,41 @@ async fn execute(start: SystemTime) -> Result<i32, String> {
             name.to_string_lossy()
         );
     }
-    let pantsd_settings = find_pantsd(&build_root, &options_parser)?;
-    client::execute_command(start, pantsd_settings, env_items, argv).await
+    // A remote pantsd is used as is: the native client cannot launch it, nor fall back to the
+    // legacy client, which only runs commands locally.
+    let remote_address = options_parser
+        .parse_string_optional(&option_id!("pantsd", "remote", "address"), None)?
+        .value
+        .filter(|address| !address.is_empty());
+    if let Some(address) = remote_address {
+        let connection = Connection::remote(&build_root, &options_parser, address)
+            .map_err(ExecuteError::RemoteFailed)?;
+        return client::execute_command(start, connection, env_items, argv).await;
+    }
+
+    let rerun_after_restart =
+        options_parser.parse_bool(&option_id!("pantsd", "rerun", "after", "restart"), false)?;
+    let pantsd_settings = find_pantsd(&build_root, &options_parser).await?;
+    let port = pantsd_settings.port;
+    let connection = Connection::Local(pantsd_settings);
+    match client::execute_command(start, connection, env_items, argv).await {
+        // If the pantsd that the command was running in is gone, fall back to the legacy client,
+        // which relaunches pantsd, and runs the command again. This is opt-in, since the command may
+        // already have had side effects.
+        Err(ExecuteError::ConnectionLost(err)) if rerun_after_restart.value => {
+            let same_pantsd = find_pantsd(&build_root, &options_parser)
+                .await
+                .map(|settings| settings.port)
+                == Ok(port);
+            if same_pantsd {
+                Err(ExecuteError::ConnectionLost(err))
+            } else {
+                log::warn!("pantsd exited during the run: relaunching it, and running again...");
+                Err(ExecuteError::Failed(err))
+            }
+        }
+        result => result,
+    }
 }
 
 fn try_execv_fallback_client(pants_server: OsString) -> Result<Infallible, i32> {
@@ -138,12 +224,17 @@ async fn main() {
     }
 
     match (execute(start).await, pants_server) {
-        (Err(_), Some(pants_server)) => {
+        (Err(ExecuteError::Failed(err)), Some(pants_server)) => {
             // We failed to connect to `pantsd`, but a server variable was provided. Fall back
             // to `execv`'ing the legacy Python client, which will handle spawning `pantsd`.
+            log::debug!("Falling back to the legacy client, since pantsd is not usable: {err}");
             execv_fallback_client(pants_server);
         }
-        (Err(err), None) => {
+        (Err(err), _) => {
+            // NB: A command whose connection to `pantsd` was lost is not run again by the legacy
+            // client unless `--pantsd-rerun-after-restart` is set, since it may have had side
+            // effects. Nor is a command which could not be run via a remote `pantsd`, since the
+            // legacy client only runs commands locally.
             eprintln!("{err}");
             // We use this exit code to indicate an error running pants via the nailgun protocol to
             // differentiate from a successful nailgun protocol session.
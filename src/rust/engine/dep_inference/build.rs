@@ -218,6 +218,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         &source_dir,
         out_dir,
     )?;
+    gen_files_for_language(
+        tree_sitter_c_sharp::language(),
+        "dotnet",
+        &source_dir,
+        out_dir,
+    )?;
+    gen_files_for_language(tree_sitter_go::language(), "go", &source_dir, out_dir)?;
+    gen_files_for_language(tree_sitter_java::language(), "java", &source_dir, out_dir)?;
+    gen_files_for_language(tree_sitter_rust::language(), "rust", &source_dir, out_dir)?;
+    gen_files_for_language(tree_sitter_scala::language(), "scala", &source_dir, out_dir)?;
+    gen_files_for_language(
+        tree_sitter_kotlin::language(),
+        "kotlin",
+        &source_dir,
+        out_dir,
+    )?;
+    // NB: Terraform is parsed without tree-sitter, so only needs an impl hash.
+    gen_impl_hash_file(
+        "terraform",
+        source_dir.join("terraform").as_path(),
+        out_dir.join("terraform").as_path(),
+        out_dir,
+    );
     println!("cargo:rerun-if-env-changed=PANTS_PRINT_IMPL_HASHES");
     println!("cargo:rerun-if-changed=build.rs");
     println!("cargo:rerun-if-changed=src");
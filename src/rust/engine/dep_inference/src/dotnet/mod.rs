@@ -0,0 +1,161 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use std::path::PathBuf;
+
+use serde_derive::{Deserialize, Serialize};
+use tree_sitter::{Node, Parser};
+
+use crate::dotnet::msbuild::parse_project;
+
+mod msbuild;
+
+include!(concat!(env!("OUT_DIR"), "/dotnet_impl_hash.rs"));
+
+#[derive(Serialize, Deserialize)]
+pub struct DotnetPackageReference {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+/// The dependencies of either a C# source file, or an MSBuild project file (e.g. a `.csproj`).
+#[derive(Serialize, Deserialize)]
+pub struct ParsedDotnetDependencies {
+    /// The namespaces declared by a source file.
+    pub namespaces: Vec<String>,
+    /// The names imported by the `using` directives of a source file: namespaces, or types for
+    /// `using static` directives and aliases.
+    pub usings: Vec<String>,
+    /// The paths of the projects referenced by a project file, relative to the project file.
+    pub project_references: Vec<String>,
+    pub package_references: Vec<DotnetPackageReference>,
+}
+
+pub fn get_dependencies(
+    contents: &str,
+    filepath: PathBuf,
+) -> Result<ParsedDotnetDependencies, String> {
+    match filepath
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some("cs") => Ok(get_source_dependencies(contents)),
+        Some(extension) if extension.ends_with("proj") || extension == "props" => {
+            let references = parse_project(contents)
+                .map_err(|e| format!("Failed to parse {}: {e}", filepath.display()))?;
+            Ok(ParsedDotnetDependencies {
+                namespaces: Vec::new(),
+                usings: Vec::new(),
+                project_references: references.projects,
+                package_references: references
+                    .packages
+                    .into_iter()
+                    .map(|package| DotnetPackageReference {
+                        name: package.name,
+                        version: package.version,
+                    })
+                    .collect(),
+            })
+        }
+        _ => Err(format!(
+            "Unsupported file for .NET dependency inference: {}",
+            filepath.display()
+        )),
+    }
+}
+
+fn get_source_dependencies(contents: &str) -> ParsedDotnetDependencies {
+    let mut parser = Parser::new();
+    parser
+        .set_language(tree_sitter_c_sharp::language())
+        .expect("Error loading C# grammar");
+    let parsed = parser.parse(contents, None);
+    let tree = parsed.unwrap();
+
+    let mut collector = DeclarationCollector {
+        code: contents,
+        namespaces: Vec::new(),
+        usings: Vec::new(),
+    };
+    collector.collect(tree.root_node(), "");
+    ParsedDotnetDependencies {
+        namespaces: collector.namespaces,
+        usings: collector.usings,
+        project_references: Vec::new(),
+        package_references: Vec::new(),
+    }
+}
+
+struct DeclarationCollector<'a> {
+    code: &'a str,
+    namespaces: Vec<String>,
+    usings: Vec<String>,
+}
+
+impl DeclarationCollector<'_> {
+    /// Collects the declarations of the compilation unit or namespace body `node`, in the
+    /// namespace `prefix`.
+    fn collect(&mut self, node: Node, prefix: &str) {
+        // A file scoped namespace (`namespace Foo;`) applies to the declarations which follow it.
+        let mut prefix = prefix.to_string();
+        for child in node.named_children(&mut node.walk()) {
+            match child.kind() {
+                "using_directive" => {
+                    // E.g. `using System;`, `global using static System.Math;` or
+                    // `using Alias = System.Collections.Generic.List<int>;`.
+                    if let Some(name) = child
+                        .named_children(&mut child.walk())
+                        .filter(|n| n.kind() != "comment" && n.kind() != "name_equals")
+                        .last()
+                    {
+                        let name = self.name_at(name);
+                        if !name.is_empty() && !self.usings.contains(&name) {
+                            self.usings.push(name);
+                        }
+                    }
+                }
+                "namespace_declaration" => {
+                    if let Some(name) = child.child_by_field_name("name") {
+                        let namespace = qualify(&prefix, &self.name_at(name));
+                        self.insert_namespace(&namespace);
+                        if let Some(body) = child.child_by_field_name("body") {
+                            self.collect(body, &namespace);
+                        }
+                    }
+                }
+                "file_scoped_namespace_declaration" => {
+                    if let Some(name) = child.child_by_field_name("name") {
+                        prefix = qualify(&prefix, &self.name_at(name));
+                        self.insert_namespace(&prefix);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn insert_namespace(&mut self, namespace: &str) {
+        if !self.namespaces.iter().any(|n| n == namespace) {
+            self.namespaces.push(namespace.to_string());
+        }
+    }
+
+    /// The (possibly qualified) name at `node`, without an alias qualifier (e.g. `global::`),
+    /// type arguments or whitespace.
+    fn name_at(&self, node: Node) -> String {
+        let code = &self.code[node.start_byte()..node.end_byte()];
+        let code = code.split('<').next().unwrap_or_default();
+        let code = code.rsplit("::").next().unwrap_or_default();
+        code.chars().filter(|c| !c.is_whitespace()).collect()
+    }
+}
+
+fn qualify(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{prefix}.{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,167 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+/// A `PackageReference` item of an MSBuild project file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageReference {
+    pub name: String,
+    /// The version, from either the `Version` attribute or a nested `Version` element. Projects
+    /// using central package management declare the version elsewhere.
+    pub version: Option<String>,
+}
+
+/// The references declared by the items of an MSBuild project file.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ProjectReferences {
+    /// The `Include`s of `ProjectReference` items, with `/` as the path separator.
+    pub projects: Vec<String>,
+    pub packages: Vec<PackageReference>,
+}
+
+/// Collects the `ProjectReference` and `PackageReference` items of an MSBuild project file (e.g.
+/// a `.csproj`).
+///
+/// This is not a validating XML parser: it only scans the elements of the document (skipping
+/// comments, processing instructions, CDATA sections and declarations), which is sufficient for
+/// the well formed files that MSBuild accepts.
+pub fn parse_project(contents: &str) -> Result<ProjectReferences, String> {
+    let mut references = ProjectReferences::default();
+    // The open elements, and for an open `PackageReference`, the index of its reference.
+    let mut open: Vec<(&str, Option<usize>)> = Vec::new();
+    let mut rest = contents;
+
+    while let Some(start) = rest.find('<') {
+        let text = &rest[..start];
+        if let [.., (parent, Some(index)), ("Version", None)] = open.as_slice() {
+            if *parent == "PackageReference" && !text.trim().is_empty() {
+                references.packages[*index].version = Some(unescape(text.trim()));
+            }
+        }
+        rest = &rest[start..];
+
+        let skipped = [
+            ("<!--", "-->"),
+            ("<?", "?>"),
+            ("<![CDATA[", "]]>"),
+            ("<!", ">"),
+        ]
+        .into_iter()
+        .find(|(delimiter, _)| rest.starts_with(delimiter));
+        if let Some((open_delimiter, close_delimiter)) = skipped {
+            let end = rest[open_delimiter.len()..]
+                .find(close_delimiter)
+                .ok_or_else(|| format!("Unterminated `{open_delimiter}` in project file."))?;
+            rest = &rest[open_delimiter.len() + end + close_delimiter.len()..];
+            continue;
+        }
+
+        let end = tag_end(rest).ok_or("Unterminated tag in project file.")?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        if let Some(name) = tag.strip_prefix('/') {
+            let name = name.trim();
+            match open.pop() {
+                Some((open_name, _)) if open_name == name => {}
+                _ => {
+                    return Err(format!(
+                        "Unexpected closing tag `</{name}>` in project file."
+                    ))
+                }
+            }
+            continue;
+        }
+
+        let (tag, self_closing) = match tag.strip_suffix('/') {
+            Some(tag) => (tag, true),
+            None => (tag, false),
+        };
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        let name = &tag[..name_end];
+        let attributes = parse_attributes(&tag[name_end..])?;
+        let attribute = |key: &str| {
+            attributes
+                .iter()
+                .find(|(attribute, _)| *attribute == key)
+                .map(|(_, value)| value.clone())
+        };
+
+        let mut package_index = None;
+        match (name, attribute("Include")) {
+            ("ProjectReference", Some(include)) => references
+                .projects
+                .extend(items(&include).map(|item| item.replace('\\', "/"))),
+            ("PackageReference", Some(include)) => {
+                let version = attribute("Version");
+                for item in items(&include) {
+                    package_index = Some(references.packages.len());
+                    references.packages.push(PackageReference {
+                        name: item.to_string(),
+                        version: version.clone(),
+                    });
+                }
+            }
+            _ => {}
+        }
+        if !self_closing {
+            open.push((name, package_index));
+        }
+    }
+    Ok(references)
+}
+
+/// The index of the `>` which ends the tag at the start of `rest`, ignoring any in quoted
+/// attribute values.
+fn tag_end(rest: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in rest.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_attributes(mut rest: &str) -> Result<Vec<(&str, String)>, String> {
+    let mut attributes = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Ok(attributes);
+        }
+        let (key, value) = rest
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid attribute `{rest}` in project file."))?;
+        let value = value.trim_start();
+        let quote = value
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')
+            .ok_or_else(|| format!("Unquoted value for attribute `{}`.", key.trim()))?;
+        let end = value[1..]
+            .find(quote)
+            .ok_or_else(|| format!("Unterminated value for attribute `{}`.", key.trim()))?;
+        attributes.push((key.trim(), unescape(&value[1..end + 1])));
+        rest = &value[end + 2..];
+    }
+}
+
+/// The items of an item `Include`, which may list several items separated by `;`.
+fn items(include: &str) -> impl Iterator<Item = &str> {
+    include
+        .split(';')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,165 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use std::path::PathBuf;
+
+use crate::dotnet::msbuild::{parse_project, PackageReference, ProjectReferences};
+use crate::dotnet::{get_dependencies, ParsedDotnetDependencies};
+
+fn parse(code: &str) -> ParsedDotnetDependencies {
+    get_dependencies(code, PathBuf::from("Source.cs")).unwrap()
+}
+
+#[test]
+fn usings() {
+    let result = parse(
+        r#"
+using System;
+using System.Collections.Generic;
+global using static System.Math;
+using Json = Newtonsoft.Json.JsonConvert;
+using IntList = System.Collections.Generic.List<int>;
+using global::Company.Product;
+using System;
+
+namespace App
+{
+    using App.Models;
+}
+"#,
+    );
+    assert_eq!(
+        vec![
+            "System",
+            "System.Collections.Generic",
+            "System.Math",
+            "Newtonsoft.Json.JsonConvert",
+            "System.Collections.Generic.List",
+            "Company.Product",
+            "App.Models",
+        ],
+        result.usings
+    );
+}
+
+#[test]
+fn namespaces() {
+    let result = parse(
+        r#"
+namespace Company.Product
+{
+    public class Foo {}
+
+    namespace Models
+    {
+        public record Bar(int Baz);
+    }
+}
+
+namespace Other {}
+"#,
+    );
+    assert_eq!(
+        vec!["Company.Product", "Company.Product.Models", "Other"],
+        result.namespaces
+    );
+
+    let result = parse(
+        r#"
+using System;
+
+namespace Company.Product.Services;
+
+public class Service {}
+"#,
+    );
+    assert_eq!(vec!["Company.Product.Services"], result.namespaces);
+    assert_eq!(vec!["System"], result.usings);
+}
+
+#[test]
+fn project_references() {
+    let result = get_dependencies(
+        r#"<Project Sdk="Microsoft.NET.Sdk">
+  <PropertyGroup>
+    <TargetFramework>net8.0</TargetFramework>
+  </PropertyGroup>
+  <ItemGroup>
+    <ProjectReference Include="..\Lib\Lib.csproj" />
+    <PackageReference Include="Newtonsoft.Json" Version="13.0.3" />
+  </ItemGroup>
+</Project>
+"#,
+        PathBuf::from("App/App.csproj"),
+    )
+    .unwrap();
+    assert_eq!(vec!["../Lib/Lib.csproj"], result.project_references);
+    assert_eq!(
+        vec![("Newtonsoft.Json".to_string(), Some("13.0.3".to_string()))],
+        result
+            .package_references
+            .into_iter()
+            .map(|package| (package.name, package.version))
+            .collect::<Vec<_>>()
+    );
+
+    assert!(get_dependencies("", PathBuf::from("README.md")).is_err());
+}
+
+#[test]
+fn project_file_items() {
+    let result = parse_project(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<Project>
+  <!-- <ProjectReference Include="Commented.csproj" /> -->
+  <ItemGroup Condition="'$(Configuration)' == 'Debug'">
+    <ProjectReference Include="A\A.csproj;B/B.fsproj" />
+    <ProjectReference Include="C\C.csproj">
+      <Private>false</Private>
+    </ProjectReference>
+    <PackageReference Include="Serilog">
+      <Version>3.1.1</Version>
+    </PackageReference>
+    <PackageReference Include="xunit" />
+    <PackageReference Update="Ignored" Version="1.0" />
+    <Compile Include="Generated/*.cs" />
+  </ItemGroup>
+</Project>
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        ProjectReferences {
+            projects: vec![
+                "A/A.csproj".to_string(),
+                "B/B.fsproj".to_string(),
+                "C/C.csproj".to_string(),
+            ],
+            packages: vec![
+                PackageReference {
+                    name: "Serilog".to_string(),
+                    version: Some("3.1.1".to_string()),
+                },
+                PackageReference {
+                    name: "xunit".to_string(),
+                    version: None,
+                },
+            ],
+        },
+        result
+    );
+}
+
+#[test]
+fn invalid_project_files() {
+    for contents in [
+        "<Project><ItemGroup></Project>",
+        "<Project><!-- </Project>",
+        "<Project Sdk=\"Microsoft.NET.Sdk\"",
+        "<Project Sdk=Microsoft.NET.Sdk></Project>",
+    ] {
+        assert!(
+            parse_project(contents).is_err(),
+            "Expected `{contents}` to fail to parse."
+        );
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use std::path::PathBuf;
+
+use serde_derive::{Deserialize, Serialize};
+use tree_sitter::{Node, Parser};
+
+include!(concat!(env!("OUT_DIR"), "/go_impl_hash.rs"));
+
+#[derive(Serialize, Deserialize)]
+pub struct ParsedGoDependencies {
+    pub package: String,
+    pub imports: Vec<String>,
+    /// The build constraint of the file, in `//go:build` expression syntax (legacy `// +build`
+    /// lines are converted).
+    pub build_constraint: Option<String>,
+    pub embed_patterns: Vec<String>,
+}
+
+pub fn get_dependencies(
+    contents: &str,
+    _filepath: PathBuf,
+) -> Result<ParsedGoDependencies, String> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(tree_sitter_go::language())
+        .expect("Error loading Go grammar");
+    let parsed = parser.parse(contents, None);
+    let tree = parsed.unwrap();
+    let root = tree.root_node();
+
+    let mut package = String::new();
+    let mut imports = Vec::new();
+    let mut go_build_constraint = None;
+    let mut plus_build_lines = Vec::new();
+    let mut embed_patterns = Vec::new();
+
+    for child in root.named_children(&mut root.walk()) {
+        match child.kind() {
+            "comment" => {
+                let comment = code_at(contents, child);
+                if package.is_empty() {
+                    if let Some(expr) = directive_argument(comment, "//go:build") {
+                        go_build_constraint = Some(expr.to_string());
+                    } else if let Some(expr) = comment
+                        .strip_prefix("//")
+                        .and_then(|c| directive_argument(c.trim_start(), "+build"))
+                    {
+                        plus_build_lines.push(plus_build_to_expression(expr));
+                    }
+                }
+                if let Some(patterns) = directive_argument(comment, "//go:embed") {
+                    embed_patterns.extend(parse_embed_patterns(patterns)?);
+                }
+            }
+            "package_clause" => {
+                if let Some(name) = child
+                    .named_children(&mut child.walk())
+                    .find(|n| n.kind() == "package_identifier")
+                {
+                    package = code_at(contents, name).to_string();
+                }
+            }
+            "import_declaration" => insert_imports(contents, child, &mut imports),
+            _ => {}
+        }
+    }
+
+    let build_constraint = go_build_constraint.or_else(|| match plus_build_lines.len() {
+        0 => None,
+        1 => plus_build_lines.pop(),
+        _ => Some(
+            plus_build_lines
+                .iter()
+                .map(|line| format!("({line})"))
+                .collect::<Vec<_>>()
+                .join(" && "),
+        ),
+    });
+
+    Ok(ParsedGoDependencies {
+        package,
+        imports,
+        build_constraint,
+        embed_patterns,
+    })
+}
+
+fn code_at<'a>(code: &'a str, node: Node) -> &'a str {
+    &code[node.start_byte()..node.end_byte()]
+}
+
+/// The (trimmed) argument of a line comment directive, if the comment is that directive.
+fn directive_argument<'a>(comment: &'a str, directive: &str) -> Option<&'a str> {
+    let rest = comment.strip_prefix(directive)?;
+    if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+        Some(rest.trim())
+    } else {
+        None
+    }
+}
+
+fn insert_imports(code: &str, node: Node, imports: &mut Vec<String>) {
+    for child in node.named_children(&mut node.walk()) {
+        match child.kind() {
+            "import_spec" => {
+                if let Some(path) = child.child_by_field_name("path") {
+                    let path = code_at(code, path);
+                    imports.push(path[1..path.len() - 1].to_string());
+                }
+            }
+            "import_spec_list" => insert_imports(code, child, imports),
+            _ => {}
+        }
+    }
+}
+
+/// Converts the argument of a `// +build` line to the equivalent `//go:build` expression: space
+/// separated options are OR'd, and comma separated terms within an option are AND'd.
+fn plus_build_to_expression(line: &str) -> String {
+    let options = line
+        .split_whitespace()
+        .map(|option| option.split(',').collect::<Vec<_>>().join(" && "))
+        .collect::<Vec<_>>();
+    if options.len() > 1 {
+        options
+            .into_iter()
+            .map(|option| {
+                if option.contains(' ') {
+                    format!("({option})")
+                } else {
+                    option
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" || ")
+    } else {
+        options.join("")
+    }
+}
+
+/// Parses the patterns of a `//go:embed` directive, which are separated by spaces, and which may be
+/// quoted with double quotes or backticks if they contain spaces.
+fn parse_embed_patterns(args: &str) -> Result<Vec<String>, String> {
+    let mut patterns = Vec::new();
+    let mut rest = args.trim_start();
+    while !rest.is_empty() {
+        let (pattern, remainder) = match rest.chars().next() {
+            Some(quote @ ('"' | '`')) => {
+                let mut pattern = String::new();
+                let mut escaped = false;
+                let mut end = None;
+                for (i, c) in rest.char_indices().skip(1) {
+                    if escaped {
+                        pattern.push(c);
+                        escaped = false;
+                    } else if c == '\\' && quote == '"' {
+                        escaped = true;
+                    } else if c == quote {
+                        end = Some(i);
+                        break;
+                    } else {
+                        pattern.push(c);
+                    }
+                }
+                let end =
+                    end.ok_or_else(|| format!("Invalid quoted string in //go:embed: {rest}"))?;
+                (pattern, &rest[end + 1..])
+            }
+            _ => {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                (rest[..end].to_string(), &rest[end..])
+            }
+        };
+        patterns.push(pattern);
+        rest = remainder.trim_start();
+    }
+    Ok(patterns)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,118 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use std::path::PathBuf;
+
+use crate::go::{get_dependencies, ParsedGoDependencies};
+
+fn parse(code: &str) -> ParsedGoDependencies {
+    get_dependencies(code, PathBuf::from("source.go")).unwrap()
+}
+
+#[test]
+fn package_and_imports() {
+    let result = parse(
+        r#"
+package foo
+
+import "fmt"
+import alias "example.com/some/pkg"
+
+import (
+	"os"
+	_ "embed"
+	. "example.com/dot"
+)
+
+func main() {
+	fmt.Println("hello")
+}
+"#,
+    );
+    assert_eq!("foo", result.package);
+    assert_eq!(
+        vec![
+            "fmt",
+            "example.com/some/pkg",
+            "os",
+            "embed",
+            "example.com/dot"
+        ],
+        result.imports
+    );
+    assert_eq!(None, result.build_constraint);
+}
+
+#[test]
+fn raw_string_imports() {
+    let result = parse(
+        r#"
+package foo
+
+import `example.com/raw`
+"#,
+    );
+    assert_eq!(vec!["example.com/raw"], result.imports);
+}
+
+#[test]
+fn go_build_constraint() {
+    let result = parse(
+        r#"
+// Copyright notice.
+
+//go:build linux && (amd64 || arm64)
+// +build linux
+// +build amd64 arm64
+
+package foo
+
+//go:build ignored
+"#,
+    );
+    assert_eq!(
+        Some("linux && (amd64 || arm64)".to_string()),
+        result.build_constraint
+    );
+}
+
+#[test]
+fn plus_build_constraint() {
+    assert_eq!(
+        Some("linux".to_string()),
+        parse("// +build linux\n\npackage foo\n").build_constraint
+    );
+    assert_eq!(
+        Some("(linux && !cgo) || darwin".to_string()),
+        parse("// +build linux,!cgo darwin\n\npackage foo\n").build_constraint
+    );
+    assert_eq!(
+        Some("(linux || darwin) && (amd64)".to_string()),
+        parse("// +build linux darwin\n// +build amd64\n\npackage foo\n").build_constraint
+    );
+}
+
+#[test]
+fn embed_patterns() {
+    let result = parse(
+        r#"
+package foo
+
+import "embed"
+
+//go:embed hello.txt
+var hello string
+
+//go:embed static/* "with space.txt" `raw "quoted".txt`
+var static embed.FS
+"#,
+    );
+    assert_eq!(
+        vec![
+            "hello.txt",
+            "static/*",
+            "with space.txt",
+            "raw \"quoted\".txt"
+        ],
+        result.embed_patterns
+    );
+}
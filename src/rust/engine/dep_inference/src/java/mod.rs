@@ -0,0 +1,274 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use serde_derive::{Deserialize, Serialize};
+use tree_sitter::{Node, Parser};
+
+include!(concat!(env!("OUT_DIR"), "/java/constants.rs"));
+include!(concat!(env!("OUT_DIR"), "/java_impl_hash.rs"));
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JavaImport {
+    pub name: String,
+    pub is_static: bool,
+    pub is_asterisk: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ParsedJavaDependencies {
+    pub declared_package: Option<String>,
+    pub imports: Vec<JavaImport>,
+    pub top_level_types: Vec<String>,
+    pub consumed_types: Vec<String>,
+    pub export_types: Vec<String>,
+}
+
+pub fn get_dependencies(
+    contents: &str,
+    _filepath: PathBuf,
+) -> Result<ParsedJavaDependencies, String> {
+    let mut collector = SymbolCollector::new(contents);
+    collector.collect();
+
+    let top_level_types = collector
+        .top_level_types
+        .into_iter()
+        .map(|name| match &collector.declared_package {
+            Some(package) => format!("{package}.{name}"),
+            None => name,
+        })
+        .collect();
+    Ok(ParsedJavaDependencies {
+        declared_package: collector.declared_package,
+        imports: collector.imports,
+        top_level_types,
+        consumed_types: collector.consumed_types.into_iter().collect(),
+        export_types: collector.export_types.into_iter().collect(),
+    })
+}
+
+///
+/// Collects the same symbols as the JavaParser-based analysis which this replaces.
+///
+/// NB: Nodes are matched by generated `KindID` constants. The grammar aliases some of its kinds,
+/// and `build.rs` generates a `[u16; N]` array (rather than a `u16`) for a kind name which maps to
+/// several symbols: such a constant cannot be used as a `u16` pattern, so the compiler checks that
+/// each matched kind is unaliased. A kind which becomes aliased by a grammar upgrade must instead be
+/// matched with `KindID::X.contains(&id)`.
+///
+struct SymbolCollector<'a> {
+    declared_package: Option<String>,
+    imports: Vec<JavaImport>,
+    top_level_types: Vec<String>,
+    consumed_types: BTreeSet<String>,
+    export_types: BTreeSet<String>,
+    code: &'a str,
+}
+
+impl SymbolCollector<'_> {
+    fn new(code: &'_ str) -> SymbolCollector<'_> {
+        SymbolCollector {
+            declared_package: None,
+            imports: Vec::new(),
+            top_level_types: Vec::new(),
+            consumed_types: BTreeSet::new(),
+            export_types: BTreeSet::new(),
+            code,
+        }
+    }
+
+    fn collect(&mut self) {
+        let mut parser = Parser::new();
+        parser
+            .set_language(tree_sitter_java::language())
+            .expect("Error loading Java grammar");
+        let parsed = parser.parse(self.code, None);
+        let tree = parsed.unwrap();
+        let root = tree.root_node();
+
+        for child in root.named_children(&mut root.walk()) {
+            match child.kind_id() {
+                KindID::PACKAGE_DECLARATION => {
+                    self.declared_package = child
+                        .named_children(&mut child.walk())
+                        .find(|n| {
+                            matches!(n.kind_id(), KindID::IDENTIFIER | KindID::SCOPED_IDENTIFIER)
+                        })
+                        .map(|name| self.name_at(name));
+                }
+                KindID::IMPORT_DECLARATION => self.insert_import(child),
+                KindID::CLASS_DECLARATION
+                | KindID::INTERFACE_DECLARATION
+                | KindID::ENUM_DECLARATION
+                | KindID::RECORD_DECLARATION
+                | KindID::ANNOTATION_TYPE_DECLARATION => {
+                    if let Some(name) = child.child_by_field_name("name") {
+                        self.top_level_types.push(self.name_at(name));
+                    }
+                    self.visit(child);
+                }
+                _ => self.visit(child),
+            }
+        }
+    }
+
+    fn code_at(&self, node: Node) -> &str {
+        &self.code[node.start_byte()..node.end_byte()]
+    }
+
+    /// The text of a (possibly qualified) name, without any whitespace or comments.
+    fn name_at(&self, node: Node) -> String {
+        match node.kind_id() {
+            KindID::SCOPED_IDENTIFIER => node
+                .named_children(&mut node.walk())
+                .filter(|n| matches!(n.kind_id(), KindID::IDENTIFIER | KindID::SCOPED_IDENTIFIER))
+                .map(|n| self.name_at(n))
+                .collect::<Vec<_>>()
+                .join("."),
+            _ => self.code_at(node).to_string(),
+        }
+    }
+
+    fn insert_import(&mut self, node: Node) {
+        let mut cursor = node.walk();
+        let children = node.children(&mut cursor).collect::<Vec<_>>();
+        let Some(name) = children
+            .iter()
+            .find(|n| matches!(n.kind_id(), KindID::IDENTIFIER | KindID::SCOPED_IDENTIFIER))
+        else {
+            return;
+        };
+        self.imports.push(JavaImport {
+            name: self.name_at(*name),
+            // NB: The `static` keyword is an anonymous node, so has no generated `KindID`.
+            is_static: children.iter().any(|n| n.kind() == "static"),
+            is_asterisk: children.iter().any(|n| n.kind_id() == KindID::ASTERISK),
+        });
+    }
+
+    fn visit(&mut self, node: Node) {
+        match node.kind_id() {
+            KindID::TYPE_IDENTIFIER | KindID::SCOPED_TYPE_IDENTIFIER | KindID::GENERIC_TYPE => {
+                let mut names = Vec::new();
+                self.type_names(node, &mut names);
+                self.consumed_types.extend(names);
+                return;
+            }
+            // The declared names of type parameters are not consumed, but their bounds are.
+            KindID::TYPE_PARAMETER => {
+                for child in node.named_children(&mut node.walk()) {
+                    if child.kind_id() != KindID::TYPE_IDENTIFIER {
+                        self.visit(child);
+                    }
+                }
+                return;
+            }
+            KindID::ANNOTATION | KindID::MARKER_ANNOTATION => {
+                if let Some(name) = node.child_by_field_name("name") {
+                    self.consumed_types.insert(self.name_at(name));
+                }
+            }
+            KindID::METHOD_INVOCATION | KindID::FIELD_ACCESS => {
+                if let Some(object) = node.child_by_field_name("object") {
+                    if object.kind_id() == KindID::IDENTIFIER {
+                        self.consumed_types.insert(self.name_at(object));
+                    }
+                }
+            }
+            KindID::METHOD_DECLARATION => {
+                if let Some(return_type) = node.child_by_field_name("type") {
+                    self.insert_export_types(return_type);
+                }
+                if let Some(parameters) = node.child_by_field_name("parameters") {
+                    for parameter in parameters.named_children(&mut parameters.walk()) {
+                        if let Some(parameter_type) = parameter.child_by_field_name("type") {
+                            self.insert_export_types(parameter_type);
+                        }
+                    }
+                }
+            }
+            KindID::SUPERCLASS | KindID::SUPER_INTERFACES | KindID::EXTENDS_INTERFACES => {
+                self.insert_export_types(node);
+            }
+            _ => {}
+        }
+        for child in node.named_children(&mut node.walk()) {
+            self.visit(child);
+        }
+    }
+
+    fn insert_export_types(&mut self, node: Node) {
+        let mut names = Vec::new();
+        self.type_names(node, &mut names);
+        self.export_types.extend(names);
+    }
+
+    /// The names of the class or interface types referenced by the given type, including those of
+    /// its type arguments.
+    fn type_names(&self, node: Node, names: &mut Vec<String>) {
+        match node.kind_id() {
+            KindID::TYPE_IDENTIFIER => {
+                let name = self.code_at(node);
+                // NB: `var` is parsed as a type identifier, but it is inferred by the compiler.
+                if name != "var" {
+                    names.push(name.to_string());
+                }
+            }
+            KindID::SCOPED_TYPE_IDENTIFIER | KindID::GENERIC_TYPE => {
+                names.push(self.scoped_type_name(node));
+                let mut cursor = node.walk();
+                for child in node.named_children(&mut cursor) {
+                    match child.kind_id() {
+                        KindID::TYPE_ARGUMENTS => self.type_names(child, names),
+                        KindID::SCOPED_TYPE_IDENTIFIER | KindID::GENERIC_TYPE => {
+                            self.type_arguments_names(child, names)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {
+                for child in node.named_children(&mut node.walk()) {
+                    self.type_names(child, names);
+                }
+            }
+        }
+    }
+
+    fn type_arguments_names(&self, node: Node, names: &mut Vec<String>) {
+        for child in node.named_children(&mut node.walk()) {
+            match child.kind_id() {
+                KindID::TYPE_ARGUMENTS => self.type_names(child, names),
+                KindID::SCOPED_TYPE_IDENTIFIER | KindID::GENERIC_TYPE => {
+                    self.type_arguments_names(child, names)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The name of a type with its enclosing types, but without any type arguments or annotations.
+    fn scoped_type_name(&self, node: Node) -> String {
+        match node.kind_id() {
+            KindID::SCOPED_TYPE_IDENTIFIER | KindID::GENERIC_TYPE => node
+                .named_children(&mut node.walk())
+                .filter(|n| {
+                    matches!(
+                        n.kind_id(),
+                        KindID::TYPE_IDENTIFIER
+                            | KindID::SCOPED_TYPE_IDENTIFIER
+                            | KindID::GENERIC_TYPE
+                    )
+                })
+                .map(|n| self.scoped_type_name(n))
+                .collect::<Vec<_>>()
+                .join("."),
+            _ => self.code_at(node).to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
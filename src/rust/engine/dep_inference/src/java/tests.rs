@@ -0,0 +1,176 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use std::path::PathBuf;
+
+use crate::java::{get_dependencies, JavaImport, ParsedJavaDependencies};
+
+fn parse(code: &str) -> ParsedJavaDependencies {
+    get_dependencies(code, PathBuf::from("Source.java")).unwrap()
+}
+
+fn import(name: &str, is_static: bool, is_asterisk: bool) -> JavaImport {
+    JavaImport {
+        name: name.to_string(),
+        is_static,
+        is_asterisk,
+    }
+}
+
+#[test]
+fn package_and_imports() {
+    let result = parse(
+        r"
+package org.pantsbuild.example;
+
+import java.util.Date;
+import bogus.*;
+import static bogus.T;
+import bogus.T.t;
+import static bogus.Foo.*;
+
+public class SimpleSource {}
+
+sealed interface SimpleInterface permits SimpleImplementation1 {}
+
+final class SimpleImplementation1 implements SimpleInterface {}
+
+class Foo {}
+",
+    );
+    assert_eq!(
+        Some("org.pantsbuild.example".to_string()),
+        result.declared_package
+    );
+    assert_eq!(
+        vec![
+            import("java.util.Date", false, false),
+            import("bogus", false, true),
+            import("bogus.T", true, false),
+            import("bogus.T.t", false, false),
+            import("bogus.Foo", true, true),
+        ],
+        result.imports
+    );
+    assert_eq!(
+        vec![
+            "org.pantsbuild.example.SimpleSource",
+            "org.pantsbuild.example.SimpleInterface",
+            "org.pantsbuild.example.SimpleImplementation1",
+            "org.pantsbuild.example.Foo",
+        ],
+        result.top_level_types
+    );
+}
+
+#[test]
+fn unnamed_package() {
+    let result = parse(
+        r#"
+public class SimpleSource {
+    public void hello() {
+        System.out.println("hello");
+    }
+}
+
+class Foo {}
+"#,
+    );
+    assert_eq!(None, result.declared_package);
+    assert!(result.imports.is_empty());
+    assert_eq!(vec!["SimpleSource", "Foo"], result.top_level_types);
+    assert_eq!(vec!["System"], result.consumed_types);
+}
+
+#[test]
+fn consumed_types() {
+    let result = parse(
+        r"
+package org.pantsbuild.test;
+
+@ClassAnnotation
+public class AnImpl implements SomeInterface {
+    @InnerClassAnnotation
+    public static class Inner extends SomeGeneric<String> {
+    }
+
+    @FieldAnnotation
+    Provided provided = provided;
+
+    public AnImpl(Provider<SomeThing> provider) {
+        this.provided = provider.provide();
+    }
+
+    @Override
+    public int foo() throws AThrownException {
+        StaticClassRef.someMethod();
+        some.qualified.ref.Foo.bar();
+        some.other.Thing[] things = new some.other.Thing[1];
+        var result = things.length;
+        return 2;
+    }
+}
+",
+    );
+    assert_eq!(
+        vec![
+            "AThrownException",
+            "ClassAnnotation",
+            "FieldAnnotation",
+            "InnerClassAnnotation",
+            "Override",
+            "Provided",
+            "Provider",
+            "SomeGeneric",
+            "SomeInterface",
+            "SomeThing",
+            "StaticClassRef",
+            "String",
+            "provider",
+            "some",
+            "some.other.Thing",
+            "things",
+        ],
+        result.consumed_types
+    );
+}
+
+#[test]
+fn export_types() {
+    let result = parse(
+        r"
+package org.pantsbuild.test;
+
+public class Exporter extends Base implements Iface<Arg> {
+    private Internal internal;
+
+    public Result<Value> compute(Input input, int count) {
+        Local local = new Local();
+        return null;
+    }
+}
+",
+    );
+    assert_eq!(
+        vec!["Arg", "Base", "Iface", "Input", "Result", "Value"],
+        result.export_types
+    );
+    assert!(result.consumed_types.contains(&"Internal".to_string()));
+    assert!(result.consumed_types.contains(&"Local".to_string()));
+}
+
+#[test]
+fn type_parameters() {
+    let result = parse(
+        r"
+public class Box<T extends Bound> {
+    public <U> Map.Entry<T, U> entry(Key<? super U> key) {
+        return null;
+    }
+}
+",
+    );
+    assert_eq!(
+        vec!["Bound", "Key", "Map.Entry", "T", "U"],
+        result.consumed_types
+    );
+}
@@ -8,6 +8,8 @@ use fnv::FnvHashSet as HashSet;
 use fnv::{FnvHashMap as HashMap, FnvHashMap};
 use itertools::Either;
 
+use crate::javascript::util::normalize_path;
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct StarMatch<'a>(pub &'a str);
 
@@ -99,6 +101,48 @@ pub fn imports_from_patterns(
     .collect()
 }
 
+/// The extensions (in addition to none at all) that a module imported via a `tsconfig.json` or
+/// `jsconfig.json` alias may have, since such imports rarely include one.
+const CONFIG_PATHS_EXTENSIONS: &[&str] = &[".ts", ".tsx", ".d.ts", ".js", ".jsx", ".mjs", ".cjs"];
+
+/// Resolves a non-relative import to candidate file paths, using the `paths` (and `baseUrl`, if
+/// any) of a `tsconfig.json` or `jsconfig.json`, as described in
+/// [module resolution](https://www.typescriptlang.org/docs/handbook/modules/reference.html#paths).
+///
+/// Since which files exist is unknown here, all candidates are returned: for each substituted
+/// path, the path itself, with each of the supported extensions, and as a directory containing an
+/// `index` file.
+pub fn imports_from_config_paths(
+    root: &str,
+    paths: &HashMap<String, Vec<String>>,
+    has_base_url: bool,
+    import: &str,
+) -> HashSet<String> {
+    let mut substitutions: Vec<String> = find_best_match(paths, import)
+        .map(|(star_match, pattern)| {
+            paths[pattern]
+                .iter()
+                .filter_map(|replacement| apply_replacements_to_match(&star_match, replacement))
+                .collect()
+        })
+        .unwrap_or_default();
+    if has_base_url {
+        substitutions.push(import.to_string());
+    }
+    substitutions
+        .into_iter()
+        .filter_map(|substitution| normalize_path(&Path::new(root).join(substitution)))
+        .flat_map(|path| {
+            let path = path.to_string_lossy().to_string();
+            let with_extensions = CONFIG_PATHS_EXTENSIONS
+                .iter()
+                .flat_map(|ext| [format!("{path}{ext}"), format!("{path}/index{ext}")])
+                .collect::<Vec<_>>();
+            once(path).chain(with_extensions)
+        })
+        .collect()
+}
+
 fn apply_replacements_to_match(
     star_match: &Option<StarMatch>,
     replacement: &str,
@@ -8,7 +8,7 @@ use tree_sitter::{Node, Parser};
 
 use protos::gen::pants::cache::JavascriptInferenceMetadata;
 
-use crate::javascript::import_pattern::imports_from_patterns;
+use crate::javascript::import_pattern::{imports_from_config_paths, imports_from_patterns};
 use crate::javascript::util::normalize_path;
 
 mod import_pattern;
@@ -45,8 +45,26 @@ pub fn get_dependencies(
                 || import.starts_with('/')
                 || (!metadata.package_root.is_empty() && import.starts_with(&metadata.package_root))
         });
+    let mut file_imports = normalize_from_path(&metadata.package_root, filepath, relative_files);
+    // NB: Aliased imports stay package imports as well, since they may still resolve to a package
+    // (the compiler falls back to `node_modules` if no candidate file exists).
+    if let Some(config_paths) = metadata.config_paths {
+        let paths = config_paths
+            .paths
+            .into_iter()
+            .map(|pattern| (pattern.pattern, pattern.replacements))
+            .collect();
+        file_imports.extend(packages.iter().flat_map(|import| {
+            imports_from_config_paths(
+                &config_paths.root,
+                &paths,
+                config_paths.has_base_url,
+                import,
+            )
+        }));
+    }
     Ok(ParsedJavascriptDependencies {
-        file_imports: normalize_from_path(&metadata.package_root, filepath, relative_files),
+        file_imports,
         package_imports: packages,
     })
 }
@@ -123,6 +141,15 @@ impl ImportCollector<'_> {
             .map_or(false, |comment| contains_pragma(node, comment))
     }
 
+    /// Whether the node is a string literal, or a template literal without substitutions.
+    fn is_literal_string(node: Node) -> bool {
+        node.kind_id() == KindID::STRING
+            || (node.kind() == "template_string"
+                && !node
+                    .named_children(&mut node.walk())
+                    .any(|child| child.kind() == "template_substitution"))
+    }
+
     fn insert_import(&mut self, import_string: Option<Node>) {
         if let Some(import_string) = import_string {
             let import_string = self.code_at(import_string.range());
@@ -148,21 +175,20 @@ impl Visitor for ImportCollector<'_> {
     }
 
     fn visit_export_statement(&mut self, node: Node) -> ChildBehavior {
+        let source = node.child_by_field_name("source");
+        if source.is_none() {
+            // E.g. `export const page = () => import('./page.js')`.
+            return self.propagate_pragma(node);
+        }
         if !self.is_pragma_ignored(node) {
-            self.insert_import(node.child_by_field_name("source"));
+            self.insert_import(source);
         }
 
         ChildBehavior::Ignore
     }
 
     fn visit_expression_statement(&mut self, node: Node) -> ChildBehavior {
-        if node.children(&mut node.walk()).any(|child| {
-            let id = child.kind_id();
-            KindID::CALL_EXPRESSION.contains(&id) || id == KindID::AWAIT_EXPRESSION
-        }) {
-            return self.propagate_pragma(node);
-        }
-        ChildBehavior::Ignore
+        self.propagate_pragma(node)
     }
 
     fn visit_lexical_declaration(&mut self, node: Node) -> ChildBehavior {
@@ -171,15 +197,19 @@ impl Visitor for ImportCollector<'_> {
 
     fn visit_call_expression(&mut self, node: Node) -> ChildBehavior {
         if let (Some(function), Some(args)) = (node.named_child(0), node.named_child(1)) {
-            if let "require" | "import" = self.code_at(function.range()) {
+            if let "require" | "import" | "require.resolve" | "import.meta.resolve" =
+                self.code_at(function.range())
+            {
                 for arg in args.children(&mut args.walk()) {
-                    if arg.kind_id() == KindID::STRING {
+                    if Self::is_literal_string(arg) {
                         self.insert_import(Some(arg))
                     }
                 }
+                return ChildBehavior::Ignore;
             }
         }
-        ChildBehavior::Ignore
+        // The arguments (or the callee) may import, e.g. `lazy(() => import('./page.js'))`.
+        ChildBehavior::Visit
     }
 }
 
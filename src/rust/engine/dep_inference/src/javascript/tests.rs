@@ -4,9 +4,11 @@
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
-use crate::javascript::import_pattern::{imports_from_patterns, Pattern, StarMatch};
+use crate::javascript::import_pattern::{
+    imports_from_config_paths, imports_from_patterns, Pattern, StarMatch,
+};
 use crate::javascript::{get_dependencies, ImportCollector};
-use javascript_inference_metadata::ImportPattern;
+use javascript_inference_metadata::{ConfigPaths, ImportPattern};
 use protos::gen::pants::cache::{javascript_inference_metadata, JavascriptInferenceMetadata};
 
 fn assert_imports(code: &str, imports: &[&str]) {
@@ -32,6 +34,25 @@ fn given_metadata(
     JavascriptInferenceMetadata {
         package_root: root.to_string(),
         import_patterns,
+        config_paths: None,
+    }
+}
+
+fn given_config_paths(
+    root: &str,
+    paths: HashMap<String, Vec<String>>,
+    has_base_url: bool,
+) -> ConfigPaths {
+    ConfigPaths {
+        root: root.to_string(),
+        paths: paths
+            .into_iter()
+            .map(|(pattern, replacements)| ImportPattern {
+                pattern,
+                replacements,
+            })
+            .collect(),
+        has_base_url,
     }
 }
 
@@ -252,6 +273,53 @@ fn dynamic_scope() {
     );
 }
 
+#[test]
+fn dynamic_imports_in_nested_expressions() {
+    assert_imports(
+        r"
+    const Page = lazy(() => import('./Page.js'));
+    import('b').then((module) => module.default);
+    module.exports = require('c');
+    exports.d = { e: require('e') };
+    routes.push({ component: () => import('./routes/f.js') });
+  ",
+        &["./Page.js", "b", "c", "e", "./routes/f.js"],
+    );
+}
+
+#[test]
+fn dynamic_imports_in_exports() {
+    assert_imports(
+        r"
+    export const page = () => import('./page.js');
+    export default require('b');
+  ",
+        &["./page.js", "b"],
+    );
+    assert_imports(
+        "export const page = () => import('./page.js'); // pants: no-infer-dep",
+        &[],
+    );
+}
+
+#[test]
+fn template_literal_imports() {
+    assert_imports("import(`./a.js`)", &["./a.js"]);
+    assert_imports("require(`b`)", &["b"]);
+    assert_imports("import(`./pages/${name}.js`)", &[]);
+}
+
+#[test]
+fn resolve_calls() {
+    assert_imports(
+        "const a = require.resolve('a/package.json');",
+        &["a/package.json"],
+    );
+    assert_imports("const b = import.meta.resolve('./b.js');", &["./b.js"]);
+    assert_imports("resolve('c')", &[]);
+    assert_imports("require.resolve('d'); // pants: no-infer-dep", &[]);
+}
+
 #[test]
 fn adds_dir_to_file_imports() -> Result<(), Box<dyn std::error::Error>> {
     let result = get_dependencies(
@@ -548,3 +616,71 @@ fn longest_prefix_wins() {
         HashSet::from_iter(["dir/src/stuff/index.js".to_string()])
     )
 }
+
+#[test]
+fn config_paths_substitute_aliases() {
+    let mut paths = HashMap::default();
+    paths.insert("@app/*".to_string(), vec!["./src/app/*".to_string()]);
+    paths.insert("@utils".to_string(), vec!["lib/utils/index.ts".to_string()]);
+
+    let imports = imports_from_config_paths("js", &paths, false, "@app/components/Button");
+    assert!(imports.contains("js/src/app/components/Button"));
+    assert!(imports.contains("js/src/app/components/Button.tsx"));
+    assert!(imports.contains("js/src/app/components/Button/index.ts"));
+    assert!(imports.contains("js/src/app/components/Button.js"));
+
+    let imports = imports_from_config_paths("js", &paths, false, "@utils");
+    assert!(imports.contains("js/lib/utils/index.ts"));
+
+    assert_eq!(
+        imports_from_config_paths("js", &paths, false, "lodash"),
+        HashSet::default()
+    );
+}
+
+#[test]
+fn config_paths_base_url() {
+    let imports = imports_from_config_paths("js/src", &HashMap::default(), true, "lib/api");
+    assert!(imports.contains("js/src/lib/api"));
+    assert!(imports.contains("js/src/lib/api.ts"));
+    assert!(imports.contains("js/src/lib/api/index.js"));
+}
+
+#[test]
+fn config_paths_do_not_walk_out_of_the_build_root() {
+    let mut paths = HashMap::default();
+    paths.insert("@up/*".to_string(), vec!["../../*".to_string()]);
+    assert_eq!(
+        imports_from_config_paths("js", &paths, false, "@up/x"),
+        HashSet::default()
+    );
+}
+
+#[test]
+fn aliased_imports_are_file_and_package_imports() {
+    let mut metadata = given_metadata("js", Default::default());
+    metadata.config_paths = Some(given_config_paths(
+        "js",
+        HashMap::from_iter([("@app/*".to_string(), vec!["./src/*".to_string()])]),
+        false,
+    ));
+    let result = get_dependencies(
+        r#"
+    import { Button } from "@app/components/Button";
+    import { x } from "./x.mjs";
+    "#,
+        PathBuf::from("js/src/index.mjs"),
+        metadata,
+    )
+    .unwrap();
+    assert!(result.file_imports.contains("js/src/x.mjs"));
+    assert!(result.file_imports.contains("js/src/components/Button.ts"));
+    assert!(!result
+        .file_imports
+        .iter()
+        .any(|import| import.starts_with("js/src/x.mjs.")));
+    assert_eq!(
+        result.package_imports,
+        HashSet::from_iter(["@app/components/Button".to_string()])
+    );
+}
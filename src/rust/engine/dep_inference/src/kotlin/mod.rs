@@ -0,0 +1,296 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use serde_derive::{Deserialize, Serialize};
+use tree_sitter::{Node, Parser};
+
+include!(concat!(env!("OUT_DIR"), "/kotlin/constants.rs"));
+include!(concat!(env!("OUT_DIR"), "/kotlin_impl_hash.rs"));
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KotlinImport {
+    pub name: String,
+    pub alias: Option<String>,
+    pub is_wildcard: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ParsedKotlinDependencies {
+    pub package: String,
+    pub imports: Vec<KotlinImport>,
+    pub named_declarations: Vec<String>,
+    pub consumed_symbols_by_scope: BTreeMap<String, BTreeSet<String>>,
+    pub scopes: BTreeSet<String>,
+}
+
+pub fn get_dependencies(
+    contents: &str,
+    _filepath: PathBuf,
+) -> Result<ParsedKotlinDependencies, String> {
+    let mut collector = SymbolCollector::new(contents);
+    collector.collect();
+
+    Ok(ParsedKotlinDependencies {
+        package: collector.package,
+        imports: collector.imports,
+        named_declarations: collector.named_declarations,
+        consumed_symbols_by_scope: collector.consumed_symbols_by_scope,
+        scopes: collector.scopes,
+    })
+}
+
+fn qualify(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{scope}.{name}")
+    }
+}
+
+///
+/// Collects the same symbols as the Kotlin compiler PSI-based analysis which this replaces: the
+/// package, imports and top-level declarations of a file, and the symbols consumed in each of its
+/// (package or non-local class) scopes.
+///
+/// NB: Nodes are matched by generated `KindID` constants. The grammar aliases some of its kinds,
+/// and `build.rs` generates a `[u16; N]` array (rather than a `u16`) for a kind name which maps to
+/// several symbols: such a constant cannot be used as a `u16` pattern, so the compiler checks that
+/// each matched kind is unaliased. A kind which becomes aliased by a grammar upgrade must instead be
+/// matched with `KindID::X.contains(&id)`.
+///
+struct SymbolCollector<'a> {
+    package: String,
+    imports: Vec<KotlinImport>,
+    named_declarations: Vec<String>,
+    consumed_symbols_by_scope: BTreeMap<String, BTreeSet<String>>,
+    scopes: BTreeSet<String>,
+    current_scope: String,
+    /// The depth of nested function bodies (and other local contexts), in which declared classes
+    /// are local, and so do not introduce a scope.
+    local_depth: usize,
+    code: &'a str,
+}
+
+impl SymbolCollector<'_> {
+    fn new(code: &'_ str) -> SymbolCollector<'_> {
+        SymbolCollector {
+            package: String::new(),
+            imports: Vec::new(),
+            named_declarations: Vec::new(),
+            consumed_symbols_by_scope: BTreeMap::new(),
+            scopes: BTreeSet::new(),
+            current_scope: String::new(),
+            local_depth: 0,
+            code,
+        }
+    }
+
+    fn collect(&mut self) {
+        let mut parser = Parser::new();
+        parser
+            .set_language(tree_sitter_kotlin::language())
+            .expect("Error loading Kotlin grammar");
+        let parsed = parser.parse(self.code, None);
+        let tree = parsed.unwrap();
+        let root = tree.root_node();
+
+        if let Some(package_header) = root
+            .named_children(&mut root.walk())
+            .find(|n| n.kind_id() == KindID::PACKAGE_HEADER)
+        {
+            if let Some(name) = package_header
+                .named_children(&mut package_header.walk())
+                .find(|n| n.kind_id() == KindID::IDENTIFIER)
+            {
+                self.package = self.name_at(name);
+            }
+        }
+        self.current_scope = self.package.clone();
+        self.scopes.insert(self.package.clone());
+
+        for child in root.named_children(&mut root.walk()) {
+            match child.kind_id() {
+                KindID::IMPORT_LIST => {
+                    for import in child.named_children(&mut child.walk()) {
+                        if import.kind_id() == KindID::IMPORT_HEADER {
+                            self.insert_import(import);
+                        }
+                    }
+                }
+                KindID::IMPORT_HEADER => self.insert_import(child),
+                KindID::CLASS_DECLARATION
+                | KindID::OBJECT_DECLARATION
+                | KindID::FUNCTION_DECLARATION
+                | KindID::TYPE_ALIAS => {
+                    if let Some(name) = self.declared_name(child) {
+                        self.named_declarations
+                            .push(qualify(&self.package, &self.name_at(name)));
+                    }
+                }
+                KindID::PROPERTY_DECLARATION => {
+                    if let Some(name) = child
+                        .named_children(&mut child.walk())
+                        .find(|n| n.kind_id() == KindID::VARIABLE_DECLARATION)
+                        .and_then(|declaration| self.declared_name(declaration))
+                    {
+                        self.named_declarations
+                            .push(qualify(&self.package, &self.name_at(name)));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.visit(root);
+    }
+
+    fn code_at(&self, node: Node) -> &str {
+        &self.code[node.start_byte()..node.end_byte()]
+    }
+
+    fn name_at(&self, node: Node) -> String {
+        match node.kind_id() {
+            KindID::IDENTIFIER => node
+                .named_children(&mut node.walk())
+                .map(|part| self.name_at(part))
+                .collect::<Vec<_>>()
+                .join("."),
+            _ => self.code_at(node).trim_matches('`').to_string(),
+        }
+    }
+
+    /// The node for the name of a declaration, which is its first direct identifier child.
+    fn declared_name<'tree>(&self, node: Node<'tree>) -> Option<Node<'tree>> {
+        let mut cursor = node.walk();
+        let name = node.named_children(&mut cursor).find(|n| {
+            matches!(
+                n.kind_id(),
+                KindID::SIMPLE_IDENTIFIER | KindID::TYPE_IDENTIFIER
+            )
+        });
+        name
+    }
+
+    fn insert_import(&mut self, node: Node) {
+        let Some(name) = node
+            .named_children(&mut node.walk())
+            .find(|n| n.kind_id() == KindID::IDENTIFIER)
+        else {
+            return;
+        };
+        let alias = node
+            .named_children(&mut node.walk())
+            .find(|n| n.kind_id() == KindID::IMPORT_ALIAS)
+            .and_then(|alias| self.declared_name(alias))
+            .map(|alias| self.name_at(alias));
+        let is_wildcard =
+            alias.is_none() && self.code[name.end_byte()..node.end_byte()].contains('*');
+        self.imports.push(KotlinImport {
+            name: self.name_at(name),
+            alias,
+            is_wildcard,
+        });
+    }
+
+    /// The fully qualified name referenced by an expression which is a chain of member accesses on
+    /// a simple name.
+    fn qualified_name_at(&self, node: Node) -> Option<String> {
+        match node.kind_id() {
+            KindID::SIMPLE_IDENTIFIER => Some(self.name_at(node)),
+            KindID::NAVIGATION_EXPRESSION => {
+                let receiver = node.named_child(0)?;
+                let suffix = node.named_child(node.named_child_count() - 1)?;
+                if suffix.kind_id() != KindID::NAVIGATION_SUFFIX
+                    || self.code_at(suffix).starts_with("?.")
+                {
+                    return None;
+                }
+                let parent = self.qualified_name_at(receiver)?;
+                match suffix
+                    .named_children(&mut suffix.walk())
+                    .find(|n| n.kind_id() == KindID::SIMPLE_IDENTIFIER)
+                {
+                    Some(child) => Some(format!("{parent}.{}", self.name_at(child))),
+                    None => Some(parent),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn record_consumed_symbol(&mut self, symbol: String) {
+        self.consumed_symbols_by_scope
+            .entry(self.current_scope.clone())
+            .or_default()
+            .insert(symbol);
+    }
+
+    fn visit_children(&mut self, node: Node) {
+        for child in node.named_children(&mut node.walk()) {
+            self.visit(child);
+        }
+    }
+
+    fn visit(&mut self, node: Node) {
+        match node.kind_id() {
+            // Imports and the package are not consumed symbols.
+            KindID::PACKAGE_HEADER | KindID::IMPORT_LIST | KindID::IMPORT_HEADER => {}
+            KindID::CLASS_DECLARATION | KindID::OBJECT_DECLARATION | KindID::COMPANION_OBJECT => {
+                let name = self.declared_name(node);
+                let original_scope = self.current_scope.clone();
+                if self.local_depth == 0 {
+                    let name = name
+                        .map(|name| self.name_at(name))
+                        .unwrap_or_else(|| "Companion".to_string());
+                    self.current_scope = qualify(&original_scope, &name);
+                    self.scopes.insert(self.current_scope.clone());
+                }
+                self.visit_children_except(node, name);
+                self.current_scope = original_scope;
+            }
+            KindID::FUNCTION_DECLARATION
+            | KindID::TYPE_ALIAS
+            | KindID::VARIABLE_DECLARATION
+            | KindID::CLASS_PARAMETER
+            | KindID::PARAMETER
+            | KindID::ENUM_ENTRY
+            | KindID::TYPE_PARAMETER => {
+                let name = self.declared_name(node);
+                self.visit_children_except(node, name);
+            }
+            KindID::FUNCTION_BODY
+            | KindID::LAMBDA_LITERAL
+            | KindID::ANONYMOUS_FUNCTION
+            | KindID::ANONYMOUS_INITIALIZER
+            | KindID::GETTER
+            | KindID::SETTER
+            | KindID::SECONDARY_CONSTRUCTOR
+            | KindID::OBJECT_LITERAL => {
+                self.local_depth += 1;
+                self.visit_children(node);
+                self.local_depth -= 1;
+            }
+            KindID::NAVIGATION_EXPRESSION => match self.qualified_name_at(node) {
+                Some(name) => self.record_consumed_symbol(name),
+                None => self.visit_children(node),
+            },
+            KindID::SIMPLE_IDENTIFIER
+            | KindID::TYPE_IDENTIFIER
+            | KindID::INTERPOLATED_IDENTIFIER => self.record_consumed_symbol(self.name_at(node)),
+            _ => self.visit_children(node),
+        }
+    }
+
+    fn visit_children_except(&mut self, node: Node, excluded: Option<Node>) {
+        for child in node.named_children(&mut node.walk()) {
+            if Some(child) != excluded {
+                self.visit(child);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
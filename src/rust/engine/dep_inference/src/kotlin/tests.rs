@@ -0,0 +1,119 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use crate::kotlin::{get_dependencies, KotlinImport, ParsedKotlinDependencies};
+
+fn parse(code: &str) -> ParsedKotlinDependencies {
+    get_dependencies(code, PathBuf::from("Source.kt")).unwrap()
+}
+
+fn import(name: &str, alias: Option<&str>, is_wildcard: bool) -> KotlinImport {
+    KotlinImport {
+        name: name.to_string(),
+        alias: alias.map(str::to_string),
+        is_wildcard,
+    }
+}
+
+fn set(names: &[&str]) -> BTreeSet<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn package_and_imports() {
+    let result = parse(
+        r"
+package org.pantsbuild.backend.kotlin
+
+import java.io.File
+import java.util.*
+import org.pantsbuild.Foo as Bar
+",
+    );
+    assert_eq!("org.pantsbuild.backend.kotlin", result.package);
+    assert_eq!(
+        vec![
+            import("java.io.File", None, false),
+            import("java.util", None, true),
+            import("org.pantsbuild.Foo", Some("Bar"), false),
+        ],
+        result.imports
+    );
+}
+
+#[test]
+fn declarations_and_consumed_symbols() {
+    let result = parse(
+        r"
+package org.pantsbuild.backend.kotlin
+
+class Foo {
+    fun grok() {
+        val x = X()
+        val y = Y()
+    }
+}
+
+class Bar {}
+
+fun main(args: Array<String>) {
+}
+",
+    );
+    assert_eq!(
+        set(&[
+            "org.pantsbuild.backend.kotlin.Bar",
+            "org.pantsbuild.backend.kotlin.Foo",
+            "org.pantsbuild.backend.kotlin.main",
+        ]),
+        result.named_declarations.into_iter().collect()
+    );
+    assert_eq!(
+        set(&[
+            "org.pantsbuild.backend.kotlin",
+            "org.pantsbuild.backend.kotlin.Bar",
+            "org.pantsbuild.backend.kotlin.Foo",
+        ]),
+        result.scopes
+    );
+    assert_eq!(
+        BTreeMap::from([
+            (
+                "org.pantsbuild.backend.kotlin".to_string(),
+                set(&["Array", "String"])
+            ),
+            (
+                "org.pantsbuild.backend.kotlin.Foo".to_string(),
+                set(&["X", "Y"])
+            ),
+        ]),
+        result.consumed_symbols_by_scope
+    );
+}
+
+#[test]
+fn qualified_references() {
+    let result = parse(
+        r"
+package example
+
+object Holder {
+    companion object {
+        val value = some.pkg.Thing.create()
+    }
+}
+",
+    );
+    assert_eq!(
+        set(&["example", "example.Holder", "example.Holder.Companion"]),
+        result.scopes
+    );
+    assert_eq!(
+        Some(&set(&["some.pkg.Thing.create"])),
+        result
+            .consumed_symbols_by_scope
+            .get("example.Holder.Companion")
+    );
+}
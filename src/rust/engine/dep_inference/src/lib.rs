@@ -1,5 +1,13 @@
 // Copyright 2023 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
+pub mod dotnet;
+pub mod go;
+pub mod java;
 pub mod javascript;
+pub mod kotlin;
 pub mod python;
+pub mod python_requirements;
+pub mod rust;
+pub mod scala;
+pub mod terraform;
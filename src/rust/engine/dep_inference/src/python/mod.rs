@@ -10,6 +10,20 @@ use fnv::{FnvHashMap as HashMap, FnvHashSet as HashSet};
 use serde_derive::{Deserialize, Serialize};
 use tree_sitter::Parser;
 
+/// Functions which import the module named by their first (string literal) argument, and which are
+/// detected as weak imports, since the import may well be guarded by runtime logic.
+const DYNAMIC_IMPORT_FUNCTIONS: &[&str] = &[
+    "__import__",
+    "importlib.__import__",
+    "importlib.import_module",
+    "import_module",
+    "lazy_import.lazy_module",
+    "lazy_import.lazy_callable",
+    "lazy_module",
+    "lazy_callable",
+    "lazy_loader.load",
+];
+
 #[derive(Serialize, Deserialize)]
 pub struct ParsedPythonDependencies {
     pub imports: HashMap<String, (u64, bool)>,
@@ -332,16 +346,26 @@ impl Visitor for ImportCollector<'_> {
 
     fn visit_call(&mut self, node: tree_sitter::Node) -> ChildBehavior {
         let funcname = node.named_child(0).unwrap();
-        if self.code_at(funcname.range()) != "__import__" {
+        if !DYNAMIC_IMPORT_FUNCTIONS.contains(&self.code_at(funcname.range())) {
             return ChildBehavior::Visit;
         }
 
         let args = node.named_child(1).unwrap();
         if let Some(arg) = args.named_child(0) {
-            if arg.kind_id() == KindID::STRING {
+            // NB: Only literal strings name a module: interpolated f-strings don't, and nor do
+            // relative names, which are relative to the `package` argument rather than to the file.
+            if arg.kind_id() == KindID::STRING
+                && !arg
+                    .named_children(&mut arg.walk())
+                    .any(|child| child.kind() == "interpolation")
+                && !self.string_at(arg.range()).starts_with('.')
+            {
                 // NB: Call nodes are children of expression nodes. The comment is a sibling of the expression.
                 if !self.is_pragma_ignored(node.parent().unwrap()) {
+                    let previous_weaken = self.weaken_imports;
+                    self.weaken_imports = true;
                     self.insert_import(arg, None, true);
+                    self.weaken_imports = previous_weaken;
                 }
             }
         }
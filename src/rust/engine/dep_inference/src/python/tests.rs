@@ -281,6 +281,50 @@ fn dunder_import() {
     );
 }
 
+#[test]
+fn dynamic_imports() {
+    assert_imports("importlib.import_module('a.b')", &["a.b"]);
+    assert_imports("import_module('a.b')", &["a.b"]);
+    assert_imports("importlib.__import__('a')", &["a"]);
+    assert_imports("lazy_import.lazy_module('a.b')", &["a.b"]);
+    assert_imports("lazy_import.lazy_callable('a.b.func')", &["a.b.func"]);
+    assert_imports("lazy_loader.load('a')", &["a"]);
+    assert_imports("importlib.import_module('a', package='b')", &["a"]);
+    assert_imports(
+        "importlib.import_module('ignored')  # pants: no-infer-dep",
+        &[],
+    );
+    // Non-literal and relative names are not imports.
+    assert_imports("importlib.import_module(name)", &[]);
+    assert_imports("importlib.import_module(f'a.{name}')", &[]);
+    assert_imports("importlib.import_module('.b', package='a')", &[]);
+    // Nor are other functions' arguments.
+    assert_imports("some_module.import_it('a.b')", &[]);
+}
+
+#[test]
+fn dynamic_imports_are_weak() {
+    assert_imports_strong_weak(
+        r"
+    import strong
+    __import__('weak0')
+    importlib.import_module('weak1')
+    lazy_import.lazy_module('weak2')
+    ",
+        &["strong"],
+        &["weak0", "weak1", "weak2"],
+    );
+    // A static import of the same module is still a strong import.
+    assert_imports_strong_weak(
+        r"
+    import both
+    importlib.import_module('both')
+    ",
+        &["both"],
+        &[],
+    );
+}
+
 fn assert_imports_strong_weak(code: &str, strong: &[&str], weak: &[&str]) {
     let mut collector = ImportCollector::new(code);
     collector.collect();
@@ -472,7 +516,7 @@ fn tryexcept_weak_imports() {
 fn tryexcept_weak_imports_dunder() {
     assert_imports_strong_weak(
         r"
-    __import__('strong')
+    import strong
     try:
       __import__('weak')
     except ImportError:
@@ -639,7 +683,7 @@ fn contextlib_suppress_weak_imports() {
 fn contextlib_suppress_weak_imports_dunder() {
     assert_imports_strong_weak(
         r"
-    __import__('strong')
+    import strong
     with contextlib.suppress(ImportError):
       __import__('weak')
     ",
@@ -735,18 +779,17 @@ fn python2() {
         HashMap::from([
             ("demo", (4, false)),
             ("project.demo.Demo", (5, false)),
-            ("pkg_resources", (7, false)),
-            ("treat.as.a.regular.import.not.a.string.import", (8, false)),
+            ("pkg_resources", (7, true)),
+            ("treat.as.a.regular.import.not.a.string.import", (8, true)),
+            ("dep.from.bytes", (11, true)),
+            ("dep.from.str", (12, true)),
+            ("dep.from.str_狗", (13, true)),
             ("weak1", (17, true)),
             ("strong1", (18, false)),
             ("strong2", (19, false)),
             ("strong3", (20, false)),
         ]),
-        HashMap::from([
-            ("dep.from.bytes", 11),
-            ("dep.from.str", 12),
-            ("dep.from.str_狗", 13),
-        ]),
+        HashMap::new(),
     );
 }
 
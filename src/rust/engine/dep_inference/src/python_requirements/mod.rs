@@ -0,0 +1,504 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+//! Parsing of PEP 508 requirement strings (https://peps.python.org/pep-0508/), and of the
+//! requirements files consumed by pip (see
+//! https://pip.pypa.io/en/stable/reference/requirements-file-format/).
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PythonRequirement {
+    pub name: String,
+    pub extras: Vec<String>,
+    pub specifiers: Vec<VersionSpecifier>,
+    pub url: Option<String>,
+    pub marker: Option<MarkerExpression>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionSpecifier {
+    pub operator: String,
+    pub version: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MarkerValue {
+    Variable(String),
+    String(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MarkerExpression {
+    Compare {
+        lhs: MarkerValue,
+        operator: String,
+        rhs: MarkerValue,
+    },
+    And(Box<MarkerExpression>, Box<MarkerExpression>),
+    Or(Box<MarkerExpression>, Box<MarkerExpression>),
+}
+
+/// An error in a requirements file, which is reported against the (first) line of the logical
+/// line that failed to parse.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RequirementsFileError {
+    pub line_number: usize,
+    pub line: String,
+    pub message: String,
+}
+
+const MARKER_VARIABLES: &[&str] = &[
+    "python_version",
+    "python_full_version",
+    "os_name",
+    "sys_platform",
+    "platform_release",
+    "platform_system",
+    "platform_version",
+    "platform_machine",
+    "platform_python_implementation",
+    "implementation_name",
+    "implementation_version",
+    "extra",
+];
+
+/// Legacy (PEP 345) marker variable names, which are accepted and normalized in the same way as
+/// `packaging` does.
+const MARKER_VARIABLE_ALIASES: &[(&str, &str)] = &[
+    ("os.name", "os_name"),
+    ("sys.platform", "sys_platform"),
+    ("platform.version", "platform_version"),
+    ("platform.machine", "platform_machine"),
+    (
+        "platform.python_implementation",
+        "platform_python_implementation",
+    ),
+    ("python_implementation", "platform_python_implementation"),
+];
+
+// NB: Longer operators must come first, since they share prefixes with the shorter ones.
+const VERSION_OPERATORS: &[&str] = &["===", "==", "!=", "~=", "<=", ">=", "<", ">"];
+
+pub fn parse_requirement(requirement: &str) -> Result<PythonRequirement, String> {
+    Cursor::new(requirement).requirement()
+}
+
+/// Parses all requirements of a requirements file, along with the (1-based) line number that each
+/// starts on.
+///
+/// Comments, blank lines, and options (both whole-line options such as `-r` or `--index-url`, and
+/// per-requirement options such as `--hash`) are ignored. Lines ending in a backslash are joined
+/// with the following line. Pip-style VCS requirements with an `#egg=` fragment are converted to
+/// PEP 508 URL requirements.
+pub fn parse_requirements_file(
+    contents: &str,
+) -> Result<Vec<(usize, PythonRequirement)>, RequirementsFileError> {
+    let mut requirements = Vec::new();
+    for (line_number, line) in logical_lines(contents) {
+        let line = strip_comment(&line);
+        let line = strip_options(line).trim();
+        if line.is_empty() || line.starts_with('-') {
+            continue;
+        }
+        let requirement = parse_requirement(line)
+            .or_else(|e| parse_vcs_requirement(line).ok_or(e))
+            .map_err(|message| RequirementsFileError {
+                line_number,
+                line: line.to_string(),
+                message,
+            })?;
+        requirements.push((line_number, requirement));
+    }
+    Ok(requirements)
+}
+
+fn logical_lines(contents: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+    for (i, line) in contents.lines().enumerate() {
+        let (line_number, mut logical_line) = current.take().unwrap_or((i + 1, String::new()));
+        match line.strip_suffix('\\') {
+            Some(continued) => {
+                logical_line.push_str(continued);
+                current = Some((line_number, logical_line));
+            }
+            None => {
+                logical_line.push_str(line);
+                lines.push((line_number, logical_line));
+            }
+        }
+    }
+    lines.extend(current);
+    lines
+}
+
+/// Strips a comment, which (as in pip) must either start the line or be preceded by whitespace, so
+/// that URL fragments are preserved.
+fn strip_comment(line: &str) -> &str {
+    // NB: The start of the line is treated as whitespace.
+    let mut previous = ' ';
+    for (i, c) in line.char_indices() {
+        if c == '#' && previous.is_whitespace() {
+            return &line[..i];
+        }
+        previous = c;
+    }
+    line
+}
+
+/// Strips any per-requirement options (which start with whitespace followed by `--`), ignoring
+/// anything within quotes, which may appear in environment markers.
+fn strip_options(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = None;
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if line[i..].starts_with("--") && previous.is_some_and(char::is_whitespace) => {
+                return &line[..i];
+            }
+            None => {}
+        }
+        previous = Some(c);
+    }
+    line
+}
+
+/// Converts a pip VCS-style requirement into a PEP 508 one. E.g.,
+///   git+https://github.com/django/django.git@stable/2.1.x#egg=Django
+/// into
+///   Django@ git+https://github.com/django/django.git@stable/2.1.x#egg=Django
+fn parse_vcs_requirement(line: &str) -> Option<PythonRequirement> {
+    let (location, fragment) = line.split_once('#')?;
+    let project = fragment
+        .split('&')
+        .find_map(|param| param.strip_prefix("egg="))
+        .filter(|project| !project.is_empty())?;
+    // NB: URLs without a scheme are local paths.
+    let url = if location.contains("://") {
+        line.to_string()
+    } else if line.starts_with('/') {
+        format!("file://{line}")
+    } else {
+        format!("file:{line}")
+    };
+    parse_requirement(&format!("{project}@ {url}")).ok()
+}
+
+struct Cursor<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Like `eat`, but only for a token that is not immediately followed by an identifier char.
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.skip_whitespace();
+        let is_keyword = self.rest().strip_prefix(keyword).is_some_and(|after| {
+            !after.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        });
+        if is_keyword {
+            self.pos += keyword.len();
+        }
+        is_keyword
+    }
+
+    fn expect(&mut self, token: &str, description: &str) -> Result<(), String> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected {description}")))
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let end = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+        self.pos += end;
+        &rest[..end]
+    }
+
+    fn error(&self, message: &str) -> String {
+        let rest = self.rest();
+        if rest.is_empty() {
+            format!("{message} at end of input.")
+        } else {
+            format!("{message} at position {}: `{rest}`.", self.pos)
+        }
+    }
+
+    fn requirement(&mut self) -> Result<PythonRequirement, String> {
+        self.skip_whitespace();
+        let name = self.identifier();
+        if name.is_empty() {
+            return Err(self.error("Expected a package name"));
+        }
+
+        let mut extras = Vec::new();
+        // NB: An empty list of extras is allowed.
+        if self.eat("[") && !self.eat("]") {
+            loop {
+                self.skip_whitespace();
+                let extra = self.identifier();
+                if extra.is_empty() {
+                    return Err(self.error("Expected an extra name"));
+                }
+                extras.push(extra.to_string());
+                if !self.eat(",") {
+                    break;
+                }
+            }
+            self.expect("]", "`,` or `]` after extras")?;
+        }
+
+        let mut specifiers = Vec::new();
+        let mut url = None;
+        if self.eat("@") {
+            self.skip_whitespace();
+            // NB: A `;` is valid within a URL, so a marker must be separated from it by whitespace.
+            let location = self.take_while(|c| !c.is_whitespace());
+            if location.is_empty() {
+                return Err(self.error("Expected a URL after `@`"));
+            }
+            url = Some(location.to_string());
+        } else if self.eat("(") {
+            specifiers = self.version_specifiers()?;
+            self.expect(")", "`)` after version specifiers")?;
+        } else {
+            specifiers = self.version_specifiers()?;
+        }
+
+        let marker = if self.eat(";") {
+            Some(self.marker_or()?)
+        } else {
+            None
+        };
+
+        self.skip_whitespace();
+        if !self.rest().is_empty() {
+            return Err(self.error("Unexpected input"));
+        }
+
+        Ok(PythonRequirement {
+            name: name.to_string(),
+            extras,
+            specifiers,
+            url,
+            marker,
+        })
+    }
+
+    fn identifier(&mut self) -> &'a str {
+        let rest = self.rest();
+        if !rest.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+            return "";
+        }
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.'))
+            .unwrap_or(rest.len());
+        // NB: Names must end with an alphanumeric char.
+        let end = rest[..end]
+            .trim_end_matches(|c: char| !c.is_ascii_alphanumeric())
+            .len();
+        self.pos += end;
+        &rest[..end]
+    }
+
+    fn version_operator(&mut self) -> Option<&'static str> {
+        VERSION_OPERATORS.iter().copied().find(|op| self.eat(op))
+    }
+
+    fn version_specifiers(&mut self) -> Result<Vec<VersionSpecifier>, String> {
+        let mut specifiers = Vec::new();
+        loop {
+            let Some(operator) = self.version_operator() else {
+                if specifiers.is_empty() {
+                    return Ok(specifiers);
+                }
+                return Err(self.error("Expected a version operator after `,`"));
+            };
+            self.skip_whitespace();
+            let version = if operator == "===" {
+                // Arbitrary equality may be used with any (non-PEP 440) version string.
+                self.take_while(|c| !(c.is_whitespace() || c == ',' || c == ';' || c == ')'))
+            } else {
+                self.take_while(|c| c.is_ascii_alphanumeric() || "-_.*+!".contains(c))
+            };
+            if version.is_empty() {
+                return Err(self.error(&format!("Expected a version after `{operator}`")));
+            }
+            specifiers.push(VersionSpecifier {
+                operator: operator.to_string(),
+                version: version.to_string(),
+            });
+            if !self.eat(",") {
+                return Ok(specifiers);
+            }
+        }
+    }
+
+    fn marker_or(&mut self) -> Result<MarkerExpression, String> {
+        let mut expression = self.marker_and()?;
+        while self.eat_keyword("or") {
+            expression = MarkerExpression::Or(Box::new(expression), Box::new(self.marker_and()?));
+        }
+        Ok(expression)
+    }
+
+    fn marker_and(&mut self) -> Result<MarkerExpression, String> {
+        let mut expression = self.marker_atom()?;
+        while self.eat_keyword("and") {
+            expression = MarkerExpression::And(Box::new(expression), Box::new(self.marker_atom()?));
+        }
+        Ok(expression)
+    }
+
+    fn marker_atom(&mut self) -> Result<MarkerExpression, String> {
+        if self.eat("(") {
+            let expression = self.marker_or()?;
+            self.expect(")", "`)` in marker")?;
+            return Ok(expression);
+        }
+        let lhs = self.marker_value()?;
+        let operator = self.marker_operator()?;
+        let rhs = self.marker_value()?;
+        Ok(MarkerExpression::Compare { lhs, operator, rhs })
+    }
+
+    fn marker_operator(&mut self) -> Result<String, String> {
+        if let Some(operator) = self.version_operator() {
+            Ok(operator.to_string())
+        } else if self.eat_keyword("in") {
+            Ok("in".to_string())
+        } else if self.eat_keyword("not") {
+            if self.eat_keyword("in") {
+                Ok("not in".to_string())
+            } else {
+                Err(self.error("Expected `in` after `not`"))
+            }
+        } else {
+            Err(self.error("Expected a marker operator"))
+        }
+    }
+
+    fn marker_value(&mut self) -> Result<MarkerValue, String> {
+        self.skip_whitespace();
+        if let Some(quote) = self
+            .rest()
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')
+        {
+            self.pos += 1;
+            let value = self.take_while(|c| c != quote);
+            if !self.eat(&quote.to_string()) {
+                return Err(self.error("Unterminated string in marker"));
+            }
+            return Ok(MarkerValue::String(value.to_string()));
+        }
+        let start = self.pos;
+        let variable = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+        if variable.is_empty() {
+            return Err(self.error("Expected a marker variable or string"));
+        }
+        if MARKER_VARIABLES.contains(&variable) {
+            return Ok(MarkerValue::Variable(variable.to_string()));
+        }
+        match MARKER_VARIABLE_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == variable)
+        {
+            Some((_, name)) => Ok(MarkerValue::Variable(name.to_string())),
+            None => {
+                self.pos = start;
+                Err(self.error(&format!("Unknown marker variable `{variable}`")))
+            }
+        }
+    }
+}
+
+/// Renders the requirement in the same canonical form as `packaging.requirements.Requirement`.
+impl fmt::Display for PythonRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.extras.is_empty() {
+            write!(f, "[{}]", self.extras.join(","))?;
+        }
+        let specifiers = self
+            .specifiers
+            .iter()
+            .map(|s| format!("{}{}", s.operator, s.version))
+            .collect::<Vec<_>>();
+        write!(f, "{}", specifiers.join(","))?;
+        if let Some(url) = &self.url {
+            write!(f, "@ {url}")?;
+            if self.marker.is_some() {
+                write!(f, " ")?;
+            }
+        }
+        if let Some(marker) = &self.marker {
+            write!(f, "; {marker}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for MarkerValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkerValue::Variable(name) => write!(f, "{name}"),
+            MarkerValue::String(value) if value.contains('"') => write!(f, "'{value}'"),
+            MarkerValue::String(value) => write!(f, "\"{value}\""),
+        }
+    }
+}
+
+impl fmt::Display for MarkerExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkerExpression::Compare { lhs, operator, rhs } => write!(f, "{lhs} {operator} {rhs}"),
+            MarkerExpression::And(lhs, rhs) => {
+                // NB: `and` binds tighter than `or`, so `or` operands must be parenthesized.
+                for (i, operand) in [lhs, rhs].into_iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " and ")?;
+                    }
+                    match operand.as_ref() {
+                        MarkerExpression::Or(..) => write!(f, "({operand})")?,
+                        _ => write!(f, "{operand}")?,
+                    }
+                }
+                Ok(())
+            }
+            MarkerExpression::Or(lhs, rhs) => write!(f, "{lhs} or {rhs}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
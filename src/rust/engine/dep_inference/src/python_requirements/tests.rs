@@ -0,0 +1,181 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use crate::python_requirements::{
+    parse_requirement, parse_requirements_file, MarkerExpression, MarkerValue,
+    RequirementsFileError, VersionSpecifier,
+};
+
+fn assert_canonical(requirement: &str, expected: &str) {
+    assert_eq!(
+        expected,
+        parse_requirement(requirement).unwrap().to_string()
+    );
+}
+
+fn specifier(operator: &str, version: &str) -> VersionSpecifier {
+    VersionSpecifier {
+        operator: operator.to_string(),
+        version: version.to_string(),
+    }
+}
+
+#[test]
+fn name_extras_and_specifiers() {
+    let requirement = parse_requirement("Foo.bar[Baz, qux] >= 1.2, <2 ").unwrap();
+    assert_eq!("Foo.bar", requirement.name);
+    assert_eq!(vec!["Baz", "qux"], requirement.extras);
+    assert_eq!(
+        vec![specifier(">=", "1.2"), specifier("<", "2")],
+        requirement.specifiers
+    );
+    assert_eq!(None, requirement.url);
+    assert_eq!(None, requirement.marker);
+
+    assert_canonical("foo", "foo");
+    assert_canonical("foo (==1.0.*)", "foo==1.0.*");
+    assert_canonical("foo~=1.4.5,!=1.4.7", "foo~=1.4.5,!=1.4.7");
+    assert_canonical("foo===some-legacy-version", "foo===some-legacy-version");
+    assert_canonical("foo[]>1", "foo>1");
+}
+
+#[test]
+fn urls() {
+    let requirement =
+        parse_requirement("Django @ git+https://github.com/django/django.git@stable/2.1.x")
+            .unwrap();
+    assert_eq!("Django", requirement.name);
+    assert_eq!(
+        Some("git+https://github.com/django/django.git@stable/2.1.x".to_string()),
+        requirement.url
+    );
+
+    assert_canonical(
+        "pip @ https://example.com/pip.whl ; python_version>'3'",
+        "pip@ https://example.com/pip.whl ; python_version > \"3\"",
+    );
+    // A `;` without leading whitespace is part of the URL.
+    assert_canonical(
+        "pip @ https://example.com/a;b",
+        "pip@ https://example.com/a;b",
+    );
+}
+
+#[test]
+fn markers() {
+    let requirement =
+        parse_requirement("foo; python_version >= '3.8' and extra == \"test\"").unwrap();
+    assert_eq!(
+        Some(MarkerExpression::And(
+            Box::new(MarkerExpression::Compare {
+                lhs: MarkerValue::Variable("python_version".to_string()),
+                operator: ">=".to_string(),
+                rhs: MarkerValue::String("3.8".to_string()),
+            }),
+            Box::new(MarkerExpression::Compare {
+                lhs: MarkerValue::Variable("extra".to_string()),
+                operator: "==".to_string(),
+                rhs: MarkerValue::String("test".to_string()),
+            }),
+        )),
+        requirement.marker
+    );
+
+    assert_canonical(
+        "foo;(os_name=='nt' or sys_platform=='darwin') and python_version<'3.10'",
+        "foo; (os_name == \"nt\" or sys_platform == \"darwin\") and python_version < \"3.10\"",
+    );
+    assert_canonical(
+        "foo; 'linux' in sys_platform or platform_machine not in 'x86_64 aarch64'",
+        "foo; \"linux\" in sys_platform or platform_machine not in \"x86_64 aarch64\"",
+    );
+    assert_canonical(
+        "foo; python_implementation == 'CPython'",
+        "foo; platform_python_implementation == \"CPython\"",
+    );
+}
+
+#[test]
+fn invalid_requirements() {
+    for requirement in [
+        "",
+        "not valid! === 3.1",
+        "foo >=",
+        "foo >= 1,",
+        "foo[bar",
+        "foo @",
+        "foo; python_version",
+        "foo; unknown_variable == '1'",
+        "foo; python_version == '3",
+        "foo; os_name not 'nt'",
+        "foo; (os_name == 'nt'",
+    ] {
+        assert!(
+            parse_requirement(requirement).is_err(),
+            "Expected `{requirement}` to fail to parse."
+        );
+    }
+    assert_eq!(
+        Err(
+            "Unknown marker variable `unknown_variable` at position 5: `unknown_variable == '1'`."
+                .to_string()
+        ),
+        parse_requirement("foo; unknown_variable == '1'")
+    );
+}
+
+#[test]
+fn requirements_file() {
+    let requirements = parse_requirements_file(
+        r#"
+# A comment.
+--index-url https://example.com/simple
+-r other-requirements.txt
+-e ./some/local/project
+
+foo==1.0  # An inline comment.
+bar>=2 \
+    --hash=sha256:abcd \
+    --hash=sha256:ef01
+baz; platform_release == "5.4 --generic"
+git+https://github.com/django/django.git@stable/2.1.x#egg=Django
+"#,
+    )
+    .unwrap()
+    .into_iter()
+    .map(|(line_number, requirement)| (line_number, requirement.to_string()))
+    .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            (7, "foo==1.0".to_string()),
+            (8, "bar>=2".to_string()),
+            (11, "baz; platform_release == \"5.4 --generic\"".to_string()),
+            (
+                12,
+                "Django@ git+https://github.com/django/django.git@stable/2.1.x#egg=Django"
+                    .to_string()
+            ),
+        ],
+        requirements
+    );
+}
+
+#[test]
+fn requirements_file_local_vcs_paths() {
+    let requirements = parse_requirements_file("/path/to#egg=foo&a=b\n").unwrap();
+    assert_eq!(
+        Some("file:///path/to#egg=foo&a=b"),
+        requirements[0].1.url.as_deref()
+    );
+}
+
+#[test]
+fn requirements_file_error() {
+    assert_eq!(
+        Err(RequirementsFileError {
+            line_number: 3,
+            line: "not valid! === 3.1".to_string(),
+            message: "Unexpected input at position 4: `valid! === 3.1`.".to_string(),
+        }),
+        parse_requirements_file("foo\n\nnot valid! === 3.1\n")
+    );
+}
@@ -0,0 +1,240 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use std::path::PathBuf;
+
+use serde_derive::{Deserialize, Serialize};
+use tree_sitter::{Node, Parser};
+
+include!(concat!(env!("OUT_DIR"), "/rust_impl_hash.rs"));
+
+const INCLUDE_MACROS: [&str; 3] = ["include", "include_str", "include_bytes"];
+
+/// A `mod foo;` declaration, whose module is loaded from another file.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct RustModDeclaration {
+    /// The name of the module, qualified by any inline modules it is declared in (e.g. `foo::bar`
+    /// for `mod foo { mod bar; }`).
+    pub name: String,
+    /// The value of a `#[path = "..."]` attribute of the declaration.
+    pub path: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ParsedRustDependencies {
+    /// The paths imported by `use` declarations (with groups expanded, e.g. `use a::{b, c::*}` is
+    /// `a::b` and `a::c::*`) and the crates of `extern crate` declarations.
+    pub uses: Vec<String>,
+    pub mods: Vec<RustModDeclaration>,
+    /// The string literal paths of `include!`, `include_str!` and `include_bytes!` invocations,
+    /// which are relative to the directory of the file.
+    pub includes: Vec<String>,
+}
+
+pub fn get_dependencies(
+    contents: &str,
+    _filepath: PathBuf,
+) -> Result<ParsedRustDependencies, String> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(tree_sitter_rust::language())
+        .expect("Error loading Rust grammar");
+    let parsed = parser.parse(contents, None);
+    let tree = parsed.unwrap();
+
+    let mut collector = DependencyCollector {
+        code: contents,
+        uses: Vec::new(),
+        mods: Vec::new(),
+        includes: Vec::new(),
+    };
+    collector.collect(tree.root_node(), &[]);
+    Ok(ParsedRustDependencies {
+        uses: collector.uses,
+        mods: collector.mods,
+        includes: collector.includes,
+    })
+}
+
+struct DependencyCollector<'a> {
+    code: &'a str,
+    uses: Vec<String>,
+    mods: Vec<RustModDeclaration>,
+    includes: Vec<String>,
+}
+
+impl<'a> DependencyCollector<'a> {
+    fn code_at(&self, node: Node) -> &'a str {
+        &self.code[node.start_byte()..node.end_byte()]
+    }
+
+    /// Collects the dependencies of the children of `node`, which are in the inline module
+    /// `module` of the file.
+    fn collect(&mut self, node: Node, module: &[String]) {
+        // The `#[path]` attribute of the next item, since attributes are siblings of their item.
+        let mut path_attribute = None;
+        for child in node.named_children(&mut node.walk()) {
+            match child.kind() {
+                // NB: The grammar parses an inner attribute on the first line of a file as a
+                // `shebang`.
+                "attribute_item" | "inner_attribute_item" | "shebang" => {
+                    if let Some((name, value)) = self.attribute_value(child) {
+                        match name {
+                            "path" => path_attribute = string_literal_value(value),
+                            // E.g. `#![doc = include_str!("../README.md")]`.
+                            _ => self.insert_include(value),
+                        }
+                    }
+                    continue;
+                }
+                "line_comment" | "block_comment" => continue,
+                "use_declaration" => {
+                    if let Some(argument) = child.child_by_field_name("argument") {
+                        self.insert_use_paths(argument, "");
+                    }
+                }
+                "extern_crate_declaration" => {
+                    if let Some(name) = child.child_by_field_name("name") {
+                        self.insert_use(self.code_at(name).to_string());
+                    }
+                }
+                "mod_item" => {
+                    if let Some(name) = child.child_by_field_name("name") {
+                        let mut qualified = module.to_vec();
+                        qualified.push(self.code_at(name).to_string());
+                        match child.child_by_field_name("body") {
+                            Some(body) => self.collect(body, &qualified),
+                            None => self.mods.push(RustModDeclaration {
+                                name: qualified.join("::"),
+                                path: path_attribute.clone(),
+                            }),
+                        }
+                    }
+                }
+                "macro_invocation" => self.insert_include(self.code_at(child)),
+                _ => self.collect(child, module),
+            }
+            path_attribute = None;
+        }
+    }
+
+    /// The name and value of an `#[name = value]` (or `#![name = value]`) attribute.
+    fn attribute_value(&self, node: Node) -> Option<(&'a str, &'a str)> {
+        let attribute = self
+            .code_at(node)
+            .strip_prefix('#')?
+            .trim_start()
+            .trim_start_matches('!')
+            .trim_start()
+            .strip_prefix('[')?
+            .strip_suffix(']')?;
+        let (name, value) = attribute.split_once('=')?;
+        Some((name.trim(), value.trim()))
+    }
+
+    /// Inserts the path of an `include!` (or `include_str!` etc.) invocation with a string literal
+    /// argument, e.g. `include_str!("README.md")`.
+    fn insert_include(&mut self, invocation: &str) {
+        let Some((name, arguments)) = invocation.split_once('!') else {
+            return;
+        };
+        let name = name.trim().rsplit("::").next().unwrap_or_default();
+        if !INCLUDE_MACROS.contains(&name) {
+            return;
+        }
+        let arguments = arguments.trim();
+        let Some(argument) = ["()", "[]", "{}"].iter().find_map(|delimiters| {
+            let (open, close) = delimiters.split_at(1);
+            arguments.strip_prefix(open)?.strip_suffix(close)
+        }) else {
+            return;
+        };
+        let argument = argument.trim();
+        let argument = argument.strip_suffix(',').unwrap_or(argument).trim_end();
+        if let Some(path) = string_literal_value(argument) {
+            if !self.includes.contains(&path) {
+                self.includes.push(path);
+            }
+        }
+    }
+
+    fn insert_use_paths(&mut self, node: Node, prefix: &str) {
+        match node.kind() {
+            "use_as_clause" => {
+                if let Some(path) = node.child_by_field_name("path") {
+                    self.insert_use_paths(path, prefix);
+                }
+            }
+            "scoped_use_list" => {
+                let prefix = match node.child_by_field_name("path") {
+                    Some(path) => join(prefix, &path_text(self.code_at(path))),
+                    None => prefix.to_string(),
+                };
+                if let Some(list) = node.child_by_field_name("list") {
+                    self.insert_use_paths(list, &prefix);
+                }
+            }
+            "use_list" => {
+                for child in node.named_children(&mut node.walk()) {
+                    self.insert_use_paths(child, prefix);
+                }
+            }
+            "line_comment" | "block_comment" => {}
+            _ => self.insert_use(join(prefix, &path_text(self.code_at(node)))),
+        }
+    }
+
+    fn insert_use(&mut self, path: String) {
+        if !path.is_empty() && !self.uses.contains(&path) {
+            self.uses.push(path);
+        }
+    }
+}
+
+/// The text of a path without whitespace or a leading `::`.
+fn path_text(code: &str) -> String {
+    let path: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    path.strip_prefix("::").map(str::to_string).unwrap_or(path)
+}
+
+fn join(prefix: &str, path: &str) -> String {
+    if path == "self" && !prefix.is_empty() {
+        prefix.to_string()
+    } else if prefix.is_empty() {
+        path.to_string()
+    } else {
+        format!("{prefix}::{path}")
+    }
+}
+
+/// The value of a (possibly raw) string literal, if `code` is one.
+fn string_literal_value(code: &str) -> Option<String> {
+    if let Some(raw) = code.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let delimiter = "#".repeat(hashes);
+        return raw
+            .strip_prefix(&delimiter)?
+            .strip_prefix('"')?
+            .strip_suffix(&delimiter)?
+            .strip_suffix('"')
+            .map(str::to_string);
+    }
+    let value = code.strip_prefix('"')?.strip_suffix('"')?;
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => result.push('\n'),
+            't' => result.push('\t'),
+            c @ ('\\' | '"' | '\'') => result.push(c),
+            _ => return None,
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests;
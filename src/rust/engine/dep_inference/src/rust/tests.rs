@@ -0,0 +1,113 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use std::path::PathBuf;
+
+use crate::rust::{get_dependencies, ParsedRustDependencies, RustModDeclaration};
+
+fn parse(code: &str) -> ParsedRustDependencies {
+    get_dependencies(code, PathBuf::from("src/lib.rs")).unwrap()
+}
+
+fn module(name: &str, path: Option<&str>) -> RustModDeclaration {
+    RustModDeclaration {
+        name: name.to_string(),
+        path: path.map(str::to_string),
+    }
+}
+
+#[test]
+fn uses() {
+    let result = parse(
+        r#"
+extern crate serde;
+
+use std::collections::{HashMap, hash_map::{self, Entry}};
+use std::io::Write as _;
+use crate::util::*;
+use super::sibling;
+use ::log::{debug, /* comment */ info};
+use self::inner::Thing;
+
+fn main() {
+    use std::fmt::Debug;
+}
+"#,
+    );
+    assert_eq!(
+        vec![
+            "serde",
+            "std::collections::HashMap",
+            "std::collections::hash_map",
+            "std::collections::hash_map::Entry",
+            "std::io::Write",
+            "crate::util::*",
+            "super::sibling",
+            "log::debug",
+            "log::info",
+            "self::inner::Thing",
+            "std::fmt::Debug",
+        ],
+        result.uses
+    );
+}
+
+#[test]
+fn mods() {
+    let result = parse(
+        r#"
+mod a;
+pub(crate) mod b;
+
+#[path = "generated/c_impl.rs"]
+#[allow(dead_code)]
+mod c;
+
+#[cfg(test)]
+mod tests;
+
+mod inline {
+    mod d;
+
+    pub mod nested {
+        #[path = r"e.rs"]
+        mod e;
+    }
+}
+"#,
+    );
+    assert_eq!(
+        vec![
+            module("a", None),
+            module("b", None),
+            module("c", Some("generated/c_impl.rs")),
+            module("tests", None),
+            module("inline::d", None),
+            module("inline::nested::e", Some("e.rs")),
+        ],
+        result.mods
+    );
+    assert!(result.uses.is_empty());
+}
+
+#[test]
+fn includes() {
+    let result = parse(
+        r##"
+#![doc = include_str!("../README.md")]
+
+include!(concat!(env!("OUT_DIR"), "/generated.rs"));
+
+static DATA: &[u8] = include_bytes!("data.bin");
+
+fn schema() -> &'static str {
+    std::include_str!(r#"schemas/schema.json"#)
+}
+
+const ALSO_DATA: &[u8] = include_bytes!("data.bin",);
+"##,
+    );
+    assert_eq!(
+        vec!["../README.md", "data.bin", "schemas/schema.json"],
+        result.includes
+    );
+}
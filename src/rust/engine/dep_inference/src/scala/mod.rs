@@ -0,0 +1,549 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use serde_derive::{Deserialize, Serialize};
+use tree_sitter::{Node, Parser};
+
+include!(concat!(env!("OUT_DIR"), "/scala/constants.rs"));
+include!(concat!(env!("OUT_DIR"), "/scala_impl_hash.rs"));
+
+/// Used in Scala as a marker for absolute qualified names.
+const ROOT_PACKAGE_QUALIFIER: &str = "_root_";
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScalaImport {
+    pub name: String,
+    pub alias: Option<String>,
+    pub is_wildcard: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScalaProvidedSymbol {
+    pub name: String,
+    pub recursive: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScalaConsumedSymbol {
+    pub name: String,
+    pub is_absolute: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ParsedScalaDependencies {
+    pub provided_symbols: Vec<ScalaProvidedSymbol>,
+    pub provided_symbols_encoded: Vec<ScalaProvidedSymbol>,
+    pub imports_by_scope: BTreeMap<String, Vec<ScalaImport>>,
+    pub consumed_symbols_by_scope: BTreeMap<String, Vec<ScalaConsumedSymbol>>,
+    pub scopes: Vec<String>,
+}
+
+pub fn get_dependencies(
+    contents: &str,
+    _filepath: PathBuf,
+) -> Result<ParsedScalaDependencies, String> {
+    let mut collector = SymbolCollector::new(contents);
+    collector.collect();
+
+    let mut provided_symbols = BTreeMap::new();
+    let mut provided_symbols_encoded = BTreeMap::new();
+    for (scope, symbols) in &collector.provided_symbols_by_scope {
+        for (name, symbol) in symbols {
+            provided_symbols
+                .entry(qualify(scope, name))
+                .or_insert(symbol.recursive);
+
+            let encoded_name = encode(name);
+            if symbol.saw_object {
+                for suffix in ["$", "$.MODULE$"] {
+                    provided_symbols_encoded
+                        .entry(qualify(scope, &format!("{encoded_name}{suffix}")))
+                        .or_insert(symbol.recursive);
+                }
+            }
+            provided_symbols_encoded
+                .entry(qualify(scope, &encoded_name))
+                .or_insert(symbol.recursive);
+        }
+    }
+    let to_provided_symbols = |symbols: BTreeMap<String, bool>| {
+        symbols
+            .into_iter()
+            .map(|(name, recursive)| ScalaProvidedSymbol { name, recursive })
+            .collect()
+    };
+
+    Ok(ParsedScalaDependencies {
+        provided_symbols: to_provided_symbols(provided_symbols),
+        provided_symbols_encoded: to_provided_symbols(provided_symbols_encoded),
+        imports_by_scope: collector.imports_by_scope,
+        consumed_symbols_by_scope: collector
+            .consumed_symbols_by_scope
+            .into_iter()
+            .map(|(scope, symbols)| {
+                let symbols = symbols
+                    .into_iter()
+                    .map(|(name, is_absolute)| ScalaConsumedSymbol { name, is_absolute })
+                    .collect();
+                (scope, symbols)
+            })
+            .collect(),
+        scopes: collector.scopes.into_iter().collect(),
+    })
+}
+
+fn qualify(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{scope}.{name}")
+    }
+}
+
+///
+/// Encodes a name as the Scala compiler does for its JVM representation (as
+/// `scala.reflect.NameTransformer.encode` does).
+///
+fn encode(name: &str) -> String {
+    let mut encoded = String::with_capacity(name.len());
+    for c in name.chars() {
+        let op = match c {
+            '~' => "$tilde",
+            '=' => "$eq",
+            '<' => "$less",
+            '>' => "$greater",
+            '!' => "$bang",
+            '#' => "$hash",
+            '%' => "$percent",
+            '^' => "$up",
+            '&' => "$amp",
+            '|' => "$bar",
+            '*' => "$times",
+            '/' => "$div",
+            '+' => "$plus",
+            '-' => "$minus",
+            ':' => "$colon",
+            '\\' => "$bslash",
+            '?' => "$qmark",
+            '@' => "$at",
+            c if c.is_alphanumeric() || c == '_' || c == '$' => {
+                encoded.push(c);
+                continue;
+            }
+            c => {
+                encoded.push_str(&format!("$u{:04X}", c as u32));
+                continue;
+            }
+        };
+        encoded.push_str(op);
+    }
+    encoded
+}
+
+#[derive(Default)]
+struct ProvidedSymbol {
+    saw_object: bool,
+    recursive: bool,
+}
+
+///
+/// Collects the same symbols as the scalameta-based analysis which this replaces: the symbols
+/// provided by, imported in, and consumed in each (package or template) scope of a file.
+///
+/// NB: Nodes are matched by generated `KindID` constants. The grammar aliases some of its kinds,
+/// and `build.rs` generates a `[u16; N]` array (rather than a `u16`) for a kind name which maps to
+/// several symbols: such a constant cannot be used as a `u16` pattern, so the compiler checks that
+/// each matched kind is unaliased. A kind which becomes aliased by a grammar upgrade must instead be
+/// matched with `KindID::X.contains(&id)`.
+///
+struct SymbolCollector<'a> {
+    name_parts: Vec<String>,
+    skip_provided_names: bool,
+    provided_symbols_by_scope: BTreeMap<String, BTreeMap<String, ProvidedSymbol>>,
+    imports_by_scope: BTreeMap<String, Vec<ScalaImport>>,
+    consumed_symbols_by_scope: BTreeMap<String, BTreeMap<String, bool>>,
+    scopes: BTreeSet<String>,
+    code: &'a str,
+}
+
+impl SymbolCollector<'_> {
+    fn new(code: &'_ str) -> SymbolCollector<'_> {
+        SymbolCollector {
+            name_parts: Vec::new(),
+            skip_provided_names: false,
+            provided_symbols_by_scope: BTreeMap::new(),
+            imports_by_scope: BTreeMap::new(),
+            consumed_symbols_by_scope: BTreeMap::new(),
+            scopes: BTreeSet::new(),
+            code,
+        }
+    }
+
+    fn collect(&mut self) {
+        let mut parser = Parser::new();
+        parser
+            .set_language(tree_sitter_scala::language())
+            .expect("Error loading Scala grammar");
+        let parsed = parser.parse(self.code, None);
+        let tree = parsed.unwrap();
+        self.visit_children(tree.root_node());
+    }
+
+    fn code_at(&self, node: Node) -> &str {
+        &self.code[node.start_byte()..node.end_byte()]
+    }
+
+    fn name_at(&self, node: Node) -> String {
+        self.code_at(node).trim_matches('`').to_string()
+    }
+
+    /// The parts of a (possibly qualified) identifier.
+    fn name_parts_at(&self, node: Node) -> Vec<String> {
+        match node.kind_id() {
+            KindID::IDENTIFIER | KindID::TYPE_IDENTIFIER | KindID::OPERATOR_IDENTIFIER => {
+                vec![self.name_at(node)]
+            }
+            _ => node
+                .named_children(&mut node.walk())
+                .flat_map(|child| self.name_parts_at(child))
+                .collect(),
+        }
+    }
+
+    /// The parts of a term which selects (possibly nested) members of a name.
+    fn term_parts_at(&self, node: Node) -> Option<Vec<String>> {
+        match node.kind_id() {
+            KindID::IDENTIFIER | KindID::OPERATOR_IDENTIFIER => Some(vec![self.name_at(node)]),
+            KindID::FIELD_EXPRESSION => {
+                let field = node.child_by_field_name("field")?;
+                let mut parts = node
+                    .child_by_field_name("value")
+                    .and_then(|value| self.term_parts_at(value))
+                    .unwrap_or_default();
+                parts.push(self.name_at(field));
+                Some(parts)
+            }
+            _ => None,
+        }
+    }
+
+    fn definition_name(&self, node: Node) -> Option<String> {
+        node.child_by_field_name("name")
+            .or_else(|| {
+                node.child_by_field_name("pattern")
+                    .filter(|pattern| pattern.kind_id() == KindID::IDENTIFIER)
+            })
+            .map(|name| self.name_at(name))
+    }
+
+    fn current_scope(&self) -> String {
+        self.name_parts.join(".")
+    }
+
+    fn record_scope(&mut self, parts: &[String]) {
+        let scope = self.qualify_in_current_scope(parts);
+        self.scopes.insert(scope);
+    }
+
+    fn qualify_in_current_scope(&self, parts: &[String]) -> String {
+        match parts.split_first() {
+            Some((first, rest)) if first == ROOT_PACKAGE_QUALIFIER => rest.join("."),
+            _ => qualify(&self.current_scope(), &parts.join(".")),
+        }
+    }
+
+    fn record_provided_name(&mut self, name: &str, saw_object: bool, recursive: bool) {
+        if self.skip_provided_names {
+            return;
+        }
+        let symbol = self
+            .provided_symbols_by_scope
+            .entry(self.current_scope())
+            .or_default()
+            .entry(name.to_string())
+            .or_default();
+        symbol.saw_object |= saw_object;
+        symbol.recursive |= recursive;
+    }
+
+    fn record_import(&mut self, parts: &[String], alias: Option<String>, is_wildcard: bool) {
+        let name = match parts.split_first() {
+            Some((first, rest)) if first == ROOT_PACKAGE_QUALIFIER => rest.join("."),
+            _ => parts.join("."),
+        };
+        self.imports_by_scope
+            .entry(self.current_scope())
+            .or_default()
+            .push(ScalaImport {
+                name,
+                alias,
+                is_wildcard,
+            });
+    }
+
+    fn record_consumed_symbol(&mut self, parts: &[String]) {
+        let (name, is_absolute) = match parts.split_first() {
+            Some((first, rest)) if first == ROOT_PACKAGE_QUALIFIER => (rest.join("."), true),
+            Some(_) => (parts.join("."), false),
+            None => return,
+        };
+        if name.is_empty() {
+            return;
+        }
+        *self
+            .consumed_symbols_by_scope
+            .entry(self.current_scope())
+            .or_default()
+            .entry(name)
+            .or_default() |= is_absolute;
+    }
+
+    /// Records the symbols consumed by the given type, including those of its type arguments.
+    fn record_types(&mut self, node: Node) {
+        match node.kind_id() {
+            KindID::TYPE_IDENTIFIER => self.record_consumed_symbol(&[self.name_at(node)]),
+            KindID::STABLE_TYPE_IDENTIFIER => {
+                self.record_consumed_symbol(&self.name_parts_at(node))
+            }
+            KindID::ANNOTATION | KindID::SINGLETON_TYPE | KindID::LITERAL_TYPE => {}
+            _ => {
+                for child in node.named_children(&mut node.walk()) {
+                    self.record_types(child);
+                }
+            }
+        }
+    }
+
+    fn visit_children(&mut self, node: Node) {
+        // NB: A package clause without a body applies to all of its following siblings.
+        let depth = self.name_parts.len();
+        for child in node.named_children(&mut node.walk()) {
+            if child.kind_id() == KindID::PACKAGE_CLAUSE {
+                self.visit_package_clause(child);
+            } else {
+                self.visit(child);
+            }
+        }
+        self.name_parts.truncate(depth);
+    }
+
+    fn visit_package_clause(&mut self, node: Node) {
+        let Some(name) = node.child_by_field_name("name") else {
+            return;
+        };
+        let parts = self.name_parts_at(name);
+        self.record_scope(&parts);
+        match node.child_by_field_name("body") {
+            Some(body) => {
+                let depth = self.name_parts.len();
+                self.name_parts.extend(parts);
+                self.visit_children(body);
+                self.name_parts.truncate(depth);
+            }
+            None => self.name_parts.extend(parts),
+        }
+    }
+
+    /// Visits the children of a definition other than its name and body, in the enclosing scope.
+    fn visit_signature(&mut self, node: Node) {
+        let skipped = [
+            node.child_by_field_name("name"),
+            node.child_by_field_name("pattern"),
+            node.child_by_field_name("body"),
+        ];
+        let types = [
+            node.child_by_field_name("type"),
+            node.child_by_field_name("return_type"),
+        ];
+        for child in node.named_children(&mut node.walk()) {
+            if skipped.contains(&Some(child)) {
+                continue;
+            }
+            if types.contains(&Some(child)) {
+                self.record_types(child);
+            } else {
+                self.visit(child);
+            }
+        }
+    }
+
+    /// Visits the body of a template definition in the scope of the template.
+    fn visit_template_body(&mut self, node: Node, name: String, skip_provided_names: bool) {
+        let Some(body) = node.child_by_field_name("body") else {
+            return;
+        };
+        let original_skip_provided_names = self.skip_provided_names;
+        self.skip_provided_names |= skip_provided_names;
+        self.name_parts.push(name);
+        self.visit_children(body);
+        self.name_parts.pop();
+        self.skip_provided_names = original_skip_provided_names;
+    }
+
+    fn visit_import(&mut self, node: Node) {
+        let mut path = Vec::new();
+        let mut selected = false;
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            match child.kind_id() {
+                KindID::IDENTIFIER | KindID::STABLE_IDENTIFIER => {
+                    path.extend(self.name_parts_at(child))
+                }
+                KindID::NAMESPACE_WILDCARD | KindID::WILDCARD => {
+                    self.record_import(&path, None, true);
+                    selected = true;
+                }
+                KindID::NAMESPACE_SELECTORS => {
+                    for selector in child.named_children(&mut child.walk()) {
+                        self.visit_import_selector(&path, selector);
+                    }
+                    selected = true;
+                }
+                KindID::AS_RENAMED_IDENTIFIER | KindID::ARROW_RENAMED_IDENTIFIER => {
+                    self.visit_import_selector(&path, child);
+                    selected = true;
+                }
+                // NB: The separator is an anonymous node, so has no generated `KindID`.
+                _ if child.kind() == "," => {
+                    if !selected && !path.is_empty() {
+                        self.record_import(&path, None, false);
+                    }
+                    path.clear();
+                    selected = false;
+                }
+                _ => {}
+            }
+        }
+        if !selected && !path.is_empty() {
+            self.record_import(&path, None, false);
+        }
+    }
+
+    fn visit_import_selector(&mut self, path: &[String], selector: Node) {
+        match selector.kind_id() {
+            KindID::IDENTIFIER => {
+                let name = [path, &[self.name_at(selector)]].concat();
+                self.record_import(&name, None, false);
+            }
+            KindID::NAMESPACE_WILDCARD | KindID::WILDCARD => self.record_import(path, None, true),
+            KindID::AS_RENAMED_IDENTIFIER | KindID::ARROW_RENAMED_IDENTIFIER => {
+                let Some(name) = selector.child_by_field_name("name") else {
+                    return;
+                };
+                let name = [path, &[self.name_at(name)]].concat();
+                // If a name is aliased to `_`, it is not brought into scope. The import is still
+                // recorded though, since compilation will fail if it is not present.
+                let alias = selector
+                    .child_by_field_name("alias")
+                    .map(|alias| self.name_at(alias))
+                    .filter(|alias| alias != "_");
+                self.record_import(&name, alias, false);
+            }
+            _ => {}
+        }
+    }
+
+    fn visit(&mut self, node: Node) {
+        match node.kind_id() {
+            KindID::PACKAGE_OBJECT => {
+                let Some(name) = self.definition_name(node) else {
+                    return;
+                };
+                self.record_scope(&[name.clone()]);
+                let recursive = node.child_by_field_name("extend").is_some();
+                self.record_provided_name(&name, true, recursive);
+                // NB: The parents of a package object are visited in its own scope, since that
+                // is where their symbols might be found.
+                self.name_parts.push(name);
+                self.visit_signature(node);
+                if let Some(body) = node.child_by_field_name("body") {
+                    self.visit_children(body);
+                }
+                self.name_parts.pop();
+            }
+            KindID::CLASS_DEFINITION | KindID::TRAIT_DEFINITION | KindID::ENUM_DEFINITION => {
+                let Some(name) = self.definition_name(node) else {
+                    return;
+                };
+                self.record_provided_name(&name, false, false);
+                self.visit_signature(node);
+                self.visit_template_body(node, name, false);
+            }
+            KindID::OBJECT_DEFINITION => {
+                let Some(name) = self.definition_name(node) else {
+                    return;
+                };
+                // The symbols provided by the parents of an object cannot be determined, so an
+                // object with parents is recursive, and its own symbols are not recorded.
+                let recursive = node.child_by_field_name("extend").is_some();
+                self.record_provided_name(&name, true, recursive);
+                self.visit_signature(node);
+                self.visit_template_body(node, name, recursive);
+            }
+            KindID::SIMPLE_ENUM_CASE
+            | KindID::FULL_ENUM_CASE
+            | KindID::TYPE_DEFINITION
+            | KindID::VAL_DEFINITION
+            | KindID::VAR_DEFINITION => {
+                if let Some(name) = self.definition_name(node) {
+                    self.record_provided_name(&name, false, false);
+                }
+                self.visit_signature(node);
+            }
+            KindID::FUNCTION_DEFINITION => {
+                if let Some(name) = self.definition_name(node) {
+                    self.record_provided_name(&name, false, false);
+                }
+                self.visit_signature(node);
+                if let Some(body) = node.child_by_field_name("body") {
+                    let original_skip_provided_names = self.skip_provided_names;
+                    self.skip_provided_names = true;
+                    self.visit(body);
+                    self.skip_provided_names = original_skip_provided_names;
+                }
+            }
+            KindID::VAL_DECLARATION
+            | KindID::VAR_DECLARATION
+            | KindID::FUNCTION_DECLARATION
+            | KindID::PARAMETER
+            | KindID::CLASS_PARAMETER => self.visit_signature(node),
+            KindID::EXTENDS_CLAUSE => {
+                for child in node.named_children(&mut node.walk()) {
+                    if child.kind_id() == KindID::ARGUMENTS {
+                        self.visit(child);
+                    } else {
+                        self.record_types(child);
+                    }
+                }
+            }
+            KindID::TYPE_PARAMETERS | KindID::SELF_TYPE => self.record_types(node),
+            KindID::IMPORT_DECLARATION => self.visit_import(node),
+            KindID::TYPE_IDENTIFIER | KindID::STABLE_TYPE_IDENTIFIER | KindID::GENERIC_TYPE => {
+                self.record_types(node)
+            }
+            KindID::IDENTIFIER | KindID::OPERATOR_IDENTIFIER => {
+                self.record_consumed_symbol(&[self.name_at(node)])
+            }
+            KindID::STABLE_IDENTIFIER => {
+                self.record_consumed_symbol(&self.name_parts_at(node));
+                if let Some(qualifier) = node.named_child(0) {
+                    self.visit(qualifier);
+                }
+            }
+            KindID::FIELD_EXPRESSION => {
+                if let Some(parts) = self.term_parts_at(node) {
+                    self.record_consumed_symbol(&parts);
+                }
+                if let Some(value) = node.child_by_field_name("value") {
+                    self.visit(value);
+                }
+            }
+            _ => self.visit_children(node),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,230 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::scala::{
+    encode, get_dependencies, ParsedScalaDependencies, ScalaConsumedSymbol, ScalaImport,
+    ScalaProvidedSymbol,
+};
+
+fn parse(code: &str) -> ParsedScalaDependencies {
+    get_dependencies(code, PathBuf::from("Source.scala")).unwrap()
+}
+
+fn import(name: &str, alias: Option<&str>, is_wildcard: bool) -> ScalaImport {
+    ScalaImport {
+        name: name.to_string(),
+        alias: alias.map(str::to_string),
+        is_wildcard,
+    }
+}
+
+fn provided(name: &str, recursive: bool) -> ScalaProvidedSymbol {
+    ScalaProvidedSymbol {
+        name: name.to_string(),
+        recursive,
+    }
+}
+
+fn consumed_names(result: &ParsedScalaDependencies, scope: &str) -> Vec<String> {
+    result
+        .consumed_symbols_by_scope
+        .get(scope)
+        .map(|symbols| symbols.iter().map(|symbol| symbol.name.clone()).collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn package_scopes() {
+    let result = parse(
+        r"
+package outer
+package more.than.one.part.at.once
+package inner
+",
+    );
+    assert_eq!(
+        vec![
+            "outer",
+            "outer.more.than.one.part.at.once",
+            "outer.more.than.one.part.at.once.inner",
+        ],
+        result.scopes
+    );
+}
+
+#[test]
+fn imports() {
+    let result = parse(
+        r"
+package foo
+
+import java.io
+import scala.{io => sio}
+import nada.{io => _}
+import _root_.io.circe.syntax._
+import a.b.{C, D => E}, f.g._
+
+object OuterObject {
+    import org.pantsbuild.{io => pio}
+}
+",
+    );
+    assert_eq!(
+        BTreeMap::from([
+            (
+                "foo".to_string(),
+                vec![
+                    import("java.io", None, false),
+                    import("scala.io", Some("sio"), false),
+                    import("nada.io", None, false),
+                    import("io.circe.syntax", None, true),
+                    import("a.b.C", None, false),
+                    import("a.b.D", Some("E"), false),
+                    import("f.g", None, true),
+                ]
+            ),
+            (
+                "foo.OuterObject".to_string(),
+                vec![import("org.pantsbuild.io", Some("pio"), false)]
+            ),
+        ]),
+        result.imports_by_scope
+    );
+}
+
+#[test]
+fn provided_symbols() {
+    let result = parse(
+        r"
+package foo
+
+object Bar {
+    def a = ???
+    def b = {
+        val notProvided = 1
+    }
+}
+
+object Foo extends Bar {
+    def c = ???
+}
+
+class Baz {
+    type T = Int
+    val v = 1
+}
+",
+    );
+    assert_eq!(
+        vec![
+            provided("foo.Bar", false),
+            provided("foo.Bar.a", false),
+            provided("foo.Bar.b", false),
+            provided("foo.Baz", false),
+            provided("foo.Baz.T", false),
+            provided("foo.Baz.v", false),
+            provided("foo.Foo", true),
+        ],
+        result.provided_symbols
+    );
+    assert_eq!(
+        vec![
+            provided("foo.Bar", false),
+            provided("foo.Bar$", false),
+            provided("foo.Bar$.MODULE$", false),
+            provided("foo.Bar.a", false),
+            provided("foo.Bar.b", false),
+            provided("foo.Baz", false),
+            provided("foo.Baz.T", false),
+            provided("foo.Baz.v", false),
+            provided("foo.Foo", true),
+            provided("foo.Foo$", true),
+            provided("foo.Foo$.MODULE$", true),
+        ],
+        result.provided_symbols_encoded
+    );
+}
+
+#[test]
+fn package_object() {
+    let result = parse(
+        r#"
+package foo
+package object bar extends Trait {
+  val Hello = "World"
+}
+"#,
+    );
+    assert_eq!(
+        vec![provided("foo.bar", true), provided("foo.bar.Hello", false)],
+        result.provided_symbols
+    );
+    assert_eq!(vec!["foo", "foo.bar"], result.scopes);
+    assert_eq!(vec!["Trait"], consumed_names(&result, "foo.bar"));
+}
+
+#[test]
+fn consumed_symbols() {
+    let result = parse(
+        r#"
+package foo
+
+@objectAnnotation("hello", SomeType)
+object Object {
+  @deprecated
+  def foo(arg: String): Unit = {
+    val i = io.apply()
+    val b: B[AnotherType] = ???
+  }
+  val root: _root_.foo.Bar = Other.Value
+}
+
+class Class(param: Param) extends Base(arg) with Mixin
+"#,
+    );
+    assert_eq!(
+        vec![
+            "???",
+            "AnotherType",
+            "B",
+            "Other",
+            "Other.Value",
+            "String",
+            "Unit",
+            "deprecated",
+            "foo.Bar",
+            "io",
+            "io.apply"
+        ],
+        consumed_names(&result, "foo.Object")
+    );
+    assert_eq!(
+        Some(&ScalaConsumedSymbol {
+            name: "foo.Bar".to_string(),
+            is_absolute: true
+        }),
+        result.consumed_symbols_by_scope["foo.Object"]
+            .iter()
+            .find(|symbol| symbol.name == "foo.Bar")
+    );
+    assert_eq!(
+        vec![
+            "Base",
+            "Mixin",
+            "Param",
+            "SomeType",
+            "arg",
+            "objectAnnotation"
+        ],
+        consumed_names(&result, "foo")
+    );
+}
+
+#[test]
+fn encoded_names() {
+    assert_eq!("Foo", encode("Foo"));
+    assert_eq!("$plus$plus", encode("++"));
+    assert_eq!("unary_$bang", encode("unary_!"));
+}
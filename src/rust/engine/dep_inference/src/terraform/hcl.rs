@@ -0,0 +1,361 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+/// The value of an attribute, as far as dependency inference is concerned.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Expression {
+    /// A string literal without template interpolations or directives.
+    String(String),
+    /// An object constructor with literal keys, e.g. `{ source = "hashicorp/aws" }`.
+    Object(Vec<(String, Expression)>),
+    /// Any other expression, e.g. a reference, a function call, a template or a `for` expression.
+    Other,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Item {
+    Attribute {
+        name: String,
+        value: Expression,
+    },
+    Block {
+        kind: String,
+        labels: Vec<String>,
+        body: Vec<Item>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Identifier(String),
+    /// A quoted string, with its value if it is a literal.
+    String(Option<String>),
+    Open(char),
+    Close(char),
+    Equals,
+    Colon,
+    Comma,
+    Newline,
+    /// Any other token, e.g. an operator, a number or a heredoc.
+    Other,
+}
+
+/// Parses the attributes and (nested) blocks of a file in the native syntax of HCL (e.g. a `.tf`
+/// file).
+///
+/// Expressions are only interpreted as far as the literal strings and objects that dependency
+/// inference needs: any other expressions are skipped, however deeply nested.
+pub fn parse_body(contents: &str) -> Result<Vec<Item>, String> {
+    let tokens = tokenize(contents)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        position: 0,
+    };
+    parser.body(false)
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    fn skip_separators(&mut self, separators: &[Token]) {
+        while self.peek().is_some_and(|token| separators.contains(token)) {
+            self.position += 1;
+        }
+    }
+
+    /// Parses the items of a body, up to and including the `}` closing it if `in_block`.
+    fn body(&mut self, in_block: bool) -> Result<Vec<Item>, String> {
+        let mut items = Vec::new();
+        loop {
+            self.skip_separators(&[Token::Newline]);
+            let name = match self.next() {
+                None if in_block => return Err("Unterminated block.".to_string()),
+                None => return Ok(items),
+                Some(Token::Close('}')) if in_block => return Ok(items),
+                Some(Token::Identifier(name)) => name.clone(),
+                Some(token) => return Err(format!("Unexpected {token:?} in body.")),
+            };
+            if self.peek() == Some(&Token::Equals) {
+                self.position += 1;
+                let value = self.expression(false)?;
+                items.push(Item::Attribute { name, value });
+                continue;
+            }
+            let mut labels = Vec::new();
+            loop {
+                match self.next() {
+                    Some(Token::Identifier(label) | Token::String(Some(label))) => {
+                        labels.push(label.clone())
+                    }
+                    Some(Token::Open('{')) => break,
+                    _ => return Err(format!("Expected an attribute or a block for `{name}`.")),
+                }
+            }
+            let body = self.body(true)?;
+            items.push(Item::Block {
+                kind: name,
+                labels,
+                body,
+            });
+        }
+    }
+
+    /// Parses the expression at the current position, which ends at the next newline (or comma,
+    /// in an object) or unmatched closing bracket outside of any brackets.
+    fn expression(&mut self, in_object: bool) -> Result<Expression, String> {
+        let start = self.position;
+        let mut open = Vec::new();
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Open(c) => open.push(*c),
+                Token::Close(c) => match open.pop() {
+                    // The `}` closing the enclosing block or object.
+                    None if *c == '}' => break,
+                    Some(o) if o == matching_open(*c) => {}
+                    _ => return Err(format!("Unexpected `{c}` in expression.")),
+                },
+                Token::Newline if open.is_empty() => break,
+                Token::Comma if in_object && open.is_empty() => break,
+                _ => {}
+            }
+            self.position += 1;
+        }
+        if !open.is_empty() {
+            return Err("Unterminated expression.".to_string());
+        }
+
+        let tokens = &self.tokens[start..self.position];
+        let tokens: Vec<&Token> = tokens.iter().filter(|t| **t != Token::Newline).collect();
+        Ok(match tokens.as_slice() {
+            [Token::String(Some(value))] => Expression::String(value.clone()),
+            [Token::Open('{'), .., Token::Close('}')] => {
+                let mut parser = Parser {
+                    tokens: &self.tokens[start + 1..self.position - 1],
+                    position: 0,
+                };
+                // An opening `{` might be closed before the last token, e.g. `{} == {}`.
+                parser.object().unwrap_or(Expression::Other)
+            }
+            _ => Expression::Other,
+        })
+    }
+
+    /// Parses the items of an object constructor, which fails for e.g. a `for` expression.
+    fn object(&mut self) -> Option<Expression> {
+        let mut items = Vec::new();
+        loop {
+            self.skip_separators(&[Token::Newline, Token::Comma]);
+            let key = match self.next() {
+                None => return Some(Expression::Object(items)),
+                Some(Token::Identifier(key) | Token::String(Some(key))) => key.clone(),
+                _ => return None,
+            };
+            if !matches!(self.next(), Some(Token::Equals | Token::Colon)) {
+                return None;
+            }
+            let value = self.expression(true).ok()?;
+            if matches!(self.peek(), Some(Token::Close(_))) {
+                return None;
+            }
+            items.push((key, value));
+        }
+    }
+}
+
+fn matching_open(close: char) -> char {
+    match close {
+        ')' => '(',
+        ']' => '[',
+        _ => '{',
+    }
+}
+
+fn tokenize(contents: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = contents.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            ' ' | '\t' | '\r' => i += 1,
+            '\n' => {
+                tokens.push(Token::Newline);
+                i += 1;
+            }
+            '#' => i = line_end(&chars, i),
+            '/' if next == Some('/') => i = line_end(&chars, i),
+            '/' if next == Some('*') => {
+                i = find(&chars, i + 2, &['*', '/']).ok_or("Unterminated comment.")? + 2;
+            }
+            '"' => {
+                let (value, end) = string(&chars, i + 1)?;
+                tokens.push(Token::String(value));
+                i = end;
+            }
+            '<' if next == Some('<') => {
+                i = heredoc(&chars, i + 2)?;
+                tokens.push(Token::Other);
+            }
+            '{' | '(' | '[' => {
+                tokens.push(Token::Open(c));
+                i += 1;
+            }
+            '}' | ')' | ']' => {
+                tokens.push(Token::Close(c));
+                i += 1;
+            }
+            '=' if next == Some('=') || next == Some('>') => {
+                tokens.push(Token::Other);
+                i += 2;
+            }
+            '=' => {
+                tokens.push(Token::Equals);
+                i += 1;
+            }
+            ':' => {
+                tokens.push(Token::Colon);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && is_identifier_char(chars[i]) {
+                    i += 1;
+                }
+                tokens.push(Token::Identifier(chars[start..i].iter().collect()));
+            }
+            _ if c.is_ascii_digit() => {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                    i += 1;
+                }
+                tokens.push(Token::Other);
+            }
+            _ => {
+                tokens.push(Token::Other);
+                i += 1;
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+/// The index of the newline ending the line of `start`.
+fn line_end(chars: &[char], start: usize) -> usize {
+    find(chars, start, &['\n']).unwrap_or(chars.len())
+}
+
+fn find(chars: &[char], start: usize, pattern: &[char]) -> Option<usize> {
+    (start..chars.len()).find(|i| chars[*i..].starts_with(pattern))
+}
+
+/// Lexes the quoted string whose contents start at `start`, and returns its value (if it has no
+/// template sequences) and the index after its closing quote.
+fn string(chars: &[char], start: usize) -> Result<(Option<String>, usize), String> {
+    let mut value = Some(String::new());
+    let mut i = start;
+    loop {
+        let c = *chars.get(i).ok_or("Unterminated string.")?;
+        let next = chars.get(i + 1).copied();
+        match c {
+            '"' => return Ok((value, i + 1)),
+            '\n' => return Err("Unterminated string.".to_string()),
+            '\\' => {
+                let escaped = match next.ok_or("Unterminated string.")? {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    c => c,
+                };
+                if let Some(value) = value.as_mut() {
+                    value.push(escaped);
+                }
+                i += 2;
+            }
+            // `$${` and `%%{` are escaped template sequences.
+            '$' | '%' if next == Some(c) && chars.get(i + 2) == Some(&'{') => {
+                if let Some(value) = value.as_mut() {
+                    value.push(c);
+                    value.push('{');
+                }
+                i += 3;
+            }
+            '$' | '%' if next == Some('{') => {
+                value = None;
+                i = template_end(chars, i + 2)?;
+            }
+            _ => {
+                if let Some(value) = value.as_mut() {
+                    value.push(c);
+                }
+                i += 1;
+            }
+        }
+    }
+}
+
+/// The index after the `}` closing the template sequence whose contents start at `start`.
+fn template_end(chars: &[char], start: usize) -> Result<usize, String> {
+    let mut depth = 1;
+    let mut i = start;
+    while depth > 0 {
+        match *chars.get(i).ok_or("Unterminated template sequence.")? {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            '"' => {
+                i = string(chars, i + 1)?.1;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    Ok(i)
+}
+
+/// The index of the newline after the closing delimiter of the heredoc (e.g. `<<EOF` or
+/// `<<-EOF`) whose delimiter starts at `start`.
+fn heredoc(chars: &[char], start: usize) -> Result<usize, String> {
+    let mut i = start;
+    if chars.get(i) == Some(&'-') {
+        i += 1;
+    }
+    let delimiter_start = i;
+    while i < chars.len() && is_identifier_char(chars[i]) {
+        i += 1;
+    }
+    let delimiter: String = chars[delimiter_start..i].iter().collect();
+    if delimiter.is_empty() {
+        return Err("Expected a heredoc delimiter after `<<`.".to_string());
+    }
+    i = line_end(chars, i);
+    while i < chars.len() {
+        let end = line_end(chars, i + 1);
+        let line: String = chars[i + 1..end].iter().collect();
+        if line.trim() == delimiter {
+            return Ok(end);
+        }
+        i = end;
+    }
+    Err(format!("Unterminated heredoc `{delimiter}`."))
+}
@@ -0,0 +1,131 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use std::path::PathBuf;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::terraform::hcl::{parse_body, Expression, Item};
+
+mod hcl;
+
+include!(concat!(env!("OUT_DIR"), "/terraform_impl_hash.rs"));
+
+/// A `module "name" { source = "..." }` block.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct TerraformModuleCall {
+    pub name: String,
+    /// The source of the module, as written: a local path (starting with `./` or `../`), or the
+    /// address of a module in a registry or other remote location.
+    pub source: String,
+    pub version: Option<String>,
+}
+
+/// An entry of a `terraform { required_providers { ... } }` block.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct TerraformProviderRequirement {
+    pub name: String,
+    /// The source address of the provider, e.g. `hashicorp/aws`.
+    pub source: Option<String>,
+    pub version: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ParsedTerraformDependencies {
+    /// The module calls with a literal `source`.
+    pub modules: Vec<TerraformModuleCall>,
+    /// The type of the backend configured by a `terraform { backend "type" { ... } }` block, or
+    /// `cloud` for a `terraform { cloud { ... } }` block.
+    pub backend: Option<String>,
+    pub providers: Vec<TerraformProviderRequirement>,
+}
+
+pub fn get_dependencies(
+    contents: &str,
+    filepath: PathBuf,
+) -> Result<ParsedTerraformDependencies, String> {
+    let body =
+        parse_body(contents).map_err(|e| format!("Failed to parse {}: {e}", filepath.display()))?;
+
+    let mut result = ParsedTerraformDependencies {
+        modules: Vec::new(),
+        backend: None,
+        providers: Vec::new(),
+    };
+    for item in &body {
+        match item {
+            Item::Block { kind, labels, body } if kind == "module" && labels.len() == 1 => {
+                if let Some(source) = string_attribute(body, "source") {
+                    result.modules.push(TerraformModuleCall {
+                        name: labels[0].clone(),
+                        source,
+                        version: string_attribute(body, "version"),
+                    });
+                }
+            }
+            Item::Block { kind, body, .. } if kind == "terraform" => {
+                collect_terraform_settings(body, &mut result)
+            }
+            _ => {}
+        }
+    }
+    Ok(result)
+}
+
+fn collect_terraform_settings(body: &[Item], result: &mut ParsedTerraformDependencies) {
+    for item in body {
+        let Item::Block {
+            kind, labels, body, ..
+        } = item
+        else {
+            continue;
+        };
+        match kind.as_str() {
+            "backend" if !labels.is_empty() => result.backend = Some(labels[0].clone()),
+            "cloud" => result.backend = Some("cloud".to_string()),
+            "required_providers" => {
+                for item in body {
+                    let Item::Attribute { name, value } = item else {
+                        continue;
+                    };
+                    let (source, version) = match value {
+                        // The legacy syntax of only a version constraint, e.g. `aws = "~> 5.0"`.
+                        Expression::String(version) => (None, Some(version.clone())),
+                        Expression::Object(entries) => (
+                            string_entry(entries, "source"),
+                            string_entry(entries, "version"),
+                        ),
+                        Expression::Other => (None, None),
+                    };
+                    if !result.providers.iter().any(|p| &p.name == name) {
+                        result.providers.push(TerraformProviderRequirement {
+                            name: name.clone(),
+                            source,
+                            version,
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn string_attribute(body: &[Item], attribute: &str) -> Option<String> {
+    body.iter().find_map(|item| match item {
+        Item::Attribute {
+            name,
+            value: Expression::String(value),
+        } if name == attribute => Some(value.clone()),
+        _ => None,
+    })
+}
+
+fn string_entry(entries: &[(String, Expression)], key: &str) -> Option<String> {
+    entries.iter().find_map(|(name, value)| match value {
+        Expression::String(value) if name == key => Some(value.clone()),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,170 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use std::path::PathBuf;
+
+use crate::terraform::hcl::{parse_body, Expression, Item};
+use crate::terraform::{
+    get_dependencies, ParsedTerraformDependencies, TerraformModuleCall,
+    TerraformProviderRequirement,
+};
+
+fn parse(code: &str) -> ParsedTerraformDependencies {
+    get_dependencies(code, PathBuf::from("main.tf")).unwrap()
+}
+
+fn module(name: &str, source: &str, version: Option<&str>) -> TerraformModuleCall {
+    TerraformModuleCall {
+        name: name.to_string(),
+        source: source.to_string(),
+        version: version.map(str::to_string),
+    }
+}
+
+fn provider(
+    name: &str,
+    source: Option<&str>,
+    version: Option<&str>,
+) -> TerraformProviderRequirement {
+    TerraformProviderRequirement {
+        name: name.to_string(),
+        source: source.map(str::to_string),
+        version: version.map(str::to_string),
+    }
+}
+
+#[test]
+fn modules() {
+    let result = parse(
+        r#"
+module "local" {
+  source = "../modules/local"
+}
+
+module "registry" {
+  source  = "terraform-aws-modules/vpc/aws" # The public registry.
+  version = "~> 5.0"
+
+  azs = [for az in data.aws_availability_zones.all.names : az if az != "${var.excluded}"]
+  tags = merge(var.tags, {
+    Name = "vpc-${var.env == "prod" ? "main" : "dev-${var.suffix}"}"
+  })
+  policy = <<-EOT
+    {
+      "Statement": [{ "Effect": "Allow"
+    EOT
+}
+
+/* module "commented" {
+  source = "./commented"
+} */
+
+module "one_line" { source = "./one/line" }
+
+module "templated" {
+  source = "./${var.name}"
+}
+
+resource "aws_instance" "web" {
+  source = "./not/a/module"
+}
+"#,
+    );
+    assert_eq!(
+        vec![
+            module("local", "../modules/local", None),
+            module("registry", "terraform-aws-modules/vpc/aws", Some("~> 5.0")),
+            module("one_line", "./one/line", None),
+        ],
+        result.modules
+    );
+    assert_eq!(None, result.backend);
+    assert!(result.providers.is_empty());
+}
+
+#[test]
+fn terraform_settings() {
+    let result = parse(
+        r#"
+terraform {
+  required_version = ">= 1.5"
+
+  backend "s3" {
+    bucket = "state"
+    key    = "${path.module}/terraform.tfstate"
+  }
+
+  required_providers {
+    aws = {
+      source  = "hashicorp/aws"
+      version = ">= 5.0, < 6.0"
+      configuration_aliases = [aws.east, aws.west]
+    }
+    random = { source = "hashicorp/random", version = "3.6.0" }
+    legacy = "~> 1.0"
+    quoted = {
+      "source": "example/quoted"
+    }
+  }
+}
+"#,
+    );
+    assert!(result.modules.is_empty());
+    assert_eq!(Some("s3".to_string()), result.backend);
+    assert_eq!(
+        vec![
+            provider("aws", Some("hashicorp/aws"), Some(">= 5.0, < 6.0")),
+            provider("random", Some("hashicorp/random"), Some("3.6.0")),
+            provider("legacy", None, Some("~> 1.0")),
+            provider("quoted", Some("example/quoted"), None),
+        ],
+        result.providers
+    );
+
+    let result = parse("terraform {\n  cloud {\n    organization = \"example\"\n  }\n}\n");
+    assert_eq!(Some("cloud".to_string()), result.backend);
+}
+
+#[test]
+fn expressions() {
+    let attribute = |code: &str| match parse_body(code).unwrap().pop() {
+        Some(Item::Attribute { value, .. }) => value,
+        item => panic!("Expected an attribute, got {item:?}"),
+    };
+    assert_eq!(
+        Expression::String("a \"b\" $${c} %{d}".to_string()),
+        attribute(r#"x = "a \"b\" $$${c} %%{d}""#)
+    );
+    assert_eq!(Expression::Other, attribute(r#"x = "${a}""#));
+    assert_eq!(Expression::Other, attribute("x = {} == {}"));
+    assert_eq!(Expression::Other, attribute("x = {a = 1} == {b = 2}"));
+    assert_eq!(
+        Expression::Other,
+        attribute("x = { for k, v in y : k => v }")
+    );
+    assert_eq!(
+        Expression::Object(vec![
+            ("a".to_string(), Expression::String("b".to_string())),
+            ("c".to_string(), Expression::Other),
+        ]),
+        attribute("x = {\n  a = \"b\",\n  c = [\n    1,\n  ]\n}")
+    );
+}
+
+#[test]
+fn invalid_files() {
+    for contents in [
+        "module \"a\" {",
+        "module \"a\" {\n  source = \"./a\n}",
+        "x = [1, 2",
+        "x = (1]",
+        "/* unterminated",
+        "x = <<EOT\nunterminated\n",
+        "x = \"${a\"",
+        "= 1",
+    ] {
+        assert!(
+            get_dependencies(contents, PathBuf::from("main.tf")).is_err(),
+            "Expected `{contents}` to fail to parse."
+        );
+    }
+}
@@ -19,7 +19,7 @@ use futures::future::{self, BoxFuture};
 use futures::FutureExt;
 use grpc_util::prost::MessageExt;
 use grpc_util::tls::CertificateCheck;
-use hashing::{Digest, Fingerprint};
+use hashing::{Digest, DigestFunction, Fingerprint};
 use parking_lot::Mutex;
 use protos::require_digest;
 use serde_derive::Serialize;
@@ -208,11 +208,30 @@ Destination must not exist before this command is run.",
                     .required(true),
               )
         )
+        .subcommand(
+          Command::new("migrate")
+              .about("Copy the files and directories of the local store of another digest function into the local store of the current one (as selected by the PANTS_DIGEST_FUNCTION environment variable).")
+              .arg(
+                Arg::new("from-digest-function")
+                    .takes_value(true)
+                    .long("from-digest-function")
+                    .possible_values(["sha256", "blake3"])
+                    .required(true),
+              )
+        )
       .arg(
         Arg::new("local-store-path")
           .takes_value(true)
           .long("local-store-path")
           .required(false),
+      )
+      .arg(
+        Arg::new("shared-store-path")
+          .help("Path to an existing store to read from (but never write to) when content is missing from the local store. May be repeated, in which case the stores are consulted in order.")
+          .takes_value(true)
+          .multiple_occurrences(true)
+          .long("shared-store-path")
+          .required(false),
       )
         .arg(
           Arg::new("server-address")
@@ -310,14 +329,30 @@ Destination must not exist before this command is run.",
 // TODO: Sure, it's a bit long...
 #[allow(clippy::cognitive_complexity)]
 async fn execute(top_match: &clap::ArgMatches) -> Result<(), ExitError> {
-    let store_dir = top_match
+    let store_root = top_match
         .value_of("local-store-path")
         .map(PathBuf::from)
         .unwrap_or_else(Store::default_path);
+    DigestFunction::from_env()?;
+    let store_dir = store::local_store_dir(&store_root, DigestFunction::current());
     let runtime = task_executor::Executor::new();
     let (store, store_has_remote) = {
-        let local_only = Store::local_only(runtime.clone(), &store_dir)
+        let mut local_only = Store::local_only(runtime.clone(), &store_dir)
             .map_err(|e| format!("Failed to open/create store for directory {store_dir:?}: {e}"))?;
+        for shared_store_dir in top_match
+            .values_of("shared-store-path")
+            .into_iter()
+            .flatten()
+        {
+            local_only = local_only
+                .into_with_shared(
+                    runtime.clone(),
+                    store::local_store_dir(Path::new(shared_store_dir), DigestFunction::current()),
+                )
+                .map_err(|e| {
+                    format!("Failed to open shared store for directory {shared_store_dir}: {e}")
+                })?;
+        }
         let (store_result, store_has_remote) = match top_match.value_of("server-address") {
             Some(cas_address) => {
                 let chunk_size_bytes = top_match
@@ -703,6 +738,30 @@ async fn execute(top_match: &clap::ArgMatches) -> Result<(), ExitError> {
                 .await?;
             Ok(())
         }
+        ("migrate", args) => {
+            let from = args
+                .value_of_t::<DigestFunction>("from-digest-function")
+                .expect("--from-digest-function must be passed as a digest function");
+            if from == DigestFunction::current() {
+                return Err(format!(
+                    "The store is already using the `{from}` digest function: set the \
+                    PANTS_DIGEST_FUNCTION environment variable to the function to migrate to."
+                )
+                .into());
+            }
+            let source_dir = store::local_store_dir(&store_root, from);
+            if !source_dir.exists() {
+                return Err(format!("There is no `{from}` store at {source_dir:?}.").into());
+            }
+            let source = Store::local_only(runtime.clone(), &source_dir)
+                .map_err(|e| format!("Failed to open store for directory {source_dir:?}: {e}"))?;
+            let (files, directories) = store.migrate_from(&source).await?;
+            println!(
+                "Migrated {files} files and {directories} directories from {source_dir:?} to \
+                {store_dir:?}."
+            );
+            Ok(())
+        }
 
         (_, _) => unimplemented!(),
     }
@@ -27,7 +27,7 @@ use crate::{LinkDepth, PathStat, RelativePath, MAX_LINK_DEPTH};
 lazy_static! {
     pub static ref EMPTY_DIGEST_TREE: DigestTrie = DigestTrie(vec![].into());
     pub static ref EMPTY_DIRECTORY_DIGEST: DirectoryDigest = DirectoryDigest {
-        digest: EMPTY_DIGEST,
+        digest: *EMPTY_DIGEST,
         tree: Some(EMPTY_DIGEST_TREE.clone()),
     };
 }
@@ -198,7 +198,7 @@ impl Entry {
         match self {
             Entry::Directory(d) => d.digest,
             Entry::File(f) => f.digest,
-            Entry::Symlink(_) => EMPTY_DIGEST,
+            Entry::Symlink(_) => *EMPTY_DIGEST,
         }
     }
 }
@@ -542,7 +542,7 @@ impl DigestTrie {
 
     pub fn compute_root_digest(&self) -> Digest {
         if self.0.is_empty() {
-            return EMPTY_DIGEST;
+            return *EMPTY_DIGEST;
         }
 
         Digest::of_bytes(&self.as_remexec_directory().to_bytes())
@@ -902,6 +902,33 @@ impl DigestTrie {
         self.entry_helper(self, path, 0)
     }
 
+    /// Return the Symlink at the given relative path in the trie, or None if the path is not a
+    /// symlink. Unlike `entry`, the final component of the path is not followed (although symlinks
+    /// in its parent components are), so dead links are returned too.
+    pub fn symlink<'a>(&'a self, path: &Path) -> Result<Option<&'a Symlink>, String> {
+        let (parent, name) = match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => (parent, name),
+            _ => return Ok(None),
+        };
+        let tree = if parent.as_os_str().is_empty() {
+            self
+        } else {
+            match self.entry(parent)? {
+                Some(Entry::Directory(d)) => &d.tree,
+                _ => return Ok(None),
+            }
+        };
+        let symlink = tree
+            .entries()
+            .binary_search_by_key(&name, |entry| Path::new(entry.name().as_ref()).as_os_str())
+            .ok()
+            .and_then(|idx| match &tree.entries()[idx] {
+                Entry::Symlink(s) => Some(s),
+                _ => None,
+            });
+        Ok(symlink)
+    }
+
     fn entry_helper<'a>(
         &'a self,
         root: &'a DigestTrie,
@@ -13,7 +13,7 @@ fn make_tree(path_stats: Vec<TypedPath>) -> DigestTrie {
     file_digests.extend(
         path_stats
             .iter()
-            .map(|path| (path.to_path_buf(), EMPTY_DIGEST)),
+            .map(|path| (path.to_path_buf(), *EMPTY_DIGEST)),
     );
 
     DigestTrie::from_unique_paths(path_stats, &file_digests).unwrap()
@@ -265,6 +265,42 @@ fn entry_gnarly_symlinks() {
     assert_entry_is_some(&tree, "dir/self_but_oh_so_obtusely/file.txt");
 }
 
+#[test]
+fn symlink() {
+    let tree = make_tree(vec![
+        TypedPath::Link {
+            path: Path::new("dir/link"),
+            target: Path::new("file.txt"),
+        },
+        TypedPath::Link {
+            path: Path::new("dir/dead"),
+            target: Path::new("nonexistant"),
+        },
+        TypedPath::Link {
+            path: Path::new("self"),
+            target: Path::new("dir"),
+        },
+        TypedPath::File {
+            path: Path::new("dir/file.txt"),
+            is_executable: false,
+        },
+    ]);
+
+    let assert_symlink_target = |path: &str, target: &str| {
+        let symlink = tree.symlink(Path::new(path)).unwrap().unwrap();
+        assert_eq!(symlink.target(), Path::new(target));
+    };
+    assert_symlink_target("dir/link", "file.txt");
+    assert_symlink_target("dir/dead", "nonexistant");
+    assert_symlink_target("self", "dir");
+    // Symlinks in parent components are followed.
+    assert_symlink_target("self/link", "file.txt");
+
+    assert!(tree.symlink(Path::new("dir/file.txt")).unwrap().is_none());
+    assert!(tree.symlink(Path::new("dir")).unwrap().is_none());
+    assert!(tree.symlink(Path::new("missing/link")).unwrap().is_none());
+}
+
 fn assert_walk(tree: &DigestTrie, expected_filenames: Vec<String>, expected_dirnames: Vec<String>) {
     let mut filenames = Vec::new();
     let mut dirnames = Vec::new();
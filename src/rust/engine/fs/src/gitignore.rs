@@ -71,26 +71,27 @@ impl GitignoreStyleExcludes {
     ///
     /// Will only add the files if they exist.
     pub fn gitignore_file_paths(build_root: &Path) -> Vec<PathBuf> {
+        Self::gitignore_file_candidates(build_root)
+            .into_iter()
+            .filter(|path| path.is_file())
+            .collect()
+    }
+
+    /// Return the absolute file paths which `gitignore_file_paths` returns if they exist, in order
+    /// to watch them for changes (including their creation).
+    pub fn gitignore_file_candidates(build_root: &Path) -> Vec<PathBuf> {
         let mut result = vec![];
 
-        if let Some(global_ignore_path) =
-            ignore::gitignore::gitconfig_excludes_path().filter(|fp| fp.is_file())
-        {
+        if let Some(global_ignore_path) = ignore::gitignore::gitconfig_excludes_path() {
             result.push(global_ignore_path);
         }
 
-        let gitignore_path = build_root.join(".gitignore");
-        if Path::is_file(&gitignore_path) {
-            result.push(gitignore_path);
-        }
+        result.push(build_root.join(".gitignore"));
 
         // Unlike Git, we hardcode `.git` and don't look for `$GIT_DIR`. See
         // https://github.com/BurntSushi/ripgrep/blob/041544853c86dde91c49983e5ddd0aa799bd2831/crates/ignore/src/dir.rs#L786-L794
         // for why.
-        let exclude_path = build_root.join(".git/info/exclude");
-        if Path::is_file(&exclude_path) {
-            result.push(exclude_path)
-        }
+        result.push(build_root.join(".git/info/exclude"));
         result
     }
 
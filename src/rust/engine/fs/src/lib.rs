@@ -25,7 +25,7 @@ pub use crate::glob_matching::{
 use std::cmp::min;
 use std::io;
 use std::ops::Deref;
-use std::os::unix::fs::PermissionsExt;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
 use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
 use std::{fmt, fs};
@@ -33,6 +33,7 @@ use std::{fmt, fs};
 use async_trait::async_trait;
 use bytes::Bytes;
 use deepsize::DeepSizeOf;
+use parking_lot::RwLock;
 use serde::Serialize;
 
 const TARGET_NOFILE_LIMIT: u64 = 10000;
@@ -239,6 +240,117 @@ impl PathStat {
 #[derive(Debug, DeepSizeOf, Eq, PartialEq)]
 pub struct DirectoryListing(pub Vec<Stat>);
 
+#[derive(Clone, Copy, Debug, DeepSizeOf, Eq, Hash, PartialEq)]
+pub enum PathMetadataKind {
+    File,
+    Directory,
+    Symlink,
+}
+
+impl PathMetadataKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PathMetadataKind::File => "file",
+            PathMetadataKind::Directory => "directory",
+            PathMetadataKind::Symlink => "symlink",
+        }
+    }
+}
+
+///
+/// Extended metadata for a single path, which is not followed if it is a symlink.
+///
+/// Paths which are not backed by a real filesystem (such as those in a DigestTrie) do not have
+/// a mode, owner or modification time.
+///
+#[derive(Clone, Debug, DeepSizeOf, Eq, Hash, PartialEq)]
+pub struct PathMetadata {
+    pub path: PathBuf,
+    pub kind: PathMetadataKind,
+    pub length: u64,
+    pub is_executable: bool,
+    pub unix_mode: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    /// Nanoseconds since the unix epoch.
+    pub mtime_ns: Option<i64>,
+    pub symlink_target: Option<PathBuf>,
+}
+
+impl PathMetadata {
+    ///
+    /// Returns the metadata for the given absolute path (which will be reported as `path`), or None
+    /// if it does not exist.
+    ///
+    pub fn for_path_sync(
+        abs_path: &Path,
+        path: PathBuf,
+    ) -> Result<Option<PathMetadata>, io::Error> {
+        let metadata = match fs::symlink_metadata(abs_path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let file_type = metadata.file_type();
+        let (kind, symlink_target) = if file_type.is_symlink() {
+            (PathMetadataKind::Symlink, Some(fs::read_link(abs_path)?))
+        } else if file_type.is_dir() {
+            (PathMetadataKind::Directory, None)
+        } else {
+            (PathMetadataKind::File, None)
+        };
+        let mtime_ns = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+            .and_then(|since_epoch| i64::try_from(since_epoch.as_nanos()).ok());
+        let mode = metadata.permissions().mode();
+        Ok(Some(PathMetadata {
+            path,
+            kind,
+            length: metadata.len(),
+            is_executable: kind == PathMetadataKind::File && mode & 0o111 != 0,
+            unix_mode: Some(mode),
+            uid: Some(metadata.uid()),
+            gid: Some(metadata.gid()),
+            mtime_ns,
+            symlink_target,
+        }))
+    }
+
+    ///
+    /// Returns the metadata for the given entry of a DigestTrie.
+    ///
+    pub fn for_entry(path: PathBuf, entry: &Entry) -> PathMetadata {
+        let (kind, length, is_executable, symlink_target) = match entry {
+            Entry::File(f) => (
+                PathMetadataKind::File,
+                f.digest().size_bytes as u64,
+                f.is_executable(),
+                None,
+            ),
+            Entry::Directory(_) => (PathMetadataKind::Directory, 0, false, None),
+            Entry::Symlink(s) => (
+                PathMetadataKind::Symlink,
+                0,
+                false,
+                Some(s.target().to_owned()),
+            ),
+        };
+        PathMetadata {
+            path,
+            kind,
+            length,
+            is_executable,
+            unix_mode: None,
+            uid: None,
+            gid: None,
+            mtime_ns: None,
+            symlink_target,
+        }
+    }
+}
+
 #[derive(Debug, DeepSizeOf, Clone, Eq, Hash, PartialEq)]
 pub enum StrictGlobMatching {
     // NB: the Error and Warn variants store a description of the origin of the PathGlob
@@ -338,7 +450,8 @@ impl fmt::Display for PathGlobs {
 #[derive(Clone)]
 pub struct PosixFS {
     root: Dir,
-    ignore: Arc<GitignoreStyleExcludes>,
+    // NB: Shared between clones, so that replacing the ignorer replaces it for all of them.
+    ignore: Arc<RwLock<Arc<GitignoreStyleExcludes>>>,
     executor: task_executor::Executor,
     symlink_behavior: SymlinkBehavior,
 }
@@ -377,7 +490,7 @@ impl PosixFS {
 
         Ok(PosixFS {
             root: canonical_root,
-            ignore: ignorer,
+            ignore: Arc::new(RwLock::new(ignorer)),
             executor: executor,
             symlink_behavior: symlink_behavior,
         })
@@ -398,8 +511,17 @@ impl PosixFS {
             .await
     }
 
+    ///
+    /// Replaces the ignorer of this PosixFS (and of its clones), e.g. because the ignore files which
+    /// it was created from have changed.
+    ///
+    pub fn set_ignorer(&self, ignorer: Arc<GitignoreStyleExcludes>) {
+        *self.ignore.write() = ignorer;
+    }
+
     fn scandir_sync(&self, dir_relative_to_root: &Dir) -> Result<DirectoryListing, io::Error> {
         let dir_abs = self.root.0.join(&dir_relative_to_root.0);
+        let ignore = self.ignore.read().clone();
         let mut stats: Vec<Stat> = dir_abs
             .read_dir()?
             .map(|readdir| {
@@ -424,7 +546,7 @@ impl PosixFS {
             })
             .filter_map(|s| match s {
                 Ok(Some(s))
-                    if !self.ignore.is_ignored_path(
+                    if !ignore.is_ignored_path(
                         &dir_relative_to_root.0.join(s.path()),
                         matches!(s, Stat::Dir(_)),
                     ) =>
@@ -448,7 +570,7 @@ impl PosixFS {
     }
 
     pub fn is_ignored(&self, stat: &Stat) -> bool {
-        self.ignore.is_ignored(stat)
+        self.ignore.read().is_ignored(stat)
     }
 
     pub fn file_path(&self, file: &File) -> PathBuf {
@@ -480,6 +602,25 @@ impl PosixFS {
             .map_err(|e| io::Error::new(e.kind(), format!("Failed to read link {link_abs:?}: {e}")))
     }
 
+    ///
+    /// Returns the PathMetadata for the given path relative to the root, or None if it does not
+    /// exist. Symlinks are never followed.
+    ///
+    pub async fn path_metadata(&self, path: PathBuf) -> Result<Option<PathMetadata>, io::Error> {
+        let abs_path = self.root.0.join(&path);
+        self.executor
+            .spawn_blocking(
+                move || PathMetadata::for_path_sync(&abs_path, path),
+                |e| {
+                    Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("Path metadata task failed: {e}"),
+                    ))
+                },
+            )
+            .await
+    }
+
     ///
     /// Makes a Stat for path_to_stat relative to its containing directory.
     ///
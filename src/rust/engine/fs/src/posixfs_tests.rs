@@ -8,8 +8,8 @@ use hashing::EMPTY_DIGEST;
 use crate::testutil::make_file;
 use crate::{
     DigestTrie, Dir, DirectoryListing, File, GitignoreStyleExcludes, GlobExpansionConjunction,
-    GlobMatching, Link, PathGlobs, PathStat, PosixFS, Stat, StrictGlobMatching, SymlinkBehavior,
-    TypedPath,
+    GlobMatching, Link, PathGlobs, PathMetadataKind, PathStat, PosixFS, Stat, StrictGlobMatching,
+    SymlinkBehavior, TypedPath,
 };
 
 #[tokio::test]
@@ -133,6 +133,51 @@ async fn stat_missing() {
     assert_eq!(posix_fs.stat_sync(Path::new("no_marmosets")).unwrap(), None,);
 }
 
+#[tokio::test]
+async fn path_metadata() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let posix_fs = new_posixfs(dir.path());
+    make_file(&dir.path().join("marmosets"), b"content", 0o755);
+    std::fs::create_dir(dir.path().join("enclosure")).unwrap();
+    std::os::unix::fs::symlink("marmosets", dir.path().join("remarkably_similar_marmoset"))
+        .unwrap();
+
+    let file = posix_fs
+        .path_metadata(PathBuf::from("marmosets"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(PathMetadataKind::File, file.kind);
+    assert_eq!(7, file.length);
+    assert!(file.is_executable);
+    assert_eq!(Some(0o755), file.unix_mode.map(|mode| mode & 0o777));
+    assert!(file.mtime_ns.is_some());
+
+    let directory = posix_fs
+        .path_metadata(PathBuf::from("enclosure"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(PathMetadataKind::Directory, directory.kind);
+    assert!(!directory.is_executable);
+
+    let link = posix_fs
+        .path_metadata(PathBuf::from("remarkably_similar_marmoset"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(PathMetadataKind::Symlink, link.kind);
+    assert_eq!(Some(PathBuf::from("marmosets")), link.symlink_target);
+
+    assert_eq!(
+        None,
+        posix_fs
+            .path_metadata(PathBuf::from("no_marmosets"))
+            .await
+            .unwrap()
+    );
+}
+
 #[tokio::test]
 async fn scandir_empty() {
     let dir = tempfile::TempDir::new().unwrap();
@@ -329,7 +374,7 @@ async fn memfs_expand_basic() {
                 is_executable: false,
             },
         ],
-        &vec![(p1.clone(), EMPTY_DIGEST), (p3.clone(), EMPTY_DIGEST)]
+        &vec![(p1.clone(), *EMPTY_DIGEST), (p3.clone(), *EMPTY_DIGEST)]
             .into_iter()
             .collect(),
     )
@@ -22,7 +22,7 @@ use tempfile::TempDir;
 use store::{OneOffStoreFileByDigest, Snapshot, SnapshotOps, Store, SubsetParams};
 
 fn executor() -> Executor {
-    Executor::new_owned(num_cpus::get(), num_cpus::get() * 4, || ()).unwrap()
+    Executor::new_owned(num_cpus::get(), num_cpus::get() * 4, None, || ()).unwrap()
 }
 
 pub fn criterion_benchmark_materialize(c: &mut Criterion) {
@@ -11,7 +11,7 @@ use hashing::Digest;
 use parking_lot::Mutex;
 use tempfile::TempDir;
 
-use crate::{Store, StoreError};
+use crate::{SnapshotOps, Store, StoreError};
 
 /// A symlink from a relative src to an absolute dst (outside of the workdir).
 #[derive(Debug)]
@@ -148,4 +148,19 @@ impl ImmutableInputs {
             })
             .collect())
     }
+
+    ///
+    /// Returns the paths of directories which contain the given immutable inputs at their relative
+    /// paths, for use as the (shared, read-only) lower layers of overlay filesystems.
+    ///
+    pub async fn overlay_layers(
+        &self,
+        immutable_inputs: &BTreeMap<RelativePath, DirectoryDigest>,
+    ) -> Result<Vec<PathBuf>, StoreError> {
+        futures::future::try_join_all(immutable_inputs.iter().map(|(path, digest)| async move {
+            let layer = self.0.store.add_prefix(digest.clone(), path).await?;
+            self.path_for_dir(layer).await
+        }))
+        .await
+    }
 }
@@ -35,7 +35,7 @@ use fs::{
 };
 use futures::future::{self, BoxFuture, Either, FutureExt, TryFutureExt};
 use grpc_util::prost::MessageExt;
-use hashing::{Digest, Fingerprint};
+use hashing::{Digest, DigestFunction, Fingerprint, EMPTY_DIGEST};
 use local::ByteStore;
 use parking_lot::Mutex;
 use prost::Message;
@@ -43,6 +43,7 @@ use protos::gen::build::bazel::remote::execution::v2 as remexec;
 use protos::require_digest;
 use remexec::Tree;
 use serde_derive::Serialize;
+pub use sharded_lmdb::StoreBackend;
 use sharded_lmdb::DEFAULT_LEASE_TIME;
 #[cfg(target_os = "macos")]
 use tokio::fs::copy;
@@ -71,8 +72,12 @@ pub use remote_provider::{RemoteProvider, RemoteStoreOptions};
 pub struct LocalOptions {
     pub files_max_size_bytes: usize,
     pub directories_max_size_bytes: usize,
+    /// The factor by which the stores may automatically grow beyond their max sizes when full.
+    pub max_size_growth_factor: usize,
     pub lease_time: Duration,
     pub shard_count: u8,
+    /// The database that the files and directories which are not stored on disk are stored in.
+    pub backend: StoreBackend,
 }
 
 ///
@@ -84,12 +89,27 @@ impl Default for LocalOptions {
         Self {
             files_max_size_bytes: 16 * 4 * GIGABYTES,
             directories_max_size_bytes: 2 * 4 * GIGABYTES,
+            max_size_growth_factor: 4,
             lease_time: DEFAULT_LEASE_TIME,
             shard_count: 16,
+            backend: StoreBackend::default(),
         }
     }
 }
 
+///
+/// The directory of the local store (and caches) for the given digest function below the given
+/// root: each function has its own store, because the digests of different functions cannot be
+/// compared. SHA-256 stores are at the root itself, where they were before other functions were
+/// supported.
+///
+pub fn local_store_dir(root: &Path, digest_function: DigestFunction) -> PathBuf {
+    match digest_function {
+        DigestFunction::Sha256 => root.to_owned(),
+        digest_function => root.join(digest_function.name()),
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum StoreError {
     /// A Digest was not present in either of the local or remote Stores.
@@ -179,6 +199,23 @@ impl RemoteStore {
         }
     }
 
+    ///
+    /// Removes the cells of uploads and downloads which are no longer in flight, and returns the
+    /// number which were removed.
+    ///
+    fn release_memory(&self) -> usize {
+        [&self.in_flight_uploads, &self.in_flight_downloads]
+            .into_iter()
+            .map(|cells| {
+                let mut cells = cells.lock();
+                let before = cells.len();
+                cells.retain(|_, weak_cell| weak_cell.strong_count() > 0);
+                cells.shrink_to_fit();
+                before - cells.len()
+            })
+            .sum()
+    }
+
     ///
     /// Guards an attempt to upload the given `Digest`, skipping the upload if another attempt has
     /// been successful. Will not return until either an attempt has succeed, or this attempt has
@@ -281,9 +318,15 @@ impl RemoteStore {
 /// It can also write back to a remote gRPC server, but will only do so when explicitly instructed
 /// to do so.
 ///
+/// Before it fetches from a remote server, it can also backfill its on-disk storage from
+/// "shared" on-disk stores, which it opens read-only (e.g. a store which was warmed on the machine
+/// for use by all of its checkouts, or the stores of sibling checkouts). It never writes to the
+/// shared stores.
+///
 #[derive(Debug, Clone)]
 pub struct Store {
     local: local::ByteStore,
+    shared: Vec<local::ByteStore>,
     remote: Option<RemoteStore>,
     immutable_inputs_base: Option<PathBuf>,
 }
@@ -306,6 +349,59 @@ pub enum ShrinkBehavior {
     Compact,
 }
 
+/// The prefix of the staging directories of `Store::materialize_directory_atomically`, which is
+/// followed by the pid of the process which created them.
+const STAGING_PREFIX: &str = ".pants-materialize-";
+
+///
+/// Removes the staging directories in the given destination which were created by processes that
+/// are no longer running (because they were killed while materializing). The staging directories
+/// of live processes, which may be materializing into the same destination, are left alone.
+///
+async fn remove_stale_staging_directories(destination: &Path) -> Result<(), String> {
+    let mut entries = tokio::fs::read_dir(destination)
+        .await
+        .map_err(|e| format!("Failed to list {}: {e}", destination.display()))?;
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| format!("Failed to list {}: {e}", destination.display()))?
+    {
+        let name = entry.file_name();
+        let Some(pid) = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(STAGING_PREFIX))
+            .and_then(|suffix| suffix.split_once('-'))
+            .and_then(|(pid, _)| pid.parse::<libc::pid_t>().ok())
+        else {
+            continue;
+        };
+        if process_is_alive(pid) {
+            continue;
+        }
+        let path = entry.path();
+        log::debug!("Removing stale staging directory {}", path.display());
+        match tokio::fs::remove_dir_all(&path).await {
+            // Another process may have removed it concurrently.
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(format!(
+                    "Failed to remove stale staging directory {}: {e}",
+                    path.display()
+                ));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn process_is_alive(pid: libc::pid_t) -> bool {
+    // NB: Signal 0 only checks whether the process exists: `EPERM` means that it does, but that it
+    // belongs to another user.
+    unsafe { libc::kill(pid, 0) == 0 }
+    || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
 // Note that Store doesn't implement ByteStore because it operates at a higher level of abstraction,
 // considering Directories as a standalone concept, rather than a buffer of bytes.
 // This has the nice property that Directories can be trusted to be valid and canonical.
@@ -320,6 +416,7 @@ impl Store {
     ) -> Result<Store, String> {
         Ok(Store {
             local: local::ByteStore::new(executor, path)?,
+            shared: Vec::new(),
             remote: None,
             immutable_inputs_base: None,
         })
@@ -333,6 +430,7 @@ impl Store {
     ) -> Result<Store, String> {
         Ok(Store {
             local: local::ByteStore::new_with_options(executor, path, options)?,
+            shared: Vec::new(),
             remote: None,
             immutable_inputs_base: Some(immutable_inputs_base.to_path_buf()),
         })
@@ -347,6 +445,7 @@ impl Store {
     pub fn into_local_only(self) -> Store {
         Store {
             local: self.local,
+            shared: self.shared,
             remote: None,
             immutable_inputs_base: self.immutable_inputs_base,
         }
@@ -362,6 +461,7 @@ impl Store {
     ) -> Result<Store, String> {
         Ok(Store {
             local: self.local,
+            shared: self.shared,
             remote: Some(RemoteStore::new(
                 remote::ByteStore::from_options(remote_options).await?,
             )),
@@ -369,6 +469,24 @@ impl Store {
         })
     }
 
+    ///
+    /// Attach an existing on-disk store to a Store, in read-only mode. If it is missing a value
+    /// which it tries to load, it will attempt to back-fill its local storage from the shared
+    /// store before trying its remote storage (if any).
+    ///
+    /// Multiple shared stores may be attached, in which case they are consulted in the order in
+    /// which they were attached.
+    ///
+    pub fn into_with_shared<P: AsRef<Path>>(
+        mut self,
+        executor: task_executor::Executor,
+        path: P,
+    ) -> Result<Store, String> {
+        self.shared
+            .push(local::ByteStore::new_read_only(executor, path)?);
+        Ok(self)
+    }
+
     // This default suffix is also hard-coded into the Python options code in global_options.py
     pub fn default_path() -> PathBuf {
         default_cache_path().join("lmdb_store")
@@ -710,12 +828,14 @@ impl Store {
             return Ok(bytes_res?);
         }
 
-        let remote = self.remote.clone().ok_or_else(|| {
-            StoreError::MissingDigest("Was not present in the local store".to_owned(), digest)
-        })?;
-        remote
-            .download_digest_to_local(self.local.clone(), digest, entry_type, f_remote)
-            .await?;
+        if !self.copy_from_shared(entry_type, digest).await? {
+            let remote = self.remote.clone().ok_or_else(|| {
+                StoreError::MissingDigest("Was not present in the local store".to_owned(), digest)
+            })?;
+            remote
+                .download_digest_to_local(self.local.clone(), digest, entry_type, f_remote)
+                .await?;
+        }
 
         Ok(
       self
@@ -728,6 +848,85 @@ impl Store {
     )
     }
 
+    ///
+    /// Copies the given digest from the first shared store which contains it (if any are attached)
+    /// to the local store, returning false if none of them do.
+    ///
+    async fn copy_from_shared(
+        &self,
+        entry_type: EntryType,
+        digest: Digest,
+    ) -> Result<bool, StoreError> {
+        for shared in &self.shared {
+            if self
+                .copy_from_one_shared(shared, entry_type, digest)
+                .await?
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    async fn copy_from_one_shared(
+        &self,
+        shared: &local::ByteStore,
+        entry_type: EntryType,
+        digest: Digest,
+    ) -> Result<bool, StoreError> {
+        if ByteStore::should_use_fsdb(entry_type, digest.size_bytes) {
+            // Large files are copied without buffering them into memory, and are re-hashed in
+            // the process.
+            let Some(path) = shared.load_from_fs(digest).await? else {
+                return Ok(false);
+            };
+            let stored_digest = self.local.store(entry_type, true, true, path).await?;
+            if stored_digest != digest {
+                return Err(format!(
+                    "The shared store contained {stored_digest:?} rather than {digest:?}."
+                )
+                .into());
+            }
+        } else {
+            let Some(bytes) = shared
+                .load_bytes_with(entry_type, digest, Bytes::copy_from_slice)
+                .await?
+            else {
+                return Ok(false);
+            };
+            self.local
+                .store_bytes(entry_type, digest.hash, bytes, true)
+                .await?;
+        }
+        Ok(true)
+    }
+
+    ///
+    /// Copies whichever of the given digests are present in the shared stores (if any are attached)
+    /// to the local store, returning the digests which were not.
+    ///
+    async fn copy_all_from_shared(
+        &self,
+        entry_type: EntryType,
+        digests: HashSet<Digest>,
+    ) -> Result<HashSet<Digest>, StoreError> {
+        if self.shared.is_empty() {
+            return Ok(digests);
+        }
+        let digests = digests.into_iter().collect::<Vec<_>>();
+        let copied = future::try_join_all(
+            digests
+                .iter()
+                .map(|&digest| self.copy_from_shared(entry_type, digest)),
+        )
+        .await?;
+        Ok(digests
+            .into_iter()
+            .zip(copied)
+            .filter_map(|(digest, copied)| if copied { None } else { Some(digest) })
+            .collect())
+    }
+
     ///
     /// Ensures that the remote ByteStore has a copy of each passed Fingerprint, including any files
     /// contained in any Directories in the list.
@@ -909,7 +1108,17 @@ impl Store {
             .get_missing_digests(EntryType::File, file_digests)
             .await?;
 
-        // If there are any digests which don't exist locally, check remotely.
+        // If there are any digests which don't exist locally, check the shared stores, and then
+        // remotely.
+        let mut missing_locally = missing_locally;
+        for shared in &self.shared {
+            if missing_locally.is_empty() {
+                break;
+            }
+            missing_locally = shared
+                .get_missing_digests(EntryType::File, missing_locally)
+                .await?;
+        }
         if missing_locally.is_empty() {
             return Ok(true);
         }
@@ -949,6 +1158,9 @@ impl Store {
             .local
             .get_missing_digests(EntryType::File, file_digests)
             .await?;
+        let missing_file_digests = self
+            .copy_all_from_shared(EntryType::File, missing_file_digests)
+            .await?;
         if missing_file_digests.is_empty() {
             return Ok(());
         }
@@ -1021,19 +1233,42 @@ impl Store {
     pub async fn lease_all_recursively<'a, Ds: Iterator<Item = &'a Digest>>(
         &self,
         digests: Ds,
+    ) -> Result<(), StoreError> {
+        self.lease_all_recursively_for(digests, None).await
+    }
+
+    ///
+    /// Leases the given digests (and everything reachable from them) for at least `lease_time`, or
+    /// for the default lease time if it is None.
+    ///
+    pub async fn lease_all_recursively_for<'a, Ds: Iterator<Item = &'a Digest>>(
+        &self,
+        digests: Ds,
+        lease_time: Option<Duration>,
     ) -> Result<(), StoreError> {
         let reachable_digests_and_types = self.expand_local_digests(digests).await?;
         // Lease all Digests which existed (ignoring any that didn't).
         self.local
-            .lease_all(
+            .lease_all_for(
                 reachable_digests_and_types
                     .into_iter()
                     .flat_map(|(digest, maybe_type)| maybe_type.map(|t| (digest, t))),
+                lease_time,
             )
             .await?;
         Ok(())
     }
 
+    ///
+    /// Releases in-memory state which can be recomputed (such as which large files have been
+    /// written, and which transfers have completed), and returns the number of entries which were
+    /// released. Stored content is unaffected.
+    ///
+    pub fn release_memory(&self) -> usize {
+        // NB: The shared stores are never written to, so they hold no such state.
+        self.local.release_memory() + self.remote.as_ref().map_or(0, RemoteStore::release_memory)
+    }
+
     pub async fn garbage_collect(
         &self,
         target_size_bytes: usize,
@@ -1184,6 +1419,133 @@ impl Store {
         force_mutable: bool,
         mutable_paths: &BTreeSet<RelativePath>,
         perms: Permissions,
+    ) -> Result<(), StoreError> {
+        self.materialize_directory_reporting(
+            destination,
+            destination_root,
+            digest,
+            force_mutable,
+            mutable_paths,
+            perms,
+            None,
+        )
+        .await
+    }
+
+    ///
+    /// Materializes the directory into the given (possibly existing) destination in the workspace,
+    /// such that no file in the destination is ever partially written: the directory is first
+    /// materialized (writably) into a staging directory inside the destination, and each of its
+    /// files and symlinks is then renamed into place, replacing any existing entry at its path.
+    /// Other entries in the destination are left as they are.
+    ///
+    /// The staging directory is a dot directory, and so is ignored by the file watcher by default.
+    /// It is removed once the directory has been materialized, or if materializing it fails. The
+    /// staging directories which were left behind by processes which were killed while
+    /// materializing into the destination are removed first.
+    ///
+    /// The given function is called with the size of each file once it has been written.
+    ///
+    pub async fn materialize_directory_atomically(
+        &self,
+        destination: PathBuf,
+        tree: DigestTrie,
+        progress: &(dyn Fn(usize) + Send + Sync),
+    ) -> Result<(), StoreError> {
+        tokio::fs::create_dir_all(&destination)
+            .await
+            .map_err(|e| format!("Failed to create directory {}: {e}", destination.display()))?;
+        remove_stale_staging_directories(&destination).await?;
+        // NB: The pid of this process is recorded in the name of the staging directory, so that it
+        // is not mistaken for a stale one while this process is alive.
+        let staging = tempfile::Builder::new()
+            .prefix(&format!("{STAGING_PREFIX}{}-", std::process::id()))
+            .tempdir_in(&destination)
+            .map_err(|e| {
+                format!(
+                    "Failed to create a staging directory in {}: {e}",
+                    destination.display()
+                )
+            })?;
+        self.materialize_directory_reporting(
+            staging.path().to_owned(),
+            staging.path(),
+            tree.clone().into(),
+            true,
+            &BTreeSet::new(),
+            Permissions::Writable,
+            Some(progress),
+        )
+        .await?;
+
+        // Create the directories in the destination (parents first), and then move the files and
+        // symlinks into them.
+        let mut directories = Vec::new();
+        let mut leaves = Vec::new();
+        tree.walk(SymlinkBehavior::Aware, &mut |path, entry| match entry {
+            directory::Entry::Directory(_) if path.as_os_str().is_empty() => {}
+            directory::Entry::Directory(_) => directories.push(path.to_owned()),
+            directory::Entry::File(_) | directory::Entry::Symlink(_) => {
+                leaves.push(path.to_owned())
+            }
+        });
+        for path in directories {
+            let path = destination.join(path);
+            match tokio::fs::symlink_metadata(&path).await {
+                Ok(metadata) if metadata.is_dir() => continue,
+                Ok(_) => tokio::fs::remove_file(&path)
+                    .await
+                    .map_err(|e| format!("Failed to remove {}: {e}", path.display()))?,
+                Err(_) => {}
+            }
+            tokio::fs::create_dir(&path)
+                .await
+                .map_err(|e| format!("Failed to create directory {}: {e}", path.display()))?;
+        }
+        future::try_join_all(leaves.into_iter().map(|path| {
+            let source = staging.path().join(&path);
+            let path = destination.join(path);
+            async move {
+                if tokio::fs::rename(&source, &path).await.is_ok() {
+                    return Ok(());
+                }
+                // A directory can only be replaced once it has been removed.
+                if let Ok(metadata) = tokio::fs::symlink_metadata(&path).await {
+                    if metadata.is_dir() {
+                        tokio::fs::remove_dir_all(&path)
+                            .await
+                            .map_err(|e| format!("Failed to remove {}: {e}", path.display()))?;
+                    }
+                }
+                tokio::fs::rename(&source, &path).await.map_err(|e| {
+                    format!(
+                        "Failed to move {} to {}: {e}",
+                        source.display(),
+                        path.display()
+                    )
+                })
+            }
+        }))
+        .await?;
+
+        staging.close().map_err(|e| {
+            format!(
+                "Failed to remove the staging directory in {}: {e}",
+                destination.display()
+            )
+        })?;
+        Ok(())
+    }
+
+    async fn materialize_directory_reporting(
+        &self,
+        destination: PathBuf,
+        destination_root: &Path,
+        digest: DirectoryDigest,
+        force_mutable: bool,
+        mutable_paths: &BTreeSet<RelativePath>,
+        perms: Permissions,
+        progress: Option<&(dyn Fn(usize) + Send + Sync)>,
     ) -> Result<(), StoreError> {
         debug_assert!(
             destination.starts_with(destination_root),
@@ -1230,6 +1592,7 @@ impl Store {
             &parent_to_child,
             &mutable_path_ancestors,
             perms,
+            progress,
         )
         .await
     }
@@ -1243,6 +1606,7 @@ impl Store {
         parent_to_child: &'a HashMap<PathBuf, Vec<directory::Entry>>,
         mutable_paths: &'a BTreeSet<PathBuf>,
         perms: Permissions,
+        progress: Option<&'a (dyn Fn(usize) + Send + Sync)>,
     ) -> BoxFuture<'a, Result<(), StoreError>> {
         let store = self.clone();
         async move {
@@ -1266,7 +1630,7 @@ impl Store {
 
                         match child {
                             directory::Entry::File(f) => {
-                                store
+                                let result = store
                                     .materialize_file_maybe_hardlink(
                                         path,
                                         f.digest(),
@@ -1274,7 +1638,11 @@ impl Store {
                                         f.is_executable(),
                                         can_be_immutable,
                                     )
-                                    .await
+                                    .await;
+                                if let (Ok(()), Some(progress)) = (&result, progress) {
+                                    progress(f.digest().size_bytes);
+                                }
+                                result
                             }
                             directory::Entry::Symlink(s) => {
                                 store
@@ -1294,6 +1662,7 @@ impl Store {
                                         parent_to_child,
                                         mutable_paths,
                                         perms,
+                                        progress,
                                     )
                                     .await
                             }
@@ -1616,6 +1985,103 @@ impl Store {
     pub async fn all_local_digests(&self, entry_type: EntryType) -> Result<Vec<Digest>, String> {
         self.local.all_digests(entry_type).await
     }
+
+    ///
+    /// Copies the files and directories of the local store `source`, which was written with another
+    /// digest function, into this store: the digests of files and directories are recomputed with
+    /// the current digest function, and directories are rewritten to refer to their children by
+    /// their new digests.
+    ///
+    /// Directories whose children are missing from `source` are skipped, as are the entries of the
+    /// process cache, which refer to digests computed by the other function.
+    ///
+    /// Returns the number of files and of directories which were migrated.
+    ///
+    pub async fn migrate_from(&self, source: &Store) -> Result<(usize, usize), String> {
+        // Empty files and directories are never stored, so their digests are translated directly.
+        let translate = |digests: &HashMap<Digest, Digest>, digest: Digest| {
+            if digest.size_bytes == 0 {
+                Some(*EMPTY_DIGEST)
+            } else {
+                digests.get(&digest).copied()
+            }
+        };
+
+        let mut files = HashMap::new();
+        for digest in source.all_local_digests(EntryType::File).await? {
+            let bytes = source
+                .local
+                .load_bytes_with(EntryType::File, digest, Bytes::copy_from_slice)
+                .await?;
+            if let Some(bytes) = bytes {
+                files.insert(digest, self.store_file_bytes(bytes, true).await?);
+            }
+        }
+
+        let mut pending = HashMap::new();
+        for digest in source.all_local_digests(EntryType::Directory).await? {
+            let directory = source
+                .local
+                .load_bytes_with(EntryType::Directory, digest, move |bytes| {
+                    remexec::Directory::decode(bytes).map_err(|e| {
+                        format!("Failed to decode directory {digest:?} from the source store: {e}")
+                    })
+                })
+                .await?;
+            if let Some(directory) = directory {
+                pending.insert(digest, directory?);
+            }
+        }
+
+        // Translate the directories whose children have all been translated, until no more
+        // progress is made.
+        let mut directories = HashMap::new();
+        loop {
+            let mut translated = vec![];
+            for (digest, directory) in &pending {
+                let mut directory = directory.clone();
+                let files_translated = directory.files.iter_mut().all(|node| {
+                    let new_digest = require_digest(node.digest.as_ref())
+                        .ok()
+                        .and_then(|d| translate(&files, d));
+                    node.digest = new_digest.map(|d| d.into());
+                    new_digest.is_some()
+                });
+                let directories_translated = directory.directories.iter_mut().all(|node| {
+                    let new_digest = require_digest(node.digest.as_ref())
+                        .ok()
+                        .and_then(|d| translate(&directories, d));
+                    node.digest = new_digest.map(|d| d.into());
+                    new_digest.is_some()
+                });
+                if files_translated && directories_translated {
+                    translated.push((*digest, directory));
+                }
+            }
+            if translated.is_empty() {
+                break;
+            }
+            let mut items = vec![];
+            for (digest, directory) in translated {
+                pending.remove(&digest);
+                let bytes = directory.to_bytes();
+                let new_digest = Digest::of_bytes(&bytes);
+                directories.insert(digest, new_digest);
+                items.push((new_digest.hash, bytes));
+            }
+            self.local
+                .store_bytes_batch(EntryType::Directory, items, true)
+                .await?;
+        }
+        if !pending.is_empty() {
+            log::warn!(
+                "Skipped {} directories whose children are missing from the source store.",
+                pending.len()
+            );
+        }
+
+        Ok((files.len(), directories.len()))
+    }
 }
 
 #[async_trait]
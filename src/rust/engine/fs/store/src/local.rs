@@ -13,10 +13,11 @@ use async_trait::async_trait;
 use bytes::Bytes;
 use futures::future::{self, join_all, try_join, try_join_all};
 use hashing::{
-    async_copy_and_hash, async_verified_copy, AgedFingerprint, Digest, Fingerprint, EMPTY_DIGEST,
+    async_copy_and_hash, async_hash_overlapped, async_verified_copy, AgedFingerprint, Digest,
+    Fingerprint, EMPTY_DIGEST,
 };
 use parking_lot::Mutex;
-use sharded_lmdb::ShardedLmdb;
+use sharded_lmdb::{ShardedStore, DEFAULT_LEASE_TIME};
 use std::os::unix::fs::PermissionsExt;
 use task_executor::Executor;
 use tempfile::Builder;
@@ -31,7 +32,12 @@ use workunit_store::ObservationMetric;
 // for somewhere between 2 and 3 uses of the corresponding entry to "break even".
 const LARGE_FILE_SIZE_LIMIT: usize = 512 * 1024;
 
-/// Trait for the underlying storage, which is either a ShardedLMDB or a ShardedFS.
+/// How big a file must be for reading it to be overlapped with hashing it: see
+/// `hashing::async_hash_overlapped`.
+const OVERLAPPED_HASHING_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+/// Trait for the underlying storage, which is either a ShardedStore (of LMDB or SQLite) or a
+/// ShardedFS.
 #[async_trait]
 trait UnderlyingByteStore {
     async fn exists_batch(
@@ -46,6 +52,10 @@ trait UnderlyingByteStore {
 
     async fn lease(&self, fingerprint: Fingerprint) -> Result<(), String>;
 
+    /// Leases the fingerprint for at least `lease_time`, without shortening an existing lease.
+    async fn lease_for(&self, fingerprint: Fingerprint, lease_time: Duration)
+        -> Result<(), String>;
+
     async fn remove(&self, fingerprint: Fingerprint) -> Result<bool, String>;
 
     async fn store_bytes_batch(
@@ -87,7 +97,7 @@ trait UnderlyingByteStore {
 }
 
 #[async_trait]
-impl UnderlyingByteStore for ShardedLmdb {
+impl UnderlyingByteStore for ShardedStore {
     async fn exists_batch(
         &self,
         fingerprints: Vec<Fingerprint>,
@@ -99,6 +109,14 @@ impl UnderlyingByteStore for ShardedLmdb {
         self.lease(fingerprint).await
     }
 
+    async fn lease_for(
+        &self,
+        fingerprint: Fingerprint,
+        lease_time: Duration,
+    ) -> Result<(), String> {
+        self.lease_for(fingerprint, lease_time).await
+    }
+
     async fn remove(&self, fingerprint: Fingerprint) -> Result<bool, String> {
         self.remove(fingerprint).await
     }
@@ -334,6 +352,19 @@ impl ShardedFSDB {
         .await
         .cloned()
     }
+
+    ///
+    /// Forgets which files have been written (other than those being written concurrently), and
+    /// returns the number which were forgotten. A forgotten file is re-written if it is stored
+    /// again.
+    ///
+    fn forget_written(&self) -> usize {
+        let mut cells = self.dest_initializer.lock();
+        let before = cells.len();
+        cells.retain(|_, cell| Arc::strong_count(cell) > 1);
+        cells.shrink_to_fit();
+        before - cells.len()
+    }
 }
 
 #[async_trait]
@@ -364,11 +395,28 @@ impl UnderlyingByteStore for ShardedFSDB {
     }
 
     async fn lease(&self, fingerprint: Fingerprint) -> Result<(), String> {
+        self.lease_for(fingerprint, self.lease_time).await
+    }
+
+    async fn lease_for(
+        &self,
+        fingerprint: Fingerprint,
+        lease_time: Duration,
+    ) -> Result<(), String> {
+        // NB: Files are expired once their mtime is outside of the lease time window (see
+        // `aged_fingerprints`), so longer leases are recorded as mtimes in the future.
         let path = self.get_path(fingerprint);
+        let mtime = SystemTime::now() + lease_time.saturating_sub(self.lease_time);
         self.executor
             .spawn_blocking(
                 move || {
-                    fs_set_times::set_mtime(&path, fs_set_times::SystemTimeSpec::SymbolicNow)
+                    let existing_mtime = std::fs::metadata(&path)
+                        .and_then(|metadata| metadata.modified())
+                        .map_err(|e| format!("Failed to read mtime of {path:?}: {e}"))?;
+                    if existing_mtime >= mtime {
+                        return Ok(());
+                    }
+                    fs_set_times::set_mtime(&path, fs_set_times::SystemTimeSpec::Absolute(mtime))
                         .map_err(|e| format!("Failed to extend mtime of {path:?}: {e}"))
                 },
                 |e| Err(format!("`lease` task failed: {e}")),
@@ -561,8 +609,8 @@ struct InnerStore {
     // Store directories separately from files because:
     //  1. They may have different lifetimes.
     //  2. It's nice to know whether we should be able to parse something as a proto.
-    file_lmdb: Result<Arc<ShardedLmdb>, String>,
-    directory_lmdb: Result<Arc<ShardedLmdb>, String>,
+    file_lmdb: Result<Arc<ShardedStore>, String>,
+    directory_lmdb: Result<Arc<ShardedStore>, String>,
     file_fsdb: ShardedFSDB,
     file_source: FileSource,
 }
@@ -592,17 +640,21 @@ impl ByteStore {
 
         Ok(ByteStore {
             inner: Arc::new(InnerStore {
-                file_lmdb: ShardedLmdb::new(
+                file_lmdb: ShardedStore::new(
+                    options.backend,
                     lmdb_files_root,
                     options.files_max_size_bytes,
+                    options.max_size_growth_factor,
                     executor.clone(),
                     options.lease_time,
                     options.shard_count,
                 )
                 .map(Arc::new),
-                directory_lmdb: ShardedLmdb::new(
+                directory_lmdb: ShardedStore::new(
+                    options.backend,
                     lmdb_directories_root,
                     options.directories_max_size_bytes,
+                    options.max_size_growth_factor,
                     executor.clone(),
                     options.lease_time,
                     options.shard_count,
@@ -626,6 +678,38 @@ impl ByteStore {
         })
     }
 
+    ///
+    /// Opens an existing store in read-only mode: see `ShardedStore::new_read_only`. Unlike the
+    /// store being opened for writing, the store must exist.
+    ///
+    pub fn new_read_only<P: AsRef<Path>>(
+        executor: task_executor::Executor,
+        path: P,
+    ) -> Result<ByteStore, String> {
+        let root = path.as_ref();
+        let open_lmdb = |name| {
+            ShardedStore::new_read_only(root.join(name), executor.clone(), DEFAULT_LEASE_TIME)
+                .map(|lmdb| Ok(Arc::new(lmdb)))
+        };
+
+        Ok(ByteStore {
+            inner: Arc::new(InnerStore {
+                file_lmdb: open_lmdb("files")?,
+                directory_lmdb: open_lmdb("directories")?,
+                file_fsdb: ShardedFSDB {
+                    executor,
+                    root: root.join("immutable").join("files"),
+                    lease_time: DEFAULT_LEASE_TIME,
+                    dest_initializer: Arc::new(Mutex::default()),
+                    hardlinkable_destinations: Arc::new(Mutex::default()),
+                },
+                file_source: FileSource {
+                    open_files: Semaphore::new(1024),
+                },
+            }),
+        })
+    }
+
     pub async fn is_hardlinkable_destination(&self, destination: &Path) -> Result<bool, String> {
         self.inner
             .file_fsdb
@@ -657,22 +741,33 @@ impl ByteStore {
         }
     }
 
-    pub async fn lease_all(
+    ///
+    /// Leases the given digests for at least `lease_time` (or for the default lease time if it is
+    /// None), without shortening any leases which expire later.
+    ///
+    pub async fn lease_all_for(
         &self,
         digests: impl Iterator<Item = (Digest, EntryType)>,
+        lease_time: Option<Duration>,
     ) -> Result<(), String> {
         // NB: Lease extension happens periodically in the background, so this code needn't be parallel.
         for (digest, entry_type) in digests {
             if ByteStore::should_use_fsdb(entry_type, digest.size_bytes) {
-                self.inner.file_fsdb.lease(digest.hash).await?;
+                let fsdb = &self.inner.file_fsdb;
+                match lease_time {
+                    Some(lease_time) => fsdb.lease_for(digest.hash, lease_time).await?,
+                    None => fsdb.lease(digest.hash).await?,
+                }
             } else {
                 let dbs = match entry_type {
                     EntryType::File => self.inner.file_lmdb.clone(),
                     EntryType::Directory => self.inner.directory_lmdb.clone(),
-                };
-                dbs?.lease(digest.hash)
-                    .await
-                    .map_err(|err| format!("Error leasing digest {digest:?}: {err}"))?;
+                }?;
+                match lease_time {
+                    Some(lease_time) => dbs.lease_for(digest.hash, lease_time).await,
+                    None => dbs.lease(digest.hash).await,
+                }
+                .map_err(|err| format!("Error leasing digest {digest:?}: {err}"))?;
             }
         }
         Ok(())
@@ -837,9 +932,17 @@ impl ByteStore {
                 .open_readonly(&src)
                 .await
                 .map_err(|e| format!("Failed to open {src:?}: {e}"))?;
-            async_copy_and_hash(&mut file, &mut tokio::io::sink())
+            let len = file
+                .metadata()
                 .await
-                .map_err(|e| format!("Failed to hash {src:?}: {e}"))?
+                .map_err(|e| format!("Failed to stat {src:?}: {e}"))?
+                .len();
+            if len >= OVERLAPPED_HASHING_THRESHOLD {
+                async_hash_overlapped(&mut file).await
+            } else {
+                async_copy_and_hash(&mut file, &mut tokio::io::sink()).await
+            }
+            .map_err(|e| format!("Failed to hash {src:?}: {e}"))?
         };
 
         if ByteStore::should_use_fsdb(entry_type, digest.size_bytes) {
@@ -888,7 +991,7 @@ impl ByteStore {
             }
             // Avoid I/O for this case. This allows some client-provided operations (like
             // merging snapshots) to work without needing to first store the empty snapshot.
-            else if *digest != EMPTY_DIGEST {
+            else if *digest != *EMPTY_DIGEST {
                 lmdb_digests.push(digest);
             }
         }
@@ -909,7 +1012,7 @@ impl ByteStore {
 
         Ok(digests
             .into_iter()
-            .filter(|digest| *digest != EMPTY_DIGEST && !existing.contains(&digest.hash))
+            .filter(|digest| *digest != *EMPTY_DIGEST && !existing.contains(&digest.hash))
             .collect())
     }
 
@@ -939,7 +1042,7 @@ impl ByteStore {
         mut f: F,
     ) -> Result<Option<T>, String> {
         let start = Instant::now();
-        if digest == EMPTY_DIGEST {
+        if digest == *EMPTY_DIGEST {
             // Avoid I/O for this case. This allows some client-provided operations (like merging
             // snapshots) to work without needing to first store the empty snapshot.
             return Ok(Some(f(&[])));
@@ -1000,6 +1103,14 @@ impl ByteStore {
         entry_type == EntryType::File && len >= LARGE_FILE_SIZE_LIMIT
     }
 
+    ///
+    /// Releases in-memory state which can be recomputed, and returns the number of entries which
+    /// were released.
+    ///
+    pub fn release_memory(&self) -> usize {
+        self.inner.file_fsdb.forget_written()
+    }
+
     pub(crate) fn get_file_fsdb(&self) -> ShardedFSDB {
         self.inner.file_fsdb.clone()
     }
@@ -68,6 +68,16 @@ async fn save_file_is_idempotent() {
     .await;
 }
 
+#[tokio::test]
+async fn save_huge_file() {
+    let dir = TempDir::new().unwrap();
+
+    // Large enough to be hashed while it is read.
+    let bytes = Bytes::from(vec![b'a'; 17 * 1024 * 1024]);
+    let digest = Digest::of_bytes(&bytes);
+    assert_store_bytes(new_store(dir.path()), EntryType::File, bytes, digest).await;
+}
+
 #[tokio::test]
 async fn roundtrip_file() {
     let testdata = TestData::roland();
@@ -179,7 +189,7 @@ async fn garbage_collect_nothing_to_do_with_lease() {
         .await
         .expect("Error storing");
     store
-        .lease_all(vec![(file_digest, EntryType::File)].into_iter())
+        .lease_all_for(vec![(file_digest, EntryType::File)].into_iter(), None)
         .await
         .expect("Error leasing");
     store
@@ -234,6 +244,45 @@ async fn garbage_collect_expired() {
     );
 }
 
+#[tokio::test]
+async fn garbage_collect_leased_for() {
+    let lease_time = Duration::from_secs(1);
+    let dir = TempDir::new().unwrap();
+    let store = new_store_with_lease_time(dir.path(), lease_time);
+    let small_testdata = TestData::roland();
+    let large_testdata = TestData::new("123456789".repeat(1000 * 512).as_str());
+    let small_digest = prime_store_with_file_bytes(&store, small_testdata.bytes()).await;
+    let large_digest = prime_store_with_file_bytes(&store, large_testdata.bytes()).await;
+
+    // Lease both files (one in LMDB, one in the FSDB) for longer than the lease time of the store,
+    // and then lease them for the lease time of the store, which should not shorten their leases.
+    let digests = vec![
+        (small_digest, EntryType::File),
+        (large_digest, EntryType::File),
+    ];
+    store
+        .lease_all_for(
+            digests.clone().into_iter(),
+            Some(Duration::from_secs(60 * 60)),
+        )
+        .await
+        .expect("Error leasing");
+    store
+        .lease_all_for(digests.into_iter(), None)
+        .await
+        .expect("Error leasing");
+
+    // Wait for the lease time of the store to pass.
+    sleep(lease_time * 2).await;
+    assert_eq!(
+        small_digest.size_bytes + large_digest.size_bytes,
+        store
+            .shrink(0, ShrinkBehavior::Fast)
+            .await
+            .expect("Error shrinking"),
+    );
+}
+
 #[tokio::test]
 async fn garbage_collect_remove_one_of_two_files_no_leases() {
     let dir = TempDir::new().unwrap();
@@ -599,6 +648,22 @@ async fn remove_big_file_and_store_again() {
     );
 }
 
+#[tokio::test]
+async fn release_memory_and_store_again() {
+    let dir = TempDir::new().unwrap();
+    let store = new_store(dir.path());
+
+    let digest = write_1mb(&store, b'0').await;
+    write_1mb(&store, b'1').await;
+    assert_eq!(store.release_memory(), 2);
+    assert_eq!(store.release_memory(), 0);
+
+    // The files are still loadable, and are re-written if they are stored again.
+    assert!(load_file_bytes(&store, digest).await.unwrap().is_some());
+    write_1mb(&store, b'0').await;
+    assert_eq!(store.release_memory(), 1);
+}
+
 #[tokio::test]
 async fn entry_type_for_file() {
     let testdata = TestData::roland();
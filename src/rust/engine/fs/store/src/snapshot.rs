@@ -47,7 +47,7 @@ impl hash::Hash for Snapshot {
 impl Snapshot {
     pub fn empty() -> Self {
         Self {
-            digest: EMPTY_DIGEST,
+            digest: *EMPTY_DIGEST,
             tree: EMPTY_DIGEST_TREE.clone(),
         }
     }
@@ -183,7 +183,7 @@ impl Snapshot {
         // NB: All files receive the EMPTY_DIGEST.
         let file_digests = files
             .iter()
-            .map(|s| (PathBuf::from(&s), EMPTY_DIGEST))
+            .map(|s| (PathBuf::from(&s), *EMPTY_DIGEST))
             .collect();
         let file_path_stats: Vec<PathStat> = files
             .into_iter()
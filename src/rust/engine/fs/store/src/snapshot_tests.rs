@@ -308,7 +308,7 @@ async fn snapshot_merge_two_files() {
     let merged_child_dirnode_digest: Result<Digest, String> = merged_child_dirnode
         .digest
         .map(|d| d.try_into())
-        .unwrap_or(Ok(EMPTY_DIGEST));
+        .unwrap_or(Ok(*EMPTY_DIGEST));
     let merged_child_directory = store
         .load_directory(merged_child_dirnode_digest.unwrap())
         .await
@@ -209,6 +209,101 @@ async fn load_file_falls_back_and_backfills_for_huge_file() {
     );
 }
 
+#[tokio::test]
+async fn load_file_falls_back_to_shared_store_and_backfills() {
+    let dir = TempDir::new().unwrap();
+    let shared_dir = TempDir::new().unwrap();
+
+    let small = TestData::roland();
+    // Large enough to be stored as a file on disk.
+    let large = TestData::new(&"12345".repeat(MEGABYTES));
+    crate::local_tests::new_store(shared_dir.path())
+        .store_bytes_batch(
+            EntryType::File,
+            vec![
+                (small.fingerprint(), small.bytes()),
+                (large.fingerprint(), large.bytes()),
+            ],
+            false,
+        )
+        .await
+        .expect("Store failed");
+
+    let cas = new_empty_cas();
+    let store = new_store(dir.path(), &cas.address())
+        .await
+        .into_with_shared(task_executor::Executor::new(), shared_dir.path())
+        .unwrap();
+    for testdata in [&small, &large] {
+        assert_eq!(
+            load_file_bytes(&store, testdata.digest()).await,
+            Ok(testdata.bytes()),
+            "Read from shared store"
+        );
+    }
+    assert_eq!(0, cas.request_count(RequestType::BSRead));
+    std::mem::drop(store);
+
+    let local = crate::local_tests::new_store(dir.path());
+    for testdata in [&small, &large] {
+        assert_eq!(
+            crate::local_tests::load_file_bytes(&local, testdata.digest()).await,
+            Ok(Some(testdata.bytes())),
+            "Read from local cache"
+        );
+    }
+}
+
+#[tokio::test]
+async fn shared_store_must_exist() {
+    let dir = TempDir::new().unwrap();
+    let shared_dir = TempDir::new().unwrap();
+
+    assert!(new_local_store(dir.path())
+        .into_with_shared(task_executor::Executor::new(), shared_dir.path())
+        .is_err());
+}
+
+#[tokio::test]
+async fn load_file_falls_back_to_each_shared_store_in_order() {
+    let dir = TempDir::new().unwrap();
+    let first_shared_dir = TempDir::new().unwrap();
+    let second_shared_dir = TempDir::new().unwrap();
+
+    let first = TestData::roland();
+    let second = TestData::catnip();
+    crate::local_tests::new_store(first_shared_dir.path())
+        .store_bytes(EntryType::File, first.fingerprint(), first.bytes(), false)
+        .await
+        .expect("Store failed");
+    crate::local_tests::new_store(second_shared_dir.path())
+        .store_bytes(EntryType::File, second.fingerprint(), second.bytes(), false)
+        .await
+        .expect("Store failed");
+
+    let cas = new_empty_cas();
+    let store = new_store(dir.path(), &cas.address())
+        .await
+        .into_with_shared(task_executor::Executor::new(), first_shared_dir.path())
+        .unwrap()
+        .into_with_shared(task_executor::Executor::new(), second_shared_dir.path())
+        .unwrap();
+    assert_eq!(
+        store
+            .exists_recursive(vec![], vec![first.digest(), second.digest()])
+            .await,
+        Ok(true)
+    );
+    for testdata in [&first, &second] {
+        assert_eq!(
+            load_file_bytes(&store, testdata.digest()).await,
+            Ok(testdata.bytes()),
+            "Read from shared store"
+        );
+    }
+    assert_eq!(0, cas.request_count(RequestType::BSRead));
+}
+
 #[tokio::test]
 async fn load_directory_small_falls_back_and_backfills() {
     let dir = TempDir::new().unwrap();
@@ -328,6 +423,55 @@ async fn load_recursive_directory() {
     );
 }
 
+#[tokio::test]
+async fn migrate_from_copies_complete_directories() {
+    let source_dir = TempDir::new().unwrap();
+    let dir = TempDir::new().unwrap();
+
+    let roland = TestData::roland();
+    let catnip = TestData::catnip();
+    let testdir = TestDirectory::containing_roland();
+    let recursive_testdir = TestDirectory::recursive();
+    // The robin of the inner directory is missing from the source store.
+    let robin_testdir = TestDirectory::containing_robin();
+    let incomplete_testdir = TestDirectory::recursive_with(TestDirectory::containing_robin());
+
+    let source = new_local_store(source_dir.path());
+    for file in [&roland, &catnip] {
+        source.store_file_bytes(file.bytes(), false).await.unwrap();
+    }
+    for directory in [
+        &testdir,
+        &recursive_testdir,
+        &robin_testdir,
+        &incomplete_testdir,
+    ] {
+        source
+            .record_directory(&directory.directory(), false)
+            .await
+            .unwrap();
+    }
+
+    let store = new_local_store(dir.path());
+    assert_eq!(store.migrate_from(&source).await, Ok((2, 2)));
+
+    assert_eq!(
+        load_file_bytes(&store, catnip.digest()).await,
+        Ok(catnip.bytes())
+    );
+    assert_eq!(
+        store
+            .load_directory(recursive_testdir.digest())
+            .await
+            .unwrap(),
+        recursive_testdir.directory()
+    );
+    assert!(matches!(
+        store.load_directory(incomplete_testdir.digest()).await,
+        Err(StoreError::MissingDigest { .. })
+    ));
+}
+
 #[tokio::test]
 async fn load_file_missing_is_none() {
     let dir = TempDir::new().unwrap();
@@ -1249,6 +1393,149 @@ async fn materialize_directory_readonly_executable() {
     materialize_directory(Permissions::Writable, true).await
 }
 
+#[tokio::test]
+async fn materialize_directory_atomically() {
+    let materialize_dir = TempDir::new().unwrap();
+    let destination = materialize_dir.path().join("dist");
+
+    let catnip = TestData::catnip();
+    let testdir = TestDirectory::with_maybe_executable_files(true);
+    let recursive_testdir = TestDirectory::recursive_with(testdir.clone());
+
+    let store_dir = TempDir::new().unwrap();
+    let store = new_local_store(store_dir.path());
+    store
+        .record_directory(&recursive_testdir.directory(), false)
+        .await
+        .expect("Error saving recursive Directory");
+    store
+        .record_directory(&testdir.directory(), false)
+        .await
+        .expect("Error saving Directory");
+    store
+        .store_file_bytes(catnip.bytes(), false)
+        .await
+        .expect("Error saving file bytes");
+
+    // Existing entries of the wrong types are replaced, and unrelated entries are left alone.
+    std::fs::create_dir_all(destination.join("treats.ext")).unwrap();
+    std::fs::write(destination.join("treats.ext").join("stale"), "stale").unwrap();
+    std::fs::write(destination.join("cats"), "stale").unwrap();
+    std::fs::write(destination.join("unrelated"), "unrelated").unwrap();
+
+    let tree = store
+        .load_digest_trie(recursive_testdir.directory_digest())
+        .await
+        .unwrap();
+    let written = std::sync::Mutex::new(Vec::new());
+    store
+        .materialize_directory_atomically(destination.clone(), tree, &|size| {
+            written.lock().unwrap().push(size)
+        })
+        .await
+        .expect("Error materializing");
+
+    // The staging directory has been removed.
+    assert_eq!(
+        list_dir(&destination),
+        vec!["cats", "treats.ext", "unrelated"]
+    );
+    assert_eq!(
+        file_contents(&destination.join("treats.ext")),
+        catnip.bytes()
+    );
+    assert_eq!(
+        list_dir(&destination.join("cats")),
+        vec!["feed.ext", "food.ext"]
+    );
+    assert!(is_executable(&destination.join("cats").join("feed.ext")));
+    assert!(!is_executable(&destination.join("cats").join("food.ext")));
+    assert!(!is_readonly(&destination.join("cats").join("feed.ext")));
+    assert_eq!(written.into_inner().unwrap(), vec![catnip.len(); 3]);
+}
+
+#[tokio::test]
+async fn materialize_directory_atomically_symlinks() {
+    let materialize_dir = TempDir::new().unwrap();
+    let destination = materialize_dir.path().to_owned();
+
+    let directory = remexec::Directory {
+        symlinks: vec![remexec::SymlinkNode {
+            name: "link".to_owned(),
+            target: "cats/roland.ext".to_owned(),
+            ..remexec::SymlinkNode::default()
+        }],
+        ..remexec::Directory::default()
+    };
+    let store_dir = TempDir::new().unwrap();
+    let store = new_local_store(store_dir.path());
+    let digest = store
+        .record_directory(&directory, false)
+        .await
+        .expect("Error saving Directory");
+
+    std::fs::write(destination.join("link"), "stale").unwrap();
+    let tree = store
+        .load_digest_trie(DirectoryDigest::from_persisted_digest(digest))
+        .await
+        .unwrap();
+    store
+        .materialize_directory_atomically(destination.clone(), tree, &|_| {
+            panic!("There are no files to write.")
+        })
+        .await
+        .expect("Error materializing");
+
+    assert_eq!(list_dir(&destination), vec!["link"]);
+    assert_eq!(
+        std::fs::read_link(destination.join("link")).unwrap(),
+        PathBuf::from("cats/roland.ext")
+    );
+}
+
+#[tokio::test]
+async fn materialize_directory_atomically_after_interruption() {
+    let materialize_dir = TempDir::new().unwrap();
+    let destination = materialize_dir.path().to_owned();
+
+    let store_dir = TempDir::new().unwrap();
+    let store = new_local_store(store_dir.path());
+    store
+        .record_directory(&TestDirectory::containing_roland().directory(), false)
+        .await
+        .expect("Error saving Directory");
+    store
+        .store_file_bytes(TestData::roland().bytes(), false)
+        .await
+        .expect("Error saving file bytes");
+
+    // A process which was killed while materializing (and which is no longer running) left its
+    // staging directory behind, while a live process is materializing concurrently.
+    let mut killed = std::process::Command::new("true").spawn().unwrap();
+    killed.wait().unwrap();
+    let stale = destination.join(format!(".pants-materialize-{}-abc123", killed.id()));
+    std::fs::create_dir_all(stale.join("cats")).unwrap();
+    std::fs::write(stale.join("cats").join("roland.ext"), "partial").unwrap();
+    let live = destination.join(format!(".pants-materialize-{}-def456", std::process::id()));
+    std::fs::create_dir_all(&live).unwrap();
+
+    let tree = store
+        .load_digest_trie(TestDirectory::containing_roland().directory_digest())
+        .await
+        .unwrap();
+    store
+        .materialize_directory_atomically(destination.clone(), tree, &|_| {})
+        .await
+        .expect("Error materializing");
+
+    assert!(!stale.exists());
+    assert!(live.exists());
+    assert_eq!(
+        file_contents(&destination.join("roland.ext")),
+        TestData::roland().bytes()
+    );
+}
+
 #[tokio::test]
 async fn contents_for_directory_empty() {
     let store_dir = TempDir::new().unwrap();
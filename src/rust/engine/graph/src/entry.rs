@@ -234,6 +234,9 @@ pub(crate) struct Entry<N: Node> {
     node: Arc<N>,
 
     state: Arc<Mutex<EntryState<N>>>,
+
+    // The number of runs which had been generated by the Graph when this Entry was last requested.
+    last_requested: u32,
 }
 
 impl<N: Node> Entry<N> {
@@ -246,6 +249,7 @@ impl<N: Node> Entry<N> {
         Entry {
             node: Arc::new(node),
             state: Arc::new(Mutex::new(EntryState::initial())),
+            last_requested: 0,
         }
     }
 
@@ -253,6 +257,14 @@ impl<N: Node> Entry<N> {
         &self.node
     }
 
+    pub(crate) fn last_requested(&self) -> u32 {
+        self.last_requested
+    }
+
+    pub(crate) fn set_last_requested(&mut self, run_count: u32) {
+        self.last_requested = run_count;
+    }
+
     pub(crate) fn cacheable_with_output(&self, output: Option<&N::Item>) -> bool {
         let output_cacheable = if let Some(item) = output {
             self.node.cacheable_item(item)
@@ -777,6 +789,46 @@ impl<N: Node> Entry<N> {
         };
     }
 
+    ///
+    /// Drops the value of this Node (if it is not running) in order to release memory, and returns
+    /// true if it had one.
+    ///
+    /// Unlike `clear`, no previous result is preserved to compute the next generation of the Node:
+    /// it is assumed that all of its dependents are also being dropped, and so will re-run. The
+    /// caller must remove the edges from this Node.
+    ///
+    pub(crate) fn drop_value(&mut self) -> bool {
+        let mut state = self.state.lock();
+        let (run_token, generation, had_value) = match *state {
+            EntryState::NotStarted {
+                run_token,
+                generation,
+                ref previous_result,
+                ..
+            } => (run_token, generation, previous_result.is_some()),
+            EntryState::Completed {
+                run_token,
+                generation,
+                ..
+            } => (run_token, generation, true),
+            EntryState::Running { .. } => return false,
+        };
+        if !had_value {
+            return false;
+        }
+
+        test_trace_log!("Dropping the value of node {:?}", self.node);
+
+        // Dropping any pollers notifies them of the change.
+        *state = EntryState::NotStarted {
+            run_token: run_token.next(),
+            generation,
+            pollers: Vec::new(),
+            previous_result: None,
+        };
+        true
+    }
+
     ///
     /// Dirties this Node, which will cause it to examine its dependencies the next time it is
     /// requested, and re-run if any of them have changed generations.
@@ -68,7 +68,10 @@ impl<N: Node> InnerGraph<N> {
     }
 
     fn ensure_entry(&mut self, node: N) -> EntryId {
-        InnerGraph::ensure_entry_internal(&mut self.pg, &mut self.nodes, node)
+        let id = InnerGraph::ensure_entry_internal(&mut self.pg, &mut self.nodes, node);
+        let run_count = self.run_id_generator;
+        self.pg[id].set_last_requested(run_count);
+        id
     }
 
     fn ensure_entry_internal(pg: &mut PGraph<N>, nodes: &mut Nodes<N>, node: N) -> EntryId {
@@ -206,6 +209,48 @@ impl<N: Node> InnerGraph<N> {
         }
     }
 
+    ///
+    /// Drops the values of all "cold" Nodes: those which have not been requested within the last
+    /// `max_idle_runs` runs, and which are not (transitive) dependencies of Nodes which have, since
+    /// those might be needed to clean them.
+    ///
+    /// Because the dependents of a cold Node are also cold, they are all dropped together, and will
+    /// all re-run if requested again.
+    ///
+    fn drop_cold(&mut self, max_idle_runs: u32) -> usize {
+        let run_count = self.run_id_generator;
+        let hot_roots = self
+            .pg
+            .node_indices()
+            .filter(|&id| {
+                let entry = &self.pg[id];
+                entry.is_running() || run_count - entry.last_requested() <= max_idle_runs
+            })
+            .collect();
+        let hot = self
+            .walk(hot_roots, Direction::Outgoing, |_| false)
+            .collect::<HashSet<_>>();
+        let cold = self
+            .pg
+            .node_indices()
+            .filter(|id| !hot.contains(id))
+            .collect::<HashSet<_>>();
+        if cold.is_empty() {
+            return 0;
+        }
+
+        self.pg.retain_edges(|pg, edge| {
+            if let Some((src, _)) = pg.edge_endpoints(edge) {
+                !cold.contains(&src)
+            } else {
+                true
+            }
+        });
+        cold.into_iter()
+            .filter(|&id| self.pg[id].drop_value())
+            .count()
+    }
+
     ///
     /// Clears the values of all "invalidation root" Nodes and dirties their transitive dependents.
     ///
@@ -628,6 +673,16 @@ impl<N: Node> Graph<N> {
         inner.clear()
     }
 
+    ///
+    /// Drops the values of Nodes which have not been requested within the last `max_idle_runs` runs
+    /// (see `generate_run_id`) in order to release memory, and returns the number which were
+    /// dropped. The Nodes will re-run if they are requested again.
+    ///
+    pub fn drop_cold(&self, max_idle_runs: u32) -> usize {
+        let mut inner = self.inner.lock();
+        inner.drop_cold(max_idle_runs)
+    }
+
     pub fn invalidate_from_roots<P: Fn(&N) -> bool>(
         &self,
         log_dirtied: bool,
@@ -107,6 +107,44 @@ async fn invalidate_and_rerun() {
     assert_eq!(context.runs(), vec![TNode::new(1), TNode::new(2)]);
 }
 
+#[tokio::test]
+async fn drop_cold() {
+    let graph = empty_graph();
+    let context = graph.context(TContext::new());
+    assert_eq!(
+        graph.create(TNode::new(2), &context).await,
+        Ok(vec![T(0, 0), T(1, 0), T(2, 0)])
+    );
+
+    // In the next run, only the upper node is requested (and is clean), but its dependencies are
+    // not cold, because they might be needed to clean it.
+    let context = graph.context(TContext::new());
+    assert_eq!(
+        graph.create(TNode::new(2), &context).await,
+        Ok(vec![T(0, 0), T(1, 0), T(2, 0)])
+    );
+    assert!(context.runs().is_empty());
+    assert_eq!(graph.drop_cold(0), 0);
+
+    // Once it has not been requested for two runs, all of the nodes are cold.
+    graph.generate_run_id();
+    graph.generate_run_id();
+    assert_eq!(graph.drop_cold(2), 0);
+    assert_eq!(graph.drop_cold(1), 3);
+    assert_eq!(graph.drop_cold(1), 0);
+
+    // And they all re-run when requested again.
+    let context = graph.context(TContext::new());
+    assert_eq!(
+        graph.create(TNode::new(2), &context).await,
+        Ok(vec![T(0, 0), T(1, 0), T(2, 0)])
+    );
+    assert_eq!(
+        context.runs(),
+        vec![TNode::new(2), TNode::new(1), TNode::new(0)]
+    );
+}
+
 #[tokio::test]
 async fn invalidate_uncacheable() {
     let graph = empty_graph();
@@ -55,3 +55,57 @@ async fn async_hashes() {
         .unwrap();
     assert_eq!("meep".as_bytes().to_vec(), contents);
 }
+
+#[tokio::test]
+async fn async_hashes_overlapped() {
+    // Spans multiple chunks, the last of which is partial.
+    let src: Vec<u8> = (0..(super::OVERLAPPED_CHUNK_SIZE * 5 / 2))
+        .map(|i| (i % 251) as u8)
+        .collect();
+    assert_eq!(
+        super::async_hash_overlapped(&mut src.as_slice())
+            .await
+            .unwrap(),
+        super::Digest::of_bytes(&src)
+    );
+
+    assert_eq!(
+        super::async_hash_overlapped(&mut "".as_bytes())
+            .await
+            .unwrap(),
+        *super::EMPTY_DIGEST
+    );
+}
+
+#[test]
+fn blake3_hashes() {
+    let function = super::DigestFunction::Blake3;
+    assert_eq!(
+        function.empty_digest(),
+        super::Digest::new(
+            super::Fingerprint::from_hex_string(
+                "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+            )
+            .unwrap(),
+            0,
+        )
+    );
+
+    let mut hasher = super::Hasher::new_with(function);
+    hasher.update(b"me");
+    hasher.update(b"ep");
+    let digest = hasher.finish();
+    assert_eq!(digest, super::Digest::of_bytes_with(function, b"meep"));
+    assert_ne!(
+        digest,
+        super::Digest::of_bytes_with(super::DigestFunction::Sha256, b"meep")
+    );
+}
+
+#[test]
+fn digest_function_from_str() {
+    for function in super::DigestFunction::ALL {
+        assert_eq!(function.name().parse(), Ok(function));
+    }
+    assert!("md5".parse::<super::DigestFunction>().is_err());
+}
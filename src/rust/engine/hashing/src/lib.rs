@@ -5,29 +5,109 @@ use std::fmt;
 use std::io::{self, Error, Write};
 use std::pin::Pin;
 use std::str::FromStr;
+use std::sync::OnceLock;
 use std::task::{Context, Poll};
 
 use byteorder::ByteOrder;
 use deepsize::DeepSizeOf;
 use digest::consts::U32;
 use generic_array::GenericArray;
+use lazy_static::lazy_static;
 use serde::de::{MapAccess, Visitor};
 use serde::ser::{Serialize, SerializeStruct, Serializer};
 use serde::{Deserialize, Deserializer};
 use sha2::{Digest as Sha256Digest, Sha256};
-use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
 
-pub const EMPTY_FINGERPRINT: Fingerprint = Fingerprint([
-    0xe3, 0xb0, 0xc4, 0x42, 0x98, 0xfc, 0x1c, 0x14, 0x9a, 0xfb, 0xf4, 0xc8, 0x99, 0x6f, 0xb9, 0x24,
-    0x27, 0xae, 0x41, 0xe4, 0x64, 0x9b, 0x93, 0x4c, 0xa4, 0x95, 0x99, 0x1b, 0x78, 0x52, 0xb8, 0x55,
-]);
-pub const EMPTY_DIGEST: Digest = Digest {
-    hash: EMPTY_FINGERPRINT,
-    size_bytes: 0,
-};
+lazy_static! {
+    /// The fingerprint of the empty input, under the `DigestFunction::current` of this process.
+    pub static ref EMPTY_FINGERPRINT: Fingerprint = EMPTY_DIGEST.hash;
+    /// The digest of the empty input, under the `DigestFunction::current` of this process.
+    pub static ref EMPTY_DIGEST: Digest = DigestFunction::current().empty_digest();
+}
 
 pub const FINGERPRINT_SIZE: usize = 32;
 
+/// The size of the chunks which `async_hash_overlapped` reads and hashes.
+const OVERLAPPED_CHUNK_SIZE: usize = 1024 * 1024;
+
+///
+/// A function that Digests are computed with. All of the functions have 32 byte fingerprints.
+///
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum DigestFunction {
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+impl DigestFunction {
+    pub const ALL: [DigestFunction; 2] = [DigestFunction::Sha256, DigestFunction::Blake3];
+
+    /// The environment variable which selects the `DigestFunction::current` of a process. It is
+    /// also the environment variable of the `[GLOBAL].digest_function` option.
+    pub const ENV_VAR: &'static str = "PANTS_DIGEST_FUNCTION";
+
+    ///
+    /// The function that this process computes digests with: selected by the `ENV_VAR`
+    /// environment variable when it is first used, and otherwise SHA-256.
+    ///
+    /// A process uses a single function, because digests of different functions cannot be
+    /// compared, and because values like `EMPTY_DIGEST` depend on it. Stores of other functions
+    /// may still be opened to read from them (see `fs_util`'s `migrate` command).
+    ///
+    /// Panics if the `ENV_VAR` environment variable is set to an unsupported value: entrypoints
+    /// should call `DigestFunction::from_env` first to report that as an error.
+    ///
+    pub fn current() -> DigestFunction {
+        static CURRENT: OnceLock<DigestFunction> = OnceLock::new();
+        *CURRENT.get_or_init(|| Self::from_env().unwrap_or_else(|e| panic!("{e}")))
+    }
+
+    ///
+    /// The function selected by the `ENV_VAR` environment variable, or SHA-256 if it is not set.
+    ///
+    pub fn from_env() -> Result<DigestFunction, String> {
+        match std::env::var(Self::ENV_VAR) {
+            Ok(value) => value
+                .parse()
+                .map_err(|e| format!("Invalid value for `{}`: {e}", Self::ENV_VAR)),
+            Err(std::env::VarError::NotPresent) => Ok(DigestFunction::default()),
+            Err(e) => Err(format!("Invalid value for `{}`: {e}", Self::ENV_VAR)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DigestFunction::Sha256 => "sha256",
+            DigestFunction::Blake3 => "blake3",
+        }
+    }
+
+    pub fn empty_digest(self) -> Digest {
+        Digest::of_bytes_with(self, &[])
+    }
+}
+
+impl fmt::Display for DigestFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for DigestFunction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DigestFunction::ALL
+            .into_iter()
+            .find(|function| function.name() == s)
+            .ok_or_else(|| {
+                format!("Unsupported digest function `{s}`: expected one of `sha256` or `blake3`.")
+            })
+    }
+}
+
 #[derive(Clone, Copy, DeepSizeOf, Eq, Hash, PartialEq, Ord, PartialOrd)]
 pub struct Fingerprint(pub [u8; FINGERPRINT_SIZE]);
 
@@ -250,37 +330,62 @@ impl Digest {
     }
 
     pub fn of_bytes(bytes: &[u8]) -> Self {
-        let mut hasher = Sha256::default();
-        hasher.update(bytes);
+        Self::of_bytes_with(DigestFunction::current(), bytes)
+    }
 
-        Digest::new(Fingerprint::from_bytes(hasher.finalize()), bytes.len())
+    pub fn of_bytes_with(function: DigestFunction, bytes: &[u8]) -> Self {
+        let mut hasher = Hasher::new_with(function);
+        hasher.update(bytes);
+        hasher.finish()
     }
 }
 
-/// A thin wrapper around a Sha256 hasher to preserve the length as well.
+#[derive(Clone)]
+enum HasherState {
+    Sha256(Sha256),
+    // NB: Boxed, because a BLAKE3 hasher is much larger than a SHA-256 one.
+    Blake3(Box<blake3::Hasher>),
+}
+
+/// A thin wrapper around a hasher of a `DigestFunction` to preserve the length as well.
+#[derive(Clone)]
 pub struct Hasher {
-    hasher: Sha256,
+    state: HasherState,
     byte_count: usize,
 }
 
 impl Hasher {
     pub fn new() -> Self {
+        Self::new_with(DigestFunction::current())
+    }
+
+    pub fn new_with(function: DigestFunction) -> Self {
+        let state = match function {
+            DigestFunction::Sha256 => HasherState::Sha256(Sha256::default()),
+            DigestFunction::Blake3 => HasherState::Blake3(Box::default()),
+        };
         Self {
-            hasher: Sha256::default(),
+            state,
             byte_count: 0,
         }
     }
 
     pub fn update(&mut self, buf: &[u8]) {
-        self.hasher.update(buf);
+        match &mut self.state {
+            HasherState::Sha256(hasher) => hasher.update(buf),
+            HasherState::Blake3(hasher) => {
+                hasher.update(buf);
+            }
+        }
         self.byte_count += buf.len();
     }
 
     pub fn finish(self) -> Digest {
-        Digest::new(
-            Fingerprint::from_bytes(self.hasher.finalize()),
-            self.byte_count,
-        )
+        let fingerprint = match self.state {
+            HasherState::Sha256(hasher) => Fingerprint::from_bytes(hasher.finalize()),
+            HasherState::Blake3(hasher) => Fingerprint(*hasher.finalize().as_bytes()),
+        };
+        Digest::new(fingerprint, self.byte_count)
     }
 }
 
@@ -294,8 +399,12 @@ pub struct WriterHasher<T> {
 
 impl<T> WriterHasher<T> {
     pub fn new(inner: T) -> WriterHasher<T> {
+        Self::new_with(DigestFunction::current(), inner)
+    }
+
+    pub fn new_with(function: DigestFunction, inner: T) -> WriterHasher<T> {
         WriterHasher {
-            hasher: Hasher::new(),
+            hasher: Hasher::new_with(function),
             inner: inner,
         }
     }
@@ -397,6 +506,50 @@ where
     Ok(hasher.finish().0)
 }
 
+///
+/// Hash the data from reader, overlapping reading it with hashing it.
+///
+/// SHA-256 cannot hash the chunks of its input in parallel, but the input can be read while the
+/// previous chunks of it are hashed (on a blocking thread), which is faster than
+/// `async_copy_and_hash` for large inputs, which are slow to both read and hash. Must be called
+/// within a tokio runtime.
+///
+pub async fn async_hash_overlapped<R>(reader: &mut R) -> tokio::io::Result<Digest>
+where
+    R: AsyncRead + Unpin,
+{
+    // NB: Bounded, so that at most a few chunks are buffered if reading is faster than hashing.
+    let (sender, mut receiver) = tokio::sync::mpsc::channel::<Vec<u8>>(2);
+    let hashing = tokio::task::spawn_blocking(move || {
+        let mut hasher = Hasher::new();
+        while let Some(chunk) = receiver.blocking_recv() {
+            hasher.update(&chunk);
+        }
+        hasher.finish()
+    });
+
+    loop {
+        let mut chunk = Vec::with_capacity(OVERLAPPED_CHUNK_SIZE);
+        while chunk.len() < OVERLAPPED_CHUNK_SIZE {
+            if reader.read_buf(&mut chunk).await? == 0 {
+                break;
+            }
+        }
+        if chunk.is_empty() {
+            break;
+        }
+        if sender.send(chunk).await.is_err() {
+            // The hashing task failed: its error is reported below.
+            break;
+        }
+    }
+    std::mem::drop(sender);
+
+    hashing
+        .await
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Hashing failed: {e}")))
+}
+
 ///
 /// Copy from reader to writer and return whether the copied data matches expected_digest.
 ///
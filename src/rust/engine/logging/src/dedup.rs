@@ -0,0 +1,120 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use log::Level;
+
+/// The identity of a record, for the purposes of deduplication.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(crate) struct RecordKey {
+    pub(crate) level: Level,
+    pub(crate) target: String,
+    pub(crate) message: String,
+}
+
+#[derive(Debug)]
+struct Repeats {
+    first_logged: Instant,
+    count: usize,
+}
+
+///
+/// A record which was repeated within the window after it was logged, and which should be logged
+/// again as `<message> (repeated <count> times)`.
+///
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct Repeated {
+    pub(crate) key: RecordKey,
+    pub(crate) count: usize,
+}
+
+impl Repeated {
+    pub(crate) fn message(&self) -> String {
+        format!("{} (repeated {} times)", self.key.message, self.count)
+    }
+}
+
+///
+/// Collapses identical records which are logged within a window of one another: the first record
+/// is logged, and the repeats of it are counted instead until the window expires.
+///
+/// NB: There is no timer: expired windows are only noticed when another record is logged, or when
+/// the logger is flushed.
+///
+#[derive(Debug)]
+pub(crate) struct Deduplicator {
+    window: Duration,
+    excluded_targets: HashSet<String>,
+    records: HashMap<RecordKey, Repeats>,
+}
+
+impl Deduplicator {
+    pub(crate) fn new(window: Duration, excluded_targets: HashSet<String>) -> Deduplicator {
+        Deduplicator {
+            window,
+            excluded_targets,
+            records: HashMap::new(),
+        }
+    }
+
+    ///
+    /// Observes a record at the given instant, returning whether it should be logged (rather than
+    /// counted as a repeat), and the records whose windows have expired.
+    ///
+    pub(crate) fn observe(&mut self, key: RecordKey, now: Instant) -> (bool, Vec<Repeated>) {
+        if self.window.is_zero() || self.excluded_targets.contains(&key.target) {
+            return (true, vec![]);
+        }
+        let window = self.window;
+        let expired = self.expire(|repeats| now.duration_since(repeats.first_logged) >= window);
+        let should_log = match self.records.get_mut(&key) {
+            Some(repeats) => {
+                repeats.count += 1;
+                false
+            }
+            None => {
+                self.records.insert(
+                    key,
+                    Repeats {
+                        first_logged: now,
+                        count: 0,
+                    },
+                );
+                true
+            }
+        };
+        (should_log, expired)
+    }
+
+    ///
+    /// Ends the windows of all records, returning those which were repeated.
+    ///
+    pub(crate) fn flush(&mut self) -> Vec<Repeated> {
+        self.expire(|_| true)
+    }
+
+    fn expire(&mut self, is_expired: impl Fn(&Repeats) -> bool) -> Vec<Repeated> {
+        let mut repeated = Vec::new();
+        self.records.retain(|key, repeats| {
+            if !is_expired(repeats) {
+                return true;
+            }
+            if repeats.count > 0 {
+                let count = repeats.count;
+                repeated.push((
+                    repeats.first_logged,
+                    Repeated {
+                        key: key.clone(),
+                        count,
+                    },
+                ));
+            }
+            false
+        });
+        // Log the repeated records in the order which they were first logged in.
+        repeated.sort_by_key(|(first_logged, _)| *first_logged);
+        repeated.into_iter().map(|(_, repeated)| repeated).collect()
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use log::Level;
+
+use crate::dedup::{Deduplicator, RecordKey, Repeated};
+
+fn key(target: &str, message: &str) -> RecordKey {
+    RecordKey {
+        level: Level::Warn,
+        target: target.to_owned(),
+        message: message.to_owned(),
+    }
+}
+
+fn deduplicator(excluded_targets: &[&str]) -> Deduplicator {
+    Deduplicator::new(
+        Duration::from_secs(10),
+        excluded_targets.iter().map(|t| t.to_string()).collect(),
+    )
+}
+
+#[test]
+fn collapses_repeats_within_the_window() {
+    let start = Instant::now();
+    let mut dedup = deduplicator(&[]);
+
+    assert_eq!(dedup.observe(key("a", "warning"), start), (true, vec![]));
+    assert_eq!(dedup.observe(key("b", "other"), start), (true, vec![]));
+    for secs in 1..=3 {
+        let now = start + Duration::from_secs(secs);
+        assert_eq!(dedup.observe(key("a", "warning"), now), (false, vec![]));
+    }
+    // The same message from another target is not a repeat.
+    assert_eq!(dedup.observe(key("b", "warning"), start), (true, vec![]));
+
+    let (should_log, repeated) =
+        dedup.observe(key("a", "warning"), start + Duration::from_secs(10));
+    assert!(should_log);
+    assert_eq!(
+        repeated,
+        vec![Repeated {
+            key: key("a", "warning"),
+            count: 3
+        }]
+    );
+    assert_eq!(repeated[0].message(), "warning (repeated 3 times)");
+}
+
+#[test]
+fn flushes_repeats() {
+    let start = Instant::now();
+    let mut dedup = deduplicator(&[]);
+    let messages = ["first", "second", "first", "second", "second"];
+    for (i, message) in messages.iter().enumerate() {
+        dedup.observe(key("a", message), start + Duration::from_millis(i as u64));
+    }
+
+    assert_eq!(
+        dedup.flush(),
+        vec![
+            Repeated {
+                key: key("a", "first"),
+                count: 1
+            },
+            Repeated {
+                key: key("a", "second"),
+                count: 2
+            },
+        ]
+    );
+    assert_eq!(dedup.flush(), vec![]);
+    assert_eq!(dedup.observe(key("a", "first"), start), (true, vec![]));
+}
+
+#[test]
+fn excludes_targets_and_disabled_windows() {
+    let now = Instant::now();
+    let mut dedup = deduplicator(&["a"]);
+    let mut disabled = Deduplicator::new(Duration::ZERO, HashSet::new());
+    for _ in 0..2 {
+        assert_eq!(dedup.observe(key("a", "warning"), now), (true, vec![]));
+        assert_eq!(disabled.observe(key("b", "warning"), now), (true, vec![]));
+    }
+    assert_eq!(dedup.flush(), vec![]);
+}
@@ -17,12 +17,48 @@ macro_rules! fatal_log {
     };
 }
 
+mod dedup;
+#[cfg(test)]
+mod dedup_tests;
+mod log_file;
+#[cfg(test)]
+mod log_file_tests;
 pub mod logger;
+mod system_log;
+#[cfg(test)]
+mod system_log_tests;
 
 pub type Logger = logger::PantsLogger;
+pub use log_file::LogRetention;
+pub use system_log::SystemLogSink;
+
+use std::str::FromStr;
 
 use num_enum::TryFromPrimitive;
 
+/// The format in which log records are rendered: see the `[GLOBAL].log_format` option.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum LogFormat {
+    /// A line of text for each record, for humans.
+    Text,
+    /// A JSON object on a line of its own for each record, for log ingestion tools.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!(
+                "Unrecognized log format: {s}. Should be one of `text` or `json`."
+            )),
+        }
+    }
+}
+
 // This is a hard-coding of constants in the standard logging python package.
 #[derive(Debug, Eq, PartialEq, TryFromPrimitive, Clone, Copy)]
 #[repr(u64)]
@@ -0,0 +1,177 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use nix::fcntl::{flock, FlockArg};
+
+/// The name of the index of per-run logs, in the directory which contains the directory of each
+/// run (see `index_run_log`).
+const RUN_LOG_INDEX_NAME: &str = "logs.index";
+
+///
+/// How the log file is rotated, and per-run logs are retained: see the `[GLOBAL].log_*` options.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LogRetention {
+    /// The size beyond which the log file is rotated, or `None` if it is never rotated.
+    pub max_size_bytes: Option<u64>,
+    /// The number of rotated log files to keep.
+    pub max_rotated_files: usize,
+    /// Whether to compress rotated log files with gzip.
+    pub compress_rotated_files: bool,
+    /// The number of per-run logs to keep.
+    pub max_run_logs: usize,
+}
+
+impl Default for LogRetention {
+    fn default() -> Self {
+        LogRetention {
+            max_size_bytes: None,
+            max_rotated_files: 0,
+            compress_rotated_files: false,
+            max_run_logs: usize::MAX,
+        }
+    }
+}
+
+///
+/// An append-only log file, which is rotated once it grows beyond a size: the file is renamed to
+/// `<name>.1` (or `<name>.1.gz` if compressed), after shifting any previously rotated files up by
+/// one, and removing the oldest.
+///
+pub(crate) struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    retention: LogRetention,
+}
+
+impl RotatingFile {
+    pub(crate) fn open(path: PathBuf, retention: LogRetention) -> io::Result<RotatingFile> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            file,
+            size,
+            retention,
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn write_all(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let len = bytes.len() as u64;
+        if let Some(max_size_bytes) = self.retention.max_size_bytes {
+            if self.size > 0 && self.size + len > max_size_bytes {
+                self.rotate()?;
+            }
+        }
+        self.file.write_all(bytes)?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotated_path(&self, index: usize, compressed: bool) -> PathBuf {
+        let mut name = OsString::from(self.path.as_os_str());
+        name.push(format!(".{index}"));
+        if compressed {
+            name.push(".gz");
+        }
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let max_rotated_files = self.retention.max_rotated_files;
+        // Rotated files may or may not be compressed, depending on the options of the process which
+        // rotated them.
+        for compressed in [false, true] {
+            remove_if_exists(&self.rotated_path(max_rotated_files, compressed))?;
+            for index in (1..max_rotated_files).rev() {
+                let from = self.rotated_path(index, compressed);
+                if from.exists() {
+                    fs::rename(&from, self.rotated_path(index + 1, compressed))?;
+                }
+            }
+        }
+
+        if max_rotated_files == 0 {
+            remove_if_exists(&self.path)?;
+        } else if self.retention.compress_rotated_files {
+            let mut encoder = GzEncoder::new(
+                File::create(self.rotated_path(1, true))?,
+                Compression::default(),
+            );
+            io::copy(&mut File::open(&self.path)?, &mut encoder)?;
+            encoder.finish()?;
+            fs::remove_file(&self.path)?;
+        } else {
+            fs::rename(&self.path, self.rotated_path(1, false))?;
+        }
+
+        *self = RotatingFile::open(self.path.clone(), self.retention)?;
+        Ok(())
+    }
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+///
+/// Adds the log of a run to the index of per-run logs, and removes the logs of the oldest runs in
+/// the index beyond `max_run_logs`.
+///
+/// The log of each run is expected to be in a directory of its own, which is removed along with
+/// the log if nothing else remains in it. The log being written (by the single run which holds the
+/// per-run log of the logger) is never removed, and the index is locked exclusively while it is
+/// updated, so that other processes sharing it (such as runs without `pantsd`) can prune it safely.
+///
+pub(crate) fn index_run_log(run_log: &Path, max_run_logs: usize) -> io::Result<()> {
+    let Some(index_dir) = run_log.parent().and_then(|run_dir| run_dir.parent()) else {
+        return Ok(());
+    };
+    let mut index = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(index_dir.join(RUN_LOG_INDEX_NAME))?;
+    // NB: The lock is released when the index is closed.
+    flock(index.as_raw_fd(), FlockArg::LockExclusive).map_err(io::Error::from)?;
+
+    let mut run_logs = BufReader::new(&index)
+        .lines()
+        .map(|line| line.map(PathBuf::from))
+        .collect::<io::Result<Vec<_>>>()?;
+    run_logs.retain(|path| path != run_log);
+    run_logs.push(run_log.to_owned());
+
+    let expired = run_logs.len().saturating_sub(max_run_logs.max(1));
+    for path in run_logs.drain(..expired) {
+        remove_if_exists(&path)?;
+        if let Some(run_dir) = path.parent() {
+            // Deliberately ignore failures to remove a directory which holds other files.
+            let _ = fs::remove_dir(run_dir);
+        }
+    }
+
+    index.set_len(0)?;
+    index.seek(SeekFrom::Start(0))?;
+    for path in run_logs {
+        writeln!(index, "{}", path.display())?;
+    }
+    Ok(())
+}
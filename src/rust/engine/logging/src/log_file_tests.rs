@@ -0,0 +1,98 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+
+use crate::log_file::{index_run_log, LogRetention, RotatingFile};
+
+fn retention(max_rotated_files: usize, compress_rotated_files: bool) -> LogRetention {
+    LogRetention {
+        max_size_bytes: Some(10),
+        max_rotated_files,
+        compress_rotated_files,
+        ..LogRetention::default()
+    }
+}
+
+fn read(path: &Path) -> String {
+    fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn rotates_beyond_max_size() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pants.log");
+    let mut file = RotatingFile::open(path.clone(), retention(2, false)).unwrap();
+    for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+        file.write_all(line.as_bytes()).unwrap();
+    }
+
+    assert_eq!(read(&path), "fourth\n");
+    assert_eq!(read(&dir.path().join("pants.log.1")), "third\n");
+    assert_eq!(read(&dir.path().join("pants.log.2")), "second\n");
+    assert!(!dir.path().join("pants.log.3").exists());
+}
+
+#[test]
+fn rotates_without_keeping_rotated_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pants.log");
+    let mut file = RotatingFile::open(path.clone(), retention(0, false)).unwrap();
+    for line in ["first\n", "second\n"] {
+        file.write_all(line.as_bytes()).unwrap();
+    }
+
+    assert_eq!(read(&path), "second\n");
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn compresses_rotated_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pants.log");
+    // A file which was rotated without compression is shifted along with the compressed files.
+    fs::write(dir.path().join("pants.log.1"), "zeroth\n").unwrap();
+    let mut file = RotatingFile::open(path.clone(), retention(3, true)).unwrap();
+    for line in ["first\n", "second\n"] {
+        file.write_all(line.as_bytes()).unwrap();
+    }
+
+    let mut decompressed = String::new();
+    GzDecoder::new(fs::File::open(dir.path().join("pants.log.1.gz")).unwrap())
+        .read_to_string(&mut decompressed)
+        .unwrap();
+    assert_eq!(decompressed, "first\n");
+    assert_eq!(read(&dir.path().join("pants.log.2")), "zeroth\n");
+    assert_eq!(read(&path), "second\n");
+}
+
+#[test]
+fn prunes_run_logs() {
+    let dir = tempfile::tempdir().unwrap();
+    let run_log = |run_id: &str| {
+        let path = dir.path().join(run_id).join("logs");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, run_id).unwrap();
+        path
+    };
+    let (a, b, c) = (run_log("a"), run_log("b"), run_log("c"));
+    // Other files in the directory of a run are left in place.
+    fs::write(dir.path().join("b").join("stats"), "").unwrap();
+
+    for path in [&a, &b, &c] {
+        index_run_log(path, 1).unwrap();
+    }
+
+    assert!(!dir.path().join("a").exists());
+    assert!(!b.exists());
+    assert!(dir.path().join("b").join("stats").exists());
+    assert_eq!(read(&c), "c");
+    assert_eq!(
+        read(&dir.path().join("logs.index")),
+        format!("{}\n", c.display())
+    );
+}
@@ -1,20 +1,24 @@
 // Copyright 2018 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
-use crate::PythonLogLevel;
+use crate::dedup::{Deduplicator, RecordKey, Repeated};
+use crate::log_file::{index_run_log, RotatingFile};
+use crate::system_log::SystemLog;
+use crate::{LogFormat, LogRetention, PythonLogLevel, SystemLogSink};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 use std::fmt::Write as FmtWrite;
 use std::fs::{File, OpenOptions};
 use std::io::Write;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use arc_swap::ArcSwap;
-use chrono::Timelike;
+use chrono::{SecondsFormat, Timelike};
 use colored::*;
 use lazy_static::lazy_static;
-use log::{debug, log, set_logger, set_max_level, Level, LevelFilter, Log, Metadata, Record};
+use log::{debug, log, set_logger, set_max_level, warn, Level, LevelFilter, Log, Metadata, Record};
 use parking_lot::Mutex;
 use regex::Regex;
 
@@ -26,13 +30,17 @@ lazy_static! {
 
 struct Inner {
     per_run_logs: Mutex<Option<File>>,
-    log_file: Mutex<Option<File>>,
+    log_file: Mutex<Option<RotatingFile>>,
+    log_retention: LogRetention,
+    system_log: Option<SystemLog>,
     global_level: LevelFilter,
     show_rust_3rdparty_logs: bool,
     show_target: bool,
+    log_format: LogFormat,
     log_level_filters: HashMap<String, log::LevelFilter>,
     literal_filters: Vec<String>,
     regex_filters: Vec<Regex>,
+    deduplicator: Mutex<Deduplicator>,
 }
 
 pub struct PantsLogger(ArcSwap<Inner>);
@@ -42,12 +50,16 @@ impl PantsLogger {
         PantsLogger(ArcSwap::from(Arc::new(Inner {
             per_run_logs: Mutex::new(None),
             log_file: Mutex::new(None),
+            log_retention: LogRetention::default(),
+            system_log: None,
             global_level: LevelFilter::Off,
             show_rust_3rdparty_logs: true,
             show_target: false,
+            log_format: LogFormat::Text,
             log_level_filters: HashMap::new(),
             literal_filters: Vec::new(),
             regex_filters: Vec::new(),
+            deduplicator: Mutex::new(Deduplicator::new(Duration::ZERO, HashSet::new())),
         })))
     }
 
@@ -55,10 +67,15 @@ impl PantsLogger {
         max_level: u64,
         show_rust_3rdparty_logs: bool,
         show_target: bool,
+        log_format: LogFormat,
         log_levels_by_target: HashMap<String, u64>,
         literal_filters: Vec<String>,
         regex_filters: Vec<Regex>,
         log_file_path: PathBuf,
+        log_retention: LogRetention,
+        system_log_sink: Option<SystemLogSink>,
+        dedup_window: Duration,
+        dedup_excluded_targets: HashSet<String>,
     ) -> Result<(), String> {
         let log_level_filters = log_levels_by_target
             .iter()
@@ -76,21 +93,26 @@ impl PantsLogger {
             .map_err(|e| format!("Unrecognised log level from Python: {max_level}: {e}"))?;
         let global_level: LevelFilter = max_python_level.into();
 
-        let log_file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(log_file_path)
+        let log_file = RotatingFile::open(log_file_path, log_retention)
             .map_err(|err| format!("Error opening pantsd logfile: {err}"))?;
+        let system_log = system_log_sink
+            .map(SystemLog::open)
+            .transpose()
+            .map_err(|err| format!("Error opening the system log: {err}"))?;
 
         PANTS_LOGGER.0.store(Arc::new(Inner {
             per_run_logs: Mutex::default(),
             log_file: Mutex::new(Some(log_file)),
+            log_retention,
+            system_log,
             global_level,
             show_rust_3rdparty_logs,
             show_target,
+            log_format,
             log_level_filters,
             literal_filters,
             regex_filters,
+            deduplicator: Mutex::new(Deduplicator::new(dedup_window, dedup_excluded_targets)),
         }));
 
         if set_logger(&*PANTS_LOGGER).is_err() {
@@ -106,6 +128,8 @@ impl PantsLogger {
     }
 
     pub fn set_per_run_logs(&self, per_run_log_path: Option<PathBuf>) {
+        // Log the repeats of records to the log of the run which they were repeated in.
+        self.flush();
         match per_run_log_path {
             None => {
                 *self.0.load().per_run_logs.lock() = None;
@@ -114,10 +138,14 @@ impl PantsLogger {
                 let file = OpenOptions::new()
                     .create(true)
                     .append(true)
-                    .open(path)
+                    .open(&path)
                     .map_err(|err| format!("Error opening per-run logfile: {err}"))
                     .unwrap();
-                *self.0.load().per_run_logs.lock() = Some(file);
+                let inner = self.0.load();
+                *inner.per_run_logs.lock() = Some(file);
+                if let Err(err) = index_run_log(&path, inner.log_retention.max_run_logs) {
+                    warn!("Failed to remove the logs of previous runs: {err}");
+                }
             }
         };
     }
@@ -182,64 +210,134 @@ impl Log for PantsLogger {
             return;
         }
 
-        let destination = stdio::get_destination();
-
-        // Build the message string.
-        let log_string = {
-            let mut log_string = {
-                let cur_date = chrono::Local::now();
-                format!(
-                    "{}.{:02}",
-                    cur_date.format(TIME_FORMAT_STR),
-                    cur_date.time().nanosecond() / 10_000_000 // Two decimal places of precision.
-                )
-            };
-
-            let use_color = destination.stderr_use_color();
-
-            let level = record.level();
-            let level_marker = match level {
-                _ if !use_color => format!("[{level}]").normal().clear(),
-                Level::Info => format!("[{level}]").normal(),
-                Level::Error => format!("[{level}]").red(),
-                Level::Warn => format!("[{level}]").yellow(),
-                Level::Debug => format!("[{level}]").green(),
-                Level::Trace => format!("[{level}]").magenta(),
-            };
-            write!(log_string, " {level_marker}").unwrap();
-
-            if inner.show_target {
-                write!(log_string, " ({})", record.target()).unwrap();
-            };
-            writeln!(log_string, " {log_msg}").unwrap();
-            log_string
+        let key = RecordKey {
+            level: record.level(),
+            target: record.target().to_owned(),
+            message: log_msg.clone(),
         };
-        let log_bytes = log_string.as_bytes();
+        let (should_log, repeated) = inner.deduplicator.lock().observe(key, Instant::now());
+        for repeated in repeated {
+            log_repeated(&inner, &repeated);
+        }
+        if should_log {
+            write_record(&inner, record, &log_msg);
+        }
+    }
 
-        {
-            let mut maybe_per_run_file = inner.per_run_logs.lock();
-            if let Some(ref mut file) = *maybe_per_run_file {
-                // deliberately ignore errors writing to per-run log file
-                let _ = file.write_all(log_bytes);
-            }
+    fn flush(&self) {
+        let inner = self.0.load();
+        let repeated = inner.deduplicator.lock().flush();
+        for repeated in repeated {
+            log_repeated(&inner, &repeated);
         }
+    }
+}
 
-        // Attempt to write to stdio, and write to the pantsd log if we fail (either because we don't
-        // have a valid stdio instance, or because of an error).
-        if destination.write_stderr_raw(log_bytes).is_err() {
-            let mut maybe_file = inner.log_file.lock();
-            if let Some(ref mut file) = *maybe_file {
-                match file.write_all(log_bytes) {
-                    Ok(()) => (),
-                    Err(e) => {
-                        // If we've failed to write to stdio, but also to our log file, our only recourse is to
-                        // try to write to a different file.
-                        fatal_log!("Failed to write to log file {:?}: {}", file, e);
-                    }
+fn log_repeated(inner: &Inner, repeated: &Repeated) {
+    let message = repeated.message();
+    write_record(
+        inner,
+        &Record::builder()
+            .args(format_args!("{message}"))
+            .level(repeated.key.level)
+            .target(&repeated.key.target)
+            .build(),
+        &message,
+    );
+}
+
+///
+/// Writes a record which has passed the filters of the logger to its destinations.
+///
+fn write_record(inner: &Inner, record: &Record, log_msg: &str) {
+    if let Some(ref system_log) = inner.system_log {
+        system_log.log(record, log_msg, current_workunit_id().as_deref());
+    }
+
+    let destination = stdio::get_destination();
+
+    // Build the message string.
+    let log_string = if inner.log_format == LogFormat::Json {
+        json_log_string(record, log_msg)
+    } else {
+        let mut log_string = {
+            let cur_date = chrono::Local::now();
+            format!(
+                "{}.{:02}",
+                cur_date.format(TIME_FORMAT_STR),
+                cur_date.time().nanosecond() / 10_000_000 // Two decimal places of precision.
+            )
+        };
+
+        let use_color = destination.stderr_use_color();
+
+        let level = record.level();
+        let level_marker = match level {
+            _ if !use_color => format!("[{level}]").normal().clear(),
+            Level::Info => format!("[{level}]").normal(),
+            Level::Error => format!("[{level}]").red(),
+            Level::Warn => format!("[{level}]").yellow(),
+            Level::Debug => format!("[{level}]").green(),
+            Level::Trace => format!("[{level}]").magenta(),
+        };
+        write!(log_string, " {level_marker}").unwrap();
+
+        if inner.show_target {
+            write!(log_string, " ({})", record.target()).unwrap();
+        };
+        writeln!(log_string, " {log_msg}").unwrap();
+        log_string
+    };
+    let log_bytes = log_string.as_bytes();
+
+    {
+        let mut maybe_per_run_file = inner.per_run_logs.lock();
+        if let Some(ref mut file) = *maybe_per_run_file {
+            // deliberately ignore errors writing to per-run log file
+            let _ = file.write_all(log_bytes);
+        }
+    }
+
+    // Attempt to write to stdio, and write to the pantsd log if we fail (either because we don't
+    // have a valid stdio instance, or because of an error).
+    if destination.write_stderr_raw(log_bytes).is_err() {
+        let mut maybe_file = inner.log_file.lock();
+        if let Some(ref mut file) = *maybe_file {
+            match file.write_all(log_bytes) {
+                Ok(()) => (),
+                Err(e) => {
+                    // If we've failed to write to stdio, but also to our log file, our only recourse is to
+                    // try to write to a different file.
+                    fatal_log!("Failed to write to log file {:?}: {}", file.path(), e);
                 }
             }
         }
     }
+}
+
+///
+/// The id of the workunit (if any) which a record is being logged in.
+///
+fn current_workunit_id() -> Option<String> {
+    workunit_store::get_workunit_store_handle()
+        .and_then(|handle| handle.parent_id)
+        .map(|span_id| span_id.to_string())
+}
 
-    fn flush(&self) {}
+///
+/// Renders a record as a JSON object on a single line, including the id of the workunit (if any)
+/// which the record was logged in.
+///
+fn json_log_string(record: &Record, message: &str) -> String {
+    let workunit_id = current_workunit_id();
+    let mut log_string = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        "level": record.level().as_str(),
+        "target": record.target(),
+        "workunit_id": workunit_id,
+        "message": message,
+    })
+    .to_string();
+    log_string.push('\n');
+    log_string
 }
@@ -0,0 +1,129 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::ffi::{CStr, CString};
+use std::io;
+use std::os::unix::net::UnixDatagram;
+use std::str::FromStr;
+
+use log::{Level, Record};
+
+/// The identifier which records are logged with.
+const IDENTIFIER: &CStr = c"pantsd";
+
+/// The socket of the native protocol of journald: see
+/// https://systemd.io/JOURNAL_NATIVE_PROTOCOL/.
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// The system log which `pantsd` forwards records to: see the `[GLOBAL].log_system_sink` option.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum SystemLogSink {
+    /// The local syslog daemon, via `syslog(3)`.
+    Syslog,
+    /// systemd-journald, via its native protocol, which preserves the fields of records.
+    Journald,
+}
+
+impl FromStr for SystemLogSink {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "syslog" => Ok(SystemLogSink::Syslog),
+            "journald" => Ok(SystemLogSink::Journald),
+            _ => Err(format!(
+                "Unrecognized system log sink: {s}. Should be one of `syslog` or `journald`."
+            )),
+        }
+    }
+}
+
+///
+/// The syslog priority of a level: journald uses the same priorities.
+///
+pub(crate) fn priority(level: Level) -> libc::c_int {
+    match level {
+        Level::Error => libc::LOG_ERR,
+        Level::Warn => libc::LOG_WARNING,
+        Level::Info => libc::LOG_INFO,
+        Level::Debug | Level::Trace => libc::LOG_DEBUG,
+    }
+}
+
+pub(crate) enum SystemLog {
+    Syslog,
+    Journald(UnixDatagram),
+}
+
+impl SystemLog {
+    pub(crate) fn open(sink: SystemLogSink) -> io::Result<SystemLog> {
+        match sink {
+            SystemLogSink::Syslog => {
+                // NB: `openlog` retains the identifier, which is why it must be static.
+                unsafe { libc::openlog(IDENTIFIER.as_ptr(), libc::LOG_PID, libc::LOG_DAEMON) };
+                Ok(SystemLog::Syslog)
+            }
+            SystemLogSink::Journald => {
+                let socket = UnixDatagram::unbound()?;
+                socket.connect(JOURNALD_SOCKET)?;
+                Ok(SystemLog::Journald(socket))
+            }
+        }
+    }
+
+    ///
+    /// Forwards a record, ignoring any failure: the record is also written to the log file.
+    ///
+    pub(crate) fn log(&self, record: &Record, message: &str, workunit_id: Option<&str>) {
+        match self {
+            SystemLog::Syslog => {
+                // NB: Messages are truncated at their first nul byte, rather than being dropped.
+                let message = message.split('\0').next().unwrap_or_default();
+                if let Ok(message) = CString::new(format!("[{}] {message}", record.target())) {
+                    unsafe {
+                        libc::syslog(priority(record.level()), c"%s".as_ptr(), message.as_ptr())
+                    };
+                }
+            }
+            SystemLog::Journald(socket) => {
+                let _ = socket.send(&journald_entry(record, message, workunit_id));
+            }
+        }
+    }
+}
+
+///
+/// Encodes a record as an entry of the native protocol of journald, with the structured fields
+/// of the record.
+///
+pub(crate) fn journald_entry(record: &Record, message: &str, workunit_id: Option<&str>) -> Vec<u8> {
+    let mut entry = Vec::new();
+    let identifier = IDENTIFIER.to_string_lossy();
+    let priority = priority(record.level()).to_string();
+    let line = record.line().map(|line| line.to_string());
+    let fields = [
+        ("MESSAGE", Some(message)),
+        ("PRIORITY", Some(priority.as_str())),
+        ("SYSLOG_IDENTIFIER", Some(identifier.as_ref())),
+        ("CODE_FILE", record.file()),
+        ("CODE_LINE", line.as_deref()),
+        ("PANTS_LOG_TARGET", Some(record.target())),
+        ("PANTS_WORKUNIT_ID", workunit_id),
+    ];
+    for (name, value) in fields {
+        let Some(value) = value else {
+            continue;
+        };
+        entry.extend_from_slice(name.as_bytes());
+        if value.contains('\n') {
+            // Values which contain newlines are prefixed with their length instead.
+            entry.push(b'\n');
+            entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            entry.push(b'=');
+        }
+        entry.extend_from_slice(value.as_bytes());
+        entry.push(b'\n');
+    }
+    entry
+}
@@ -0,0 +1,56 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use log::{Level, Record};
+
+use crate::system_log::{journald_entry, priority};
+
+#[test]
+fn maps_levels_to_priorities() {
+    assert_eq!(priority(Level::Error), 3);
+    assert_eq!(priority(Level::Warn), 4);
+    assert_eq!(priority(Level::Info), 6);
+    assert_eq!(priority(Level::Debug), 7);
+    assert_eq!(priority(Level::Trace), 7);
+}
+
+#[test]
+fn encodes_journald_entries() {
+    let args = format_args!("ignored");
+    let record = Record::builder()
+        .args(args)
+        .level(Level::Warn)
+        .target("pants.pantsd")
+        .file(Some("pantsd.rs"))
+        .line(Some(12))
+        .build();
+
+    assert_eq!(
+        String::from_utf8(journald_entry(&record, "A message", Some("abc"))).unwrap(),
+        "MESSAGE=A message\n\
+         PRIORITY=4\n\
+         SYSLOG_IDENTIFIER=pantsd\n\
+         CODE_FILE=pantsd.rs\n\
+         CODE_LINE=12\n\
+         PANTS_LOG_TARGET=pants.pantsd\n\
+         PANTS_WORKUNIT_ID=abc\n"
+    );
+}
+
+#[test]
+fn encodes_multiline_journald_values_with_their_length() {
+    let args = format_args!("ignored");
+    let record = Record::builder()
+        .args(args)
+        .level(Level::Info)
+        .target("pants")
+        .build();
+
+    let entry = journald_entry(&record, "two\nlines", None);
+
+    let mut expected = b"MESSAGE\n".to_vec();
+    expected.extend_from_slice(&9_u64.to_le_bytes());
+    expected.extend_from_slice(b"two\nlines\nPRIORITY=6\nSYSLOG_IDENTIFIER=pantsd\n");
+    expected.extend_from_slice(b"PANTS_LOG_TARGET=pants\n");
+    assert_eq!(entry, expected);
+}
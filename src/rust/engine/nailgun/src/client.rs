@@ -3,6 +3,7 @@
 
 use std::io;
 use std::net::Ipv4Addr;
+use std::path::PathBuf;
 
 use futures::channel::mpsc;
 use futures::{try_join, SinkExt, Stream, StreamExt};
@@ -12,6 +13,8 @@ use tokio::io::AsyncWriteExt;
 use tokio::net::TcpStream;
 use tokio::signal::unix::{signal, Signal, SignalKind};
 
+use crate::remote::{self, RemoteClientConfig};
+
 pub enum NailgunClientError {
     PreConnect(String),
     PostConnect(String),
@@ -98,19 +101,9 @@ pub async fn client_execute(
     args: Vec<String>,
     env: Vec<(String, String)>,
 ) -> Result<i32, NailgunClientError> {
-    use nails::execution::{child_channel, Command};
-
     let working_dir =
         std::env::current_dir().map_err(|e| NailgunClientError::PreConnect(e.to_string()))?;
 
-    let config = Config::default();
-    let command = Command {
-        command,
-        args,
-        env,
-        working_dir,
-    };
-
     let signal_stream = signal(SignalKind::interrupt()).map_err(|err| {
         NailgunClientError::PreConnect(format!("Failed to install interrupt handler: {err}"))
     })?;
@@ -120,6 +113,53 @@ pub async fn client_execute(
             NailgunClientError::PreConnect(format!("Failed to connect to localhost: {err}"))
         })?;
 
+    execute(socket, signal_stream, command, args, env, working_dir).await
+}
+
+///
+/// Execute the given command on the given remote server (see the `remote` module), in the given
+/// working directory, relative to the root of the server's workspace.
+///
+/// The TTYs of the client are not available to the server: so no `NAILGUN_TTY_PATH_*` variables
+/// should be sent, and all stdio is sent over the connection.
+///
+/// See `client_execute` for the signal handling which this method installs.
+///
+pub async fn client_execute_remote(
+    config: RemoteClientConfig,
+    working_dir: PathBuf,
+    command: String,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+) -> Result<i32, NailgunClientError> {
+    let signal_stream = signal(SignalKind::interrupt()).map_err(|err| {
+        NailgunClientError::PreConnect(format!("Failed to install interrupt handler: {err}"))
+    })?;
+    let socket = remote::connect(config)
+        .await
+        .map_err(NailgunClientError::PreConnect)?;
+
+    execute(socket, signal_stream, command, args, env, working_dir).await
+}
+
+async fn execute(
+    socket: TcpStream,
+    signal_stream: Signal,
+    command: String,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    working_dir: PathBuf,
+) -> Result<i32, NailgunClientError> {
+    use nails::execution::{child_channel, Command};
+
+    let config = Config::default();
+    let command = Command {
+        command,
+        args,
+        env,
+        working_dir,
+    };
+
     let mut child = nails::client::handle_connection(config, socket, command, async {
         let (stdin_write, stdin_read) = child_channel::<ChildInput>();
         let _input_handler = tokio::spawn(handle_client_input(stdin_write));
@@ -5,8 +5,10 @@
 mod tests;
 
 mod client;
+mod remote;
 mod server;
 
-pub use client::{client_execute, NailgunClientError};
+pub use client::{client_execute, client_execute_remote, NailgunClientError};
 pub use nails::execution::ExitCode;
+pub use remote::{RemoteClientConfig, RemoteServerConfig};
 pub use server::{RawFdExecution, Server};
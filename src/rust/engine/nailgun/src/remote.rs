@@ -0,0 +1,287 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+//! Connections to a nailgun server from other machines: over TCP with TLS, and authenticated by a
+//! token which is shared by the client and the server.
+//!
+//! A remote connection starts with the client sending the token on a line of its own, to which the
+//! server replies with a line which is `OK` if the token matched. The connection then continues
+//! with the nailgun protocol. Since `nails` only speaks the protocol over a `TcpStream`, each side
+//! pipes the TLS stream through a loopback connection.
+
+use std::io;
+use std::net::Ipv4Addr;
+use std::path::{Component, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{debug, warn};
+use nails::execution::Command;
+use nails::Nail;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::rustls::{ClientConfig, ServerConfig, ServerName};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+// The reply of the server to a client whose token matched.
+const AUTHENTICATED: &str = "OK";
+// The longest token line that the server will read.
+const MAX_TOKEN_LENGTH: u64 = 4096;
+// How long a client has to complete the TLS handshake and send its token.
+const AUTHENTICATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+///
+/// The configuration of a nailgun server's listener for remote connections.
+///
+pub struct RemoteServerConfig {
+    /// The address to listen on: e.g. `0.0.0.0:7777`.
+    pub address: String,
+    /// The PEM-encoded certificate chain of the server.
+    pub cert_chain_pem: Vec<u8>,
+    /// The PEM-encoded private key of the server's certificate.
+    pub key_pem: Vec<u8>,
+    /// The token that clients must send before running commands.
+    pub token: String,
+}
+
+///
+/// The configuration of a client's connection to a remote nailgun server.
+///
+pub struct RemoteClientConfig {
+    /// The `host:port` of the server.
+    pub address: String,
+    /// PEM-encoded CA certificates to verify the server's certificate with, rather than the
+    /// native ones.
+    pub root_ca_certs_pem: Option<Vec<u8>>,
+    /// The token to authenticate with.
+    pub token: String,
+}
+
+///
+/// Binds a listener for remote connections.
+///
+pub(crate) async fn bind(config: RemoteServerConfig) -> Result<RemoteListener, String> {
+    let tls = grpc_util::tls::MtlsConfig::from_pem_buffers(&config.cert_chain_pem, &config.key_pem)
+        .map_err(|e| format!("Invalid certificate or key for remote connections: {e}"))?;
+    let server_config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(tls.cert_chain, tls.key)
+        .map_err(|e| format!("Invalid certificate or key for remote connections: {e}"))?;
+    let listener = TcpListener::bind(&config.address)
+        .await
+        .map_err(|e| format!("Could not bind to {}: {e:?}", config.address))?;
+    Ok(RemoteListener {
+        listener,
+        acceptor: TlsAcceptor::from(Arc::new(server_config)),
+        token: Arc::new(config.token),
+    })
+}
+
+pub(crate) struct RemoteListener {
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    token: Arc<String>,
+}
+
+impl RemoteListener {
+    pub(crate) fn local_addr(&self) -> Result<std::net::SocketAddr, String> {
+        self.listener
+            .local_addr()
+            .map_err(|e| format!("No local address for remote listener: {e:?}"))
+    }
+
+    ///
+    /// Accepts remote connections until the receiver of `connections` is dropped, and sends the
+    /// loopback end of each authenticated connection to it.
+    ///
+    pub(crate) async fn accept_loop(self, connections: mpsc::Sender<TcpStream>) {
+        loop {
+            let tcp_stream = tokio::select! {
+                accepted = self.listener.accept() => match accepted {
+                    Ok((tcp_stream, _addr)) => tcp_stream,
+                    Err(e) => {
+                        warn!("Server failed to accept remote connections: {e}");
+                        break;
+                    }
+                },
+                _ = connections.closed() => break,
+            };
+            let acceptor = self.acceptor.clone();
+            let token = self.token.clone();
+            let connections = connections.clone();
+            tokio::spawn(async move {
+                let peer = tcp_stream.peer_addr().ok();
+                if let Err(e) = Self::handle(acceptor, &token, tcp_stream, connections).await {
+                    debug!("Remote connection from {peer:?} failed: {e}");
+                }
+            });
+        }
+    }
+
+    async fn handle(
+        acceptor: TlsAcceptor,
+        token: &str,
+        tcp_stream: TcpStream,
+        connections: mpsc::Sender<TcpStream>,
+    ) -> io::Result<()> {
+        let authenticate = async {
+            let mut tls_stream = BufReader::new(acceptor.accept(tcp_stream).await?);
+            let mut line = String::new();
+            (&mut tls_stream)
+                .take(MAX_TOKEN_LENGTH)
+                .read_line(&mut line)
+                .await?;
+            let authenticated = tokens_match(token, line.trim_end_matches('\n'));
+            let reply = if authenticated {
+                AUTHENTICATED
+            } else {
+                "Unauthorized"
+            };
+            tls_stream
+                .write_all(format!("{reply}\n").as_bytes())
+                .await?;
+            tls_stream.flush().await?;
+            Ok::<_, io::Error>((tls_stream, authenticated))
+        };
+        let (mut tls_stream, authenticated) =
+            tokio::time::timeout(AUTHENTICATION_TIMEOUT, authenticate)
+                .await
+                .map_err(|_| {
+                    io::Error::new(io::ErrorKind::TimedOut, "Timed out authenticating.")
+                })??;
+        if !authenticated {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "The token did not match.",
+            ));
+        }
+
+        let (mut local_end, server_end) = loopback_pair().await?;
+        connections
+            .send(server_end)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "The server has exited."))?;
+        tokio::io::copy_bidirectional(&mut tls_stream, &mut local_end).await?;
+        Ok(())
+    }
+}
+
+///
+/// Compares every byte of the tokens, so that the time taken does not reveal how much of a token
+/// matched.
+///
+fn tokens_match(expected: &str, actual: &str) -> bool {
+    expected.len() == actual.len()
+        && expected
+            .bytes()
+            .zip(actual.bytes())
+            .fold(0, |acc, (e, a)| acc | (e ^ a))
+            == 0
+}
+
+///
+/// Connects to the given remote server, and returns the loopback end of the authenticated
+/// connection to it.
+///
+pub(crate) async fn connect(config: RemoteClientConfig) -> Result<TcpStream, String> {
+    let tls_config: ClientConfig =
+        grpc_util::tls::Config::new(config.root_ca_certs_pem.as_deref(), None)?.try_into()?;
+    let host = {
+        let (host, _port) = config.address.rsplit_once(':').ok_or_else(|| {
+            format!(
+                "The address of a remote pantsd should be a `host:port`, but was {}.",
+                config.address
+            )
+        })?;
+        host.trim_start_matches('[').trim_end_matches(']')
+    };
+    let server_name = ServerName::try_from(host)
+        .map_err(|e| format!("Invalid host name in {}: {e}", config.address))?;
+
+    let tcp_stream = TcpStream::connect(&config.address)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {e}", config.address))?;
+    let tls_stream = TlsConnector::from(Arc::new(tls_config))
+        .connect(server_name, tcp_stream)
+        .await
+        .map_err(|e| format!("Failed to establish TLS with {}: {e}", config.address))?;
+    let mut tls_stream = BufReader::new(tls_stream);
+    let mut reply = String::new();
+    async {
+        tls_stream
+            .write_all(format!("{}\n", config.token).as_bytes())
+            .await?;
+        tls_stream.flush().await?;
+        (&mut tls_stream)
+            .take(MAX_TOKEN_LENGTH)
+            .read_line(&mut reply)
+            .await
+    }
+    .await
+    .map_err(|e| format!("Failed to authenticate with {}: {e}", config.address))?;
+    if reply.trim_end() != AUTHENTICATED {
+        return Err(format!(
+            "The pantsd at {} did not accept the token: check `--pantsd-remote-token`.",
+            config.address
+        ));
+    }
+
+    let (client_end, mut local_end) = loopback_pair()
+        .await
+        .map_err(|e| format!("Failed to open a loopback connection: {e}"))?;
+    tokio::spawn(async move {
+        if let Err(e) = tokio::io::copy_bidirectional(&mut tls_stream, &mut local_end).await {
+            debug!("Remote connection failed: {e}");
+        }
+    });
+    Ok(client_end)
+}
+
+///
+/// Opens a connection on the loopback interface, and returns both of its ends.
+///
+async fn loopback_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+    let connected = TcpStream::connect(listener.local_addr()?).await?;
+    let (accepted, peer) = listener.accept().await?;
+    // Another process could have connected to the listener first.
+    if peer != connected.local_addr()? {
+        return Err(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            "An unexpected peer connected to the loopback listener.",
+        ));
+    }
+    Ok((connected, accepted))
+}
+
+///
+/// A Nail for the commands of remote clients, which run in the server's own environment and
+/// workspace.
+///
+/// The client's environment is applied over the server's, except for the TTY paths of the client,
+/// which do not exist on the server. And the working directory of the command is made relative, to
+/// be resolved against the root of the server's workspace.
+///
+#[derive(Clone)]
+pub(crate) struct RemoteNail<N: Nail> {
+    pub(crate) nail: N,
+}
+
+impl<N: Nail> Nail for RemoteNail<N> {
+    fn spawn(&self, mut cmd: Command) -> Result<nails::server::Child, io::Error> {
+        let client_env = std::mem::take(&mut cmd.env)
+            .into_iter()
+            .filter(|(name, _)| !name.starts_with("NAILGUN_TTY_PATH_"));
+        cmd.env = std::env::vars().chain(client_env).collect();
+        if !cmd
+            .working_dir
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            cmd.working_dir = PathBuf::new();
+        }
+        self.nail.spawn(cmd)
+    }
+}
@@ -4,7 +4,7 @@
 use std::collections::HashMap;
 use std::fs::OpenOptions;
 use std::io;
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, SocketAddr};
 use std::os::fd::OwnedFd;
 use std::os::unix::io::{AsRawFd, RawFd};
 use std::path::PathBuf;
@@ -19,14 +19,17 @@ use nails::execution::{self, child_channel, sink_for, ChildInput, ChildOutput, E
 use nails::Nail;
 use task_executor::Executor;
 use tokio::fs::File;
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::{mpsc, Notify, RwLock};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 
+use crate::remote::{self, RemoteNail, RemoteServerConfig};
+
 pub struct Server {
     exit_sender: oneshot::Sender<()>,
     exited_receiver: oneshot::Receiver<Result<(), String>>,
     port: u16,
+    remote_address: Option<SocketAddr>,
 }
 
 impl Server {
@@ -40,6 +43,19 @@ impl Server {
         executor: Executor,
         port_requested: u16,
         runner: impl RawFdRunner + 'static,
+    ) -> Result<Server, String> {
+        Self::new_with_remote(executor, port_requested, None, runner).await
+    }
+
+    ///
+    /// Spawn the server on a background Task, additionally listening for remote connections (see
+    /// the `remote` module) if a config is given for them.
+    ///
+    pub async fn new_with_remote(
+        executor: Executor,
+        port_requested: u16,
+        remote: Option<RemoteServerConfig>,
+        runner: impl RawFdRunner + 'static,
     ) -> Result<Server, String> {
         let listener = TcpListener::bind((Ipv4Addr::new(127, 0, 0, 1), port_requested))
             .await
@@ -49,6 +65,16 @@ impl Server {
             .map_err(|e| format!("No local address for listener: {e:?}"))?
             .port();
 
+        let (remote_address, remote_connections) = if let Some(remote) = remote {
+            let remote_listener = remote::bind(remote).await?;
+            let remote_address = remote_listener.local_addr()?;
+            let (sender, receiver) = mpsc::channel(1);
+            let _join = executor.native_spawn(remote_listener.accept_loop(sender));
+            (Some(remote_address), Some(receiver))
+        } else {
+            (None, None)
+        };
+
         // NB: The C client requires noisy_stdin (see the `nails` crate for more info), but neither
         // `nails` nor the pants python client do.
         let config = nails::Config::default().noisy_stdin(false);
@@ -67,12 +93,14 @@ impl Server {
             exit_receiver,
             exited_sender,
             listener,
+            remote_connections,
         ));
 
         Ok(Server {
             exit_sender,
             exited_receiver,
             port: port_actual,
+            remote_address,
         })
     }
 
@@ -86,8 +114,17 @@ impl Server {
         should_exit: oneshot::Receiver<()>,
         exited: oneshot::Sender<Result<(), String>>,
         listener: TcpListener,
+        remote_connections: Option<mpsc::Receiver<TcpStream>>,
     ) {
-        let exit_result = Self::accept_loop(executor, config, nail, should_exit, listener).await;
+        let exit_result = Self::accept_loop(
+            executor,
+            config,
+            nail,
+            should_exit,
+            listener,
+            remote_connections,
+        )
+        .await;
         info!("Server exiting with {:?}", exit_result);
         let _ = exited.send(exit_result);
     }
@@ -98,49 +135,50 @@ impl Server {
         nail: impl Nail,
         mut should_exit: oneshot::Receiver<()>,
         listener: TcpListener,
+        mut remote_connections: Option<mpsc::Receiver<TcpStream>>,
     ) -> Result<(), String> {
         // While connections are ongoing, they acquire `read`; before shutting down, the server
         // acquires `write`.
         let ongoing_connections = Arc::new(RwLock::new(()));
 
         let result = loop {
-            let tcp_stream = match future::select(listener.accept().boxed(), should_exit).await {
-                future::Either::Left((Ok((tcp_stream, _addr)), s_e)) => {
-                    // Got a connection.
-                    should_exit = s_e;
-                    tcp_stream
-                }
-                future::Either::Left((Err(e), _)) => {
-                    break Err(format!("Server failed to accept connections: {e}"));
-                }
-                future::Either::Right((_, _)) => {
-                    break Ok(());
+            let remote_connection = async {
+                match remote_connections.as_mut() {
+                    Some(receiver) => receiver.recv().await,
+                    None => future::pending().await,
                 }
             };
-
-            debug!("Accepted connection: {:?}", tcp_stream);
-
-            // There is a slightly delicate dance here: we wait for a connection to have acquired the
-            // ongoing connections lock before proceeding to the next iteration of the loop. This
-            // prevents us from observing an empty lock and exiting before the connection has actually
-            // acquired it. Unfortunately we cannot acquire the lock in this thread and then send the
-            // guard to the other thread due to its lifetime bounds.
-            let connection_started = Arc::new(Notify::new());
-            let _join = executor.native_spawn({
-                let config = config.clone();
-                let nail = nail.clone();
-                let connection_started = connection_started.clone();
-                let ongoing_connections = ongoing_connections.clone();
-                async move {
-                    let ongoing_connection_guard = ongoing_connections.read().await;
-                    connection_started.notify_one();
-                    let result = nails::server::handle_connection(config, nail, tcp_stream).await;
-                    std::mem::drop(ongoing_connection_guard);
-                    result
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((tcp_stream, _addr)) => {
+                        debug!("Accepted connection: {:?}", tcp_stream);
+                        Self::spawn_connection(
+                            &executor,
+                            config.clone(),
+                            nail.clone(),
+                            tcp_stream,
+                            &ongoing_connections,
+                        )
+                        .await;
+                    }
+                    Err(e) => break Err(format!("Server failed to accept connections: {e}")),
+                },
+                Some(tcp_stream) = remote_connection => {
+                    debug!("Accepted remote connection: {:?}", tcp_stream);
+                    Self::spawn_connection(
+                        &executor,
+                        config.clone(),
+                        RemoteNail { nail: nail.clone() },
+                        tcp_stream,
+                        &ongoing_connections,
+                    )
+                    .await;
                 }
-            });
-            connection_started.notified().await;
+                _ = &mut should_exit => break Ok(()),
+            }
         };
+        // Stop accepting remote connections.
+        std::mem::drop(remote_connections);
 
         // Before exiting, acquire write access on the ongoing_connections lock to prove that all
         // connections have completed.
@@ -150,6 +188,33 @@ impl Server {
         result
     }
 
+    async fn spawn_connection(
+        executor: &Executor,
+        config: nails::Config,
+        nail: impl Nail,
+        tcp_stream: TcpStream,
+        ongoing_connections: &Arc<RwLock<()>>,
+    ) {
+        // There is a slightly delicate dance here: we wait for a connection to have acquired the
+        // ongoing connections lock before the server accepts another connection. This prevents us
+        // from observing an empty lock and exiting before the connection has actually acquired it.
+        // Unfortunately we cannot acquire the lock in this thread and then send the guard to the
+        // other thread due to its lifetime bounds.
+        let connection_started = Arc::new(Notify::new());
+        let _join = executor.native_spawn({
+            let connection_started = connection_started.clone();
+            let ongoing_connections = ongoing_connections.clone();
+            async move {
+                let ongoing_connection_guard = ongoing_connections.read().await;
+                connection_started.notify_one();
+                let result = nails::server::handle_connection(config, nail, tcp_stream).await;
+                std::mem::drop(ongoing_connection_guard);
+                result
+            }
+        });
+        connection_started.notified().await;
+    }
+
     ///
     /// The port that the server is listening on.
     ///
@@ -157,6 +222,13 @@ impl Server {
         self.port
     }
 
+    ///
+    /// The address that the server is listening on for remote connections, if any.
+    ///
+    pub fn remote_address(&self) -> Option<SocketAddr> {
+        self.remote_address
+    }
+
     ///
     /// Returns a Future that will shut down the server by:
     /// 1. stopping accepting new connections
@@ -1,12 +1,19 @@
 // Copyright 2022 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
-use crate::Server;
+use crate::{remote, RemoteClientConfig, RemoteServerConfig, Server};
 
+use std::fs::File;
+use std::io::Read;
+use std::iter;
+use std::mem::ManuallyDrop;
+use std::os::unix::io::FromRawFd;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use futures::{future, FutureExt};
+use bytes::Bytes;
+use futures::{future, FutureExt, SinkExt};
 use nails::execution::{child_channel, ChildInput, Command, ExitCode};
 use nails::Config;
 use task_executor::Executor;
@@ -79,7 +86,212 @@ async fn shutdown_awaits_ongoing() {
     server_shutdown.await.unwrap().unwrap();
 }
 
+#[tokio::test]
+async fn binary_stdin() {
+    // Every byte value, in more chunks than fit in the stdin channel at once.
+    let chunk = Bytes::from((0..=255).collect::<Vec<u8>>());
+    let chunks = vec![chunk; 64];
+    let expected = chunks.concat();
+    let server = Server::new(Executor::new(), 0, move |execution| {
+        // NB: The caller closes the file handle.
+        let mut stdin = ManuallyDrop::new(unsafe { File::from_raw_fd(execution.stdin_fd) });
+        let mut actual = Vec::new();
+        stdin.read_to_end(&mut actual).unwrap();
+        ExitCode(if actual == expected { 0 } else { 1 })
+    })
+    .await
+    .unwrap();
+
+    let exit_code = run_client_with_stdin(server.port(), chunks.into_iter())
+        .await
+        .unwrap();
+    assert_eq!(ExitCode(0), exit_code);
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn stdin_is_backpressured() {
+    // Far more stdin than fits in the channels and socket buffers between the client and the nail.
+    let chunk = Bytes::from(vec![0; 64 * 1024]);
+    let chunk_count = 2048;
+    let expected_len = chunk_count * chunk.len();
+    // A server which waits for a signal before reading its stdin.
+    let should_read_stdin = Arc::new(Notify::new());
+    let server = Server::new(Executor::new(), 0, {
+        let should_read_stdin = should_read_stdin.clone();
+        move |execution| {
+            tokio::runtime::Handle::current().block_on(should_read_stdin.notified());
+            // NB: The caller closes the file handle.
+            let mut stdin = ManuallyDrop::new(unsafe { File::from_raw_fd(execution.stdin_fd) });
+            let mut actual = Vec::new();
+            stdin.read_to_end(&mut actual).unwrap();
+            ExitCode(if actual.len() == expected_len { 0 } else { 1 })
+        }
+    })
+    .await
+    .unwrap();
+
+    let taken = Arc::new(AtomicUsize::new(0));
+    let stdin = {
+        let taken = taken.clone();
+        (0..chunk_count).map(move |_| {
+            taken.fetch_add(1, Ordering::SeqCst);
+            chunk.clone()
+        })
+    };
+    let client_completed = tokio::spawn(run_client_with_stdin(server.port(), stdin));
+
+    // Confirm that the client stops sending stdin while the nail is not reading it.
+    sleep(Duration::from_millis(500)).await;
+    let taken_before_reading = taken.load(Ordering::SeqCst);
+    assert!(
+        taken_before_reading < chunk_count / 2,
+        "{taken_before_reading} of {chunk_count} chunks were sent before stdin was read."
+    );
+
+    // Then signal the nail to read its stdin, and confirm that all of it arrives.
+    should_read_stdin.notify_one();
+    assert_eq!(ExitCode(0), client_completed.await.unwrap().unwrap());
+    assert_eq!(chunk_count, taken.load(Ordering::SeqCst));
+    server.shutdown().await.unwrap();
+}
+
+fn test_cert(name: &str) -> Vec<u8> {
+    std::fs::read(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test-certs")
+            .join(name),
+    )
+    .unwrap()
+}
+
+async fn remote_server(
+    runner: impl Fn(crate::RawFdExecution) -> ExitCode + Send + Sync + 'static,
+) -> Server {
+    let remote = RemoteServerConfig {
+        address: "127.0.0.1:0".to_owned(),
+        cert_chain_pem: test_cert("cert.pem"),
+        key_pem: test_cert("key.pem"),
+        token: "secret".to_owned(),
+    };
+    Server::new_with_remote(Executor::new(), 0, Some(remote), runner)
+        .await
+        .unwrap()
+}
+
+fn remote_client_config(server: &Server, token: &str) -> RemoteClientConfig {
+    RemoteClientConfig {
+        address: format!("localhost:{}", server.remote_address().unwrap().port()),
+        root_ca_certs_pem: Some(test_cert("ca.pem")),
+        token: token.to_owned(),
+    }
+}
+
+#[tokio::test]
+async fn remote_accept() {
+    let commands = Arc::new(Mutex::new(Vec::new()));
+    let server = remote_server({
+        let commands = commands.clone();
+        move |execution| {
+            commands.lock().unwrap().push(execution.cmd);
+            ExitCode(42)
+        }
+    })
+    .await;
+
+    let stream = remote::connect(remote_client_config(&server, "secret"))
+        .await
+        .unwrap();
+    let cmd = Command {
+        command: "nothing".to_owned(),
+        args: vec![],
+        env: vec![
+            ("PANTS_REMOTE_TEST".to_owned(), "client".to_owned()),
+            ("NAILGUN_TTY_PATH_1".to_owned(), "/dev/pts/0".to_owned()),
+        ],
+        working_dir: PathBuf::from("src/python"),
+    };
+    assert_eq!(
+        ExitCode(42),
+        run_command(stream, cmd, iter::empty()).await.unwrap()
+    );
+    server.shutdown().await.unwrap();
+
+    let commands = commands.lock().unwrap();
+    let env = &commands[0].env;
+    // The client's variables are applied over the server's, except for its TTY paths.
+    assert!(env.contains(&("PANTS_REMOTE_TEST".to_owned(), "client".to_owned())));
+    assert!(env.iter().any(|(name, _)| name == "PATH"));
+    assert!(!env
+        .iter()
+        .any(|(name, _)| name.starts_with("NAILGUN_TTY_PATH_")));
+    assert_eq!(PathBuf::from("src/python"), commands[0].working_dir);
+}
+
+#[tokio::test]
+async fn remote_absolute_working_dir() {
+    let working_dirs = Arc::new(Mutex::new(Vec::new()));
+    let server = remote_server({
+        let working_dirs = working_dirs.clone();
+        move |execution| {
+            working_dirs.lock().unwrap().push(execution.cmd.working_dir);
+            ExitCode(0)
+        }
+    })
+    .await;
+
+    for working_dir in ["/etc", "../elsewhere"] {
+        let stream = remote::connect(remote_client_config(&server, "secret"))
+            .await
+            .unwrap();
+        let cmd = Command {
+            command: "nothing".to_owned(),
+            args: vec![],
+            env: vec![],
+            working_dir: PathBuf::from(working_dir),
+        };
+        run_command(stream, cmd, iter::empty()).await.unwrap();
+    }
+    server.shutdown().await.unwrap();
+
+    // Working directories outside of the server's workspace are replaced with its root.
+    assert_eq!(
+        vec![PathBuf::new(), PathBuf::new()],
+        *working_dirs.lock().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn remote_rejects_token() {
+    let server = remote_server(|_| ExitCode(0)).await;
+
+    let err = remote::connect(remote_client_config(&server, "guess"))
+        .await
+        .unwrap_err();
+    assert!(err.contains("did not accept the token"), "{err}");
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn remote_rejects_untrusted_server() {
+    let server = remote_server(|_| ExitCode(0)).await;
+
+    let mut config = remote_client_config(&server, "secret");
+    // The server's certificate is not signed by this (self-signed) CA.
+    config.root_ca_certs_pem = Some(test_cert("cert.pem"));
+    let err = remote::connect(config).await.unwrap_err();
+    assert!(err.contains("Failed to establish TLS"), "{err}");
+    server.shutdown().await.unwrap();
+}
+
 async fn run_client(port: u16) -> Result<ExitCode, String> {
+    run_client_with_stdin(port, iter::empty()).await
+}
+
+async fn run_client_with_stdin(
+    port: u16,
+    stdin: impl Iterator<Item = Bytes> + Send + 'static,
+) -> Result<ExitCode, String> {
     let cmd = Command {
         command: "nothing".to_owned(),
         args: vec![],
@@ -87,8 +299,22 @@ async fn run_client(port: u16) -> Result<ExitCode, String> {
         working_dir: PathBuf::from("/dev/null"),
     };
     let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    run_command(stream, cmd, stdin).await
+}
+
+async fn run_command(
+    stream: TcpStream,
+    cmd: Command,
+    stdin: impl Iterator<Item = Bytes> + Send + 'static,
+) -> Result<ExitCode, String> {
     let child = nails::client::handle_connection(Config::default(), stream, cmd, async {
-        let (_stdin_write, stdin_read) = child_channel::<ChildInput>();
+        let (mut stdin_write, stdin_read) = child_channel::<ChildInput>();
+        // Dropping the sender once all of the chunks have been sent closes stdin.
+        tokio::spawn(async move {
+            for chunk in stdin {
+                stdin_write.send(ChildInput::Stdin(chunk)).await.unwrap();
+            }
+        });
         stdin_read
     })
     .await
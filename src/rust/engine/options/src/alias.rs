@@ -0,0 +1,69 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+
+///
+/// Expands any of the given args which are aliases defined by the `[cli].alias` option, up to the
+/// passthrough args delimiter (`--`).
+///
+/// An alias may expand to other aliases, which are expanded in turn, but not cyclically.
+///
+pub(crate) fn expand_aliases(
+    args: &[String],
+    aliases: &HashMap<String, String>,
+) -> Result<Vec<String>, String> {
+    if aliases.is_empty() {
+        return Ok(args.to_vec());
+    }
+    let definitions = aliases
+        .iter()
+        .map(|(alias, definition)| {
+            shlex::split(definition)
+                .map(|expansion| (alias.as_str(), expansion))
+                .ok_or_else(|| {
+                    format!(
+                        "Invalid definition of the alias `{alias}` in the `[cli].alias` option: \
+                         {definition}"
+                    )
+                })
+        })
+        .collect::<Result<HashMap<_, _>, _>>()?;
+
+    let mut expanded = vec![];
+    let mut args_iter = args.iter();
+    while let Some(arg) = args_iter.next() {
+        if arg == "--" {
+            // Passthrough args are not expanded.
+            expanded.push(arg.clone());
+            expanded.extend(args_iter.cloned());
+            break;
+        }
+        expand(arg, &definitions, &mut vec![], &mut expanded)?;
+    }
+    Ok(expanded)
+}
+
+fn expand<'a>(
+    arg: &'a str,
+    definitions: &'a HashMap<&str, Vec<String>>,
+    trail: &mut Vec<&'a str>,
+    expanded: &mut Vec<String>,
+) -> Result<(), String> {
+    let Some((alias, expansion)) = definitions.get_key_value(arg) else {
+        expanded.push(arg.to_owned());
+        return Ok(());
+    };
+    if trail.contains(alias) {
+        let cycle = trail.iter().rev().copied().collect::<Vec<_>>().join(" -> ");
+        return Err(format!(
+            "CLI alias cycle detected in `[cli].alias` option:\n{arg} -> {cycle}"
+        ));
+    }
+    trail.push(alias);
+    for expansion_arg in expansion {
+        expand(expansion_arg, definitions, trail, expanded)?;
+    }
+    trail.pop();
+    Ok(())
+}
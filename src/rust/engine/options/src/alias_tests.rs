@@ -0,0 +1,79 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+
+use crate::alias::expand_aliases;
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+fn aliases(aliases: &[(&str, &str)]) -> HashMap<String, String> {
+    aliases
+        .iter()
+        .map(|(alias, definition)| (alias.to_string(), definition.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_expand_aliases() {
+    let aliases = aliases(&[
+        ("green", "fmt lint check"),
+        (
+            "--all-changed",
+            "--changed-since=HEAD --changed-dependents=transitive",
+        ),
+        ("ci", "green test --all-changed"),
+        ("quoted", "run 'src/py/app.py' -- --message='hello world'"),
+    ]);
+
+    assert_eq!(
+        args(&["fmt", "lint", "check", "src::"]),
+        expand_aliases(&args(&["green", "src::"]), &aliases).unwrap()
+    );
+    assert_eq!(
+        args(&[
+            "-ldebug",
+            "fmt",
+            "lint",
+            "check",
+            "test",
+            "--changed-since=HEAD",
+            "--changed-dependents=transitive",
+        ]),
+        expand_aliases(&args(&["-ldebug", "ci"]), &aliases).unwrap()
+    );
+    assert_eq!(
+        args(&["run", "src/py/app.py", "--", "--message=hello world",]),
+        expand_aliases(&args(&["quoted"]), &aliases).unwrap()
+    );
+
+    // Passthrough args are not expanded.
+    assert_eq!(
+        args(&["test", "--", "green"]),
+        expand_aliases(&args(&["test", "--", "green"]), &aliases).unwrap()
+    );
+    assert_eq!(
+        args(&["lint"]),
+        expand_aliases(&args(&["lint"]), &HashMap::new()).unwrap()
+    );
+}
+
+#[test]
+fn test_expand_aliases_errors() {
+    let cyclic = aliases(&[("a", "b"), ("b", "fmt c"), ("c", "a")]);
+    assert_eq!(
+        "CLI alias cycle detected in `[cli].alias` option:\na -> c -> b -> a",
+        expand_aliases(&args(&["a"]), &cyclic).unwrap_err()
+    );
+    assert_eq!(
+        args(&["lint"]),
+        expand_aliases(&args(&["lint"]), &cyclic).unwrap()
+    );
+
+    assert_eq!(
+        "Invalid definition of the alias `bad` in the `[cli].alias` option: 'unclosed",
+        expand_aliases(&args(&["lint"]), &aliases(&[("bad", "'unclosed")])).unwrap_err()
+    );
+}
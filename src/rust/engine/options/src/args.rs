@@ -1,8 +1,10 @@
 // Copyright 2021 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
+use std::collections::HashMap;
 use std::env;
 
+use super::alias::expand_aliases;
 use super::id::{is_valid_scope_name, NameTransform, OptionId, Scope};
 use super::{DictEdit, OptionsSource};
 use crate::fromfile::FromfileExpander;
@@ -11,7 +13,7 @@ use crate::ListEdit;
 use core::iter::once;
 use itertools::{chain, Itertools};
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct Arg {
     context: Scope,
     flag: String,
@@ -83,8 +85,10 @@ impl Arg {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Args {
+    // The args as given, before being split into flags and passthrough args.
+    arg_strs: Vec<String>,
     args: Vec<Arg>,
     passthrough_args: Option<Vec<String>>,
 }
@@ -93,10 +97,11 @@ impl Args {
     // Create an Args instance with the provided args, which must *not* include the
     // argv[0] process name.
     pub fn new<I: IntoIterator<Item = String>>(arg_strs: I) -> Self {
+        let arg_strs = arg_strs.into_iter().collect::<Vec<_>>();
         let mut args: Vec<Arg> = vec![];
         let mut passthrough_args: Option<Vec<String>> = None;
         let mut scope = Scope::Global;
-        let mut args_iter = arg_strs.into_iter();
+        let mut args_iter = arg_strs.iter().cloned();
         while let Some(arg_str) = args_iter.next() {
             if arg_str == "--" {
                 // We've hit the passthrough args delimiter (`--`).
@@ -131,11 +136,19 @@ impl Args {
         }
 
         Self {
+            arg_strs,
             args,
             passthrough_args,
         }
     }
 
+    ///
+    /// Returns these args with any of the given `[cli].alias` definitions expanded.
+    ///
+    pub(crate) fn expand_aliases(&self, aliases: &HashMap<String, String>) -> Result<Self, String> {
+        Ok(Self::new(expand_aliases(&self.arg_strs, aliases)?))
+    }
+
     pub fn argv() -> Self {
         let mut args = env::args().collect::<Vec<_>>().into_iter();
         args.next(); // Consume the process name (argv[0]).
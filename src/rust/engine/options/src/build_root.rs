@@ -12,7 +12,8 @@ use std::os::unix::ffi::OsStrExt;
 pub struct BuildRoot(PathBuf);
 
 impl BuildRoot {
-    const SENTINEL_FILES: &'static [&'static str] = &["pants.toml", "BUILDROOT", "BUILD_ROOT"];
+    const SENTINEL_FILES: &'static [&'static str] =
+        &["pants.toml", "pants.yaml", "BUILDROOT", "BUILD_ROOT"];
 
     // Useful in tests.
     pub fn for_path(path: PathBuf) -> Self {
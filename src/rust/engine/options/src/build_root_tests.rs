@@ -33,6 +33,7 @@ fn test_find_cwd() {
     assert_sentinel("BUILDROOT");
     assert_sentinel("BUILD_ROOT");
     assert_sentinel("pants.toml");
+    assert_sentinel("pants.yaml");
 }
 
 #[test]
@@ -0,0 +1,438 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::str::FromStr;
+
+use crate::id::{NameTransform, OptionId, Scope};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(format!(
+                "Unsupported shell for completion: {s}. Expected one of: bash, zsh, fish."
+            )),
+        }
+    }
+}
+
+///
+/// A registered option, as far as completion is concerned.
+///
+pub struct CompletionOption {
+    pub id: OptionId,
+    pub is_bool: bool,
+    pub help: String,
+    /// The valid values of an option with a constrained set of choices (e.g. an enum option).
+    pub choices: Vec<String>,
+}
+
+///
+/// A registered scope, and the options registered in it.
+///
+pub struct CompletionScope {
+    pub scope: Scope,
+    pub is_goal: bool,
+    pub help: String,
+    pub options: Vec<CompletionOption>,
+}
+
+impl CompletionOption {
+    ///
+    /// The long flags of this option (including the negation of a bool option): if `qualified`, as
+    /// given in any context (e.g. `--fmt-only`), else as given in the context of its own scope
+    /// (e.g. `--only` after the `fmt` goal).
+    ///
+    fn long_flags(&self, qualified: bool) -> Vec<String> {
+        let name = self.id.name("-", NameTransform::ToLower);
+        let name = match &self.id.scope {
+            Scope::Scope(scope) if qualified => format!("{}-{name}", scope.to_ascii_lowercase()),
+            _ => name,
+        };
+        if self.is_bool {
+            vec![format!("--{name}"), format!("--no-{name}")]
+        } else {
+            vec![format!("--{name}")]
+        }
+    }
+}
+
+/// A flag, and the context it is given in: `None` for a qualified flag, since it is valid in any.
+type ContextFlag<'a> = (Option<&'a str>, String);
+
+///
+/// The flags and goals of a set of scopes, in the form that the completion scripts need them.
+///
+struct Completions<'a> {
+    goals: Vec<&'a CompletionScope>,
+    /// The names of the scopes which set the context of the unqualified flags that follow them.
+    scope_names: Vec<&'a str>,
+    /// The flags which are valid in any context: qualified long flags, and short flags.
+    anywhere_flags: Vec<String>,
+    /// The unqualified long flags of each context, where the global context is the empty string.
+    context_flags: Vec<(&'a str, Vec<String>)>,
+    /// The choices of each option with choices, with the flags it is given by.
+    choices: Vec<(Vec<ContextFlag<'a>>, &'a [String])>,
+}
+
+impl<'a> Completions<'a> {
+    fn new(scopes: &'a [CompletionScope]) -> Self {
+        let mut goals: Vec<&CompletionScope> = scopes.iter().filter(|s| s.is_goal).collect();
+        goals.sort_by_key(|s| s.scope.name());
+
+        let mut completions = Completions {
+            goals,
+            scope_names: Vec::new(),
+            anywhere_flags: Vec::new(),
+            context_flags: Vec::new(),
+            choices: Vec::new(),
+        };
+        for scope in scopes {
+            let context = match &scope.scope {
+                Scope::Global => "",
+                Scope::Scope(name) => {
+                    completions.scope_names.push(name);
+                    name
+                }
+            };
+            let mut flags = Vec::new();
+            for option in &scope.options {
+                let mut choice_flags = Vec::new();
+                for flag in option.long_flags(false) {
+                    choice_flags.push((Some(context), flag.clone()));
+                    flags.push(flag);
+                }
+                if scope.scope != Scope::Global {
+                    for flag in option.long_flags(true) {
+                        choice_flags.push((None, flag.clone()));
+                        completions.anywhere_flags.push(flag);
+                    }
+                }
+                if let Some(short_name) = &option.id.short_name {
+                    completions.anywhere_flags.push(format!("-{short_name}"));
+                }
+                if !option.choices.is_empty() && !option.is_bool {
+                    completions.choices.push((choice_flags, &option.choices));
+                }
+            }
+            if !flags.is_empty() {
+                completions.context_flags.push((context, flags));
+            }
+        }
+        completions.scope_names.sort_unstable();
+        completions.anywhere_flags.sort_unstable();
+        completions.anywhere_flags.dedup();
+        completions
+    }
+}
+
+///
+/// Generates a completion script for `shell`, which completes the goals, the flags of each scope,
+/// and the values of the options with choices, of the given registered `scopes`.
+///
+/// The scripts only complete `--flag=value` flags with their values, since that is the only form
+/// that the options parser accepts for long flags with a value.
+///
+pub fn completion_script(shell: Shell, scopes: &[CompletionScope]) -> String {
+    let completions = Completions::new(scopes);
+    match shell {
+        Shell::Bash => bash_script(&completions),
+        Shell::Zsh => zsh_script(&completions),
+        Shell::Fish => fish_script(scopes, &completions),
+    }
+}
+
+fn bash_script(completions: &Completions) -> String {
+    let goals = completions.goals.iter().map(|g| g.scope.name());
+    format!(
+        r#"# bash completion support for Pants, generated by `pants complete --shell=bash`.
+
+_pants_goals={goals}
+_pants_scopes={scopes}
+_pants_anywhere_flags={anywhere_flags}
+
+{functions}
+function _pants_completions() {{
+    local current_word=${{COMP_WORDS[COMP_CWORD]}}
+    local context="" flag="" prefix="" word i
+
+    for (( i = 1; i < COMP_CWORD; i++ )); do
+        word=${{COMP_WORDS[i]}}
+        if [[ $word == "--" ]]; then
+            # Passthrough arguments are completed as files.
+            COMPREPLY=()
+            return 0
+        elif [[ " $_pants_scopes " == *" $word "* ]]; then
+            context=$word
+        fi
+    done
+
+    # NB: `=` is usually in COMP_WORDBREAKS, in which case `--flag=value` is three words.
+    if [[ $current_word == "=" ]]; then
+        flag=${{COMP_WORDS[COMP_CWORD-1]}}
+        current_word=""
+    elif (( COMP_CWORD > 1 )) && [[ ${{COMP_WORDS[COMP_CWORD-1]}} == "=" ]]; then
+        flag=${{COMP_WORDS[COMP_CWORD-2]}}
+    elif [[ $current_word == --*=* ]]; then
+        flag=${{current_word%%=*}}
+        prefix="$flag="
+        current_word=${{current_word#*=}}
+    fi
+
+    local IFS=$'\n' words
+    if [[ -n $flag ]]; then
+        words=$(_pants_flag_choices "$context" "$flag")
+        COMPREPLY=( $(compgen -P "$prefix" -W "$words" -- "$current_word") )
+    elif [[ $current_word == -* ]]; then
+        words=$(_pants_context_flags "$context")$'\n'$_pants_anywhere_flags
+        COMPREPLY=( $(compgen -W "$words" -- "$current_word") )
+    else
+        COMPREPLY=( $(compgen -W "$_pants_goals" -- "$current_word") )
+    fi
+    return 0
+}}
+
+complete -o default -F _pants_completions pants
+"#,
+        goals = quote(&join_lines(goals)),
+        scopes = quote(&completions.scope_names.join(" ")),
+        anywhere_flags = quote(&join_lines(completions.anywhere_flags.iter())),
+        functions = posix_functions(completions),
+    )
+}
+
+fn zsh_script(completions: &Completions) -> String {
+    let goals = completions.goals.iter().map(|goal| {
+        let description = summary(&goal.help);
+        let entry = if description.is_empty() {
+            goal.scope.name().to_string()
+        } else {
+            format!("{}:{description}", goal.scope.name())
+        };
+        format!("    {}", quote(&entry))
+    });
+    format!(
+        r#"#compdef pants
+
+# zsh completion support for Pants, generated by `pants complete --shell=zsh`.
+
+_pants_goals=(
+{goals}
+)
+_pants_scopes=( {scopes} )
+_pants_anywhere_flags=(
+{anywhere_flags}
+)
+
+{functions}
+function _pants_completions() {{
+    local current_word=${{words[CURRENT]}}
+    local context="" flag="" word
+
+    for word in ${{words[2,CURRENT-1]}}; do
+        if [[ $word == "--" ]]; then
+            # Passthrough arguments are completed as files.
+            _files
+            return 0
+        elif (( ${{_pants_scopes[(Ie)$word]}} )); then
+            context=$word
+        fi
+    done
+
+    if [[ $current_word == --*=* ]]; then
+        flag=${{current_word%%=*}}
+        compset -P '*='
+        compadd -- ${{(f)"$(_pants_flag_choices "$context" "$flag")"}}
+    elif [[ $current_word == -* ]]; then
+        compadd -- ${{(f)"$(_pants_context_flags "$context")"}} $_pants_anywhere_flags
+    else
+        _describe 'goal' _pants_goals
+        _files
+    fi
+    return 0
+}}
+
+compdef _pants_completions pants
+"#,
+        goals = join_lines(goals),
+        scopes = completions.scope_names.join(" "),
+        anywhere_flags = join_lines(
+            completions
+                .anywhere_flags
+                .iter()
+                .map(|f| format!("    {f}"))
+        ),
+        functions = posix_functions(completions),
+    )
+}
+
+///
+/// The `_pants_context_flags` and `_pants_flag_choices` functions, whose syntax is shared by bash
+/// and zsh.
+///
+fn posix_functions(completions: &Completions) -> String {
+    let mut context_cases = String::new();
+    for (context, flags) in &completions.context_flags {
+        context_cases.push_str(&format!(
+            "        {}) printf '%s\\n' {} ;;\n",
+            quote(context),
+            flags.join(" ")
+        ));
+    }
+    let mut choice_cases = String::new();
+    for (flags, choices) in &completions.choices {
+        let patterns = flags
+            .iter()
+            .map(|(context, flag)| match context {
+                Some(context) => quote(&format!("{context} {flag}")),
+                None => format!("*{}", quote(&format!(" {flag}"))),
+            })
+            .collect::<Vec<_>>();
+        let choices = choices.iter().map(|c| quote(c)).collect::<Vec<_>>();
+        choice_cases.push_str(&format!(
+            "        {}) printf '%s\\n' {} ;;\n",
+            patterns.join("|"),
+            choices.join(" ")
+        ));
+    }
+    format!(
+        r#"# The unqualified flags which are valid in the context of the given scope.
+function _pants_context_flags() {{
+    case "$1" in
+{context_cases}    esac
+}}
+
+# The choices of the given flag in the context of the given scope.
+function _pants_flag_choices() {{
+    case "$1 $2" in
+{choice_cases}    esac
+}}
+"#
+    )
+}
+
+fn fish_script(scopes: &[CompletionScope], completions: &Completions) -> String {
+    let mut script = format!(
+        r#"# fish completion support for Pants, generated by `pants complete --shell=fish`.
+
+set -g __pants_scopes {scopes}
+
+# The scope which sets the context of the unqualified flags, which fails for passthrough arguments.
+function __pants_context
+    set -l context ""
+    for word in (commandline -opc)[2..-1]
+        if test "$word" = "--"
+            return 1
+        else if contains -- $word $__pants_scopes
+            set context $word
+        end
+    end
+    echo $context
+end
+
+function __pants_in_context
+    set -l context (__pants_context); or return 1
+    test "$context" = "$argv[1]"
+end
+
+function __pants_not_passthrough
+    __pants_context >/dev/null
+end
+
+"#,
+        scopes = completions.scope_names.join(" "),
+    );
+    for goal in &completions.goals {
+        script.push_str(&format!(
+            "complete -c pants -n __pants_not_passthrough -a {}{}\n",
+            fish_quote(goal.scope.name()),
+            fish_description(&goal.help)
+        ));
+    }
+    for scope in scopes {
+        let condition = match &scope.scope {
+            Scope::Global => "'__pants_in_context \"\"'".to_string(),
+            Scope::Scope(name) => fish_quote(&format!("__pants_in_context {name}")),
+        };
+        for option in &scope.options {
+            let arguments = if option.is_bool {
+                String::new()
+            } else if option.choices.is_empty() {
+                " -r".to_string()
+            } else {
+                format!(" -x -a {}", fish_quote(&option.choices.join(" ")))
+            };
+            let description = fish_description(&option.help);
+            let mut flags: Vec<(&str, String)> = option
+                .long_flags(false)
+                .into_iter()
+                .map(|flag| (condition.as_str(), flag))
+                .collect();
+            if scope.scope != Scope::Global {
+                flags.extend(
+                    option
+                        .long_flags(true)
+                        .into_iter()
+                        .map(|flag| ("__pants_not_passthrough", flag)),
+                );
+            }
+            for (condition, flag) in flags {
+                script.push_str(&format!(
+                    "complete -c pants -n {condition} -l {}{arguments}{description}\n",
+                    &flag[2..]
+                ));
+            }
+            if let Some(short_name) = &option.id.short_name {
+                script.push_str(&format!(
+                    "complete -c pants -n __pants_not_passthrough -s {short_name}{arguments}\
+                     {description}\n"
+                ));
+            }
+        }
+    }
+    script
+}
+
+/// The description argument of a fish `complete` command for the given help, if any.
+fn fish_description(help: &str) -> String {
+    let description = summary(help);
+    if description.is_empty() {
+        String::new()
+    } else {
+        format!(" -d {}", fish_quote(&description))
+    }
+}
+
+/// The first sentence of the first line of a help message.
+fn summary(help: &str) -> String {
+    let line = help.trim().lines().next().unwrap_or_default().trim();
+    let sentence = line.split_once(". ").map_or(line, |(sentence, _)| sentence);
+    sentence.trim_end_matches('.').to_string()
+}
+
+fn join_lines<S: AsRef<str>>(lines: impl Iterator<Item = S>) -> String {
+    lines
+        .map(|line| line.as_ref().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Quotes `s` for a POSIX shell.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+fn fish_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\\', r"\\").replace('\'', r"\'"))
+}
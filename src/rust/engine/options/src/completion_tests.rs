@@ -0,0 +1,160 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::process::Command;
+use std::str::FromStr;
+
+use crate::completion::{completion_script, CompletionOption, CompletionScope, Shell};
+use crate::{option_id, OptionId, Scope};
+
+fn option(id: OptionId, is_bool: bool, choices: &[&str]) -> CompletionOption {
+    CompletionOption {
+        id,
+        is_bool,
+        help: "Some help. More details.\n\nEven more details.".to_string(),
+        choices: choices.iter().map(|c| c.to_string()).collect(),
+    }
+}
+
+fn scopes() -> Vec<CompletionScope> {
+    vec![
+        CompletionScope {
+            scope: Scope::Global,
+            is_goal: false,
+            help: "Global options.".to_string(),
+            options: vec![
+                option(option_id!(-'l', "level"), false, &["debug", "info"]),
+                option(option_id!("colors"), true, &[]),
+            ],
+        },
+        CompletionScope {
+            scope: Scope::named("fmt"),
+            is_goal: true,
+            help: "Autoformat source code.".to_string(),
+            options: vec![option(option_id!(["fmt"], "only"), false, &[])],
+        },
+        CompletionScope {
+            scope: Scope::named("check"),
+            is_goal: true,
+            help: "Run type checking. Or else.".to_string(),
+            options: vec![],
+        },
+        CompletionScope {
+            scope: Scope::named("python-infer"),
+            is_goal: false,
+            help: "".to_string(),
+            options: vec![option(
+                option_id!(["python-infer"], "ambiguity", "resolution"),
+                false,
+                &["none", "by_source_root"],
+            )],
+        },
+    ]
+}
+
+#[test]
+fn test_shell_from_str() {
+    assert_eq!(Ok(Shell::Bash), Shell::from_str("bash"));
+    assert_eq!(Ok(Shell::Zsh), Shell::from_str("zsh"));
+    assert_eq!(Ok(Shell::Fish), Shell::from_str("fish"));
+    assert!(Shell::from_str("tcsh").is_err());
+}
+
+///
+/// Runs the bash completion function for the given words, the last of which is being completed,
+/// and returns the completions.
+///
+fn bash_completions(words: &[&str]) -> Vec<String> {
+    let script = completion_script(Shell::Bash, &scopes());
+    let words = words
+        .iter()
+        .map(|w| format!("'{w}'"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let output = Command::new("bash")
+        .arg("-c")
+        .arg(format!(
+            "{script}\nCOMP_WORDS=(pants {words})\nCOMP_CWORD=$((${{#COMP_WORDS[@]}} - 1))\n\
+             _pants_completions\nprintf '%s\\n' \"${{COMPREPLY[@]}}\""
+        ))
+        .output()
+        .expect("Failed to run bash");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let mut completions = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    completions.sort();
+    completions
+}
+
+#[test]
+fn test_bash_completions() {
+    assert_eq!(vec!["check", "fmt"], bash_completions(&[""]));
+    assert_eq!(vec!["fmt"], bash_completions(&["--colors", "f"]));
+    assert!(bash_completions(&["src/"]).is_empty());
+
+    // Global flags are only valid before the first goal, unless qualified.
+    assert_eq!(
+        vec!["--colors", "--level", "--no-colors"],
+        bash_completions(&["--"])
+            .into_iter()
+            .filter(|f| !f.starts_with("--fmt") && !f.starts_with("--python"))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec!["--only", "--fmt-only"],
+        bash_completions(&["fmt", "--", "x"])
+            .into_iter()
+            .chain(bash_completions(&["fmt", "--o"]))
+            .chain(bash_completions(&["fmt", "--fmt"]))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(vec!["-l"], bash_completions(&["fmt", "-l"]));
+
+    // Choices, with `=` as a separate word (as split by COMP_WORDBREAKS) or not.
+    assert_eq!(vec!["debug", "info"], bash_completions(&["--level", "="]));
+    assert_eq!(vec!["debug"], bash_completions(&["--level", "=", "d"]));
+    assert!(bash_completions(&["fmt", "--level", "="]).is_empty());
+    assert_eq!(
+        vec!["--python-infer-ambiguity-resolution=by_source_root"],
+        bash_completions(&["fmt", "--python-infer-ambiguity-resolution=b"])
+    );
+}
+
+#[test]
+fn test_zsh_script() {
+    let script = completion_script(Shell::Zsh, &scopes());
+    assert!(script.starts_with("#compdef pants\n"));
+    assert!(script.contains("    'check:Run type checking'\n    'fmt:Autoformat source code'\n"));
+    assert!(script.contains("_pants_scopes=( check fmt python-infer )\n"));
+    assert!(script.contains("        '') printf '%s\\n' --level --colors --no-colors ;;\n"));
+    assert!(script.contains(
+        "        'python-infer --ambiguity-resolution'|*' --python-infer-ambiguity-resolution') \
+         printf '%s\\n' 'none' 'by_source_root' ;;\n"
+    ));
+    assert!(script.ends_with("compdef _pants_completions pants\n"));
+}
+
+#[test]
+fn test_fish_script() {
+    let script = completion_script(Shell::Fish, &scopes());
+    for line in [
+        "set -g __pants_scopes check fmt python-infer\n",
+        "complete -c pants -n __pants_not_passthrough -a 'fmt' -d 'Autoformat source code'\n",
+        "complete -c pants -n '__pants_in_context \"\"' -l level -x -a 'debug info' \
+         -d 'Some help'\n",
+        "complete -c pants -n __pants_not_passthrough -s l -x -a 'debug info' -d 'Some help'\n",
+        "complete -c pants -n '__pants_in_context \"\"' -l no-colors -d 'Some help'\n",
+        "complete -c pants -n '__pants_in_context fmt' -l only -r -d 'Some help'\n",
+        "complete -c pants -n __pants_not_passthrough -l fmt-only -r -d 'Some help'\n",
+    ] {
+        assert!(script.contains(line), "Expected `{line}` in:\n{script}");
+    }
+}
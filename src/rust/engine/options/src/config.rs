@@ -2,6 +2,7 @@
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
 use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -17,10 +18,18 @@ use crate::parse::Parseable;
 
 type InterpolationMap = HashMap<String, String>;
 
-static DEFAULT_SECTION: &str = "DEFAULT";
+pub(crate) static DEFAULT_SECTION: &str = "DEFAULT";
+
+// The top-level key of a config file which lists the config files that it includes.
+pub(crate) static INCLUDE_KEY: &str = "include";
 
 lazy_static! {
-    static ref PLACEHOLDER_RE: Regex = Regex::new(r"%\(([a-zA-Z0-9_.]+)\)s").unwrap();
+    // Either a `%(name)s` placeholder, or a `${env.NAME}` reference to an environment variable,
+    // with an optional fallback for when it is unset or empty, as in `${env.NAME:-fallback}`.
+    static ref PLACEHOLDER_RE: Regex = Regex::new(
+        r"%\(([a-zA-Z0-9_.]+)\)s|\$\{env\.([a-zA-Z0-9_]+)(?::-([^}]*))?\}"
+    )
+    .unwrap();
 }
 
 pub(crate) fn interpolate_string(
@@ -37,11 +46,27 @@ pub(crate) fn interpolate_string(
     for caps in caps_vec {
         let m = caps.get(0).unwrap();
         new_value.push_str(&value[last_match..m.start()]);
-        let placeholder_name = &caps[1];
-        let replacement = replacements.get(placeholder_name).ok_or(format!(
-            "Unknown value for placeholder `{}`",
-            placeholder_name
-        ))?;
+        let replacement: &str = if let Some(placeholder_name) = caps.get(1) {
+            let placeholder_name = placeholder_name.as_str();
+            replacements.get(placeholder_name).ok_or(format!(
+                "Unknown value for placeholder `{}`",
+                placeholder_name
+            ))?
+        } else {
+            // The environment is seeded into the replacements as `env.NAME` placeholders.
+            let env_var = &caps[2];
+            match (replacements.get(&format!("env.{env_var}")), caps.get(3)) {
+                (Some(env_value), Some(fallback)) if env_value.is_empty() => fallback.as_str(),
+                (Some(env_value), _) => env_value,
+                (None, Some(fallback)) => fallback.as_str(),
+                (None, None) => {
+                    return Err(format!(
+                        "Unknown value for environment variable `{env_var}`, which is unset \
+                        (use `${{env.{env_var}:-fallback}}` to provide a fallback)"
+                    ))
+                }
+            }
+        };
         new_value.push_str(replacement);
         last_match = m.end();
     }
@@ -229,6 +254,17 @@ fn toml_table_to_dict(table: &Value) -> HashMap<String, Val> {
     }
 }
 
+///
+/// Whether the config file at the given path is in YAML format (based on its extension), rather
+/// than in TOML format.
+///
+fn is_yaml(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("yaml" | "yml")
+    )
+}
+
 #[derive(Clone, Debug)]
 pub struct ConfigSource {
     pub path: PathBuf,
@@ -254,20 +290,108 @@ impl ConfigSource {
 #[derive(Clone)]
 pub(crate) struct Config {
     value: Value,
+    /// The paths of the config files included by this one, in the order they are listed.
+    includes: Vec<PathBuf>,
 }
 
 impl Config {
+    ///
+    /// Parses the given config source and the config files that it transitively includes, and
+    /// returns them with their paths, in ascending order of precedence.
+    ///
+    /// The files listed by the `include` key of a config file are merged as if they were given
+    /// before it, in the order that they are listed: so the including file overrides the files it
+    /// includes, and each included file overrides the ones listed before it. A file which was
+    /// already parsed (as recorded in `parsed`) is not included again, and a file which
+    /// transitively includes itself is an error.
+    ///
+    pub(crate) fn parse_with_includes(
+        config_source: &ConfigSource,
+        seed_values: &InterpolationMap,
+        parsed: &mut HashSet<PathBuf>,
+    ) -> Result<Vec<(PathBuf, Config)>, String> {
+        fn canonical(path: &Path) -> PathBuf {
+            // Config sources need not exist on disk (e.g. in tests), unlike included files.
+            fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+        }
+
+        fn parse_recursively(
+            config_source: &ConfigSource,
+            seed_values: &InterpolationMap,
+            parsed: &mut HashSet<PathBuf>,
+            stack: &mut Vec<PathBuf>,
+            configs: &mut Vec<(PathBuf, Config)>,
+        ) -> Result<(), String> {
+            let config = Config::parse(config_source, seed_values)?;
+            for include in &config.includes {
+                let include_path = canonical(include);
+                if let Some(start) = stack.iter().position(|path| *path == include_path) {
+                    let cycle = stack[start..]
+                        .iter()
+                        .chain([&include_path])
+                        .map(|path| path.display().to_string())
+                        .collect::<Vec<_>>();
+                    return Err(format!(
+                        "Config file {} includes itself, via: {}",
+                        include.display(),
+                        cycle.join(" -> ")
+                    ));
+                }
+                if !parsed.insert(include_path.clone()) {
+                    continue;
+                }
+                let include_source = ConfigSource::from_file(include)
+                    .map_err(|e| format!("{e} (included by {})", config_source.path.display()))?;
+                stack.push(include_path);
+                parse_recursively(&include_source, seed_values, parsed, stack, configs)?;
+                stack.pop();
+            }
+            configs.push((config_source.path.clone(), config));
+            Ok(())
+        }
+
+        let path = canonical(&config_source.path);
+        parsed.insert(path.clone());
+        let mut configs = vec![];
+        parse_recursively(
+            config_source,
+            seed_values,
+            parsed,
+            &mut vec![path],
+            &mut configs,
+        )?;
+        Ok(configs)
+    }
+
     pub(crate) fn parse(
         config_source: &ConfigSource,
         seed_values: &InterpolationMap,
     ) -> Result<Config, String> {
-        let config = config_source.content.parse::<Value>().map_err(|e| {
+        let parse_error = |e: &dyn Display| {
             format!(
                 "Failed to parse config file {}: {}",
                 config_source.path.display(),
                 e
             )
-        })?;
+        };
+        let mut config = if is_yaml(&config_source.path) {
+            // A YAML config file has the same structure as a TOML one, so is parsed into the same
+            // representation. An empty YAML document is an empty config.
+            if config_source.content.trim().is_empty() {
+                Value::Table(Table::new())
+            } else {
+                serde_yaml::from_str::<Value>(&config_source.content)
+                    .map_err(|e| parse_error(&e))?
+            }
+        } else {
+            config_source
+                .content
+                .parse::<Value>()
+                .map_err(|e| parse_error(&e))?
+        };
+        let include = config
+            .as_table_mut()
+            .and_then(|table| table.remove(INCLUDE_KEY));
 
         fn add_section_to_interpolation_map(
             mut imap: InterpolationMap,
@@ -288,6 +412,34 @@ impl Config {
         let default_imap =
             add_section_to_interpolation_map(seed_values.clone(), config.get(DEFAULT_SECTION))?;
 
+        // Included paths may contain placeholders, and are relative to the including file.
+        let mut includes = vec![];
+        if let Some(include) = include {
+            let include_error = || {
+                format!(
+                    "Expected `{INCLUDE_KEY}` in config file {} to be an array of paths, but \
+                    given {include}",
+                    config_source.path.display(),
+                )
+            };
+            for path in include.as_array().ok_or_else(include_error)? {
+                let path = path.as_str().ok_or_else(include_error)?;
+                let path = interpolate_string(path.to_owned(), &default_imap).map_err(|e| {
+                    format!(
+                        "{e} in config file {}, key {INCLUDE_KEY}",
+                        config_source.path.display()
+                    )
+                })?;
+                includes.push(
+                    config_source
+                        .path
+                        .parent()
+                        .unwrap_or(Path::new(""))
+                        .join(path),
+                );
+            }
+        }
+
         let new_sections: Result<Vec<(String, Value)>, String> = match config {
             Value::Table(t) => t
                 .into_iter()
@@ -332,8 +484,17 @@ impl Config {
         let new_table = Table::from_iter(new_sections?);
         Ok(Self {
             value: Value::Table(new_table),
+            includes,
         })
     }
+
+    /// The names of the sections of this config.
+    pub(crate) fn sections(&self) -> impl Iterator<Item = &str> {
+        self.value
+            .as_table()
+            .into_iter()
+            .flat_map(|table| table.keys().map(String::as_str))
+    }
 }
 
 pub(crate) struct ConfigReader {
@@ -349,7 +510,7 @@ impl ConfigReader {
         }
     }
 
-    fn option_name(id: &OptionId) -> String {
+    pub(crate) fn option_name(id: &OptionId) -> String {
         id.name("_", NameTransform::None)
     }
 
@@ -3,14 +3,16 @@
 
 use maplit::hashmap;
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::fs::File;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use crate::config::{interpolate_string, ConfigSource};
 use crate::{
-    option_id, DictEdit, DictEditAction, ListEdit, ListEditAction, OptionId, OptionsSource, Val,
+    option_id, DictEdit, DictEditAction, ListEdit, ListEditAction, OptionId, OptionsSource, Scope,
+    Val,
 };
 
 use crate::config::{Config, ConfigReader};
@@ -99,6 +101,31 @@ fn test_interpolate_string() {
         "Hello world, what's your real name?",
         interp(template, replacements).unwrap()
     );
+
+    let template = "${env.HOME}/.cache/${env.CACHE_NAME:-pants} %(user)s";
+    let replacements = vec![("env.HOME", "/home/jo"), ("user", "jo")];
+    assert_eq!(
+        "/home/jo/.cache/pants jo",
+        interp(template, replacements).unwrap()
+    );
+
+    let template = "${env.EMPTY:-fallback} ${env.EMPTY:-} ${env.UNSET:-%(buildroot)s/cache}";
+    let replacements = vec![("env.EMPTY", ""), ("buildroot", "/repo")];
+    assert_eq!(
+        "fallback  /repo/cache",
+        interp(template, replacements).unwrap()
+    );
+
+    let template = "${env.EMPTY} ${not_env}";
+    let replacements = vec![("env.EMPTY", "")];
+    assert_eq!(" ${not_env}", interp(template, replacements).unwrap());
+
+    let result = interp("${env.UNSET}", vec![]);
+    assert_eq!(
+        "Unknown value for environment variable `UNSET`, which is unset (use \
+         `${env.UNSET:-fallback}` to provide a fallback)",
+        result.unwrap_err()
+    );
 }
 
 #[test]
@@ -410,3 +437,263 @@ fn test_nonexistent_optional_fromfile() {
     let conf = config("[GLOBAL]\nfoo = '@?/does/not/exist'\n");
     assert!(conf.get_string(&option_id!("foo")).unwrap().is_none());
 }
+
+fn write_configs(dir: &Path, files: &[(&str, &str)]) {
+    for (path, content) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        File::create(&path)
+            .unwrap()
+            .write_all(content.as_bytes())
+            .unwrap();
+    }
+}
+
+fn parse_with_includes(
+    path: &Path,
+    parsed: &mut HashSet<PathBuf>,
+) -> Result<Vec<(PathBuf, ConfigReader)>, String> {
+    Config::parse_with_includes(
+        &ConfigSource::from_file(path)?,
+        &HashMap::from([("seed1".to_string(), "seed1val".to_string())]),
+        parsed,
+    )
+    .map(|configs| {
+        configs
+            .into_iter()
+            .map(|(path, config)| {
+                (
+                    path,
+                    ConfigReader::new(config, FromfileExpander::relative_to_cwd()),
+                )
+            })
+            .collect()
+    })
+}
+
+#[test]
+fn test_includes() {
+    let dir = TempDir::new().unwrap();
+    write_configs(
+        dir.path(),
+        &[
+            (
+                "pants.toml",
+                "include = ['shared/remote-cache.toml', 'shared/%(seed1)s.toml']\n\
+                 [GLOBAL]\nfoo = 'pants'\n",
+            ),
+            (
+                "shared/remote-cache.toml",
+                "include = ['base.toml']\n[GLOBAL]\nfoo = 'remote'\nbar = 'remote'\n",
+            ),
+            (
+                "shared/seed1val.toml",
+                "include = ['base.toml']\n[GLOBAL]\nbar = 'seed1'\n",
+            ),
+            ("shared/base.toml", "[GLOBAL]\nbaz = 'base'\n"),
+        ],
+    );
+
+    let mut parsed = HashSet::new();
+    let configs = parse_with_includes(&dir.path().join("pants.toml"), &mut parsed).unwrap();
+    // The base config is only included once, by the first file which includes it.
+    assert_eq!(
+        vec![
+            dir.path().join("shared/base.toml"),
+            dir.path().join("shared/remote-cache.toml"),
+            dir.path().join("shared/seed1val.toml"),
+            dir.path().join("pants.toml"),
+        ],
+        configs
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>()
+    );
+    let get = |index: usize, name: &str| -> Option<String> {
+        let id = OptionId::new(Scope::Global, [name].iter(), None).unwrap();
+        configs[index].1.get_string(&id).unwrap()
+    };
+    assert_eq!(Some("base".to_string()), get(0, "baz"));
+    assert_eq!(Some("remote".to_string()), get(1, "foo"));
+    assert_eq!(Some("seed1".to_string()), get(2, "bar"));
+    assert_eq!(Some("pants".to_string()), get(3, "foo"));
+    assert_eq!(None, get(3, "baz"));
+
+    // Files which were already parsed are not included again.
+    let configs =
+        parse_with_includes(&dir.path().join("shared/seed1val.toml"), &mut parsed).unwrap();
+    assert_eq!(1, configs.len());
+}
+
+#[test]
+fn test_include_errors() {
+    let dir = TempDir::new().unwrap();
+    write_configs(
+        dir.path(),
+        &[
+            ("a.toml", "include = ['b/b.toml']\n"),
+            ("b/b.toml", "include = ['../a.toml']\n"),
+            ("self.toml", "include = ['self.toml']\n"),
+            ("missing.toml", "include = ['does-not-exist.toml']\n"),
+            ("not_a_list.toml", "include = 'a.toml'\n"),
+        ],
+    );
+    let error = |path: &str| {
+        parse_with_includes(&dir.path().join(path), &mut HashSet::new())
+            .err()
+            .unwrap()
+    };
+    let canonical_dir = std::fs::canonicalize(dir.path()).unwrap();
+    let canonical = |path: &str| canonical_dir.join(path).display().to_string();
+
+    assert!(error("a.toml").ends_with(&format!(
+        "includes itself, via: {} -> {} -> {}",
+        canonical("a.toml"),
+        canonical("b/b.toml"),
+        canonical("a.toml")
+    )));
+    assert!(error("self.toml").ends_with(&format!(
+        "includes itself, via: {} -> {}",
+        canonical("self.toml"),
+        canonical("self.toml")
+    )));
+    let missing = error("missing.toml");
+    assert!(
+        missing.starts_with("Failed to read config file ")
+            && missing.ends_with(&format!(
+                "(included by {})",
+                dir.path().join("missing.toml").display()
+            )),
+        "{missing}"
+    );
+    assert!(error("not_a_list.toml").starts_with(&format!(
+        "Expected `include` in config file {} to be an array of paths, but given ",
+        dir.path().join("not_a_list.toml").display()
+    )));
+}
+
+fn yaml_config(file_content: &str) -> Result<ConfigReader, String> {
+    Config::parse(
+        &ConfigSource {
+            path: PathBuf::from("pants.yaml"),
+            content: file_content.to_string(),
+        },
+        &HashMap::from([("seed1".to_string(), "seed1val".to_string())]),
+    )
+    .map(|config| ConfigReader::new(config, FromfileExpander::relative_to_cwd()))
+}
+
+#[test]
+fn test_yaml_config() {
+    let conf = yaml_config(
+        "DEFAULT:\n  \
+           color: black\n\
+         GLOBAL:\n  \
+           pantsd: false\n  \
+           level: '%(seed1)s'\n\
+         groceries:\n  \
+           count: 3\n  \
+           stringlist:\n    \
+             add: [apple, '%(color)sberry']\n    \
+             remove: [pear]\n  \
+           intlist: [1, 2]\n  \
+           inline_table:\n    \
+             fruit: strawberry\n    \
+             amount: 2\n",
+    )
+    .unwrap();
+
+    assert_eq!(Some(false), conf.get_bool(&option_id!("pantsd")).unwrap());
+    assert_eq!(
+        Some("seed1val".to_string()),
+        conf.get_string(&option_id!("level")).unwrap()
+    );
+    assert_eq!(
+        Some(3),
+        conf.get_int(&option_id!(["groceries"], "count")).unwrap()
+    );
+    assert_eq!(
+        Some(vec![
+            ListEdit {
+                action: ListEditAction::Add,
+                items: vec!["apple".to_string(), "blackberry".to_string()],
+            },
+            ListEdit {
+                action: ListEditAction::Remove,
+                items: vec!["pear".to_string()],
+            }
+        ]),
+        conf.get_string_list(&option_id!(["groceries"], "stringlist"))
+            .unwrap()
+    );
+    assert_eq!(
+        Some(vec![ListEdit {
+            action: ListEditAction::Replace,
+            items: vec![1, 2],
+        }]),
+        conf.get_int_list(&option_id!(["groceries"], "intlist"))
+            .unwrap()
+    );
+    assert_eq!(
+        Some(vec![DictEdit {
+            action: DictEditAction::Replace,
+            items: hashmap! {
+                "fruit".to_string() => Val::String("strawberry".to_string()),
+                "amount".to_string() => Val::Int(2),
+            },
+        }]),
+        conf.get_dict(&option_id!(["groceries"], "inline_table"))
+            .unwrap()
+    );
+
+    // An empty file is an empty config.
+    assert_eq!(
+        None,
+        yaml_config("")
+            .unwrap()
+            .get_bool(&option_id!("pantsd"))
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_yaml_config_errors() {
+    let error = |content: &str| yaml_config(content).err().unwrap();
+    assert!(error("GLOBAL: [pantsd]\n").starts_with(
+        "Expected the config file pants.yaml to contain tables per section, but section GLOBAL \
+         contained a array"
+    ));
+    assert!(error("- GLOBAL\n").starts_with(
+        "Expected the config file pants.yaml to contain a table but contained a array"
+    ));
+    assert!(
+        error("GLOBAL:\n  level: null\n").starts_with("Failed to parse config file pants.yaml: ")
+    );
+    assert!(error("GLOBAL: {\n").starts_with("Failed to parse config file pants.yaml: "));
+}
+
+#[test]
+fn test_yaml_includes() {
+    let dir = TempDir::new().unwrap();
+    write_configs(
+        dir.path(),
+        &[
+            (
+                "pants.yaml",
+                "include: [shared/base.toml, shared/jvm.yml]\nGLOBAL:\n  foo: pants\n",
+            ),
+            ("shared/base.toml", "[GLOBAL]\nfoo = 'base'\nbar = 'base'\n"),
+            ("shared/jvm.yml", "GLOBAL:\n  bar: jvm\n"),
+        ],
+    );
+
+    let configs = parse_with_includes(&dir.path().join("pants.yaml"), &mut HashSet::new()).unwrap();
+    let get = |index: usize, name: &str| -> Option<String> {
+        let id = OptionId::new(Scope::Global, [name].iter(), None).unwrap();
+        configs[index].1.get_string(&id).unwrap()
+    };
+    assert_eq!(3, configs.len());
+    assert_eq!(Some("base".to_string()), get(0, "foo"));
+    assert_eq!(Some("jvm".to_string()), get(1, "bar"));
+    assert_eq!(Some("pants".to_string()), get(2, "foo"));
+}
@@ -4,6 +4,8 @@
 use std::collections::HashMap;
 use std::env;
 use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
 
 use super::id::{NameTransform, OptionId, Scope};
 use super::{DictEdit, OptionsSource};
@@ -11,7 +13,7 @@ use crate::fromfile::FromfileExpander;
 use crate::parse::Parseable;
 use crate::ListEdit;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Env {
     pub(crate) env: HashMap<String, String>,
 }
@@ -51,6 +53,102 @@ impl Env {
         }
         (Self::new(env), dropped)
     }
+
+    ///
+    /// Adds the variables set by the given `.env` file (if it exists) which are not already set,
+    /// so that the file has a lower precedence than the actual environment.
+    ///
+    pub fn with_dotenv_file(mut self, path: &Path) -> Result<Self, String> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(self),
+            Err(e) => return Err(format!("Failed to read {}: {e}", path.display())),
+        };
+        let dotenv: HashMap<_, _> = parse_dotenv(&content)
+            .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?
+            .into_iter()
+            .collect();
+        for (name, value) in dotenv {
+            self.env.entry(name).or_insert(value);
+        }
+        Ok(self)
+    }
+}
+
+///
+/// Parses the contents of a `.env` file: lines of `NAME=value`, optionally prefixed by `export`,
+/// where `#` starts a comment outside of a value.
+///
+/// A value may be single quoted, in which case it is taken literally, or double quoted, in which
+/// case `\n`, `\t`, `\"` and `\\` escapes are interpreted. Any other value is trimmed, and ends at
+/// a ` #` comment.
+///
+pub fn parse_dotenv(content: &str) -> Result<Vec<(String, String)>, String> {
+    let mut vars = vec![];
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line
+            .strip_prefix("export ")
+            .map(str::trim_start)
+            .unwrap_or(line);
+        let invalid = |reason: &str| format!("line {}: {reason}: {line}", index + 1);
+
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| invalid("Expected `NAME=value`"))?;
+        let name = name.trim_end();
+        let mut name_chars = name.chars();
+        if !name_chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            || !name_chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(invalid("Invalid variable name"));
+        }
+
+        let value = value.trim_start();
+        let (value, rest) = match value.chars().next() {
+            Some(quote @ ('\'' | '"')) => {
+                let mut unquoted = String::new();
+                let mut chars = value[1..].char_indices();
+                let mut end = None;
+                while let Some((i, c)) = chars.next() {
+                    if c == quote {
+                        end = Some(i + 2);
+                        break;
+                    } else if c == '\\' && quote == '"' {
+                        match chars.next().map(|(_, c)| c) {
+                            Some('n') => unquoted.push('\n'),
+                            Some('t') => unquoted.push('\t'),
+                            Some(c @ ('"' | '\\')) => unquoted.push(c),
+                            Some(c) => {
+                                unquoted.push('\\');
+                                unquoted.push(c);
+                            }
+                            None => break,
+                        }
+                    } else {
+                        unquoted.push(c);
+                    }
+                }
+                let end = end.ok_or_else(|| invalid("Unterminated quoted value"))?;
+                (unquoted, &value[end..])
+            }
+            _ => {
+                let end = value.find(" #").unwrap_or(value.len());
+                (value[..end].trim_end().to_owned(), "")
+            }
+        };
+        let rest = rest.trim_start();
+        if !(rest.is_empty() || rest.starts_with('#')) {
+            return Err(invalid("Unexpected characters after quoted value"));
+        }
+        vars.push((name.to_owned(), value));
+    }
+    Ok(vars)
 }
 
 pub(crate) struct EnvReader {
@@ -1,7 +1,7 @@
 // Copyright 2021 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
-use crate::env::{Env, EnvReader};
+use crate::env::{parse_dotenv, Env, EnvReader};
 use crate::fromfile::test_util::write_fromfile;
 use crate::fromfile::FromfileExpander;
 use crate::{option_id, DictEdit, DictEditAction};
@@ -206,6 +206,69 @@ Expected \",\" or the end of a tuple indicated by ')' at line 1 column 18"
     );
 }
 
+#[test]
+fn test_scalar_list_edits() {
+    let env = env([
+        ("PANTS_INTS", "+[1, 2],-[3]"),
+        ("PANTS_FLOATS", "-[1.5]"),
+        ("PANTS_BOOLS", "+[True]"),
+    ]);
+
+    assert_eq!(
+        vec![
+            ListEdit {
+                action: ListEditAction::Add,
+                items: vec![1, 2]
+            },
+            ListEdit {
+                action: ListEditAction::Remove,
+                items: vec![3]
+            },
+        ],
+        env.get_int_list(&option_id!("ints")).unwrap().unwrap()
+    );
+    assert_eq!(
+        vec![ListEdit {
+            action: ListEditAction::Remove,
+            items: vec![1.5]
+        }],
+        env.get_float_list(&option_id!("floats")).unwrap().unwrap()
+    );
+    assert_eq!(
+        vec![ListEdit {
+            action: ListEditAction::Add,
+            items: vec![true]
+        }],
+        env.get_bool_list(&option_id!("bools")).unwrap().unwrap()
+    );
+}
+
+#[test]
+fn test_dict() {
+    let env = env([
+        ("PANTS_REPLACE", "{'foo': 1}"),
+        ("PANTS_ADD", "+{'bar': [\"baz\"]}"),
+    ]);
+
+    assert_eq!(
+        vec![DictEdit {
+            action: DictEditAction::Replace,
+            items: hashmap! {"foo".to_string() => Val::Int(1)},
+        }],
+        env.get_dict(&option_id!("replace")).unwrap().unwrap()
+    );
+    assert_eq!(
+        vec![DictEdit {
+            action: DictEditAction::Add,
+            items: hashmap! {
+                "bar".to_string() => Val::List(vec![Val::String("baz".to_string())]),
+            },
+        }],
+        env.get_dict(&option_id!("add")).unwrap().unwrap()
+    );
+    assert!(env.get_dict(&option_id!("dne")).unwrap().is_none());
+}
+
 #[test]
 fn test_scalar_fromfile() {
     fn do_test<T: PartialEq + Debug>(
@@ -326,3 +389,73 @@ fn test_nonexistent_optional_fromfile() {
     let env = env([("PANTS_FOO", "@?/does/not/exist")]);
     assert!(env.get_string(&option_id!("foo")).unwrap().is_none());
 }
+
+#[test]
+fn test_parse_dotenv() {
+    let content = r#"
+# A comment.
+FOO=bar
+export BAZ = qux  # Trailing comment.
+EMPTY=
+SINGLE='literal \n # not a comment'
+DOUBLE="line1\nline2 \"quoted\" \\"  # Comment.
+URL=http://example.com/#anchor
+"#;
+    assert_eq!(
+        vec![
+            ("FOO".to_string(), "bar".to_string()),
+            ("BAZ".to_string(), "qux".to_string()),
+            ("EMPTY".to_string(), "".to_string()),
+            (
+                "SINGLE".to_string(),
+                "literal \\n # not a comment".to_string()
+            ),
+            (
+                "DOUBLE".to_string(),
+                "line1\nline2 \"quoted\" \\".to_string()
+            ),
+            ("URL".to_string(), "http://example.com/#anchor".to_string()),
+        ],
+        parse_dotenv(content).unwrap()
+    );
+
+    let assert_invalid = |content: &str, expected: &str| {
+        assert_eq!(expected, parse_dotenv(content).unwrap_err());
+    };
+    assert_invalid("FOO=bar\nBAZ", "line 2: Expected `NAME=value`: BAZ");
+    assert_invalid("1FOO=bar", "line 1: Invalid variable name: 1FOO=bar");
+    assert_invalid("FOO='bar", "line 1: Unterminated quoted value: FOO='bar");
+    assert_invalid(
+        "FOO=\"bar\" baz",
+        "line 1: Unexpected characters after quoted value: FOO=\"bar\" baz",
+    );
+}
+
+#[test]
+fn test_with_dotenv_file() {
+    let tmpdir = tempfile::TempDir::new().unwrap();
+    let path = tmpdir.path().join(".env");
+    let real_env = || Env::new(hashmap! {"FOO".to_string() => "real".to_string()});
+
+    // A missing file is ignored.
+    assert_eq!(
+        real_env().env,
+        real_env().with_dotenv_file(&path).unwrap().env
+    );
+
+    std::fs::write(&path, "FOO=dotenv\nBAR=dotenv\nBAR=dotenv_again\n").unwrap();
+    assert_eq!(
+        hashmap! {
+            "FOO".to_string() => "real".to_string(),
+            "BAR".to_string() => "dotenv_again".to_string(),
+        },
+        real_env().with_dotenv_file(&path).unwrap().env
+    );
+
+    std::fs::write(&path, "FOO\n").unwrap();
+    let err = real_env().with_dotenv_file(&path).unwrap_err();
+    assert!(
+        err.starts_with(&format!("Failed to parse {}: line 1", path.display())),
+        "{err}"
+    );
+}
@@ -3,12 +3,24 @@
 
 use super::{BuildRoot, DictEdit, DictEditAction, ListEdit, ListEditAction};
 
-use crate::parse::{mk_parse_err, parse_dict, ParseError, Parseable};
+use crate::parse::{mk_parse_err, mk_secret_err, parse_dict, ParseError, Parseable};
+use lazy_static::lazy_static;
 use log::warn;
 use serde::de::Deserialize;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
 use std::{fs, io};
 
+lazy_static! {
+    // The outputs of the commands of `@cmd` secret references, by build root and command. Options
+    // are parsed repeatedly (and by both the Rust and Python parsers), so commands are memoized
+    // in order to run at most once per process.
+    static ref SECRET_COMMAND_OUTPUTS: Mutex<HashMap<(PathBuf, String), String>> =
+        Mutex::new(HashMap::new());
+}
+
 // If the corresponding unexpanded value points to a @fromfile, then the
 // first component is the path to that file, and the second is the value from the file,
 // or None if the file doesn't exist and the @?fromfile syntax was used.
@@ -51,6 +63,28 @@ fn try_deserialize<'a, DE: Deserialize<'a>>(
     }
 }
 
+///
+/// A reference to a secret option value, which is resolved when the option is parsed: either
+/// `@cmd(<command>)`, for the output of a command, or `@file(<path>)`, for the content of a file.
+///
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) enum SecretRef<'a> {
+    Cmd(&'a str),
+    File(&'a str),
+}
+
+impl<'a> SecretRef<'a> {
+    pub(crate) fn parse(value: &'a str) -> Option<SecretRef<'a>> {
+        if let Some(command) = value.strip_prefix("@cmd(") {
+            command.strip_suffix(')').map(SecretRef::Cmd)
+        } else if let Some(path) = value.strip_prefix("@file(") {
+            path.strip_suffix(')').map(SecretRef::File)
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct FromfileExpander {
     build_root: BuildRoot,
@@ -73,7 +107,66 @@ impl FromfileExpander {
         }
     }
 
+    // Resolves a secret reference. The command of a `@cmd` reference is split like a shell
+    // command line, but is not run by a shell, and runs in the build root. Surrounding whitespace
+    // (such as a trailing newline) is trimmed from the resolved value.
+    fn resolve_secret(&self, secret_ref: SecretRef, value: &str) -> Result<String, ParseError> {
+        let resolved = match secret_ref {
+            SecretRef::Cmd(command) => {
+                let memo_key = (self.build_root.to_path_buf(), command.to_owned());
+                if let Some(output) = SECRET_COMMAND_OUTPUTS.lock().unwrap().get(&memo_key) {
+                    return Ok(output.clone());
+                }
+                let argv = shlex::split(command)
+                    .filter(|argv| !argv.is_empty())
+                    .ok_or_else(|| mk_secret_err("Invalid command", value))?;
+                let output = Command::new(&argv[0])
+                    .args(&argv[1..])
+                    .current_dir(self.build_root.as_path())
+                    .output()
+                    .map_err(|e| mk_secret_err(e, value))?;
+                if !output.status.success() {
+                    return Err(mk_secret_err(
+                        format!(
+                            "The command failed with {}: {}",
+                            output.status,
+                            String::from_utf8_lossy(&output.stderr).trim()
+                        ),
+                        value,
+                    ));
+                }
+                let output = String::from_utf8(output.stdout)
+                    .map_err(|_| mk_secret_err("The output of the command is not UTF-8", value))?
+                    .trim()
+                    .to_owned();
+                SECRET_COMMAND_OUTPUTS
+                    .lock()
+                    .unwrap()
+                    .insert(memo_key, output.clone());
+                output
+            }
+            SecretRef::File(path) => fs::read_to_string(self.build_root.join(path))
+                .map_err(|e| mk_secret_err(e, value))?,
+        };
+        Ok(resolved.trim().to_owned())
+    }
+
+    ///
+    /// Resolves the given `@cmd(...)` or `@file(...)` secret reference, for the option with the
+    /// given name.
+    ///
+    pub fn resolve_secret_ref(&self, value: &str, name: &str) -> Result<String, String> {
+        let secret_ref = SecretRef::parse(value)
+            .ok_or_else(|| format!("{value} is not a secret reference, for {name}."))?;
+        self.resolve_secret(secret_ref, value)
+            .map_err(|e| e.render(name))
+    }
+
     fn maybe_expand(&self, value: String) -> Result<ExpandedValue, ParseError> {
+        if let Some(secret_ref) = SecretRef::parse(&value) {
+            // Secrets are never deserialized from JSON or YAML, whatever their source.
+            return Ok((None, Some(self.resolve_secret(secret_ref, &value)?)));
+        }
         if let Some(suffix) = value.strip_prefix('@') {
             if suffix.starts_with('@') {
                 // @@ escapes the initial @.
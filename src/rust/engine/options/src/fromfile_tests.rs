@@ -317,3 +317,78 @@ fn test_expand_fromfile_to_dict() {
         res.unwrap().unwrap()
     );
 }
+
+#[test]
+fn test_parse_secret_ref() {
+    assert_eq!(
+        Some(SecretRef::Cmd("vault read -field=token secret/ci")),
+        SecretRef::parse("@cmd(vault read -field=token secret/ci)")
+    );
+    assert_eq!(
+        Some(SecretRef::File("/run/secrets/token")),
+        SecretRef::parse("@file(/run/secrets/token)")
+    );
+    assert_eq!(None, SecretRef::parse("@cmd(unterminated"));
+    assert_eq!(None, SecretRef::parse("@file.txt"));
+    assert_eq!(None, SecretRef::parse("cmd(echo)"));
+}
+
+#[test]
+fn test_expand_secret() {
+    let (tmpdir, secret_path) = write_fromfile("token.json", "s3cr3t\n");
+    let expander = FromfileExpander::relative_to(BuildRoot::for_path(tmpdir.path().to_owned()));
+
+    assert_eq!(
+        Ok(Some("s3cr3t".to_string())),
+        expander.expand("@file(token.json)".to_string())
+    );
+    assert_eq!(
+        Ok(Some("s3cr3t".to_string())),
+        expander.expand(format!("@file({})", secret_path.display()))
+    );
+    // Secrets are not deserialized, even from a file with a JSON extension.
+    assert_eq!(
+        Ok(Some(vec![ListEdit {
+            action: ListEditAction::Add,
+            items: vec!["s3cr3t".to_string()]
+        }])),
+        expander.expand_to_list::<String>("@file(token.json)".to_string())
+    );
+    // Commands run in the build root.
+    assert_eq!(
+        Ok(Some("s3cr3t".to_string())),
+        expander.expand("@cmd(cat 'token.json')".to_string())
+    );
+
+    let render_err = |value: &str| {
+        expander
+            .expand(value.to_string())
+            .unwrap_err()
+            .render("XXX")
+    };
+    assert!(render_err("@file(missing)")
+        .starts_with("Problem resolving the secret @file(missing) for XXX: No such file"));
+    assert_eq!(
+        "Problem resolving the secret @cmd(sh -c 'echo denied >&2; exit 3') for XXX: The command \
+         failed with exit status: 3: denied",
+        render_err("@cmd(sh -c 'echo denied >&2; exit 3')")
+    );
+    assert_eq!(
+        "Problem resolving the secret @cmd() for XXX: Invalid command",
+        render_err("@cmd()")
+    );
+}
+
+#[test]
+fn test_expand_secret_command_is_memoized() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let expander = FromfileExpander::relative_to(BuildRoot::for_path(tmpdir.path().to_owned()));
+    let value = "@cmd(sh -c 'echo run >> runs; wc -l < runs')".to_string();
+
+    assert_eq!(Ok(Some("1".to_string())), expander.expand(value.clone()));
+    assert_eq!(Ok(Some("1".to_string())), expander.expand(value));
+    assert_eq!(
+        "run\n",
+        std::fs::read_to_string(tmpdir.path().join("runs")).unwrap()
+    );
+}
@@ -1,6 +1,10 @@
 // Copyright 2021 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
+mod alias;
+#[cfg(test)]
+mod alias_tests;
+
 mod args;
 #[cfg(test)]
 mod args_tests;
@@ -9,6 +13,10 @@ mod build_root;
 #[cfg(test)]
 mod build_root_tests;
 
+mod completion;
+#[cfg(test)]
+mod completion_tests;
+
 mod config;
 #[cfg(test)]
 mod config_tests;
@@ -29,6 +37,14 @@ mod parse;
 #[cfg(test)]
 mod parse_tests;
 
+mod schema;
+#[cfg(test)]
+mod schema_tests;
+
+mod suggest;
+#[cfg(test)]
+mod suggest_tests;
+
 #[cfg(test)]
 mod tests;
 
@@ -37,7 +53,7 @@ mod types;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Debug;
 use std::hash::Hash;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use serde::Deserialize;
@@ -48,12 +64,26 @@ pub use self::config::ConfigSource;
 use self::config::{Config, ConfigReader};
 pub use self::env::Env;
 use self::env::EnvReader;
-use crate::fromfile::FromfileExpander;
+pub use crate::fromfile::FromfileExpander;
 use crate::parse::Parseable;
 pub use build_root::BuildRoot;
+pub use completion::{completion_script, CompletionOption, CompletionScope, Shell};
 pub use id::{OptionId, Scope};
+pub use schema::{config_schema, SchemaOption, SchemaScope, SchemaType, ValueType};
+pub use suggest::did_you_mean;
 pub use types::OptionType;
 
+/// The name of the optional file of environment variables in the build root (see `--dotenv`).
+pub const DOTENV_FILE: &str = ".env";
+
+/// The names of the config files in the build root which are read by default, in ascending order
+/// of precedence.
+const DEFAULT_CONFIG_FILES: &[&str] = &["pants.toml", "pants.yaml"];
+
+/// The name of the optional config files in subdirectories of the build root which override the
+/// options of the targets below them (see `OptionParser::with_directory_config`).
+pub const DIRECTORY_CONFIG_FILE: &str = "pants.dir.toml";
+
 // NB: The legacy Python options parser supported dicts with member_type "Any", which means
 // the values can be arbitrarily-nested lists, tuples and dicts, including heterogeneous
 // ones that are not supported as top-level option values. We have very few dict[Any] options,
@@ -268,6 +298,9 @@ pub struct OptionParser {
     sources: BTreeMap<Source, Arc<dyn OptionsSource>>,
     include_derivation: bool,
     passthrough_args: Option<Vec<String>>,
+    // The values that placeholders in config files are interpolated with.
+    seed_values: HashMap<String, String>,
+    fromfile_expander: FromfileExpander,
 }
 
 impl OptionParser {
@@ -282,6 +315,38 @@ impl OptionParser {
         buildroot: Option<BuildRoot>,
     ) -> Result<OptionParser, String> {
         let buildroot = buildroot.unwrap_or(BuildRoot::find()?);
+        let parser = Self::new_with_env(
+            args.clone(),
+            env.clone(),
+            config_sources.clone(),
+            allow_pantsrc,
+            include_derivation,
+            buildroot.clone(),
+        )?;
+        if !parser.parse_bool(&option_id!("dotenv"), false)?.value {
+            return Ok(parser);
+        }
+        // The `.env` file may set options itself (including those that determine which config
+        // files are read), so we parse again using the merged environment.
+        let env = env.with_dotenv_file(&buildroot.join(DOTENV_FILE))?;
+        Self::new_with_env(
+            args,
+            env,
+            config_sources,
+            allow_pantsrc,
+            include_derivation,
+            buildroot,
+        )
+    }
+
+    fn new_with_env(
+        args: Args,
+        env: Env,
+        config_sources: Option<Vec<ConfigSource>>,
+        allow_pantsrc: bool,
+        include_derivation: bool,
+        buildroot: BuildRoot,
+    ) -> Result<OptionParser, String> {
         let buildroot_string = buildroot.convert_to_string()?;
         let fromfile_expander = FromfileExpander::relative_to(buildroot);
 
@@ -291,7 +356,7 @@ impl OptionParser {
                 .map(|(k, v)| (format!("env.{k}", k = k), v.clone())),
         );
 
-        let args_reader = ArgsReader::new(args, fromfile_expander.clone());
+        let args_reader = ArgsReader::new(args.clone(), fromfile_expander.clone());
         let passthrough_args = args_reader.get_passthrough_args().cloned();
 
         let mut sources: BTreeMap<Source, Arc<dyn OptionsSource>> = BTreeMap::new();
@@ -304,6 +369,8 @@ impl OptionParser {
             sources: sources.clone(),
             include_derivation: false,
             passthrough_args: None,
+            seed_values: HashMap::new(),
+            fromfile_expander: fromfile_expander.clone(),
         };
 
         fn path_join(prefix: &str, suffix: &str) -> String {
@@ -326,11 +393,10 @@ impl OptionParser {
         let config_sources = match config_sources {
             Some(cs) => cs,
             None => {
-                let default_config_path = path_join(&buildroot_string, "pants.toml");
                 let config_paths = parser
                     .parse_string_list(
                         &option_id!("pants", "config", "files"),
-                        vec![default_config_path],
+                        default_config_paths(Path::new(&buildroot_string)),
                     )?
                     .value;
                 config_paths
@@ -359,24 +425,27 @@ impl OptionParser {
         ]);
 
         let mut ordinal: usize = 0;
+        let mut parsed_config_paths = HashSet::new();
         for config_source in config_sources {
-            let config = Config::parse(&config_source, &seed_values)?;
-            sources.insert(
-                Source::Config {
-                    ordinal,
-                    path: path_strip(
-                        &buildroot_string,
-                        config_source.path.to_string_lossy().as_ref(),
-                    ),
-                },
-                Arc::new(ConfigReader::new(config, fromfile_expander.clone())),
-            );
-            ordinal += 1;
+            for (path, config) in
+                Config::parse_with_includes(&config_source, &seed_values, &mut parsed_config_paths)?
+            {
+                sources.insert(
+                    Source::Config {
+                        ordinal,
+                        path: path_strip(&buildroot_string, path.to_string_lossy().as_ref()),
+                    },
+                    Arc::new(ConfigReader::new(config, fromfile_expander.clone())),
+                );
+                ordinal += 1;
+            }
         }
         parser = OptionParser {
             sources: sources.clone(),
             include_derivation: false,
             passthrough_args: None,
+            seed_values: HashMap::new(),
+            fromfile_expander: fromfile_expander.clone(),
         };
 
         if allow_pantsrc && parser.parse_bool(&option_id!("pantsrc"), true)?.value {
@@ -393,24 +462,50 @@ impl OptionParser {
             {
                 let rcfile_path = Path::new(&rcfile);
                 if rcfile_path.exists() {
-                    let rc_config =
-                        Config::parse(&ConfigSource::from_file(rcfile_path)?, &seed_values)?;
-                    sources.insert(
-                        Source::Config {
-                            ordinal,
-                            path: rcfile,
-                        },
-                        Arc::new(ConfigReader::new(rc_config, fromfile_expander.clone())),
-                    );
-                    ordinal += 1;
+                    for (path, rc_config) in Config::parse_with_includes(
+                        &ConfigSource::from_file(rcfile_path)?,
+                        &seed_values,
+                        &mut parsed_config_paths,
+                    )? {
+                        sources.insert(
+                            Source::Config {
+                                ordinal,
+                                path: path.to_string_lossy().into_owned(),
+                            },
+                            Arc::new(ConfigReader::new(rc_config, fromfile_expander.clone())),
+                        );
+                        ordinal += 1;
+                    }
                 }
             }
         }
-        Ok(OptionParser {
+        parser = OptionParser {
             sources,
             include_derivation,
             passthrough_args,
-        })
+            seed_values,
+            fromfile_expander: fromfile_expander.clone(),
+        };
+
+        // Now that all config has been read, expand any `[cli].alias` definitions in the args.
+        let aliases = parser
+            .parse_dict(&option_id!(["cli"], "alias"), HashMap::new())?
+            .value
+            .into_iter()
+            .map(|(alias, definition)| match definition {
+                Val::String(definition) => Ok((alias, definition)),
+                _ => Err(format!(
+                    "The definition of the alias `{alias}` in the `[cli].alias` option must be a \
+                     string, but was: {definition:?}"
+                )),
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        if !aliases.is_empty() {
+            let args_reader = ArgsReader::new(args.expand_aliases(&aliases)?, fromfile_expander);
+            parser.passthrough_args = args_reader.get_passthrough_args().cloned();
+            parser.sources.insert(Source::Flag, Arc::new(args_reader));
+        }
+        Ok(parser)
     }
 
     #[allow(clippy::type_complexity)]
@@ -666,6 +761,99 @@ impl OptionParser {
     pub fn get_passthrough_args(&self) -> Option<&Vec<String>> {
         self.passthrough_args.as_ref()
     }
+
+    ///
+    /// Returns a parser for the options of the targets in a directory, which also reads the given
+    /// directory config files (as found at the `directory_config_paths` of the directory), in
+    /// ascending order of precedence.
+    ///
+    /// Directory config files override all other config files, but not env vars or flags. Since
+    /// global options apply to the whole run, they may not be set in directory config files.
+    ///
+    pub fn with_directory_config(
+        &self,
+        config_sources: Vec<ConfigSource>,
+    ) -> Result<OptionParser, String> {
+        let mut sources = self.sources.clone();
+        let mut ordinal = sources
+            .keys()
+            .filter_map(|source| match source {
+                Source::Config { ordinal, path: _ } => Some(ordinal + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        let mut parsed_config_paths = HashSet::new();
+        for config_source in config_sources {
+            for (path, config) in Config::parse_with_includes(
+                &config_source,
+                &self.seed_values,
+                &mut parsed_config_paths,
+            )? {
+                if config
+                    .sections()
+                    .any(|section| section == Scope::Global.name())
+                {
+                    return Err(format!(
+                        "Directory config file {} may not set global options, which apply to \
+                         the whole run, but it has a [{}] section.",
+                        path.display(),
+                        Scope::Global.name()
+                    ));
+                }
+                sources.insert(
+                    Source::Config {
+                        ordinal,
+                        path: path.to_string_lossy().into_owned(),
+                    },
+                    Arc::new(ConfigReader::new(config, self.fromfile_expander.clone())),
+                );
+                ordinal += 1;
+            }
+        }
+        Ok(OptionParser {
+            sources,
+            include_derivation: self.include_derivation,
+            passthrough_args: self.passthrough_args.clone(),
+            seed_values: self.seed_values.clone(),
+            fromfile_expander: self.fromfile_expander.clone(),
+        })
+    }
+}
+
+///
+/// The paths of the default config files which exist in the given build root. If there are none,
+/// the path of `pants.toml` is returned anyway, so that its absence is reported.
+///
+fn default_config_paths(buildroot: &Path) -> Vec<String> {
+    // TODO: The calling code should traffic in Path, or OsString, not String.
+    //  For now we assume the paths are valid UTF8 strings, via unwrap().
+    let path_string = |name: &str| buildroot.join(name).to_str().unwrap().to_string();
+    let paths = DEFAULT_CONFIG_FILES
+        .iter()
+        .filter(|name| buildroot.join(name).is_file())
+        .map(|name| path_string(name))
+        .collect::<Vec<_>>();
+    if paths.is_empty() {
+        vec![path_string(DEFAULT_CONFIG_FILES[0])]
+    } else {
+        paths
+    }
+}
+
+///
+/// The paths of the directory config files which may apply to the targets in the given directory
+/// (relative to the build root), in ascending order of precedence: one in each of the directory and
+/// its ancestors, below the build root (whose config file is read regardless).
+///
+pub fn directory_config_paths(directory: &Path) -> Vec<PathBuf> {
+    let mut paths = directory
+        .ancestors()
+        .filter(|ancestor| !ancestor.as_os_str().is_empty())
+        .map(|ancestor| ancestor.join(DIRECTORY_CONFIG_FILE))
+        .collect::<Vec<_>>();
+    paths.reverse();
+    paths
 }
 
 pub fn render_choice(items: &[&str]) -> Option<String> {
@@ -284,6 +284,12 @@ pub(crate) fn mk_parse_err(err: impl Display, path: &Path) -> ParseError {
     ))
 }
 
+pub(crate) fn mk_secret_err(err: impl Display, reference: &str) -> ParseError {
+    ParseError::new(format!(
+        "Problem resolving the secret {reference} for {{name}}: {err}"
+    ))
+}
+
 fn format_parse_error(
     type_id: &str,
     value: &str,
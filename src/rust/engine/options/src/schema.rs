@@ -0,0 +1,198 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::str::FromStr;
+
+use serde_json::{json, Map, Value};
+
+use crate::config::{ConfigReader, DEFAULT_SECTION, INCLUDE_KEY};
+use crate::id::{OptionId, Scope};
+
+/// The JSON Schema dialect of the generated schema.
+const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+///
+/// The type of a scalar option value, or of the members of a list option value.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ValueType {
+    Bool,
+    Int,
+    Float,
+    String,
+}
+
+impl FromStr for ValueType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bool" => Ok(ValueType::Bool),
+            "int" => Ok(ValueType::Int),
+            "float" => Ok(ValueType::Float),
+            "str" => Ok(ValueType::String),
+            _ => Err(format!(
+                "Unsupported option value type: {s}. Expected one of: bool, int, float, str."
+            )),
+        }
+    }
+}
+
+///
+/// The type of an option, as parsed by the options parser.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SchemaType {
+    Scalar(ValueType),
+    List(ValueType),
+    Dict,
+}
+
+///
+/// A registered option, as far as its schema is concerned.
+///
+pub struct SchemaOption {
+    pub id: OptionId,
+    pub option_type: SchemaType,
+    pub default: Value,
+    pub help: String,
+    /// The valid values of an option (or of the members of a list option) with a constrained set
+    /// of choices (e.g. an enum option).
+    pub choices: Vec<String>,
+    /// If the option is deprecated, a message explaining what to do instead.
+    pub deprecation: Option<String>,
+}
+
+///
+/// A registered scope, and the options registered in it.
+///
+pub struct SchemaScope {
+    pub scope: Scope,
+    pub help: String,
+    /// If the scope is deprecated, a message explaining what to do instead.
+    pub deprecation: Option<String>,
+    pub options: Vec<SchemaOption>,
+}
+
+///
+/// Generates a JSON Schema for config files (e.g. `pants.toml`) that set the given options.
+///
+/// The schema accepts the same values as the config file parser: in particular, any option may be
+/// set as a string to be parsed (which is how list and dict edits, and `@file` values, are given),
+/// and a list option may be set as a table of `add` and/or `remove` members.
+///
+pub fn config_schema(scopes: &[SchemaScope]) -> Value {
+    let mut properties = Map::new();
+    properties.insert(
+        INCLUDE_KEY.to_owned(),
+        json!({
+            "description": "Other config files to read before this one, relative to it.",
+            "type": "array",
+            "items": {"type": "string"},
+        }),
+    );
+    properties.insert(
+        DEFAULT_SECTION.to_owned(),
+        json!({
+            "description": "Values for the options of this name in any scope.",
+            "type": "object",
+        }),
+    );
+    for scope in scopes {
+        properties.insert(scope.scope.name().to_owned(), scope_schema(scope));
+    }
+    json!({
+        "$schema": JSON_SCHEMA_DIALECT,
+        "title": "Pants config file",
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+    })
+}
+
+fn scope_schema(scope: &SchemaScope) -> Value {
+    let properties = scope
+        .options
+        .iter()
+        .map(|option| (ConfigReader::option_name(&option.id), option_schema(option)))
+        .collect::<Map<_, _>>();
+    let mut schema = json!({
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+    });
+    describe(&mut schema, &scope.help, scope.deprecation.as_deref());
+    schema
+}
+
+fn option_schema(option: &SchemaOption) -> Value {
+    let mut schema = match option.option_type {
+        SchemaType::Scalar(ValueType::String) => value_schema(ValueType::String, &option.choices),
+        SchemaType::Scalar(value_type) => {
+            any_of([value_schema(value_type, &option.choices), parsed_string()])
+        }
+        SchemaType::List(member_type) => {
+            let array = json!({
+                "type": "array",
+                "items": value_schema(member_type, &option.choices),
+            });
+            any_of([
+                array.clone(),
+                json!({
+                    "type": "object",
+                    "properties": {"add": array.clone(), "remove": array},
+                    "additionalProperties": false,
+                    "minProperties": 1,
+                }),
+                parsed_string(),
+            ])
+        }
+        SchemaType::Dict => any_of([json!({"type": "object"}), parsed_string()]),
+    };
+    describe(&mut schema, &option.help, option.deprecation.as_deref());
+    if !option.default.is_null() {
+        schema["default"] = option.default.clone();
+    }
+    schema
+}
+
+fn value_schema(value_type: ValueType, choices: &[String]) -> Value {
+    match value_type {
+        ValueType::Bool => json!({"type": "boolean"}),
+        ValueType::Int => json!({"type": "integer"}),
+        ValueType::Float => json!({"type": "number"}),
+        ValueType::String if choices.is_empty() => json!({"type": "string"}),
+        ValueType::String => any_of([json!({"enum": choices}), fromfile_string()]),
+    }
+}
+
+///
+/// A string that is parsed as the value of an option (or expanded from a file, if it starts
+/// with `@`).
+///
+fn parsed_string() -> Value {
+    json!({"type": "string"})
+}
+
+fn fromfile_string() -> Value {
+    json!({"type": "string", "pattern": "^@"})
+}
+
+fn any_of<const N: usize>(schemas: [Value; N]) -> Value {
+    json!({ "anyOf": Vec::from(schemas) })
+}
+
+fn describe(schema: &mut Value, help: &str, deprecation: Option<&str>) {
+    let description = match deprecation {
+        Some(deprecation) => {
+            schema["deprecated"] = Value::Bool(true);
+            format!("{help}\n\nDeprecated: {deprecation}")
+                .trim()
+                .to_owned()
+        }
+        None => help.trim().to_owned(),
+    };
+    if !description.is_empty() {
+        schema["description"] = Value::String(description);
+    }
+}
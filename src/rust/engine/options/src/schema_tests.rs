@@ -0,0 +1,178 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::str::FromStr;
+
+use serde_json::{json, Value};
+
+use crate::schema::{config_schema, SchemaOption, SchemaScope, SchemaType, ValueType};
+use crate::{option_id, OptionId, Scope};
+
+fn option(id: OptionId, option_type: SchemaType, default: Value) -> SchemaOption {
+    SchemaOption {
+        id,
+        option_type,
+        default,
+        help: "Some help.".to_string(),
+        choices: vec![],
+        deprecation: None,
+    }
+}
+
+fn schema() -> Value {
+    config_schema(&[
+        SchemaScope {
+            scope: Scope::Global,
+            help: "Global options.".to_string(),
+            deprecation: None,
+            options: vec![
+                SchemaOption {
+                    choices: vec!["debug".to_string(), "info".to_string()],
+                    ..option(
+                        option_id!(-'l', "level"),
+                        SchemaType::Scalar(ValueType::String),
+                        json!("info"),
+                    )
+                },
+                option(
+                    option_id!("pantsd"),
+                    SchemaType::Scalar(ValueType::Bool),
+                    json!(true),
+                ),
+                option(
+                    option_id!("process", "cache", "size"),
+                    SchemaType::Scalar(ValueType::Int),
+                    Value::Null,
+                ),
+            ],
+        },
+        SchemaScope {
+            scope: Scope::named("python"),
+            help: "".to_string(),
+            deprecation: Some("Use [pythonic] instead.".to_string()),
+            options: vec![
+                option(
+                    option_id!(["python"], "interpreter", "constraints"),
+                    SchemaType::List(ValueType::String),
+                    json!([">=3.8"]),
+                ),
+                SchemaOption {
+                    deprecation: Some("Use `resolves_to_lockfiles` instead.".to_string()),
+                    ..option(
+                        option_id!(["python"], "resolves"),
+                        SchemaType::Dict,
+                        json!({"default": "3rdparty/python/default.lock"}),
+                    )
+                },
+            ],
+        },
+    ])
+}
+
+#[test]
+fn test_value_type_from_str() {
+    assert_eq!(Ok(ValueType::Bool), ValueType::from_str("bool"));
+    assert_eq!(Ok(ValueType::Int), ValueType::from_str("int"));
+    assert_eq!(Ok(ValueType::Float), ValueType::from_str("float"));
+    assert_eq!(Ok(ValueType::String), ValueType::from_str("str"));
+    assert!(ValueType::from_str("list").is_err());
+}
+
+#[test]
+fn test_sections() {
+    let schema = schema();
+    assert_eq!(
+        "https://json-schema.org/draft/2020-12/schema",
+        schema["$schema"]
+    );
+    assert_eq!(json!(false), schema["additionalProperties"]);
+    let mut sections = schema["properties"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    sections.sort();
+    assert_eq!(vec!["DEFAULT", "GLOBAL", "include", "python"], sections);
+    assert_eq!(
+        json!({"type": "array", "items": {"type": "string"}}),
+        without_description(&schema["properties"]["include"])
+    );
+
+    let global = &schema["properties"]["GLOBAL"];
+    assert_eq!("Global options.", global["description"]);
+    assert_eq!(json!(false), global["additionalProperties"]);
+    assert_eq!(Value::Null, global["deprecated"]);
+
+    let python = &schema["properties"]["python"];
+    assert_eq!("Deprecated: Use [pythonic] instead.", python["description"]);
+    assert_eq!(json!(true), python["deprecated"]);
+}
+
+#[test]
+fn test_scalar_options() {
+    let schema = schema();
+    let global = &schema["properties"]["GLOBAL"]["properties"];
+    assert_eq!(
+        json!({
+            "description": "Some help.",
+            "default": "info",
+            "anyOf": [{"enum": ["debug", "info"]}, {"type": "string", "pattern": "^@"}],
+        }),
+        global["level"]
+    );
+    assert_eq!(
+        json!({
+            "description": "Some help.",
+            "default": true,
+            "anyOf": [{"type": "boolean"}, {"type": "string"}],
+        }),
+        global["pantsd"]
+    );
+    assert_eq!(
+        json!({
+            "description": "Some help.",
+            "anyOf": [{"type": "integer"}, {"type": "string"}],
+        }),
+        global["process_cache_size"]
+    );
+}
+
+#[test]
+fn test_list_and_dict_options() {
+    let schema = schema();
+    let python = &schema["properties"]["python"]["properties"];
+    let array = json!({"type": "array", "items": {"type": "string"}});
+    assert_eq!(
+        json!({
+            "description": "Some help.",
+            "default": [">=3.8"],
+            "anyOf": [
+                array,
+                {
+                    "type": "object",
+                    "properties": {"add": array, "remove": array},
+                    "additionalProperties": false,
+                    "minProperties": 1,
+                },
+                {"type": "string"},
+            ],
+        }),
+        python["interpreter_constraints"]
+    );
+    assert_eq!(
+        json!({
+            "description": "Some help.\n\nDeprecated: Use `resolves_to_lockfiles` instead.",
+            "deprecated": true,
+            "default": {"default": "3rdparty/python/default.lock"},
+            "anyOf": [{"type": "object"}, {"type": "string"}],
+        }),
+        python["resolves"]
+    );
+}
+
+fn without_description(schema: &Value) -> Value {
+    let mut schema = schema.clone();
+    schema.as_object_mut().unwrap().remove("description");
+    schema
+}
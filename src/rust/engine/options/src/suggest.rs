@@ -0,0 +1,68 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+/// The maximum number of suggestions returned by `did_you_mean`.
+const MAX_SUGGESTIONS: usize = 3;
+
+///
+/// Returns the candidates which are close enough to the given (unknown) name to be likely
+/// intended instead, closest first.
+///
+/// Closeness is measured by the edit distance between the names, which must be at most a third
+/// of the length of the longer name (so that, as a rule of thumb, a single typo is tolerated in
+/// any name of at least three characters).
+///
+pub fn did_you_mean<'a, I: IntoIterator<Item = &'a str>>(
+    name: &str,
+    candidates: I,
+) -> Vec<&'a str> {
+    let mut suggestions = candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .filter_map(|candidate| {
+            let distance = edit_distance(name, candidate);
+            let max_len = name.chars().count().max(candidate.chars().count());
+            if distance * 3 <= max_len {
+                Some((distance, candidate))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    suggestions.sort();
+    suggestions.dedup();
+    suggestions
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+///
+/// The Damerau-Levenshtein (optimal string alignment) distance between the given strings: the
+/// number of single character insertions, deletions, substitutions and adjacent transpositions
+/// needed to turn one into the other.
+///
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    // The rows of the distance matrix for the prefixes of `a` of length `i - 2`, `i - 1` and `i`.
+    let mut prev_prev: Vec<usize> = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut current: Vec<usize> = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (prev[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(prev_prev[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut prev_prev, &mut prev);
+        std::mem::swap(&mut prev, &mut current);
+    }
+    prev[b.len()]
+}
@@ -0,0 +1,43 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use crate::suggest::{did_you_mean, edit_distance};
+
+#[test]
+fn test_edit_distance() {
+    assert_eq!(0, edit_distance("", ""));
+    assert_eq!(0, edit_distance("lint", "lint"));
+    assert_eq!(4, edit_distance("", "lint"));
+    assert_eq!(1, edit_distance("lint", "list"));
+    assert_eq!(1, edit_distance("lint", "lnit"));
+    assert_eq!(1, edit_distance("chek", "check"));
+    assert_eq!(3, edit_distance("kitten", "sitting"));
+}
+
+#[test]
+fn test_did_you_mean() {
+    let goals = ["check", "fmt", "fix", "lint", "list", "test"];
+    assert_eq!(vec!["lint"], did_you_mean("lnit", goals));
+    assert_eq!(vec!["check"], did_you_mean("chekc", goals));
+    assert_eq!(vec!["fix", "fmt"], did_you_mean("fit", goals));
+    assert!(did_you_mean("publish", goals).is_empty());
+    // A known name is not suggested for itself.
+    assert!(did_you_mean("check", goals).is_empty());
+
+    let flags = [
+        "--changed-since",
+        "--changed-dependents",
+        "--all-changed",
+        "--level",
+    ];
+    assert_eq!(
+        vec!["--changed-since"],
+        did_you_mean("--changed-snice", flags)
+    );
+    assert_eq!(vec!["--level"], did_you_mean("--levle", flags));
+    // Duplicate candidates are only suggested once.
+    assert_eq!(
+        vec!["--changed-since"],
+        did_you_mean("--changed-sinc", ["--changed-since", "--changed-since"])
+    );
+}
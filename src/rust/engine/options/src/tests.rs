@@ -3,13 +3,14 @@
 
 use crate::config::ConfigSource;
 use crate::{
-    option_id, Args, BuildRoot, DictEdit, DictEditAction, Env, ListEdit, ListEditAction,
-    OptionParser, Source, Val,
+    directory_config_paths, option_id, Args, BuildRoot, DictEdit, DictEditAction, Env, ListEdit,
+    ListEditAction, OptionParser, Source, Val,
 };
 use maplit::hashmap;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 use tempfile::TempDir;
 
 fn config_source() -> Source {
@@ -555,3 +556,227 @@ fn test_parse_dict_options() {
         "",
     );
 }
+
+#[test]
+fn test_env_interpolation_in_config() {
+    let cache_dir = option_id!(["scope"], "cache", "dir");
+    with_setup(
+        vec![],
+        vec![("CI_CACHE_ROOT", "/mnt/cache")],
+        "[scope]\ncache_dir = \"${env.CI_CACHE_ROOT:-%(buildroot)s/.cache}/pants\"",
+        "",
+        |option_parser| {
+            let value = option_parser.parse_string(&cache_dir, "").unwrap();
+            assert_eq!("/mnt/cache/pants", value.value);
+            assert_eq!(config_source(), value.source);
+        },
+    );
+    with_setup(
+        vec![],
+        vec![],
+        "[scope]\ncache_dir = \"${env.CI_CACHE_ROOT:-/tmp/cache}/pants\"",
+        "",
+        |option_parser| {
+            let value = option_parser.parse_string(&cache_dir, "").unwrap();
+            assert_eq!("/tmp/cache/pants", value.value);
+        },
+    );
+}
+
+#[test]
+fn test_dotenv() {
+    let buildroot = TempDir::new().unwrap();
+    File::create(buildroot.path().join(".env"))
+        .unwrap()
+        .write_all(
+            b"PANTS_SCOPE_FOO=from_dotenv\nPANTS_SCOPE_BAR=from_dotenv\nCACHE_ROOT=/dotenv\n",
+        )
+        .unwrap();
+    let config_path = buildroot.path().join("pants.toml");
+    let mk_parser = |config: &str| {
+        File::create(&config_path)
+            .unwrap()
+            .write_all(config.as_bytes())
+            .unwrap();
+        OptionParser::new(
+            Args::new(vec![]),
+            Env::new(hashmap! {"PANTS_SCOPE_BAR".to_string() => "from_env".to_string()}),
+            Some(vec![ConfigSource::from_file(&config_path).unwrap()]),
+            false,
+            false,
+            Some(BuildRoot::find_from(buildroot.path()).unwrap()),
+        )
+        .unwrap()
+    };
+
+    let option_parser = mk_parser(
+        "[GLOBAL]\ndotenv = true\n[scope]\ncache_dir = \"${env.CACHE_ROOT:-/tmp}/pants\"\n",
+    );
+    let foo = option_parser
+        .parse_string(&option_id!(["scope"], "foo"), "")
+        .unwrap();
+    assert_eq!("from_dotenv", foo.value);
+    assert_eq!(Source::Env, foo.source);
+    // The actual environment takes precedence over the `.env` file.
+    let bar = option_parser
+        .parse_string(&option_id!(["scope"], "bar"), "")
+        .unwrap();
+    assert_eq!("from_env", bar.value);
+    let cache_dir = option_parser
+        .parse_string(&option_id!(["scope"], "cache", "dir"), "")
+        .unwrap();
+    assert_eq!("/dotenv/pants", cache_dir.value);
+
+    // The `.env` file is ignored unless enabled.
+    let option_parser = mk_parser("[scope]\ncache_dir = \"${env.CACHE_ROOT:-/tmp}/pants\"\n");
+    let foo = option_parser
+        .parse_string(&option_id!(["scope"], "foo"), "")
+        .unwrap();
+    assert_eq!(Source::Default, foo.source);
+    let cache_dir = option_parser
+        .parse_string(&option_id!(["scope"], "cache", "dir"), "")
+        .unwrap();
+    assert_eq!("/tmp/pants", cache_dir.value);
+}
+
+#[test]
+fn test_cli_aliases() {
+    with_setup(
+        vec!["-ldebug", "green", "--all-changed", "--", "green"],
+        vec![],
+        "[cli.alias]\n\
+         green = \"fmt --only=black lint\"\n\
+         --all-changed = \"--changed-since=HEAD\"\n",
+        "",
+        |option_parser| {
+            let only = option_parser
+                .parse_string_list(&option_id!(["fmt"], "only"), vec![])
+                .unwrap();
+            assert_eq!(vec!["black".to_string()], only.value);
+            assert_eq!(Source::Flag, only.source);
+            let changed_since = option_parser
+                .parse_string(&option_id!(["lint"], "changed", "since"), "")
+                .unwrap();
+            assert_eq!("HEAD", changed_since.value);
+            assert_eq!(
+                "debug",
+                option_parser
+                    .parse_string(&option_id!(-'l', "level"), "info")
+                    .unwrap()
+                    .value
+            );
+            // Passthrough args are not expanded.
+            assert_eq!(
+                Some(&vec!["green".to_string()]),
+                option_parser.get_passthrough_args()
+            );
+        },
+    );
+}
+
+#[test]
+fn test_default_config_files() {
+    let buildroot = TempDir::new().unwrap();
+    let write = |name: &str, content: &str| {
+        File::create(buildroot.path().join(name))
+            .unwrap()
+            .write_all(content.as_bytes())
+            .unwrap()
+    };
+    let parse_foo = || {
+        let option_parser = OptionParser::new(
+            Args::new(vec![]),
+            Env::new(HashMap::new()),
+            None,
+            false,
+            false,
+            Some(BuildRoot::for_path(buildroot.path().to_path_buf())),
+        )?;
+        let foo = option_parser.parse_string(&option_id!(["scope"], "foo"), "default")?;
+        Ok::<_, String>((foo.value, foo.source))
+    };
+    let yaml_source = |ordinal| Source::Config {
+        ordinal,
+        path: "pants.yaml".to_string(),
+    };
+
+    assert!(parse_foo().unwrap_err().contains("pants.toml"));
+
+    write("pants.yaml", "scope:\n  foo: from_yaml\n");
+    assert_eq!(Ok(("from_yaml".to_string(), yaml_source(0))), parse_foo());
+
+    // If both exist, `pants.yaml` overrides `pants.toml`.
+    write("pants.toml", "[scope]\nfoo = 'from_toml'\n");
+    assert_eq!(Ok(("from_yaml".to_string(), yaml_source(1))), parse_foo());
+}
+
+#[test]
+fn test_directory_config_paths() {
+    assert_eq!(
+        vec![
+            PathBuf::from("src/pants.dir.toml"),
+            PathBuf::from("src/python/pants.dir.toml"),
+            PathBuf::from("src/python/legacy/pants.dir.toml"),
+        ],
+        directory_config_paths(Path::new("src/python/legacy"))
+    );
+    assert!(directory_config_paths(Path::new("")).is_empty());
+}
+
+#[test]
+fn test_with_directory_config() {
+    let directory_config = |path: &str, content: &str| ConfigSource {
+        path: PathBuf::from(path),
+        content: content.to_string(),
+    };
+    with_setup(
+        vec!["--scope-flagged=from_flag"],
+        vec![],
+        "[scope]\nfoo = 'root'\nbar = 'root'\nflagged = 'root'\nitems = ['a']\n",
+        "",
+        |option_parser| {
+            let option_parser = option_parser
+                .with_directory_config(vec![
+                    directory_config(
+                        "src/pants.dir.toml",
+                        "[scope]\nfoo = 'src'\nbar = 'src'\nflagged = 'src'\nitems.add = ['b']\n",
+                    ),
+                    directory_config("src/legacy/pants.dir.toml", "[scope]\nfoo = 'legacy'\n"),
+                ])
+                .unwrap();
+            let foo = option_parser
+                .parse_string(&option_id!(["scope"], "foo"), "")
+                .unwrap();
+            assert_eq!("legacy", foo.value);
+            assert_eq!(
+                Source::Config {
+                    ordinal: 3,
+                    path: "src/legacy/pants.dir.toml".to_string()
+                },
+                foo.source
+            );
+            let bar = option_parser
+                .parse_string(&option_id!(["scope"], "bar"), "")
+                .unwrap();
+            assert_eq!("src", bar.value);
+            // Directory config files do not override flags.
+            let flagged = option_parser
+                .parse_string(&option_id!(["scope"], "flagged"), "")
+                .unwrap();
+            assert_eq!("from_flag", flagged.value);
+            let items = option_parser
+                .parse_string_list(&option_id!(["scope"], "items"), vec![])
+                .unwrap();
+            assert_eq!(vec!["a".to_string(), "b".to_string()], items.value);
+
+            let err = option_parser
+                .with_directory_config(vec![directory_config(
+                    "src/pants.dir.toml",
+                    "[GLOBAL]\nlevel = 'debug'\n",
+                )])
+                .err()
+                .unwrap();
+            assert!(err.starts_with("Directory config file src/pants.dir.toml may not set global"));
+        },
+    );
+}
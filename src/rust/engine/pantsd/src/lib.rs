@@ -4,9 +4,13 @@
 pub mod pantsd_testing;
 #[cfg(test)]
 mod pantsd_tests;
+mod startup;
 
+use std::fmt;
 use std::fs;
+use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use libc::pid_t;
 use log::debug;
@@ -15,6 +19,8 @@ use sha2::digest::Update;
 use sha2::{Digest, Sha256};
 use sysinfo::{ProcessExt, ProcessStatus, System, SystemExt};
 
+use crate::startup::StartupProgress;
+
 pub struct ConnectionSettings {
     pub port: u16,
     pub timeout_limit: f64,
@@ -33,10 +39,12 @@ impl ConnectionSettings {
 
 pub(crate) struct Metadata {
     metadata_dir: PathBuf,
+    lifecycle_lock_path: PathBuf,
 }
 
 impl Metadata {
-    pub(crate) fn mount<P: AsRef<Path>>(directory: P) -> Result<Metadata, String> {
+    /// The directory of the metadata of `pantsd` for this host, below the given directory.
+    pub(crate) fn dir<P: AsRef<Path>>(directory: P) -> Result<PathBuf, String> {
         let info = uname::uname().map_err(|e| format!("{e}"))?;
         let host_hash = Sha256::new()
             .chain(&info.sysname)
@@ -49,9 +57,17 @@ impl Metadata {
         const HOST_FINGERPRINT_LENGTH: usize = 6;
         let hex_digest = hex::encode(&host_hash[..HOST_FINGERPRINT_LENGTH]);
 
-        let metadata_dir = directory.as_ref().join(hex_digest).join("pantsd");
+        Ok(directory.as_ref().join(hex_digest).join("pantsd"))
+    }
+
+    pub(crate) fn mount<P: AsRef<Path>>(directory: P) -> Result<Metadata, String> {
+        let metadata_dir = Self::dir(&directory)?;
         if metadata_dir.is_dir() {
-            Ok(Metadata { metadata_dir })
+            Ok(Metadata {
+                metadata_dir,
+                // NB: This must match the path of `ProcessManager.lifecycle_lock` in Python.
+                lifecycle_lock_path: directory.as_ref().join(".lock.pantsd"),
+            })
         } else {
             Err(format!(
                 "There is no pantsd metadata at {metadata_dir}.",
@@ -114,6 +130,44 @@ impl Metadata {
         self.read_metadata("fingerprint").map(|(_, value)| value)
     }
 
+    ///
+    /// Removes this metadata, which was written by the given pantsd pid that is no longer running.
+    ///
+    /// This is skipped (returning false) if pantsd is concurrently being launched or terminated, as
+    /// indicated by its lifecycle lock being held, or if the metadata was meanwhile rewritten for
+    /// another pid.
+    ///
+    fn purge_stale(&self, stale_pid: pid_t) -> Result<bool, String> {
+        let lock_file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.lifecycle_lock_path)
+            .map_err(|e| {
+                format!(
+                    "Failed to open the pantsd lifecycle lock {path}: {e}",
+                    path = self.lifecycle_lock_path.display()
+                )
+            })?;
+        // The lock is released when the file is closed, at the end of this method.
+        if unsafe { libc::lockf(lock_file.as_raw_fd(), libc::F_TLOCK, 0) } != 0 {
+            debug!(
+                "Not purging stale pantsd metadata, since {path} is locked.",
+                path = self.lifecycle_lock_path.display()
+            );
+            return Ok(false);
+        }
+        if self.pid() != Ok(stale_pid) {
+            return Ok(false);
+        }
+        fs::remove_dir_all(&self.metadata_dir).map_err(|e| {
+            format!(
+                "Failed to remove stale pantsd metadata from {metadata_dir}: {e}",
+                metadata_dir = self.metadata_dir.display()
+            )
+        })?;
+        Ok(true)
+    }
+
     fn read_metadata(&self, name: &str) -> Result<(PathBuf, String), String> {
         let metadata_path = self.metadata_dir.join(name);
         fs::read_to_string(&metadata_path)
@@ -145,9 +199,41 @@ impl FingerprintedOption {
 
 type Fingerprint = String;
 
+/// Why a `pantsd` could not be connected to.
+pub(crate) enum ProbeError {
+    /// The given `pantsd` pid is running, but has not finished starting up.
+    Starting(pid_t, String),
+    /// There is no usable `pantsd`.
+    Unusable(String),
+}
+
+impl From<String> for ProbeError {
+    fn from(reason: String) -> Self {
+        ProbeError::Unusable(reason)
+    }
+}
+
+impl fmt::Display for ProbeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProbeError::Starting(_, reason) | ProbeError::Unusable(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+/// How often a `pantsd` which is starting up is probed.
+const STARTUP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 /// If there is a live `pantsd` process for a valid fingerprint in the given build root, return the
 /// ConnectionSettings to use to connect to it.
-pub fn find_pantsd(
+///
+/// If that `pantsd` is still starting up (e.g. because it was just launched by another run), this
+/// waits for up to `[GLOBAL].pantsd_timeout_when_multiple_invocations` for it to be ready, while
+/// showing a spinner and streaming the output that it logs meanwhile to stderr.
+///
+/// NB: This never launches a `pantsd` itself: the native client leaves that to the legacy client
+/// which it falls back to, so this only waits for a `pantsd` which some other run launched.
+pub async fn find_pantsd(
     build_root: &BuildRoot,
     options_parser: &OptionParser,
 ) -> Result<ConnectionSettings, String> {
@@ -184,8 +270,7 @@ pub fn find_pantsd(
         value = option_value.value,
         source = option_value.source
     );
-    let port = probe(build_root, &metadata_dir, options_parser)?;
-    let mut pantsd_settings = ConnectionSettings::new(port);
+    let mut pantsd_settings = ConnectionSettings::new(0);
     pantsd_settings.timeout_limit = options_parser
         .parse_float(
             &option_id!("pantsd", "timeout", "when", "multiple", "invocations"),
@@ -195,71 +280,139 @@ pub fn find_pantsd(
     pantsd_settings.dynamic_ui = options_parser
         .parse_bool(&option_id!("dynamic", "ui"), pantsd_settings.dynamic_ui)?
         .value;
+    pantsd_settings.port =
+        await_startup(build_root, &metadata_dir, options_parser, &pantsd_settings).await?;
     Ok(pantsd_settings)
 }
 
-pub(crate) fn probe(
+/// Probes for a `pantsd`, waiting for one which is still starting up for up to the timeout of the
+/// given settings (or forever, if it is negative).
+async fn await_startup(
     build_root: &BuildRoot,
     metadata_dir: &Path,
     options_parser: &OptionParser,
+    pantsd_settings: &ConnectionSettings,
 ) -> Result<u16, String> {
-    let pantsd_metadata = Metadata::mount(metadata_dir)?;
+    let started_waiting = Instant::now();
+    let mut startup_progress = None;
+    loop {
+        match probe(build_root, metadata_dir, options_parser) {
+            Ok(port) => return Ok(port),
+            Err(ProbeError::Starting(pid, reason)) => {
+                let waited = started_waiting.elapsed().as_secs_f64();
+                if pantsd_settings.timeout_limit >= 0.0 && waited >= pantsd_settings.timeout_limit {
+                    return Err(format!(
+                        "{reason} Timed out after waiting {waited:.1}s for it to start: see its \
+                        log at {log_path}.",
+                        log_path = pantsd_log_path(build_root, options_parser)?.display(),
+                    ));
+                }
+                if startup_progress.is_none() {
+                    debug!("{reason} Waiting for it to start.");
+                    startup_progress = Some(StartupProgress::new(
+                        pantsd_log_path(build_root, options_parser)?,
+                        pid,
+                        pantsd_settings.dynamic_ui,
+                    ));
+                }
+                if let Some(progress) = &mut startup_progress {
+                    progress.stream_log();
+                }
+                tokio::time::sleep(STARTUP_POLL_INTERVAL).await;
+            }
+            Err(ProbeError::Unusable(reason)) => return Err(reason),
+        }
+    }
+}
 
-    // Grab the purported port early. If we can't get that, then none of the following checks
-    // are useful.
-    let port = pantsd_metadata.port()?;
+/// The log of `pantsd`: see `pants_log_path` in Python.
+fn pantsd_log_path(
+    build_root: &BuildRoot,
+    options_parser: &OptionParser,
+) -> Result<PathBuf, String> {
+    let default_workdir = build_root.join(".pants.d").join("workdir");
+    let workdir = options_parser.parse_string(
+        &option_id!("pants", "workdir"),
+        default_workdir
+            .to_str()
+            .ok_or_else(|| format!("Build root was not UTF8: {default_workdir:?}"))?,
+    )?;
+    Ok(build_root.join(workdir.value).join("pants.log"))
+}
 
-    let expected_fingerprint = pantsd_metadata.fingerprint()?;
-    let actual_fingerprint = fingerprint_compute(build_root, options_parser)?;
-    if expected_fingerprint != actual_fingerprint {
-        return Err(format!(
-            "Fingerprint mismatched: {expected_fingerprint} vs {actual_fingerprint}."
-        ));
-    }
+pub(crate) fn probe(
+    build_root: &BuildRoot,
+    metadata_dir: &Path,
+    options_parser: &OptionParser,
+) -> Result<u16, ProbeError> {
+    let pantsd_metadata = Metadata::mount(metadata_dir)?;
 
+    // Check that the recorded pid is a live pantsd process first: if it isn't, then the rest of the
+    // metadata is stale, and is removed so that it is not mistaken for that of a live pantsd.
     let pid = pantsd_metadata.pid()?;
+    let stale = |reason: String| -> ProbeError {
+        ProbeError::Unusable(match pantsd_metadata.purge_stale(pid) {
+            Ok(true) => format!("{reason} Removed its stale metadata."),
+            Ok(false) => reason,
+            Err(e) => format!("{reason} {e}"),
+        })
+    };
     let mut system = System::new();
     system.refresh_process(pid);
-    // Check that the recorded pid is a live process.
-    match system.process(pid) {
-        None => Err(format!(
+    let Some(process) = system.process(pid) else {
+        return Err(stale(format!(
             "\
         The last pid for the pantsd controlling {build_root} was {pid} but it no longer appears \
         to be running.\
         ",
             build_root = build_root.display(),
             pid = pid,
-        )),
-        Some(process) => {
-            // Check that the live process is in fact the expected pantsd process (i.e.: pids have not
-            // wrapped).
-            if std::mem::discriminant(&ProcessStatus::Zombie)
-                == std::mem::discriminant(&process.status())
-            {
-                return Err(format!("The pantsd at pid {pid} is a zombie."));
-            }
-            let expected_process_name_prefix = pantsd_metadata.process_name()?;
-            let actual_argv0 = {
-                let actual_command_line = process.cmd();
-                if actual_command_line.is_empty() {
-                    process.name()
-                } else {
-                    &actual_command_line[0]
-                }
-            };
-            // It appears that the daemon only records a prefix of the process name, so we just check that.
-            if actual_argv0.starts_with(&expected_process_name_prefix) {
-                Ok(port)
-            } else {
-                Err(format!(
-                    "\
+        )));
+    };
+    // Check that the live process is in fact the expected pantsd process (i.e.: pids have not
+    // wrapped).
+    if std::mem::discriminant(&ProcessStatus::Zombie) == std::mem::discriminant(&process.status()) {
+        return Err(stale(format!("The pantsd at pid {pid} is a zombie.")));
+    }
+    let expected_process_name_prefix = pantsd_metadata.process_name()?;
+    let actual_argv0 = {
+        let actual_command_line = process.cmd();
+        if actual_command_line.is_empty() {
+            process.name()
+        } else {
+            &actual_command_line[0]
+        }
+    };
+    // It appears that the daemon only records a prefix of the process name, so we just check that.
+    if !actual_argv0.starts_with(&expected_process_name_prefix) {
+        return Err(stale(format!(
+            "\
           The process with pid {pid} is not pantsd. Expected a process name matching \
           {expected_process_name_prefix} but is {actual_argv0}.\
           "
-                ))
-            }
-        }
+        )));
     }
+
+    // The daemon records its port once it has opened its socket, and then its fingerprint.
+    let port = pantsd_metadata.port().map_err(|e| {
+        ProbeError::Starting(
+            pid,
+            format!("The pantsd at pid {pid} has not opened its socket yet: {e}"),
+        )
+    })?;
+    let expected_fingerprint = pantsd_metadata.fingerprint().map_err(|e| {
+        ProbeError::Starting(
+            pid,
+            format!("The pantsd at pid {pid} has not recorded its fingerprint yet: {e}"),
+        )
+    })?;
+    let actual_fingerprint = fingerprint_compute(build_root, options_parser)?;
+    if expected_fingerprint != actual_fingerprint {
+        return Err(ProbeError::Unusable(format!(
+            "Fingerprint mismatched: {expected_fingerprint} vs {actual_fingerprint}."
+        )));
+    }
+    Ok(port)
 }
 
 /// Computes a fingerprint of the relevant options for `pantsd` (see `fingerprinted_options`).
@@ -331,6 +484,13 @@ pub fn fingerprinted_options(build_root: &BuildRoot) -> Result<Vec<Fingerprinted
     Ok(vec![
         FingerprintedOption::new(option_id!(-'l', "level"), "info"),
         FingerprintedOption::new(option_id!("show", "log", "target"), false),
+        FingerprintedOption::new(option_id!("log", "format"), "text"),
+        FingerprintedOption::new(option_id!("log", "max", "size", "bytes"), 100_000_000),
+        FingerprintedOption::new(option_id!("log", "max", "rotated", "files"), 5),
+        FingerprintedOption::new(option_id!("log", "compress", "rotated", "files"), false),
+        FingerprintedOption::new(option_id!("log", "max", "run", "logs"), 100),
+        FingerprintedOption::new(option_id!("log", "dedup", "window"), 10.0),
+        FingerprintedOption::new(option_id!("log", "dedup", "excluded", "targets"), vec![]),
         // TODO: No support for parsing dictionaries, so not fingerprinted. But should be. See #19832.
         // FingerprintedOption::new(option_id!("log", "levels", "by", "target"), ...),
         FingerprintedOption::new(option_id!("log", "show", "rust", "3rdparty"), false),
@@ -351,8 +511,24 @@ pub fn fingerprinted_options(build_root: &BuildRoot) -> Result<Vec<Fingerprinted
             dot_pants_dot_d_subdir("pids")?,
         ),
         FingerprintedOption::new(option_id!("logdir"), "<none>"),
+        FingerprintedOption::new(option_id!("log", "system", "sink"), "<none>"),
+        FingerprintedOption::new(option_id!("digest", "function"), "sha256"),
         FingerprintedOption::new(option_id!("pantsd"), true),
         FingerprintedOption::new(option_id!("pantsd", "pailgun", "port"), 0),
+        FingerprintedOption::new(option_id!("pantsd", "tokio", "console"), false),
+        FingerprintedOption::new(option_id!("pantsd", "heap", "profiling"), false),
+        FingerprintedOption::new(
+            option_id!("pantsd", "remote", "listen", "address"),
+            "<none>",
+        ),
+        FingerprintedOption::new(
+            option_id!("pantsd", "remote", "tls", "cert", "path"),
+            "<none>",
+        ),
+        FingerprintedOption::new(
+            option_id!("pantsd", "remote", "tls", "key", "path"),
+            "<none>",
+        ),
         FingerprintedOption::new(option_id!("pantsd", "invalidation", "globs"), vec![]),
     ])
 }
@@ -1,7 +1,13 @@
 // Copyright 2021 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
+use std::collections::HashMap;
+use std::fs;
 use std::net::TcpStream;
+use std::process::Command;
+
+use options::{Args, BuildRoot, Env, OptionParser};
+use tempfile::TempDir;
 
 use crate::pantsd_testing::launch_pantsd;
 
@@ -24,10 +30,89 @@ fn test_address_integration() {
     assert_connect(port);
 }
 
-#[test]
-fn test_find_pantsd() {
+#[tokio::test]
+async fn test_find_pantsd() {
     let (build_root, options_parser, _tmpdir) = launch_pantsd();
 
-    let connection_settings = crate::find_pantsd(&build_root, &options_parser).unwrap();
+    let connection_settings = crate::find_pantsd(&build_root, &options_parser)
+        .await
+        .unwrap();
     assert_connect(connection_settings.port);
 }
+
+#[test]
+fn test_stale_metadata() {
+    let pants_subprocessdir = TempDir::new().unwrap();
+    let metadata_dir = crate::Metadata::dir(&pants_subprocessdir).unwrap();
+    fs::create_dir_all(&metadata_dir).unwrap();
+
+    // Record the pid of a process which is no longer running.
+    let mut process = Command::new("true").spawn().unwrap();
+    process.wait().unwrap();
+    fs::write(metadata_dir.join("pid"), process.id().to_string()).unwrap();
+    fs::write(metadata_dir.join("socket"), "1234").unwrap();
+
+    let build_root = BuildRoot::for_path(pants_subprocessdir.path().to_path_buf());
+    let options_parser = OptionParser::new(
+        Args::new(vec![]),
+        Env::new(HashMap::new()),
+        Some(vec![]),
+        false,
+        false,
+        Some(build_root.clone()),
+    )
+    .unwrap();
+    let error = crate::probe(&build_root, pants_subprocessdir.path(), &options_parser)
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(
+        error.contains("no longer appears to be running. Removed its stale metadata."),
+        "Error was: {error}"
+    );
+    assert!(!metadata_dir.exists());
+}
+
+#[tokio::test]
+async fn test_starting_pantsd_times_out() {
+    let pants_subprocessdir = TempDir::new().unwrap();
+    let metadata_dir = crate::Metadata::dir(&pants_subprocessdir).unwrap();
+    fs::create_dir_all(&metadata_dir).unwrap();
+
+    // Record this (live) process as a pantsd which has not opened its socket yet.
+    fs::write(metadata_dir.join("pid"), std::process::id().to_string()).unwrap();
+    fs::write(
+        metadata_dir.join("process_name"),
+        std::env::args().next().unwrap(),
+    )
+    .unwrap();
+
+    let build_root = BuildRoot::for_path(pants_subprocessdir.path().to_path_buf());
+    let options_parser = OptionParser::new(
+        Args::new(vec![]),
+        Env::new(HashMap::new()),
+        Some(vec![]),
+        false,
+        false,
+        Some(build_root.clone()),
+    )
+    .unwrap();
+    let mut pantsd_settings = crate::ConnectionSettings::new(0);
+    pantsd_settings.timeout_limit = 0.0;
+    pantsd_settings.dynamic_ui = false;
+    let error = crate::await_startup(
+        &build_root,
+        pants_subprocessdir.path(),
+        &options_parser,
+        &pantsd_settings,
+    )
+    .await
+    .err()
+    .unwrap();
+    assert!(
+        error.contains("has not opened its socket yet") && error.contains("Timed out"),
+        "Error was: {error}"
+    );
+    // The metadata of a pantsd which is starting up is not stale.
+    assert!(metadata_dir.exists());
+}
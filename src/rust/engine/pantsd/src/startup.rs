@@ -0,0 +1,86 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+///
+/// Reports on a `pantsd` which is starting up: shows a spinner on stderr (if the dynamic UI is
+/// enabled), and streams the lines which `pantsd` appends to its log meanwhile to stderr.
+///
+/// The spinner is cleared when this is dropped.
+///
+pub(crate) struct StartupProgress {
+    spinner: ProgressBar,
+    log_path: PathBuf,
+    log_offset: u64,
+    partial_line: Vec<u8>,
+}
+
+impl StartupProgress {
+    pub(crate) fn new(log_path: PathBuf, pid: libc::pid_t, dynamic_ui: bool) -> StartupProgress {
+        let draw_target = if dynamic_ui {
+            ProgressDrawTarget::stderr()
+        } else {
+            ProgressDrawTarget::hidden()
+        };
+        let spinner = ProgressBar::with_draw_target(None, draw_target).with_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner} {msg} ({elapsed})")
+                .expect("Valid template."),
+        );
+        spinner.set_message(format!("Waiting for pantsd (pid {pid}) to start..."));
+        spinner.enable_steady_tick(Duration::from_millis(100));
+        // Only the output which is logged from now on is streamed.
+        let log_offset = log_path.metadata().map(|m| m.len()).unwrap_or(0);
+        StartupProgress {
+            spinner,
+            log_path,
+            log_offset,
+            partial_line: Vec::new(),
+        }
+    }
+
+    /// Streams any complete lines which have been appended to the log since the last call.
+    pub(crate) fn stream_log(&mut self) {
+        let Ok(mut log) = File::open(&self.log_path) else {
+            return;
+        };
+        let len = log.metadata().map(|m| m.len()).unwrap_or(0);
+        if len < self.log_offset {
+            // The log was rotated: stream the new log from its start.
+            self.log_offset = 0;
+            self.partial_line.clear();
+        }
+        let mut appended = Vec::new();
+        if log.seek(SeekFrom::Start(self.log_offset)).is_err()
+            || log.read_to_end(&mut appended).is_err()
+        {
+            return;
+        }
+        self.log_offset += appended.len() as u64;
+        self.partial_line.extend(appended);
+
+        let Some(end) = self.partial_line.iter().rposition(|b| *b == b'\n') else {
+            return;
+        };
+        let lines = self.partial_line.drain(..=end).collect::<Vec<_>>();
+        for line in String::from_utf8_lossy(&lines).lines() {
+            if self.spinner.is_hidden() {
+                eprintln!("{line}");
+            } else {
+                self.spinner.println(line);
+            }
+        }
+    }
+}
+
+impl Drop for StartupProgress {
+    fn drop(&mut self) {
+        self.spinner.finish_and_clear();
+    }
+}
@@ -3,6 +3,7 @@
 use std::collections::{BTreeMap, HashMap};
 use std::ffi::OsStr;
 use std::fmt;
+use std::io::Write;
 use std::net::ToSocketAddrs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -55,7 +56,7 @@ pub struct CommandRunner<'a> {
     work_dir_base: PathBuf,
     immutable_inputs: ImmutableInputs,
     keep_sandboxes: KeepSandboxes,
-    container_cache: ContainerCache<'a>,
+    container_cache: Arc<ContainerCache<'a>>,
 }
 
 #[derive(Clone)]
@@ -356,24 +357,56 @@ async fn pull_image(
     Ok(())
 }
 
+/// The path within a container of the given sandbox, which must be within the `work_dir_base`.
+fn sandbox_path_in_container(work_dir_base: &Path, sandbox: &Path) -> Result<String, String> {
+    let sandbox_relpath = sandbox.strip_prefix(work_dir_base).map_err(|err| {
+        format!("Internal error - base directory was not prefix of sandbox directory: {err}")
+    })?;
+    Path::new(&SANDBOX_BASE_PATH_IN_CONTAINER)
+        .join(sandbox_relpath)
+        .into_os_string()
+        .into_string()
+        .map_err(|s| {
+            format!("Unable to convert sandbox path to string due to non UTF-8 characters: {s:?}")
+        })
+}
+
+/// Update the given Process to run in the given sandbox (which must be within the `work_dir_base`
+/// that is bind mounted into containers) by replacing `{chroot}` placeholders with the path to the
+/// sandbox within the container, and return the absolute working directory within the container.
+fn apply_container_sandbox(
+    work_dir_base: &Path,
+    sandbox: &Path,
+    req: &mut Process,
+) -> Result<String, String> {
+    let sandbox_path_in_container = sandbox_path_in_container(work_dir_base, sandbox)?;
+    apply_chroot(&sandbox_path_in_container, req);
+    log::trace!(
+        "sandbox_path_in_container = {:?}",
+        &sandbox_path_in_container
+    );
+
+    req.working_directory
+        .as_ref()
+        .map(|relpath| Path::new(&sandbox_path_in_container).join(relpath))
+        .unwrap_or_else(|| Path::new(&sandbox_path_in_container).to_path_buf())
+        .into_os_string()
+        .into_string()
+        .map_err(|s| {
+            format!("Unable to convert working directory due to non UTF-8 characters: {s:?}")
+        })
+}
+
 impl<'a> CommandRunner<'a> {
     pub fn new(
         store: Store,
         executor: Executor,
         docker: &'a DockerOnceCell,
-        image_pull_cache: &'a ImagePullCache,
+        container_cache: Arc<ContainerCache<'a>>,
         work_dir_base: PathBuf,
         immutable_inputs: ImmutableInputs,
         keep_sandboxes: KeepSandboxes,
     ) -> Result<Self, String> {
-        let container_cache = ContainerCache::new(
-            docker,
-            image_pull_cache,
-            executor.clone(),
-            &work_dir_base,
-            &immutable_inputs,
-        )?;
-
         Ok(CommandRunner {
             store,
             executor,
@@ -442,39 +475,8 @@ impl<'a> process_execution::CommandRunner for CommandRunner<'a> {
 
                 // Compute the absolute working directory within the container, and update the env to
                 // replace `{chroot}` placeholders with the path to the sandbox within the Docker container.
-                let working_dir = {
-                    let sandbox_relpath = workdir
-                        .path()
-                        .strip_prefix(&self.work_dir_base)
-                        .map_err(|err| {
-                            format!(
-                  "Internal error - base directory was not prefix of sandbox directory: {err}"
-                )
-                        })?;
-                    let sandbox_path_in_container = Path::new(&SANDBOX_BASE_PATH_IN_CONTAINER)
-            .join(sandbox_relpath)
-            .into_os_string()
-            .into_string()
-            .map_err(|s| {
-              format!("Unable to convert sandbox path to string due to non UTF-8 characters: {s:?}")
-            })?;
-                    apply_chroot(&sandbox_path_in_container, &mut req);
-                    log::trace!(
-                        "sandbox_path_in_container = {:?}",
-                        &sandbox_path_in_container
-                    );
-
-                    req
-            .working_directory
-            .as_ref()
-            .map(|relpath| Path::new(&sandbox_path_in_container).join(relpath))
-            .unwrap_or_else(|| Path::new(&sandbox_path_in_container).to_path_buf())
-            .into_os_string()
-            .into_string()
-            .map_err(|s| {
-              format!("Unable to convert working directory due to non UTF-8 characters: {s:?}")
-            })?
-                };
+                let working_dir =
+                    apply_container_sandbox(&self.work_dir_base, workdir.path(), &mut req)?;
 
                 // Prepare the workdir.
                 // DOCKER-NOTE: The input root will be bind mounted into the container.
@@ -485,9 +487,10 @@ impl<'a> process_execution::CommandRunner for CommandRunner<'a> {
                     req.input_digests.inputs.clone(),
                     &self.store,
                     &named_caches,
-                    &self.immutable_inputs,
+                    Some(&self.immutable_inputs),
                     Some(Path::new(NAMED_CACHES_BASE_PATH_IN_CONTAINER)),
                     Some(Path::new(IMMUTABLE_INPUTS_BASE_PATH_IN_CONTAINER)),
+                    None,
                 )
                 .await?;
 
@@ -744,6 +747,134 @@ impl Command {
     }
 }
 
+///
+/// A Process which runs interactively in a cached container.
+///
+/// Unlike other processes (which are run via the Docker API), interactive processes are run via the
+/// `docker exec` CLI, which allocates a PTY and places the local terminal in raw mode. But because
+/// `docker exec` does not forward signals to the process that it runs, the process records its PID
+/// in a file in the container (outside of its sandbox, so that the file is not visible to the
+/// process or captured with its outputs) so that signals can be forwarded to it with
+/// `InteractiveExec::signal`.
+///
+/// The environment of the process is passed to `docker exec` in a file which is only readable by
+/// the current user, rather than on the command line where it would be visible to other users.
+///
+pub struct InteractiveExec {
+    container_id: String,
+    working_dir: String,
+    env_file: tempfile::NamedTempFile,
+    argv: Vec<String>,
+    pid_file_in_container: String,
+}
+
+impl InteractiveExec {
+    pub fn new(
+        container_id: String,
+        working_dir: String,
+        env: &BTreeMap<String, String>,
+        argv: Vec<String>,
+        pid_file_in_container: String,
+    ) -> Result<Self, String> {
+        // NB: `NamedTempFile` creates its file with mode 0600.
+        let mut env_file = tempfile::NamedTempFile::new()
+            .map_err(|e| format!("Failed to create environment file for docker exec: {e}"))?;
+        for (key, value) in env {
+            // The env file format has no quoting, so newlines cannot be represented.
+            if key.contains('\n') || value.contains('\n') {
+                return Err(format!(
+                    "The environment variable {key:?} cannot be passed to an interactive process in \
+                     a docker environment, because it contains a newline."
+                ));
+            }
+            writeln!(env_file, "{key}={value}")
+                .map_err(|e| format!("Failed to write environment file for docker exec: {e}"))?;
+        }
+        env_file
+            .flush()
+            .map_err(|e| format!("Failed to write environment file for docker exec: {e}"))?;
+        Ok(Self {
+            container_id,
+            working_dir,
+            env_file,
+            argv,
+            pid_file_in_container,
+        })
+    }
+
+    /// Create a `docker exec` command for the process, which allocates a PTY if `tty` is set.
+    pub fn command(&self, tty: bool) -> tokio::process::Command {
+        let mut command = tokio::process::Command::new("docker");
+        command.args(["exec", "--interactive"]);
+        if tty {
+            command.arg("--tty");
+        }
+        command
+            .arg("--workdir")
+            .arg(&self.working_dir)
+            .arg("--env-file")
+            .arg(self.env_file.path())
+            .arg(&self.container_id)
+            .args(["/bin/sh", "-c", r#"echo $$ > "$0" && exec "$@""#])
+            .arg(&self.pid_file_in_container)
+            .args(&self.argv);
+        command
+    }
+
+    /// Send the given signal (e.g. `INT`) to the process within the container, if it has started.
+    pub async fn signal(&self, docker: &DockerOnceCell, signal: &str) -> Result<(), String> {
+        let (exit_code, stderr) = self
+            .exec_in_container(
+                docker,
+                r#"[ ! -f "$1" ] || kill -s "$0" "$(cat "$1")""#,
+                signal,
+            )
+            .await?;
+        // The process may already have exited, so we do not treat this as fatal.
+        if exit_code != 0 {
+            log::debug!(
+                "Failed to send {signal} to interactive process in Docker container:\n{stderr}"
+            );
+        }
+        Ok(())
+    }
+
+    /// Remove the file in the container to which the process wrote its PID.
+    pub async fn remove_pid_file(&self, docker: &DockerOnceCell) -> Result<(), String> {
+        let (exit_code, stderr) = self
+            .exec_in_container(docker, r#"rm -f "$1""#, "rm")
+            .await?;
+        if exit_code != 0 {
+            return Err(format!(
+                "Failed to remove {} in Docker container:\n{stderr}",
+                self.pid_file_in_container
+            ));
+        }
+        Ok(())
+    }
+
+    /// Run the given shell script in the container, with `$0` set to `arg0` and `$1` set to the
+    /// path of the PID file.
+    async fn exec_in_container(
+        &self,
+        docker: &DockerOnceCell,
+        script: &str,
+        arg0: &str,
+    ) -> Result<(i32, String), String> {
+        let docker = docker.get().await?;
+        let (exit_code, _stdout, stderr) = Command::new(vec![
+            "/bin/sh".to_owned(),
+            "-c".to_owned(),
+            script.to_owned(),
+            arg0.to_owned(),
+            self.pid_file_in_container.clone(),
+        ])
+        .output(docker, self.container_id.clone())
+        .await?;
+        Ok((exit_code, String::from_utf8_lossy(&stderr).into_owned()))
+    }
+}
+
 /// Container ID and NamedCaches for that container. async_oncecell::OnceCell is used so that
 /// multiple tasks trying to access an initializing container do not try to start multiple
 /// containers.
@@ -751,7 +882,7 @@ type CachedContainer = Arc<OnceCell<(String, NamedCaches)>>;
 
 /// Caches running containers so that build actions can be invoked by running "executions"
 /// within those cached containers.
-pub(crate) struct ContainerCache<'a> {
+pub struct ContainerCache<'a> {
     docker: &'a DockerOnceCell,
     image_pull_cache: &'a ImagePullCache,
     executor: Executor,
@@ -1002,6 +1133,62 @@ impl<'a> ContainerCache<'a> {
         Ok(container_id.to_owned())
     }
 
+    /// Prepare the given sandbox (which must be within the base directory which is mounted into
+    /// containers) to run the given Process (which must have the Docker execution strategy)
+    /// interactively in a container for its image.
+    pub async fn interactive_exec(
+        &self,
+        store: &Store,
+        immutable_inputs: &ImmutableInputs,
+        req: &mut Process,
+        sandbox: &Path,
+        build_generation: &str,
+    ) -> Result<InteractiveExec, String> {
+        let ProcessExecutionStrategy::Docker(image) = &req.execution_environment.strategy else {
+            return Err(
+                "The Docker execution strategy was not set on the interactive Process.".to_owned(),
+            );
+        };
+        let (container_id, named_caches) = self
+            .container_for_image(image, &req.execution_environment.platform, build_generation)
+            .await?;
+
+        let work_dir_base = Path::new(&self.work_dir_base);
+        let working_dir = apply_container_sandbox(work_dir_base, sandbox, req)?;
+        // The sandbox directory names are unique, and so are used to name the PID file in the
+        // container's own temporary directory.
+        let pid_file_in_container = format!(
+            "/tmp/.pants-interactive-{}.pid",
+            sandbox
+                .file_name()
+                .and_then(OsStr::to_str)
+                .ok_or_else(|| format!("Invalid sandbox path: {}", sandbox.display()))?
+        );
+
+        prepare_workdir(
+            sandbox.to_owned(),
+            work_dir_base,
+            req,
+            req.input_digests.inputs.clone(),
+            store,
+            &named_caches,
+            Some(immutable_inputs),
+            Some(Path::new(NAMED_CACHES_BASE_PATH_IN_CONTAINER)),
+            Some(Path::new(IMMUTABLE_INPUTS_BASE_PATH_IN_CONTAINER)),
+            None,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+        InteractiveExec::new(
+            container_id,
+            working_dir,
+            &req.env,
+            req.argv.clone(),
+            pid_file_in_container,
+        )
+    }
+
     pub async fn shutdown(&self) -> Result<(), String> {
         // Skip shutting down if Docker was never used in the first place.
         if self.containers.lock().is_empty() {
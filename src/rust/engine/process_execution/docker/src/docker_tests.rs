@@ -3,6 +3,7 @@
 use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::env;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
 use bollard::Docker;
@@ -14,7 +15,9 @@ use testutil::data::{TestData, TestDirectory};
 use testutil::{owned_string_vec, relative_paths};
 use workunit_store::{RunningWorkunit, WorkunitStore};
 
-use crate::docker::{DockerOnceCell, ImagePullCache, SANDBOX_BASE_PATH_IN_CONTAINER};
+use crate::docker::{
+    ContainerCache, DockerOnceCell, ImagePullCache, InteractiveExec, SANDBOX_BASE_PATH_IN_CONTAINER,
+};
 use process_execution::local::KeepSandboxes;
 use process_execution::{
     local, CacheName, CommandRunner, Context, FallibleProcessResultWithPlatform, InputDigests,
@@ -731,6 +734,61 @@ async fn immutable_inputs() {
     assert_eq!(result.original.exit_code, 0);
 }
 
+#[test]
+#[cfg(unix)]
+fn interactive_exec_env_is_not_in_argv() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let env = BTreeMap::from([
+        ("SECRET".to_owned(), "hunter2".to_owned()),
+        ("EMPTY".to_owned(), "".to_owned()),
+    ]);
+    let exec = InteractiveExec::new(
+        "container".to_owned(),
+        "/pants-sandbox/sandbox".to_owned(),
+        &env,
+        owned_string_vec(&["/bin/echo", "hello"]),
+        "/tmp/.pants-interactive-sandbox.pid".to_owned(),
+    )
+    .unwrap();
+
+    let command = exec.command(false);
+    let args = command
+        .as_std()
+        .get_args()
+        .map(|arg| arg.to_str().unwrap().to_owned())
+        .collect::<Vec<_>>();
+    assert!(
+        args.iter().all(|arg| !arg.contains("hunter2")),
+        "An environment value was passed on the command line: {args:?}"
+    );
+
+    let env_file = &args[args.iter().position(|arg| arg == "--env-file").unwrap() + 1];
+    assert_eq!(
+        std::fs::read_to_string(env_file).unwrap(),
+        "EMPTY=\nSECRET=hunter2\n"
+    );
+    assert_eq!(
+        std::fs::metadata(env_file).unwrap().permissions().mode() & 0o777,
+        0o600
+    );
+}
+
+#[test]
+fn interactive_exec_env_with_newline_is_rejected() {
+    let env = BTreeMap::from([("MULTILINE".to_owned(), "one\ntwo".to_owned())]);
+    let err = InteractiveExec::new(
+        "container".to_owned(),
+        "/pants-sandbox/sandbox".to_owned(),
+        &env,
+        owned_string_vec(&["/bin/true"]),
+        "/tmp/.pants-interactive-sandbox.pid".to_owned(),
+    )
+    .err()
+    .unwrap();
+    assert!(err.contains("MULTILINE"), "{err}");
+}
+
 async fn run_command_via_docker_in_dir(
     mut req: Process,
     dir: PathBuf,
@@ -753,11 +811,18 @@ async fn run_command_via_docker_in_dir(
 
     let docker = Box::new(DockerOnceCell::new());
     let image_pull_cache = Box::new(ImagePullCache::new());
+    let container_cache = Arc::new(ContainerCache::new(
+        &docker,
+        &image_pull_cache,
+        executor.clone(),
+        &dir,
+        &immutable_inputs,
+    )?);
     let runner = crate::docker::CommandRunner::new(
         store.clone(),
         executor.clone(),
         &docker,
-        &image_pull_cache,
+        container_cache,
         dir.clone(),
         immutable_inputs,
         cleanup,
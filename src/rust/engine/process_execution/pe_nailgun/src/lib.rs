@@ -192,7 +192,8 @@ impl process_execution::CommandRunner for CommandRunner {
                     client_req.input_digests.inputs.clone(),
                     &self.store,
                     &self.named_caches,
-                    &self.immutable_inputs,
+                    Some(&self.immutable_inputs),
+                    None,
                     None,
                     None,
                 )
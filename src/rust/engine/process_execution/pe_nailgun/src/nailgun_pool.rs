@@ -377,7 +377,8 @@ impl NailgunProcess {
             startup_options.input_digests.inputs.clone(),
             store,
             named_caches,
-            immutable_inputs,
+            Some(immutable_inputs),
+            None,
             None,
             None,
         )
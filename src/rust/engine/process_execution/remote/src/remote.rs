@@ -19,6 +19,7 @@ use protos::gen::google::longrunning::{
     operations_client::OperationsClient, CancelOperationRequest, Operation,
 };
 use protos::gen::google::rpc::{PreconditionFailure, Status as StatusProto};
+use protos::require_digest;
 use rand::{thread_rng, Rng};
 use remexec::{
     capabilities_client::CapabilitiesClient, execution_client::ExecutionClient,
@@ -911,9 +912,46 @@ impl process_execution::CommandRunner for CommandRunner {
         _workunit: &mut RunningWorkunit,
         request: Process,
     ) -> Result<FallibleProcessResultWithPlatform, ProcessError> {
+        // NB: REv2 can only set env vars for an execution via its `Command`, which is stored in the
+        // remote CAS (and so readable by anyone with access to it). Rather than leaking values which
+        // were marked as execution-only (e.g. credentials), we refuse to execute the process.
+        if !request.execution_only_env_vars.is_empty() {
+            return Err(format!(
+                "The process `{}` sets execution-only env vars ({}), which are not supported by \
+                 remote execution.",
+                request.description,
+                request
+                    .execution_only_env_vars
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+            .into());
+        }
+
         // Retrieve capabilities for this server.
         let capabilities = self.get_capabilities().await?;
         trace!("RE capabilities: {:?}", &capabilities);
+        let digest_function = hashing::DigestFunction::current();
+        if digest_function != hashing::DigestFunction::Sha256 {
+            let supported = capabilities
+                .execution_capabilities
+                .as_ref()
+                .map(|c| {
+                    if c.digest_functions.is_empty() {
+                        vec![c.digest_function]
+                    } else {
+                        c.digest_functions.clone()
+                    }
+                })
+                .unwrap_or_default();
+            protos::require_digest_function(
+                digest_function,
+                &supported,
+                "remote execution server",
+            )?;
+        }
 
         // Construct the REv2 ExecuteRequest and related data for this execution request.
         let EntireExecuteRequest {
@@ -952,9 +990,18 @@ impl process_execution::CommandRunner for CommandRunner {
             &self.store,
             command_digest,
             action_digest,
-            Some(input_root_digest),
+            Some(input_root_digest.clone()),
         )
         .await?;
+        // If the tool inputs of a persistent worker were marked, the action refers to a copy of the
+        // input root (which shares all of its files), which must also be uploaded.
+        let action_input_root_digest = require_digest(action.input_root_digest.as_ref())?;
+        if action_input_root_digest != input_root_digest.as_digest() {
+            let _ = self
+                .store
+                .ensure_remote_has_recursive(vec![action_input_root_digest])
+                .await?;
+        }
 
         // Submit the execution request to the RE server for execution.
         let context2 = context.clone();
@@ -1052,7 +1099,7 @@ async fn populate_fallible_execution_result_for_timeout(
 
     Ok(FallibleProcessResultWithPlatform {
         stdout_digest,
-        stderr_digest: hashing::EMPTY_DIGEST,
+        stderr_digest: *hashing::EMPTY_DIGEST,
         exit_code: -libc::SIGTERM,
         output_directory: EMPTY_DIRECTORY_DIGEST.clone(),
         metadata: ProcessResultMetadata::new(
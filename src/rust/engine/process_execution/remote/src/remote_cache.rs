@@ -191,6 +191,69 @@ impl CommandRunner {
         }
     }
 
+    /// Returns an `OutputSymlink` if the given output path is a symlink in the output digest
+    /// (including if it is a dead link), so that it is preserved rather than followed.
+    pub(crate) fn extract_output_symlink(
+        root_trie: &DigestTrie,
+        path: &str,
+    ) -> Result<Option<remexec::OutputSymlink>, String> {
+        let symlink = match root_trie.symlink(&RelativePath::new(path)?)? {
+            Some(symlink) => symlink,
+            None => return Ok(None),
+        };
+        let target = symlink
+            .target()
+            .to_str()
+            .ok_or_else(|| format!("Non-UTF8 target of output symlink {path:?}"))?;
+        Ok(Some(remexec::OutputSymlink {
+            path: path.to_owned(),
+            target: target.to_owned(),
+            ..remexec::OutputSymlink::default()
+        }))
+    }
+
+    async fn add_output_directory(
+        &self,
+        action_result: &mut ActionResult,
+        digests: &mut HashSet<Digest>,
+        output_trie: &DigestTrie,
+        output_directory: &str,
+    ) -> Result<(), StoreError> {
+        let (tree, file_digests) = match Self::make_tree_for_output_directory(
+            output_trie,
+            RelativePath::new(output_directory)?,
+        )? {
+            Some(res) => res,
+            None => return Ok(()),
+        };
+
+        let tree_digest = crate::remote::store_proto_locally(&self.store, &tree).await?;
+        digests.insert(tree_digest);
+        digests.extend(file_digests);
+
+        action_result
+            .output_directories
+            .push(remexec::OutputDirectory {
+                path: output_directory.to_owned(),
+                tree_digest: Some(tree_digest.into()),
+                is_topologically_sorted: false,
+            });
+        Ok(())
+    }
+
+    fn add_output_file(
+        action_result: &mut ActionResult,
+        digests: &mut HashSet<Digest>,
+        output_trie: &DigestTrie,
+        output_file_path: &str,
+    ) -> Result<(), StoreError> {
+        if let Some(output_file) = Self::extract_output_file(output_trie, output_file_path)? {
+            digests.insert(require_digest(output_file.digest.as_ref())?);
+            action_result.output_files.push(output_file);
+        }
+        Ok(())
+    }
+
     /// Converts a REAPI `Command` and a `FallibleProcessResultWithPlatform` produced from executing
     /// that Command into a REAPI `ActionResult` suitable for upload to the REAPI Action Cache.
     ///
@@ -221,36 +284,67 @@ impl CommandRunner {
         digests.insert(result.stdout_digest);
         digests.insert(result.stderr_digest);
 
+        // Per v2.1 of the API, if `output_paths` is set, then the deprecated `output_files` and
+        // `output_directories` are ignored, and the kind of each output is determined by what was
+        // produced. Symlinks are reported in both the deprecated and unified fields, so that they
+        // are preserved for clients of either version.
+        if !command.output_paths.is_empty() {
+            for output_path in &command.output_paths {
+                if let Some(symlink) = Self::extract_output_symlink(&output_trie, output_path)? {
+                    action_result.output_symlinks.push(symlink);
+                    continue;
+                }
+                match output_trie.entry(&RelativePath::new(output_path)?)? {
+                    None => {}
+                    Some(directory::Entry::Directory(_)) => {
+                        self.add_output_directory(
+                            &mut action_result,
+                            &mut digests,
+                            &output_trie,
+                            output_path,
+                        )
+                        .await?
+                    }
+                    Some(_) => Self::add_output_file(
+                        &mut action_result,
+                        &mut digests,
+                        &output_trie,
+                        output_path,
+                    )?,
+                }
+            }
+            return Ok((action_result, digests.into_iter().collect::<Vec<_>>()));
+        }
+
         for output_directory in &command.output_directories {
-            let (tree, file_digests) = match Self::make_tree_for_output_directory(
+            if let Some(symlink) = Self::extract_output_symlink(&output_trie, output_directory)? {
+                action_result
+                    .output_directory_symlinks
+                    .push(symlink.clone());
+                action_result.output_symlinks.push(symlink);
+                continue;
+            }
+            self.add_output_directory(
+                &mut action_result,
+                &mut digests,
                 &output_trie,
-                RelativePath::new(output_directory).unwrap(),
-            )? {
-                Some(res) => res,
-                None => continue,
-            };
-
-            let tree_digest = crate::remote::store_proto_locally(&self.store, &tree).await?;
-            digests.insert(tree_digest);
-            digests.extend(file_digests);
-
-            action_result
-                .output_directories
-                .push(remexec::OutputDirectory {
-                    path: output_directory.to_owned(),
-                    tree_digest: Some(tree_digest.into()),
-                    is_topologically_sorted: false,
-                });
+                output_directory,
+            )
+            .await?;
         }
 
         for output_file_path in &command.output_files {
-            let output_file = match Self::extract_output_file(&output_trie, output_file_path)? {
-                Some(output_file) => output_file,
-                None => continue,
-            };
-
-            digests.insert(require_digest(output_file.digest.as_ref())?);
-            action_result.output_files.push(output_file);
+            if let Some(symlink) = Self::extract_output_symlink(&output_trie, output_file_path)? {
+                action_result.output_file_symlinks.push(symlink.clone());
+                action_result.output_symlinks.push(symlink);
+                continue;
+            }
+            Self::add_output_file(
+                &mut action_result,
+                &mut digests,
+                &output_trie,
+                output_file_path,
+            )?;
         }
 
         Ok((action_result, digests.into_iter().collect::<Vec<_>>()))
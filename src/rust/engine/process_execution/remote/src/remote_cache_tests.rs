@@ -1,7 +1,8 @@
 // Copyright 2022 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::TryInto;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
@@ -11,7 +12,7 @@ use maplit::hashset;
 use tempfile::TempDir;
 use tokio::time::sleep;
 
-use fs::{DirectoryDigest, RelativePath, EMPTY_DIRECTORY_DIGEST};
+use fs::{DigestTrie, DirectoryDigest, RelativePath, TypedPath, EMPTY_DIRECTORY_DIGEST};
 use grpc_util::tls;
 use hashing::{Digest, EMPTY_DIGEST};
 use mock::StubCAS;
@@ -47,8 +48,8 @@ impl MockLocalCommandRunner {
     ) -> MockLocalCommandRunner {
         MockLocalCommandRunner {
             result: Ok(FallibleProcessResultWithPlatform {
-                stdout_digest: EMPTY_DIGEST,
-                stderr_digest: EMPTY_DIGEST,
+                stdout_digest: *EMPTY_DIGEST,
+                stderr_digest: *EMPTY_DIGEST,
                 exit_code,
                 output_directory: EMPTY_DIRECTORY_DIGEST.clone(),
                 metadata: ProcessResultMetadata::new(
@@ -208,7 +209,7 @@ async fn cache_read_success() {
     store_setup
         .cas
         .action_cache
-        .insert(action_digest, 0, EMPTY_DIGEST, EMPTY_DIGEST);
+        .insert(action_digest, 0, *EMPTY_DIGEST, *EMPTY_DIGEST);
 
     assert_eq!(local_runner_call_counter.load(Ordering::SeqCst), 0);
     let remote_result = cache_runner
@@ -233,7 +234,7 @@ async fn cache_read_skipped_on_action_cache_errors() {
     store_setup
         .cas
         .action_cache
-        .insert(action_digest, 0, EMPTY_DIGEST, EMPTY_DIGEST);
+        .insert(action_digest, 0, *EMPTY_DIGEST, *EMPTY_DIGEST);
     store_setup
         .cas
         .action_cache
@@ -273,7 +274,7 @@ async fn cache_read_skipped_on_missing_digest() {
         action_digest,
         0,
         Digest::of_bytes("pigs flying".as_bytes()),
-        EMPTY_DIGEST,
+        *EMPTY_DIGEST,
     );
 
     assert_eq!(
@@ -374,8 +375,8 @@ async fn cache_read_speculation() {
             store_setup.cas.action_cache.insert(
                 action_digest,
                 cached_exit_code,
-                EMPTY_DIGEST,
-                EMPTY_DIGEST,
+                *EMPTY_DIGEST,
+                *EMPTY_DIGEST,
             );
         }
 
@@ -703,6 +704,45 @@ async fn extract_output_file() {
     );
 }
 
+#[test]
+fn extract_output_symlink() {
+    let output_trie = DigestTrie::from_unique_paths(
+        vec![
+            TypedPath::File {
+                path: Path::new("cats/roland.ext"),
+                is_executable: false,
+            },
+            TypedPath::Link {
+                path: Path::new("cats/link.ext"),
+                target: Path::new("roland.ext"),
+            },
+            TypedPath::Link {
+                path: Path::new("felines"),
+                target: Path::new("cats"),
+            },
+        ],
+        &HashMap::from([(
+            PathBuf::from("cats/roland.ext"),
+            TestData::roland().digest(),
+        )]),
+    )
+    .unwrap();
+
+    let extract = |path: &str| {
+        crate::remote_cache::CommandRunner::extract_output_symlink(&output_trie, path).unwrap()
+    };
+    let link = extract("cats/link.ext").unwrap();
+    assert_eq!(link.path, "cats/link.ext");
+    assert_eq!(link.target, "roland.ext");
+    // A symlinked output directory is preserved rather than followed.
+    assert_eq!(extract("felines").unwrap().target, "cats");
+    assert_eq!(extract("felines/link.ext").unwrap().target, "roland.ext");
+
+    assert!(extract("cats/roland.ext").is_none());
+    assert!(extract("cats").is_none());
+    assert!(extract("dogs").is_none());
+}
+
 #[tokio::test]
 async fn make_action_result_basic() {
     #[derive(Debug)]
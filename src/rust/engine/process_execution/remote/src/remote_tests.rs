@@ -1,6 +1,6 @@
 // Copyright 2022 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
-use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
@@ -13,6 +13,7 @@ use mock::execution_server::{ExpectedAPICall, MockOperation};
 use prost::Message;
 use protos::gen::build::bazel::remote::execution::v2 as remexec;
 use protos::gen::google::longrunning::Operation;
+use protos::require_digest;
 use remexec::{execution_stage::Value as ExecutionStageValue, ExecutedActionMetadata};
 use store::{RemoteProvider, RemoteStoreOptions, SnapshotOps, Store, StoreError};
 use tempfile::TempDir;
@@ -22,11 +23,13 @@ use tokio::time::{sleep, timeout};
 use workunit_store::{Level, RunId, RunningWorkunit, WorkunitStore};
 
 use crate::remote::{CommandRunner, ExecutionError, OperationOrStatus};
-use fs::{DirectoryDigest, RelativePath, SymlinkBehavior, EMPTY_DIRECTORY_DIGEST};
+use fs::{
+    DigestTrie, DirectoryDigest, RelativePath, SymlinkBehavior, TypedPath, EMPTY_DIRECTORY_DIGEST,
+};
 use process_execution::{
     CacheName, CommandRunner as CommandRunnerTrait, Context, EntireExecuteRequest,
-    FallibleProcessResultWithPlatform, InputDigests, Platform, Process, ProcessCacheScope,
-    ProcessError, ProcessExecutionEnvironment, ProcessExecutionStrategy,
+    FallibleProcessResultWithPlatform, InputDigests, LocalCacheSettings, Platform, Process,
+    ProcessCacheScope, ProcessError, ProcessExecutionEnvironment, ProcessExecutionStrategy,
 };
 use std::any::type_name;
 use std::io::Cursor;
@@ -88,6 +91,7 @@ async fn make_execute_request() {
         env: vec![("SOME".to_owned(), "value".to_owned())]
             .into_iter()
             .collect(),
+        execution_only_env_vars: BTreeSet::new(),
         working_directory: None,
         input_digests: InputDigests::with_input_files(input_directory.directory_digest()),
         // Intentionally poorly sorted:
@@ -95,6 +99,7 @@ async fn make_execute_request() {
         output_directories: relative_paths(&["directory/name"]).collect(),
         timeout: None,
         description: "some description".to_owned(),
+        tags: BTreeSet::new(),
         level: log::Level::Info,
         append_only_caches: BTreeMap::new(),
         jdk_home: None,
@@ -103,6 +108,8 @@ async fn make_execute_request() {
         cache_scope: ProcessCacheScope::Always,
         execution_environment: make_environment(Platform::Linux_x86_64),
         remote_cache_speculation_delay: std::time::Duration::from_millis(0),
+        local_cache: LocalCacheSettings::default(),
+        remote_persistent_worker: false,
         attempt: 0,
     };
 
@@ -181,6 +188,7 @@ async fn make_execute_request_with_instance_name() {
         env: vec![("SOME".to_owned(), "value".to_owned())]
             .into_iter()
             .collect(),
+        execution_only_env_vars: BTreeSet::new(),
         working_directory: None,
         input_digests: InputDigests::with_input_files(input_directory.directory_digest()),
         // Intentionally poorly sorted:
@@ -188,6 +196,7 @@ async fn make_execute_request_with_instance_name() {
         output_directories: relative_paths(&["directory/name"]).collect(),
         timeout: None,
         description: "some description".to_owned(),
+        tags: BTreeSet::new(),
         level: log::Level::Info,
         append_only_caches: BTreeMap::new(),
         jdk_home: None,
@@ -203,6 +212,8 @@ async fn make_execute_request_with_instance_name() {
             )]),
         },
         remote_cache_speculation_delay: std::time::Duration::from_millis(0),
+        local_cache: LocalCacheSettings::default(),
+        remote_persistent_worker: false,
         attempt: 0,
     };
 
@@ -294,6 +305,7 @@ async fn make_execute_request_with_cache_key_gen_version() {
         env: vec![("SOME".to_owned(), "value".to_owned())]
             .into_iter()
             .collect(),
+        execution_only_env_vars: BTreeSet::new(),
         working_directory: None,
         input_digests: InputDigests::with_input_files(input_directory.directory_digest()),
         // Intentionally poorly sorted:
@@ -301,6 +313,7 @@ async fn make_execute_request_with_cache_key_gen_version() {
         output_directories: relative_paths(&["directory/name"]).collect(),
         timeout: None,
         description: "some description".to_owned(),
+        tags: BTreeSet::new(),
         level: log::Level::Info,
         append_only_caches: BTreeMap::new(),
         jdk_home: None,
@@ -309,6 +322,8 @@ async fn make_execute_request_with_cache_key_gen_version() {
         cache_scope: ProcessCacheScope::Always,
         execution_environment: make_environment(Platform::Linux_x86_64),
         remote_cache_speculation_delay: std::time::Duration::from_millis(0),
+        local_cache: LocalCacheSettings::default(),
+        remote_persistent_worker: false,
         attempt: 0,
     };
 
@@ -568,6 +583,7 @@ async fn make_execute_request_with_timeout() {
         env: vec![("SOME".to_owned(), "value".to_owned())]
             .into_iter()
             .collect(),
+        execution_only_env_vars: BTreeSet::new(),
         working_directory: None,
         input_digests: InputDigests::with_input_files(input_directory.directory_digest()),
         // Intentionally poorly sorted:
@@ -575,6 +591,7 @@ async fn make_execute_request_with_timeout() {
         output_directories: relative_paths(&["directory/name"]).collect(),
         timeout: one_second(),
         description: "some description".to_owned(),
+        tags: BTreeSet::new(),
         level: log::Level::Info,
         append_only_caches: BTreeMap::new(),
         jdk_home: None,
@@ -583,6 +600,8 @@ async fn make_execute_request_with_timeout() {
         cache_scope: ProcessCacheScope::Always,
         execution_environment: make_environment(Platform::Linux_x86_64),
         remote_cache_speculation_delay: std::time::Duration::from_millis(0),
+        local_cache: LocalCacheSettings::default(),
+        remote_persistent_worker: false,
         attempt: 0,
     };
 
@@ -667,12 +686,14 @@ async fn make_execute_request_with_append_only_caches() {
         env: vec![("SOME".to_owned(), "value".to_owned())]
             .into_iter()
             .collect(),
+        execution_only_env_vars: BTreeSet::new(),
         working_directory: Some(RelativePath::new(Path::new("animals")).unwrap()),
         input_digests: InputDigests::with_input_files(input_directory.directory_digest()),
         output_files: BTreeSet::new(),
         output_directories: BTreeSet::new(),
         timeout: one_second(),
         description: "some description".to_owned(),
+        tags: BTreeSet::new(),
         level: log::Level::Info,
         append_only_caches: btreemap! {
           CacheName::new(String::from("xyzzy")).unwrap() => RelativePath::new(Path::new(".cache/xyzzy")).unwrap(),
@@ -683,6 +704,8 @@ async fn make_execute_request_with_append_only_caches() {
         cache_scope: ProcessCacheScope::Always,
         execution_environment: make_environment(Platform::Linux_x86_64),
         remote_cache_speculation_delay: std::time::Duration::from_millis(0),
+        local_cache: LocalCacheSettings::default(),
+        remote_persistent_worker: false,
         attempt: 0,
     };
 
@@ -829,12 +852,14 @@ async fn make_execute_request_using_immutable_inputs() {
         env: vec![("SOME".to_owned(), "value".to_owned())]
             .into_iter()
             .collect(),
+        execution_only_env_vars: BTreeSet::new(),
         working_directory: None,
         input_digests,
         output_files: relative_paths(&["path/to/file.ext", "other/file.ext"]).collect(),
         output_directories: relative_paths(&["directory/name"]).collect(),
         timeout: None,
         description: "some description".to_owned(),
+        tags: BTreeSet::new(),
         level: log::Level::Info,
         append_only_caches: BTreeMap::new(),
         jdk_home: None,
@@ -843,6 +868,8 @@ async fn make_execute_request_using_immutable_inputs() {
         cache_scope: ProcessCacheScope::Always,
         execution_environment: make_environment(Platform::Linux_x86_64),
         remote_cache_speculation_delay: std::time::Duration::from_millis(0),
+        local_cache: LocalCacheSettings::default(),
+        remote_persistent_worker: false,
         attempt: 0,
     };
 
@@ -909,6 +936,143 @@ async fn make_execute_request_using_immutable_inputs() {
     );
 }
 
+#[tokio::test]
+async fn make_execute_request_with_persistent_worker() {
+    let executor = task_executor::Executor::new();
+    let store_dir = TempDir::new().unwrap();
+    let store = Store::local_only(executor, store_dir).unwrap();
+
+    let worker_key = |request: &EntireExecuteRequest| {
+        request
+            .command
+            .platform
+            .as_ref()
+            .unwrap()
+            .properties
+            .iter()
+            .find(|property| {
+                property.name == process_execution::PERSISTENT_WORKER_KEY_PLATFORM_PROPERTY
+            })
+            .map(|property| property.value.clone())
+    };
+    let argv = ["/bin/worker", "--verbose", "@flagfile"];
+    let first =
+        make_persistent_worker_execute_request(&store, &TestDirectory::containing_robin(), &argv)
+            .await
+            .unwrap();
+    let second =
+        make_persistent_worker_execute_request(&store, &TestDirectory::containing_treats(), &argv)
+            .await
+            .unwrap();
+
+    // The worker is identified by its tool inputs and startup arguments, but not its other inputs.
+    assert!(worker_key(&first).is_some());
+    assert_eq!(worker_key(&first), worker_key(&second));
+    assert_ne!(first.action, second.action);
+
+    // The tool inputs are marked in a copy of the input root.
+    let root_digest = require_digest(first.action.input_root_digest.as_ref()).unwrap();
+    assert_ne!(root_digest, first.input_root_digest.as_digest());
+    let root = store.load_directory(root_digest).await.unwrap();
+    assert!(root
+        .files
+        .iter()
+        .all(|file_node| file_node.node_properties.is_none()));
+    let tool_node = root
+        .directories
+        .iter()
+        .find(|directory_node| directory_node.name == "tool")
+        .unwrap();
+    let tool = store
+        .load_directory(require_digest(tool_node.digest.as_ref()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(
+        tool.files[0].node_properties.as_ref().unwrap().properties,
+        vec![remexec::NodeProperty {
+            name: process_execution::TOOL_INPUT_NODE_PROPERTY.to_owned(),
+            value: "".to_owned(),
+        }]
+    );
+
+    // A worker must be passed its arguments in a flagfile.
+    assert!(make_persistent_worker_execute_request(
+        &store,
+        &TestDirectory::containing_robin(),
+        &["/bin/worker", "--verbose"],
+    )
+    .await
+    .is_err());
+}
+
+#[tokio::test]
+async fn make_execute_request_with_execution_only_env_vars() {
+    let executor = task_executor::Executor::new();
+    let store_dir = TempDir::new().unwrap();
+    let store = Store::local_only(executor, store_dir).unwrap();
+
+    let process = |token: Option<&str>| {
+        let mut env = btreemap! {"SOME".to_owned() => "value".to_owned()};
+        if let Some(token) = token {
+            env.insert("TOKEN".to_owned(), token.to_owned());
+        }
+        Process::new(owned_string_vec(&["/bin/echo", "yo"]))
+            .env(env)
+            .execution_only_env_vars(BTreeSet::from(["TOKEN".to_owned()]))
+    };
+    let first = process(Some("first"));
+    let first_request = process_execution::make_execute_request(&first, None, None, &store, None)
+        .await
+        .unwrap();
+    let second_request =
+        process_execution::make_execute_request(&process(Some("second")), None, None, &store, None)
+            .await
+            .unwrap();
+    let without_request =
+        process_execution::make_execute_request(&process(None), None, None, &store, None)
+            .await
+            .unwrap();
+
+    // The cache key does not depend on the values of execution-only env vars.
+    assert_eq!(first_request, second_request);
+    assert_eq!(first_request, without_request);
+    assert!(!first_request
+        .command
+        .environment_variables
+        .iter()
+        .any(|env| env.name == "TOKEN"));
+}
+
+async fn make_persistent_worker_execute_request(
+    store: &Store,
+    input_files: &TestDirectory,
+    argv: &[&str],
+) -> Result<EntireExecuteRequest, String> {
+    let tool_prefix = RelativePath::new("tool").unwrap();
+    let tool = TestDirectory::containing_roland();
+    store
+        .record_directory(&tool.directory(), false)
+        .await
+        .unwrap();
+    store
+        .record_directory(&input_files.directory(), false)
+        .await
+        .unwrap();
+
+    let mut process = Process::new(owned_string_vec(argv))
+        .remote_execution(vec![])
+        .remote_persistent_worker(true);
+    process.input_digests = InputDigests::new(
+        store,
+        input_files.directory_digest(),
+        btreemap! {tool_prefix.clone() => tool.directory_digest()},
+        BTreeSet::from([tool_prefix]),
+    )
+    .await
+    .unwrap();
+    process_execution::make_execute_request(&process, None, None, store, None).await
+}
+
 #[tokio::test]
 async fn successful_with_only_call_to_execute() {
     WorkunitStore::setup_for_tests();
@@ -1237,6 +1401,25 @@ async fn server_rejecting_execute_request_gives_error() {
     assert!(&error.to_string().contains("Did not expect this request"));
 }
 
+#[tokio::test]
+async fn execution_only_env_vars_are_refused() {
+    WorkunitStore::setup_for_tests();
+    let mock_server = mock::execution_server::TestServer::new(
+        mock::execution_server::MockExecution::new(vec![]),
+        None,
+    );
+
+    let request = echo_foo_request()
+        .env(btreemap! {"TOKEN".to_owned() => "secret".to_owned()})
+        .execution_only_env_vars(BTreeSet::from(["TOKEN".to_owned()]));
+    let error = run_command_remote(mock_server.address(), request)
+        .await
+        .expect_err("Want Err")
+        .to_string();
+    assert!(error.contains("not supported by remote execution"));
+    assert!(!error.contains("secret"));
+}
+
 #[tokio::test]
 async fn server_sending_triggering_timeout_with_deadline_exceeded() {
     WorkunitStore::setup_for_tests();
@@ -2384,6 +2567,63 @@ async fn extract_output_files_from_response_no_prefix() {
     )
 }
 
+#[tokio::test]
+async fn extract_output_files_from_response_symlinks() {
+    let _ = WorkunitStore::setup_for_tests();
+    let link = remexec::OutputSymlink {
+        path: "link.ext".into(),
+        target: "roland.ext".into(),
+        ..Default::default()
+    };
+    let execute_response = remexec::ExecuteResponse {
+        result: Some(remexec::ActionResult {
+            exit_code: 0,
+            output_files: vec![remexec::OutputFile {
+                path: "roland.ext".into(),
+                digest: Some((&TestData::roland().digest()).into()),
+                is_executable: false,
+                ..Default::default()
+            }],
+            // Servers implementing v2.1 report symlinks in both fields.
+            output_file_symlinks: vec![link.clone()],
+            output_symlinks: vec![
+                link,
+                remexec::OutputSymlink {
+                    path: "dead".into(),
+                    target: "nonexistent".into(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let expected = DigestTrie::from_unique_paths(
+        vec![
+            TypedPath::File {
+                path: Path::new("roland.ext"),
+                is_executable: false,
+            },
+            TypedPath::Link {
+                path: Path::new("link.ext"),
+                target: Path::new("roland.ext"),
+            },
+            TypedPath::Link {
+                path: Path::new("dead"),
+                target: Path::new("nonexistent"),
+            },
+        ],
+        &HashMap::from([(PathBuf::from("roland.ext"), TestData::roland().digest())]),
+    )
+    .unwrap();
+
+    assert_eq!(
+        extract_output_files_from_response(&execute_response).await,
+        Ok(expected.compute_root_digest())
+    )
+}
+
 pub fn echo_foo_request() -> Process {
     let mut req = Process::new(owned_string_vec(&["/bin/echo", "-n", "foo"]));
     req.timeout = Some(Duration::from_millis(5000));
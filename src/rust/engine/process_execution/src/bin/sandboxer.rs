@@ -0,0 +1,71 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::path::PathBuf;
+use std::process::exit;
+use std::time::Duration;
+
+use clap::StructOpt;
+use store::{LocalOptions, Store, StoreBackend};
+
+#[derive(StructOpt)]
+struct Opt {
+    /// The unix domain socket to serve on.
+    #[structopt(long)]
+    socket_path: PathBuf,
+
+    /// The root of the local store which is shared with the engine.
+    #[structopt(long)]
+    store_dir: PathBuf,
+
+    /// The directory below which immutable inputs are materialized.
+    #[structopt(long)]
+    immutable_inputs_base: PathBuf,
+
+    #[structopt(long)]
+    files_max_size_bytes: usize,
+
+    #[structopt(long)]
+    directories_max_size_bytes: usize,
+
+    #[structopt(long)]
+    max_size_growth_factor: usize,
+
+    #[structopt(long)]
+    lease_time_secs: u64,
+
+    #[structopt(long)]
+    shard_count: u8,
+
+    #[structopt(long)]
+    backend: StoreBackend,
+}
+
+/// A binary which is started by the engine (when `[GLOBAL].sandboxer` is set) to materialize
+/// sandboxes and spawn processes on its behalf. It exits when the engine does.
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+
+    let args = Opt::from_args();
+    let executor = task_executor::Executor::new();
+    let store = Store::local_only_with_options(
+        executor,
+        &args.store_dir,
+        &args.immutable_inputs_base,
+        LocalOptions {
+            files_max_size_bytes: args.files_max_size_bytes,
+            directories_max_size_bytes: args.directories_max_size_bytes,
+            max_size_growth_factor: args.max_size_growth_factor,
+            lease_time: Duration::from_secs(args.lease_time_secs),
+            shard_count: args.shard_count,
+            backend: args.backend,
+        },
+    )
+    .expect("Error making local store");
+
+    if let Err(e) = process_execution::sandboxer::serve(store, &args.socket_path).await {
+        eprintln!("{e}");
+        exit(1);
+    }
+}
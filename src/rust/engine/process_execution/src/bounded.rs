@@ -2,7 +2,7 @@
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 use std::borrow::Cow;
 use std::cmp::{max, min, Ordering, Reverse};
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::{self, Debug};
 use std::future::Future;
 use std::sync::{atomic, Arc};
@@ -14,7 +14,7 @@ use log::Level;
 use parking_lot::Mutex;
 use regex::Regex;
 use task_executor::Executor;
-use tokio::sync::{Notify, Semaphore, SemaphorePermit};
+use tokio::sync::{oneshot, Notify};
 use tokio::time::sleep;
 use workunit_store::{in_workunit, RunningWorkunit};
 
@@ -25,6 +25,9 @@ lazy_static! {
   static ref CONCURRENCY_TEMPLATE_RE: Regex = Regex::new(r"\{pants_concurrency\}").unwrap();
 }
 
+/// The highest niceness that a client may set: see `Context::niceness`.
+pub const MAX_NICENESS: u8 = 19;
+
 ///
 /// A CommandRunner wrapper which limits the number of concurrent requests and which provides
 /// concurrency information to the process being executed.
@@ -32,6 +35,9 @@ lazy_static! {
 /// If a Process sets a non-zero `concurrency_available` value, it may be preempted (i.e. canceled
 /// and restarted) with a new concurrency value for a short period after starting.
 ///
+/// When slots are contended, they are shared fairly between the clients (i.e., the Sessions) which
+/// are waiting for them, weighted by each client's niceness: see `State::next_waiter`.
+///
 #[derive(Clone)]
 pub struct CommandRunner {
     inner: Arc<dyn crate::CommandRunner>,
@@ -72,7 +78,10 @@ impl crate::CommandRunner for CommandRunner {
         workunit: &mut RunningWorkunit,
         process: Process,
     ) -> Result<FallibleProcessResultWithPlatform, ProcessError> {
-        let semaphore_acquisition = self.sema.acquire(process.concurrency_available);
+        let semaphore_acquisition = self.sema.acquire(
+            Client::new(context.build_id.clone(), context.niceness),
+            process.concurrency_available,
+        );
         let permit = in_workunit!(
             "acquire_command_runner_slot",
             // TODO: The UI uses the presence of a blocked workunit below a parent as an indication that
@@ -159,18 +168,44 @@ impl crate::CommandRunner for CommandRunner {
     }
 }
 
+///
+/// The client (generally: one run of the pants CLI) on whose behalf a slot is acquired.
+///
+#[derive(Clone, Debug)]
+pub(crate) struct Client {
+    build_id: String,
+    // A client is owed a share of the slots which is proportional to its weight.
+    weight: usize,
+}
+
+impl Client {
+    pub(crate) fn new(build_id: String, niceness: u8) -> Self {
+        Self {
+            build_id,
+            weight: (MAX_NICENESS + 1 - min(niceness, MAX_NICENESS)) as usize,
+        }
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new(String::default(), 0)
+    }
+}
+
 /// A wrapped Semaphore which adds concurrency metadata which supports overcommit.
 #[derive(Clone)]
 pub(crate) struct AsyncSemaphore {
-    sema: Arc<Semaphore>,
     state: Arc<Mutex<State>>,
-    preemptible_duration: Duration,
 }
 
 pub(crate) struct State {
     total_concurrency: usize,
+    preemptible_duration: Duration,
     available_ids: VecDeque<usize>,
     tasks: Vec<Arc<Task>>,
+    waiters: VecDeque<Waiter>,
+    next_waiter_id: usize,
 }
 
 impl State {
@@ -178,8 +213,117 @@ impl State {
     pub(crate) fn new_for_tests(total_concurrency: usize, tasks: Vec<Arc<Task>>) -> Self {
         Self {
             total_concurrency,
+            preemptible_duration: Duration::ZERO,
             available_ids: VecDeque::new(),
             tasks,
+            waiters: VecDeque::new(),
+            next_waiter_id: 0,
+        }
+    }
+
+    ///
+    /// Starts a Task in the given slot. A Task is initially given its fair share of the available
+    /// concurrency: i.e., the first arriving task gets all of the slots, and the second arriving
+    /// gets half, even though that means that we overcommit. Balancing will adjust concurrency
+    /// later, to the extent that it can given preemption timeouts.
+    ///
+    /// This is because we cannot anticipate the number of inbound processes, and we never want to
+    /// delay a process from starting.
+    ///
+    fn start(&mut self, id: usize, client: Client, concurrency_desired: usize) -> Arc<Task> {
+        let concurrency_desired = max(concurrency_desired, 1);
+        let concurrency_actual = min(
+            concurrency_desired,
+            self.total_concurrency / (self.tasks.len() + 1),
+        );
+        let task = Arc::new(Task::new(
+            id,
+            client,
+            concurrency_desired,
+            concurrency_actual,
+            Instant::now() + self.preemptible_duration,
+        ));
+        self.tasks.push(task.clone());
+        task
+    }
+
+    ///
+    /// Finishes the given Task, and hands its slot to the next waiter (if any).
+    ///
+    fn finish(&mut self, task: &Task) {
+        // NB: The next waiter is chosen while the finishing Task still counts toward its client's
+        // usage, so that a client cannot keep a slot which another client is owed by queueing
+        // another process before it releases the slot.
+        let next_waiter = self.next_waiter();
+        let tasks_position = self.tasks.iter().position(|t| t.id == task.id).unwrap();
+        self.tasks.swap_remove(tasks_position);
+
+        if let Some(waiter) = next_waiter.and_then(|position| self.waiters.remove(position)) {
+            let next_task = self.start(task.id, waiter.client, waiter.concurrency_desired);
+            // NB: A waiter removes itself from the queue (under the lock) before it stops
+            // listening, so this cannot fail.
+            let _ = waiter.sender.send(next_task);
+        } else {
+            self.available_ids.push_back(task.id);
+        }
+    }
+
+    ///
+    /// Returns the position of the next waiter to acquire a slot: the first waiter of the client
+    /// which is using the fewest slots relative to its weight.
+    ///
+    /// NB: This only decides which waiter acquires the next free slot: it never preempts a running
+    /// Task in favor of another client.
+    ///
+    fn next_waiter(&self) -> Option<usize> {
+        let mut slots_used: HashMap<&str, usize> = HashMap::new();
+        for task in &self.tasks {
+            *slots_used.entry(task.client.build_id.as_str()).or_default() += 1;
+        }
+        let slots_used = |client: &Client| {
+            slots_used
+                .get(client.build_id.as_str())
+                .copied()
+                .unwrap_or(0)
+        };
+
+        // NB: `min_by` returns the first of equal elements, so waiters of equally served clients
+        // acquire in the order that they arrived.
+        self.waiters
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                (slots_used(&a.client) * b.client.weight)
+                    .cmp(&(slots_used(&b.client) * a.client.weight))
+            })
+            .map(|(position, _)| position)
+    }
+}
+
+struct Waiter {
+    id: usize,
+    client: Client,
+    concurrency_desired: usize,
+    sender: oneshot::Sender<Arc<Task>>,
+}
+
+///
+/// Removes a waiter from the queue if it stops waiting before it is handed a slot, or releases the
+/// slot if it was handed one without observing it.
+///
+struct WaiterGuard {
+    state: Arc<Mutex<State>>,
+    id: usize,
+    receiver: oneshot::Receiver<Arc<Task>>,
+}
+
+impl Drop for WaiterGuard {
+    fn drop(&mut self) {
+        let mut state = self.state.lock();
+        if let Some(position) = state.waiters.iter().position(|w| w.id == self.id) {
+            state.waiters.remove(position);
+        } else if let Ok(task) = self.receiver.try_recv() {
+            state.finish(&task);
         }
     }
 }
@@ -197,8 +341,11 @@ impl AsyncSemaphore {
 
         let state = Arc::new(Mutex::new(State {
             total_concurrency: permits,
+            preemptible_duration,
             available_ids,
             tasks: Vec::new(),
+            waiters: VecDeque::new(),
+            next_waiter_id: 0,
         }));
 
         // Spawn a task which will periodically balance Tasks.
@@ -219,16 +366,12 @@ impl AsyncSemaphore {
             })
         };
 
-        AsyncSemaphore {
-            sema: Arc::new(Semaphore::new(permits)),
-            state,
-            preemptible_duration,
-        }
+        AsyncSemaphore { state }
     }
 
     #[cfg(test)]
     pub(crate) fn available_permits(&self) -> usize {
-        self.sema.available_permits()
+        self.state.lock().available_ids.len()
     }
 
     ///
@@ -243,63 +386,60 @@ impl AsyncSemaphore {
         F: FnOnce(usize) -> B,
         B: Future<Output = O>,
     {
-        let permit = self.acquire(1).await;
+        let permit = self.acquire(Client::default(), 1).await;
         let res = f(permit.task.id).await;
         drop(permit);
         res
     }
 
     ///
-    /// Acquire a slot on the semaphore when it becomes available. Additionally, attempt to acquire
-    /// the given amount of concurrency. The amount actually acquired will be reported on the
-    /// returned Permit.
+    /// Acquire a slot on the semaphore on behalf of the given client when it becomes available.
+    /// Additionally, attempt to acquire the given amount of concurrency. The amount actually
+    /// acquired will be reported on the returned Permit.
     ///
-    pub async fn acquire(&self, concurrency_desired: usize) -> Permit<'_> {
-        let permit = self.sema.acquire().await.expect("semaphore closed");
-        let task = {
+    pub(crate) async fn acquire(&self, client: Client, concurrency_desired: usize) -> Permit {
+        let mut waiter_guard = {
             let mut state = self.state.lock();
-            let id = state
-                .available_ids
-                .pop_front()
-                .expect("More permits were distributed than ids exist.");
-
-            // A Task is initially given its fair share of the available concurrency: i.e., the first
-            // arriving task gets all of the slots, and the second arriving gets half, even though that
-            // means that we overcommit. Balancing will adjust concurrency later, to the extent that it
-            // can given preemption timeouts.
-            //
-            // This is because we cannot anticipate the number of inbound processes, and we never want to
-            // delay a process from starting.
-            let concurrency_desired = max(concurrency_desired, 1);
-            let concurrency_actual = min(
-                concurrency_desired,
-                state.total_concurrency / (state.tasks.len() + 1),
-            );
-            let task = Arc::new(Task::new(
+            if let Some(id) = state.available_ids.pop_front() {
+                let task = state.start(id, client, concurrency_desired);
+                return Permit {
+                    state: self.state.clone(),
+                    task,
+                };
+            }
+
+            let (sender, receiver) = oneshot::channel();
+            let id = state.next_waiter_id;
+            state.next_waiter_id += 1;
+            state.waiters.push_back(Waiter {
                 id,
+                client,
                 concurrency_desired,
-                concurrency_actual,
-                Instant::now() + self.preemptible_duration,
-            ));
-            state.tasks.push(task.clone());
-            task
+                sender,
+            });
+            WaiterGuard {
+                state: self.state.clone(),
+                id,
+                receiver,
+            }
         };
+
+        let task = (&mut waiter_guard.receiver)
+            .await
+            .expect("semaphore closed");
         Permit {
             state: self.state.clone(),
-            _permit: permit,
             task,
         }
     }
 }
 
-pub struct Permit<'a> {
+pub struct Permit {
     state: Arc<Mutex<State>>,
-    // NB: Kept for its `Drop` impl.
-    _permit: SemaphorePermit<'a>,
     task: Arc<Task>,
 }
 
-impl Permit<'_> {
+impl Permit {
     pub fn concurrency_slot(&self) -> usize {
         self.task.id
     }
@@ -313,21 +453,15 @@ impl Permit<'_> {
     }
 }
 
-impl<'a> Drop for Permit<'a> {
+impl Drop for Permit {
     fn drop(&mut self) {
-        let mut state = self.state.lock();
-        state.available_ids.push_back(self.task.id);
-        let tasks_position = state
-            .tasks
-            .iter()
-            .position(|t| t.id == self.task.id)
-            .unwrap();
-        state.tasks.swap_remove(tasks_position);
+        self.state.lock().finish(&self.task);
     }
 }
 
 pub(crate) struct Task {
     id: usize,
+    client: Client,
     concurrency_desired: usize,
     pub(crate) concurrency_actual: atomic::AtomicUsize,
     notify_concurrency_changed: Notify,
@@ -337,6 +471,7 @@ pub(crate) struct Task {
 impl Task {
     pub(crate) fn new(
         id: usize,
+        client: Client,
         concurrency_desired: usize,
         concurrency_actual: usize,
         preemptible_until: Instant,
@@ -344,6 +479,7 @@ impl Task {
         assert!(concurrency_actual <= concurrency_desired);
         Self {
             id,
+            client,
             concurrency_desired,
             concurrency_actual: atomic::AtomicUsize::new(concurrency_actual),
             notify_concurrency_changed: Notify::new(),
@@ -1,13 +1,15 @@
 // Copyright 2022 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 use std::sync::Arc;
+use std::task::Poll;
 use std::time::{Duration, Instant};
 
 use futures::channel::oneshot;
 use futures::future::{self, FutureExt};
+use futures::poll;
 use tokio::time::{sleep, timeout};
 
-use crate::bounded::{balance, AsyncSemaphore, State, Task};
+use crate::bounded::{balance, AsyncSemaphore, Client, State, Task, MAX_NICENESS};
 
 fn mk_semaphore(permits: usize) -> AsyncSemaphore {
     mk_semaphore_with_preemptible_duration(permits, Duration::from_millis(200))
@@ -250,7 +252,7 @@ async fn drop_while_waiting() {
 
     // thread2 will wait for a little while, but then drop its PermitFuture to give up on waiting.
     tokio::spawn(async move {
-        let permit_future = handle2.acquire(1).boxed();
+        let permit_future = handle2.acquire(Client::default(), 1).boxed();
         let delay_future = sleep(Duration::from_millis(100)).boxed();
         let raced_result = future::select(delay_future, permit_future).await;
         // We expect to have timed out, because the other Future will not resolve until asked.
@@ -348,14 +350,14 @@ async fn preemption() {
     let sema = mk_semaphore_with_preemptible_duration(2, ten_secs);
 
     // Acquire a permit which will take all concurrency, and confirm that it doesn't get preempted.
-    let permit1 = sema.acquire(2).await;
+    let permit1 = sema.acquire(Client::default(), 2).await;
     assert_eq!(2, permit1.concurrency());
     if let Ok(_) = timeout(ten_secs / 100, permit1.notified_concurrency_changed()).await {
         panic!("permit1 should not have been preempted.");
     }
 
     // Acquire another permit, and confirm that it doesn't get preempted.
-    let permit2 = sema.acquire(2).await;
+    let permit2 = sema.acquire(Client::default(), 2).await;
     if let Ok(_) = timeout(ten_secs / 100, permit2.notified_concurrency_changed()).await {
         panic!("permit2 should not have been preempted.");
     }
@@ -369,6 +371,59 @@ async fn preemption() {
     assert_eq!(1, permit2.concurrency());
 }
 
+#[tokio::test]
+async fn fair_share_between_clients() {
+    let sema = mk_semaphore(1);
+    let lint = Client::new("lint".to_owned(), 0);
+    let test = Client::new("test".to_owned(), 0);
+
+    let lint1 = sema.acquire(lint.clone(), 1).await;
+    let mut lint2 = Box::pin(sema.acquire(lint.clone(), 1));
+    let mut test1 = Box::pin(sema.acquire(test, 1));
+    assert!(poll!(&mut lint2).is_pending());
+    assert!(poll!(&mut test1).is_pending());
+
+    // Although `lint` queued first, it is already using the slot, so `test` is owed it next.
+    drop(lint1);
+    let Poll::Ready(test1) = poll!(&mut test1) else {
+        panic!("test should have acquired the slot.");
+    };
+    assert!(poll!(&mut lint2).is_pending());
+
+    drop(test1);
+    assert!(poll!(&mut lint2).is_ready());
+}
+
+#[tokio::test]
+async fn fair_share_weighted_by_niceness() {
+    // Returns which of two clients, which are each using one slot and each waiting for another,
+    // acquires the slot that `lint` (which queued first) releases.
+    async fn next_to_acquire(lint_niceness: u8) -> &'static str {
+        let sema = mk_semaphore(2);
+        let lint = Client::new("lint".to_owned(), lint_niceness);
+        let test = Client::new("test".to_owned(), 0);
+
+        let lint1 = sema.acquire(lint.clone(), 1).await;
+        let _test1 = sema.acquire(test.clone(), 1).await;
+        let mut lint2 = Box::pin(sema.acquire(lint, 1));
+        let mut test2 = Box::pin(sema.acquire(test, 1));
+        assert!(poll!(&mut lint2).is_pending());
+        assert!(poll!(&mut test2).is_pending());
+
+        drop(lint1);
+        match (poll!(&mut lint2), poll!(&mut test2)) {
+            (Poll::Ready(_), Poll::Pending) => "lint",
+            (Poll::Pending, Poll::Ready(_)) => "test",
+            _ => panic!("Exactly one waiter should have acquired the slot."),
+        }
+    }
+
+    // Equally nice clients acquire in the order that they queued.
+    assert_eq!("lint", next_to_acquire(0).await);
+    // But a nicer client yields to a less nice one.
+    assert_eq!("test", next_to_acquire(MAX_NICENESS).await);
+}
+
 /// Given Tasks as triples of desired, actual, and expected concurrency (all of which are
 /// assumed to be preemptible), assert that the expected concurrency is applied.
 fn test_balance(
@@ -381,7 +436,13 @@ fn test_balance(
         .iter()
         .enumerate()
         .map(|(id, (desired, actual, _))| {
-            Arc::new(Task::new(id, *desired, *actual, ten_minutes_from_now))
+            Arc::new(Task::new(
+                id,
+                Client::default(),
+                *desired,
+                *actual,
+                ten_minutes_from_now,
+            ))
         })
         .collect::<Vec<_>>();
 
@@ -2,7 +2,7 @@
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 use std::fmt::{self, Debug};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use bytes::Bytes;
@@ -90,7 +90,10 @@ impl crate::CommandRunner for CommandRunner {
                 .into(),
             ),
             key_type: CacheKeyType::Process.into(),
+            namespace: req.local_cache.namespace.clone(),
+            namespace_version: req.local_cache.namespace_version,
         };
+        let ttl = req.local_cache.ttl;
 
         if self.cache_read {
             let context2 = context.clone();
@@ -166,7 +169,7 @@ impl crate::CommandRunner for CommandRunner {
         if result.exit_code == 0 || write_failures_to_cache {
             let result = result.clone();
             in_workunit!("local_cache_write", Level::Trace, |workunit| async move {
-                if let Err(err) = self.store(&key, &result).await {
+                if let Err(err) = self.store(&key, &result, ttl).await {
                     warn!(
             "Error storing process execution result to local cache: {} - ignoring and continuing",
             err
@@ -244,6 +247,7 @@ impl CommandRunner {
         &self,
         action_key: &CacheKey,
         result: &FallibleProcessResultWithPlatform,
+        ttl: Option<Duration>,
     ) -> Result<(), StoreError> {
         let stdout_digest = result.stdout_digest;
         let stderr_digest = result.stderr_digest;
@@ -273,7 +277,8 @@ impl CommandRunner {
 
         // TODO: Should probably have a configurable lease time which is larger than default.
         // (This isn't super urgent because we don't ever actually GC this store. So also...)
-        // TODO: GC the local process execution cache.
+        // TODO: GC the local process execution cache, beyond clearing expired entries with
+        // the `clear-local-cache` goal.
 
         let mut response_bytes = Vec::with_capacity(execute_response.encoded_len());
         execute_response
@@ -287,7 +292,9 @@ impl CommandRunner {
         .map(Bytes::from)
         .map_err(|err| format!("Error serializing platform and execute process result: {err}"))?;
 
-        self.cache.store(action_key, bytes_to_store).await?;
+        self.cache
+            .store_with_ttl(action_key, bytes_to_store, ttl)
+            .await?;
         Ok(())
     }
 }
@@ -6,7 +6,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use cache::PersistentCache;
-use sharded_lmdb::DEFAULT_LEASE_TIME;
+use sharded_lmdb::{StoreBackend, DEFAULT_LEASE_TIME};
 use store::{ImmutableInputs, Store};
 use tempfile::TempDir;
 use testutil::data::TestData;
@@ -16,7 +16,7 @@ use workunit_store::{RunningWorkunit, WorkunitStore};
 
 use crate::{
     local::KeepSandboxes, CacheContentBehavior, CommandRunner as CommandRunnerTrait, Context,
-    FallibleProcessResultWithPlatform, NamedCaches, Process, ProcessError,
+    FallibleProcessResultWithPlatform, LocalCacheSettings, NamedCaches, Process, ProcessError,
 };
 
 struct RoundtripResults {
@@ -37,7 +37,10 @@ fn create_local_runner() -> (Box<dyn CommandRunnerTrait>, Store, TempDir) {
         NamedCaches::new_local(named_cache_dir),
         ImmutableInputs::new(store.clone(), base_dir.path()).unwrap(),
         KeepSandboxes::Never,
+        false,
+        false,
         Arc::new(RwLock::new(())),
+        None,
     ));
     (runner, store, base_dir)
 }
@@ -53,9 +56,11 @@ fn create_cached_runner(
     let cache = PersistentCache::new(
         cache_dir.path(),
         max_lmdb_size,
+        1,
         runtime,
         DEFAULT_LEASE_TIME,
         1,
+        StoreBackend::Lmdb,
     )
     .unwrap();
 
@@ -195,3 +200,45 @@ async fn recover_from_missing_store_contents() {
         .ok()
         .is_some())
 }
+
+#[tokio::test]
+async fn namespace_version_invalidates() {
+    let (_, mut workunit) = WorkunitStore::setup_for_tests();
+
+    let (local, store, _local_runner_dir) = create_local_runner();
+    let (caching, _cache_dir) = create_cached_runner(local, store.clone());
+    let (process, script_path, _script_dir) = create_script(0);
+    let process = process.local_cache(LocalCacheSettings {
+        namespace: "plugin".to_owned(),
+        namespace_version: 1,
+        ttl: None,
+    });
+
+    let first_result = caching
+        .run(Context::default(), &mut workunit, process.clone())
+        .await
+        .unwrap();
+    assert_eq!(first_result.exit_code, 0);
+
+    // Without the script, only a cache hit succeeds.
+    std::fs::remove_file(&script_path).unwrap();
+    let cached_result = caching
+        .run(Context::default(), &mut workunit, process.clone())
+        .await
+        .unwrap();
+    assert_eq!(cached_result.exit_code, 0);
+
+    let bumped_result = caching
+        .run(
+            Context::default(),
+            &mut workunit,
+            process.local_cache(LocalCacheSettings {
+                namespace: "plugin".to_owned(),
+                namespace_version: 2,
+                ttl: None,
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(bumped_result.exit_code, 127);
+}
@@ -72,6 +72,14 @@ impl ManagedChild {
         Ok(())
     }
 
+    /// Notify the child process group that the size of its terminal has changed.
+    ///
+    /// The child is the leader of its own session, so it does not receive the `SIGWINCH` that
+    /// the terminal sends to the foreground process group of our session when it is resized.
+    pub fn notify_window_size_changed(&mut self) -> Result<(), String> {
+        self.signal_pg(signal::Signal::SIGWINCH)
+    }
+
     /// Check if the child has exited.
     ///
     /// This returns true if the child has exited with any return code, or false
@@ -0,0 +1,122 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::fmt;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use fs::EMPTY_DIRECTORY_DIGEST;
+use itertools::Itertools;
+use log::info;
+use store::Store;
+use workunit_store::RunningWorkunit;
+
+use crate::{
+    get_digest, CommandRunner as CommandRunnerTrait, Context, FallibleProcessResultWithPlatform,
+    Process, ProcessError, ProcessResultMetadata, ProcessResultSource,
+};
+
+///
+/// A CommandRunner which, rather than executing processes, reports the components of their cache
+/// keys, and returns successful results without any outputs.
+///
+/// It should be used as the "leaf" runner, beneath runners which read (but must not write) caches,
+/// so that only the processes which would actually run are reported.
+///
+pub struct CommandRunner {
+    store: Store,
+    process_cache_namespace: Option<String>,
+}
+
+impl CommandRunner {
+    pub fn new(store: Store, process_cache_namespace: Option<String>) -> CommandRunner {
+        CommandRunner {
+            store,
+            process_cache_namespace,
+        }
+    }
+
+    ///
+    /// Describes the given process, and the components of its cache key.
+    ///
+    pub async fn explain(&self, req: &Process) -> String {
+        let cache_key = get_digest(
+            req,
+            None,
+            self.process_cache_namespace.clone(),
+            &self.store,
+            None,
+        )
+        .await;
+        let immutable_inputs = req
+            .input_digests
+            .immutable_inputs
+            .iter()
+            .map(|(path, digest)| format!("{}={:?}", path.display(), digest.as_digest()))
+            .join(", ");
+        let env = req
+            .cache_key_env()
+            .map(|(name, value)| format!("{name}={value}"))
+            .join(" ");
+        format!(
+            "Would run `{}`:\n  \
+             cache key: {:?}\n  \
+             argv: {:?}\n  \
+             env: {env}\n  \
+             working directory: {}\n  \
+             input digest: {:?}\n  \
+             immutable inputs: [{immutable_inputs}]\n  \
+             output files: {:?}\n  \
+             output directories: {:?}\n  \
+             platform: {}\n  \
+             strategy: {:?}",
+            req.description,
+            cache_key,
+            req.argv,
+            req.working_directory
+                .as_ref()
+                .map_or_else(|| ".".to_owned(), |path| path.display().to_string()),
+            req.input_digests.inputs.as_digest(),
+            req.output_files,
+            req.output_directories,
+            String::from(req.execution_environment.platform),
+            req.execution_environment.strategy,
+        )
+    }
+}
+
+impl fmt::Debug for CommandRunner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("dry_run::CommandRunner")
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl CommandRunnerTrait for CommandRunner {
+    async fn run(
+        &self,
+        context: Context,
+        _workunit: &mut RunningWorkunit,
+        req: Process,
+    ) -> Result<FallibleProcessResultWithPlatform, ProcessError> {
+        info!("{}", self.explain(&req).await);
+        let empty = self.store.store_file_bytes(Bytes::new(), true).await?;
+        Ok(FallibleProcessResultWithPlatform {
+            stdout_digest: empty,
+            stderr_digest: empty,
+            exit_code: 0,
+            output_directory: EMPTY_DIRECTORY_DIGEST.clone(),
+            metadata: ProcessResultMetadata::new(
+                None,
+                ProcessResultSource::DryRun,
+                req.execution_environment,
+                context.run_id,
+            ),
+        })
+    }
+
+    async fn shutdown(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use fs::EMPTY_DIRECTORY_DIGEST;
+use store::Store;
+use tempfile::TempDir;
+use testutil::owned_string_vec;
+use workunit_store::WorkunitStore;
+
+use crate::dry_run::CommandRunner;
+use crate::{CommandRunner as CommandRunnerTrait, Context, Process, ProcessResultSource};
+
+#[tokio::test]
+async fn reports_instead_of_running() {
+    let (_, mut workunit) = WorkunitStore::setup_for_tests();
+    let store_dir = TempDir::new().unwrap();
+    let store = Store::local_only(task_executor::Executor::new(), store_dir.path()).unwrap();
+    let runner = CommandRunner::new(store.clone(), None);
+
+    let mut process = Process::new(owned_string_vec(&["/bin/false", "--flag"]));
+    process.env.insert("NAME".to_owned(), "value".to_owned());
+    process.description = "would fail".to_owned();
+
+    let explanation = runner.explain(&process).await;
+    assert!(explanation.starts_with("Would run `would fail`:\n"));
+    assert!(explanation.contains(r#"argv: ["/bin/false", "--flag"]"#));
+    assert!(explanation.contains("env: NAME=value"));
+
+    let result = runner
+        .run(Context::default(), &mut workunit, process)
+        .await
+        .unwrap();
+    assert_eq!(result.exit_code, 0);
+    assert_eq!(result.output_directory, *EMPTY_DIRECTORY_DIGEST);
+    assert_eq!(result.metadata.source, ProcessResultSource::DryRun);
+    assert!(store
+        .load_file_bytes_with(result.stdout_digest, |bytes| bytes.is_empty())
+        .await
+        .unwrap());
+}
@@ -0,0 +1,193 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+use std::time::Duration;
+
+use fs::{Entry, SymlinkBehavior};
+use serde::{Deserialize, Serialize};
+use store::{Store, StoreError};
+
+use crate::{get_digest, Process};
+
+/// The maximum number of changed entries of a map which are described individually.
+const MAX_DESCRIBED_CHANGES: usize = 10;
+
+///
+/// The components of the cache key of a `Process`, which can be persisted between runs and
+/// compared in order to explain why a process missed the cache.
+///
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CacheKeyComponents {
+    /// The hex fingerprint of the digest of the Action of the process.
+    pub cache_key: String,
+    pub argv: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub working_directory: Option<String>,
+    /// The fingerprints of the (mutable) input files of the process, by path. The fingerprints of
+    /// executable files are suffixed with `+x`, and symlinks are represented by their targets.
+    pub inputs: BTreeMap<String, String>,
+    /// The fingerprints of the immutable input directories of the process, by path.
+    pub immutable_inputs: BTreeMap<String, String>,
+    pub output_files: BTreeSet<String>,
+    pub output_directories: BTreeSet<String>,
+    pub append_only_caches: BTreeMap<String, String>,
+    pub timeout: Option<Duration>,
+    pub platform: String,
+    pub strategy: String,
+}
+
+impl CacheKeyComponents {
+    pub async fn new(
+        process: &Process,
+        process_cache_namespace: Option<String>,
+        store: &Store,
+    ) -> Result<CacheKeyComponents, StoreError> {
+        let cache_key = get_digest(process, None, process_cache_namespace, store, None).await;
+
+        let tree = store
+            .load_digest_trie(process.input_digests.inputs.clone())
+            .await?;
+        let mut inputs = BTreeMap::new();
+        tree.walk(SymlinkBehavior::Aware, &mut |path, entry| {
+            let value = match entry {
+                Entry::Directory(_) => return,
+                Entry::File(f) if f.is_executable() => format!("{}+x", f.digest().hash.to_hex()),
+                Entry::File(f) => f.digest().hash.to_hex(),
+                Entry::Symlink(s) => format!("-> {}", s.target().display()),
+            };
+            inputs.insert(path.display().to_string(), value);
+        });
+
+        let relative_paths = |paths: &BTreeSet<fs::RelativePath>| {
+            paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect()
+        };
+        Ok(CacheKeyComponents {
+            cache_key: cache_key.hash.to_hex(),
+            argv: process.argv.clone(),
+            env: process
+                .cache_key_env()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            working_directory: process
+                .working_directory
+                .as_ref()
+                .map(|path| path.display().to_string()),
+            inputs,
+            immutable_inputs: process
+                .input_digests
+                .immutable_inputs
+                .iter()
+                .map(|(path, digest)| {
+                    (path.display().to_string(), digest.as_digest().hash.to_hex())
+                })
+                .collect(),
+            output_files: relative_paths(&process.output_files),
+            output_directories: relative_paths(&process.output_directories),
+            append_only_caches: process
+                .append_only_caches
+                .iter()
+                .map(|(name, path)| (name.name().to_owned(), path.display().to_string()))
+                .collect(),
+            timeout: process.timeout,
+            platform: String::from(process.execution_environment.platform),
+            strategy: format!("{:?}", process.execution_environment.strategy),
+        })
+    }
+
+    ///
+    /// Describes the components which differ between the given previous components and these.
+    ///
+    /// If the cache keys are equal, nothing is reported, even though the process missed the cache:
+    /// its previous result was evicted, or was not cached (e.g. because it failed).
+    ///
+    pub fn changes_since(&self, previous: &CacheKeyComponents) -> Vec<String> {
+        let mut changes = Vec::new();
+        if self.cache_key == previous.cache_key {
+            return changes;
+        }
+        describe_change(&mut changes, "argv", &previous.argv, &self.argv);
+        describe_map_changes(&mut changes, "env var", &previous.env, &self.env);
+        describe_change(
+            &mut changes,
+            "working directory",
+            &previous.working_directory,
+            &self.working_directory,
+        );
+        describe_map_changes(&mut changes, "input", &previous.inputs, &self.inputs);
+        describe_map_changes(
+            &mut changes,
+            "immutable input",
+            &previous.immutable_inputs,
+            &self.immutable_inputs,
+        );
+        describe_change(
+            &mut changes,
+            "output files",
+            &previous.output_files,
+            &self.output_files,
+        );
+        describe_change(
+            &mut changes,
+            "output directories",
+            &previous.output_directories,
+            &self.output_directories,
+        );
+        describe_map_changes(
+            &mut changes,
+            "append-only cache",
+            &previous.append_only_caches,
+            &self.append_only_caches,
+        );
+        describe_change(&mut changes, "timeout", &previous.timeout, &self.timeout);
+        describe_change(&mut changes, "platform", &previous.platform, &self.platform);
+        describe_change(&mut changes, "strategy", &previous.strategy, &self.strategy);
+        if changes.is_empty() {
+            // E.g. the cache namespace, or the cache key format, changed.
+            changes.push("the cache key changed, but none of its explained components".to_owned());
+        }
+        changes
+    }
+}
+
+fn describe_change<T: Debug + PartialEq>(
+    changes: &mut Vec<String>,
+    name: &str,
+    previous: &T,
+    current: &T,
+) {
+    if previous != current {
+        changes.push(format!("{name} changed from {previous:?} to {current:?}"));
+    }
+}
+
+fn describe_map_changes(
+    changes: &mut Vec<String>,
+    name: &str,
+    previous: &BTreeMap<String, String>,
+    current: &BTreeMap<String, String>,
+) {
+    let keys = previous
+        .keys()
+        .chain(current.keys())
+        .collect::<BTreeSet<_>>();
+    let mut changed = keys
+        .into_iter()
+        .filter_map(|key| match (previous.get(key), current.get(key)) {
+            (Some(p), Some(c)) if p == c => None,
+            (Some(_), Some(_)) => Some(format!("{name} `{key}` changed")),
+            (Some(_), None) => Some(format!("{name} `{key}` was removed")),
+            (None, _) => Some(format!("{name} `{key}` was added")),
+        })
+        .collect::<Vec<_>>();
+    if changed.len() > MAX_DESCRIBED_CHANGES {
+        let remaining = changed.len() - MAX_DESCRIBED_CHANGES;
+        changed.truncate(MAX_DESCRIBED_CHANGES);
+        changed.push(format!("... and {remaining} more {name} changes"));
+    }
+    changes.extend(changed);
+}
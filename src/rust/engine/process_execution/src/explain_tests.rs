@@ -0,0 +1,95 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use store::Store;
+use tempfile::TempDir;
+use testutil::owned_string_vec;
+
+use crate::explain::CacheKeyComponents;
+use crate::Process;
+
+fn components(cache_key: &str, inputs: &[(&str, &str)]) -> CacheKeyComponents {
+    CacheKeyComponents {
+        cache_key: cache_key.to_owned(),
+        argv: owned_string_vec(&["/bin/echo"]),
+        env: BTreeMap::new(),
+        working_directory: None,
+        inputs: inputs
+            .iter()
+            .map(|(path, fingerprint)| ((*path).to_owned(), (*fingerprint).to_owned()))
+            .collect(),
+        immutable_inputs: BTreeMap::new(),
+        output_files: BTreeSet::new(),
+        output_directories: BTreeSet::new(),
+        append_only_caches: BTreeMap::new(),
+        timeout: None,
+        platform: "linux_x86_64".to_owned(),
+        strategy: "Local".to_owned(),
+    }
+}
+
+#[test]
+fn no_changes_for_equal_cache_keys() {
+    let previous = components("a", &[("x", "1")]);
+    let current = components("a", &[("x", "2")]);
+    assert!(current.changes_since(&previous).is_empty());
+}
+
+#[test]
+fn path_level_input_changes() {
+    let previous = components("a", &[("changed", "1"), ("removed", "1"), ("same", "1")]);
+    let mut current = components("b", &[("added", "1"), ("changed", "2"), ("same", "1")]);
+    current.argv.push("hello".to_owned());
+    current.platform = "linux_arm64".to_owned();
+    assert_eq!(
+        current.changes_since(&previous),
+        vec![
+            r#"argv changed from ["/bin/echo"] to ["/bin/echo", "hello"]"#.to_owned(),
+            "input `added` was added".to_owned(),
+            "input `changed` changed".to_owned(),
+            "input `removed` was removed".to_owned(),
+            r#"platform changed from "linux_x86_64" to "linux_arm64""#.to_owned(),
+        ]
+    );
+}
+
+#[test]
+fn many_changes_are_truncated() {
+    let previous = components("a", &[]);
+    let paths = (0..12).map(|i| format!("{i:02}")).collect::<Vec<_>>();
+    let current = components(
+        "b",
+        &paths
+            .iter()
+            .map(|path| (path.as_str(), "1"))
+            .collect::<Vec<_>>(),
+    );
+    let changes = current.changes_since(&previous);
+    assert_eq!(changes.len(), 11);
+    assert_eq!(changes[9], "input `09` was added");
+    assert_eq!(changes[10], "... and 2 more input changes");
+}
+
+#[tokio::test]
+async fn env_changes_do_not_describe_values() {
+    let store_dir = TempDir::new().unwrap();
+    let store = Store::local_only(task_executor::Executor::new(), store_dir.path()).unwrap();
+
+    let mut process = Process::new(owned_string_vec(&["/bin/echo"]));
+    process.env.insert("SECRET".to_owned(), "1".to_owned());
+    let previous = CacheKeyComponents::new(&process, None, &store)
+        .await
+        .unwrap();
+    process.env.insert("SECRET".to_owned(), "2".to_owned());
+    let current = CacheKeyComponents::new(&process, None, &store)
+        .await
+        .unwrap();
+
+    assert_ne!(previous.cache_key, current.cache_key);
+    assert_eq!(
+        current.changes_since(&previous),
+        vec!["env var `SECRET` changed".to_owned()]
+    );
+}
@@ -14,8 +14,10 @@ use async_trait::async_trait;
 use bytes::Bytes;
 use concrete_time::{Duration, TimeSpan};
 use deepsize::DeepSizeOf;
-use fs::{DirectoryDigest, RelativePath, EMPTY_DIRECTORY_DIGEST};
-use fs::{File, PathStat};
+use fs::{
+    DigestTrie, DirectoryDigest, Entry, RelativePath, SymlinkBehavior, EMPTY_DIRECTORY_DIGEST,
+};
+use fs::{File, Link, PathStat};
 use futures::future::try_join_all;
 use futures::future::{self, BoxFuture, TryFutureExt};
 use futures::try_join;
@@ -51,6 +53,14 @@ pub mod switched;
 
 pub mod children;
 
+pub mod dry_run;
+#[cfg(test)]
+mod dry_run_tests;
+
+pub mod explain;
+#[cfg(test)]
+mod explain_tests;
+
 pub mod local;
 #[cfg(test)]
 pub mod local_tests;
@@ -59,8 +69,18 @@ pub mod named_caches;
 #[cfg(test)]
 pub mod named_caches_tests;
 
+pub mod overlay;
+#[cfg(test)]
+mod overlay_tests;
+
+pub mod routing;
+#[cfg(test)]
+mod routing_tests;
+
 pub(crate) mod fork_exec;
 
+pub mod sandboxer;
+
 pub mod workspace;
 #[cfg(test)]
 pub mod workspace_tests;
@@ -88,6 +108,13 @@ pub const CACHE_KEY_SALT_ENV_VAR_NAME: &str = "PANTS_CACHE_KEY_SALT";
 // CommandRunner.
 pub const CACHE_KEY_TARGET_PLATFORM_ENV_VAR_NAME: &str = "PANTS_CACHE_KEY_TARGET_PLATFORM";
 
+// Platform property which identifies the persistent worker which may run a remote action, as
+// understood by implementations of the remote persistent workers extension of the REAPI.
+pub const PERSISTENT_WORKER_KEY_PLATFORM_PROPERTY: &str = "persistentWorkerKey";
+
+// Node property which marks the files of the input root which make up a persistent worker.
+pub const TOOL_INPUT_NODE_PROPERTY: &str = "bazel_tool_input";
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ProcessError {
     /// A Digest was not present in either of the local or remote Stores.
@@ -479,6 +506,21 @@ pub struct ProcessExecutionEnvironment {
     pub strategy: ProcessExecutionStrategy,
 }
 
+///
+/// How the result of a Process is stored in the local cache.
+///
+#[derive(DeepSizeOf, Debug, Default, Clone, Hash, PartialEq, Eq, Serialize)]
+pub struct LocalCacheSettings {
+    /// The namespace that owns the entry, which may be cleared with the `clear-local-cache` goal.
+    /// Empty for the processes of Pants itself.
+    pub namespace: String,
+    /// The version of the namespace, which its owner may bump to invalidate the entries that it
+    /// previously stored.
+    pub namespace_version: u32,
+    /// How long the entry is valid for, or None if it does not expire.
+    pub ttl: Option<std::time::Duration>,
+}
+
 ///
 /// A process to be executed.
 ///
@@ -505,6 +547,15 @@ pub struct Process {
     ///
     pub env: BTreeMap<String, String>,
 
+    ///
+    /// The names of (some of) the `env` vars which are set for the execution, but are excluded from
+    /// the cache key: e.g. credentials, whose values should neither bust nor be stored in caches.
+    ///
+    /// Remote execution refuses processes which set them, since REv2 would store their values in
+    /// the remote CAS.
+    ///
+    pub execution_only_env_vars: BTreeSet<String>,
+
     ///
     /// A relative path to a directory existing in the `input_files` digest to execute the process
     /// from. Defaults to the `input_files` root.
@@ -540,6 +591,13 @@ pub struct Process {
     #[derivative(PartialEq = "ignore", Hash = "ignore")]
     pub description: String,
 
+    ///
+    /// Tags which are declared for this process, and which may be matched by the rules which route
+    /// processes between local and remote execution: see `routing::RoutingRule`.
+    ///
+    #[derivative(PartialEq = "ignore", Hash = "ignore")]
+    pub tags: BTreeSet<String>,
+
     // NB: We serialize with a function to avoid adding a serde dep to the logging crate.
     #[serde(serialize_with = "serialize_level")]
     pub level: log::Level,
@@ -575,6 +633,18 @@ pub struct Process {
 
     pub remote_cache_speculation_delay: std::time::Duration,
 
+    pub local_cache: LocalCacheSettings,
+
+    ///
+    /// If true, this Process may be run by a persistent worker when it is executed remotely, via
+    /// the remote persistent workers extension of the REAPI. The `use_nailgun` inputs of the
+    /// Process are its tool inputs, which (along with its argv and env) identify the worker.
+    ///
+    /// The Process must implement the persistent worker protocol: i.e., its last argument must be
+    /// a flagfile (`@<path>` or `--flagfile=<path>`) containing the arguments of each request.
+    ///
+    pub remote_persistent_worker: bool,
+
     ///
     /// The attempt number, in the case this Process is being retried.
     ///
@@ -599,12 +669,14 @@ impl Process {
         Process {
             argv,
             env: BTreeMap::new(),
+            execution_only_env_vars: BTreeSet::new(),
             working_directory: None,
             input_digests: InputDigests::default(),
             output_files: BTreeSet::new(),
             output_directories: BTreeSet::new(),
             timeout: None,
             description: "".to_string(),
+            tags: BTreeSet::new(),
             level: log::Level::Info,
             append_only_caches: BTreeMap::new(),
             jdk_home: None,
@@ -617,6 +689,8 @@ impl Process {
                 strategy: ProcessExecutionStrategy::Local,
             },
             remote_cache_speculation_delay: std::time::Duration::from_millis(0),
+            local_cache: LocalCacheSettings::default(),
+            remote_persistent_worker: false,
             attempt: 0,
         }
     }
@@ -629,6 +703,32 @@ impl Process {
         self
     }
 
+    ///
+    /// Replaces the execution-only env vars for this process.
+    ///
+    pub fn execution_only_env_vars(mut self, execution_only_env_vars: BTreeSet<String>) -> Process {
+        self.execution_only_env_vars = execution_only_env_vars;
+        self
+    }
+
+    ///
+    /// The `env` vars which are included in the cache key of this process: i.e. those which are not
+    /// `execution_only_env_vars`.
+    ///
+    pub fn cache_key_env(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.env
+            .iter()
+            .filter(|(name, _)| !self.execution_only_env_vars.contains(*name))
+    }
+
+    ///
+    /// Replaces the tags for this process.
+    ///
+    pub fn tags(mut self, tags: BTreeSet<String>) -> Process {
+        self.tags = tags;
+        self
+    }
+
     ///
     /// Replaces the working_directory for this process.
     ///
@@ -697,6 +797,16 @@ impl Process {
         self.cache_scope = cache_scope;
         self
     }
+
+    pub fn remote_persistent_worker(mut self, remote_persistent_worker: bool) -> Process {
+        self.remote_persistent_worker = remote_persistent_worker;
+        self
+    }
+
+    pub fn local_cache(mut self, local_cache: LocalCacheSettings) -> Process {
+        self.local_cache = local_cache;
+        self
+    }
 }
 
 ///
@@ -824,6 +934,8 @@ pub enum ProcessResultSource {
     Ran,
     HitLocally,
     HitRemotely,
+    /// The process was reported rather than run, by a `dry_run::CommandRunner`.
+    DryRun,
 }
 
 impl From<ProcessResultSource> for &'static str {
@@ -832,6 +944,7 @@ impl From<ProcessResultSource> for &'static str {
             ProcessResultSource::Ran => "ran",
             ProcessResultSource::HitLocally => "hit_locally",
             ProcessResultSource::HitRemotely => "hit_remotely",
+            ProcessResultSource::DryRun => "dry_run",
         }
     }
 }
@@ -895,6 +1008,9 @@ pub struct Context {
     pub build_id: String,
     pub run_id: RunId,
     pub tail_tasks: TailTasks,
+    /// How readily the client which is running this Process yields contended slots to other
+    /// clients: from 0 (the default) to `bounded::MAX_NICENESS`.
+    pub niceness: u8,
 }
 
 impl Default for Context {
@@ -904,6 +1020,7 @@ impl Default for Context {
             build_id: String::default(),
             run_id: RunId(0),
             tail_tasks: TailTasks::new(),
+            niceness: 0,
         }
     }
 }
@@ -914,12 +1031,14 @@ impl Context {
         build_id: String,
         run_id: RunId,
         tail_tasks: TailTasks,
+        niceness: u8,
     ) -> Context {
         Context {
             workunit_store,
             build_id,
             run_id,
             tail_tasks,
+            niceness,
         }
     }
 }
@@ -1119,6 +1238,10 @@ pub async fn make_execute_request(
                 "Cannot set env var with name {name} as that is reserved for internal use by pants"
             ));
         }
+        if req.execution_only_env_vars.contains(name) {
+            // See `Process::execution_only_env_vars`.
+            continue;
+        }
 
         command
             .environment_variables
@@ -1133,6 +1256,18 @@ pub async fn make_execute_request(
         _ => vec![],
     };
 
+    let use_persistent_worker = req.remote_persistent_worker
+        && matches!(
+            req.execution_environment.strategy,
+            ProcessExecutionStrategy::RemoteExecution(_)
+        );
+    if use_persistent_worker {
+        platform_properties.push((
+            PERSISTENT_WORKER_KEY_PLATFORM_PROPERTY.to_owned(),
+            persistent_worker_key(req, &command.arguments)?,
+        ));
+    }
+
     if let Some(cache_key_gen_version) = cache_key_gen_version {
         command
             .environment_variables
@@ -1271,9 +1406,39 @@ pub async fn make_execute_request(
         None => req.input_digests.complete.clone(),
     };
 
+    // The tool inputs of a persistent worker are marked in a copy of the input root, which is what
+    // the action refers to.
+    let action_input_root_digest = if use_persistent_worker {
+        let tool_inputs = store
+            .load_digest_trie(req.input_digests.nailgun.clone())
+            .await
+            .map_err(|err| format!("store error: {err}"))?
+            .files(SymlinkBehavior::Aware)
+            .into_iter()
+            .collect::<HashSet<_>>();
+        let input_root_tree = store
+            .load_digest_trie(input_root_digest.clone())
+            .await
+            .map_err(|err| format!("store error: {err}"))?;
+        let mut directories = Vec::new();
+        let root_digest = mark_tool_inputs(
+            &input_root_tree,
+            input_root_digest.as_digest(),
+            Path::new(""),
+            &tool_inputs,
+            &mut directories,
+        )?;
+        for directory in directories {
+            store.record_directory(&directory, true).await?;
+        }
+        root_digest
+    } else {
+        input_root_digest.as_digest()
+    };
+
     let mut action = remexec::Action {
         command_digest: Some((&digest(&command)?).into()),
-        input_root_digest: Some(input_root_digest.as_digest().into()),
+        input_root_digest: Some(action_input_root_digest.into()),
         ..remexec::Action::default()
     };
 
@@ -1289,6 +1454,7 @@ pub async fn make_execute_request(
         // more importantly because they do not have our same caching semantics, e.g.
         // `ProcessCacheScope.SUCCESSFUL` vs `ProcessCacheScope.ALWAYS`.
         skip_cache_lookup: true,
+        digest_function: protos::digest_function_field(hashing::DigestFunction::current()),
         ..remexec::ExecuteRequest::default()
     };
 
@@ -1300,6 +1466,95 @@ pub async fn make_execute_request(
     })
 }
 
+///
+/// Computes the key of the persistent worker which may run the given Process: i.e. the digest of
+/// an Action which would start the worker (with the given arguments, minus the flagfile) from its
+/// tool inputs.
+///
+fn persistent_worker_key(req: &Process, arguments: &[String]) -> Result<String, String> {
+    let startup_arguments = match arguments.split_last() {
+        Some((flagfile, startup_arguments))
+            if flagfile.starts_with('@') || flagfile.starts_with("--flagfile=") =>
+        {
+            startup_arguments
+        }
+        _ => {
+            return Err(format!(
+                "A process which uses a remote persistent worker must pass a flagfile (i.e. \
+                 `@<path>` or `--flagfile=<path>`) as its last argument, but `{}` had: {:?}",
+                req.description, req.argv
+            ))
+        }
+    };
+    let command = remexec::Command {
+        arguments: startup_arguments.to_vec(),
+        environment_variables: req
+            .cache_key_env()
+            .map(|(name, value)| remexec::command::EnvironmentVariable {
+                name: name.clone(),
+                value: value.clone(),
+            })
+            .collect(),
+        ..remexec::Command::default()
+    };
+    let action = remexec::Action {
+        command_digest: Some((&digest(&command)?).into()),
+        input_root_digest: Some(req.input_digests.nailgun.as_digest().into()),
+        ..remexec::Action::default()
+    };
+    Ok(digest(&action)?.hash.to_hex())
+}
+
+///
+/// Converts the given input root into `Directory` protos in which the files at the given paths are
+/// marked with the `TOOL_INPUT_NODE_PROPERTY`, and returns the digest of the root. Only the protos
+/// of directories which (transitively) contain tool inputs are collected: the others are unchanged.
+///
+fn mark_tool_inputs(
+    tree: &DigestTrie,
+    tree_digest: Digest,
+    prefix: &Path,
+    tool_inputs: &HashSet<PathBuf>,
+    directories: &mut Vec<remexec::Directory>,
+) -> Result<Digest, String> {
+    if !tool_inputs.iter().any(|path| path.starts_with(prefix)) {
+        return Ok(tree_digest);
+    }
+
+    let mut directory = tree.as_remexec_directory();
+    for file_node in &mut directory.files {
+        if tool_inputs.contains(&prefix.join(&file_node.name)) {
+            file_node.node_properties = Some(remexec::NodeProperties {
+                properties: vec![remexec::NodeProperty {
+                    name: TOOL_INPUT_NODE_PROPERTY.to_owned(),
+                    value: String::new(),
+                }],
+                ..remexec::NodeProperties::default()
+            });
+        }
+    }
+    // NB: The directory nodes of the proto are in the same (sorted) order as the directory entries
+    // of the tree.
+    let subdirectories = tree.entries().iter().filter_map(|entry| match entry {
+        Entry::Directory(d) => Some(d),
+        _ => None,
+    });
+    for (directory_node, subdirectory) in directory.directories.iter_mut().zip(subdirectories) {
+        let subdirectory_digest = mark_tool_inputs(
+            subdirectory.tree(),
+            subdirectory.digest(),
+            &prefix.join(&directory_node.name),
+            tool_inputs,
+            directories,
+        )?;
+        directory_node.digest = Some(subdirectory_digest.into());
+    }
+
+    let directory_digest = digest(&directory)?;
+    directories.push(directory);
+    Ok(directory_digest)
+}
+
 /// Convert an ActionResult into a FallibleProcessResultWithPlatform.
 ///
 /// HACK: The caching CommandRunner stores the digest of the Directory that merges all output
@@ -1462,17 +1717,38 @@ pub fn extract_output_files(
             let output_file_path_buf = PathBuf::from(output_file.path.clone());
             let digest: Result<Digest, String> = require_digest(output_file.digest.as_ref());
             path_map.insert(output_file_path_buf.clone(), digest?);
+            // The executable bit may also be reported via the unix mode of the NodeProperties.
+            let executable_mode = output_file
+                .node_properties
+                .as_ref()
+                .and_then(|node_properties| node_properties.unix_mode)
+                .map_or(false, |unix_mode| unix_mode & 0o111 != 0);
             Ok(PathStat::file(
                 output_file_path_buf.clone(),
                 File {
                     path: output_file_path_buf,
-                    is_executable: output_file.is_executable,
+                    is_executable: output_file.is_executable || executable_mode,
                 },
             ))
         })
         .collect();
 
-    let path_stats = try_future!(path_stats_result);
+    let mut path_stats = try_future!(path_stats_result);
+
+    // And for the symlinks, which servers implementing v2.1 of the API report in `output_symlinks`
+    // as well as in the deprecated `output_file_symlinks` and `output_directory_symlinks`.
+    let output_symlinks = action_result
+        .output_symlinks
+        .iter()
+        .chain(&action_result.output_file_symlinks)
+        .chain(&action_result.output_directory_symlinks)
+        .map(|symlink| (PathBuf::from(&symlink.path), PathBuf::from(&symlink.target)))
+        .collect::<BTreeMap<_, _>>();
+    path_stats.extend(
+        output_symlinks
+            .into_iter()
+            .map(|(path, target)| PathStat::link(path.clone(), Link { path, target })),
+    );
 
     #[derive(Clone)]
     struct StoreOneOffRemoteDigest {
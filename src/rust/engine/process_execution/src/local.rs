@@ -4,6 +4,7 @@ use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::{self, Debug};
 use std::io::Write;
 use std::ops::Neg;
+use std::os::unix::io::AsRawFd;
 use std::os::unix::{fs::OpenOptionsExt, process::ExitStatusExt};
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
@@ -22,6 +23,8 @@ use futures::stream::{BoxStream, StreamExt, TryStreamExt};
 use futures::{try_join, FutureExt, TryFutureExt};
 use log::{debug, info};
 use nails::execution::ExitCode;
+use nix::errno::Errno;
+use nix::fcntl::{flock, FlockArg};
 use shell_quote::bash;
 use store::{
     ImmutableInputs, OneOffStoreFileByDigest, Snapshot, SnapshotOps, Store, StoreError,
@@ -33,9 +36,13 @@ use tokio::process::Command;
 use tokio::sync::RwLock;
 use tokio::time::timeout;
 use tokio_util::codec::{BytesCodec, FramedRead};
-use workunit_store::{in_workunit, Level, Metric, RunningWorkunit};
+use workunit_store::{
+    append_live_output_if_in_workunit, in_workunit, Level, Metric, RunningWorkunit,
+};
 
 use crate::fork_exec::spawn_process;
+use crate::overlay::OverlaySandbox;
+use crate::sandboxer::Sandboxer;
 use crate::{
     Context, FallibleProcessResultWithPlatform, ManagedChild, NamedCaches, Process, ProcessError,
     ProcessResultMetadata, ProcessResultSource,
@@ -58,7 +65,10 @@ pub struct CommandRunner {
     named_caches: NamedCaches,
     immutable_inputs: ImmutableInputs,
     keep_sandboxes: KeepSandboxes,
+    deterministic_sandbox_paths: bool,
+    overlay_sandboxes: bool,
     spawn_lock: Arc<RwLock<()>>,
+    sandboxer: Option<Sandboxer>,
 }
 
 impl CommandRunner {
@@ -69,7 +79,10 @@ impl CommandRunner {
         named_caches: NamedCaches,
         immutable_inputs: ImmutableInputs,
         keep_sandboxes: KeepSandboxes,
+        deterministic_sandbox_paths: bool,
+        overlay_sandboxes: bool,
         spawn_lock: Arc<RwLock<()>>,
+        sandboxer: Option<Sandboxer>,
     ) -> CommandRunner {
         CommandRunner {
             store,
@@ -78,7 +91,10 @@ impl CommandRunner {
             named_caches,
             immutable_inputs,
             keep_sandboxes,
+            deterministic_sandbox_paths,
+            overlay_sandboxes,
             spawn_lock,
+            sandboxer,
         }
     }
 
@@ -159,7 +175,8 @@ pub enum ChildOutput {
 }
 
 ///
-/// Collect the outputs of a child process.
+/// Collect the outputs of a child process, which are also recorded as the live output of the
+/// current workunit (see `WorkunitStore::live_output`).
 ///
 pub async fn collect_child_outputs<'a, 'b>(
     stdout: &'a mut BytesMut,
@@ -170,8 +187,14 @@ pub async fn collect_child_outputs<'a, 'b>(
 
     while let Some(child_output_res) = stream.next().await {
         match child_output_res? {
-            ChildOutput::Stdout(bytes) => stdout.extend_from_slice(&bytes),
-            ChildOutput::Stderr(bytes) => stderr.extend_from_slice(&bytes),
+            ChildOutput::Stdout(bytes) => {
+                append_live_output_if_in_workunit(&bytes);
+                stdout.extend_from_slice(&bytes)
+            }
+            ChildOutput::Stderr(bytes) => {
+                append_live_output_if_in_workunit(&bytes);
+                stderr.extend_from_slice(&bytes)
+            }
             ChildOutput::Exit(code) => exit_code = code.0,
         };
     }
@@ -198,29 +221,57 @@ impl super::CommandRunner for CommandRunner {
             // renders at the Process's level.
             desc = Some(req.description.clone()),
             |workunit| async move {
-                let mut workdir = create_sandbox(
-                    self.executor.clone(),
-                    &self.work_dir_base,
-                    &req.description,
-                    self.keep_sandboxes,
-                )?;
+                let mut workdir = if self.deterministic_sandbox_paths {
+                    create_sandbox_in_slot(
+                        self.executor.clone(),
+                        &self.work_dir_base,
+                        &req.description,
+                        self.keep_sandboxes,
+                    )?
+                } else {
+                    create_sandbox(
+                        self.executor.clone(),
+                        &self.work_dir_base,
+                        &req.description,
+                        self.keep_sandboxes,
+                    )?
+                };
+
+                // With overlay sandboxes, the immutable inputs of the process are the lower layers
+                // of an overlay (rather than being symlinked), and its other inputs are
+                // materialized in the upper layer, to which it writes its outputs.
+                let overlay =
+                    if self.overlay_sandboxes && !req.input_digests.immutable_inputs.is_empty() {
+                        let layers = self
+                            .immutable_inputs
+                            .overlay_layers(&req.input_digests.immutable_inputs)
+                            .await?;
+                        Some(OverlaySandbox::new(workdir.path(), layers)?)
+                    } else {
+                        None
+                    };
+                let (workdir_path, chroot_path) = match &overlay {
+                    Some(overlay) => (overlay.upper().to_owned(), overlay.merged().to_owned()),
+                    None => (workdir.path().to_owned(), workdir.path().to_owned()),
+                };
 
                 // Start working on a mutable version of the process.
                 let mut req = req;
-                // Update env, replacing `{chroot}` placeholders with `workdir_path`.
-                apply_chroot(workdir.path().to_str().unwrap(), &mut req);
+                // Update env, replacing `{chroot}` placeholders with `chroot_path`.
+                apply_chroot(chroot_path.to_str().unwrap(), &mut req);
 
                 // Prepare the workdir.
                 let exclusive_spawn = prepare_workdir(
-                    workdir.path().to_owned(),
+                    workdir_path.clone(),
                     &self.work_dir_base,
                     &req,
                     req.input_digests.inputs.clone(),
                     &self.store,
                     &self.named_caches,
-                    &self.immutable_inputs,
+                    overlay.is_none().then_some(&self.immutable_inputs),
                     None,
                     None,
+                    self.sandboxer.as_ref(),
                 )
                 .await?;
 
@@ -235,8 +286,8 @@ impl super::CommandRunner for CommandRunner {
                         context,
                         self.store.clone(),
                         self.executor.clone(),
-                        workdir.path().to_owned(),
-                        (),
+                        workdir_path.clone(),
+                        overlay,
                         exclusive_spawn,
                     )
                     .map_err(|msg| {
@@ -264,7 +315,7 @@ impl super::CommandRunner for CommandRunner {
                         &req.env,
                         &req.working_directory,
                         &req.argv,
-                        workdir.path(),
+                        &workdir_path,
                     )?;
                 }
 
@@ -281,70 +332,129 @@ impl super::CommandRunner for CommandRunner {
 
 #[async_trait]
 impl CapturedWorkdir for CommandRunner {
-    type WorkdirToken = ();
+    // The overlay which the process runs in, if any: see `OverlaySandbox`.
+    type WorkdirToken = Option<OverlaySandbox>;
 
     async fn run_in_workdir<'s, 'c, 'w, 'r>(
         &'s self,
         _context: &'c Context,
         workdir_path: &'w Path,
-        _workdir_token: (),
+        workdir_token: Option<OverlaySandbox>,
         req: Process,
         exclusive_spawn: bool,
     ) -> Result<BoxStream<'r, Result<ChildOutput, String>>, String> {
+        let chroot_path = workdir_token
+            .as_ref()
+            .map_or(workdir_path, |overlay| overlay.merged());
         let cwd = if let Some(ref working_directory) = req.working_directory {
-            workdir_path.join(working_directory)
+            chroot_path.join(working_directory)
         } else {
-            workdir_path.to_owned()
+            chroot_path.to_owned()
+        };
+        if let Some(sandboxer) = &self.sandboxer {
+            return sandboxer
+                .spawn(&req.argv, &req.env, &cwd, exclusive_spawn)
+                .await;
+        }
+
+        let mut command = match &workdir_token {
+            Some(overlay) => {
+                // NB: The working directory only exists once the overlay has been mounted.
+                let mut command = make_command(&req.argv, &req.env, overlay.merged());
+                overlay.mount_before_exec(&mut command, &cwd)?;
+                command
+            }
+            None => make_command(&req.argv, &req.env, &cwd),
         };
-        let mut command = Command::new(&req.argv[0]);
-        command
-            .env_clear()
-            // It would be really nice not to have to manually set PATH but this is sadly the only way
-            // to stop automatic PATH searching.
-            .env("PATH", "")
-            .args(&req.argv[1..])
-            .current_dir(cwd)
-            .envs(&req.env)
-            .stdin(Stdio::null())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-
-        let mut child = spawn_process(self.spawn_lock.clone(), exclusive_spawn, move || {
+        let child = spawn_process(self.spawn_lock.clone(), exclusive_spawn, move || {
             ManagedChild::spawn(&mut command, None)
         })
-        .await?;
+        .await
+        .map_err(|e| {
+            if workdir_token.is_some() {
+                format!(
+                    "{e}\n\nOverlay sandboxes require Linux 5.11 or newer, with unprivileged user \
+                     namespaces enabled."
+                )
+            } else {
+                e
+            }
+        })?;
 
         debug!("spawned local process as {:?} for {:?}", child.id(), req);
-        let stdout_stream = FramedRead::new(child.stdout.take().unwrap(), BytesCodec::new())
-            .map_ok(|bytes| ChildOutput::Stdout(bytes.into()))
-            .fuse()
-            .boxed();
-        let stderr_stream = FramedRead::new(child.stderr.take().unwrap(), BytesCodec::new())
-            .map_ok(|bytes| ChildOutput::Stderr(bytes.into()))
-            .fuse()
-            .boxed();
-        let exit_stream = async move {
-            child
-                .wait()
-                .map_ok(|exit_status| {
-                    ChildOutput::Exit(ExitCode(
-                        exit_status
-                            .code()
-                            .or_else(|| exit_status.signal().map(Neg::neg))
-                            .expect("Child process should exit via returned code or signal."),
-                    ))
-                })
-                .await
+        Ok(child_output_stream(child))
+    }
+
+    async fn prepare_workdir_for_capture(
+        &self,
+        _context: &Context,
+        _workdir_path: &Path,
+        workdir_token: Option<OverlaySandbox>,
+        _req: &Process,
+    ) -> Result<(), String> {
+        if let Some(overlay) = workdir_token {
+            overlay.make_removable()?;
         }
-        .into_stream()
-        .boxed();
-        let result_stream =
-            futures::stream::select_all(vec![stdout_stream, stderr_stream, exit_stream]);
+        Ok(())
+    }
+}
 
-        Ok(result_stream
-            .map_err(|e| format!("Failed to consume process outputs: {e:?}"))
-            .boxed())
+///
+/// Creates a Command which runs the given argv in the given directory, with only the given
+/// environment variables set.
+///
+pub(crate) fn make_command(argv: &[String], env: &BTreeMap<String, String>, cwd: &Path) -> Command {
+    let mut command = Command::new(&argv[0]);
+    command
+        .env_clear()
+        // It would be really nice not to have to manually set PATH but this is sadly the only way
+        // to stop automatic PATH searching.
+        .env("PATH", "")
+        .args(&argv[1..])
+        .current_dir(cwd)
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    command
+}
+
+///
+/// Streams the outputs of a child which was spawned with piped stdout and stderr, followed by its
+/// exit code.
+///
+pub(crate) fn child_output_stream(
+    mut child: ManagedChild,
+) -> BoxStream<'static, Result<ChildOutput, String>> {
+    let stdout_stream = FramedRead::new(child.stdout.take().unwrap(), BytesCodec::new())
+        .map_ok(|bytes| ChildOutput::Stdout(bytes.into()))
+        .fuse()
+        .boxed();
+    let stderr_stream = FramedRead::new(child.stderr.take().unwrap(), BytesCodec::new())
+        .map_ok(|bytes| ChildOutput::Stderr(bytes.into()))
+        .fuse()
+        .boxed();
+    let exit_stream = async move {
+        child
+            .wait()
+            .map_ok(|exit_status| {
+                ChildOutput::Exit(ExitCode(
+                    exit_status
+                        .code()
+                        .or_else(|| exit_status.signal().map(Neg::neg))
+                        .expect("Child process should exit via returned code or signal."),
+                ))
+            })
+            .await
     }
+    .into_stream()
+    .boxed();
+    let result_stream =
+        futures::stream::select_all(vec![stdout_stream, stderr_stream, exit_stream]);
+
+    result_stream
+        .map_err(|e| format!("Failed to consume process outputs: {e:?}"))
+        .boxed()
 }
 
 #[async_trait]
@@ -627,6 +737,11 @@ pub async fn prepare_workdir_digest(
 
 /// Prepares the given workdir for use by the given Process.
 ///
+/// If `ImmutableInputs` are given, the immutable inputs of the Process are symlinked into the
+/// workdir: otherwise they must be provided by other means (see `OverlaySandbox`).
+///
+/// If a `Sandboxer` is given, the workdir is materialized by it rather than by this process.
+///
 /// Returns true if the executable for the Process was created in the workdir, indicating that
 /// `exclusive_spawn` is required.
 ///
@@ -637,9 +752,10 @@ pub async fn prepare_workdir(
     materialized_input_digest: DirectoryDigest,
     store: &Store,
     named_caches: &NamedCaches,
-    immutable_inputs: &ImmutableInputs,
+    immutable_inputs: Option<&ImmutableInputs>,
     named_caches_prefix: Option<&Path>,
     immutable_inputs_prefix: Option<&Path>,
+    sandboxer: Option<&Sandboxer>,
 ) -> Result<bool, StoreError> {
     // Capture argv0 as the executable path so that we can test whether we have created it in the
     // sandbox.
@@ -662,7 +778,7 @@ pub async fn prepare_workdir(
             materialized_input_digest,
             store,
             named_caches,
-            Some(immutable_inputs),
+            immutable_inputs,
             named_caches_prefix,
             immutable_inputs_prefix,
         )
@@ -670,16 +786,28 @@ pub async fn prepare_workdir(
 
         let mut mutable_paths = req.output_files.clone();
         mutable_paths.extend(req.output_directories.clone());
-        store
-            .materialize_directory(
-                workdir_path,
-                workdir_root_path,
-                complete_input_digest,
-                false,
-                &mutable_paths,
-                Permissions::Writable,
-            )
-            .await?;
+        if let Some(sandboxer) = sandboxer {
+            sandboxer
+                .materialize_directory(
+                    store,
+                    &workdir_path,
+                    workdir_root_path,
+                    complete_input_digest,
+                    &mutable_paths,
+                )
+                .await?;
+        } else {
+            store
+                .materialize_directory(
+                    workdir_path,
+                    workdir_root_path,
+                    complete_input_digest,
+                    false,
+                    &mutable_paths,
+                    Permissions::Writable,
+                )
+                .await?;
+        }
 
         if let Some(executable_path) = maybe_executable_path {
             Ok(tokio::fs::metadata(executable_path).await.is_ok())
@@ -707,17 +835,95 @@ pub fn create_sandbox(
         .tempdir_in(base_directory)
         .map_err(|err| format!("Error making tempdir for local process execution: {err:?}"))?;
 
-    let mut sandbox = AsyncDropSandbox(executor, workdir.path().to_owned(), Some(workdir));
+    let mut sandbox = AsyncDropSandbox(
+        executor,
+        workdir.path().to_owned(),
+        Some(SandboxDir::Temp(workdir)),
+    );
     if keep_sandboxes == KeepSandboxes::Always {
         sandbox.keep(description);
     }
     Ok(sandbox)
 }
 
+///
+/// Like `create_sandbox`, but creates the sandbox at a stable path in the given base path (i.e.
+/// `pants-sandbox-slot-<N>`, for the lowest `N` which is not in use), rather than at a random path.
+///
+/// Tools which embed the absolute path of their sandbox into their outputs (e.g. in debug info
+/// or shebangs) thus produce the same outputs for the same inputs. Slots are locked with `flock`,
+/// so that concurrent processes which share the base path never use the same slot, and are
+/// cleaned before they are used. A preserved sandbox retains its slot until this process exits.
+///
+pub fn create_sandbox_in_slot(
+    executor: Executor,
+    base_directory: &Path,
+    description: &str,
+    keep_sandboxes: KeepSandboxes,
+) -> Result<AsyncDropSandbox, String> {
+    let slot = SandboxSlot::acquire(base_directory)
+        .map_err(|err| format!("Error making sandbox for local process execution: {err}"))?;
+
+    let mut sandbox = AsyncDropSandbox(executor, slot.path.clone(), Some(SandboxDir::Slot(slot)));
+    if keep_sandboxes == KeepSandboxes::Always {
+        sandbox.keep(description);
+    }
+    Ok(sandbox)
+}
+
+enum SandboxDir {
+    Temp(TempDir),
+    Slot(SandboxSlot),
+}
+
+///
+/// A sandbox directory at a stable path, which is locked while this struct is alive, and which is
+/// cleaned up when it is dropped.
+///
+struct SandboxSlot {
+    path: PathBuf,
+    _lock: std::fs::File,
+}
+
+impl SandboxSlot {
+    fn acquire(base_directory: &Path) -> Result<SandboxSlot, String> {
+        for slot in 0.. {
+            let path = base_directory.join(format!("pants-sandbox-slot-{slot}"));
+            let lock_path = path.with_extension("lock");
+            let lock = std::fs::File::create(&lock_path)
+                .map_err(|e| format!("Error creating lock file {}: {e}", lock_path.display()))?;
+            match flock(lock.as_raw_fd(), FlockArg::LockExclusiveNonblock) {
+                Ok(()) => {}
+                Err(Errno::EWOULDBLOCK) => continue,
+                Err(e) => return Err(format!("Error locking {}: {e}", lock_path.display())),
+            }
+
+            // The slot may contain a sandbox which was preserved (or not cleaned up) by a process
+            // which has since exited.
+            match std::fs::remove_dir_all(&path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(format!("Error cleaning {}: {e}", path.display())),
+            }
+            std::fs::create_dir(&path)
+                .map_err(|e| format!("Error creating {}: {e}", path.display()))?;
+            return Ok(SandboxSlot { path, _lock: lock });
+        }
+        unreachable!("There is always another slot.")
+    }
+}
+
+impl Drop for SandboxSlot {
+    fn drop(&mut self) {
+        // NB: The directory is removed before the lock is released (when the lock is dropped).
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
 /// Dropping sandboxes can involve a lot of IO, so it is spawned to the background as a blocking
 /// task.
 #[must_use]
-pub struct AsyncDropSandbox(Executor, PathBuf, Option<TempDir>);
+pub struct AsyncDropSandbox(Executor, PathBuf, Option<SandboxDir>);
 
 impl AsyncDropSandbox {
     pub fn path(&self) -> &Path {
@@ -730,7 +936,15 @@ impl AsyncDropSandbox {
     ///
     pub fn keep(&mut self, description: &str) {
         if let Some(workdir) = self.2.take() {
-            let preserved_path = workdir.into_path();
+            let preserved_path = match workdir {
+                SandboxDir::Temp(workdir) => workdir.into_path(),
+                SandboxDir::Slot(slot) => {
+                    let path = slot.path.clone();
+                    // Hold the lock (but do not clean up the slot) until this process exits.
+                    std::mem::forget(slot);
+                    path
+                }
+            };
             info!(
                 "Preserving local process execution dir {} for {}",
                 preserved_path.display(),
@@ -497,6 +497,32 @@ async fn test_directory_preservation_error() {
     assert_eq!(testutil::file::list_dir(&preserved_work_root).len(), 1);
 }
 
+#[tokio::test]
+async fn sandbox_slots() {
+    let work_dir = TempDir::new().unwrap();
+    let slot_path = |slot: usize| work_dir.path().join(format!("pants-sandbox-slot-{slot}"));
+    // A sandbox which was left behind by a previous process is cleaned before its slot is used.
+    std::fs::create_dir(slot_path(0)).unwrap();
+    std::fs::write(slot_path(0).join("stale"), "").unwrap();
+
+    let executor = task_executor::Executor::new();
+    let first = local::create_sandbox_in_slot(
+        executor.clone(),
+        work_dir.path(),
+        "first",
+        KeepSandboxes::Never,
+    )
+    .unwrap();
+    assert_eq!(first.path(), slot_path(0));
+    assert_eq!(testutil::file::list_dir(first.path()).len(), 0);
+
+    // Slots which are in use are skipped.
+    let second =
+        local::create_sandbox_in_slot(executor, work_dir.path(), "second", KeepSandboxes::Never)
+            .unwrap();
+    assert_eq!(second.path(), slot_path(1));
+}
+
 #[tokio::test]
 async fn all_containing_directories_for_outputs_are_created() {
     let result = run_command_locally(
@@ -736,7 +762,8 @@ async fn prepare_workdir_exclusive_relative() {
         TestDirectory::recursive().directory_digest(),
         &store,
         &named_caches,
-        &immutable_inputs,
+        Some(&immutable_inputs),
+        None,
         None,
         None,
     )
@@ -796,7 +823,10 @@ async fn run_command_locally_in_dir(
         named_caches,
         immutable_inputs,
         cleanup,
+        false,
+        false,
         Arc::new(RwLock::new(())),
+        None,
     );
     let original = runner.run(Context::default(), workunit, req).await?;
     let stdout_bytes = store
@@ -0,0 +1,178 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::ffi::CString;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use tokio::process::Command;
+
+///
+/// A sandbox which is an overlayfs mount: its read-only lower layers are directories which are
+/// shared between sandboxes (i.e., the materialized immutable inputs of a process), and its upper
+/// layer is a per-execution directory, in which the other inputs of the process are materialized,
+/// and to which its outputs are written.
+///
+/// The overlay is mounted by the process itself before it execs, in a new (unprivileged) user and
+/// mount namespace: it is thus only visible to the process and its children, and is unmounted when
+/// they have all exited. This requires Linux 5.11 or newer, with unprivileged user namespaces.
+///
+#[derive(Clone, Debug)]
+pub struct OverlaySandbox {
+    lower: Vec<PathBuf>,
+    upper: PathBuf,
+    work: PathBuf,
+    merged: PathBuf,
+}
+
+impl OverlaySandbox {
+    ///
+    /// Creates the directories of an overlay with the given lower layers in the given (empty)
+    /// sandbox directory.
+    ///
+    pub fn new(sandbox: &Path, lower: Vec<PathBuf>) -> Result<OverlaySandbox, String> {
+        if lower.is_empty() {
+            return Err("An overlay sandbox must have at least one lower layer.".to_owned());
+        }
+        let overlay = OverlaySandbox {
+            lower,
+            upper: sandbox.join("upper"),
+            work: sandbox.join("work"),
+            merged: sandbox.join("merged"),
+        };
+        for dir in [&overlay.upper, &overlay.work, &overlay.merged] {
+            std::fs::create_dir(dir)
+                .map_err(|e| format!("Error creating overlay directory {}: {e}", dir.display()))?;
+        }
+        Ok(overlay)
+    }
+
+    ///
+    /// The directory in which the inputs of the process (other than its lower layers) should be
+    /// materialized, and from which its outputs should be captured.
+    ///
+    pub fn upper(&self) -> &Path {
+        &self.upper
+    }
+
+    ///
+    /// The path at which the overlay is mounted for the process, which it runs in.
+    ///
+    pub fn merged(&self) -> &Path {
+        &self.merged
+    }
+
+    ///
+    /// The options with which the overlay is mounted.
+    ///
+    pub fn mount_options(&self) -> Result<CString, String> {
+        let path_str = |path: &Path| {
+            // NB: These characters separate the options and the lower layers.
+            match path.to_str() {
+                Some(s) if !s.contains([',', ':', '\\']) => Ok(s.to_owned()),
+                _ => Err(format!(
+                    "The path {} cannot be used in an overlay sandbox.",
+                    path.display()
+                )),
+            }
+        };
+        let lower = self
+            .lower
+            .iter()
+            .map(|path| path_str(path))
+            .collect::<Result<Vec<_>, _>>()?
+            .join(":");
+        let options = format!(
+            "lowerdir={lower},upperdir={},workdir={},userxattr",
+            path_str(&self.upper)?,
+            path_str(&self.work)?
+        );
+        CString::new(options).map_err(|e| format!("Invalid overlay mount options: {e}"))
+    }
+
+    ///
+    /// Adjusts the given Command to mount the overlay before it execs, and to then change to the
+    /// given working directory in the overlay.
+    ///
+    /// NB: The working directory of the Command itself is changed to before the overlay is mounted,
+    /// so it should be the `merged` directory, or some other directory which exists beforehand.
+    ///
+    #[cfg(target_os = "linux")]
+    pub fn mount_before_exec(&self, command: &mut Command, cwd: &Path) -> Result<(), String> {
+        use std::ffi::CStr;
+        use std::io;
+        use std::os::unix::ffi::OsStrExt;
+
+        // NB: Only async-signal-safe functions may be called between fork and exec, so everything
+        // which the child needs is allocated here.
+        let options = self.mount_options()?;
+        let target = CString::new(self.merged.as_os_str().as_bytes())
+            .map_err(|e| format!("Invalid overlay mount target: {e}"))?;
+        let cwd = CString::new(cwd.as_os_str().as_bytes())
+            .map_err(|e| format!("Invalid working directory: {e}"))?;
+        let uid_map = format!("{0} {0} 1", nix::unistd::getuid()).into_bytes();
+        let gid_map = format!("{0} {0} 1", nix::unistd::getgid()).into_bytes();
+
+        fn check(result: libc::c_int) -> io::Result<()> {
+            if result < 0 {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(())
+            }
+        }
+
+        fn write_file(path: &CStr, content: &[u8]) -> io::Result<()> {
+            let fd = unsafe { libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC) };
+            check(fd)?;
+            let written = unsafe { libc::write(fd, content.as_ptr().cast(), content.len()) };
+            let result = if written < 0 {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(())
+            };
+            unsafe { libc::close(fd) };
+            result
+        }
+
+        unsafe {
+            command.pre_exec(move || {
+                check(libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNS))?;
+                // Map the current user and group to themselves in the new user namespace, which
+                // (for an unprivileged process) requires that setgroups is denied first.
+                write_file(c"/proc/self/setgroups", b"deny")?;
+                write_file(c"/proc/self/uid_map", &uid_map)?;
+                write_file(c"/proc/self/gid_map", &gid_map)?;
+                check(libc::mount(
+                    c"overlay".as_ptr(),
+                    target.as_ptr(),
+                    c"overlay".as_ptr(),
+                    0,
+                    options.as_ptr().cast(),
+                ))?;
+                check(libc::chdir(cwd.as_ptr()))
+            });
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn mount_before_exec(&self, _command: &mut Command, _cwd: &Path) -> Result<(), String> {
+        Err("Overlay sandboxes are only supported on Linux.".to_owned())
+    }
+
+    ///
+    /// Makes the sandbox removable once the process has exited: overlayfs leaves an inaccessible
+    /// directory in its work directory.
+    ///
+    pub fn make_removable(&self) -> Result<(), String> {
+        let path = self.work.join("work");
+        match std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o700)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!(
+                "Error making overlay directory {} removable: {e}",
+                path.display()
+            )),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+use tempfile::TempDir;
+
+use crate::overlay::OverlaySandbox;
+
+#[test]
+fn creates_overlay_directories() {
+    let sandbox = TempDir::new().unwrap();
+    let overlay = OverlaySandbox::new(sandbox.path(), vec![PathBuf::from("/layers/one")]).unwrap();
+    assert_eq!(overlay.upper(), sandbox.path().join("upper"));
+    assert_eq!(overlay.merged(), sandbox.path().join("merged"));
+    for dir in ["upper", "work", "merged"] {
+        assert!(sandbox.path().join(dir).is_dir());
+    }
+}
+
+#[test]
+fn requires_a_lower_layer() {
+    let sandbox = TempDir::new().unwrap();
+    assert!(OverlaySandbox::new(sandbox.path(), vec![]).is_err());
+}
+
+#[test]
+fn mount_options() {
+    let sandbox = TempDir::new().unwrap();
+    let overlay = OverlaySandbox::new(
+        sandbox.path(),
+        vec![PathBuf::from("/layers/one"), PathBuf::from("/layers/two")],
+    )
+    .unwrap();
+    assert_eq!(
+        overlay.mount_options().unwrap().to_str().unwrap(),
+        format!(
+            "lowerdir=/layers/one:/layers/two,upperdir={},workdir={},userxattr",
+            sandbox.path().join("upper").display(),
+            sandbox.path().join("work").display()
+        )
+    );
+}
+
+#[test]
+fn mount_options_reject_separators() {
+    let sandbox = TempDir::new().unwrap();
+    let overlay =
+        OverlaySandbox::new(sandbox.path(), vec![PathBuf::from("/layers/one:two")]).unwrap();
+    assert!(overlay.mount_options().is_err());
+}
+
+#[test]
+fn make_removable() {
+    let sandbox = TempDir::new().unwrap();
+    let overlay = OverlaySandbox::new(sandbox.path(), vec![PathBuf::from("/layers/one")]).unwrap();
+    // Before the overlay has been mounted, there is nothing to do.
+    overlay.make_removable().unwrap();
+
+    // Once it has been, overlayfs leaves an inaccessible directory in its work directory.
+    let work = sandbox.path().join("work").join("work");
+    std::fs::create_dir(&work).unwrap();
+    std::fs::File::create(work.join("file")).unwrap();
+    std::fs::set_permissions(&work, std::fs::Permissions::from_mode(0o000)).unwrap();
+    overlay.make_removable().unwrap();
+    std::fs::remove_dir_all(sandbox.path()).unwrap();
+}
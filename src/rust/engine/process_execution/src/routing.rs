@@ -0,0 +1,385 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use cache::PersistentCache;
+use fs::{Entry, SymlinkBehavior};
+use hashing::Digest;
+use log::debug;
+use protos::gen::pants::cache::{CacheKey, CacheKeyType};
+use regex::Regex;
+use store::Store;
+use workunit_store::RunningWorkunit;
+
+use crate::{
+    CommandRunner as CommandRunnerTrait, Context, FallibleProcessResultWithPlatform, Platform,
+    Process, ProcessError, ProcessExecutionStrategy, ProcessResultSource,
+};
+
+///
+/// Where a process which has missed the caches is executed.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Route {
+    Local,
+    Remote,
+    /// The process is never executed: if it misses the caches, it fails.
+    CacheOnly,
+}
+
+impl FromStr for Route {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "local" => Ok(Route::Local),
+            "remote" => Ok(Route::Remote),
+            "cache_only" => Ok(Route::CacheOnly),
+            _ => Err(format!(
+                "Unknown route `{s}`: expected one of `local`, `remote` or `cache_only`."
+            )),
+        }
+    }
+}
+
+///
+/// A rule which routes the processes which match all of its conditions, specified as e.g.:
+///
+///   local: description=^Run (isort|black); max_input_size=1048576
+///
+/// The conditions (separated by `;`) are:
+///   * `description`: a regex which is searched for in the description of the process.
+///   * `tag`: a tag which must be declared by the process. May be repeated.
+///   * `min_input_size`/`max_input_size`: bounds (in bytes, inclusive) of the total size of the
+///     input files of the process.
+///   * `min_estimated_duration`/`max_estimated_duration`: bounds (in seconds, inclusive) of the
+///     duration which the process took when it last ran. A process which has not run before has no
+///     estimated duration, and so does not match either.
+///
+/// A rule with no conditions matches all processes.
+///
+#[derive(Clone, Debug)]
+pub struct RoutingRule {
+    spec: String,
+    pub route: Route,
+    description: Option<Regex>,
+    tags: BTreeSet<String>,
+    min_input_size: Option<usize>,
+    max_input_size: Option<usize>,
+    min_estimated_duration: Option<Duration>,
+    max_estimated_duration: Option<Duration>,
+}
+
+impl RoutingRule {
+    fn uses_input_size(&self) -> bool {
+        self.min_input_size.is_some() || self.max_input_size.is_some()
+    }
+
+    fn uses_estimated_duration(&self) -> bool {
+        self.min_estimated_duration.is_some() || self.max_estimated_duration.is_some()
+    }
+
+    fn matches(&self, process: &Process, facts: &ProcessFacts) -> bool {
+        fn within<T: PartialOrd>(value: Option<T>, min: &Option<T>, max: &Option<T>) -> bool {
+            if min.is_none() && max.is_none() {
+                return true;
+            }
+            match value {
+                Some(value) => {
+                    min.as_ref().map_or(true, |min| &value >= min)
+                        && max.as_ref().map_or(true, |max| &value <= max)
+                }
+                None => false,
+            }
+        }
+
+        self.description
+            .as_ref()
+            .map_or(true, |regex| regex.is_match(&process.description))
+            && self.tags.is_subset(&process.tags)
+            && within(facts.input_size, &self.min_input_size, &self.max_input_size)
+            && within(
+                facts.estimated_duration,
+                &self.min_estimated_duration,
+                &self.max_estimated_duration,
+            )
+    }
+}
+
+impl FromStr for RoutingRule {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = |e: String| format!("Invalid routing rule `{spec}`: {e}");
+        let (route, conditions) = spec
+            .split_once(':')
+            .ok_or_else(|| invalid("expected `<route>: <conditions>`.".to_owned()))?;
+        let mut rule = RoutingRule {
+            spec: spec.to_owned(),
+            route: route.trim().parse().map_err(invalid)?,
+            description: None,
+            tags: BTreeSet::new(),
+            min_input_size: None,
+            max_input_size: None,
+            min_estimated_duration: None,
+            max_estimated_duration: None,
+        };
+
+        let size = |value: &str| {
+            value
+                .parse::<usize>()
+                .map_err(|e| invalid(format!("invalid size `{value}`: {e}")))
+        };
+        let duration = |value: &str| {
+            value
+                .parse::<f64>()
+                .map_err(|e| e.to_string())
+                .and_then(|secs| Duration::try_from_secs_f64(secs).map_err(|e| e.to_string()))
+                .map_err(|e| invalid(format!("invalid duration `{value}`: {e}")))
+        };
+        for condition in conditions.split(';').map(str::trim) {
+            if condition.is_empty() {
+                continue;
+            }
+            let (key, value) = condition
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("expected `<key>=<value>`, got `{condition}`.")))?;
+            let value = value.trim();
+            match key.trim() {
+                "description" => {
+                    rule.description = Some(Regex::new(value).map_err(|e| invalid(e.to_string()))?)
+                }
+                "tag" => {
+                    rule.tags.insert(value.to_owned());
+                }
+                "min_input_size" => rule.min_input_size = Some(size(value)?),
+                "max_input_size" => rule.max_input_size = Some(size(value)?),
+                "min_estimated_duration" => rule.min_estimated_duration = Some(duration(value)?),
+                "max_estimated_duration" => rule.max_estimated_duration = Some(duration(value)?),
+                key => return Err(invalid(format!("unknown condition `{key}`."))),
+            }
+        }
+        Ok(rule)
+    }
+}
+
+impl fmt::Display for RoutingRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.spec)
+    }
+}
+
+///
+/// The facts about a process which are only computed if some rule uses them.
+///
+#[derive(Default)]
+struct ProcessFacts {
+    input_size: Option<usize>,
+    estimated_duration: Option<Duration>,
+}
+
+///
+/// A CommandRunner which routes each process to either local or remote execution according to the
+/// first of its `RoutingRule`s which matches the process. Processes which match no rule are run
+/// remotely if they have a remote execution strategy (and remote execution is enabled), and locally
+/// otherwise.
+///
+/// Only processes with the `Local` or `RemoteExecution` strategies are routed. A process is only
+/// routed locally if its platform is the current platform, and is only routed remotely if it has
+/// the `RemoteExecution` strategy: the platform properties which select a remote worker for it are
+/// those of that strategy, and a `Local` process has none (so it might otherwise be executed on a
+/// worker of another platform). The process is not modified when it is routed: its cache key
+/// remains that of its strategy.
+///
+/// It should be used as the "leaf" runner, beneath the caches: a `cache_only` process which reaches
+/// it has missed them.
+///
+pub struct CommandRunner {
+    local: Box<dyn CommandRunnerTrait>,
+    remote: Option<Box<dyn CommandRunnerTrait>>,
+    rules: Vec<RoutingRule>,
+    store: Store,
+    cache: PersistentCache,
+    uses_input_size: bool,
+    uses_estimated_duration: bool,
+}
+
+impl CommandRunner {
+    pub fn new(
+        local: Box<dyn CommandRunnerTrait>,
+        remote: Option<Box<dyn CommandRunnerTrait>>,
+        rules: Vec<RoutingRule>,
+        store: Store,
+        cache: PersistentCache,
+    ) -> Result<CommandRunner, String> {
+        if remote.is_none() {
+            if let Some(rule) = rules.iter().find(|rule| rule.route == Route::Remote) {
+                return Err(format!(
+                    "The routing rule `{rule}` routes processes remotely, but remote execution is \
+                     not enabled."
+                ));
+            }
+        }
+        Ok(CommandRunner {
+            uses_input_size: rules.iter().any(RoutingRule::uses_input_size),
+            uses_estimated_duration: rules.iter().any(RoutingRule::uses_estimated_duration),
+            local,
+            remote,
+            rules,
+            store,
+            cache,
+        })
+    }
+
+    fn duration_key(process: &Process) -> CacheKey {
+        // NB: As for `CacheKeyComponents`, processes are identified across runs by their
+        // descriptions.
+        let identity = format!(
+            "{}\n{}",
+            process.description,
+            String::from(process.execution_environment.platform)
+        );
+        CacheKey {
+            key_type: CacheKeyType::ProcessDuration.into(),
+            digest: Some(Digest::of_bytes(identity.as_bytes()).into()),
+            ..CacheKey::default()
+        }
+    }
+
+    async fn facts(&self, process: &Process) -> Result<ProcessFacts, ProcessError> {
+        let mut facts = ProcessFacts::default();
+        if self.uses_input_size {
+            let tree = self
+                .store
+                .load_digest_trie(process.input_digests.complete.clone())
+                .await?;
+            let mut input_size = 0;
+            tree.walk(SymlinkBehavior::Aware, &mut |_, entry| {
+                if let Entry::File(f) = entry {
+                    input_size += f.digest().size_bytes;
+                }
+            });
+            facts.input_size = Some(input_size);
+        }
+        if self.uses_estimated_duration {
+            facts.estimated_duration = match self.cache.load(&Self::duration_key(process)).await {
+                Ok(bytes) => bytes
+                    .and_then(|bytes| <[u8; 8]>::try_from(&bytes[..]).ok())
+                    .map(|millis| Duration::from_millis(u64::from_be_bytes(millis))),
+                Err(e) => {
+                    debug!(
+                        "Failed to load the duration of `{}`: {e}",
+                        process.description
+                    );
+                    None
+                }
+            };
+        }
+        Ok(facts)
+    }
+
+    ///
+    /// Returns the first rule which applies to the given process, if any.
+    ///
+    async fn rule_for(&self, process: &Process) -> Result<Option<&RoutingRule>, ProcessError> {
+        let can_run_remotely = match process.execution_environment.strategy {
+            ProcessExecutionStrategy::Local => false,
+            ProcessExecutionStrategy::RemoteExecution(_) => true,
+            _ => return Ok(None),
+        };
+        let facts = self.facts(process).await?;
+        let can_run_locally =
+            Platform::current().ok() == Some(process.execution_environment.platform);
+        Ok(self.rules.iter().find(|rule| {
+            let applicable = match rule.route {
+                Route::Local => can_run_locally,
+                Route::Remote => can_run_remotely,
+                Route::CacheOnly => true,
+            };
+            applicable && rule.matches(process, &facts)
+        }))
+    }
+
+    async fn record_duration(&self, process: &Process, duration: Duration) {
+        let millis = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+        let value = Bytes::copy_from_slice(&millis.to_be_bytes());
+        if let Err(e) = self.cache.store(&Self::duration_key(process), value).await {
+            debug!(
+                "Failed to store the duration of `{}`: {e}",
+                process.description
+            );
+        }
+    }
+}
+
+impl fmt::Debug for CommandRunner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("routing::CommandRunner")
+            .field("rules", &self.rules)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl CommandRunnerTrait for CommandRunner {
+    async fn run(
+        &self,
+        context: Context,
+        workunit: &mut RunningWorkunit,
+        req: Process,
+    ) -> Result<FallibleProcessResultWithPlatform, ProcessError> {
+        let route = match self.rule_for(&req).await? {
+            Some(rule) if rule.route == Route::CacheOnly => {
+                return Err(ProcessError::Unclassified(format!(
+                    "`{}` missed the caches, but the routing rule `{rule}` only allows it to be \
+                     fetched from them.",
+                    req.description
+                )));
+            }
+            Some(rule) => {
+                debug!("Routing `{}` by the rule `{rule}`.", req.description);
+                rule.route
+            }
+            None if matches!(
+                req.execution_environment.strategy,
+                ProcessExecutionStrategy::RemoteExecution(_)
+            ) =>
+            {
+                Route::Remote
+            }
+            None => Route::Local,
+        };
+        let runner = match (route, &self.remote) {
+            (Route::Remote, Some(remote)) => remote,
+            _ => &self.local,
+        };
+
+        if !self.uses_estimated_duration {
+            return runner.run(context, workunit, req).await;
+        }
+        let start = Instant::now();
+        let result = runner.run(context, workunit, req.clone()).await?;
+        if result.metadata.source == ProcessResultSource::Ran {
+            let duration = result
+                .metadata
+                .total_elapsed
+                .map_or_else(|| start.elapsed(), Duration::from);
+            self.record_duration(&req, duration).await;
+        }
+        Ok(result)
+    }
+
+    async fn shutdown(&self) -> Result<(), String> {
+        self.local.shutdown().await?;
+        if let Some(remote) = &self.remote {
+            remote.shutdown().await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use cache::PersistentCache;
+use fs::EMPTY_DIRECTORY_DIGEST;
+use hashing::EMPTY_DIGEST;
+use sharded_lmdb::{StoreBackend, DEFAULT_LEASE_TIME};
+use store::Store;
+use tempfile::TempDir;
+use testutil::data::TestDirectory;
+use workunit_store::{RunningWorkunit, WorkunitStore};
+
+use crate::routing::{CommandRunner, RoutingRule};
+use crate::{
+    CommandRunner as CommandRunnerTrait, Context, FallibleProcessResultWithPlatform, InputDigests,
+    Process, ProcessError, ProcessResultMetadata, ProcessResultSource,
+};
+
+const LOCAL: i32 = 1;
+const REMOTE: i32 = 2;
+
+/// Runs all processes successfully, with the given exit code to identify it.
+#[derive(Debug)]
+struct MockCommandRunner(i32);
+
+#[async_trait]
+impl CommandRunnerTrait for MockCommandRunner {
+    async fn run(
+        &self,
+        context: Context,
+        _workunit: &mut RunningWorkunit,
+        req: Process,
+    ) -> Result<FallibleProcessResultWithPlatform, ProcessError> {
+        Ok(FallibleProcessResultWithPlatform {
+            stdout_digest: *EMPTY_DIGEST,
+            stderr_digest: *EMPTY_DIGEST,
+            exit_code: self.0,
+            output_directory: EMPTY_DIRECTORY_DIGEST.clone(),
+            metadata: ProcessResultMetadata::new(
+                Some(Duration::from_secs(2).into()),
+                ProcessResultSource::Ran,
+                req.execution_environment,
+                context.run_id,
+            ),
+        })
+    }
+
+    async fn shutdown(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+struct Setup {
+    runner: CommandRunner,
+    store: Store,
+    _dir: TempDir,
+}
+
+fn setup(rules: &[&str]) -> Setup {
+    let executor = task_executor::Executor::new();
+    let dir = TempDir::new().unwrap();
+    let store = Store::local_only(executor.clone(), dir.path().join("store")).unwrap();
+    let cache = PersistentCache::new(
+        &dir.path().join("cache"),
+        50 * 1024 * 1024,
+        1,
+        executor,
+        DEFAULT_LEASE_TIME,
+        1,
+        StoreBackend::Lmdb,
+    )
+    .unwrap();
+    let rules = rules
+        .iter()
+        .map(|rule| rule.parse::<RoutingRule>().unwrap())
+        .collect();
+    let runner = CommandRunner::new(
+        Box::new(MockCommandRunner(LOCAL)),
+        Some(Box::new(MockCommandRunner(REMOTE))),
+        rules,
+        store.clone(),
+        cache,
+    )
+    .unwrap();
+    Setup {
+        runner,
+        store,
+        _dir: dir,
+    }
+}
+
+fn process(description: &str) -> Process {
+    let mut process = Process::new(vec!["/bin/true".to_owned()]);
+    process.description = description.to_owned();
+    process
+}
+
+async fn run(runner: &CommandRunner, process: Process) -> Result<i32, ProcessError> {
+    let (_, mut workunit) = WorkunitStore::setup_for_tests();
+    runner
+        .run(Context::default(), &mut workunit, process)
+        .await
+        .map(|result| result.exit_code)
+}
+
+#[test]
+fn parse_rules() {
+    let rule: RoutingRule = "local: description=^Run (isort|black); tag=fast; tag=small; \
+                             max_input_size=1024; min_estimated_duration=0.5"
+        .parse()
+        .unwrap();
+    assert_eq!(rule.route, crate::routing::Route::Local);
+
+    let rule: RoutingRule = " cache_only : ".parse().unwrap();
+    assert_eq!(rule.route, crate::routing::Route::CacheOnly);
+
+    for (spec, error) in [
+        ("local", "expected `<route>: <conditions>`"),
+        ("elsewhere: tag=fast", "Unknown route `elsewhere`"),
+        ("remote: tag", "expected `<key>=<value>`"),
+        ("remote: color=blue", "unknown condition `color`"),
+        ("remote: description=(", "regex parse error"),
+        ("remote: max_input_size=big", "invalid size `big`"),
+        ("remote: max_estimated_duration=-1", "invalid duration `-1`"),
+    ] {
+        let err = spec.parse::<RoutingRule>().unwrap_err();
+        assert!(err.contains(error), "{err}");
+    }
+}
+
+#[tokio::test]
+async fn remote_rules_require_remote_execution() {
+    let dir = TempDir::new().unwrap();
+    let executor = task_executor::Executor::new();
+    let store = Store::local_only(executor.clone(), dir.path().join("store")).unwrap();
+    let cache = PersistentCache::new(
+        &dir.path().join("cache"),
+        50 * 1024 * 1024,
+        1,
+        executor,
+        DEFAULT_LEASE_TIME,
+        1,
+        StoreBackend::Lmdb,
+    )
+    .unwrap();
+    let err = CommandRunner::new(
+        Box::new(MockCommandRunner(LOCAL)),
+        None,
+        vec!["remote: tag=heavy".parse().unwrap()],
+        store,
+        cache,
+    )
+    .unwrap_err();
+    assert!(err.contains("remote execution is not enabled"), "{err}");
+}
+
+#[tokio::test]
+async fn routes_by_strategy_by_default() {
+    let setup = setup(&["local: description=^never matches$"]);
+    assert_eq!(run(&setup.runner, process("local")).await.unwrap(), LOCAL);
+    assert_eq!(
+        run(&setup.runner, process("remote").remote_execution(vec![]))
+            .await
+            .unwrap(),
+        REMOTE
+    );
+    assert_eq!(
+        run(&setup.runner, process("docker").docker("image".to_owned()))
+            .await
+            .unwrap(),
+        LOCAL
+    );
+}
+
+#[tokio::test]
+async fn routes_by_description_and_tags() {
+    let setup = setup(&[
+        "local: description=^Format ",
+        "remote: tag=heavy; tag=slow",
+        "local: ",
+    ]);
+    let remote = |description: &str| process(description).remote_execution(vec![]);
+    assert_eq!(
+        run(&setup.runner, remote("Format foo.py")).await.unwrap(),
+        LOCAL
+    );
+
+    let tags = |tags: &[&str]| {
+        tags.iter()
+            .map(|tag| tag.to_string())
+            .collect::<BTreeSet<_>>()
+    };
+    assert_eq!(
+        run(&setup.runner, remote("Compile").tags(tags(&["heavy"])))
+            .await
+            .unwrap(),
+        LOCAL
+    );
+    assert_eq!(
+        run(
+            &setup.runner,
+            remote("Compile").tags(tags(&["heavy", "slow"]))
+        )
+        .await
+        .unwrap(),
+        REMOTE
+    );
+}
+
+#[tokio::test]
+async fn only_routes_locally_on_the_same_platform() {
+    let setup = setup(&["local: "]);
+    let mut process = process("remote").remote_execution(vec![]);
+    process.execution_environment.platform =
+        if process.execution_environment.platform == crate::Platform::Linux_x86_64 {
+            crate::Platform::Macos_arm64
+        } else {
+            crate::Platform::Linux_x86_64
+        };
+    assert_eq!(run(&setup.runner, process).await.unwrap(), REMOTE);
+}
+
+#[tokio::test]
+async fn never_routes_local_processes_remotely() {
+    let setup = setup(&["remote: "]);
+    // A local process has no platform properties to select a remote worker with, and so might be
+    // executed on a worker of another platform.
+    let mut process = process("local");
+    process.execution_environment.platform = crate::Platform::Macos_arm64;
+    assert_eq!(run(&setup.runner, process).await.unwrap(), LOCAL);
+}
+
+#[tokio::test]
+async fn routes_by_input_size() {
+    let setup = setup(&["remote: min_input_size=10", "local: "]);
+    let directory = TestDirectory::containing_roland();
+    setup
+        .store
+        .record_directory(&directory.directory(), true)
+        .await
+        .unwrap();
+    let mut process = process("with inputs").remote_execution(vec![]);
+    assert_eq!(run(&setup.runner, process.clone()).await.unwrap(), LOCAL);
+
+    process.input_digests = InputDigests::with_input_files(directory.directory_digest());
+    assert_eq!(run(&setup.runner, process).await.unwrap(), REMOTE);
+}
+
+#[tokio::test]
+async fn routes_by_estimated_duration() {
+    let setup = setup(&["remote: min_estimated_duration=1", "local: "]);
+    let remote = |description: &str| process(description).remote_execution(vec![]);
+    // The process has not run before, and so has no estimated duration. It then takes 2 seconds.
+    assert_eq!(run(&setup.runner, remote("slow")).await.unwrap(), LOCAL);
+    assert_eq!(run(&setup.runner, remote("slow")).await.unwrap(), REMOTE);
+    assert_eq!(run(&setup.runner, remote("other")).await.unwrap(), LOCAL);
+}
+
+#[tokio::test]
+async fn cache_only() {
+    let setup = setup(&["cache_only: tag=expensive"]);
+    let process = process("Build it").tags(BTreeSet::from(["expensive".to_owned()]));
+    let err = run(&setup.runner, process).await.unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("`Build it` missed the caches, but the routing rule"),
+        "{err}"
+    );
+}
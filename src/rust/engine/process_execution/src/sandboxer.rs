@@ -0,0 +1,349 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+//!
+//! A helper process which materializes sandboxes and spawns processes on behalf of the engine.
+//!
+//! Forking a process copies (the page tables of) its address space, which for a `pantsd` with a
+//! multi-gigabyte heap makes spawning processes slow and memory hungry. The `sandboxer` binary is
+//! small, and is started once by the engine: the engine then sends it the specifications of
+//! sandboxes and processes over a unix domain socket, so that its own address space is never
+//! forked.
+//!
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use fs::{DirectoryDigest, Permissions, RelativePath};
+use futures::stream::{BoxStream, StreamExt};
+use log::debug;
+use nails::execution::ExitCode;
+use parking_lot::Mutex;
+use protos::gen::pants::sandboxer::{
+    sandboxer_client::SandboxerClient,
+    sandboxer_server::{self, SandboxerServer},
+    spawn_response, MaterializeDirectoryRequest, MaterializeDirectoryResponse, SpawnRequest,
+    SpawnResponse,
+};
+use protos::require_digest;
+use store::{LocalOptions, Store, StoreError};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::process::Command;
+use tokio::sync::RwLock;
+use tokio_stream::wrappers::UnixListenerStream;
+use tonic::transport::{Channel, Endpoint, Server, Uri};
+use tonic::{Request, Response, Status};
+use workunit_store::{scope_task_workunit_store_handle, Level, WorkunitStore, WorkunitStoreHandle};
+
+use crate::fork_exec::spawn_process;
+use crate::local::{child_output_stream, make_command, ChildOutput};
+use crate::ManagedChild;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const CONNECT_RETRY_DELAY: Duration = Duration::from_millis(20);
+const PARENT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+///
+/// A client of a `sandboxer` process, which is started by `Sandboxer::new`, and killed when the
+/// last clone of the `Sandboxer` is dropped.
+///
+#[derive(Clone)]
+pub struct Sandboxer {
+    client: SandboxerClient<Channel>,
+    _process: Arc<Mutex<ManagedChild>>,
+}
+
+impl Sandboxer {
+    ///
+    /// Starts the given `sandboxer` binary, which shares the given local store, and connects to
+    /// it via a socket in `socket_dir`.
+    ///
+    pub async fn new(
+        sandboxer_bin: &Path,
+        socket_dir: &Path,
+        store_dir: &Path,
+        immutable_inputs_base: &Path,
+        store_options: &LocalOptions,
+    ) -> Result<Sandboxer, String> {
+        let socket_path = socket_dir.join(format!("sandboxer-{}.sock", std::process::id()));
+        // A socket may remain from a previous process with the same pid.
+        let _ = std::fs::remove_file(&socket_path);
+
+        let mut command = Command::new(sandboxer_bin);
+        command
+            .arg("--socket-path")
+            .arg(&socket_path)
+            .arg("--store-dir")
+            .arg(store_dir)
+            .arg("--immutable-inputs-base")
+            .arg(immutable_inputs_base)
+            .arg(format!(
+                "--files-max-size-bytes={}",
+                store_options.files_max_size_bytes
+            ))
+            .arg(format!(
+                "--directories-max-size-bytes={}",
+                store_options.directories_max_size_bytes
+            ))
+            .arg(format!(
+                "--max-size-growth-factor={}",
+                store_options.max_size_growth_factor
+            ))
+            .arg(format!(
+                "--lease-time-secs={}",
+                store_options.lease_time.as_secs()
+            ))
+            .arg(format!("--shard-count={}", store_options.shard_count))
+            .arg(format!("--backend={}", store_options.backend))
+            .stdin(Stdio::null());
+        let process = ManagedChild::spawn(&mut command, None).map_err(|e| {
+            format!(
+                "Failed to start the sandboxer at {}: {e}",
+                sandboxer_bin.display()
+            )
+        })?;
+        debug!("Started the sandboxer as {:?}", process.id());
+        let process = Arc::new(Mutex::new(process));
+
+        let client = Self::connect(&socket_path, &process).await?;
+        Ok(Sandboxer {
+            client,
+            _process: process,
+        })
+    }
+
+    async fn connect(
+        socket_path: &Path,
+        process: &Mutex<ManagedChild>,
+    ) -> Result<SandboxerClient<Channel>, String> {
+        let start = Instant::now();
+        loop {
+            let socket_path = socket_path.to_owned();
+            // NB: The URI is ignored by the connector, but must be valid.
+            let channel = Endpoint::from_static("http://sandboxer")
+                .connect_with_connector(tower::service_fn(move |_: Uri| {
+                    UnixStream::connect(socket_path.clone())
+                }))
+                .await;
+            match channel {
+                Ok(channel) => return Ok(SandboxerClient::new(channel)),
+                Err(e) => {
+                    if let Ok(Some(status)) = process.lock().try_wait() {
+                        return Err(format!(
+                            "The sandboxer exited during startup with {status}."
+                        ));
+                    }
+                    if start.elapsed() > CONNECT_TIMEOUT {
+                        return Err(format!("Failed to connect to the sandboxer: {e}"));
+                    }
+                    tokio::time::sleep(CONNECT_RETRY_DELAY).await;
+                }
+            }
+        }
+    }
+
+    ///
+    /// Materializes the given digest at the given destination, as `Store::materialize_directory`
+    /// would (writably, without forcing mutability).
+    ///
+    pub async fn materialize_directory(
+        &self,
+        store: &Store,
+        destination: &Path,
+        destination_root: &Path,
+        digest: DirectoryDigest,
+        mutable_paths: &BTreeSet<RelativePath>,
+    ) -> Result<(), StoreError> {
+        // The sandboxer reads from the local store, so the directory must be persisted there, along
+        // with all of its contents.
+        store
+            .ensure_directory_digest_persisted(digest.clone())
+            .await?;
+        store
+            .ensure_downloaded(HashSet::new(), HashSet::from([digest.clone()]))
+            .await?;
+
+        let request = MaterializeDirectoryRequest {
+            destination: path_to_string(destination)?,
+            destination_root: path_to_string(destination_root)?,
+            digest: Some(digest.as_digest().into()),
+            mutable_paths: mutable_paths
+                .iter()
+                .map(path_to_string)
+                .collect::<Result<_, _>>()?,
+        };
+        self.client
+            .clone()
+            .materialize_directory(request)
+            .await
+            .map_err(|status| {
+                format!(
+                    "Failed to materialize {} via the sandboxer: {}",
+                    destination.display(),
+                    status.message()
+                )
+            })?;
+        Ok(())
+    }
+
+    ///
+    /// Spawns the given process, and streams its outputs as `local::CommandRunner` would. Dropping
+    /// the stream kills the process.
+    ///
+    pub async fn spawn(
+        &self,
+        argv: &[String],
+        env: &BTreeMap<String, String>,
+        working_directory: &Path,
+        exclusive_spawn: bool,
+    ) -> Result<BoxStream<'static, Result<ChildOutput, String>>, String> {
+        let request = SpawnRequest {
+            argv: argv.to_vec(),
+            env: env.clone().into_iter().collect(),
+            working_directory: path_to_string(working_directory)?,
+            exclusive_spawn,
+        };
+        let responses = self
+            .client
+            .clone()
+            .spawn(request)
+            .await
+            .map_err(|status| {
+                format!(
+                    "Error launching process via the sandboxer: {}",
+                    status.message()
+                )
+            })?
+            .into_inner();
+
+        Ok(responses
+            .map(|response| {
+                let response = response.map_err(|status| {
+                    format!("Failed to consume process outputs: {}", status.message())
+                })?;
+                match response.output {
+                    Some(spawn_response::Output::Stdout(bytes)) => Ok(ChildOutput::Stdout(bytes)),
+                    Some(spawn_response::Output::Stderr(bytes)) => Ok(ChildOutput::Stderr(bytes)),
+                    Some(spawn_response::Output::ExitCode(code)) => {
+                        Ok(ChildOutput::Exit(ExitCode(code)))
+                    }
+                    None => Err("The sandboxer sent an empty process output.".to_owned()),
+                }
+            })
+            .boxed())
+    }
+}
+
+fn path_to_string(path: impl AsRef<Path>) -> Result<String, String> {
+    let path = path.as_ref();
+    path.to_str()
+        .map(str::to_owned)
+        .ok_or_else(|| format!("Non-UTF8 path: {path:?}"))
+}
+
+///
+/// Serves the sandboxer on the given socket until the process which started it exits.
+///
+pub async fn serve(store: Store, socket_path: &Path) -> Result<(), String> {
+    let listener = UnixListener::bind(socket_path)
+        .map_err(|e| format!("Failed to bind {}: {e}", socket_path.display()))?;
+
+    // If the engine exits without killing us (e.g. because it was killed), we are reparented.
+    let parent = nix::unistd::getppid();
+    let parent_exited = async move {
+        while nix::unistd::getppid() == parent {
+            tokio::time::sleep(PARENT_POLL_INTERVAL).await;
+        }
+        debug!("The parent of the sandboxer exited: shutting down.");
+    };
+
+    let service = SandboxerService {
+        store,
+        spawn_lock: Arc::new(RwLock::new(())),
+    };
+    let result = Server::builder()
+        .add_service(SandboxerServer::new(service))
+        .serve_with_incoming_shutdown(UnixListenerStream::new(listener), parent_exited)
+        .await
+        .map_err(|e| format!("The sandboxer failed: {e}"));
+    let _ = std::fs::remove_file(socket_path);
+    result
+}
+
+struct SandboxerService {
+    store: Store,
+    spawn_lock: Arc<RwLock<()>>,
+}
+
+#[tonic::async_trait]
+impl sandboxer_server::Sandboxer for SandboxerService {
+    async fn materialize_directory(
+        &self,
+        request: Request<MaterializeDirectoryRequest>,
+    ) -> Result<Response<MaterializeDirectoryResponse>, Status> {
+        let request = request.into_inner();
+        let digest = require_digest(request.digest.as_ref()).map_err(Status::invalid_argument)?;
+        let mutable_paths = request
+            .mutable_paths
+            .iter()
+            .map(RelativePath::new)
+            .collect::<Result<BTreeSet<_>, _>>()
+            .map_err(Status::invalid_argument)?;
+        // Workunits are not reported to the engine: a store per request avoids accumulating them.
+        let workunit_store = WorkunitStore::new(false, Level::Error);
+        let materialize = self.store.materialize_directory(
+            PathBuf::from(request.destination),
+            Path::new(&request.destination_root),
+            DirectoryDigest::from_persisted_digest(digest),
+            false,
+            &mutable_paths,
+            Permissions::Writable,
+        );
+        scope_task_workunit_store_handle(
+            Some(WorkunitStoreHandle {
+                store: workunit_store,
+                parent_id: None,
+            }),
+            materialize,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(MaterializeDirectoryResponse {}))
+    }
+
+    type SpawnStream = BoxStream<'static, Result<SpawnResponse, Status>>;
+
+    async fn spawn(
+        &self,
+        request: Request<SpawnRequest>,
+    ) -> Result<Response<Self::SpawnStream>, Status> {
+        let request = request.into_inner();
+        if request.argv.is_empty() {
+            return Err(Status::invalid_argument("A process must have an argv."));
+        }
+        let env = request.env.into_iter().collect::<BTreeMap<_, _>>();
+        let mut command = make_command(&request.argv, &env, Path::new(&request.working_directory));
+        let child = spawn_process(
+            self.spawn_lock.clone(),
+            request.exclusive_spawn,
+            move || ManagedChild::spawn(&mut command, None),
+        )
+        .await
+        .map_err(Status::internal)?;
+        debug!("spawned process as {:?} for {:?}", child.id(), request.argv);
+
+        let responses = child_output_stream(child).map(|output| {
+            let output = match output.map_err(Status::internal)? {
+                ChildOutput::Stdout(bytes) => spawn_response::Output::Stdout(bytes),
+                ChildOutput::Stderr(bytes) => spawn_response::Output::Stderr(bytes),
+                ChildOutput::Exit(code) => spawn_response::Output::ExitCode(code.0),
+            };
+            Ok(SpawnResponse {
+                output: Some(output),
+            })
+        });
+        Ok(Response::new(responses.boxed()))
+    }
+}
@@ -95,7 +95,8 @@ impl super::CommandRunner for CommandRunner {
                     req.input_digests.inputs.clone(),
                     &self.store,
                     &self.named_caches,
-                    &self.immutable_inputs,
+                    Some(&self.immutable_inputs),
+                    None,
                     None,
                     None,
                 )
@@ -0,0 +1,86 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+//!
+//! NB: These are integration tests (rather than `sandboxer_tests.rs` unit tests) because
+//! `CARGO_BIN_EXE_sandboxer` is only set when building integration tests.
+//!
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use process_execution::local::{self, KeepSandboxes};
+use process_execution::sandboxer::Sandboxer;
+use process_execution::{CommandRunner, Context, InputDigests, NamedCaches, Process};
+use store::{ImmutableInputs, LocalOptions, Store};
+use tempfile::TempDir;
+use testutil::data::{TestData, TestDirectory};
+use testutil::owned_string_vec;
+use tokio::sync::RwLock;
+use workunit_store::WorkunitStore;
+
+#[tokio::test]
+#[cfg(unix)]
+async fn round_trip() {
+    let (_, mut workunit) = WorkunitStore::setup_for_tests();
+    let executor = task_executor::Executor::new();
+    let store_dir = TempDir::new().unwrap();
+    let root = TempDir::new().unwrap();
+    let store = Store::local_only_with_options(
+        executor.clone(),
+        store_dir.path(),
+        root.path(),
+        LocalOptions::default(),
+    )
+    .unwrap();
+
+    // The sandboxer must materialize the input from the store which it shares with the engine.
+    store
+        .store_file_bytes(TestData::roland().bytes(), false)
+        .await
+        .unwrap();
+    store
+        .record_directory(&TestDirectory::containing_roland().directory(), true)
+        .await
+        .unwrap();
+
+    let sandboxer = Sandboxer::new(
+        Path::new(env!("CARGO_BIN_EXE_sandboxer")),
+        root.path(),
+        store_dir.path(),
+        root.path(),
+        &LocalOptions::default(),
+    )
+    .await
+    .unwrap();
+    let runner = local::CommandRunner::new(
+        store.clone(),
+        executor,
+        root.path().to_owned(),
+        NamedCaches::new_local(root.path().join("named")),
+        ImmutableInputs::new(store.clone(), root.path()).unwrap(),
+        KeepSandboxes::Never,
+        false,
+        false,
+        Arc::new(RwLock::new(())),
+        Some(sandboxer),
+    );
+
+    let mut process = Process::new(owned_string_vec(&["/bin/cat", "roland.ext"]));
+    process.input_digests =
+        InputDigests::with_input_files(TestDirectory::containing_roland().directory_digest());
+    process.timeout = Some(Duration::from_secs(10));
+    process.description = "cat via the sandboxer".to_owned();
+
+    let result = runner
+        .run(Context::default(), &mut workunit, process)
+        .await
+        .unwrap();
+
+    assert_eq!(result.exit_code, 0);
+    let stdout = store
+        .load_file_bytes_with(result.stdout_digest, |bytes| bytes.to_vec())
+        .await
+        .unwrap();
+    assert_eq!(stdout, TestData::roland().bytes());
+}
@@ -3,9 +3,15 @@
 
 #![type_length_limit = "1257309"]
 
+mod spec;
+#[cfg(test)]
+mod spec_tests;
+#[cfg(test)]
+mod tests;
+
 use std::collections::{BTreeMap, BTreeSet};
 use std::iter::{FromIterator, Iterator};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 use std::sync::Arc;
 use std::time::Duration;
@@ -14,8 +20,9 @@ use clap::StructOpt;
 use fs::{DirectoryDigest, Permissions, RelativePath};
 use hashing::{Digest, Fingerprint};
 use process_execution::{
-    local::KeepSandboxes, CacheContentBehavior, Context, InputDigests, NamedCaches, Platform,
-    ProcessCacheScope, ProcessExecutionEnvironment, ProcessExecutionStrategy,
+    local::KeepSandboxes, CacheContentBehavior, Context, FallibleProcessResultWithPlatform,
+    InputDigests, LocalCacheSettings, NamedCaches, Platform, ProcessCacheScope,
+    ProcessExecutionEnvironment, ProcessExecutionStrategy,
 };
 use prost::Message;
 use protos::gen::build::bazel::remote::execution::v2::{Action, Command};
@@ -26,6 +33,8 @@ use store::{ImmutableInputs, RemoteProvider, RemoteStoreOptions, Store};
 use tokio::sync::RwLock;
 use workunit_store::{in_workunit, Level, WorkunitStore};
 
+use crate::spec::ProcessSpec;
+
 #[derive(Clone, Debug, Default)]
 struct ProcessMetadata {
     instance_name: Option<String>,
@@ -102,6 +111,11 @@ struct Opt {
     #[structopt(long)]
     buildbarn_url: Option<String>,
 
+    /// Path to a JSON (or, with a `.toml` extension, TOML) file containing a complete specification
+    /// of the process to run, including its input digests, caches and execution environment.
+    #[structopt(long)]
+    process_spec: Option<PathBuf>,
+
     #[structopt(long)]
     run_under: Option<String>,
 
@@ -109,6 +123,29 @@ struct Opt {
     #[structopt(long)]
     materialize_output_to: Option<PathBuf>,
 
+    /// The name of a directory (which may or may not exist), where the input tree will be
+    /// materialized before the process runs: e.g. to inspect the inputs of an action.
+    #[structopt(long)]
+    materialize_input_to: Option<PathBuf>,
+
+    /// Fetch the process (and materialize its inputs, if requested) without running it.
+    #[structopt(long)]
+    skip_execution: bool,
+
+    /// Whether to keep the sandbox of a local execution: `always`, `never`, or `on_failure`.
+    #[structopt(long, default_value = "never")]
+    keep_sandboxes: KeepSandboxes,
+
+    /// Create the sandbox of a local execution at a stable path in the workdir, rather than at a
+    /// random path.
+    #[structopt(long)]
+    deterministic_sandbox_paths: bool,
+
+    /// Run local processes with immutable inputs in overlayfs sandboxes (on Linux), whose lower
+    /// layers are their immutable inputs.
+    #[structopt(long)]
+    overlay_sandboxes: bool,
+
     /// Path to workdir.
     #[structopt(long)]
     work_dir: Option<PathBuf>,
@@ -204,6 +241,15 @@ struct Opt {
 /// It outputs its output/err to stdout/err, and exits with its exit code.
 ///
 /// It does not perform $PATH lookup or shell expansion.
+///
+/// Alternatively, a complete specification of the process may be read from a file:
+///  process_executor --process-spec=process.json
+///
+/// It can also replay an action which was executed remotely, by fetching its Action, Command and
+/// input root from the CAS (or the local store) and running it locally, in an environment which
+/// contains only the variables of its Command:
+///  process_executor --cas-server=localhost:8980 --action-digest=abc123 --action-digest-length=142
+///    --materialize-input-to=/tmp/inputs --keep-sandboxes=always
 #[tokio::main]
 async fn main() {
     env_logger::init();
@@ -212,8 +258,6 @@ async fn main() {
 
     let args = Opt::from_args();
 
-    let mut headers: BTreeMap<String, String> = collection_from_keyvalues(args.header.iter());
-
     let executor = task_executor::Executor::new();
 
     let local_store_path = args
@@ -282,6 +326,37 @@ async fn main() {
   }
   .expect("Error making remote store");
 
+    let result = match run(args, store.clone(), executor).await {
+        Some(result) => result,
+        None => exit(0),
+    };
+
+    let stdout: Vec<u8> = store
+        .load_file_bytes_with(result.stdout_digest, |bytes| bytes.to_vec())
+        .await
+        .unwrap();
+
+    let stderr: Vec<u8> = store
+        .load_file_bytes_with(result.stderr_digest, |bytes| bytes.to_vec())
+        .await
+        .unwrap();
+
+    print!("{}", String::from_utf8(stdout).unwrap());
+    eprint!("{}", String::from_utf8(stderr).unwrap());
+    exit(result.exit_code);
+}
+
+///
+/// Runs the process which is specified by the given args, and materializes its outputs if
+/// requested. Returns `None` without running it if `--skip-execution` is set.
+///
+async fn run(
+    args: Opt,
+    store: Store,
+    executor: task_executor::Executor,
+) -> Option<FallibleProcessResultWithPlatform> {
+    let mut headers: BTreeMap<String, String> = collection_from_keyvalues(args.header.iter());
+
     let (mut request, process_metadata) = make_request(&store, &args)
         .await
         .expect("Failed to construct request");
@@ -293,6 +368,24 @@ async fn main() {
             .chain(request.argv.into_iter())
             .collect();
     }
+
+    if let Some(input) = args.materialize_input_to {
+        let input_root = input.clone();
+        store
+            .materialize_directory(
+                input,
+                &input_root,
+                request.input_digests.complete.clone(),
+                false,
+                &BTreeSet::new(),
+                Permissions::Writable,
+            )
+            .await
+            .expect("Error materializing the input tree");
+    }
+    if args.skip_execution {
+        return None;
+    }
     let workdir = args.work_dir.unwrap_or_else(std::env::temp_dir);
 
     let runner: Box<dyn process_execution::CommandRunner> = match args.server {
@@ -393,8 +486,11 @@ async fn main() {
                     .unwrap_or_else(NamedCaches::default_local_path),
             ),
             ImmutableInputs::new(store.clone(), &workdir).unwrap(),
-            KeepSandboxes::Never,
+            args.keep_sandboxes,
+            args.deterministic_sandbox_paths,
+            args.overlay_sandboxes,
             Arc::new(RwLock::new(())),
+            None,
         )) as Box<dyn process_execution::CommandRunner>,
     };
 
@@ -421,19 +517,7 @@ async fn main() {
             .unwrap();
     }
 
-    let stdout: Vec<u8> = store
-        .load_file_bytes_with(result.stdout_digest, |bytes| bytes.to_vec())
-        .await
-        .unwrap();
-
-    let stderr: Vec<u8> = store
-        .load_file_bytes_with(result.stderr_digest, |bytes| bytes.to_vec())
-        .await
-        .unwrap();
-
-    print!("{}", String::from_utf8(stdout).unwrap());
-    eprint!("{}", String::from_utf8(stderr).unwrap());
-    exit(result.exit_code);
+    Some(result)
 }
 
 async fn make_request(
@@ -464,11 +548,12 @@ async fn make_request(
     args.action_digest.action_digest,
     args.action_digest.action_digest_length,
     args.buildbarn_url.as_ref(),
+    args.process_spec.as_ref(),
   ) {
-    (Some(input_digest), Some(input_digest_length), None, None, None) => {
+    (Some(input_digest), Some(input_digest_length), None, None, None, None) => {
       make_request_from_flat_args(store, args, Digest::new(input_digest, input_digest_length), execution_environment).await
     }
-    (None, None, Some(action_fingerprint), Some(action_digest_length), None) => {
+    (None, None, Some(action_fingerprint), Some(action_digest_length), None, None) => {
       extract_request_from_action_digest(
         store,
         Digest::new(action_fingerprint, action_digest_length),
@@ -477,7 +562,7 @@ async fn make_request(
         args.command.cache_key_gen_version.clone(),
       ).await
     }
-    (None, None, None, None, Some(buildbarn_url)) => {
+    (None, None, None, None, Some(buildbarn_url), None) => {
       extract_request_from_buildbarn_url(
         store,
         buildbarn_url,
@@ -485,8 +570,11 @@ async fn make_request(
         args.command.cache_key_gen_version.clone()
       ).await
     }
-    (None, None, None, None, None) => {
-      Err("Must specify either action input digest or action digest or buildbarn URL".to_owned())
+    (None, None, None, None, None, Some(process_spec)) => {
+      make_request_from_spec(store, args, process_spec, execution_environment).await
+    }
+    (None, None, None, None, None, None) => {
+      Err("Must specify either action input digest or action digest or buildbarn URL or process spec".to_owned())
     }
     _ => {
       Err("Unsupported combination of arguments - can only set one of action digest or all other action-specifying flags".to_owned())
@@ -494,6 +582,24 @@ async fn make_request(
   }
 }
 
+async fn make_request_from_spec(
+    store: &Store,
+    args: &Opt,
+    path: &Path,
+    execution_environment: ProcessExecutionEnvironment,
+) -> Result<(process_execution::Process, ProcessMetadata), String> {
+    let spec = ProcessSpec::read(path)?;
+    let metadata = ProcessMetadata {
+        instance_name: args.remote_instance_name.clone(),
+        cache_key_gen_version: spec
+            .cache_key_gen_version
+            .clone()
+            .or_else(|| args.command.cache_key_gen_version.clone()),
+    };
+    let process = spec.into_process(store, execution_environment).await?;
+    Ok((process, metadata))
+}
+
 async fn make_request_from_flat_args(
     store: &Store,
     args: &Opt,
@@ -536,12 +642,14 @@ async fn make_request_from_flat_args(
     let process = process_execution::Process {
         argv: args.command.argv.clone(),
         env: collection_from_keyvalues(args.command.env.iter()),
+        execution_only_env_vars: BTreeSet::new(),
         working_directory,
         input_digests,
         output_files,
         output_directories,
         timeout: Some(Duration::new(15 * 60, 0)),
         description: "process_executor".to_string(),
+        tags: BTreeSet::new(),
         level: Level::Info,
         append_only_caches: BTreeMap::new(),
         jdk_home: args.command.jdk.clone(),
@@ -550,6 +658,8 @@ async fn make_request_from_flat_args(
         cache_scope: ProcessCacheScope::Always,
         execution_environment,
         remote_cache_speculation_delay: Duration::from_millis(0),
+        local_cache: LocalCacheSettings::default(),
+        remote_persistent_worker: false,
         attempt: 0,
     };
     let metadata = ProcessMetadata {
@@ -563,7 +673,7 @@ async fn make_request_from_flat_args(
 async fn extract_request_from_action_digest(
     store: &Store,
     action_digest: Digest,
-    execution_environment: ProcessExecutionEnvironment,
+    mut execution_environment: ProcessExecutionEnvironment,
     instance_name: Option<String>,
     cache_key_gen_version: Option<String>,
 ) -> Result<(process_execution::Process, ProcessMetadata), String> {
@@ -592,6 +702,23 @@ async fn extract_request_from_action_digest(
         )
     };
 
+    // Unless platform properties were given explicitly, execute remotely with the platform
+    // properties of the original Command.
+    if let ProcessExecutionStrategy::RemoteExecution(properties) =
+        &mut execution_environment.strategy
+    {
+        if properties.is_empty() {
+            if let Some(platform) = &command.platform {
+                properties.extend(
+                    platform
+                        .properties
+                        .iter()
+                        .map(|property| (property.name.clone(), property.value.clone())),
+                );
+            }
+        }
+    }
+
     let input_digests = InputDigests::with_input_files(DirectoryDigest::from_persisted_digest(
         require_digest(&action.input_root_digest)
             .map_err(|err| format!("Bad input root digest: {err:?}"))?,
@@ -616,6 +743,7 @@ async fn extract_request_from_action_digest(
             })
             .map(|env| (env.name.clone(), env.value.clone()))
             .collect(),
+        execution_only_env_vars: BTreeSet::new(),
         working_directory,
         input_digests,
         output_files: command
@@ -623,9 +751,12 @@ async fn extract_request_from_action_digest(
             .iter()
             .map(RelativePath::new)
             .collect::<Result<_, _>>()?,
+        // The kind of each of the unified `output_paths` is only known once the process has run,
+        // but capturing a path as a directory captures a file at that path too.
         output_directories: command
             .output_directories
             .iter()
+            .chain(&command.output_paths)
             .map(RelativePath::new)
             .collect::<Result<_, _>>()?,
         timeout: action.timeout.map(|timeout| {
@@ -634,12 +765,15 @@ async fn extract_request_from_action_digest(
         execution_slot_variable: None,
         concurrency_available: 0,
         description: "".to_string(),
+        tags: BTreeSet::new(),
         level: Level::Error,
         append_only_caches: BTreeMap::new(),
         jdk_home: None,
         cache_scope: ProcessCacheScope::Always,
         execution_environment,
         remote_cache_speculation_delay: Duration::from_millis(0),
+        local_cache: LocalCacheSettings::default(),
+        remote_persistent_worker: false,
         attempt: 0,
     };
 
@@ -0,0 +1,214 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use fs::{DirectoryDigest, RelativePath, EMPTY_DIRECTORY_DIGEST};
+use hashing::Digest;
+use process_execution::{
+    CacheName, InputDigests, LocalCacheSettings, Platform, Process, ProcessCacheScope,
+    ProcessExecutionEnvironment, ProcessExecutionStrategy,
+};
+use serde::Deserialize;
+use store::Store;
+use workunit_store::Level;
+
+///
+/// A complete specification of a `Process`, as read from a JSON or TOML file: e.g. to reproduce a
+/// process which was run by the engine, with all of its configuration.
+///
+/// Digests are specified as `{"fingerprint": "<hex>", "size_bytes": <int>}`, and must be present in
+/// the local store or the CAS.
+///
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProcessSpec {
+    pub argv: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    /// The names of the `env` vars which are excluded from the cache key.
+    pub execution_only_env_vars: BTreeSet<String>,
+    pub working_directory: Option<PathBuf>,
+    /// The input root, which defaults to the empty directory.
+    pub input_digest: Option<Digest>,
+    /// Directories which are symlinked into the sandbox at the given relative paths.
+    pub immutable_inputs: BTreeMap<PathBuf, Digest>,
+    /// The relative paths of the `immutable_inputs` which are used by nailgun.
+    pub use_nailgun: BTreeSet<PathBuf>,
+    pub output_files: BTreeSet<PathBuf>,
+    pub output_directories: BTreeSet<PathBuf>,
+    pub timeout_secs: Option<f64>,
+    pub execution_slot_variable: Option<String>,
+    pub concurrency_available: usize,
+    pub description: Option<String>,
+    /// Tags which may be matched by routing rules.
+    pub tags: BTreeSet<String>,
+    /// Named caches, by name, and the relative paths at which they are exposed to the process.
+    pub append_only_caches: BTreeMap<String, PathBuf>,
+    pub jdk_home: Option<PathBuf>,
+    /// One of `always`, `successful`, `per_restart_always`, `per_restart_successful` or
+    /// `per_session`.
+    pub cache_scope: Option<String>,
+    /// The environment to run in, which otherwise defaults to the one selected by the flags.
+    pub execution_environment: Option<ExecutionEnvironmentSpec>,
+    pub cache_key_gen_version: Option<String>,
+    /// Whether the process may be run by a remote persistent worker.
+    pub remote_persistent_worker: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExecutionEnvironmentSpec {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// One of `linux_x86_64`, `linux_arm64`, `macos_x86_64` or `macos_arm64`: defaults to the
+    /// current platform.
+    #[serde(default)]
+    pub platform: Option<String>,
+    pub strategy: StrategySpec,
+}
+
+///
+/// Mirrors `ProcessExecutionStrategy`: e.g. `"local"`, `{"docker": "<image>"}` or
+/// `{"remote_execution": [["<name>", "<value>"]]}`.
+///
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategySpec {
+    Local,
+    LocalInWorkspace,
+    RemoteExecution(Vec<(String, String)>),
+    Docker(String),
+}
+
+impl From<StrategySpec> for ProcessExecutionStrategy {
+    fn from(strategy: StrategySpec) -> Self {
+        match strategy {
+            StrategySpec::Local => Self::Local,
+            StrategySpec::LocalInWorkspace => Self::LocalInWorkspace,
+            StrategySpec::RemoteExecution(properties) => Self::RemoteExecution(properties),
+            StrategySpec::Docker(image) => Self::Docker(image),
+        }
+    }
+}
+
+impl ProcessSpec {
+    ///
+    /// Reads a spec from the given file, which is parsed as TOML if it has a `.toml` extension, and
+    /// as JSON otherwise.
+    ///
+    pub fn read(path: &Path) -> Result<ProcessSpec, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read process spec {}: {e}", path.display()))?;
+        if path
+            .extension()
+            .map_or(false, |extension| extension == "toml")
+        {
+            toml::from_str(&content)
+                .map_err(|e| format!("Could not parse process spec {}: {e}", path.display()))
+        } else {
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Could not parse process spec {}: {e}", path.display()))
+        }
+    }
+
+    ///
+    /// Converts the spec into a `Process`, using the given environment if the spec does not
+    /// specify one.
+    ///
+    pub async fn into_process(
+        self,
+        store: &Store,
+        default_execution_environment: ProcessExecutionEnvironment,
+    ) -> Result<Process, String> {
+        let relative_path = |path: PathBuf| {
+            RelativePath::new(&path)
+                .map_err(|e| format!("{} must be relative: {e}", path.display()))
+        };
+
+        let immutable_inputs = self
+            .immutable_inputs
+            .into_iter()
+            .map(|(path, digest)| -> Result<_, String> {
+                Ok((
+                    relative_path(path)?,
+                    DirectoryDigest::from_persisted_digest(digest),
+                ))
+            })
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+        let use_nailgun = self
+            .use_nailgun
+            .into_iter()
+            .map(relative_path)
+            .collect::<Result<BTreeSet<_>, _>>()?;
+        let input_root = self.input_digest.map_or_else(
+            || EMPTY_DIRECTORY_DIGEST.clone(),
+            DirectoryDigest::from_persisted_digest,
+        );
+        let input_digests = InputDigests::new(store, input_root, immutable_inputs, use_nailgun)
+            .await
+            .map_err(|e| format!("Could not create input digest for process: {e:?}"))?;
+
+        let append_only_caches = self
+            .append_only_caches
+            .into_iter()
+            .map(|(name, path)| -> Result<_, String> {
+                Ok((CacheName::new(name)?, relative_path(path)?))
+            })
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+
+        let execution_environment = match self.execution_environment {
+            Some(environment) => ProcessExecutionEnvironment {
+                name: environment.name,
+                platform: match environment.platform {
+                    Some(platform) => Platform::try_from(platform)?,
+                    None => Platform::current()?,
+                },
+                strategy: environment.strategy.into(),
+            },
+            None => default_execution_environment,
+        };
+
+        Ok(Process {
+            argv: self.argv,
+            env: self.env,
+            execution_only_env_vars: self.execution_only_env_vars,
+            working_directory: self.working_directory.map(relative_path).transpose()?,
+            input_digests,
+            output_files: self
+                .output_files
+                .into_iter()
+                .map(relative_path)
+                .collect::<Result<_, _>>()?,
+            output_directories: self
+                .output_directories
+                .into_iter()
+                .map(relative_path)
+                .collect::<Result<_, _>>()?,
+            timeout: self
+                .timeout_secs
+                .map(Duration::try_from_secs_f64)
+                .transpose()
+                .map_err(|e| format!("Invalid timeout_secs: {e}"))?,
+            execution_slot_variable: self.execution_slot_variable,
+            concurrency_available: self.concurrency_available,
+            description: self
+                .description
+                .unwrap_or_else(|| "process_executor".to_owned()),
+            tags: self.tags,
+            level: Level::Info,
+            append_only_caches,
+            jdk_home: self.jdk_home,
+            cache_scope: match self.cache_scope {
+                Some(cache_scope) => ProcessCacheScope::try_from(cache_scope)?,
+                None => ProcessCacheScope::Always,
+            },
+            execution_environment,
+            remote_cache_speculation_delay: Duration::from_millis(0),
+            local_cache: LocalCacheSettings::default(),
+            remote_persistent_worker: self.remote_persistent_worker,
+            attempt: 0,
+        })
+    }
+}
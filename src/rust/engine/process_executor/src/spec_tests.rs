@@ -0,0 +1,305 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use fs::EMPTY_DIRECTORY_DIGEST;
+use process_execution::{
+    Platform, ProcessCacheScope, ProcessExecutionEnvironment, ProcessExecutionStrategy,
+};
+use store::Store;
+use tempfile::TempDir;
+use testutil::data::{TestData, TestDirectory};
+
+use crate::spec::{ProcessSpec, StrategySpec};
+
+fn write_spec(dir: &TempDir, name: &str, content: &str) -> PathBuf {
+    let path = dir.path().join(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+fn default_environment() -> ProcessExecutionEnvironment {
+    ProcessExecutionEnvironment {
+        name: None,
+        platform: Platform::Linux_x86_64,
+        strategy: ProcessExecutionStrategy::Local,
+    }
+}
+
+async fn store_with_roland(dir: &TempDir) -> Store {
+    let store = Store::local_only(task_executor::Executor::new(), dir.path()).unwrap();
+    store
+        .store_file_bytes(TestData::roland().bytes(), false)
+        .await
+        .unwrap();
+    store
+        .record_directory(&TestDirectory::containing_roland().directory(), false)
+        .await
+        .unwrap();
+    store
+}
+
+#[test]
+fn read_json() {
+    let dir = TempDir::new().unwrap();
+    let roland = TestDirectory::containing_roland().digest();
+    let path = write_spec(
+        &dir,
+        "spec.json",
+        &format!(
+            r#"{{
+              "argv": ["/bin/cat", "roland.ext"],
+              "env": {{"PATH": "/bin"}},
+              "input_digest": {{"fingerprint": "{}", "size_bytes": {}}},
+              "output_files": ["out.txt"],
+              "timeout_secs": 1.5,
+              "cache_scope": "per_session",
+              "execution_environment": {{"platform": "linux_arm64", "strategy": "local"}}
+            }}"#,
+            roland.hash.to_hex(),
+            roland.size_bytes,
+        ),
+    );
+
+    let spec = ProcessSpec::read(&path).unwrap();
+    assert_eq!(spec.argv, vec!["/bin/cat", "roland.ext"]);
+    assert_eq!(spec.env.get("PATH").map(String::as_str), Some("/bin"));
+    assert_eq!(spec.input_digest, Some(roland));
+    assert_eq!(spec.output_files, [PathBuf::from("out.txt")].into());
+    assert_eq!(spec.timeout_secs, Some(1.5));
+    assert_eq!(spec.cache_scope.as_deref(), Some("per_session"));
+    let environment = spec.execution_environment.unwrap();
+    assert_eq!(environment.name, None);
+    assert_eq!(environment.platform.as_deref(), Some("linux_arm64"));
+    assert!(matches!(environment.strategy, StrategySpec::Local));
+}
+
+#[test]
+fn read_toml() {
+    let dir = TempDir::new().unwrap();
+    let path = write_spec(
+        &dir,
+        "spec.toml",
+        r#"
+        argv = ["/bin/echo", "hello"]
+        description = "say hello"
+        tags = ["greeting"]
+
+        [append_only_caches]
+        named = ".cache/named"
+
+        [execution_environment]
+        name = "docker"
+        strategy = { docker = "busybox:latest" }
+        "#,
+    );
+
+    let spec = ProcessSpec::read(&path).unwrap();
+    assert_eq!(spec.argv, vec!["/bin/echo", "hello"]);
+    assert_eq!(spec.description.as_deref(), Some("say hello"));
+    assert_eq!(spec.tags, ["greeting".to_owned()].into());
+    assert_eq!(
+        spec.append_only_caches.get("named"),
+        Some(&PathBuf::from(".cache/named"))
+    );
+    let environment = spec.execution_environment.unwrap();
+    assert_eq!(environment.name.as_deref(), Some("docker"));
+    assert!(matches!(
+        environment.strategy,
+        StrategySpec::Docker(image) if image == "busybox:latest"
+    ));
+}
+
+#[test]
+fn read_missing_fields_default() {
+    let dir = TempDir::new().unwrap();
+    for (name, content) in [("spec.json", "{}"), ("spec.toml", "")] {
+        let spec = ProcessSpec::read(&write_spec(&dir, name, content)).unwrap();
+        assert!(spec.argv.is_empty());
+        assert!(spec.env.is_empty());
+        assert_eq!(spec.input_digest, None);
+        assert_eq!(spec.timeout_secs, None);
+        assert_eq!(spec.concurrency_available, 0);
+        assert!(!spec.remote_persistent_worker);
+        assert!(spec.execution_environment.is_none());
+    }
+}
+
+#[test]
+fn read_rejects_unknown_fields() {
+    let dir = TempDir::new().unwrap();
+    for (name, content) in [
+        ("spec.json", r#"{"argv": ["/bin/true"], "argvs": []}"#),
+        ("spec.toml", "argv = [\"/bin/true\"]\nargvs = []"),
+        (
+            "environment.json",
+            r#"{"execution_environment": {"strategy": "local", "image": "busybox"}}"#,
+        ),
+    ] {
+        let err = ProcessSpec::read(&write_spec(&dir, name, content)).unwrap_err();
+        assert!(err.contains("Could not parse process spec"), "{err}");
+        assert!(err.contains("unknown field"), "{err}");
+    }
+}
+
+#[test]
+fn read_requires_a_strategy() {
+    let dir = TempDir::new().unwrap();
+    let path = write_spec(
+        &dir,
+        "spec.json",
+        r#"{"execution_environment": {"platform": "linux_x86_64"}}"#,
+    );
+
+    let err = ProcessSpec::read(&path).unwrap_err();
+    assert!(err.contains("missing field `strategy`"), "{err}");
+}
+
+#[test]
+fn read_missing_file() {
+    let dir = TempDir::new().unwrap();
+    let err = ProcessSpec::read(&dir.path().join("missing.json")).unwrap_err();
+    assert!(err.contains("Could not read process spec"), "{err}");
+}
+
+#[test]
+fn strategy_variants() {
+    let parse = |json: &str| -> ProcessExecutionStrategy {
+        serde_json::from_str::<StrategySpec>(json).unwrap().into()
+    };
+
+    assert_eq!(parse(r#""local""#), ProcessExecutionStrategy::Local);
+    assert_eq!(
+        parse(r#""local_in_workspace""#),
+        ProcessExecutionStrategy::LocalInWorkspace
+    );
+    assert_eq!(
+        parse(r#"{"remote_execution": [["OSFamily", "linux"], ["pool", "large"]]}"#),
+        ProcessExecutionStrategy::RemoteExecution(vec![
+            ("OSFamily".to_owned(), "linux".to_owned()),
+            ("pool".to_owned(), "large".to_owned()),
+        ])
+    );
+    assert_eq!(
+        parse(r#"{"docker": "busybox:latest"}"#),
+        ProcessExecutionStrategy::Docker("busybox:latest".to_owned())
+    );
+    assert!(serde_json::from_str::<StrategySpec>(r#""remote""#).is_err());
+}
+
+#[tokio::test]
+async fn into_process_defaults() {
+    let dir = TempDir::new().unwrap();
+    let store = Store::local_only(task_executor::Executor::new(), dir.path()).unwrap();
+    let spec = ProcessSpec {
+        argv: vec!["/bin/true".to_owned()],
+        ..ProcessSpec::default()
+    };
+
+    let process = spec
+        .into_process(&store, default_environment())
+        .await
+        .unwrap();
+    assert_eq!(process.argv, vec!["/bin/true"]);
+    assert_eq!(process.input_digests.complete, *EMPTY_DIRECTORY_DIGEST);
+    assert_eq!(process.timeout, None);
+    assert_eq!(process.description, "process_executor");
+    assert_eq!(process.cache_scope, ProcessCacheScope::Always);
+    assert_eq!(process.execution_environment, default_environment());
+}
+
+#[tokio::test]
+async fn into_process() {
+    let dir = TempDir::new().unwrap();
+    let store = store_with_roland(&dir).await;
+    let spec_dir = TempDir::new().unwrap();
+    let roland = TestDirectory::containing_roland().digest();
+    let path = write_spec(
+        &spec_dir,
+        "spec.toml",
+        &format!(
+            r#"
+            argv = ["/bin/cat", "roland.ext"]
+            working_directory = "subdir"
+            timeout_secs = 2.5
+            cache_scope = "successful"
+
+            [input_digest]
+            fingerprint = "{}"
+            size_bytes = {}
+
+            [execution_environment]
+            name = "remote"
+            platform = "linux_arm64"
+            strategy = {{ remote_execution = [["OSFamily", "linux"]] }}
+            "#,
+            roland.hash.to_hex(),
+            roland.size_bytes,
+        ),
+    );
+
+    let process = ProcessSpec::read(&path)
+        .unwrap()
+        .into_process(&store, default_environment())
+        .await
+        .unwrap();
+    assert_eq!(
+        process.input_digests.complete.as_digest(),
+        TestDirectory::containing_roland().digest()
+    );
+    assert_eq!(
+        process.working_directory.map(PathBuf::from),
+        Some(PathBuf::from("subdir"))
+    );
+    assert_eq!(process.timeout, Some(Duration::from_millis(2500)));
+    assert_eq!(process.cache_scope, ProcessCacheScope::Successful);
+    assert_eq!(
+        process.execution_environment,
+        ProcessExecutionEnvironment {
+            name: Some("remote".to_owned()),
+            platform: Platform::Linux_arm64,
+            strategy: ProcessExecutionStrategy::RemoteExecution(vec![(
+                "OSFamily".to_owned(),
+                "linux".to_owned()
+            )]),
+        }
+    );
+}
+
+#[tokio::test]
+async fn into_process_rejects_invalid_values() {
+    let dir = TempDir::new().unwrap();
+    let store = Store::local_only(task_executor::Executor::new(), dir.path()).unwrap();
+
+    let absolute = ProcessSpec {
+        output_files: [PathBuf::from("/etc/passwd")].into(),
+        ..ProcessSpec::default()
+    };
+    let err = absolute
+        .into_process(&store, default_environment())
+        .await
+        .unwrap_err();
+    assert!(err.contains("/etc/passwd must be relative"), "{err}");
+
+    let cache_scope = ProcessSpec {
+        cache_scope: Some("sometimes".to_owned()),
+        ..ProcessSpec::default()
+    };
+    let err = cache_scope
+        .into_process(&store, default_environment())
+        .await
+        .unwrap_err();
+    assert!(err.contains("Unknown Process cache scope"), "{err}");
+
+    let timeout = ProcessSpec {
+        timeout_secs: Some(-1.0),
+        ..ProcessSpec::default()
+    };
+    let err = timeout
+        .into_process(&store, default_environment())
+        .await
+        .unwrap_err();
+    assert!(err.contains("Invalid timeout_secs"), "{err}");
+}
@@ -0,0 +1,241 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::path::Path;
+
+use clap::StructOpt;
+use hashing::Digest;
+use process_execution::{Platform, ProcessExecutionEnvironment, ProcessExecutionStrategy};
+use prost::Message;
+use protos::gen::build::bazel::remote::execution::v2 as remexec;
+use store::Store;
+use tempfile::TempDir;
+use testutil::data::{TestData, TestDirectory};
+use workunit_store::WorkunitStore;
+
+use crate::{extract_request_from_action_digest, run, Opt};
+
+async fn store_with_roland(dir: &TempDir) -> Store {
+    let store = Store::local_only(task_executor::Executor::new(), dir.path()).unwrap();
+    store
+        .store_file_bytes(TestData::roland().bytes(), false)
+        .await
+        .unwrap();
+    store
+        .record_directory(&TestDirectory::containing_roland().directory(), false)
+        .await
+        .unwrap();
+    store
+}
+
+///
+/// Parses the given args, with flags to run in the given workdir, with the roland directory as the
+/// input root.
+///
+fn opt(work_dir: &TempDir, args: &[&str]) -> Opt {
+    let roland = TestDirectory::containing_roland().digest();
+    let flags = vec![
+        "process_executor".to_owned(),
+        format!("--input-digest={}", roland.hash.to_hex()),
+        format!("--input-digest-length={}", roland.size_bytes),
+        format!("--work-dir={}", work_dir.path().display()),
+        format!(
+            "--named-cache-path={}",
+            work_dir.path().join("named_caches").display()
+        ),
+    ];
+    Opt::from_iter(
+        flags
+            .into_iter()
+            .chain(args.iter().map(|arg| (*arg).to_owned())),
+    )
+}
+
+fn sandboxes(work_dir: &Path) -> Vec<String> {
+    std::fs::read_dir(work_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("pants-sandbox-"))
+        .collect()
+}
+
+#[tokio::test]
+async fn skip_execution_materializes_inputs() {
+    let (_, _workunit) = WorkunitStore::setup_for_tests();
+    let store_dir = TempDir::new().unwrap();
+    let store = store_with_roland(&store_dir).await;
+    let work_dir = TempDir::new().unwrap();
+    let input_dir = TempDir::new().unwrap();
+    let marker = work_dir.path().join("ran");
+
+    let args = opt(
+        &work_dir,
+        &[
+            &format!("--materialize-input-to={}", input_dir.path().display()),
+            "--skip-execution",
+            "--",
+            "/bin/sh",
+            "-c",
+            &format!("touch {}", marker.display()),
+        ],
+    );
+    let result = run(args, store, task_executor::Executor::new()).await;
+
+    assert!(result.is_none());
+    assert_eq!(
+        std::fs::read(input_dir.path().join("roland.ext")).unwrap(),
+        TestData::roland().bytes()
+    );
+    assert!(!marker.exists());
+    assert!(sandboxes(work_dir.path()).is_empty());
+}
+
+#[tokio::test]
+async fn materialize_input_then_run() {
+    let (_, _workunit) = WorkunitStore::setup_for_tests();
+    let store_dir = TempDir::new().unwrap();
+    let store = store_with_roland(&store_dir).await;
+    let work_dir = TempDir::new().unwrap();
+    let input_dir = TempDir::new().unwrap();
+
+    let args = opt(
+        &work_dir,
+        &[
+            &format!("--materialize-input-to={}", input_dir.path().display()),
+            "--",
+            "/bin/cat",
+            "roland.ext",
+        ],
+    );
+    let result = run(args, store.clone(), task_executor::Executor::new())
+        .await
+        .unwrap();
+
+    assert_eq!(result.exit_code, 0);
+    assert_eq!(
+        store
+            .load_file_bytes_with(result.stdout_digest, |bytes| bytes.to_vec())
+            .await
+            .unwrap(),
+        TestData::roland().bytes()
+    );
+    assert!(input_dir.path().join("roland.ext").is_file());
+}
+
+#[tokio::test]
+async fn keep_sandboxes() {
+    let (_, _workunit) = WorkunitStore::setup_for_tests();
+    for (keep_sandboxes, expected) in [
+        ("--keep-sandboxes=always", 1),
+        ("--keep-sandboxes=on_failure", 0),
+        ("--keep-sandboxes=never", 0),
+    ] {
+        let store_dir = TempDir::new().unwrap();
+        let store = store_with_roland(&store_dir).await;
+        let work_dir = TempDir::new().unwrap();
+
+        let args = opt(&work_dir, &[keep_sandboxes, "--", "/bin/cat", "roland.ext"]);
+        let result = run(args, store, task_executor::Executor::new())
+            .await
+            .unwrap();
+
+        assert_eq!(result.exit_code, 0);
+        let sandboxes = sandboxes(work_dir.path());
+        assert_eq!(sandboxes.len(), expected, "{keep_sandboxes}: {sandboxes:?}");
+        for sandbox in sandboxes {
+            assert!(work_dir.path().join(sandbox).join("roland.ext").is_file());
+        }
+    }
+}
+
+///
+/// Stores an Action whose Command has the given platform properties, and returns its digest.
+///
+async fn store_action(store: &Store, properties: &[(&str, &str)]) -> Digest {
+    let command = remexec::Command {
+        arguments: vec!["/bin/cat".to_owned(), "roland.ext".to_owned()],
+        platform: Some(remexec::Platform {
+            properties: properties
+                .iter()
+                .map(|(name, value)| remexec::platform::Property {
+                    name: (*name).to_owned(),
+                    value: (*value).to_owned(),
+                })
+                .collect(),
+        }),
+        ..remexec::Command::default()
+    };
+    let command_digest = store
+        .store_file_bytes(command.encode_to_vec().into(), false)
+        .await
+        .unwrap();
+    let action = remexec::Action {
+        command_digest: Some((&command_digest).into()),
+        input_root_digest: Some((&TestDirectory::containing_roland().digest()).into()),
+        ..remexec::Action::default()
+    };
+    store
+        .store_file_bytes(action.encode_to_vec().into(), false)
+        .await
+        .unwrap()
+}
+
+async fn strategy_for_action(
+    properties: &[(&str, &str)],
+    strategy: ProcessExecutionStrategy,
+) -> ProcessExecutionStrategy {
+    let store_dir = TempDir::new().unwrap();
+    let store = store_with_roland(&store_dir).await;
+    let action_digest = store_action(&store, properties).await;
+    let (process, _) = extract_request_from_action_digest(
+        &store,
+        action_digest,
+        ProcessExecutionEnvironment {
+            name: None,
+            platform: Platform::Linux_x86_64,
+            strategy,
+        },
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(process.argv, vec!["/bin/cat", "roland.ext"]);
+    process.execution_environment.strategy
+}
+
+#[tokio::test]
+async fn action_platform_properties_default_to_those_of_the_command() {
+    assert_eq!(
+        strategy_for_action(
+            &[("OSFamily", "linux"), ("container-image", "busybox")],
+            ProcessExecutionStrategy::RemoteExecution(vec![]),
+        )
+        .await,
+        ProcessExecutionStrategy::RemoteExecution(vec![
+            ("OSFamily".to_owned(), "linux".to_owned()),
+            ("container-image".to_owned(), "busybox".to_owned()),
+        ])
+    );
+}
+
+#[tokio::test]
+async fn action_platform_properties_explicit() {
+    let explicit = vec![("OSFamily".to_owned(), "macos".to_owned())];
+    assert_eq!(
+        strategy_for_action(
+            &[("OSFamily", "linux")],
+            ProcessExecutionStrategy::RemoteExecution(explicit.clone()),
+        )
+        .await,
+        ProcessExecutionStrategy::RemoteExecution(explicit)
+    );
+}
+
+#[tokio::test]
+async fn action_platform_properties_ignored_locally() {
+    assert_eq!(
+        strategy_for_action(&[("OSFamily", "linux")], ProcessExecutionStrategy::Local).await,
+        ProcessExecutionStrategy::Local
+    );
+}
@@ -23,11 +23,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         "protos/bazelbuild_remote-apis/build/bazel/semver/semver.proto",
         "protos/buildbarn/cas.proto",
         "protos/googleapis/google/bytestream/bytestream.proto",
+        "protos/googleapis/google/devtools/build/v1/publish_build_event.proto",
         "protos/googleapis/google/rpc/code.proto",
         "protos/googleapis/google/rpc/error_details.proto",
         "protos/googleapis/google/rpc/status.proto",
         "protos/googleapis/google/longrunning/operations.proto",
         "protos/pants/cache.proto",
+        "protos/pants/sandboxer.proto",
         "protos/standard/google/protobuf/empty.proto",
       ],
       &[
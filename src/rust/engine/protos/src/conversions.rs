@@ -55,3 +55,62 @@ pub fn require_digest<
         }
     }
 }
+
+impl From<hashing::DigestFunction>
+    for crate::gen::build::bazel::remote::execution::v2::digest_function::Value
+{
+    fn from(function: hashing::DigestFunction) -> Self {
+        match function {
+            hashing::DigestFunction::Sha256 => Self::Sha256,
+            hashing::DigestFunction::Blake3 => Self::Blake3,
+        }
+    }
+}
+
+///
+/// The value of the `digest_function` field of requests which use the given function.
+///
+/// The field is left unset for SHA-256, because servers infer it from the length of digests (and
+/// servers which predate the field would not expect it).
+///
+pub fn digest_function_field(function: hashing::DigestFunction) -> i32 {
+    use crate::gen::build::bazel::remote::execution::v2::digest_function::Value;
+    match function {
+        hashing::DigestFunction::Sha256 => Value::Unknown as i32,
+        function => Value::from(function) as i32,
+    }
+}
+
+///
+/// Fails if a server which advertises the given `supported` digest functions (in its
+/// capabilities) does not support the given function. A server which advertises none is assumed
+/// to support only SHA-256.
+///
+pub fn require_digest_function(
+    function: hashing::DigestFunction,
+    supported: &[i32],
+    server: &str,
+) -> Result<(), String> {
+    use crate::gen::build::bazel::remote::execution::v2::digest_function::Value;
+    let wanted = Value::from(function) as i32;
+    if supported.contains(&wanted) || (supported.is_empty() && wanted == Value::Sha256 as i32) {
+        return Ok(());
+    }
+    let supported = supported
+        .iter()
+        .map(|value| {
+            Value::try_from(*value)
+                .map(|value| value.as_str_name().to_lowercase())
+                .unwrap_or_else(|_| value.to_string())
+        })
+        .collect::<Vec<_>>();
+    Err(format!(
+        "The {server} does not support the `{function}` digest function of \
+        `[GLOBAL].digest_function`: it supports {}.",
+        if supported.is_empty() {
+            "only `sha256`".to_owned()
+        } else {
+            supported.join(", ")
+        }
+    ))
+}
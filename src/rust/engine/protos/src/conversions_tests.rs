@@ -51,3 +51,40 @@ fn from_bad_bazel_digest() {
         "Bad error message: {err}"
     );
 }
+
+#[test]
+fn digest_function_field() {
+    assert_eq!(
+        crate::digest_function_field(hashing::DigestFunction::Sha256),
+        remexec::digest_function::Value::Unknown as i32
+    );
+    assert_eq!(
+        crate::digest_function_field(hashing::DigestFunction::Blake3),
+        remexec::digest_function::Value::Blake3 as i32
+    );
+}
+
+#[test]
+fn require_digest_function() {
+    let sha256 = remexec::digest_function::Value::Sha256 as i32;
+    let blake3 = remexec::digest_function::Value::Blake3 as i32;
+    assert_eq!(
+        Ok(()),
+        crate::require_digest_function(hashing::DigestFunction::Sha256, &[], "server")
+    );
+    assert_eq!(
+        Ok(()),
+        crate::require_digest_function(
+            hashing::DigestFunction::Blake3,
+            &[sha256, blake3],
+            "server"
+        )
+    );
+    let error = crate::require_digest_function(hashing::DigestFunction::Blake3, &[], "server")
+        .expect_err("Want error");
+    assert!(error.contains("it supports only `sha256`"), "{error}");
+    let error =
+        crate::require_digest_function(hashing::DigestFunction::Blake3, &[sha256], "server")
+            .expect_err("Want error");
+    assert!(error.contains("it supports sha256."), "{error}");
+}
@@ -12,6 +12,14 @@ impl Hash for JavascriptInferenceMetadata {
             pattern.pattern.hash(state);
             pattern.replacements.hash(state);
         }
+        if let Some(config_paths) = &self.config_paths {
+            config_paths.root.hash(state);
+            for pattern in &config_paths.paths {
+                pattern.pattern.hash(state);
+                pattern.replacements.hash(state);
+            }
+            config_paths.has_base_url.hash(state);
+        }
     }
 }
 
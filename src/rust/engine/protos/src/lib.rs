@@ -6,7 +6,7 @@
 #![allow(clippy::derive_partial_eq_without_eq)]
 
 mod conversions;
-pub use conversions::require_digest;
+pub use conversions::{digest_function_field, require_digest, require_digest_function};
 
 #[cfg(test)]
 mod conversions_tests;
@@ -19,6 +19,13 @@ pub mod gen {
         pub mod bytestream {
             tonic::include_proto!("google.bytestream");
         }
+        pub mod devtools {
+            pub mod build {
+                pub mod v1 {
+                    tonic::include_proto!("google.devtools.build.v1");
+                }
+            }
+        }
         pub mod longrunning {
             tonic::include_proto!("google.longrunning");
         }
@@ -58,6 +65,9 @@ pub mod gen {
         pub mod cache {
             tonic::include_proto!("pants.cache");
         }
+        pub mod sandboxer {
+            tonic::include_proto!("pants.sandboxer");
+        }
     }
 }
 
@@ -17,7 +17,7 @@ const OTHER_DIRECTORY_SIZE: i64 = 0;
 fn empty_directory() {
     assert_eq!(
         Ok(()),
-        verify_directory_canonical(EMPTY_DIGEST, &Directory::default())
+        verify_directory_canonical(*EMPTY_DIGEST, &Directory::default())
     );
 }
 
@@ -61,7 +61,10 @@ fn canonical_directory() {
         ..Directory::default()
     };
 
-    assert_eq!(Ok(()), verify_directory_canonical(EMPTY_DIGEST, &directory));
+    assert_eq!(
+        Ok(()),
+        verify_directory_canonical(*EMPTY_DIGEST, &directory)
+    );
 }
 
 #[test]
@@ -77,7 +80,7 @@ fn empty_child_name() {
         ..Directory::default()
     };
 
-    let error = verify_directory_canonical(EMPTY_DIGEST, &directory).expect_err("Want error");
+    let error = verify_directory_canonical(*EMPTY_DIGEST, &directory).expect_err("Want error");
     assert!(
         error.contains("A child name must not be empty"),
         "Bad error message: {error}"
@@ -97,7 +100,7 @@ fn multiple_path_segments_in_directory() {
         ..Directory::default()
     };
 
-    let error = verify_directory_canonical(EMPTY_DIGEST, &directory).expect_err("Want error");
+    let error = verify_directory_canonical(*EMPTY_DIGEST, &directory).expect_err("Want error");
     assert!(error.contains("pets/cats"), "Bad error message: {error}");
 }
 
@@ -115,7 +118,7 @@ fn multiple_path_segments_in_file() {
         ..Directory::default()
     };
 
-    let error = verify_directory_canonical(EMPTY_DIGEST, &directory).expect_err("Want error");
+    let error = verify_directory_canonical(*EMPTY_DIGEST, &directory).expect_err("Want error");
     assert!(error.contains("cats/roland"), "Bad error message: {error}");
 }
 
@@ -141,7 +144,7 @@ fn duplicate_path_in_directory() {
         ..Directory::default()
     };
 
-    let error = verify_directory_canonical(EMPTY_DIGEST, &directory).expect_err("Want error");
+    let error = verify_directory_canonical(*EMPTY_DIGEST, &directory).expect_err("Want error");
     assert!(error.contains("cats"), "Bad error message: {error}");
 }
 
@@ -169,7 +172,7 @@ fn duplicate_path_in_file() {
         ..Directory::default()
     };
 
-    let error = verify_directory_canonical(EMPTY_DIGEST, &directory).expect_err("Want error");
+    let error = verify_directory_canonical(*EMPTY_DIGEST, &directory).expect_err("Want error");
     assert!(error.contains("roland"), "Bad error message: {error}");
 }
 
@@ -194,5 +197,5 @@ fn duplicate_path_in_file_and_directory() {
         ..Directory::default()
     };
 
-    verify_directory_canonical(EMPTY_DIGEST, &directory).expect_err("Want error");
+    verify_directory_canonical(*EMPTY_DIGEST, &directory).expect_err("Want error");
 }
@@ -136,7 +136,7 @@ impl Provider {
     ) -> Result<bool, String> {
         // Some providers (e.g. GitHub Actions Cache) don't like storing an empty file, so we just magic
         // it up here, and ignore it when storing.
-        if digest == EMPTY_DIGEST {
+        if digest == *EMPTY_DIGEST {
             // `destination` starts off empty, so is already in the right state.
             return Ok(true);
         }
@@ -199,7 +199,7 @@ impl ByteStoreProvider for Provider {
     async fn store_bytes(&self, digest: Digest, bytes: Bytes) -> Result<(), String> {
         // Some providers (e.g. GitHub Actions Cache) don't like storing an empty file, so we don't
         // store it here, and magic it up when loading.
-        if digest == EMPTY_DIGEST {
+        if digest == *EMPTY_DIGEST {
             return Ok(());
         }
 
@@ -219,7 +219,7 @@ impl ByteStoreProvider for Provider {
     async fn store_file(&self, digest: Digest, mut file: File) -> Result<(), String> {
         // Some providers (e.g. GitHub Actions Cache) don't like storing an empty file, so we don't
         // store it here, and magic it up when loading.
-        if digest == EMPTY_DIGEST {
+        if digest == *EMPTY_DIGEST {
             return Ok(());
         }
 
@@ -267,7 +267,7 @@ impl ByteStoreProvider for Provider {
         let existences = future::try_join_all(digests.map(|digest| async move {
             // Some providers (e.g. GitHub Actions Cache) don't like storing an empty file, so we don't
             // store it, but can still magic it up when loading, i.e. it is never missing.
-            if digest == EMPTY_DIGEST {
+            if digest == *EMPTY_DIGEST {
                 return Ok(None);
             }
 
@@ -6,7 +6,7 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use grpc_util::retry::{retry_call, status_is_retryable};
 use grpc_util::{headers_to_http_header_map, layered_service, status_to_str, LayeredService};
-use hashing::Digest;
+use hashing::{Digest, DigestFunction};
 use protos::gen::build::bazel::remote::execution::v2 as remexec;
 use remexec::action_cache_client::ActionCacheClient;
 use remexec::ActionResult;
@@ -70,6 +70,7 @@ impl ActionCacheProvider for Provider {
                     instance_name: self.instance_name.clone().unwrap_or_else(|| "".to_owned()),
                     action_digest: Some(action_digest.into()),
                     action_result: Some(action_result.clone()),
+                    digest_function: protos::digest_function_field(DigestFunction::current()),
                     ..remexec::UpdateActionResultRequest::default()
                 };
 
@@ -99,6 +100,7 @@ impl ActionCacheProvider for Provider {
                 let request = remexec::GetActionResultRequest {
                     action_digest: Some(action_digest.into()),
                     instance_name: self.instance_name.clone().unwrap_or_default(),
+                    digest_function: protos::digest_function_field(DigestFunction::current()),
                     ..remexec::GetActionResultRequest::default()
                 };
                 let request = apply_headers(Request::new(request), build_id);
@@ -15,7 +15,7 @@ use grpc_util::retry::{retry_call, status_is_retryable};
 use grpc_util::{
     headers_to_http_header_map, layered_service, status_ref_to_str, status_to_str, LayeredService,
 };
-use hashing::{Digest, Hasher};
+use hashing::{Digest, DigestFunction, Hasher};
 use protos::gen::build::bazel::remote::execution::v2 as remexec;
 use protos::gen::google::bytestream::byte_stream_client::ByteStreamClient;
 use remexec::{
@@ -118,6 +118,7 @@ impl Provider {
                 data: bytes,
                 compressor: remexec::compressor::Value::Identity as i32,
             }],
+            digest_function: protos::digest_function_field(DigestFunction::current()),
         };
 
         let mut client = self.cas_client.as_ref().clone();
@@ -136,10 +137,11 @@ impl Provider {
         let len = digest.size_bytes;
         let instance_name = self.instance_name.clone().unwrap_or_default();
         let resource_name = format!(
-            "{}{}uploads/{}/blobs/{}/{}",
+            "{}{}uploads/{}/blobs/{}{}/{}",
             &instance_name,
             if instance_name.is_empty() { "" } else { "/" },
             uuid::Uuid::new_v4(),
+            resource_name_digest_function(),
             digest.hash,
             digest.size_bytes,
         );
@@ -245,11 +247,42 @@ impl Provider {
             .get_or_try_init(capabilities_fut)
             .await
     }
+
+    ///
+    /// Fails if the server does not support the current digest function. The capabilities of the
+    /// server are only fetched to check this for functions other than SHA-256, which servers are
+    /// assumed to support.
+    ///
+    async fn check_digest_function(&self) -> Result<(), String> {
+        let function = DigestFunction::current();
+        if function == DigestFunction::Sha256 {
+            return Ok(());
+        }
+        let capabilities = self.get_capabilities().await.map_err(|e| e.to_string())?;
+        let supported = capabilities
+            .cache_capabilities
+            .as_ref()
+            .map(|c| c.digest_functions.as_slice())
+            .unwrap_or_default();
+        protos::require_digest_function(function, supported, "remote store")
+    }
+}
+
+///
+/// The component of resource names for the current digest function, which is omitted for SHA-256
+/// (and the other functions that servers can infer from the length of a digest).
+///
+fn resource_name_digest_function() -> &'static str {
+    match DigestFunction::current() {
+        DigestFunction::Sha256 => "",
+        DigestFunction::Blake3 => "blake3/",
+    }
 }
 
 #[async_trait]
 impl ByteStoreProvider for Provider {
     async fn store_bytes(&self, digest: Digest, bytes: Bytes) -> Result<(), String> {
+        self.check_digest_function().await?;
         let len = digest.size_bytes;
 
         let max_batch_total_size_bytes = {
@@ -283,6 +316,7 @@ impl ByteStoreProvider for Provider {
     }
 
     async fn store_file(&self, digest: Digest, file: File) -> Result<(), String> {
+        self.check_digest_function().await?;
         let source = Arc::new(Mutex::new(file));
         retry_call(
       source,
@@ -312,11 +346,13 @@ impl ByteStoreProvider for Provider {
         digest: Digest,
         destination: &mut dyn LoadDestination,
     ) -> Result<bool, String> {
+        self.check_digest_function().await?;
         let instance_name = self.instance_name.clone().unwrap_or_default();
         let resource_name = format!(
-            "{}{}blobs/{}/{}",
+            "{}{}blobs/{}{}/{}",
             &instance_name,
             if instance_name.is_empty() { "" } else { "/" },
+            resource_name_digest_function(),
             digest.hash,
             digest.size_bytes
         );
@@ -393,9 +429,11 @@ impl ByteStoreProvider for Provider {
         &self,
         digests: &mut (dyn Iterator<Item = Digest> + Send),
     ) -> Result<HashSet<Digest>, String> {
+        self.check_digest_function().await?;
         let request = remexec::FindMissingBlobsRequest {
             instance_name: self.instance_name.as_ref().cloned().unwrap_or_default(),
             blob_digests: digests.into_iter().map(|d| d.into()).collect::<Vec<_>>(),
+            digest_function: protos::digest_function_field(DigestFunction::current()),
         };
 
         let client = self.cas_client.as_ref().clone();
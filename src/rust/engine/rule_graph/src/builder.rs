@@ -242,14 +242,31 @@ pub struct Builder<R: Rule> {
 }
 
 impl<R: Rule> Builder<R> {
-    pub fn new(rules: IndexSet<R>, mut queries: IndexSet<Query<R::TypeId>>) -> Builder<R> {
-        // Extend the Queries with those assumed by Reentry nodes.
+    pub fn new(rules: IndexSet<R>, queries: IndexSet<Query<R::TypeId>>) -> Builder<R> {
+        let queries = Self::with_reentry_queries(&rules, queries);
+        Self::new_exact(rules, queries)
+    }
+
+    ///
+    /// Extend the given Queries with those assumed by Reentry nodes of the given Rules.
+    ///
+    pub(crate) fn with_reentry_queries(
+        rules: &IndexSet<R>,
+        mut queries: IndexSet<Query<R::TypeId>>,
+    ) -> IndexSet<Query<R::TypeId>> {
         queries.extend(rules.iter().flat_map(|rule| {
             rule.dependency_keys(0)
                 .into_iter()
                 .filter_map(|dk| dk.as_reentry_query())
         }));
+        queries
+    }
 
+    ///
+    /// Create a Builder which will solve exactly the given Queries, without extending them with
+    /// the Queries assumed by Reentry nodes.
+    ///
+    pub(crate) fn new_exact(rules: IndexSet<R>, queries: IndexSet<Query<R::TypeId>>) -> Builder<R> {
         // Group rules by product/return type.
         let mut rules_by_type = BTreeMap::new();
         for rule in rules {
@@ -303,7 +320,7 @@ impl<R: Rule> Builder<R> {
     ///
     /// Validate that all rules have unique RuleIds.
     ///
-    fn validate_rule_ids(&self) -> Result<(), String> {
+    pub(crate) fn validate_rule_ids(&self) -> Result<(), String> {
         let mut invalid_rule_ids: Vec<&RuleId> = self
             .rules
             .values()
@@ -1449,6 +1466,11 @@ impl<R: Rule> Builder<R> {
         Ok(RuleGraph {
             queries: self.queries.into_iter().collect(),
             rule_dependency_edges,
+            rules_by_product: self
+                .rules
+                .into_iter()
+                .map(|(product, rules)| (product, rules.into_iter().collect()))
+                .collect(),
             // TODO
             unreachable_rules: Vec::default(),
         })
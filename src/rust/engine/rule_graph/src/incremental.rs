@@ -0,0 +1,205 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+use fnv::{FnvHashSet as HashSet, FnvHasher};
+use indexmap::IndexSet;
+use internment::Intern;
+
+use crate::builder::Builder;
+use crate::rules::{Query, Rule};
+use crate::{EntryWithDeps, RootEntry, RuleGraph, RulesByProduct};
+
+impl<R: Rule> RuleGraph<R> {
+    ///
+    /// Create a RuleGraph for the given Rules and Queries, reusing the solved subgraphs of the given
+    /// previous RuleGraph for any Query whose relevant Rules have not changed.
+    ///
+    /// The Rules which are relevant to a Query are all of those which produce a type that is
+    /// transitively reachable via `DependencyKey`s from the Query. If any of them were added, removed,
+    /// or modified, then the Query might now be solved differently (or might have become ambiguous),
+    /// and so it is solved from scratch.
+    ///
+    pub fn new_incremental(
+        rules: IndexSet<R>,
+        queries: IndexSet<Query<R::TypeId>>,
+        previous: &RuleGraph<R>,
+    ) -> Result<RuleGraph<R>, String> {
+        let queries = Builder::with_reentry_queries(&rules, queries);
+
+        let mut rules_by_product = RulesByProduct::<R>::default();
+        for rule in &rules {
+            rules_by_product
+                .entry(rule.product())
+                .or_default()
+                .insert(rule.clone());
+        }
+
+        let mut reused_roots = Vec::new();
+        let mut unsolved_queries = IndexSet::new();
+        for query in &queries {
+            let root = Intern::new(EntryWithDeps::Root(RootEntry(query.clone())));
+            if previous.rule_dependency_edges.contains_key(&root)
+                && Self::relevant_products(&rules_by_product, query)
+                    .iter()
+                    .all(|product| {
+                        rules_by_product.get(product) == previous.rules_by_product.get(product)
+                    })
+            {
+                reused_roots.push(root);
+            } else {
+                unsolved_queries.insert(query.clone());
+            }
+        }
+        log::debug!(
+            "Reusing {} of {} solved queries from the previous rule graph.",
+            reused_roots.len(),
+            queries.len()
+        );
+
+        let mut rule_dependency_edges = previous.reachable_edges(reused_roots)?;
+        let builder = Builder::new_exact(rules, unsolved_queries.clone());
+        if unsolved_queries.is_empty() {
+            // Solving is skipped entirely, but the rules must still be validated.
+            builder.validate_rule_ids()?;
+        } else {
+            rule_dependency_edges.extend(builder.graph()?.rule_dependency_edges);
+        }
+
+        Ok(RuleGraph {
+            queries: queries.into_iter().collect(),
+            rule_dependency_edges,
+            rules_by_product,
+            unreachable_rules: Vec::default(),
+        })
+    }
+
+    ///
+    /// Returns all product types which might be consumed while solving the given Query.
+    ///
+    fn relevant_products(
+        rules_by_product: &RulesByProduct<R>,
+        query: &Query<R::TypeId>,
+    ) -> HashSet<R::TypeId> {
+        let mut relevant = HashSet::default();
+        let mut to_visit = vec![query.product];
+        while let Some(product) = to_visit.pop() {
+            if !relevant.insert(product) {
+                continue;
+            }
+            to_visit.extend(
+                rules_by_product
+                    .get(&product)
+                    .into_iter()
+                    .flatten()
+                    .flat_map(|rule| rule.dependency_keys(0))
+                    .map(|dk| dk.product()),
+            );
+        }
+        relevant
+    }
+}
+
+///
+/// Holds the most recently solved RuleGraph, keyed by a fingerprint of the Rules and Queries that
+/// it was solved for.
+///
+/// An identical set of Rules and Queries reuses the cached RuleGraph as-is, while a modified set is
+/// solved incrementally against it via `RuleGraph::new_incremental`. Before a RuleGraph has been
+/// solved via the cache, a persisted RuleGraph (e.g. from a previous process) may be solved against
+/// instead.
+///
+pub struct RuleGraphCache<R: Rule> {
+    previous: Mutex<Option<SolvedRuleGraph<R>>>,
+}
+
+struct SolvedRuleGraph<R: Rule> {
+    fingerprint: u64,
+    rules: IndexSet<R>,
+    queries: IndexSet<Query<R::TypeId>>,
+    rule_graph: Arc<RuleGraph<R>>,
+}
+
+impl<R: Rule> RuleGraphCache<R> {
+    pub fn new() -> RuleGraphCache<R> {
+        RuleGraphCache {
+            previous: Mutex::new(None),
+        }
+    }
+
+    pub fn get_or_solve(
+        &self,
+        rules: IndexSet<R>,
+        queries: IndexSet<Query<R::TypeId>>,
+    ) -> Result<Arc<RuleGraph<R>>, String> {
+        self.get_or_solve_persisted(rules, queries, None)
+            .map(|(rule_graph, _)| rule_graph)
+    }
+
+    ///
+    /// Like `get_or_solve`, but if no RuleGraph has been solved via this cache yet (e.g. because
+    /// the process has just started), solves incrementally against the given persisted RuleGraph
+    /// (see `RuleGraph::encode`) if it can be decoded.
+    ///
+    /// Returns the RuleGraph, and whether it was newly solved (rather than reused as-is from this
+    /// cache), in which case the caller should persist it in place of the given one.
+    ///
+    pub fn get_or_solve_persisted(
+        &self,
+        rules: IndexSet<R>,
+        queries: IndexSet<Query<R::TypeId>>,
+        persisted: Option<&[u8]>,
+    ) -> Result<(Arc<RuleGraph<R>>, bool), String> {
+        let fingerprint = Self::fingerprint(&rules, &queries);
+        let mut previous = self.previous.lock().unwrap();
+        let rule_graph = match previous.as_ref() {
+            Some(solved)
+                if solved.fingerprint == fingerprint
+                    && solved.rules == rules
+                    && solved.queries == queries =>
+            {
+                log::debug!("Reusing the previous rule graph, which had identical rules.");
+                return Ok((solved.rule_graph.clone(), false));
+            }
+            Some(solved) => {
+                RuleGraph::new_incremental(rules.clone(), queries.clone(), &solved.rule_graph)?
+            }
+            None => match persisted.map(|bytes| RuleGraph::decode(bytes, &rules, &queries)) {
+                Some(Ok(persisted)) => {
+                    RuleGraph::new_incremental(rules.clone(), queries.clone(), &persisted)?
+                }
+                Some(Err(e)) => {
+                    log::debug!("Ignoring the persisted rule graph: {e}");
+                    RuleGraph::new(rules.clone(), queries.clone())?
+                }
+                None => RuleGraph::new(rules.clone(), queries.clone())?,
+            },
+        };
+
+        let rule_graph = Arc::new(rule_graph);
+        *previous = Some(SolvedRuleGraph {
+            fingerprint,
+            rules,
+            queries,
+            rule_graph: rule_graph.clone(),
+        });
+        Ok((rule_graph, true))
+    }
+
+    ///
+    /// Compute an order-independent fingerprint of the given Rules and Queries.
+    ///
+    fn fingerprint(rules: &IndexSet<R>, queries: &IndexSet<Query<R::TypeId>>) -> u64 {
+        fn hash_of<T: Hash>(t: &T) -> u64 {
+            let mut hasher = FnvHasher::default();
+            t.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let rules_fingerprint = rules.iter().map(hash_of).fold(0, u64::wrapping_add);
+        let queries_fingerprint = queries.iter().map(hash_of).fold(0, u64::wrapping_add);
+        hash_of(&(rules_fingerprint, queries_fingerprint))
+    }
+}
@@ -2,6 +2,8 @@
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
 mod builder;
+mod incremental;
+mod persisted;
 mod rules;
 
 use std::io;
@@ -12,6 +14,7 @@ use indexmap::IndexSet;
 use internment::Intern;
 
 pub use crate::builder::Builder;
+pub use crate::incremental::RuleGraphCache;
 pub use crate::rules::{
     DependencyKey, DisplayForGraph, DisplayForGraphArgs, ParamTypes, Query, Rule, RuleId, TypeId,
 };
@@ -90,6 +93,8 @@ impl<R: Rule> RuleEntry<R> {
 
 type RuleDependencyEdges<R> = HashMap<Intern<EntryWithDeps<R>>, RuleEdges<R>>;
 
+type RulesByProduct<R> = HashMap<<R as Rule>::TypeId, HashSet<R>>;
+
 #[derive(Eq, Hash, PartialEq, Clone, Debug)]
 struct Diagnostic<R: Rule> {
     params: ParamTypes<R::TypeId>,
@@ -104,6 +109,9 @@ struct Diagnostic<R: Rule> {
 pub struct RuleGraph<R: Rule> {
     queries: Vec<Query<R::TypeId>>,
     rule_dependency_edges: RuleDependencyEdges<R>,
+    // The rules that this graph was solved with, grouped by product type. Used to determine which
+    // portions of the graph may be reused when solving for a modified set of rules.
+    rules_by_product: RulesByProduct<R>,
     unreachable_rules: Vec<UnreachableError<R>>,
 }
 
@@ -114,6 +122,7 @@ impl<R: Rule> Default for RuleGraph<R> {
         RuleGraph {
             queries: Vec::default(),
             rule_dependency_edges: RuleDependencyEdges::default(),
+            rules_by_product: RulesByProduct::default(),
             unreachable_rules: Vec::default(),
         }
     }
@@ -264,8 +273,23 @@ impl<R: Rule> RuleGraph<R> {
     ) -> Result<RuleGraph<R>, String> {
         let (root, _) = self.find_root(param_inputs, product)?;
 
+        Ok(RuleGraph {
+            queries: self.queries.clone(),
+            rule_dependency_edges: self.reachable_edges(vec![root])?,
+            rules_by_product: self.rules_by_product.clone(),
+            unreachable_rules: Vec::default(),
+        })
+    }
+
+    ///
+    /// Collect the edges of all entries which are transitively reachable from the given entries.
+    ///
+    fn reachable_edges<I: IntoIterator<Item = Intern<EntryWithDeps<R>>>>(
+        &self,
+        roots: I,
+    ) -> Result<RuleDependencyEdges<R>, String> {
         // Walk the graph, starting from root entries.
-        let mut entry_stack: Vec<_> = vec![root];
+        let mut entry_stack: Vec<_> = roots.into_iter().collect();
         let mut reachable = HashMap::default();
         while let Some(entry) = entry_stack.pop() {
             if reachable.contains_key(&entry) {
@@ -276,19 +300,14 @@ impl<R: Rule> RuleGraph<R> {
                 reachable.insert(entry, edges.clone());
 
                 entry_stack.extend(edges.all_dependencies().filter_map(|e| match e.as_ref() {
-                    Entry::WithDeps(e) => Some(e),
+                    Entry::WithDeps(e) => Some(*e),
                     _ => None,
                 }));
             } else {
                 return Err(format!("Unknown entry in RuleGraph: {entry:?}"));
             }
         }
-
-        Ok(RuleGraph {
-            queries: self.queries.clone(),
-            rule_dependency_edges: reachable,
-            unreachable_rules: Vec::default(),
-        })
+        Ok(reachable)
     }
 
     ///
@@ -424,9 +443,9 @@ impl<R: Rule> RuleGraph<R> {
     /// TODO: It's not clear what is preventing `Node` implementations from ending up with non-Inner
     /// entries, but it would be good to make it typesafe instead.
     ///
-    pub fn edges_for_inner(&self, entry: &Entry<R>) -> Option<RuleEdges<R>> {
+    pub fn edges_for_inner(&self, entry: &Entry<R>) -> Option<&RuleEdges<R>> {
         if let Entry::WithDeps(ref e) = entry {
-            self.rule_dependency_edges.get(e).cloned()
+            self.rule_dependency_edges.get(e)
         } else {
             panic!("not an inner entry! {entry:?}")
         }
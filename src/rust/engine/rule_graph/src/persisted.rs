@@ -0,0 +1,433 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use fnv::{FnvHashMap as HashMap, FnvHashSet as HashSet};
+use indexmap::IndexSet;
+use internment::Intern;
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+
+use crate::rules::{CallSignature, DependencyKey, Query, Rule, RuleId, TypeId};
+use crate::{
+    Entry, EntryWithDeps, Reentry, RootEntry, RuleDependencyEdges, RuleEdges, RuleEntry, RuleGraph,
+    RulesByProduct,
+};
+
+///
+/// A RuleGraph in a form which may be decoded by another process: types are identified by their
+/// `TypeId::stable_name`, rules by their `RuleId`, and entries by their index in `entries`.
+///
+#[derive(Serialize, Deserialize)]
+struct PersistedRuleGraph {
+    rules: Vec<PersistedRule>,
+    entries: Vec<PersistedEntryWithDeps>,
+    // The edges of the entry at each index of `entries`.
+    edges: Vec<Vec<(PersistedDependencyKey, PersistedEntry)>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PersistedRule {
+    id: String,
+    product: String,
+    // Everything about the rule which affects how it is solved: see `PersistedRule::new`.
+    description: String,
+}
+
+impl PersistedRule {
+    fn new<R: Rule>(rule: &R) -> PersistedRule {
+        let mut masked_params = rule
+            .masked_params()
+            .iter()
+            .map(TypeId::stable_name)
+            .collect::<Vec<_>>();
+        masked_params.sort();
+        let dependency_keys = rule
+            .dependency_keys(0)
+            .into_iter()
+            .map(PersistedDependencyKey::new)
+            .collect::<Vec<_>>();
+        PersistedRule {
+            id: rule.id().to_string(),
+            product: rule.product().stable_name(),
+            description: format!(
+                "{} from {dependency_keys:?} masking {masked_params:?} (require_reachable: {})",
+                rule.product().stable_name(),
+                rule.require_reachable()
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedQuery {
+    product: String,
+    params: Vec<String>,
+}
+
+impl PersistedQuery {
+    fn new<T: TypeId>(query: &Query<T>) -> PersistedQuery {
+        PersistedQuery {
+            product: query.product.stable_name(),
+            params: query.params.iter().map(TypeId::stable_name).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+enum PersistedEntryWithDeps {
+    Root(PersistedQuery),
+    Rule {
+        params: Vec<String>,
+        rule: String,
+        explicit_args_arity: u16,
+    },
+    Reentry {
+        params: Vec<String>,
+        query: PersistedQuery,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+enum PersistedEntry {
+    Param(String),
+    WithDeps(usize),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedDependencyKey {
+    call_signature: Option<(String, u16)>,
+    product: String,
+    provided_params: Vec<String>,
+    in_scope_params: Option<Vec<String>>,
+}
+
+impl PersistedDependencyKey {
+    fn new<T: TypeId>(dependency_key: &DependencyKey<T>) -> PersistedDependencyKey {
+        let names = |types: &SmallVec<[T; 2]>| {
+            let mut names = types.iter().map(TypeId::stable_name).collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+        PersistedDependencyKey {
+            call_signature: dependency_key
+                .call_signature
+                .as_ref()
+                .map(|cs| (cs.rule_id.to_string(), cs.explicit_args_arity)),
+            product: dependency_key.product.stable_name(),
+            provided_params: names(&dependency_key.provided_params),
+            in_scope_params: dependency_key.in_scope_params.as_ref().map(names),
+        }
+    }
+}
+
+///
+/// Resolves the names of a PersistedRuleGraph to the types and rules of the current process.
+///
+struct Resolver<'a, R: Rule> {
+    // The types with each name, or None if more than one type has the name.
+    types: HashMap<String, Option<R::TypeId>>,
+    // The rules which are unchanged since the graph was persisted, by id.
+    rules: HashMap<String, &'a R>,
+}
+
+impl<'a, R: Rule> Resolver<'a, R> {
+    fn new(
+        persisted_rules: &[PersistedRule],
+        rules: &'a IndexSet<R>,
+        queries: &IndexSet<Query<R::TypeId>>,
+    ) -> Resolver<'a, R> {
+        let mut types = HashMap::default();
+        let mut add_type = |type_id: R::TypeId| {
+            types
+                .entry(type_id.stable_name())
+                .and_modify(|existing: &mut Option<R::TypeId>| {
+                    if *existing != Some(type_id) {
+                        *existing = None;
+                    }
+                })
+                .or_insert(Some(type_id));
+        };
+        for query in queries {
+            add_type(query.product);
+            query.params.iter().cloned().for_each(&mut add_type);
+        }
+        for rule in rules {
+            add_type(rule.product());
+            rule.masked_params().into_iter().for_each(&mut add_type);
+            for dependency_key in rule.dependency_keys(0) {
+                add_type(dependency_key.product);
+                dependency_key
+                    .provided_params
+                    .iter()
+                    .chain(dependency_key.in_scope_params.iter().flatten())
+                    .cloned()
+                    .for_each(&mut add_type);
+            }
+        }
+
+        let persisted_descriptions = persisted_rules
+            .iter()
+            .map(|rule| (rule.id.as_str(), rule.description.as_str()))
+            .collect::<HashMap<_, _>>();
+        let rules = rules
+            .iter()
+            .filter(|rule| {
+                let description = PersistedRule::new(*rule).description;
+                persisted_descriptions.get(rule.id().to_string().as_str())
+                    == Some(&description.as_str())
+            })
+            .map(|rule| (rule.id().to_string(), rule))
+            .collect();
+
+        Resolver { types, rules }
+    }
+
+    fn type_id(&self, name: &str) -> Option<R::TypeId> {
+        self.types.get(name).cloned().flatten()
+    }
+
+    fn type_ids<'n, I: IntoIterator<Item = &'n String>>(&self, names: I) -> Option<Vec<R::TypeId>> {
+        names.into_iter().map(|name| self.type_id(name)).collect()
+    }
+
+    fn query(&self, query: &PersistedQuery) -> Option<Query<R::TypeId>> {
+        Some(Query::new(
+            self.type_id(&query.product)?,
+            self.type_ids(&query.params)?,
+        ))
+    }
+
+    fn entry_with_deps(&self, entry: &PersistedEntryWithDeps) -> Option<EntryWithDeps<R>> {
+        Some(match entry {
+            PersistedEntryWithDeps::Root(query) => {
+                EntryWithDeps::Root(RootEntry(self.query(query)?))
+            }
+            PersistedEntryWithDeps::Rule {
+                params,
+                rule,
+                explicit_args_arity,
+            } => EntryWithDeps::Rule(RuleEntry {
+                params: self.type_ids(params)?.into_iter().collect(),
+                rule: (*self.rules.get(rule.as_str())?).clone(),
+                explicit_args_arity: *explicit_args_arity,
+            }),
+            PersistedEntryWithDeps::Reentry { params, query } => EntryWithDeps::Reentry(Reentry {
+                params: self.type_ids(params)?.into_iter().collect(),
+                query: self.query(query)?,
+            }),
+        })
+    }
+
+    fn dependency_key(
+        &self,
+        dependency_key: &PersistedDependencyKey,
+    ) -> Option<DependencyKey<R::TypeId>> {
+        // NB: The params of a DependencyKey are sorted by the builder methods, in the order of the
+        // types of this process.
+        let mut resolved = DependencyKey::new(self.type_id(&dependency_key.product)?)
+            .provided_params(self.type_ids(&dependency_key.provided_params)?);
+        if let Some(in_scope_params) = &dependency_key.in_scope_params {
+            resolved = resolved.in_scope_params(self.type_ids(in_scope_params)?);
+        }
+        resolved.call_signature =
+            dependency_key
+                .call_signature
+                .as_ref()
+                .map(|(rule_id, explicit_args_arity)| CallSignature {
+                    rule_id: RuleId::new(rule_id),
+                    explicit_args_arity: *explicit_args_arity,
+                });
+        Some(resolved)
+    }
+}
+
+impl<R: Rule> RuleGraph<R> {
+    ///
+    /// Encode this RuleGraph so that it may be decoded (via `RuleGraph::decode`) by another process,
+    /// as the previous RuleGraph of `RuleGraph::new_incremental`.
+    ///
+    pub fn encode(&self) -> Result<Vec<u8>, String> {
+        let entries = self.rule_dependency_edges.keys().collect::<Vec<_>>();
+        let indexes = entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| (**entry, index))
+            .collect::<HashMap<_, _>>();
+        let mut persisted_entries = Vec::with_capacity(entries.len());
+        let mut persisted_edges = Vec::with_capacity(entries.len());
+        for entry in entries {
+            persisted_entries.push(match entry.as_ref() {
+                EntryWithDeps::Root(RootEntry(query)) => {
+                    PersistedEntryWithDeps::Root(PersistedQuery::new(query))
+                }
+                EntryWithDeps::Rule(RuleEntry {
+                    params,
+                    rule,
+                    explicit_args_arity,
+                }) => PersistedEntryWithDeps::Rule {
+                    params: params.iter().map(TypeId::stable_name).collect(),
+                    rule: rule.id().to_string(),
+                    explicit_args_arity: *explicit_args_arity,
+                },
+                EntryWithDeps::Reentry(Reentry { params, query }) => {
+                    PersistedEntryWithDeps::Reentry {
+                        params: params.iter().map(TypeId::stable_name).collect(),
+                        query: PersistedQuery::new(query),
+                    }
+                }
+            });
+            persisted_edges.push(
+                self.rule_dependency_edges[entry]
+                    .dependencies
+                    .iter()
+                    .map(|(dependency_key, dependency)| {
+                        let dependency = match dependency.as_ref() {
+                            Entry::Param(type_id) => PersistedEntry::Param(type_id.stable_name()),
+                            Entry::WithDeps(entry) => {
+                                PersistedEntry::WithDeps(*indexes.get(entry).ok_or_else(|| {
+                                    format!("Unknown entry in RuleGraph: {entry:?}")
+                                })?)
+                            }
+                        };
+                        Ok((PersistedDependencyKey::new(dependency_key), dependency))
+                    })
+                    .collect::<Result<Vec<_>, String>>()?,
+            );
+        }
+
+        let persisted = PersistedRuleGraph {
+            rules: self
+                .rules_by_product
+                .values()
+                .flatten()
+                .map(PersistedRule::new)
+                .collect(),
+            entries: persisted_entries,
+            edges: persisted_edges,
+        };
+        bincode::serialize(&persisted).map_err(|e| format!("Failed to encode RuleGraph: {e}"))
+    }
+
+    ///
+    /// Decode a RuleGraph which was encoded by `RuleGraph::encode` (possibly by another process),
+    /// for use as the previous RuleGraph of `RuleGraph::new_incremental` with the given Rules and
+    /// Queries.
+    ///
+    /// The decoded RuleGraph contains only the solved Queries whose entries can all be resolved to
+    /// the given Rules and their types: a Query whose solution involved a Rule which has since been
+    /// removed or modified is left out, and so will be solved from scratch.
+    ///
+    pub fn decode(
+        bytes: &[u8],
+        rules: &IndexSet<R>,
+        queries: &IndexSet<Query<R::TypeId>>,
+    ) -> Result<RuleGraph<R>, String> {
+        let persisted: PersistedRuleGraph =
+            bincode::deserialize(bytes).map_err(|e| format!("Failed to decode RuleGraph: {e}"))?;
+        let resolver = Resolver::new(&persisted.rules, rules, queries);
+
+        // Resolve each entry and its edges: an entry is None if either cannot be resolved.
+        let entries = persisted
+            .entries
+            .iter()
+            .map(|entry| resolver.entry_with_deps(entry).map(Intern::new))
+            .collect::<Vec<_>>();
+        let mut rule_dependency_edges = RuleDependencyEdges::default();
+        for (entry, edges) in entries.iter().zip(&persisted.edges) {
+            let Some(entry) = entry else {
+                continue;
+            };
+            let dependencies = edges
+                .iter()
+                .map(|(dependency_key, dependency)| {
+                    let dependency = match dependency {
+                        PersistedEntry::Param(name) => Entry::Param(resolver.type_id(name)?),
+                        PersistedEntry::WithDeps(index) => {
+                            Entry::WithDeps((*entries.get(*index)?)?)
+                        }
+                    };
+                    Some((
+                        resolver.dependency_key(dependency_key)?,
+                        Intern::new(dependency),
+                    ))
+                })
+                .collect::<Option<HashMap<_, _>>>();
+            if let Some(dependencies) = dependencies {
+                rule_dependency_edges.insert(*entry, RuleEdges { dependencies });
+            }
+        }
+
+        // Keep only the roots whose dependencies were all resolved.
+        let roots = rule_dependency_edges
+            .keys()
+            .filter(|entry| matches!(entry.as_ref(), EntryWithDeps::Root(_)))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut graph = RuleGraph {
+            queries: Vec::default(),
+            rule_dependency_edges,
+            rules_by_product: RulesByProduct::default(),
+            unreachable_rules: Vec::default(),
+        };
+        let mut reachable = RuleDependencyEdges::default();
+        let mut queries = Vec::new();
+        for root in roots {
+            if let Ok(edges) = graph.reachable_edges(vec![root]) {
+                if let EntryWithDeps::Root(RootEntry(query)) = root.as_ref() {
+                    queries.push(query.clone());
+                }
+                reachable.extend(edges);
+            }
+        }
+        graph.queries = queries;
+        graph.rule_dependency_edges = reachable;
+        graph.rules_by_product = Self::decode_rules_by_product(&persisted.rules, &resolver, rules);
+        Ok(graph)
+    }
+
+    ///
+    /// The rules of the persisted graph, grouped by product type. The rules of a product are only
+    /// the given (current) rules for it if those are identical to the persisted rules: otherwise
+    /// they are empty, so that the rules for the product compare as modified.
+    ///
+    fn decode_rules_by_product(
+        persisted_rules: &[PersistedRule],
+        resolver: &Resolver<R>,
+        rules: &IndexSet<R>,
+    ) -> RulesByProduct<R> {
+        let mut persisted_by_product = HashMap::<&str, HashSet<&str>>::default();
+        for rule in persisted_rules {
+            persisted_by_product
+                .entry(rule.product.as_str())
+                .or_default()
+                .insert(rule.id.as_str());
+        }
+        let mut current_by_product = RulesByProduct::<R>::default();
+        for rule in rules {
+            current_by_product
+                .entry(rule.product())
+                .or_default()
+                .insert(rule.clone());
+        }
+
+        persisted_by_product
+            .into_iter()
+            .filter_map(|(product, rule_ids)| {
+                let product = resolver.type_id(product)?;
+                let current = current_by_product.remove(&product).unwrap_or_default();
+                let unchanged = current.len() == rule_ids.len()
+                    && current
+                        .iter()
+                        .all(|rule| resolver.rules.contains_key(&rule.id().to_string()));
+                Some((
+                    product,
+                    if unchanged {
+                        current
+                    } else {
+                        HashSet::default()
+                    },
+                ))
+            })
+            .collect()
+    }
+}
@@ -22,6 +22,12 @@ pub trait TypeId:
     fn display<I>(type_ids: I) -> String
     where
         I: Iterator<Item = Self>;
+
+    ///
+    /// A name for this type which is stable across processes: used to identify it in a persisted
+    /// RuleGraph.
+    ///
+    fn stable_name(&self) -> String;
 }
 
 // Identifies a specific Rule when called by name.
@@ -1,11 +1,12 @@
 // Copyright 2022 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 use std::fmt;
+use std::sync::Arc;
 
 use indexmap::{indexset, IndexSet};
 
 use crate::builder::combinations_of_one;
-use crate::{DependencyKey, Palette, Query, RuleGraph, RuleId};
+use crate::{DependencyKey, Entry, Palette, Query, RuleGraph, RuleGraphCache, RuleId};
 
 #[test]
 fn combinations_of_one_test() {
@@ -1065,6 +1066,124 @@ fn full_scale_target() {
     graph.validate_reachability().unwrap();
 }
 
+#[test]
+fn incremental_identical() {
+    let rules = indexset![Rule::new("a", "a_from_b", vec![DependencyKey::new("b")])];
+    let queries = indexset![Query::new("a", vec!["b"])];
+    let cache = RuleGraphCache::new();
+    let first = cache.get_or_solve(rules.clone(), queries.clone()).unwrap();
+    let second = cache.get_or_solve(rules, queries).unwrap();
+
+    assert!(Arc::ptr_eq(&first, &second));
+}
+
+#[test]
+fn incremental_modified() {
+    let rules = indexset![
+        Rule::new("a", "a_from_b", vec![DependencyKey::new("b")]),
+        Rule::new("c", "c_from_d", vec![DependencyKey::new("d")]),
+    ];
+    let queries = indexset![Query::new("a", vec!["b"]), Query::new("c", vec!["d"])];
+    let previous = RuleGraph::new(rules, queries.clone()).unwrap();
+
+    // Adding a singleton source of `c` should cause its Query to be re-solved, while the Query for
+    // `a` is reused.
+    let rules = indexset![
+        Rule::new("a", "a_from_b", vec![DependencyKey::new("b")]),
+        Rule::new("c", "c_from_d", vec![DependencyKey::new("d")]),
+        Rule::new("c", "c_singleton", vec![]),
+    ];
+    let graph = RuleGraph::new_incremental(rules, queries, &previous).unwrap();
+
+    assert_eq!("a_from_b", root_rule_name(&graph, vec!["b"], "a"));
+    assert_eq!("c_singleton", root_rule_name(&graph, vec!["d"], "c"));
+}
+
+#[test]
+fn incremental_ambiguity() {
+    let rules = indexset![
+        Rule::new("a", "a_from_b", vec![DependencyKey::new("b")]),
+        Rule::new("x", "x_from_y", vec![DependencyKey::new("y")]),
+    ];
+    let queries = indexset![Query::new("a", vec!["b", "c"]), Query::new("x", vec!["y"])];
+    let previous = RuleGraph::new(rules, queries.clone()).unwrap();
+
+    let rules = indexset![
+        Rule::new("a", "a_from_b", vec![DependencyKey::new("b")]),
+        Rule::new("a", "a_from_c", vec![DependencyKey::new("c")]),
+        Rule::new("x", "x_from_y", vec![DependencyKey::new("y")]),
+    ];
+
+    assert!(RuleGraph::new_incremental(rules, queries, &previous)
+        .err()
+        .unwrap()
+        .contains("Encountered 1 rule graph error:\n  Too many"));
+}
+
+#[test]
+fn persisted_modified() {
+    let rules = indexset![
+        Rule::new("a", "a_from_b", vec![DependencyKey::new("b")]),
+        Rule::new("c", "c_from_d", vec![DependencyKey::new("d")]),
+    ];
+    let queries = indexset![Query::new("a", vec!["b"]), Query::new("c", vec!["d"])];
+    let persisted = RuleGraph::new(rules, queries.clone())
+        .unwrap()
+        .encode()
+        .unwrap();
+
+    // A new cache (e.g. in a new process) solves incrementally against the persisted graph.
+    let rules = indexset![
+        Rule::new("a", "a_from_b", vec![DependencyKey::new("b")]),
+        Rule::new("c", "c_from_d", vec![DependencyKey::new("d")]),
+        Rule::new("c", "c_singleton", vec![]),
+    ];
+    let cache = RuleGraphCache::new();
+    let (graph, solved) = cache
+        .get_or_solve_persisted(rules, queries, Some(&persisted))
+        .unwrap();
+
+    assert!(solved);
+    assert_eq!("a_from_b", root_rule_name(&graph, vec!["b"], "a"));
+    assert_eq!("c_singleton", root_rule_name(&graph, vec!["d"], "c"));
+}
+
+#[test]
+fn persisted_omits_modified_rules() {
+    let rules = indexset![
+        Rule::new("a", "a_from_b", vec![DependencyKey::new("b")]),
+        Rule::new("c", "c_from_d", vec![DependencyKey::new("d")]),
+    ];
+    let queries = indexset![Query::new("a", vec!["b"]), Query::new("c", vec!["d"])];
+    let persisted = RuleGraph::new(rules, queries.clone())
+        .unwrap()
+        .encode()
+        .unwrap();
+
+    // The root whose rule has been modified is not decoded, while the other one is.
+    let rules = indexset![
+        Rule::new("a", "a_from_b", vec![DependencyKey::new("b")]).masked_params(vec!["d"]),
+        Rule::new("c", "c_from_d", vec![DependencyKey::new("d")]),
+    ];
+    let decoded = RuleGraph::decode(&persisted, &rules, &queries).unwrap();
+
+    assert!(decoded.find_root_edges(vec!["b"], "a").is_err());
+    assert_eq!("c_from_d", root_rule_name(&decoded, vec!["d"], "c"));
+}
+
+fn root_rule_name(
+    graph: &RuleGraph<Rule>,
+    params: Vec<&'static str>,
+    product: &'static str,
+) -> &'static str {
+    let edges = graph.find_root_edges(params, product).unwrap();
+    let entry = edges.entry_for(&DependencyKey::new(product)).unwrap();
+    match entry.as_ref() {
+        Entry::WithDeps(entry) => entry.rule().unwrap().name,
+        e => panic!("Expected a rule for {product}, got: {e:?}"),
+    }
+}
+
 impl super::TypeId for &'static str {
     fn display<I>(type_ids: I) -> String
     where
@@ -1072,6 +1191,10 @@ impl super::TypeId for &'static str {
     {
         type_ids.collect::<Vec<_>>().join("+")
     }
+
+    fn stable_name(&self) -> String {
+        self.to_string()
+    }
 }
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
@@ -4,8 +4,11 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fmt::Debug;
+use std::fs::File;
 use std::io::{self, Read};
+use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::{self, Duration};
 
@@ -13,11 +16,18 @@ use bytes::{BufMut, Bytes};
 use hashing::{sync_verified_copy, AgedFingerprint, Digest, Fingerprint, FINGERPRINT_SIZE};
 use lmdb::{
     self, Cursor, Database, DatabaseFlags, Environment, EnvironmentCopyFlags, EnvironmentFlags,
-    RwTransaction, Transaction, WriteFlags,
+    RoTransaction, RwTransaction, Transaction, WriteFlags,
 };
-use log::trace;
+use log::{debug, info, trace, warn};
+use nix::errno::Errno;
+use nix::fcntl::{flock, FlockArg};
+use parking_lot::{RwLock, RwLockReadGuard};
 use tempfile::TempDir;
 
+mod sqlite;
+
+pub use crate::sqlite::ShardedSqlite;
+
 ///
 /// The lease time is relatively short, because we in general would like things to be
 /// garbage collectible. Leases are set on creation, and extended by pantsd for things that
@@ -28,6 +38,13 @@ use tempfile::TempDir;
 ///
 pub const DEFAULT_LEASE_TIME: Duration = Duration::from_secs(2 * 60 * 60);
 
+fn lease_until_secs_since_epoch(lease_time: Duration) -> u64 {
+    let now_since_epoch = time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .expect("Surely you're not before the unix epoch?");
+    (now_since_epoch + lease_time).as_secs()
+}
+
 const VERSIONED_FINGERPRINT_SIZE: usize = FINGERPRINT_SIZE + 1;
 
 /// VersionedFingerprint is a byte buffer one longer than the number of bytes stored in a
@@ -91,6 +108,11 @@ pub struct ShardedLmdb {
     lmdbs: HashMap<EnvironmentId, (EnvironmentId, PathBuf, Arc<Environment>, Database, Database)>,
     root_path: PathBuf,
     max_size_per_shard: usize,
+    // The size that the map of each shard may grow to when it is full.
+    max_map_size_per_shard: usize,
+    // Held for reading by every transaction, and for writing while resizing a map, which LMDB only
+    // allows while no transactions are active.
+    resize_lock: Arc<RwLock<()>>,
     executor: task_executor::Executor,
     lease_time: Duration,
     shard_count: u8,
@@ -111,9 +133,16 @@ impl ShardedLmdb {
     // for the mmap; in theory it should be possible not to bound this, but in practice we see travis
     // occasionally fail tests because it's unable to allocate virtual memory if we set this too high,
     // and we have too many tests running concurrently or close together.
+    //
+    // When a shard fills up, its map is grown until the databases together reach
+    // max_size * max_size_growth_factor: a factor of 1 disables growth.
+    //
+    // If the store at root_path was created with a different shard_count, its entries are first
+    // migrated into the new shards.
     pub fn new(
         root_path: PathBuf,
         max_size: usize,
+        max_size_growth_factor: usize,
         executor: task_executor::Executor,
         lease_time: Duration,
         shard_count: u8,
@@ -123,36 +152,22 @@ impl ShardedLmdb {
                 "The shard_count must be a power of two: got {shard_count}."
             ));
         }
+        if max_size_growth_factor == 0 {
+            return Err("The max_size_growth_factor must be at least 1.".to_owned());
+        }
 
         let max_size_per_shard = max_size / (shard_count as usize);
-        // We select which shard to use by masking to select only the relevant number of high order bits
-        // from the high order byte of each stored key.
-        let shard_fingerprint_mask = {
-            // Create a mask of the appropriate width.
-            let mask_width = shard_count.trailing_zeros();
-            let mut mask = 0_u8;
-            for _ in 0..mask_width {
-                mask <<= 1;
-                mask |= 1;
-            }
-            // Then move it into the high order bits.
-            mask.rotate_left(Self::shard_shift(shard_count) as u32)
-        };
+        let max_map_size_per_shard = max_size_per_shard.saturating_mul(max_size_growth_factor);
+        let shard_fingerprint_mask = Self::shard_fingerprint_mask(shard_count);
 
         trace!("Initializing ShardedLmdb at root {:?}", root_path);
+        Self::migrate_shards(&root_path, max_map_size_per_shard, shard_count)?;
         let mut lmdbs = HashMap::new();
 
         for (env, dir, environment_id) in
             ShardedLmdb::envs(&root_path, max_size_per_shard, shard_count)?
         {
-            let content_database = env
-                .create_db(Some("content-versioned"), DatabaseFlags::empty())
-                .map_err(|e| format!("Error creating/opening content database at {dir:?}: {e}"))?;
-
-            let lease_database = env
-                .create_db(Some("leases-versioned"), DatabaseFlags::empty())
-                .map_err(|e| format!("Error creating/opening content database at {dir:?}: {e}"))?;
-
+            let (content_database, lease_database) = Self::create_dbs(&env, &dir)?;
             lmdbs.insert(
                 environment_id,
                 (
@@ -169,6 +184,8 @@ impl ShardedLmdb {
             lmdbs,
             root_path,
             max_size_per_shard,
+            max_map_size_per_shard,
+            resize_lock: Arc::default(),
             executor,
             lease_time,
             shard_count,
@@ -176,6 +193,67 @@ impl ShardedLmdb {
         })
     }
 
+    ///
+    /// Opens an existing store (which may be in use by other processes, or on a read-only
+    /// filesystem) in read-only mode, with whichever shard count it was created with.
+    ///
+    /// Writes to a read-only store fail: it is intended to be shared between many consumers, and
+    /// written to only by its owner.
+    ///
+    pub fn new_read_only(
+        root_path: PathBuf,
+        executor: task_executor::Executor,
+        lease_time: Duration,
+    ) -> Result<ShardedLmdb, String> {
+        let shards = Self::existing_shards(&root_path)?;
+        let shard_count = u8::try_from(shards.len())
+            .ok()
+            .filter(|shard_count| {
+                shard_count.count_ones() == 1
+                    && shards
+                        .iter()
+                        .cloned()
+                        .eq((0..*shard_count).map(|b| root_path.join(format!("{b:x}"))))
+            })
+            .ok_or_else(|| format!("There is no complete store at {root_path:?} to read from."))?;
+        let shard_shift = Self::shard_shift(shard_count);
+
+        trace!("Initializing read-only ShardedLmdb at root {:?}", root_path);
+        let mut lmdbs = HashMap::new();
+        for (b, dir) in (0..shard_count).zip(shards) {
+            let env = Self::make_read_only_env(&dir)?;
+            let open_db = |name| {
+                env.open_db(Some(name))
+                    .map_err(|e| format!("Error opening {name} database at {dir:?}: {e}"))
+            };
+            let content_database = open_db("content-versioned")?;
+            let lease_database = open_db("leases-versioned")?;
+            let environment_id = EnvironmentId(b.rotate_left(shard_shift as u32));
+            lmdbs.insert(
+                environment_id,
+                (
+                    environment_id,
+                    dir,
+                    Arc::new(env),
+                    content_database,
+                    lease_database,
+                ),
+            );
+        }
+
+        Ok(ShardedLmdb {
+            lmdbs,
+            root_path,
+            max_size_per_shard: 0,
+            max_map_size_per_shard: 0,
+            resize_lock: Arc::default(),
+            executor,
+            lease_time,
+            shard_count,
+            shard_fingerprint_mask: Self::shard_fingerprint_mask(shard_count),
+        })
+    }
+
     ///
     /// Return the left shift value that will place the relevant portion of a byte (for the given
     /// shard count, which is asserted in the constructor to be a power of two) into the high order
@@ -186,6 +264,176 @@ impl ShardedLmdb {
         8 - mask_width
     }
 
+    ///
+    /// Return the mask that selects which shard to use (for the given shard count) from the high
+    /// order byte of each stored key: i.e., only the relevant number of its high order bits.
+    ///
+    fn shard_fingerprint_mask(shard_count: u8) -> u8 {
+        // Create a mask of the appropriate width.
+        let mask_width = shard_count.trailing_zeros();
+        let mut mask = 0_u8;
+        for _ in 0..mask_width {
+            mask <<= 1;
+            mask |= 1;
+        }
+        // Then move it into the high order bits.
+        mask.rotate_left(Self::shard_shift(shard_count) as u32)
+    }
+
+    // First Database is content, second is leases.
+    fn create_dbs(env: &Environment, dir: &Path) -> Result<(Database, Database), String> {
+        let content_database = env
+            .create_db(Some("content-versioned"), DatabaseFlags::empty())
+            .map_err(|e| format!("Error creating/opening content database at {dir:?}: {e}"))?;
+
+        let lease_database = env
+            .create_db(Some("leases-versioned"), DatabaseFlags::empty())
+            .map_err(|e| format!("Error creating/opening content database at {dir:?}: {e}"))?;
+
+        Ok((content_database, lease_database))
+    }
+
+    ///
+    /// Return the directories of the shards which already exist below the given root, in order.
+    ///
+    fn existing_shards(root_path: &Path) -> Result<Vec<PathBuf>, String> {
+        let entries = match std::fs::read_dir(root_path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(format!("Error listing the store at {root_path:?}: {e}")),
+        };
+        let mut shards = Vec::new();
+        for entry in entries {
+            let entry =
+                entry.map_err(|e| format!("Error listing the store at {root_path:?}: {e}"))?;
+            let Some(shard) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| u8::from_str_radix(name, 16).ok())
+            else {
+                continue;
+            };
+            if entry.path().join("data.mdb").is_file() {
+                shards.push((shard, entry.path()));
+            }
+        }
+        shards.sort();
+        Ok(shards.into_iter().map(|(_, dir)| dir).collect())
+    }
+
+    ///
+    /// If the store at the given root was created with a different shard count (i.e., it does not
+    /// consist of exactly the shards for the given count), copies all of its entries into new
+    /// shards for the given count, and then replaces the old shards with them.
+    ///
+    /// The new shards are staged in a temporary directory below the root, so an interrupted
+    /// migration leaves the old shards in place to be migrated again. Concurrent migrations of the
+    /// same store (by other processes) are serialized by a lock file.
+    ///
+    fn migrate_shards(
+        root_path: &Path,
+        max_size_per_shard: usize,
+        shard_count: u8,
+    ) -> Result<(), String> {
+        let is_migrated = |existing: &[PathBuf]| {
+            existing.is_empty()
+                || existing
+                    .iter()
+                    .cloned()
+                    .eq((0..shard_count).map(|b| root_path.join(format!("{b:x}"))))
+        };
+        if is_migrated(&Self::existing_shards(root_path)?) {
+            return Ok(());
+        }
+
+        let lock_path = root_path.join("migration.lock");
+        let lock = File::create(&lock_path)
+            .map_err(|e| format!("Error creating lock file at {lock_path:?}: {e}"))?;
+        flock(lock.as_raw_fd(), FlockArg::LockExclusive)
+            .map_err(|e| format!("Error locking {lock_path:?}: {e}"))?;
+        // Another process may have migrated the store while this one was waiting for the lock.
+        let existing = Self::existing_shards(root_path)?;
+        if is_migrated(&existing) {
+            return Ok(());
+        }
+        info!(
+            "Migrating the store at {root_path:?} from {} to {shard_count} shards.",
+            existing.len()
+        );
+
+        let staging = TempDir::new_in(root_path)
+            .map_err(|e| format!("Error creating a directory to migrate {root_path:?}: {e}"))?;
+        let new_shards = Self::envs(&staging.path().join("new"), max_size_per_shard, shard_count)?
+            .into_iter()
+            .map(|(env, dir, environment_id)| {
+                let (content_database, lease_database) = Self::create_dbs(&env, &dir)?;
+                Ok((environment_id, (env, dir, content_database, lease_database)))
+            })
+            .collect::<Result<HashMap<_, _>, String>>()?;
+        let shard_fingerprint_mask = Self::shard_fingerprint_mask(shard_count);
+
+        for old_dir in &existing {
+            let old_env = ShardedLmdb::make_env(old_dir, max_size_per_shard)?;
+            let (old_content_database, old_lease_database) = Self::create_dbs(&old_env, old_dir)?;
+            let txn = old_env
+                .begin_ro_txn()
+                .map_err(|e| format!("Error beginning transaction to migrate {old_dir:?}: {e}"))?;
+            for (old_database, is_content) in
+                [(old_content_database, true), (old_lease_database, false)]
+            {
+                let mut cursor = txn
+                    .open_ro_cursor(old_database)
+                    .map_err(|err| format!("Failed to open lmdb read cursor: {err}"))?;
+                let mut items_by_env = HashMap::new();
+                for item in cursor.iter() {
+                    let (key, value) =
+                        item.map_err(|err| format!("Failed to advance lmdb read cursor: {err}"))?;
+                    items_by_env
+                        .entry(EnvironmentId(key[0] & shard_fingerprint_mask))
+                        .or_insert_with(Vec::new)
+                        .push((key, value));
+                }
+                for (environment_id, items) in items_by_env {
+                    let (env, dir, content_database, lease_database) = &new_shards[&environment_id];
+                    let database = if is_content {
+                        *content_database
+                    } else {
+                        *lease_database
+                    };
+                    env.begin_rw_txn()
+                        .and_then(|mut new_txn| {
+                            for (key, value) in items {
+                                new_txn.put(database, &key, &value, WriteFlags::empty())?;
+                            }
+                            new_txn.commit()
+                        })
+                        .map_err(|e| format!("Error migrating {old_dir:?} to {dir:?}: {e}"))?;
+                }
+            }
+        }
+        // Close the new shards before they are moved into place.
+        let new_dirs = new_shards
+            .into_values()
+            .map(|(_, dir, _, _)| dir)
+            .collect::<Vec<_>>();
+
+        let old_root = staging.path().join("old");
+        std::fs::create_dir_all(&old_root)
+            .map_err(|e| format!("Error making directory at {old_root:?}: {e}"))?;
+        for old_dir in existing {
+            let dest = old_root.join(old_dir.file_name().unwrap());
+            std::fs::rename(&old_dir, &dest)
+                .map_err(|e| format!("Error moving {old_dir:?} to {dest:?}: {e}"))?;
+        }
+        for new_dir in new_dirs {
+            let dest = root_path.join(new_dir.file_name().unwrap());
+            std::fs::rename(&new_dir, &dest)
+                .map_err(|e| format!("Error moving {new_dir:?} to {dest:?}: {e}"))?;
+        }
+        debug!("Migrated the store at {root_path:?} to {shard_count} shards.");
+        Ok(())
+    }
+
     fn envs(
         root_path: &Path,
         max_size_per_shard: usize,
@@ -248,6 +496,25 @@ impl ShardedLmdb {
             .map_err(|e| format!("Error making env for store at {dir:?}: {e}"))
     }
 
+    fn make_read_only_env(dir: &Path) -> Result<Environment, String> {
+        let open = |flags| {
+            Environment::new()
+                .set_flags(EnvironmentFlags::READ_ONLY | EnvironmentFlags::NO_TLS | flags)
+                .set_max_dbs(2)
+                .open(dir)
+        };
+        match open(EnvironmentFlags::empty()) {
+            // Readers must still record themselves in the lock file of the store, which cannot be
+            // written on a read-only filesystem. In that case the store is read without locking,
+            // which is only safe while its owner is not writing to it.
+            Err(lmdb::Error::Other(errno)) if errno == Errno::EROFS as i32 => {
+                open(EnvironmentFlags::NO_LOCK)
+            }
+            res => res,
+        }
+        .map_err(|e| format!("Error opening read-only store at {dir:?}: {e}"))
+    }
+
     // First Database is content, second is leases.
     pub fn get(&self, fingerprint: &Fingerprint) -> (Arc<Environment>, Database, Database) {
         let (_, _, env, db1, db2) = self.get_raw(&fingerprint.0);
@@ -268,6 +535,92 @@ impl ShardedLmdb {
             .collect()
     }
 
+    ///
+    /// Begins a read transaction in the given Environment, which is returned along with a guard
+    /// that prevents its map from being resized until the transaction has been dropped.
+    ///
+    fn begin_ro_txn<'env>(
+        &self,
+        env: &'env Environment,
+    ) -> Result<(RwLockReadGuard<'_, ()>, RoTransaction<'env>), lmdb::Error> {
+        loop {
+            let resize_guard = self.resize_lock.read();
+            match env.begin_ro_txn() {
+                Ok(txn) => return Ok((resize_guard, txn)),
+                Err(lmdb::Error::MapResized) => {
+                    std::mem::drop(resize_guard);
+                    self.adopt_map_size(env)?;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    ///
+    /// Runs the given write transaction in the given Environment. If the map of the Environment is
+    /// full, it is grown (up to `max_map_size_per_shard`) and the transaction is retried.
+    ///
+    fn write<T, E: From<lmdb::Error> + AsLmdbError>(
+        &self,
+        env: &Environment,
+        mut txn: impl FnMut() -> Result<T, E>,
+    ) -> Result<T, E> {
+        loop {
+            let (map_size, res) = {
+                let _resize_guard = self.resize_lock.read();
+                (env.info()?.map_size(), txn())
+            };
+            match res.as_ref().err().and_then(AsLmdbError::as_lmdb_error) {
+                Some(lmdb::Error::MapFull) => {
+                    if !self.grow_map(env, map_size)? {
+                        return res;
+                    }
+                }
+                Some(lmdb::Error::MapResized) => self.adopt_map_size(env)?,
+                _ => return res,
+            }
+        }
+    }
+
+    ///
+    /// Doubles the size of the full map of the given Environment (up to `max_map_size_per_shard`),
+    /// returning false if it could not be grown any further.
+    ///
+    fn grow_map(&self, env: &Environment, full_map_size: usize) -> Result<bool, lmdb::Error> {
+        let _resize_guard = self.resize_lock.write();
+        if env.info()?.map_size() > full_map_size {
+            // Another thread grew the map while this one was waiting to.
+            return Ok(true);
+        }
+        if full_map_size >= self.max_map_size_per_shard {
+            warn!(
+                "A shard of the store at {:?} is full at {full_map_size} bytes, and cannot grow \
+                 any further: consider increasing the max size of the store, or the factor by \
+                 which it may grow.",
+                self.root_path
+            );
+            return Ok(false);
+        }
+        let map_size = full_map_size
+            .saturating_mul(2)
+            .min(self.max_map_size_per_shard);
+        debug!(
+            "Growing a shard of the store at {:?} from {full_map_size} to {map_size} bytes.",
+            self.root_path
+        );
+        env.set_map_size(map_size)?;
+        Ok(true)
+    }
+
+    ///
+    /// Adopts the size of the map of the given Environment after another process has grown it.
+    ///
+    fn adopt_map_size(&self, env: &Environment) -> Result<(), lmdb::Error> {
+        let _resize_guard = self.resize_lock.write();
+        // A size of zero adopts the size which is currently recorded in the Environment.
+        env.set_map_size(0)
+    }
+
     pub async fn remove(&self, fingerprint: Fingerprint) -> Result<bool, String> {
         let store = self.clone();
         self.executor
@@ -276,7 +629,8 @@ impl ShardedLmdb {
                     let effective_key =
                         VersionedFingerprint::new(fingerprint, ShardedLmdb::SCHEMA_VERSION);
                     let (env, db, lease_database) = store.get(&fingerprint);
-                    let del_res = env.begin_rw_txn().and_then(|mut txn| {
+                    let del_res = store.write(&env, || {
+                        let mut txn = env.begin_rw_txn()?;
                         txn.del(db, &effective_key, None)?;
                         txn.del(lease_database, &effective_key, None)
                             .or_else(|err| match err {
@@ -340,8 +694,9 @@ impl ShardedLmdb {
                     // Open and commit a Transaction per Environment. Since we never have more than one
                     // Transaction open at a time, we don't have to worry about ordering.
                     for (_, (env, db, batch)) in items_by_env {
-                        env.begin_ro_txn()
-                            .and_then(|txn| {
+                        store
+                            .begin_ro_txn(&env)
+                            .and_then(|(_resize_guard, txn)| {
                                 for effective_key in &batch {
                                     let get_res = txn.get(db, &effective_key);
                                     match get_res {
@@ -382,7 +737,7 @@ impl ShardedLmdb {
                 move || {
                     let mut fingerprints = Vec::new();
                     for (env, database, lease_database) in &store.all_lmdbs() {
-                        let txn = env.begin_ro_txn().map_err(|err| {
+                        let (_resize_guard, txn) = store.begin_ro_txn(env).map_err(|err| {
                             format!("Error beginning transaction to garbage collect: {err}")
                         })?;
                         let mut cursor = txn
@@ -484,8 +839,9 @@ impl ShardedLmdb {
                     // Open and commit a Transaction per Environment. Since we never have more than one
                     // Transaction open at a time, we don't have to worry about ordering.
                     for (_, (env, db, lease_database, batch)) in items_by_env {
-                        env.begin_rw_txn()
-                            .and_then(|mut txn| {
+                        store
+                            .write(&env, || {
+                                let mut txn = env.begin_rw_txn()?;
                                 for (effective_key, bytes) in &batch {
                                     let put_res = txn.put(
                                         db,
@@ -502,7 +858,7 @@ impl ShardedLmdb {
                                         store.lease_inner(
                                             lease_database,
                                             effective_key,
-                                            store.lease_until_secs_since_epoch(),
+                                            lease_until_secs_since_epoch(store.lease_time),
                                             &mut txn,
                                         )?;
                                     }
@@ -557,7 +913,7 @@ impl ShardedLmdb {
                             ShardedLmdb::SCHEMA_VERSION,
                         );
                         let (env, db, lease_database) = store.get(&expected_digest.hash);
-                        let put_res: Result<(), StoreError> =
+                        let put_res: Result<(), StoreError> = store.write(&env, || {
                             env.begin_rw_txn()
                                 .map_err(StoreError::Lmdb)
                                 .and_then(|mut txn| {
@@ -588,13 +944,14 @@ impl ShardedLmdb {
                                         store.lease_inner(
                                             lease_database,
                                             &effective_key,
-                                            store.lease_until_secs_since_epoch(),
+                                            lease_until_secs_since_epoch(store.lease_time),
                                             &mut txn,
                                         )?;
                                     }
                                     txn.commit()?;
                                     Ok(())
-                                });
+                                })
+                        });
 
                         match put_res {
                             Ok(()) => return Ok(()),
@@ -623,23 +980,46 @@ impl ShardedLmdb {
     }
 
     pub async fn lease(&self, fingerprint: Fingerprint) -> Result<(), String> {
+        self.lease_for(fingerprint, self.lease_time).await
+    }
+
+    ///
+    /// Leases the given fingerprint for at least `lease_time` from now. An existing lease which
+    /// expires later than that is left as it is, so that entries which were leased for longer than
+    /// the default lease time are not garbage collected early.
+    ///
+    pub async fn lease_for(
+        &self,
+        fingerprint: Fingerprint,
+        lease_time: Duration,
+    ) -> Result<(), String> {
         let store = self.clone();
         self.executor
             .spawn_blocking(
                 move || {
-                    let until_secs_since_epoch: u64 = store.lease_until_secs_since_epoch();
+                    let until_secs_since_epoch: u64 = lease_until_secs_since_epoch(lease_time);
                     let (env, _, lease_database) = store.get(&fingerprint);
-                    env.begin_rw_txn()
-                        .and_then(|mut txn| {
-                            store.lease_inner(
-                                lease_database,
-                                &VersionedFingerprint::new(
-                                    fingerprint,
-                                    ShardedLmdb::SCHEMA_VERSION,
-                                ),
-                                until_secs_since_epoch,
-                                &mut txn,
-                            )?;
+                    let key = VersionedFingerprint::new(fingerprint, ShardedLmdb::SCHEMA_VERSION);
+                    store
+                        .write(&env, || {
+                            let mut txn = env.begin_rw_txn()?;
+                            let leased_until = match txn.get(lease_database, &key.as_ref()) {
+                                Ok(b) => {
+                                    let mut array = [0_u8; 8];
+                                    array.copy_from_slice(b);
+                                    u64::from_le_bytes(array)
+                                }
+                                Err(lmdb::Error::NotFound) => 0,
+                                Err(e) => return Err(e),
+                            };
+                            if leased_until < until_secs_since_epoch {
+                                store.lease_inner(
+                                    lease_database,
+                                    &key,
+                                    until_secs_since_epoch,
+                                    &mut txn,
+                                )?;
+                            }
                             txn.commit()
                         })
                         .map_err(|e| format!("Error leasing {fingerprint:?}: {e}"))
@@ -664,13 +1044,6 @@ impl ShardedLmdb {
         )
     }
 
-    fn lease_until_secs_since_epoch(&self) -> u64 {
-        let now_since_epoch = time::SystemTime::now()
-            .duration_since(time::UNIX_EPOCH)
-            .expect("Surely you're not before the unix epoch?");
-        (now_since_epoch + self.lease_time).as_secs()
-    }
-
     pub async fn load_bytes_with<
         T: Send + 'static,
         F: FnMut(&[u8]) -> Result<T, String> + Send + Sync + 'static,
@@ -685,8 +1058,8 @@ impl ShardedLmdb {
             .spawn_blocking(
                 move || {
                     let (env, db, _) = store.get(&fingerprint);
-                    let ro_txn = env
-                        .begin_ro_txn()
+                    let (_resize_guard, ro_txn) = store
+                        .begin_ro_txn(&env)
                         .map_err(|err| format!("Failed to begin read transaction: {err}"))?;
                     match ro_txn.get(db, &effective_key) {
                         Ok(bytes) => f(bytes).map(Some),
@@ -736,6 +1109,242 @@ impl ShardedLmdb {
     }
 }
 
+///
+/// The database that the shards of a `ShardedStore` are stored in.
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum StoreBackend {
+    #[default]
+    Lmdb,
+    Sqlite,
+}
+
+impl StoreBackend {
+    pub const ALL: [StoreBackend; 2] = [StoreBackend::Lmdb, StoreBackend::Sqlite];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StoreBackend::Lmdb => "lmdb",
+            StoreBackend::Sqlite => "sqlite",
+        }
+    }
+}
+
+impl fmt::Display for StoreBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for StoreBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        StoreBackend::ALL
+            .into_iter()
+            .find(|backend| backend.name() == s)
+            .ok_or_else(|| {
+                format!("Unsupported store backend `{s}`: expected one of `lmdb` or `sqlite`.")
+            })
+    }
+}
+
+///
+/// A sharded store of either backend, which is selected when it is created. See `ShardedLmdb`
+/// for the semantics of each of its methods, which both backends share.
+///
+#[derive(Debug, Clone)]
+pub enum ShardedStore {
+    Lmdb(ShardedLmdb),
+    Sqlite(ShardedSqlite),
+}
+
+impl ShardedStore {
+    pub fn new(
+        backend: StoreBackend,
+        root_path: PathBuf,
+        max_size: usize,
+        max_size_growth_factor: usize,
+        executor: task_executor::Executor,
+        lease_time: Duration,
+        shard_count: u8,
+    ) -> Result<ShardedStore, String> {
+        match backend {
+            StoreBackend::Lmdb => ShardedLmdb::new(
+                root_path,
+                max_size,
+                max_size_growth_factor,
+                executor,
+                lease_time,
+                shard_count,
+            )
+            .map(ShardedStore::Lmdb),
+            StoreBackend::Sqlite => ShardedSqlite::new(
+                root_path,
+                max_size,
+                max_size_growth_factor,
+                executor,
+                lease_time,
+                shard_count,
+            )
+            .map(ShardedStore::Sqlite),
+        }
+    }
+
+    ///
+    /// Opens an existing store in read-only mode, with whichever backend and shard count it was
+    /// created with.
+    ///
+    pub fn new_read_only(
+        root_path: PathBuf,
+        executor: task_executor::Executor,
+        lease_time: Duration,
+    ) -> Result<ShardedStore, String> {
+        if ShardedSqlite::existing_shards(&root_path)?.is_empty() {
+            ShardedLmdb::new_read_only(root_path, executor, lease_time).map(ShardedStore::Lmdb)
+        } else {
+            ShardedSqlite::new_read_only(root_path, executor, lease_time).map(ShardedStore::Sqlite)
+        }
+    }
+
+    pub fn backend(&self) -> StoreBackend {
+        match self {
+            ShardedStore::Lmdb(_) => StoreBackend::Lmdb,
+            ShardedStore::Sqlite(_) => StoreBackend::Sqlite,
+        }
+    }
+
+    pub async fn remove(&self, fingerprint: Fingerprint) -> Result<bool, String> {
+        match self {
+            ShardedStore::Lmdb(store) => store.remove(fingerprint).await,
+            ShardedStore::Sqlite(store) => store.remove(fingerprint).await,
+        }
+    }
+
+    pub async fn exists(&self, fingerprint: Fingerprint) -> Result<bool, String> {
+        match self {
+            ShardedStore::Lmdb(store) => store.exists(fingerprint).await,
+            ShardedStore::Sqlite(store) => store.exists(fingerprint).await,
+        }
+    }
+
+    pub async fn exists_batch(
+        &self,
+        fingerprints: Vec<Fingerprint>,
+    ) -> Result<HashSet<Fingerprint>, String> {
+        match self {
+            ShardedStore::Lmdb(store) => store.exists_batch(fingerprints).await,
+            ShardedStore::Sqlite(store) => store.exists_batch(fingerprints).await,
+        }
+    }
+
+    pub async fn all_fingerprints(&self) -> Result<Vec<AgedFingerprint>, String> {
+        match self {
+            ShardedStore::Lmdb(store) => store.all_fingerprints().await,
+            ShardedStore::Sqlite(store) => store.all_fingerprints().await,
+        }
+    }
+
+    pub async fn store_bytes(
+        &self,
+        fingerprint: Fingerprint,
+        bytes: Bytes,
+        initial_lease: bool,
+    ) -> Result<Fingerprint, String> {
+        match self {
+            ShardedStore::Lmdb(store) => store.store_bytes(fingerprint, bytes, initial_lease).await,
+            ShardedStore::Sqlite(store) => {
+                store.store_bytes(fingerprint, bytes, initial_lease).await
+            }
+        }
+    }
+
+    pub async fn store_bytes_batch(
+        &self,
+        items: Vec<(Fingerprint, Bytes)>,
+        initial_lease: bool,
+    ) -> Result<(), String> {
+        match self {
+            ShardedStore::Lmdb(store) => store.store_bytes_batch(items, initial_lease).await,
+            ShardedStore::Sqlite(store) => store.store_bytes_batch(items, initial_lease).await,
+        }
+    }
+
+    pub async fn store<F, R>(
+        &self,
+        initial_lease: bool,
+        data_is_immutable: bool,
+        expected_digest: Digest,
+        data_provider: F,
+    ) -> Result<(), String>
+    where
+        R: Read + Debug,
+        F: Fn() -> Result<R, io::Error> + Send + 'static,
+    {
+        match self {
+            ShardedStore::Lmdb(store) => {
+                store
+                    .store(
+                        initial_lease,
+                        data_is_immutable,
+                        expected_digest,
+                        data_provider,
+                    )
+                    .await
+            }
+            ShardedStore::Sqlite(store) => {
+                store
+                    .store(
+                        initial_lease,
+                        data_is_immutable,
+                        expected_digest,
+                        data_provider,
+                    )
+                    .await
+            }
+        }
+    }
+
+    pub async fn lease(&self, fingerprint: Fingerprint) -> Result<(), String> {
+        match self {
+            ShardedStore::Lmdb(store) => store.lease(fingerprint).await,
+            ShardedStore::Sqlite(store) => store.lease(fingerprint).await,
+        }
+    }
+
+    pub async fn lease_for(
+        &self,
+        fingerprint: Fingerprint,
+        lease_time: Duration,
+    ) -> Result<(), String> {
+        match self {
+            ShardedStore::Lmdb(store) => store.lease_for(fingerprint, lease_time).await,
+            ShardedStore::Sqlite(store) => store.lease_for(fingerprint, lease_time).await,
+        }
+    }
+
+    pub async fn load_bytes_with<
+        T: Send + 'static,
+        F: FnMut(&[u8]) -> Result<T, String> + Send + Sync + 'static,
+    >(
+        &self,
+        fingerprint: Fingerprint,
+        f: F,
+    ) -> Result<Option<T>, String> {
+        match self {
+            ShardedStore::Lmdb(store) => store.load_bytes_with(fingerprint, f).await,
+            ShardedStore::Sqlite(store) => store.load_bytes_with(fingerprint, f).await,
+        }
+    }
+
+    pub fn compact(&self) -> Result<(), String> {
+        match self {
+            ShardedStore::Lmdb(store) => store.compact(),
+            ShardedStore::Sqlite(store) => store.compact(),
+        }
+    }
+}
+
 enum StoreError {
     Lmdb(lmdb::Error),
     Io(String),
@@ -754,5 +1363,27 @@ impl From<String> for StoreError {
     }
 }
 
+///
+/// An error which may have been caused by LMDB, and so may indicate that a map is full.
+///
+trait AsLmdbError {
+    fn as_lmdb_error(&self) -> Option<&lmdb::Error>;
+}
+
+impl AsLmdbError for lmdb::Error {
+    fn as_lmdb_error(&self) -> Option<&lmdb::Error> {
+        Some(self)
+    }
+}
+
+impl AsLmdbError for StoreError {
+    fn as_lmdb_error(&self) -> Option<&lmdb::Error> {
+        match self {
+            Self::Lmdb(err) => Some(err),
+            Self::Io(_) | Self::Retry(_) => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,819 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{self, Duration};
+
+use bytes::Bytes;
+use hashing::{sync_verified_copy, AgedFingerprint, Digest, Fingerprint};
+use log::{debug, info, trace};
+use nix::fcntl::{flock, FlockArg};
+use parking_lot::Mutex;
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, DatabaseName, ErrorCode, OpenFlags, OptionalExtension};
+use tempfile::TempDir;
+
+use crate::{lease_until_secs_since_epoch, EnvironmentId, ShardedLmdb, VersionedFingerprint};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS content (key BLOB PRIMARY KEY, value BLOB NOT NULL);
+    CREATE TABLE IF NOT EXISTS leases (key BLOB PRIMARY KEY, until INTEGER NOT NULL);
+";
+
+// The file extension of the database of each shard.
+const SHARD_EXTENSION: &str = "sqlite";
+
+#[derive(Debug)]
+struct Shard {
+    path: PathBuf,
+    // The connection that writes to the shard: SQLite allows a single writer at a time anyway.
+    connection: Mutex<Connection>,
+    // Idle read-only connections, which are opened on demand: reads do not wait for each other or
+    // for the writer, except while it commits.
+    readers: Mutex<Vec<Connection>>,
+}
+
+impl Shard {
+    fn new(path: PathBuf, connection: Connection) -> Shard {
+        Shard {
+            path,
+            connection: Mutex::new(connection),
+            readers: Mutex::new(Vec::new()),
+        }
+    }
+
+    ///
+    /// Calls the given function with an idle read-only connection to the shard, or with a new one
+    /// if none is idle.
+    ///
+    fn read<T>(
+        &self,
+        f: impl FnOnce(&Connection) -> Result<T, rusqlite::Error>,
+    ) -> Result<T, rusqlite::Error> {
+        let reader = self.readers.lock().pop();
+        let connection = match reader {
+            Some(connection) => connection,
+            None => ShardedSqlite::open_read_only(&self.path)?,
+        };
+        let result = f(&connection);
+        self.readers.lock().push(connection);
+        result
+    }
+}
+
+///
+/// A store with the same layout and interface as `ShardedLmdb`, but with a SQLite database per
+/// shard: entries are stored under the same versioned keys, in a `content` table and a `leases`
+/// table.
+///
+/// Unlike an LMDB map, a SQLite database grows as it is written to, so the shards do not reserve
+/// virtual memory for their max size. And SQLite supports concurrent access by other processes
+/// on network filesystems which support locking: the databases use a rollback journal rather than
+/// a write-ahead log, because the shared memory index of a WAL does not work across hosts.
+///
+#[derive(Debug, Clone)]
+pub struct ShardedSqlite {
+    shards: Arc<HashMap<EnvironmentId, Shard>>,
+    executor: task_executor::Executor,
+    lease_time: Duration,
+    shard_fingerprint_mask: u8,
+}
+
+impl ShardedSqlite {
+    ///
+    /// Creates (or opens) a store at root_path, with the arguments of `ShardedLmdb::new`: no shard
+    /// may grow beyond its share of max_size * max_size_growth_factor.
+    ///
+    pub fn new(
+        root_path: PathBuf,
+        max_size: usize,
+        max_size_growth_factor: usize,
+        executor: task_executor::Executor,
+        lease_time: Duration,
+        shard_count: u8,
+    ) -> Result<ShardedSqlite, String> {
+        if shard_count.count_ones() != 1 {
+            return Err(format!(
+                "The shard_count must be a power of two: got {shard_count}."
+            ));
+        }
+        if max_size_growth_factor == 0 {
+            return Err("The max_size_growth_factor must be at least 1.".to_owned());
+        }
+        let max_size_per_shard =
+            (max_size / (shard_count as usize)).saturating_mul(max_size_growth_factor);
+
+        trace!("Initializing ShardedSqlite at root {:?}", root_path);
+        std::fs::create_dir_all(&root_path)
+            .map_err(|err| format!("Error making directory for store at {root_path:?}: {err:?}"))?;
+        Self::migrate_shards(&root_path, max_size_per_shard, shard_count)?;
+
+        let shard_shift = ShardedLmdb::shard_shift(shard_count);
+        let mut shards = HashMap::new();
+        for b in 0..shard_count {
+            let path = Self::shard_path(&root_path, b);
+            let connection = Self::open(&path, max_size_per_shard)?;
+            shards.insert(
+                EnvironmentId(b.rotate_left(shard_shift as u32)),
+                Shard::new(path, connection),
+            );
+        }
+
+        Ok(ShardedSqlite {
+            shards: Arc::new(shards),
+            executor,
+            lease_time,
+            shard_fingerprint_mask: ShardedLmdb::shard_fingerprint_mask(shard_count),
+        })
+    }
+
+    ///
+    /// Opens an existing store in read-only mode, with whichever shard count it was created with:
+    /// see `ShardedLmdb::new_read_only`.
+    ///
+    pub fn new_read_only(
+        root_path: PathBuf,
+        executor: task_executor::Executor,
+        lease_time: Duration,
+    ) -> Result<ShardedSqlite, String> {
+        let paths = Self::existing_shards(&root_path)?;
+        let shard_count = u8::try_from(paths.len())
+            .ok()
+            .filter(|shard_count| {
+                shard_count.count_ones() == 1
+                    && paths
+                        .iter()
+                        .cloned()
+                        .eq((0..*shard_count).map(|b| Self::shard_path(&root_path, b)))
+            })
+            .ok_or_else(|| format!("There is no complete store at {root_path:?} to read from."))?;
+        let shard_shift = ShardedLmdb::shard_shift(shard_count);
+
+        trace!(
+            "Initializing read-only ShardedSqlite at root {:?}",
+            root_path
+        );
+        let mut shards = HashMap::new();
+        for (b, path) in (0..shard_count).zip(paths) {
+            let connection = Self::open_read_only(&path)
+                .map_err(|e| format!("Error opening read-only store at {path:?}: {e}"))?;
+            shards.insert(
+                EnvironmentId(b.rotate_left(shard_shift as u32)),
+                Shard::new(path, connection),
+            );
+        }
+
+        Ok(ShardedSqlite {
+            shards: Arc::new(shards),
+            executor,
+            lease_time,
+            shard_fingerprint_mask: ShardedLmdb::shard_fingerprint_mask(shard_count),
+        })
+    }
+
+    fn shard_path(root_path: &Path, b: u8) -> PathBuf {
+        root_path.join(format!("{b:x}.{SHARD_EXTENSION}"))
+    }
+
+    ///
+    /// Return the databases of the shards which already exist below the given root, in order.
+    ///
+    pub(crate) fn existing_shards(root_path: &Path) -> Result<Vec<PathBuf>, String> {
+        let entries = match std::fs::read_dir(root_path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(format!("Error listing the store at {root_path:?}: {e}")),
+        };
+        let mut shards = Vec::new();
+        for entry in entries {
+            let entry =
+                entry.map_err(|e| format!("Error listing the store at {root_path:?}: {e}"))?;
+            let Some(shard) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_suffix(&format!(".{SHARD_EXTENSION}")))
+                .and_then(|name| u8::from_str_radix(name, 16).ok())
+            else {
+                continue;
+            };
+            if entry.path().is_file() {
+                shards.push((shard, entry.path()));
+            }
+        }
+        shards.sort();
+        Ok(shards.into_iter().map(|(_, path)| path).collect())
+    }
+
+    fn open(path: &Path, max_size_per_shard: usize) -> Result<Connection, String> {
+        let open = || {
+            let connection = Connection::open(path)?;
+            // Other processes may be writing to the same store.
+            connection.busy_timeout(Duration::from_secs(60))?;
+            // NB: A WAL would allow readers to run concurrently with a commit, but it relies on
+            // shared memory, which does not work for databases on network filesystems.
+            connection.pragma_update_and_check(None, "journal_mode", "DELETE", |row| {
+                row.get::<_, String>(0)
+            })?;
+            // Like `NO_SYNC` for LMDB: a system crash may roll back the latest transactions, which
+            // is fine for a content-addressed cache.
+            connection.pragma_update(None, "synchronous", "OFF")?;
+            let page_size: usize =
+                connection.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+            let max_page_count = (max_size_per_shard / page_size).max(1);
+            connection.pragma_update_and_check(
+                None,
+                "max_page_count",
+                max_page_count as i64,
+                |row| row.get::<_, i64>(0),
+            )?;
+            connection.execute_batch(SCHEMA)?;
+            Ok::<_, rusqlite::Error>(connection)
+        };
+        open().map_err(|e| format!("Error opening store at {path:?}: {e}"))
+    }
+
+    fn open_read_only(path: &Path) -> Result<Connection, rusqlite::Error> {
+        let connection = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        // Other processes may be writing to the same store.
+        connection.busy_timeout(Duration::from_secs(60))?;
+        Ok(connection)
+    }
+
+    ///
+    /// If the store at the given root was created with a different shard count, copies all of its
+    /// entries into new shards for the given count, and then replaces the old shards with them:
+    /// see `ShardedLmdb::migrate_shards`.
+    ///
+    fn migrate_shards(
+        root_path: &Path,
+        max_size_per_shard: usize,
+        shard_count: u8,
+    ) -> Result<(), String> {
+        let is_migrated = |existing: &[PathBuf]| {
+            existing.is_empty()
+                || existing
+                    .iter()
+                    .cloned()
+                    .eq((0..shard_count).map(|b| Self::shard_path(root_path, b)))
+        };
+        if is_migrated(&Self::existing_shards(root_path)?) {
+            return Ok(());
+        }
+
+        let lock_path = root_path.join("migration.lock");
+        let lock = File::create(&lock_path)
+            .map_err(|e| format!("Error creating lock file at {lock_path:?}: {e}"))?;
+        flock(lock.as_raw_fd(), FlockArg::LockExclusive)
+            .map_err(|e| format!("Error locking {lock_path:?}: {e}"))?;
+        // Another process may have migrated the store while this one was waiting for the lock.
+        let existing = Self::existing_shards(root_path)?;
+        if is_migrated(&existing) {
+            return Ok(());
+        }
+        info!(
+            "Migrating the store at {root_path:?} from {} to {shard_count} shards.",
+            existing.len()
+        );
+
+        let staging = TempDir::new_in(root_path)
+            .map_err(|e| format!("Error creating a directory to migrate {root_path:?}: {e}"))?;
+        let new_root = staging.path().join("new");
+        std::fs::create_dir_all(&new_root)
+            .map_err(|e| format!("Error making directory at {new_root:?}: {e}"))?;
+        let shard_fingerprint_mask = ShardedLmdb::shard_fingerprint_mask(shard_count);
+        let shard_shift = ShardedLmdb::shard_shift(shard_count);
+        let new_shards = (0..shard_count)
+            .map(|b| {
+                let path = Self::shard_path(&new_root, b);
+                let connection = Self::open(&path, max_size_per_shard)?;
+                Ok((
+                    EnvironmentId(b.rotate_left(shard_shift as u32)),
+                    (path, connection),
+                ))
+            })
+            .collect::<Result<HashMap<_, _>, String>>()?;
+
+        for old_path in &existing {
+            let migrate = || {
+                let old = Connection::open_with_flags(
+                    old_path,
+                    OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+                )?;
+                for (select, insert) in [
+                    (
+                        "SELECT key, value FROM content",
+                        "INSERT OR IGNORE INTO content (key, value) VALUES (?1, ?2)",
+                    ),
+                    (
+                        "SELECT key, until FROM leases",
+                        "INSERT OR REPLACE INTO leases (key, until) VALUES (?1, ?2)",
+                    ),
+                ] {
+                    let mut statement = old.prepare(select)?;
+                    let mut rows = statement.query([])?;
+                    while let Some(row) = rows.next()? {
+                        let key = row.get_ref(0)?.as_blob()?;
+                        let (_, connection) = new_shards
+                            .get(&EnvironmentId(key[0] & shard_fingerprint_mask))
+                            .unwrap();
+                        connection.execute(insert, params![key, row.get::<_, Value>(1)?])?;
+                    }
+                }
+                Ok::<_, rusqlite::Error>(())
+            };
+            migrate().map_err(|e| format!("Error migrating {old_path:?}: {e}"))?;
+        }
+        // Close the new shards before they are moved into place.
+        let new_paths = new_shards
+            .into_values()
+            .map(|(path, _)| path)
+            .collect::<Vec<_>>();
+
+        let old_root = staging.path().join("old");
+        std::fs::create_dir_all(&old_root)
+            .map_err(|e| format!("Error making directory at {old_root:?}: {e}"))?;
+        for old_path in existing {
+            let dest = old_root.join(old_path.file_name().unwrap());
+            std::fs::rename(&old_path, &dest)
+                .map_err(|e| format!("Error moving {old_path:?} to {dest:?}: {e}"))?;
+        }
+        for new_path in new_paths {
+            let dest = root_path.join(new_path.file_name().unwrap());
+            std::fs::rename(&new_path, &dest)
+                .map_err(|e| format!("Error moving {new_path:?} to {dest:?}: {e}"))?;
+        }
+        debug!("Migrated the store at {root_path:?} to {shard_count} shards.");
+        Ok(())
+    }
+
+    fn get(&self, fingerprint: &Fingerprint) -> &Shard {
+        &self.shards[&EnvironmentId(fingerprint.0[0] & self.shard_fingerprint_mask)]
+    }
+
+    ///
+    /// Describes an error from SQLite, with a hint when a shard is full.
+    ///
+    fn describe(&self, err: rusqlite::Error) -> String {
+        match err.sqlite_error_code() {
+            Some(ErrorCode::DiskFull) => format!(
+                "{err}: a shard of the store is full: consider increasing the max size of the \
+                 store, or the factor by which it may grow."
+            ),
+            _ => err.to_string(),
+        }
+    }
+
+    pub async fn remove(&self, fingerprint: Fingerprint) -> Result<bool, String> {
+        let store = self.clone();
+        self.executor
+            .spawn_blocking(
+                move || {
+                    let effective_key =
+                        VersionedFingerprint::new(fingerprint, ShardedLmdb::SCHEMA_VERSION);
+                    let mut connection = store.get(&fingerprint).connection.lock();
+                    let del_res = connection.transaction().and_then(|txn| {
+                        let removed = txn.execute(
+                            "DELETE FROM content WHERE key = ?1",
+                            [effective_key.as_ref()],
+                        )? > 0;
+                        txn.execute(
+                            "DELETE FROM leases WHERE key = ?1",
+                            [effective_key.as_ref()],
+                        )?;
+                        txn.commit()?;
+                        Ok(removed)
+                    });
+                    del_res.map_err(|err| {
+                        format!(
+                            "Error removing versioned key {:?}: {}",
+                            effective_key.to_hex(),
+                            store.describe(err)
+                        )
+                    })
+                },
+                |e| Err(format!("`remove` task failed: {e}")),
+            )
+            .await
+    }
+
+    ///
+    /// Singular form of `Self::exists_batch`. When checking the existence of more than one item,
+    /// prefer `Self::exists_batch`.
+    ///
+    pub async fn exists(&self, fingerprint: Fingerprint) -> Result<bool, String> {
+        let exists = self.exists_batch(vec![fingerprint]).await?;
+        Ok(exists.contains(&fingerprint))
+    }
+
+    ///
+    /// Determine which of the given Fingerprints are already present in the store,
+    /// returning them as a set.
+    ///
+    pub async fn exists_batch(
+        &self,
+        fingerprints: Vec<Fingerprint>,
+    ) -> Result<HashSet<Fingerprint>, String> {
+        let store = self.clone();
+        self.executor
+            .spawn_blocking(
+                move || {
+                    // Group the items by the shard that they will be looked up in.
+                    let mut items_by_shard = HashMap::new();
+                    for fingerprint in fingerprints {
+                        items_by_shard
+                            .entry(EnvironmentId(
+                                fingerprint.0[0] & store.shard_fingerprint_mask,
+                            ))
+                            .or_insert_with(Vec::new)
+                            .push(fingerprint);
+                    }
+
+                    let mut exists = HashSet::new();
+                    for (shard_id, batch) in items_by_shard {
+                        let check = |connection: &Connection| {
+                            let mut statement = connection
+                                .prepare_cached("SELECT 1 FROM content WHERE key = ?1")?;
+                            for fingerprint in &batch {
+                                let effective_key = VersionedFingerprint::new(
+                                    *fingerprint,
+                                    ShardedLmdb::SCHEMA_VERSION,
+                                );
+                                if statement.exists([effective_key.as_ref()])? {
+                                    exists.insert(*fingerprint);
+                                }
+                            }
+                            Ok(())
+                        };
+                        store.shards[&shard_id].read(check).map_err(|e| {
+                            format!(
+                                "Error checking existence of fingerprints {batch:?}: {}",
+                                store.describe(e)
+                            )
+                        })?;
+                    }
+                    Ok(exists)
+                },
+                |e| Err(format!("`exists_batch` task failed: {e}")),
+            )
+            .await
+    }
+
+    ///
+    /// Returns all fingerprints and their ages.
+    ///
+    pub async fn all_fingerprints(&self) -> Result<Vec<AgedFingerprint>, String> {
+        let store = self.clone();
+        self.executor
+            .spawn_blocking(
+                move || {
+                    let now = time::SystemTime::now();
+                    let mut fingerprints = Vec::new();
+                    for shard in store.shards.values() {
+                        let collect = |connection: &Connection| {
+                            let mut statement = connection.prepare(
+                                "SELECT content.key, length(content.value), leases.until \
+                                 FROM content LEFT JOIN leases ON content.key = leases.key",
+                            )?;
+                            let mut rows = statement.query([])?;
+                            while let Some(row) = rows.next()? {
+                                let key = row.get_ref(0)?.as_blob()?;
+                                let size_bytes: usize = row.get(1)?;
+                                let lease_until_unix_timestamp =
+                                    row.get::<_, Option<i64>>(2)?.unwrap_or(0);
+
+                                let leased_until = time::UNIX_EPOCH
+                                    + Duration::from_secs(lease_until_unix_timestamp as u64);
+                                let expired_seconds_ago = now
+                                    .duration_since(leased_until)
+                                    .map(|t| t.as_secs())
+                                    // 0 indicates unexpired.
+                                    .unwrap_or(0);
+
+                                fingerprints.push(AgedFingerprint {
+                                    expired_seconds_ago,
+                                    fingerprint: VersionedFingerprint::from_bytes_unsafe(key)
+                                        .get_fingerprint(),
+                                    size_bytes,
+                                });
+                            }
+                            Ok(())
+                        };
+                        shard.read(collect).map_err(|e| {
+                            format!(
+                                "Error listing the store at {:?} to garbage collect: {}",
+                                shard.path,
+                                store.describe(e)
+                            )
+                        })?;
+                    }
+                    Ok(fingerprints)
+                },
+                |e| Err(format!("`all_fingerprints` task failed: {e}")),
+            )
+            .await
+    }
+
+    ///
+    /// Singular form of `Self::store_bytes_batch`. When storing more than one item in parallel,
+    /// prefer `Self::store_bytes_batch`.
+    ///
+    pub async fn store_bytes(
+        &self,
+        fingerprint: Fingerprint,
+        bytes: Bytes,
+        initial_lease: bool,
+    ) -> Result<Fingerprint, String> {
+        self.store_bytes_batch(vec![(fingerprint, bytes)], initial_lease)
+            .await?;
+        Ok(fingerprint)
+    }
+
+    ///
+    /// Store the given Bytes instances under the given Fingerprints. For large/streaming usecases,
+    /// prefer `Self::store`.
+    ///
+    pub async fn store_bytes_batch(
+        &self,
+        items: Vec<(Fingerprint, Bytes)>,
+        initial_lease: bool,
+    ) -> Result<(), String> {
+        let store = self.clone();
+        self.executor
+            .spawn_blocking(
+                move || {
+                    // Group the items by the shard that they will be stored in.
+                    let mut items_by_shard = HashMap::new();
+                    for (fingerprint, bytes) in items {
+                        items_by_shard
+                            .entry(EnvironmentId(
+                                fingerprint.0[0] & store.shard_fingerprint_mask,
+                            ))
+                            .or_insert_with(Vec::new)
+                            .push((
+                                VersionedFingerprint::new(fingerprint, ShardedLmdb::SCHEMA_VERSION),
+                                bytes,
+                            ));
+                    }
+
+                    // Commit a transaction per shard.
+                    let leased_until = lease_until_secs_since_epoch(store.lease_time) as i64;
+                    for (shard_id, batch) in items_by_shard {
+                        let mut connection = store.shards[&shard_id].connection.lock();
+                        let put_res = connection.transaction().and_then(|txn| {
+                            for (effective_key, bytes) in &batch {
+                                let inserted = txn.execute(
+                                    "INSERT OR IGNORE INTO content (key, value) VALUES (?1, ?2)",
+                                    params![effective_key.as_ref(), bytes.as_ref()],
+                                )? > 0;
+                                if inserted && initial_lease {
+                                    Self::lease_inner(&txn, effective_key, leased_until)?;
+                                }
+                            }
+                            txn.commit()
+                        });
+                        put_res.map_err(|e| {
+                            format!(
+                                "Error storing fingerprints {:?}: {}",
+                                batch
+                                    .iter()
+                                    .map(|(key, _)| key.to_hex())
+                                    .collect::<Vec<_>>(),
+                                store.describe(e)
+                            )
+                        })?;
+                    }
+                    Ok(())
+                },
+                |e| Err(format!("`store_bytes_batch` task failed: {e}")),
+            )
+            .await
+    }
+
+    ///
+    /// Stores the given Read instance under its computed digest in two passes: see
+    /// `ShardedLmdb::store`.
+    ///
+    pub async fn store<F, R>(
+        &self,
+        initial_lease: bool,
+        data_is_immutable: bool,
+        expected_digest: Digest,
+        data_provider: F,
+    ) -> Result<(), String>
+    where
+        R: Read + Debug,
+        F: Fn() -> Result<R, io::Error> + Send + 'static,
+    {
+        let store = self.clone();
+        self.executor
+            .spawn_blocking(
+                move || {
+                    let effective_key =
+                        VersionedFingerprint::new(expected_digest.hash, ShardedLmdb::SCHEMA_VERSION);
+                    let mut connection = store.get(&expected_digest.hash).connection.lock();
+                    let mut attempts = 0;
+                    loop {
+                        let txn = connection
+                            .transaction()
+                            .map_err(|e| format!("Error storing {expected_digest:?}: {e}"))?;
+                        let put_res = (|| {
+                            // Reserve space for the content, and then copy into it.
+                            let inserted = txn.execute(
+                                "INSERT OR IGNORE INTO content (key, value) VALUES (?1, zeroblob(?2))",
+                                params![effective_key.as_ref(), expected_digest.size_bytes as i64],
+                            )? > 0;
+                            if !inserted {
+                                return Ok(None);
+                            }
+                            let mut writer = txn.blob_open(
+                                DatabaseName::Main,
+                                "content",
+                                "value",
+                                txn.last_insert_rowid(),
+                                false,
+                            )?;
+                            let mut read = data_provider()
+                                .map_err(|e| format!("Failed to read: {e}"))
+                                .map_err(StoreError::Io)?;
+                            let matches = sync_verified_copy(
+                                expected_digest,
+                                data_is_immutable,
+                                &mut read,
+                                &mut writer,
+                            )
+                            .map_err(|e| {
+                                StoreError::Io(format!("Failed to copy from {read:?}: {e:?}"))
+                            })?;
+                            std::mem::drop(writer);
+                            Ok(Some((matches, read)))
+                        })();
+
+                        match put_res {
+                            Ok(None) => return Ok(()),
+                            Ok(Some((true, _))) => {
+                                if initial_lease {
+                                    Self::lease_inner(
+                                        &txn,
+                                        &effective_key,
+                                        lease_until_secs_since_epoch(store.lease_time) as i64,
+                                    )
+                                    .map_err(|e| {
+                                        format!(
+                                            "Error storing {expected_digest:?}: {}",
+                                            store.describe(e)
+                                        )
+                                    })?;
+                                }
+                                return txn.commit().map_err(|e| {
+                                    format!(
+                                        "Error storing {expected_digest:?}: {}",
+                                        store.describe(e)
+                                    )
+                                });
+                            }
+                            Ok(Some((false, read))) => {
+                                // Input changed during reading: maybe retry.
+                                let msg = format!("Input {read:?} changed while reading.");
+                                log::debug!("{}", msg);
+                                std::mem::drop(txn);
+                                if attempts > 10 {
+                                    return Err(msg);
+                                }
+                                attempts += 1;
+                            }
+                            Err(StoreError::Sqlite(err)) => {
+                                return Err(format!(
+                                    "Error storing {expected_digest:?}: {}",
+                                    store.describe(err)
+                                ))
+                            }
+                            Err(StoreError::Io(err)) => {
+                                return Err(format!("Error storing {expected_digest:?}: {err}"))
+                            }
+                        }
+                    }
+                },
+                |e| Err(format!("`store` task failed: {e}")),
+            )
+            .await
+    }
+
+    pub async fn lease(&self, fingerprint: Fingerprint) -> Result<(), String> {
+        self.lease_for(fingerprint, self.lease_time).await
+    }
+
+    ///
+    /// Leases the given fingerprint for at least `lease_time` from now, without shortening an
+    /// existing lease: see `ShardedLmdb::lease_for`.
+    ///
+    pub async fn lease_for(
+        &self,
+        fingerprint: Fingerprint,
+        lease_time: Duration,
+    ) -> Result<(), String> {
+        let store = self.clone();
+        self.executor
+            .spawn_blocking(
+                move || {
+                    let until_secs_since_epoch = lease_until_secs_since_epoch(lease_time) as i64;
+                    let key = VersionedFingerprint::new(fingerprint, ShardedLmdb::SCHEMA_VERSION);
+                    let connection = store.get(&fingerprint).connection.lock();
+                    connection
+                        .execute(
+                            "INSERT INTO leases (key, until) VALUES (?1, ?2) \
+                             ON CONFLICT (key) DO UPDATE SET until = max(until, excluded.until)",
+                            params![key.as_ref(), until_secs_since_epoch],
+                        )
+                        .map(|_| ())
+                        .map_err(|e| {
+                            format!("Error leasing {fingerprint:?}: {}", store.describe(e))
+                        })
+                },
+                |e| Err(format!("`lease` task failed: {e}")),
+            )
+            .await
+    }
+
+    fn lease_inner(
+        connection: &Connection,
+        versioned_fingerprint: &VersionedFingerprint,
+        until_secs_since_epoch: i64,
+    ) -> Result<(), rusqlite::Error> {
+        connection.execute(
+            "INSERT OR REPLACE INTO leases (key, until) VALUES (?1, ?2)",
+            params![versioned_fingerprint.as_ref(), until_secs_since_epoch],
+        )?;
+        Ok(())
+    }
+
+    pub async fn load_bytes_with<
+        T: Send + 'static,
+        F: FnMut(&[u8]) -> Result<T, String> + Send + Sync + 'static,
+    >(
+        &self,
+        fingerprint: Fingerprint,
+        mut f: F,
+    ) -> Result<Option<T>, String> {
+        let store = self.clone();
+        let effective_key = VersionedFingerprint::new(fingerprint, ShardedLmdb::SCHEMA_VERSION);
+        self.executor
+            .spawn_blocking(
+                move || {
+                    let load = |connection: &Connection| {
+                        let mut statement = connection
+                            .prepare_cached("SELECT value FROM content WHERE key = ?1")?;
+                        statement
+                            .query_row([effective_key.as_ref()], |row| {
+                                Ok(f(row.get_ref(0)?.as_blob()?))
+                            })
+                            .optional()
+                    };
+                    match store.get(&fingerprint).read(load) {
+                        Ok(loaded) => loaded.transpose(),
+                        Err(err) => Err(format!(
+                            "Error loading versioned key {:?}: {}",
+                            effective_key.to_hex(),
+                            store.describe(err),
+                        )),
+                    }
+                },
+                |e| Err(format!("`load_bytes_with` task failed: {e}")),
+            )
+            .await
+    }
+
+    ///
+    /// Rebuilds the database of each shard, to return the space of removed entries to the
+    /// filesystem.
+    ///
+    pub fn compact(&self) -> Result<(), String> {
+        for shard in self.shards.values() {
+            shard
+                .connection
+                .lock()
+                .execute_batch("VACUUM")
+                .map_err(|e| format!("Error compacting store at {:?}: {e}", shard.path))?;
+        }
+        Ok(())
+    }
+}
+
+enum StoreError {
+    Sqlite(rusqlite::Error),
+    Io(String),
+}
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(err: rusqlite::Error) -> Self {
+        Self::Sqlite(err)
+    }
+}
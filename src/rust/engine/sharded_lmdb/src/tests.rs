@@ -1,20 +1,23 @@
 // Copyright 2022 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 use std::collections::HashMap;
+use std::time::Duration;
 
 use bytes::{Buf, Bytes};
-use hashing::Digest;
+use hashing::{Digest, Fingerprint};
+use lmdb::Transaction;
 use parking_lot::Mutex;
 use task_executor::Executor;
 use tempfile::TempDir;
 
-use crate::{ShardedLmdb, DEFAULT_LEASE_TIME};
+use crate::{ShardedLmdb, ShardedStore, StoreBackend, VersionedFingerprint, DEFAULT_LEASE_TIME};
 
 fn new_store(shard_count: u8) -> (ShardedLmdb, TempDir) {
     let tempdir = TempDir::new().unwrap();
     let s = ShardedLmdb::new(
         tempdir.path().to_owned(),
         15_000_000,
+        1,
         Executor::new(),
         DEFAULT_LEASE_TIME,
         shard_count,
@@ -92,6 +95,335 @@ async fn store_failure() {
     assert!(result.is_err());
 }
 
+#[tokio::test]
+async fn lease_for() {
+    let (s, _tempdir) = new_store(1);
+    let digest = Digest::of_bytes(&bytes(0));
+    s.store_bytes_batch(vec![(digest.hash, bytes(0))], true)
+        .await
+        .unwrap();
+    let initial = leased_until(&s, digest.hash);
+
+    let week = Duration::from_secs(7 * 24 * 60 * 60);
+    s.lease_for(digest.hash, week).await.unwrap();
+    let extended = leased_until(&s, digest.hash);
+    assert!(extended >= initial + (week - DEFAULT_LEASE_TIME).as_secs());
+
+    // Leasing for the default lease time does not shorten the longer lease.
+    s.lease(digest.hash).await.unwrap();
+    assert_eq!(leased_until(&s, digest.hash), extended);
+}
+
+#[tokio::test]
+async fn store_grows_when_full() {
+    let store_all = |max_size_growth_factor| async move {
+        let tempdir = TempDir::new().unwrap();
+        let s = ShardedLmdb::new(
+            tempdir.path().to_owned(),
+            1024 * 1024,
+            max_size_growth_factor,
+            Executor::new(),
+            DEFAULT_LEASE_TIME,
+            1,
+        )
+        .unwrap();
+        // Twice the max size of the store.
+        for content in 0..20 {
+            let bytes = Bytes::from(vec![content; 100 * 1024]);
+            s.store_bytes(Digest::of_bytes(&bytes).hash, bytes, true)
+                .await?;
+        }
+        Ok::<_, String>(())
+    };
+
+    let err = store_all(1).await.unwrap_err();
+    assert!(err.contains("MDB_MAP_FULL"), "{err}");
+    store_all(4).await.unwrap();
+}
+
+#[tokio::test]
+async fn shard_count_migration() {
+    let tempdir = TempDir::new().unwrap();
+    let new_store = |shard_count| {
+        ShardedLmdb::new(
+            tempdir.path().to_owned(),
+            15_000_000,
+            1,
+            Executor::new(),
+            DEFAULT_LEASE_TIME,
+            shard_count,
+        )
+        .unwrap()
+    };
+    let digests = (0..32).map(|content| Digest::of_bytes(&bytes(content)));
+
+    let s = new_store(4);
+    s.store_bytes_batch(
+        (0..32)
+            .map(|content| (Digest::of_bytes(&bytes(content)).hash, bytes(content)))
+            .collect(),
+        true,
+    )
+    .await
+    .unwrap();
+    let leases = digests
+        .clone()
+        .map(|digest| leased_until(&s, digest.hash))
+        .collect::<Vec<_>>();
+    std::mem::drop(s);
+
+    for shard_count in [2, 8] {
+        let s = new_store(shard_count);
+        for ((content, digest), lease) in (0..32).zip(digests.clone()).zip(&leases) {
+            let loaded = s
+                .load_bytes_with(digest.hash, |b| Ok(Bytes::copy_from_slice(b)))
+                .await
+                .unwrap();
+            assert_eq!(loaded, Some(bytes(content)));
+            assert_eq!(leased_until(&s, digest.hash), *lease);
+        }
+    }
+
+    // Only the shards for the last shard count remain.
+    let mut shards = std::fs::read_dir(tempdir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name != "migration.lock")
+        .collect::<Vec<_>>();
+    shards.sort();
+    assert_eq!(shards, (0..8).map(|b| format!("{b:x}")).collect::<Vec<_>>());
+}
+
+fn new_sharded_store(backend: StoreBackend, root: &TempDir, shard_count: u8) -> ShardedStore {
+    ShardedStore::new(
+        backend,
+        root.path().to_owned(),
+        15_000_000,
+        1,
+        Executor::new(),
+        DEFAULT_LEASE_TIME,
+        shard_count,
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn backends_store_load_and_remove() {
+    for backend in StoreBackend::ALL {
+        let tempdir = TempDir::new().unwrap();
+        let s = new_sharded_store(backend, &tempdir, 4);
+        let items = (0..16)
+            .map(|content| (Digest::of_bytes(&bytes(content)).hash, bytes(content)))
+            .collect::<Vec<_>>();
+        s.store_bytes_batch(items.clone(), true).await.unwrap();
+        // Storing existing entries again is a no-op.
+        s.store_bytes_batch(items.clone(), true).await.unwrap();
+
+        let missing = Digest::of_bytes(&bytes(16)).hash;
+        let exists = s
+            .exists_batch(items.iter().map(|(fingerprint, _)| *fingerprint).collect())
+            .await
+            .unwrap();
+        assert_eq!(exists.len(), 16, "{backend}");
+        assert!(!s.exists(missing).await.unwrap(), "{backend}");
+        for (fingerprint, content) in &items {
+            let loaded = s
+                .load_bytes_with(*fingerprint, |b| Ok(Bytes::copy_from_slice(b)))
+                .await
+                .unwrap();
+            assert_eq!(loaded.as_ref(), Some(content), "{backend}");
+        }
+        assert_eq!(
+            s.load_bytes_with(missing, |b| Ok(b.len())).await.unwrap(),
+            None,
+            "{backend}"
+        );
+
+        let fingerprints = s.all_fingerprints().await.unwrap();
+        assert_eq!(fingerprints.len(), 16, "{backend}");
+        assert!(fingerprints
+            .iter()
+            .all(|f| f.size_bytes == 100 && f.expired_seconds_ago == 0));
+
+        let (fingerprint, _) = items[0];
+        assert!(s.remove(fingerprint).await.unwrap(), "{backend}");
+        assert!(!s.remove(fingerprint).await.unwrap(), "{backend}");
+        assert!(!s.exists(fingerprint).await.unwrap(), "{backend}");
+        assert_eq!(s.all_fingerprints().await.unwrap().len(), 15, "{backend}");
+        s.compact().unwrap();
+    }
+}
+
+#[tokio::test]
+async fn backends_store_verified_copies() {
+    for backend in StoreBackend::ALL {
+        let tempdir = TempDir::new().unwrap();
+        let s = new_sharded_store(backend, &tempdir, 1);
+
+        // Produces Readers that change during the first two reads, but stabilize on the third.
+        let contents = Mutex::new(vec![bytes(0), bytes(1), bytes(2), bytes(2)].into_iter());
+        let digest = Digest::of_bytes(&bytes(2));
+        s.store(true, false, digest, move || {
+            Ok(contents.lock().next().unwrap().reader())
+        })
+        .await
+        .unwrap();
+        let loaded = s
+            .load_bytes_with(digest.hash, |b| Ok(Bytes::copy_from_slice(b)))
+            .await
+            .unwrap();
+        assert_eq!(loaded, Some(bytes(2)), "{backend}");
+        // Storing an existing entry does not read it again.
+        s.store(true, false, digest, || -> std::io::Result<&[u8]> {
+            panic!("Should not have been read.")
+        })
+        .await
+        .unwrap();
+
+        // Produces Readers that never stabilize.
+        let contents = Mutex::new((0..100).map(bytes));
+        let result = s
+            .store(true, false, Digest::of_bytes(&bytes(101)), move || {
+                Ok(contents.lock().next().unwrap().reader())
+            })
+            .await;
+        assert!(result.is_err(), "{backend}");
+        assert!(!s.exists(Digest::of_bytes(&bytes(101)).hash).await.unwrap());
+    }
+}
+
+#[tokio::test]
+async fn backends_lease() {
+    for backend in StoreBackend::ALL {
+        let tempdir = TempDir::new().unwrap();
+        let s = new_sharded_store(backend, &tempdir, 1);
+        let digest = Digest::of_bytes(&bytes(0));
+        s.store_bytes(digest.hash, bytes(0), false).await.unwrap();
+        let expired_seconds_ago = |s: ShardedStore| async move {
+            s.all_fingerprints().await.unwrap()[0].expired_seconds_ago
+        };
+        // An entry which was never leased is expired.
+        assert_ne!(expired_seconds_ago(s.clone()).await, 0, "{backend}");
+
+        s.lease_for(digest.hash, Duration::from_secs(7 * 24 * 60 * 60))
+            .await
+            .unwrap();
+        assert_eq!(expired_seconds_ago(s.clone()).await, 0, "{backend}");
+        // Leasing for no time at all does not shorten the longer lease.
+        s.lease_for(digest.hash, Duration::ZERO).await.unwrap();
+        std::thread::sleep(Duration::from_secs(1));
+        assert_eq!(expired_seconds_ago(s.clone()).await, 0, "{backend}");
+    }
+}
+
+#[tokio::test]
+async fn sqlite_shard_count_migration() {
+    let tempdir = TempDir::new().unwrap();
+    let items = (0..32)
+        .map(|content| (Digest::of_bytes(&bytes(content)).hash, bytes(content)))
+        .collect::<Vec<_>>();
+
+    let s = new_sharded_store(StoreBackend::Sqlite, &tempdir, 4);
+    s.store_bytes_batch(items.clone(), true).await.unwrap();
+    std::mem::drop(s);
+
+    for shard_count in [2, 8] {
+        let s = new_sharded_store(StoreBackend::Sqlite, &tempdir, shard_count);
+        for (fingerprint, content) in &items {
+            let loaded = s
+                .load_bytes_with(*fingerprint, |b| Ok(Bytes::copy_from_slice(b)))
+                .await
+                .unwrap();
+            assert_eq!(loaded.as_ref(), Some(content));
+        }
+        assert!(s
+            .all_fingerprints()
+            .await
+            .unwrap()
+            .iter()
+            .all(|f| f.expired_seconds_ago == 0));
+    }
+
+    // Only the shards for the last shard count remain.
+    let mut shards = std::fs::read_dir(tempdir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".sqlite"))
+        .collect::<Vec<_>>();
+    shards.sort();
+    assert_eq!(
+        shards,
+        (0..8).map(|b| format!("{b:x}.sqlite")).collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn read_only_stores_are_opened_with_their_backend() {
+    for backend in StoreBackend::ALL {
+        let tempdir = TempDir::new().unwrap();
+        let digest = Digest::of_bytes(&bytes(0));
+        {
+            let s = new_sharded_store(backend, &tempdir, 2);
+            s.store_bytes(digest.hash, bytes(0), true).await.unwrap();
+        }
+
+        let s = ShardedStore::new_read_only(
+            tempdir.path().to_owned(),
+            Executor::new(),
+            DEFAULT_LEASE_TIME,
+        )
+        .unwrap();
+        assert_eq!(s.backend(), backend);
+        let loaded = s
+            .load_bytes_with(digest.hash, |b| Ok(Bytes::copy_from_slice(b)))
+            .await
+            .unwrap();
+        assert_eq!(loaded, Some(bytes(0)), "{backend}");
+        let other = Digest::of_bytes(&bytes(1));
+        assert!(
+            s.store_bytes(other.hash, bytes(1), true).await.is_err(),
+            "{backend}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn sqlite_store_is_bounded_by_max_size() {
+    let store_all = |max_size_growth_factor| async move {
+        let tempdir = TempDir::new().unwrap();
+        let s = ShardedStore::new(
+            StoreBackend::Sqlite,
+            tempdir.path().to_owned(),
+            1024 * 1024,
+            max_size_growth_factor,
+            Executor::new(),
+            DEFAULT_LEASE_TIME,
+            1,
+        )
+        .unwrap();
+        // Twice the max size of the store.
+        for content in 0..20 {
+            let bytes = Bytes::from(vec![content; 100 * 1024]);
+            s.store_bytes(Digest::of_bytes(&bytes).hash, bytes, true)
+                .await?;
+        }
+        Ok::<_, String>(())
+    };
+
+    let err = store_all(1).await.unwrap_err();
+    assert!(err.contains("full"), "{err}");
+    store_all(4).await.unwrap();
+}
+
+fn leased_until(s: &ShardedLmdb, fingerprint: Fingerprint) -> u64 {
+    let (env, _, lease_database) = s.get(&fingerprint);
+    let txn = env.begin_ro_txn().unwrap();
+    let key = VersionedFingerprint::new(fingerprint, ShardedLmdb::SCHEMA_VERSION);
+    let mut array = [0_u8; 8];
+    array.copy_from_slice(txn.get(lease_database, &key.as_ref()).unwrap());
+    u64::from_le_bytes(array)
+}
+
 fn bytes(content: u8) -> Bytes {
     Bytes::from(vec![content; 100])
 }
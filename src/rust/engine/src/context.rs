@@ -2,10 +2,11 @@
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
 use std::cmp::max;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::Into;
 use std::io::Read;
 use std::ops::Deref;
+use std::os::unix::ffi::OsStrExt;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
@@ -16,28 +17,36 @@ use crate::session::{Session, Sessions};
 use crate::tasks::{Rule, Tasks};
 use crate::types::Types;
 
+use bytes::Bytes;
 use cache::PersistentCache;
-use fs::{GitignoreStyleExcludes, PosixFS};
+use fs::{FilespecMatcher, GitignoreStyleExcludes, PosixFS};
 use futures::FutureExt;
 use graph::{Graph, InvalidationResult};
+use grpc_util::prost::MessageExt;
 use hashing::Digest;
 use log::{log, Level};
 use parking_lot::Mutex;
 // use docker::docker::{self, DOCKER, IMAGE_PULL_CACHE};
 use docker::docker;
+use process_execution::routing::{self, RoutingRule};
+use process_execution::sandboxer::Sandboxer;
 use process_execution::switched::SwitchedCommandRunner;
 use process_execution::{
-    self, bounded, local, CacheContentBehavior, CommandRunner, NamedCaches,
+    self, bounded, dry_run, local, CacheContentBehavior, CommandRunner, NamedCaches,
     ProcessExecutionStrategy,
 };
+use prost::Message;
+use protos::gen::build::bazel::remote::execution::v2 as remexec;
+use protos::gen::pants::cache::{CacheKey, CacheKeyType};
+use protos::require_digest;
 use regex::Regex;
 use remote::remote_cache::{RemoteCacheRunnerOptions, RemoteCacheWarningsBehavior};
 use remote::{self, remote_cache};
-use rule_graph::RuleGraph;
-use store::{self, ImmutableInputs, RemoteProvider, RemoteStoreOptions, Store};
+use rule_graph::{RuleGraph, RuleGraphCache};
+use store::{self, ImmutableInputs, RemoteProvider, RemoteStoreOptions, Store, StoreBackend};
 use task_executor::Executor;
 use tokio::sync::RwLock;
-use watch::{Invalidatable, InvalidateCaller, InvalidationWatcher};
+use watch::{Invalidatable, InvalidateCaller, InvalidationWatcher, WatchMode};
 use workunit_store::{Metric, RunningWorkunit};
 
 // The reqwest crate has no support for ingesting multiple certificates in a single file,
@@ -59,7 +68,7 @@ const PEM_RE_STR: &str =
 pub struct Core {
     pub graph: Arc<InvalidatableGraph>,
     pub tasks: Tasks,
-    pub rule_graph: RuleGraph<Rule>,
+    pub rule_graph: Arc<RuleGraph<Rule>>,
     pub types: Types,
     pub executor: Executor,
     store: Store,
@@ -69,6 +78,8 @@ pub struct Core {
     pub command_runners: Vec<Arc<dyn CommandRunner>>,
     pub http_client: reqwest::Client,
     pub local_cache: PersistentCache,
+    /// The results of dependency inference, keyed by the digest of the parsed file.
+    pub dep_inference_cache: PersistentCache,
     pub vfs: PosixFS,
     pub watcher: Option<Arc<InvalidationWatcher>>,
     pub build_root: PathBuf,
@@ -77,7 +88,23 @@ pub struct Core {
     pub sessions: Sessions,
     pub named_caches: NamedCaches,
     pub immutable_inputs: ImmutableInputs,
+    /// The cache of containers used by the Docker CommandRunner, which is shared with interactive
+    /// processes.
+    pub docker_container_cache: Arc<docker::ContainerCache<'static>>,
     pub local_execution_root_dir: PathBuf,
+    /// How long the artifacts which are attached to workunits are retained in the local store.
+    pub artifacts_lease_time: Duration,
+    /// If set, the output files of processes whose provenance is recorded by their Session.
+    pub provenance_outputs: Option<FilespecMatcher>,
+    /// If set, a directory in which each Session appends the processes which it executes to an
+    /// audit log (see `Session::append_to_audit_log`).
+    pub process_audit_dir: Option<PathBuf>,
+    /// The names of the environment variables whose values are recorded in audit logs: the values
+    /// of other variables are redacted.
+    pub process_audit_env_allowlist: BTreeSet<String>,
+    /// Whether to explain why processes missed the cache, by comparison with a previous run.
+    pub explain_cache_misses: bool,
+    pub process_cache_namespace: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -138,6 +165,11 @@ pub struct ExecutionStrategyOptions {
     pub local_parallelism: usize,
     pub remote_parallelism: usize,
     pub local_keep_sandboxes: local::KeepSandboxes,
+    /// Whether local sandboxes are created at stable paths (see `local::create_sandbox_in_slot`).
+    pub local_deterministic_sandbox_paths: bool,
+    /// Whether local processes with immutable inputs run in overlay sandboxes (see
+    /// `overlay::OverlaySandbox`).
+    pub local_overlay_sandboxes: bool,
     pub local_cache: bool,
     pub local_enable_nailgun: bool,
     pub remote_cache_read: bool,
@@ -145,16 +177,43 @@ pub struct ExecutionStrategyOptions {
     pub child_max_memory: usize,
     pub child_default_memory: usize,
     pub graceful_shutdown_timeout: Duration,
+    /// If set, a `sandboxer` binary which materializes sandboxes and spawns local processes, so
+    /// that the (potentially large) address space of this process is never forked.
+    pub sandboxer_bin: Option<PathBuf>,
+    /// Globs (with `!`-prefixed excludes) for the output files of processes whose provenance
+    /// should be recorded: if empty, no provenance is recorded.
+    pub provenance_output_globs: Vec<String>,
+    /// If set, a directory to write a (JSON Lines) audit log of the processes of each run to.
+    pub process_audit_dir: Option<PathBuf>,
+    pub process_audit_env_allowlist: Vec<String>,
+    /// If true, processes which miss the caches are reported (see `dry_run::CommandRunner`), rather
+    /// than executed, and no caches are written.
+    pub dry_run: bool,
+    /// If true, the cache key components of processes are persisted, so that those of processes
+    /// which miss the cache can be compared against those of a previous run.
+    pub explain_cache_misses: bool,
+    /// Rules which route processes between local and remote execution (see
+    /// `routing::RoutingRule`): if empty, processes are routed by their strategies.
+    pub process_routing_rules: Vec<String>,
 }
 
 #[derive(Clone, Debug)]
 pub struct LocalStoreOptions {
     pub store_dir: PathBuf,
+    /// Existing stores (e.g. those of sibling checkouts) which are attached read-only, and
+    /// consulted in order on misses (of both content and cache entries) before the remote.
+    pub shared_store_dirs: Vec<PathBuf>,
     pub process_cache_max_size_bytes: usize,
+    pub dep_inference_cache_max_size_bytes: usize,
     pub files_max_size_bytes: usize,
     pub directories_max_size_bytes: usize,
+    /// The factor by which the stores may automatically grow beyond their max sizes when full.
+    pub max_size_growth_factor: usize,
     pub lease_time: Duration,
+    /// How long the artifacts of workunits are leased for, which may be longer than `lease_time`.
+    pub artifacts_lease_time: Duration,
     pub shard_count: u8,
+    pub backend: StoreBackend,
 }
 
 impl From<&LocalStoreOptions> for store::LocalOptions {
@@ -162,8 +221,10 @@ impl From<&LocalStoreOptions> for store::LocalOptions {
         Self {
             files_max_size_bytes: lso.files_max_size_bytes,
             directories_max_size_bytes: lso.directories_max_size_bytes,
+            max_size_growth_factor: lso.max_size_growth_factor,
             lease_time: lso.lease_time,
             shard_count: lso.shard_count,
+            backend: lso.backend,
         }
     }
 }
@@ -177,12 +238,22 @@ impl Core {
         remoting_opts: &RemotingOptions,
         tls_config: grpc_util::tls::Config,
     ) -> Result<Store, String> {
-        let local_only = Store::local_only_with_options(
+        let mut local_only = Store::local_only_with_options(
             executor.clone(),
             local_store_options.store_dir.clone(),
             local_execution_root_dir,
             local_store_options.into(),
         )?;
+        for shared_store_dir in &local_store_options.shared_store_dirs {
+            local_only = local_only
+                .into_with_shared(executor.clone(), shared_store_dir)
+                .map_err(|e| {
+                    format!(
+                        "Could not attach the shared store at {}: {e}",
+                        shared_store_dir.display()
+                    )
+                })?;
+        }
         if enable_remote {
             local_only
                 .into_with_remote(remoting_opts.to_remote_store_options(tls_config)?)
@@ -200,16 +271,26 @@ impl Core {
         full_store: &Store,
         local_runner_store: &Store,
         executor: &Executor,
+        local_cache: &PersistentCache,
         build_root: &Path,
         local_execution_root_dir: &Path,
         immutable_inputs: &ImmutableInputs,
         named_caches: &NamedCaches,
+        docker_container_cache: &Arc<docker::ContainerCache<'static>>,
+        sandboxer: Option<&Sandboxer>,
         instance_name: Option<String>,
         process_cache_namespace: Option<String>,
         tls_config: grpc_util::tls::Config,
         exec_strategy_opts: &ExecutionStrategyOptions,
         remoting_opts: &RemotingOptions,
     ) -> Result<Arc<dyn CommandRunner>, String> {
+        if exec_strategy_opts.dry_run {
+            return Ok(Arc::new(dry_run::CommandRunner::new(
+                full_store.clone(),
+                process_cache_namespace,
+            )));
+        }
+
         // Lock shared between `local::CommandRunner` and `workspace::CommandRunner` to protect concurrent spawning
         // of subprocesses.
         let spawn_lock = Arc::new(RwLock::new(()));
@@ -221,7 +302,10 @@ impl Core {
             named_caches.clone(),
             immutable_inputs.clone(),
             exec_strategy_opts.local_keep_sandboxes,
+            exec_strategy_opts.local_deterministic_sandbox_paths,
+            exec_strategy_opts.local_overlay_sandboxes,
             spawn_lock.clone(),
+            sandboxer.cloned(),
         );
 
         // TODO: Consider whether to limit parallel execution and/or concurrency of in-workspace executions. (For example,
@@ -281,7 +365,7 @@ impl Core {
             local_runner_store.clone(),
             executor.clone(),
             &docker::DOCKER,
-            &docker::IMAGE_PULL_CACHE,
+            docker_container_cache.clone(),
             local_execution_root_dir.to_path_buf(),
             immutable_inputs.clone(),
             exec_strategy_opts.local_keep_sandboxes,
@@ -299,11 +383,12 @@ impl Core {
             exec_strategy_opts.local_parallelism,
         ));
 
+        let mut remote_execution_runner: Option<Box<dyn CommandRunner>> = None;
         if remoting_opts.execution_enable {
             // We always create the remote execution runner if it is globally enabled, but it may not
-            // actually be used thanks to the `SwitchedCommandRunner` below. Only one of local execution
-            // or remote execution will be used for any particular process.
-            let remote_execution_runner = Box::new(
+            // actually be used thanks to the `SwitchedCommandRunner` (or routing rules) below. Only
+            // one of local execution or remote execution will be used for any particular process.
+            let remote = Box::new(
                 remote::remote::CommandRunner::new(
                     // We unwrap because global_options.py will have already validated this is defined.
                     remoting_opts.execution_address.as_ref().unwrap(),
@@ -320,11 +405,27 @@ impl Core {
                 )
                 .await?,
             );
-            let remote_execution_runner = Box::new(bounded::CommandRunner::new(
+            remote_execution_runner = Some(Box::new(bounded::CommandRunner::new(
                 executor,
-                remote_execution_runner,
+                remote,
                 exec_strategy_opts.remote_parallelism,
-            ));
+            )));
+        }
+
+        if !exec_strategy_opts.process_routing_rules.is_empty() {
+            let rules = exec_strategy_opts
+                .process_routing_rules
+                .iter()
+                .map(|rule| rule.parse())
+                .collect::<Result<Vec<RoutingRule>, _>>()?;
+            runner = Box::new(routing::CommandRunner::new(
+                runner,
+                remote_execution_runner,
+                rules,
+                full_store.clone(),
+                local_cache.clone(),
+            )?);
+        } else if let Some(remote_execution_runner) = remote_execution_runner {
             runner = Box::new(SwitchedCommandRunner::new(
                 remote_execution_runner,
                 runner,
@@ -409,6 +510,8 @@ impl Core {
         local_execution_root_dir: &Path,
         immutable_inputs: &ImmutableInputs,
         named_caches: &NamedCaches,
+        docker_container_cache: &Arc<docker::ContainerCache<'static>>,
+        sandboxer: Option<&Sandboxer>,
         instance_name: Option<String>,
         process_cache_namespace: Option<String>,
         tls_config: grpc_util::tls::Config,
@@ -419,10 +522,13 @@ impl Core {
             full_store,
             local_runner_store,
             executor,
+            local_cache,
             build_root,
             local_execution_root_dir,
             immutable_inputs,
             named_caches,
+            docker_container_cache,
+            sandboxer,
             instance_name.clone(),
             process_cache_namespace.clone(),
             tls_config.clone(),
@@ -432,8 +538,11 @@ impl Core {
         .await?;
 
         let remote_cache_read = exec_strategy_opts.remote_cache_read;
-        let remote_cache_write = exec_strategy_opts.remote_cache_write;
+        let remote_cache_write =
+            exec_strategy_opts.remote_cache_write && !exec_strategy_opts.dry_run;
         let local_cache_read_write = exec_strategy_opts.local_cache;
+        // The results of a dry run are fabricated, and so must not be written to caches.
+        let local_cache_write = local_cache_read_write && !exec_strategy_opts.dry_run;
 
         // The first attempt is always with all caches.
         let mut runners = {
@@ -449,7 +558,7 @@ impl Core {
                 remote_cache_read,
                 remote_cache_write,
                 local_cache_read_write,
-                local_cache_read_write,
+                local_cache_write,
             )
             .await?;
 
@@ -470,7 +579,7 @@ impl Core {
                 false,
                 remote_cache_write,
                 false,
-                local_cache_read_write,
+                local_cache_write,
             )
             .await?;
 
@@ -480,6 +589,60 @@ impl Core {
         Ok(runners)
     }
 
+    ///
+    /// Solve the RuleGraph via the given cache, and persist it if it was newly solved. The encoded
+    /// RuleGraph is large, so it is stored in the Store, under a Digest recorded in the local cache.
+    ///
+    async fn solve_rule_graph(
+        rule_graph_cache: &RuleGraphCache<Rule>,
+        tasks: &Tasks,
+        build_root: &Path,
+        store: &Store,
+        local_cache: &PersistentCache,
+    ) -> Result<Arc<RuleGraph<Rule>>, String> {
+        let key = CacheKey {
+            key_type: CacheKeyType::RuleGraph.into(),
+            digest: Some(Digest::of_bytes(build_root.as_os_str().as_bytes()).into()),
+            ..CacheKey::default()
+        };
+        let load = async {
+            let Some(value) = local_cache.load(&key).await? else {
+                return Ok(None);
+            };
+            let digest =
+                require_digest(&remexec::Digest::decode(value).map_err(|e| e.to_string())?)?;
+            store
+                .load_file_bytes_with(digest, |bytes| bytes.to_vec())
+                .await
+                .map(Some)
+                .map_err(|e| e.to_string())
+        };
+        let persisted = load.await.unwrap_or_else(|e: String| {
+            log::debug!("Failed to load the persisted rule graph: {e}");
+            None
+        });
+
+        let (rule_graph, solved) = rule_graph_cache.get_or_solve_persisted(
+            tasks.rules().clone(),
+            tasks.queries().clone(),
+            persisted.as_deref(),
+        )?;
+        if solved {
+            let persist = async {
+                let digest = store
+                    .store_file_bytes(Bytes::from(rule_graph.encode()?), true)
+                    .await?;
+                local_cache
+                    .store(&key, remexec::Digest::from(digest).to_bytes())
+                    .await
+            };
+            if let Err(e) = persist.await {
+                log::debug!("Failed to persist the rule graph: {e}");
+            }
+        }
+        Ok(rule_graph)
+    }
+
     fn load_certificates(
         ca_certs_path: Option<PathBuf>,
     ) -> Result<Vec<reqwest::Certificate>, String> {
@@ -521,12 +684,17 @@ impl Core {
         ignore_patterns: Vec<String>,
         use_gitignore: bool,
         watch_filesystem: bool,
+        watch_mode: WatchMode,
+        watch_poll_interval: Duration,
+        watch_coalesce_window: Duration,
+        watch_ignore_patterns: Vec<String>,
         local_execution_root_dir: PathBuf,
         named_caches_dir: PathBuf,
         ca_certs_path: Option<PathBuf>,
         local_store_options: LocalStoreOptions,
         remoting_opts: RemotingOptions,
         exec_strategy_opts: ExecutionStrategyOptions,
+        rule_graph_cache: Option<&RuleGraphCache<Rule>>,
     ) -> Result<Core, String> {
         // We re-use these certs for both the execution and store service; they're generally tied together.
         let root_ca_certs = if let Some(ref path) = remoting_opts.root_ca_certs_path {
@@ -593,14 +761,36 @@ impl Core {
         .await
         .map_err(|e| format!("Could not initialize Store: {e:?}"))?;
 
-        let local_cache = PersistentCache::new(
+        let with_peers = |mut cache: PersistentCache| -> Result<PersistentCache, String> {
+            for shared_store_dir in &local_store_options.shared_store_dirs {
+                cache = cache.into_with_peer(
+                    shared_store_dir,
+                    executor.clone(),
+                    local_store_options.lease_time,
+                )?;
+            }
+            Ok(cache)
+        };
+        let local_cache = with_peers(PersistentCache::new(
             &local_store_options.store_dir,
             // TODO: Rename.
             local_store_options.process_cache_max_size_bytes,
+            local_store_options.max_size_growth_factor,
             executor.clone(),
             local_store_options.lease_time,
             local_store_options.shard_count,
-        )?;
+            local_store_options.backend,
+        )?)?;
+        let dep_inference_cache = with_peers(PersistentCache::new_named(
+            &local_store_options.store_dir,
+            "dep_inference",
+            local_store_options.dep_inference_cache_max_size_bytes,
+            local_store_options.max_size_growth_factor,
+            executor.clone(),
+            local_store_options.lease_time,
+            local_store_options.shard_count,
+            local_store_options.backend,
+        )?)?;
 
         let store = if (exec_strategy_opts.remote_cache_read
             || exec_strategy_opts.remote_cache_write)
@@ -621,6 +811,34 @@ impl Core {
 
         let immutable_inputs = ImmutableInputs::new(store.clone(), &local_execution_root_dir)?;
         let named_caches = NamedCaches::new_local(named_caches_dir);
+        let docker_container_cache = Arc::new(docker::ContainerCache::new(
+            &docker::DOCKER,
+            &docker::IMAGE_PULL_CACHE,
+            executor.clone(),
+            &local_execution_root_dir,
+            &immutable_inputs,
+        )?);
+        if exec_strategy_opts.local_overlay_sandboxes {
+            if !cfg!(target_os = "linux") {
+                return Err("Overlay sandboxes are only supported on Linux.".to_owned());
+            }
+            if exec_strategy_opts.sandboxer_bin.is_some() {
+                return Err("Overlay sandboxes cannot be used with a sandboxer.".to_owned());
+            }
+        }
+        let sandboxer = match &exec_strategy_opts.sandboxer_bin {
+            Some(sandboxer_bin) => Some(
+                Sandboxer::new(
+                    sandboxer_bin,
+                    &local_execution_root_dir,
+                    &local_store_options.store_dir,
+                    &local_execution_root_dir,
+                    &(&local_store_options).into(),
+                )
+                .await?,
+            ),
+            None => None,
+        };
         let command_runners = Self::make_command_runners(
             &full_store,
             &store,
@@ -630,6 +848,8 @@ impl Core {
             &local_execution_root_dir,
             &immutable_inputs,
             &named_caches,
+            &docker_container_cache,
+            sandboxer.as_ref(),
             remoting_opts.instance_name.clone(),
             remoting_opts.execution_process_cache_namespace.clone(),
             tls_config.clone(),
@@ -652,27 +872,79 @@ impl Core {
         let http_client = http_client_builder
             .build()
             .map_err(|err| format!("Error building HTTP client: {err}"))?;
-        let rule_graph = RuleGraph::new(tasks.rules().clone(), tasks.queries().clone())?;
-
-        let gitignore_files = if use_gitignore {
-            GitignoreStyleExcludes::gitignore_file_paths(&build_root)
+        // If a cache was provided, the RuleGraph is reused (entirely, or for unaffected subgraphs)
+        // from the one most recently solved for it, or else from the one most recently persisted
+        // for this build root (e.g. by a pantsd which has since restarted).
+        let rule_graph = if let Some(rule_graph_cache) = rule_graph_cache {
+            Self::solve_rule_graph(
+                rule_graph_cache,
+                &tasks,
+                &build_root,
+                &full_store.clone().into_local_only(),
+                &local_cache,
+            )
+            .await?
         } else {
-            vec![]
+            Arc::new(RuleGraph::new(
+                tasks.rules().clone(),
+                tasks.queries().clone(),
+            )?)
         };
 
-        let ignorer =
-            GitignoreStyleExcludes::create_with_gitignore_files(ignore_patterns, gitignore_files)
-                .map_err(|e| format!("Could not parse build ignore patterns: {e:?}"))?;
+        let ignorer = Self::create_ignorer(&build_root, ignore_patterns.clone(), use_gitignore)?;
+        let vfs = PosixFS::new(&build_root, ignorer.clone(), executor.clone())
+            .map_err(|e| format!("Could not initialize Vfs: {e:?}"))?;
 
         let watcher = if watch_filesystem {
-            let w =
-                InvalidationWatcher::new(executor.clone(), build_root.clone(), ignorer.clone())?;
+            let w = InvalidationWatcher::new(
+                executor.clone(),
+                build_root.clone(),
+                ignorer,
+                GitignoreStyleExcludes::create(watch_ignore_patterns)
+                    .map_err(|e| format!("Could not parse file watcher ignore patterns: {e:?}"))?,
+                watch_mode,
+                watch_poll_interval,
+                watch_coalesce_window,
+            )?;
+            if use_gitignore {
+                // Rebuild the ignorer when the gitignore files change, rather than requiring a
+                // restart.
+                let (build_root, vfs) = (build_root.clone(), vfs.clone());
+                w.reload_ignorer_on_change(
+                    GitignoreStyleExcludes::gitignore_file_candidates(&build_root),
+                    Box::new(move || {
+                        let ignorer =
+                            Self::create_ignorer(&build_root, ignore_patterns.clone(), true)?;
+                        vfs.set_ignorer(ignorer.clone());
+                        Ok(ignorer)
+                    }),
+                )
+                .await;
+            }
             w.start(&graph)?;
             Some(w)
         } else {
             None
         };
 
+        let provenance_outputs = if exec_strategy_opts.provenance_output_globs.is_empty() {
+            None
+        } else {
+            let (excludes, includes): (Vec<_>, Vec<_>) = exec_strategy_opts
+                .provenance_output_globs
+                .iter()
+                .cloned()
+                .partition(|glob| glob.starts_with('!'));
+            let excludes = excludes
+                .into_iter()
+                .map(|glob| glob[1..].to_owned())
+                .collect();
+            Some(
+                FilespecMatcher::new(includes, excludes)
+                    .map_err(|e| format!("Could not parse provenance output globs: {e}"))?,
+            )
+        };
+
         let sessions = Sessions::new(&executor)?;
 
         Ok(Core {
@@ -685,8 +957,8 @@ impl Core {
             command_runners,
             http_client,
             local_cache,
-            vfs: PosixFS::new(&build_root, ignorer, executor)
-                .map_err(|e| format!("Could not initialize Vfs: {e:?}"))?,
+            dep_inference_cache,
+            vfs,
             build_root,
             watcher,
             local_parallelism: exec_strategy_opts.local_parallelism,
@@ -694,10 +966,35 @@ impl Core {
             sessions,
             named_caches,
             immutable_inputs,
+            docker_container_cache,
             local_execution_root_dir,
+            artifacts_lease_time: local_store_options.artifacts_lease_time,
+            provenance_outputs,
+            process_audit_dir: exec_strategy_opts.process_audit_dir.clone(),
+            process_audit_env_allowlist: exec_strategy_opts
+                .process_audit_env_allowlist
+                .iter()
+                .cloned()
+                .collect(),
+            explain_cache_misses: exec_strategy_opts.explain_cache_misses,
+            process_cache_namespace: remoting_opts.execution_process_cache_namespace.clone(),
         })
     }
 
+    fn create_ignorer(
+        build_root: &Path,
+        ignore_patterns: Vec<String>,
+        use_gitignore: bool,
+    ) -> Result<Arc<GitignoreStyleExcludes>, String> {
+        let gitignore_files = if use_gitignore {
+            GitignoreStyleExcludes::gitignore_file_paths(build_root)
+        } else {
+            vec![]
+        };
+        GitignoreStyleExcludes::create_with_gitignore_files(ignore_patterns, gitignore_files)
+            .map_err(|e| format!("Could not parse build ignore patterns: {e:?}"))
+    }
+
     pub fn store(&self) -> Store {
         self.store.clone()
     }
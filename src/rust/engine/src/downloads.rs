@@ -2,24 +2,31 @@
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
 use std::collections::BTreeMap;
+use std::fmt;
 use std::io::{self, Write};
 use std::pin::Pin;
+use std::process::Stdio;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use bytes::{BufMut, Bytes};
 use futures::stream::StreamExt;
-use hashing::Digest;
+use hashing::{Digest, DigestFunction};
 use humansize::{file_size_opts, FileSize};
-use reqwest::header::{HeaderMap, HeaderName};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
 use reqwest::Error;
-use tokio_retry::strategy::{jitter, ExponentialBackoff};
+use sha2::{Digest as Sha2Digest, Sha256, Sha512};
+use tokio_retry::strategy::jitter;
 use tokio_retry::RetryIf;
 use url::Url;
 
 use crate::context::Core;
 use workunit_store::{in_workunit, Level};
 
+mod netrc;
+
 enum StreamingError {
     Retryable(String),
     Permanent(String),
@@ -38,6 +45,91 @@ trait StreamingDownload: Send {
     async fn next(&mut self) -> Option<Result<Bytes, String>>;
 }
 
+///
+/// The sources of credentials for a download, in priority order. None of them are part of the
+/// cache key for the downloaded file.
+///
+pub struct DownloadAuth {
+    /// Explicit headers, which are only sent to the host of the first (i.e. non-mirror) URL.
+    pub headers: BTreeMap<String, String>,
+    /// A command whose stdout is used as a bearer token. The URL being downloaded is provided to
+    /// the command as `PANTS_DOWNLOAD_URL`.
+    pub token_command: Option<Vec<String>>,
+    /// Whether to use the credentials from `$NETRC` (or `~/.netrc`) for the URL's host.
+    pub use_netrc: bool,
+}
+
+impl DownloadAuth {
+    ///
+    /// Resolve the credentials to use for the given URL. If an `Authorization` header was not
+    /// explicitly provided, a token command takes precedence over netrc.
+    ///
+    async fn resolve(&self, url: &Url, primary_host: Option<&str>) -> Result<ResolvedAuth, String> {
+        let mut resolved = ResolvedAuth::default();
+        if url.scheme() == "file" {
+            return Ok(resolved);
+        }
+
+        if url.host_str() == primary_host {
+            for (k, v) in &self.headers {
+                let name = HeaderName::from_bytes(k.as_bytes())
+                    .map_err(|e| format!("Invalid header name `{k}`: {e}"))?;
+                let mut value = HeaderValue::from_str(v)
+                    .map_err(|e| format!("Invalid value for header `{k}`: {e}"))?;
+                value.set_sensitive(true);
+                resolved.headers.insert(name, value);
+            }
+        }
+        if resolved.headers.contains_key(AUTHORIZATION) {
+            return Ok(resolved);
+        }
+
+        if let Some(token_command) = &self.token_command {
+            let token = Self::run_token_command(token_command, url).await?;
+            let mut value = HeaderValue::from_str(&format!("Bearer {token}"))
+                .map_err(|e| format!("Token command produced an invalid token: {e}"))?;
+            value.set_sensitive(true);
+            resolved.headers.insert(AUTHORIZATION, value);
+        } else if self.use_netrc {
+            let credentials = match (url.host_str(), netrc::Netrc::load()?) {
+                (Some(host), Some(netrc)) => netrc.credentials_for(host).cloned(),
+                _ => None,
+            };
+            resolved.basic = credentials;
+        }
+        Ok(resolved)
+    }
+
+    async fn run_token_command(token_command: &[String], url: &Url) -> Result<String, String> {
+        let (program, args) = token_command
+            .split_first()
+            .ok_or_else(|| "The auth token command must not be empty.".to_owned())?;
+        let output = tokio::process::Command::new(program)
+            .args(args)
+            .env("PANTS_DOWNLOAD_URL", url.as_str())
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run auth token command `{program}`: {e}"))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Auth token command `{program}` failed with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        let token = String::from_utf8(output.stdout)
+            .map_err(|e| format!("Auth token command `{program}` produced invalid UTF-8: {e}"))?;
+        Ok(token.trim().to_owned())
+    }
+}
+
+#[derive(Default)]
+struct ResolvedAuth {
+    headers: HeaderMap,
+    basic: Option<netrc::Credentials>,
+}
+
 struct NetDownload {
     stream: futures_core::stream::BoxStream<'static, Result<Bytes, Error>>,
 }
@@ -46,21 +138,18 @@ impl NetDownload {
     async fn start(
         core: &Arc<Core>,
         url: Url,
-        auth_headers: BTreeMap<String, String>,
+        auth: &ResolvedAuth,
         file_name: String,
     ) -> Result<NetDownload, StreamingError> {
-        let mut headers = HeaderMap::new();
-        for (k, v) in &auth_headers {
-            headers.insert(
-                HeaderName::from_bytes(k.as_bytes()).unwrap(),
-                v.parse().unwrap(),
-            );
+        let mut request = core
+            .http_client
+            .get(url.clone())
+            .headers(auth.headers.clone());
+        if let Some(credentials) = &auth.basic {
+            request = request.basic_auth(&credentials.login, credentials.password.as_ref());
         }
 
-        let response = core
-      .http_client
-      .get(url.clone())
-      .headers(headers)
+        let response = request
       .send()
       .await
       .map_err(|err| StreamingError::Retryable(format!("Error downloading file: {err}")))
@@ -134,7 +223,7 @@ impl StreamingDownload for FileDownload {
 async fn attempt_download(
     core: &Arc<Core>,
     url: &Url,
-    auth_headers: &BTreeMap<String, String>,
+    auth: &ResolvedAuth,
     file_name: String,
     expected_digest: Digest,
 ) -> Result<(Digest, Bytes), StreamingError> {
@@ -152,7 +241,7 @@ async fn attempt_download(
             }
             Box::new(FileDownload::start(url.path(), file_name).await?)
         } else {
-            Box::new(NetDownload::start(core, url.clone(), auth_headers.clone(), file_name).await?)
+            Box::new(NetDownload::start(core, url.clone(), auth, file_name).await?)
         }
     };
 
@@ -182,11 +271,16 @@ async fn attempt_download(
         }
     }
 
-    let mut hasher = hashing::WriterHasher::new(SizeLimiter {
-        writer: bytes::BytesMut::with_capacity(expected_digest.size_bytes).writer(),
-        written: 0,
-        size_limit: expected_digest.size_bytes,
-    });
+    // NB: The expected digests of downloads are declared (by `DownloadFile` and the
+    // `known_versions` of tools) with SHA-256, regardless of the digest function of the store.
+    let mut hasher = hashing::WriterHasher::new_with(
+        DigestFunction::Sha256,
+        SizeLimiter {
+            writer: bytes::BytesMut::with_capacity(expected_digest.size_bytes).writer(),
+            written: 0,
+            size_limit: expected_digest.size_bytes,
+        },
+    );
 
     while let Some(next_chunk) = response_stream.next().await {
         let chunk = next_chunk.map_err(|err| {
@@ -200,52 +294,191 @@ async fn attempt_download(
     Ok((digest, bytewriter.writer.into_inner().freeze()))
 }
 
+///
+/// A checksum algorithm which may be used to verify a downloaded file, in addition to the SHA-256
+/// `Digest` under which it is stored.
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ChecksumAlgorithm {
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+impl ChecksumAlgorithm {
+    fn hex_digest(&self, bytes: &[u8]) -> String {
+        match self {
+            Self::Sha256 => hex::encode(Sha256::digest(bytes)),
+            Self::Sha512 => hex::encode(Sha512::digest(bytes)),
+            Self::Blake3 => blake3::hash(bytes).to_hex().to_string(),
+        }
+    }
+}
+
+impl FromStr for ChecksumAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha256" => Ok(Self::Sha256),
+            "sha512" => Ok(Self::Sha512),
+            "blake3" => Ok(Self::Blake3),
+            _ => Err(format!(
+                "Unsupported checksum algorithm `{s}`: expected one of `sha256`, `sha512`, or `blake3`."
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+            Self::Blake3 => "blake3",
+        };
+        write!(f, "{name}")
+    }
+}
+
+///
+/// How retryable download failures are retried for each URL: with exponentially increasing (and
+/// jittered) delays between attempts.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub max_retries: usize,
+    pub initial_delay: Duration,
+    pub backoff_factor: u32,
+}
+
+impl RetryPolicy {
+    fn delays(&self) -> impl Iterator<Item = Duration> {
+        let RetryPolicy {
+            max_retries,
+            initial_delay,
+            backoff_factor,
+        } = *self;
+        (0..max_retries)
+            .map(move |attempt| {
+                let attempt = u32::try_from(attempt).unwrap_or(u32::MAX);
+                initial_delay.saturating_mul(backoff_factor.saturating_pow(attempt))
+            })
+            .map(jitter)
+    }
+}
+
+///
+/// Verify the given bytes against each of the given checksums.
+///
+pub(crate) fn verify_checksums(
+    checksums: &BTreeMap<ChecksumAlgorithm, String>,
+    bytes: &[u8],
+) -> Result<(), String> {
+    for (algorithm, expected_checksum) in checksums {
+        let actual_checksum = algorithm.hex_digest(bytes);
+        if !actual_checksum.eq_ignore_ascii_case(expected_checksum) {
+            return Err(format!(
+                "Wrong {algorithm} checksum for downloaded file: want {expected_checksum} got {actual_checksum}"
+            ));
+        }
+    }
+    Ok(())
+}
+
+///
+/// Download the file with the given digest from the first of the given URLs which successfully
+/// provides it, retrying each URL according to the RetryPolicy before moving on to the next.
+///
+/// The `expected_digest` is a SHA-256 digest. Returns the digest that the file was stored under,
+/// which differs from it when the store uses another `DigestFunction`.
+///
 pub async fn download(
     core: Arc<Core>,
-    url: Url,
-    auth_headers: BTreeMap<String, String>,
+    urls: Vec<Url>,
+    auth: DownloadAuth,
     file_name: String,
     expected_digest: hashing::Digest,
-) -> Result<(), String> {
+    checksums: BTreeMap<ChecksumAlgorithm, String>,
+    retry_policy: RetryPolicy,
+) -> Result<Digest, String> {
     let core2 = core.clone();
-    let (actual_digest, bytes) = in_workunit!(
+    let mirrors_desc = if urls.len() > 1 {
+        format!(" and {} mirror(s)", urls.len() - 1)
+    } else {
+        "".to_owned()
+    };
+    let bytes = in_workunit!(
         "download_file",
         Level::Debug,
         desc = Some(format!(
-            "Downloading: {url} ({})",
+            "Downloading: {}{mirrors_desc} ({})",
+            urls.first().map(Url::as_str).unwrap_or_default(),
             expected_digest
                 .size_bytes
                 .file_size(file_size_opts::CONVENTIONAL)
                 .unwrap()
         )),
         |_workunit| async move {
-            // TODO: Allow the retry strategy to be configurable?
-            // For now we retry after 10ms, 100ms, 1s, and 10s.
-            let retry_strategy = ExponentialBackoff::from_millis(10).map(jitter).take(4);
-            RetryIf::spawn(
-                retry_strategy,
-                || {
-                    attempt_download(
-                        &core2,
-                        &url,
-                        &auth_headers,
-                        file_name.clone(),
-                        expected_digest,
-                    )
-                },
-                |err: &StreamingError| matches!(err, StreamingError::Retryable(_)),
-            )
-            .await
+            let primary_host = urls.first().and_then(Url::host_str);
+            let mut errors = Vec::new();
+            for url in &urls {
+                let resolved_auth = match auth.resolve(url, primary_host).await {
+                    Ok(resolved_auth) => resolved_auth,
+                    Err(err) => {
+                        errors.push(err);
+                        continue;
+                    }
+                };
+                let result = RetryIf::spawn(
+                    retry_policy.delays(),
+                    || {
+                        attempt_download(
+                            &core2,
+                            url,
+                            &resolved_auth,
+                            file_name.clone(),
+                            expected_digest,
+                        )
+                    },
+                    |err: &StreamingError| matches!(err, StreamingError::Retryable(_)),
+                )
+                .await
+                .map_err(String::from)
+                .and_then(|(actual_digest, bytes)| {
+                    if expected_digest != actual_digest {
+                        return Err(format!(
+                            "Wrong digest for downloaded file: want {expected_digest:?} got {actual_digest:?}"
+                        ));
+                    }
+                    verify_checksums(&checksums, &bytes)?;
+                    Ok(bytes)
+                });
+
+                match result {
+                    Ok(bytes) => return Ok(bytes),
+                    Err(err) if urls.len() > 1 => {
+                        log::debug!("Failed to download {file_name} from {url}: {err}");
+                        errors.push(format!("{url}: {err}"));
+                    }
+                    Err(err) => errors.push(err),
+                }
+            }
+
+            if errors.len() > 1 {
+                Err(format!(
+                    "Failed to download {file_name} from any of {} URLs:\n  {}",
+                    errors.len(),
+                    errors.join("\n  ")
+                ))
+            } else {
+                Err(errors
+                    .pop()
+                    .unwrap_or_else(|| format!("No URLs were provided to download {file_name}.")))
+            }
         }
     )
     .await?;
 
-    if expected_digest != actual_digest {
-        return Err(format!(
-            "Wrong digest for downloaded file: want {expected_digest:?} got {actual_digest:?}"
-        ));
-    }
-
-    let _ = core.store().store_file_bytes(bytes, true).await?;
-    Ok(())
+    core.store().store_file_bytes(bytes, true).await
 }
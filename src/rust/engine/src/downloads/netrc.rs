@@ -0,0 +1,188 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+
+///
+/// The login and password for a machine in a netrc file.
+///
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Credentials {
+    pub login: String,
+    pub password: Option<String>,
+}
+
+///
+/// A parsed netrc file: see
+/// https://www.gnu.org/software/inetutils/manual/html_node/The-_002enetrc-file.html.
+///
+/// Macro definitions (`macdef`) are skipped, and quoted tokens are not supported.
+///
+#[derive(Debug, Default)]
+pub struct Netrc {
+    machines: HashMap<String, Credentials>,
+    default: Option<Credentials>,
+}
+
+impl Netrc {
+    ///
+    /// Load the netrc file located by `$NETRC`, or otherwise at `~/.netrc`, if it exists.
+    ///
+    pub fn load() -> Result<Option<Netrc>, String> {
+        let Some(path) = std::env::var_os("NETRC")
+            .map(PathBuf::from)
+            .or_else(|| dirs_next::home_dir().map(|home| home.join(".netrc")))
+        else {
+            return Ok(None);
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(content) => Netrc::parse(&content)
+                .map(Some)
+                .map_err(|e| format!("Failed to parse {}: {e}", path.display())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read {}: {e}", path.display())),
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<Netrc, String> {
+        let mut tokens = Vec::new();
+        let mut in_macdef = false;
+        for line in content.lines() {
+            // A macro definition continues until the next empty line.
+            if in_macdef {
+                in_macdef = !line.trim().is_empty();
+                continue;
+            }
+            if line.trim_start().starts_with('#') {
+                continue;
+            }
+            for token in line.split_whitespace() {
+                if token == "macdef" {
+                    in_macdef = true;
+                    break;
+                }
+                tokens.push(token);
+            }
+        }
+
+        let mut netrc = Netrc::default();
+        // The machine name (or None for `default`) and credentials of the entry being parsed.
+        let mut current: Option<(Option<&str>, Credentials)> = None;
+        let mut tokens = tokens.into_iter();
+        while let Some(token) = tokens.next() {
+            match token {
+                "machine" | "default" => {
+                    if let Some((machine, credentials)) = current.take() {
+                        netrc.insert(machine, credentials);
+                    }
+                    let machine = if token == "machine" {
+                        Some(
+                            tokens
+                                .next()
+                                .ok_or_else(|| "Expected a name after `machine`.".to_owned())?,
+                        )
+                    } else {
+                        None
+                    };
+                    current = Some((machine, Credentials::default()));
+                }
+                "login" | "password" | "account" => {
+                    let value = tokens
+                        .next()
+                        .ok_or_else(|| format!("Expected a value after `{token}`."))?;
+                    let Some((_, credentials)) = current.as_mut() else {
+                        return Err(format!("`{token}` must follow `machine` or `default`."));
+                    };
+                    match token {
+                        "login" => credentials.login = value.to_owned(),
+                        "password" => credentials.password = Some(value.to_owned()),
+                        _ => {}
+                    }
+                }
+                unknown => return Err(format!("Unexpected token `{unknown}`.")),
+            }
+        }
+        if let Some((machine, credentials)) = current.take() {
+            netrc.insert(machine, credentials);
+        }
+        Ok(netrc)
+    }
+
+    fn insert(&mut self, machine: Option<&str>, credentials: Credentials) {
+        // As with other netrc consumers, the first matching entry wins.
+        match machine {
+            Some(machine) => {
+                self.machines
+                    .entry(machine.to_owned())
+                    .or_insert(credentials);
+            }
+            None => {
+                self.default.get_or_insert(credentials);
+            }
+        }
+    }
+
+    ///
+    /// Returns the credentials for the given host, falling back to the `default` entry.
+    ///
+    pub fn credentials_for(&self, host: &str) -> Option<&Credentials> {
+        self.machines.get(host).or(self.default.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Credentials, Netrc};
+
+    #[test]
+    fn parse() {
+        let netrc = Netrc::parse(
+            "# A comment.\n\
+             machine example.com login alice password secret\n\
+             macdef init\n\
+             cd /pub\n\
+             \n\
+             machine other.example.com\n\
+               login bob\n\
+             default login anonymous password guest\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            Some(&Credentials {
+                login: "alice".to_owned(),
+                password: Some("secret".to_owned()),
+            }),
+            netrc.credentials_for("example.com")
+        );
+        assert_eq!(
+            Some(&Credentials {
+                login: "bob".to_owned(),
+                password: None,
+            }),
+            netrc.credentials_for("other.example.com")
+        );
+        assert_eq!(
+            Some(&Credentials {
+                login: "anonymous".to_owned(),
+                password: Some("guest".to_owned()),
+            }),
+            netrc.credentials_for("unknown.example.com")
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Netrc::parse("login alice")
+            .unwrap_err()
+            .contains("must follow `machine` or `default`"));
+        assert!(Netrc::parse("machine")
+            .unwrap_err()
+            .contains("Expected a name after `machine`"));
+        assert!(Netrc::parse("machine example.com port 80")
+            .unwrap_err()
+            .contains("Unexpected token `port`"));
+    }
+}
@@ -0,0 +1,184 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use pyo3::exceptions::PyException;
+use pyo3::intern;
+use pyo3::prelude::*;
+use tokio::sync::oneshot;
+
+use crate::context::Context;
+use crate::externs::PyGeneratorResponseNativeCall;
+use crate::nodes::{task_context, task_try_get_context};
+use crate::python::{throw, Failure, Value};
+
+pub(crate) fn register(m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyTaskContext>()?;
+    m.add_function(wrap_pyfunction!(task_context_capture, m)?)?;
+    m.add_function(wrap_pyfunction!(await_concurrent_future, m)?)?;
+    Ok(())
+}
+
+///
+/// The context of a running `@rule`, which allows the engine calls which it would otherwise have
+/// awaited itself (downloads, process executions, store reads, etc.) to be started from other
+/// threads, such as the thread of an `asyncio` event loop.
+///
+/// Calls are run as if they had been awaited by the `@rule`: their workunits are its children, and
+/// their dependencies are recorded for it.
+///
+#[pyclass(name = "TaskContext")]
+pub struct PyTaskContext {
+    context: Context,
+    stdio_destination: Arc<stdio::Destination>,
+    workunit_store_handle: Option<workunit_store::WorkunitStoreHandle>,
+}
+
+#[pymethods]
+impl PyTaskContext {
+    ///
+    /// Starts the given call, and returns a `concurrent.futures.Future` for its result.
+    ///
+    /// If the future is cancelled before the call completes, the call continues in the background,
+    /// but its result is discarded.
+    ///
+    fn spawn(&self, py: Python, call: &PyGeneratorResponseNativeCall) -> PyResult<PyObject> {
+        let call = call.take().map_err(PyException::new_err)?;
+        let future: PyObject = py
+            .import("concurrent.futures")?
+            .getattr("Future")?
+            .call0()?
+            .into();
+
+        let context = self.context.clone();
+        let result_future = Value::new(future.clone_ref(py));
+        let call = stdio::scope_task_destination(
+            self.stdio_destination.clone(),
+            workunit_store::scope_task_workunit_store_handle(
+                self.workunit_store_handle.clone(),
+                async move {
+                    let side_effected = Arc::new(AtomicBool::new(false));
+                    task_context(context, false, &side_effected, call.call).await
+                },
+            ),
+        );
+        self.context.core.executor.native_spawn(async move {
+            let result = call.await;
+            Python::with_gil(|py| {
+                let future = result_future.as_ref().as_ref(py);
+                if future
+                    .call_method0(intern!(py, "done"))
+                    .and_then(|done| done.is_truthy())
+                    .unwrap_or(true)
+                {
+                    // Cancelled.
+                    return;
+                }
+                let set = match result {
+                    Ok(value) => future.call_method1(
+                        intern!(py, "set_result"),
+                        (value.consume_into_py_object(py),),
+                    ),
+                    Err(failure) => future.call_method1(
+                        intern!(py, "set_exception"),
+                        (PyErr::from(failure).into_value(py),),
+                    ),
+                };
+                if let Err(e) = set {
+                    log::warn!("Failed to complete an engine call started from asyncio: {e}");
+                }
+            })
+        });
+        Ok(future)
+    }
+}
+
+///
+/// Captures the context of the running `@rule`, which must be done synchronously within the body
+/// of the `@rule`.
+///
+#[pyfunction]
+fn task_context_capture() -> PyResult<PyTaskContext> {
+    let context = task_try_get_context().ok_or_else(|| {
+        PyException::new_err(
+            "The context of a `@rule` may only be captured within the body of the `@rule`.",
+        )
+    })?;
+    Ok(PyTaskContext {
+        context,
+        stdio_destination: stdio::get_destination(),
+        workunit_store_handle: workunit_store::get_workunit_store_handle(),
+    })
+}
+
+///
+/// Awaits a `concurrent.futures.Future` (such as the result of `asyncio.run_coroutine_threadsafe`)
+/// from a `@rule`, without blocking the thread which runs the `@rule`.
+///
+/// If the `@rule` is cancelled while waiting, the future is cancelled.
+///
+#[pyfunction]
+fn await_concurrent_future(future: &PyAny) -> PyResult<PyGeneratorResponseNativeCall> {
+    let (sender, receiver) = oneshot::channel();
+    future.call_method1(
+        intern!(future.py(), "add_done_callback"),
+        (PyDoneCallback(Mutex::new(Some(sender))),),
+    )?;
+    let mut cancel_on_drop = CancelOnDrop(Some(Value::new(future.into())));
+    Ok(PyGeneratorResponseNativeCall::new(async move {
+        let future = receiver
+            .await
+            .map_err(|_| throw("The awaited future was dropped.".to_owned()))?;
+        cancel_on_drop.disarm();
+        Python::with_gil(|py| {
+            future
+                .as_ref()
+                .as_ref(py)
+                .call_method0(intern!(py, "result"))
+                .map(|result| Value::new(result.into()))
+                .map_err(|e| Failure::from_py_err_with_gil(py, e))
+        })
+    }))
+}
+
+///
+/// A callback for `concurrent.futures.Future.add_done_callback`, which sends the completed future.
+///
+#[pyclass]
+struct PyDoneCallback(Mutex<Option<oneshot::Sender<Value>>>);
+
+#[pymethods]
+impl PyDoneCallback {
+    fn __call__(&self, future: PyObject) {
+        if let Some(sender) = self.0.lock().take() {
+            let _ = sender.send(Value::new(future));
+        }
+    }
+}
+
+///
+/// Cancels a `concurrent.futures.Future` which is no longer awaited.
+///
+struct CancelOnDrop(Option<Value>);
+
+impl CancelOnDrop {
+    fn disarm(&mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if let Some(future) = self.0.take() {
+            Python::with_gil(|py| {
+                let _ = future
+                    .as_ref()
+                    .as_ref(py)
+                    .call_method0(intern!(py, "cancel"));
+            });
+        }
+    }
+}
@@ -0,0 +1,327 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+
+use futures::channel::mpsc;
+use grpc_util::{headers_to_http_header_map, layered_service, status_to_str, LayeredService};
+use protos::gen::google::devtools::build::v1 as bes;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use task_executor::Executor;
+use tokio::task::JoinHandle;
+
+use crate::externs::scheduler::PyExecutor;
+use bes::build_event::Event;
+use bes::publish_build_event_client::PublishBuildEventClient;
+use bes::stream_id::BuildComponent;
+
+pub(crate) fn register(m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyBuildEventStream>()
+}
+
+// The type of the (Bazel) build events which are published as `bazel_event`s.
+const BAZEL_EVENT_TYPE_URL: &str = "type.googleapis.com/build_event_stream.BuildEvent";
+
+#[derive(Clone)]
+struct BuildEventService {
+    client: PublishBuildEventClient<LayeredService>,
+    build_id: String,
+    invocation_id: String,
+    project_id: String,
+}
+
+impl BuildEventService {
+    ///
+    /// The id of the stream of the build (if `invocation` is false), or of its invocation.
+    ///
+    fn stream_id(&self, component: BuildComponent, invocation: bool) -> bes::StreamId {
+        bes::StreamId {
+            build_id: self.build_id.clone(),
+            invocation_id: if invocation {
+                self.invocation_id.clone()
+            } else {
+                String::new()
+            },
+            component: component as i32,
+        }
+    }
+
+    fn ordered_build_event(
+        &self,
+        component: BuildComponent,
+        invocation: bool,
+        sequence_number: i64,
+        event: Event,
+    ) -> bes::OrderedBuildEvent {
+        bes::OrderedBuildEvent {
+            stream_id: Some(self.stream_id(component, invocation)),
+            sequence_number,
+            event: Some(bes::BuildEvent {
+                event_time: Some(SystemTime::now().into()),
+                event: Some(event),
+            }),
+        }
+    }
+
+    ///
+    /// Publishes a lifecycle event of the build (if `invocation` is false) or of its invocation,
+    /// at the given position in the stream of its lifecycle events.
+    ///
+    async fn publish_lifecycle_event(
+        &self,
+        invocation: bool,
+        sequence_number: i64,
+        event: Event,
+    ) -> Result<(), String> {
+        let request = bes::PublishLifecycleEventRequest {
+            build_event: Some(self.ordered_build_event(
+                BuildComponent::Controller,
+                invocation,
+                sequence_number,
+                event,
+            )),
+            project_id: self.project_id.clone(),
+            ..bes::PublishLifecycleEventRequest::default()
+        };
+        self.client
+            .clone()
+            .publish_lifecycle_event(request)
+            .await
+            .map_err(status_to_str)?;
+        Ok(())
+    }
+}
+
+fn build_status(exit_code: i32) -> bes::BuildStatus {
+    let result = if exit_code == 0 {
+        bes::build_status::Result::CommandSucceeded
+    } else {
+        bes::build_status::Result::CommandFailed
+    };
+    bes::BuildStatus {
+        result: result as i32,
+        build_tool_exit_code: Some(exit_code),
+        ..bes::BuildStatus::default()
+    }
+}
+
+///
+/// A client of the Build Event Service (BES), which publishes the lifecycle events of a build and
+/// the stream of build tool events of its (single) invocation.
+///
+/// The build tool events are encoded `build_event_stream.BuildEvent`s, which are published in the
+/// order that they are sent in without blocking the caller.
+///
+#[pyclass]
+struct PyBuildEventStream {
+    executor: Executor,
+    service: BuildEventService,
+    timeout: Duration,
+    sender: Option<mpsc::UnboundedSender<bes::PublishBuildToolEventStreamRequest>>,
+    // Resolves to the sequence number of the last event which was acknowledged by the service.
+    acknowledged: Option<JoinHandle<Result<i64, String>>>,
+    sequence_number: i64,
+}
+
+impl PyBuildEventStream {
+    fn send(&mut self, event: Event) -> Result<(), String> {
+        let sender = self
+            .sender
+            .as_ref()
+            .ok_or_else(|| "The build event stream is not open.".to_owned())?;
+        self.sequence_number += 1;
+        let request = bes::PublishBuildToolEventStreamRequest {
+            ordered_build_event: Some(self.service.ordered_build_event(
+                BuildComponent::Tool,
+                true,
+                self.sequence_number,
+                event,
+            )),
+            project_id: self.service.project_id.clone(),
+            ..bes::PublishBuildToolEventStreamRequest::default()
+        };
+        sender
+            .unbounded_send(request)
+            .map_err(|_| "The build event stream was closed.".to_owned())
+    }
+}
+
+#[pymethods]
+impl PyBuildEventStream {
+    #[new]
+    #[pyo3(signature = (
+        py_executor,
+        address,
+        headers,
+        build_id,
+        invocation_id,
+        project_id,
+        timeout_secs
+    ))]
+    fn __new__(
+        py_executor: &PyExecutor,
+        address: &str,
+        headers: BTreeMap<String, String>,
+        build_id: String,
+        invocation_id: String,
+        project_id: Option<String>,
+        timeout_secs: u64,
+    ) -> PyResult<Self> {
+        let executor = py_executor.0.clone();
+        let client = executor
+            .block_on(async {
+                let tls_config = address
+                    .starts_with("https://")
+                    .then(|| grpc_util::tls::Config::default().try_into())
+                    .transpose()?;
+                let channel = grpc_util::create_channel(address, tls_config.as_ref()).await?;
+                let http_headers = headers_to_http_header_map(&headers)?;
+                // NB: The lifecycle events are published while the stream of build tool events is
+                // open, so at least two requests must be allowed at once.
+                let service = layered_service(channel, 2, http_headers, None);
+                Ok::<_, String>(PublishBuildEventClient::new(service))
+            })
+            .map_err(PyException::new_err)?;
+        Ok(Self {
+            executor,
+            service: BuildEventService {
+                client,
+                build_id,
+                invocation_id,
+                project_id: project_id.unwrap_or_default(),
+            },
+            timeout: Duration::from_secs(timeout_secs),
+            sender: None,
+            acknowledged: None,
+            sequence_number: 0,
+        })
+    }
+
+    ///
+    /// Publishes that the build was enqueued and that its invocation started, and then opens the
+    /// stream of build tool events.
+    ///
+    fn start(&mut self, py: Python) -> PyResult<()> {
+        let (executor, service, timeout) =
+            (self.executor.clone(), self.service.clone(), self.timeout);
+        py.allow_threads(move || {
+            executor.block_on(async move {
+                let publish = async {
+                    service
+                        .publish_lifecycle_event(
+                            false,
+                            1,
+                            Event::BuildEnqueued(bes::build_event::BuildEnqueued::default()),
+                        )
+                        .await?;
+                    service
+                        .publish_lifecycle_event(
+                            true,
+                            1,
+                            Event::InvocationAttemptStarted(
+                                bes::build_event::InvocationAttemptStarted { attempt_number: 1 },
+                            ),
+                        )
+                        .await
+                };
+                tokio::time::timeout(timeout, publish)
+                    .await
+                    .map_err(|_| "Timed out publishing the start of the build.".to_owned())?
+            })
+        })
+        .map_err(PyException::new_err)?;
+
+        let (sender, receiver) = mpsc::unbounded();
+        let mut client = self.service.client.clone();
+        self.sender = Some(sender);
+        self.acknowledged = Some(self.executor.native_spawn(async move {
+            let mut responses = client
+                .publish_build_tool_event_stream(receiver)
+                .await
+                .map_err(status_to_str)?
+                .into_inner();
+            let mut acknowledged = 0;
+            while let Some(response) = responses.message().await.map_err(status_to_str)? {
+                acknowledged = response.sequence_number;
+            }
+            Ok(acknowledged)
+        }));
+        Ok(())
+    }
+
+    ///
+    /// Sends an encoded `build_event_stream.BuildEvent`, without waiting for it to be published.
+    ///
+    fn publish(&mut self, event: &[u8]) -> PyResult<()> {
+        self.send(Event::BazelEvent(prost_types::Any {
+            type_url: BAZEL_EVENT_TYPE_URL.to_owned(),
+            value: event.to_vec(),
+        }))
+        .map_err(PyException::new_err)
+    }
+
+    ///
+    /// Closes the stream of build tool events, waits for all of them to be acknowledged, and then
+    /// publishes that the invocation and the build finished with the given exit code.
+    ///
+    fn finish(&mut self, py: Python, exit_code: i32) -> PyResult<()> {
+        use bes::build_event::build_component_stream_finished::FinishType;
+
+        self.send(Event::ComponentStreamFinished(
+            bes::build_event::BuildComponentStreamFinished {
+                r#type: FinishType::Finished as i32,
+            },
+        ))
+        .map_err(PyException::new_err)?;
+        // Dropping the sender ends the stream of requests.
+        self.sender = None;
+        let acknowledged = self
+            .acknowledged
+            .take()
+            .ok_or_else(|| PyException::new_err("The build event stream was not started."))?;
+
+        let (executor, service, timeout) =
+            (self.executor.clone(), self.service.clone(), self.timeout);
+        let expected = self.sequence_number;
+        py.allow_threads(move || {
+            executor.block_on(async move {
+                let publish = async {
+                    let acknowledged = acknowledged
+                        .await
+                        .map_err(|e| format!("Failed to publish build events: {e}"))??;
+                    if acknowledged != expected {
+                        return Err(format!(
+                            "Only {acknowledged} of {expected} build events were acknowledged."
+                        ));
+                    }
+                    service
+                        .publish_lifecycle_event(
+                            true,
+                            2,
+                            Event::InvocationAttemptFinished(
+                                bes::build_event::InvocationAttemptFinished {
+                                    invocation_status: Some(build_status(exit_code)),
+                                },
+                            ),
+                        )
+                        .await?;
+                    service
+                        .publish_lifecycle_event(
+                            false,
+                            2,
+                            Event::BuildFinished(bes::build_event::BuildFinished {
+                                status: Some(build_status(exit_code)),
+                            }),
+                        )
+                        .await
+                };
+                tokio::time::timeout(timeout, publish)
+                    .await
+                    .map_err(|_| "Timed out publishing the end of the build.".to_owned())?
+            })
+        })
+        .map_err(PyException::new_err)
+    }
+}
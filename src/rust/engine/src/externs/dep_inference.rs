@@ -4,10 +4,12 @@ use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
 use pyo3::basic::CompareOp;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
 use pyo3::{IntoPy, PyObject, Python};
 
+use dep_inference::python_requirements::{self, PythonRequirement};
 use fs::DirectoryDigest;
 use protos::gen::pants::cache::{
     dependency_inference_request, javascript_inference_metadata, JavascriptInferenceMetadata,
@@ -16,8 +18,12 @@ use protos::gen::pants::cache::{
 use crate::externs::fs::PyDigest;
 
 pub(crate) fn register(m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(parse_python_requirement, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_python_requirements_file, m)?)?;
+
     m.add_class::<PyNativeDependenciesRequest>()?;
-    m.add_class::<PyInferenceMetadata>()
+    m.add_class::<PyInferenceMetadata>()?;
+    m.add_class::<PyPythonRequirement>()
 }
 
 #[pyclass(name = "InferenceMetadata")]
@@ -27,21 +33,46 @@ pub struct PyInferenceMetadata(pub dependency_inference_request::Metadata);
 #[pymethods]
 impl PyInferenceMetadata {
     #[staticmethod]
-    fn javascript(package_root: String, import_patterns: &PyDict) -> PyResult<Self> {
-        use javascript_inference_metadata::ImportPattern;
-        let import_patterns: PyResult<Vec<ImportPattern>> = import_patterns
-            .iter()
-            .map(|(key, value)| {
-                Ok(ImportPattern {
-                    pattern: key.extract()?,
-                    replacements: value.extract()?,
+    #[pyo3(signature = (
+        package_root,
+        import_patterns,
+        config_root = None,
+        config_paths = None,
+        config_has_base_url = false
+    ))]
+    fn javascript(
+        package_root: String,
+        import_patterns: &PyDict,
+        config_root: Option<String>,
+        config_paths: Option<&PyDict>,
+        config_has_base_url: bool,
+    ) -> PyResult<Self> {
+        use javascript_inference_metadata::{ConfigPaths, ImportPattern};
+        fn to_import_patterns(patterns: &PyDict) -> PyResult<Vec<ImportPattern>> {
+            patterns
+                .iter()
+                .map(|(key, value)| {
+                    Ok(ImportPattern {
+                        pattern: key.extract()?,
+                        replacements: value.extract()?,
+                    })
+                })
+                .collect()
+        }
+        let config_paths = config_root
+            .map(|root| -> PyResult<ConfigPaths> {
+                Ok(ConfigPaths {
+                    root,
+                    paths: config_paths.map_or(Ok(vec![]), to_import_patterns)?,
+                    has_base_url: config_has_base_url,
                 })
             })
-            .collect();
+            .transpose()?;
         Ok(Self(dependency_inference_request::Metadata::Js(
             JavascriptInferenceMetadata {
                 package_root,
-                import_patterns: import_patterns?,
+                import_patterns: to_import_patterns(import_patterns)?,
+                config_paths,
             },
         )))
     }
@@ -105,3 +136,82 @@ impl PyNativeDependenciesRequest {
         }
     }
 }
+
+#[pyclass(name = "PythonRequirement")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PyPythonRequirement(PythonRequirement);
+
+#[pymethods]
+impl PyPythonRequirement {
+    #[getter]
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    #[getter]
+    fn extras(&self) -> Vec<String> {
+        self.0.extras.clone()
+    }
+
+    #[getter]
+    fn specifiers(&self) -> Vec<(String, String)> {
+        self.0
+            .specifiers
+            .iter()
+            .map(|s| (s.operator.clone(), s.version.clone()))
+            .collect()
+    }
+
+    #[getter]
+    fn url(&self) -> Option<&str> {
+        self.0.url.as_deref()
+    }
+
+    #[getter]
+    fn marker(&self) -> Option<String> {
+        self.0.marker.as_ref().map(|marker| marker.to_string())
+    }
+
+    fn __richcmp__(&self, other: &Self, op: CompareOp, py: Python) -> PyObject {
+        match op {
+            CompareOp::Eq => (self == other).into_py(py),
+            CompareOp::Ne => (self != other).into_py(py),
+            _ => py.NotImplemented(),
+        }
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("PythonRequirement('{}')", self.0)
+    }
+}
+
+#[pyfunction]
+fn parse_python_requirement(requirement: &str) -> PyResult<PyPythonRequirement> {
+    python_requirements::parse_requirement(requirement)
+        .map(PyPythonRequirement)
+        .map_err(|e| PyValueError::new_err(format!("Invalid requirement '{requirement}': {e}")))
+}
+
+#[pyfunction]
+fn parse_python_requirements_file(
+    contents: &str,
+    rel_path: &str,
+) -> PyResult<Vec<(usize, PyPythonRequirement)>> {
+    python_requirements::parse_requirements_file(contents)
+        .map(|requirements| {
+            requirements
+                .into_iter()
+                .map(|(line_number, requirement)| (line_number, PyPythonRequirement(requirement)))
+                .collect()
+        })
+        .map_err(|e| {
+            PyValueError::new_err(format!(
+                "Invalid requirement '{}' in {rel_path} at line {}: {}",
+                e.line, e.line_number, e.message
+            ))
+        })
+}
@@ -3,13 +3,17 @@
 
 use std::sync::Arc;
 
+use crate::context::Context;
 use crate::externs;
-use crate::externs::fs::PyFileDigest;
+use crate::externs::fs::{PyDigest, PyFileDigest};
 use crate::nodes::{lift_directory_digest, lift_file_digest};
+use crate::python::Failure;
 use crate::Value;
 
+use bytes::Bytes;
+use fs::DirectoryDigest;
 use pyo3::prelude::*;
-use pyo3::types::PyDict;
+use pyo3::types::{PyBytes, PyDict};
 
 use workunit_store::{ArtifactOutput, Level, RunningWorkunit, UserMetadataItem, WorkunitMetadata};
 
@@ -20,8 +24,68 @@ use workunit_store::{ArtifactOutput, Level, RunningWorkunit, UserMetadataItem, W
 #[derive(Default, Clone, Debug)]
 pub(crate) struct EngineAwareReturnType;
 
+/// An artifact returned by `EngineAwareReturnType.artifacts`, before it is persisted to the Store.
+enum Artifact {
+    FileDigest(hashing::Digest),
+    Directory(DirectoryDigest),
+    Bytes(Bytes),
+}
+
 impl EngineAwareReturnType {
-    pub(crate) fn update_workunit(workunit: &mut RunningWorkunit, task_result: &PyAny) {
+    ///
+    /// Persists the artifacts of the given result to the Store (storing any byte payloads as files),
+    /// and leases them for the configured retention time, so that they may be loaded by digest
+    /// after the run which produced them.
+    ///
+    pub(crate) async fn persist_artifacts(
+        context: &Context,
+        task_result: &Value,
+    ) -> Result<Vec<(String, ArtifactOutput)>, Failure> {
+        let artifacts =
+            Python::with_gil(|py| Self::artifacts((**task_result).as_ref(py))).unwrap_or_default();
+        if artifacts.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let store = context.core.store();
+        let mut digests = Vec::with_capacity(artifacts.len());
+        let mut output = Vec::with_capacity(artifacts.len());
+        for (name, artifact) in artifacts {
+            let artifact_output = match artifact {
+                Artifact::FileDigest(digest) => {
+                    digests.push(digest);
+                    ArtifactOutput::FileDigest(digest)
+                }
+                Artifact::Directory(directory_digest) => {
+                    store
+                        .ensure_directory_digest_persisted(directory_digest.clone())
+                        .await?;
+                    digests.push(directory_digest.as_digest());
+                    ArtifactOutput::Snapshot(Arc::new(directory_digest))
+                }
+                Artifact::Bytes(bytes) => {
+                    let digest = store.store_file_bytes(bytes, true).await?;
+                    digests.push(digest);
+                    ArtifactOutput::FileDigest(digest)
+                }
+            };
+            output.push((name, artifact_output));
+        }
+        store
+            .lease_all_recursively_for(digests.iter(), Some(context.core.artifacts_lease_time))
+            .await?;
+        Ok(output)
+    }
+
+    ///
+    /// Updates the metadata of the workunit from the given result, including the given artifacts
+    /// (which were persisted by `persist_artifacts`).
+    ///
+    pub(crate) fn update_workunit(
+        workunit: &mut RunningWorkunit,
+        task_result: &PyAny,
+        artifacts: Vec<(String, ArtifactOutput)>,
+    ) {
         workunit.update_metadata(|old| {
             let new_level = Self::level(task_result);
 
@@ -35,9 +99,7 @@ impl EngineAwareReturnType {
             };
 
             metadata.message = Self::message(task_result);
-            metadata
-                .artifacts
-                .extend(Self::artifacts(task_result).unwrap_or_default());
+            metadata.artifacts.extend(artifacts);
             metadata
                 .user_metadata
                 .extend(metadata_for(task_result).unwrap_or_default());
@@ -61,7 +123,7 @@ impl EngineAwareReturnType {
         msg_val.extract().ok()
     }
 
-    fn artifacts(obj: &PyAny) -> Option<Vec<(String, ArtifactOutput)>> {
+    fn artifacts(obj: &PyAny) -> Option<Vec<(String, Artifact)>> {
         let artifacts_val = obj.call_method0("artifacts").ok()?;
         if artifacts_val.is_none() {
             return None;
@@ -72,14 +134,18 @@ impl EngineAwareReturnType {
 
         for kv_pair in artifacts_dict.items().into_iter() {
             let (key, value): (String, &PyAny) = kv_pair.extract().ok()?;
-            let artifact_output = if value.is_instance_of::<PyFileDigest>() {
-                lift_file_digest(value).map(ArtifactOutput::FileDigest)
+            let artifact = if value.is_instance_of::<PyFileDigest>() {
+                lift_file_digest(value).map(Artifact::FileDigest)
+            } else if value.is_instance_of::<PyDigest>() {
+                lift_directory_digest(value).map(Artifact::Directory)
+            } else if let Ok(bytes) = value.downcast::<PyBytes>() {
+                Ok(Artifact::Bytes(Bytes::copy_from_slice(bytes.as_bytes())))
             } else {
                 let digest_value = value.getattr("digest").ok()?;
-                lift_directory_digest(digest_value).map(|dd| ArtifactOutput::Snapshot(Arc::new(dd)))
+                lift_directory_digest(digest_value).map(Artifact::Directory)
             }
             .ok()?;
-            output.push((key, artifact_output));
+            output.push((key, artifact));
         }
         Some(output)
     }
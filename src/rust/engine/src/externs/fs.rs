@@ -4,13 +4,16 @@
 use std::collections::hash_map::DefaultHasher;
 use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::os::raw::{c_int, c_void};
 use std::path::{Path, PathBuf};
 
+use bytes::Bytes;
 use itertools::Itertools;
 use pyo3::basic::CompareOp;
 use pyo3::exceptions::{PyException, PyTypeError, PyValueError};
+use pyo3::ffi;
 use pyo3::prelude::*;
-use pyo3::types::{PyIterator, PyString, PyTuple, PyType};
+use pyo3::types::{PyBytes, PyIterator, PyString, PyTuple, PyType};
 
 use fs::{
     DirectoryDigest, FilespecMatcher, GlobExpansionConjunction, PathGlobs, StrictGlobMatching,
@@ -24,6 +27,8 @@ use crate::Failure;
 pub(crate) fn register(m: &PyModule) -> PyResult<()> {
     m.add_class::<PyDigest>()?;
     m.add_class::<PyFileDigest>()?;
+    m.add_class::<PyFileDigestHasher>()?;
+    m.add_class::<PyContentBuffer>()?;
     m.add_class::<PySnapshot>()?;
     m.add_class::<PyMergeDigests>()?;
     m.add_class::<PyAddPrefix>()?;
@@ -31,7 +36,7 @@ pub(crate) fn register(m: &PyModule) -> PyResult<()> {
     m.add_class::<PyFilespecMatcher>()?;
 
     m.add("EMPTY_DIGEST", PyDigest(EMPTY_DIRECTORY_DIGEST.clone()))?;
-    m.add("EMPTY_FILE_DIGEST", PyFileDigest(EMPTY_DIGEST))?;
+    m.add("EMPTY_FILE_DIGEST", PyFileDigest(*EMPTY_DIGEST))?;
     m.add("EMPTY_SNAPSHOT", PySnapshot(Snapshot::empty()))?;
 
     m.add_function(wrap_pyfunction!(default_cache_path, m)?)?;
@@ -151,6 +156,71 @@ impl PyFileDigest {
     }
 }
 
+#[pyclass(name = "FileDigestHasher")]
+pub struct PyFileDigestHasher(hashing::Hasher);
+
+#[pymethods]
+impl PyFileDigestHasher {
+    #[new]
+    fn __new__() -> Self {
+        Self(hashing::Hasher::new())
+    }
+
+    fn update(&mut self, data: &[u8], py: Python) {
+        // NB: Large inputs are hashed without holding the GIL.
+        let hasher = &mut self.0;
+        py.allow_threads(|| hasher.update(data));
+    }
+
+    fn digest(&self) -> PyFileDigest {
+        PyFileDigest(self.0.clone().finish())
+    }
+}
+
+///
+/// Read-only file content which is owned by Rust, and which is exposed to Python via the buffer
+/// protocol: a `memoryview` of it does not copy the content.
+///
+#[pyclass(name = "ContentBuffer", frozen)]
+pub struct PyContentBuffer(pub Bytes);
+
+#[pymethods]
+impl PyContentBuffer {
+    unsafe fn __getbuffer__(
+        slf: PyRef<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        // NB: The view holds a reference to this object (and so to the content) until it is
+        // released. Requests for a writable view fail.
+        let content = &slf.0;
+        let filled = ffi::PyBuffer_FillInfo(
+            view,
+            slf.as_ptr(),
+            content.as_ptr() as *mut c_void,
+            content.len() as ffi::Py_ssize_t,
+            1,
+            flags,
+        );
+        if filled == -1 {
+            return Err(PyErr::fetch(slf.py()));
+        }
+        Ok(())
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    fn __bytes__<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.0)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("ContentBuffer(len:{})", self.0.len())
+    }
+}
+
 #[pyclass(name = "Snapshot")]
 pub struct PySnapshot(pub Snapshot);
 
@@ -258,13 +328,12 @@ pub struct PyMergeDigests(pub Vec<DirectoryDigest>);
 impl PyMergeDigests {
     #[new]
     fn __new__(digests: &PyAny, _py: Python) -> PyResult<Self> {
-        let digests: PyResult<Vec<DirectoryDigest>> = PyIterator::from_object(digests)?
-            .map(|v| {
-                let py_digest = v?.extract::<PyDigest>()?;
-                Ok(py_digest.0)
-            })
-            .collect();
-        Ok(Self(digests?))
+        // NB: `digests` may be any iterable, but is usually a sized collection.
+        let mut result = Vec::with_capacity(digests.len().unwrap_or(0));
+        for py_digest in PyIterator::from_object(digests)? {
+            result.push(py_digest?.extract::<PyRef<PyDigest>>()?.0.clone());
+        }
+        Ok(Self(result))
     }
 
     fn __hash__(&self) -> u64 {
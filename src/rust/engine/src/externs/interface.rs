@@ -8,7 +8,7 @@
 use std::any::Any;
 use std::cell::RefCell;
 use std::collections::hash_map::HashMap;
-use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::collections::{BTreeMap, HashSet};
 use std::convert::TryInto;
 use std::fs::File;
 use std::hash::Hasher;
@@ -17,17 +17,19 @@ use std::panic;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use async_latch::AsyncLatch;
 use fnv::FnvHasher;
 use fs::DirectoryDigest;
 use futures::future::{self, FutureExt};
 use futures::Future;
-use hashing::Digest;
+use hashing::{Digest, DigestFunction};
+use humansize::{file_size_opts, FileSize};
 use log::{self, debug, error, warn, Log};
 use logging::logger::PANTS_LOGGER;
-use logging::{Logger, PythonLogLevel};
+use logging::{LogFormat, LogRetention, Logger, PythonLogLevel, SystemLogSink};
+use parking_lot::Mutex;
 use petgraph::graph::{DiGraph, Graph};
 use process_execution::CacheContentBehavior;
 use pyo3::exceptions::{PyException, PyIOError, PyKeyboardInterrupt, PyValueError};
@@ -39,12 +41,14 @@ use pyo3::types::{PyBytes, PyDict, PyList, PyTuple, PyType};
 use pyo3::{create_exception, IntoPy, PyAny, PyRef};
 use regex::Regex;
 use remote::remote_cache::RemoteCacheWarningsBehavior;
-use rule_graph::{self, RuleGraph};
-use store::RemoteProvider;
+use rule_graph::{self, RuleGraph, RuleGraphCache};
+use store::{RemoteProvider, StoreBackend};
 use task_executor::Executor;
+use ui::{RunEstimate, UiRenderer};
+use watch::WatchMode;
 use workunit_store::{
     ArtifactOutput, ObservationMetric, UserMetadataItem, Workunit, WorkunitState, WorkunitStore,
-    WorkunitStoreHandle,
+    WorkunitStoreHandle, OBSERVATION_BUCKET_BOUNDS,
 };
 
 use crate::externs::fs::{possible_store_missing_digest, PyFileDigest};
@@ -61,6 +65,8 @@ fn native_engine(py: Python, m: &PyModule) -> PyO3Result<()> {
     intrinsics::register(py, m)?;
     externs::register(py, m)?;
     externs::address::register(py, m)?;
+    externs::asyncio::register(m)?;
+    externs::build_events::register(m)?;
     externs::fs::register(m)?;
     externs::nailgun::register(py, m)?;
     externs::options::register(m)?;
@@ -71,6 +77,7 @@ fn native_engine(py: Python, m: &PyModule) -> PyO3Result<()> {
     externs::testutil::register(m)?;
     externs::workunits::register(m)?;
     externs::dep_inference::register(m)?;
+    externs::structured_data::register(m)?;
 
     m.add("PollTimeout", py.get_type::<PollTimeout>())?;
 
@@ -80,6 +87,7 @@ fn native_engine(py: Python, m: &PyModule) -> PyO3Result<()> {
     m.add_class::<PyNailgunServer>()?;
     m.add_class::<PyRemotingOptions>()?;
     m.add_class::<PyResult>()?;
+    m.add_class::<PyRuleGraphCache>()?;
     m.add_class::<PyScheduler>()?;
     m.add_class::<PySession>()?;
     m.add_class::<PySessionCancellationLatch>()?;
@@ -123,6 +131,7 @@ fn native_engine(py: Python, m: &PyModule) -> PyO3Result<()> {
     m.add_function(wrap_pyfunction!(nailgun_server_await_shutdown, m)?)?;
 
     m.add_function(wrap_pyfunction!(garbage_collect_store, m)?)?;
+    m.add_function(wrap_pyfunction!(clear_local_cache, m)?)?;
     m.add_function(wrap_pyfunction!(lease_files_in_graph, m)?)?;
     m.add_function(wrap_pyfunction!(check_invalidation_watcher_liveness, m)?)?;
 
@@ -139,7 +148,10 @@ fn native_engine(py: Python, m: &PyModule) -> PyO3Result<()> {
     m.add_function(wrap_pyfunction!(session_run_interactive_process, m)?)?;
     m.add_function(wrap_pyfunction!(session_get_metrics, m)?)?;
     m.add_function(wrap_pyfunction!(session_get_observation_histograms, m)?)?;
+    m.add_function(wrap_pyfunction!(session_get_observation_buckets, m)?)?;
+    m.add_function(wrap_pyfunction!(session_get_provenance, m)?)?;
     m.add_function(wrap_pyfunction!(session_record_test_observation, m)?)?;
+    m.add_function(wrap_pyfunction!(session_set_run_estimate, m)?)?;
     m.add_function(wrap_pyfunction!(session_isolated_shallow_clone, m)?)?;
     m.add_function(wrap_pyfunction!(session_wait_for_tail_tasks, m)?)?;
 
@@ -150,6 +162,8 @@ fn native_engine(py: Python, m: &PyModule) -> PyO3Result<()> {
     m.add_function(wrap_pyfunction!(scheduler_execute, m)?)?;
     m.add_function(wrap_pyfunction!(scheduler_metrics, m)?)?;
     m.add_function(wrap_pyfunction!(scheduler_live_items, m)?)?;
+    m.add_function(wrap_pyfunction!(scheduler_shed_memory, m)?)?;
+    m.add_function(wrap_pyfunction!(scheduler_live_sessions, m)?)?;
     m.add_function(wrap_pyfunction!(scheduler_create, m)?)?;
     m.add_function(wrap_pyfunction!(scheduler_shutdown, m)?)?;
 
@@ -172,6 +186,24 @@ impl PyTasks {
     }
 }
 
+///
+/// Holds the most recently solved RuleGraph, so that a Scheduler which is re-created for a similar
+/// set of rules (e.g. when pantsd re-initializes after a plugin has been edited) can solve its
+/// RuleGraph incrementally. It is owned by the caller, and so lives only as long as they hold it:
+/// but the RuleGraphs solved via it are also persisted in the local cache, and the first RuleGraph
+/// solved via a new cache is solved against the persisted one.
+///
+#[pyclass]
+struct PyRuleGraphCache(RuleGraphCache<Rule>);
+
+#[pymethods]
+impl PyRuleGraphCache {
+    #[new]
+    fn __new__() -> Self {
+        Self(RuleGraphCache::new())
+    }
+}
+
 #[pyclass]
 struct PyTypes(RefCell<Option<Types>>);
 
@@ -181,15 +213,23 @@ impl PyTypes {
     fn __new__(
         paths: &PyType,
         file_content: &PyType,
+        file_content_view: &PyType,
         file_entry: &PyType,
         symlink_entry: &PyType,
         directory: &PyType,
         digest_contents: &PyType,
+        digest_content_views: &PyType,
         digest_entries: &PyType,
         path_globs: &PyType,
         create_digest: &PyType,
         digest_subset: &PyType,
         native_download_file: &PyType,
+        path_metadata: &PyType,
+        path_metadata_result: &PyType,
+        git_metadata: &PyType,
+        digest_diff: &PyType,
+        digests: &PyType,
+        digest_fingerprint: &PyType,
         platform: &PyType,
         process: &PyType,
         process_result: &PyType,
@@ -202,8 +242,16 @@ impl PyTypes {
         engine_aware_parameter: &PyType,
         docker_resolve_image_request: &PyType,
         docker_resolve_image_result: &PyType,
+        resolve_image_digest_result: &PyType,
         parsed_python_deps_result: &PyType,
         parsed_javascript_deps_result: &PyType,
+        parsed_java_deps_result: &PyType,
+        parsed_scala_deps_result: &PyType,
+        parsed_kotlin_deps_result: &PyType,
+        parsed_go_deps_result: &PyType,
+        parsed_dotnet_deps_result: &PyType,
+        parsed_rust_deps_result: &PyType,
+        parsed_terraform_deps_result: &PyType,
         py: Python,
     ) -> Self {
         Self(RefCell::new(Some(Types {
@@ -212,10 +260,12 @@ impl PyTypes {
             snapshot: TypeId::new(py.get_type::<externs::fs::PySnapshot>()),
             paths: TypeId::new(paths),
             file_content: TypeId::new(file_content),
+            file_content_view: TypeId::new(file_content_view),
             file_entry: TypeId::new(file_entry),
             symlink_entry: TypeId::new(symlink_entry),
             directory: TypeId::new(directory),
             digest_contents: TypeId::new(digest_contents),
+            digest_content_views: TypeId::new(digest_content_views),
             digest_entries: TypeId::new(digest_entries),
             path_globs: TypeId::new(path_globs),
             merge_digests: TypeId::new(py.get_type::<externs::fs::PyMergeDigests>()),
@@ -224,6 +274,12 @@ impl PyTypes {
             create_digest: TypeId::new(create_digest),
             digest_subset: TypeId::new(digest_subset),
             native_download_file: TypeId::new(native_download_file),
+            path_metadata: TypeId::new(path_metadata),
+            path_metadata_result: TypeId::new(path_metadata_result),
+            git_metadata: TypeId::new(git_metadata),
+            digest_diff: TypeId::new(digest_diff),
+            digests: TypeId::new(digests),
+            digest_fingerprint: TypeId::new(digest_fingerprint),
             platform: TypeId::new(platform),
             process: TypeId::new(process),
             process_result: TypeId::new(process_result),
@@ -239,8 +295,16 @@ impl PyTypes {
             engine_aware_parameter: TypeId::new(engine_aware_parameter),
             docker_resolve_image_request: TypeId::new(docker_resolve_image_request),
             docker_resolve_image_result: TypeId::new(docker_resolve_image_result),
+            resolve_image_digest_result: TypeId::new(resolve_image_digest_result),
             parsed_python_deps_result: TypeId::new(parsed_python_deps_result),
             parsed_javascript_deps_result: TypeId::new(parsed_javascript_deps_result),
+            parsed_java_deps_result: TypeId::new(parsed_java_deps_result),
+            parsed_scala_deps_result: TypeId::new(parsed_scala_deps_result),
+            parsed_kotlin_deps_result: TypeId::new(parsed_kotlin_deps_result),
+            parsed_go_deps_result: TypeId::new(parsed_go_deps_result),
+            parsed_dotnet_deps_result: TypeId::new(parsed_dotnet_deps_result),
+            parsed_rust_deps_result: TypeId::new(parsed_rust_deps_result),
+            parsed_terraform_deps_result: TypeId::new(parsed_terraform_deps_result),
             deps_request: TypeId::new(
                 py.get_type::<externs::dep_inference::PyNativeDependenciesRequest>(),
             ),
@@ -265,10 +329,33 @@ struct PyExecutionStrategyOptions(ExecutionStrategyOptions);
 #[pymethods]
 impl PyExecutionStrategyOptions {
     #[new]
+    #[pyo3(signature = (
+        local_parallelism,
+        remote_parallelism,
+        local_keep_sandboxes,
+        local_deterministic_sandbox_paths,
+        local_overlay_sandboxes,
+        local_cache,
+        local_enable_nailgun,
+        remote_cache_read,
+        remote_cache_write,
+        child_default_memory,
+        child_max_memory,
+        graceful_shutdown_timeout,
+        sandboxer_bin,
+        provenance_output_globs,
+        process_audit_dir,
+        process_audit_env_allowlist,
+        dry_run,
+        explain_cache_misses,
+        process_routing_rules
+    ))]
     fn __new__(
         local_parallelism: usize,
         remote_parallelism: usize,
         local_keep_sandboxes: String,
+        local_deterministic_sandbox_paths: bool,
+        local_overlay_sandboxes: bool,
         local_cache: bool,
         local_enable_nailgun: bool,
         remote_cache_read: bool,
@@ -276,6 +363,13 @@ impl PyExecutionStrategyOptions {
         child_default_memory: usize,
         child_max_memory: usize,
         graceful_shutdown_timeout: usize,
+        sandboxer_bin: Option<String>,
+        provenance_output_globs: Vec<String>,
+        process_audit_dir: Option<String>,
+        process_audit_env_allowlist: Vec<String>,
+        dry_run: bool,
+        explain_cache_misses: bool,
+        process_routing_rules: Vec<String>,
     ) -> Self {
         Self(ExecutionStrategyOptions {
             local_parallelism,
@@ -284,6 +378,8 @@ impl PyExecutionStrategyOptions {
                 &local_keep_sandboxes,
             )
             .unwrap(),
+            local_deterministic_sandbox_paths,
+            local_overlay_sandboxes,
             local_cache,
             local_enable_nailgun,
             remote_cache_read,
@@ -293,6 +389,13 @@ impl PyExecutionStrategyOptions {
             graceful_shutdown_timeout: Duration::from_secs(
                 graceful_shutdown_timeout.try_into().unwrap(),
             ),
+            sandboxer_bin: sandboxer_bin.map(PathBuf::from),
+            provenance_output_globs,
+            process_audit_dir: process_audit_dir.map(PathBuf::from),
+            process_audit_env_allowlist,
+            dry_run,
+            explain_cache_misses,
+            process_routing_rules,
         })
     }
 }
@@ -369,24 +472,58 @@ impl PyLocalStoreOptions {
     #[new]
     fn __new__(
         store_dir: PathBuf,
+        shared_store_dirs: Vec<PathBuf>,
         process_cache_max_size_bytes: usize,
+        dep_inference_cache_max_size_bytes: usize,
         files_max_size_bytes: usize,
         directories_max_size_bytes: usize,
+        max_size_growth_factor: usize,
         lease_time_millis: u64,
+        artifacts_lease_time_millis: u64,
         shard_count: u8,
+        backend: String,
+        digest_function: String,
     ) -> PyO3Result<Self> {
         if shard_count.count_ones() != 1 {
             return Err(PyValueError::new_err(format!(
                 "The local store shard count must be a power of two: got {shard_count}"
             )));
         }
+        if max_size_growth_factor == 0 {
+            return Err(PyValueError::new_err(
+                "The local store max size growth factor must be at least 1: got 0",
+            ));
+        }
+        let backend = StoreBackend::from_str(&backend).map_err(PyValueError::new_err)?;
+        let digest_function =
+            DigestFunction::from_str(&digest_function).map_err(PyValueError::new_err)?;
+        let current_digest_function = DigestFunction::from_env().map_err(PyValueError::new_err)?;
+        if digest_function != current_digest_function {
+            return Err(PyValueError::new_err(format!(
+                "`[GLOBAL].digest_function` is `{digest_function}`, but the engine computes digests \
+                with `{current_digest_function}`: the digest function may only be set with the `{}` \
+                environment variable.",
+                DigestFunction::ENV_VAR,
+            )));
+        }
+        // Each digest function has its own stores.
+        let store_dir = store::local_store_dir(&store_dir, digest_function);
+        let shared_store_dirs = shared_store_dirs
+            .iter()
+            .map(|dir| store::local_store_dir(dir, digest_function))
+            .collect();
         Ok(Self(LocalStoreOptions {
             store_dir,
+            shared_store_dirs,
             process_cache_max_size_bytes,
+            dep_inference_cache_max_size_bytes,
             files_max_size_bytes,
             directories_max_size_bytes,
+            max_size_growth_factor,
             lease_time: Duration::from_millis(lease_time_millis),
+            artifacts_lease_time: Duration::from_millis(artifacts_lease_time_millis),
             shard_count,
+            backend,
         }))
     }
 }
@@ -400,11 +537,12 @@ impl PySession {
     fn __new__(
         scheduler: &PyScheduler,
         dynamic_ui: bool,
-        ui_use_prodash: bool,
+        ui_renderer: String,
         max_workunit_level: u64,
         build_id: String,
         session_values: PyObject,
         cancellation_latch: &PySessionCancellationLatch,
+        niceness: u8,
         py: Python,
     ) -> PyO3Result<Self> {
         let core = scheduler.0.core.clone();
@@ -412,6 +550,7 @@ impl PySession {
         let py_level: PythonLogLevel = max_workunit_level
             .try_into()
             .map_err(|e| PyException::new_err(format!("{e}")))?;
+        let ui_renderer = UiRenderer::from_str(&ui_renderer).map_err(PyException::new_err)?;
         // NB: Session creation interacts with the Graph, which must not be accessed while the GIL is
         // held.
         let session = py
@@ -419,11 +558,12 @@ impl PySession {
                 Session::new(
                     core,
                     dynamic_ui,
-                    ui_use_prodash,
+                    ui_renderer,
                     py_level.into(),
                     build_id,
                     session_values,
                     cancellation_latch,
+                    niceness,
                 )
             })
             .map_err(PyException::new_err)?;
@@ -568,14 +708,48 @@ impl PyThreadLocals {
 }
 
 #[pyfunction]
+#[pyo3(signature = (
+    py_executor,
+    port,
+    runner,
+    remote_address=None,
+    remote_tls_cert_path=None,
+    remote_tls_key_path=None,
+    remote_token=None
+))]
 fn nailgun_server_create(
     py_executor: &externs::scheduler::PyExecutor,
     port: u16,
     runner: PyObject,
+    remote_address: Option<String>,
+    remote_tls_cert_path: Option<PathBuf>,
+    remote_tls_key_path: Option<PathBuf>,
+    remote_token: Option<String>,
 ) -> PyO3Result<PyNailgunServer> {
+    let remote = remote_address
+        .map(|address| {
+            let read = |path: Option<PathBuf>, name: &str| {
+                let path = path.ok_or_else(|| format!("A TLS {name} path is required."))?;
+                std::fs::read(&path).map_err(|e| {
+                    format!("Failed to read the TLS {name} at {}: {e}", path.display())
+                })
+            };
+            Ok::<_, String>(nailgun::RemoteServerConfig {
+                address,
+                cert_chain_pem: read(remote_tls_cert_path, "certificate")?,
+                key_pem: read(remote_tls_key_path, "key")?,
+                token: remote_token
+                    .filter(|token| !token.is_empty())
+                    .ok_or_else(|| "A token is required.".to_owned())?,
+            })
+        })
+        .transpose()
+        .map_err(|e| {
+            PyException::new_err(format!("Could not listen for remote connections: {e}"))
+        })?;
     let server_future = {
         let executor = py_executor.0.clone();
-        nailgun::Server::new(executor, port, move |exe: nailgun::RawFdExecution| {
+        nailgun::Server::new_with_remote(executor, port, remote, move |exe| {
             Python::with_gil(|py| {
                 let result = runner.as_ref(py).call1((
                     exe.cmd.command,
@@ -689,10 +863,15 @@ fn scheduler_create(
     ignore_patterns: Vec<String>,
     use_gitignore: bool,
     watch_filesystem: bool,
+    file_watcher: String,
+    file_watcher_poll_interval_secs: f64,
+    file_watcher_coalesce_window_secs: f64,
+    file_watcher_ignore: Vec<String>,
     remoting_options: &PyRemotingOptions,
     local_store_options: &PyLocalStoreOptions,
     exec_strategy_opts: &PyExecutionStrategyOptions,
     ca_certs_path: Option<PathBuf>,
+    rule_graph_cache: Option<&PyRuleGraphCache>,
 ) -> PyO3Result<PyScheduler> {
     match fs::increase_limits() {
         Ok(msg) => debug!("{}", msg),
@@ -704,6 +883,11 @@ fn scheduler_create(
         .take()
         .ok_or_else(|| PyException::new_err("An instance of PyTypes may only be used once."))?;
     let tasks = py_tasks.0.replace(Tasks::new());
+    let watch_mode = WatchMode::from_str(&file_watcher).map_err(PyValueError::new_err)?;
+    let watch_poll_interval = Duration::try_from_secs_f64(file_watcher_poll_interval_secs)
+        .map_err(|e| PyValueError::new_err(format!("Invalid file watcher poll interval: {e}")))?;
+    let watch_coalesce_window = Duration::try_from_secs_f64(file_watcher_coalesce_window_secs)
+        .map_err(|e| PyValueError::new_err(format!("Invalid file watcher coalesce window: {e}")))?;
 
     // NOTE: Enter the Tokio runtime so that libraries like Tonic (for gRPC) are able to
     // use `tokio::spawn` since Python does not setup Tokio for the main thread. This also
@@ -720,12 +904,17 @@ fn scheduler_create(
                     ignore_patterns,
                     use_gitignore,
                     watch_filesystem,
+                    watch_mode,
+                    watch_poll_interval,
+                    watch_coalesce_window,
+                    file_watcher_ignore,
                     local_execution_root_dir,
                     named_caches_dir,
                     ca_certs_path,
                     local_store_options.0.clone(),
                     remoting_options.0.clone(),
                     exec_strategy_opts.0.clone(),
+                    rule_graph_cache.map(|cache| &cache.0),
                 )
                 .await
             })
@@ -914,12 +1103,12 @@ async fn workunit_to_py_value(
         if !has_parent_ids {
             let mut metrics = workunit_store.get_metrics();
 
-            metrics.insert("DEPRECATED_ConsumeGlobalCountersInstead", 0);
+            metrics.insert("DEPRECATED_ConsumeGlobalCountersInstead".to_owned(), 0);
             let counters_entries = metrics
                 .into_iter()
                 .map(|(counter_name, counter_value)| {
                     (
-                        externs::store_utf8(py, counter_name),
+                        externs::store_utf8(py, &counter_name),
                         externs::store_u64(py, counter_value),
                     )
                 })
@@ -1060,6 +1249,26 @@ fn scheduler_live_items<'py>(
     (py_items, sizes)
 }
 
+#[pyfunction]
+fn scheduler_shed_memory(
+    py: Python,
+    py_scheduler: &PyScheduler,
+    max_idle_runs: u32,
+) -> HashMap<&'static str, usize> {
+    py_scheduler
+        .0
+        .core
+        .executor
+        .enter(|| py.allow_threads(|| py_scheduler.0.shed_memory(max_idle_runs)))
+}
+
+#[pyfunction]
+fn scheduler_live_sessions(py: Python, py_scheduler: &PyScheduler) -> Vec<String> {
+    let core = &py_scheduler.0.core;
+    core.executor
+        .enter(|| py.allow_threads(|| core.sessions.build_ids()))
+}
+
 #[pyfunction]
 fn scheduler_shutdown(py: Python, py_scheduler: &PyScheduler, timeout_secs: u64) {
     let core = &py_scheduler.0.core;
@@ -1282,7 +1491,7 @@ fn session_new_run_id(py_session: &PySession) {
 }
 
 #[pyfunction]
-fn session_get_metrics(py: Python<'_>, py_session: &PySession) -> HashMap<&'static str, u64> {
+fn session_get_metrics(py: Python<'_>, py_session: &PySession) -> HashMap<String, u64> {
     py.allow_threads(|| py_session.0.workunit_store().get_metrics())
 }
 
@@ -1317,6 +1526,34 @@ fn session_get_observation_histograms<'py>(
     })
 }
 
+#[pyfunction]
+#[allow(clippy::type_complexity)]
+fn session_get_observation_buckets(
+    py: Python<'_>,
+    py_session: &PySession,
+) -> HashMap<String, (Vec<(u64, u64)>, u64, u64)> {
+    let observations = py.allow_threads(|| py_session.0.workunit_store().observation_buckets());
+    observations
+        .into_iter()
+        .map(|(metric, buckets)| {
+            let counts = OBSERVATION_BUCKET_BOUNDS
+                .into_iter()
+                .zip(buckets.counts)
+                .collect();
+            (metric, (counts, buckets.count, buckets.sum))
+        })
+        .collect()
+}
+
+#[pyfunction]
+fn session_get_provenance(py: Python<'_>, py_session: &PySession) -> Vec<String> {
+    let provenance = py.allow_threads(|| py_session.0.provenance());
+    provenance
+        .into_iter()
+        .map(|provenance| provenance.to_string())
+        .collect()
+}
+
 #[pyfunction]
 fn session_record_test_observation(py_scheduler: &PyScheduler, py_session: &PySession, value: u64) {
     py_scheduler.0.core.executor.enter(|| {
@@ -1327,6 +1564,24 @@ fn session_record_test_observation(py_scheduler: &PyScheduler, py_session: &PySe
     })
 }
 
+#[pyfunction]
+fn session_set_run_estimate(
+    py_session: &PySession,
+    remaining_secs: f64,
+    process_secs: HashMap<String, f64>,
+) {
+    // NB: Negative (or otherwise invalid) durations are treated as zero.
+    let duration = |secs: f64| Duration::try_from_secs_f64(secs).unwrap_or_default();
+    let process_durations = process_secs
+        .into_iter()
+        .map(|(description, secs)| (description, duration(secs)))
+        .collect();
+    py_session.0.set_run_estimate(RunEstimate::new(
+        duration(remaining_secs),
+        process_durations,
+    ));
+}
+
 #[pyfunction]
 fn session_isolated_shallow_clone(
     py_session: &PySession,
@@ -1526,6 +1781,28 @@ fn garbage_collect_store(
     })
 }
 
+#[pyfunction]
+fn clear_local_cache(
+    py: Python,
+    py_scheduler: &PyScheduler,
+    namespaces: Vec<String>,
+) -> PyO3Result<HashMap<String, usize>> {
+    let core = &py_scheduler.0.core;
+    core.executor.enter(|| {
+        py.allow_threads(|| {
+            core.executor.block_on(async {
+                let mut removed = HashMap::new();
+                for namespace in namespaces {
+                    let count = core.local_cache.clear(&namespace).await?;
+                    removed.insert(namespace, count);
+                }
+                Ok(removed)
+            })
+        })
+        .map_err(PyException::new_err)
+    })
+}
+
 #[pyfunction]
 fn lease_files_in_graph(
     py: Python,
@@ -1692,6 +1969,62 @@ fn ensure_path_doesnt_exist(path: &Path) -> io::Result<()> {
     }
 }
 
+///
+/// Periodically logs the progress of writing a large digest to the workspace.
+///
+struct WriteProgress {
+    destination: PathBuf,
+    total_files: usize,
+    total_bytes: usize,
+    // The files and bytes written so far, and when progress was last logged.
+    written: Mutex<(usize, usize, Instant)>,
+}
+
+impl WriteProgress {
+    const MIN_FILES: usize = 1000;
+    const MIN_BYTES: usize = 100 * 1024 * 1024;
+    const INTERVAL: Duration = Duration::from_secs(2);
+
+    fn new(destination: PathBuf, tree: &fs::DigestTrie) -> Self {
+        let mut total_files = 0;
+        let mut total_bytes = 0;
+        tree.walk(fs::SymlinkBehavior::Aware, &mut |_, entry| {
+            if let fs::Entry::File(f) = entry {
+                total_files += 1;
+                total_bytes += f.digest().size_bytes;
+            }
+        });
+        Self {
+            destination,
+            total_files,
+            total_bytes,
+            written: Mutex::new((0, 0, Instant::now())),
+        }
+    }
+
+    fn wrote(&self, size_bytes: usize) {
+        if self.total_files < Self::MIN_FILES && self.total_bytes < Self::MIN_BYTES {
+            return;
+        }
+        let mut written = self.written.lock();
+        let (files, bytes, last_logged) = &mut *written;
+        *files += 1;
+        *bytes += size_bytes;
+        if last_logged.elapsed() < Self::INTERVAL {
+            return;
+        }
+        *last_logged = Instant::now();
+        let size = |bytes: usize| bytes.file_size(file_size_opts::CONVENTIONAL).unwrap();
+        log::info!(
+            "Wrote {files} of {} files ({} of {}) to {}",
+            self.total_files,
+            size(*bytes),
+            size(self.total_bytes),
+            self.destination.display()
+        );
+    }
+}
+
 #[pyfunction]
 fn write_digest(
     py: Python,
@@ -1726,21 +2059,17 @@ fn write_digest(
 
         block_in_place_and_wait(py, || async move {
             let store = core.store();
+            let tree = store.load_digest_trie(lifted_digest).await?;
+            let progress = WriteProgress::new(path_prefix.to_owned(), &tree);
             store
-                .materialize_directory(
-                    destination.clone(),
-                    &core.build_root,
-                    lifted_digest.clone(),
-                    true, // Force everything we write to be mutable
-                    &BTreeSet::new(),
-                    fs::Permissions::Writable,
-                )
+                .materialize_directory_atomically(destination.clone(), tree.clone(), &|size| {
+                    progress.wrote(size)
+                })
                 .await?;
 
             // Invalidate all the paths we've changed within `path_prefix`: both the paths we cleared and
             // the files we've just written to.
-            let snapshot = store::Snapshot::from_digest(store, lifted_digest).await?;
-            let written_paths = snapshot.tree.leaf_paths();
+            let written_paths = tree.leaf_paths();
             let written_paths = written_paths.iter().map(|p| p as &Path);
 
             let cleared_paths = clear_paths.iter().map(Path::new);
@@ -1759,14 +2088,39 @@ fn write_digest(
 }
 
 #[pyfunction]
+#[pyo3(signature = (
+    level,
+    show_rust_3rdparty_logs,
+    show_target,
+    log_format,
+    log_levels_by_target,
+    literal_filters,
+    regex_filters,
+    log_file_path,
+    log_max_size_bytes,
+    log_max_rotated_files,
+    log_compress_rotated_files,
+    log_max_run_logs,
+    log_system_sink,
+    log_dedup_window_secs,
+    log_dedup_excluded_targets
+))]
 fn stdio_initialize(
     level: u64,
     show_rust_3rdparty_logs: bool,
     show_target: bool,
+    log_format: String,
     log_levels_by_target: HashMap<String, u64>,
     literal_filters: Vec<String>,
     regex_filters: Vec<String>,
     log_file_path: PathBuf,
+    log_max_size_bytes: u64,
+    log_max_rotated_files: usize,
+    log_compress_rotated_files: bool,
+    log_max_run_logs: usize,
+    log_system_sink: Option<String>,
+    log_dedup_window_secs: f64,
+    log_dedup_excluded_targets: Vec<String>,
 ) -> PyO3Result<(
     externs::stdio::PyStdioRead,
     externs::stdio::PyStdioWrite,
@@ -1784,15 +2138,30 @@ fn stdio_initialize(
       })
     })
     .collect::<Result<Vec<Regex>, _>>()?;
+    let log_format = LogFormat::from_str(&log_format).map_err(PyException::new_err)?;
+    let log_system_sink = log_system_sink
+        .map(|sink| SystemLogSink::from_str(&sink))
+        .transpose()
+        .map_err(PyException::new_err)?;
 
     Logger::init(
         level,
         show_rust_3rdparty_logs,
         show_target,
+        log_format,
         log_levels_by_target,
         literal_filters,
         regex_filters,
         log_file_path,
+        LogRetention {
+            max_size_bytes: Some(log_max_size_bytes).filter(|size| *size > 0),
+            max_rotated_files: log_max_rotated_files,
+            compress_rotated_files: log_compress_rotated_files,
+            max_run_logs: log_max_run_logs,
+        },
+        log_system_sink,
+        Duration::from_secs_f64(log_dedup_window_secs),
+        log_dedup_excluded_targets.into_iter().collect(),
     )
     .map_err(|s| PyException::new_err(format!("Could not initialize logging: {s}")))?;
 
@@ -26,6 +26,8 @@ use crate::interning::Interns;
 use crate::python::{Failure, Key, TypeId, Value};
 
 mod address;
+mod asyncio;
+mod build_events;
 pub mod dep_inference;
 pub mod engine_aware;
 pub mod fs;
@@ -38,7 +40,10 @@ mod pantsd;
 pub mod process;
 pub mod scheduler;
 mod stdio;
+pub mod structured_data;
 mod target;
+#[cfg(test)]
+mod tests;
 pub mod testutil;
 pub mod workunits;
 
@@ -123,11 +128,20 @@ pub fn union_in_scope_types<'p>(
 }
 
 pub fn store_tuple(py: Python, values: Vec<Value>) -> Value {
-    let arg_handles: Vec<_> = values
-        .into_iter()
-        .map(|v| v.consume_into_py_object(py))
-        .collect();
-    Value::from(PyTuple::new(py, &arg_handles).to_object(py))
+    store_tuple_of_objects(py, values.into_iter().map(|v| v.consume_into_py_object(py)))
+}
+
+///
+/// Store the given objects as a Python tuple, which is allocated once at its final size.
+///
+/// Converting a batch of items directly to `PyObject`s (rather than to `Value`s, which each
+/// allocate an `Arc`) and then storing them with this method avoids per-item allocations.
+///
+pub fn store_tuple_of_objects(
+    py: Python,
+    objects: impl ExactSizeIterator<Item = PyObject>,
+) -> Value {
+    Value::from(PyTuple::new(py, objects).to_object(py))
 }
 
 /// Store a slice containing 2-tuples of (key, value) as a Python dictionary.
@@ -341,26 +355,28 @@ pub(crate) fn generator_send(
         Ok(GeneratorResponse::NativeCall(call.take()?))
     } else if let Ok(get_multi) = response.downcast::<PySequence>(py) {
         // Was an `All` or `MultiGet`.
-        let gogs = get_multi
-            .iter()?
-            .map(|gog| {
-                let gog = gog?;
-                // TODO: Find a better way to check whether something is a coroutine... this seems
-                // unnecessarily awkward.
-                if gog.is_instance(generator_type.as_py_type(py).into())? {
-                    Ok(GetOrGenerator::Generator(Value::new(gog.into())))
-                } else if let Ok(get) = gog.extract::<PyRef<PyGeneratorResponseGet>>() {
-                    Ok(GetOrGenerator::Get(
-                        get.take().map_err(PyException::new_err)?,
-                    ))
-                } else {
-                    Err(PyValueError::new_err(format!(
-            "Expected an `All` or `MultiGet` to receive either `Get`s or calls to rules, \
-            but got: {response}"
-          )))
-                }
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+        // TODO: The `Get`s of a batch are still constructed (and submitted) one Python object at a
+        // time: a batched submission API which avoids that is out of scope for now.
+        let generator_type: &PyAny = generator_type.as_py_type(py).into();
+        let mut gogs = Vec::with_capacity(get_multi.len()?);
+        for gog in get_multi.iter()? {
+            let gog = gog?;
+            // TODO: Find a better way to check whether something is a coroutine... this seems
+            // unnecessarily awkward.
+            if gog.is_instance(generator_type)? {
+                gogs.push(GetOrGenerator::Generator(Value::new(gog.into())));
+            } else if let Ok(get) = gog.extract::<PyRef<PyGeneratorResponseGet>>() {
+                gogs.push(GetOrGenerator::Get(
+                    get.take().map_err(PyException::new_err)?,
+                ));
+            } else {
+                return Err(PyValueError::new_err(format!(
+                    "Expected an `All` or `MultiGet` to receive either `Get`s or calls to rules, \
+                    but got: {response}"
+                ))
+                .into());
+            }
+        }
         Ok(GeneratorResponse::All(gogs))
     } else {
         Err(PyValueError::new_err(format!(
@@ -386,6 +402,21 @@ pub fn unsafe_call(py: Python, type_id: TypeId, args: &[Value]) -> Value {
     Value::new(res.into_py(py))
 }
 
+/// Like `unsafe_call`, but converts a tuple of arguments directly, and returns the result as a
+/// `PyObject` rather than a `Value`. Intended for constructing batches of objects: see
+/// `store_tuple_of_objects`.
+pub fn unsafe_call_with(py: Python, type_id: TypeId, args: impl IntoPy<Py<PyTuple>>) -> PyObject {
+    let py_type = type_id.as_py_type(py);
+    let res = py_type.call1(args).unwrap_or_else(|e| {
+        panic!(
+            "Core type constructor `{}` failed: {:?}",
+            py_type.name().unwrap(),
+            e
+        );
+    });
+    res.into_py(py)
+}
+
 lazy_static! {
     pub static ref INTERNS: Interns = Interns::new();
 }
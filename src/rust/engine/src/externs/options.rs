@@ -6,16 +6,25 @@ use pyo3::prelude::*;
 use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
 
 use options::{
-    Args, ConfigSource, Env, ListOptionValue, OptionId, OptionParser, OptionalOptionValue, Scope,
-    Val,
+    completion_script, config_schema, did_you_mean, directory_config_paths, Args, BuildRoot,
+    CompletionOption, CompletionScope, ConfigSource, Env, FromfileExpander, ListOptionValue,
+    OptionId, OptionParser, OptionalOptionValue, SchemaOption, SchemaScope, SchemaType, Scope,
+    Shell, Val, ValueType,
 };
 
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 pub(crate) fn register(m: &PyModule) -> PyResult<()> {
     m.add_class::<PyOptionId>()?;
     m.add_class::<PyConfigSource>()?;
     m.add_class::<PyOptionParser>()?;
+    m.add_function(wrap_pyfunction!(generate_completion_script, m)?)?;
+    m.add_function(wrap_pyfunction!(generate_config_schema, m)?)?;
+    m.add_function(wrap_pyfunction!(read_dotenv_file, m)?)?;
+    m.add_function(wrap_pyfunction!(py_did_you_mean, m)?)?;
+    m.add_function(wrap_pyfunction!(py_directory_config_paths, m)?)?;
+    m.add_function(wrap_pyfunction!(resolve_option_secret, m)?)?;
     Ok(())
 }
 
@@ -297,4 +306,135 @@ impl PyOptionParser {
     fn get_passthrough_args(&self) -> PyResult<Option<Vec<String>>> {
         Ok(self.0.get_passthrough_args().cloned())
     }
+
+    fn with_directory_config(&self, configs: Vec<PyRef<PyConfigSource>>) -> PyResult<Self> {
+        let option_parser = self
+            .0
+            .with_directory_config(configs.iter().map(|c| c.0.clone()).collect())
+            .map_err(PyValueError::new_err)?;
+        Ok(Self(option_parser))
+    }
+}
+
+/// A registered option: its id, whether it is a bool, its help and its choices.
+type PyCompletionOption<'a> = (PyRef<'a, PyOptionId>, bool, String, Vec<String>);
+
+/// A registered scope: its name, whether it is a goal, its help and its options.
+type PyCompletionScope<'a> = (String, bool, String, Vec<PyCompletionOption<'a>>);
+
+#[pyfunction]
+fn generate_completion_script(shell: &str, scopes: Vec<PyCompletionScope>) -> PyResult<String> {
+    let shell = shell.parse::<Shell>().map_err(PyValueError::new_err)?;
+    let scopes = scopes
+        .into_iter()
+        .map(|(name, is_goal, help, options)| CompletionScope {
+            scope: Scope::named(&name),
+            is_goal,
+            help,
+            options: options
+                .into_iter()
+                .map(|(option_id, is_bool, help, choices)| CompletionOption {
+                    id: option_id.0.clone(),
+                    is_bool,
+                    help,
+                    choices,
+                })
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    Ok(completion_script(shell, &scopes))
+}
+
+/// A registered option: its id, the names of its type and member type (for lists), its default
+/// as JSON, its help, its choices and, if it is deprecated, a deprecation message.
+type PySchemaOption<'a> = (
+    PyRef<'a, PyOptionId>,
+    String,
+    Option<String>,
+    String,
+    String,
+    Vec<String>,
+    Option<String>,
+);
+
+/// A registered scope: its name, its help, a deprecation message if it is deprecated, and its
+/// options.
+type PySchemaScope<'a> = (String, String, Option<String>, Vec<PySchemaOption<'a>>);
+
+fn schema_option(option: PySchemaOption) -> Result<SchemaOption, String> {
+    let (option_id, option_type, member_type, default, help, choices, deprecation) = option;
+    let option_type = match (option_type.as_str(), member_type) {
+        ("list", Some(member_type)) => SchemaType::List(member_type.parse()?),
+        ("list", None) => SchemaType::List(ValueType::String),
+        ("dict", _) => SchemaType::Dict,
+        (option_type, _) => SchemaType::Scalar(option_type.parse()?),
+    };
+    let default = serde_json::from_str(&default).map_err(|e| {
+        format!(
+            "Invalid default for {}: {e}",
+            option_id.0.name_underscored()
+        )
+    })?;
+    Ok(SchemaOption {
+        id: option_id.0.clone(),
+        option_type,
+        default,
+        help,
+        choices,
+        deprecation,
+    })
+}
+
+#[pyfunction]
+fn generate_config_schema(scopes: Vec<PySchemaScope>) -> PyResult<String> {
+    let scopes = scopes
+        .into_iter()
+        .map(|(name, help, deprecation, options)| {
+            Ok(SchemaScope {
+                scope: Scope::named(&name),
+                help,
+                deprecation,
+                options: options
+                    .into_iter()
+                    .map(schema_option)
+                    .collect::<Result<Vec<_>, _>>()?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(PyValueError::new_err)?;
+    serde_json::to_string_pretty(&config_schema(&scopes))
+        .map_err(|e| PyException::new_err(e.to_string()))
+}
+
+#[pyfunction]
+fn read_dotenv_file(path: &str) -> PyResult<HashMap<String, String>> {
+    let env = Env::new(HashMap::new())
+        .with_dotenv_file(Path::new(path))
+        .map_err(PyValueError::new_err)?;
+    Ok(Vec::from(&env).into_iter().collect())
+}
+
+#[pyfunction]
+#[pyo3(name = "did_you_mean")]
+fn py_did_you_mean(name: &str, candidates: Vec<String>) -> Vec<String> {
+    did_you_mean(name, candidates.iter().map(String::as_str))
+        .into_iter()
+        .map(str::to_owned)
+        .collect()
+}
+
+#[pyfunction]
+#[pyo3(name = "directory_config_paths")]
+fn py_directory_config_paths(directory: &str) -> Vec<String> {
+    directory_config_paths(Path::new(directory))
+        .into_iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect()
+}
+
+#[pyfunction]
+fn resolve_option_secret(build_root: &str, value: &str, name: &str) -> PyResult<String> {
+    FromfileExpander::relative_to(BuildRoot::for_path(PathBuf::from(build_root)))
+        .resolve_secret_ref(value, name)
+        .map_err(PyValueError::new_err)
 }
@@ -2,17 +2,91 @@
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
 use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Once;
 
 use pyo3::exceptions::PyException;
 use pyo3::prelude::*;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
 
 use options::{Args, BuildRoot, Env, OptionParser};
 
 pub fn register(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(pantsd_fingerprint_compute, m)?)?;
+    m.add_function(wrap_pyfunction!(pantsd_tokio_console_init, m)?)?;
+    m.add_function(wrap_pyfunction!(pantsd_heap_profiling_activate, m)?)?;
+    m.add_function(wrap_pyfunction!(pantsd_heap_profile_dump, m)?)?;
     Ok(())
 }
 
+/// Installs the `console-subscriber` layer, so that `tokio-console` can attach to this process.
+///
+/// The layer is configured by the environment variables of `console-subscriber`: in particular,
+/// it listens on `TOKIO_CONSOLE_BIND` (by default `127.0.0.1:6669`). Only the first call has any
+/// effect, and failing to install the layer (because some other global `tracing` subscriber has
+/// already been installed) is logged rather than fatal.
+#[pyfunction]
+fn pantsd_tokio_console_init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        let console_layer = console_subscriber::ConsoleLayer::builder()
+            .with_default_env()
+            .spawn();
+        if let Err(e) = tracing_subscriber::registry()
+            .with(console_layer)
+            .try_init()
+        {
+            log::warn!("Failed to install the tokio-console layer: {e}");
+        }
+    });
+}
+
+/// Starts sampling allocations, so that `pantsd_heap_profile_dump` can report on them.
+#[pyfunction]
+fn pantsd_heap_profiling_activate() -> PyResult<()> {
+    #[cfg(target_os = "linux")]
+    unsafe {
+        tikv_jemalloc_ctl::raw::write(b"prof.active\0", true)
+            .map_err(|e| PyException::new_err(format!("Failed to activate heap profiling: {e}")))
+    }
+    #[cfg(not(target_os = "linux"))]
+    Err(PyException::new_err(
+        "Heap profiling is only supported on Linux.",
+    ))
+}
+
+/// Writes a profile of the sampled live allocations to the given path, in the format of
+/// jemalloc's `jeprof` (which can also render flamegraphs of it).
+#[pyfunction]
+fn pantsd_heap_profile_dump(path: PathBuf) -> PyResult<()> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        let active: bool = unsafe { tikv_jemalloc_ctl::raw::read(b"prof.active\0") }
+            .map_err(|e| PyException::new_err(format!("Failed to query heap profiling: {e}")))?;
+        if !active {
+            return Err(PyException::new_err(
+                "Heap profiling is not active: restart pantsd with `--pantsd-heap-profiling`.",
+            ));
+        }
+        let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| PyException::new_err(format!("Invalid path {}: {e}", path.display())))?;
+        unsafe { tikv_jemalloc_ctl::raw::write(b"prof.dump\0", c_path.as_ptr()) }.map_err(|e| {
+            PyException::new_err(format!(
+                "Failed to write a heap profile to {}: {e}",
+                path.display()
+            ))
+        })
+    }
+    #[cfg(not(target_os = "linux"))]
+    Err(PyException::new_err(format!(
+        "Heap profiling is only supported on Linux: cannot write {}.",
+        path.display()
+    )))
+}
+
 /// Computes the current `pantsd` fingerprint.
 ///
 /// Validates that the given expected pantsd fingerprint option names (all in the global scope)
@@ -46,3 +120,14 @@ fn pantsd_fingerprint_compute(expected_option_names: HashSet<String>) -> PyResul
 
     pantsd::fingerprint_compute(&build_root, &options_parser).map_err(PyException::new_err)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::pantsd_tokio_console_init;
+
+    #[test]
+    fn tokio_console_init_twice() {
+        pantsd_tokio_console_init();
+        pantsd_tokio_console_init();
+    }
+}
@@ -26,8 +26,13 @@ pub struct PyExecutor(pub task_executor::Executor);
 #[pymethods]
 impl PyExecutor {
     #[new]
-    fn __new__(core_threads: usize, max_threads: usize) -> PyResult<Self> {
-        task_executor::Executor::new_owned(core_threads, max_threads, || {
+    #[pyo3(signature = (core_threads, max_threads, io_threads=None))]
+    fn __new__(
+        core_threads: usize,
+        max_threads: usize,
+        io_threads: Option<usize>,
+    ) -> PyResult<Self> {
+        task_executor::Executor::new_owned(core_threads, max_threads, io_threads, || {
             // NB: We need a PyThreadState object which lives throughout the lifetime of this thread
             // as the debug trace object is attached to it. Otherwise the PyThreadState is
             // constructed/destroyed with each `with_gil` call (inside PyGILState_Ensure/PyGILState_Release).
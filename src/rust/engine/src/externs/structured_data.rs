@@ -0,0 +1,133 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::sync::Arc;
+
+use hashing::Digest;
+use pyo3::basic::CompareOp;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+
+pub(crate) fn register(m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyStructuredData>()
+}
+
+///
+/// A parsed JSON, TOML or YAML file.
+///
+/// Rather than converting the entire document into Python structures (which is expensive for large
+/// files), the document is held natively, and is queried by JSON pointer (RFC 6901). Two instances
+/// are equal if they were parsed from the same content in the same format.
+///
+#[pyclass(name = "StructuredData")]
+#[derive(Clone, Debug)]
+pub struct PyStructuredData {
+    pub path: String,
+    pub format: String,
+    pub content_digest: Digest,
+    pub value: Arc<serde_json::Value>,
+}
+
+impl PyStructuredData {
+    fn lookup(&self, pointer: &str) -> Option<&serde_json::Value> {
+        self.value.pointer(pointer)
+    }
+}
+
+#[pymethods]
+impl PyStructuredData {
+    fn __hash__(&self) -> u64 {
+        self.content_digest.hash.prefix_hash()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "StructuredData(path='{}', format='{}')",
+            self.path, self.format
+        )
+    }
+
+    fn __richcmp__(&self, other: &PyStructuredData, op: CompareOp, py: Python) -> PyObject {
+        let eq = self.content_digest == other.content_digest && self.format == other.format;
+        match op {
+            CompareOp::Eq => eq.into_py(py),
+            CompareOp::Ne => (!eq).into_py(py),
+            _ => py.NotImplemented(),
+        }
+    }
+
+    #[getter]
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    #[getter]
+    fn format(&self) -> &str {
+        &self.format
+    }
+
+    /// The value at the given JSON pointer (or the whole document for the empty pointer) as Python
+    /// structures, or `default` if there is no such value.
+    #[pyo3(signature = (pointer = "", default = None))]
+    fn get(&self, py: Python, pointer: &str, default: Option<PyObject>) -> PyResult<PyObject> {
+        match self.lookup(pointer) {
+            Some(value) => to_py_object(py, value),
+            None => Ok(default.unwrap_or_else(|| py.None())),
+        }
+    }
+
+    /// The keys of the mapping at the given JSON pointer, or None if there is no mapping there.
+    #[pyo3(signature = (pointer = ""))]
+    fn keys(&self, pointer: &str) -> Option<Vec<String>> {
+        self.lookup(pointer)
+            .and_then(serde_json::Value::as_object)
+            .map(|object| object.keys().cloned().collect())
+    }
+
+    /// The number of items in the mapping or sequence at the given JSON pointer, or None if there
+    /// is no mapping or sequence there.
+    #[pyo3(signature = (pointer = ""))]
+    fn length(&self, pointer: &str) -> Option<usize> {
+        match self.lookup(pointer)? {
+            serde_json::Value::Object(object) => Some(object.len()),
+            serde_json::Value::Array(array) => Some(array.len()),
+            _ => None,
+        }
+    }
+
+    fn __contains__(&self, pointer: &str) -> bool {
+        self.lookup(pointer).is_some()
+    }
+}
+
+/// Convert a parsed value into (mutable) Python structures.
+fn to_py_object(py: Python, value: &serde_json::Value) -> PyResult<PyObject> {
+    Ok(match value {
+        serde_json::Value::Null => py.None(),
+        serde_json::Value::Bool(b) => b.into_py(py),
+        serde_json::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                i.into_py(py)
+            } else if let Some(u) = n.as_u64() {
+                u.into_py(py)
+            } else {
+                n.as_f64().unwrap_or(f64::NAN).into_py(py)
+            }
+        }
+        serde_json::Value::String(s) => s.into_py(py),
+        serde_json::Value::Array(array) => {
+            let list = PyList::empty(py);
+            for item in array {
+                list.append(to_py_object(py, item)?)?;
+            }
+            list.into_py(py)
+        }
+        serde_json::Value::Object(object) => {
+            let dict = PyDict::new(py);
+            for (key, item) in object {
+                dict.set_item(key, to_py_object(py, item)?)?;
+            }
+            dict.into_py(py)
+        }
+    })
+}
@@ -0,0 +1,130 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::time::{Duration, Instant};
+
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyType};
+
+use crate::externs;
+use crate::python::{TypeId, Value};
+
+const FILE_CONTENT: &str = r#"
+from dataclasses import dataclass
+
+@dataclass(frozen=True)
+class FileContent:
+    path: str
+    content: bytes
+    is_executable: bool
+"#;
+
+fn file_content_type(py: Python) -> TypeId {
+    let module = PyModule::from_code(py, FILE_CONTENT, "file_content.py", "file_content").unwrap();
+    TypeId::new(
+        module
+            .getattr("FileContent")
+            .unwrap()
+            .downcast::<PyType>()
+            .unwrap(),
+    )
+}
+
+/// Converts `FileContent`s as they were before batching: via a `Value` per entry and per field.
+fn file_contents_via_values(py: Python, file_content: TypeId, count: usize) -> Value {
+    let entries = (0..count)
+        .map(|i| {
+            externs::unsafe_call(
+                py,
+                file_content,
+                &[
+                    externs::store_utf8(py, &format!("file{i}")),
+                    externs::store_bytes(py, b"content"),
+                    externs::store_bool(py, i % 2 == 0),
+                ],
+            )
+        })
+        .collect::<Vec<_>>();
+    externs::store_tuple(py, entries)
+}
+
+/// Converts `FileContent`s as `Snapshot::store_digest_contents` does.
+fn file_contents_batched(py: Python, file_content: TypeId, count: usize) -> Value {
+    let mut entries = Vec::with_capacity(count);
+    for i in 0..count {
+        entries.push(externs::unsafe_call_with(
+            py,
+            file_content,
+            (
+                format!("file{i}").as_str(),
+                PyBytes::new(py, b"content"),
+                i % 2 == 0,
+            ),
+        ));
+    }
+    externs::store_tuple_of_objects(py, entries.into_iter())
+}
+
+#[test]
+fn batched_conversion_is_equivalent() {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let file_content = file_content_type(py);
+        let batched = file_contents_batched(py, file_content, 3);
+        assert_eq!(file_contents_via_values(py, file_content, 3), batched);
+
+        let batched = batched.as_ref().as_ref(py);
+        assert_eq!(batched.len().unwrap(), 3);
+        let first = batched.get_item(0).unwrap();
+        assert_eq!(externs::getattr::<String>(first, "path").unwrap(), "file0");
+        assert_eq!(
+            externs::getattr::<&[u8]>(first, "content").unwrap(),
+            b"content"
+        );
+        assert!(externs::getattr::<bool>(first, "is_executable").unwrap());
+    });
+}
+
+#[test]
+fn store_tuple_of_objects_empty() {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let empty = externs::store_tuple_of_objects(py, std::iter::empty());
+        assert_eq!(empty, externs::store_tuple(py, vec![]));
+        assert_eq!(empty.as_ref().as_ref(py).len().unwrap(), 0);
+    });
+}
+
+///
+/// Compares the cost of converting a large digest's worth of entries before and after batching.
+///
+/// NB: Timings are too noisy to assert on in CI: run with `--ignored --nocapture`.
+///
+#[test]
+#[ignore]
+fn batched_conversion_microbenchmark() {
+    const COUNT: usize = 100_000;
+    const ITERATIONS: u32 = 10;
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let file_content = file_content_type(py);
+        let time = |convert: fn(Python, TypeId, usize) -> Value| -> Duration {
+            // Warm up.
+            convert(py, file_content, COUNT);
+            let start = Instant::now();
+            for _ in 0..ITERATIONS {
+                convert(py, file_content, COUNT);
+            }
+            start.elapsed() / ITERATIONS
+        };
+
+        let via_values = time(file_contents_via_values);
+        let batched = time(file_contents_batched);
+        println!(
+            "Converted {COUNT} FileContents in {via_values:?} via `Value`s, and in {batched:?} \
+            batched ({:.1}% faster).",
+            100.0 * (1.0 - batched.as_secs_f64() / via_values.as_secs_f64()),
+        );
+        assert!(batched < via_values);
+    });
+}
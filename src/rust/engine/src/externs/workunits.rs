@@ -1,11 +1,14 @@
 // Copyright 2021 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
+use pyo3::exceptions::{PyException, PyValueError};
 use pyo3::prelude::*;
-use workunit_store::Metric;
+use workunit_store::{get_workunit_store_handle, Metric, WorkunitStore};
 
 pub fn register(m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(all_counter_names, m)?)?;
+    m.add_function(wrap_pyfunction!(increment_custom_counter, m)?)?;
+    m.add_function(wrap_pyfunction!(record_custom_observation, m)?)?;
     Ok(())
 }
 
@@ -13,3 +16,31 @@ pub fn register(m: &PyModule) -> PyResult<()> {
 fn all_counter_names() -> Vec<String> {
     Metric::all_metrics()
 }
+
+fn current_workunit_store() -> PyResult<WorkunitStore> {
+    get_workunit_store_handle()
+        .map(|handle| handle.store)
+        .ok_or_else(|| {
+            PyException::new_err("Custom metrics may only be recorded while rules are running.")
+        })
+}
+
+#[pyfunction]
+fn increment_custom_counter(name: &str, change: u64, display: bool) -> PyResult<()> {
+    let store = current_workunit_store()?;
+    if display {
+        store
+            .display_custom_counter(name)
+            .map_err(PyValueError::new_err)?;
+    }
+    store
+        .increment_custom_counter(name, change)
+        .map_err(PyValueError::new_err)
+}
+
+#[pyfunction]
+fn record_custom_observation(name: &str, value: u64) -> PyResult<()> {
+    current_workunit_store()?
+        .record_custom_observation(name, value)
+        .map_err(PyValueError::new_err)
+}
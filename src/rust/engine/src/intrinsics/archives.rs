@@ -0,0 +1,1006 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Cursor, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use bytes::Bytes;
+use fs::{DigestTrie, Entry, RelativePath, SymlinkBehavior, TypedPath};
+use hashing::{Digest, Fingerprint, EMPTY_DIGEST};
+use pyo3::prelude::{pyfunction, wrap_pyfunction, PyModule, PyResult, Python};
+
+use crate::externs;
+use crate::externs::PyGeneratorResponseNativeCall;
+use crate::nodes::{lift_directory_digest, task_get_context, NodeResult, Snapshot};
+use crate::python::{throw, Value};
+use crate::Failure;
+
+pub fn register(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(create_archive_to_digest, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_archive_to_digest, m)?)?;
+
+    Ok(())
+}
+
+#[pyfunction]
+fn extract_archive_to_digest(extract_archive: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+        let store = context.core.store();
+
+        let (digest, use_suffix) = Python::with_gil(|py| {
+            let py_extract_archive = extract_archive.as_ref().as_ref(py);
+            let digest = lift_directory_digest(externs::getattr(py_extract_archive, "digest")?)?;
+            let use_suffix: Option<String> = externs::getattr(py_extract_archive, "use_suffix")?;
+            let res: NodeResult<_> = Ok((digest, use_suffix));
+            res
+        })?;
+
+        let trie = store.load_digest_trie(digest).await?;
+        let archive_path = match &trie.files(SymlinkBehavior::Oblivious)[..] {
+            [archive_path] => archive_path.clone(),
+            files => {
+                return Err(throw(format!(
+                    "Expected a digest containing exactly one archive file, but it contained {} \
+                     files.",
+                    files.len()
+                )))
+            }
+        };
+        let archive_digest = match trie.entry(&archive_path)? {
+            Some(Entry::File(archive_file)) => archive_file.digest(),
+            _ => {
+                return Err(throw(format!(
+                    "Failed to locate the archive file {}.",
+                    archive_path.display()
+                )))
+            }
+        };
+
+        let suffix = use_suffix.unwrap_or_else(|| {
+            archive_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+        let format = ArchiveFormat::for_suffix(&suffix).ok_or_else(|| {
+            throw(format!(
+                "Cannot natively extract {}: unsupported archive suffix `{suffix}`.",
+                archive_path.display()
+            ))
+        })?;
+
+        let content = store
+            .load_file_bytes_with(archive_digest, Bytes::copy_from_slice)
+            .await?;
+        let (items_to_store, trie) = context
+            .core
+            .executor
+            .spawn_blocking(
+                {
+                    let archive_path = archive_path.clone();
+                    move || {
+                        let mut entries = ExtractedEntries::default();
+                        entries.extract(format, &archive_path, content)?;
+                        entries.into_digest_trie()
+                    }
+                },
+                |e| Err(format!("Archive extraction task failed: {e}")),
+            )
+            .await
+            .map_err(|e| throw(format!("Failed to extract {}: {e}", archive_path.display())))?;
+
+        store.store_file_bytes_batch(items_to_store, true).await?;
+        Ok::<_, Failure>(Python::with_gil(|py| {
+            Snapshot::store_directory_digest(py, trie.into())
+        })?)
+    })
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ArchiveFormat {
+    Tar,
+    TarZstd,
+    TarXz,
+    SevenZip,
+    Zstd,
+    Xz,
+}
+
+impl ArchiveFormat {
+    fn for_suffix(suffix: &str) -> Option<ArchiveFormat> {
+        // NB: Longer suffixes must be matched before any suffixes of their own.
+        [
+            (".tar.zst", ArchiveFormat::TarZstd),
+            (".tzst", ArchiveFormat::TarZstd),
+            (".tar.xz", ArchiveFormat::TarXz),
+            (".txz", ArchiveFormat::TarXz),
+            (".tar", ArchiveFormat::Tar),
+            (".7z", ArchiveFormat::SevenZip),
+            (".zst", ArchiveFormat::Zstd),
+            (".xz", ArchiveFormat::Xz),
+        ]
+        .into_iter()
+        .find(|(s, _)| suffix.ends_with(s))
+        .map(|(_, format)| format)
+    }
+}
+
+enum ExtractedEntry {
+    File { content: Bytes, is_executable: bool },
+    Link(PathBuf),
+    Dir,
+}
+
+///
+/// The entries of an extracted archive, keyed by their normalized relative paths.
+///
+/// Archives may legally contain multiple entries for the same path, in which case the last entry
+/// wins (as it would if the archive were extracted to disk).
+///
+#[derive(Default)]
+struct ExtractedEntries(BTreeMap<PathBuf, ExtractedEntry>);
+
+impl ExtractedEntries {
+    fn extract(
+        &mut self,
+        format: ArchiveFormat,
+        archive_path: &Path,
+        content: Bytes,
+    ) -> Result<(), String> {
+        let reader = Cursor::new(content);
+        match format {
+            ArchiveFormat::Tar => self.extract_tar(reader),
+            ArchiveFormat::TarZstd => self
+                .extract_tar(zstd::stream::read::Decoder::new(reader).map_err(|e| e.to_string())?),
+            ArchiveFormat::TarXz => self.extract_tar(xz2::read::XzDecoder::new(reader)),
+            ArchiveFormat::SevenZip => self.extract_7z(reader),
+            ArchiveFormat::Zstd => self.decompress_file(
+                archive_path,
+                zstd::stream::read::Decoder::new(reader).map_err(|e| e.to_string())?,
+            ),
+            ArchiveFormat::Xz => {
+                self.decompress_file(archive_path, xz2::read::XzDecoder::new(reader))
+            }
+        }
+    }
+
+    fn insert(&mut self, path: &Path, entry: ExtractedEntry) -> Result<(), String> {
+        let path: PathBuf = RelativePath::new(path)?.into();
+        if path.as_os_str().is_empty() {
+            // The root of the archive (generally `./`) is implied.
+            return Ok(());
+        }
+        self.0.insert(path, entry);
+        Ok(())
+    }
+
+    ///
+    /// Decompresses a single (non-archive) compressed file to a file named like the archive, minus
+    /// its compression suffix.
+    ///
+    fn decompress_file(
+        &mut self,
+        archive_path: &Path,
+        mut reader: impl Read,
+    ) -> Result<(), String> {
+        let file_name = archive_path
+            .file_stem()
+            .ok_or_else(|| format!("Invalid archive path: {}", archive_path.display()))?;
+        let mut content = Vec::new();
+        reader
+            .read_to_end(&mut content)
+            .map_err(|e| format!("Failed to decompress: {e}"))?;
+        self.insert(
+            Path::new(file_name),
+            ExtractedEntry::File {
+                content: content.into(),
+                is_executable: false,
+            },
+        )
+    }
+
+    fn extract_tar(&mut self, reader: impl Read) -> Result<(), String> {
+        let mut archive = tar::Archive::new(reader);
+        let entries = archive
+            .entries()
+            .map_err(|e| format!("Failed to read tar entries: {e}"))?;
+        for entry in entries {
+            let mut entry = entry.map_err(|e| format!("Failed to read tar entry: {e}"))?;
+            let path = entry
+                .path()
+                .map_err(|e| format!("Invalid tar entry path: {e}"))?
+                .into_owned();
+            let header = entry.header();
+            let entry_type = header.entry_type();
+            let mode = header.mode().unwrap_or(0o644);
+            let link_name = entry
+                .link_name()
+                .map_err(|e| format!("Invalid link target for {}: {e}", path.display()))?
+                .map(|link_name| link_name.into_owned());
+
+            let extracted = match entry_type {
+                tar::EntryType::Regular | tar::EntryType::Continuous => {
+                    let mut content = Vec::with_capacity(entry.size() as usize);
+                    entry
+                        .read_to_end(&mut content)
+                        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+                    ExtractedEntry::File {
+                        content: content.into(),
+                        is_executable: mode & 0o111 != 0,
+                    }
+                }
+                tar::EntryType::Directory => ExtractedEntry::Dir,
+                tar::EntryType::Symlink => ExtractedEntry::Link(
+                    link_name
+                        .ok_or_else(|| format!("Symlink {} had no target.", path.display()))?,
+                ),
+                tar::EntryType::Link => {
+                    // Hardlinks refer to earlier entries by their path within the archive.
+                    let target = link_name
+                        .ok_or_else(|| format!("Hardlink {} had no target.", path.display()))?;
+                    let target: PathBuf = RelativePath::new(&target)?.into();
+                    match self.0.get(&target) {
+                        Some(ExtractedEntry::File {
+                            content,
+                            is_executable,
+                        }) => ExtractedEntry::File {
+                            content: content.clone(),
+                            is_executable: *is_executable,
+                        },
+                        _ => {
+                            return Err(format!(
+                                "Hardlink {} refers to {}, which is not an earlier file entry.",
+                                path.display(),
+                                target.display()
+                            ))
+                        }
+                    }
+                }
+                tar::EntryType::XGlobalHeader | tar::EntryType::XHeader => continue,
+                other => {
+                    return Err(format!(
+                        "Unsupported tar entry type {other:?} for {}.",
+                        path.display()
+                    ))
+                }
+            };
+            self.insert(&path, extracted)?;
+        }
+        Ok(())
+    }
+
+    fn extract_7z(&mut self, reader: Cursor<Bytes>) -> Result<(), String> {
+        // The high 16 bits of the attributes contain a unix mode if this flag is set.
+        const FILE_ATTRIBUTE_UNIX_EXTENSION: u32 = 0x8000;
+        const S_IFMT: u32 = 0o170000;
+        const S_IFLNK: u32 = 0o120000;
+
+        let len = reader.get_ref().len() as u64;
+        let mut archive =
+            sevenz_rust::SevenZReader::new(reader, len, sevenz_rust::Password::empty())
+                .map_err(|e| format!("Failed to read 7z archive: {e}"))?;
+        let mut result = Ok(());
+        archive
+            .for_each_entries(|entry, entry_reader| {
+                let path = PathBuf::from(entry.name());
+                let mode = if entry.has_windows_attributes
+                    && entry.windows_attributes & FILE_ATTRIBUTE_UNIX_EXTENSION != 0
+                {
+                    Some(entry.windows_attributes >> 16)
+                } else {
+                    None
+                };
+
+                let extracted = if entry.is_directory() {
+                    ExtractedEntry::Dir
+                } else {
+                    let mut content = Vec::new();
+                    entry_reader.read_to_end(&mut content)?;
+                    match mode {
+                        Some(mode) if mode & S_IFMT == S_IFLNK => ExtractedEntry::Link(
+                            PathBuf::from(String::from_utf8_lossy(&content).as_ref()),
+                        ),
+                        mode => ExtractedEntry::File {
+                            content: content.into(),
+                            is_executable: mode.map_or(false, |mode| mode & 0o111 != 0),
+                        },
+                    }
+                };
+                result = self.insert(&path, extracted);
+                Ok(result.is_ok())
+            })
+            .map_err(|e| format!("Failed to read 7z archive: {e}"))?;
+        result
+    }
+
+    ///
+    /// Converts the extracted entries into a DigestTrie, along with the file content that must be
+    /// stored for it.
+    ///
+    fn into_digest_trie(self) -> Result<(Vec<(Fingerprint, Bytes)>, DigestTrie), String> {
+        let mut typed_paths = Vec::with_capacity(self.0.len());
+        let mut file_digests = HashMap::with_capacity(self.0.len());
+        let mut items_to_store = Vec::new();
+        for (path, entry) in &self.0 {
+            // Parent directories are implied by their children, and a non-directory may not have any.
+            if let Some((parent, _)) = path
+                .ancestors()
+                .skip(1)
+                .filter_map(|ancestor| self.0.get_key_value(ancestor))
+                .find(|(_, entry)| !matches!(entry, ExtractedEntry::Dir))
+            {
+                return Err(format!(
+                    "{} is nested below {}, which is not a directory.",
+                    path.display(),
+                    parent.display()
+                ));
+            }
+            match entry {
+                ExtractedEntry::File {
+                    content,
+                    is_executable,
+                } => {
+                    let digest = Digest::of_bytes(content);
+                    items_to_store.push((digest.hash, content.clone()));
+                    typed_paths.push(TypedPath::File {
+                        path,
+                        is_executable: *is_executable,
+                    });
+                    file_digests.insert(path.clone(), digest);
+                }
+                ExtractedEntry::Link(target) => {
+                    typed_paths.push(TypedPath::Link { path, target });
+                    file_digests.insert(path.clone(), *EMPTY_DIGEST);
+                }
+                ExtractedEntry::Dir => {
+                    typed_paths.push(TypedPath::Dir(path));
+                    file_digests.insert(path.clone(), *EMPTY_DIGEST);
+                }
+            }
+        }
+        let trie = DigestTrie::from_unique_paths(typed_paths, &file_digests)?;
+        Ok((items_to_store, trie))
+    }
+}
+
+#[pyfunction]
+fn create_archive_to_digest(create_archive: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+        let store = context.core.store();
+
+        let (digest, output_filename, format, compression_level) = Python::with_gil(|py| {
+            let py_create_archive = create_archive.as_ref().as_ref(py);
+            let digest = lift_directory_digest(externs::getattr(py_create_archive, "digest")?)?;
+            let output_filename: String = externs::getattr(py_create_archive, "output_filename")?;
+            let format: String = externs::getattr(py_create_archive, "format")?;
+            let compression_level: Option<u32> =
+                externs::getattr(py_create_archive, "compression_level")?;
+            let res: NodeResult<_> = Ok((digest, output_filename, format, compression_level));
+            res
+        })?;
+        let output_path = RelativePath::new(&output_filename)
+            .map_err(|e| throw(format!("The `output_filename` must be relative: {e}")))?;
+        let format = OutputFormat::from_str(&format).map_err(throw)?;
+        format
+            .validate_compression_level(compression_level)
+            .map_err(throw)?;
+
+        // Collect the entries in the (sorted) order of the DigestTrie, which makes the order of
+        // entries in the archive deterministic.
+        let trie = store.load_digest_trie(digest).await?;
+        let mut entries = Vec::new();
+        trie.walk(SymlinkBehavior::Aware, &mut |path, entry| match entry {
+            // Non-empty directories are implied by their contents.
+            Entry::Directory(d)
+                if !path.as_os_str().is_empty() && d.tree().entries().is_empty() =>
+            {
+                entries.push((path.to_owned(), ArchiveEntry::Dir));
+            }
+            Entry::Directory(_) => {}
+            Entry::File(f) => entries.push((
+                path.to_owned(),
+                ArchiveEntry::File {
+                    digest: f.digest(),
+                    is_executable: f.is_executable(),
+                },
+            )),
+            Entry::Symlink(s) => {
+                entries.push((path.to_owned(), ArchiveEntry::Link(s.target().to_owned())));
+            }
+        });
+        // The archive is written to a temporary file, and the content of each file is loaded from
+        // the Store only while it is being written, so that neither the inputs nor the archive are
+        // held in memory all at once.
+        let archive_file = tempfile::NamedTempFile::new_in(&context.core.local_execution_root_dir)
+            .map_err(|e| throw(format!("Failed to create a temporary file: {e}")))?;
+        let archive_file = context
+            .core
+            .executor
+            .spawn_blocking(
+                {
+                    let (executor, store) = (context.core.executor.clone(), store.clone());
+                    move || {
+                        let load_content = |digest: Digest| {
+                            executor
+                                .block_on(
+                                    store.load_file_bytes_with(digest, Bytes::copy_from_slice),
+                                )
+                                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
+                        };
+                        format.write(archive_file, &entries, load_content, compression_level)
+                    }
+                },
+                |e| Err(io::Error::new(io::ErrorKind::Other, e)),
+            )
+            .await
+            .map_err(|e| throw(format!("Failed to create {output_filename}: {e}")))?;
+
+        let archive_digest = store
+            .store_file(true, false, archive_file.path().to_owned())
+            .await?;
+        let trie = DigestTrie::from_unique_paths(
+            vec![TypedPath::File {
+                path: &output_path,
+                is_executable: false,
+            }],
+            &HashMap::from([(output_path.to_path_buf(), archive_digest)]),
+        )?;
+        Ok::<_, Failure>(Python::with_gil(|py| {
+            Snapshot::store_directory_digest(py, trie.into())
+        })?)
+    })
+}
+
+///
+/// The modification time used for all entries of created archives: 1980-01-01T00:00:00Z, which is
+/// the earliest time that can be represented in a zip file.
+///
+const NORMALIZED_MTIME: u64 = 315_532_800;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum OutputFormat {
+    Tar,
+    TarGzip,
+    TarBzip2,
+    TarXz,
+    TarZstd,
+    Zip,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tar" => Ok(OutputFormat::Tar),
+            "tar.gz" => Ok(OutputFormat::TarGzip),
+            "tar.bz2" => Ok(OutputFormat::TarBzip2),
+            "tar.xz" => Ok(OutputFormat::TarXz),
+            "tar.zst" => Ok(OutputFormat::TarZstd),
+            "zip" => Ok(OutputFormat::Zip),
+            unknown => Err(format!(
+                "Unsupported archive format `{unknown}`: expected one of `tar`, `tar.gz`, \
+                 `tar.bz2`, `tar.xz`, `tar.zst`, or `zip`."
+            )),
+        }
+    }
+}
+
+enum ArchiveEntry {
+    File { digest: Digest, is_executable: bool },
+    Link(PathBuf),
+    Dir,
+}
+
+impl OutputFormat {
+    ///
+    /// Validates that the given `compression_level` is supported by this format, since the
+    /// compression libraries panic (rather than failing) for levels outside of their ranges.
+    ///
+    fn validate_compression_level(self, compression_level: Option<u32>) -> Result<(), String> {
+        let Some(level) = compression_level else {
+            return Ok(());
+        };
+        let (name, range) = match self {
+            OutputFormat::Tar => {
+                return Err(format!(
+                    "A `compression_level` of {level} was given, but the `tar` format is not \
+                     compressed."
+                ))
+            }
+            OutputFormat::TarGzip => ("tar.gz", 0..=9),
+            OutputFormat::TarBzip2 => ("tar.bz2", 1..=9),
+            OutputFormat::TarXz => ("tar.xz", 0..=9),
+            OutputFormat::TarZstd => ("tar.zst", 1..=22),
+            OutputFormat::Zip => ("zip", 0..=9),
+        };
+        if range.contains(&level) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid `compression_level` {level} for the `{name}` format: expected a level \
+                 between {} and {}.",
+                range.start(),
+                range.end()
+            ))
+        }
+    }
+
+    ///
+    /// Write an archive of the given entries to the given writer, loading the content of each file
+    /// entry (by its Digest) only when it is written.
+    ///
+    /// If no `compression_level` is given, each compression algorithm uses its own default level.
+    ///
+    fn write<W: Write + Seek>(
+        self,
+        writer: W,
+        entries: &[(PathBuf, ArchiveEntry)],
+        load_content: impl FnMut(Digest) -> io::Result<Bytes>,
+        compression_level: Option<u32>,
+    ) -> io::Result<W> {
+        match self {
+            OutputFormat::Tar => write_tar(writer, entries, load_content),
+            OutputFormat::TarGzip => {
+                let level = compression_level.map_or_else(flate2::Compression::default, |level| {
+                    flate2::Compression::new(level)
+                });
+                write_tar(
+                    flate2::write::GzEncoder::new(writer, level),
+                    entries,
+                    load_content,
+                )?
+                .finish()
+            }
+            OutputFormat::TarBzip2 => {
+                let level = compression_level.map_or_else(bzip2::Compression::default, |level| {
+                    bzip2::Compression::new(level)
+                });
+                write_tar(
+                    bzip2::write::BzEncoder::new(writer, level),
+                    entries,
+                    load_content,
+                )?
+                .finish()
+            }
+            OutputFormat::TarXz => write_tar(
+                xz2::write::XzEncoder::new(writer, compression_level.unwrap_or(6)),
+                entries,
+                load_content,
+            )?
+            .finish(),
+            OutputFormat::TarZstd => {
+                // NB: A level of `0` selects the zstd default level.
+                let level = compression_level
+                    .map_or(Ok(0), i32::try_from)
+                    .map_err(|_| {
+                        io::Error::new(io::ErrorKind::InvalidInput, "Invalid compression level.")
+                    })?;
+                write_tar(
+                    zstd::stream::write::Encoder::new(writer, level)?,
+                    entries,
+                    load_content,
+                )?
+                .finish()
+            }
+            OutputFormat::Zip => write_zip(writer, entries, load_content, compression_level),
+        }
+    }
+}
+
+fn entry_name(path: &Path) -> io::Result<&str> {
+    path.to_str().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not valid UTF-8.", path.display()),
+        )
+    })
+}
+
+fn write_tar<W: Write>(
+    writer: W,
+    entries: &[(PathBuf, ArchiveEntry)],
+    mut load_content: impl FnMut(Digest) -> io::Result<Bytes>,
+) -> io::Result<W> {
+    let mut builder = tar::Builder::new(writer);
+    for (path, entry) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_mtime(NORMALIZED_MTIME);
+        header.set_uid(0);
+        header.set_gid(0);
+        match entry {
+            ArchiveEntry::File {
+                digest,
+                is_executable,
+            } => {
+                let content = load_content(*digest)?;
+                header.set_entry_type(tar::EntryType::Regular);
+                header.set_mode(if *is_executable { 0o755 } else { 0o644 });
+                header.set_size(content.len() as u64);
+                builder.append_data(&mut header, path, content.as_ref())?;
+            }
+            ArchiveEntry::Link(target) => {
+                header.set_entry_type(tar::EntryType::Symlink);
+                header.set_mode(0o777);
+                header.set_size(0);
+                builder.append_link(&mut header, path, target)?;
+            }
+            ArchiveEntry::Dir => {
+                header.set_entry_type(tar::EntryType::Directory);
+                header.set_mode(0o755);
+                header.set_size(0);
+                builder.append_data(&mut header, path, io::empty())?;
+            }
+        }
+    }
+    builder.into_inner()
+}
+
+fn write_zip<W: Write + Seek>(
+    writer: W,
+    entries: &[(PathBuf, ArchiveEntry)],
+    mut load_content: impl FnMut(Digest) -> io::Result<Bytes>,
+    compression_level: Option<u32>,
+) -> io::Result<W> {
+    let mut writer = zip::ZipWriter::new(writer);
+    let options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .compression_level(compression_level.map(|level| level as i32))
+        .last_modified_time(zip::DateTime::default());
+    for (path, entry) in entries {
+        let name = entry_name(path)?;
+        match entry {
+            ArchiveEntry::File {
+                digest,
+                is_executable,
+            } => {
+                let content = load_content(*digest)?;
+                writer.start_file(
+                    name,
+                    options.unix_permissions(if *is_executable { 0o755 } else { 0o644 }),
+                )?;
+                writer.write_all(&content)?;
+            }
+            ArchiveEntry::Link(target) => {
+                writer.add_symlink(name, entry_name(target)?, options)?;
+            }
+            ArchiveEntry::Dir => {
+                writer.add_directory(name, options.unix_permissions(0o755))?;
+            }
+        }
+    }
+    Ok(writer.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+    use std::path::{Path, PathBuf};
+
+    use bytes::Bytes;
+    use hashing::Digest;
+
+    use super::{ArchiveEntry, ArchiveFormat, ExtractedEntries, ExtractedEntry, OutputFormat};
+
+    #[test]
+    fn for_suffix() {
+        assert_eq!(
+            Some(ArchiveFormat::TarZstd),
+            ArchiveFormat::for_suffix("tool-1.0.tar.zst")
+        );
+        assert_eq!(
+            Some(ArchiveFormat::TarZstd),
+            ArchiveFormat::for_suffix(".tzst")
+        );
+        assert_eq!(
+            Some(ArchiveFormat::TarXz),
+            ArchiveFormat::for_suffix("tool.txz")
+        );
+        assert_eq!(
+            Some(ArchiveFormat::Tar),
+            ArchiveFormat::for_suffix("tool.tar")
+        );
+        assert_eq!(
+            Some(ArchiveFormat::SevenZip),
+            ArchiveFormat::for_suffix("tool.7z")
+        );
+        assert_eq!(
+            Some(ArchiveFormat::Zstd),
+            ArchiveFormat::for_suffix("tool.zst")
+        );
+        assert_eq!(
+            Some(ArchiveFormat::Xz),
+            ArchiveFormat::for_suffix("tool.xz")
+        );
+        assert_eq!(None, ArchiveFormat::for_suffix("tool.zip"));
+    }
+
+    ///
+    /// Writes an archive of a fixed set of entries, loading file content via its Digest as the
+    /// Store would.
+    ///
+    fn write(format: OutputFormat, compression_level: Option<u32>) -> Bytes {
+        let contents = [
+            Bytes::from_static(b"#!/bin/sh"),
+            Bytes::from_static(b"data"),
+        ];
+        let entries = vec![
+            (
+                PathBuf::from("bin/tool"),
+                ArchiveEntry::File {
+                    digest: Digest::of_bytes(&contents[0]),
+                    is_executable: true,
+                },
+            ),
+            (PathBuf::from("empty"), ArchiveEntry::Dir),
+            (
+                PathBuf::from("share/data"),
+                ArchiveEntry::File {
+                    digest: Digest::of_bytes(&contents[1]),
+                    is_executable: false,
+                },
+            ),
+            (
+                PathBuf::from("tool"),
+                ArchiveEntry::Link(PathBuf::from("bin/tool")),
+            ),
+        ];
+        let load_content = |digest: Digest| {
+            Ok(contents
+                .iter()
+                .find(|content| Digest::of_bytes(content) == digest)
+                .unwrap()
+                .clone())
+        };
+        format
+            .write(
+                Cursor::new(Vec::new()),
+                &entries,
+                load_content,
+                compression_level,
+            )
+            .unwrap()
+            .into_inner()
+            .into()
+    }
+
+    #[test]
+    fn create_is_deterministic() {
+        for format in [
+            OutputFormat::Tar,
+            OutputFormat::TarGzip,
+            OutputFormat::TarBzip2,
+            OutputFormat::TarXz,
+            OutputFormat::TarZstd,
+            OutputFormat::Zip,
+        ] {
+            let first = write(format, None);
+            let second = write(format, None);
+            assert_eq!(first, second, "{format:?} was not deterministic.");
+        }
+    }
+
+    #[test]
+    fn create_validates_compression_level() {
+        assert!(OutputFormat::Tar.validate_compression_level(None).is_ok());
+        assert!(OutputFormat::Tar
+            .validate_compression_level(Some(1))
+            .is_err());
+        assert!(OutputFormat::TarGzip
+            .validate_compression_level(Some(0))
+            .is_ok());
+        assert!(OutputFormat::TarBzip2
+            .validate_compression_level(Some(0))
+            .is_err());
+        assert!(OutputFormat::TarXz
+            .validate_compression_level(Some(10))
+            .is_err());
+        assert!(OutputFormat::TarZstd
+            .validate_compression_level(Some(22))
+            .is_ok());
+        assert!(OutputFormat::TarZstd
+            .validate_compression_level(Some(23))
+            .is_err());
+        assert!(OutputFormat::Zip
+            .validate_compression_level(Some(10))
+            .is_err());
+    }
+
+    #[test]
+    fn create_and_extract_tar() {
+        let archive = write(OutputFormat::TarZstd, Some(19));
+
+        let mut extracted = ExtractedEntries::default();
+        extracted
+            .extract(ArchiveFormat::TarZstd, Path::new("a.tar.zst"), archive)
+            .unwrap();
+        let paths = extracted.0.keys().cloned().collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                PathBuf::from("bin/tool"),
+                PathBuf::from("empty"),
+                PathBuf::from("share/data"),
+                PathBuf::from("tool"),
+            ],
+            paths
+        );
+        assert!(matches!(
+            extracted.0.get(Path::new("bin/tool")),
+            Some(ExtractedEntry::File {
+                is_executable: true,
+                ..
+            })
+        ));
+        assert!(matches!(
+            extracted.0.get(Path::new("tool")),
+            Some(ExtractedEntry::Link(target)) if target == Path::new("bin/tool")
+        ));
+        extracted.into_digest_trie().unwrap();
+    }
+
+    fn assert_round_trips(format: ArchiveFormat, archive_path: &str, archive: Bytes) {
+        let mut extracted = ExtractedEntries::default();
+        extracted
+            .extract(format, Path::new(archive_path), archive)
+            .unwrap();
+        let paths = extracted.0.keys().cloned().collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                PathBuf::from("bin/tool"),
+                PathBuf::from("empty"),
+                PathBuf::from("share/data"),
+                PathBuf::from("tool"),
+            ],
+            paths,
+            "{format:?}"
+        );
+        assert!(matches!(
+            extracted.0.get(Path::new("bin/tool")),
+            Some(ExtractedEntry::File {
+                content,
+                is_executable: true,
+            }) if content.as_ref() == b"#!/bin/sh"
+        ));
+        assert!(matches!(
+            extracted.0.get(Path::new("share/data")),
+            Some(ExtractedEntry::File {
+                content,
+                is_executable: false,
+            }) if content.as_ref() == b"data"
+        ));
+        assert!(matches!(
+            extracted.0.get(Path::new("empty")),
+            Some(ExtractedEntry::Dir)
+        ));
+        assert!(matches!(
+            extracted.0.get(Path::new("tool")),
+            Some(ExtractedEntry::Link(target)) if target == Path::new("bin/tool")
+        ));
+        extracted.into_digest_trie().unwrap();
+    }
+
+    #[test]
+    fn extract_compressed_tar() {
+        for (output_format, format, archive_path) in [
+            (OutputFormat::Tar, ArchiveFormat::Tar, "a.tar"),
+            (OutputFormat::TarXz, ArchiveFormat::TarXz, "a.tar.xz"),
+            (OutputFormat::TarZstd, ArchiveFormat::TarZstd, "a.tar.zst"),
+        ] {
+            let archive = write(output_format, None);
+            assert_round_trips(format, archive_path, archive);
+        }
+    }
+
+    #[test]
+    fn extract_7z() {
+        // The unix mode of an entry is stored in the high 16 bits of its attributes.
+        fn entry(name: &str, mode: u32) -> sevenz_rust::SevenZArchiveEntry {
+            let mut entry = sevenz_rust::SevenZArchiveEntry::new();
+            entry.name = name.to_owned();
+            entry.has_stream = mode & 0o040000 == 0;
+            entry.is_directory = mode & 0o040000 != 0;
+            entry.has_windows_attributes = true;
+            entry.windows_attributes = 0x8000 | (mode << 16);
+            entry
+        }
+
+        let mut writer = sevenz_rust::SevenZWriter::new(Cursor::new(Vec::new())).unwrap();
+        for (entry, content) in [
+            (entry("bin/tool", 0o100755), Some(&b"#!/bin/sh"[..])),
+            (entry("empty", 0o040755), None),
+            (entry("share/data", 0o100644), Some(&b"data"[..])),
+            (entry("tool", 0o120777), Some(&b"bin/tool"[..])),
+        ] {
+            writer.push_archive_entry(entry, content).unwrap();
+        }
+        let archive = writer.finish().unwrap().into_inner();
+
+        assert_round_trips(ArchiveFormat::SevenZip, "a.7z", archive.into());
+    }
+
+    #[test]
+    fn extract_tar_with_links() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_mode(0o755);
+        header.set_size(9);
+        builder
+            .append_data(&mut header, "bin/tool", &b"#!/bin/sh"[..])
+            .unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Link);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "bin/tool-hardlink", "bin/tool")
+            .unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "tool", "bin/tool")
+            .unwrap();
+        let archive = builder.into_inner().unwrap();
+
+        let mut extracted = ExtractedEntries::default();
+        extracted
+            .extract(ArchiveFormat::Tar, Path::new("a.tar"), archive.into())
+            .unwrap();
+        // A hardlink is extracted as a copy of the file that it refers to.
+        assert!(matches!(
+            extracted.0.get(Path::new("bin/tool-hardlink")),
+            Some(ExtractedEntry::File {
+                content,
+                is_executable: true,
+            }) if content.as_ref() == b"#!/bin/sh"
+        ));
+        assert!(matches!(
+            extracted.0.get(Path::new("tool")),
+            Some(ExtractedEntry::Link(target)) if target == Path::new("bin/tool")
+        ));
+        extracted.into_digest_trie().unwrap();
+    }
+
+    #[test]
+    fn extract_tar_with_dangling_hardlink() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Link);
+        header.set_size(0);
+        builder.append_link(&mut header, "tool", "missing").unwrap();
+        let archive = builder.into_inner().unwrap();
+
+        let mut extracted = ExtractedEntries::default();
+        let err = extracted
+            .extract(ArchiveFormat::Tar, Path::new("a.tar"), archive.into())
+            .unwrap_err();
+        assert!(err.contains("not an earlier file entry"), "{err}");
+    }
+
+    #[test]
+    fn decompress_file() {
+        let mut encoder = zstd::stream::write::Encoder::new(Vec::new(), 0).unwrap();
+        encoder.write_all(b"data").unwrap();
+        let archive = encoder.finish().unwrap();
+
+        let mut extracted = ExtractedEntries::default();
+        extracted
+            .extract(
+                ArchiveFormat::Zstd,
+                Path::new("dir/data.zst"),
+                archive.into(),
+            )
+            .unwrap();
+        assert!(matches!(
+            extracted.0.get(Path::new("data")),
+            Some(ExtractedEntry::File {
+                content,
+                is_executable: false,
+            }) if content.as_ref() == b"data"
+        ));
+    }
+}
@@ -1,13 +1,21 @@
 // Copyright 2021 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 use bytes::Bytes;
+use cache::PersistentCache;
+use dep_inference::dotnet::ParsedDotnetDependencies;
+use dep_inference::go::ParsedGoDependencies;
+use dep_inference::java::ParsedJavaDependencies;
 use dep_inference::javascript::ParsedJavascriptDependencies;
+use dep_inference::kotlin::ParsedKotlinDependencies;
 use dep_inference::python::ParsedPythonDependencies;
-use dep_inference::{javascript, python};
+use dep_inference::rust::ParsedRustDependencies;
+use dep_inference::scala::{ParsedScalaDependencies, ScalaProvidedSymbol};
+use dep_inference::terraform::ParsedTerraformDependencies;
+use dep_inference::{dotnet, go, java, javascript, kotlin, python, rust, scala, terraform};
 use fs::{DirectoryDigest, Entry, SymlinkBehavior};
 use grpc_util::prost::MessageExt;
 use hashing::Digest;
@@ -18,19 +26,32 @@ use pyo3::prelude::{pyfunction, wrap_pyfunction, PyModule, PyResult, Python, ToP
 use store::Store;
 use workunit_store::{in_workunit, Level};
 
+use crate::externs;
 use crate::externs::dep_inference::PyNativeDependenciesRequest;
 use crate::externs::PyGeneratorResponseNativeCall;
 use crate::nodes::{task_get_context, NodeResult};
 use crate::python::{Failure, Value};
-use crate::{externs, Core};
 
 pub fn register(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(parse_python_deps, m)?)?;
     m.add_function(wrap_pyfunction!(parse_javascript_deps, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_java_deps, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_scala_deps, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_kotlin_deps, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_go_deps, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_dotnet_deps, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_rust_deps, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_terraform_deps, m)?)?;
 
     Ok(())
 }
 
+///
+/// The backends whose parsers' results only depend on the content of the file (and the metadata),
+/// and not on its path, so that copies and moves of a file share a cache entry.
+///
+const PATH_INDEPENDENT_BACKENDS: &[&str] = &["Java", "Scala", "Kotlin", "Go", "Terraform"];
+
 pub(crate) struct PreparedInferenceRequest {
     digest: Digest,
     /// The request that's guaranteed to have been constructed via ::prepare().
@@ -38,6 +59,9 @@ pub(crate) struct PreparedInferenceRequest {
     /// NB. this `inner` value is used as the cache key, so anything that can influence the dep
     /// inference should (also) be inside it, not just a key on the outer struct
     inner: DependencyInferenceRequest,
+    /// Whether the results of the parser only depend on the content of the file (and the
+    /// metadata), and not on its path.
+    path_independent: bool,
 }
 
 impl PreparedInferenceRequest {
@@ -53,17 +77,26 @@ impl PreparedInferenceRequest {
         } = Python::with_gil(|py| deps_request.extract(py))?;
 
         let (path, digest) = Self::find_one_file(directory_digest, store, backend).await?;
-        let str_path = path.display().to_string();
+        Ok(Self::new(&path, digest, metadata, backend, impl_hash))
+    }
 
-        Ok(Self {
+    fn new(
+        path: &Path,
+        digest: Digest,
+        metadata: Option<dependency_inference_request::Metadata>,
+        backend: &str,
+        impl_hash: &str,
+    ) -> Self {
+        Self {
             digest,
             inner: DependencyInferenceRequest {
-                input_file_path: str_path,
+                input_file_path: path.display().to_string(),
                 input_file_digest: Some(digest.into()),
                 metadata,
                 impl_hash: impl_hash.to_string(),
             },
-        })
+            path_independent: PATH_INDEPENDENT_BACKENDS.contains(&backend),
+        }
     }
 
     pub async fn read_digest(&self, store: &Store) -> NodeResult<String> {
@@ -102,9 +135,19 @@ impl PreparedInferenceRequest {
     }
 
     fn cache_key(&self) -> CacheKey {
+        let digest = if self.path_independent {
+            let request = DependencyInferenceRequest {
+                input_file_path: String::new(),
+                ..self.inner.clone()
+            };
+            Digest::of_bytes(&request.to_bytes())
+        } else {
+            Digest::of_bytes(&self.inner.to_bytes())
+        };
         CacheKey {
             key_type: CacheKeyType::DepInferenceRequest.into(),
-            digest: Some(Digest::of_bytes(&self.inner.to_bytes()).into()),
+            digest: Some(digest.into()),
+            ..CacheKey::default()
         }
     }
 }
@@ -128,7 +171,7 @@ fn parse_python_deps(deps_request: Value) -> PyGeneratorResponseNativeCall {
             )),
             |_workunit| async move {
                 let result: ParsedPythonDependencies = get_or_create_inferred_dependencies(
-                    core,
+                    &core.dep_inference_cache,
                     &store,
                     prepared_inference_request,
                     |content, request| {
@@ -179,7 +222,7 @@ fn parse_javascript_deps(deps_request: Value) -> PyGeneratorResponseNativeCall {
             )),
             |_workunit| async move {
                 let result: ParsedJavascriptDependencies = get_or_create_inferred_dependencies(
-                    core,
+                    &core.dep_inference_cache,
                     &store,
                     prepared_inference_request,
                     |content, request| {
@@ -219,8 +262,414 @@ fn parse_javascript_deps(deps_request: Value) -> PyGeneratorResponseNativeCall {
     })
 }
 
+#[pyfunction]
+fn parse_java_deps(deps_request: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+
+        let core = &context.core;
+        let store = core.store();
+        let prepared_inference_request =
+            PreparedInferenceRequest::prepare(deps_request, &store, "Java", java::IMPL_HASH)
+                .await?;
+        in_workunit!(
+            "parse_java_dependencies",
+            Level::Debug,
+            desc = Some(format!(
+                "Determine Java dependencies for {:?}",
+                &prepared_inference_request.inner.input_file_path
+            )),
+            |_workunit| async move {
+                let result: ParsedJavaDependencies = get_or_create_inferred_dependencies(
+                    &core.dep_inference_cache,
+                    &store,
+                    prepared_inference_request,
+                    |content, request| {
+                        java::get_dependencies(content, request.inner.input_file_path.into())
+                    },
+                )
+                .await?;
+
+                let imports = result
+                    .imports
+                    .into_iter()
+                    .map(|import| (import.name, import.is_static, import.is_asterisk))
+                    .collect::<Vec<_>>();
+                let result = Python::with_gil(|py| {
+                    externs::unsafe_call(
+                        py,
+                        core.types.parsed_java_deps_result,
+                        &[
+                            result.declared_package.to_object(py).into(),
+                            imports.to_object(py).into(),
+                            result.top_level_types.to_object(py).into(),
+                            result.consumed_types.to_object(py).into(),
+                            result.export_types.to_object(py).into(),
+                        ],
+                    )
+                });
+
+                Ok::<_, Failure>(result)
+            }
+        )
+        .await
+    })
+}
+
+#[pyfunction]
+fn parse_scala_deps(deps_request: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+
+        let core = &context.core;
+        let store = core.store();
+        let prepared_inference_request =
+            PreparedInferenceRequest::prepare(deps_request, &store, "Scala", scala::IMPL_HASH)
+                .await?;
+        in_workunit!(
+            "parse_scala_dependencies",
+            Level::Debug,
+            desc = Some(format!(
+                "Determine Scala dependencies for {:?}",
+                &prepared_inference_request.inner.input_file_path
+            )),
+            |_workunit| async move {
+                let result: ParsedScalaDependencies = get_or_create_inferred_dependencies(
+                    &core.dep_inference_cache,
+                    &store,
+                    prepared_inference_request,
+                    |content, request| {
+                        scala::get_dependencies(content, request.inner.input_file_path.into())
+                    },
+                )
+                .await?;
+
+                let provided_symbols = |symbols: Vec<ScalaProvidedSymbol>| {
+                    symbols
+                        .into_iter()
+                        .map(|symbol| (symbol.name, symbol.recursive))
+                        .collect::<Vec<_>>()
+                };
+                let provided_symbols_encoded = provided_symbols(result.provided_symbols_encoded);
+                let provided_symbols = provided_symbols(result.provided_symbols);
+                let imports_by_scope = result
+                    .imports_by_scope
+                    .into_iter()
+                    .map(|(scope, imports)| {
+                        let imports = imports
+                            .into_iter()
+                            .map(|import| (import.name, import.alias, import.is_wildcard))
+                            .collect::<Vec<_>>();
+                        (scope, imports)
+                    })
+                    .collect::<BTreeMap<_, _>>();
+                let consumed_symbols_by_scope = result
+                    .consumed_symbols_by_scope
+                    .into_iter()
+                    .map(|(scope, symbols)| {
+                        let symbols = symbols
+                            .into_iter()
+                            .map(|symbol| (symbol.name, symbol.is_absolute))
+                            .collect::<Vec<_>>();
+                        (scope, symbols)
+                    })
+                    .collect::<BTreeMap<_, _>>();
+                let result = Python::with_gil(|py| {
+                    externs::unsafe_call(
+                        py,
+                        core.types.parsed_scala_deps_result,
+                        &[
+                            provided_symbols.to_object(py).into(),
+                            provided_symbols_encoded.to_object(py).into(),
+                            imports_by_scope.to_object(py).into(),
+                            consumed_symbols_by_scope.to_object(py).into(),
+                            result.scopes.to_object(py).into(),
+                        ],
+                    )
+                });
+
+                Ok::<_, Failure>(result)
+            }
+        )
+        .await
+    })
+}
+
+#[pyfunction]
+fn parse_kotlin_deps(deps_request: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+
+        let core = &context.core;
+        let store = core.store();
+        let prepared_inference_request =
+            PreparedInferenceRequest::prepare(deps_request, &store, "Kotlin", kotlin::IMPL_HASH)
+                .await?;
+        in_workunit!(
+            "parse_kotlin_dependencies",
+            Level::Debug,
+            desc = Some(format!(
+                "Determine Kotlin dependencies for {:?}",
+                &prepared_inference_request.inner.input_file_path
+            )),
+            |_workunit| async move {
+                let result: ParsedKotlinDependencies = get_or_create_inferred_dependencies(
+                    &core.dep_inference_cache,
+                    &store,
+                    prepared_inference_request,
+                    |content, request| {
+                        kotlin::get_dependencies(content, request.inner.input_file_path.into())
+                    },
+                )
+                .await?;
+
+                let imports = result
+                    .imports
+                    .into_iter()
+                    .map(|import| (import.name, import.alias, import.is_wildcard))
+                    .collect::<Vec<_>>();
+                let consumed_symbols_by_scope = result
+                    .consumed_symbols_by_scope
+                    .into_iter()
+                    .map(|(scope, symbols)| (scope, symbols.into_iter().collect::<Vec<_>>()))
+                    .collect::<BTreeMap<_, _>>();
+                let scopes = result.scopes.into_iter().collect::<Vec<_>>();
+                let result = Python::with_gil(|py| {
+                    externs::unsafe_call(
+                        py,
+                        core.types.parsed_kotlin_deps_result,
+                        &[
+                            result.package.to_object(py).into(),
+                            imports.to_object(py).into(),
+                            result.named_declarations.to_object(py).into(),
+                            consumed_symbols_by_scope.to_object(py).into(),
+                            scopes.to_object(py).into(),
+                        ],
+                    )
+                });
+
+                Ok::<_, Failure>(result)
+            }
+        )
+        .await
+    })
+}
+
+#[pyfunction]
+fn parse_go_deps(deps_request: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+
+        let core = &context.core;
+        let store = core.store();
+        let prepared_inference_request =
+            PreparedInferenceRequest::prepare(deps_request, &store, "Go", go::IMPL_HASH).await?;
+        in_workunit!(
+            "parse_go_dependencies",
+            Level::Debug,
+            desc = Some(format!(
+                "Determine Go dependencies for {:?}",
+                &prepared_inference_request.inner.input_file_path
+            )),
+            |_workunit| async move {
+                let result: ParsedGoDependencies = get_or_create_inferred_dependencies(
+                    &core.dep_inference_cache,
+                    &store,
+                    prepared_inference_request,
+                    |content, request| {
+                        go::get_dependencies(content, request.inner.input_file_path.into())
+                    },
+                )
+                .await?;
+
+                let result = Python::with_gil(|py| {
+                    externs::unsafe_call(
+                        py,
+                        core.types.parsed_go_deps_result,
+                        &[
+                            result.package.to_object(py).into(),
+                            result.imports.to_object(py).into(),
+                            result.build_constraint.to_object(py).into(),
+                            result.embed_patterns.to_object(py).into(),
+                        ],
+                    )
+                });
+
+                Ok::<_, Failure>(result)
+            }
+        )
+        .await
+    })
+}
+
+#[pyfunction]
+fn parse_dotnet_deps(deps_request: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+
+        let core = &context.core;
+        let store = core.store();
+        let prepared_inference_request =
+            PreparedInferenceRequest::prepare(deps_request, &store, ".NET", dotnet::IMPL_HASH)
+                .await?;
+        in_workunit!(
+            "parse_dotnet_dependencies",
+            Level::Debug,
+            desc = Some(format!(
+                "Determine .NET dependencies for {:?}",
+                &prepared_inference_request.inner.input_file_path
+            )),
+            |_workunit| async move {
+                let result: ParsedDotnetDependencies = get_or_create_inferred_dependencies(
+                    &core.dep_inference_cache,
+                    &store,
+                    prepared_inference_request,
+                    |content, request| {
+                        dotnet::get_dependencies(content, request.inner.input_file_path.into())
+                    },
+                )
+                .await?;
+
+                let result = Python::with_gil(|py| {
+                    let package_references = result
+                        .package_references
+                        .into_iter()
+                        .map(|package| (package.name, package.version))
+                        .collect::<Vec<_>>();
+                    externs::unsafe_call(
+                        py,
+                        core.types.parsed_dotnet_deps_result,
+                        &[
+                            result.namespaces.to_object(py).into(),
+                            result.usings.to_object(py).into(),
+                            result.project_references.to_object(py).into(),
+                            package_references.to_object(py).into(),
+                        ],
+                    )
+                });
+
+                Ok::<_, Failure>(result)
+            }
+        )
+        .await
+    })
+}
+
+#[pyfunction]
+fn parse_rust_deps(deps_request: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+
+        let core = &context.core;
+        let store = core.store();
+        let prepared_inference_request =
+            PreparedInferenceRequest::prepare(deps_request, &store, "Rust", rust::IMPL_HASH)
+                .await?;
+        in_workunit!(
+            "parse_rust_dependencies",
+            Level::Debug,
+            desc = Some(format!(
+                "Determine Rust dependencies for {:?}",
+                &prepared_inference_request.inner.input_file_path
+            )),
+            |_workunit| async move {
+                let result: ParsedRustDependencies = get_or_create_inferred_dependencies(
+                    &core.dep_inference_cache,
+                    &store,
+                    prepared_inference_request,
+                    |content, request| {
+                        rust::get_dependencies(content, request.inner.input_file_path.into())
+                    },
+                )
+                .await?;
+
+                let result = Python::with_gil(|py| {
+                    let mods = result
+                        .mods
+                        .into_iter()
+                        .map(|module| (module.name, module.path))
+                        .collect::<Vec<_>>();
+                    externs::unsafe_call(
+                        py,
+                        core.types.parsed_rust_deps_result,
+                        &[
+                            result.uses.to_object(py).into(),
+                            mods.to_object(py).into(),
+                            result.includes.to_object(py).into(),
+                        ],
+                    )
+                });
+
+                Ok::<_, Failure>(result)
+            }
+        )
+        .await
+    })
+}
+
+#[pyfunction]
+fn parse_terraform_deps(deps_request: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+
+        let core = &context.core;
+        let store = core.store();
+        let prepared_inference_request = PreparedInferenceRequest::prepare(
+            deps_request,
+            &store,
+            "Terraform",
+            terraform::IMPL_HASH,
+        )
+        .await?;
+        in_workunit!(
+            "parse_terraform_dependencies",
+            Level::Debug,
+            desc = Some(format!(
+                "Determine Terraform dependencies for {:?}",
+                &prepared_inference_request.inner.input_file_path
+            )),
+            |_workunit| async move {
+                let result: ParsedTerraformDependencies = get_or_create_inferred_dependencies(
+                    &core.dep_inference_cache,
+                    &store,
+                    prepared_inference_request,
+                    |content, request| {
+                        terraform::get_dependencies(content, request.inner.input_file_path.into())
+                    },
+                )
+                .await?;
+
+                let result = Python::with_gil(|py| {
+                    let modules = result
+                        .modules
+                        .into_iter()
+                        .map(|module| (module.name, (module.source, module.version)))
+                        .collect::<Vec<_>>();
+                    let providers = result
+                        .providers
+                        .into_iter()
+                        .map(|provider| (provider.name, (provider.source, provider.version)))
+                        .collect::<Vec<_>>();
+                    externs::unsafe_call(
+                        py,
+                        core.types.parsed_terraform_deps_result,
+                        &[
+                            modules.to_object(py).into(),
+                            result.backend.to_object(py).into(),
+                            providers.to_object(py).into(),
+                        ],
+                    )
+                });
+
+                Ok::<_, Failure>(result)
+            }
+        )
+        .await
+    })
+}
+
 pub(crate) async fn get_or_create_inferred_dependencies<T, F>(
-    core: &Arc<Core>,
+    cache: &PersistentCache,
     store: &Store,
     request: PreparedInferenceRequest,
     dependencies_parser: F,
@@ -231,12 +680,12 @@ where
 {
     let cache_key = request.cache_key();
     let result =
-        if let Some(result) = lookup_inferred_dependencies(&cache_key, core).await? {
+        if let Some(result) = lookup_inferred_dependencies(&cache_key, cache).await? {
             result
         } else {
             let contents = request.read_digest(store).await?;
             let result = dependencies_parser(&contents, request)?;
-            core.local_cache
+            cache
                 .store(
                     &cache_key,
                     Bytes::from(serde_json::to_string(&result).map_err(|e| {
@@ -251,10 +700,185 @@ where
 
 pub(crate) async fn lookup_inferred_dependencies<T: serde::de::DeserializeOwned>(
     key: &CacheKey,
-    core: &Arc<Core>,
+    cache: &PersistentCache,
 ) -> NodeResult<Option<T>> {
-    let cached_result = core.local_cache.load(key).await?;
+    let cached_result = cache.load(key).await?;
     Ok(cached_result
         .and_then(|bytes| serde_json::from_slice(&bytes).ok())
         .flatten())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use cache::PersistentCache;
+    use hashing::Digest;
+    use protos::gen::pants::cache::CacheKey;
+    use store::{Store, StoreBackend};
+    use task_executor::Executor;
+    use tempfile::TempDir;
+
+    use super::{
+        get_or_create_inferred_dependencies, PreparedInferenceRequest, PATH_INDEPENDENT_BACKENDS,
+    };
+
+    const PATH_DEPENDENT_BACKENDS: &[&str] = &["Python", "JavaScript", "Rust", "Dotnet"];
+
+    fn new_cache(store_dir: &Path, name: &str) -> PersistentCache {
+        PersistentCache::new_named(
+            store_dir,
+            name,
+            15_000_000,
+            1,
+            Executor::new(),
+            Duration::from_secs(3600),
+            1,
+            StoreBackend::Lmdb,
+        )
+        .unwrap()
+    }
+
+    ///
+    /// Infers the dependencies of identical files at two paths, and returns how many times the
+    /// parser was invoked.
+    ///
+    async fn parses_of_moved_file(backend: &str, cache: &PersistentCache, store: &Store) -> usize {
+        let digest = store
+            .store_file_bytes(Bytes::from_static(b"content"), false)
+            .await
+            .unwrap();
+        let parses = AtomicUsize::new(0);
+        for path in ["a/File.src", "b/File.src"] {
+            let request =
+                PreparedInferenceRequest::new(Path::new(path), digest, None, backend, "impl_hash");
+            let result: String =
+                get_or_create_inferred_dependencies(cache, store, request, |content, _| {
+                    parses.fetch_add(1, Ordering::SeqCst);
+                    Ok(content.to_owned())
+                })
+                .await
+                .unwrap();
+            assert_eq!("content", result);
+        }
+        parses.into_inner()
+    }
+
+    fn cache_key(path: &str, content: &str, backend: &str, impl_hash: &str) -> CacheKey {
+        PreparedInferenceRequest::new(
+            Path::new(path),
+            Digest::of_bytes(content.as_bytes()),
+            None,
+            backend,
+            impl_hash,
+        )
+        .cache_key()
+    }
+
+    #[test]
+    fn path_independent_backends() {
+        for backend in ["Go", "Java", "Scala", "Kotlin"] {
+            assert!(PATH_INDEPENDENT_BACKENDS.contains(&backend), "{backend}");
+        }
+        for backend in PATH_DEPENDENT_BACKENDS {
+            assert!(!PATH_INDEPENDENT_BACKENDS.contains(backend), "{backend}");
+        }
+    }
+
+    #[test]
+    fn path_independent_cache_keys_ignore_the_path() {
+        for backend in PATH_INDEPENDENT_BACKENDS {
+            let key = cache_key("a/File.src", "content", backend, "impl_hash");
+            assert_eq!(
+                key,
+                cache_key("b/Moved.src", "content", backend, "impl_hash"),
+                "{backend}"
+            );
+            // But not the content, or the implementation of the parser.
+            assert_ne!(
+                key,
+                cache_key("a/File.src", "other", backend, "impl_hash"),
+                "{backend}"
+            );
+            assert_ne!(
+                key,
+                cache_key("a/File.src", "content", backend, "other_hash"),
+                "{backend}"
+            );
+        }
+    }
+
+    #[test]
+    fn path_dependent_cache_keys_include_the_path() {
+        for backend in PATH_DEPENDENT_BACKENDS {
+            let key = cache_key("a/File.src", "content", backend, "impl_hash");
+            assert_ne!(
+                key,
+                cache_key("b/Moved.src", "content", backend, "impl_hash"),
+                "{backend}"
+            );
+            assert_eq!(
+                key,
+                cache_key("a/File.src", "content", backend, "impl_hash"),
+                "{backend}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn path_independent_backends_share_cache_entries() {
+        let dir = TempDir::new().unwrap();
+        let store = Store::local_only(Executor::new(), dir.path().join("store")).unwrap();
+        for backend in PATH_INDEPENDENT_BACKENDS {
+            let cache = new_cache(dir.path(), backend);
+            assert_eq!(
+                1,
+                parses_of_moved_file(backend, &cache, &store).await,
+                "{backend}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn path_dependent_backends_do_not_share_cache_entries() {
+        let dir = TempDir::new().unwrap();
+        let store = Store::local_only(Executor::new(), dir.path().join("store")).unwrap();
+        for backend in PATH_DEPENDENT_BACKENDS {
+            let cache = new_cache(dir.path(), backend);
+            assert_eq!(
+                2,
+                parses_of_moved_file(backend, &cache, &store).await,
+                "{backend}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn results_are_stored_in_the_named_cache() {
+        let dir = TempDir::new().unwrap();
+        let store = Store::local_only(Executor::new(), dir.path().join("store")).unwrap();
+        let local_cache = new_cache(dir.path(), "cache");
+        let dep_inference_cache = new_cache(dir.path(), "dep_inference");
+
+        parses_of_moved_file("Java", &dep_inference_cache, &store).await;
+
+        let digest = store
+            .store_file_bytes(Bytes::from_static(b"content"), false)
+            .await
+            .unwrap();
+        let key = PreparedInferenceRequest::new(
+            Path::new("a/File.src"),
+            digest,
+            None,
+            "Java",
+            "impl_hash",
+        )
+        .cache_key();
+        assert!(dep_inference_cache.load(&key).await.unwrap().is_some());
+        assert!(local_cache.load(&key).await.unwrap().is_none());
+        assert!(dir.path().join("dep_inference").is_dir());
+    }
+}
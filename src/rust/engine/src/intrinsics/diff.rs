@@ -0,0 +1,363 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use bytes::Bytes;
+use fs::{DigestTrie, Entry, SymlinkBehavior};
+use futures::future;
+use hashing::Digest;
+use pyo3::prelude::{pyfunction, wrap_pyfunction, PyAny, PyModule, PyResult, Python};
+use similar::TextDiff;
+
+use crate::externs;
+use crate::externs::PyGeneratorResponseNativeCall;
+use crate::nodes::{lift_directory_digest, task_get_context, NodeResult, Snapshot};
+use crate::python::{throw, Value};
+use crate::Failure;
+
+pub fn register(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(diff_digests_to_digest_diff, m)?)?;
+
+    Ok(())
+}
+
+#[pyfunction]
+fn diff_digests_to_digest_diff(diff_digests: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+        let store = context.core.store();
+
+        let (before, after, before_path, after_path, mode, context_lines) =
+            Python::with_gil(|py| {
+                let py_request = diff_digests.as_ref().as_ref(py);
+                let before = lift_directory_digest(externs::getattr(py_request, "before")?)?;
+                let after = lift_directory_digest(externs::getattr(py_request, "after")?)?;
+                let before_path: Option<String> = externs::getattr(py_request, "before_path")?;
+                let after_path: Option<String> = externs::getattr(py_request, "after_path")?;
+                let mode: String =
+                    externs::getattr(externs::getattr::<&PyAny>(py_request, "mode")?, "value")?;
+                let context_lines: usize = externs::getattr(py_request, "context_lines")?;
+                let res: NodeResult<_> =
+                    Ok((before, after, before_path, after_path, mode, context_lines));
+                res
+            })?;
+        let mode = match mode.as_str() {
+            "lines" => DiffMode::Lines,
+            "bytes" => DiffMode::Bytes,
+            unknown => return Err(throw(format!("Unknown diff mode: {unknown}"))),
+        };
+
+        let (before_trie, after_trie) = future::try_join(
+            store.load_digest_trie(before),
+            store.load_digest_trie(after),
+        )
+        .await?;
+
+        let changes = match (before_path, after_path) {
+            (Some(before_path), Some(after_path)) => {
+                let (before_path, after_path) =
+                    (PathBuf::from(before_path), PathBuf::from(after_path));
+                let before_entry = DiffEntry::for_path(&before_trie, &before_path)?;
+                let after_entry = DiffEntry::for_path(&after_trie, &after_path)?;
+                if before_entry == after_entry {
+                    vec![]
+                } else {
+                    vec![Change {
+                        before: Some((before_path, before_entry)),
+                        after: Some((after_path, after_entry)),
+                    }]
+                }
+            }
+            (None, None) => Change::between(&before_trie, &after_trie),
+            _ => {
+                return Err(throw(
+                    "Both or neither of `before_path` and `after_path` must be set.".to_owned(),
+                ))
+            }
+        };
+
+        let load = |side: &Option<(PathBuf, DiffEntry)>| {
+            let store = store.clone();
+            let side = side.clone();
+            async move {
+                match side {
+                    Some((path, DiffEntry::File(digest))) => {
+                        let content = store
+                            .load_file_bytes_with(digest, Bytes::copy_from_slice)
+                            .await?;
+                        Ok::<_, Failure>(Some((path, content)))
+                    }
+                    Some((path, DiffEntry::Symlink(target))) => Ok(Some((
+                        path,
+                        Bytes::from(target.to_string_lossy().into_owned()),
+                    ))),
+                    None => Ok(None),
+                }
+            }
+        };
+        let contents = future::try_join_all(
+            changes
+                .iter()
+                .map(|change| future::try_join(load(&change.before), load(&change.after))),
+        )
+        .await?;
+
+        let changed_paths = changes
+            .iter()
+            .map(|change| change.path().to_owned())
+            .collect::<Vec<_>>();
+        let content = context
+            .core
+            .executor
+            .spawn_blocking(
+                move || {
+                    let mut content = Vec::new();
+                    for (before, after) in contents {
+                        write_diff(
+                            &mut content,
+                            mode,
+                            context_lines,
+                            before
+                                .as_ref()
+                                .map(|(path, content)| (path.as_path(), &content[..])),
+                            after
+                                .as_ref()
+                                .map(|(path, content)| (path.as_path(), &content[..])),
+                        )
+                        .map_err(|e| format!("Failed to write diff: {e}"))?;
+                    }
+                    Ok(content)
+                },
+                |e| Err(format!("Diff task failed: {e}")),
+            )
+            .await
+            .map_err(throw)?;
+
+        Ok::<_, Failure>(Python::with_gil(|py| {
+            let changed_paths = changed_paths
+                .iter()
+                .map(|path| Snapshot::store_path(py, path))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok::<_, String>(externs::unsafe_call(
+                py,
+                context.core.types.digest_diff,
+                &[
+                    externs::store_bytes(py, &content),
+                    externs::store_tuple(py, changed_paths),
+                ],
+            ))
+        })?)
+    })
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum DiffMode {
+    /// Diff UTF-8 content by lines, and only report that non-UTF-8 content differs.
+    Lines,
+    /// Diff arbitrary content by lines, without decoding it.
+    Bytes,
+}
+
+///
+/// The diffable content of a path: symlinks are diffed by their targets.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum DiffEntry {
+    File(Digest),
+    Symlink(PathBuf),
+}
+
+impl DiffEntry {
+    fn for_path(trie: &DigestTrie, path: &Path) -> Result<DiffEntry, String> {
+        match trie.entry(path)? {
+            Some(Entry::File(f)) => Ok(DiffEntry::File(f.digest())),
+            Some(Entry::Symlink(s)) => Ok(DiffEntry::Symlink(s.target().to_owned())),
+            Some(Entry::Directory(_)) => Err(format!("{} is a directory.", path.display())),
+            None => Err(format!("{} does not exist.", path.display())),
+        }
+    }
+
+    fn all(trie: &DigestTrie) -> BTreeMap<PathBuf, DiffEntry> {
+        let mut entries = BTreeMap::new();
+        trie.walk(SymlinkBehavior::Aware, &mut |path, entry| match entry {
+            Entry::File(f) => {
+                entries.insert(path.to_owned(), DiffEntry::File(f.digest()));
+            }
+            Entry::Symlink(s) => {
+                entries.insert(path.to_owned(), DiffEntry::Symlink(s.target().to_owned()));
+            }
+            Entry::Directory(_) => {}
+        });
+        entries
+    }
+}
+
+///
+/// A path which differs between the two sides of a diff, and is missing from one side if it was
+/// added or removed.
+///
+struct Change {
+    before: Option<(PathBuf, DiffEntry)>,
+    after: Option<(PathBuf, DiffEntry)>,
+}
+
+impl Change {
+    fn between(before_trie: &DigestTrie, after_trie: &DigestTrie) -> Vec<Change> {
+        let mut before = DiffEntry::all(before_trie);
+        let mut after = DiffEntry::all(after_trie);
+        let paths = before
+            .keys()
+            .chain(after.keys())
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                let before = before.remove(&path);
+                let after = after.remove(&path);
+                if before == after {
+                    return None;
+                }
+                Some(Change {
+                    before: before.map(|entry| (path.clone(), entry)),
+                    after: after.map(|entry| (path, entry)),
+                })
+            })
+            .collect()
+    }
+
+    fn path(&self) -> &Path {
+        self.after
+            .as_ref()
+            .or(self.before.as_ref())
+            .map(|(path, _)| path.as_path())
+            .unwrap()
+    }
+}
+
+///
+/// Write a unified diff (with git-style `a/` and `b/` path prefixes) between the given contents,
+/// either of which might be missing.
+///
+fn write_diff(
+    out: &mut Vec<u8>,
+    mode: DiffMode,
+    context_lines: usize,
+    before: Option<(&Path, &[u8])>,
+    after: Option<(&Path, &[u8])>,
+) -> io::Result<()> {
+    let label = |prefix: &str, side: Option<(&Path, &[u8])>| match side {
+        Some((path, _)) => format!("{prefix}/{}", path.display()),
+        None => "/dev/null".to_owned(),
+    };
+    let before_label = label("a", before);
+    let after_label = label("b", after);
+    let before_content = before.map(|(_, content)| content).unwrap_or_default();
+    let after_content = after.map(|(_, content)| content).unwrap_or_default();
+
+    match mode {
+        DiffMode::Lines => {
+            let (Ok(before_text), Ok(after_text)) = (
+                std::str::from_utf8(before_content),
+                std::str::from_utf8(after_content),
+            ) else {
+                return writeln!(out, "Binary files {before_label} and {after_label} differ");
+            };
+            TextDiff::from_lines(before_text, after_text)
+                .unified_diff()
+                .context_radius(context_lines)
+                .header(&before_label, &after_label)
+                .to_writer(out)
+        }
+        DiffMode::Bytes => {
+            // NB: `UnifiedDiff::to_writer` renders each hunk via `Display`, which would lossily
+            // convert non-UTF-8 content, so the raw bytes of each hunk are written instead.
+            let diff = TextDiff::from_lines(before_content, after_content);
+            let mut unified_diff = diff.unified_diff();
+            unified_diff.context_radius(context_lines);
+            for (i, hunk) in unified_diff.iter_hunks().enumerate() {
+                if i == 0 {
+                    writeln!(out, "--- {before_label}")?;
+                    writeln!(out, "+++ {after_label}")?;
+                }
+                hunk.to_writer(&mut *out)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{write_diff, DiffMode};
+
+    fn diff(mode: DiffMode, before: Option<(&str, &[u8])>, after: Option<(&str, &[u8])>) -> String {
+        let mut out = Vec::new();
+        write_diff(
+            &mut out,
+            mode,
+            3,
+            before.map(|(path, content)| (Path::new(path), content)),
+            after.map(|(path, content)| (Path::new(path), content)),
+        )
+        .unwrap();
+        String::from_utf8_lossy(&out).into_owned()
+    }
+
+    #[test]
+    fn modified() {
+        assert_eq!(
+            "--- a/f.txt\n+++ b/f.txt\n@@ -1,2 +1,2 @@\n one\n-two\n+three\n",
+            diff(
+                DiffMode::Lines,
+                Some(("f.txt", b"one\ntwo\n")),
+                Some(("f.txt", b"one\nthree\n"))
+            )
+        );
+    }
+
+    #[test]
+    fn added_and_removed() {
+        assert_eq!(
+            "--- /dev/null\n+++ b/f.txt\n@@ -0,0 +1 @@\n+one\n",
+            diff(DiffMode::Lines, None, Some(("f.txt", b"one\n")))
+        );
+        assert_eq!(
+            "--- a/f.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-one\n",
+            diff(DiffMode::Lines, Some(("f.txt", b"one\n")), None)
+        );
+    }
+
+    #[test]
+    fn binary() {
+        let before: &[u8] = b"\xff\x00\n";
+        let after: &[u8] = b"\xfe\x00\n";
+        assert_eq!(
+            "Binary files a/f.bin and b/f.bin differ\n",
+            diff(
+                DiffMode::Lines,
+                Some(("f.bin", before)),
+                Some(("f.bin", after))
+            )
+        );
+
+        let mut out = Vec::new();
+        write_diff(
+            &mut out,
+            DiffMode::Bytes,
+            3,
+            Some((Path::new("f.bin"), before)),
+            Some((Path::new("f.bin"), after)),
+        )
+        .unwrap();
+        assert_eq!(
+            &b"--- a/f.bin\n+++ b/f.bin\n@@ -1 +1 @@\n-\xff\x00\n+\xfe\x00\n"[..],
+            &out[..]
+        );
+    }
+}
@@ -1,37 +1,53 @@
 // Copyright 2021 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
 use fs::{
-    DigestTrie, DirectoryDigest, GlobMatching, PathStat, RelativePath, SymlinkBehavior, TypedPath,
+    DigestTrie, DirectoryDigest, Entry, GlobMatching, PathMetadata, PathStat, RelativePath,
+    SymlinkBehavior, TypedPath,
 };
+use futures::future;
 use hashing::{Digest, EMPTY_DIGEST};
-use pyo3::prelude::{pyfunction, wrap_pyfunction, PyModule, PyRef, PyResult, Python};
+use pyo3::prelude::{pyfunction, wrap_pyfunction, Py, PyAny, PyModule, PyRef, PyResult, Python};
+use pyo3::{IntoPy, ToPyObject};
+use sha2::{Digest as Sha2Digest, Sha256};
 use store::{SnapshotOps, SubsetParams};
 
 use crate::externs;
-use crate::externs::fs::{PyAddPrefix, PyFileDigest, PyMergeDigests, PyRemovePrefix};
+use crate::externs::fs::{PyAddPrefix, PyDigest, PyFileDigest, PyMergeDigests, PyRemovePrefix};
 use crate::externs::PyGeneratorResponseNativeCall;
 use crate::nodes::{
-    lift_directory_digest, task_get_context, unmatched_globs_additional_context, DownloadedFile,
-    NodeResult, Snapshot,
+    self, lift_directory_digest, task_get_context, unmatched_globs_additional_context,
+    DownloadedFile, NodeResult, Snapshot,
 };
 use crate::python::{throw, Key, Value};
 use crate::Failure;
 
 pub fn register(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(add_prefix_batch_to_digests, m)?)?;
     m.add_function(wrap_pyfunction!(add_prefix_request_to_digest, m)?)?;
     m.add_function(wrap_pyfunction!(create_digest_to_digest, m)?)?;
     m.add_function(wrap_pyfunction!(digest_subset_to_digest, m)?)?;
     m.add_function(wrap_pyfunction!(digest_to_snapshot, m)?)?;
     m.add_function(wrap_pyfunction!(directory_digest_to_digest_contents, m)?)?;
+    m.add_function(wrap_pyfunction!(
+        directory_digest_to_digest_content_views,
+        m
+    )?)?;
     m.add_function(wrap_pyfunction!(directory_digest_to_digest_entries, m)?)?;
     m.add_function(wrap_pyfunction!(download_file_to_digest, m)?)?;
+    m.add_function(wrap_pyfunction!(
+        fingerprint_digest_to_digest_fingerprint,
+        m
+    )?)?;
+    m.add_function(wrap_pyfunction!(merge_digests_batch_to_digests, m)?)?;
     m.add_function(wrap_pyfunction!(merge_digests_request_to_digest, m)?)?;
     m.add_function(wrap_pyfunction!(path_globs_to_digest, m)?)?;
     m.add_function(wrap_pyfunction!(path_globs_to_paths, m)?)?;
+    m.add_function(wrap_pyfunction!(path_metadata_request, m)?)?;
+    m.add_function(wrap_pyfunction!(remove_prefix_batch_to_digests, m)?)?;
     m.add_function(wrap_pyfunction!(remove_prefix_request_to_digest, m)?)?;
 
     Ok(())
@@ -55,6 +71,24 @@ fn directory_digest_to_digest_contents(digest: Value) -> PyGeneratorResponseNati
     })
 }
 
+#[pyfunction]
+fn directory_digest_to_digest_content_views(digest: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+
+        let digest = Python::with_gil(|py| {
+            let py_digest = digest.as_ref().as_ref(py);
+            lift_directory_digest(py_digest)
+        })?;
+
+        let digest_contents = context.core.store().contents_for_directory(digest).await?;
+
+        Ok::<_, Failure>(Python::with_gil(|py| {
+            Snapshot::store_digest_content_views(py, &context, digest_contents)
+        })?)
+    })
+}
+
 #[pyfunction]
 fn directory_digest_to_digest_entries(digest: Value) -> PyGeneratorResponseNativeCall {
     PyGeneratorResponseNativeCall::new(async move {
@@ -118,6 +152,66 @@ fn add_prefix_request_to_digest(add_prefix: Value) -> PyGeneratorResponseNativeC
     })
 }
 
+#[pyfunction]
+fn remove_prefix_batch_to_digests(remove_prefix_batch: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+        let store = context.core.store();
+
+        let requests = Python::with_gil(|py| {
+            let py_requests: Vec<PyRef<PyRemovePrefix>> =
+                externs::getattr(remove_prefix_batch.as_ref().as_ref(py), "requests")?;
+            py_requests
+                .iter()
+                .map(|py_remove_prefix| {
+                    let prefix = RelativePath::new(&py_remove_prefix.prefix)
+                        .map_err(|e| throw(format!("The `prefix` must be relative: {e}")))?;
+                    Ok((py_remove_prefix.digest.clone(), prefix))
+                })
+                .collect::<NodeResult<Vec<_>>>()
+        })?;
+        let digests = future::try_join_all(
+            requests
+                .iter()
+                .map(|(digest, prefix)| store.strip_prefix(digest.clone(), prefix)),
+        )
+        .await?;
+        Ok::<_, Failure>(Python::with_gil(|py| {
+            store_digests(py, &context.core.types, digests)
+        })?)
+    })
+}
+
+#[pyfunction]
+fn add_prefix_batch_to_digests(add_prefix_batch: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+        let store = context.core.store();
+
+        let requests = Python::with_gil(|py| {
+            let py_requests: Vec<PyRef<PyAddPrefix>> =
+                externs::getattr(add_prefix_batch.as_ref().as_ref(py), "requests")?;
+            py_requests
+                .iter()
+                .map(|py_add_prefix| {
+                    let prefix = RelativePath::new(&py_add_prefix.prefix)
+                        .map_err(|e| throw(format!("The `prefix` must be relative: {e}")))?;
+                    Ok((py_add_prefix.digest.clone(), prefix))
+                })
+                .collect::<NodeResult<Vec<_>>>()
+        })?;
+        let digests = future::try_join_all(
+            requests
+                .iter()
+                .map(|(digest, prefix)| store.add_prefix(digest.clone(), prefix)),
+        )
+        .await?;
+        Ok::<_, Failure>(Python::with_gil(|py| {
+            store_digests(py, &context.core.types, digests)
+        })?)
+    })
+}
+
 #[pyfunction]
 fn digest_to_snapshot(digest: Value) -> PyGeneratorResponseNativeCall {
     PyGeneratorResponseNativeCall::new(async move {
@@ -158,6 +252,53 @@ fn merge_digests_request_to_digest(digests: Value) -> PyGeneratorResponseNativeC
     })
 }
 
+#[pyfunction]
+fn merge_digests_batch_to_digests(merge_digests_batch: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+        let store = context.core.store();
+
+        let requests = Python::with_gil(|py| {
+            let py_requests: Vec<PyRef<PyMergeDigests>> =
+                externs::getattr(merge_digests_batch.as_ref().as_ref(py), "requests")?;
+            let res: NodeResult<_> = Ok(py_requests
+                .iter()
+                .map(|py_merge_digests| py_merge_digests.0.clone())
+                .collect::<Vec<_>>());
+            res
+        })?;
+        let digests =
+            future::try_join_all(requests.into_iter().map(|digests| store.merge(digests))).await?;
+        Ok::<_, Failure>(Python::with_gil(|py| {
+            store_digests(py, &context.core.types, digests)
+        })?)
+    })
+}
+
+///
+/// Store the results of a batched request as a `Digests` collection, in request order.
+///
+fn store_digests(
+    py: Python,
+    types: &crate::types::Types,
+    digests: Vec<DirectoryDigest>,
+) -> Result<Value, String> {
+    let mut py_digests = Vec::with_capacity(digests.len());
+    for digest in digests {
+        py_digests.push(Py::new(py, PyDigest(digest)).map_err(|e| format!("{e}"))?);
+    }
+    Ok(externs::unsafe_call(
+        py,
+        types.digests,
+        &[externs::store_tuple_of_objects(
+            py,
+            py_digests
+                .into_iter()
+                .map(|py_digest| py_digest.into_py(py)),
+        )],
+    ))
+}
+
 #[pyfunction]
 fn download_file_to_digest(download_file: Value) -> PyGeneratorResponseNativeCall {
     PyGeneratorResponseNativeCall::new(async move {
@@ -237,6 +378,128 @@ fn path_globs_to_paths(path_globs: Value) -> PyGeneratorResponseNativeCall {
     })
 }
 
+#[pyfunction]
+fn path_metadata_request(path_metadata_request: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+
+        let (paths, namespace, digest) = Python::with_gil(|py| {
+            let py_request = path_metadata_request.as_ref().as_ref(py);
+            let paths: Vec<String> = externs::getattr(py_request, "paths")?;
+            let namespace: String = externs::getattr(
+                externs::getattr::<&PyAny>(py_request, "namespace")?,
+                "value",
+            )?;
+            let py_digest: &PyAny = externs::getattr(py_request, "digest")?;
+            let digest = if py_digest.is_none() {
+                None
+            } else {
+                Some(lift_directory_digest(py_digest)?)
+            };
+            let res: NodeResult<_> = Ok((paths, namespace, digest));
+            res
+        })?;
+
+        let metadata: Vec<Option<PathMetadata>> = match (namespace.as_str(), digest) {
+            ("workspace", _) => {
+                let paths = paths
+                    .iter()
+                    .map(|path| {
+                        RelativePath::new(path).map_err(|e| {
+                            throw(format!(
+                                "Workspace paths must be relative to the build root: {e}"
+                            ))
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                future::try_join_all(
+                    paths
+                        .into_iter()
+                        .map(|path| context.get(nodes::PathMetadata::new(path.into()))),
+                )
+                .await?
+            }
+            ("system", _) => {
+                // NB: System paths are outside of the build root, and so are not watched.
+                if let Some(path) = paths.iter().find(|path| !Path::new(path).is_absolute()) {
+                    return Err(throw(format!("System paths must be absolute: {path}")));
+                }
+                context
+                    .core
+                    .executor
+                    .spawn_blocking(
+                        move || {
+                            paths
+                                .into_iter()
+                                .map(|path| {
+                                    PathMetadata::for_path_sync(
+                                        Path::new(&path),
+                                        PathBuf::from(&path),
+                                    )
+                                    .map_err(|e| format!("Failed to read metadata for {path}: {e}"))
+                                })
+                                .collect::<Result<Vec<_>, _>>()
+                        },
+                        |e| Err(format!("Path metadata task failed: {e}")),
+                    )
+                    .await
+                    .map_err(throw)?
+            }
+            ("digest", Some(digest)) => {
+                let trie = context.core.store().load_digest_trie(digest).await?;
+                paths
+                    .into_iter()
+                    .map(|path| {
+                        let path = PathBuf::from(path);
+                        Ok(trie
+                            .entry(&path)?
+                            .map(|entry| PathMetadata::for_entry(path.clone(), entry)))
+                    })
+                    .collect::<Result<Vec<_>, String>>()?
+            }
+            ("digest", None) => {
+                return Err(throw(
+                    "A `digest` must be provided for the `digest` namespace.".to_owned(),
+                ))
+            }
+            (unknown, _) => return Err(throw(format!("Unknown path namespace: {unknown}"))),
+        };
+
+        Ok::<_, Failure>(Python::with_gil(|py| {
+            let types = &context.core.types;
+            let metadata = metadata
+                .into_iter()
+                .map(|metadata| match metadata {
+                    Some(metadata) => Ok(externs::unsafe_call(
+                        py,
+                        types.path_metadata,
+                        &[
+                            Snapshot::store_path(py, &metadata.path)?,
+                            externs::store_utf8(py, metadata.kind.as_str()),
+                            externs::store_u64(py, metadata.length),
+                            externs::store_bool(py, metadata.is_executable),
+                            Value::from(metadata.unix_mode.to_object(py)),
+                            Value::from(metadata.uid.to_object(py)),
+                            Value::from(metadata.gid.to_object(py)),
+                            Value::from(metadata.mtime_ns.to_object(py)),
+                            match metadata.symlink_target {
+                                Some(target) => Snapshot::store_path(py, &target)?,
+                                None => Value::from(py.None()),
+                            },
+                        ],
+                    )),
+                    None => Ok(Value::from(py.None())),
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            Ok::<_, String>(externs::unsafe_call(
+                py,
+                types.path_metadata_result,
+                &[externs::store_tuple(py, metadata)],
+            ))
+        })?)
+    })
+}
+
 enum CreateDigestItem {
     FileContent(RelativePath, bytes::Bytes, bool),
     FileEntry(RelativePath, Digest, bool),
@@ -307,11 +570,11 @@ fn create_digest_to_digest(py: Python, create_digest: Value) -> PyGeneratorRespo
                 }
                 CreateDigestItem::SymlinkEntry(path, target) => {
                     typed_paths.push(TypedPath::Link { path, target });
-                    file_digests.insert(path.to_path_buf(), EMPTY_DIGEST);
+                    file_digests.insert(path.to_path_buf(), *EMPTY_DIGEST);
                 }
                 CreateDigestItem::Dir(path) => {
                     typed_paths.push(TypedPath::Dir(path));
-                    file_digests.insert(path.to_path_buf(), EMPTY_DIGEST);
+                    file_digests.insert(path.to_path_buf(), *EMPTY_DIGEST);
                 }
             }
         }
@@ -354,3 +617,89 @@ fn digest_subset_to_digest(digest_subset: Value) -> PyGeneratorResponseNativeCal
         })?)
     })
 }
+
+#[pyfunction]
+fn fingerprint_digest_to_digest_fingerprint(
+    fingerprint_digest: Value,
+) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+
+        let (digest, include_permissions, include_symlinks) = Python::with_gil(|py| {
+            let py_request = fingerprint_digest.as_ref().as_ref(py);
+            let digest = lift_directory_digest(externs::getattr(py_request, "digest")?)?;
+            let include_permissions: bool = externs::getattr(py_request, "include_permissions")?;
+            let include_symlinks: bool = externs::getattr(py_request, "include_symlinks")?;
+            let res: NodeResult<_> = Ok((digest, include_permissions, include_symlinks));
+            res
+        })?;
+        let trie = context.core.store().load_digest_trie(digest).await?;
+        let fingerprint = fingerprint_trie(&trie, include_permissions, include_symlinks);
+
+        Ok::<_, Failure>(Python::with_gil(|py| {
+            externs::unsafe_call(
+                py,
+                context.core.types.digest_fingerprint,
+                &[externs::store_utf8(py, &fingerprint)],
+            )
+        }))
+    })
+}
+
+/// A version prefix for the input to a `fingerprint_trie`, which must change if its encoding does.
+const FINGERPRINT_VERSION: &[u8] = b"pants-digest-fingerprint-v1";
+
+///
+/// Compute a hex SHA-256 fingerprint of the paths, file contents and (optionally) metadata of a
+/// trie.
+///
+/// Unlike the digest of the trie, this depends only on a canonical encoding of its entries (sorted
+/// by path, with each field length-prefixed), rather than on the serialization of its `Directory`
+/// protos, and so is stable across digest formats.
+///
+fn fingerprint_trie(
+    trie: &DigestTrie,
+    include_permissions: bool,
+    include_symlinks: bool,
+) -> String {
+    fn write_field(hasher: &mut Sha256, field: &[u8]) {
+        hasher.update((field.len() as u64).to_be_bytes());
+        hasher.update(field);
+    }
+
+    let mut entries = BTreeMap::new();
+    trie.walk(SymlinkBehavior::Aware, &mut |path, entry| {
+        entries.insert(path.to_owned(), entry.clone());
+    });
+
+    let mut hasher = Sha256::new();
+    write_field(&mut hasher, FINGERPRINT_VERSION);
+    write_field(
+        &mut hasher,
+        &[u8::from(include_permissions), u8::from(include_symlinks)],
+    );
+    for (path, entry) in entries {
+        let kind: &[u8] = match &entry {
+            Entry::Directory(_) => b"directory",
+            Entry::File(_) => b"file",
+            Entry::Symlink(_) if include_symlinks => b"symlink",
+            Entry::Symlink(_) => continue,
+        };
+        write_field(&mut hasher, kind);
+        write_field(&mut hasher, path.as_os_str().as_encoded_bytes());
+        match &entry {
+            Entry::Directory(_) => {}
+            Entry::File(f) => {
+                write_field(&mut hasher, f.digest().hash.as_bytes());
+                write_field(&mut hasher, &(f.digest().size_bytes as u64).to_be_bytes());
+                if include_permissions {
+                    write_field(&mut hasher, &[u8::from(f.is_executable())]);
+                }
+            }
+            Entry::Symlink(s) => {
+                write_field(&mut hasher, s.target().as_os_str().as_encoded_bytes());
+            }
+        }
+    }
+    hex::encode(hasher.finalize())
+}
@@ -1,17 +1,24 @@
 // Copyright 2021 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
 use docker::docker::{ImagePullPolicy, ImagePullScope, DOCKER, IMAGE_PULL_CACHE};
+use hashing::Digest;
 use process_execution::Platform;
+use protos::gen::pants::cache::{CacheKey, CacheKeyType};
 use pyo3::prelude::{pyfunction, wrap_pyfunction, PyAny, PyModule, PyResult, Python, ToPyObject};
 use pyo3::types::PyString;
 
 use crate::externs::{self, PyGeneratorResponseNativeCall};
-use crate::nodes::task_get_context;
-use crate::python::{Failure, Value};
+use crate::nodes::{task_get_context, NodeResult};
+use crate::oci_registry::{self, ImageReference, RegistryCredentials};
+use crate::python::{throw, Failure, Value};
 
 pub fn register(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(docker_resolve_image, m)?)?;
+    m.add_function(wrap_pyfunction!(resolve_image_digest, m)?)?;
 
     Ok(())
 }
@@ -68,3 +75,93 @@ fn docker_resolve_image(docker_request: Value) -> PyGeneratorResponseNativeCall
         }))
     })
 }
+
+///
+/// Resolve an image reference to the digest of its manifest using the registry HTTP API (rather
+/// than a docker daemon), caching the result in the local cache for a configurable TTL.
+///
+/// NB: The credentials are not part of the cache key.
+///
+#[pyfunction]
+fn resolve_image_digest(resolve_request: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+        let core = &context.core;
+
+        let (image_ref, username, password, cache_ttl_seconds) = Python::with_gil(|py| {
+            let py_request: &PyAny = resolve_request.as_ref().as_ref(py);
+            let image_ref: String = externs::getattr(py_request, "image_ref")?;
+            let username: Option<String> = externs::getattr(py_request, "username")?;
+            let password: Option<String> = externs::getattr(py_request, "password")?;
+            let cache_ttl_seconds: u64 = externs::getattr(py_request, "cache_ttl_seconds")?;
+            let res: NodeResult<_> = Ok((image_ref, username, password, cache_ttl_seconds));
+            res
+        })?;
+        let reference = ImageReference::parse(&image_ref).map_err(throw)?;
+
+        let cache_key = CacheKey {
+            key_type: CacheKeyType::OciImageReference.into(),
+            digest: Some(Digest::of_bytes(reference.to_string().as_bytes()).into()),
+            ..CacheKey::default()
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| format!("{e}"))?
+            .as_secs();
+        let cached_digest = if cache_ttl_seconds > 0 && reference.digest.is_none() {
+            core.local_cache
+                .load(&cache_key)
+                .await?
+                .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+                .filter(|cached| {
+                    cached["resolved_at"].as_u64().is_some_and(|resolved_at| {
+                        now.saturating_sub(resolved_at) < cache_ttl_seconds
+                    })
+                })
+                .and_then(|cached| cached["digest"].as_str().map(str::to_owned))
+        } else {
+            None
+        };
+
+        let digest = if let Some(digest) = cached_digest {
+            digest
+        } else {
+            let credentials = match (username, password) {
+                (Some(username), Some(password)) => {
+                    Some(RegistryCredentials { username, password })
+                }
+                (None, None) => RegistryCredentials::from_docker_config(&reference.registry)?,
+                _ => {
+                    return Err(throw(
+                        "Both or neither of `username` and `password` must be set.".to_owned(),
+                    ))
+                }
+            };
+            let digest = oci_registry::resolve_manifest_digest(
+                &core.http_client,
+                &reference,
+                credentials.as_ref(),
+            )
+            .await?;
+            if reference.digest.is_none() {
+                let cached = serde_json::json!({"digest": digest, "resolved_at": now});
+                core.local_cache
+                    .store(&cache_key, Bytes::from(cached.to_string()))
+                    .await?;
+            }
+            digest
+        };
+
+        let pinned_image_ref = reference.pinned(&digest);
+        Ok::<_, Failure>(Python::with_gil(|py| {
+            externs::unsafe_call(
+                py,
+                core.types.resolve_image_digest_result,
+                &[
+                    externs::store_utf8(py, &digest),
+                    externs::store_utf8(py, &pinned_image_ref),
+                ],
+            )
+        }))
+    })
+}
@@ -0,0 +1,233 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use futures::future;
+use gix::commit::describe::SelectRef;
+use pyo3::prelude::{pyfunction, wrap_pyfunction, PyModule, PyResult, Python};
+use pyo3::ToPyObject;
+
+use crate::externs;
+use crate::externs::PyGeneratorResponseNativeCall;
+use crate::nodes::{task_get_context, NodeResult, PathMetadata, Snapshot};
+use crate::python::{throw, Value};
+use crate::Failure;
+
+pub fn register(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(git_metadata_request, m)?)?;
+
+    Ok(())
+}
+
+///
+/// Reads the state of the git repository containing the build root.
+///
+/// Rather than being uncacheable, the result depends on the `PathMetadata` of the files in the git
+/// metadata directory which determine the state of the repository (`HEAD`, the ref that it points
+/// to), and is thus invalidated by commits and checkouts. Only if dirty paths were requested (which
+/// callers must opt in to) does it additionally depend on the index, and on the tracked files and
+/// their parent directories, so that it is invalidated by edits to the worktree.
+///
+/// NB: A git metadata directory which is outside of the build root (as for a linked worktree) is
+/// not watched.
+///
+#[pyfunction]
+fn git_metadata_request(git_metadata_request: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+        let build_root = context.core.build_root.clone();
+
+        let include_dirty_paths = Python::with_gil(|py| {
+            let py_request = git_metadata_request.as_ref().as_ref(py);
+            let include_dirty_paths: bool = externs::getattr(py_request, "include_dirty_paths")?;
+            let res: NodeResult<_> = Ok(include_dirty_paths);
+            res
+        })?;
+
+        // Depend on the metadata directory itself, so that a repository which is created later is
+        // detected.
+        context
+            .get(PathMetadata::new(PathBuf::from(".git")))
+            .await?;
+
+        let repository = context
+            .core
+            .executor
+            .spawn_blocking(
+                {
+                    let build_root = build_root.clone();
+                    move || GitRepository::discover(&build_root, include_dirty_paths)
+                },
+                |e| Err(format!("Git discovery task failed: {e}")),
+            )
+            .await
+            .map_err(throw)?;
+
+        let metadata = if let Some(repository) = repository {
+            future::try_join_all(
+                repository
+                    .watched_paths
+                    .iter()
+                    .map(|path| context.get(PathMetadata::new(path.clone()))),
+            )
+            .await?;
+            context
+                .core
+                .executor
+                .spawn_blocking(
+                    move || repository.read_metadata(&build_root, include_dirty_paths),
+                    |e| Err(format!("Git metadata task failed: {e}")),
+                )
+                .await
+                .map_err(throw)?
+        } else {
+            GitMetadata::default()
+        };
+
+        Ok::<_, Failure>(Python::with_gil(|py| {
+            let dirty_paths = metadata
+                .dirty_paths
+                .iter()
+                .map(|path| Snapshot::store_path(py, path))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok::<_, String>(externs::unsafe_call(
+                py,
+                context.core.types.git_metadata,
+                &[
+                    Value::from(metadata.commit_id.to_object(py)),
+                    Value::from(metadata.branch_name.to_object(py)),
+                    Value::from(metadata.describe.to_object(py)),
+                    externs::store_tuple(py, dirty_paths),
+                ],
+            ))
+        })?)
+    })
+}
+
+///
+/// A discovered git repository, and the paths (relative to the build root) whose changes might
+/// change its state.
+///
+struct GitRepository {
+    git_dir: PathBuf,
+    watched_paths: BTreeSet<PathBuf>,
+}
+
+impl GitRepository {
+    fn discover(build_root: &Path, include_dirty_paths: bool) -> Result<Option<Self>, String> {
+        let repo = match gix::discover(build_root) {
+            Ok(repo) => repo,
+            Err(e) => {
+                log::debug!("No git repository at {}: {e}", build_root.display());
+                return Ok(None);
+            }
+        };
+
+        let mut watched_paths = BTreeSet::new();
+        if let Ok(git_dir) = repo.git_dir().strip_prefix(build_root) {
+            watched_paths.insert(git_dir.join("HEAD"));
+            watched_paths.insert(git_dir.join("packed-refs"));
+            let head = repo
+                .head()
+                .map_err(|e| format!("Failed to read the git HEAD: {e}"))?;
+            if let Some(referent) = head.referent_name() {
+                watched_paths.insert(git_dir.join(gix::path::from_bstr(referent.as_bstr())));
+            }
+            if include_dirty_paths {
+                watched_paths.insert(git_dir.join("index"));
+            }
+        }
+
+        if include_dirty_paths {
+            if let Some(work_dir) = repo.work_dir() {
+                let index = repo
+                    .index_or_empty()
+                    .map_err(|e| format!("Failed to read the git index: {e}"))?;
+                for entry in index.entries() {
+                    let path = work_dir.join(gix::path::from_bstr(entry.path(&index)));
+                    let Ok(path) = path.strip_prefix(build_root) else {
+                        continue;
+                    };
+                    // Depending on the parent directories of tracked files additionally detects the
+                    // creation of untracked files.
+                    watched_paths.extend(path.ancestors().map(Path::to_path_buf));
+                }
+            }
+        }
+
+        Ok(Some(GitRepository {
+            git_dir: repo.git_dir().to_path_buf(),
+            watched_paths,
+        }))
+    }
+
+    fn read_metadata(
+        &self,
+        build_root: &Path,
+        include_dirty_paths: bool,
+    ) -> Result<GitMetadata, String> {
+        let repo = gix::open(&self.git_dir).map_err(|e| {
+            format!(
+                "Failed to open the git repository at {}: {e}",
+                self.git_dir.display()
+            )
+        })?;
+
+        let mut head = repo
+            .head()
+            .map_err(|e| format!("Failed to read the git HEAD: {e}"))?;
+        let branch_name = head.referent_name().map(|name| name.shorten().to_string());
+        let commit_id = head.id().map(|id| id.to_string());
+        let describe = if head.is_unborn() {
+            None
+        } else {
+            let commit = head
+                .peel_to_commit_in_place()
+                .map_err(|e| format!("Failed to read the git HEAD commit: {e}"))?;
+            commit
+                .describe()
+                .names(SelectRef::AllTags)
+                .id_as_fallback(true)
+                .try_format()
+                .map_err(|e| format!("Failed to describe the git HEAD commit: {e}"))?
+                .map(|format| format.to_string())
+        };
+
+        let mut dirty_paths = BTreeSet::new();
+        if include_dirty_paths {
+            if let Some(work_dir) = repo.work_dir() {
+                let status = repo
+                    .status(gix::progress::Discard)
+                    .map_err(|e| format!("Failed to compute the git status: {e}"))?
+                    .untracked_files(gix::status::UntrackedFiles::Files)
+                    .into_index_worktree_iter(Vec::new())
+                    .map_err(|e| format!("Failed to compute the git status: {e}"))?;
+                for item in status {
+                    let item =
+                        item.map_err(|e| format!("Failed to compute the git status: {e}"))?;
+                    let path = work_dir.join(gix::path::from_bstr(item.rela_path()));
+                    if let Ok(path) = path.strip_prefix(build_root) {
+                        dirty_paths.insert(path.to_path_buf());
+                    }
+                }
+            }
+        }
+
+        Ok(GitMetadata {
+            commit_id,
+            branch_name,
+            describe,
+            dirty_paths: dirty_paths.into_iter().collect(),
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct GitMetadata {
+    commit_id: Option<String>,
+    branch_name: Option<String>,
+    describe: Option<String>,
+    dirty_paths: Vec<PathBuf>,
+}
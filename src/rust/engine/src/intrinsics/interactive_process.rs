@@ -2,10 +2,14 @@
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
 use std::env::current_dir;
+use std::io::IsTerminal;
+use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::str::FromStr;
+use std::time::Duration;
 
+use docker::docker;
 use futures::future::TryFutureExt;
 use process_execution::local::{
     apply_chroot, create_sandbox, prepare_workdir, setup_run_sh_script, KeepSandboxes,
@@ -13,6 +17,7 @@ use process_execution::local::{
 use process_execution::{ManagedChild, ProcessExecutionStrategy};
 use pyo3::prelude::{pyfunction, wrap_pyfunction, PyAny, PyModule, PyResult, Python, ToPyObject};
 use stdio::TryCloneAsFile;
+use terminal_size::terminal_size_using_fd;
 use tokio::process;
 use workunit_store::{in_workunit, Level};
 
@@ -21,6 +26,9 @@ use crate::externs::{self, PyGeneratorResponseNativeCall};
 use crate::nodes::{task_get_context, task_side_effected, ExecuteProcess, NodeResult};
 use crate::python::{Failure, Value};
 
+/// How often to check whether the terminal of an interactive process has been resized.
+const WINDOW_SIZE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
 pub fn register(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(interactive_process, m)?)?;
 
@@ -65,17 +73,14 @@ pub async fn interactive_process_inner(
         )
     });
 
-    match process_config.environment.strategy {
-        ProcessExecutionStrategy::Docker(_) | ProcessExecutionStrategy::RemoteExecution(_) => {
-            // TODO: #17182 covers adding support for running processes interactively in Docker.
-            Err(format!(
-                "Only local environments support running processes \
-       interactively, but a {} environment was used.",
-                process_config.environment.strategy.strategy_type(),
-            ))
-        }
-        _ => Ok(()),
-    }?;
+    if let ProcessExecutionStrategy::RemoteExecution(_) = process_config.environment.strategy {
+        return Err(format!(
+            "Only local and docker environments support running processes interactively, but \
+             a {} environment was used.",
+            process_config.environment.strategy.strategy_type(),
+        )
+        .into());
+    }
     let mut process = ExecuteProcess::lift(&context.core.store(), py_process, process_config)
         .await?
         .process;
@@ -93,6 +98,27 @@ pub async fn interactive_process_inner(
         (run_in_workspace, restartable, keep_sandboxes)
     });
 
+    // Interactive processes are attached to the console, so tell them whether to use color in the
+    // same way as Pants does, unless the process sets the variables itself.
+    for (name, value) in stdio::get_destination().child_color_env() {
+        process
+            .env
+            .entry((*name).to_owned())
+            .or_insert_with(|| (*value).to_owned());
+    }
+
+    let is_docker = matches!(
+        process.execution_environment.strategy,
+        ProcessExecutionStrategy::Docker(_)
+    );
+    if is_docker && run_in_workspace {
+        return Err(
+            "Processes cannot be run interactively in the workspace in a docker environment."
+                .to_owned()
+                .into(),
+        );
+    }
+
     let session = context.session.clone();
 
     let mut tempdir = create_sandbox(
@@ -101,42 +127,62 @@ pub async fn interactive_process_inner(
         "interactive process",
         keep_sandboxes,
     )?;
-    prepare_workdir(
-        tempdir.path().to_owned(),
-        &context.core.local_execution_root_dir,
-        &process,
-        process.input_digests.inputs.clone(),
-        &context.core.store(),
-        &context.core.named_caches,
-        &context.core.immutable_inputs,
-        None,
-        None,
-    )
-    .await?;
-    apply_chroot(tempdir.path().to_str().unwrap(), &mut process);
-
-    let p = Path::new(&process.argv[0]);
-    // TODO: Deprecate this program name calculation, and recommend `{chroot}` replacement in args
-    // instead.
-    let program_name = if !run_in_workspace && p.is_relative() {
-        let mut buf = PathBuf::new();
-        buf.push(tempdir.path());
-        buf.push(p);
-        buf
+
+    // Processes in docker environments are run via `docker exec` in the same containers as other
+    // processes, and so the sandbox is prepared using paths within the container.
+    let (command, docker_exec) = if is_docker {
+        let docker_exec = context
+            .core
+            .docker_container_cache
+            .interactive_exec(
+                &context.core.store(),
+                &context.core.immutable_inputs,
+                &mut process,
+                tempdir.path(),
+                session.build_id(),
+            )
+            .await?;
+        (None, Some(docker_exec))
     } else {
-        p.to_path_buf()
-    };
+        prepare_workdir(
+            tempdir.path().to_owned(),
+            &context.core.local_execution_root_dir,
+            &process,
+            process.input_digests.inputs.clone(),
+            &context.core.store(),
+            &context.core.named_caches,
+            Some(&context.core.immutable_inputs),
+            None,
+            None,
+            None,
+        )
+        .await?;
+        apply_chroot(tempdir.path().to_str().unwrap(), &mut process);
 
-    let mut command = process::Command::new(program_name);
-    if !run_in_workspace {
-        command.current_dir(tempdir.path());
-    }
-    for arg in process.argv[1..].iter() {
-        command.arg(arg);
-    }
+        let p = Path::new(&process.argv[0]);
+        // TODO: Deprecate this program name calculation, and recommend `{chroot}` replacement in args
+        // instead.
+        let program_name = if !run_in_workspace && p.is_relative() {
+            let mut buf = PathBuf::new();
+            buf.push(tempdir.path());
+            buf.push(p);
+            buf
+        } else {
+            p.to_path_buf()
+        };
 
-    command.env_clear();
-    command.envs(&process.env);
+        let mut command = process::Command::new(program_name);
+        if !run_in_workspace {
+            command.current_dir(tempdir.path());
+        }
+        for arg in process.argv[1..].iter() {
+            command.arg(arg);
+        }
+
+        command.env_clear();
+        command.envs(&process.env);
+        (Some(command), None)
+    };
 
     if !restartable {
         task_side_effected()?;
@@ -152,12 +198,17 @@ pub async fn interactive_process_inner(
     }))?;
   // NB: Command's stdio methods take ownership of a file-like to use, so we use
   // `TryCloneAsFile` here to `dup` our thread-local stdio.
+  let stdin = term_stdin
+    .try_clone_as_file()
+    .map_err(|e| format!("Couldn't clone stdin: {e}"))?;
+  // The `docker` CLI allocates a PTY in the container if (and only if) stdin is a terminal.
+  let mut command = match (command, &docker_exec) {
+    (Some(command), _) => command,
+    (None, Some(docker_exec)) => docker_exec.command(stdin.is_terminal()),
+    (None, None) => unreachable!("Either a local or docker command is prepared."),
+  };
   command
-    .stdin(Stdio::from(
-      term_stdin
-        .try_clone_as_file()
-        .map_err(|e| format!("Couldn't clone stdin: {e}"))?,
-    ))
+    .stdin(Stdio::from(stdin))
     .stdout(Stdio::from(
       term_stdout
         .try_clone_as_file()
@@ -171,24 +222,52 @@ pub async fn interactive_process_inner(
   let mut subprocess =
       ManagedChild::spawn(&mut command, Some(context.core.graceful_shutdown_timeout))
         .map_err(|e| format!("Error executing interactive process: {e}"))?;
-  tokio::select! {
-    _ = session.cancelled() => {
-      // The Session was cancelled: attempt to kill the process group / process, and
-      // then wait for it to exit (to avoid zombies).
-      if let Err(e) = subprocess.attempt_shutdown_sync() {
-        // Failed to kill the PGID: try the non-group form.
-        log::warn!("Failed to kill spawned process group ({}). Will try killing only the top process.\n\
-                  This is unexpected: please file an issue about this problem at \
-                  [https://github.com/pantsbuild/pants/issues/new]", e);
-        subprocess.kill().map_err(|e| format!("Failed to interrupt child process: {e}")).await?;
-      };
-      subprocess.wait().await.map_err(|e| e.to_string())
+  // Interactive processes run in their own session, and so do not receive the `SIGWINCH` that the
+  // terminal sends when it is resized: poll for changes to its size instead, and forward them.
+  let terminal_fd = term_stdout.as_raw_fd();
+  let mut window_size = terminal_size_using_fd(terminal_fd).map(|(w, h)| (w.0, h.0));
+  let mut window_size_poll = tokio::time::interval(WINDOW_SIZE_POLL_INTERVAL);
+  let exit_status = loop {
+    tokio::select! {
+      _ = session.cancelled() => {
+        // The Session was cancelled: attempt to kill the process group / process, and
+        // then wait for it to exit (to avoid zombies). Because `docker exec` does not forward
+        // signals, the process in the container is interrupted first.
+        if let Some(docker_exec) = &docker_exec {
+          if let Err(e) = docker_exec.signal(&docker::DOCKER, "INT").await {
+            log::warn!("Failed to interrupt interactive process in container: {e}");
+          }
+        }
+        if let Err(e) = subprocess.attempt_shutdown_sync() {
+          // Failed to kill the PGID: try the non-group form.
+          log::warn!("Failed to kill spawned process group ({}). Will try killing only the top process.\n\
+                    This is unexpected: please file an issue about this problem at \
+                    [https://github.com/pantsbuild/pants/issues/new]", e);
+          subprocess.kill().map_err(|e| format!("Failed to interrupt child process: {e}")).await?;
+        };
+        break subprocess.wait().await.map_err(|e| e.to_string());
+      }
+      exit_status = subprocess.wait() => {
+        // The process exited.
+        break exit_status.map_err(|e| e.to_string());
+      }
+      _ = window_size_poll.tick(), if window_size.is_some() => {
+        let new_window_size = terminal_size_using_fd(terminal_fd).map(|(w, h)| (w.0, h.0));
+        if new_window_size != window_size {
+          window_size = new_window_size;
+          if let Err(e) = subprocess.notify_window_size_changed() {
+            log::debug!("Failed to notify interactive process of terminal resize: {e}");
+          }
+        }
+      }
     }
-    exit_status = subprocess.wait() => {
-      // The process exited.
-      exit_status.map_err(|e| e.to_string())
+  };
+  if let Some(docker_exec) = &docker_exec {
+    if let Err(e) = docker_exec.remove_pid_file(&docker::DOCKER).await {
+      log::debug!("{e}");
     }
   }
+  exit_status
 })
 .await?;
 
@@ -4,21 +4,29 @@
 use pyo3::prelude::{PyModule, PyResult, Python};
 
 // Sub-modules with intrinsic implementations.
+mod archives;
 mod dep_inference;
+mod diff;
 mod digests;
 mod docker;
+mod git;
 mod interactive_process;
 mod process;
+mod structured_data;
 mod values;
 
 pub use interactive_process::interactive_process_inner;
 
 pub fn register(py: Python, m: &PyModule) -> PyResult<()> {
+    archives::register(py, m)?;
     dep_inference::register(py, m)?;
+    diff::register(py, m)?;
     digests::register(py, m)?;
     docker::register(py, m)?;
+    git::register(py, m)?;
     interactive_process::register(py, m)?;
     process::register(py, m)?;
+    structured_data::register(py, m)?;
     values::register(py, m)?;
 
     Ok(())
@@ -0,0 +1,229 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use fs::Entry;
+use pyo3::prelude::{pyfunction, wrap_pyfunction, IntoPy, Py, PyAny, PyModule, PyResult, Python};
+
+use crate::externs;
+use crate::externs::structured_data::PyStructuredData;
+use crate::externs::PyGeneratorResponseNativeCall;
+use crate::nodes::{lift_directory_digest, task_get_context, NodeResult};
+use crate::python::{throw, Value};
+use crate::Failure;
+
+pub fn register(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(parse_structured_file, m)?)?;
+
+    Ok(())
+}
+
+#[pyfunction]
+fn parse_structured_file(parse_structured_file: Value) -> PyGeneratorResponseNativeCall {
+    PyGeneratorResponseNativeCall::new(async move {
+        let context = task_get_context();
+        let store = context.core.store();
+
+        let (digest, path, format) = Python::with_gil(|py| {
+            let py_request = parse_structured_file.as_ref().as_ref(py);
+            let digest = lift_directory_digest(externs::getattr(py_request, "digest")?)?;
+            let path: String = externs::getattr(py_request, "path")?;
+            let format: Option<&PyAny> = externs::getattr(py_request, "format")?;
+            let format: Option<String> = format
+                .map(|format| externs::getattr(format, "value"))
+                .transpose()?;
+            let res: NodeResult<_> = Ok((digest, path, format));
+            res
+        })?;
+        let format = match format {
+            Some(format) => StructuredFormat::from_name(&format)?,
+            None => StructuredFormat::for_path(Path::new(&path))?,
+        };
+
+        let trie = store.load_digest_trie(digest).await?;
+        let file_digest = match trie.entry(Path::new(&path))? {
+            Some(Entry::File(f)) => f.digest(),
+            Some(_) => return Err(throw(format!("{path} is not a file."))),
+            None => return Err(throw(format!("{path} does not exist."))),
+        };
+        let content = store
+            .load_file_bytes_with(file_digest, Bytes::copy_from_slice)
+            .await?;
+
+        let value = context
+            .core
+            .executor
+            .spawn_blocking(
+                {
+                    let path = PathBuf::from(&path);
+                    move || format.parse(&path, &content)
+                },
+                |e| Err(format!("Parsing task failed: {e}")),
+            )
+            .await
+            .map_err(throw)?;
+
+        Ok::<_, Failure>(Python::with_gil(|py| {
+            let structured_data = Py::new(
+                py,
+                PyStructuredData {
+                    path,
+                    format: format.name().to_owned(),
+                    content_digest: file_digest,
+                    value: Arc::new(value),
+                },
+            )
+            .map_err(|e| format!("{e}"))?;
+            Ok::<_, String>(Value::new(structured_data.into_py(py)))
+        })?)
+    })
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum StructuredFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl StructuredFormat {
+    fn from_name(name: &str) -> Result<StructuredFormat, String> {
+        match name {
+            "json" => Ok(StructuredFormat::Json),
+            "toml" => Ok(StructuredFormat::Toml),
+            "yaml" => Ok(StructuredFormat::Yaml),
+            unknown => Err(format!("Unknown structured file format: {unknown}")),
+        }
+    }
+
+    fn for_path(path: &Path) -> Result<StructuredFormat, String> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Ok(StructuredFormat::Json),
+            Some("toml") => Ok(StructuredFormat::Toml),
+            Some("yaml" | "yml") => Ok(StructuredFormat::Yaml),
+            _ => Err(format!(
+                "Could not infer the format of {} from its extension: please set `format`.",
+                path.display()
+            )),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            StructuredFormat::Json => "json",
+            StructuredFormat::Toml => "toml",
+            StructuredFormat::Yaml => "yaml",
+        }
+    }
+
+    ///
+    /// Parse the given content into a JSON-compatible value. TOML datetimes are represented as
+    /// strings.
+    ///
+    fn parse(&self, path: &Path, content: &[u8]) -> Result<serde_json::Value, String> {
+        let parse_error = |e: &dyn std::fmt::Display| {
+            format!("Failed to parse {} as {}: {e}", path.display(), self.name())
+        };
+        match self {
+            StructuredFormat::Json => serde_json::from_slice(content).map_err(|e| parse_error(&e)),
+            StructuredFormat::Toml => toml::from_slice::<toml::Value>(content)
+                .map(toml_to_json)
+                .map_err(|e| parse_error(&e)),
+            StructuredFormat::Yaml => serde_yaml::from_slice(content).map_err(|e| parse_error(&e)),
+        }
+    }
+}
+
+fn toml_to_json(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(s) => serde_json::Value::String(s),
+        toml::Value::Integer(i) => serde_json::Value::from(i),
+        toml::Value::Float(f) => serde_json::Value::from(f),
+        toml::Value::Boolean(b) => serde_json::Value::Bool(b),
+        toml::Value::Datetime(d) => serde_json::Value::String(d.to_string()),
+        toml::Value::Array(array) => {
+            serde_json::Value::Array(array.into_iter().map(toml_to_json).collect())
+        }
+        toml::Value::Table(table) => serde_json::Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use serde_json::json;
+
+    use super::StructuredFormat;
+
+    fn parse(format: StructuredFormat, content: &str) -> Result<serde_json::Value, String> {
+        format.parse(Path::new("lockfile"), content.as_bytes())
+    }
+
+    #[test]
+    fn for_path() {
+        assert_eq!(
+            StructuredFormat::Yaml,
+            StructuredFormat::for_path(Path::new("pnpm-lock.yaml")).unwrap()
+        );
+        assert_eq!(
+            StructuredFormat::Json,
+            StructuredFormat::for_path(Path::new("a/package-lock.json")).unwrap()
+        );
+        assert!(StructuredFormat::for_path(Path::new("Cargo.lock")).is_err());
+    }
+
+    #[test]
+    fn parse_formats() {
+        let expected = json!({"package": [{"name": "a", "version": "1.0", "deps": 2}]});
+        assert_eq!(
+            expected,
+            parse(
+                StructuredFormat::Json,
+                r#"{"package": [{"name": "a", "version": "1.0", "deps": 2}]}"#
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            expected,
+            parse(
+                StructuredFormat::Toml,
+                "[[package]]\nname = \"a\"\nversion = \"1.0\"\ndeps = 2\n"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            expected,
+            parse(
+                StructuredFormat::Yaml,
+                "package:\n  - name: a\n    version: '1.0'\n    deps: 2\n"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_toml_datetime() {
+        assert_eq!(
+            json!({"generated": "1979-05-27T07:32:00Z"}),
+            parse(StructuredFormat::Toml, "generated = 1979-05-27T07:32:00Z\n").unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_error() {
+        let err = parse(StructuredFormat::Json, "{").unwrap_err();
+        assert!(
+            err.starts_with("Failed to parse lockfile as json:"),
+            "{err}"
+        );
+    }
+}
@@ -14,6 +14,7 @@ mod externs;
 mod interning;
 mod intrinsics;
 mod nodes;
+mod oci_registry;
 mod python;
 mod scheduler;
 mod session;
@@ -28,3 +29,16 @@ pub use crate::scheduler::{ExecutionRequest, ExecutionTermination, Scheduler};
 pub use crate::session::Session;
 pub use crate::tasks::{Rule, Tasks};
 pub use crate::types::Types;
+
+// NB: On Linux, jemalloc is the allocator so that `pantsd` can profile its heap: see
+// `externs::pantsd::pantsd_heap_profile_dump`.
+#[cfg(target_os = "linux")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+// Heap profiling is compiled in, but inactive until `pantsd_heap_profiling_activate` is called.
+// While active, an allocation is sampled about every 512KiB (2^19 bytes).
+#[cfg(target_os = "linux")]
+#[allow(non_upper_case_globals)]
+#[export_name = "_rjem_malloc_conf"]
+pub static malloc_conf: &[u8] = b"prof:true,prof_active:false,lg_prof_sample:19\0";
@@ -3,6 +3,7 @@
 
 use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use bytes::Bytes;
 use deepsize::DeepSizeOf;
@@ -10,13 +11,16 @@ use fs::RelativePath;
 use graph::CompoundNode;
 use grpc_util::prost::MessageExt;
 use hashing::Digest;
+use prost::Message;
+use protos::gen::build::bazel::remote::execution::v2 as remexec;
 use protos::gen::pants::cache::{CacheKey, CacheKeyType, ObservedUrl};
+use protos::require_digest;
 use pyo3::prelude::Python;
 use url::Url;
 
 use super::{NodeKey, NodeResult};
 use crate::context::{Context, Core};
-use crate::downloads;
+use crate::downloads::{self, ChecksumAlgorithm, DownloadAuth, RetryPolicy};
 use crate::externs;
 use crate::externs::fs::PyFileDigest;
 use crate::python::{throw, Key};
@@ -33,16 +37,23 @@ impl DownloadedFile {
         CacheKey {
             key_type: CacheKeyType::Url.into(),
             digest: Some(Digest::of_bytes(&observed_url.to_bytes()).into()),
+            ..CacheKey::default()
         }
     }
 
     pub async fn load_or_download(
         &self,
         core: Arc<Core>,
-        url: Url,
-        auth_headers: BTreeMap<String, String>,
+        urls: Vec<Url>,
+        auth: DownloadAuth,
         digest: hashing::Digest,
+        checksums: BTreeMap<ChecksumAlgorithm, String>,
+        retry_policy: RetryPolicy,
     ) -> Result<store::Snapshot, String> {
+        // NB: Mirrors are expected to provide the same file name as the primary URL.
+        let url = urls
+            .first()
+            .ok_or_else(|| "At least one URL must be provided to download a file.".to_owned())?;
         let file_name = url
             .path_segments()
             .and_then(Iterator::last)
@@ -58,47 +69,124 @@ impl DownloadedFile {
         // See if we have observed this URL and Digest before: if so, see whether we already have the
         // Digest fetched. The extra layer of indirection through the PersistentCache is to sanity
         // check that a Digest has ever been observed at the given URL.
-        // NB: The auth_headers are not part of the key.
-        let url_key = Self::url_key(&url, digest);
-        let have_observed_url = core.local_cache.load(&url_key).await?.is_some();
+        // NB: The auth is not part of the key.
+        //
+        // The expected digest is always SHA-256, so the entry records the digest that the file was
+        // stored under: entries without one were stored under the expected digest.
+        let url_key = Self::url_key(url, digest);
+        let observed_digest = match core.local_cache.load(&url_key).await? {
+            Some(stored) if stored.is_empty() => Some(digest),
+            Some(stored) => Some(require_digest(
+                &remexec::Digest::decode(stored).map_err(|e| e.to_string())?,
+            )?),
+            None => None,
+        };
 
         // If we hit the ObservedUrls cache, then we have successfully fetched this Digest from
         // this URL before. If we still have the bytes, then we skip fetching the content again.
-        let usable_in_store = have_observed_url
-            && (core
+        let usable_in_store = match observed_digest {
+            Some(stored_digest) => core
                 .store()
-                .load_file_bytes_with(digest, |_| ())
+                .load_file_bytes_with(stored_digest, |_| ())
                 .await
-                .is_ok());
+                .is_ok(),
+            None => false,
+        };
 
-        if !usable_in_store {
-            downloads::download(core.clone(), url, auth_headers, file_name, digest).await?;
-            // The value was successfully fetched and matched the digest: record in the ObservedUrls
-            // cache.
-            core.local_cache.store(&url_key, Bytes::from("")).await?;
-        }
-        core.store().snapshot_of_one_file(path, digest, true).await
+        let stored_digest = match observed_digest {
+            Some(stored_digest) if usable_in_store => {
+                if !checksums.is_empty() {
+                    // The Digest was verified when it was stored, but the additional checksums may
+                    // not have been.
+                    core.store()
+                        .load_file_bytes_with(stored_digest, move |bytes| {
+                            downloads::verify_checksums(&checksums, bytes)
+                        })
+                        .await
+                        .map_err(|e| e.to_string())??;
+                }
+                stored_digest
+            }
+            _ => {
+                let stored_digest = downloads::download(
+                    core.clone(),
+                    urls.clone(),
+                    auth,
+                    file_name,
+                    digest,
+                    checksums,
+                    retry_policy,
+                )
+                .await?;
+                // The value was successfully fetched and matched the digest: record in the
+                // ObservedUrls cache.
+                let stored = if stored_digest == digest {
+                    Bytes::new()
+                } else {
+                    remexec::Digest::from(stored_digest).to_bytes()
+                };
+                core.local_cache.store(&url_key, stored).await?;
+                stored_digest
+            }
+        };
+        core.store()
+            .snapshot_of_one_file(path, stored_digest, true)
+            .await
     }
 
     pub(super) async fn run_node(self, context: Context) -> NodeResult<store::Snapshot> {
-        let (url_str, expected_digest, auth_headers) = Python::with_gil(|py| {
+        let (url_strs, expected_digest, auth, checksums, retry_policy) = Python::with_gil(|py| {
             let py_download_file_val = self.0.to_value();
             let py_download_file = (*py_download_file_val).as_ref(py);
             let url_str: String = externs::getattr(py_download_file, "url")
                 .map_err(|e| format!("Failed to get `url` for field: {e}"))?;
-            let auth_headers =
-                externs::getattr_from_str_frozendict(py_download_file, "auth_headers");
+            let mirror_url_strs: Vec<String> = externs::getattr(py_download_file, "mirror_urls")?;
+            let token_command: Vec<String> =
+                externs::getattr(py_download_file, "auth_token_command")?;
+            let auth = DownloadAuth {
+                headers: externs::getattr_from_str_frozendict(py_download_file, "auth_headers"),
+                token_command: Some(token_command).filter(|command| !command.is_empty()),
+                use_netrc: externs::getattr(py_download_file, "use_netrc")?,
+            };
             let py_file_digest: PyFileDigest =
                 externs::getattr(py_download_file, "expected_digest")?;
-            let res: NodeResult<(String, Digest, BTreeMap<String, String>)> =
-                Ok((url_str, py_file_digest.0, auth_headers));
+            let checksums =
+                externs::getattr_from_str_frozendict::<String>(py_download_file, "checksums")
+                    .into_iter()
+                    .map(|(algorithm, checksum)| Ok((algorithm.parse()?, checksum)))
+                    .collect::<Result<BTreeMap<ChecksumAlgorithm, String>, String>>()?;
+            let retry_policy = RetryPolicy {
+                max_retries: externs::getattr(py_download_file, "retry_attempts")?,
+                initial_delay: Duration::from_millis(externs::getattr(
+                    py_download_file,
+                    "retry_delay_ms",
+                )?),
+                backoff_factor: externs::getattr(py_download_file, "retry_backoff_factor")?,
+            };
+            let url_strs = std::iter::once(url_str)
+                .chain(mirror_url_strs)
+                .collect::<Vec<_>>();
+            let res: NodeResult<_> =
+                Ok((url_strs, py_file_digest.0, auth, checksums, retry_policy));
             res
         })?;
-        let url = Url::parse(&url_str)
-            .map_err(|err| throw(format!("Error parsing URL {url_str}: {err}")))?;
-        self.load_or_download(context.core.clone(), url, auth_headers, expected_digest)
-            .await
-            .map_err(throw)
+        let urls = url_strs
+            .iter()
+            .map(|url_str| {
+                Url::parse(url_str)
+                    .map_err(|err| throw(format!("Error parsing URL {url_str}: {err}")))
+            })
+            .collect::<NodeResult<Vec<_>>>()?;
+        self.load_or_download(
+            context.core.clone(),
+            urls,
+            auth,
+            expected_digest,
+            checksums,
+            retry_policy,
+        )
+        .await
+        .map_err(throw)
     }
 }
 
@@ -3,15 +3,19 @@
 
 use std::collections::{BTreeMap, BTreeSet};
 use std::path::PathBuf;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use bytes::Bytes;
 use deepsize::DeepSizeOf;
-use fs::RelativePath;
+use fs::{Entry, FilespecMatcher, RelativePath, SymlinkBehavior};
 use graph::CompoundNode;
+use hashing::Digest;
+use process_execution::explain::CacheKeyComponents;
 use process_execution::{
-    self, CacheName, InputDigests, Process, ProcessCacheScope, ProcessExecutionStrategy,
-    ProcessResultSource,
+    self, CacheName, FallibleProcessResultWithPlatform, InputDigests, LocalCacheSettings, Process,
+    ProcessCacheScope, ProcessExecutionStrategy, ProcessResultSource,
 };
+use protos::gen::pants::cache::{CacheKey, CacheKeyType};
 use pyo3::prelude::{PyAny, Python};
 use store::{self, Store, StoreError};
 use workunit_store::{
@@ -71,6 +75,11 @@ impl ExecuteProcess {
     ) -> Result<Process, StoreError> {
         let env = externs::getattr_from_str_frozendict(value, "env");
 
+        let execution_only_env_vars =
+            externs::getattr::<Vec<String>>(value, "execution_only_env_vars")?
+                .into_iter()
+                .collect();
+
         let working_directory = externs::getattr_as_optional_string(value, "working_directory")
             .map_err(|e| format!("Failed to get `working_directory` from field: {e}"))?
             .map(RelativePath::new)
@@ -96,6 +105,10 @@ impl ExecuteProcess {
 
         let description: String = externs::getattr(value, "description")?;
 
+        let tags = externs::getattr::<Vec<String>>(value, "tags")?
+            .into_iter()
+            .collect();
+
         let py_level = externs::getattr(value, "level")?;
 
         let level = externs::val_to_log_level(py_level)?;
@@ -126,17 +139,29 @@ impl ExecuteProcess {
                 .map_err(|e| format!("Failed to get `name` for field: {e}"))? as u64,
         );
 
+        let local_cache = LocalCacheSettings {
+            namespace: externs::getattr::<Option<String>>(value, "local_cache_namespace")?
+                .unwrap_or_default(),
+            namespace_version: externs::getattr(value, "local_cache_namespace_version")?,
+            ttl: externs::getattr::<Option<u64>>(value, "local_cache_ttl_seconds")?
+                .map(Duration::from_secs),
+        };
+
+        let remote_persistent_worker: bool = externs::getattr(value, "remote_persistent_worker")?;
+
         let attempt = externs::getattr(value, "attempt").unwrap_or(0);
 
         Ok(Process {
             argv: externs::getattr(value, "argv").unwrap(),
             env,
+            execution_only_env_vars,
             working_directory,
             input_digests,
             output_files,
             output_directories,
             timeout,
             description,
+            tags,
             level,
             append_only_caches,
             jdk_home,
@@ -145,6 +170,8 @@ impl ExecuteProcess {
             cache_scope,
             execution_environment: process_config.environment,
             remote_cache_speculation_delay,
+            local_cache,
+            remote_persistent_worker,
             attempt,
         })
     }
@@ -187,6 +214,7 @@ impl ExecuteProcess {
             context.session.build_id().to_string(),
             context.session.run_id(),
             context.session.tail_tasks(),
+            context.session.niceness(),
         );
 
         let res = command_runner
@@ -267,6 +295,18 @@ impl ExecuteProcess {
             }
         }
 
+        if context.core.explain_cache_misses {
+            Self::explain_cache_miss(&context, &request, &res).await?;
+        }
+
+        if context.core.process_audit_dir.is_some() {
+            Self::audit(&context, &request, &res)?;
+        }
+
+        if let Some(provenance_outputs) = &context.core.provenance_outputs {
+            Self::record_provenance(&context, provenance_outputs, &request, &res).await?;
+        }
+
         if backtrack_level > 0 {
             // TODO: This message is symmetrical to the "Making attempt {} to backtrack and retry {}"
             // message in `context.rs`, but both of them are effectively debug output. They should be
@@ -285,6 +325,154 @@ impl ExecuteProcess {
             backtrack_level,
         })
     }
+
+    ///
+    /// If the given process (with a persistent cache scope) missed the cache, logs which components
+    /// of its cache key changed since those of the last process with the same description and
+    /// platform. The components are then persisted for comparison by later processes.
+    ///
+    async fn explain_cache_miss(
+        context: &Context,
+        process: &Process,
+        res: &FallibleProcessResultWithPlatform,
+    ) -> NodeResult<()> {
+        if !matches!(
+            process.cache_scope,
+            ProcessCacheScope::Always | ProcessCacheScope::Successful
+        ) {
+            return Ok(());
+        }
+        let core = &context.core;
+        let components =
+            CacheKeyComponents::new(process, core.process_cache_namespace.clone(), &core.store())
+                .await?;
+        // NB: Processes are identified across runs by their descriptions, which are not unique, but
+        // in practice usually identify a process which made a particular request.
+        let identity = format!("{}\n{}", process.description, components.platform);
+        let key = CacheKey {
+            key_type: CacheKeyType::ProcessCacheKeyComponents.into(),
+            digest: Some(Digest::of_bytes(identity.as_bytes()).into()),
+            ..CacheKey::default()
+        };
+
+        if matches!(
+            res.metadata.source,
+            ProcessResultSource::Ran | ProcessResultSource::DryRun
+        ) {
+            let previous = core
+                .local_cache
+                .load(&key)
+                .await?
+                .and_then(|bytes| serde_json::from_slice::<CacheKeyComponents>(&bytes).ok());
+            if let Some(previous) = previous {
+                let changes = components.changes_since(&previous);
+                if !changes.is_empty() {
+                    log::info!(
+                        "`{}` missed the cache, because since it previously ran:\n  {}",
+                        process.description,
+                        changes.join("\n  ")
+                    );
+                }
+            }
+        }
+
+        let components = serde_json::to_vec(&components)
+            .map_err(|e| throw(format!("Failed to serialize cache key components: {e}")))?;
+        core.local_cache
+            .store(&key, Bytes::from(components))
+            .await?;
+        Ok(())
+    }
+
+    ///
+    /// Appends the given process and its result to the audit log of the Session. The values of
+    /// environment variables which are not in `Core::process_audit_env_allowlist` are redacted.
+    ///
+    fn audit(
+        context: &Context,
+        process: &Process,
+        res: &FallibleProcessResultWithPlatform,
+    ) -> NodeResult<()> {
+        let allowlist = &context.core.process_audit_env_allowlist;
+        let (env, redacted_env): (BTreeMap<_, _>, BTreeMap<_, _>) = process
+            .env
+            .iter()
+            .partition(|(name, _)| allowlist.contains(*name));
+        let input_digests = serde_json::to_value(&process.input_digests)
+            .map_err(|e| throw(format!("Failed to serialize process inputs: {e}")))?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        let entry = serde_json::json!({
+            "timestamp": timestamp,
+            "description": process.description,
+            "argv": process.argv,
+            "env": env,
+            "redacted_env": redacted_env.into_keys().collect::<Vec<_>>(),
+            "working_directory": process.working_directory,
+            "input_digests": input_digests,
+            "output_digest": res.output_directory.as_digest(),
+            "stdout_digest": res.stdout_digest,
+            "stderr_digest": res.stderr_digest,
+            "exit_code": res.exit_code,
+            "environment": res.metadata.environment,
+            "source": format!("{:?}", res.metadata.source),
+        });
+        context.session.append_to_audit_log(&entry).map_err(throw)
+    }
+
+    ///
+    /// Records the provenance of those output files of the given (successful) process which match
+    /// `provenance_outputs`, for the Session to report at the end of the run.
+    ///
+    async fn record_provenance(
+        context: &Context,
+        provenance_outputs: &FilespecMatcher,
+        process: &Process,
+        res: &FallibleProcessResultWithPlatform,
+    ) -> NodeResult<()> {
+        if res.exit_code != 0 {
+            return Ok(());
+        }
+        let store = context.core.store();
+        let tree = store.load_digest_trie(res.output_directory.clone()).await?;
+        let mut outputs = Vec::new();
+        tree.walk(SymlinkBehavior::Aware, &mut |path, entry| {
+            if let Entry::File(file) = entry {
+                if provenance_outputs.matches(path) {
+                    outputs.push(serde_json::json!({
+                        "path": path.to_string_lossy(),
+                        "digest": file.digest(),
+                        "is_executable": file.is_executable(),
+                    }));
+                }
+            }
+        });
+        if outputs.is_empty() {
+            return Ok(());
+        }
+
+        let action_digest = process_execution::get_digest(process, None, None, &store, None).await;
+        // The values of execution-only env vars (e.g. credentials) are not part of the definition
+        // of the outputs, just as they are not part of the cache key.
+        let mut process = process.clone();
+        let execution_only_env_vars = std::mem::take(&mut process.execution_only_env_vars);
+        process
+            .env
+            .retain(|name, _| !execution_only_env_vars.contains(name));
+        let definition = serde_json::to_value(&process)
+            .map_err(|e| throw(format!("Failed to serialize process: {e}")))?;
+        context.session.record_provenance(serde_json::json!({
+            "description": process.description,
+            "action_digest": action_digest,
+            "definition": definition,
+            "source": format!("{:?}", res.metadata.source),
+            "environment": res.metadata.environment,
+            "outputs": outputs,
+        }));
+        Ok(())
+    }
 }
 
 impl From<ExecuteProcess> for NodeKey {
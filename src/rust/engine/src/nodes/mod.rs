@@ -29,6 +29,7 @@ use crate::tasks::Rule;
 mod digest_file;
 mod downloaded_file;
 mod execute_process;
+mod path_metadata;
 mod read_link;
 mod root;
 mod run_id;
@@ -41,6 +42,7 @@ mod task;
 pub use self::digest_file::DigestFile;
 pub use self::downloaded_file::DownloadedFile;
 pub use self::execute_process::{ExecuteProcess, ProcessResult};
+pub use self::path_metadata::PathMetadata;
 pub use self::read_link::{LinkDest, ReadLink};
 pub use self::root::Root;
 pub use self::run_id::RunId;
@@ -70,6 +72,10 @@ pub fn task_get_context() -> Context {
     TASK_CONTEXT.with(|c| (**c).clone())
 }
 
+pub fn task_try_get_context() -> Option<Context> {
+    TASK_CONTEXT.try_with(|c| (**c).clone()).ok()
+}
+
 pub async fn task_context<T, F: future::Future<Output = T>>(
     context: Context,
     is_side_effecting: bool,
@@ -224,6 +230,7 @@ pub enum NodeKey {
     DigestFile(DigestFile),
     DownloadedFile(DownloadedFile),
     ExecuteProcess(Box<ExecuteProcess>),
+    PathMetadata(PathMetadata),
     ReadLink(ReadLink),
     Scandir(Scandir),
     Root(Box<Root>),
@@ -237,6 +244,7 @@ impl NodeKey {
     pub fn fs_subject(&self) -> Option<&Path> {
         match self {
             NodeKey::DigestFile(s) => Some(s.0.path.as_path()),
+            NodeKey::PathMetadata(s) => Some(s.0.as_path()),
             NodeKey::ReadLink(s) => Some((s.0).path.as_path()),
             NodeKey::Scandir(s) => Some((s.0).0.as_path()),
 
@@ -279,6 +287,7 @@ impl NodeKey {
             NodeKey::Snapshot(..) => "snapshot",
             NodeKey::DigestFile(..) => "digest_file",
             NodeKey::DownloadedFile(..) => "downloaded_file",
+            NodeKey::PathMetadata(..) => "path_metadata",
             NodeKey::ReadLink(..) => "read_link",
             NodeKey::Scandir(..) => "scandir",
             NodeKey::Root(..) => "root",
@@ -325,6 +334,9 @@ impl NodeKey {
             NodeKey::DigestFile(DigestFile(File { path, .. })) => {
                 Some(format!("Fingerprinting: {}", path.display()))
             }
+            NodeKey::PathMetadata(PathMetadata(path)) => {
+                Some(format!("Reading metadata: {}", path.display()))
+            }
             NodeKey::ReadLink(ReadLink(Link { path, .. })) => {
                 Some(format!("Reading link: {}", path.display()))
             }
@@ -339,6 +351,10 @@ impl NodeKey {
     }
 
     async fn maybe_watch(&self, context: &Context) -> NodeResult<()> {
+        if let NodeKey::PathMetadata(..) = self {
+            // The path might not exist, so the Node installs its own watch.
+            return Ok(());
+        }
         if let Some((path, watcher)) = self.fs_subject().zip(context.core.watcher.as_ref()) {
             let abs_path = context.core.build_root.join(path);
             watcher
@@ -415,6 +431,9 @@ impl Node for NodeKey {
                             .await
                             .map(|r| NodeOutput::ProcessResult(Box::new(r)))
                     }
+                    NodeKey::PathMetadata(n) => {
+                        n.run_node(context).await.map(NodeOutput::PathMetadata)
+                    }
                     NodeKey::ReadLink(n) => n.run_node(context).await.map(NodeOutput::LinkDest),
                     NodeKey::Scandir(n) => {
                         n.run_node(context).await.map(NodeOutput::DirectoryListing)
@@ -519,6 +538,7 @@ impl Display for NodeKey {
             NodeKey::ExecuteProcess(s) => {
                 write!(f, "Process({})", s.process.description)
             }
+            NodeKey::PathMetadata(s) => write!(f, "PathMetadata({})", s.0.display()),
             NodeKey::ReadLink(s) => write!(f, "ReadLink({})", (s.0).path.display()),
             NodeKey::Scandir(s) => write!(f, "Scandir({})", (s.0).0.display()),
             NodeKey::Root(s) => write!(f, "{}", s.product),
@@ -565,6 +585,7 @@ pub enum NodeOutput {
     Snapshot(store::Snapshot),
     DirectoryListing(Arc<DirectoryListing>),
     LinkDest(LinkDest),
+    PathMetadata(Option<fs::PathMetadata>),
     ProcessResult(Box<ProcessResult>),
     Value(Value),
 }
@@ -586,9 +607,10 @@ impl NodeOutput {
                 digests.push(p.result.stderr_digest);
                 digests
             }
-            NodeOutput::DirectoryListing(_) | NodeOutput::LinkDest(_) | NodeOutput::Value(_) => {
-                vec![]
-            }
+            NodeOutput::DirectoryListing(_)
+            | NodeOutput::LinkDest(_)
+            | NodeOutput::PathMetadata(_)
+            | NodeOutput::Value(_) => vec![],
         }
     }
 }
@@ -0,0 +1,94 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::path::PathBuf;
+
+use deepsize::DeepSizeOf;
+use graph::CompoundNode;
+
+use super::{NodeKey, NodeOutput, NodeResult};
+use crate::context::Context;
+use crate::python::throw;
+
+///
+/// A Node that represents reading the metadata of a path (relative to the build root), which might
+/// not exist.
+///
+/// Because the path might not exist, this Node installs its own filesystem watch: on the path
+/// itself if it exists, or otherwise on its parent directory. If the parent directory does not exist
+/// either, this Node depends on the PathMetadata of the parent directory, so that it will be
+/// invalidated if that directory is created.
+///
+#[derive(Clone, Debug, DeepSizeOf, Eq, Hash, PartialEq)]
+pub struct PathMetadata(pub(super) PathBuf);
+
+impl PathMetadata {
+    pub fn new(path: PathBuf) -> PathMetadata {
+        PathMetadata(path)
+    }
+
+    pub(super) async fn run_node(self, context: Context) -> NodeResult<Option<fs::PathMetadata>> {
+        let node = self;
+        let metadata = context
+            .core
+            .vfs
+            .path_metadata(node.0.clone())
+            .await
+            .map_err(|e| {
+                throw(format!(
+                    "Failed to read metadata for {}: {e}",
+                    node.0.display()
+                ))
+            })?;
+
+        let watch_path = if metadata.is_some() {
+            Some(node.0.clone())
+        } else {
+            match node.0.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => {
+                    let parent_metadata = context.get(PathMetadata(parent.to_owned())).await?;
+                    match parent_metadata {
+                        Some(parent_metadata)
+                            if parent_metadata.kind == fs::PathMetadataKind::Directory =>
+                        {
+                            Some(parent.to_owned())
+                        }
+                        // The parent Node is itself watching for the creation of the parent directory.
+                        _ => None,
+                    }
+                }
+                // The parent is the build root.
+                _ => Some(PathBuf::new()),
+            }
+        };
+
+        if let Some((watch_path, watcher)) = watch_path.zip(context.core.watcher.as_ref()) {
+            watcher
+                .watch(context.core.build_root.join(watch_path))
+                .await
+                .map_err(throw)?;
+        }
+        Ok(metadata)
+    }
+}
+
+impl CompoundNode<NodeKey> for PathMetadata {
+    type Item = Option<fs::PathMetadata>;
+}
+
+impl From<PathMetadata> for NodeKey {
+    fn from(n: PathMetadata) -> Self {
+        NodeKey::PathMetadata(n)
+    }
+}
+
+impl TryFrom<NodeOutput> for Option<fs::PathMetadata> {
+    type Error = ();
+
+    fn try_from(nr: NodeOutput) -> Result<Self, ()> {
+        match nr {
+            NodeOutput::PathMetadata(v) => Ok(v),
+            _ => Err(()),
+        }
+    }
+}
@@ -10,7 +10,8 @@ use fs::{
 };
 use futures::TryFutureExt;
 use graph::CompoundNode;
-use pyo3::prelude::{Py, PyAny, Python};
+use pyo3::prelude::{Py, PyAny, PyObject, Python};
+use pyo3::types::{PyBytes, PyMemoryView};
 use pyo3::IntoPy;
 
 use super::{unmatched_globs_additional_context, NodeKey, NodeOutput, NodeResult};
@@ -82,26 +83,46 @@ impl Snapshot {
     }
 
     pub fn store_path(py: Python, item: &Path) -> Result<Value, String> {
-        if let Some(p) = item.as_os_str().to_str() {
-            Ok(externs::store_utf8(py, p))
-        } else {
-            Err(format!("Could not decode path `{item:?}` as UTF8."))
-        }
+        Ok(externs::store_utf8(py, Self::path_str(item)?))
+    }
+
+    fn path_str(item: &Path) -> Result<&str, String> {
+        item.as_os_str()
+            .to_str()
+            .ok_or_else(|| format!("Could not decode path `{item:?}` as UTF8."))
     }
 
+    // NB: The entries of a digest are converted in batches, so they are converted directly to
+    // `PyObject`s (rather than `Value`s) to avoid allocations per entry.
+
     fn store_file_content(
         py: Python,
         types: &crate::types::Types,
         item: &FileContent,
-    ) -> Result<Value, String> {
-        Ok(externs::unsafe_call(
+    ) -> Result<PyObject, String> {
+        Ok(externs::unsafe_call_with(
             py,
             types.file_content,
-            &[
-                Self::store_path(py, &item.path)?,
-                externs::store_bytes(py, &item.content),
-                externs::store_bool(py, item.is_executable),
-            ],
+            (
+                Self::path_str(&item.path)?,
+                PyBytes::new(py, &item.content),
+                item.is_executable,
+            ),
+        ))
+    }
+
+    fn store_file_content_view(
+        py: Python,
+        types: &crate::types::Types,
+        item: FileContent,
+    ) -> Result<PyObject, String> {
+        let buffer =
+            Py::new(py, externs::fs::PyContentBuffer(item.content)).map_err(|e| format!("{e}"))?;
+        let view = PyMemoryView::from(buffer.as_ref(py)).map_err(|e| format!("{e}"))?;
+        Ok(externs::unsafe_call_with(
+            py,
+            types.file_content_view,
+            (Self::path_str(&item.path)?, view, item.is_executable),
         ))
     }
 
@@ -109,15 +130,17 @@ impl Snapshot {
         py: Python,
         types: &crate::types::Types,
         item: &FileEntry,
-    ) -> Result<Value, String> {
-        Ok(externs::unsafe_call(
+    ) -> Result<PyObject, String> {
+        let py_file_digest =
+            Py::new(py, externs::fs::PyFileDigest(item.digest)).map_err(|e| format!("{e}"))?;
+        Ok(externs::unsafe_call_with(
             py,
             types.file_entry,
-            &[
-                Self::store_path(py, &item.path)?,
-                Self::store_file_digest(py, item.digest)?,
-                externs::store_bool(py, item.is_executable),
-            ],
+            (
+                Self::path_str(&item.path)?,
+                py_file_digest,
+                item.is_executable,
+            ),
         ))
     }
 
@@ -125,14 +148,11 @@ impl Snapshot {
         py: Python,
         types: &crate::types::Types,
         item: &SymlinkEntry,
-    ) -> Result<Value, String> {
-        Ok(externs::unsafe_call(
+    ) -> Result<PyObject, String> {
+        Ok(externs::unsafe_call_with(
             py,
             types.symlink_entry,
-            &[
-                Self::store_path(py, &item.path)?,
-                externs::store_utf8(py, item.target.to_str().unwrap()),
-            ],
+            (Self::path_str(&item.path)?, item.target.to_str().unwrap()),
         ))
     }
 
@@ -140,11 +160,11 @@ impl Snapshot {
         py: Python,
         types: &crate::types::Types,
         path: &Path,
-    ) -> Result<Value, String> {
-        Ok(externs::unsafe_call(
+    ) -> Result<PyObject, String> {
+        Ok(externs::unsafe_call_with(
             py,
             types.directory,
-            &[Self::store_path(py, path)?],
+            (Self::path_str(path)?,),
         ))
     }
 
@@ -153,14 +173,42 @@ impl Snapshot {
         context: &Context,
         item: &[FileContent],
     ) -> Result<Value, String> {
-        let entries = item
-            .iter()
-            .map(|e| Self::store_file_content(py, &context.core.types, e))
-            .collect::<Result<Vec<_>, _>>()?;
+        let mut entries = Vec::with_capacity(item.len());
+        for file_content in item {
+            entries.push(Self::store_file_content(
+                py,
+                &context.core.types,
+                file_content,
+            )?);
+        }
         Ok(externs::unsafe_call(
             py,
             context.core.types.digest_contents,
-            &[externs::store_tuple(py, entries)],
+            &[externs::store_tuple_of_objects(py, entries.into_iter())],
+        ))
+    }
+
+    ///
+    /// Like `store_digest_contents`, but exposes the content of each file as a view of the given
+    /// (Rust-owned) memory, rather than copying it.
+    ///
+    pub fn store_digest_content_views(
+        py: Python,
+        context: &Context,
+        item: Vec<FileContent>,
+    ) -> Result<Value, String> {
+        let mut entries = Vec::with_capacity(item.len());
+        for file_content in item {
+            entries.push(Self::store_file_content_view(
+                py,
+                &context.core.types,
+                file_content,
+            )?);
+        }
+        Ok(externs::unsafe_call(
+            py,
+            context.core.types.digest_content_views,
+            &[externs::store_tuple_of_objects(py, entries.into_iter())],
         ))
     }
 
@@ -169,9 +217,9 @@ impl Snapshot {
         context: &Context,
         item: &[DigestEntry],
     ) -> Result<Value, String> {
-        let entries = item
-            .iter()
-            .map(|digest_entry| match digest_entry {
+        let mut entries = Vec::with_capacity(item.len());
+        for digest_entry in item {
+            entries.push(match digest_entry {
                 DigestEntry::File(file_entry) => {
                     Self::store_file_entry(py, &context.core.types, file_entry)
                 }
@@ -181,12 +229,12 @@ impl Snapshot {
                 DigestEntry::EmptyDirectory(path) => {
                     Self::store_empty_directory(py, &context.core.types, path)
                 }
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+            }?);
+        }
         Ok(externs::unsafe_call(
             py,
             context.core.types.digest_entries,
-            &[externs::store_tuple(py, entries)],
+            &[externs::store_tuple_of_objects(py, entries.into_iter())],
         ))
     }
 
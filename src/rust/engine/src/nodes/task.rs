@@ -244,7 +244,7 @@ impl Task {
         let deps = {
             // While waiting for dependencies, mark ourselves blocking.
             let _blocking_token = workunit.blocking();
-            let edges = &context
+            let edges = context
                 .core
                 .rule_graph
                 .edges_for_inner(&self.entry)
@@ -330,8 +330,13 @@ impl Task {
         }
 
         if self.task.engine_aware_return_type {
+            let artifacts = EngineAwareReturnType::persist_artifacts(&context, &result_val).await?;
             Python::with_gil(|py| {
-                EngineAwareReturnType::update_workunit(workunit, (*result_val).as_ref(py))
+                EngineAwareReturnType::update_workunit(
+                    workunit,
+                    (*result_val).as_ref(py),
+                    artifacts,
+                )
             })
         };
 
@@ -0,0 +1,471 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use base64::Engine;
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::header::{HeaderValue, ACCEPT, WWW_AUTHENTICATE};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use sha2::{Digest as Sha2Digest, Sha256};
+
+/// The registry used for references which do not include one.
+const DEFAULT_REGISTRY: &str = "docker.io";
+/// The host which actually serves the registry API for `docker.io`.
+const DOCKER_HUB_REGISTRY_HOST: &str = "registry-1.docker.io";
+/// The key for `docker.io` in a docker `config.json`.
+const DOCKER_HUB_CONFIG_KEY: &str = "https://index.docker.io/v1/";
+
+/// The manifest media types which might be served for a tag, in order of preference. Image indexes
+/// (and manifest lists) are preferred, so that the resolved digest covers all platforms.
+const MANIFEST_MEDIA_TYPES: &[&str] = &[
+    "application/vnd.oci.image.index.v1+json",
+    "application/vnd.docker.distribution.manifest.list.v2+json",
+    "application/vnd.oci.image.manifest.v1+json",
+    "application/vnd.docker.distribution.manifest.v2+json",
+];
+
+///
+/// A parsed OCI image reference, of the form `[registry/]repository[:tag][@digest]`.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ImageReference {
+    pub registry: String,
+    pub repository: String,
+    pub tag: String,
+    pub digest: Option<String>,
+}
+
+impl ImageReference {
+    pub fn parse(reference: &str) -> Result<ImageReference, String> {
+        let (name, digest) = match reference.split_once('@') {
+            Some((name, digest)) => (name, Some(digest.to_owned())),
+            None => (reference, None),
+        };
+        // The first component is a registry if it looks like a host.
+        let (registry, remainder) = match name.split_once('/') {
+            Some((first, remainder))
+                if first.contains('.') || first.contains(':') || first == "localhost" =>
+            {
+                (first.to_owned(), remainder)
+            }
+            _ => (DEFAULT_REGISTRY.to_owned(), name),
+        };
+        // A tag follows a colon in the final path component.
+        let (repository, tag) = match remainder.rsplit_once(':') {
+            Some((repository, tag)) if !tag.contains('/') => (repository, tag.to_owned()),
+            _ => (remainder, "latest".to_owned()),
+        };
+        if repository.is_empty()
+            || !repository.split('/').all(|component| {
+                !component.is_empty()
+                    && component
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "._-".contains(c))
+            })
+        {
+            return Err(format!("Invalid image reference: `{reference}`"));
+        }
+        let repository = if registry == DEFAULT_REGISTRY && !repository.contains('/') {
+            format!("library/{repository}")
+        } else {
+            repository.to_owned()
+        };
+        Ok(ImageReference {
+            registry,
+            repository,
+            tag,
+            digest,
+        })
+    }
+
+    ///
+    /// The base URL of the registry API. Registries on the loopback interface are assumed to be
+    /// served over plain HTTP.
+    ///
+    fn api_url(&self) -> String {
+        let host = if self.registry == DEFAULT_REGISTRY {
+            DOCKER_HUB_REGISTRY_HOST
+        } else {
+            &self.registry
+        };
+        let is_loopback = ["localhost", "127.0.0.1", "[::1]"]
+            .iter()
+            .any(|loopback| host == *loopback || host.starts_with(&format!("{loopback}:")));
+        let scheme = if is_loopback { "http" } else { "https" };
+        format!("{scheme}://{host}/v2")
+    }
+
+    ///
+    /// The reference, pinned to the given manifest digest rather than its tag.
+    ///
+    pub fn pinned(&self, digest: &str) -> String {
+        format!("{}/{}@{digest}", self.registry, self.repository)
+    }
+}
+
+impl fmt::Display for ImageReference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}:{}", self.registry, self.repository, self.tag)
+    }
+}
+
+///
+/// Credentials for a registry, which are used as basic auth (or to acquire a bearer token).
+///
+#[derive(Clone, Debug, Default)]
+pub struct RegistryCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl RegistryCredentials {
+    ///
+    /// Load the credentials for the given registry from the `auths` of the docker `config.json`
+    /// located by `$DOCKER_CONFIG` (or otherwise in `~/.docker`), if any.
+    ///
+    /// NB: Credential helpers (`credsStore` and `credHelpers`) are not supported.
+    ///
+    pub fn from_docker_config(registry: &str) -> Result<Option<RegistryCredentials>, String> {
+        let Some(config_dir) = std::env::var_os("DOCKER_CONFIG")
+            .map(PathBuf::from)
+            .or_else(|| dirs_next::home_dir().map(|home| home.join(".docker")))
+        else {
+            return Ok(None);
+        };
+        let config_path = config_dir.join("config.json");
+        let content = match std::fs::read(&config_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("Failed to read {}: {e}", config_path.display())),
+        };
+        let config: serde_json::Value = serde_json::from_slice(&content)
+            .map_err(|e| format!("Failed to parse {}: {e}", config_path.display()))?;
+
+        let key = if registry == DEFAULT_REGISTRY {
+            DOCKER_HUB_CONFIG_KEY
+        } else {
+            registry
+        };
+        let Some(auth) = config["auths"][key]["auth"].as_str() else {
+            return Ok(None);
+        };
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(auth)
+            .ok()
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+            .ok_or_else(|| {
+                format!(
+                    "Invalid `auth` for {registry} in {}.",
+                    config_path.display()
+                )
+            })?;
+        let (username, password) = decoded.split_once(':').ok_or_else(|| {
+            format!(
+                "Invalid `auth` for {registry} in {}.",
+                config_path.display()
+            )
+        })?;
+        Ok(Some(RegistryCredentials {
+            username: username.to_owned(),
+            password: password.to_owned(),
+        }))
+    }
+}
+
+///
+/// Resolve the given reference to the digest of its manifest (or image index) using the registry
+/// HTTP API, authenticating if the registry challenges the request.
+///
+pub async fn resolve_manifest_digest(
+    client: &reqwest::Client,
+    reference: &ImageReference,
+    credentials: Option<&RegistryCredentials>,
+) -> Result<String, String> {
+    if let Some(digest) = &reference.digest {
+        return Ok(digest.clone());
+    }
+
+    let url = format!(
+        "{}/{}/manifests/{}",
+        reference.api_url(),
+        reference.repository,
+        reference.tag
+    );
+    let manifest_request = |method: Method| {
+        client
+            .request(method, url.as_str())
+            .header(ACCEPT, MANIFEST_MEDIA_TYPES.join(", "))
+    };
+    let send = |request: RequestBuilder| async move {
+        request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to resolve {reference}: {e}"))
+    };
+
+    let mut authorization = None;
+    let mut response = send(manifest_request(Method::HEAD)).await?;
+    if response.status() == StatusCode::UNAUTHORIZED {
+        let challenge = response
+            .headers()
+            .get(WWW_AUTHENTICATE)
+            .and_then(|value| value.to_str().ok())
+            .map(AuthChallenge::parse)
+            .ok_or_else(|| {
+                format!("Failed to resolve {reference}: unauthorized, without a challenge.")
+            })?;
+        authorization = Some(
+            challenge
+                .authorization(client, credentials)
+                .await
+                .map_err(|e| format!("Failed to authenticate to resolve {reference}: {e}"))?,
+        );
+        response = send(with_authorization(
+            manifest_request(Method::HEAD),
+            authorization.as_ref(),
+        ))
+        .await?;
+    }
+    let response = check_status(reference, response)?;
+
+    if let Some(digest) = response
+        .headers()
+        .get("Docker-Content-Digest")
+        .and_then(|value| value.to_str().ok())
+    {
+        return Ok(digest.to_owned());
+    }
+
+    // Some registries omit the digest header for HEAD requests, in which case the manifest is
+    // fetched and digested.
+    let response = check_status(
+        reference,
+        send(with_authorization(
+            manifest_request(Method::GET),
+            authorization.as_ref(),
+        ))
+        .await?,
+    )?;
+    let manifest = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to fetch the manifest for {reference}: {e}"))?;
+    Ok(format!(
+        "sha256:{}",
+        hex::encode(Sha256::digest(&manifest[..]))
+    ))
+}
+
+fn with_authorization(
+    request: RequestBuilder,
+    authorization: Option<&HeaderValue>,
+) -> RequestBuilder {
+    match authorization {
+        Some(authorization) => {
+            request.header(reqwest::header::AUTHORIZATION, authorization.clone())
+        }
+        None => request,
+    }
+}
+
+fn check_status(reference: &ImageReference, response: Response) -> Result<Response, String> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else if status == StatusCode::NOT_FOUND {
+        Err(format!("Image {reference} does not exist."))
+    } else {
+        Err(format!("Failed to resolve {reference}: HTTP {status}"))
+    }
+}
+
+lazy_static! {
+    // A `key="value"` parameter of a `WWW-Authenticate` challenge.
+    static ref AUTH_CHALLENGE_PARAM_RE: Regex = Regex::new(r#"(\w+)="([^"]*)""#).unwrap();
+}
+
+///
+/// A parsed `WWW-Authenticate` challenge: see
+/// https://distribution.github.io/distribution/spec/auth/token/.
+///
+#[derive(Debug, Eq, PartialEq)]
+struct AuthChallenge {
+    scheme: String,
+    params: BTreeMap<String, String>,
+}
+
+impl AuthChallenge {
+    fn parse(header: &str) -> AuthChallenge {
+        let (scheme, params) = header.trim().split_once(' ').unwrap_or((header.trim(), ""));
+        let params = AUTH_CHALLENGE_PARAM_RE
+            .captures_iter(params)
+            .map(|captures| (captures[1].to_owned(), captures[2].to_owned()))
+            .collect();
+        AuthChallenge {
+            scheme: scheme.to_owned(),
+            params,
+        }
+    }
+
+    ///
+    /// Compute the `Authorization` header which satisfies this challenge.
+    ///
+    async fn authorization(
+        &self,
+        client: &reqwest::Client,
+        credentials: Option<&RegistryCredentials>,
+    ) -> Result<HeaderValue, String> {
+        let value = match self.scheme.to_ascii_lowercase().as_str() {
+            "basic" => {
+                let credentials = credentials.ok_or_else(|| {
+                    "The registry requires credentials, but none were found.".to_owned()
+                })?;
+                let encoded = base64::engine::general_purpose::STANDARD
+                    .encode(format!("{}:{}", credentials.username, credentials.password));
+                format!("Basic {encoded}")
+            }
+            "bearer" => format!("Bearer {}", self.fetch_token(client, credentials).await?),
+            _ => {
+                return Err(format!(
+                    "Unsupported authentication scheme `{}`.",
+                    self.scheme
+                ))
+            }
+        };
+        let mut value =
+            HeaderValue::from_str(&value).map_err(|e| format!("Invalid authorization: {e}"))?;
+        value.set_sensitive(true);
+        Ok(value)
+    }
+
+    async fn fetch_token(
+        &self,
+        client: &reqwest::Client,
+        credentials: Option<&RegistryCredentials>,
+    ) -> Result<String, String> {
+        let realm = self
+            .params
+            .get("realm")
+            .ok_or_else(|| "The bearer challenge did not include a `realm`.".to_owned())?;
+        let query = ["service", "scope"]
+            .into_iter()
+            .filter_map(|key| self.params.get(key).map(|value| (key, value)))
+            .collect::<Vec<_>>();
+        let mut request = client.get(realm.as_str()).query(&query);
+        if let Some(credentials) = credentials {
+            request = request.basic_auth(&credentials.username, Some(&credentials.password));
+        }
+        let response = request
+            .send()
+            .await
+            .and_then(Response::error_for_status)
+            .map_err(|e| format!("Failed to fetch a token from {realm}: {e}"))?;
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to fetch a token from {realm}: {e}"))?;
+        let body: serde_json::Value = serde_json::from_slice(&body)
+            .map_err(|e| format!("Failed to parse the token from {realm}: {e}"))?;
+        body["token"]
+            .as_str()
+            .or_else(|| body["access_token"].as_str())
+            .map(str::to_owned)
+            .ok_or_else(|| format!("The response from {realm} did not include a token."))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{AuthChallenge, ImageReference};
+
+    fn reference(registry: &str, repository: &str, tag: &str) -> ImageReference {
+        ImageReference {
+            registry: registry.to_owned(),
+            repository: repository.to_owned(),
+            tag: tag.to_owned(),
+            digest: None,
+        }
+    }
+
+    #[test]
+    fn parse_reference() {
+        assert_eq!(
+            reference("docker.io", "library/alpine", "latest"),
+            ImageReference::parse("alpine").unwrap()
+        );
+        assert_eq!(
+            reference("docker.io", "pantsbuild/pants", "2.0"),
+            ImageReference::parse("pantsbuild/pants:2.0").unwrap()
+        );
+        assert_eq!(
+            reference("localhost:5000", "team/app", "latest"),
+            ImageReference::parse("localhost:5000/team/app").unwrap()
+        );
+        assert_eq!(
+            reference("ghcr.io", "org/app", "v1.2.3"),
+            ImageReference::parse("ghcr.io/org/app:v1.2.3").unwrap()
+        );
+        assert_eq!(
+            Some("sha256:abc".to_owned()),
+            ImageReference::parse("alpine:3@sha256:abc").unwrap().digest
+        );
+        assert!(ImageReference::parse("Alpine").is_err());
+        assert!(ImageReference::parse("ghcr.io/").is_err());
+    }
+
+    #[test]
+    fn api_url() {
+        assert_eq!(
+            "https://registry-1.docker.io/v2",
+            ImageReference::parse("alpine").unwrap().api_url()
+        );
+        assert_eq!(
+            "http://localhost:5000/v2",
+            ImageReference::parse("localhost:5000/app")
+                .unwrap()
+                .api_url()
+        );
+        assert_eq!(
+            "docker.io/library/alpine@sha256:abc",
+            ImageReference::parse("alpine:3")
+                .unwrap()
+                .pinned("sha256:abc")
+        );
+    }
+
+    #[test]
+    fn parse_challenge() {
+        assert_eq!(
+            AuthChallenge {
+                scheme: "Bearer".to_owned(),
+                params: BTreeMap::from([
+                    (
+                        "realm".to_owned(),
+                        "https://auth.docker.io/token".to_owned()
+                    ),
+                    ("service".to_owned(), "registry.docker.io".to_owned()),
+                    (
+                        "scope".to_owned(),
+                        "repository:library/alpine:pull".to_owned()
+                    ),
+                ]),
+            },
+            AuthChallenge::parse(
+                r#"Bearer realm="https://auth.docker.io/token",service="registry.docker.io",scope="repository:library/alpine:pull""#
+            )
+        );
+        assert_eq!(
+            AuthChallenge {
+                scheme: "Basic".to_owned(),
+                params: BTreeMap::from([("realm".to_owned(), "Registry".to_owned())]),
+            },
+            AuthChallenge::parse(r#"Basic realm="Registry""#)
+        );
+    }
+}
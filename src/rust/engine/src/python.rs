@@ -184,6 +184,15 @@ impl rule_graph::TypeId for TypeId {
     {
         display_sorted_in_parens(type_ids)
     }
+
+    fn stable_name(&self) -> String {
+        Python::with_gil(|py| {
+            let py_type = self.as_py_type(py);
+            let module: String = externs::getattr(py_type, "__module__").unwrap();
+            let qualname: String = externs::getattr(py_type, "__qualname__").unwrap();
+            format!("{module}.{qualname}")
+        })
+    }
 }
 
 /// An identifier for a Python function.
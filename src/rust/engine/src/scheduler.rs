@@ -139,6 +139,21 @@ impl Scheduler {
             session.preceding_graph_size() as i64,
         );
         m.insert("resulting_graph_size", self.core.graph.len() as i64);
+        m.extend(
+            self.core
+                .executor
+                .metrics()
+                .into_iter()
+                .map(|(name, value)| (name, value as i64)),
+        );
+        if let Some(watcher) = &self.core.watcher {
+            m.extend(
+                watcher
+                    .metrics()
+                    .into_iter()
+                    .map(|(name, value)| (name, value as i64)),
+            );
+        }
         m
     }
 
@@ -172,6 +187,25 @@ impl Scheduler {
         (items, sizes)
     }
 
+    ///
+    /// Releases memory by dropping the values of graph Nodes which have not been requested within
+    /// the last `max_idle_runs` runs, along with in-memory state of the Store which can be
+    /// recomputed. Returns the number of entries released by each.
+    ///
+    pub fn shed_memory(&self, max_idle_runs: u32) -> HashMap<&'static str, usize> {
+        let graph_entries = self.core.graph.drop_cold(max_idle_runs);
+        let store_entries = self.core.store().release_memory();
+        // Freed memory is otherwise retained by the allocator, and so still counts toward the RSS.
+        #[cfg(all(target_os = "linux", target_env = "gnu"))]
+        unsafe {
+            libc::malloc_trim(0);
+        }
+        HashMap::from([
+            ("graph_entries", graph_entries),
+            ("store_entries", store_entries),
+        ])
+    }
+
     ///
     /// Return unit if the Scheduler is still valid, or an error string if something has invalidated
     /// the Scheduler, indicating that it should re-initialize. See InvalidationWatcher.
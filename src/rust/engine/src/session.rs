@@ -2,7 +2,9 @@
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
 use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
 use std::future::Future;
+use std::io::Write;
 use std::sync::atomic::{self, AtomicU32};
 use std::sync::{Arc, Weak};
 use std::time::{Duration, Instant};
@@ -20,7 +22,7 @@ use pyo3::prelude::*;
 use task_executor::{Executor, TailTasks};
 use tokio::signal::unix::{signal, SignalKind};
 use tokio::task::JoinHandle;
-use ui::ConsoleUI;
+use ui::{ConsoleUI, RunEstimate, UiRenderer};
 use workunit_store::{format_workunit_duration_ms, RunId, WorkunitStore};
 
 // When enabled, the interval at which all stragglers that have been running for longer than a
@@ -48,13 +50,17 @@ impl SessionDisplay {
         workunit_store: &WorkunitStore,
         parallelism: usize,
         dynamic_ui: bool,
-        ui_use_prodash: bool,
+        ui_renderer: UiRenderer,
+        run_estimate: &Arc<Mutex<Option<RunEstimate>>>,
+        cancelled: &AsyncLatch,
     ) -> SessionDisplay {
         if dynamic_ui {
             SessionDisplay::ConsoleUI(Box::new(ConsoleUI::new(
                 workunit_store.clone(),
                 parallelism,
-                ui_use_prodash,
+                ui_renderer,
+                run_estimate.clone(),
+                cancelled.clone(),
             )))
         } else {
             SessionDisplay::Logging {
@@ -89,6 +95,15 @@ struct SessionState {
     run_id: AtomicU32,
     /// Tasks to await at the "tail" of the session.
     tail_tasks: TailTasks,
+    // An estimate of when the run will complete, for display by the ConsoleUI (if any).
+    run_estimate: Arc<Mutex<Option<RunEstimate>>>,
+    // The provenance of the outputs of processes which completed during this Session, if
+    // `Core::provenance_outputs` is enabled.
+    provenance: Mutex<Vec<serde_json::Value>>,
+    // The audit log of this Session, which is opened when it is first appended to.
+    audit_log: Mutex<Option<File>>,
+    // How readily this Session yields contended process execution slots to other Sessions.
+    niceness: u8,
 }
 
 ///
@@ -143,11 +158,12 @@ impl Session {
     pub fn new(
         core: Arc<Core>,
         dynamic_ui: bool,
-        ui_use_prodash: bool,
+        ui_renderer: UiRenderer,
         mut max_workunit_level: log::Level,
         build_id: String,
         session_values: PyObject,
         cancelled: AsyncLatch,
+        niceness: u8,
     ) -> Result<Session, String> {
         // We record workunits with the maximum level of:
         // 1. the given `max_workunit_verbosity`, which should be computed from:
@@ -158,11 +174,14 @@ impl Session {
             max_workunit_level = std::cmp::max(max_workunit_level, log::Level::Debug);
         }
         let workunit_store = WorkunitStore::new(!dynamic_ui, max_workunit_level);
+        let run_estimate = Arc::new(Mutex::new(None));
         let display = tokio::sync::Mutex::new(SessionDisplay::new(
             &workunit_store,
             core.local_parallelism,
             dynamic_ui,
-            ui_use_prodash,
+            ui_renderer,
+            &run_estimate,
+            &cancelled,
         ));
 
         let handle = Arc::new(SessionHandle {
@@ -184,6 +203,10 @@ impl Session {
                 session_values: Mutex::new(session_values),
                 run_id: AtomicU32::new(run_id.0),
                 tail_tasks: TailTasks::new(),
+                run_estimate,
+                provenance: Mutex::new(Vec::new()),
+                audit_log: Mutex::new(None),
+                niceness,
             }),
         })
     }
@@ -205,16 +228,19 @@ impl Session {
     /// when a client disconnects, or killed by Ctrl+C.
     ///
     pub fn isolated_shallow_clone(&self, build_id: String) -> Result<Session, String> {
+        let cancelled = AsyncLatch::new();
         let display = tokio::sync::Mutex::new(SessionDisplay::new(
             &self.state.workunit_store,
             self.state.core.local_parallelism,
             false,
-            false,
+            UiRenderer::IndicatifSpinner,
+            &self.state.run_estimate,
+            &cancelled,
         ));
         let handle = Arc::new(SessionHandle {
             build_id,
             isolated: true,
-            cancelled: AsyncLatch::new(),
+            cancelled,
             display,
         });
         self.state.core.sessions.add(&handle)?;
@@ -286,6 +312,10 @@ impl Session {
         &self.handle.build_id
     }
 
+    pub fn niceness(&self) -> u8 {
+        self.state.niceness
+    }
+
     pub fn run_id(&self) -> RunId {
         RunId(self.state.run_id.load(atomic::Ordering::SeqCst))
     }
@@ -295,6 +325,8 @@ impl Session {
             self.state.core.graph.generate_run_id().0,
             atomic::Ordering::SeqCst,
         );
+        // An estimate is for a single run.
+        *self.state.run_estimate.lock() = None;
     }
 
     pub async fn with_console_ui_disabled<T>(&self, f: impl Future<Output = T>) -> T {
@@ -336,6 +368,58 @@ impl Session {
         teardown.await;
     }
 
+    ///
+    /// Sets the estimate of when the run will complete, which the ConsoleUI (if any) displays.
+    ///
+    pub fn set_run_estimate(&self, run_estimate: RunEstimate) {
+        *self.state.run_estimate.lock() = Some(run_estimate);
+    }
+
+    ///
+    /// Records the provenance of the outputs of a process which completed in this Session.
+    ///
+    pub fn record_provenance(&self, provenance: serde_json::Value) {
+        self.state.provenance.lock().push(provenance);
+    }
+
+    ///
+    /// The provenance which has been recorded by this Session, in the order it was recorded.
+    ///
+    pub fn provenance(&self) -> Vec<serde_json::Value> {
+        self.state.provenance.lock().clone()
+    }
+
+    ///
+    /// Appends the given entry as a line of the audit log of this Session, which is named for its
+    /// build id within `Core::process_audit_dir` (if set).
+    ///
+    pub fn append_to_audit_log(&self, entry: &serde_json::Value) -> Result<(), String> {
+        let Some(audit_dir) = &self.state.core.process_audit_dir else {
+            return Ok(());
+        };
+        let path = audit_dir.join(format!("{}.jsonl", self.handle.build_id));
+        let mut audit_log = self.state.audit_log.lock();
+        if audit_log.is_none() {
+            std::fs::create_dir_all(audit_dir).map_err(|e| {
+                format!(
+                    "Failed to create audit log dir {}: {e}",
+                    audit_dir.display()
+                )
+            })?;
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .map_err(|e| format!("Failed to open audit log {}: {e}", path.display()))?;
+            *audit_log = Some(file);
+        }
+        let file = audit_log.as_mut().unwrap();
+        // NB: Each entry is written with a single call, so that it is not interleaved with those of
+        // other Sessions which share the file.
+        file.write_all(format!("{entry}\n").as_bytes())
+            .map_err(|e| format!("Failed to append to audit log {}: {e}", path.display()))
+    }
+
     pub fn maybe_display_render(&self) {
         let mut display = if let Ok(display) = self.handle.display.try_lock() {
             display
@@ -450,6 +534,19 @@ impl Sessions {
         }
     }
 
+    ///
+    /// The build ids of the live Sessions.
+    ///
+    pub fn build_ids(&self) -> Vec<String> {
+        let sessions = self.sessions.lock();
+        sessions
+            .iter()
+            .flatten()
+            .filter_map(|weak_handle| weak_handle.upgrade())
+            .map(|handle| handle.build_id.clone())
+            .collect()
+    }
+
     ///
     /// Shuts down this Sessions instance by waiting for all existing Sessions to exit.
     ///
@@ -9,10 +9,12 @@ pub struct Types {
     pub snapshot: TypeId,
     pub paths: TypeId,
     pub file_content: TypeId,
+    pub file_content_view: TypeId,
     pub file_entry: TypeId,
     pub symlink_entry: TypeId,
     pub directory: TypeId,
     pub digest_contents: TypeId,
+    pub digest_content_views: TypeId,
     pub digest_entries: TypeId,
     pub path_globs: TypeId,
     pub merge_digests: TypeId,
@@ -21,6 +23,12 @@ pub struct Types {
     pub create_digest: TypeId,
     pub digest_subset: TypeId,
     pub native_download_file: TypeId,
+    pub path_metadata: TypeId,
+    pub path_metadata_result: TypeId,
+    pub git_metadata: TypeId,
+    pub digest_diff: TypeId,
+    pub digests: TypeId,
+    pub digest_fingerprint: TypeId,
     pub platform: TypeId,
     pub process: TypeId,
     pub process_config_from_environment: TypeId,
@@ -34,7 +42,15 @@ pub struct Types {
     pub engine_aware_parameter: TypeId,
     pub docker_resolve_image_request: TypeId,
     pub docker_resolve_image_result: TypeId,
+    pub resolve_image_digest_result: TypeId,
     pub parsed_python_deps_result: TypeId,
     pub parsed_javascript_deps_result: TypeId,
+    pub parsed_java_deps_result: TypeId,
+    pub parsed_scala_deps_result: TypeId,
+    pub parsed_kotlin_deps_result: TypeId,
+    pub parsed_go_deps_result: TypeId,
+    pub parsed_dotnet_deps_result: TypeId,
+    pub parsed_rust_deps_result: TypeId,
+    pub parsed_terraform_deps_result: TypeId,
     pub deps_request: TypeId,
 }
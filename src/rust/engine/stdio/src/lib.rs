@@ -16,6 +16,19 @@ use std::sync::Arc;
 use parking_lot::Mutex;
 use tokio::task_local;
 
+/// The environment variables which are conventionally used to force tools to use color: tools
+/// which would otherwise disable color when stdout is not a terminal, or when `TERM` is unset (as
+/// it is in sandboxes), honor at least one of them.
+const COLOR_ENV: &[(&str, &str)] = &[
+    ("CLICOLOR_FORCE", "1"),
+    ("FORCE_COLOR", "1"),
+    ("TERM", "xterm-256color"),
+];
+
+/// The environment variable which is conventionally used to disable color: see
+/// https://no-color.org/.
+const NO_COLOR_ENV: &[(&str, &str)] = &[("NO_COLOR", "1")];
+
 ///
 /// A Console wraps some "borrowed" file handles: when it is dropped, we forget about the file
 /// handles rather than closing them. The file handles are optional only so that they may be
@@ -213,6 +226,18 @@ impl Destination {
         }
     }
 
+    ///
+    /// The environment variables which tell a child process attached to this Destination whether
+    /// to use color, even though its output may be a pipe. See `COLOR_ENV` and `NO_COLOR_ENV`.
+    ///
+    pub fn child_color_env(&self) -> &'static [(&'static str, &'static str)] {
+        if self.stderr_use_color() {
+            COLOR_ENV
+        } else {
+            NO_COLOR_ENV
+        }
+    }
+
     ///
     /// Read from stdin if it is available on the current Destination.
     ///
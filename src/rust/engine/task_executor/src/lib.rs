@@ -12,6 +12,7 @@ use itertools::Itertools;
 use parking_lot::Mutex;
 use tokio::runtime::{Builder, Handle, Runtime};
 use tokio::task::{Id, JoinError, JoinHandle, JoinSet};
+use workunit_store::ObservationMetric;
 
 /// Copy our (thread-local or task-local) stdio destination and current workunit parent into
 /// the task. The former ensures that when a pantsd thread kicks off a future, any stdio done
@@ -44,10 +45,16 @@ fn future_with_correct_context<F: Future>(future: F) -> impl Future<Output = F::
 ///       Additionally, the explicit shutdown methods can be used to shut down the Executor for all
 ///       clones.
 ///
+/// Blocking tasks (see `spawn_blocking`) run on the blocking pool of the Runtime by default, which
+/// they share with `block_in_place`. An owned Executor may instead have a dedicated pool for them,
+/// so that (e.g.) the file I/O of capturing large snapshots cannot starve CPU-bound work.
+///
 #[derive(Debug, Clone)]
 pub struct Executor {
     runtime: Arc<Mutex<Option<Runtime>>>,
     handle: Handle,
+    io_runtime: Arc<Mutex<Option<Runtime>>>,
+    io_handle: Option<Handle>,
 }
 
 impl Executor {
@@ -64,6 +71,8 @@ impl Executor {
         Self {
             runtime: Arc::new(Mutex::new(None)),
             handle: Handle::current(),
+            io_runtime: Arc::new(Mutex::new(None)),
+            io_handle: None,
         }
     }
 
@@ -71,6 +80,10 @@ impl Executor {
     /// Gets a reference to a global static Executor with an owned tokio::Runtime, initializing it
     /// with the given thread configuration if this is the first usage.
     ///
+    /// If `max_io_threads` is set, blocking tasks run on a dedicated pool of (up to) that many
+    /// threads, rather than on the `max_threads - num_worker_threads` threads of the blocking pool
+    /// of the Runtime.
+    ///
     /// NB: The global static Executor eases lifecycle issues when consumed from Python, where we
     /// need thread configurability, but also want to know reliably when the Runtime will shutdown
     /// (which, because it is static, will only be at the entire process' exit).
@@ -78,6 +91,7 @@ impl Executor {
     pub fn new_owned<F>(
         num_worker_threads: usize,
         max_threads: usize,
+        max_io_threads: Option<usize>,
         on_thread_start: F,
     ) -> Result<Executor, String>
     where
@@ -98,10 +112,27 @@ impl Executor {
             .build()
             .map_err(|e| format!("Failed to start the runtime: {e}"))?;
 
+        // NB: Only the blocking pool of the IO Runtime is used, so it does not need worker threads
+        // of its own.
+        let io_runtime = max_io_threads
+            .map(|max_io_threads| {
+                Builder::new_current_thread()
+                    .max_blocking_threads(max_io_threads)
+                    .thread_name("pants-io")
+                    .build()
+                    .map_err(|e| format!("Failed to start the IO runtime: {e}"))
+            })
+            .transpose()?;
+
         let handle = runtime.handle().clone();
+        let io_handle = io_runtime
+            .as_ref()
+            .map(|io_runtime| io_runtime.handle().clone());
         Ok(Executor {
             runtime: Arc::new(Mutex::new(Some(runtime))),
             handle,
+            io_runtime: Arc::new(Mutex::new(io_runtime)),
+            io_handle,
         })
     }
 
@@ -113,6 +144,8 @@ impl Executor {
         Self {
             runtime: Arc::new(Mutex::new(None)),
             handle: self.handle.clone(),
+            io_runtime: Arc::new(Mutex::new(None)),
+            io_handle: self.io_handle.clone(),
         }
     }
 
@@ -205,15 +238,63 @@ impl Executor {
     ) -> JoinHandle<R> {
         let stdio_destination = stdio::get_destination();
         let workunit_store_handle = workunit_store::get_workunit_store_handle();
+        let pool = self.io_handle.as_ref().unwrap_or(&self.handle);
+        if let Some(workunit_store_handle) = &workunit_store_handle {
+            workunit_store_handle.store.record_observation(
+                ObservationMetric::ExecutorBlockingQueueDepth,
+                pool.metrics().blocking_queue_depth() as u64,
+            );
+        }
+        let handle = self.handle.clone();
+        let spawned = Instant::now();
         // NB: We unwrap here because the only thing that should cause an error in a spawned task is a
         // panic, in which case we want to propagate that.
-        self.handle.spawn_blocking(move || {
+        pool.spawn_blocking(move || {
+            // Blocking tasks on the IO pool interact with the main Runtime (e.g. via `block_on`).
+            let _context = handle.enter();
+            if let Some(workunit_store_handle) = &workunit_store_handle {
+                workunit_store_handle.store.record_observation(
+                    ObservationMetric::ExecutorBlockingTaskDelayMicros,
+                    spawned.elapsed().as_micros() as u64,
+                );
+            }
             stdio::set_thread_destination(stdio_destination);
             workunit_store::set_thread_workunit_store_handle(workunit_store_handle);
             f()
         })
     }
 
+    ///
+    /// Returns gauges of the state of the thread pools of this Executor, by name.
+    ///
+    pub fn metrics(&self) -> Vec<(&'static str, usize)> {
+        let metrics = self.handle.metrics();
+        let io_metrics = self.io_handle.as_ref().unwrap_or(&self.handle).metrics();
+        vec![
+            ("executor_worker_threads", metrics.num_workers()),
+            ("executor_active_tasks", metrics.active_tasks_count()),
+            ("executor_queued_tasks", metrics.injection_queue_depth()),
+            ("executor_blocking_threads", metrics.num_blocking_threads()),
+            (
+                "executor_idle_blocking_threads",
+                metrics.num_idle_blocking_threads(),
+            ),
+            (
+                "executor_queued_blocking_tasks",
+                metrics.blocking_queue_depth(),
+            ),
+            ("executor_io_threads", io_metrics.num_blocking_threads()),
+            (
+                "executor_idle_io_threads",
+                io_metrics.num_idle_blocking_threads(),
+            ),
+            (
+                "executor_queued_io_tasks",
+                io_metrics.blocking_queue_depth(),
+            ),
+        ]
+    }
+
     /// Return a reference to this executor's runtime handle.
     pub fn handle(&self) -> &Handle {
         &self.handle
@@ -231,6 +312,9 @@ impl Executor {
         };
 
         let start = Instant::now();
+        if let Some(io_runtime) = self.io_runtime.lock().take() {
+            io_runtime.shutdown_timeout(timeout);
+        }
         runtime.shutdown_timeout(timeout + Duration::from_millis(250));
         if start.elapsed() > timeout {
             // Leaked tasks could lead to panics in some cases (see #16105), so warn for them.
@@ -87,14 +87,15 @@ struct ParsedWriteResourceName<'a> {
 
 /// Parses a resource name of the form `{instance_name}/uploads/{uuid}/blobs/{hash}/{size}` into
 /// a struct with references to the individual components of the resource name. The
-/// `{instance_name}` may be blank (with no leading slash) as per REAPI specification.
+/// `{instance_name}` may be blank (with no leading slash) as per REAPI specification, and `blobs`
+/// may be followed by the name of the digest function (e.g. `blobs/blake3/{hash}/{size}`).
 fn parse_write_resource_name(resource: &str) -> Result<ParsedWriteResourceName, String> {
     if resource.is_empty() {
         return Err("Missing resource name".to_owned());
     }
 
     // Parse the resource name into parts separated by slashes (/).
-    let parts: Vec<_> = resource.split('/').collect();
+    let parts = without_digest_function(resource.split('/').collect());
 
     // Search for the `uploads` path component.
     let uploads_index = match parts.iter().position(|p| *p == "uploads") {
@@ -133,6 +134,17 @@ fn parse_write_resource_name(resource: &str) -> Result<ParsedWriteResourceName,
     })
 }
 
+/// Removes the digest function component which follows `blobs` in a resource name, if any. The
+/// blobs of every digest function are stored together, keyed by their fingerprints.
+fn without_digest_function(mut parts: Vec<&str>) -> Vec<&str> {
+    if let Some(blobs_index) = parts.iter().rposition(|p| *p == "blobs") {
+        if parts.get(blobs_index + 1) == Some(&"blake3") {
+            parts.remove(blobs_index + 1);
+        }
+    }
+    parts
+}
+
 #[derive(Debug, Eq, PartialEq)]
 struct ParsedReadResourceName<'a> {
     instance_name: &'a str,
@@ -140,14 +152,14 @@ struct ParsedReadResourceName<'a> {
     size: usize,
 }
 
-/// `"{instance_name}/blobs/{hash}/{size}"`
+/// `"{instance_name}/blobs/{hash}/{size}"`, or `"{instance_name}/blobs/blake3/{hash}/{size}"`.
 fn parse_read_resource_name(resource: &str) -> Result<ParsedReadResourceName, String> {
     if resource.is_empty() {
         return Err("Missing resource name".to_owned());
     }
 
     // Parse the resource name into parts separated by slashes (/).
-    let parts: Vec<_> = resource.split('/').collect();
+    let parts = without_digest_function(resource.split('/').collect());
 
     // Search for the `blobs` path component.
     let blobs_index = match parts.iter().position(|p| *p == "blobs") {
@@ -532,7 +544,10 @@ impl Capabilities for StubCASResponder {
 
         let response = ServerCapabilities {
             cache_capabilities: Some(CacheCapabilities {
-                digest_functions: vec![remexec::digest_function::Value::Sha256 as i32],
+                digest_functions: vec![
+                    remexec::digest_function::Value::Sha256 as i32,
+                    remexec::digest_function::Value::Blake3 as i32,
+                ],
                 max_batch_total_size_bytes: 0,
                 ..CacheCapabilities::default()
             }),
@@ -584,6 +599,18 @@ mod tests {
             }
         );
 
+        let result =
+            parse_write_resource_name("main/uploads/uuid-12345/blobs/blake3/abc123/12").unwrap();
+        assert_eq!(
+            result,
+            ParsedWriteResourceName {
+                instance_name: "main",
+                _uuid: "uuid-12345",
+                hash: "abc123",
+                size: 12,
+            }
+        );
+
         let result = parse_write_resource_name("a/b/c/uploads/uuid-12345/blobs/abc123/12").unwrap();
         assert_eq!(
             result,
@@ -665,6 +692,16 @@ mod tests {
                 size: 12,
             }
         );
+
+        let result = parse_read_resource_name("main/blobs/blake3/abc123/12").unwrap();
+        assert_eq!(
+            result,
+            ParsedReadResourceName {
+                instance_name: "main",
+                hash: "abc123",
+                size: 12,
+            }
+        );
     }
 
     #[test]
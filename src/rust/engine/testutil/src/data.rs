@@ -273,7 +273,7 @@ impl TestDirectory {
         let files = (0..100000)
             .map(|idx| remexec::FileNode {
                 name: format!("{idx:05}.ext"),
-                digest: Some(EMPTY_DIGEST.into()),
+                digest: Some((*EMPTY_DIGEST).into()),
                 is_executable: false,
                 ..remexec::FileNode::default()
             })
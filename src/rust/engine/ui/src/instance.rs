@@ -1,24 +1,39 @@
 // Copyright 2020 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
+use async_latch::AsyncLatch;
 use futures::future::BoxFuture;
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::time::SystemTime;
+use std::time::{Instant, SystemTime};
 use task_executor::Executor;
 use terminal_size::terminal_size_using_fd;
-use workunit_store::SpanId;
+use workunit_store::{Metric, SpanId, WorkunitStore};
 
+mod compact;
 mod indicatif;
 mod prodash;
+mod tui;
 
+use self::compact::CompactInstance;
 use self::indicatif::IndicatifInstance;
 use self::prodash::ProdashInstance;
+use self::tui::TuiInstance;
+use crate::{RunEstimate, UiRenderer};
+
+/// The workunits of local processes, which each hold one of the local process slots while running.
+const LOCAL_PROCESS_WORKUNITS: &[&str] = &["run_local_process", "run_local_process_in_workspace"];
+
+/// The workunits of remote processes, which are running while a process is queued or executing on
+/// the remote execution service.
+const REMOTE_PROCESS_WORKUNITS: &[&str] = &["run_remote_process"];
 
 /// The state for one run of the ConsoleUI.
 pub(super) enum Instance {
     Indicatif(IndicatifInstance),
     Prodash(ProdashInstance),
+    Compact(CompactInstance),
+    Tui(TuiInstance),
 }
 
 enum TaskState {
@@ -36,37 +51,60 @@ impl Instance {
     /// log from this method would deadlock (by causing the method to wait for _itself_ to finish).
     ///
     pub fn new(
-        ui_use_prodash: bool,
+        renderer: UiRenderer,
+        workunit_store: WorkunitStore,
         local_parallelism: usize,
         executor: Executor,
+        cancelled: AsyncLatch,
     ) -> Result<Instance, String> {
         let stderr_fd = stdio::get_destination().stderr_as_raw_fd()?;
         let (terminal_width, terminal_height) = terminal_size_using_fd(stderr_fd)
             .map(|terminal_dimensions| (terminal_dimensions.0 .0, terminal_dimensions.1 .0 - 1))
             .unwrap_or((50, local_parallelism.try_into().unwrap()));
 
-        if ui_use_prodash {
-            let instance =
-                prodash::ProdashInstance::new(executor.clone(), terminal_width, terminal_height)?;
-            Ok(Instance::Prodash(instance))
-        } else {
-            let instance = indicatif::IndicatifInstance::new(
-                local_parallelism,
-                terminal_width,
-                terminal_height,
-            )?;
-
-            Ok(Instance::Indicatif(instance))
+        match renderer {
+            UiRenderer::ExperimentalProdash => {
+                let instance = prodash::ProdashInstance::new(
+                    workunit_store,
+                    executor.clone(),
+                    terminal_width,
+                    terminal_height,
+                )?;
+                Ok(Instance::Prodash(instance))
+            }
+            UiRenderer::IndicatifSpinner => {
+                let instance = indicatif::IndicatifInstance::new(
+                    workunit_store,
+                    local_parallelism,
+                    terminal_width,
+                    terminal_height,
+                )?;
+                Ok(Instance::Indicatif(instance))
+            }
+            UiRenderer::Compact => {
+                let instance = compact::CompactInstance::new(workunit_store, terminal_width)?;
+                Ok(Instance::Compact(instance))
+            }
+            UiRenderer::ExperimentalTui => {
+                let instance = tui::TuiInstance::new(workunit_store, local_parallelism, cancelled)?;
+                Ok(Instance::Tui(instance))
+            }
         }
     }
 
     ///
     /// Update the rendering with new data.
     ///
-    pub fn render(&mut self, heavy_hitters: &HashMap<SpanId, (String, SystemTime)>) {
+    pub fn render(
+        &mut self,
+        heavy_hitters: &HashMap<SpanId, (String, SystemTime)>,
+        run_estimate: Option<&RunEstimate>,
+    ) {
         match self {
-            Instance::Indicatif(indicatif) => indicatif.render(heavy_hitters),
+            Instance::Indicatif(indicatif) => indicatif.render(heavy_hitters, run_estimate),
             Instance::Prodash(prodash) => prodash.render(heavy_hitters),
+            Instance::Compact(compact) => compact.render(heavy_hitters, run_estimate),
+            Instance::Tui(tui) => tui.render(),
         };
     }
 
@@ -77,10 +115,81 @@ impl Instance {
         match self {
             Instance::Indicatif(indicatif) => indicatif.teardown(),
             Instance::Prodash(prodash) => prodash.teardown(),
+            Instance::Compact(compact) => compact.teardown(),
+            Instance::Tui(tui) => tui.teardown(),
         }
     }
 }
 
+///
+/// Renders the live telemetry of a run as e.g.
+/// `Cache hits: 56% local, 31% remote | Process slots: 3/8 | Remote queue: 12`.
+///
+fn telemetry_footer(workunit_store: &WorkunitStore, local_parallelism: usize) -> String {
+    let metrics = workunit_store.get_metrics();
+    let hit_rate = |cached: Metric, requests: Metric| match metric(&metrics, requests) {
+        0 => "-".to_owned(),
+        requests => format!("{}%", metric(&metrics, cached) * 100 / requests),
+    };
+    let local_hit_rate = hit_rate(Metric::LocalCacheRequestsCached, Metric::LocalCacheRequests);
+    let remote_hit_rate = hit_rate(
+        Metric::RemoteCacheRequestsCached,
+        Metric::RemoteCacheRequests,
+    );
+    let local_processes = workunit_store.running_count(LOCAL_PROCESS_WORKUNITS);
+    let remote_processes = workunit_store.running_count(REMOTE_PROCESS_WORKUNITS);
+
+    format!(
+        "Cache hits: {local_hit_rate} local, {remote_hit_rate} remote | \
+         Process slots: {local_processes}/{local_parallelism} | Remote queue: {remote_processes}"
+    )
+}
+
+///
+/// Renders the custom counters which rules publish for display as e.g.
+/// `tests passed: 12, tests failed: 1`, if there are any.
+///
+fn displayed_counters_summary(workunit_store: &WorkunitStore) -> Option<String> {
+    let displayed_counters = workunit_store.displayed_counters();
+    if displayed_counters.is_empty() {
+        return None;
+    }
+    let summary = displayed_counters
+        .into_iter()
+        .map(|(name, value)| format!("{}: {value}", name.replace('_', " ")))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Some(summary)
+}
+
+///
+/// Renders the estimated time remaining of a run as e.g. `ETA: ~42s (critical path: ~17s)`, where
+/// the critical path is the time until the running workunit which is expected to complete last
+/// will complete.
+///
+fn estimate_summary(
+    run_estimate: &RunEstimate,
+    heavy_hitters: &HashMap<SpanId, (String, SystemTime)>,
+) -> String {
+    let critical_path = run_estimate.critical_path(heavy_hitters, SystemTime::now());
+    let remaining = run_estimate.remaining(critical_path, Instant::now());
+    match critical_path {
+        Some(critical_path) => format!(
+            "ETA: ~{}s (critical path: ~{}s)",
+            remaining.as_secs(),
+            critical_path.as_secs()
+        ),
+        None => format!("ETA: ~{}s", remaining.as_secs()),
+    }
+}
+
+fn metric(metrics: &HashMap<String, u64>, metric: Metric) -> u64 {
+    metrics
+        .get(<&str>::from(metric))
+        .copied()
+        .unwrap_or_default()
+}
+
 fn classify_tasks(
     heavy_hitters: &HashMap<SpanId, (String, SystemTime)>,
     mut current_ids: HashSet<SpanId>,
@@ -0,0 +1,115 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+use std::future;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use indicatif::MultiProgress;
+use indicatif::ProgressBar;
+use indicatif::ProgressStyle;
+use parking_lot::Mutex;
+
+use workunit_store::format_workunit_duration_ms;
+use workunit_store::{Metric, SpanId, WorkunitStore};
+
+use super::indicatif::setup_bar_outputs;
+use super::{displayed_counters_summary, estimate_summary, metric};
+use crate::RunEstimate;
+
+///
+/// Renders a single status line for the whole run, rather than a line per running workunit, for
+/// narrow terminals and for log viewers which do not support redrawing multiple lines.
+///
+pub struct CompactInstance {
+    workunit_store: WorkunitStore,
+    start_time: Instant,
+    // NB: Kept for Drop.
+    _multi_progress: MultiProgress,
+    bar: ProgressBar,
+}
+
+impl CompactInstance {
+    pub fn new(
+        workunit_store: WorkunitStore,
+        terminal_width: u16,
+    ) -> Result<CompactInstance, String> {
+        // See the equivalent in `IndicatifInstance::new`: the lock is held until the bar exists.
+        let stderr_dest_bar = Arc::new(Mutex::new(None));
+        let mut stderr_dest_bar_guard = stderr_dest_bar.lock();
+        let multi_progress = setup_bar_outputs(stderr_dest_bar.clone())?;
+
+        let style = ProgressStyle::default_bar()
+            .template("{spinner} {wide_msg}")
+            .expect("Valid template.");
+        let bar = multi_progress.add(ProgressBar::new(terminal_width.into()).with_style(style));
+        *stderr_dest_bar_guard = Some(bar.downgrade());
+
+        Ok(CompactInstance {
+            workunit_store,
+            start_time: Instant::now(),
+            _multi_progress: multi_progress,
+            bar,
+        })
+    }
+
+    pub fn teardown(self) -> BoxFuture<'static, ()> {
+        std::mem::drop(self);
+        future::ready(()).boxed()
+    }
+
+    pub fn render(
+        &mut self,
+        heavy_hitters: &HashMap<SpanId, (String, SystemTime)>,
+        run_estimate: Option<&RunEstimate>,
+    ) {
+        // NB: The heavy hitters which are rendered by the other renderers are limited to the
+        // parallelism of the run, whereas all running workunits are counted here.
+        let running = self.workunit_store.heavy_hitters(usize::MAX).len();
+        let metrics = self.workunit_store.get_metrics();
+        let elapsed_ms = self.start_time.elapsed().as_millis();
+        let mut line = status_line(elapsed_ms, running, &metrics);
+        if let Some(counters) = displayed_counters_summary(&self.workunit_store) {
+            line.push_str(" | ");
+            line.push_str(&counters);
+        }
+        if let Some(run_estimate) = run_estimate {
+            line.push_str(" | ");
+            line.push_str(&estimate_summary(run_estimate, heavy_hitters));
+        }
+        self.bar.set_message(line);
+        self.bar.tick();
+    }
+}
+
+///
+/// Summarizes a run as e.g. `12.3s | 4 running | 57 processes, 56% from cache`.
+///
+/// Processes are counted once they are started or found in a cache, and the hit rate is that of
+/// the cache lookups of processes: a process which is looked up in both the local and the remote
+/// cache is only counted once.
+///
+fn status_line(elapsed_ms: u128, running: usize, metrics: &HashMap<String, u64>) -> String {
+    let cached = metric(metrics, Metric::LocalCacheRequestsCached)
+        + metric(metrics, Metric::RemoteCacheRequestsCached);
+    let executed = metric(metrics, Metric::LocalExecutionRequests)
+        + metric(metrics, Metric::RemoteExecutionRequests)
+        + metric(metrics, Metric::DockerExecutionRequests);
+    let lookups = std::cmp::max(
+        metric(metrics, Metric::LocalCacheRequests),
+        metric(metrics, Metric::RemoteCacheRequests),
+    );
+
+    let mut line = format!(
+        "{} | {running} running | {} processes",
+        format_workunit_duration_ms!(elapsed_ms),
+        cached + executed
+    );
+    if lookups > 0 {
+        line.push_str(&format!(", {}% from cache", cached * 100 / lookups));
+    }
+    line
+}
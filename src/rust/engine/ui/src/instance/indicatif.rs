@@ -18,20 +18,24 @@ use indicatif::WeakProgressBar;
 use parking_lot::Mutex;
 
 use workunit_store::format_workunit_duration_ms;
-use workunit_store::SpanId;
+use workunit_store::{SpanId, WorkunitStore};
 
 use super::TaskState;
-use crate::ConsoleUI;
+use crate::{ConsoleUI, RunEstimate};
 
 pub struct IndicatifInstance {
+    workunit_store: WorkunitStore,
+    local_parallelism: usize,
     tasks_to_display: IndexSet<SpanId>,
     // NB: Kept for Drop.
     _multi_progress: MultiProgress,
     bars: Vec<ProgressBar>,
+    footer: ProgressBar,
 }
 
 impl IndicatifInstance {
     pub fn new(
+        workunit_store: WorkunitStore,
         local_parallelism: usize,
         terminal_width: u16,
         terminal_height: u16,
@@ -48,7 +52,9 @@ impl IndicatifInstance {
         let mut stderr_dest_bar_guard = stderr_dest_bar.lock();
         let multi_progress = setup_bar_outputs(stderr_dest_bar.clone())?;
 
-        let bars = (0..cmp::min(local_parallelism, terminal_height.into()))
+        // NB: One line of the terminal is reserved for the footer.
+        let task_lines = cmp::max(usize::from(terminal_height).saturating_sub(1), 1);
+        let bars = (0..cmp::min(local_parallelism, task_lines))
             .map(|_n| {
                 let style = ProgressStyle::default_bar()
                     .template("{spinner} {wide_msg}")
@@ -58,12 +64,21 @@ impl IndicatifInstance {
             })
             .collect::<Vec<_>>();
 
+        let footer_style = ProgressStyle::default_bar()
+            .template("  {wide_msg}")
+            .expect("Valid template.");
+        let footer =
+            multi_progress.add(ProgressBar::new(terminal_width.into()).with_style(footer_style));
+
         *stderr_dest_bar_guard = Some(bars[0].downgrade());
 
         Ok(IndicatifInstance {
+            workunit_store,
+            local_parallelism,
             tasks_to_display: IndexSet::new(),
             _multi_progress: multi_progress,
             bars,
+            footer,
         })
     }
 
@@ -74,7 +89,11 @@ impl IndicatifInstance {
         future::ready(()).boxed()
     }
 
-    pub fn render(&mut self, heavy_hitters: &HashMap<SpanId, (String, SystemTime)>) {
+    pub fn render(
+        &mut self,
+        heavy_hitters: &HashMap<SpanId, (String, SystemTime)>,
+        run_estimate: Option<&RunEstimate>,
+    ) {
         let tasks_to_display = &mut self.tasks_to_display;
         super::classify_tasks(
             heavy_hitters,
@@ -112,10 +131,22 @@ impl IndicatifInstance {
 
             pbar.tick();
         }
+
+        let mut footer = super::telemetry_footer(&self.workunit_store, self.local_parallelism);
+        if let Some(counters) = super::displayed_counters_summary(&self.workunit_store) {
+            footer.push_str(" | ");
+            footer.push_str(&counters);
+        }
+        if let Some(run_estimate) = run_estimate {
+            footer.push_str(" | ");
+            footer.push_str(&super::estimate_summary(run_estimate, heavy_hitters));
+        }
+        self.footer.set_message(footer);
+        self.footer.tick();
     }
 }
 
-fn setup_bar_outputs(
+pub(super) fn setup_bar_outputs(
     stderr_dest_bar: Arc<Mutex<Option<WeakProgressBar>>>,
 ) -> Result<MultiProgress, String> {
     let (term_read, _, term_stderr_write) = {
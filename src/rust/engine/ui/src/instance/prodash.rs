@@ -18,13 +18,16 @@ use prodash::TreeOptions;
 use logging::fatal_log;
 use task_executor::Executor;
 use workunit_store::format_workunit_duration_ms;
-use workunit_store::SpanId;
+use workunit_store::{SpanId, WorkunitStore};
 
 use super::TaskState;
 use crate::ConsoleUI;
 
 pub struct ProdashInstance {
+    workunit_store: WorkunitStore,
     tasks_to_display: HashMap<SpanId, prodash::tree::Item>,
+    // A line for the custom counters which rules publish for display, once there are any.
+    counters: Option<prodash::tree::Item>,
     tree: prodash::Tree,
     handle: line::JoinHandle,
     terminal_width: u16,
@@ -33,6 +36,7 @@ pub struct ProdashInstance {
 
 impl ProdashInstance {
     pub fn new(
+        workunit_store: WorkunitStore,
         executor: Executor,
         terminal_width: u16,
         terminal_height: u16,
@@ -87,7 +91,9 @@ impl ProdashInstance {
         });
 
         Ok(ProdashInstance {
+            workunit_store,
             tasks_to_display: HashMap::new(),
+            counters: None,
             tree,
             handle,
             terminal_width,
@@ -99,6 +105,7 @@ impl ProdashInstance {
         // Drop all tasks to clear the Tree. The call to shutdown will render a final "Tick" with the
         // empty Tree, which will clear the screen.
         self.tasks_to_display.clear();
+        self.counters = None;
         self.executor
             .clone()
             .spawn_blocking(
@@ -142,7 +149,14 @@ impl ProdashInstance {
                     tasks_to_display.insert(span_id, item);
                 }
             },
-        )
+        );
+
+        if let Some(summary) = super::displayed_counters_summary(&self.workunit_store) {
+            match &mut self.counters {
+                Some(counters) => counters.set_name(summary),
+                None => self.counters = Some(self.tree.add_child(summary)),
+            }
+        }
     }
 }
 
@@ -0,0 +1,468 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::future;
+use std::io::{self, Read, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use async_latch::AsyncLatch;
+use crossterm::{cursor, terminal};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use nix::poll::{poll, PollFd, PollFlags};
+use nix::sys::termios::{self, LocalFlags, SetArg, Termios};
+use parking_lot::Mutex;
+use ratatui::backend::{Backend, ClearType, CrosstermBackend, WindowSize};
+use ratatui::buffer::Cell;
+use ratatui::layout::{Constraint, Direction, Layout, Rect, Size};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{Frame, Terminal};
+use stdio::{TermReadDestination, TermWriteDestination};
+use terminal_size::terminal_size_using_fd;
+
+use workunit_store::format_workunit_duration_ms;
+use workunit_store::{SpanId, WorkunitStore};
+
+use super::telemetry_footer;
+
+/// The most log messages which are displayed below the running workunits.
+const MAX_DISPLAYED_MESSAGES: usize = 5;
+
+/// The keys which the TUI responds to.
+#[derive(Debug, Eq, PartialEq)]
+enum Key {
+    Up,
+    Down,
+    Enter,
+    Back,
+    Cancel,
+}
+
+///
+/// A full screen UI which lists the running workunits, and which allows for selecting one of them
+/// with the keyboard to view its live output, or to cancel the run that it is a part of.
+///
+/// The UI is drawn on the alternate screen of the terminal, so messages which are logged while it
+/// is running are displayed below the workunits, and then printed to the terminal once it is torn
+/// down.
+///
+pub struct TuiInstance {
+    workunit_store: WorkunitStore,
+    local_parallelism: usize,
+    cancelled: AsyncLatch,
+    terminal: Terminal<TuiBackend>,
+    input: TermReadDestination,
+    // The mode of the terminal before it was switched to raw mode, which is restored on teardown.
+    // None if stdin is not a terminal, in which case keys are not read.
+    original_termios: Option<Termios>,
+    // The messages which were logged while the UI was running.
+    messages: Arc<Mutex<Vec<String>>>,
+    // The running workunits as of the last render, oldest first.
+    running: Vec<(SpanId, String, SystemTime)>,
+    list_state: ListState,
+    // The workunit whose live output is being viewed, if any.
+    viewing: Option<SpanId>,
+}
+
+impl TuiInstance {
+    pub fn new(
+        workunit_store: WorkunitStore,
+        local_parallelism: usize,
+        cancelled: AsyncLatch,
+    ) -> Result<TuiInstance, String> {
+        let stderr_fd = stdio::get_destination().stderr_as_raw_fd()?;
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let (input, _, stderr) = {
+            let messages = messages.clone();
+            stdio::get_destination().exclusive_start(Box::new(move |msg: &str| {
+                messages.lock().push(msg.to_owned());
+                Ok(())
+            }))?
+        };
+
+        let original_termios = termios::tcgetattr(input.as_raw_fd()).ok();
+        if let Some(original_termios) = &original_termios {
+            // Keys are read as they are pressed, but Ctrl+C continues to interrupt the run.
+            let mut raw_termios = original_termios.clone();
+            termios::cfmakeraw(&mut raw_termios);
+            raw_termios.local_flags.insert(LocalFlags::ISIG);
+            termios::tcsetattr(input.as_raw_fd(), SetArg::TCSANOW, &raw_termios)
+                .map_err(|e| format!("Failed to set the terminal to raw mode: {e}"))?;
+        }
+
+        let mut backend = TuiBackend {
+            inner: CrosstermBackend::new(stderr),
+            fd: stderr_fd,
+        };
+        crossterm::execute!(backend, terminal::EnterAlternateScreen, cursor::Hide)
+            .map_err(|e| format!("Failed to enter the alternate screen: {e}"))?;
+        let terminal =
+            Terminal::new(backend).map_err(|e| format!("Failed to set up the terminal: {e}"))?;
+
+        workunit_store.set_live_output_enabled(true);
+        Ok(TuiInstance {
+            workunit_store,
+            local_parallelism,
+            cancelled,
+            terminal,
+            input,
+            original_termios,
+            messages,
+            running: Vec::new(),
+            list_state: ListState::default(),
+            viewing: None,
+        })
+    }
+
+    pub fn teardown(self) -> BoxFuture<'static, ()> {
+        std::mem::drop(self);
+        future::ready(()).boxed()
+    }
+
+    pub fn render(&mut self) {
+        for key in self.read_keys() {
+            self.handle_key(key);
+        }
+
+        let selected_span_id = self.selected_span_id();
+        self.running = self
+            .workunit_store
+            .heavy_hitters(usize::MAX)
+            .into_iter()
+            .map(|(span_id, (desc, start_time))| (span_id, desc, start_time))
+            .collect();
+        self.running
+            .sort_by_key(|(span_id, _, start_time)| (*start_time, *span_id));
+        // Keep the same workunit selected while it is running.
+        let position = |span_id: SpanId| self.running.iter().position(|(s, ..)| *s == span_id);
+        let selected = match selected_span_id.and_then(position) {
+            Some(i) => Some(i),
+            None if self.running.is_empty() => None,
+            None => Some(
+                self.list_state
+                    .selected()
+                    .unwrap_or(0)
+                    .min(self.running.len() - 1),
+            ),
+        };
+        self.list_state.select(selected);
+        if self.viewing.and_then(position).is_none() {
+            // The viewed workunit (if any) has completed.
+            self.viewing = None;
+        }
+
+        let footer = format!(
+            "{} | ↑/↓: select | enter: view output | esc: back | c: cancel the run",
+            telemetry_footer(&self.workunit_store, self.local_parallelism)
+        );
+        let recent_messages = {
+            let messages = self.messages.lock();
+            messages[messages.len().saturating_sub(MAX_DISPLAYED_MESSAGES)..]
+                .iter()
+                .map(|message| printable(message))
+                .collect::<Vec<_>>()
+        };
+        let Self {
+            terminal,
+            workunit_store,
+            running,
+            list_state,
+            viewing,
+            cancelled,
+            ..
+        } = self;
+        let cancelling = cancelled.poll_triggered();
+        // NB: Rendering errors are ignored, because logging them would be rendered by this UI.
+        let _ = terminal.draw(|frame| {
+            let message_lines = recent_messages.len() as u16;
+            let [main, messages_area, footer_area] = split(
+                frame.size(),
+                [
+                    Constraint::Min(3),
+                    Constraint::Length(if message_lines > 0 {
+                        message_lines + 2
+                    } else {
+                        0
+                    }),
+                    Constraint::Length(1),
+                ],
+            );
+            match viewing {
+                Some(span_id) => render_output(frame, main, workunit_store, running, *span_id),
+                None => render_running(frame, main, running, list_state, cancelling),
+            }
+            if message_lines > 0 {
+                frame.render_widget(
+                    Paragraph::new(recent_messages.join("\n"))
+                        .block(Block::default().borders(Borders::ALL).title(" Messages ")),
+                    messages_area,
+                );
+            }
+            frame.render_widget(
+                Paragraph::new(footer.as_str()).style(Style::default().add_modifier(Modifier::DIM)),
+                footer_area,
+            );
+        });
+    }
+
+    fn selected_span_id(&self) -> Option<SpanId> {
+        self.list_state
+            .selected()
+            .and_then(|i| self.running.get(i))
+            .map(|(span_id, ..)| *span_id)
+    }
+
+    fn handle_key(&mut self, key: Key) {
+        let selected = self.list_state.selected().unwrap_or(0);
+        match key {
+            Key::Up if self.viewing.is_none() => {
+                self.list_state.select(Some(selected.saturating_sub(1)));
+            }
+            Key::Down if self.viewing.is_none() => {
+                let last = self.running.len().saturating_sub(1);
+                self.list_state.select(Some((selected + 1).min(last)));
+            }
+            Key::Enter => self.viewing = self.selected_span_id(),
+            Key::Back => self.viewing = None,
+            // Cancelling the Session cancels all of its roots, as Ctrl+C would.
+            Key::Cancel => self.cancelled.trigger(),
+            Key::Up | Key::Down => (),
+        }
+    }
+
+    ///
+    /// Reads the keys which have been pressed since the last render, without blocking.
+    ///
+    fn read_keys(&mut self) -> Vec<Key> {
+        if self.original_termios.is_none() {
+            return vec![];
+        }
+        let mut fds = [PollFd::new(self.input.as_raw_fd(), PollFlags::POLLIN)];
+        if !matches!(poll(&mut fds, 0), Ok(1)) {
+            return vec![];
+        }
+        let mut buf = [0; 64];
+        match self.input.read(&mut buf) {
+            Ok(n) => parse_keys(&buf[..n]),
+            Err(_) => vec![],
+        }
+    }
+}
+
+impl Drop for TuiInstance {
+    fn drop(&mut self) {
+        self.workunit_store.set_live_output_enabled(false);
+        let backend = self.terminal.backend_mut();
+        let _ = crossterm::execute!(backend, terminal::LeaveAlternateScreen, cursor::Show);
+        if let Some(original_termios) = &self.original_termios {
+            let _ = termios::tcsetattr(self.input.as_raw_fd(), SetArg::TCSANOW, original_termios);
+        }
+        // Print the messages which were logged while the alternate screen was displayed.
+        for message in self.messages.lock().drain(..) {
+            let _ = writeln!(backend, "{message}");
+        }
+        let _ = Write::flush(backend);
+    }
+}
+
+fn split<const N: usize>(area: Rect, constraints: [Constraint; N]) -> [Rect; N] {
+    let areas = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(area);
+    std::array::from_fn(|i| areas[i])
+}
+
+fn render_running(
+    frame: &mut Frame,
+    area: Rect,
+    running: &[(SpanId, String, SystemTime)],
+    list_state: &mut ListState,
+    cancelling: bool,
+) {
+    let now = SystemTime::now();
+    let items = running
+        .iter()
+        .map(|(_, desc, start_time)| {
+            let elapsed_ms = now
+                .duration_since(*start_time)
+                .unwrap_or_default()
+                .as_millis();
+            ListItem::new(format!(
+                "{:>8} {}",
+                format_workunit_duration_ms!(elapsed_ms).to_string(),
+                printable(desc)
+            ))
+        })
+        .collect::<Vec<_>>();
+    let title = if cancelling {
+        format!(" Running ({}): cancelling... ", running.len())
+    } else {
+        format!(" Running ({}) ", running.len())
+    };
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, area, list_state);
+}
+
+fn render_output(
+    frame: &mut Frame,
+    area: Rect,
+    workunit_store: &WorkunitStore,
+    running: &[(SpanId, String, SystemTime)],
+    span_id: SpanId,
+) {
+    let desc = running
+        .iter()
+        .find(|(s, ..)| *s == span_id)
+        .map(|(_, desc, _)| printable(desc))
+        .unwrap_or_default();
+    let output = workunit_store
+        .live_output(span_id)
+        .map(|output| printable(&String::from_utf8_lossy(&output)))
+        .unwrap_or_default();
+    // Display the most recent lines which fit.
+    let visible_lines = usize::from(area.height.saturating_sub(2));
+    let lines = output.lines().collect::<Vec<_>>();
+    let tail = lines[lines.len().saturating_sub(visible_lines)..].join("\n");
+    frame.render_widget(
+        Paragraph::new(tail).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" {desc} ")),
+        ),
+        area,
+    );
+}
+
+///
+/// Removes the escape sequences and control characters from the given output of a process or
+/// log message, which would otherwise corrupt the rendering. Carriage returns (as used by progress
+/// bars) discard the beginning of their line.
+///
+fn printable(s: &str) -> String {
+    console::strip_ansi_codes(s)
+        .lines()
+        .map(|line| {
+            line.rsplit('\r')
+                .find(|segment| !segment.is_empty())
+                .unwrap_or_default()
+                .replace('\t', "    ")
+                .chars()
+                .filter(|c| !c.is_control())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+///
+/// Parses the given bytes read from a terminal in raw mode into the keys which they represent, and
+/// ignores any other input.
+///
+fn parse_keys(mut bytes: &[u8]) -> Vec<Key> {
+    let mut keys = vec![];
+    while let Some((first, rest)) = bytes.split_first() {
+        bytes = rest;
+        let key = match first {
+            // The escape sequences of the arrow keys.
+            b'\x1b' => match bytes {
+                [b'[' | b'O', b'A', rest @ ..] => {
+                    bytes = rest;
+                    Key::Up
+                }
+                [b'[' | b'O', b'B', rest @ ..] => {
+                    bytes = rest;
+                    Key::Down
+                }
+                [b'[' | b'O', _, rest @ ..] => {
+                    bytes = rest;
+                    continue;
+                }
+                _ => Key::Back,
+            },
+            b'k' => Key::Up,
+            b'j' => Key::Down,
+            b'\r' | b'\n' => Key::Enter,
+            b'q' | b'\x7f' => Key::Back,
+            b'c' => Key::Cancel,
+            _ => continue,
+        };
+        keys.push(key);
+    }
+    keys
+}
+
+///
+/// Draws with crossterm on the console of the run, which might not be the terminal of this
+/// process (when running in pantsd), so the size of the terminal is read from the console.
+///
+struct TuiBackend {
+    inner: CrosstermBackend<TermWriteDestination>,
+    fd: RawFd,
+}
+
+impl Write for TuiBackend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Write::flush(&mut self.inner)
+    }
+}
+
+impl Backend for TuiBackend {
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        self.inner.draw(content)
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.inner.hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.inner.show_cursor()
+    }
+
+    fn get_cursor(&mut self) -> io::Result<(u16, u16)> {
+        self.inner.get_cursor()
+    }
+
+    fn set_cursor(&mut self, x: u16, y: u16) -> io::Result<()> {
+        self.inner.set_cursor(x, y)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.inner.clear()
+    }
+
+    fn clear_region(&mut self, clear_type: ClearType) -> io::Result<()> {
+        self.inner.clear_region(clear_type)
+    }
+
+    fn size(&self) -> io::Result<Rect> {
+        let (width, height) =
+            terminal_size_using_fd(self.fd).ok_or_else(|| io::Error::other("Not a terminal."))?;
+        Ok(Rect::new(0, 0, width.0, height.0))
+    }
+
+    fn window_size(&mut self) -> io::Result<WindowSize> {
+        let size = self.size()?;
+        Ok(WindowSize {
+            columns_rows: Size::new(size.width, size.height),
+            pixels: Size::default(),
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Backend::flush(&mut self.inner)
+    }
+}
@@ -1,19 +1,107 @@
 // Copyright 2020 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
+use async_latch::AsyncLatch;
 use futures::future::BoxFuture;
 use futures::future::FutureExt;
 use instance::Instance;
+use parking_lot::Mutex;
+use std::collections::HashMap;
 use std::future::Future;
-use std::time::Duration;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use task_executor::Executor;
-use workunit_store::WorkunitStore;
+use workunit_store::{SpanId, WorkunitStore};
 mod instance;
 
+/// The renderer of the dynamic UI: see the `[GLOBAL].dynamic_ui_renderer` option.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum UiRenderer {
+    /// A spinner and a line for each of the longest running workunits.
+    IndicatifSpinner,
+    /// A line for each of the longest running workunits, rendered with prodash.
+    ExperimentalProdash,
+    /// A single status line, which summarizes the workunits of the run.
+    Compact,
+    /// A full screen UI, which lists the running workunits and allows for viewing their output and
+    /// cancelling the run with the keyboard.
+    ExperimentalTui,
+}
+
+impl FromStr for UiRenderer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "indicatif-spinner" => Ok(UiRenderer::IndicatifSpinner),
+            "experimental-prodash" => Ok(UiRenderer::ExperimentalProdash),
+            "compact" => Ok(UiRenderer::Compact),
+            "experimental-tui" => Ok(UiRenderer::ExperimentalTui),
+            _ => Err(format!(
+                "Unrecognized dynamic UI renderer: {s}. Should be one of `indicatif-spinner`, \
+                 `experimental-prodash`, `compact` or `experimental-tui`."
+            )),
+        }
+    }
+}
+
+///
+/// An estimate of when a run will complete, from the durations of previous runs of the same goals,
+/// and the durations of the processes which previously ran.
+///
+pub struct RunEstimate {
+    deadline: Instant,
+    process_durations: HashMap<String, Duration>,
+}
+
+impl RunEstimate {
+    pub fn new(remaining: Duration, process_durations: HashMap<String, Duration>) -> RunEstimate {
+        RunEstimate {
+            deadline: Instant::now() + remaining,
+            process_durations,
+        }
+    }
+
+    ///
+    /// The time until the running workunit which is expected to complete last will complete, for
+    /// the running workunits which completed in previous runs.
+    ///
+    pub fn critical_path(
+        &self,
+        heavy_hitters: &HashMap<SpanId, (String, SystemTime)>,
+        now: SystemTime,
+    ) -> Option<Duration> {
+        heavy_hitters
+            .values()
+            .filter_map(|(desc, start_time)| {
+                let expected = self.process_durations.get(desc)?;
+                let elapsed = now.duration_since(*start_time).unwrap_or_default();
+                Some(expected.saturating_sub(elapsed))
+            })
+            .max()
+    }
+
+    ///
+    /// The time until the run is expected to complete: the run cannot complete before its critical
+    /// path does, even once it has overrun the durations of previous runs.
+    ///
+    pub fn remaining(&self, critical_path: Option<Duration>, now: Instant) -> Duration {
+        std::cmp::max(
+            self.deadline.saturating_duration_since(now),
+            critical_path.unwrap_or_default(),
+        )
+    }
+}
+
 pub struct ConsoleUI {
     workunit_store: WorkunitStore,
     local_parallelism: usize,
-    ui_use_prodash: bool,
+    renderer: UiRenderer,
+    // Set (at most once per run) by the consumers of workunits which persist historical durations.
+    run_estimate: Arc<Mutex<Option<RunEstimate>>>,
+    // The cancellation latch of the Session, which the `experimental-tui` renderer may trigger.
+    cancelled: AsyncLatch,
     // While the UI is running, there will be an Instance present.
     instance: Option<Instance>,
 }
@@ -22,12 +110,16 @@ impl ConsoleUI {
     pub fn new(
         workunit_store: WorkunitStore,
         local_parallelism: usize,
-        ui_use_prodash: bool,
+        renderer: UiRenderer,
+        run_estimate: Arc<Mutex<Option<RunEstimate>>>,
+        cancelled: AsyncLatch,
     ) -> ConsoleUI {
         ConsoleUI {
             workunit_store,
             local_parallelism,
-            ui_use_prodash,
+            renderer,
+            run_estimate,
+            cancelled,
             instance: None,
         }
     }
@@ -64,7 +156,7 @@ impl ConsoleUI {
         };
 
         let heavy_hitters = self.workunit_store.heavy_hitters(self.local_parallelism);
-        instance.render(&heavy_hitters)
+        instance.render(&heavy_hitters, self.run_estimate.lock().as_ref())
     }
 
     ///
@@ -79,9 +171,11 @@ impl ConsoleUI {
         }
 
         self.instance = Some(Instance::new(
-            self.ui_use_prodash,
+            self.renderer,
+            self.workunit_store.clone(),
             self.local_parallelism,
             executor,
+            self.cancelled.clone(),
         )?);
 
         Ok(())
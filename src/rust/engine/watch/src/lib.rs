@@ -1,23 +1,63 @@
 // Copyright 2020 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
 
+mod poll;
 #[cfg(test)]
 mod tests;
+mod watchman;
 
 use std::collections::HashSet;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Weak};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crossbeam_channel::{self, Receiver, RecvTimeoutError, TryRecvError};
 use fs::GitignoreStyleExcludes;
-use log::{debug, trace, warn};
+use log::{debug, info, trace, warn};
 use notify::event::{Flag, MetadataKind, ModifyKind};
 use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use parking_lot::Mutex;
 use task_executor::Executor;
 
+use crate::poll::Poller;
+use crate::watchman::Watchman;
+
+///
+/// How an InvalidationWatcher watches the filesystem: see the `[GLOBAL].file_watcher` option.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WatchMode {
+    /// Native notifications of changes only (inotify on Linux, and FSEvents on macOS).
+    Native,
+    /// Polling of the watched paths for changes only.
+    Polling,
+    /// Native notifications, falling back to polling for the paths which cannot be watched
+    /// natively (or for all paths, if native watching cannot be started at all).
+    Auto,
+    /// A subscription to the changes to the build root which are observed by a Watchman server.
+    Watchman,
+}
+
+impl FromStr for WatchMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "native" => Ok(WatchMode::Native),
+            "polling" => Ok(WatchMode::Polling),
+            "auto" => Ok(WatchMode::Auto),
+            "watchman" => Ok(WatchMode::Watchman),
+            _ => Err(format!(
+                "Unrecognized file watcher: {s}. Should be one of `native`, `polling`, `auto` or \
+                 `watchman`."
+            )),
+        }
+    }
+}
+
 ///
 /// An InvalidationWatcher maintains a Thread that receives events from a notify Watcher.
 ///
@@ -26,9 +66,21 @@ use task_executor::Executor;
 /// this will mean polling.
 ///
 struct Inner {
-    watcher: RecommendedWatcher,
+    // The native watcher, unless polling was selected, or native watching could not be started.
+    watcher: Option<RecommendedWatcher>,
+    // The poller, unless native watching (or Watchman) was selected.
+    poller: Option<Poller>,
+    // The subscription to Watchman, if it was selected: it watches the whole build root.
+    watchman: Option<Watchman>,
+    // True once a path has fallen back to being polled: see `WatchMode::Auto`.
+    fell_back_to_polling: bool,
     executor: Executor,
     liveness: Receiver<String>,
+    metrics: Arc<WatchMetrics>,
+    build_root: PathBuf,
+    canonical_build_root: PathBuf,
+    // Moved to the background task when it is started.
+    ignore_files: IgnoreFiles,
     // Until the background task has started, contains the relevant inputs to launch it via
     // start_background_thread. The decoupling of creating the `InvalidationWatcher` and starting it
     // is to allow for testing of the background thread.
@@ -36,12 +88,127 @@ struct Inner {
 }
 
 type WatcherTaskInputs = (
+    Arc<GitignoreStyleExcludes>,
     Arc<GitignoreStyleExcludes>,
     PathBuf,
+    Duration,
     crossbeam_channel::Sender<String>,
     Receiver<notify::Result<Event>>,
 );
 
+///
+/// Rebuilds the ignorer of an InvalidationWatcher from its ignore files, when they have changed. It
+/// should also replace the ignorer of any other consumers of it, such as a `PosixFS`.
+///
+pub type ReloadIgnorer = Box<dyn Fn() -> Result<Arc<GitignoreStyleExcludes>, String> + Send>;
+
+///
+/// The ignore files (such as `.gitignore`) which the ignorer of an InvalidationWatcher was created
+/// from, and how to rebuild it when they change.
+///
+#[derive(Default)]
+pub(crate) struct IgnoreFiles {
+    // Relative to the build root, unless they are outside of it.
+    paths: HashSet<PathBuf>,
+    reload: Option<ReloadIgnorer>,
+}
+
+///
+/// Metrics of the invalidations of an InvalidationWatcher, since it was started.
+///
+#[derive(Default)]
+pub struct WatchMetrics {
+    events: AtomicU64,
+    batches: AtomicU64,
+    invalidated_paths: AtomicU64,
+    max_batch_paths: AtomicU64,
+}
+
+impl WatchMetrics {
+    fn record_batch(&self, batch: &InvalidationBatch) {
+        let paths = batch.paths.len() as u64;
+        self.events
+            .fetch_add(batch.events as u64, Ordering::Relaxed);
+        self.batches.fetch_add(1, Ordering::Relaxed);
+        self.invalidated_paths.fetch_add(paths, Ordering::Relaxed);
+        self.max_batch_paths.fetch_max(paths, Ordering::Relaxed);
+    }
+
+    ///
+    /// The metrics by name: the number of events which were received, the number of batches of
+    /// them which were invalidated, the total number of paths which were invalidated, and the
+    /// largest number of paths which were invalidated by one batch.
+    ///
+    pub fn snapshot(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("watcher_events", self.events.load(Ordering::Relaxed)),
+            (
+                "watcher_invalidation_batches",
+                self.batches.load(Ordering::Relaxed),
+            ),
+            (
+                "watcher_invalidated_paths",
+                self.invalidated_paths.load(Ordering::Relaxed),
+            ),
+            (
+                "watcher_max_invalidation_batch_paths",
+                self.max_batch_paths.load(Ordering::Relaxed),
+            ),
+        ]
+    }
+}
+
+///
+/// The invalidations for the events which were received within one coalescing window, which are
+/// applied together in a single pass over the graph.
+///
+#[derive(Default)]
+struct InvalidationBatch {
+    events: usize,
+    paths: HashSet<PathBuf>,
+    // True if the queue of events overflowed, and so all paths must be invalidated.
+    invalidate_all: bool,
+}
+
+impl IgnoreFiles {
+    ///
+    /// Rebuilds the ignorer if the batch contains any of the ignore files, returning it unless it
+    /// could not be rebuilt (in which case the previous ignorer remains in use).
+    ///
+    fn reload_if_changed(&self, batch: &InvalidationBatch) -> Option<Arc<GitignoreStyleExcludes>> {
+        let reload = self.reload.as_ref()?;
+        if !batch.paths.iter().any(|path| self.paths.contains(path)) {
+            return None;
+        }
+        match reload() {
+            Ok(ignorer) => {
+                info!("The ignore files changed: reloaded them, and invalidating all paths.");
+                Some(ignorer)
+            }
+            Err(e) => {
+                warn!("Failed to reload the ignore files, so the previous ones remain in use: {e}");
+                None
+            }
+        }
+    }
+}
+
+impl InvalidationBatch {
+    fn apply<I: Invalidatable>(self, invalidatable: &I, metrics: &WatchMetrics) {
+        metrics.record_batch(&self);
+        if self.invalidate_all {
+            debug!("notify queue overflowed: invalidating all paths");
+            invalidatable.invalidate_all(InvalidateCaller::Notify);
+        } else if !self.paths.is_empty() {
+            debug!(
+                "notify invalidating {:?} because of {} events",
+                self.paths, self.events
+            );
+            invalidatable.invalidate(&self.paths, InvalidateCaller::Notify);
+        }
+    }
+}
+
 pub struct InvalidationWatcher(Mutex<Inner>);
 
 impl InvalidationWatcher {
@@ -49,6 +216,10 @@ impl InvalidationWatcher {
         executor: Executor,
         build_root: PathBuf,
         ignorer: Arc<GitignoreStyleExcludes>,
+        ignored_events: Arc<GitignoreStyleExcludes>,
+        mode: WatchMode,
+        poll_interval: Duration,
+        coalesce_window: Duration,
     ) -> Result<Arc<InvalidationWatcher>, String> {
         // Inotify events contain canonical paths to the files being watched.
         // If the build_root contains a symlink the paths returned in notify events
@@ -57,6 +228,66 @@ impl InvalidationWatcher {
         let canonical_build_root =
             std::fs::canonicalize(build_root.as_path()).map_err(|e| format!("{e:?}"))?;
         let (watch_sender, watch_receiver) = crossbeam_channel::unbounded();
+
+        let watcher = match mode {
+            WatchMode::Polling | WatchMode::Watchman => None,
+            WatchMode::Native => Some(Self::native_watcher(
+                &canonical_build_root,
+                watch_sender.clone(),
+            )?),
+            WatchMode::Auto => {
+                match Self::native_watcher(&canonical_build_root, watch_sender.clone()) {
+                    Ok(watcher) => Some(watcher),
+                    Err(e) => {
+                        warn!("{e}\n\nFalling back to polling the filesystem for changes.");
+                        None
+                    }
+                }
+            }
+        };
+        let poller = match mode {
+            WatchMode::Native | WatchMode::Watchman => None,
+            WatchMode::Polling | WatchMode::Auto => Some(Poller::start(
+                poll_interval,
+                build_root.clone(),
+                canonical_build_root.clone(),
+                watch_sender.clone(),
+            )?),
+        };
+        let watchman = if mode == WatchMode::Watchman {
+            Some(Watchman::start(&canonical_build_root, watch_sender)?)
+        } else {
+            None
+        };
+
+        let (liveness_sender, liveness_receiver) = crossbeam_channel::unbounded();
+
+        Ok(Arc::new(InvalidationWatcher(Mutex::new(Inner {
+            watcher,
+            poller,
+            watchman,
+            fell_back_to_polling: false,
+            executor,
+            liveness: liveness_receiver,
+            metrics: Arc::default(),
+            build_root,
+            canonical_build_root: canonical_build_root.clone(),
+            ignore_files: IgnoreFiles::default(),
+            background_task_inputs: Some((
+                ignorer,
+                ignored_events,
+                canonical_build_root,
+                coalesce_window,
+                liveness_sender,
+                watch_receiver,
+            )),
+        }))))
+    }
+
+    fn native_watcher(
+        canonical_build_root: &Path,
+        watch_sender: crossbeam_channel::Sender<notify::Result<Event>>,
+    ) -> Result<RecommendedWatcher, String> {
         let mut watcher: RecommendedWatcher = notify::recommended_watcher(move |ev| {
             if watch_sender.send(ev).is_err() {
                 // The watch thread shutting down first is ok, because it can exit when the Invalidatable
@@ -72,31 +303,60 @@ impl InvalidationWatcher {
         })
         .map_err(|e| format!("Failed to begin watching the filesystem: {e}"))?;
 
-        let (liveness_sender, liveness_receiver) = crossbeam_channel::unbounded();
-
         // On darwin the notify API is much more efficient if you watch the build root
         // recursively, so we set up that watch here and then return early when watch() is
         // called by nodes that are running. On Linux the notify crate handles adding paths to watch
         // much more efficiently so we do that instead on Linux.
         if cfg!(target_os = "macos") {
             watcher
-                .watch(&canonical_build_root, RecursiveMode::Recursive)
+                .watch(canonical_build_root, RecursiveMode::Recursive)
                 .map_err(|e| {
                     format!("Failed to begin recursively watching files in the build root: {e}")
                 })?
         }
+        Ok(watcher)
+    }
 
-        Ok(Arc::new(InvalidationWatcher(Mutex::new(Inner {
-            watcher,
-            executor,
-            liveness: liveness_receiver,
-            background_task_inputs: Some((
-                ignorer,
-                canonical_build_root,
-                liveness_sender,
-                watch_receiver,
-            )),
-        }))))
+    ///
+    /// Watches the given ignore files (which the ignorer was created from, or would be if they
+    /// existed), and calls `reload` to rebuild the ignorer when they change, after which all paths
+    /// are invalidated. Must be called before `start`.
+    ///
+    /// Events for the ignore files are never ignored, even if they match the ignorer.
+    ///
+    pub async fn reload_ignorer_on_change(
+        self: &Arc<Self>,
+        ignore_files: Vec<PathBuf>,
+        reload: ReloadIgnorer,
+    ) {
+        let paths = {
+            let inner = self.0.lock();
+            ignore_files
+                .iter()
+                .map(|path| {
+                    path.strip_prefix(&inner.build_root)
+                        .or_else(|_| path.strip_prefix(&inner.canonical_build_root))
+                        .map_or_else(|_| path.clone(), Path::to_path_buf)
+                })
+                .collect()
+        };
+        // Watch the directories which contain the ignore files as well, in order to observe their
+        // creation.
+        let watched: HashSet<&Path> = ignore_files
+            .iter()
+            .flat_map(|path| [Some(path.as_path()), path.parent()])
+            .flatten()
+            .filter(|path| path.exists())
+            .collect();
+        for path in watched {
+            if let Err(e) = self.watch(path.to_owned()).await {
+                warn!("Changes to the ignore file(s) in {path:?} will not be reloaded: {e}");
+            }
+        }
+        self.0.lock().ignore_files = IgnoreFiles {
+            paths,
+            reload: Some(reload),
+        };
     }
 
     ///
@@ -104,7 +364,14 @@ impl InvalidationWatcher {
     ///
     pub fn start<I: Invalidatable>(&self, invalidatable: &Arc<I>) -> Result<(), String> {
         let mut inner = self.0.lock();
-        let (ignorer, canonical_build_root, liveness_sender, watch_receiver) = inner
+        let (
+            ignorer,
+            ignored_events,
+            canonical_build_root,
+            coalesce_window,
+            liveness_sender,
+            watch_receiver,
+        ) = inner
             .background_task_inputs
             .take()
             .expect("An InvalidationWatcher can only be started once.");
@@ -112,7 +379,11 @@ impl InvalidationWatcher {
         InvalidationWatcher::start_background_thread(
             Arc::downgrade(invalidatable),
             ignorer,
+            ignored_events,
+            std::mem::take(&mut inner.ignore_files),
             canonical_build_root,
+            coalesce_window,
+            inner.metrics.clone(),
             liveness_sender,
             watch_receiver,
         )?;
@@ -120,19 +391,41 @@ impl InvalidationWatcher {
         Ok(())
     }
 
+    ///
+    /// The metrics of the invalidations of this watcher: see `WatchMetrics::snapshot`.
+    ///
+    pub fn metrics(&self) -> Vec<(&'static str, u64)> {
+        self.0.lock().metrics.snapshot()
+    }
+
     // Public for testing purposes.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn start_background_thread<I: Invalidatable>(
         invalidatable: Weak<I>,
-        ignorer: Arc<GitignoreStyleExcludes>,
+        mut ignorer: Arc<GitignoreStyleExcludes>,
+        ignored_events: Arc<GitignoreStyleExcludes>,
+        ignore_files: IgnoreFiles,
         canonical_build_root: PathBuf,
+        coalesce_window: Duration,
+        metrics: Arc<WatchMetrics>,
         liveness_sender: crossbeam_channel::Sender<String>,
         watch_receiver: Receiver<notify::Result<Event>>,
     ) -> Result<thread::JoinHandle<()>, String> {
         thread::Builder::new()
             .name("fs-watcher".to_owned())
             .spawn(move || {
+                // The events which have been received since the first event of the current
+                // coalescing window, which will be invalidated together once the window ends.
+                let mut batch = InvalidationBatch::default();
+                let mut batch_deadline: Option<Instant> = None;
                 let exit_msg = loop {
-                    let event_res = watch_receiver.recv_timeout(Duration::from_millis(10));
+                    let timeout = batch_deadline.map_or(Duration::from_millis(10), |deadline| {
+                        std::cmp::min(
+                            deadline.saturating_duration_since(Instant::now()),
+                            Duration::from_millis(10),
+                        )
+                    });
+                    let event_res = watch_receiver.recv_timeout(timeout);
                     let invalidatable = if let Some(g) = invalidatable.upgrade() {
                         g
                     } else {
@@ -141,7 +434,15 @@ impl InvalidationWatcher {
                     };
                     match event_res {
                         Ok(Ok(ev)) => {
-                            Self::handle_event(&*invalidatable, &ignorer, &canonical_build_root, ev)
+                            Self::handle_event(
+                                &mut batch,
+                                &ignorer,
+                                &ignored_events,
+                                &ignore_files.paths,
+                                &canonical_build_root,
+                                ev,
+                            );
+                            batch_deadline.get_or_insert_with(|| Instant::now() + coalesce_window);
                         }
                         Ok(Err(err)) => {
                             if let notify::ErrorKind::PathNotFound = err.kind {
@@ -151,11 +452,20 @@ impl InvalidationWatcher {
                                 break format!("Watch error: {err}");
                             }
                         }
-                        Err(RecvTimeoutError::Timeout) => continue,
+                        Err(RecvTimeoutError::Timeout) => {}
                         Err(RecvTimeoutError::Disconnected) => {
                             break "The watch provider exited.".to_owned();
                         }
                     };
+                    if batch_deadline.is_some_and(|deadline| deadline <= Instant::now()) {
+                        batch_deadline = None;
+                        if let Some(reloaded) = ignore_files.reload_if_changed(&batch) {
+                            // Any path might have been ignored or unignored.
+                            ignorer = reloaded;
+                            batch.invalidate_all = true;
+                        }
+                        std::mem::take(&mut batch).apply(&*invalidatable, &metrics);
+                    }
                 };
 
                 // Log and send the exit code.
@@ -166,14 +476,16 @@ impl InvalidationWatcher {
     }
 
     ///
-    /// Handle a single invalidation Event.
+    /// Handle a single invalidation Event, by adding the paths which it invalidates to the batch.
     ///
     /// This method must not assume that it receives PreciseEvents, because construction does not
     /// validate that it is possible to enable them.
     ///
-    fn handle_event<I: Invalidatable>(
-        invalidatable: &I,
+    fn handle_event(
+        batch: &mut InvalidationBatch,
         ignorer: &GitignoreStyleExcludes,
+        ignored_events: &GitignoreStyleExcludes,
+        ignore_files: &HashSet<PathBuf>,
         canonical_build_root: &Path,
         ev: Event,
     ) {
@@ -216,9 +528,22 @@ impl InvalidationWatcher {
                 if ignorer.is_ignored_or_child_of_ignored_path(
                     &path_relative_to_build_root,
                     /* is_dir */ false,
-                ) {
+                ) && !is_git_metadata_path(&path_relative_to_build_root)
+                    && !ignore_files.contains(&path_relative_to_build_root)
+                {
                     trace!("notify ignoring {:?}", path_relative_to_build_root);
                     None
+                } else if ignored_events.is_ignored_or_child_of_ignored_path(
+                    &path_relative_to_build_root,
+                    /* is_dir */ false,
+                ) {
+                    // Unlike the paths which are ignored by the ignorer, these paths are visible
+                    // to Pants: but changes to them are never invalidated.
+                    trace!(
+                        "notify ignoring event for {:?}",
+                        path_relative_to_build_root
+                    );
+                    None
                 } else {
                     Some(path_relative_to_build_root)
                 }
@@ -237,12 +562,12 @@ impl InvalidationWatcher {
             })
             .collect();
 
+        batch.events += 1;
         if flag == Some(Flag::Rescan) {
-            debug!("notify queue overflowed: invalidating all paths");
-            invalidatable.invalidate_all(InvalidateCaller::Notify);
+            batch.invalidate_all = true;
         } else if !paths.is_empty() {
-            debug!("notify invalidating {:?} because of {:?}", paths, ev.kind);
-            invalidatable.invalidate(&paths, InvalidateCaller::Notify);
+            trace!("notify batching {:?} because of {:?}", paths, ev.kind);
+            batch.paths.extend(paths);
         }
     }
 
@@ -278,14 +603,17 @@ impl InvalidationWatcher {
     /// Add a path to the set of paths being watched by this invalidation watcher, non-recursively.
     ///
     pub async fn watch(self: &Arc<Self>, path: PathBuf) -> Result<(), String> {
-        if cfg!(target_os = "macos") {
-            // Short circuit here if we are on a Darwin platform because we should be watching
-            // the entire build root recursively already.
-            return Ok(());
-        }
-
         let executor = {
             let inner = self.0.lock();
+            if cfg!(target_os = "macos") && inner.watcher.is_some() {
+                // Short circuit here if we are on a Darwin platform because we should be watching
+                // the entire build root recursively already.
+                return Ok(());
+            }
+            if inner.watchman.is_some() {
+                // Watchman watches the entire build root.
+                return Ok(());
+            }
             inner.executor.clone()
         };
 
@@ -294,10 +622,29 @@ impl InvalidationWatcher {
             .spawn_blocking(
                 move || {
                     let mut inner = watcher.0.lock();
-                    inner
-                        .watcher
-                        .watch(&path, RecursiveMode::NonRecursive)
-                        .map_err(|e| maybe_enrich_notify_error(&path, e))
+                    let inner = &mut *inner;
+                    let Some(native_watcher) = &mut inner.watcher else {
+                        // Polling was selected, or native watching could not be started.
+                        inner.poller.as_ref().unwrap().watch(path);
+                        return Ok(());
+                    };
+                    let error = match native_watcher.watch(&path, RecursiveMode::NonRecursive) {
+                        Ok(()) => return Ok(()),
+                        Err(e) => maybe_enrich_notify_error(&path, e),
+                    };
+                    // If a poller is running, fall back to polling the path.
+                    let Some(poller) = &inner.poller else {
+                        return Err(error);
+                    };
+                    if !inner.fell_back_to_polling {
+                        inner.fell_back_to_polling = true;
+                        warn!(
+                            "{error}\n\nFalling back to polling for changes to this path, and to \
+                             any others which cannot be watched natively."
+                        );
+                    }
+                    poller.watch(path);
+                    Ok(())
                 },
                 |e| Err(format!("Watch attempt failed: {e}")),
             )
@@ -315,6 +662,26 @@ pub trait Invalidatable: Send + Sync + 'static {
     fn invalidate_all(&self, caller: InvalidateCaller) -> usize;
 }
 
+///
+/// Returns true if the given path (relative to the build root) is one of the files in the git
+/// metadata directory which determine the state of the repository.
+///
+/// Although the metadata directory is usually ignored, events for these paths are never ignored,
+/// because intrinsics which read the state of the repository depend on them.
+///
+fn is_git_metadata_path(path: &Path) -> bool {
+    let Ok(path) = path.strip_prefix(".git") else {
+        return false;
+    };
+    match path.components().next() {
+        None => true,
+        Some(Component::Normal(name)) => {
+            name == "HEAD" || name == "index" || name == "packed-refs" || name == "refs"
+        }
+        Some(_) => false,
+    }
+}
+
 ///
 /// If on Linux, attempt to report the relevant inotify limit(s).
 ///
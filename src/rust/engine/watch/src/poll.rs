@@ -0,0 +1,193 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crossbeam_channel::Sender;
+use hashing::Digest;
+use log::debug;
+use notify::event::{DataChange, ModifyKind};
+use notify::{Event, EventKind};
+use parking_lot::Mutex;
+
+///
+/// The state of a polled path, which is compared between polls to detect changes.
+///
+#[derive(Clone, Debug)]
+pub(crate) enum Fingerprint {
+    Missing,
+    File {
+        // NB: The modification time and length of the file are only used to skip hashing files
+        // which have not been modified: a file has only changed if its digest has.
+        modified: Option<SystemTime>,
+        len: u64,
+        digest: Digest,
+    },
+    Dir(Vec<OsString>),
+    Symlink(PathBuf),
+    // Any other kind of path, or a path which could not be read.
+    Other,
+}
+
+impl Fingerprint {
+    ///
+    /// Computes the fingerprint of the given path, reusing the digest of the previous fingerprint
+    /// (if any) of a file which has not been modified since.
+    ///
+    pub(crate) fn of(path: &Path, previous: Option<&Fingerprint>) -> Fingerprint {
+        let metadata = match fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Fingerprint::Missing,
+            Err(_) => return Fingerprint::Other,
+        };
+        let file_type = metadata.file_type();
+        let fingerprint = if file_type.is_file() {
+            let (modified, len) = (metadata.modified().ok(), metadata.len());
+            match previous {
+                Some(&Fingerprint::File {
+                    modified: previous_modified,
+                    len: previous_len,
+                    digest,
+                }) if modified.is_some()
+                    && modified == previous_modified
+                    && len == previous_len =>
+                {
+                    Ok(digest)
+                }
+                _ => File::open(path)
+                    .and_then(|mut file| hashing::sync_copy_and_hash(&mut file, &mut io::sink())),
+            }
+            .map(|digest| Fingerprint::File {
+                modified,
+                len,
+                digest,
+            })
+        } else if file_type.is_dir() {
+            fs::read_dir(path)
+                .and_then(|entries| {
+                    entries
+                        .map(|entry| entry.map(|entry| entry.file_name()))
+                        .collect::<io::Result<Vec<_>>>()
+                })
+                .map(|mut names| {
+                    names.sort();
+                    Fingerprint::Dir(names)
+                })
+        } else if file_type.is_symlink() {
+            fs::read_link(path).map(Fingerprint::Symlink)
+        } else {
+            Ok(Fingerprint::Other)
+        };
+        fingerprint.unwrap_or(Fingerprint::Other)
+    }
+
+    ///
+    /// The kind of the event for a change from this fingerprint to the given one, if the path has
+    /// changed.
+    ///
+    pub(crate) fn change_to(&self, current: &Fingerprint) -> Option<EventKind> {
+        match (self, current) {
+            (Fingerprint::File { digest: before, .. }, Fingerprint::File { digest: after, .. }) => {
+                (before != after)
+                    .then_some(EventKind::Modify(ModifyKind::Data(DataChange::Content)))
+            }
+            (Fingerprint::Missing, Fingerprint::Missing)
+            | (Fingerprint::Other, Fingerprint::Other) => None,
+            (Fingerprint::Dir(before), Fingerprint::Dir(after)) if before == after => None,
+            (Fingerprint::Symlink(before), Fingerprint::Symlink(after)) if before == after => None,
+            // Otherwise the path was created, removed, replaced, or (as a directory) had entries
+            // added or removed: all of which invalidate the parent of the path as well.
+            _ => Some(EventKind::Any),
+        }
+    }
+}
+
+///
+/// Polls the paths which cannot be watched natively (such as those on network filesystems, or in
+/// containers which do not support inotify) for changes, and sends events for them to the
+/// InvalidationWatcher in the same way as native watches do.
+///
+pub(crate) struct Poller {
+    build_root: PathBuf,
+    canonical_build_root: PathBuf,
+    // The background thread exits once this is dropped.
+    fingerprints: Arc<Mutex<HashMap<PathBuf, Fingerprint>>>,
+}
+
+impl Poller {
+    pub(crate) fn start(
+        interval: Duration,
+        build_root: PathBuf,
+        canonical_build_root: PathBuf,
+        events: Sender<notify::Result<Event>>,
+    ) -> Result<Poller, String> {
+        let fingerprints = Arc::new(Mutex::new(HashMap::new()));
+        let weak_fingerprints = Arc::downgrade(&fingerprints);
+        thread::Builder::new()
+            .name("fs-poller".to_owned())
+            .spawn(move || loop {
+                thread::sleep(interval);
+                let Some(fingerprints) = weak_fingerprints.upgrade() else {
+                    debug!("The poller was shut down.");
+                    break;
+                };
+                for event in poll(&fingerprints) {
+                    if events.send(Ok(event)).is_err() {
+                        debug!("Watch thread has shutdown, but the poller is still running.");
+                        return;
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to start fs-poller thread: {e}"))?;
+        Ok(Poller {
+            build_root,
+            canonical_build_root,
+            fingerprints,
+        })
+    }
+
+    ///
+    /// Starts polling the given path, if it is not already polled.
+    ///
+    /// NB: Like native watches, this computes the initial state of the path, so it should be called
+    /// before the path is read.
+    ///
+    pub(crate) fn watch(&self, path: PathBuf) {
+        // Events are relative to the canonical build root, as are those of native watches.
+        let path = match path.strip_prefix(&self.build_root) {
+            Ok(relative_path) => self.canonical_build_root.join(relative_path),
+            Err(_) => path,
+        };
+        if self.fingerprints.lock().contains_key(&path) {
+            return;
+        }
+        let fingerprint = Fingerprint::of(&path, None);
+        self.fingerprints.lock().entry(path).or_insert(fingerprint);
+    }
+}
+
+///
+/// Polls each of the paths once, returning events for those which have changed.
+///
+/// NB: The lock is not held while paths are read, so that paths may be added concurrently.
+///
+pub(crate) fn poll(fingerprints: &Mutex<HashMap<PathBuf, Fingerprint>>) -> Vec<Event> {
+    let paths: Vec<PathBuf> = fingerprints.lock().keys().cloned().collect();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let previous = fingerprints.lock().get(&path).cloned()?;
+            let current = Fingerprint::of(&path, Some(&previous));
+            let change = previous.change_to(&current);
+            fingerprints.lock().insert(path.clone(), current);
+            change.map(|kind| Event::new(kind).add_path(path))
+        })
+        .collect()
+}
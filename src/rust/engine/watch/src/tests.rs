@@ -1,16 +1,25 @@
 // Copyright 2022 Pants project contributors (see CONTRIBUTORS.md).
 // Licensed under the Apache License, Version 2.0 (see LICENSE).
-use crate::{Invalidatable, InvalidateCaller, InvalidationWatcher};
+use crate::poll::{self, Fingerprint};
+use crate::watchman;
+use crate::{
+    is_git_metadata_path, IgnoreFiles, Invalidatable, InvalidateCaller, InvalidationWatcher,
+    WatchMetrics, WatchMode,
+};
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs::create_dir;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::thread::sleep;
 use std::time::Duration;
 
 use crossbeam_channel::{self, RecvTimeoutError};
 use fs::GitignoreStyleExcludes;
+use notify::event::{DataChange, Flag, ModifyKind};
+use notify::EventKind;
+use serde_json::json;
 
 use parking_lot::Mutex;
 use task_executor::Executor;
@@ -32,10 +41,27 @@ async fn setup_watch(
     ignorer: Arc<GitignoreStyleExcludes>,
     build_root: PathBuf,
     file_path: PathBuf,
+) -> Arc<InvalidationWatcher> {
+    setup_watch_with_mode(ignorer, build_root, file_path, WatchMode::Native).await
+}
+
+async fn setup_watch_with_mode(
+    ignorer: Arc<GitignoreStyleExcludes>,
+    build_root: PathBuf,
+    file_path: PathBuf,
+    mode: WatchMode,
 ) -> Arc<InvalidationWatcher> {
     let executor = Executor::new();
-    let watcher = InvalidationWatcher::new(executor, build_root, ignorer)
-        .expect("Couldn't create InvalidationWatcher");
+    let watcher = InvalidationWatcher::new(
+        executor,
+        build_root,
+        ignorer,
+        GitignoreStyleExcludes::empty(),
+        mode,
+        Duration::from_millis(10),
+        Duration::ZERO,
+    )
+    .expect("Couldn't create InvalidationWatcher");
     watcher.watch(file_path).await.unwrap();
     watcher
 }
@@ -73,6 +99,62 @@ async fn receive_watch_event_on_file_change() {
     assert!(false, "Did not observe invalidation.")
 }
 
+#[tokio::test]
+async fn receive_poll_event_on_file_change() {
+    let (tempdir, file_path) = setup_fs();
+    let build_root = tempdir.path().to_path_buf();
+    let file_path_rel = file_path.strip_prefix(&build_root).unwrap().to_path_buf();
+
+    let invalidatable = Arc::new(TestInvalidatable::default());
+    let ignorer = GitignoreStyleExcludes::empty();
+    let watcher =
+        setup_watch_with_mode(ignorer, build_root, file_path.clone(), WatchMode::Polling).await;
+    watcher.start(&invalidatable).unwrap();
+
+    append_to_existing_file(&file_path, b"stnetnoc");
+
+    for _ in 0..10 {
+        sleep(Duration::from_millis(100));
+        if invalidatable.was_invalidated(&file_path_rel) {
+            return;
+        }
+    }
+    assert!(false, "Did not observe invalidation.")
+}
+
+#[test]
+fn poll_detects_changes_by_digest() {
+    let (tempdir, file_path) = setup_fs();
+    let dir_path = tempdir.path().join("foo");
+    let fingerprints = Mutex::new(HashMap::from([
+        (file_path.clone(), Fingerprint::of(&file_path, None)),
+        (dir_path.clone(), Fingerprint::of(&dir_path, None)),
+    ]));
+    let changes = || {
+        poll::poll(&fingerprints)
+            .into_iter()
+            .map(|event| (event.paths, event.kind))
+            .collect::<Vec<_>>()
+    };
+    assert!(changes().is_empty());
+
+    // Rewriting a file with the same content is not a change.
+    make_file(&file_path, b"contents", 0o600);
+    assert!(changes().is_empty());
+
+    append_to_existing_file(&file_path, b"stnetnoc");
+    assert_eq!(
+        vec![(
+            vec![file_path.clone()],
+            EventKind::Modify(ModifyKind::Data(DataChange::Content))
+        )],
+        changes()
+    );
+
+    make_file(&dir_path.join("new.txt"), b"", 0o600);
+    assert_eq!(vec![(vec![dir_path], EventKind::Any)], changes());
+}
+
 #[tokio::test]
 async fn ignore_file_events_matching_patterns_in_pants_ignore() {
     let (tempdir, file_path) = setup_fs();
@@ -102,6 +184,45 @@ async fn ignore_file_events_matching_patterns_in_pants_ignore() {
     }
 }
 
+#[tokio::test]
+async fn receive_watch_event_on_ignored_git_metadata_change() {
+    let tempdir = tempfile::TempDir::new().unwrap();
+    let build_root = tempdir.path().to_path_buf();
+    create_dir(build_root.join(".git")).unwrap();
+    let head_path = build_root.join(".git/HEAD");
+    make_file(&head_path, b"ref: refs/heads/main\n", 0o600);
+
+    let invalidatable = Arc::new(TestInvalidatable::default());
+    let ignorer = GitignoreStyleExcludes::create(vec![".*/".to_string()]).unwrap();
+    let watcher = setup_watch(ignorer, build_root, head_path.clone()).await;
+    watcher.start(&invalidatable).unwrap();
+
+    append_to_existing_file(&head_path, b"ref: refs/heads/other\n");
+
+    for _ in 0..10 {
+        sleep(Duration::from_millis(100));
+        if invalidatable.was_invalidated(Path::new(".git/HEAD")) {
+            return;
+        }
+    }
+    assert!(false, "Did not observe invalidation of git metadata.")
+}
+
+#[test]
+fn git_metadata_paths() {
+    for path in [".git", ".git/HEAD", ".git/index", ".git/refs/heads/main"] {
+        assert!(is_git_metadata_path(Path::new(path)), "{path}");
+    }
+    for path in [
+        ".git/objects/ab/cdef",
+        ".git/index.lock",
+        "src/.git/HEAD",
+        "HEAD",
+    ] {
+        assert!(!is_git_metadata_path(Path::new(path)), "{path}");
+    }
+}
+
 #[tokio::test]
 async fn liveness_watch_error() {
     let (tempdir, file_path) = setup_fs();
@@ -117,7 +238,11 @@ async fn liveness_watch_error() {
     let join_handle = InvalidationWatcher::start_background_thread(
         Arc::downgrade(&invalidatable),
         ignorer,
+        GitignoreStyleExcludes::empty(),
+        IgnoreFiles::default(),
         build_root,
+        Duration::ZERO,
+        Arc::default(),
         liveness_sender,
         event_receiver,
     )
@@ -141,9 +266,236 @@ async fn liveness_watch_error() {
     join_handle.join().unwrap();
 }
 
+#[tokio::test]
+async fn coalesce_events_within_window() {
+    let (tempdir, _) = setup_fs();
+    let build_root = tempdir.path().canonicalize().unwrap();
+
+    let invalidatable = Arc::new(TestInvalidatable::default());
+    let metrics = Arc::new(WatchMetrics::default());
+    let (liveness_sender, _liveness_receiver) = crossbeam_channel::unbounded();
+    let (event_sender, event_receiver) = crossbeam_channel::unbounded();
+    let _join_handle = InvalidationWatcher::start_background_thread(
+        Arc::downgrade(&invalidatable),
+        GitignoreStyleExcludes::empty(),
+        GitignoreStyleExcludes::empty(),
+        IgnoreFiles::default(),
+        build_root.clone(),
+        Duration::from_millis(500),
+        metrics.clone(),
+        liveness_sender,
+        event_receiver,
+    )
+    .unwrap();
+
+    for name in ["a.txt", "b.txt", "a.txt"] {
+        let data_change = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+        event_sender
+            .send(Ok(
+                notify::Event::new(data_change).add_path(build_root.join("foo").join(name))
+            ))
+            .unwrap();
+    }
+
+    // The events are not invalidated until the window has ended, and then in a single pass.
+    sleep(Duration::from_millis(100));
+    assert!(invalidatable.calls.lock().is_empty());
+    for _ in 0..20 {
+        if !invalidatable.calls.lock().is_empty() {
+            break;
+        }
+        sleep(Duration::from_millis(100));
+    }
+    let expected: HashSet<PathBuf> = [PathBuf::from("foo/a.txt"), PathBuf::from("foo/b.txt")]
+        .into_iter()
+        .collect();
+    assert_eq!(*invalidatable.calls.lock(), vec![expected]);
+
+    let metrics: HashMap<_, _> = metrics.snapshot().into_iter().collect();
+    assert_eq!(metrics["watcher_events"], 3);
+    assert_eq!(metrics["watcher_invalidation_batches"], 1);
+    assert_eq!(metrics["watcher_invalidated_paths"], 2);
+    assert_eq!(metrics["watcher_max_invalidation_batch_paths"], 2);
+}
+
+#[tokio::test]
+async fn ignore_events_matching_globs() {
+    let (tempdir, _) = setup_fs();
+    let build_root = tempdir.path().canonicalize().unwrap();
+
+    let invalidatable = Arc::new(TestInvalidatable::default());
+    let (liveness_sender, _liveness_receiver) = crossbeam_channel::unbounded();
+    let (event_sender, event_receiver) = crossbeam_channel::unbounded();
+    let _join_handle = InvalidationWatcher::start_background_thread(
+        Arc::downgrade(&invalidatable),
+        GitignoreStyleExcludes::empty(),
+        GitignoreStyleExcludes::create(vec!["bazel-out/".to_owned(), "*.swp".to_owned()]).unwrap(),
+        IgnoreFiles::default(),
+        build_root.clone(),
+        Duration::ZERO,
+        Arc::default(),
+        liveness_sender,
+        event_receiver,
+    )
+    .unwrap();
+
+    for path in [
+        "bazel-out/bin/out.o",
+        "foo/.watch_me.txt.swp",
+        "foo/watch_me.txt",
+    ] {
+        let data_change = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+        event_sender
+            .send(Ok(
+                notify::Event::new(data_change).add_path(build_root.join(path))
+            ))
+            .unwrap();
+    }
+
+    // Only the last event is invalidated: the others are processed before it.
+    for _ in 0..20 {
+        if !invalidatable.calls.lock().is_empty() {
+            break;
+        }
+        sleep(Duration::from_millis(100));
+    }
+    let expected: HashSet<PathBuf> = [PathBuf::from("foo/watch_me.txt")].into_iter().collect();
+    assert_eq!(*invalidatable.calls.lock(), vec![expected]);
+}
+
+#[tokio::test]
+async fn reload_ignorer_on_ignore_file_change() {
+    let (tempdir, _) = setup_fs();
+    let build_root = tempdir.path().canonicalize().unwrap();
+
+    let invalidatable = Arc::new(TestInvalidatable::default());
+    let reloads = Arc::new(AtomicUsize::new(0));
+    let ignore_files = IgnoreFiles {
+        paths: [PathBuf::from(".gitignore")].into_iter().collect(),
+        reload: Some(Box::new({
+            let reloads = reloads.clone();
+            move || {
+                reloads.fetch_add(1, Ordering::SeqCst);
+                Ok(GitignoreStyleExcludes::empty())
+            }
+        })),
+    };
+    let (liveness_sender, _liveness_receiver) = crossbeam_channel::unbounded();
+    let (event_sender, event_receiver) = crossbeam_channel::unbounded();
+    let _join_handle = InvalidationWatcher::start_background_thread(
+        Arc::downgrade(&invalidatable),
+        // NB: The ignore file is not ignored, even though it matches the ignorer.
+        GitignoreStyleExcludes::create(vec!["*.txt".to_owned(), ".gitignore".to_owned()]).unwrap(),
+        GitignoreStyleExcludes::empty(),
+        ignore_files,
+        build_root.clone(),
+        Duration::ZERO,
+        Arc::default(),
+        liveness_sender,
+        event_receiver,
+    )
+    .unwrap();
+    let send = |path: &str| {
+        let data_change = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+        event_sender
+            .send(Ok(
+                notify::Event::new(data_change).add_path(build_root.join(path))
+            ))
+            .unwrap();
+    };
+    let wait_for = |condition: &dyn Fn() -> bool| {
+        for _ in 0..20 {
+            if condition() {
+                return;
+            }
+            sleep(Duration::from_millis(100));
+        }
+        panic!("Timed out waiting for the watcher.");
+    };
+
+    // Before the ignore file changes, the ignorer applies.
+    send("foo/ignored.txt");
+    send(".gitignore");
+    wait_for(&|| invalidatable.invalidated_all.load(Ordering::SeqCst) == 1);
+    assert_eq!(reloads.load(Ordering::SeqCst), 1);
+    assert!(!invalidatable.was_invalidated(Path::new("foo/ignored.txt")));
+
+    // And after it changes, the reloaded ignorer does.
+    send("foo/ignored.txt");
+    wait_for(&|| invalidatable.was_invalidated(Path::new("foo/ignored.txt")));
+    assert_eq!(reloads.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn watchman_subscription_events() {
+    let build_root = PathBuf::from("/build/root");
+    let mut initial = true;
+    let events = |message, initial: &mut bool| {
+        watchman::subscription_events(&message, &build_root, initial)
+            .unwrap()
+            .into_iter()
+            .map(|event| {
+                let flag = event.flag();
+                (event.kind, event.paths, flag)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // The initial fresh instance is empty, because Pants has not read anything yet.
+    let fresh_instance = json!({
+        "subscription": "pants",
+        "is_fresh_instance": true,
+        "files": [],
+    });
+    assert_eq!(events(fresh_instance.clone(), &mut initial), vec![]);
+
+    let changes = json!({
+        "subscription": "pants",
+        "is_fresh_instance": false,
+        "files": [
+            {"name": "src/changed.txt", "exists": true, "new": false},
+            {"name": "src/created.txt", "exists": true, "new": true},
+            {"name": "src/removed.txt", "exists": false, "new": false},
+        ],
+    });
+    assert_eq!(
+        events(changes, &mut initial),
+        vec![
+            (
+                EventKind::Modify(ModifyKind::Data(DataChange::Content)),
+                vec![build_root.join("src/changed.txt")],
+                None
+            ),
+            (
+                EventKind::Any,
+                vec![build_root.join("src/created.txt")],
+                None
+            ),
+            (
+                EventKind::Any,
+                vec![build_root.join("src/removed.txt")],
+                None
+            ),
+        ]
+    );
+
+    // Messages of other subscriptions, or without files, are ignored.
+    let other = json!({"subscription": "other", "files": [{"name": "a", "exists": true}]});
+    assert_eq!(events(other, &mut initial), vec![]);
+    let state = json!({"subscription": "pants", "state-enter": "hg.update"});
+    assert_eq!(events(state, &mut initial), vec![]);
+
+    // But any later fresh instance means that Watchman lost track of changes.
+    assert_eq!(
+        events(fresh_instance, &mut initial),
+        vec![(EventKind::Other, vec![], Some(Flag::Rescan))]
+    );
+}
+
 #[derive(Default)]
 struct TestInvalidatable {
     pub calls: Mutex<Vec<HashSet<PathBuf>>>,
+    pub invalidated_all: AtomicUsize,
 }
 
 impl TestInvalidatable {
@@ -162,6 +514,7 @@ impl Invalidatable for TestInvalidatable {
     }
 
     fn invalidate_all(&self, _caller: InvalidateCaller) -> usize {
-        unimplemented!();
+        self.invalidated_all.fetch_add(1, Ordering::SeqCst);
+        0
     }
 }
@@ -0,0 +1,224 @@
+// Copyright 2024 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::env;
+use std::io::{BufRead, BufReader, Write};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+
+use crossbeam_channel::Sender;
+use log::debug;
+use notify::event::{DataChange, Flag, ModifyKind};
+use notify::{Event, EventKind};
+use serde_json::{json, Value};
+
+/// The name of the subscription of pantsd.
+const SUBSCRIPTION: &str = "pants";
+
+///
+/// Subscribes to the changes to the build root which are observed by a Watchman server, and sends
+/// events for them to the InvalidationWatcher in the same way as native watches do.
+///
+/// Watchman watches the whole build root, so individual paths do not need to be watched.
+///
+pub(crate) struct Watchman {
+    // The background thread exits once this is shut down.
+    stream: UnixStream,
+}
+
+impl Watchman {
+    pub(crate) fn start(
+        canonical_build_root: &Path,
+        events: Sender<notify::Result<Event>>,
+    ) -> Result<Watchman, String> {
+        let stream = UnixStream::connect(sockname()?)
+            .map_err(|e| format!("Failed to connect to the Watchman server: {e}"))?;
+        let mut reader = BufReader::new(
+            stream
+                .try_clone()
+                .map_err(|e| format!("Failed to connect to the Watchman server: {e}"))?,
+        );
+        let mut writer = &stream;
+
+        let watch = command(
+            &mut writer,
+            &mut reader,
+            json!(["watch-project", canonical_build_root]),
+        )?;
+        let watch_root = watch
+            .get("watch")
+            .and_then(Value::as_str)
+            .ok_or_else(|| format!("Unexpected response from Watchman: {watch}"))?;
+        // The build root may be within a directory which was already watched by Watchman (a
+        // "project"), in which case the changes to it are subscribed to relative to that directory.
+        let mut subscription = json!({
+            "fields": ["name", "exists", "new"],
+            // The changes before the subscription was started were already observed by the
+            // Scheduler, so there is nothing to invalidate initially.
+            "empty_on_fresh_instance": true,
+        });
+        if let Some(relative_path) = watch.get("relative_path") {
+            subscription["relative_root"] = relative_path.clone();
+        }
+        command(
+            &mut writer,
+            &mut reader,
+            json!(["subscribe", watch_root, SUBSCRIPTION, subscription]),
+        )?;
+
+        let canonical_build_root = canonical_build_root.to_owned();
+        thread::Builder::new()
+            .name("fs-watchman".to_owned())
+            .spawn(move || {
+                let mut initial = true;
+                let error = loop {
+                    let message = match read_message(&mut reader) {
+                        Ok(message) => message,
+                        Err(e) => break e,
+                    };
+                    match subscription_events(&message, &canonical_build_root, &mut initial) {
+                        Ok(subscription_events) => {
+                            for event in subscription_events {
+                                if events.send(Ok(event)).is_err() {
+                                    debug!(
+                                        "Watch thread has shutdown, but Watchman is still running."
+                                    );
+                                    return;
+                                }
+                            }
+                        }
+                        Err(e) => break e,
+                    }
+                };
+                // NB: This kills the watch thread, so that the InvalidationWatcher is restarted.
+                let _ = events.send(Err(notify::Error::generic(&error)));
+            })
+            .map_err(|e| format!("Failed to start fs-watchman thread: {e}"))?;
+        Ok(Watchman { stream })
+    }
+}
+
+impl Drop for Watchman {
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+///
+/// The path of the socket of the Watchman server, which is started if it is not already running.
+///
+fn sockname() -> Result<PathBuf, String> {
+    if let Some(sockname) = env::var_os("WATCHMAN_SOCK") {
+        return Ok(sockname.into());
+    }
+    let output = Command::new("watchman")
+        .args(["--output-encoding=json", "--no-pretty", "get-sockname"])
+        .output()
+        .map_err(|e| format!("Failed to run `watchman`, which must be installed: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to start the Watchman server ({}):\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    let response: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Unexpected output from `watchman get-sockname`: {e}"))?;
+    response
+        .get("unix_domain")
+        .or_else(|| response.get("sockname"))
+        .and_then(Value::as_str)
+        .map(PathBuf::from)
+        .ok_or_else(|| format!("Unexpected output from `watchman get-sockname`: {response}"))
+}
+
+///
+/// Sends a command to the Watchman server, and waits for its response.
+///
+fn command(
+    writer: &mut impl Write,
+    reader: &mut impl BufRead,
+    command: Value,
+) -> Result<Value, String> {
+    writeln!(writer, "{command}")
+        .and_then(|()| writer.flush())
+        .map_err(|e| format!("Failed to send a command to the Watchman server: {e}"))?;
+    loop {
+        let response = read_message(reader)?;
+        // Unilateral messages (such as those of subscriptions) may precede the response.
+        if response.get("unilateral").is_none() && response.get("subscription").is_none() {
+            return Ok(response);
+        }
+    }
+}
+
+///
+/// Reads the next message from the Watchman server, which is a line of JSON.
+///
+fn read_message(reader: &mut impl BufRead) -> Result<Value, String> {
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(0) => Err("The Watchman server closed the connection.".to_owned()),
+        Ok(_) => {
+            let message: Value = serde_json::from_str(&line)
+                .map_err(|e| format!("Unexpected message from the Watchman server: {e}"))?;
+            match message.get("error") {
+                Some(error) => Err(format!("Watchman error: {error}")),
+                None => Ok(message),
+            }
+        }
+        Err(e) => Err(format!("Failed to read from the Watchman server: {e}")),
+    }
+}
+
+///
+/// Converts a message of the subscription into events for the changed paths, which Watchman names
+/// relative to the build root.
+///
+/// A "fresh instance" after the initial one means that Watchman could not determine which paths
+/// changed (because the server restarted, or had to recrawl the build root), and so it is
+/// converted into a rescan, which invalidates all paths.
+///
+pub(crate) fn subscription_events(
+    message: &Value,
+    canonical_build_root: &Path,
+    initial: &mut bool,
+) -> Result<Vec<Event>, String> {
+    if message.get("subscription").and_then(Value::as_str) != Some(SUBSCRIPTION) {
+        // Some other unilateral message, such as a log message.
+        return Ok(vec![]);
+    }
+    let Some(files) = message.get("files").and_then(Value::as_array) else {
+        // A notification of a state change (such as a VCS operation entering a "state").
+        return Ok(vec![]);
+    };
+    if message.get("is_fresh_instance").and_then(Value::as_bool) == Some(true) {
+        if std::mem::replace(initial, false) {
+            return Ok(vec![]);
+        }
+        return Ok(vec![Event::new(EventKind::Other).set_flag(Flag::Rescan)]);
+    }
+    *initial = false;
+
+    files
+        .iter()
+        .map(|file| {
+            let name = file
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or_else(|| format!("Unexpected file in Watchman subscription: {file}"))?;
+            let is_flag = |field: &str| file.get(field).and_then(Value::as_bool) == Some(true);
+            // Only the content of a file which existed before and after the change has changed:
+            // otherwise it was created or removed, which also changes its parent directory.
+            let kind = if is_flag("exists") && !is_flag("new") {
+                EventKind::Modify(ModifyKind::Data(DataChange::Content))
+            } else {
+                EventKind::Any
+            };
+            Ok(Event::new(kind).add_path(canonical_build_root.join(name)))
+        })
+        .collect()
+}
@@ -17,6 +17,7 @@ use deepsize::DeepSizeOf;
 use hdrhistogram::serialization::Serializer;
 use log::log;
 pub use log::Level;
+use metrics::validate_custom_metric_name;
 pub use metrics::{Metric, ObservationMetric};
 use parking_lot::Mutex;
 use petgraph::stable_graph::{NodeIndex, StableDiGraph};
@@ -144,6 +145,15 @@ impl RunningWorkunitGraph {
         }
     }
 
+    /// Count the running workunits with any of the given names.
+    fn running_count(&self, names: &[&str]) -> usize {
+        self.entries
+            .values()
+            .filter_map(|(_, _, workunit)| workunit.as_ref())
+            .filter(|workunit| names.contains(&workunit.name))
+            .count()
+    }
+
     /// Return the non-blocked leaves of the graph.
     fn running_leaves(&self) -> impl Iterator<Item = SpanId> + '_ {
         self.graph
@@ -359,6 +369,40 @@ pub struct WorkunitStore {
     streaming_workunit_data: Arc<Mutex<StreamingWorkunitData>>,
     heavy_hitters_data: Arc<Mutex<HeavyHittersData>>,
     metrics_data: Arc<MetricsData>,
+    live_output_data: Arc<LiveOutputData>,
+}
+
+///
+/// The most recent output of the running workunits which produce output (i.e. processes), which is
+/// only recorded while a consumer (i.e. the `experimental-tui` renderer) has enabled it.
+///
+#[derive(Default)]
+struct LiveOutputData {
+    enabled: AtomicBool,
+    outputs: Mutex<HashMap<SpanId, BytesMut>>,
+}
+
+impl LiveOutputData {
+    /// The most output which is retained for each workunit: older output is discarded first.
+    const MAX_BYTES: usize = 64 * 1024;
+
+    fn append(&self, span_id: SpanId, bytes: &[u8]) {
+        if !self.enabled.load(atomic::Ordering::Relaxed) {
+            return;
+        }
+        let mut outputs = self.outputs.lock();
+        let output = outputs.entry(span_id).or_default();
+        output.extend_from_slice(bytes);
+        if output.len() > Self::MAX_BYTES {
+            let _ = output.split_to(output.len() - Self::MAX_BYTES);
+        }
+    }
+
+    fn remove(&self, span_id: SpanId) {
+        if self.enabled.load(atomic::Ordering::Relaxed) {
+            self.outputs.lock().remove(&span_id);
+        }
+    }
 }
 
 struct StreamingWorkunitData {
@@ -519,6 +563,11 @@ impl HeavyHittersData {
         stragglers
     }
 
+    fn running_count(&mut self, names: &[&str]) -> usize {
+        self.refresh_store();
+        self.running_graph.running_count(names)
+    }
+
     fn is_visible(level: Level, workunit: Option<&Workunit>) -> bool {
         level <= Level::Debug
             && workunit
@@ -556,6 +605,7 @@ impl WorkunitStore {
             streaming_workunit_data: Arc::new(Mutex::new(StreamingWorkunitData::new(receiver1))),
             heavy_hitters_data: Arc::new(Mutex::new(HeavyHittersData::new(receiver2))),
             metrics_data: Arc::default(),
+            live_output_data: Arc::default(),
         }
     }
 
@@ -588,6 +638,46 @@ impl WorkunitStore {
         self.heavy_hitters_data.lock().heavy_hitters(k)
     }
 
+    ///
+    /// Count the running workunits (whether blocked or not) with any of the given names, e.g. to
+    /// count the processes which are running.
+    ///
+    pub fn running_count(&self, names: &[&str]) -> usize {
+        self.heavy_hitters_data.lock().running_count(names)
+    }
+
+    ///
+    /// Enables or disables the recording of the live output of running workunits: see
+    /// `Self::live_output`. Disabling recording discards any output which was recorded.
+    ///
+    pub fn set_live_output_enabled(&self, enabled: bool) {
+        self.live_output_data
+            .enabled
+            .store(enabled, atomic::Ordering::Relaxed);
+        if !enabled {
+            self.live_output_data.outputs.lock().clear();
+        }
+    }
+
+    ///
+    /// Records output produced by the given running workunit, if recording is enabled.
+    ///
+    pub fn append_live_output(&self, span_id: SpanId, bytes: &[u8]) {
+        self.live_output_data.append(span_id, bytes)
+    }
+
+    ///
+    /// The most recent output of the given workunit, if it is running and has produced any since
+    /// recording was enabled.
+    ///
+    pub fn live_output(&self, span_id: SpanId) -> Option<Bytes> {
+        self.live_output_data
+            .outputs
+            .lock()
+            .get(&span_id)
+            .map(|output| Bytes::copy_from_slice(output))
+    }
+
     fn send(&self, msg: StoreMsg) {
         let send_inner = |sender: &UnboundedSender<StoreMsg>, msg: StoreMsg| {
             sender
@@ -637,6 +727,7 @@ impl WorkunitStore {
     }
 
     fn cancel_workunit(&self, workunit: Workunit) {
+        self.live_output_data.remove(workunit.span_id);
         workunit.log_workunit_state(true);
         self.send(StoreMsg::Canceled(
             workunit.span_id,
@@ -649,6 +740,7 @@ impl WorkunitStore {
         let span_id = workunit.span_id;
         let new_metadata = workunit.metadata.clone();
 
+        self.live_output_data.remove(span_id);
         self.send(StoreMsg::Completed(span_id, level, new_metadata, end_time));
 
         let start_time = match workunit.state {
@@ -706,11 +798,60 @@ impl WorkunitStore {
             .or_insert(change);
     }
 
-    pub fn get_metrics(&self) -> HashMap<&'static str, u64> {
+    ///
+    /// Increments a custom counter, recorded by a plugin rather than by the engine. Custom
+    /// counters are reported alongside the builtin `Metric`s.
+    ///
+    pub fn increment_custom_counter(&self, name: &str, change: u64) -> Result<(), String> {
+        validate_custom_metric_name(name)?;
+        *self
+            .metrics_data
+            .custom_counters
+            .lock()
+            .entry(name.to_owned())
+            .or_insert(0) += change;
+        Ok(())
+    }
+
+    ///
+    /// Marks a custom counter to be displayed live by the dynamic UI, e.g. to show how many tests
+    /// have passed so far. Counters are displayed in the order in which they were first marked.
+    ///
+    pub fn display_custom_counter(&self, name: &str) -> Result<(), String> {
+        validate_custom_metric_name(name)?;
+        let mut displayed_counters = self.metrics_data.displayed_counters.lock();
+        if !displayed_counters.iter().any(|displayed| displayed == name) {
+            displayed_counters.push(name.to_owned());
+        }
+        Ok(())
+    }
+
+    ///
+    /// The values of the custom counters which are displayed by the dynamic UI.
+    ///
+    pub fn displayed_counters(&self) -> Vec<(String, u64)> {
+        let displayed_counters = self.metrics_data.displayed_counters.lock();
+        let custom_counters = self.metrics_data.custom_counters.lock();
+        displayed_counters
+            .iter()
+            .map(|name| {
+                let value = custom_counters.get(name).copied().unwrap_or_default();
+                (name.clone(), value)
+            })
+            .collect()
+    }
+
+    pub fn get_metrics(&self) -> HashMap<String, u64> {
         let counters = self.metrics_data.counters.lock();
+        let custom_counters = self.metrics_data.custom_counters.lock();
         counters
             .iter()
-            .map(|(metric, value)| (metric.into(), *value))
+            .map(|(metric, value)| (<&str>::from(metric).to_owned(), *value))
+            .chain(
+                custom_counters
+                    .iter()
+                    .map(|(name, value)| (name.clone(), *value)),
+            )
             .collect()
     }
 
@@ -719,43 +860,84 @@ impl WorkunitStore {
     ///
     pub fn record_observation(&self, metric: ObservationMetric, value: u64) {
         let mut histograms_by_metric = self.metrics_data.observations.lock();
-        histograms_by_metric
+        let _ = histograms_by_metric
             .entry(metric)
-            .and_modify(|h| {
-                let _ = h.record(value);
-            })
-            .or_insert_with(|| {
-                let mut h =
-                    hdrhistogram::Histogram::<u64>::new(3).expect("Failed to allocate histogram");
-                let _ = h.record(value);
-                h
-            });
+            .or_insert_with(new_histogram)
+            .record(value);
+    }
+
+    ///
+    /// Records an observation of a custom metric, recorded by a plugin rather than by the engine.
+    /// Custom observations are reported alongside the builtin `ObservationMetric`s.
+    ///
+    pub fn record_custom_observation(&self, name: &str, value: u64) -> Result<(), String> {
+        validate_custom_metric_name(name)?;
+        let mut histograms_by_name = self.metrics_data.custom_observations.lock();
+        let _ = histograms_by_name
+            .entry(name.to_owned())
+            .or_insert_with(new_histogram)
+            .record(value);
+        Ok(())
+    }
+
+    ///
+    /// Applies the given function to the histograms of both the builtin and the custom
+    /// observations, by name.
+    ///
+    fn map_histograms<T>(
+        &self,
+        mut f: impl FnMut(&str, &hdrhistogram::Histogram<u64>) -> T,
+    ) -> HashMap<String, T> {
+        let histograms_by_metric = self.metrics_data.observations.lock();
+        let custom_histograms_by_name = self.metrics_data.custom_observations.lock();
+        histograms_by_metric
+            .iter()
+            .map(|(metric, histogram)| (<&str>::from(metric), histogram))
+            .chain(
+                custom_histograms_by_name
+                    .iter()
+                    .map(|(name, histogram)| (name.as_str(), histogram)),
+            )
+            .map(|(name, histogram)| (name.to_owned(), f(name, histogram)))
+            .collect()
     }
 
     ///
     /// Return all observations in binary encoded format.
     ///
-    pub fn encode_observations(&self) -> Result<HashMap<&'static str, Bytes>, String> {
+    pub fn encode_observations(&self) -> Result<HashMap<String, Bytes>, String> {
         use hdrhistogram::serialization::V2DeflateSerializer;
 
         let mut serializer = V2DeflateSerializer::new();
 
-        let mut result = HashMap::new();
-
-        let histograms_by_metric = self.metrics_data.observations.lock();
-        for (metric, histogram) in histograms_by_metric.iter() {
+        self.map_histograms(|name, histogram| -> Result<Bytes, String> {
             let mut writer = BytesMut::new().writer();
 
             serializer
                 .serialize(histogram, &mut writer)
-                .map_err(
-                    |err| format!("Failed to encode histogram for key `{metric:?}`: {err}",),
-                )?;
+                .map_err(|err| format!("Failed to encode histogram for key `{name}`: {err}",))?;
 
-            result.insert(metric.into(), writer.into_inner().freeze());
-        }
+            Ok(writer.into_inner().freeze())
+        })
+        .into_iter()
+        .map(|(name, encoded)| encoded.map(|encoded| (name, encoded)))
+        .collect()
+    }
 
-        Ok(result)
+    ///
+    /// Return all observations as counts of the observations in each of the
+    /// `OBSERVATION_BUCKET_BOUNDS`. Unlike the histograms, these can be summed across stores.
+    ///
+    pub fn observation_buckets(&self) -> HashMap<String, ObservationBuckets> {
+        self.map_histograms(|_, histogram| ObservationBuckets {
+            counts: OBSERVATION_BUCKET_BOUNDS
+                .iter()
+                .map(|bound| histogram.count_between(0, *bound))
+                .collect(),
+            count: histogram.len(),
+            // NB: Histograms do not record the exact sum of their observations.
+            sum: (histogram.mean() * histogram.len() as f64) as u64,
+        })
     }
 
     pub fn setup_for_tests() -> (WorkunitStore, RunningWorkunit) {
@@ -829,6 +1011,16 @@ pub fn record_observation_if_in_workunit(metric: ObservationMetric, value: u64)
         handle.store.record_observation(metric, value)
     }
 }
+/// If this thread is running in a workunit, record `bytes` as live output of the workunit.
+pub fn append_live_output_if_in_workunit(bytes: &[u8]) {
+    if let Some(WorkunitStoreHandle {
+        store,
+        parent_id: Some(span_id),
+    }) = get_workunit_store_handle()
+    {
+        store.append_live_output(span_id, bytes)
+    }
+}
 
 /// Run the given async block. If the level given by the WorkunitMetadata is above a configured
 /// threshold, the block will run inside of a workunit recorded in the workunit store.
@@ -955,10 +1147,57 @@ impl Drop for BlockingWorkunitToken {
     }
 }
 
+///
+/// The (inclusive) upper bounds of the buckets that `WorkunitStore::observation_buckets` counts
+/// observations into. Observation metrics have different units, so the bounds span many orders of
+/// magnitude.
+///
+pub const OBSERVATION_BUCKET_BOUNDS: [u64; 19] = [
+    1,
+    5,
+    10,
+    50,
+    100,
+    500,
+    1_000,
+    5_000,
+    10_000,
+    50_000,
+    100_000,
+    500_000,
+    1_000_000,
+    5_000_000,
+    10_000_000,
+    50_000_000,
+    100_000_000,
+    500_000_000,
+    1_000_000_000,
+];
+
+///
+/// The observations of a metric, bucketed by `OBSERVATION_BUCKET_BOUNDS`.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ObservationBuckets {
+    /// The cumulative count of the observations which are less than or equal to each bound.
+    pub counts: Vec<u64>,
+    /// The total count of the observations.
+    pub count: u64,
+    /// The (approximate) sum of the observations.
+    pub sum: u64,
+}
+
 #[derive(Default)]
 struct MetricsData {
     counters: Mutex<HashMap<Metric, u64>>,
     observations: Mutex<HashMap<ObservationMetric, hdrhistogram::Histogram<u64>>>,
+    custom_counters: Mutex<HashMap<String, u64>>,
+    custom_observations: Mutex<HashMap<String, hdrhistogram::Histogram<u64>>>,
+    displayed_counters: Mutex<Vec<String>>,
+}
+
+fn new_histogram() -> hdrhistogram::Histogram<u64> {
+    hdrhistogram::Histogram::<u64>::new(3).expect("Failed to allocate histogram")
 }
 
 ///
@@ -76,7 +76,9 @@ impl Metric {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, strum_macros::IntoStaticStr)]
+#[derive(
+    Clone, Copy, PartialEq, Eq, Hash, Debug, strum_macros::IntoStaticStr, strum_macros::EnumIter,
+)]
 #[strum(serialize_all = "snake_case")]
 pub enum ObservationMetric {
     TestObservation,
@@ -102,4 +104,32 @@ pub enum ObservationMetric {
     RemoteCacheGetActionResultTimeMicros,
     /// Remote cache timing (in microseconds) for GetActionResult calls (network timing only).
     RemoteCacheGetActionResultNetworkTimeMicros,
+    /// The number of blocking tasks (such as file I/O) which were waiting for a thread when another
+    /// blocking task was spawned.
+    ExecutorBlockingQueueDepth,
+    /// The time (in microseconds) that a blocking task waited for a thread before it started.
+    ExecutorBlockingTaskDelayMicros,
+}
+
+///
+/// Validates the name of a custom metric recorded by a plugin: it must be in snake_case, and must
+/// not collide with the name of a builtin `Metric` or `ObservationMetric`.
+///
+pub fn validate_custom_metric_name(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let is_snake_case = chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !is_snake_case {
+        return Err(format!(
+            "The name of a custom metric must be in snake_case, but got `{name}`."
+        ));
+    }
+    let is_builtin = Metric::iter().any(|metric| <&str>::from(metric) == name)
+        || ObservationMetric::iter().any(|metric| <&str>::from(metric) == name);
+    if is_builtin {
+        return Err(format!(
+            "`{name}` is the name of a builtin metric, and cannot be used for a custom metric."
+        ));
+    }
+    Ok(())
 }
@@ -6,7 +6,11 @@ use std::time::Duration;
 
 use internment::Intern;
 
-use crate::{Level, ParentIds, SpanId, WorkunitMetadata, WorkunitState, WorkunitStore};
+use crate::{
+    append_live_output_if_in_workunit, expect_workunit_store_handle, Level, Metric,
+    ObservationMetric, ParentIds, SpanId, WorkunitMetadata, WorkunitState, WorkunitStore,
+    OBSERVATION_BUCKET_BOUNDS,
+};
 
 #[test]
 fn heavy_hitters_basic() {
@@ -60,6 +64,61 @@ fn straggling_workunits_blocked_path() {
     assert!(ws.straggling_workunits(Duration::from_secs(0)).is_empty());
 }
 
+#[test]
+fn running_count() {
+    // Completed workunits are not counted, while blocked workunits are.
+    let ws = create_store(vec![wu_root(0), wu(1, 0)], vec![wu(2, 1)], vec![wu(3, 1)]);
+    assert_eq!(2, ws.running_count(&["1", "2", "3"]));
+    assert_eq!(0, ws.running_count(&["4"]));
+}
+
+#[tokio::test]
+async fn live_output_is_recorded_while_running() {
+    let ws = WorkunitStore::new(true, Level::Debug);
+    ws.init_thread_state(None);
+    ws.set_live_output_enabled(true);
+
+    let span_id = in_workunit!("process", Level::Debug, |_workunit| async move {
+        let span_id = expect_workunit_store_handle().parent_id.unwrap();
+        append_live_output_if_in_workunit(b"Hello, ");
+        append_live_output_if_in_workunit(b"world!");
+        assert_eq!(
+            expect_workunit_store_handle()
+                .store
+                .live_output(span_id)
+                .as_deref(),
+            Some(&b"Hello, world!"[..])
+        );
+
+        // Only the most recent output is retained.
+        append_live_output_if_in_workunit(&[b'!'; 128 * 1024]);
+        let output = expect_workunit_store_handle()
+            .store
+            .live_output(span_id)
+            .unwrap();
+        assert_eq!(output.len(), 64 * 1024);
+        assert!(output.iter().all(|b| *b == b'!'));
+        span_id
+    })
+    .await;
+
+    // The output of completed workunits is discarded.
+    assert_eq!(ws.live_output(span_id), None);
+}
+
+#[tokio::test]
+async fn live_output_is_not_recorded_when_disabled() {
+    let ws = WorkunitStore::new(true, Level::Debug);
+    ws.init_thread_state(None);
+
+    in_workunit!("process", Level::Debug, |_workunit| async move {
+        append_live_output_if_in_workunit(b"Hello, world!");
+        let handle = expect_workunit_store_handle();
+        assert_eq!(handle.store.live_output(handle.parent_id.unwrap()), None);
+    })
+    .await;
+}
+
 #[tokio::test]
 async fn disabled_workunit_is_filtered() {
     // Create a chain of completed workunits like: Info -> Trace -> Info (where `Trace` is below the
@@ -149,6 +208,76 @@ fn hex_16_digit_string_actually_uses_input_number() {
     );
 }
 
+#[test]
+fn observation_buckets() {
+    let (store, _) = WorkunitStore::setup_for_tests();
+    for value in [3, 10, 70, 2_000_000_000] {
+        store.record_observation(ObservationMetric::TestObservation, value);
+    }
+
+    let buckets = store
+        .observation_buckets()
+        .remove("test_observation")
+        .unwrap();
+    assert_eq!(OBSERVATION_BUCKET_BOUNDS.len(), buckets.counts.len());
+    assert_eq!(&[0, 1, 2, 2, 3], &buckets.counts[..5]);
+    assert_eq!(3, *buckets.counts.last().unwrap());
+    assert_eq!(4, buckets.count);
+}
+
+#[test]
+fn custom_metrics() {
+    let (store, _) = WorkunitStore::setup_for_tests();
+    store.increment_counter(Metric::LocalCacheRequests, 1);
+    store
+        .increment_custom_counter("lint_violations", 2)
+        .unwrap();
+    store
+        .increment_custom_counter("lint_violations", 3)
+        .unwrap();
+    store.record_custom_observation("lint_time_ms", 7).unwrap();
+
+    let metrics = store.get_metrics();
+    assert_eq!(Some(&1), metrics.get("local_cache_requests"));
+    assert_eq!(Some(&5), metrics.get("lint_violations"));
+    assert_eq!(1, store.observation_buckets()["lint_time_ms"].count);
+    assert!(store
+        .encode_observations()
+        .unwrap()
+        .contains_key("lint_time_ms"));
+
+    assert!(store.increment_custom_counter("LintViolations", 1).is_err());
+    assert!(store.increment_custom_counter("", 1).is_err());
+    assert!(store
+        .increment_custom_counter("local_cache_requests", 1)
+        .is_err());
+    assert!(store
+        .record_custom_observation("test_observation", 1)
+        .is_err());
+}
+
+#[test]
+fn displayed_counters() {
+    let (store, _) = WorkunitStore::setup_for_tests();
+    store.display_custom_counter("tests_passed").unwrap();
+    store.display_custom_counter("tests_failed").unwrap();
+    store.increment_custom_counter("tests_failed", 2).unwrap();
+    store.increment_custom_counter("tests_skipped", 1).unwrap();
+    store.display_custom_counter("tests_passed").unwrap();
+
+    // Counters are displayed once, in the order in which they were first marked.
+    assert_eq!(
+        vec![
+            ("tests_passed".to_owned(), 0),
+            ("tests_failed".to_owned(), 2)
+        ],
+        store.displayed_counters()
+    );
+    assert!(store
+        .display_custom_counter("local_cache_requests")
+        .is_err());
+}
+
 fn create_store(
     started: Vec<AnonymousWorkunit>,
     blocked: Vec<AnonymousWorkunit>,
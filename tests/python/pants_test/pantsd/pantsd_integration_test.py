@@ -19,9 +19,18 @@ import pytest
 
 from pants.testutil.pants_integration_test import read_pants_log, setup_tmpdir, temporary_workdir
 from pants.util.contextutil import environment_as, temporary_dir, temporary_file
-from pants.util.dirutil import rm_rf, safe_file_dump, safe_mkdir, safe_open, safe_rmtree, touch
+from pants.util.dirutil import (
+    maybe_read_file,
+    rm_rf,
+    safe_file_dump,
+    safe_mkdir,
+    safe_open,
+    safe_rmtree,
+    touch,
+)
 from pants_test.pantsd.pantsd_integration_test_base import (
     PantsDaemonIntegrationTestBase,
+    attempts,
     launch_waiter,
 )
 
@@ -623,6 +632,48 @@ class TestPantsDaemonIntegration(PantsDaemonIntegrationTestBase):
             result.assert_success()
             checker.assert_running()
 
+    def test_pantsd_rerun_after_restart(self):
+        """Test that the client relaunches pantsd and runs again if pantsd exits during a run."""
+        config = {"GLOBAL": {"pantsd_rerun_after_restart": True}}
+        with self.pantsd_test_context(extra_config=config) as (workdir, config, checker):
+            client_handle, waiter_pid, _, file_to_create = launch_waiter(
+                workdir=workdir, config=config
+            )
+            pantsd_pid = checker.assert_started()
+            os.kill(pantsd_pid, signal.SIGKILL)
+
+            # The command runs again against a new pantsd, and so launches a new waiter.
+            waiter_pid_file = os.path.join(workdir, "pid_file")
+            for _ in attempts("The command should have run again."):
+                rerun_waiter_pid = maybe_read_file(waiter_pid_file)
+                if rerun_waiter_pid and int(rerun_waiter_pid) != waiter_pid:
+                    break
+
+            # Let both waiters exit.
+            safe_file_dump(file_to_create, "content!")
+            result = client_handle.join()
+            result.assert_success()
+            self.assertIn("exited during the run: relaunching it", result.stderr)
+            checker.assert_running()
+            assert checker.pid != pantsd_pid
+
+    def test_pantsd_no_rerun_after_restart(self):
+        """Test that the client fails, rather than running again, if pantsd exits during a run."""
+        with self.pantsd_test_context() as (workdir, config, checker):
+            client_handle, waiter_pid, _, file_to_create = launch_waiter(
+                workdir=workdir, config=config
+            )
+            pantsd_pid = checker.assert_started()
+            os.kill(pantsd_pid, signal.SIGKILL)
+
+            result = client_handle.join()
+            result.assert_failure()
+            self.assertNotIn("relaunching it", result.stderr)
+            # The command did not run again, and so did not launch another waiter.
+            waiter_pid_file = os.path.join(workdir, "pid_file")
+            assert int(maybe_read_file(waiter_pid_file) or waiter_pid) == waiter_pid
+            safe_file_dump(file_to_create, "content!")
+
     def test_pantsd_unicode_environment(self):
         with self.pantsd_successful_run_context(extra_env={"XXX": "¡"}) as ctx:
             result = ctx.runner(["help"])